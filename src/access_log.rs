@@ -318,6 +318,8 @@ pub(crate) fn build_json_log(
     duration_ms: u128,
     client_ip: &str,
     upstream: &str,
+    hedge: &str,
+    user: &str,
     req_body_size: u64,
     resp_body_size: u64,
     user_agent: &str,
@@ -376,6 +378,18 @@ pub(crate) fn build_json_log(
     json_field!("upstream", {
         write_json_str(buf, upstream);
     });
+    // hedge はヘッジを送ったリクエストのみ出力する（F-137）
+    if !hedge.is_empty() {
+        json_field!("hedge", {
+            write_json_str(buf, hedge);
+        });
+    }
+    // user は認証した利用者・API キーのあるリクエストのみ出力する（F-151）
+    if !user.is_empty() {
+        json_field!("user", {
+            write_json_str(buf, user);
+        });
+    }
     json_field!("req_body_size", {
         write_u64(buf, req_body_size);
     });
@@ -403,6 +417,8 @@ pub(crate) fn build_text_log(
     duration_ms: u128,
     client_ip: &str,
     upstream: &str,
+    hedge: &str,
+    user: &str,
     req_body_size: u64,
     resp_body_size: u64,
     user_agent: &str,
@@ -455,6 +471,16 @@ pub(crate) fn build_text_log(
     text_field!("upstream", {
         buf.extend_from_slice(upstream.as_bytes());
     });
+    if !hedge.is_empty() {
+        text_field!("hedge", {
+            buf.extend_from_slice(hedge.as_bytes());
+        });
+    }
+    if !user.is_empty() {
+        text_field!("user", {
+            buf.extend_from_slice(user.as_bytes());
+        });
+    }
     text_field!("req_body_size", {
         write_u64(buf, req_body_size);
     });
@@ -495,6 +521,8 @@ pub(crate) fn log_access_structured(
     duration_ms: u128,
     client_ip: &str,
     upstream: &str,
+    hedge: &str,
+    user: &str,
 ) {
     let config = CURRENT_CONFIG.load();
     let acfg = &config.access_log_config;
@@ -518,6 +546,8 @@ pub(crate) fn log_access_structured(
                 duration_ms,
                 client_ip,
                 upstream,
+                hedge,
+                user,
                 req_body_size,
                 resp_body_size,
                 ua,
@@ -534,6 +564,8 @@ pub(crate) fn log_access_structured(
                 duration_ms,
                 client_ip,
                 upstream,
+                hedge,
+                user,
                 req_body_size,
                 resp_body_size,
                 ua,
@@ -576,6 +608,8 @@ mod tests {
             42,
             "127.0.0.1",
             "10.0.0.1:8080",
+            "",
+            "",
             0,
             1234,
             "curl/7.0",
@@ -635,6 +669,8 @@ mod tests {
             1,
             "127.0.0.1",
             "",
+            "",
+            "",
             0,
             0,
             "-",
@@ -669,6 +705,8 @@ mod tests {
             1,
             "127.0.0.1",
             "",
+            "",
+            "",
             0,
             0,
             "-",
@@ -700,6 +738,8 @@ mod tests {
             1,
             "127.0.0.1",
             "",
+            "",
+            "",
             0,
             0,
             "-",
@@ -734,6 +774,8 @@ mod tests {
             10,
             "192.168.1.1",
             "",
+            "",
+            "",
             512,
             256,
             "TestAgent/1.0",
@@ -764,6 +806,8 @@ mod tests {
             5,
             "10.0.0.1",
             "",
+            "",
+            "",
             0,
             0,
             "-",
@@ -852,6 +896,8 @@ mod tests {
             0,
             "127.0.0.1",
             "",
+            "",
+            "",
         );
         // パニックしなければ OK
     }
@@ -870,6 +916,8 @@ mod tests {
             1,
             "1.2.3.4",
             "",
+            "",
+            "",
             0,
             0,
             "-",
@@ -882,6 +930,68 @@ mod tests {
         assert!(!s.contains("status="), "status should be filtered");
     }
 
+    #[test]
+    fn test_hedge_field_only_for_hedged_requests() {
+        let build = |json: bool, hedge: &str| {
+            let mut buf = Vec::new();
+            let builder = if json { build_json_log } else { build_text_log };
+            builder(
+                &mut buf,
+                test_dt(),
+                "GET",
+                "example.com",
+                "/",
+                200,
+                1,
+                "1.2.3.4",
+                "",
+                hedge,
+                "",
+                0,
+                0,
+                "-",
+                &[],
+            );
+            String::from_utf8(buf).unwrap()
+        };
+        assert!(build(true, "hedge").contains("\"upstream\":\"\",\"hedge\":\"hedge\""));
+        assert!(build(false, "primary").contains(" hedge=primary "));
+        // ヘッジなしのリクエストでは出力しない
+        assert!(!build(true, "").contains("hedge"));
+        assert!(!build(false, "").contains("hedge"));
+    }
+
+    #[test]
+    fn test_user_field_only_for_authenticated_requests() {
+        let build = |json: bool, user: &str| {
+            let mut buf = Vec::new();
+            let builder = if json { build_json_log } else { build_text_log };
+            builder(
+                &mut buf,
+                test_dt(),
+                "GET",
+                "example.com",
+                "/",
+                200,
+                1,
+                "1.2.3.4",
+                "",
+                "",
+                user,
+                0,
+                0,
+                "-",
+                &[],
+            );
+            String::from_utf8(buf).unwrap()
+        };
+        assert!(build(true, "alice").contains("\"upstream\":\"\",\"user\":\"alice\""));
+        assert!(build(false, "ci-key").contains(" user=ci-key "));
+        // 認証のないリクエストでは出力しない
+        assert!(!build(true, "").contains("\"user\""));
+        assert!(!build(false, "").contains(" user="));
+    }
+
     #[test]
     fn test_access_log_format_serde() {
         // "json" → Json, "text" → Text
@@ -0,0 +1,882 @@
+//! 不正の兆候から IP を一定時間遮断する動的ブロックリスト（F-152）
+//!
+//! グローバル IP ブロックリスト（F-35、`blocked_ips`）は設定のリロードでしか変わらない。
+//! 本モジュールはリクエスト処理の途中で見つかった不正の兆候をクライアント IP ごとに数え、
+//! `[security.auto_ban]` の規則のしきい値に達した IP を一定時間遮断する。
+//!
+//! - 兆候: レートリミットの超過（F-146/F-147 の 429）、401・403・404 の応答、HTTP/2 の
+//!   フラッド検知（RST_STREAM・制御フレーム・CONTINUATION の上限超過による ENHANCE_YOUR_CALM）、
+//!   WAF（F-154）の異常スコアがしきい値に達したリクエスト。
+//! - 遮断中の IP は [`crate::config::is_ip_blocked`] により、TCP は accept 直後、QUIC は新規接続を
+//!   受け入れる前に切断する。確立済みの接続は切らない。
+//! - 遮断時間は `ban_secs` から始まり、同じ IP を再び遮断するたびに `escalation_factor` 倍に
+//!   延びる（`max_ban_secs` まで）。遮断が明けてから `forget_after_secs` の間遮断されなければ
+//!   回数を忘れる。
+//! - IPv6 は `ipv6_prefix_len`（既定 /64）のプレフィックス単位で数えて遮断する。
+//! - 管理 API（`/__admin/bans`）で一覧・追加・解除できる。追加した遮断の理由は `admin`。
+//! - `persist_file` を指定すると、専用スレッドが遮断中の IP を書き出し、起動時に読み戻す。
+//!
+//! 状態は IP のハッシュで分けたシャードごとの Mutex に持つ。遮断中の IP が無い間は、
+//! accept 時の確認は原子変数の読み出し 1 回で終わる。
+
+use std::collections::{hash_map, HashMap};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Once};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use arc_swap::ArcSwapOption;
+use ftlog::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{monotonic_ms, AutoBanConfig, CidrRange};
+
+/// 状態表のシャード数
+const SHARDS: usize = 64;
+
+/// 専用スレッドの巡回間隔（遮断明けの掃除と保存）
+const TICK: Duration = Duration::from_secs(1);
+
+/// 管理 API で追加した遮断の理由（規則名には使えない）
+pub const MANUAL_REASON: &str = "admin";
+
+/// 遮断の根拠になる不正の兆候
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    /// レートリミットの超過（429）
+    RateLimited,
+    /// 401 の応答
+    Unauthorized,
+    /// 403 の応答
+    Forbidden,
+    /// 404 の応答
+    NotFound,
+    /// HTTP/2 のフラッド検知による切断
+    H2Flood,
+    /// WAF の異常スコアがしきい値に達した（`detection_only` でも数える）
+    Waf,
+}
+
+impl Signal {
+    /// 設定の名前からパース
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "rate_limited" => Some(Self::RateLimited),
+            "unauthorized" => Some(Self::Unauthorized),
+            "forbidden" => Some(Self::Forbidden),
+            "not_found" => Some(Self::NotFound),
+            "h2_flood" => Some(Self::H2Flood),
+            "waf" => Some(Self::Waf),
+            _ => None,
+        }
+    }
+
+    /// 応答ステータスに対応する兆候
+    fn from_status(status: u16) -> Option<Self> {
+        match status {
+            401 => Some(Self::Unauthorized),
+            403 => Some(Self::Forbidden),
+            404 => Some(Self::NotFound),
+            _ => None,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// 規則（兆候はビット集合で持つ）
+struct Rule {
+    name: Arc<str>,
+    signals: u8,
+    threshold: u32,
+    window_ms: u64,
+}
+
+/// 設定から作る評価用の値
+struct Settings {
+    rules: Vec<Rule>,
+    /// いずれかの規則が数える兆候
+    signals: u8,
+    /// 規則の期間の最大（ミリ秒、兆候の無くなった IP を掃除する目安）
+    max_window_ms: u64,
+    ban_ms: u64,
+    max_ban_ms: u64,
+    escalation_factor: u64,
+    forget_ms: u64,
+    ipv6_prefix_len: u8,
+    exempt: Vec<CidrRange>,
+    persist_file: Option<String>,
+    max_entries: usize,
+}
+
+impl Settings {
+    fn new(cfg: &AutoBanConfig) -> Self {
+        let rules: Vec<Rule> = cfg
+            .rules
+            .iter()
+            .map(|rule| Rule {
+                name: Arc::from(rule.name.as_str()),
+                signals: rule
+                    .signals
+                    .iter()
+                    .filter_map(|s| Signal::parse(s))
+                    .fold(0, |bits, s| bits | s.bit()),
+                threshold: rule.threshold.max(1),
+                window_ms: rule.window_secs.saturating_mul(1000),
+            })
+            .collect();
+        Self {
+            signals: rules.iter().fold(0, |bits, r| bits | r.signals),
+            max_window_ms: rules.iter().map(|r| r.window_ms).max().unwrap_or(0),
+            rules,
+            ban_ms: cfg.ban_secs.saturating_mul(1000),
+            max_ban_ms: cfg.max_ban_secs.saturating_mul(1000),
+            escalation_factor: u64::from(cfg.escalation_factor.max(1)),
+            forget_ms: cfg.forget_after_secs.saturating_mul(1000),
+            ipv6_prefix_len: cfg.ipv6_prefix_len.min(128),
+            exempt: cfg
+                .exempt_ips
+                .iter()
+                .filter_map(|s| CidrRange::parse(s))
+                .collect(),
+            persist_file: cfg.persist_file.clone(),
+            max_entries: cfg.max_entries,
+        }
+    }
+
+    /// 遮断・集計の単位に揃える（IPv4 射影の IPv6 は IPv4 に、IPv6 はプレフィックスに）
+    fn normalize(&self, ip: IpAddr) -> IpAddr {
+        match ip.to_canonical() {
+            IpAddr::V6(v6) if self.ipv6_prefix_len < 128 => {
+                let mask = !0u128 << (128 - u32::from(self.ipv6_prefix_len));
+                IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+            }
+            ip => ip,
+        }
+    }
+
+    /// `offenses` 回目の遮断時間（ミリ秒）
+    fn ban_duration_ms(&self, offenses: u32) -> u64 {
+        let mut duration = self.ban_ms;
+        for _ in 1..offenses {
+            if duration >= self.max_ban_ms {
+                break;
+            }
+            duration = duration.saturating_mul(self.escalation_factor);
+        }
+        duration.min(self.max_ban_ms)
+    }
+
+    /// 一覧に出す IP の表記（IPv6 のプレフィックスは `/N` を付ける）
+    fn display(&self, ip: IpAddr) -> String {
+        match ip {
+            IpAddr::V6(v6) if self.ipv6_prefix_len < 128 => {
+                format!("{}/{}", v6, self.ipv6_prefix_len)
+            }
+            ip => ip.to_string(),
+        }
+    }
+}
+
+/// IP ごとの状態
+struct Entry {
+    /// 規則ごとの (期間の開始, 回数)
+    counters: Vec<(u64, u32)>,
+    /// 最後に兆候を数えた時刻
+    last_signal_ms: u64,
+    /// 最後の遮断が明ける時刻（明けた後も回数を忘れるまで残す）
+    banned_until_ms: u64,
+    /// 遮断中として数えているか（明けたら専用スレッドが下ろす）
+    active: bool,
+    /// 遮断された回数
+    offenses: u32,
+    /// 最後の遮断の理由（規則名か `admin`）
+    reason: Arc<str>,
+}
+
+impl Entry {
+    fn new() -> Self {
+        Self {
+            counters: Vec::new(),
+            last_signal_ms: 0,
+            banned_until_ms: 0,
+            active: false,
+            offenses: 0,
+            reason: Arc::from(""),
+        }
+    }
+}
+
+/// 遮断中の IP（一覧・保存用）
+#[derive(Debug, PartialEq, Eq)]
+struct BanInfo {
+    ip: IpAddr,
+    reason: Arc<str>,
+    remaining_ms: u64,
+    offenses: u32,
+}
+
+/// 保存ファイルの形式
+#[derive(Serialize, Deserialize, Default)]
+struct PersistedBans {
+    #[serde(default)]
+    bans: Vec<PersistedBan>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PersistedBan {
+    ip: String,
+    reason: String,
+    /// 遮断が明ける UNIX 時刻（ミリ秒）
+    expires_unix_ms: u64,
+    #[serde(default)]
+    offenses: u32,
+}
+
+/// IP ごとの状態表
+struct BanTable {
+    shards: Vec<Mutex<HashMap<IpAddr, Entry>>>,
+    /// 遮断中の IP の数
+    active: AtomicUsize,
+    /// 追跡している IP の数
+    entries: AtomicUsize,
+    /// 保存していない変更があるか
+    dirty: AtomicBool,
+}
+
+impl BanTable {
+    fn new() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+            active: AtomicUsize::new(0),
+            entries: AtomicUsize::new(0),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    fn shard(&self, ip: IpAddr) -> MutexGuard<'_, HashMap<IpAddr, Entry>> {
+        let hash = match ip {
+            IpAddr::V4(v4) => xxhash_rust::xxh3::xxh3_64(&v4.octets()),
+            IpAddr::V6(v6) => xxhash_rust::xxh3::xxh3_64(&v6.octets()),
+        };
+        self.shards[(hash as usize) % SHARDS]
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn is_banned(&self, ip: IpAddr, now_ms: u64) -> bool {
+        if self.active.load(Ordering::Relaxed) == 0 {
+            return false;
+        }
+        self.shard(ip)
+            .get(&ip)
+            .is_some_and(|e| e.banned_until_ms > now_ms)
+    }
+
+    /// 兆候を数え、規則のしきい値に達したら遮断する（遮断したら理由と遮断時間を返す）
+    fn report(
+        &self,
+        settings: &Settings,
+        ip: IpAddr,
+        signal: Signal,
+        now_ms: u64,
+    ) -> Option<(Arc<str>, u64)> {
+        let bit = signal.bit();
+        let mut shard = self.shard(ip);
+        let entry = match shard.entry(ip) {
+            hash_map::Entry::Occupied(e) => e.into_mut(),
+            hash_map::Entry::Vacant(e) => {
+                if self.entries.load(Ordering::Relaxed) >= settings.max_entries {
+                    return None;
+                }
+                self.entries.fetch_add(1, Ordering::Relaxed);
+                e.insert(Entry::new())
+            }
+        };
+        if entry.banned_until_ms > now_ms {
+            return None;
+        }
+        entry.last_signal_ms = now_ms;
+        if entry.counters.len() != settings.rules.len() {
+            entry.counters = vec![(0, 0); settings.rules.len()];
+        }
+        let mut hit: Option<Arc<str>> = None;
+        for (rule, counter) in settings.rules.iter().zip(entry.counters.iter_mut()) {
+            if rule.signals & bit == 0 {
+                continue;
+            }
+            if counter.1 == 0 || now_ms.saturating_sub(counter.0) >= rule.window_ms {
+                *counter = (now_ms, 0);
+            }
+            counter.1 = counter.1.saturating_add(1);
+            if counter.1 >= rule.threshold && hit.is_none() {
+                hit = Some(rule.name.clone());
+            }
+        }
+        let reason = hit?;
+        if entry.offenses > 0 && now_ms.saturating_sub(entry.banned_until_ms) >= settings.forget_ms
+        {
+            entry.offenses = 0;
+        }
+        entry.offenses = entry.offenses.saturating_add(1);
+        let duration_ms = settings.ban_duration_ms(entry.offenses);
+        entry.counters.fill((0, 0));
+        self.set_ban(entry, now_ms.saturating_add(duration_ms), reason.clone());
+        Some((reason, duration_ms))
+    }
+
+    fn set_ban(&self, entry: &mut Entry, until_ms: u64, reason: Arc<str>) {
+        if !entry.active {
+            entry.active = true;
+            self.active.fetch_add(1, Ordering::Relaxed);
+        }
+        entry.banned_until_ms = until_ms;
+        entry.reason = reason;
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// 遮断する（遮断回数は変えない。上限に関わらず追加する）
+    fn ban(&self, ip: IpAddr, until_ms: u64, reason: Arc<str>, offenses: u32) {
+        let mut shard = self.shard(ip);
+        let entry = shard.entry(ip).or_insert_with(|| {
+            self.entries.fetch_add(1, Ordering::Relaxed);
+            Entry::new()
+        });
+        entry.offenses = entry.offenses.max(offenses);
+        self.set_ban(entry, until_ms, reason);
+    }
+
+    /// 遮断を解き、数えた兆候と遮断回数も忘れる（遮断中だったら true）
+    fn unban(&self, ip: IpAddr, now_ms: u64) -> bool {
+        let Some(entry) = self.shard(ip).remove(&ip) else {
+            return false;
+        };
+        self.entries.fetch_sub(1, Ordering::Relaxed);
+        if entry.active {
+            self.active.fetch_sub(1, Ordering::Relaxed);
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        entry.banned_until_ms > now_ms
+    }
+
+    /// 遮断中の IP（IP 順）
+    fn list(&self, now_ms: u64) -> Vec<BanInfo> {
+        let mut bans: Vec<BanInfo> = Vec::new();
+        for shard in &self.shards {
+            let shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+            bans.extend(
+                shard
+                    .iter()
+                    .filter(|(_, e)| e.banned_until_ms > now_ms)
+                    .map(|(ip, e)| BanInfo {
+                        ip: *ip,
+                        reason: e.reason.clone(),
+                        remaining_ms: e.banned_until_ms - now_ms,
+                        offenses: e.offenses,
+                    }),
+            );
+        }
+        bans.sort_by_key(|b| b.ip);
+        bans
+    }
+
+    /// 明けた遮断を下ろし、兆候も遮断回数も残っていない IP を忘れる
+    fn sweep(&self, settings: &Settings, now_ms: u64) {
+        for shard in &self.shards {
+            let mut shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+            shard.retain(|_, e| {
+                if e.active && e.banned_until_ms <= now_ms {
+                    e.active = false;
+                    self.active.fetch_sub(1, Ordering::Relaxed);
+                }
+                let keep = e.active
+                    || now_ms.saturating_sub(e.last_signal_ms) < settings.max_window_ms
+                    || (e.offenses > 0
+                        && now_ms.saturating_sub(e.banned_until_ms) < settings.forget_ms);
+                if !keep {
+                    self.entries.fetch_sub(1, Ordering::Relaxed);
+                }
+                keep
+            });
+        }
+    }
+
+    /// 保存する内容（遮断中の IP）
+    fn snapshot(&self, now_ms: u64, now_unix_ms: u64) -> PersistedBans {
+        PersistedBans {
+            bans: self
+                .list(now_ms)
+                .into_iter()
+                .map(|b| PersistedBan {
+                    ip: b.ip.to_string(),
+                    reason: b.reason.to_string(),
+                    expires_unix_ms: now_unix_ms.saturating_add(b.remaining_ms),
+                    offenses: b.offenses,
+                })
+                .collect(),
+        }
+    }
+
+    /// 保存した遮断を読み戻す（明けたものと読めない IP は捨てる。戻した数を返す）
+    fn restore(
+        &self,
+        settings: &Settings,
+        saved: PersistedBans,
+        now_ms: u64,
+        now_unix_ms: u64,
+    ) -> usize {
+        let mut restored = 0;
+        for ban in saved.bans {
+            let Some(ip) = parse_ip(&ban.ip) else {
+                continue;
+            };
+            let Some(remaining_ms) = ban
+                .expires_unix_ms
+                .checked_sub(now_unix_ms)
+                .filter(|&ms| ms > 0)
+            else {
+                continue;
+            };
+            self.ban(
+                settings.normalize(ip),
+                now_ms.saturating_add(remaining_ms),
+                Arc::from(ban.reason.as_str()),
+                ban.offenses,
+            );
+            restored += 1;
+        }
+        restored
+    }
+}
+
+static TABLE: Lazy<BanTable> = Lazy::new(BanTable::new);
+
+/// 現在の規則（`[security.auto_ban]` が無ければ None）
+static SETTINGS: ArcSwapOption<Settings> = ArcSwapOption::const_empty();
+
+/// `[security.auto_ban]` が無いときの値（管理 API の遮断と掃除に使う）
+static DEFAULT_SETTINGS: Lazy<Arc<Settings>> =
+    Lazy::new(|| Arc::new(Settings::new(&AutoBanConfig::default())));
+
+/// 読み戻した保存ファイル（リロードで同じファイルを読み直さない）
+static LOADED_FILE: Mutex<Option<String>> = Mutex::new(None);
+
+static WORKER: Once = Once::new();
+
+fn current_settings() -> Arc<Settings> {
+    SETTINGS
+        .load_full()
+        .unwrap_or_else(|| DEFAULT_SETTINGS.clone())
+}
+
+/// `"203.0.113.7"` / `"2001:db8::1"` / `"2001:db8::/64"`（`/N` は無視）/ `"203.0.113.7:443"`
+fn parse_ip(s: &str) -> Option<IpAddr> {
+    let addr = s.split_once('/').map_or(s, |(addr, _)| addr);
+    addr.parse::<IpAddr>()
+        .ok()
+        .or_else(|| s.parse::<SocketAddr>().ok().map(|a| a.ip()))
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// 規則を適用する（起動時・SIGHUP リロード時。遮断中の IP と数えた兆候は残す）
+///
+/// `persist_file` があれば、初めて指定されたときに保存した遮断を読み戻す。
+pub fn configure(cfg: Option<&AutoBanConfig>) {
+    let settings = cfg.map(|cfg| Arc::new(Settings::new(cfg)));
+    if let Some(path) = settings.as_ref().and_then(|s| s.persist_file.as_ref()) {
+        let mut loaded = LOADED_FILE.lock().unwrap_or_else(|e| e.into_inner());
+        if loaded.as_deref() != Some(path.as_str()) {
+            load_persisted(path, settings.as_ref().unwrap());
+            *loaded = Some(path.clone());
+            // 読み戻した遮断と既存の遮断を合わせて書き出す
+            TABLE.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+    if settings.is_some() {
+        start_worker();
+    }
+    SETTINGS.store(settings);
+}
+
+/// 保存した遮断を読み戻す
+// 理由付き allow: 設定の読み込み・リロード時にのみ呼ばれる（データプレーン非経由）。
+#[allow(clippy::disallowed_methods)]
+fn load_persisted(path: &str, settings: &Settings) {
+    let body = match std::fs::read(path) {
+        Ok(body) => body,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+        Err(e) => {
+            warn!("[auto-ban] Failed to read {}: {}", path, e);
+            return;
+        }
+    };
+    match serde_json::from_slice::<PersistedBans>(&body) {
+        Ok(saved) => {
+            let restored = TABLE.restore(settings, saved, monotonic_ms(), unix_ms());
+            info!("[auto-ban] Restored {} bans from {}", restored, path);
+        }
+        Err(e) => warn!("[auto-ban] Ignoring invalid ban file {}: {}", path, e),
+    }
+}
+
+fn start_worker() {
+    WORKER.call_once(|| {
+        if let Err(e) = std::thread::Builder::new()
+            .name("veil-auto-ban".to_string())
+            .spawn(worker_loop)
+        {
+            warn!("Failed to spawn auto-ban thread: {}", e);
+        }
+    });
+}
+
+/// 明けた遮断の掃除と保存（専用スレッド）
+// 理由付き allow: 専用スレッドの巡回待ちとファイルの書き出し（イベントループ外）。
+#[allow(clippy::disallowed_methods)]
+fn worker_loop() {
+    info!("Auto-ban thread started");
+    loop {
+        std::thread::sleep(TICK);
+        let settings = current_settings();
+        let now_ms = monotonic_ms();
+        TABLE.sweep(&settings, now_ms);
+        crate::metrics::set_auto_ban_active(TABLE.active.load(Ordering::Relaxed));
+
+        let Some(path) = settings.persist_file.as_deref() else {
+            continue;
+        };
+        if !TABLE.dirty.swap(false, Ordering::Relaxed) {
+            continue;
+        }
+        let snapshot = TABLE.snapshot(now_ms, unix_ms());
+        let result = serde_json::to_vec(&snapshot)
+            .map_err(std::io::Error::other)
+            .and_then(|body| {
+                // 書きかけのファイルを読まないよう一時ファイルから置き換える
+                let tmp = format!("{}.tmp", path);
+                std::fs::write(&tmp, body)?;
+                std::fs::rename(&tmp, path)
+            });
+        if let Err(e) = result {
+            warn!("[auto-ban] Failed to write {}: {}", path, e);
+            TABLE.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 遮断中の IP か（accept ホットパス。遮断中の IP が無ければ原子変数の読み出しのみ）
+#[inline]
+pub fn is_banned(ip: IpAddr) -> bool {
+    if TABLE.active.load(Ordering::Relaxed) == 0 {
+        return false;
+    }
+    let guard = SETTINGS.load();
+    let settings = guard.as_deref().unwrap_or(&DEFAULT_SETTINGS);
+    let banned = TABLE.is_banned(settings.normalize(ip), monotonic_ms());
+    if banned {
+        crate::metrics::record_auto_ban_drop();
+    }
+    banned
+}
+
+/// 兆候を報告する（`[security.auto_ban]` が無い・数える規則が無ければ何もしない）
+pub fn report(ip: IpAddr, signal: Signal) {
+    let guard = SETTINGS.load();
+    let Some(settings) = guard.as_ref() else {
+        return;
+    };
+    if settings.signals & signal.bit() == 0 || settings.exempt.iter().any(|c| c.contains_addr(ip)) {
+        return;
+    }
+    let ip = settings.normalize(ip);
+    if let Some((reason, duration_ms)) = TABLE.report(settings, ip, signal, monotonic_ms()) {
+        warn!(
+            "[auto-ban] Banned {} for {}s ({})",
+            settings.display(ip),
+            duration_ms / 1000,
+            reason
+        );
+        crate::metrics::record_auto_ban(&reason);
+    }
+}
+
+/// クライアント IP の文字列で兆候を報告する
+pub fn report_str(client_ip: &str, signal: Signal) {
+    if SETTINGS.load().is_none() {
+        return;
+    }
+    if let Some(ip) = parse_ip(client_ip) {
+        report(ip, signal);
+    }
+}
+
+/// 応答ステータスを兆候として報告する（401 / 403 / 404 以外は何もしない）
+#[inline]
+pub fn observe_status(client_ip: &str, status: u16) {
+    if let Some(signal) = Signal::from_status(status) {
+        report_str(client_ip, signal);
+    }
+}
+
+/// 管理 API: 遮断中の IP の一覧（JSON）
+pub fn list_json() -> String {
+    let settings = current_settings();
+    let bans: Vec<serde_json::Value> = TABLE
+        .list(monotonic_ms())
+        .into_iter()
+        .map(|b| {
+            serde_json::json!({
+                "ip": settings.display(b.ip),
+                "reason": &*b.reason,
+                "expires_in_secs": b.remaining_ms.div_ceil(1000),
+                "offenses": b.offenses,
+            })
+        })
+        .collect();
+    serde_json::json!({ "bans": bans }).to_string()
+}
+
+/// 管理 API: IP を遮断する（`secs` を省略すると `ban_secs`）
+pub fn ban_manual(ip: &str, secs: Option<u64>) -> Result<(), String> {
+    let addr = parse_ip(ip).ok_or_else(|| format!("invalid IP '{}'", ip))?;
+    let settings = current_settings();
+    let duration_ms = match secs {
+        Some(0) => return Err("secs must be greater than 0".to_string()),
+        Some(secs) => secs.saturating_mul(1000),
+        None => settings.ban_ms,
+    };
+    let addr = settings.normalize(addr);
+    TABLE.ban(
+        addr,
+        monotonic_ms().saturating_add(duration_ms),
+        Arc::from(MANUAL_REASON),
+        0,
+    );
+    start_worker();
+    info!(
+        "[auto-ban] Banned {} for {}s through the admin API",
+        settings.display(addr),
+        duration_ms / 1000
+    );
+    crate::metrics::record_auto_ban(MANUAL_REASON);
+    Ok(())
+}
+
+/// 管理 API: 遮断を解く（遮断中だったら true）
+pub fn unban(ip: &str) -> Result<bool, String> {
+    let addr = parse_ip(ip).ok_or_else(|| format!("invalid IP '{}'", ip))?;
+    let settings = current_settings();
+    let addr = settings.normalize(addr);
+    let removed = TABLE.unban(addr, monotonic_ms());
+    if removed {
+        info!("[auto-ban] Unbanned {}", settings.display(addr));
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AutoBanRuleConfig;
+
+    fn settings(rules: &[(&str, &[&str], u32, u64)]) -> Settings {
+        Settings::new(&AutoBanConfig {
+            rules: rules
+                .iter()
+                .map(
+                    |(name, signals, threshold, window_secs)| AutoBanRuleConfig {
+                        name: name.to_string(),
+                        signals: signals.iter().map(|s| s.to_string()).collect(),
+                        threshold: *threshold,
+                        window_secs: *window_secs,
+                    },
+                )
+                .collect(),
+            ban_secs: 10,
+            max_ban_secs: 35,
+            forget_after_secs: 100,
+            ..AutoBanConfig::default()
+        })
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn signals_parse_and_map_statuses() {
+        assert_eq!(Signal::parse("rate_limited"), Some(Signal::RateLimited));
+        assert_eq!(Signal::parse("h2_flood"), Some(Signal::H2Flood));
+        assert_eq!(Signal::parse("waf"), Some(Signal::Waf));
+        assert_eq!(Signal::parse("sqli"), None);
+        assert_eq!(Signal::from_status(401), Some(Signal::Unauthorized));
+        assert_eq!(Signal::from_status(404), Some(Signal::NotFound));
+        assert_eq!(Signal::from_status(429), None);
+        assert_eq!(Signal::from_status(500), None);
+    }
+
+    #[test]
+    fn threshold_within_window_bans() {
+        let table = BanTable::new();
+        let s = settings(&[("auth", &["unauthorized", "forbidden"], 3, 60)]);
+        let a = ip("203.0.113.7");
+
+        assert!(table.report(&s, a, Signal::Unauthorized, 1_000).is_none());
+        assert!(table.report(&s, a, Signal::NotFound, 1_100).is_none());
+        assert!(table.report(&s, a, Signal::Forbidden, 1_200).is_none());
+        assert!(!table.is_banned(a, 1_200));
+        // 期間を過ぎた兆候は数えない
+        assert!(table.report(&s, a, Signal::Unauthorized, 70_000).is_none());
+        assert!(table.report(&s, a, Signal::Unauthorized, 70_100).is_none());
+        let (reason, duration_ms) = table.report(&s, a, Signal::Unauthorized, 70_200).unwrap();
+        assert_eq!(&*reason, "auth");
+        assert_eq!(duration_ms, 10_000);
+        assert!(table.is_banned(a, 70_200));
+        assert!(!table.is_banned(ip("203.0.113.8"), 70_200));
+        assert!(!table.is_banned(a, 80_200));
+    }
+
+    #[test]
+    fn waf_hits_ban_only_under_rules_that_count_them() {
+        let table = BanTable::new();
+        let s = settings(&[("waf", &["waf"], 2, 60), ("scan", &["not_found"], 1, 60)]);
+        let a = ip("192.0.2.44");
+
+        assert!(table.report(&s, a, Signal::Waf, 1_000).is_none());
+        let (reason, duration_ms) = table.report(&s, a, Signal::Waf, 1_500).unwrap();
+        assert_eq!(&*reason, "waf");
+        assert_eq!(duration_ms, 10_000);
+        assert!(table.is_banned(a, 1_500));
+        // WAF を数えない規則では何度報告しても遮断しない
+        let s = settings(&[("scan", &["not_found"], 1, 60)]);
+        let b = ip("192.0.2.45");
+        for i in 0..5 {
+            assert!(table.report(&s, b, Signal::Waf, 2_000 + i).is_none());
+        }
+        assert!(!table.is_banned(b, 2_100));
+    }
+
+    #[test]
+    fn repeated_bans_escalate_and_are_forgotten() {
+        let table = BanTable::new();
+        let s = settings(&[("flood", &["h2_flood"], 1, 60)]);
+        let a = ip("198.51.100.1");
+
+        let mut now = 0;
+        for expected in [10_000, 20_000, 35_000, 35_000] {
+            let (_, duration_ms) = table.report(&s, a, Signal::H2Flood, now).unwrap();
+            assert_eq!(duration_ms, expected);
+            // 遮断中の兆候は数えない
+            assert!(table.report(&s, a, Signal::H2Flood, now + 1).is_none());
+            now += duration_ms;
+        }
+        // 遮断明けから forget_after_secs 経てば 1 回目に戻る
+        now += 100_000;
+        let (_, duration_ms) = table.report(&s, a, Signal::H2Flood, now).unwrap();
+        assert_eq!(duration_ms, 10_000);
+    }
+
+    #[test]
+    fn sweep_expires_bans_and_forgets_idle_ips() {
+        let table = BanTable::new();
+        let s = settings(&[("nf", &["not_found"], 2, 10)]);
+        let (a, b) = (ip("192.0.2.1"), ip("192.0.2.2"));
+        table.report(&s, a, Signal::NotFound, 0);
+        table.report(&s, a, Signal::NotFound, 0);
+        table.report(&s, b, Signal::NotFound, 0);
+        assert_eq!(table.active.load(Ordering::Relaxed), 1);
+        assert_eq!(table.entries.load(Ordering::Relaxed), 2);
+
+        table.sweep(&s, 10_000);
+        assert_eq!(table.active.load(Ordering::Relaxed), 0);
+        // a は遮断回数を覚えている間は残り、b は兆候の期間が過ぎたので忘れる
+        assert_eq!(table.entries.load(Ordering::Relaxed), 1);
+        table.sweep(&s, 110_000);
+        assert_eq!(table.entries.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn entries_are_bounded_but_bans_are_not() {
+        let table = BanTable::new();
+        let mut s = settings(&[("nf", &["not_found"], 1, 10)]);
+        s.max_entries = 1;
+        assert!(table
+            .report(&s, ip("192.0.2.1"), Signal::NotFound, 0)
+            .is_some());
+        assert!(table
+            .report(&s, ip("192.0.2.2"), Signal::NotFound, 0)
+            .is_none());
+        table.ban(ip("192.0.2.3"), 5_000, Arc::from(MANUAL_REASON), 0);
+        assert!(table.is_banned(ip("192.0.2.3"), 0));
+        assert!(!table.is_banned(ip("192.0.2.2"), 0));
+    }
+
+    #[test]
+    fn ipv6_is_grouped_by_prefix() {
+        let s = settings(&[]);
+        assert_eq!(
+            s.normalize(ip("2001:db8:1:2:aaaa::1")),
+            ip("2001:db8:1:2::")
+        );
+        assert_eq!(s.normalize(ip("::ffff:192.0.2.9")), ip("192.0.2.9"));
+        assert_eq!(s.normalize(ip("192.0.2.9")), ip("192.0.2.9"));
+        assert_eq!(s.display(ip("2001:db8:1:2::")), "2001:db8:1:2::/64");
+        assert_eq!(parse_ip("2001:db8:1:2::/64"), Some(ip("2001:db8:1:2::")));
+        assert_eq!(parse_ip("192.0.2.9:443"), Some(ip("192.0.2.9")));
+        assert_eq!(parse_ip("nope"), None);
+    }
+
+    #[test]
+    fn unban_forgets_the_ip() {
+        let table = BanTable::new();
+        let s = settings(&[("nf", &["not_found"], 1, 10)]);
+        let a = ip("192.0.2.1");
+        table.report(&s, a, Signal::NotFound, 0);
+        assert!(table.unban(a, 1));
+        assert!(!table.is_banned(a, 1));
+        assert!(!table.unban(a, 1));
+        assert_eq!(table.active.load(Ordering::Relaxed), 0);
+        assert_eq!(table.entries.load(Ordering::Relaxed), 0);
+        // 遮断回数も忘れる
+        let (_, duration_ms) = table.report(&s, a, Signal::NotFound, 2).unwrap();
+        assert_eq!(duration_ms, 10_000);
+    }
+
+    #[test]
+    fn bans_survive_a_snapshot_round_trip() {
+        let table = BanTable::new();
+        let s = settings(&[("nf", &["not_found"], 1, 10)]);
+        table.report(&s, ip("192.0.2.1"), Signal::NotFound, 1_000);
+        table.ban(ip("192.0.2.2"), 500, Arc::from(MANUAL_REASON), 0);
+
+        // 保存時点で明けている遮断は書き出さない
+        let snapshot = table.snapshot(2_000, 1_700_000_000_000);
+        assert_eq!(snapshot.bans.len(), 1);
+        let body = serde_json::to_vec(&snapshot).unwrap();
+
+        let restored = BanTable::new();
+        let saved: PersistedBans = serde_json::from_slice(&body).unwrap();
+        // 保存から 4 秒後に読み戻すと残りは 5 秒
+        assert_eq!(restored.restore(&s, saved, 0, 1_700_000_004_000), 1);
+        assert_eq!(
+            restored.list(0),
+            vec![BanInfo {
+                ip: ip("192.0.2.1"),
+                reason: Arc::from("nf"),
+                remaining_ms: 5_000,
+                offenses: 1,
+            }]
+        );
+        // 明けた遮断は読み戻さない
+        let saved: PersistedBans = serde_json::from_slice(&body).unwrap();
+        assert_eq!(BanTable::new().restore(&s, saved, 0, 1_700_000_020_000), 0);
+    }
+}
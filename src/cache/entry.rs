@@ -219,7 +219,7 @@ impl CacheStorage {
 /// ビルダーパターンによる柔軟な`CacheEntry`の作成を提供します。
 ///
 /// # 使用例
-/// ```rust
+/// ```ignore
 /// let entry = CacheEntryBuilder::new(200)
 ///     .header(b"content-type", b"text/plain")
 ///     .body(b"Hello, World!".to_vec())
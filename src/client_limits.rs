@@ -0,0 +1,677 @@
+//! 接続元 IP・プレフィックスごとの接続数・ストリーム数・新規接続レートの上限（F-153）
+//!
+//! `max_concurrent_connections` はプロセス全体の上限のため、1 つのクライアントが枠を
+//! 使い切れる。本モジュールは `[security.client_limits]` の上限を接続元 IP ごと（`per_ip`）と
+//! プレフィックスごと（`per_prefix`、既定 IPv4 /24・IPv6 /64）に適用する。
+//!
+//! - TCP（HTTPS・H2C）と QUIC（HTTP/3）の同時接続数、1 秒あたりの新規接続数は accept 直後
+//!   （QUIC は新規接続を受け入れる前）に判定し、超えた接続は TLS ハンドシェイク前に切る。
+//! - HTTP/2・HTTP/3 の同時ストリーム数は接続をまたいだ合計で数え、超えたストリームを
+//!   HTTP/2 は REFUSED_STREAM、HTTP/3 は H3_REQUEST_REJECTED で拒否する（どちらも再試行可能）。
+//! - カウンターは全ワーカーで共有する（IP のハッシュで分けたシャードごとの Mutex）。
+//!   接続は [`ConnectionPermit`]、ストリームは [`StreamBudget`] の Drop で戻す。
+//! - `exempt_ips`（信頼する NAT・ロードバランサーなど）は数えない。
+//!
+//! `[security.client_limits]` が無い間は、accept 時の確認は `ArcSwapOption` の読み出しのみ。
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use arc_swap::ArcSwapOption;
+use once_cell::sync::Lazy;
+
+use crate::config::{monotonic_ms, CidrRange, ClientLimitValues, ClientLimitsConfig};
+
+/// カウンター表のシャード数
+const SHARDS: usize = 64;
+
+/// 新規接続を数える期間（ミリ秒）
+const RATE_WINDOW_MS: u64 = 1000;
+
+/// 接続の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnKind {
+    /// TCP（HTTPS・H2C）
+    Tcp,
+    /// QUIC（HTTP/3）
+    Quic,
+}
+
+/// 超えた上限
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Limit {
+    Connections,
+    QuicConnections,
+    Streams,
+    NewConnections,
+}
+
+/// 上限で拒否したこと（メトリクス・ログのラベルを持つ）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rejected {
+    limit: Limit,
+    per_prefix: bool,
+}
+
+impl Rejected {
+    /// `veil_client_limit_rejections_total` の `limit` ラベル
+    pub fn label(self) -> &'static str {
+        match (self.limit, self.per_prefix) {
+            (Limit::Connections, false) => "connections_per_ip",
+            (Limit::Connections, true) => "connections_per_prefix",
+            (Limit::QuicConnections, false) => "quic_connections_per_ip",
+            (Limit::QuicConnections, true) => "quic_connections_per_prefix",
+            (Limit::Streams, false) => "streams_per_ip",
+            (Limit::Streams, true) => "streams_per_prefix",
+            (Limit::NewConnections, false) => "new_connections_per_ip",
+            (Limit::NewConnections, true) => "new_connections_per_prefix",
+        }
+    }
+}
+
+/// 設定から作る評価用の値
+struct Settings {
+    per_ip: ClientLimitValues,
+    per_prefix: ClientLimitValues,
+    ipv4_prefix_len: u8,
+    ipv6_prefix_len: u8,
+    exempt: Vec<CidrRange>,
+}
+
+impl Settings {
+    fn new(cfg: &ClientLimitsConfig) -> Self {
+        Self {
+            per_ip: cfg.per_ip.clone(),
+            per_prefix: cfg.per_prefix.clone(),
+            ipv4_prefix_len: cfg.ipv4_prefix_len.min(32),
+            ipv6_prefix_len: cfg.ipv6_prefix_len.min(128),
+            exempt: cfg
+                .exempt_ips
+                .iter()
+                .filter_map(|s| CidrRange::parse(s))
+                .collect(),
+        }
+    }
+
+    fn is_exempt(&self, ip: IpAddr) -> bool {
+        self.exempt.iter().any(|c| c.contains_addr(ip))
+    }
+
+    /// `per_prefix` で数える単位（`ip` は正規化済み）
+    fn prefix(&self, ip: IpAddr) -> IpAddr {
+        match ip {
+            IpAddr::V4(v4) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.ipv4_prefix_len))
+                    .unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(v4) & mask))
+            }
+            IpAddr::V6(v6) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.ipv6_prefix_len))
+                    .unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+            }
+        }
+    }
+}
+
+/// IP（またはプレフィックス）ごとのカウンター
+#[derive(Default)]
+struct Counts {
+    tcp: u32,
+    quic: u32,
+    streams: u32,
+    /// 新規接続を数えている期間の開始
+    window_start_ms: u64,
+    /// 期間内の新規接続数
+    new_connections: u32,
+}
+
+impl Counts {
+    fn is_idle(&self, now_ms: u64) -> bool {
+        self.tcp == 0
+            && self.quic == 0
+            && self.streams == 0
+            && now_ms.saturating_sub(self.window_start_ms) >= RATE_WINDOW_MS
+    }
+}
+
+struct Shard {
+    counts: HashMap<IpAddr, Counts>,
+    /// 使われなくなったカウンターを最後に掃除した時刻
+    last_sweep_ms: u64,
+}
+
+impl Shard {
+    /// 新規接続の期間が過ぎた空のカウンターを捨てる（1 秒に 1 回まで）
+    fn sweep(&mut self, now_ms: u64) {
+        if now_ms.saturating_sub(self.last_sweep_ms) >= RATE_WINDOW_MS {
+            self.last_sweep_ms = now_ms;
+            self.counts.retain(|_, c| !c.is_idle(now_ms));
+        }
+    }
+}
+
+/// IP（またはプレフィックス）ごとのカウンター表
+struct Table {
+    shards: Vec<Mutex<Shard>>,
+}
+
+impl Table {
+    fn new() -> Self {
+        Self {
+            shards: (0..SHARDS)
+                .map(|_| {
+                    Mutex::new(Shard {
+                        counts: HashMap::new(),
+                        last_sweep_ms: 0,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    fn shard(&self, key: IpAddr) -> MutexGuard<'_, Shard> {
+        let hash = match key {
+            IpAddr::V4(v4) => xxhash_rust::xxh3::xxh3_64(&v4.octets()),
+            IpAddr::V6(v6) => xxhash_rust::xxh3::xxh3_64(&v6.octets()),
+        };
+        self.shards[(hash as usize) % SHARDS]
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 新しい接続を数える（上限を超えるなら数えずに超えた上限を返す）
+    fn try_connect(
+        &self,
+        key: IpAddr,
+        kind: ConnKind,
+        limits: &ClientLimitValues,
+        now_ms: u64,
+    ) -> Result<(), Limit> {
+        let mut shard = self.shard(key);
+        shard.sweep(now_ms);
+        let counts = shard.counts.entry(key).or_default();
+        if now_ms.saturating_sub(counts.window_start_ms) >= RATE_WINDOW_MS {
+            counts.window_start_ms = now_ms;
+            counts.new_connections = 0;
+        }
+        if limits.max_new_connections_per_sec > 0
+            && counts.new_connections >= limits.max_new_connections_per_sec
+        {
+            return Err(Limit::NewConnections);
+        }
+        let (current, max, limit) = match kind {
+            ConnKind::Tcp => (&mut counts.tcp, limits.max_connections, Limit::Connections),
+            ConnKind::Quic => (
+                &mut counts.quic,
+                limits.max_quic_connections,
+                Limit::QuicConnections,
+            ),
+        };
+        if max > 0 && *current >= max {
+            return Err(limit);
+        }
+        *current += 1;
+        counts.new_connections += 1;
+        Ok(())
+    }
+
+    fn release_connection(&self, key: IpAddr, kind: ConnKind, now_ms: u64) {
+        self.update(key, now_ms, |c| match kind {
+            ConnKind::Tcp => c.tcp = c.tcp.saturating_sub(1),
+            ConnKind::Quic => c.quic = c.quic.saturating_sub(1),
+        });
+    }
+
+    /// 数えた接続を取り消す（新規接続の数も戻す）
+    fn cancel_connection(&self, key: IpAddr, kind: ConnKind, now_ms: u64) {
+        self.update(key, now_ms, |c| {
+            c.new_connections = c.new_connections.saturating_sub(1);
+            match kind {
+                ConnKind::Tcp => c.tcp = c.tcp.saturating_sub(1),
+                ConnKind::Quic => c.quic = c.quic.saturating_sub(1),
+            }
+        });
+    }
+
+    /// ストリームを 1 本数える（`max` を超えるなら数えずに false）
+    fn try_stream(&self, key: IpAddr, max: u32) -> bool {
+        let mut shard = self.shard(key);
+        let counts = shard.counts.entry(key).or_default();
+        if max > 0 && counts.streams >= max {
+            return false;
+        }
+        counts.streams += 1;
+        true
+    }
+
+    fn release_streams(&self, key: IpAddr, n: u32, now_ms: u64) {
+        self.update(key, now_ms, |c| c.streams = c.streams.saturating_sub(n));
+    }
+
+    /// カウンターを減らし、使われなくなったら捨てる
+    fn update(&self, key: IpAddr, now_ms: u64, f: impl FnOnce(&mut Counts)) {
+        let mut shard = self.shard(key);
+        if let Some(counts) = shard.counts.get_mut(&key) {
+            f(counts);
+            if counts.is_idle(now_ms) {
+                shard.counts.remove(&key);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|s| s.lock().unwrap().counts.len())
+            .sum()
+    }
+}
+
+/// IP ごととプレフィックスごとのカウンター
+struct Limiter {
+    ips: Table,
+    prefixes: Table,
+}
+
+impl Limiter {
+    fn new() -> Self {
+        Self {
+            ips: Table::new(),
+            prefixes: Table::new(),
+        }
+    }
+
+    /// IP とプレフィックスの両方で接続を数える（片方でも超えたら数えない）
+    fn connect(
+        &self,
+        settings: &Settings,
+        ip: IpAddr,
+        kind: ConnKind,
+        now_ms: u64,
+    ) -> Result<IpAddr, Rejected> {
+        let prefix = settings.prefix(ip);
+        self.ips
+            .try_connect(ip, kind, &settings.per_ip, now_ms)
+            .map_err(|limit| Rejected {
+                limit,
+                per_prefix: false,
+            })?;
+        if let Err(limit) = self
+            .prefixes
+            .try_connect(prefix, kind, &settings.per_prefix, now_ms)
+        {
+            self.ips.cancel_connection(ip, kind, now_ms);
+            return Err(Rejected {
+                limit,
+                per_prefix: true,
+            });
+        }
+        Ok(prefix)
+    }
+
+    fn disconnect(&self, ip: IpAddr, prefix: IpAddr, kind: ConnKind, now_ms: u64) {
+        self.ips.release_connection(ip, kind, now_ms);
+        self.prefixes.release_connection(prefix, kind, now_ms);
+    }
+
+    /// IP とプレフィックスの両方でストリームを 1 本数える（片方でも超えたら数えない）
+    fn open_stream(
+        &self,
+        settings: Option<&Settings>,
+        ip: IpAddr,
+        prefix: IpAddr,
+        now_ms: u64,
+    ) -> Result<(), Rejected> {
+        let (ip_max, prefix_max) =
+            settings.map_or((0, 0), |s| (s.per_ip.max_streams, s.per_prefix.max_streams));
+        if !self.ips.try_stream(ip, ip_max) {
+            return Err(Rejected {
+                limit: Limit::Streams,
+                per_prefix: false,
+            });
+        }
+        if !self.prefixes.try_stream(prefix, prefix_max) {
+            self.ips.release_streams(ip, 1, now_ms);
+            return Err(Rejected {
+                limit: Limit::Streams,
+                per_prefix: true,
+            });
+        }
+        Ok(())
+    }
+
+    fn close_streams(&self, ip: IpAddr, prefix: IpAddr, n: u32, now_ms: u64) {
+        self.ips.release_streams(ip, n, now_ms);
+        self.prefixes.release_streams(prefix, n, now_ms);
+    }
+}
+
+static LIMITER: Lazy<Limiter> = Lazy::new(Limiter::new);
+
+/// 現在の上限（`[security.client_limits]` が無ければ None）
+static SETTINGS: ArcSwapOption<Settings> = ArcSwapOption::const_empty();
+
+/// 上限を適用する（起動時・SIGHUP リロード時。数えている接続・ストリームはそのまま）
+pub fn configure(cfg: Option<&ClientLimitsConfig>) {
+    SETTINGS.store(cfg.map(|cfg| Arc::new(Settings::new(cfg))));
+}
+
+fn reject(rejected: Rejected) -> Rejected {
+    crate::metrics::record_client_limit_rejection(rejected.label());
+    rejected
+}
+
+/// 数えている接続（Drop で戻す）
+pub struct ConnectionPermit {
+    ip: IpAddr,
+    prefix: IpAddr,
+    kind: ConnKind,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        LIMITER.disconnect(self.ip, self.prefix, self.kind, monotonic_ms());
+    }
+}
+
+/// 新しい接続を受け入れてよいか判定して数える（accept ホットパス）
+///
+/// 上限が無い・除外する接続元なら `Ok(None)`。上限を超えたら `Err`（メトリクスは記録済み）。
+#[inline]
+pub fn acquire(ip: IpAddr, kind: ConnKind) -> Result<Option<ConnectionPermit>, Rejected> {
+    let guard = SETTINGS.load();
+    let Some(settings) = guard.as_deref() else {
+        return Ok(None);
+    };
+    let ip = ip.to_canonical();
+    if settings.is_exempt(ip) {
+        return Ok(None);
+    }
+    let prefix = LIMITER
+        .connect(settings, ip, kind, monotonic_ms())
+        .map_err(reject)?;
+    Ok(Some(ConnectionPermit { ip, prefix, kind }))
+}
+
+/// 1 接続のストリームを接続元ごとの合計に数える（Drop で残りを戻す）
+pub struct StreamBudget {
+    ip: IpAddr,
+    prefix: IpAddr,
+    /// この接続が数えているストリーム数
+    held: u32,
+}
+
+impl StreamBudget {
+    /// 新しいストリームを開いてよいか判定して数える（`open` はこの接続で開いている数）
+    pub fn try_open(&mut self, open: usize) -> bool {
+        self.sync(open);
+        let guard = SETTINGS.load();
+        match LIMITER.open_stream(guard.as_deref(), self.ip, self.prefix, monotonic_ms()) {
+            Ok(()) => {
+                self.held += 1;
+                true
+            }
+            Err(rejected) => {
+                reject(rejected);
+                false
+            }
+        }
+    }
+
+    /// 閉じたストリームを戻す（`open` はこの接続で開いている数）
+    pub fn sync(&mut self, open: usize) {
+        let open = u32::try_from(open).unwrap_or(u32::MAX);
+        if self.held > open {
+            LIMITER.close_streams(self.ip, self.prefix, self.held - open, monotonic_ms());
+            self.held = open;
+        }
+    }
+}
+
+impl Drop for StreamBudget {
+    fn drop(&mut self) {
+        self.sync(0);
+    }
+}
+
+/// 接続のストリーム数を数える準備（ストリームの上限が無い・除外する接続元なら None）
+pub fn stream_budget(ip: IpAddr) -> Option<StreamBudget> {
+    let guard = SETTINGS.load();
+    let settings = guard.as_deref()?;
+    if settings.per_ip.max_streams == 0 && settings.per_prefix.max_streams == 0 {
+        return None;
+    }
+    let ip = ip.to_canonical();
+    if settings.is_exempt(ip) {
+        return None;
+    }
+    Some(StreamBudget {
+        ip,
+        prefix: settings.prefix(ip),
+        held: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(per_ip: ClientLimitValues, per_prefix: ClientLimitValues) -> Settings {
+        Settings::new(&ClientLimitsConfig {
+            per_ip,
+            per_prefix,
+            exempt_ips: vec!["10.0.0.0/8".to_string()],
+            ..ClientLimitsConfig::default()
+        })
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn prefix_groups_ipv4_and_ipv6() {
+        let s = settings(ClientLimitValues::default(), ClientLimitValues::default());
+        assert_eq!(s.prefix(ip("203.0.113.77")), ip("203.0.113.0"));
+        assert_eq!(s.prefix(ip("2001:db8:1:2:3::9")), ip("2001:db8:1:2::"));
+        assert!(s.is_exempt(ip("10.1.2.3")));
+        assert!(!s.is_exempt(ip("11.1.2.3")));
+
+        let whole = Settings::new(&ClientLimitsConfig {
+            ipv4_prefix_len: 32,
+            ipv6_prefix_len: 128,
+            ..ClientLimitsConfig::default()
+        });
+        assert_eq!(whole.prefix(ip("203.0.113.77")), ip("203.0.113.77"));
+        assert_eq!(whole.prefix(ip("2001:db8::9")), ip("2001:db8::9"));
+    }
+
+    #[test]
+    fn connections_are_limited_per_ip_and_released() {
+        let s = settings(
+            ClientLimitValues {
+                max_connections: 2,
+                max_quic_connections: 1,
+                ..Default::default()
+            },
+            ClientLimitValues::default(),
+        );
+        let limiter = Limiter::new();
+        let a = ip("203.0.113.1");
+        let prefix = limiter.connect(&s, a, ConnKind::Tcp, 0).unwrap();
+        limiter.connect(&s, a, ConnKind::Tcp, 1).unwrap();
+        let err = limiter.connect(&s, a, ConnKind::Tcp, 2).unwrap_err();
+        assert_eq!(err.label(), "connections_per_ip");
+        // TCP と QUIC は別に数え、別の IP は影響を受けない
+        limiter.connect(&s, a, ConnKind::Quic, 3).unwrap();
+        assert_eq!(
+            limiter
+                .connect(&s, a, ConnKind::Quic, 4)
+                .unwrap_err()
+                .label(),
+            "quic_connections_per_ip"
+        );
+        limiter
+            .connect(&s, ip("203.0.113.2"), ConnKind::Tcp, 5)
+            .unwrap();
+
+        limiter.disconnect(a, prefix, ConnKind::Tcp, 6);
+        limiter.connect(&s, a, ConnKind::Tcp, 7).unwrap();
+    }
+
+    #[test]
+    fn prefix_limit_rolls_back_the_ip_count() {
+        let s = settings(
+            ClientLimitValues {
+                max_connections: 1,
+                ..Default::default()
+            },
+            ClientLimitValues {
+                max_connections: 2,
+                ..Default::default()
+            },
+        );
+        let limiter = Limiter::new();
+        limiter
+            .connect(&s, ip("198.51.100.1"), ConnKind::Tcp, 0)
+            .unwrap();
+        let prefix = limiter
+            .connect(&s, ip("198.51.100.2"), ConnKind::Tcp, 0)
+            .unwrap();
+        let c = ip("198.51.100.3");
+        assert_eq!(
+            limiter
+                .connect(&s, c, ConnKind::Tcp, 0)
+                .unwrap_err()
+                .label(),
+            "connections_per_prefix"
+        );
+        // 拒否した接続は IP 側にも残らない
+        limiter.disconnect(ip("198.51.100.2"), prefix, ConnKind::Tcp, 0);
+        limiter.connect(&s, c, ConnKind::Tcp, 0).unwrap();
+        assert_eq!(
+            limiter
+                .connect(&s, ip("198.51.101.1"), ConnKind::Tcp, 0)
+                .map(|_| ()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn new_connection_rate_resets_every_second() {
+        let s = settings(
+            ClientLimitValues {
+                max_new_connections_per_sec: 2,
+                ..Default::default()
+            },
+            ClientLimitValues::default(),
+        );
+        let limiter = Limiter::new();
+        let a = ip("2001:db8::1");
+        for now in [100, 200] {
+            let prefix = limiter.connect(&s, a, ConnKind::Tcp, now).unwrap();
+            limiter.disconnect(a, prefix, ConnKind::Tcp, now);
+        }
+        assert_eq!(
+            limiter
+                .connect(&s, a, ConnKind::Quic, 900)
+                .unwrap_err()
+                .label(),
+            "new_connections_per_ip"
+        );
+        limiter.connect(&s, a, ConnKind::Quic, 1100).unwrap();
+    }
+
+    #[test]
+    fn prefix_rejections_do_not_use_up_the_ip_rate() {
+        let s = settings(
+            ClientLimitValues {
+                max_new_connections_per_sec: 1,
+                ..Default::default()
+            },
+            ClientLimitValues {
+                max_connections: 1,
+                ..Default::default()
+            },
+        );
+        let limiter = Limiter::new();
+        let prefix = limiter
+            .connect(&s, ip("192.0.2.1"), ConnKind::Tcp, 0)
+            .unwrap();
+        let b = ip("192.0.2.2");
+        for now in [100, 200, 300] {
+            assert_eq!(
+                limiter
+                    .connect(&s, b, ConnKind::Tcp, now)
+                    .unwrap_err()
+                    .label(),
+                "connections_per_prefix"
+            );
+        }
+        // 拒否した接続は新規接続の数にも残らない
+        limiter.disconnect(ip("192.0.2.1"), prefix, ConnKind::Tcp, 400);
+        limiter.connect(&s, b, ConnKind::Tcp, 500).unwrap();
+    }
+
+    #[test]
+    fn streams_are_shared_across_connections() {
+        let s = settings(
+            ClientLimitValues {
+                max_streams: 3,
+                ..Default::default()
+            },
+            ClientLimitValues::default(),
+        );
+        let limiter = Limiter::new();
+        let a = ip("203.0.113.9");
+        let prefix = s.prefix(a);
+        for _ in 0..3 {
+            limiter.open_stream(Some(&s), a, prefix, 0).unwrap();
+        }
+        assert_eq!(
+            limiter
+                .open_stream(Some(&s), a, prefix, 0)
+                .unwrap_err()
+                .label(),
+            "streams_per_ip"
+        );
+        limiter.close_streams(a, prefix, 2, 0);
+        limiter.open_stream(Some(&s), a, prefix, 0).unwrap();
+        // 上限が無くなっても数え続ける（戻すときに合うように）
+        limiter.open_stream(None, a, prefix, 0).unwrap();
+        limiter.close_streams(a, prefix, 3, 5000);
+        assert_eq!(limiter.ips.len(), 0);
+        assert_eq!(limiter.prefixes.len(), 0);
+    }
+
+    #[test]
+    fn idle_counters_are_removed() {
+        let s = settings(
+            ClientLimitValues {
+                max_new_connections_per_sec: 10,
+                ..Default::default()
+            },
+            ClientLimitValues::default(),
+        );
+        let limiter = Limiter::new();
+        let a = ip("192.0.2.1");
+        let prefix = limiter.connect(&s, a, ConnKind::Tcp, 0).unwrap();
+        // 期間内は新規接続数を覚えておき、期間が過ぎたら掃除で捨てる
+        limiter.disconnect(a, prefix, ConnKind::Tcp, 10);
+        assert_eq!(limiter.ips.len(), 1);
+        for shard in &limiter.ips.shards {
+            shard.lock().unwrap().sweep(5000);
+        }
+        assert_eq!(limiter.ips.len(), 0);
+        // 期間が過ぎてから閉じた接続はその場で捨てる
+        let prefix = limiter.connect(&s, a, ConnKind::Tcp, 6000).unwrap();
+        limiter.disconnect(a, prefix, ConnKind::Tcp, 8000);
+        assert_eq!(limiter.ips.len(), 0);
+        assert_eq!(limiter.prefixes.len(), 0);
+    }
+}
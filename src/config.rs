@@ -6,6 +6,8 @@ use crate::constants::*;
 use crate::logging::*;
 use crate::pool::*;
 use crate::runtime::io::AsyncWriteRentExt;
+use crate::runtime::local_bind::LocalBind;
+use crate::runtime::sockopt::{SocketOptions, CONGESTION_NAME_MAX};
 use crate::runtime::tcp::TcpStream;
 use crate::runtime::time::timeout;
 use arc_swap::ArcSwap;
@@ -16,13 +18,15 @@ use once_cell::sync::Lazy;
 use rustls::ServerConfig;
 use rustls_pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
 use serde::Deserialize;
+use std::borrow::Cow;
+#[cfg(feature = "http2")]
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::fs::File;
 use std::io;
 use std::io::BufReader;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
@@ -480,13 +484,84 @@ pub struct SecurityConfig {
     pub allowed_methods: Vec<String>,
 
     /// 分間リクエスト数上限（0 = 無制限）
+    ///
+    /// クライアント IP をキーとする容量 N・毎分 N 回補充のトークンバケットとして扱う（F-146）。
     #[serde(default)]
     pub rate_limit_requests_per_min: u64,
 
+    /// ルートのレートリミッター（設定ファイルからは読まない、F-146）
+    #[serde(skip)]
+    pub rate_limit: Option<Arc<crate::rate_limit::RouteRateLimit>>,
+
+    /// ルートの JWT 認証（設定ファイルからは読まない、F-148）
+    #[serde(skip)]
+    pub jwt: Option<Arc<crate::jwt_auth::JwtAuth>>,
+
+    /// ルートの外部認可（設定ファイルからは読まない、F-149）
+    #[serde(skip)]
+    pub ext_authz: Option<Arc<crate::ext_authz::ExtAuthz>>,
+
+    /// ルートの OIDC ログイン（設定ファイルからは読まない、F-150）
+    #[serde(skip)]
+    pub oidc: Option<Arc<crate::oidc::Oidc>>,
+
+    /// ルートの Basic 認証・API キー認証（設定ファイルからは読まない、F-151）
+    #[serde(skip)]
+    pub credential_auth: Option<Arc<crate::credential_auth::CredentialAuth>>,
+
+    /// ルートの WAF（設定ファイルからは読まない、F-154）
+    #[serde(skip)]
+    pub waf: Option<Arc<crate::waf::Waf>>,
+
+    /// ルートの CORS（設定ファイルからは読まない、F-155）
+    #[serde(skip)]
+    pub cors: Option<Arc<crate::cors::Cors>>,
+
     /// バックエンド接続タイムアウト（秒）
     #[serde(default = "default_backend_connect_timeout")]
     pub backend_connect_timeout_secs: u64,
 
+    // ====================
+    // 上流タイムアウト（F-139）
+    // ====================
+    /// 上流の応答ヘッダー待ちタイムアウト（ミリ秒、0 = 既定の 10 秒）
+    ///
+    /// リクエスト送信から応答ヘッダーの受信完了まで。超過時は 504。
+    #[serde(default)]
+    pub upstream_header_timeout_ms: u64,
+
+    /// 上流からの応答受信中の読み取り間隔の上限（ミリ秒、0 = 既定の 30 秒）
+    #[serde(default)]
+    pub upstream_idle_timeout_ms: u64,
+
+    /// リクエスト全体の期限（ミリ秒、0 = 無制限）
+    ///
+    /// リクエスト受信から上流応答の中継完了まで。応答ヘッダー前に期限切れなら 504、
+    /// 中継中なら接続を切断する。
+    #[serde(default)]
+    pub request_timeout_ms: u64,
+
+    /// 試行ごとのタイムアウト（ミリ秒、0 = 無制限）
+    ///
+    /// 各試行（一次試行・F-137 のヘッジ）が応答を返し始めるまでの上限。
+    #[serde(default)]
+    pub per_try_timeout_ms: u64,
+
+    /// gRPC リクエストの `grpc-timeout` を期限として扱う（デフォルト: true）
+    ///
+    /// 経過時間を差し引いた残りを上流へ転送し、期限切れは veil が
+    /// `grpc-status: 4`（DEADLINE_EXCEEDED）で応答する。
+    #[serde(default = "default_true")]
+    pub grpc_deadline_propagation: bool,
+
+    /// `grpc-timeout` の上限（ミリ秒、0 = 上限なし）
+    #[serde(default)]
+    pub max_grpc_timeout_ms: u64,
+
+    /// リクエスト単位の上流期限（設定ファイルからは読まない、F-139）
+    #[serde(skip)]
+    pub upstream_deadline: Option<crate::timeouts::UpstreamDeadline>,
+
     /// ホストごとの最大アイドル接続数
     #[serde(default = "default_max_idle_connections")]
     pub max_idle_connections_per_host: usize,
@@ -584,6 +659,42 @@ impl SecurityConfig {
         IpFilter::from_lists(&self.allowed_ips, &self.denied_ips)
     }
 
+    /// 応答に加えるヘッダー（ルートの `add_response_headers` とリクエスト単位の追加分）
+    pub fn response_headers<'a>(
+        &'a self,
+        extra: &'a ExtraHeaders,
+    ) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        self.add_response_headers
+            .iter()
+            .map(|(n, v)| (n.as_str(), v.as_str()))
+            .chain(extra.response.iter().map(|(n, v)| (n.as_str(), v.as_str())))
+    }
+
+    /// 上流へ転送しないリクエストヘッダーか（ルートの `remove_request_headers` と
+    /// リクエスト単位で置き換える・取り除くヘッダー）
+    pub fn is_removed_request_header(&self, name: &[u8], extra: &ExtraHeaders) -> bool {
+        self.remove_request_headers
+            .iter()
+            .any(|n| name.eq_ignore_ascii_case(n.as_bytes()))
+            || extra.overrides_request_header(name)
+    }
+
+    /// リクエスト単位の上流期限を設定したコピーを返す（F-139）
+    pub fn with_upstream_deadline(
+        &self,
+        deadline: crate::timeouts::UpstreamDeadline,
+    ) -> SecurityConfig {
+        let mut cfg = self.clone();
+        cfg.upstream_deadline = Some(deadline);
+        cfg
+    }
+
+    /// 上流の読み取りタイムアウト群（F-139）
+    #[inline]
+    pub fn upstream_timeouts(&self) -> crate::timeouts::UpstreamTimeouts {
+        crate::timeouts::UpstreamTimeouts::new(self)
+    }
+
     /// ヘッダー操作が設定されているかどうか
     pub fn has_header_operations(&self) -> bool {
         !self.add_request_headers.is_empty()
@@ -603,13 +714,22 @@ impl SecurityConfig {
     /// チェック対象:
     /// - IP制限（allowed_ips, denied_ips）
     /// - HTTPメソッド制限（allowed_methods）
-    /// - レートリミット（rate_limit_requests_per_min）
+    /// - レートリミット（rate_limit_requests_per_min・ルートの rate_limits）
+    /// - JWT 認証（ルートの jwt）
+    /// - 外部認可（ルートの ext_authz）
+    /// - OIDC ログイン（ルートの oidc）
+    /// - WAF（ルートの waf）
     #[inline]
     pub fn has_security_checks(&self) -> bool {
         !self.allowed_ips.is_empty()
             || !self.denied_ips.is_empty()
             || !self.allowed_methods.is_empty()
             || self.rate_limit_requests_per_min > 0
+            || self.rate_limit.is_some()
+            || self.jwt.is_some()
+            || self.ext_authz.is_some()
+            || self.oidc.is_some()
+            || self.waf.is_some()
     }
 
     /// WebSocketポーリング設定を構築
@@ -627,6 +747,94 @@ impl SecurityConfig {
     }
 }
 
+/// リクエスト単位で加えるヘッダー
+///
+/// ルートの `SecurityConfig` は共有したまま、スティッキー Cookie（F-132）・RateLimit（F-146）・
+/// 認証で得たクレーム（F-148 / F-150 / F-151）・外部認可のヘッダー操作（F-149）・
+/// CORS（F-155）など、リクエストごとに決まるヘッダーだけを運ぶ。
+#[derive(Debug, Clone, Default)]
+pub struct ExtraHeaders {
+    /// 上流へのリクエストに加えるヘッダー（クライアントの同名ヘッダーとルートの追加分を置き換える）
+    pub request: Vec<(String, String)>,
+    /// 上流へ送らないリクエストヘッダー（F-149）
+    pub removed_request: Vec<String>,
+    /// 応答に加えるヘッダー（同名のヘッダーも置き換えずに並べる）
+    pub response: Vec<(String, String)>,
+}
+
+impl ExtraHeaders {
+    /// 応答ヘッダーを 1 つ加える
+    pub fn push_response_header(&mut self, name: &str, value: String) {
+        self.response.push((name.to_string(), value));
+    }
+
+    /// `RateLimit-Policy` / `RateLimit` ヘッダーを加える（F-146）
+    pub fn push_rate_limit_headers(&mut self, decision: &crate::rate_limit::RateLimitDecision) {
+        self.response.extend(
+            decision
+                .headers()
+                .into_iter()
+                .map(|(n, v)| (n.to_string(), v.to_string())),
+        );
+    }
+
+    /// CORS の応答ヘッダーを加える（F-155）
+    ///
+    /// `Vary` は上流やルートの `Vary` とは別の行として並ぶ。
+    pub fn push_cors_headers(&mut self, headers: Vec<(&'static str, String)>) {
+        self.response
+            .extend(headers.into_iter().map(|(n, v)| (n.to_string(), v)));
+    }
+
+    /// 上流へのリクエストヘッダーを加える（認証で得たクレームの転送用、F-148 / F-150 / F-151）
+    ///
+    /// 同じ名前のヘッダーは後から加えたもので置き換える。
+    pub fn set_request_headers(&mut self, headers: Vec<(String, String)>) {
+        for (name, value) in headers {
+            self.request.retain(|(n, _)| !n.eq_ignore_ascii_case(&name));
+            self.request.push((name, value));
+        }
+    }
+
+    /// 外部認可（F-149）が許可時に返したヘッダー操作を加える
+    ///
+    /// 設定するヘッダーはクライアントが送った同名のヘッダーを置き換える。
+    pub fn apply_request_header_changes(&mut self, set: &[(String, String)], remove: &[String]) {
+        self.request
+            .retain(|(n, _)| !remove.iter().any(|r| r.eq_ignore_ascii_case(n)));
+        self.removed_request.extend(remove.iter().cloned());
+        self.set_request_headers(set.to_vec());
+    }
+
+    /// 上流へのリクエストヘッダーを HTTP/2・HTTP/3 向けの小文字の名前で返す
+    pub fn request_header_pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        lowercase_pairs(&self.request)
+    }
+
+    /// 応答ヘッダーを HTTP/2・HTTP/3 向けの小文字の名前で返す
+    pub fn response_header_pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        lowercase_pairs(&self.response)
+    }
+
+    /// クライアントのヘッダー・ルートの追加分を上流へ送らず置き換える（または取り除く）か
+    pub fn overrides_request_header(&self, name: &[u8]) -> bool {
+        self.request
+            .iter()
+            .any(|(n, _)| name.eq_ignore_ascii_case(n.as_bytes()))
+            || self
+                .removed_request
+                .iter()
+                .any(|n| name.eq_ignore_ascii_case(n.as_bytes()))
+    }
+}
+
+fn lowercase_pairs(headers: &[(String, String)]) -> Vec<(Vec<u8>, Vec<u8>)> {
+    headers
+        .iter()
+        .map(|(n, v)| (n.to_ascii_lowercase().into_bytes(), v.as_bytes().to_vec()))
+        .collect()
+}
+
 impl Default for SecurityConfig {
     fn default() -> Self {
         Self {
@@ -636,7 +844,21 @@ impl Default for SecurityConfig {
             client_body_timeout_secs: default_client_body_timeout(),
             allowed_methods: Vec::new(),
             rate_limit_requests_per_min: 0,
+            rate_limit: None,
+            jwt: None,
+            ext_authz: None,
+            oidc: None,
+            credential_auth: None,
+            waf: None,
+            cors: None,
             backend_connect_timeout_secs: default_backend_connect_timeout(),
+            upstream_header_timeout_ms: 0,
+            upstream_idle_timeout_ms: 0,
+            request_timeout_ms: 0,
+            per_try_timeout_ms: 0,
+            grpc_deadline_propagation: true,
+            max_grpc_timeout_ms: 0,
+            upstream_deadline: None,
             max_idle_connections_per_host: default_max_idle_connections(),
             idle_connection_timeout_secs: default_idle_connection_timeout(),
             max_request_header_size: default_max_header_size(),
@@ -1105,6 +1327,16 @@ pub struct GlobalSecurityConfig {
     #[serde(default)]
     pub blocked_ips: Vec<String>,
 
+    /// 不正の兆候から IP を一定時間遮断する動的ブロックリスト（F-152、`[security.auto_ban]`）。
+    /// 遮断中の IP は `blocked_ips` と同じく accept 直後に切断する。
+    #[serde(default)]
+    pub auto_ban: Option<AutoBanConfig>,
+
+    /// 接続元 IP・プレフィックスごとの接続数・ストリーム数・新規接続レートの上限
+    /// （F-153、`[security.client_limits]`）。`max_concurrent_connections` はプロセス全体の上限。
+    #[serde(default)]
+    pub client_limits: Option<ClientLimitsConfig>,
+
     // ====================
     // io_uring / seccomp セキュリティ設定
     // ====================
@@ -1856,6 +2088,204 @@ impl PrometheusConfig {
     }
 }
 
+/// 自動遮断（F-152、`[security.auto_ban]`）
+///
+/// 規則ごとに、クライアント IP が `window_secs` 秒のうちに `signals` のいずれかを
+/// `threshold` 回起こしたら遮断する。遮断時間は同じ IP の遮断のたびに
+/// `escalation_factor` 倍に延びる（`max_ban_secs` まで）。
+///
+/// 例:
+/// ```toml
+/// [security.auto_ban]
+/// ban_secs = 600
+/// persist_file = "/var/lib/veil/bans.json"
+///
+/// [[security.auto_ban.rules]]
+/// name = "auth"
+/// signals = ["unauthorized", "forbidden"]
+/// threshold = 20
+/// window_secs = 60
+/// ```
+#[derive(Deserialize, Clone, Debug)]
+pub struct AutoBanConfig {
+    /// 遮断規則
+    #[serde(default)]
+    pub rules: Vec<AutoBanRuleConfig>,
+
+    /// 初回の遮断時間（秒）
+    #[serde(default = "default_auto_ban_secs")]
+    pub ban_secs: u64,
+
+    /// 遮断時間の上限（秒）
+    #[serde(default = "default_auto_ban_max_secs")]
+    pub max_ban_secs: u64,
+
+    /// 再び遮断するときに遮断時間へ掛ける倍率（1 なら延ばさない）
+    #[serde(default = "default_auto_ban_escalation_factor")]
+    pub escalation_factor: u32,
+
+    /// 遮断が明けてからこの秒数のあいだ遮断されなければ、遮断回数を忘れる
+    #[serde(default = "default_auto_ban_forget_after_secs")]
+    pub forget_after_secs: u64,
+
+    /// IPv6 アドレスをまとめて数えて遮断するプレフィックス長
+    #[serde(default = "default_auto_ban_ipv6_prefix_len")]
+    pub ipv6_prefix_len: u8,
+
+    /// 遮断しない IP/CIDR
+    #[serde(default)]
+    pub exempt_ips: Vec<String>,
+
+    /// 遮断中の IP を保存するファイル（起動時に読み、変更があれば 1 秒ごとに書き出す）
+    #[serde(default)]
+    pub persist_file: Option<String>,
+
+    /// 追跡する IP の上限（超えた分の新しい IP は数えない。遮断中の IP は含む）
+    #[serde(default = "default_auto_ban_max_entries")]
+    pub max_entries: usize,
+}
+
+impl Default for AutoBanConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            ban_secs: default_auto_ban_secs(),
+            max_ban_secs: default_auto_ban_max_secs(),
+            escalation_factor: default_auto_ban_escalation_factor(),
+            forget_after_secs: default_auto_ban_forget_after_secs(),
+            ipv6_prefix_len: default_auto_ban_ipv6_prefix_len(),
+            exempt_ips: Vec::new(),
+            persist_file: None,
+            max_entries: default_auto_ban_max_entries(),
+        }
+    }
+}
+
+/// 自動遮断の規則（F-152、`[[security.auto_ban.rules]]`）
+#[derive(Deserialize, Clone, Debug)]
+pub struct AutoBanRuleConfig {
+    /// 規則名（遮断の理由として一覧・メトリクスに出る）
+    pub name: String,
+
+    /// 数える兆候（`rate_limited` / `unauthorized` / `forbidden` / `not_found` / `h2_flood` / `waf`）
+    pub signals: Vec<String>,
+
+    /// 遮断する回数
+    pub threshold: u32,
+
+    /// 数える期間（秒）
+    #[serde(default = "default_auto_ban_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_auto_ban_secs() -> u64 {
+    600
+}
+
+fn default_auto_ban_max_secs() -> u64 {
+    86_400
+}
+
+fn default_auto_ban_escalation_factor() -> u32 {
+    2
+}
+
+fn default_auto_ban_forget_after_secs() -> u64 {
+    86_400
+}
+
+fn default_auto_ban_ipv6_prefix_len() -> u8 {
+    64
+}
+
+fn default_auto_ban_max_entries() -> usize {
+    100_000
+}
+
+/// 接続元ごとの上限（F-153、`[security.client_limits]`）
+///
+/// `per_ip` は接続元 IP アドレスごと、`per_prefix` は IPv4 の `ipv4_prefix_len`・IPv6 の
+/// `ipv6_prefix_len` のプレフィックスごとの上限。カウンターは全ワーカーで共有する。
+///
+/// 例:
+/// ```toml
+/// [security.client_limits]
+/// exempt_ips = ["100.64.0.0/10"]
+///
+/// [security.client_limits.per_ip]
+/// max_connections = 100
+/// max_new_connections_per_sec = 20
+///
+/// [security.client_limits.per_prefix]
+/// max_connections = 1000
+/// ```
+#[derive(Deserialize, Clone, Debug)]
+pub struct ClientLimitsConfig {
+    /// 接続元 IP ごとの上限
+    #[serde(default)]
+    pub per_ip: ClientLimitValues,
+
+    /// 接続元プレフィックスごとの上限
+    #[serde(default)]
+    pub per_prefix: ClientLimitValues,
+
+    /// `per_prefix` で IPv4 アドレスをまとめるプレフィックス長
+    #[serde(default = "default_client_limits_ipv4_prefix_len")]
+    pub ipv4_prefix_len: u8,
+
+    /// `per_prefix` で IPv6 アドレスをまとめるプレフィックス長
+    #[serde(default = "default_client_limits_ipv6_prefix_len")]
+    pub ipv6_prefix_len: u8,
+
+    /// 上限を適用しない IP/CIDR（信頼する NAT・ロードバランサーなど）
+    #[serde(default)]
+    pub exempt_ips: Vec<String>,
+}
+
+impl Default for ClientLimitsConfig {
+    fn default() -> Self {
+        Self {
+            per_ip: ClientLimitValues::default(),
+            per_prefix: ClientLimitValues::default(),
+            ipv4_prefix_len: default_client_limits_ipv4_prefix_len(),
+            ipv6_prefix_len: default_client_limits_ipv6_prefix_len(),
+            exempt_ips: Vec::new(),
+        }
+    }
+}
+
+/// 接続元ごとの上限値（F-153。0 = 無制限）
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct ClientLimitValues {
+    /// 同時 TCP 接続数（HTTPS・H2C）
+    #[serde(default)]
+    pub max_connections: u32,
+
+    /// 同時 QUIC 接続数（HTTP/3）
+    #[serde(default)]
+    pub max_quic_connections: u32,
+
+    /// HTTP/2・HTTP/3 の同時ストリーム数（全接続の合計）
+    #[serde(default)]
+    pub max_streams: u32,
+
+    /// 1 秒あたりの新規接続数（TCP・QUIC の合計）
+    #[serde(default)]
+    pub max_new_connections_per_sec: u32,
+}
+
+fn default_client_limits_ipv4_prefix_len() -> u8 {
+    24
+}
+
+fn default_client_limits_ipv6_prefix_len() -> u8 {
+    64
+}
+
+fn default_auto_ban_window_secs() -> u64 {
+    60
+}
+
 // 権限降格（get_uid_by_name, get_gid_by_name, drop_privileges）と
 // build_sandbox_config は crate::system モジュールに移動しました。
 
@@ -1898,13 +2328,15 @@ pub fn set_global_blocked_ips(cidrs: &[String]) {
 /// 指定 IP がグローバルブロックリストに含まれるか（accept ホットパス、ゼロアロケーション）。
 ///
 /// ブロックリスト未設定（空）時は即 `false` を返し、ホットパスのオーバーヘッドを発生させない。
+/// 自動遮断（F-152）で遮断中の IP も含む。
 #[inline]
 pub fn is_ip_blocked(ip: std::net::IpAddr) -> bool {
     let list = GLOBAL_BLOCKED_IPS.load();
-    if list.is_empty() {
-        return false;
+    if !list.is_empty() && list.iter().any(|cidr| cidr.contains_addr(ip)) {
+        return true;
     }
-    list.iter().any(|cidr| cidr.contains_addr(ip))
+    // F-152: 自動遮断・管理 API で遮断中の IP（遮断が無ければ原子変数 1 回の読み出しのみ）
+    crate::auto_ban::is_banned(ip)
 }
 
 /// グレースフルシャットダウンタイムアウト（秒）
@@ -1982,138 +2414,6 @@ pub async fn drain_connections(worker_type: &str, thread_id: usize) {
     }
 }
 
-// ====================
-// レートリミッター（スライディングウィンドウ方式）
-// ====================
-//
-// クライアントIPごとに分間リクエスト数を追跡します。
-// スレッドローカルで管理し、ロックフリーで高パフォーマンスを実現。
-// ====================
-
-/// レートリミットのエントリ
-pub struct RateLimitEntry {
-    /// 現在のウィンドウ（分）のリクエスト数
-    pub current_count: u32,
-    /// 前のウィンドウ（分）のリクエスト数
-    pub previous_count: u32,
-    /// 現在のウィンドウの開始時刻（分単位のタイムスタンプ）
-    pub current_minute: u64,
-}
-
-impl RateLimitEntry {
-    pub fn new(current_minute: u64) -> Self {
-        Self {
-            current_count: 1,
-            previous_count: 0,
-            current_minute,
-        }
-    }
-
-    /// リクエストを記録し、現在のレートを返す（スライディングウィンドウ方式）
-    /// 返り値: 推定される分間リクエスト数
-    pub fn record_request(&mut self, now_minute: u64, now_second_in_minute: u32) -> u32 {
-        if now_minute > self.current_minute {
-            if now_minute == self.current_minute + 1 {
-                // 次の分に移行
-                self.previous_count = self.current_count;
-                self.current_count = 1;
-            } else {
-                // 2分以上経過 - リセット
-                self.previous_count = 0;
-                self.current_count = 1;
-            }
-            self.current_minute = now_minute;
-        } else {
-            self.current_count += 1;
-        }
-
-        // スライディングウィンドウによる推定レート計算
-        // 現在の分の経過割合に基づいて重み付け
-        let weight = (60 - now_second_in_minute) as f32 / 60.0;
-        let estimated = (self.previous_count as f32 * weight) + self.current_count as f32;
-        estimated.ceil() as u32
-    }
-}
-
-/// スレッドローカルなレートリミットマップ
-/// キー: クライアントIPアドレス（文字列）
-/// 値: RateLimitEntry
-pub struct RateLimiter {
-    pub entries: HashMap<String, RateLimitEntry>,
-    pub last_cleanup: std::time::Instant,
-}
-
-impl RateLimiter {
-    fn new() -> Self {
-        Self {
-            entries: HashMap::new(),
-            last_cleanup: std::time::Instant::now(),
-        }
-    }
-
-    /// リクエストをチェックし、レート制限を超えていないか確認
-    /// 戻り値: (許可されたか, 現在のレート)
-    ///
-    /// ## パフォーマンス最適化
-    ///
-    /// SystemTime::now()の代わりにCoarse Timerを使用してシステムコールを削減。
-    /// 100ms程度の精度低下は、レートリミットの用途では許容範囲。
-    fn check_and_record(&mut self, client_ip: &str, limit: u64) -> (bool, u32) {
-        // 定期的なクリーンアップ（5分ごと）
-        if self.last_cleanup.elapsed().as_secs() > 300 {
-            self.cleanup();
-            self.last_cleanup = std::time::Instant::now();
-        }
-
-        // Coarse Timerから現在時刻を取得（システムコール削減）
-        // OffsetDateTime から Unix タイムスタンプを計算
-        let now_time = coarse_now();
-        let now_secs = now_time.unix_timestamp() as u64;
-        let now_minute = now_secs / 60;
-        let now_second_in_minute = (now_secs % 60) as u32;
-
-        let rate = if let Some(entry) = self.entries.get_mut(client_ip) {
-            entry.record_request(now_minute, now_second_in_minute)
-        } else {
-            self.entries
-                .insert(client_ip.to_string(), RateLimitEntry::new(now_minute));
-            1
-        };
-
-        (rate as u64 <= limit, rate)
-    }
-
-    /// 古いエントリをクリーンアップ
-    ///
-    /// Coarse Timerを使用してシステムコールを削減。
-    fn cleanup(&mut self) {
-        // Coarse Timerから現在時刻を取得
-        let now_time = coarse_now();
-        let now_minute = now_time.unix_timestamp() as u64 / 60;
-
-        // 2分以上古いエントリを削除
-        self.entries
-            .retain(|_, entry| now_minute.saturating_sub(entry.current_minute) < 2);
-    }
-}
-
-thread_local! {
-    static RATE_LIMITER: RefCell<RateLimiter> = RefCell::new(RateLimiter::new());
-}
-
-/// レートリミットをチェック
-/// 戻り値: レート制限内であればtrue
-pub fn check_rate_limit(client_ip: &str, limit: u64) -> bool {
-    if limit == 0 {
-        return true; // 0 = 無制限
-    }
-
-    RATE_LIMITER.with(|limiter| {
-        let (allowed, _rate) = limiter.borrow_mut().check_and_record(client_ip, limit);
-        allowed
-    })
-}
-
 // ====================
 // TLSコネクタ（スレッドローカル）
 // ====================
@@ -2131,7 +2431,7 @@ thread_local! {
 
         // kTLS が有効な場合のみシークレット抽出を有効化した設定を使用
         let config = (*crate::ktls_rustls::client_config(ktls_enabled)).clone();
-        let config = crate::protocol::configure_alpn_h2_client(config, false);
+        let config = crate::protocol::configure_alpn_http11_client(config);
 
         RustlsConnector::new(Arc::new(config))
             .with_ktls(ktls_enabled)        // 設定に基づいて kTLS を有効化
@@ -2170,7 +2470,7 @@ thread_local! {
         let tcp_cork_enabled = config_guard.ktls_config.tcp_cork_enabled;
 
         let config = (*crate::ktls_rustls::insecure_client_config()).clone();
-        let config = crate::protocol::configure_alpn_h2_client(config, false);
+        let config = crate::protocol::configure_alpn_http11_client(config);
 
         RustlsConnector::new(Arc::new(config))
             .with_ktls(ktls_enabled)
@@ -2202,7 +2502,7 @@ thread_local! {
 thread_local! {
     static TLS_CONNECTOR: simple_tls::SimpleTlsConnector = {
         let config = (*simple_tls::default_client_config()).clone();
-        let config = protocol::configure_alpn_h2_client(config, false);
+        let config = protocol::configure_alpn_http11_client(config);
         simple_tls::SimpleTlsConnector::new(Arc::new(config))
     };
 }
@@ -2221,7 +2521,7 @@ thread_local! {
 thread_local! {
     static TLS_CONNECTOR_INSECURE: simple_tls::SimpleTlsConnector = {
         let config = (*simple_tls::insecure_client_config()).clone();
-        let config = protocol::configure_alpn_h2_client(config, false);
+        let config = protocol::configure_alpn_http11_client(config);
         simple_tls::SimpleTlsConnector::new(Arc::new(config))
     };
 }
@@ -2263,6 +2563,47 @@ pub fn get_tls_connector_insecure() -> crate::simple_tls::SimpleTlsConnector {
     TLS_CONNECTOR_INSECURE.with(|c| c.clone())
 }
 
+/// 上流 TLS コネクタの型（kTLS の有無で切り替え）
+#[cfg(veil_ktls)]
+pub type UpstreamTlsConnector = RustlsConnector;
+
+/// 上流 TLS コネクタの型（kTLS の有無で切り替え）
+#[cfg(not(veil_ktls))]
+pub type UpstreamTlsConnector = crate::simple_tls::SimpleTlsConnector;
+
+// F-134: ALPN h2 を広告するコネクタ（検証あり/なし × h2 のみ/h2+http/1.1）。
+// 既定コネクタ（http/1.1 のみ）から初回利用時に派生させてスレッドごとにキャッシュする。
+#[cfg(feature = "http2")]
+thread_local! {
+    static TLS_CONNECTORS_H2: RefCell<[Option<UpstreamTlsConnector>; 4]> =
+        const { RefCell::new([None, None, None, None]) };
+}
+
+/// ALPN で h2 を広告する TLS コネクタを取得（F-134）
+///
+/// `allow_http11` が true なら `h2, http/1.1`（`protocol = "auto"`）、false なら `h2` のみ。
+#[cfg(feature = "http2")]
+pub fn get_tls_connector_h2(insecure: bool, allow_http11: bool) -> UpstreamTlsConnector {
+    let idx = (insecure as usize) << 1 | allow_http11 as usize;
+    TLS_CONNECTORS_H2.with(|cache| {
+        cache.borrow_mut()[idx]
+            .get_or_insert_with(|| {
+                let base = if insecure {
+                    get_tls_connector_insecure()
+                } else {
+                    get_tls_connector()
+                };
+                let protocols = if allow_http11 {
+                    crate::protocol::negotiation::ALPN_H2_HTTP11
+                } else {
+                    crate::protocol::negotiation::ALPN_H2_ONLY
+                };
+                base.with_alpn_protocols(protocols)
+            })
+            .clone()
+    })
+}
+
 // ====================
 // WASM Response Filter Context
 // ====================
@@ -2281,8 +2622,13 @@ pub struct UpstreamServerEntry {
     pub use_h2c: bool,
     /// 重み（Weighted Round Robin 用、デフォルト 1）
     pub weight: u32,
+    /// 優先度ティア（F-133、0 が最優先。`backup = true` は [`BACKUP_PRIORITY`]）
+    pub priority: u32,
 }
 
+/// `backup = true` のサーバーに割り当てる優先度（最下位ティア、F-133）
+pub const BACKUP_PRIORITY: u32 = u32::MAX;
+
 impl<'de> serde::Deserialize<'de> for UpstreamServerEntry {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -2309,10 +2655,11 @@ impl<'de> serde::Deserialize<'de> for UpstreamServerEntry {
                     sni_name: None,
                     use_h2c: false,
                     weight: 1,
+                    priority: 0,
                 })
             }
 
-            // 構造体形式: { url = "...", sni_name = "...", weight = 2 }
+            // 構造体形式: { url = "...", sni_name = "...", weight = 2, priority = 1 }
             fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
             where
                 M: MapAccess<'de>,
@@ -2321,6 +2668,8 @@ impl<'de> serde::Deserialize<'de> for UpstreamServerEntry {
                 let mut sni_name: Option<String> = None;
                 let mut use_h2c: Option<bool> = None;
                 let mut weight: Option<u32> = None;
+                let mut priority: Option<u32> = None;
+                let mut backup = false;
 
                 while let Some(key) = map.next_key::<String>()? {
                     match key.as_str() {
@@ -2328,6 +2677,8 @@ impl<'de> serde::Deserialize<'de> for UpstreamServerEntry {
                         "sni_name" => sni_name = Some(map.next_value()?),
                         "use_h2c" | "h2c" => use_h2c = Some(map.next_value()?),
                         "weight" => weight = Some(map.next_value()?),
+                        "priority" => priority = Some(map.next_value()?),
+                        "backup" => backup = map.next_value()?,
                         _ => {
                             let _: serde::de::IgnoredAny = map.next_value()?;
                         }
@@ -2338,11 +2689,18 @@ impl<'de> serde::Deserialize<'de> for UpstreamServerEntry {
                 let use_h2c = use_h2c.unwrap_or(false);
                 // weight=0 は無効なので最低1に補正
                 let weight = weight.unwrap_or(1).max(1);
+                // backup は最下位ティア（priority 指定より優先）
+                let priority = if backup {
+                    BACKUP_PRIORITY
+                } else {
+                    priority.unwrap_or(0)
+                };
                 Ok(UpstreamServerEntry {
                     url,
                     sni_name,
                     use_h2c,
                     weight,
+                    priority,
                 })
             }
         }
@@ -2358,12 +2716,19 @@ pub struct UpstreamConfig {
     /// - "round_robin": ラウンドロビン（デフォルト）
     /// - "least_conn": Least Connections
     /// - "ip_hash": クライアントIPハッシュ
+    /// - "consistent_hash" / "bounded_load" / "maglev": 一貫ハッシュ系
     #[serde(default)]
     pub algorithm: LoadBalanceAlgorithm,
-    /// Consistent Hash 用のハッシュキー（algorithm = "consistent_hash" 時のみ有効）
+    /// ハッシュ系アルゴリズム用のハッシュキー（"ip" / "path" / "header:X" / "cookie:X"）
     /// 省略時は IP ベース
     #[serde(default)]
     pub hash_key: Option<HashKey>,
+    /// Bounded-Load の負荷係数 c（各サーバーの上限 = ceil(c × 平均負荷)、> 1.0）
+    #[serde(default = "default_hash_load_factor")]
+    pub hash_load_factor: f64,
+    /// Maglev ルックアップテーブルサイズ（素数、サーバー数より十分大きいこと）
+    #[serde(default = "default_maglev_table_size")]
+    pub maglev_table_size: usize,
     /// バックエンドサーバーエントリ一覧
     /// 文字列形式と構造体形式の両方をサポート
     pub servers: Vec<UpstreamServerEntry>,
@@ -2381,3175 +2746,7390 @@ pub struct UpstreamConfig {
     /// 異常検知（Outlier Detection）設定（F-06）
     #[serde(default)]
     pub outlier_detection: OutlierConfig,
+    /// パッシブヘルスチェック（F-144、省略時は無効）
+    #[serde(default)]
+    pub passive_health: Option<PassiveHealthConfig>,
+    /// 適応型の同時実行数制限（F-138、省略時は無効）
+    #[serde(default)]
+    pub adaptive_concurrency: AdaptiveConcurrencyConfig,
+    /// プロキシ発行のスティッキーセッション Cookie（F-132、省略時は無効）
+    #[serde(default)]
+    pub sticky_cookie: Option<StickyCookieConfig>,
+    /// スロースタート設定（F-133、復帰サーバーの重みを段階的に戻す）
+    #[serde(default)]
+    pub slow_start: SlowStartConfig,
+    /// 優先度ティアのフェイルオーバー閾値（F-133、0.0-1.0）
+    ///
+    /// 上位ティアの利用可能容量（重み比）がこの値を下回ると次のティアも候補に加える。
+    /// 0.0（デフォルト）は上位ティアが全滅したときのみ下位ティアを使う。
+    #[serde(default)]
+    pub priority_failover_threshold: f64,
+    /// 上流との HTTP プロトコル（F-134 / F-135、"http1" / "h2" / "auto" / "h3"）
+    #[serde(default)]
+    pub protocol: UpstreamProtocol,
+    /// h2 / h3 接続 1 本あたりの同時ストリーム上限（F-134、サーバーの SETTINGS 値・
+    /// QUIC の MAX_STREAMS とで小さい方）
+    #[serde(default = "default_upstream_h2_max_concurrent_streams")]
+    pub h2_max_concurrent_streams: u32,
+    /// 多重化接続（ALPN h2・HTTP/3）で受け取る応答ボディの上限（F-134 / F-135、バイト）
+    ///
+    /// 応答はバッファしてからクライアントへ返すため、超えたストリームは打ち切って 502 にする。
+    #[serde(default = "default_upstream_max_response_body_bytes")]
+    pub max_response_body_bytes: usize,
+    /// 上流コネクションプールの上限とライフサイクル（F-136）
+    #[serde(default)]
+    pub connection_pool: ConnectionPoolConfig,
+    /// クライアントの IP を送信元として上流へ接続する（F-141、IP_TRANSPARENT、Linux のみ）
+    #[serde(default)]
+    pub transparent: bool,
+    /// 上流接続の送信元 IP（F-141、文字列 1 個または配列。複数はラウンドロビン）
+    #[serde(default, deserialize_with = "deserialize_source_addresses")]
+    pub source_address: Vec<IpAddr>,
+    /// 上流接続に適用するソケットプロファイル名（F-142）
+    #[serde(default)]
+    pub socket_profile: Option<String>,
+    /// 解決済みのソケットプロファイル（設定ファイルからは読まない、F-142）
+    #[serde(skip)]
+    pub socket: Option<Arc<SocketOptions>>,
 }
 
-/// サーキットブレーカー設定（F-06）
+/// 上流コネクションプールの上限とライフサイクル（F-136）
 ///
-/// アップストリームサーバー単位で適用される。連続失敗が閾値を超えると
-/// Open 状態へ遷移し、一定時間経過後 HalfOpen でプローブを行う。
-#[derive(Deserialize, Clone, Debug)]
-pub struct CircuitBreakerConfig {
-    /// 有効化フラグ
+/// 上限はワーカースレッドごと・上流ホスト（host:port）ごとに適用する（thread-per-core で
+/// プールがスレッドローカルのため）。HTTP/1.1（HTTP / HTTPS）と h2c のプールが対象で、
+/// ALPN h2・HTTP/3 の多重化接続はストリーム上限（`h2_max_concurrent_streams`）で制御する。
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConnectionPoolConfig {
+    /// 同時に使用中の上流接続数の上限（0 = 無制限、デフォルト）
     #[serde(default)]
-    pub enabled: bool,
-    /// Open へ遷移する失敗回数の閾値
-    #[serde(default = "default_cb_failure_threshold")]
-    pub failure_threshold: u32,
-    /// 失敗をカウントするスライディングウィンドウ（秒）
-    #[serde(default = "default_cb_failure_window")]
-    pub failure_window_secs: u64,
-    /// Open 状態を維持する時間（秒）
-    #[serde(default = "default_cb_open_duration")]
-    pub open_duration_secs: u64,
-    /// HalfOpen 時に許可するプローブ数
-    #[serde(default = "default_cb_half_open_probes")]
-    pub half_open_probes: u32,
-    /// HalfOpen から Closed へ戻る成功回数の閾値
-    #[serde(default = "default_cb_success_threshold")]
-    pub success_threshold: u32,
-    /// タイムアウトを失敗としてカウントするか
-    #[serde(default = "default_true")]
-    pub trip_on_timeout: bool,
+    pub max_connections: usize,
+    /// 新規 connect の同時実行数の上限（B-44 の接続ストーム対策、デフォルト: 64）
+    #[serde(default = "default_pool_max_concurrent_connects")]
+    pub max_concurrent_connects: usize,
+    /// 接続の空きを待つリクエスト数の上限（超過分は即座に 503、デフォルト: 1024）
+    #[serde(default = "default_pool_max_pending_requests")]
+    pub max_pending_requests: usize,
+    /// 接続の空きを待つ最大時間（ミリ秒、超過で 503、デフォルト: 10000）
+    #[serde(default = "default_pool_pending_timeout_ms")]
+    pub pending_timeout_ms: u64,
+    /// 1 接続で処理するリクエスト数の上限（到達した接続はプールへ戻さず閉じる、0 = 無制限）
+    #[serde(default)]
+    pub max_requests_per_connection: u32,
+    /// 接続の最大寿命（秒、確立から経過した接続はプールへ戻さず閉じる、0 = 無制限）
+    #[serde(default)]
+    pub max_connection_lifetime_secs: u64,
+    /// 起動時に各ワーカーでサーバーごとに事前確立する接続数（0 = 無効、デフォルト）
+    #[serde(default)]
+    pub prewarm_connections: usize,
 }
 
-impl Default for CircuitBreakerConfig {
+impl Default for ConnectionPoolConfig {
     fn default() -> Self {
         Self {
-            enabled: false,
-            failure_threshold: default_cb_failure_threshold(),
-            failure_window_secs: default_cb_failure_window(),
-            open_duration_secs: default_cb_open_duration(),
-            half_open_probes: default_cb_half_open_probes(),
-            success_threshold: default_cb_success_threshold(),
-            trip_on_timeout: true,
+            max_connections: 0,
+            max_concurrent_connects: default_pool_max_concurrent_connects(),
+            max_pending_requests: default_pool_max_pending_requests(),
+            pending_timeout_ms: default_pool_pending_timeout_ms(),
+            max_requests_per_connection: 0,
+            max_connection_lifetime_secs: 0,
+            prewarm_connections: 0,
         }
     }
 }
 
-fn default_cb_failure_threshold() -> u32 {
-    5
+impl ConnectionPoolConfig {
+    /// 接続の空き待ちの最大時間
+    #[inline]
+    pub fn pending_timeout(&self) -> Duration {
+        Duration::from_millis(self.pending_timeout_ms)
+    }
 }
-fn default_cb_failure_window() -> u64 {
-    60
+
+fn default_pool_max_concurrent_connects() -> usize {
+    64
 }
-fn default_cb_open_duration() -> u64 {
-    30
+fn default_pool_max_pending_requests() -> usize {
+    1024
 }
-fn default_cb_half_open_probes() -> u32 {
-    3
+fn default_pool_pending_timeout_ms() -> u64 {
+    10_000
 }
-fn default_cb_success_threshold() -> u32 {
-    2
+
+/// `source_address` を文字列 1 個または配列として読む（F-141）
+fn deserialize_source_addresses<'de, D>(deserializer: D) -> Result<Vec<IpAddr>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(IpAddr),
+        Many(Vec<IpAddr>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(ip) => vec![ip],
+        OneOrMany::Many(ips) => ips,
+    })
 }
 
-/// 異常検知（パッシブ Outlier Detection）設定（F-06）
+/// 上流接続の送信元アドレス（F-141）
+///
+/// `transparent = true` はクライアントの IP、`source_address` は指定したローカル IP を
+/// 送信元として bind する。送信元ごとに接続が分かれるため、プールキーにも送信元を含める。
+#[derive(Debug)]
+pub enum UpstreamSource {
+    /// クライアントの IP を送信元にする（IP_TRANSPARENT）
+    Transparent,
+    /// 指定したローカル IP（複数はラウンドロビン）
+    Addresses {
+        addrs: Vec<IpAddr>,
+        next: AtomicUsize,
+    },
+}
+
+impl UpstreamSource {
+    /// 設定から構築する（どちらも未設定なら None）
+    pub fn from_config(transparent: bool, addrs: &[IpAddr]) -> Option<Arc<Self>> {
+        if transparent {
+            Some(Arc::new(Self::Transparent))
+        } else if addrs.is_empty() {
+            None
+        } else {
+            Some(Arc::new(Self::Addresses {
+                addrs: addrs.to_vec(),
+                next: AtomicUsize::new(0),
+            }))
+        }
+    }
+
+    /// この接続で bind する送信元を決める（透過モードでクライアント IP が無ければエラー）
+    pub fn local_bind(&self, client: Option<IpAddr>) -> io::Result<LocalBind> {
+        match self {
+            Self::Transparent => client.map(|ip| LocalBind::new(ip, true)).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "transparent upstream requires an IP client address",
+                )
+            }),
+            Self::Addresses { addrs, next } => {
+                let i = next.fetch_add(1, Ordering::Relaxed) % addrs.len();
+                Ok(LocalBind::new(addrs[i], false))
+            }
+        }
+    }
+}
+
+/// 送信元指定の妥当性チェック（F-141、upstream と L4 リスナーで共通）
+fn validate_upstream_source(
+    kind: &str,
+    name: &str,
+    transparent: bool,
+    addrs: &[IpAddr],
+) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} '{}': {}", kind, name, msg),
+        ))
+    };
+    if transparent && !addrs.is_empty() {
+        return invalid("transparent and source_address are mutually exclusive".into());
+    }
+    if transparent && !cfg!(target_os = "linux") {
+        return invalid("transparent requires Linux (IP_TRANSPARENT)".into());
+    }
+    if !addrs.is_empty() && cfg!(windows) {
+        return invalid("source_address is not supported on Windows".into());
+    }
+    if let Some(ip) = addrs
+        .iter()
+        .find(|ip| ip.is_unspecified() || ip.is_multicast())
+    {
+        return invalid(format!("source_address {} is not a unicast address", ip));
+    }
+    if addrs.iter().any(|ip| ip.is_ipv6() != addrs[0].is_ipv6()) {
+        return invalid("source_address must not mix IPv4 and IPv6 addresses".into());
+    }
+    Ok(())
+}
+
+/// `[socket_profiles]` をすべて解決する（F-142、値が不正なプロファイルはエラー）
+fn resolve_socket_profiles(config: &Config) -> io::Result<HashMap<String, Arc<SocketOptions>>> {
+    config
+        .socket_profiles
+        .iter()
+        .map(|(name, profile)| Ok((name.clone(), Arc::new(profile.resolve(name)?))))
+        .collect()
+}
+
+/// `socket_profile` の参照を解決する（F-142、未定義の名前はエラー）
+fn lookup_socket_profile(
+    profiles: &HashMap<String, Arc<SocketOptions>>,
+    owner: &str,
+    name: Option<&str>,
+) -> io::Result<Option<Arc<SocketOptions>>> {
+    let Some(name) = name else {
+        return Ok(None);
+    };
+    profiles.get(name).cloned().map(Some).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}: unknown socket profile '{}'", owner, name),
+        )
+    })
+}
+
+/// ソケットプロファイルを参照元（upstream / L4）へ設定し、`[server]` のリスナー用を返す（F-142）
+fn apply_socket_profiles(config: &mut Config) -> io::Result<Option<Arc<SocketOptions>>> {
+    let profiles = resolve_socket_profiles(config)?;
+    for (name, upstream) in config.upstreams.iter_mut().flatten() {
+        upstream.socket = lookup_socket_profile(
+            &profiles,
+            &format!("Upstream '{}'", name),
+            upstream.socket_profile.as_deref(),
+        )?;
+    }
+    #[cfg(feature = "l4-proxy")]
+    for l4 in config.l4.iter_mut().flatten() {
+        let owner = format!("L4 listener '{}'", l4.name);
+        l4.listener_socket =
+            lookup_socket_profile(&profiles, &owner, l4.socket_profile.as_deref())?;
+        l4.upstream_socket =
+            lookup_socket_profile(&profiles, &owner, l4.upstream_socket_profile.as_deref())?;
+    }
+    lookup_socket_profile(
+        &profiles,
+        "[server]",
+        config.server.socket_profile.as_deref(),
+    )
+}
+
+/// `veil -t` で表示するソケットプロファイルの一覧と適用先（F-142）
+///
+/// Linux では輻輳制御アルゴリズムがカーネルで使えるか、backlog が net.core.somaxconn で
+/// 頭打ちになるかも確認する（起動時は setsockopt の失敗としてしか分からないため）。
+// 理由付き allow: 設定検証（`veil -t`）時のみ実行されるコールドパス。
+#[allow(clippy::disallowed_methods)]
+fn socket_profile_report(config: &Config) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut names: Vec<&String> = config.socket_profiles.keys().collect();
+    names.sort();
+    for name in names {
+        if let Ok(opts) = config.socket_profiles[name].resolve(name) {
+            lines.push(format!("socket profile \"{}\": {}", name, opts));
+        }
+    }
+    let mut uses = Vec::new();
+    if let Some(profile) = &config.server.socket_profile {
+        let mut listeners = vec![config.server.listen.as_str()];
+        listeners.extend(config.server.http.as_deref());
+        #[cfg(feature = "http2")]
+        listeners.extend(config.server.h2c_listen.as_deref());
+        for listen in listeners {
+            uses.push((format!("listener {}", listen), profile));
+        }
+    }
+    let mut upstreams: Vec<_> = config.upstreams.iter().flatten().collect();
+    upstreams.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, upstream) in upstreams {
+        if let Some(profile) = &upstream.socket_profile {
+            uses.push((format!("upstream \"{}\"", name), profile));
+        }
+    }
+    #[cfg(feature = "l4-proxy")]
+    for l4 in config.l4.iter().flatten() {
+        if let Some(profile) = &l4.socket_profile {
+            uses.push((
+                format!("l4 \"{}\" listener {}", l4.name, l4.listen),
+                profile,
+            ));
+        }
+        if let Some(profile) = &l4.upstream_socket_profile {
+            uses.push((format!("l4 \"{}\" upstreams", l4.name), profile));
+        }
+    }
+    for (target, profile) in uses {
+        lines.push(format!("{} uses socket profile \"{}\"", target, profile));
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let read_sysctl = |path: &str| fs::read_to_string(path).ok();
+        let available = read_sysctl("/proc/sys/net/ipv4/tcp_available_congestion_control");
+        let allowed = read_sysctl("/proc/sys/net/ipv4/tcp_allowed_congestion_control");
+        let somaxconn =
+            read_sysctl("/proc/sys/net/core/somaxconn").and_then(|v| v.trim().parse::<u32>().ok());
+        let mut profiles: Vec<_> = config.socket_profiles.iter().collect();
+        profiles.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, profile) in profiles {
+            if let (Some(cc), Some(available)) = (&profile.congestion, &available) {
+                if !available.split_whitespace().any(|a| a == cc) {
+                    lines.push(format!(
+                        "warning: socket profile \"{}\": congestion control \"{}\" is not available (tcp_available_congestion_control: {})",
+                        name,
+                        cc,
+                        available.trim()
+                    ));
+                } else if allowed
+                    .as_ref()
+                    .is_some_and(|allowed| !allowed.split_whitespace().any(|a| a == cc))
+                {
+                    lines.push(format!(
+                        "warning: socket profile \"{}\": congestion control \"{}\" is not in tcp_allowed_congestion_control and needs CAP_NET_ADMIN",
+                        name, cc
+                    ));
+                }
+            }
+            if let (Some(backlog), Some(max)) = (profile.backlog, somaxconn) {
+                if backlog > max {
+                    lines.push(format!(
+                        "warning: socket profile \"{}\": backlog {} is capped by net.core.somaxconn ({})",
+                        name, backlog, max
+                    ));
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// 上流との HTTP プロトコル（F-134）
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum UpstreamProtocol {
+    /// HTTP/1.1（デフォルト）
+    #[default]
+    #[serde(rename = "http1", alias = "http/1.1")]
+    Http1,
+    /// HTTP/2。https は ALPN で h2 必須、http は h2c（Prior Knowledge）
+    #[serde(rename = "h2")]
+    H2,
+    /// https で ALPN に h2 と http/1.1 を広告し、サーバーの選択に従う
+    #[serde(rename = "auto")]
+    Auto,
+    /// HTTP/3（QUIC、F-135）。https のみ。UDP が通らない場合は TCP（HTTP/1.1）へフォールバック
+    #[serde(rename = "h3")]
+    H3,
+}
+
+fn default_upstream_h2_max_concurrent_streams() -> u32 {
+    100
+}
+
+fn default_upstream_max_response_body_bytes() -> usize {
+    64 * 1024 * 1024
+}
+
+/// スロースタート設定（F-133）
+///
+/// ヘルスチェックで healthy に戻ったサーバー、または Outlier 排除から復帰した
+/// サーバーの実効重みを `window_secs` かけて戻す。実効重みの係数は
+/// `max(min_weight_percent / 100, (経過 / window) ^ (1 / aggression))`。
 #[derive(Deserialize, Clone, Debug)]
-pub struct OutlierConfig {
-    /// 有効化フラグ
+pub struct SlowStartConfig {
+    /// ランプアップ時間（秒）。0 で無効（デフォルト）
     #[serde(default)]
-    pub enabled: bool,
-    /// エラー率の閾値（0.0-1.0、デフォルト 0.5）
-    #[serde(default = "default_outlier_error_rate")]
-    pub error_rate_threshold: f64,
-    /// 評価間隔（秒）
-    #[serde(default = "default_outlier_interval")]
-    pub interval_secs: u64,
-    /// 基本排除時間（秒）
-    #[serde(default = "default_outlier_base_ejection")]
-    pub base_ejection_time_secs: u64,
-    /// 同時に排除可能なサーバーの最大割合（%）
-    #[serde(default = "default_outlier_max_eject_percent")]
-    pub max_ejection_percent: u32,
+    pub window_secs: u64,
+    /// 曲線の形状（1.0 = 線形、> 1.0 で序盤に速く立ち上がる、< 1.0 で序盤を抑える）
+    #[serde(default = "default_slow_start_aggression")]
+    pub aggression: f64,
+    /// 復帰直後の最小係数（%、1-100）
+    #[serde(default = "default_slow_start_min_weight_percent")]
+    pub min_weight_percent: u32,
 }
 
-impl Default for OutlierConfig {
+impl Default for SlowStartConfig {
     fn default() -> Self {
         Self {
-            enabled: false,
-            error_rate_threshold: default_outlier_error_rate(),
-            interval_secs: default_outlier_interval(),
-            base_ejection_time_secs: default_outlier_base_ejection(),
-            max_ejection_percent: default_outlier_max_eject_percent(),
+            window_secs: 0,
+            aggression: default_slow_start_aggression(),
+            min_weight_percent: default_slow_start_min_weight_percent(),
         }
     }
 }
 
-fn default_outlier_error_rate() -> f64 {
-    0.5
-}
-fn default_outlier_interval() -> u64 {
-    10
+impl SlowStartConfig {
+    /// 復帰からの経過ミリ秒に対する実効重み係数（0.0-1.0）
+    pub fn factor(&self, elapsed_ms: u64) -> f64 {
+        let window_ms = self.window_secs.saturating_mul(1000);
+        if window_ms == 0 || elapsed_ms >= window_ms {
+            return 1.0;
+        }
+        let t = elapsed_ms as f64 / window_ms as f64;
+        let aggression = if self.aggression > 0.0 {
+            self.aggression
+        } else {
+            1.0
+        };
+        let min = (self.min_weight_percent.clamp(1, 100) as f64) / 100.0;
+        t.powf(1.0 / aggression).clamp(min, 1.0)
+    }
 }
-fn default_outlier_base_ejection() -> u64 {
-    30
+
+fn default_slow_start_aggression() -> f64 {
+    1.0
 }
-fn default_outlier_max_eject_percent() -> u32 {
-    50
+fn default_slow_start_min_weight_percent() -> u32 {
+    10
 }
 
-// 注: リトライポリシー（RetryPolicy）は F-06 で構造体のみ定義されたが、リトライ機構
-// 本体が未実装でどこからも参照されない dead code だったため F-51 で削除した。
-// リトライを実装する際は resilience.rs と併せて再設計すること。
-
-/// ヘルスチェックの種別（F-22）
-#[derive(Deserialize, Clone, Debug, PartialEq)]
-#[serde(rename_all = "snake_case")]
-#[derive(Default)]
-pub enum HealthCheckType {
-    /// HTTP/HTTPS リクエストを送信してステータスコードを確認（デフォルト）
+/// Cookie の SameSite 属性（F-132）
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SameSite {
     #[default]
-    Http,
-    /// TCP 接続の確立可否のみ確認
-    Tcp,
-    /// gRPC Health Checking Protocol (grpc.health.v1.Health/Check)
-    Grpc,
+    Lax,
+    Strict,
+    None,
 }
 
-/// 健康チェック設定
+impl SameSite {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Lax => "Lax",
+            SameSite::Strict => "Strict",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// スティッキーセッション Cookie 設定（F-132）
+///
+/// veil が HMAC 署名付きのサーバー ID を Cookie として発行し、以降のリクエストを
+/// そのサーバーへ固定する。固定先が利用不可（unhealthy / 排除中 / CB Open）なら
+/// 通常のアルゴリズムで選び直し、新しい Cookie を発行する（透過的な再固定）。
 #[derive(Deserialize, Clone, Debug)]
-pub struct HealthCheckConfig {
-    /// チェック種別（http / tcp / grpc）。省略時は http。
+pub struct StickyCookieConfig {
+    /// Cookie 名
+    #[serde(default = "default_sticky_cookie_name")]
+    pub name: String,
+    /// 有効期間（秒）。0 はセッション Cookie（Max-Age なし・署名側でも期限なし）
     #[serde(default)]
-    pub check_type: HealthCheckType,
-    /// チェック間隔（秒）
-    #[serde(default = "default_health_check_interval")]
-    pub interval_secs: u64,
-    /// チェック対象パス（HTTP チェック時のリクエストパス、gRPC チェック時のサービス名）
-    #[serde(default = "default_health_check_path")]
+    pub ttl_secs: u64,
+    /// Cookie の Path 属性
+    #[serde(default = "default_sticky_cookie_path")]
     pub path: String,
-    /// タイムアウト（秒）
-    #[serde(default = "default_health_check_timeout")]
-    pub timeout_secs: u64,
-    /// 成功と判断するHTTPステータスコード（HTTP チェック時のみ有効、デフォルト: 200-399）
-    #[serde(default = "default_healthy_statuses")]
-    pub healthy_statuses: Vec<u16>,
-    /// 何回連続で失敗したら unhealthy とするか
-    #[serde(default = "default_unhealthy_threshold")]
-    pub unhealthy_threshold: u32,
-    /// 何回連続で成功したら healthy に戻すか
-    #[serde(default = "default_healthy_threshold")]
-    pub healthy_threshold: u32,
-    /// TLS接続を使用するかどうか
-    /// デフォルト: false（既存のHTTP健康チェックを使用）
-    #[serde(default)]
-    pub use_tls: bool,
-    /// 証明書検証を有効化するかどうか（use_tls=true時のみ有効）
-    /// デフォルト: true
+    /// Secure 属性
     #[serde(default = "default_true")]
-    pub verify_cert: bool,
+    pub secure: bool,
+    /// HttpOnly 属性
+    #[serde(default = "default_true")]
+    pub http_only: bool,
+    /// SameSite 属性（"lax" / "strict" / "none"）
+    #[serde(default)]
+    pub same_site: SameSite,
+    /// HMAC-SHA256 署名鍵。省略時はプロセス起動ごとのランダム鍵
+    /// （再起動や複数インスタンス間では Cookie が無効になり再固定される）
+    #[serde(default)]
+    pub secret: Option<String>,
 }
 
-fn default_health_check_interval() -> u64 {
-    10
+fn default_sticky_cookie_name() -> String {
+    "veil_sticky".to_string()
 }
-fn default_health_check_path() -> String {
+
+fn default_sticky_cookie_path() -> String {
     "/".to_string()
 }
-fn default_health_check_timeout() -> u64 {
-    5
-}
-fn default_healthy_statuses() -> Vec<u16> {
-    vec![200, 201, 202, 204, 301, 302, 304]
-}
-fn default_unhealthy_threshold() -> u32 {
-    3
-}
-fn default_healthy_threshold() -> u32 {
-    2
-}
 
-impl Default for HealthCheckConfig {
+impl Default for StickyCookieConfig {
     fn default() -> Self {
         Self {
-            check_type: HealthCheckType::Http,
-            interval_secs: default_health_check_interval(),
-            path: default_health_check_path(),
-            timeout_secs: default_health_check_timeout(),
-            healthy_statuses: default_healthy_statuses(),
-            unhealthy_threshold: default_unhealthy_threshold(),
-            healthy_threshold: default_healthy_threshold(),
-            use_tls: false,
-            verify_cert: default_true(),
+            name: default_sticky_cookie_name(),
+            ttl_secs: 0,
+            path: default_sticky_cookie_path(),
+            secure: true,
+            http_only: true,
+            same_site: SameSite::default(),
+            secret: None,
         }
     }
 }
 
-// ====================
-// 統合ルーティング（AWS ALB準拠）
-// ====================
-
-/// ルーティング条件（AWS ALB準拠）
+/// リクエストヘッジング設定（F-137、ルート単位）
 ///
-/// すべての条件はANDで結合されます。
-/// 条件が指定されていない場合は、すべてのリクエストにマッチします（デフォルトルート）。
-#[derive(Clone, Debug, Deserialize, Default)]
-pub struct RouteConditions {
-    /// host-header: ホスト名マッチ（ワイルドカード対応）
-    /// 例: "api.example.com", "*.example.com"
+/// 冪等メソッドで一次試行が `delay` 内に応答ヘッダーを返し始めない場合、同じ upstream の
+/// 別サーバーへ二次試行を送り、先に応答した方を採用する（もう一方は取り消す）。
+/// 対象は平文 HTTP/1.1 の upstream かつリクエストボディなしのリクエストのみ。
+#[derive(Deserialize, Clone, Debug)]
+pub struct HedgingConfig {
+    /// ヘッジを送るまでの固定遅延（ミリ秒）。0 なら upstream ごとの観測パーセンタイル
     #[serde(default)]
-    pub host: Option<String>,
+    pub delay_ms: u64,
+    /// 適応遅延に使うパーセンタイル（0 < p <= 100）
+    #[serde(default = "default_hedging_percentile")]
+    pub percentile: f64,
+    /// 適応遅延の下限（ミリ秒）
+    #[serde(default = "default_hedging_min_delay_ms")]
+    pub min_delay_ms: u64,
+    /// 適応遅延の上限（ミリ秒）。観測サンプルが揃うまではこの値を使う
+    #[serde(default = "default_hedging_max_delay_ms")]
+    pub max_delay_ms: u64,
+    /// ヘッジ予算: 対象リクエスト数に対するヘッジの最大割合（%）
+    #[serde(default = "default_hedging_budget_percent")]
+    pub budget_percent: f64,
+    /// 対象メソッド（冪等メソッドのみ指定可）
+    #[serde(default = "default_hedging_methods")]
+    pub methods: Vec<String>,
+}
+
+/// ヘッジ対象に指定できる冪等メソッド（RFC 9110 9.2.2）
+pub const HEDGING_IDEMPOTENT_METHODS: &[&str] =
+    &["GET", "HEAD", "OPTIONS", "TRACE", "PUT", "DELETE"];
+
+fn default_hedging_percentile() -> f64 {
+    95.0
+}
+
+fn default_hedging_min_delay_ms() -> u64 {
+    5
+}
 
-    /// path-pattern: パスマッチ（ワイルドカード対応）
-    /// 例: "/api/*", "/static/*", "/api/v2/*"
-    #[serde(default)]
-    pub path: Option<String>,
+fn default_hedging_max_delay_ms() -> u64 {
+    1000
+}
 
-    /// http-header: HTTPヘッダーマッチ（複数指定可能）
-    /// 例: { "X-Version" = "v2" }
-    #[serde(default)]
-    pub header: Option<HashMap<String, String>>,
+fn default_hedging_budget_percent() -> f64 {
+    10.0
+}
 
-    /// http-request-method: HTTPメソッドマッチ（配列対応）
-    /// 例: ["GET", "POST"]
-    #[serde(default)]
-    pub method: Option<Vec<String>>,
+fn default_hedging_methods() -> Vec<String> {
+    vec!["GET".to_string(), "HEAD".to_string(), "OPTIONS".to_string()]
+}
 
-    /// query-string: クエリパラメータマッチ（複数指定可能）
-    /// 例: { "key" = "value" }
-    #[serde(default)]
-    pub query: Option<HashMap<String, String>>,
+impl Default for HedgingConfig {
+    fn default() -> Self {
+        Self {
+            delay_ms: 0,
+            percentile: default_hedging_percentile(),
+            min_delay_ms: default_hedging_min_delay_ms(),
+            max_delay_ms: default_hedging_max_delay_ms(),
+            budget_percent: default_hedging_budget_percent(),
+            methods: default_hedging_methods(),
+        }
+    }
+}
 
-    /// source-ip: ソースIPマッチ（CIDR表記）
-    /// 例: ["192.168.0.0/16", "10.0.0.0/8"]
+/// トークンバケット式レートリミットの規則（F-146、ルート単位で複数指定可）
+///
+/// `rate` 回 / `period_secs` 秒の速度で補充される容量 `burst` のバケットを、`key` で
+/// 組み立てたキーごとに全ワーカーで共有する。
+#[derive(Deserialize, Clone, Debug)]
+pub struct RateLimitRuleConfig {
+    /// 規則名（`RateLimit-Policy` の識別子・メトリクスのラベル）
+    ///
+    /// 名前とパラメータが同じ規則はルートをまたいでバケットを共有する。
+    pub name: String,
+    /// キーの構成要素（`client_ip` / `header:<name>` / `cookie:<name>` / `path` / `route` /
+    /// `jwt_claim:<claim>` / `api_key`）
+    #[serde(default = "default_rate_limit_key")]
+    pub key: Vec<String>,
+    /// `period_secs` あたりの許可数
+    pub rate: u32,
+    /// 補充の単位（秒）
+    #[serde(default = "default_rate_limit_period_secs")]
+    pub period_secs: u64,
+    /// バケット容量（連続で許可できる数、0 = `rate`）
     #[serde(default)]
-    pub source_ip: Option<Vec<String>>,
+    pub burst: u32,
+    /// 規則を適用するレートリミットのクラス（F-151、API キーの `rate_limit_class`、空ならすべて）
+    #[serde(default)]
+    pub classes: Vec<String>,
 }
 
-/// ルーティングルール
+fn default_rate_limit_key() -> Vec<String> {
+    vec!["client_ip".to_string()]
+}
+
+fn default_rate_limit_period_secs() -> u64 {
+    1
+}
+
+impl RateLimitRuleConfig {
+    /// 実効のバケット容量
+    pub fn effective_burst(&self) -> u32 {
+        if self.burst == 0 {
+            self.rate
+        } else {
+            self.burst
+        }
+    }
+}
+
+/// 外部レートリミットサービスの接続先（F-147、トップレベル `[rate_limit_service]`）
 ///
-/// 条件に一致するリクエストに対して、指定されたバックエンドアクションを実行します。
-#[derive(Clone, Debug, Deserialize)]
-pub struct Route {
-    /// ルーティング条件（空の場合はデフォルトルート）
-    #[serde(default)]
-    pub conditions: RouteConditions,
+/// Envoy の Rate Limit Service（`envoy.service.ratelimit.v3`）を h2c（平文 HTTP/2）で呼び出す。
+#[derive(Deserialize, Clone, Debug)]
+pub struct RateLimitServiceConfig {
+    /// 接続先（`host:port`）
+    pub address: String,
+    /// `RateLimitRequest.domain`
+    #[serde(default = "default_rate_limit_service_domain")]
+    pub domain: String,
+    /// 1 回の問い合わせのタイムアウト（ミリ秒）
+    #[serde(default = "default_rate_limit_service_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_rate_limit_service_domain() -> String {
+    "veil".to_string()
+}
 
-    /// バックエンドアクション
-    pub action: BackendConfig,
+fn default_rate_limit_service_timeout_ms() -> u64 {
+    100
+}
 
-    /// ルートレベルのセキュリティ設定（actionの設定をオーバーライド）
+/// ルート単位のグローバルレートリミット（F-147）
+#[derive(Deserialize, Clone, Debug)]
+pub struct GlobalRateLimitConfig {
+    /// 送信するディスクリプタ（値を解決できないディスクリプタは送らない）
+    pub descriptors: Vec<RateLimitDescriptorConfig>,
+    /// サービスに問い合わせできないときの扱い
     #[serde(default)]
-    pub security: Option<SecurityConfig>,
+    pub failure_mode: RateLimitFailureMode,
+}
 
-    /// ルートレベルの圧縮設定（actionの設定をオーバーライド）
-    #[serde(default)]
-    pub compression: Option<CompressionConfig>,
+/// レートリミットのディスクリプタ（F-147）
+#[derive(Deserialize, Clone, Debug)]
+pub struct RateLimitDescriptorConfig {
+    pub entries: Vec<RateLimitDescriptorEntryConfig>,
+}
 
-    /// ルートレベルのバッファリング設定（actionの設定をオーバーライド）
+/// ディスクリプタのエントリ（`value` か `from` のどちらか一方を指定、F-147）
+#[derive(Deserialize, Clone, Debug)]
+pub struct RateLimitDescriptorEntryConfig {
+    pub key: String,
+    /// 固定値
     #[serde(default)]
-    pub buffering: Option<buffering::BufferingConfig>,
-
-    /// ルートレベルのキャッシュ設定（actionの設定をオーバーライド）
+    pub value: Option<String>,
+    /// リクエスト属性（`rate_limits` の `key` と同じ構成要素）
     #[serde(default)]
-    pub cache: Option<cache::CacheConfig>,
+    pub from: Option<String>,
+}
 
-    /// ルートレベルのOpenFileCache設定（actionの設定をオーバーライド、Fileバックエンドのみ）
+/// 外部レートリミットサービスの障害時の扱い（F-147）
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitFailureMode {
+    /// 許可する
+    #[default]
+    Open,
+    /// 503 で拒否する
+    Closed,
+}
+
+/// ルート単位の JWT 認証（F-148、`[route.jwt]`）
+///
+/// 鍵は `jwks_file` か `jwks_url` のどちらか一方から読む。
+#[derive(Deserialize, Clone, Debug)]
+pub struct JwtAuthConfig {
+    /// 許可する署名アルゴリズム（HS256/384/512, RS256/384/512, PS256/384/512, ES256/384, EdDSA）
+    ///
+    /// デフォルトは公開鍵方式のすべて。HS* は明示した場合のみ許可する。
+    #[serde(default = "default_jwt_algorithms")]
+    pub algorithms: Vec<String>,
+
+    /// JWKS ファイルのパス（設定の読み込み・リロード時に読む）
     #[serde(default)]
-    pub open_file_cache: Option<cache::OpenFileCacheConfig>,
+    pub jwks_file: Option<String>,
 
-    /// ルートレベルのWASMモジュール名のリスト（このルートに適用するWASMモジュール）
-    /// 注意: modules は route 直下で設定（action配下の設定は削除）
+    /// JWKS の URL（`http://` / `https://`）
     #[serde(default)]
-    pub modules: Option<Vec<String>>,
-}
+    pub jwks_url: Option<String>,
 
-#[derive(Deserialize)]
-struct Config {
-    server: ServerConfigSection,
-    tls: TlsConfigSection,
+    /// `jwks_url` を取り直す間隔（秒）
+    #[serde(default = "default_jwks_refresh_secs")]
+    pub jwks_refresh_secs: u64,
+
+    /// `jwks_url` の取得タイムアウト（ミリ秒）
+    #[serde(default = "default_jwks_timeout_ms")]
+    pub jwks_timeout_ms: u64,
+
+    /// 期待する `iss`（未指定なら検証しない）
     #[serde(default)]
-    performance: PerformanceConfigSection,
-    /// グローバルセキュリティ設定（権限降格など）
+    pub issuer: Option<String>,
+
+    /// 受け入れる `aud`（いずれかを含めばよい、空なら検証しない）
     #[serde(default)]
-    security: GlobalSecurityConfig,
-    /// ログ設定（非同期ログの最適化）
+    pub audiences: Vec<String>,
+
+    /// `exp` の無いトークンを拒否する
+    #[serde(default = "default_true")]
+    pub require_exp: bool,
+
+    /// `exp` / `nbf` の時刻のずれの許容（秒）
+    #[serde(default = "default_jwt_clock_skew_secs")]
+    pub clock_skew_secs: u64,
+
+    /// `Authorization: Bearer` が無いときにトークンを読む Cookie 名
     #[serde(default)]
-    logging: LoggingConfigSection,
-    /// Prometheusメトリクス設定
+    pub token_cookie: Option<String>,
+
+    /// 必須のクレーム（クレーム → 受け入れる値のいずれか）
+    ///
+    /// クレームは `.` 区切りで入れ子を辿る。配列のクレームは要素のいずれかが一致すればよい。
+    /// 例: `{ "roles" = ["admin", "ops"], "email_verified" = ["true"] }`
     #[serde(default)]
-    prometheus: PrometheusConfig,
-    /// 管理 API 設定（F-20: キャッシュ Purge 等）
-    #[cfg(feature = "admin")]
+    pub required_claims: BTreeMap<String, Vec<String>>,
+
+    /// 上流へヘッダーとして転送するクレーム（クレーム → ヘッダー名）
+    ///
+    /// クライアントが送った同名のヘッダーは常に削除する。配列は `,` で連結する。
     #[serde(default)]
-    admin: AdminConfig,
-    /// 構造化アクセスログ設定（F-21）
-    #[cfg(feature = "access-log")]
+    pub claims_to_headers: BTreeMap<String, String>,
+
+    /// トークンを含む `Authorization` ヘッダーを上流へ転送する
+    #[serde(default = "default_true")]
+    pub forward_token: bool,
+}
+
+fn default_jwt_algorithms() -> Vec<String> {
+    [
+        "RS256", "RS384", "RS512", "PS256", "PS384", "PS512", "ES256", "ES384", "EdDSA",
+    ]
+    .iter()
+    .map(|a| a.to_string())
+    .collect()
+}
+
+fn default_jwks_refresh_secs() -> u64 {
+    300
+}
+
+fn default_jwks_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_jwt_clock_skew_secs() -> u64 {
+    60
+}
+
+/// ルート単位の外部認可（F-149、`[route.ext_authz]`）
+///
+/// リクエストごとに認可サービスへ問い合わせ、許可（HTTP なら 2xx、gRPC なら `OK`）された
+/// リクエストだけを上流へ転送する。拒否の応答はそのままクライアントへ返す。
+#[derive(Deserialize, Clone, Debug)]
+pub struct ExtAuthzConfig {
+    /// 呼び出し方式
     #[serde(default)]
-    access_log: crate::access_log::AccessLogConfig,
-    /// OpenTelemetry 設定（F-10）
+    pub protocol: ExtAuthzProtocol,
+    /// HTTP の認可サービスの URL（`http` / `https`、パスはリクエストのパスの前に付ける）
     #[serde(default)]
-    opentelemetry: OpenTelemetryConfig,
-    /// バッファプール設定（メモリ最適化）
+    pub url: Option<String>,
+    /// gRPC（`envoy.service.auth.v3.Authorization/Check`）の接続先（`host:port`、h2c）
     #[serde(default)]
-    buffer_pool: BufferPoolConfig,
-    /// HTTP/2 設定セクション
+    pub address: Option<String>,
+    /// 1 回の問い合わせのタイムアウト（ミリ秒）
+    #[serde(default = "default_ext_authz_timeout_ms")]
+    pub timeout_ms: u64,
+    /// 認可サービスに問い合わせできないときの扱い
     #[serde(default)]
-    #[cfg_attr(not(feature = "http2"), allow(dead_code))]
-    http2: Http2ConfigSection,
-    /// HTTP/3 設定セクション
+    pub failure_mode: ExtAuthzFailureMode,
+    /// `failure_mode = "closed"` で返すステータス
+    #[serde(default = "default_ext_authz_status_on_error")]
+    pub status_on_error: u16,
+    /// 認可サービスへ渡すクライアントのリクエストヘッダー
+    #[serde(default = "default_ext_authz_request_headers")]
+    pub request_headers: Vec<String>,
+    /// 認可サービスへ渡すボディの上限（バイト、0 = 渡さない）
     #[serde(default)]
-    #[cfg_attr(not(feature = "http3"), allow(dead_code))]
-    http3: Http3ConfigSection,
-    /// Upstream グループ定義（ロードバランシング用）
+    pub max_body_bytes: usize,
+    /// 上限を超えるボディは先頭だけを渡す（false なら 413 で拒否）
     #[serde(default)]
-    upstreams: Option<HashMap<String, UpstreamConfig>>,
-    /// 統合ルーティング（唯一のルーティング方式）
-    /// 配列の順序で評価（first-match方式）
+    pub allow_partial_body: bool,
+    /// HTTP: 許可時に認可サービスの応答から上流へのリクエストへ移すヘッダー
     #[serde(default)]
-    route: Option<Vec<Route>>,
-    /// WASM拡張設定（feature flagで条件付きコンパイル）
-    #[cfg(feature = "wasm")]
+    pub upstream_headers: Vec<String>,
+    /// 判定をキャッシュするキー（`rate_limits` の `key` と同じ構成要素、空 = キャッシュしない）
     #[serde(default)]
-    wasm: Option<crate::wasm::WasmConfig>,
-    /// L4 (TCP/UDP) ストリームプロキシ設定（F-18）
-    #[cfg(feature = "l4-proxy")]
+    pub cache_key: Vec<String>,
+    /// 判定をキャッシュする秒数（0 = キャッシュしない）
     #[serde(default)]
-    l4: Option<Vec<L4ListenerConfig>>,
+    pub cache_ttl_secs: u64,
 }
 
-// ====================
-// L4 (TCP/UDP) ストリームプロキシ設定（F-18）
-// ====================
-
-/// L4 リスナーのトランスポートプロトコル（F-124）
-///
-/// TOML では `protocol = "tcp"` / `"udp"`。省略時は後方互換のため `Tcp`。
-#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+/// 外部認可の呼び出し方式（F-149）
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
-pub enum L4Protocol {
-    /// TCP（バイダイレクショナルストリーム転送、既存動作）
+pub enum ExtAuthzProtocol {
+    /// HTTP/1.1 のサブリクエスト
     #[default]
-    Tcp,
-    /// UDP（セッションテーブル方式のデータグラム転送、F-124）
-    Udp,
+    Http,
+    /// Envoy `envoy.service.auth.v3` の gRPC API（http2 + grpc feature）
+    Grpc,
 }
 
-/// L4 TLS モード
-#[derive(Deserialize, Clone, Debug, PartialEq)]
-#[serde(rename_all = "snake_case")]
-#[derive(Default)]
-pub enum L4TlsMode {
-    /// TLS なし（プレーンな TCP）
+/// 外部認可サービスの障害時の扱い（F-149）
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtAuthzFailureMode {
+    /// 許可する
+    Open,
+    /// `status_on_error` で拒否する
     #[default]
-    None,
-    /// TLS パススルー（TLS を復号せず upstream にそのまま転送）
-    Passthrough,
-    /// TLS ターミネーション（veil で TLS を終端し、upstream にはプレーン TCP で接続）
-    Terminate,
+    Closed,
 }
 
-/// L4 ロードバランシングアルゴリズム
-#[derive(Deserialize, Clone, Debug, PartialEq)]
-#[serde(rename_all = "snake_case")]
-#[derive(Default)]
-pub enum L4LbAlgorithm {
-    /// ラウンドロビン（デフォルト）
-    #[default]
-    RoundRobin,
-    /// 最小接続数
-    LeastConn,
+fn default_ext_authz_timeout_ms() -> u64 {
+    200
 }
 
-/// L4 upstream バックエンド
-#[derive(Deserialize, Clone, Debug)]
-pub struct L4UpstreamEntry {
-    /// バックエンドアドレス（"host:port" 形式）
-    pub addr: String,
-    /// 重み（weighted round-robin 用、デフォルト: 1）
-    #[serde(default = "default_l4_weight")]
-    pub weight: u32,
+fn default_ext_authz_status_on_error() -> u16 {
+    403
 }
 
-fn default_l4_weight() -> u32 {
-    1
+fn default_ext_authz_request_headers() -> Vec<String> {
+    vec!["Authorization".to_string(), "Cookie".to_string()]
 }
 
-/// L4 リスナー設定（TOML: [[l4]]）
+/// ルート単位の OpenID Connect ログイン（F-150、`[route.oidc]`）
+///
+/// セッションの無いブラウザーを IdP へリダイレクトし（認可コードフロー + PKCE）、コールバックで
+/// ID トークンを検証して暗号化したセッション Cookie を発行する。期限が来たセッションは
+/// リフレッシュトークンで更新する。
 #[derive(Deserialize, Clone, Debug)]
-pub struct L4ListenerConfig {
-    /// リスナー名（ログ・メトリクス識別用）
-    pub name: String,
-    /// リッスンアドレス（例: "0.0.0.0:3306"）
-    pub listen: String,
-    /// upstream バックエンド一覧
-    pub upstreams: Vec<L4UpstreamEntry>,
-    /// トランスポートプロトコル（tcp / udp）。省略時は後方互換で tcp（F-124）。
+pub struct OidcConfig {
+    /// IdP の issuer（`{issuer}/.well-known/openid-configuration` からエンドポイントを取得する）
+    pub issuer: String,
+
+    /// クライアント ID（ID トークンの `aud` にも使う）
+    pub client_id: String,
+
+    /// クライアントシークレット（未指定なら PKCE のみの公開クライアント）
     #[serde(default)]
-    pub protocol: L4Protocol,
-    /// ロードバランシングアルゴリズム
+    pub client_secret: Option<String>,
+
+    /// トークンエンドポイントでのクライアント認証方式
     #[serde(default)]
-    pub lb: L4LbAlgorithm,
-    /// TLS モード（none / passthrough / terminate）
+    pub token_endpoint_auth_method: OidcClientAuthMethod,
+
+    /// 要求するスコープ（`openid` を含むこと）
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+
+    /// 認可エンドポイント（discovery の値を上書きする）
     #[serde(default)]
-    pub tls: L4TlsMode,
-    /// 最大同時接続数（0 = 無制限）
+    pub authorization_endpoint: Option<String>,
+
+    /// トークンエンドポイント（discovery の値を上書きする）
     #[serde(default)]
-    pub max_connections: usize,
-    /// ヘルスチェック設定（省略可）
+    pub token_endpoint: Option<String>,
+
+    /// JWKS の URL（discovery の値を上書きする）
     #[serde(default)]
-    pub health_check: Option<HealthCheckConfig>,
-    /// バックエンド接続タイムアウト（秒）
-    #[serde(default = "default_l4_connect_timeout")]
-    pub connect_timeout_secs: u64,
-    /// アイドルタイムアウト（秒）: この時間データ転送がなければ接続を切断（デフォルト: 600）
-    #[serde(default = "default_l4_idle_timeout")]
-    pub idle_timeout_secs: u64,
-}
+    pub jwks_uri: Option<String>,
 
-fn default_l4_connect_timeout() -> u64 {
-    10
-}
+    /// ログアウトエンドポイント（discovery の値を上書きする）
+    #[serde(default)]
+    pub end_session_endpoint: Option<String>,
 
-fn default_l4_idle_timeout() -> u64 {
-    600
-}
+    /// コールバックの URL またはパス
+    ///
+    /// パスならリクエストのホストから `https://{host}{path}` を組み立てる。
+    #[serde(default = "default_oidc_redirect_uri")]
+    pub redirect_uri: String,
 
-// ====================
-// HTTP/2 設定セクション (RFC 7540)
-// ====================
+    /// ログアウトのパス（セッション Cookie を消して IdP のログアウトへリダイレクトする）
+    #[serde(default = "default_oidc_logout_path")]
+    pub logout_path: String,
 
-/// HTTP/2 詳細設定
-///
-/// HTTP/2 プロトコルのパラメータを設定します。
-/// 有効化は `server.http2_enabled` で行います。
-#[derive(Deserialize, Clone)]
-pub struct Http2ConfigSection {
-    /// SETTINGS_HEADER_TABLE_SIZE (HPACK動的テーブルサイズ)
-    /// デフォルト: 4096 (4KB)
-    /// 高パフォーマンス: 65536 (64KB)
-    #[serde(default = "default_h2_header_table_size")]
-    pub header_table_size: u32,
+    /// ログアウト後の戻り先（IdP の `post_logout_redirect_uri`）
+    #[serde(default)]
+    pub post_logout_redirect_uri: Option<String>,
 
-    /// SETTINGS_MAX_CONCURRENT_STREAMS (同時ストリーム数)
-    /// デフォルト: 100
-    #[serde(default = "default_h2_max_concurrent_streams")]
-    pub max_concurrent_streams: u32,
+    /// discovery・JWKS・トークンエンドポイントの問い合わせのタイムアウト（ミリ秒）
+    #[serde(default = "default_oidc_timeout_ms")]
+    pub timeout_ms: u64,
 
-    /// SETTINGS_INITIAL_WINDOW_SIZE (ストリームウィンドウサイズ)
-    /// デフォルト: 65535 (64KB - 1)
-    #[serde(default = "default_h2_initial_window_size")]
-    pub initial_window_size: u32,
+    /// ID トークンに許可する署名アルゴリズム（`[route.jwt]` と同じ）
+    #[serde(default = "default_jwt_algorithms")]
+    pub algorithms: Vec<String>,
 
-    /// SETTINGS_MAX_FRAME_SIZE (最大フレームサイズ)
-    /// デフォルト: 65536 (64KB)。これは **受信側の上限として相手へ広告する値** であり、
-    /// 送信フレームは相手の SETTINGS_MAX_FRAME_SIZE（既定 16384）に従って分割される。
-    #[serde(default = "default_h2_max_frame_size")]
-    pub max_frame_size: u32,
+    /// `exp` / `nbf` の時刻のずれの許容（秒）
+    #[serde(default = "default_jwt_clock_skew_secs")]
+    pub clock_skew_secs: u64,
 
-    /// SETTINGS_MAX_HEADER_LIST_SIZE (最大ヘッダーリストサイズ)
-    /// デフォルト: 16384 (16KB)
-    #[serde(default = "default_h2_max_header_list_size")]
-    pub max_header_list_size: u32,
+    /// ID トークンに必須のクレーム（`[route.jwt]` と同じ、満たさなければ 403）
+    #[serde(default)]
+    pub required_claims: BTreeMap<String, Vec<String>>,
 
-    /// コネクションウィンドウサイズ（コネクション全体のフロー制御）
-    /// デフォルト: 65535 (64KB - 1)
-    #[serde(default = "default_h2_connection_window_size")]
-    pub connection_window_size: u32,
+    /// 上流へヘッダーとして転送するクレーム（クレーム → ヘッダー名）
+    ///
+    /// クライアントが送った同名のヘッダーは常に削除する。
+    #[serde(default = "default_oidc_claims_to_headers")]
+    pub claims_to_headers: BTreeMap<String, String>,
 
-    // ====================
-    // DoS 対策設定
-    // ====================
-    /// RST_STREAM レート制限 (1秒あたりの最大数)
-    /// Rapid Reset 対策 (CVE-2023-44487)
-    /// デフォルト: 100
-    #[serde(default = "default_h2_max_rst_stream_per_second")]
-    pub max_rst_stream_per_second: u32,
+    /// アクセストークンを `Authorization: Bearer` で上流へ渡す（セッション Cookie に保存する）
+    #[serde(default)]
+    pub forward_access_token: bool,
 
-    /// 制御フレームレート制限 (1秒あたりの最大数)
-    /// PING/SETTINGS フラッド対策
-    /// デフォルト: 500
-    #[serde(default = "default_h2_max_control_frames_per_second")]
-    pub max_control_frames_per_second: u32,
+    /// セッション Cookie 名（ログイン中の状態は `{cookie_name}_state`）
+    #[serde(default = "default_oidc_cookie_name")]
+    pub cookie_name: String,
 
-    /// CONTINUATION フレーム制限 (ヘッダーブロックあたりの最大数)
-    /// CONTINUATION Flood 対策 (CVE-2024-24786)
-    /// デフォルト: 10
-    #[serde(default = "default_h2_max_continuation_frames")]
-    pub max_continuation_frames: u32,
+    /// セッション Cookie の暗号鍵の元になる秘密（32 バイト以上）
+    pub cookie_secret: String,
 
-    /// 最大ヘッダーブロックサイズ (bytes)
-    /// HPACK Bomb 対策
-    /// デフォルト: 65536 (64KB)
-    #[serde(default = "default_h2_max_header_block_size")]
-    pub max_header_block_size: usize,
+    /// Cookie の Path 属性
+    #[serde(default = "default_oidc_cookie_path")]
+    pub cookie_path: String,
 
-    /// ストリームアイドルタイムアウト (秒)
-    /// Slow Loris 対策
-    /// デフォルト: 60
-    #[serde(default = "default_h2_stream_idle_timeout_secs")]
-    pub stream_idle_timeout_secs: u64,
+    /// Cookie の Secure 属性
+    #[serde(default = "default_true")]
+    pub cookie_secure: bool,
+
+    /// セッション Cookie の SameSite 属性（ログイン中の状態の Cookie は常に Lax）
+    #[serde(default)]
+    pub cookie_same_site: SameSite,
+
+    /// ログインからセッションを使える最長の時間（秒、リフレッシュしても延びない）
+    #[serde(default = "default_oidc_session_lifetime_secs")]
+    pub session_lifetime_secs: u64,
 }
 
-// HTTP/2 設定のデフォルト値（high_performance と同等）
-// HPACK動的テーブルサイズを大きくすることで、ヘッダー圧縮効率が向上
-fn default_h2_header_table_size() -> u32 {
-    65536
-} // 64KB (より多くのヘッダーをキャッシュ)
-fn default_h2_max_concurrent_streams() -> u32 {
-    256
-} // より多くの同時ストリーム
-fn default_h2_initial_window_size() -> u32 {
-    1048576
-} // 1MB (より大きなウィンドウ)
-fn default_h2_max_frame_size() -> u32 {
-    65536
-} // 64KB (より大きなフレーム)
-fn default_h2_max_header_list_size() -> u32 {
-    65536
-} // 64KB
-fn default_h2_connection_window_size() -> u32 {
-    1048576
-} // 1MB
+/// トークンエンドポイントでのクライアント認証方式（F-150）
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OidcClientAuthMethod {
+    /// `Authorization: Basic`
+    #[default]
+    ClientSecretBasic,
+    /// フォームの `client_id` / `client_secret`
+    ClientSecretPost,
+}
 
-// DoS 対策のデフォルト値
-fn default_h2_max_rst_stream_per_second() -> u32 {
-    100
+fn default_oidc_scopes() -> Vec<String> {
+    vec![
+        "openid".to_string(),
+        "profile".to_string(),
+        "email".to_string(),
+    ]
 }
-fn default_h2_max_control_frames_per_second() -> u32 {
-    500
+
+fn default_oidc_redirect_uri() -> String {
+    "/oauth2/callback".to_string()
 }
-fn default_h2_max_continuation_frames() -> u32 {
-    10
+
+fn default_oidc_logout_path() -> String {
+    "/oauth2/logout".to_string()
 }
-fn default_h2_max_header_block_size() -> usize {
-    65536
+
+fn default_oidc_timeout_ms() -> u64 {
+    5000
 }
-fn default_h2_stream_idle_timeout_secs() -> u64 {
-    60
+
+fn default_oidc_claims_to_headers() -> BTreeMap<String, String> {
+    BTreeMap::from([
+        ("sub".to_string(), "X-Auth-Request-User".to_string()),
+        ("email".to_string(), "X-Auth-Request-Email".to_string()),
+    ])
 }
 
-impl Default for Http2ConfigSection {
-    fn default() -> Self {
-        Self {
-            header_table_size: default_h2_header_table_size(),
-            max_concurrent_streams: default_h2_max_concurrent_streams(),
-            initial_window_size: default_h2_initial_window_size(),
-            max_frame_size: default_h2_max_frame_size(),
-            max_header_list_size: default_h2_max_header_list_size(),
-            connection_window_size: default_h2_connection_window_size(),
-            // DoS 対策
-            max_rst_stream_per_second: default_h2_max_rst_stream_per_second(),
-            max_control_frames_per_second: default_h2_max_control_frames_per_second(),
-            max_continuation_frames: default_h2_max_continuation_frames(),
-            max_header_block_size: default_h2_max_header_block_size(),
-            stream_idle_timeout_secs: default_h2_stream_idle_timeout_secs(),
-        }
-    }
+fn default_oidc_cookie_name() -> String {
+    "veil_oidc".to_string()
 }
 
-impl Http2ConfigSection {
-    /// HTTP/2 設定を Http2Settings に変換
-    #[cfg(feature = "http2")]
-    pub fn to_http2_settings(&self) -> http2::Http2Settings {
-        http2::Http2Settings {
-            header_table_size: self.header_table_size,
-            max_concurrent_streams: self.max_concurrent_streams,
-            initial_window_size: self.initial_window_size,
-            max_frame_size: self.max_frame_size,
-            max_header_list_size: self.max_header_list_size,
-            enable_push: false, // サーバーではpush無効
-            connection_window_size: self.connection_window_size,
-            // DoS 対策
-            max_rst_stream_per_second: self.max_rst_stream_per_second,
-            max_control_frames_per_second: self.max_control_frames_per_second,
-            max_continuation_frames: self.max_continuation_frames,
-            max_header_block_size: self.max_header_block_size,
-            stream_idle_timeout_secs: self.stream_idle_timeout_secs,
-        }
-    }
+fn default_oidc_cookie_path() -> String {
+    "/".to_string()
 }
 
-// ====================
-// HTTP/3 設定セクション (RFC 9114, QUIC RFC 9000)
-// ====================
+fn default_oidc_session_lifetime_secs() -> u64 {
+    86400
+}
 
-/// HTTP/3 専用圧縮設定
+/// ルート単位の Basic 認証（F-151、`[route.basic_auth]`）
 ///
-/// HTTP/3接続時に使用する圧縮パラメータを設定します。
-/// 未設定のフィールドはパスごとの設定または全体設定を継承します。
-#[derive(Deserialize, Clone, Debug, Default)]
-#[serde(default)]
-pub struct Http3CompressionConfig {
-    /// 圧縮方式の優先順位
-    /// サポート: "zstd", "br" (Brotli), "gzip", "deflate"
-    /// 未設定時はパスごとの設定を使用
-    pub preferred_encodings: Option<Vec<String>>,
+/// 利用者とパスワードのハッシュは htpasswd 形式のファイルから読む。
+#[derive(Deserialize, Clone, Debug)]
+pub struct BasicAuthConfig {
+    /// htpasswd ファイルのパス（bcrypt `$2a$`/`$2b$`/`$2y$`、SHA-crypt `$5$`/`$6$`）
+    ///
+    /// 更新を検知して読み直す（SIGHUP の設定リロードでも読み直す）。
+    pub htpasswd_file: String,
 
-    /// Gzip圧縮レベル (1-9)
-    /// 未設定時はパスごとの設定を使用
-    pub gzip_level: Option<u32>,
+    /// `WWW-Authenticate: Basic realm="..."` の realm
+    #[serde(default = "default_basic_auth_realm")]
+    pub realm: String,
 
-    /// Brotli圧縮レベル (0-11)
-    /// 未設定時はパスごとの設定を使用
-    pub brotli_level: Option<u32>,
+    /// `Authorization` ヘッダーを上流へ転送しない
+    #[serde(default)]
+    pub strip_credentials: bool,
 
-    /// Zstd圧縮レベル (1-22)
-    /// 未設定時はパスごとの設定を使用
-    pub zstd_level: Option<i32>,
+    /// 上流へヘッダーとして転送する利用者の属性（`sub` → ヘッダー名）
+    ///
+    /// クライアントが送った同名のヘッダーは常に削除する。
+    #[serde(default)]
+    pub claims_to_headers: BTreeMap<String, String>,
+}
 
-    /// 最小圧縮サイズ（バイト）
-    /// 未設定時はパスごとの設定を使用
-    pub min_size: Option<usize>,
+fn default_basic_auth_realm() -> String {
+    "veil".to_string()
+}
 
-    /// 圧縮対象のMIMEタイプ（プレフィックスマッチ）
-    /// 未設定時はパスごとの設定を使用
-    pub compressible_types: Option<Vec<String>>,
+/// ルート単位の API キー認証（F-151、`[route.api_key]`）
+///
+/// 鍵は SHA-256 のハッシュと属性（テナント・使えるルート・レートリミットのクラス）を並べた
+/// TOML ファイルから読む。
+#[derive(Deserialize, Clone, Debug)]
+pub struct ApiKeyAuthConfig {
+    /// 鍵ファイルのパス（更新を検知して読み直す。SIGHUP の設定リロードでも読み直す）
+    pub keys_file: String,
 
-    /// 圧縮をスキップするMIMEタイプ（プレフィックスマッチ）
-    /// 未設定時はパスごとの設定を使用
-    pub skip_types: Option<Vec<String>>,
+    /// 鍵を読むリクエストヘッダー
+    #[serde(default = "default_api_key_header")]
+    pub header: String,
+
+    /// 鍵の `routes` と照合するこのルートの名前（省略時は `route[N]`）
+    #[serde(default)]
+    pub route_name: Option<String>,
+
+    /// 鍵のヘッダーを上流へ転送しない
+    #[serde(default)]
+    pub strip_credentials: bool,
+
+    /// 上流へヘッダーとして転送する鍵の属性（`sub` / `tenant` / `rate_limit_class` → ヘッダー名）
+    ///
+    /// クライアントが送った同名のヘッダーは常に削除する。
+    #[serde(default)]
+    pub claims_to_headers: BTreeMap<String, String>,
 }
 
-impl Http3CompressionConfig {
-    /// 設定の妥当性を検証
-    pub fn validate(&self) -> Result<(), String> {
-        if let Some(level) = self.gzip_level {
-            if !(1..=9).contains(&level) {
-                return Err(format!(
-                    "http3.compression.gzip_level: {} (must be 1-9)",
-                    level
-                ));
-            }
-        }
-        if let Some(level) = self.brotli_level {
-            if level > 11 {
-                return Err(format!(
-                    "http3.compression.brotli_level: {} (must be 0-11)",
-                    level
-                ));
-            }
-        }
-        if let Some(level) = self.zstd_level {
-            if !(1..=22).contains(&level) {
-                return Err(format!(
-                    "http3.compression.zstd_level: {} (must be 1-22)",
-                    level
-                ));
-            }
-        }
-        if let Some(ref encodings) = self.preferred_encodings {
-            for enc in encodings {
-                if !["zstd", "br", "gzip", "deflate"].contains(&enc.as_str()) {
-                    return Err(format!(
-                        "http3.compression.preferred_encodings: unknown encoding '{}'",
-                        enc
-                    ));
-                }
-            }
-        }
-        Ok(())
-    }
+fn default_api_key_header() -> String {
+    "X-API-Key".to_string()
 }
 
-/// HTTP/3 詳細設定
+/// WAF の動作モード（F-154）
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WafMode {
+    /// 異常スコアがしきい値に達した・`deny` の規則に一致したリクエストを 403 で拒否する
+    #[default]
+    Blocking,
+    /// 一致を記録するだけで拒否しない
+    DetectionOnly,
+}
+
+/// WAF の `max_body_bytes` を超えたボディの扱い（F-154）
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WafBodyLimitAction {
+    /// `blocking` では 413 で拒否する（`detection_only` では記録して先頭だけ検査する）
+    #[default]
+    Reject,
+    /// 上限までの先頭だけを検査する
+    ProcessPartial,
+}
+
+/// ルート単位の WAF（F-154、`[route.waf]`）
 ///
-/// HTTP/3 (QUIC) プロトコルのパラメータを設定します。
-/// 有効化は `server.http3_enabled` で行います。
-#[derive(Deserialize, Clone)]
-pub struct Http3ConfigSection {
-    /// HTTP/3リッスンアドレス（UDP）
-    /// 未指定の場合は server.listen と同じアドレスを使用
+/// ModSecurity の `SecRule` のサブセットの規則（OWASP CRS を含む）でリクエストを検査する。
+#[derive(Deserialize, Clone, Debug)]
+pub struct WafConfig {
+    /// 規則ファイルのパス（ファイル名に `*` を 1 つ含めると一致するファイルを名前順に読む）
+    ///
+    /// 設定の読み込み・リロード時に読む。
     #[serde(default)]
-    pub listen: Option<String>,
+    pub rule_files: Vec<String>,
 
-    /// 最大アイドルタイムアウト（ミリ秒）
-    /// デフォルト: 30000 (30秒)
-    #[serde(default = "default_h3_max_idle_timeout")]
-    pub max_idle_timeout: u64,
+    /// 設定ファイルに直接書く規則（`rule_files` の後に読む）
+    #[serde(default)]
+    pub rules: String,
 
-    /// 最大UDPペイロードサイズ
-    /// デフォルト: 1350 (MTU考慮)
-    #[serde(default = "default_h3_max_udp_payload_size")]
-    pub max_udp_payload_size: u64,
-
-    /// 初期最大データサイズ（コネクション全体）
-    /// デフォルト: 10000000 (10MB)
-    #[serde(default = "default_h3_initial_max_data")]
-    pub initial_max_data: u64,
+    /// `blocking`（拒否する）または `detection_only`（記録だけする）
+    #[serde(default)]
+    pub mode: WafMode,
 
-    /// 初期最大ストリームデータサイズ（双方向ローカル）
-    #[serde(default = "default_h3_initial_max_stream_data")]
-    pub initial_max_stream_data_bidi_local: u64,
+    /// 拒否する異常スコアのしきい値
+    #[serde(default = "default_waf_anomaly_threshold")]
+    pub anomaly_threshold: u32,
 
-    /// 初期最大ストリームデータサイズ（双方向リモート）
-    #[serde(default = "default_h3_initial_max_stream_data")]
-    pub initial_max_stream_data_bidi_remote: u64,
+    /// 読み込む規則の paranoia level（`tag:'paranoia-level/N'`、タグの無い規則は常に読む）
+    #[serde(default = "default_waf_paranoia_level")]
+    pub paranoia_level: u8,
 
-    /// 初期最大ストリームデータサイズ（単方向）
-    #[serde(default = "default_h3_initial_max_stream_data")]
-    pub initial_max_stream_data_uni: u64,
+    /// 読み込まない規則の ID（`"942100"`・範囲 `"942100-942199"`）
+    #[serde(default)]
+    pub disabled_rule_ids: Vec<String>,
 
-    /// 初期最大双方向ストリーム数
-    #[serde(default = "default_h3_max_streams")]
-    pub initial_max_streams_bidi: u64,
+    /// リクエストボディを検査する（ボディを受信し終えてから検査する）
+    #[serde(default = "default_true")]
+    pub inspect_body: bool,
 
-    /// 初期最大単方向ストリーム数
-    #[serde(default = "default_h3_max_streams")]
-    pub initial_max_streams_uni: u64,
+    /// 検査するボディの上限（バイト）
+    #[serde(default = "default_waf_max_body_bytes")]
+    pub max_body_bytes: usize,
 
-    /// HTTP/3接続時の圧縮を常に有効化
-    /// デフォルト: false
-    ///
-    /// true の場合、パスごとの設定で明示的に無効化されていない限り、
-    /// すべてのHTTP/3レスポンスで圧縮を試みます。
-    /// パスごとの compression.enabled = false の場合はそちらが優先されます。
+    /// `max_body_bytes` を超えたボディの扱い（`reject` / `process_partial`）
     #[serde(default)]
-    pub compression_enabled: bool,
+    pub body_limit_action: WafBodyLimitAction,
 
-    /// HTTP/3専用の圧縮パラメータ
-    ///
-    /// パスごとの圧縮設定より優先されます。
-    /// 未設定のフィールドはパスごとの設定を継承します。
+    /// 一致したリクエストを JSON Lines で書き出す監査ログのパス
     #[serde(default)]
-    pub compression: Http3CompressionConfig,
+    pub audit_log: Option<String>,
+}
 
-    /// GSO/GRO を有効化するかどうか
-    ///
-    /// GSO (Generic Segmentation Offload) / GRO (Generic Receive Offload) は
-    /// カーネルレベルでUDPパケットの送受信を効率化する機能です。
-    ///
-    /// 効果:
-    /// - 複数の小さなUDPパケットを一度に送受信
-    /// - システムコール回数の削減
-    /// - CPU使用率の低減
-    ///
-    /// 注意:
-    /// - Linux 5.0+ でサポート
-    /// - 一部の仮想環境やDockerでは期待通りに動作しない場合あり
-    /// - 問題が発生した場合は false に設定してください
-    ///
-    /// デフォルト: false
-    #[serde(default)]
-    pub gso_gro_enabled: bool,
+fn default_waf_anomaly_threshold() -> u32 {
+    5
+}
 
-    // ====================
-    // quiche::Config 輻輳制御 / Pacing / バッチ（F-124）
-    // ====================
-    /// QUIC 輻輳制御アルゴリズム（quiche `set_cc_algorithm_name`）
-    ///
-    /// 受け付ける値（大小無視）:
-    /// - `"reno"` / `"cubic"` / `"bbr"` / `"bbr2"` / `"bbr2_gcongestion"`
-    ///
-    /// リバースプロキシの短時間バーストでは CUBIC のスロースタートが RPS を抑えやすいため、
-    /// 既定は `"bbr"`。未知の値は起動時に警告して `"bbr"` にフォールバックする。
-    ///
-    /// デフォルト: `"bbr"`
-    #[serde(default = "default_h3_cc_algorithm")]
-    pub cc_algorithm: String,
+fn default_waf_paranoia_level() -> u8 {
+    1
+}
 
-    /// Packet Pacing を有効にするか（quiche `enable_pacing`）
-    ///
-    /// 有効時は送信間隔をならしパケットロスを減らす。quiche 自体の既定も true。
-    /// デフォルト: `true`
-    #[serde(default = "default_true")]
-    pub pacing: bool,
+fn default_waf_max_body_bytes() -> usize {
+    131_072
+}
 
-    /// 最大 pacing レート（バイト/秒）。未指定時は制限なし（quiche `set_max_pacing_rate`）
-    ///
-    /// デフォルト: なし（`None`）
+/// ルート単位の CORS（F-155、`[route.cors]`）
+///
+/// プリフライトに上流へ送らずに応答し、通常の応答に `Access-Control-*` と `Vary: Origin` を付ける。
+#[derive(Deserialize, Clone, Debug)]
+pub struct CorsConfig {
+    /// 許可するオリジン（`"*"` はすべて、`"https://*.example.com"` のように `*` を含めると
+    /// `/` 以外の 1 文字以上に一致）
     #[serde(default)]
-    pub max_pacing_rate: Option<u64>,
+    pub allowed_origins: Vec<String>,
 
-    /// HyStart++ を有効にするか（quiche `enable_hystart`）
-    ///
-    /// デフォルト: `true`
-    #[serde(default = "default_true")]
-    pub hystart: bool,
+    /// 許可するオリジンの正規表現（オリジン全体に一致させる、大文字小文字を区別しない）
+    #[serde(default)]
+    pub allowed_origin_regexes: Vec<String>,
 
-    /// UDP データグラム送受信バッチ幅（`recvmmsg` / `sendmmsg` / multishot 提供バッファ数）
-    ///
-    /// 1 回の syscall / 1 本の multishot アームで扱う最大データグラム数。
-    /// 範囲: 1..=128。範囲外はクランプする。
-    ///
-    /// デフォルト: `64`
-    #[serde(default = "default_h3_mmsg_batch_size")]
-    pub mmsg_batch_size: usize,
+    /// プリフライトで許可するメソッド（`"*"` は要求されたメソッドをそのまま許可）
+    #[serde(default = "default_cors_allowed_methods")]
+    pub allowed_methods: Vec<String>,
 
-    // ====================
-    // Alt-Svc（HTTP/3 広告、F-94）— すべて [http3] に集約
-    // ====================
-    /// HTTP/1.1・HTTP/2 応答へ `Alt-Svc` ヘッダーを付与するか
-    ///
-    /// デフォルト: `true`（`server.http3_enabled = true` のときのみ実際に広告される）
-    /// `false` にすると広告を抑制する。
-    #[serde(default = "default_true")]
-    pub alt_svc_enabled: bool,
+    /// プリフライトで許可するリクエストヘッダー（`"*"` は要求されたヘッダーをそのまま許可）
+    #[serde(default)]
+    pub allowed_headers: Vec<String>,
 
-    /// Alt-Svc ヘッダー値の上書き
-    ///
-    /// 未指定時はリッスンポートから `h3=":PORT"; ma=86400` を自動生成する。
-    /// 明示指定例: `h3=":443"; ma=86400, h3=":443"; ma=2592000`
+    /// `Access-Control-Expose-Headers` で公開する応答ヘッダー
     #[serde(default)]
-    pub alt_svc: Option<String>,
+    pub exposed_headers: Vec<String>,
 
-    /// Alt-Svc の max-age（秒）。`alt_svc` 未指定時の自動生成に使用。デフォルト: 86400
-    #[serde(default = "default_h3_alt_svc_ma")]
-    pub alt_svc_ma_secs: u64,
+    /// `Access-Control-Allow-Credentials: true` を返す（`allowed_origins = ["*"]` とは併用不可）
+    #[serde(default)]
+    pub allow_credentials: bool,
+
+    /// プリフライトの結果をブラウザがキャッシュする秒数（`Access-Control-Max-Age`）
+    #[serde(default)]
+    pub max_age_secs: Option<u64>,
 }
 
-fn default_h3_max_idle_timeout() -> u64 {
-    30000
+fn default_cors_allowed_methods() -> Vec<String> {
+    vec!["GET".to_string(), "HEAD".to_string(), "POST".to_string()]
 }
-fn default_h3_max_udp_payload_size() -> u64 {
-    1350
+
+fn default_hash_load_factor() -> f64 {
+    DEFAULT_HASH_LOAD_FACTOR
 }
-fn default_h3_initial_max_data() -> u64 {
-    10_000_000
+
+fn default_maglev_table_size() -> usize {
+    DEFAULT_MAGLEV_TABLE_SIZE
 }
-fn default_h3_initial_max_stream_data() -> u64 {
-    1_000_000
+
+/// サーキットブレーカー設定（F-06）
+///
+/// アップストリームサーバー単位で適用される。連続失敗が閾値を超えると
+/// Open 状態へ遷移し、一定時間経過後 HalfOpen でプローブを行う。
+#[derive(Deserialize, Clone, Debug)]
+pub struct CircuitBreakerConfig {
+    /// 有効化フラグ
+    #[serde(default)]
+    pub enabled: bool,
+    /// Open へ遷移する失敗回数の閾値
+    #[serde(default = "default_cb_failure_threshold")]
+    pub failure_threshold: u32,
+    /// 失敗をカウントするスライディングウィンドウ（秒）
+    #[serde(default = "default_cb_failure_window")]
+    pub failure_window_secs: u64,
+    /// Open 状態を維持する時間（秒）
+    #[serde(default = "default_cb_open_duration")]
+    pub open_duration_secs: u64,
+    /// HalfOpen 時に許可するプローブ数
+    #[serde(default = "default_cb_half_open_probes")]
+    pub half_open_probes: u32,
+    /// HalfOpen から Closed へ戻る成功回数の閾値
+    #[serde(default = "default_cb_success_threshold")]
+    pub success_threshold: u32,
+    /// タイムアウトを失敗としてカウントするか
+    #[serde(default = "default_true")]
+    pub trip_on_timeout: bool,
 }
-fn default_h3_max_streams() -> u64 {
-    100
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            failure_threshold: default_cb_failure_threshold(),
+            failure_window_secs: default_cb_failure_window(),
+            open_duration_secs: default_cb_open_duration(),
+            half_open_probes: default_cb_half_open_probes(),
+            success_threshold: default_cb_success_threshold(),
+            trip_on_timeout: true,
+        }
+    }
 }
-fn default_h3_alt_svc_ma() -> u64 {
-    86400
+
+fn default_cb_failure_threshold() -> u32 {
+    5
 }
-fn default_h3_cc_algorithm() -> String {
-    "bbr".to_string()
+fn default_cb_failure_window() -> u64 {
+    60
 }
-fn default_h3_mmsg_batch_size() -> usize {
-    64
+fn default_cb_open_duration() -> u64 {
+    30
+}
+fn default_cb_half_open_probes() -> u32 {
+    3
+}
+fn default_cb_success_threshold() -> u32 {
+    2
 }
 
-impl Default for Http3ConfigSection {
+/// 適応型の同時実行数制限の設定（F-138）
+///
+/// upstream グループ単位（全ワーカー共有）で同時実行数の上限を持ち、最小 RTT と直近の
+/// 観測 RTT の比から上限を自動調整する。上限を超えたリクエストは即座に 503 を返す。
+#[derive(Deserialize, Clone, Debug)]
+pub struct AdaptiveConcurrencyConfig {
+    /// 有効化フラグ
+    #[serde(default)]
+    pub enabled: bool,
+    /// 上限の調整方式（"gradient"（デフォルト）/ "aimd"）
+    #[serde(default)]
+    pub algorithm: AdaptiveConcurrencyAlgorithm,
+    /// 初期上限（デフォルト: 20）
+    #[serde(default = "default_ac_initial_limit")]
+    pub initial_limit: u32,
+    /// 上限の下限（デフォルト: 1）
+    #[serde(default = "default_ac_min_limit")]
+    pub min_limit: u32,
+    /// 上限の上限（デフォルト: 1000）
+    #[serde(default = "default_ac_max_limit")]
+    pub max_limit: u32,
+    /// 許容する RTT の増加率（観測 RTT が最小 RTT × この値以下なら上限を下げない、デフォルト: 1.5）
+    #[serde(default = "default_ac_rtt_tolerance")]
+    pub rtt_tolerance: f64,
+    /// RTT を集計して上限を見直す間隔（ミリ秒、デフォルト: 250）
+    #[serde(default = "default_ac_sample_window_ms")]
+    pub sample_window_ms: u64,
+    /// 最小 RTT を測り直す間隔（秒、デフォルト: 30）
+    #[serde(default = "default_ac_min_rtt_recalc_secs")]
+    pub min_rtt_recalc_secs: u64,
+    /// AIMD で上限を下げるときの倍率（0.0-1.0、デフォルト: 0.9）
+    #[serde(default = "default_ac_backoff_ratio")]
+    pub backoff_ratio: f64,
+    /// 拒否時の `Retry-After`（秒、デフォルト: 1）
+    #[serde(default = "default_ac_retry_after_secs")]
+    pub retry_after_secs: u64,
+}
+
+impl Default for AdaptiveConcurrencyConfig {
     fn default() -> Self {
         Self {
-            listen: None,
-            max_idle_timeout: default_h3_max_idle_timeout(),
-            max_udp_payload_size: default_h3_max_udp_payload_size(),
-            initial_max_data: default_h3_initial_max_data(),
-            initial_max_stream_data_bidi_local: default_h3_initial_max_stream_data(),
-            initial_max_stream_data_bidi_remote: default_h3_initial_max_stream_data(),
-            initial_max_stream_data_uni: default_h3_initial_max_stream_data(),
-            initial_max_streams_bidi: default_h3_max_streams(),
-            initial_max_streams_uni: default_h3_max_streams(),
-            compression_enabled: false,
-            compression: Http3CompressionConfig::default(),
-            gso_gro_enabled: false,
-            cc_algorithm: default_h3_cc_algorithm(),
-            pacing: true,
-            max_pacing_rate: None,
-            hystart: true,
-            mmsg_batch_size: default_h3_mmsg_batch_size(),
-            alt_svc_enabled: true,
-            alt_svc: None,
-            alt_svc_ma_secs: default_h3_alt_svc_ma(),
+            enabled: false,
+            algorithm: AdaptiveConcurrencyAlgorithm::default(),
+            initial_limit: default_ac_initial_limit(),
+            min_limit: default_ac_min_limit(),
+            max_limit: default_ac_max_limit(),
+            rtt_tolerance: default_ac_rtt_tolerance(),
+            sample_window_ms: default_ac_sample_window_ms(),
+            min_rtt_recalc_secs: default_ac_min_rtt_recalc_secs(),
+            backoff_ratio: default_ac_backoff_ratio(),
+            retry_after_secs: default_ac_retry_after_secs(),
         }
     }
 }
 
-#[cfg(feature = "http3")]
-impl Http3ConfigSection {
-    /// HTTP/3 設定を Http3ServerConfig に変換
-    pub fn to_http3_config(
-        &self,
-        cert_path: &str,
-        key_path: &str,
-    ) -> http3_server::Http3ServerConfig {
-        http3_server::Http3ServerConfig {
-            cert_path: cert_path.to_string(),
-            key_path: key_path.to_string(),
-            cert_pem: None, // quicheはファイルパスからの読み込みのみサポート
-            key_pem: None,
-            max_idle_timeout: self.max_idle_timeout,
-            max_udp_payload_size: self.max_udp_payload_size,
-            initial_max_data: self.initial_max_data,
-            initial_max_stream_data_bidi_local: self.initial_max_stream_data_bidi_local,
-            initial_max_stream_data_bidi_remote: self.initial_max_stream_data_bidi_remote,
-            initial_max_stream_data_uni: self.initial_max_stream_data_uni,
-            initial_max_streams_bidi: self.initial_max_streams_bidi,
-            initial_max_streams_uni: self.initial_max_streams_uni,
-            gso_gro_enabled: self.gso_gro_enabled,
-            cc_algorithm: self.cc_algorithm.clone(),
-            pacing: self.pacing,
-            max_pacing_rate: self.max_pacing_rate,
-            hystart: self.hystart,
-            mmsg_batch_size: self
-                .mmsg_batch_size
-                .clamp(1, crate::udp::socket::MMSG_BATCH_MAX),
-        }
-    }
+/// 同時実行数上限の調整方式（F-138）
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AdaptiveConcurrencyAlgorithm {
+    /// 勾配方式: 上限 × (最小 RTT × 許容率 / 観測 RTT) + √上限 を平滑化して追従する
+    #[default]
+    #[serde(rename = "gradient")]
+    Gradient,
+    /// AIMD: RTT 超過・失敗で倍率減少、上限近くまで使われていれば 1 ずつ増加
+    #[serde(rename = "aimd")]
+    Aimd,
 }
 
-#[derive(Deserialize)]
-pub struct ServerConfigSection {
-    pub listen: String,
-    /// HTTPリスナーアドレス（オプション）
-    ///
-    /// 指定した場合、HTTPアクセスをHTTPSにリダイレクトするリスナーを起動します。
-    /// 例: "0.0.0.0:80"
-    ///
-    /// リダイレクトのみを行い、コンテンツは配信しません（セキュリティ考慮）。
-    #[serde(default)]
-    pub http: Option<String>,
-    /// ワーカースレッド数
-    /// 未指定または0の場合はCPUコア数と同じスレッド数を使用
-    #[serde(default)]
-    pub threads: Option<usize>,
-
-    // ====================
-    // Serverヘッダー設定
-    // ====================
-    /// Serverヘッダーを有効化（デフォルト: false）
-    ///
-    /// セキュリティ考慮事項:
-    /// - Serverヘッダーはサーバーソフトウェア情報を公開します
-    /// - 攻撃者がバージョン別の脆弱性を狙う手がかりになり得ます
-    /// - 本番環境では無効化を推奨
-    #[serde(default)]
-    pub server_header_enabled: bool,
-
-    /// Serverヘッダーの値（server_header_enabled = true時のみ有効）
-    ///
-    /// デフォルト: "veil"
-    /// 空文字の場合: ヘッダー自体を送信しない
-    #[serde(default = "default_server_header_value")]
-    pub server_header_value: String,
-
-    // ====================
-    // HTTP/2・HTTP/3 設定
-    // ====================
-    /// HTTP/2 を有効化するかどうか
-    ///
-    /// TLS ALPN ネゴシエーションにより HTTP/2 (h2) をサポートします。
-    /// HTTP/1.1 へのフォールバックも可能です。
-    ///
-    /// 効果:
-    /// - ストリーム多重化によるレイテンシ削減
-    /// - HPACK ヘッダー圧縮によるオーバーヘッド削減
-    /// - サーバープッシュ（無効化推奨）
-    ///
-    /// 注意: `--features http2` でビルドする必要があります
-    #[serde(default)]
-    #[cfg_attr(not(feature = "http2"), allow(dead_code))]
-    pub http2_enabled: bool,
-
-    /// HTTP/3 を有効化するかどうか
-    ///
-    /// QUIC/UDP ベースの HTTP/3 プロトコルをサポートします。
-    ///
-    /// 効果:
-    /// - 0-RTT 接続確立
-    /// - 接続マイグレーション
-    /// - Head-of-Line ブロッキング解消
-    ///
-    /// 注意:
-    /// - `--features http3` でビルドする必要があります
-    /// - HTTP/3 は UDP ベースのため kTLS は使用不可
-    /// - GSO/GRO による高パフォーマンス UDP 処理を使用
-    /// - リッスンアドレスは [http3].listen で設定
-    #[serde(default)]
-    #[cfg_attr(not(feature = "http3"), allow(dead_code))]
-    pub http3_enabled: bool,
-
-    // ====================
-    // H2C (HTTP/2 Cleartext) 設定
-    // ====================
-    /// H2C (HTTP/2 Cleartext) サーバーを有効化するかどうか
-    ///
-    /// TLSなしでHTTP/2を使用するプロトコルです（RFC 7540 Section 3.4）。
-    /// Prior Knowledgeモードをサポートします。
-    ///
-    /// 効果:
-    /// - ストリーム多重化によるレイテンシ削減
-    /// - HPACK ヘッダー圧縮によるオーバーヘッド削減
-    /// - gRPC バックエンドへの接続に適している
-    ///
-    /// セキュリティ考慮事項:
-    /// - H2Cは平文通信のため、本番環境では内部ネットワークでのみ使用を推奨
-    /// - 外部公開時はTLS必須
-    /// - デフォルトで無効（明示的に有効化が必要）
-    ///
-    /// 注意: `--features http2` でビルドする必要があります
-    #[serde(default)]
-    #[cfg_attr(not(feature = "http2"), allow(dead_code))]
-    pub h2c_enabled: bool,
+fn default_ac_initial_limit() -> u32 {
+    20
+}
+fn default_ac_min_limit() -> u32 {
+    1
+}
+fn default_ac_max_limit() -> u32 {
+    1000
+}
+fn default_ac_rtt_tolerance() -> f64 {
+    1.5
+}
+fn default_ac_sample_window_ms() -> u64 {
+    250
+}
+fn default_ac_min_rtt_recalc_secs() -> u64 {
+    30
+}
+fn default_ac_backoff_ratio() -> f64 {
+    0.9
+}
+fn default_ac_retry_after_secs() -> u64 {
+    1
+}
 
-    /// H2C リスニングアドレス（オプション）
-    ///
-    /// 指定した場合、H2C専用のリスナーを起動します。
-    /// 未指定の場合は、TLSリスナーと同じアドレスでプロトコル検出を行います。
-    ///
-    /// 例: "0.0.0.0:8080"
-    ///
-    /// 注意: 同じポートでTLSとH2Cの両方を処理する場合は未指定にしてください。
+/// 異常検知（パッシブ Outlier Detection）設定（F-06）
+#[derive(Deserialize, Clone, Debug)]
+pub struct OutlierConfig {
+    /// 有効化フラグ
     #[serde(default)]
-    #[cfg_attr(not(feature = "http2"), allow(dead_code))]
-    pub h2c_listen: Option<String>,
+    pub enabled: bool,
+    /// エラー率の閾値（0.0-1.0、デフォルト 0.5）
+    #[serde(default = "default_outlier_error_rate")]
+    pub error_rate_threshold: f64,
+    /// 評価間隔（秒）
+    #[serde(default = "default_outlier_interval")]
+    pub interval_secs: u64,
+    /// 基本排除時間（秒）
+    #[serde(default = "default_outlier_base_ejection")]
+    pub base_ejection_time_secs: u64,
+    /// 同時に排除可能なサーバーの最大割合（%）
+    #[serde(default = "default_outlier_max_eject_percent")]
+    pub max_ejection_percent: u32,
+}
 
-    // ====================
-    // グレースフルシャットダウン設定
-    // ====================
-    /// グレースフルシャットダウンのドレインタイムアウト（秒）
-    ///
-    /// SIGTERMまたはSIGINT受信時に、既存の接続が完了するまで待機する最大時間。
-    /// タイムアウト後は残りの接続を強制終了します。
-    ///
-    /// デフォルト: 30秒
-    /// 0: 待機せずに即座に終了（既存の動作）
-    #[serde(default = "default_graceful_shutdown_timeout")]
-    pub graceful_shutdown_timeout_secs: u64,
+impl Default for OutlierConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            error_rate_threshold: default_outlier_error_rate(),
+            interval_secs: default_outlier_interval(),
+            base_ejection_time_secs: default_outlier_base_ejection(),
+            max_ejection_percent: default_outlier_max_eject_percent(),
+        }
+    }
 }
 
-/// グレースフルシャットダウンタイムアウトのデフォルト値（30秒）
-fn default_graceful_shutdown_timeout() -> u64 {
+fn default_outlier_error_rate() -> f64 {
+    0.5
+}
+fn default_outlier_interval() -> u64 {
+    10
+}
+fn default_outlier_base_ejection() -> u64 {
     30
 }
-
-/// Serverヘッダーのデフォルト値
-fn default_server_header_value() -> String {
-    "veil".to_string()
+fn default_outlier_max_eject_percent() -> u32 {
+    50
 }
 
-#[derive(Deserialize)]
-pub struct TlsConfigSection {
-    pub cert_path: String,
-    pub key_path: String,
-    /// kTLSを有効化するかどうか（Linux 5.15+、modprobe tls 必須）
-    ///
-    /// kTLS有効化時の効果:
-    /// - TLSデータ転送フェーズでカーネルオフロード
-    /// - sendfileでゼロコピー送信（TLS暗号化済み）
-    /// - 高負荷時にCPU 20-40%節約、スループット最大2倍
-    ///
-    /// 注意事項:
-    /// - TLSハンドシェイクはrustlsで実行（セキュリティ維持）
-    /// - AES-GCM暗号スイートのみサポート
-    /// - カーネルバグの影響範囲に注意
-    #[serde(default)]
-    pub ktls_enabled: bool,
-    /// kTLS有効化失敗時にrustlsへフォールバックするかどうか
-    ///
-    /// - false: kTLS必須モード（失敗時は接続拒否）
-    /// - true: kTLS失敗時はrustlsで継続（デフォルト）
-    ///
-    /// フォールバック無効化のメリット:
-    /// - パフォーマンス予測可能性（確実にkTLSを使用）
-    /// - デバッグ容易性（kTLS/rustls混在なし）
-    /// - 環境問題の早期発見
-    #[serde(default = "default_ktls_fallback")]
-    pub ktls_fallback_enabled: bool,
-    /// kTLS有効時にTCP_CORKを使用するかどうか
-    ///
-    /// TCP_CORKはkTLS設定中に小さなTCPパケットの送信を遅延し、
-    /// パケット結合により効率的なネットワーク転送を実現します。
-    ///
-    /// - true: TCP_CORK有効（デフォルト、推奨）
-    /// - false: TCP_CORK無効（特定の低遅延要件がある場合）
-    #[serde(default = "default_tcp_cork")]
-    pub tcp_cork_enabled: bool,
-    /// 利用を許可する TLS 暗号スイート（nginx の `ssl_ciphers` 相当、F-50）
-    ///
-    /// **記載順 = サーバ優先度順**（rustls はサーバ選好でネゴシエートする）。
-    /// 未指定（空）の場合は rustls 既定（DEFAULT_CIPHER_SUITES）を使用する。
-    /// 不正なスイート名は起動時（設定検証時）にエラーとなる。
-    ///
-    /// 指定可能な名前（aws-lc-rs プロバイダ）:
-    /// - TLS 1.3: `TLS13_AES_256_GCM_SHA384`, `TLS13_AES_128_GCM_SHA256`,
-    ///   `TLS13_CHACHA20_POLY1305_SHA256`
-    /// - TLS 1.2: `TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384`,
-    ///   `TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256`,
-    ///   `TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256`,
-    ///   `TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384`,
-    ///   `TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256`,
-    ///   `TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256`
-    ///
-    /// 注意: kTLS は AES-GCM 系のみ対応。kTLS 有効時に CHACHA20 系を含めると、
-    /// そのスイートでネゴシエートした接続は kTLS へオフロードされず rustls で
-    /// 処理される（`ktls_fallback_enabled = false` の場合は接続拒否）。
-    #[serde(default)]
-    pub cipher_suites: Vec<String>,
-    /// 証明書ファイルの変更を自動検知してリロードするか（F-03）
-    /// デフォルト: false
+/// パッシブヘルスチェック設定（F-144、実トラフィックの結果で即座に unhealthy にする）
+///
+/// 各閾値は 0 で無効。いずれか 1 つ以上を有効にすること。
+#[derive(Deserialize, Clone, Debug)]
+pub struct PassiveHealthConfig {
+    /// 連続した 5xx 応答（接続エラーを含む）の回数（デフォルト 5）
+    #[serde(default = "default_passive_consecutive_5xx")]
+    pub consecutive_5xx: u32,
+    /// 連続した接続エラーの回数（デフォルト 3）
+    #[serde(default = "default_passive_consecutive_connect_failures")]
+    pub consecutive_connect_failures: u32,
+    /// ウィンドウ内のゲートウェイエラー（502/503/504・接続エラー）の回数（デフォルト 0 = 無効）
     #[serde(default)]
-    pub auto_reload: bool,
-    /// 証明書変更チェックの間隔（秒、F-03）
-    /// デフォルト: 60
-    #[serde(default = "default_tls_reload_interval")]
-    pub reload_interval_secs: u64,
+    pub gateway_errors: u32,
+    /// `gateway_errors` を数えるウィンドウ（秒、デフォルト 10）
+    #[serde(default = "default_passive_gateway_error_window")]
+    pub gateway_error_window_secs: u64,
+    /// unhealthy にしてから自動で復帰させるまでの秒数（0 はアクティブチェックでのみ復帰、デフォルト 30）
+    #[serde(default = "default_passive_recovery_secs")]
+    pub recovery_secs: u64,
 }
 
-/// 証明書リロードチェック間隔のデフォルト値（秒）
-fn default_tls_reload_interval() -> u64 {
-    60
+impl Default for PassiveHealthConfig {
+    fn default() -> Self {
+        Self {
+            consecutive_5xx: default_passive_consecutive_5xx(),
+            consecutive_connect_failures: default_passive_consecutive_connect_failures(),
+            gateway_errors: 0,
+            gateway_error_window_secs: default_passive_gateway_error_window(),
+            recovery_secs: default_passive_recovery_secs(),
+        }
+    }
 }
 
-/// kTLSフォールバックのデフォルト値（true = フォールバック有効）
-fn default_ktls_fallback() -> bool {
-    true
+fn default_passive_consecutive_5xx() -> u32 {
+    5
 }
-
-/// TCP_CORKのデフォルト値（true = 有効）
-fn default_tcp_cork() -> bool {
-    true
+fn default_passive_consecutive_connect_failures() -> u32 {
+    3
+}
+fn default_passive_gateway_error_window() -> u64 {
+    10
+}
+fn default_passive_recovery_secs() -> u64 {
+    30
 }
 
-/// パフォーマンス設定
-#[derive(Deserialize, Clone, Default)]
-pub struct PerformanceConfigSection {
-    /// SO_REUSEPORTの振り分け方式
-    /// - "kernel": カーネルデフォルト（3元タプルハッシュ）
-    /// - "cbpf": クライアントIPベースのCBPF振り分け（Linux 4.6+必須）
+// 注: リトライポリシー（RetryPolicy）は F-06 で構造体のみ定義されたが、リトライ機構
+// 本体が未実装でどこからも参照されない dead code だったため F-51 で削除した。
+// リトライを実装する際は resilience.rs と併せて再設計すること。
+
+/// ヘルスチェックの種別（F-22）
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum HealthCheckType {
+    /// HTTP/HTTPS リクエストを送信してステータスコードを確認（デフォルト）
+    #[default]
+    Http,
+    /// TCP 接続の確立可否のみ確認
+    Tcp,
+    /// gRPC Health Checking Protocol (grpc.health.v1.Health/Check)
+    Grpc,
+}
+
+/// 健康チェック設定
+#[derive(Deserialize, Clone, Debug)]
+pub struct HealthCheckConfig {
+    /// チェック種別（http / tcp / grpc）。省略時は http。
     #[serde(default)]
-    pub reuseport_balancing: ReuseportBalancing,
-    /// Huge Pages (Large OS Pages) の使用
-    ///
-    /// mimallocでHuge Pages（2MB）を優先使用し、TLBミスを削減します。
-    ///
-    /// 効果:
-    /// - TLB（Translation Lookaside Buffer）ミス削減
-    /// - 大容量メモリ使用時のページフォルト減少
-    /// - kTLS/splice時のカーネル連携で5-10%パフォーマンス向上
-    ///
-    /// 要件（Linux）:
-    /// - /proc/sys/vm/nr_hugepages に十分な値を設定
-    /// - コンテナ環境では追加設定が必要な場合あり
+    pub check_type: HealthCheckType,
+    /// チェック間隔（秒）
+    #[serde(default = "default_health_check_interval")]
+    pub interval_secs: u64,
+    /// チェック対象パス（HTTP チェック時のリクエストパス、gRPC チェック時のサービス名）
+    #[serde(default = "default_health_check_path")]
+    pub path: String,
+    /// タイムアウト（秒）
+    #[serde(default = "default_health_check_timeout")]
+    pub timeout_secs: u64,
+    /// 成功と判断するHTTPステータスコード（HTTP チェック時のみ有効、デフォルト: 200-399）
+    #[serde(default = "default_healthy_statuses")]
+    pub healthy_statuses: Vec<u16>,
+    /// 何回連続で失敗したら unhealthy とするか
+    #[serde(default = "default_unhealthy_threshold")]
+    pub unhealthy_threshold: u32,
+    /// 何回連続で成功したら healthy に戻すか
+    #[serde(default = "default_healthy_threshold")]
+    pub healthy_threshold: u32,
+    /// TLS接続を使用するかどうか
+    /// デフォルト: false（既存のHTTP健康チェックを使用）
     #[serde(default)]
-    pub huge_pages_enabled: bool,
-
-    // ====================
-    // Viaヘッダー設定
-    // ====================
-    /// Viaヘッダーを追加するかどうか
-    ///
-    /// RFC 7230 Section 5.7.1 に従い、プロキシ経由のリクエスト/レスポンスに
-    /// Via ヘッダーを追加します。
-    ///
-    /// 形式: Via: 1.1 <hostname>
-    ///
-    /// - true: Viaヘッダーを追加
-    /// - false: Viaヘッダーを追加しない（デフォルト）
+    pub use_tls: bool,
+    /// 証明書検証を有効化するかどうか（use_tls=true時のみ有効）
+    /// デフォルト: true
+    #[serde(default = "default_true")]
+    pub verify_cert: bool,
+    /// HTTP チェックのリクエストメソッド（F-143、デフォルト: GET）
+    #[serde(default = "default_health_check_method")]
+    pub method: String,
+    /// HTTP チェックで追加するリクエストヘッダー（F-143）
     #[serde(default)]
-    pub via_header_enabled: bool,
-
-    /// Viaヘッダーに使用するホスト名
-    ///
-    /// via_header_enabled = true 時に使用します。
-    /// 未指定の場合はシステムホスト名または "veil" を使用します。
+    pub headers: HashMap<String, String>,
+    /// Host ヘッダー / `:authority` の値（F-143、省略時はサーバーのホスト名）
     #[serde(default)]
-    pub via_header_hostname: Option<String>,
-
-    // ====================
-    // sendfile/splice チャンクサイズ設定
-    // ====================
-    /// チャンクサイズ調整モード
-    ///
-    /// - "dynamic": ファイルサイズに応じて動的にチャンクサイズを決定（デフォルト）
-    ///   - 0-64KB: 64KB
-    ///   - 64KB-1MB: 256KB
-    ///   - 1MB超: 1MB
-    /// - "manual": 固定チャンクサイズを使用
-    #[serde(default = "default_chunk_size_mode")]
-    #[cfg_attr(not(veil_ktls), allow(dead_code))]
-    pub chunk_size_mode: ChunkSizeMode,
-
-    /// 手動チャンクサイズ（バイト）
-    ///
-    /// chunk_size_mode = "manual" 時に使用します。
-    /// デフォルト: 1048576 (1MB)
-    #[serde(default = "default_manual_chunk_size")]
-    #[cfg_attr(not(veil_ktls), allow(dead_code))]
-    pub manual_chunk_size: usize,
-
-    // ====================
-    // パイプ割当設定
-    // ====================
-    /// ストリーム毎にパイプを割り当てるかどうか
-    ///
-    /// kTLS splice 使用時のパイプバッファ管理方式を設定します。
-    ///
-    /// - false: スレッドローカルパイプを再利用（デフォルト、メモリ効率重視）
-    /// - true: ストリーム毎にパイプを割り当て（高並行性環境向け）
-    ///
-    /// 高並行性環境（同時接続数1000+）ではtrueを推奨します。
+    pub host: Option<String>,
+    /// リクエストボディ（F-143、Content-Length 付きで送信）
     #[serde(default)]
-    #[cfg_attr(not(veil_ktls), allow(dead_code))]
-    pub per_stream_pipe_enabled: bool,
-
-    // ====================
-    // OpenFileCache設定
-    // ====================
-    /// OpenFileCache（ファイルメタデータキャッシュ）のグローバルデフォルト設定
-    ///
-    /// 効果:
-    ///   - canonicalize、metadata、mime_guessのシステムコールをキャッシュ
-    ///   - 1リクエストあたり5〜6回のシステムコールを2回に削減（キャッシュヒット時）
-    ///   - パフォーマンス向上: 60〜67%のシステムコール削減
-    ///
-    /// 注意事項:
-    ///   - ファイル変更の検出が最大60秒（デフォルト）遅延する可能性
-    ///   - シンボリックリンク変更の検出が遅延する可能性
-    ///   - 静的ファイル配信に最適（動的に変更されるファイルには不向き）
-    ///
-    /// ルーティングごとの設定:
-    ///   - 各ルーティング（[[route]]）で`open_file_cache`セクションを指定可能
-    ///   - ルーティング設定がない場合は、このグローバル設定が使用される
-    ///
-    /// デフォルト: false（無効）
+    pub body: Option<String>,
+    /// レスポンスボディに含まれるべき部分文字列（F-143）
     #[serde(default)]
-    pub open_file_cache_enabled: bool,
+    pub expect_body: Option<String>,
+    /// レスポンスボディが一致すべき正規表現（F-143、部分一致）
+    #[serde(default)]
+    pub expect_body_regex: Option<String>,
+    /// JSON パス（`$.status`、`$.checks[0].ok`）と期待値（F-143、全て一致で成功）
+    #[serde(default)]
+    pub expect_json: BTreeMap<String, toml::Value>,
+    /// プローブのプロトコル（F-143: http1 / h2c / h2）。
+    /// 省略時は h3 上流なら HTTP/3、それ以外は HTTP/1.1（L4 リスナーでは TCP 接続確認）
+    #[serde(default)]
+    pub protocol: Option<HealthCheckProtocol>,
+    /// サーバーごとのチェック間隔を ±N% の範囲でずらす（F-143、0〜50、デフォルト: 0）
+    #[serde(default)]
+    pub jitter_percent: u32,
+    /// unhealthy なサーバーのチェック間隔（秒、F-143）。省略時は interval_secs
+    #[serde(default)]
+    pub unhealthy_interval_secs: Option<u64>,
+    /// `expect_body_regex` のコンパイル結果（初回プローブで生成し、クローン間で共有する）
+    #[serde(skip)]
+    pub(crate) body_regex: Arc<once_cell::sync::OnceCell<Option<regex::bytes::Regex>>>,
+}
+
+/// HTTP ヘルスチェックのプロトコル（F-143）
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HealthCheckProtocol {
+    /// HTTP/1.1（use_tls=true で HTTPS）
+    #[serde(rename = "http1", alias = "http/1.1")]
+    Http1,
+    /// HTTP/2 Prior Knowledge（平文）
+    #[serde(rename = "h2c")]
+    H2c,
+    /// TLS + ALPN h2（use_tls=true が必須）
+    #[serde(rename = "h2")]
+    H2,
+}
+
+impl HealthCheckConfig {
+    /// 次回チェックまでの基準間隔（unhealthy 時は unhealthy_interval_secs を優先）
+    pub fn interval_for(&self, healthy: bool) -> Duration {
+        let secs = if healthy {
+            self.interval_secs
+        } else {
+            self.unhealthy_interval_secs.unwrap_or(self.interval_secs)
+        };
+        Duration::from_secs(secs.max(1))
+    }
 
-    /// OpenFileCacheの有効期間（秒、グローバルデフォルト）
-    /// キャッシュされたファイル情報が有効とみなされる期間
-    /// デフォルト: 60秒
-    #[serde(default = "default_open_file_cache_valid_duration")]
-    pub open_file_cache_valid_duration_secs: u64,
+    /// HTTP リクエストまたはレスポンス検証のカスタマイズがあるか
+    fn has_http_options(&self) -> bool {
+        !self.method.eq_ignore_ascii_case("GET")
+            || !self.headers.is_empty()
+            || self.host.is_some()
+            || self.body.is_some()
+            || self.expect_body.is_some()
+            || self.expect_body_regex.is_some()
+            || !self.expect_json.is_empty()
+    }
+}
 
-    /// OpenFileCacheの最大エントリ数（グローバルデフォルト）
-    /// キャッシュに保持する最大ファイル情報数
-    /// デフォルト: 10000
-    #[serde(default = "default_open_file_cache_max_entries")]
-    pub open_file_cache_max_entries: usize,
+/// ヘルスチェック設定の妥当性を検証する（F-143）
+///
+/// `h3` は HTTP/3 上流（プローブ既定が HTTP/3）、`l4` は L4 リスナー（既定が TCP 接続確認）。
+fn validate_health_check(
+    owner: &str,
+    name: &str,
+    hc: &HealthCheckConfig,
+    h3: bool,
+    l4: bool,
+) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} '{}': health_check {}", owner, name, msg),
+        ))
+    };
+    if hc.jitter_percent > 50 {
+        return invalid(format!(
+            "jitter_percent must be between 0 and 50 (got {})",
+            hc.jitter_percent
+        ));
+    }
+    if hc.interval_secs == 0 || hc.unhealthy_interval_secs == Some(0) {
+        return invalid("intervals must be at least 1 second".to_string());
+    }
+    if hc.method.is_empty() || !hc.method.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return invalid(format!("invalid method \"{}\"", hc.method));
+    }
+    for (key, value) in &hc.headers {
+        if key.is_empty()
+            || !key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            || value.contains(['\r', '\n'])
+        {
+            return invalid(format!("invalid header \"{}\"", key));
+        }
+    }
+    if hc
+        .host
+        .as_deref()
+        .is_some_and(|h| h.contains(['\r', '\n']) || h.is_empty())
+    {
+        return invalid("invalid host".to_string());
+    }
+    if hc
+        .body
+        .as_ref()
+        .is_some_and(|b| b.len() > crate::health::MAX_PROBE_REQUEST_BODY)
+    {
+        return invalid(format!(
+            "body must be at most {} bytes",
+            crate::health::MAX_PROBE_REQUEST_BODY
+        ));
+    }
+    if let Some(pattern) = &hc.expect_body_regex {
+        if let Err(e) = regex::bytes::Regex::new(pattern) {
+            return invalid(format!("expect_body_regex: {}", e));
+        }
+    }
+    for path in hc.expect_json.keys() {
+        if crate::health::parse_json_path(path).is_none() {
+            return invalid(format!("invalid expect_json path \"{}\"", path));
+        }
+    }
+    match hc.protocol {
+        Some(HealthCheckProtocol::H2) if !hc.use_tls => {
+            return invalid("protocol = \"h2\" requires use_tls = true".to_string());
+        }
+        Some(HealthCheckProtocol::H2c) if hc.use_tls => {
+            return invalid("protocol = \"h2c\" cannot be used with use_tls".to_string());
+        }
+        Some(HealthCheckProtocol::H2 | HealthCheckProtocol::H2c) if !cfg!(feature = "http2") => {
+            return invalid("h2 / h2c probes require the http2 feature".to_string());
+        }
+        _ => {}
+    }
+    if hc.check_type == HealthCheckType::Http && hc.protocol.is_none() && hc.has_http_options() {
+        if h3 && cfg!(feature = "http3") {
+            return invalid(
+                "request / body options are not supported by HTTP/3 probes; set protocol = \"http1\" or \"h2\""
+                    .to_string(),
+            );
+        }
+        if l4 {
+            return invalid(
+                "request / body options require protocol = \"http1\", \"h2c\" or \"h2\" on L4 listeners"
+                    .to_string(),
+            );
+        }
+    }
+    Ok(())
 }
 
-fn default_open_file_cache_valid_duration() -> u64 {
-    60
+fn default_health_check_method() -> String {
+    "GET".to_string()
 }
 
-fn default_open_file_cache_max_entries() -> usize {
-    10000
+fn default_health_check_interval() -> u64 {
+    10
+}
+fn default_health_check_path() -> String {
+    "/".to_string()
+}
+fn default_health_check_timeout() -> u64 {
+    5
+}
+fn default_healthy_statuses() -> Vec<u16> {
+    vec![200, 201, 202, 204, 301, 302, 304]
+}
+fn default_unhealthy_threshold() -> u32 {
+    3
+}
+fn default_healthy_threshold() -> u32 {
+    2
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            check_type: HealthCheckType::Http,
+            interval_secs: default_health_check_interval(),
+            path: default_health_check_path(),
+            timeout_secs: default_health_check_timeout(),
+            healthy_statuses: default_healthy_statuses(),
+            unhealthy_threshold: default_unhealthy_threshold(),
+            healthy_threshold: default_healthy_threshold(),
+            use_tls: false,
+            verify_cert: default_true(),
+            method: default_health_check_method(),
+            headers: HashMap::new(),
+            host: None,
+            body: None,
+            expect_body: None,
+            expect_body_regex: None,
+            expect_json: BTreeMap::new(),
+            protocol: None,
+            jitter_percent: 0,
+            unhealthy_interval_secs: None,
+            body_regex: Arc::default(),
+        }
+    }
 }
 
 // ====================
-// ログ設定
-// ====================
-//
-// ftlogは内部でバックグラウンドスレッドとチャネルを使用した
-// 非同期ログライブラリです。以下の設定で最適化が可能です。
-//
-// ## grokの指摘に対する検証結果
-//
-// grokは「ftlogは同期ログ」と主張していましたが、これは不正確です。
-// ftlogは以下の非同期アーキテクチャを使用しています：
-// - ログマクロ → 内部チャネルにプッシュ（ノンブロッキング）
-// - バックグラウンドスレッド → チャネルから読み取りファイルI/O
-//
-// したがって、tokio::sync::mpscを使った追加の非同期化層は不要であり、
-// むしろオーバーヘッドを増やす可能性があります。
-//
-// ## 推奨される最適化
-// - channel_size: 高負荷時のバックプレッシャーを軽減
-// - flush_interval_ms: ディスクI/O頻度の調整
-// - level: 本番環境ではinfo以上を推奨
+// 統合ルーティング（AWS ALB準拠）
 // ====================
 
-// LogFormat, LoggingConfigSection, parse_log_level は
-// crate::logging モジュールに移動しました。
+/// ルーティング条件（AWS ALB準拠）
+///
+/// すべての条件はANDで結合されます。
+/// 条件が指定されていない場合は、すべてのリクエストにマッチします（デフォルトルート）。
+#[derive(Clone, Debug, Deserialize, Default)]
+pub struct RouteConditions {
+    /// host-header: ホスト名マッチ（ワイルドカード対応）
+    /// 例: "api.example.com", "*.example.com"
+    #[serde(default)]
+    pub host: Option<String>,
 
-/// SO_REUSEPORTの振り分け方式
-#[derive(Default, Clone, Copy, Debug, PartialEq)]
-pub enum ReuseportBalancing {
-    /// カーネルデフォルト（3元タプルハッシュ: protocol + source IP + source port）
-    #[default]
-    Kernel,
-    /// クライアントIPベースのCBPF振り分け
-    /// 同一クライアントIPからの接続を常に同じワーカースレッドに振り分け
-    /// CPUキャッシュ効率とセッション再開効率を向上
-    Cbpf,
-}
+    /// path-pattern: パスマッチ（ワイルドカード対応）
+    /// 例: "/api/*", "/static/*", "/api/v2/*"
+    #[serde(default)]
+    pub path: Option<String>,
 
-impl<'de> serde::Deserialize<'de> for ReuseportBalancing {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        match s.as_str() {
-            "kernel" => Ok(ReuseportBalancing::Kernel),
-            "cbpf" => Ok(ReuseportBalancing::Cbpf),
-            other => Err(serde::de::Error::custom(format!(
-                "unknown reuseport_balancing value: '{}', expected 'kernel' or 'cbpf'",
-                other
-            ))),
-        }
-    }
-}
+    /// http-header: HTTPヘッダーマッチ（複数指定可能）
+    /// 例: { "X-Version" = "v2" }
+    #[serde(default)]
+    pub header: Option<HashMap<String, String>>,
 
-/// sendfile/splice チャンクサイズ調整モード
-#[derive(Clone, Copy, Debug, PartialEq, Default)]
-pub enum ChunkSizeMode {
-    /// ファイルサイズに応じて動的にチャンクサイズを決定
-    /// - 0-64KB: 64KB
-    /// - 64KB-1MB: 256KB
-    /// - 1MB超: 1MB
-    #[default]
-    Dynamic,
-    /// 固定チャンクサイズを使用（manual_chunk_sizeで指定）
-    Manual,
-}
+    /// http-request-method: HTTPメソッドマッチ（配列対応）
+    /// 例: ["GET", "POST"]
+    #[serde(default)]
+    pub method: Option<Vec<String>>,
 
-impl<'de> serde::Deserialize<'de> for ChunkSizeMode {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        match s.as_str() {
-            "dynamic" | "Dynamic" => Ok(ChunkSizeMode::Dynamic),
-            "manual" | "Manual" => Ok(ChunkSizeMode::Manual),
-            other => Err(serde::de::Error::custom(format!(
-                "unknown chunk_size_mode value: '{}', expected 'dynamic' or 'manual'",
-                other
-            ))),
-        }
-    }
-}
+    /// query-string: クエリパラメータマッチ（複数指定可能）
+    /// 例: { "key" = "value" }
+    #[serde(default)]
+    pub query: Option<HashMap<String, String>>,
 
-/// チャンクサイズモードのデフォルト値（動的調整）
-fn default_chunk_size_mode() -> ChunkSizeMode {
-    ChunkSizeMode::Dynamic
-}
+    /// source-ip: ソースIPマッチ（CIDR表記）
+    /// 例: ["192.168.0.0/16", "10.0.0.0/8"]
+    #[serde(default)]
+    pub source_ip: Option<Vec<String>>,
 
-/// 手動チャンクサイズのデフォルト値（1MB）
-fn default_manual_chunk_size() -> usize {
-    1048576 // 1MB
+    /// jwt-claim: `Authorization: Bearer` の JWT クレームマッチ（ワイルドカード対応、F-148）
+    /// 例: { "tenant" = "acme", "roles" = "admin" }
+    ///
+    /// ルーティング時は署名を検証しないため、`[route.jwt]` を設定したルートでのみ使える。
+    /// 配列のクレームは要素のいずれかが一致すればよい。
+    #[serde(default)]
+    pub jwt_claims: Option<HashMap<String, String>>,
 }
 
-/// ファイルサイズに応じた最適なチャンクサイズを計算
+/// ルーティングルール
 ///
-/// 動的調整モード時に使用されます。
-pub fn calculate_optimal_chunk_size(file_size: u64) -> usize {
-    match file_size {
-        0..=65_536 => 65_536,          // 64KB以下: 64KB
-        65_537..=1_048_576 => 262_144, // 1MB以下: 256KB
-        _ => 1_048_576,                // 1MB超: 1MB
-    }
-}
+/// 条件に一致するリクエストに対して、指定されたバックエンドアクションを実行します。
+#[derive(Clone, Debug, Deserialize)]
+pub struct Route {
+    /// ルーティング条件（空の場合はデフォルトルート）
+    #[serde(default)]
+    pub conditions: RouteConditions,
 
-#[derive(Clone, Debug)]
-pub enum BackendConfig {
-    /// 単一URLプロキシ（後方互換性のため維持）
-    /// - sni_name: TLS接続時のSNI名（IP直打ち時にドメイン名を指定可能）
-    /// - use_h2c: H2C (HTTP/2 over cleartext) を使用するかどうか
-    ///
-    /// 注意: security, compression, buffering, cache, modules は route 直下で設定
-    Proxy {
-        url: String,
-        sni_name: Option<String>,
-        use_h2c: bool,
-    },
-    /// Upstream グループ参照（ロードバランシング用）
-    /// 注意: security, compression, buffering, cache, modules は route 直下で設定
-    ProxyUpstream { upstream: String, use_h2c: bool },
-    /// File バックエンド設定
-    /// - path: ファイルまたはディレクトリのパス
-    /// - mode: "sendfile" または "memory"
-    /// - index: ディレクトリアクセス時に返すファイル名（デフォルト: "index.html"）
-    ///
-    /// 注意: security, cache, open_file_cache, modules は route 直下で設定
-    File {
-        path: String,
-        mode: String,
-        index: Option<String>,
-    },
-    /// Redirect バックエンド設定
-    /// - redirect_url: リダイレクト先URL（$request_uri, $host, $path 変数使用可能）
-    /// - redirect_status: ステータスコード（301, 302, 307, 308）
-    /// - preserve_path: 元のパスをリダイレクト先に追加するか
-    ///
-    /// 注意: modules は route 直下で設定
-    Redirect {
-        redirect_url: String,
-        redirect_status: u16,
-        preserve_path: bool,
-    },
-}
+    /// バックエンドアクション
+    pub action: BackendConfig,
 
-impl<'de> serde::Deserialize<'de> for BackendConfig {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        use serde::de::{MapAccess, Visitor};
+    /// ルートレベルのセキュリティ設定（actionの設定をオーバーライド）
+    #[serde(default)]
+    pub security: Option<SecurityConfig>,
 
-        struct BackendConfigVisitor;
+    /// ルートレベルの圧縮設定（actionの設定をオーバーライド）
+    #[serde(default)]
+    pub compression: Option<CompressionConfig>,
 
-        impl<'de> Visitor<'de> for BackendConfigVisitor {
-            type Value = BackendConfig;
+    /// ルートレベルのバッファリング設定（actionの設定をオーバーライド）
+    #[serde(default)]
+    pub buffering: Option<buffering::BufferingConfig>,
 
-            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("a backend configuration object")
-            }
+    /// ルートレベルのキャッシュ設定（actionの設定をオーバーライド）
+    #[serde(default)]
+    pub cache: Option<cache::CacheConfig>,
 
-            fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
-            where
-                M: MapAccess<'de>,
-            {
-                let mut backend_type: Option<String> = None;
-                let mut url: Option<String> = None;
-                let mut upstream: Option<String> = None;
-                let mut path: Option<String> = None;
-                let mut mode: Option<String> = None;
-                let mut index: Option<String> = None;
-                // Redirect 用フィールド
-                let mut redirect_url: Option<String> = None;
-                let mut redirect_status: Option<u16> = None;
-                let mut preserve_path: Option<bool> = None;
-                // SNI 用フィールド（Proxy用）
-                let mut sni_name: Option<String> = None;
-                // H2C 用フィールド（Proxy用）
-                let mut use_h2c: Option<bool> = None;
+    /// ルートレベルのOpenFileCache設定（actionの設定をオーバーライド、Fileバックエンドのみ）
+    #[serde(default)]
+    pub open_file_cache: Option<cache::OpenFileCacheConfig>,
 
-                while let Some(key) = map.next_key::<String>()? {
-                    match key.as_str() {
-                        "type" => backend_type = Some(map.next_value()?),
-                        "url" => url = Some(map.next_value()?),
-                        "upstream" => upstream = Some(map.next_value()?),
-                        "path" => path = Some(map.next_value()?),
-                        "mode" => mode = Some(map.next_value()?),
-                        "index" => index = Some(map.next_value()?),
-                        // security, compression, buffering, cache, open_file_cache, modules は route 直下で設定されるため、ここでは無視
-                        "security" | "compression" | "buffering" | "cache" | "open_file_cache"
-                        | "modules" => {
-                            let _: serde::de::IgnoredAny = map.next_value()?;
-                        }
-                        "redirect_url" => redirect_url = Some(map.next_value()?),
-                        "redirect_status" => redirect_status = Some(map.next_value()?),
-                        "preserve_path" => preserve_path = Some(map.next_value()?),
-                        "sni_name" => sni_name = Some(map.next_value()?),
-                        "use_h2c" | "h2c" => use_h2c = Some(map.next_value()?),
-                        _ => {
-                            let _: serde::de::IgnoredAny = map.next_value()?;
-                        }
-                    }
-                }
+    /// ルートレベルのWASMモジュール名のリスト（このルートに適用するWASMモジュール）
+    /// 注意: modules は route 直下で設定（action配下の設定は削除）
+    #[serde(default)]
+    pub modules: Option<Vec<String>>,
 
-                let backend_type = backend_type.unwrap_or_else(|| "File".to_string());
+    /// ルートレベルのリクエストヘッジング設定（F-137、Proxy/ProxyUpstream のみ）
+    #[serde(default)]
+    pub hedging: Option<HedgingConfig>,
 
-                match backend_type.as_str() {
-                    "Proxy" => {
-                        // upstream が指定されている場合はロードバランシング用
-                        if let Some(upstream_name) = upstream {
-                            let use_h2c = use_h2c.unwrap_or(false);
-                            Ok(BackendConfig::ProxyUpstream {
-                                upstream: upstream_name,
-                                use_h2c,
-                            })
-                        } else {
-                            let url = url.ok_or_else(|| {
-                                serde::de::Error::missing_field("url or upstream")
-                            })?;
-                            let use_h2c = use_h2c.unwrap_or(false);
-                            Ok(BackendConfig::Proxy {
-                                url,
-                                sni_name,
-                                use_h2c,
-                            })
-                        }
-                    }
-                    "Redirect" => {
-                        let redirect_url = redirect_url
-                            .ok_or_else(|| serde::de::Error::missing_field("redirect_url"))?;
-                        let redirect_status = redirect_status.unwrap_or(301);
-                        // ステータスコードの検証（301, 302, 303, 307, 308のみ許可）
-                        if !matches!(redirect_status, 301 | 302 | 303 | 307 | 308) {
-                            return Err(serde::de::Error::custom(format!(
-                                "invalid redirect_status: {}, expected 301, 302, 303, 307, or 308",
-                                redirect_status
-                            )));
-                        }
-                        let preserve_path = preserve_path.unwrap_or(false);
-                        Ok(BackendConfig::Redirect {
-                            redirect_url,
-                            redirect_status,
-                            preserve_path,
-                        })
-                    }
-                    _ => {
-                        let path = path.ok_or_else(|| serde::de::Error::missing_field("path"))?;
-                        let mode = mode.unwrap_or_else(|| "sendfile".to_string());
-                        Ok(BackendConfig::File { path, mode, index })
-                    }
-                }
-            }
-        }
-
-        deserializer.deserialize_map(BackendConfigVisitor)
-    }
-}
+    /// ルートレベルのレートリミット規則（F-146、すべての規則を評価する）
+    #[serde(default)]
+    pub rate_limits: Vec<RateLimitRuleConfig>,
 
-// ====================
-// ランタイムBackend
-// ====================
+    /// 外部レートリミットサービスによるグローバルレートリミット（F-147）
+    #[serde(default)]
+    pub global_rate_limit: Option<GlobalRateLimitConfig>,
 
-#[derive(Clone)]
-pub enum Backend {
-    /// Proxy バックエンド（ロードバランシング対応）
-    /// - Arc<UpstreamGroup>: アップストリームグループ（単一または複数バックエンド）
-    /// - Arc<SecurityConfig>: ルートごとのセキュリティ設定
-    /// - Arc<CompressionConfig>: 圧縮設定
-    /// - Arc<buffering::BufferingConfig>: バッファリング設定
-    /// - Arc<cache::CacheConfig>: キャッシュ設定
-    Proxy(
-        Arc<UpstreamGroup>,
-        Arc<SecurityConfig>,
-        Arc<CompressionConfig>,
-        Arc<buffering::BufferingConfig>,
-        Arc<cache::CacheConfig>,
-        /// WASMモジュール名のリスト（このバックエンドに適用するWASMモジュール）
-        Option<Arc<Vec<String>>>,
-    ),
-    /// MemoryFile バックエンド
-    /// - Arc<Vec<u8>>: ファイルコンテンツ
-    /// - Arc<str>: MIMEタイプ
-    /// - Arc<SecurityConfig>: ルートごとのセキュリティ設定
-    MemoryFile(
-        Arc<Vec<u8>>,
-        Arc<str>,
-        Arc<SecurityConfig>,
-        /// WASMモジュール名のリスト（このバックエンドに適用するWASMモジュール）
-        Option<Arc<Vec<String>>>,
-    ),
-    /// SendFile バックエンド
-    /// - Arc<PathBuf>: ベースパス
-    /// - bool: ディレクトリかどうか
-    /// - Option<Arc<str>>: インデックスファイル名（None = "index.html"）
-    /// - Arc<SecurityConfig>: ルートごとのセキュリティ設定
-    /// - Arc<cache::CacheConfig>: キャッシュ設定
-    /// - Option<Arc<cache::OpenFileCacheConfig>>: OpenFileCache設定（ルーティングごと）
-    SendFile(
-        Arc<PathBuf>,
-        bool,
-        Option<Arc<str>>,
-        Arc<SecurityConfig>,
-        Arc<cache::CacheConfig>,
-        Option<Arc<cache::OpenFileCacheConfig>>,
-        /// WASMモジュール名のリスト（このバックエンドに適用するWASMモジュール）
-        Option<Arc<Vec<String>>>,
-    ),
-    /// Redirect バックエンド
-    /// - Arc<str>: リダイレクト先URL
-    /// - u16: ステータスコード（301, 302, 307, 308）
-    /// - bool: 元のパスを保持するか
-    Redirect(
-        Arc<str>,
-        u16,
-        bool,
-        /// WASMモジュール名のリスト（このバックエンドに適用するWASMモジュール）
-        Option<Arc<Vec<String>>>,
-    ),
-}
+    /// 構築済みのレートリミット（設定読み込み時に [`Route::prepare`] で構築）
+    #[serde(skip)]
+    pub rate_limiter: Option<Arc<crate::rate_limit::RouteRateLimit>>,
 
-impl Backend {
-    /// このバックエンドのセキュリティ設定を取得
-    #[inline]
-    pub fn security(&self) -> &SecurityConfig {
-        // デフォルトのセキュリティ設定（Redirect用）
-        static DEFAULT_SECURITY: Lazy<SecurityConfig> = Lazy::new(SecurityConfig::default);
+    /// ルートレベルの JWT 認証（F-148）
+    #[serde(default)]
+    pub jwt: Option<JwtAuthConfig>,
 
-        match self {
-            Backend::Proxy(_, security, _, _, _, _) => security,
-            Backend::MemoryFile(_, _, security, _) => security,
-            Backend::SendFile(_, _, _, security, _, _, _) => security,
-            Backend::Redirect(_, _, _, _) => &DEFAULT_SECURITY,
-        }
-    }
+    /// 構築済みの JWT 認証（設定読み込み時に [`Route::prepare`] で構築）
+    #[serde(skip)]
+    pub jwt_auth: Option<Arc<crate::jwt_auth::JwtAuth>>,
 
-    /// このバックエンドに適用するWASMモジュール名のリストを取得
-    #[inline]
-    /// F-43: WASM モジュールリストを Arc 共有で取得する（リクエストごとの deep copy 排除）。
-    pub fn modules_arc(&self) -> Option<&Arc<Vec<String>>> {
-        match self {
-            Backend::Proxy(_, _, _, _, _, modules) => modules.as_ref(),
-            Backend::MemoryFile(_, _, _, modules) => modules.as_ref(),
-            Backend::SendFile(_, _, _, _, _, _, modules) => modules.as_ref(),
-            Backend::Redirect(_, _, _, modules) => modules.as_ref(),
-        }
-    }
+    /// ルートレベルの外部認可（F-149）
+    #[serde(default)]
+    pub ext_authz: Option<ExtAuthzConfig>,
 
-    pub fn modules(&self) -> Option<&[String]> {
-        match self {
-            Backend::Proxy(_, _, _, _, _, modules) => modules.as_deref().map(|v| v.as_slice()),
-            Backend::MemoryFile(_, _, _, modules) => modules.as_deref().map(|v| v.as_slice()),
-            Backend::SendFile(_, _, _, _, _, _, modules) => {
-                modules.as_deref().map(|v| v.as_slice())
-            }
-            Backend::Redirect(_, _, _, modules) => modules.as_deref().map(|v| v.as_slice()),
-        }
-    }
-}
+    /// 構築済みの外部認可（設定読み込み時に [`Route::prepare`] で構築）
+    #[serde(skip)]
+    pub ext_authorizer: Option<Arc<crate::ext_authz::ExtAuthz>>,
 
-#[derive(Clone)]
-pub struct ProxyTarget {
-    pub host: String,
-    pub port: u16,
-    pub use_tls: bool,
-    pub path_prefix: String,
-    /// SNI (Server Name Indication) に使用するホスト名
-    /// Noneの場合はhostを使用。IP直打ちの場合にドメイン名を指定可能
-    pub sni_name: Option<String>,
-    /// H2C (HTTP/2 over cleartext) を使用するかどうか
-    /// true の場合、非TLSバックエンドにHTTP/2で接続
-    /// HTTP/2 Upgrade 経由ではなく、Prior Knowledge モードを使用
-    pub use_h2c: bool,
-}
+    /// ルートレベルの OIDC ログイン（F-150）
+    #[serde(default)]
+    pub oidc: Option<OidcConfig>,
 
-impl ProxyTarget {
-    pub fn parse(url: &str) -> Option<Self> {
-        let (scheme, rest) = if let Some(rest) = url.strip_prefix("https://") {
-            (true, rest)
-        } else if let Some(rest) = url.strip_prefix("http://") {
-            (false, rest)
-        } else {
-            return None;
-        };
+    /// 構築済みの OIDC ログイン（設定読み込み時に [`Route::prepare`] で構築）
+    #[serde(skip)]
+    pub oidc_auth: Option<Arc<crate::oidc::Oidc>>,
 
-        let (host_port, path) = match rest.find('/') {
-            Some(idx) => (&rest[..idx], &rest[idx..]),
-            None => (rest, "/"),
-        };
+    /// ルートレベルの Basic 認証（F-151）
+    #[serde(default)]
+    pub basic_auth: Option<BasicAuthConfig>,
 
-        let (host, port) = match host_port.find(':') {
-            Some(idx) => {
-                let h = &host_port[..idx];
-                let p = host_port[idx + 1..].parse().ok()?;
-                (h.to_string(), p)
-            }
-            None => (host_port.to_string(), if scheme { 443 } else { 80 }),
-        };
+    /// ルートレベルの API キー認証（F-151）
+    #[serde(default)]
+    pub api_key: Option<ApiKeyAuthConfig>,
 
-        Some(ProxyTarget {
-            host,
-            port,
-            use_tls: scheme,
-            path_prefix: path.to_string(),
-            sni_name: None,
-            use_h2c: false, // デフォルトでは無効
-        })
-    }
+    /// 構築済みの Basic 認証・API キー認証（設定読み込み時に [`Route::prepare`] で構築）
+    #[serde(skip)]
+    pub credential_auth: Option<Arc<crate::credential_auth::CredentialAuth>>,
 
-    /// SNI名を設定したコピーを作成
-    pub fn with_sni_name(mut self, sni_name: Option<String>) -> Self {
-        self.sni_name = sni_name;
-        self
-    }
+    /// ルートレベルの WAF（F-154）
+    #[serde(default)]
+    pub waf: Option<WafConfig>,
 
-    /// H2C設定を変更したコピーを作成
-    pub fn with_h2c(mut self, use_h2c: bool) -> Self {
-        // H2Cは非TLSの場合のみ有効
-        if !self.use_tls {
-            self.use_h2c = use_h2c;
-        }
-        self
-    }
+    /// 構築済みの WAF（設定読み込み時に [`Route::prepare`] で構築）
+    #[serde(skip)]
+    pub waf_engine: Option<Arc<crate::waf::Waf>>,
 
-    /// TLS接続時に使用するSNI名を取得
-    #[inline]
-    pub fn sni(&self) -> &str {
-        self.sni_name.as_deref().unwrap_or(&self.host)
-    }
+    /// ルートレベルの CORS（F-155）
+    #[serde(default)]
+    pub cors: Option<CorsConfig>,
 
-    /// デフォルトポートかどうかを判定
-    #[inline]
-    pub fn is_default_port(&self) -> bool {
-        if self.use_tls {
-            self.port == 443
-        } else {
-            self.port == 80
-        }
-    }
+    /// 構築済みの CORS（設定読み込み時に [`Route::prepare`] で構築）
+    #[serde(skip)]
+    pub cors_policy: Option<Arc<crate::cors::Cors>>,
 }
 
-// ====================
-// ロードバランシング（Upstream Group）
-// ====================
-//
-// 複数のバックエンドサーバーへのリクエスト分散をサポートします。
-//
-// ## サポートするアルゴリズム
-// - RoundRobin: 順番に振り分け（デフォルト）
-// - LeastConnections: 接続数が最も少ないサーバーを選択
-// - IpHash: クライアントIPに基づいて一貫したサーバーを選択
-//
-// ## 設定例
-// ```toml
-// [upstreams."backend-pool"]
-// algorithm = "round_robin"
-// servers = ["http://localhost:8080", "http://localhost:8081"]
-//
-// [[route]]
-// conditions = { host = "example.com", path = "/api/*" }
-// type = "Proxy"
-// upstream = "backend-pool"
-// ```
-// ====================
-
-/// Consistent Hash 用のハッシュキー
-///
-/// シリアライズ形式:
-/// - `"ip"`              -> クライアント IP（デフォルト）
-/// - `"header:X-User-Id"` -> 指定ヘッダーの値
-/// - `"cookie:session_id"` -> 指定 Cookie の値
-#[derive(Clone, Debug, PartialEq, Eq, Default)]
-pub enum HashKey {
-    /// クライアント IP アドレス
-    #[default]
-    Ip,
-    /// 指定したリクエストヘッダーの値
-    Header(String),
-    /// 指定した Cookie の値
-    Cookie(String),
+impl Route {
+    /// 読み込み後の実行時状態を構築する（`index` は `[[route]]` の位置）
+    ///
+    /// `rate_limits` と `security.rate_limit_requests_per_min`、`global_rate_limit` を
+    /// レートリミッターにまとめる（F-146 / F-147）。`jwt` の鍵もここで読む（F-148）。
+    /// `oidc` の IdP のメタデータもここで取得する（F-150）。`basic_auth` / `api_key` の
+    /// 資格情報ファイルもここで読む（F-151）。`waf` の規則ファイルもここで読み、検証後に
+    /// 読めなくなっていればエラーにする（F-154、リロードは直前の設定のまま）。
+    /// `cors` のオリジンの正規表現もここでコンパイルする（F-155）。
+    pub fn prepare(
+        &mut self,
+        index: usize,
+        service: Option<&Arc<RateLimitServiceConfig>>,
+    ) -> io::Result<()> {
+        self.cors_policy = self.cors.as_ref().and_then(|cfg| {
+            crate::cors::Cors::new(cfg)
+                .map(Arc::new)
+                .map_err(|e| warn!("route[{}]: CORS disabled: {}", index, e))
+                .ok()
+        });
+        self.waf_engine = self
+            .waf
+            .as_ref()
+            .map(|cfg| crate::waf::Waf::new(format!("route[{}]", index), cfg).map(Arc::new))
+            .transpose()?;
+        if self.basic_auth.is_some() || self.api_key.is_some() {
+            self.credential_auth = Some(Arc::new(crate::credential_auth::CredentialAuth::new(
+                format!("route[{}]", index),
+                self.basic_auth.as_ref(),
+                self.api_key.as_ref(),
+            )));
+        }
+        self.oidc_auth = self
+            .oidc
+            .as_ref()
+            .map(|cfg| Arc::new(crate::oidc::Oidc::new(format!("route[{}]", index), cfg)));
+        self.ext_authorizer = self.ext_authz.as_ref().map(|cfg| {
+            Arc::new(crate::ext_authz::ExtAuthz::new(
+                format!("route[{}]", index),
+                cfg,
+            ))
+        });
+        self.jwt_auth = self.jwt.as_ref().map(|cfg| {
+            Arc::new(crate::jwt_auth::JwtAuth::new(cfg).unwrap_or_else(|e| {
+                // 検証後にファイルが消えた等。鍵を持たない認証としてすべて拒否する
+                warn!("route[{}]: JWT keys unavailable: {}", index, e);
+                crate::jwt_auth::JwtAuth::without_keys(cfg)
+            }))
+        });
+        let per_min = self
+            .security
+            .as_ref()
+            .map_or(0, |s| s.rate_limit_requests_per_min);
+        let global = self.global_rate_limit.as_ref().zip(service);
+        self.rate_limiter = crate::rate_limit::RouteRateLimit::build(
+            format!("route[{}]", index),
+            &self.rate_limits,
+            per_min,
+            global,
+        )
+        .map(Arc::new);
+        Ok(())
+    }
 }
 
-/// Cookie ヘッダ値から指定名の値を取り出す（アロケーションなし）。
-/// `name=value` を `;` 区切りで走査し、名前一致時に value スライスを返す。
-fn extract_cookie_value<'a>(cookie_header: &'a str, name: &str) -> Option<&'a str> {
-    for part in cookie_header.split(';') {
-        let part = part.trim();
-        if part.is_empty() {
-            continue;
-        }
-        if let Some((k, v)) = part.split_once('=') {
-            if k.trim().eq_ignore_ascii_case(name) {
-                return Some(v.trim());
-            }
-        }
-    }
-    None
+#[derive(Deserialize)]
+struct Config {
+    server: ServerConfigSection,
+    tls: TlsConfigSection,
+    #[serde(default)]
+    performance: PerformanceConfigSection,
+    /// グローバルセキュリティ設定（権限降格など）
+    #[serde(default)]
+    security: GlobalSecurityConfig,
+    /// ログ設定（非同期ログの最適化）
+    #[serde(default)]
+    logging: LoggingConfigSection,
+    /// Prometheusメトリクス設定
+    #[serde(default)]
+    prometheus: PrometheusConfig,
+    /// 管理 API 設定（F-20: キャッシュ Purge 等）
+    #[cfg(feature = "admin")]
+    #[serde(default)]
+    admin: AdminConfig,
+    /// 構造化アクセスログ設定（F-21）
+    #[cfg(feature = "access-log")]
+    #[serde(default)]
+    access_log: crate::access_log::AccessLogConfig,
+    /// OpenTelemetry 設定（F-10）
+    #[serde(default)]
+    opentelemetry: OpenTelemetryConfig,
+    /// バッファプール設定（メモリ最適化）
+    #[serde(default)]
+    buffer_pool: BufferPoolConfig,
+    /// HTTP/2 設定セクション
+    #[serde(default)]
+    #[cfg_attr(not(feature = "http2"), allow(dead_code))]
+    http2: Http2ConfigSection,
+    /// HTTP/3 設定セクション
+    #[serde(default)]
+    #[cfg_attr(not(feature = "http3"), allow(dead_code))]
+    http3: Http3ConfigSection,
+    /// Upstream グループ定義（ロードバランシング用）
+    #[serde(default)]
+    upstreams: Option<HashMap<String, UpstreamConfig>>,
+    /// 統合ルーティング（唯一のルーティング方式）
+    /// 配列の順序で評価（first-match方式）
+    #[serde(default)]
+    route: Option<Vec<Route>>,
+    /// WASM拡張設定（feature flagで条件付きコンパイル）
+    #[cfg(feature = "wasm")]
+    #[serde(default)]
+    wasm: Option<crate::wasm::WasmConfig>,
+    /// L4 (TCP/UDP) ストリームプロキシ設定（F-18）
+    #[cfg(feature = "l4-proxy")]
+    #[serde(default)]
+    l4: Option<Vec<L4ListenerConfig>>,
+    /// 名前付きのソケットオプション（F-142）
+    #[serde(default)]
+    socket_profiles: HashMap<String, SocketProfileConfig>,
+    /// 外部レートリミットサービス（F-147）
+    #[serde(default)]
+    rate_limit_service: Option<RateLimitServiceConfig>,
 }
 
-impl HashKey {
-    /// 文字列からパース（`"ip"`, `"header:X-Foo"`, `"cookie:session"`）
-    pub fn parse(s: &str) -> Result<Self, String> {
-        let s = s.trim();
-        if s.eq_ignore_ascii_case("ip") {
-            return Ok(HashKey::Ip);
-        }
-        if let Some(rest) = s.strip_prefix("header:") {
-            return Ok(HashKey::Header(rest.trim().to_string()));
-        }
-        if let Some(rest) = s.strip_prefix("cookie:") {
-            return Ok(HashKey::Cookie(rest.trim().to_string()));
-        }
-        Err(format!(
-            "invalid hash_key: '{}', expected 'ip', 'header:NAME', or 'cookie:NAME'",
-            s
-        ))
-    }
+// ====================
+// L4 (TCP/UDP) ストリームプロキシ設定（F-18）
+// ====================
+
+/// L4 リスナーのトランスポートプロトコル（F-124）
+///
+/// TOML では `protocol = "tcp"` / `"udp"`。省略時は後方互換のため `Tcp`。
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum L4Protocol {
+    /// TCP（バイダイレクショナルストリーム転送、既存動作）
+    #[default]
+    Tcp,
+    /// UDP（セッションテーブル方式のデータグラム転送、F-124）
+    Udp,
 }
 
-impl<'de> serde::Deserialize<'de> for HashKey {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        HashKey::parse(&s).map_err(serde::de::Error::custom)
-    }
+/// L4 TLS モード
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum L4TlsMode {
+    /// TLS なし（プレーンな TCP）
+    #[default]
+    None,
+    /// TLS パススルー（TLS を復号せず upstream にそのまま転送）
+    Passthrough,
+    /// TLS ターミネーション（veil で TLS を終端し、upstream にはプレーン TCP で接続）
+    Terminate,
 }
 
-/// ロードバランシングアルゴリズム
-#[derive(Clone, Debug, PartialEq, Default)]
-pub enum LoadBalanceAlgorithm {
-    /// ラウンドロビン（順番に振り分け）
+/// L4 ロードバランシングアルゴリズム
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum L4LbAlgorithm {
+    /// ラウンドロビン（デフォルト）
     #[default]
     RoundRobin,
-    /// Least Connections（接続数が最も少ないサーバー）
-    LeastConnections,
-    /// IP Hash（クライアントIPに基づく一貫したルーティング）
-    IpHash,
-    /// Weighted Round Robin（重み付きラウンドロビン）
-    Weighted,
-    /// Consistent Hash（仮想ノードリングによる一貫したルーティング）
-    ConsistentHash {
-        /// ハッシュキーの種類
-        hash_key: HashKey,
-    },
+    /// 最小接続数
+    LeastConn,
 }
 
-impl<'de> serde::Deserialize<'de> for LoadBalanceAlgorithm {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        match s.to_lowercase().as_str() {
-            "round_robin" | "roundrobin" => Ok(LoadBalanceAlgorithm::RoundRobin),
-            "least_conn" | "least_connections" | "leastconn" => Ok(LoadBalanceAlgorithm::LeastConnections),
-            "ip_hash" | "iphash" => Ok(LoadBalanceAlgorithm::IpHash),
-            "weighted" | "weighted_round_robin" | "wrr" => Ok(LoadBalanceAlgorithm::Weighted),
-            // hash_key は UpstreamConfig の別フィールドで上書きされる（デフォルト Ip）
-            "consistent_hash" | "consistenthash" => Ok(LoadBalanceAlgorithm::ConsistentHash {
-                hash_key: HashKey::Ip,
-            }),
-            other => Err(serde::de::Error::custom(format!(
-                "unknown load balance algorithm: '{}', expected 'round_robin', 'least_conn', 'ip_hash', 'weighted', or 'consistent_hash'",
-                other
-            ))),
-        }
-    }
+/// L4 upstream バックエンド
+#[derive(Deserialize, Clone, Debug)]
+pub struct L4UpstreamEntry {
+    /// バックエンドアドレス（"host:port" 形式）
+    pub addr: String,
+    /// 重み（weighted round-robin 用、デフォルト: 1）
+    #[serde(default = "default_l4_weight")]
+    pub weight: u32,
 }
 
-/// algorithm と hash_key 設定から実際に使うアルゴリズムを決定する。
-///
-/// algorithm が ConsistentHash の場合、UpstreamConfig 側の hash_key
-/// フィールドがあればそれで上書きする。
-pub fn resolve_algorithm(
-    algorithm: &LoadBalanceAlgorithm,
-    hash_key: &Option<HashKey>,
-) -> LoadBalanceAlgorithm {
-    match algorithm {
-        LoadBalanceAlgorithm::ConsistentHash { hash_key: inner } => {
-            let key = hash_key.clone().unwrap_or_else(|| inner.clone());
-            LoadBalanceAlgorithm::ConsistentHash { hash_key: key }
-        }
-        other => other.clone(),
-    }
+fn default_l4_weight() -> u32 {
+    1
 }
 
-/// Upstream サーバーの状態
-#[derive(Clone)]
-pub struct UpstreamServer {
-    /// バックエンドターゲット
-    pub target: ProxyTarget,
-    /// 現在のアクティブ接続数（Least Connections用）
-    pub active_connections: Arc<AtomicUsize>,
-    /// サーバーが利用可能かどうか（ヘルスチェック用）
-    pub healthy: Arc<AtomicBool>,
-    /// 連続成功回数（健康チェック用）
-    pub consecutive_successes: Arc<AtomicUsize>,
-    /// 連続失敗回数（健康チェック用）
-    pub consecutive_failures: Arc<AtomicUsize>,
-    /// サーキットブレーカー（F-06、有効時のみ Some）
-    pub circuit_breaker: Option<crate::resilience::CircuitBreaker>,
-    /// 異常検知用エラー率ウィンドウ（F-06）
-    pub error_rate_window: Arc<std::sync::Mutex<crate::resilience::SlidingWindow>>,
-    /// EWMA レイテンシ（ミリ秒、F-06）
-    pub avg_latency_ms: Arc<AtomicU64>,
-    /// 排除期限（F-06、Some の間は select 対象外）
-    pub ejected_until: Arc<std::sync::Mutex<Option<std::time::Instant>>>,
+/// L4 リスナー設定（TOML: [[l4]]）
+#[derive(Deserialize, Clone, Debug)]
+pub struct L4ListenerConfig {
+    /// リスナー名（ログ・メトリクス識別用）
+    pub name: String,
+    /// リッスンアドレス（例: "0.0.0.0:3306"）
+    pub listen: String,
+    /// upstream バックエンド一覧
+    pub upstreams: Vec<L4UpstreamEntry>,
+    /// トランスポートプロトコル（tcp / udp）。省略時は後方互換で tcp（F-124）。
+    #[serde(default)]
+    pub protocol: L4Protocol,
+    /// ロードバランシングアルゴリズム
+    #[serde(default)]
+    pub lb: L4LbAlgorithm,
+    /// TLS モード（none / passthrough / terminate）
+    #[serde(default)]
+    pub tls: L4TlsMode,
+    /// 最大同時接続数（0 = 無制限）
+    #[serde(default)]
+    pub max_connections: usize,
+    /// ヘルスチェック設定（省略可）
+    #[serde(default)]
+    pub health_check: Option<HealthCheckConfig>,
+    /// バックエンド接続タイムアウト（秒）
+    #[serde(default = "default_l4_connect_timeout")]
+    pub connect_timeout_secs: u64,
+    /// アイドルタイムアウト（秒）: この時間データ転送がなければ接続を切断（デフォルト: 600）
+    #[serde(default = "default_l4_idle_timeout")]
+    pub idle_timeout_secs: u64,
+    /// クライアントの IP を送信元として upstream へ接続する（F-141、TCP のみ）
+    #[serde(default)]
+    pub transparent: bool,
+    /// upstream 接続の送信元 IP（F-141、TCP のみ。複数はラウンドロビン）
+    #[serde(default, deserialize_with = "deserialize_source_addresses")]
+    pub source_address: Vec<IpAddr>,
+    /// リスナーに適用するソケットプロファイル名（F-142、TCP のみ）
+    #[serde(default)]
+    pub socket_profile: Option<String>,
+    /// upstream 接続に適用するソケットプロファイル名（F-142、TCP のみ）
+    #[serde(default)]
+    pub upstream_socket_profile: Option<String>,
+    /// 解決済みのリスナー用プロファイル（設定ファイルからは読まない、F-142）
+    #[serde(skip)]
+    pub listener_socket: Option<Arc<SocketOptions>>,
+    /// 解決済みの upstream 接続用プロファイル（設定ファイルからは読まない、F-142）
+    #[serde(skip)]
+    pub upstream_socket: Option<Arc<SocketOptions>>,
 }
 
-impl UpstreamServer {
-    pub fn new(target: ProxyTarget) -> Self {
-        Self {
-            target,
-            active_connections: Arc::new(AtomicUsize::new(0)),
-            healthy: Arc::new(AtomicBool::new(true)),
-            consecutive_successes: Arc::new(AtomicUsize::new(0)),
-            consecutive_failures: Arc::new(AtomicUsize::new(0)),
-            circuit_breaker: None,
-            error_rate_window: Arc::new(std::sync::Mutex::new(
-                crate::resilience::SlidingWindow::new(std::time::Duration::from_secs(10)),
-            )),
-            avg_latency_ms: Arc::new(AtomicU64::new(0)),
-            ejected_until: Arc::new(std::sync::Mutex::new(None)),
-        }
-    }
+fn default_l4_connect_timeout() -> u64 {
+    10
+}
 
-    /// サーキットブレーカーを設定したコピーを返す
-    pub fn with_circuit_breaker(mut self, cb: Option<crate::resilience::CircuitBreaker>) -> Self {
-        self.circuit_breaker = cb;
-        self
-    }
+fn default_l4_idle_timeout() -> u64 {
+    600
+}
 
-    /// このサーバーが排除中かどうか（排除期限を過ぎていれば自動復帰）
-    pub fn is_ejected(&self) -> bool {
-        let mut guard = match self.ejected_until.lock() {
-            Ok(g) => g,
-            Err(p) => p.into_inner(),
-        };
-        match *guard {
-            Some(until) => {
-                if std::time::Instant::now() >= until {
-                    *guard = None;
-                    false
-                } else {
-                    true
-                }
-            }
-            None => false,
-        }
-    }
+// ====================
+// HTTP/2 設定セクション (RFC 7540)
+// ====================
 
-    /// 指定時間だけサーバーを排除する
-    pub fn eject_for(&self, dur: std::time::Duration) {
-        if let Ok(mut guard) = self.ejected_until.lock() {
-            *guard = Some(std::time::Instant::now() + dur);
-        }
-    }
+/// HTTP/2 詳細設定
+///
+/// HTTP/2 プロトコルのパラメータを設定します。
+/// 有効化は `server.http2_enabled` で行います。
+#[derive(Deserialize, Clone)]
+pub struct Http2ConfigSection {
+    /// SETTINGS_HEADER_TABLE_SIZE (HPACK動的テーブルサイズ)
+    /// デフォルト: 4096 (4KB)
+    /// 高パフォーマンス: 65536 (64KB)
+    #[serde(default = "default_h2_header_table_size")]
+    pub header_table_size: u32,
 
-    /// リクエスト結果を記録し、必要なら排除判定を行う（パッシブ Outlier Detection）
-    ///
-    /// `outlier` が None または無効の場合は EWMA レイテンシのみ更新する。
-    pub fn record_outcome(&self, success: bool, latency_ms: u64, outlier: Option<&OutlierConfig>) {
-        // EWMA レイテンシ更新（係数 0.2）
-        let prev = self.avg_latency_ms.load(Ordering::Relaxed);
-        let next = if prev == 0 {
-            latency_ms
-        } else {
-            ((prev as f64) * 0.8 + (latency_ms as f64) * 0.2) as u64
-        };
-        self.avg_latency_ms.store(next, Ordering::Relaxed);
+    /// SETTINGS_MAX_CONCURRENT_STREAMS (同時ストリーム数)
+    /// デフォルト: 100
+    #[serde(default = "default_h2_max_concurrent_streams")]
+    pub max_concurrent_streams: u32,
 
-        // サーキットブレーカーへ反映
-        if let Some(cb) = &self.circuit_breaker {
-            if success {
-                cb.record_success();
-            } else {
-                cb.record_failure();
-            }
-        }
+    /// SETTINGS_INITIAL_WINDOW_SIZE (ストリームウィンドウサイズ)
+    /// デフォルト: 65535 (64KB - 1)
+    #[serde(default = "default_h2_initial_window_size")]
+    pub initial_window_size: u32,
 
-        // Outlier Detection
-        if let Some(cfg) = outlier {
-            if !cfg.enabled {
-                return;
-            }
-            let (total, errors) = {
-                let mut w = match self.error_rate_window.lock() {
-                    Ok(g) => g,
-                    Err(p) => p.into_inner(),
-                };
-                w.record(success);
-                (w.total(), w.failures())
-            };
-            // 一定サンプル数を超えたらエラー率を評価
-            if total >= 5 {
-                let rate = errors as f64 / total as f64;
-                if rate >= cfg.error_rate_threshold {
-                    self.eject_for(std::time::Duration::from_secs(cfg.base_ejection_time_secs));
-                }
-            }
-        }
-    }
+    /// SETTINGS_MAX_FRAME_SIZE (最大フレームサイズ)
+    /// デフォルト: 65536 (64KB)。これは **受信側の上限として相手へ広告する値** であり、
+    /// 送信フレームは相手の SETTINGS_MAX_FRAME_SIZE（既定 16384）に従って分割される。
+    #[serde(default = "default_h2_max_frame_size")]
+    pub max_frame_size: u32,
 
-    /// 接続カウンターを増加
-    pub fn acquire(&self) {
-        self.active_connections.fetch_add(1, Ordering::Relaxed);
-    }
+    /// SETTINGS_MAX_HEADER_LIST_SIZE (最大ヘッダーリストサイズ)
+    /// デフォルト: 16384 (16KB)
+    #[serde(default = "default_h2_max_header_list_size")]
+    pub max_header_list_size: u32,
 
-    /// 接続カウンターを減少
-    pub fn release(&self) {
-        self.active_connections.fetch_sub(1, Ordering::Relaxed);
-    }
+    /// コネクションウィンドウサイズ（コネクション全体のフロー制御）
+    /// デフォルト: 65535 (64KB - 1)
+    #[serde(default = "default_h2_connection_window_size")]
+    pub connection_window_size: u32,
 
-    /// 現在の接続数を取得
-    pub fn connections(&self) -> usize {
-        self.active_connections.load(Ordering::Relaxed)
-    }
+    // ====================
+    // DoS 対策設定
+    // ====================
+    /// RST_STREAM レート制限 (1秒あたりの最大数)
+    /// Rapid Reset 対策 (CVE-2023-44487)
+    /// デフォルト: 100
+    #[serde(default = "default_h2_max_rst_stream_per_second")]
+    pub max_rst_stream_per_second: u32,
 
-    /// サーバーが健全かどうか
-    pub fn is_healthy(&self) -> bool {
-        self.healthy.load(Ordering::Relaxed)
-    }
+    /// 制御フレームレート制限 (1秒あたりの最大数)
+    /// PING/SETTINGS フラッド対策
+    /// デフォルト: 500
+    #[serde(default = "default_h2_max_control_frames_per_second")]
+    pub max_control_frames_per_second: u32,
 
-    /// 健康チェック成功を記録
-    pub fn record_success(&self, healthy_threshold: u32) {
-        self.consecutive_failures.store(0, Ordering::Relaxed);
-        let successes = self.consecutive_successes.fetch_add(1, Ordering::Relaxed) + 1;
+    /// CONTINUATION フレーム制限 (ヘッダーブロックあたりの最大数)
+    /// CONTINUATION Flood 対策 (CVE-2024-24786)
+    /// デフォルト: 10
+    #[serde(default = "default_h2_max_continuation_frames")]
+    pub max_continuation_frames: u32,
 
-        // 閾値に達したら healthy に設定
-        if successes >= healthy_threshold as usize && !self.is_healthy() {
-            self.healthy.store(true, Ordering::SeqCst);
-            info!(
-                "Upstream {}:{} is now healthy",
-                self.target.host, self.target.port
-            );
-        }
-    }
+    /// 最大ヘッダーブロックサイズ (bytes)
+    /// HPACK Bomb 対策
+    /// デフォルト: 65536 (64KB)
+    #[serde(default = "default_h2_max_header_block_size")]
+    pub max_header_block_size: usize,
 
-    /// 健康チェック失敗を記録
-    pub fn record_failure(&self, unhealthy_threshold: u32) {
-        self.consecutive_successes.store(0, Ordering::Relaxed);
-        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+    /// ストリームアイドルタイムアウト (秒)
+    /// Slow Loris 対策
+    /// デフォルト: 60
+    #[serde(default = "default_h2_stream_idle_timeout_secs")]
+    pub stream_idle_timeout_secs: u64,
+}
 
-        // 閾値に達したら unhealthy に設定
-        if failures >= unhealthy_threshold as usize && self.is_healthy() {
-            self.healthy.store(false, Ordering::SeqCst);
-            warn!(
-                "Upstream {}:{} is now unhealthy",
-                self.target.host, self.target.port
-            );
-        }
-    }
+// HTTP/2 設定のデフォルト値（high_performance と同等）
+// HPACK動的テーブルサイズを大きくすることで、ヘッダー圧縮効率が向上
+fn default_h2_header_table_size() -> u32 {
+    65536
+} // 64KB (より多くのヘッダーをキャッシュ)
+fn default_h2_max_concurrent_streams() -> u32 {
+    256
+} // より多くの同時ストリーム
+fn default_h2_initial_window_size() -> u32 {
+    1048576
+} // 1MB (より大きなウィンドウ)
+fn default_h2_max_frame_size() -> u32 {
+    65536
+} // 64KB (より大きなフレーム)
+fn default_h2_max_header_list_size() -> u32 {
+    65536
+} // 64KB
+fn default_h2_connection_window_size() -> u32 {
+    1048576
+} // 1MB
 
-    /// Get the host address
-    pub fn host(&self) -> &str {
-        &self.target.host
-    }
+// DoS 対策のデフォルト値
+fn default_h2_max_rst_stream_per_second() -> u32 {
+    100
+}
+fn default_h2_max_control_frames_per_second() -> u32 {
+    500
+}
+fn default_h2_max_continuation_frames() -> u32 {
+    10
+}
+fn default_h2_max_header_block_size() -> usize {
+    65536
+}
+fn default_h2_stream_idle_timeout_secs() -> u64 {
+    60
+}
 
-    /// Get the port number
-    pub fn port(&self) -> u16 {
-        self.target.port
+impl Default for Http2ConfigSection {
+    fn default() -> Self {
+        Self {
+            header_table_size: default_h2_header_table_size(),
+            max_concurrent_streams: default_h2_max_concurrent_streams(),
+            initial_window_size: default_h2_initial_window_size(),
+            max_frame_size: default_h2_max_frame_size(),
+            max_header_list_size: default_h2_max_header_list_size(),
+            connection_window_size: default_h2_connection_window_size(),
+            // DoS 対策
+            max_rst_stream_per_second: default_h2_max_rst_stream_per_second(),
+            max_control_frames_per_second: default_h2_max_control_frames_per_second(),
+            max_continuation_frames: default_h2_max_continuation_frames(),
+            max_header_block_size: default_h2_max_header_block_size(),
+            stream_idle_timeout_secs: default_h2_stream_idle_timeout_secs(),
+        }
     }
+}
 
-    /// Check if TLS is enabled
-    pub fn use_tls(&self) -> bool {
-        self.target.use_tls
+impl Http2ConfigSection {
+    /// HTTP/2 設定を Http2Settings に変換
+    #[cfg(feature = "http2")]
+    pub fn to_http2_settings(&self) -> http2::Http2Settings {
+        http2::Http2Settings {
+            header_table_size: self.header_table_size,
+            max_concurrent_streams: self.max_concurrent_streams,
+            initial_window_size: self.initial_window_size,
+            max_frame_size: self.max_frame_size,
+            max_header_list_size: self.max_header_list_size,
+            enable_push: false, // サーバーではpush無効
+            connection_window_size: self.connection_window_size,
+            // DoS 対策
+            max_rst_stream_per_second: self.max_rst_stream_per_second,
+            max_control_frames_per_second: self.max_control_frames_per_second,
+            max_continuation_frames: self.max_continuation_frames,
+            max_header_block_size: self.max_header_block_size,
+            stream_idle_timeout_secs: self.stream_idle_timeout_secs,
+        }
     }
 }
 
-/// Consistent Hash の仮想ノード数（サーバーあたり）
-const CONSISTENT_HASH_VNODES: usize = 150;
-/// Consistent Hash 用のシード（固定）
-const CONSISTENT_HASH_SEED: u64 = 0x9E3779B97F4A7C15;
+// ====================
+// HTTP/3 設定セクション (RFC 9114, QUIC RFC 9000)
+// ====================
 
-/// Upstream グループ（複数バックエンドのロードバランシング）
-#[derive(Clone)]
-pub struct UpstreamGroup {
-    /// グループ名（ログ出力用）
-    pub name: String,
-    /// バックエンドサーバーリスト
-    pub servers: Vec<UpstreamServer>,
-    /// ロードバランシングアルゴリズム
-    pub algorithm: LoadBalanceAlgorithm,
-    /// ラウンドロビン用カウンター
-    pub rr_counter: Arc<AtomicUsize>,
-    /// 健康チェック設定（オプション）
-    pub health_check: Option<HealthCheckConfig>,
-    /// TLS証明書検証を無効化（自己署名証明書を許可）
-    pub tls_insecure: bool,
-    /// H2C (HTTP/2 over cleartext) を強制するかどうか
-    pub use_h2c: bool,
-    /// Weighted Round Robin 用の累積重みオフセット（構築時計算、O(1) 選択用）
-    /// servers と同じ順序。weighted_offsets[i] は servers[0..=i] の重みの累積和。
-    pub weighted_offsets: Vec<u32>,
-    /// 全サーバーの重み合計（0 の場合は Weighted を使わない）
-    pub total_weight: u32,
-    /// Consistent Hash の仮想ノードリング（(hash, server_idx) を hash 昇順でソート）
-    pub consistent_ring: Vec<(u64, usize)>,
-    /// 異常検知設定（select 時に排除中サーバーを除外するために保持）
-    pub outlier_detection: OutlierConfig,
-}
+/// HTTP/3 専用圧縮設定
+///
+/// HTTP/3接続時に使用する圧縮パラメータを設定します。
+/// 未設定のフィールドはパスごとの設定または全体設定を継承します。
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Http3CompressionConfig {
+    /// 圧縮方式の優先順位
+    /// サポート: "zstd", "br" (Brotli), "gzip", "deflate"
+    /// 未設定時はパスごとの設定を使用
+    pub preferred_encodings: Option<Vec<String>>,
 
-impl UpstreamGroup {
-    /// 新しい Upstream グループを作成
-    pub fn new(
-        name: String,
-        entries: Vec<UpstreamServerEntry>,
-        algorithm: LoadBalanceAlgorithm,
-        health_check: Option<HealthCheckConfig>,
-        tls_insecure: bool,
-    ) -> Option<Self> {
-        // 有効なエントリのみを (entry, server) として残す
-        let pairs: Vec<(UpstreamServerEntry, UpstreamServer)> = entries
-            .iter()
-            .filter_map(|entry| {
-                ProxyTarget::parse(&entry.url)
-                    .map(|target| target.with_sni_name(entry.sni_name.clone()))
-                    .map(|target| target.with_h2c(entry.use_h2c))
-                    .map(|target| (entry.clone(), UpstreamServer::new(target)))
-            })
-            .collect();
+    /// Gzip圧縮レベル (1-9)
+    /// 未設定時はパスごとの設定を使用
+    pub gzip_level: Option<u32>,
 
-        if pairs.is_empty() {
-            return None;
-        }
+    /// Brotli圧縮レベル (0-11)
+    /// 未設定時はパスごとの設定を使用
+    pub brotli_level: Option<u32>,
 
-        let servers: Vec<UpstreamServer> = pairs.iter().map(|(_, s)| s.clone()).collect();
+    /// Zstd圧縮レベル (1-22)
+    /// 未設定時はパスごとの設定を使用
+    pub zstd_level: Option<i32>,
 
-        // Weighted Round Robin 用の累積オフセットを構築
-        let mut weighted_offsets = Vec::with_capacity(pairs.len());
-        let mut acc: u32 = 0;
-        for (entry, _) in &pairs {
-            acc = acc.saturating_add(entry.weight.max(1));
-            weighted_offsets.push(acc);
-        }
-        let total_weight = acc;
+    /// 最小圧縮サイズ（バイト）
+    /// 未設定時はパスごとの設定を使用
+    pub min_size: Option<usize>,
 
-        // Consistent Hash 用の仮想ノードリングを構築
-        let consistent_ring = Self::build_ring(&pairs);
+    /// 圧縮対象のMIMEタイプ（プレフィックスマッチ）
+    /// 未設定時はパスごとの設定を使用
+    pub compressible_types: Option<Vec<String>>,
 
-        Some(Self {
-            name,
-            servers,
-            algorithm,
-            rr_counter: Arc::new(AtomicUsize::new(0)),
-            health_check,
-            tls_insecure,
-            use_h2c: false, // デフォルトでは各サーバーの設定に従う
-            weighted_offsets,
-            total_weight,
-            consistent_ring,
-            outlier_detection: OutlierConfig::default(),
-        })
-    }
+    /// 圧縮をスキップするMIMEタイプ（プレフィックスマッチ）
+    /// 未設定時はパスごとの設定を使用
+    pub skip_types: Option<Vec<String>>,
+}
 
-    /// 仮想ノードリングを構築する（サーバーごとに CONSISTENT_HASH_VNODES 個の vnode）
-    fn build_ring(pairs: &[(UpstreamServerEntry, UpstreamServer)]) -> Vec<(u64, usize)> {
-        use xxhash_rust::xxh3::xxh3_64_with_seed;
-        let mut ring: Vec<(u64, usize)> = Vec::with_capacity(pairs.len() * CONSISTENT_HASH_VNODES);
-        for (idx, (_, server)) in pairs.iter().enumerate() {
-            // サーバー識別子（host:port）を基に vnode を生成
-            let id = format!("{}:{}", server.target.host, server.target.port);
-            for vnode in 0..CONSISTENT_HASH_VNODES {
-                let key = format!("{}#{}", id, vnode);
-                let h = xxh3_64_with_seed(key.as_bytes(), CONSISTENT_HASH_SEED);
-                ring.push((h, idx));
+impl Http3CompressionConfig {
+    /// 設定の妥当性を検証
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(level) = self.gzip_level {
+            if !(1..=9).contains(&level) {
+                return Err(format!(
+                    "http3.compression.gzip_level: {} (must be 1-9)",
+                    level
+                ));
             }
         }
-        ring.sort_by_key(|(h, _)| *h);
-        ring
-    }
-
-    /// サーキットブレーカー・異常検知を適用したグループを返す（設定読み込み時に使用）
-    pub fn with_resilience(
-        mut self,
-        cb_config: &CircuitBreakerConfig,
-        outlier: &OutlierConfig,
-    ) -> Self {
-        if cb_config.enabled {
-            for server in &mut self.servers {
-                server.circuit_breaker =
-                    Some(crate::resilience::CircuitBreaker::new(cb_config.clone()));
+        if let Some(level) = self.brotli_level {
+            if level > 11 {
+                return Err(format!(
+                    "http3.compression.brotli_level: {} (must be 0-11)",
+                    level
+                ));
             }
         }
-        // error_rate_window の長さを interval に合わせて再構築
-        if outlier.enabled {
-            for server in &mut self.servers {
-                server.error_rate_window = Arc::new(std::sync::Mutex::new(
-                    crate::resilience::SlidingWindow::new(std::time::Duration::from_secs(
-                        outlier.interval_secs,
-                    )),
+        if let Some(level) = self.zstd_level {
+            if !(1..=22).contains(&level) {
+                return Err(format!(
+                    "http3.compression.zstd_level: {} (must be 1-22)",
+                    level
                 ));
             }
         }
-        self.outlier_detection = outlier.clone();
-        self
-    }
-
-    /// 単一サーバーからグループを作成
-    pub fn single(target: ProxyTarget) -> Self {
-        let server = UpstreamServer::new(target);
-        Self {
-            name: String::new(),
-            servers: vec![server],
-            algorithm: LoadBalanceAlgorithm::RoundRobin,
-            rr_counter: Arc::new(AtomicUsize::new(0)),
-            health_check: None,  // 単一サーバーでは健康チェックなし
-            tls_insecure: false, // 単一サーバーではデフォルトで証明書検証を有効
-            use_h2c: false,
-            weighted_offsets: vec![1],
-            total_weight: 1,
-            consistent_ring: Vec::new(),
-            outlier_detection: OutlierConfig::default(),
+        if let Some(ref encodings) = self.preferred_encodings {
+            for enc in encodings {
+                if !["zstd", "br", "gzip", "deflate"].contains(&enc.as_str()) {
+                    return Err(format!(
+                        "http3.compression.preferred_encodings: unknown encoding '{}'",
+                        enc
+                    ));
+                }
+            }
         }
+        Ok(())
     }
+}
 
-    /// H2C設定を変更したコピーを作成
-    pub fn with_h2c(&self, use_h2c: bool) -> Self {
-        let mut new_group = self.clone();
-        new_group.use_h2c = use_h2c;
-        new_group
-    }
+/// HTTP/3 詳細設定
+///
+/// HTTP/3 (QUIC) プロトコルのパラメータを設定します。
+/// 有効化は `server.http3_enabled` で行います。
+#[derive(Deserialize, Clone)]
+pub struct Http3ConfigSection {
+    /// HTTP/3リッスンアドレス（UDP）
+    /// 未指定の場合は server.listen と同じアドレスを使用
+    #[serde(default)]
+    pub listen: Option<String>,
 
-    /// 選択候補となるサーバーを抽出（healthy かつ排除されていないもの）
-    ///
-    /// サーキットブレーカーが Open のサーバーも除外する。
-    fn candidates(&self) -> Vec<(usize, &UpstreamServer)> {
-        let avail: Vec<(usize, &UpstreamServer)> = self
-            .servers
-            .iter()
-            .enumerate()
-            .filter(|(_, s)| s.is_healthy() && !s.is_ejected())
-            .filter(|(_, s)| match &s.circuit_breaker {
-                Some(cb) => cb.allow_request(),
-                None => true,
-            })
-            .collect();
-        if !avail.is_empty() {
-            return avail;
-        }
-        // 全て利用不可なら healthy なものへフォールバック（全滅回避）
-        self.servers
-            .iter()
-            .enumerate()
-            .filter(|(_, s)| s.is_healthy())
-            .collect()
-    }
+    /// 最大アイドルタイムアウト（ミリ秒）
+    /// デフォルト: 30000 (30秒)
+    #[serde(default = "default_h3_max_idle_timeout")]
+    pub max_idle_timeout: u64,
 
-    /// 次のバックエンドサーバーを選択
-    ///
-    /// # Arguments
-    /// * `client_ip` - クライアントIPアドレス（IpHash / ConsistentHash 用）
-    ///
-    /// # Returns
-    /// 選択されたサーバーへの参照（健全なサーバーがない場合は None）
-    pub fn select(&self, client_ip: &str) -> Option<&UpstreamServer> {
-        self.select_with_key(client_ip, None, None)
-    }
+    /// 最大UDPペイロードサイズ
+    /// デフォルト: 1350 (MTU考慮)
+    #[serde(default = "default_h3_max_udp_payload_size")]
+    pub max_udp_payload_size: u64,
 
-    /// リクエストヘッダから Consistent Hash キーを解決してサーバーを選択する。
-    ///
-    /// - `ConsistentHash { Header }` / `Cookie` のときだけ `get_header` を呼ぶ
-    ///   （それ以外のアルゴリズムではヘッダ走査ゼロ — ホットパス最適化）。
-    /// - 値が取れない場合は `client_ip` にフォールバックする。
-    ///
-    /// `get_header` はヘッダ名（小文字比較用バイト列）を受け取り、値のバイト列を返す。
-    /// Cookie 名は `HashKey::Cookie` の名前で `cookie` ヘッダをパースする。
-    pub fn select_with_header_fn<'a, F>(
-        &'a self,
-        client_ip: &str,
-        mut get_header: F,
-    ) -> Option<&'a UpstreamServer>
-    where
-        F: FnMut(&[u8]) -> Option<&'a [u8]>,
-    {
-        match &self.algorithm {
-            LoadBalanceAlgorithm::ConsistentHash {
-                hash_key: HashKey::Header(name),
-            } => {
-                let val = get_header(name.as_bytes()).and_then(|v| std::str::from_utf8(v).ok());
-                self.select_with_key(client_ip, val, None)
-            }
-            LoadBalanceAlgorithm::ConsistentHash {
-                hash_key: HashKey::Cookie(name),
-            } => {
-                let cookie_hdr = get_header(b"cookie").and_then(|v| std::str::from_utf8(v).ok());
-                let val = cookie_hdr.and_then(|c| extract_cookie_value(c, name));
-                self.select_with_key(client_ip, val, None)
-            }
-            _ => self.select(client_ip),
-        }
-    }
+    /// 初期最大データサイズ（コネクション全体）
+    /// デフォルト: 10000000 (10MB)
+    #[serde(default = "default_h3_initial_max_data")]
+    pub initial_max_data: u64,
 
-    /// ハッシュキーの値を指定してサーバーを選択する
-    ///
-    /// ConsistentHash で `header:` / `cookie:` を使う場合は、呼び出し側で
-    /// 該当ヘッダー/Cookie の値を解決して `hash_value` に渡す。値が解決
-    /// できない場合は client_ip にフォールバックする。
-    pub fn select_with_key(
-        &self,
-        client_ip: &str,
-        hash_value: Option<&str>,
-        _unused: Option<()>,
-    ) -> Option<&UpstreamServer> {
-        let candidates = self.candidates();
-        if candidates.is_empty() {
-            return None;
-        }
+    /// 初期最大ストリームデータサイズ（双方向ローカル）
+    #[serde(default = "default_h3_initial_max_stream_data")]
+    pub initial_max_stream_data_bidi_local: u64,
 
-        let selected_idx = match &self.algorithm {
-            LoadBalanceAlgorithm::RoundRobin => {
-                let counter = self.rr_counter.fetch_add(1, Ordering::Relaxed);
-                counter % candidates.len()
-            }
-            LoadBalanceAlgorithm::LeastConnections => candidates
-                .iter()
-                .enumerate()
-                .min_by_key(|(_, (_, s))| s.connections())
-                .map(|(i, _)| i)
-                .unwrap_or(0),
-            LoadBalanceAlgorithm::IpHash => {
-                let hash = Self::fnv1a(client_ip.as_bytes());
-                (hash as usize) % candidates.len()
-            }
-            LoadBalanceAlgorithm::Weighted => {
-                // 健全なサーバー集合に対する重み合計を計算し、
-                // rr_counter を total で割った余りで二分探索する。
-                return self.select_weighted(&candidates);
-            }
-            LoadBalanceAlgorithm::ConsistentHash { hash_key } => {
-                // ハッシュ対象の値を決定
-                let key: &str = match hash_key {
-                    HashKey::Ip => client_ip,
-                    HashKey::Header(_) | HashKey::Cookie(_) => hash_value.unwrap_or(client_ip),
-                };
-                return self.select_consistent(key, &candidates);
-            }
-        };
+    /// 初期最大ストリームデータサイズ（双方向リモート）
+    #[serde(default = "default_h3_initial_max_stream_data")]
+    pub initial_max_stream_data_bidi_remote: u64,
 
-        candidates.get(selected_idx).map(|(_, s)| *s)
-    }
+    /// 初期最大ストリームデータサイズ（単方向）
+    #[serde(default = "default_h3_initial_max_stream_data")]
+    pub initial_max_stream_data_uni: u64,
 
-    /// FNV-1a ハッシュ（IpHash 用）
-    fn fnv1a(bytes: &[u8]) -> u64 {
-        let mut hash: u64 = 14695981039346656037;
-        for &byte in bytes {
-            hash ^= byte as u64;
-            hash = hash.wrapping_mul(1099511628211);
-        }
-        hash
-    }
+    /// 初期最大双方向ストリーム数
+    #[serde(default = "default_h3_max_streams")]
+    pub initial_max_streams_bidi: u64,
 
-    /// Weighted Round Robin 選択（候補内の重みで按分）
-    fn select_weighted<'a>(
-        &'a self,
-        candidates: &[(usize, &'a UpstreamServer)],
-    ) -> Option<&'a UpstreamServer> {
-        // 候補（healthy）の重みを weighted_offsets から逆算して累積を作る
-        let mut cum: Vec<(u32, usize)> = Vec::with_capacity(candidates.len());
-        let mut acc: u32 = 0;
-        for (ci, (orig_idx, _)) in candidates.iter().enumerate() {
-            let w = self.weight_of(*orig_idx);
-            acc = acc.saturating_add(w);
-            cum.push((acc, ci));
-        }
-        let total = acc;
-        if total == 0 {
-            return candidates.first().map(|(_, s)| *s);
-        }
-        let pos = (self.rr_counter.fetch_add(1, Ordering::Relaxed) as u32) % total;
-        // pos < offset となる最初の要素を二分探索
-        let idx = match cum.binary_search_by(|(off, _)| {
-            if *off <= pos {
-                std::cmp::Ordering::Less
-            } else {
-                std::cmp::Ordering::Greater
-            }
-        }) {
-            Ok(i) => i,
-            Err(i) => i,
-        };
-        let ci = cum.get(idx).map(|(_, ci)| *ci).unwrap_or(0);
-        candidates.get(ci).map(|(_, s)| *s)
-    }
+    /// 初期最大単方向ストリーム数
+    #[serde(default = "default_h3_max_streams")]
+    pub initial_max_streams_uni: u64,
 
-    /// 元インデックスのサーバーの重みを取得
-    fn weight_of(&self, orig_idx: usize) -> u32 {
-        let prev = if orig_idx == 0 {
-            0
-        } else {
-            self.weighted_offsets
-                .get(orig_idx - 1)
-                .copied()
-                .unwrap_or(0)
-        };
-        let cur = self
-            .weighted_offsets
-            .get(orig_idx)
-            .copied()
-            .unwrap_or(prev + 1);
-        cur.saturating_sub(prev).max(1)
-    }
+    /// HTTP/3接続時の圧縮を常に有効化
+    /// デフォルト: false
+    ///
+    /// true の場合、パスごとの設定で明示的に無効化されていない限り、
+    /// すべてのHTTP/3レスポンスで圧縮を試みます。
+    /// パスごとの compression.enabled = false の場合はそちらが優先されます。
+    #[serde(default)]
+    pub compression_enabled: bool,
 
-    /// Consistent Hash 選択（リング上の二分探索）
-    fn select_consistent<'a>(
-        &'a self,
-        key: &str,
-        candidates: &[(usize, &'a UpstreamServer)],
-    ) -> Option<&'a UpstreamServer> {
-        use xxhash_rust::xxh3::xxh3_64_with_seed;
-        if self.consistent_ring.is_empty() {
-            // リング未構築（単一サーバー等）の場合はハッシュで按分
-            let h = xxh3_64_with_seed(key.as_bytes(), CONSISTENT_HASH_SEED);
-            let idx = (h as usize) % candidates.len();
-            return candidates.get(idx).map(|(_, s)| *s);
-        }
-        let h = xxh3_64_with_seed(key.as_bytes(), CONSISTENT_HASH_SEED);
-        // h 以上の最初の vnode を探す（なければ先頭へラップ）
-        let start = self.consistent_ring.partition_point(|(vh, _)| *vh < h);
-        let ring_len = self.consistent_ring.len();
-        // リングを start から一周し、候補に含まれる最初のサーバーを選ぶ
-        for offset in 0..ring_len {
-            let (_, server_idx) = self.consistent_ring[(start + offset) % ring_len];
-            if let Some((_, s)) = candidates.iter().find(|(oi, _)| *oi == server_idx) {
-                return Some(*s);
-            }
-        }
-        candidates.first().map(|(_, s)| *s)
-    }
+    /// HTTP/3専用の圧縮パラメータ
+    ///
+    /// パスごとの圧縮設定より優先されます。
+    /// 未設定のフィールドはパスごとの設定を継承します。
+    #[serde(default)]
+    pub compression: Http3CompressionConfig,
 
-    /// 指定インデックスのサーバーのリクエスト結果を記録（F-06）
-    pub fn record_outcome(&self, server_idx: usize, success: bool, latency_ms: u64) {
-        if let Some(server) = self.servers.get(server_idx) {
-            server.record_outcome(success, latency_ms, Some(&self.outlier_detection));
-        }
-    }
+    /// GSO/GRO を有効化するかどうか
+    ///
+    /// GSO (Generic Segmentation Offload) / GRO (Generic Receive Offload) は
+    /// カーネルレベルでUDPパケットの送受信を効率化する機能です。
+    ///
+    /// 効果:
+    /// - 複数の小さなUDPパケットを一度に送受信
+    /// - システムコール回数の削減
+    /// - CPU使用率の低減
+    ///
+    /// 注意:
+    /// - Linux 5.0+ でサポート
+    /// - 一部の仮想環境やDockerでは期待通りに動作しない場合あり
+    /// - 問題が発生した場合は false に設定してください
+    ///
+    /// デフォルト: false
+    #[serde(default)]
+    pub gso_gro_enabled: bool,
 
-    /// サーバー数を取得
-    pub fn len(&self) -> usize {
-        self.servers.len()
-    }
+    // ====================
+    // quiche::Config 輻輳制御 / Pacing / バッチ（F-124）
+    // ====================
+    /// QUIC 輻輳制御アルゴリズム（quiche `set_cc_algorithm_name`）
+    ///
+    /// 受け付ける値（大小無視）:
+    /// - `"reno"` / `"cubic"` / `"bbr"` / `"bbr2"` / `"bbr2_gcongestion"`
+    ///
+    /// リバースプロキシの短時間バーストでは CUBIC のスロースタートが RPS を抑えやすいため、
+    /// 既定は `"bbr"`。未知の値は起動時に警告して `"bbr"` にフォールバックする。
+    ///
+    /// デフォルト: `"bbr"`
+    #[serde(default = "default_h3_cc_algorithm")]
+    pub cc_algorithm: String,
 
-    /// サーバーが空かどうか
-    pub fn is_empty(&self) -> bool {
-        self.servers.is_empty()
-    }
+    /// Packet Pacing を有効にするか（quiche `enable_pacing`）
+    ///
+    /// 有効時は送信間隔をならしパケットロスを減らす。quiche 自体の既定も true。
+    /// デフォルト: `true`
+    #[serde(default = "default_true")]
+    pub pacing: bool,
 
-    /// TLS証明書検証を無効化するかどうかを取得
-    pub fn tls_insecure(&self) -> bool {
-        self.tls_insecure
-    }
+    /// 最大 pacing レート（バイト/秒）。未指定時は制限なし（quiche `set_max_pacing_rate`）
+    ///
+    /// デフォルト: なし（`None`）
+    #[serde(default)]
+    pub max_pacing_rate: Option<u64>,
 
-    /// H2C (HTTP/2 over cleartext) を強制するかどうかを取得
-    pub fn use_h2c(&self) -> bool {
-        self.use_h2c
-    }
-}
+    /// HyStart++ を有効にするか（quiche `enable_hystart`）
+    ///
+    /// デフォルト: `true`
+    #[serde(default = "default_true")]
+    pub hystart: bool,
 
-// ====================
-// 非同期I/Oトレイト（コード重複解消）
-// ====================
+    /// UDP データグラム送受信バッチ幅（`recvmmsg` / `sendmmsg` / multishot 提供バッファ数）
+    ///
+    /// 1 回の syscall / 1 本の multishot アームで扱う最大データグラム数。
+    /// 範囲: 1..=128。範囲外はクランプする。
+    ///
+    /// デフォルト: `64`
+    #[serde(default = "default_h3_mmsg_batch_size")]
+    pub mmsg_batch_size: usize,
 
-/// 非同期読み込みトレイト（SafeReadBuffer対応）
-///
-/// 読み込み操作で `SafeReadBuffer` を受け取り、返却します。
-/// monoio の `set_init()` コールバックにより、読み込み完了時に
-/// 自動的に `valid_len` が設定されます。
-#[allow(async_fn_in_trait)]
-pub trait AsyncReader {
-    async fn read_buf(&mut self, buf: SafeReadBuffer) -> (io::Result<usize>, SafeReadBuffer);
-}
+    // ====================
+    // Alt-Svc（HTTP/3 広告、F-94）— すべて [http3] に集約
+    // ====================
+    /// HTTP/1.1・HTTP/2 応答へ `Alt-Svc` ヘッダーを付与するか
+    ///
+    /// デフォルト: `true`（`server.http3_enabled = true` のときのみ実際に広告される）
+    /// `false` にすると広告を抑制する。
+    #[serde(default = "default_true")]
+    pub alt_svc_enabled: bool,
 
-/// 非同期書き込みトレイト
-///
-/// 書き込み操作では `Vec<u8>` を受け取ります。
-/// 書き込みデータは既に有効なデータなので、SafeReadBuffer は不要です。
-#[allow(async_fn_in_trait)]
-pub trait AsyncWriter {
-    async fn write_buf(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>);
-}
+    /// Alt-Svc ヘッダー値の上書き
+    ///
+    /// 未指定時はリッスンポートから `h3=":PORT"; ma=86400` を自動生成する。
+    /// 明示指定例: `h3=":443"; ma=86400, h3=":443"; ma=2592000`
+    #[serde(default)]
+    pub alt_svc: Option<String>,
 
-// TcpStream用の実装
-impl AsyncReader for TcpStream {
-    async fn read_buf(&mut self, buf: SafeReadBuffer) -> (io::Result<usize>, SafeReadBuffer) {
-        use crate::runtime::io::AsyncReadRent;
-        self.read(buf).await
-    }
+    /// Alt-Svc の max-age（秒）。`alt_svc` 未指定時の自動生成に使用。デフォルト: 86400
+    #[serde(default = "default_h3_alt_svc_ma")]
+    pub alt_svc_ma_secs: u64,
 }
 
-impl AsyncWriter for TcpStream {
-    async fn write_buf(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
-        self.write_all(buf).await
-    }
+fn default_h3_max_idle_timeout() -> u64 {
+    30000
 }
-
-// KtlsServerStream用の実装（rustls + ktls2）
-#[cfg(veil_ktls)]
-impl AsyncReader for KtlsServerStream {
-    async fn read_buf(&mut self, buf: SafeReadBuffer) -> (io::Result<usize>, SafeReadBuffer) {
-        use crate::runtime::io::AsyncReadRent;
-        self.read(buf).await
-    }
+fn default_h3_max_udp_payload_size() -> u64 {
+    1350
 }
-
-#[cfg(veil_ktls)]
-impl AsyncWriter for KtlsServerStream {
-    async fn write_buf(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
-        self.write_all(buf).await
-    }
+fn default_h3_initial_max_data() -> u64 {
+    10_000_000
 }
-
-// KtlsClientStream用の実装（rustls + ktls2）
-#[cfg(veil_ktls)]
-impl AsyncReader for KtlsClientStream {
-    async fn read_buf(&mut self, buf: SafeReadBuffer) -> (io::Result<usize>, SafeReadBuffer) {
-        use crate::runtime::io::AsyncReadRent;
-        self.read(buf).await
-    }
+fn default_h3_initial_max_stream_data() -> u64 {
+    1_000_000
 }
-
-#[cfg(veil_ktls)]
-impl AsyncWriter for KtlsClientStream {
-    async fn write_buf(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
-        self.write_all(buf).await
-    }
+fn default_h3_max_streams() -> u64 {
+    100
 }
-
-// SimpleTlsServerStream用の実装（rustls のみ）
-#[cfg(not(veil_ktls))]
-impl AsyncReader for crate::simple_tls::SimpleTlsServerStream {
-    async fn read_buf(&mut self, buf: SafeReadBuffer) -> (io::Result<usize>, SafeReadBuffer) {
-        use crate::runtime::io::AsyncReadRent;
-        self.read(buf).await
-    }
+fn default_h3_alt_svc_ma() -> u64 {
+    86400
 }
-
-#[cfg(not(veil_ktls))]
-impl AsyncWriter for crate::simple_tls::SimpleTlsServerStream {
-    async fn write_buf(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
-        self.write_all(buf).await
-    }
+fn default_h3_cc_algorithm() -> String {
+    "bbr".to_string()
 }
-
-// SimpleTlsClientStream用の実装（rustls のみ）
-#[cfg(not(veil_ktls))]
-impl AsyncReader for crate::simple_tls::SimpleTlsClientStream {
-    async fn read_buf(&mut self, buf: SafeReadBuffer) -> (io::Result<usize>, SafeReadBuffer) {
-        use crate::runtime::io::AsyncReadRent;
-        self.read(buf).await
-    }
+fn default_h3_mmsg_batch_size() -> usize {
+    64
 }
 
-#[cfg(not(veil_ktls))]
-impl AsyncWriter for crate::simple_tls::SimpleTlsClientStream {
-    async fn write_buf(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
-        self.write_all(buf).await
+impl Default for Http3ConfigSection {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            max_idle_timeout: default_h3_max_idle_timeout(),
+            max_udp_payload_size: default_h3_max_udp_payload_size(),
+            initial_max_data: default_h3_initial_max_data(),
+            initial_max_stream_data_bidi_local: default_h3_initial_max_stream_data(),
+            initial_max_stream_data_bidi_remote: default_h3_initial_max_stream_data(),
+            initial_max_stream_data_uni: default_h3_initial_max_stream_data(),
+            initial_max_streams_bidi: default_h3_max_streams(),
+            initial_max_streams_uni: default_h3_max_streams(),
+            compression_enabled: false,
+            compression: Http3CompressionConfig::default(),
+            gso_gro_enabled: false,
+            cc_algorithm: default_h3_cc_algorithm(),
+            pacing: true,
+            max_pacing_rate: None,
+            hystart: true,
+            mmsg_batch_size: default_h3_mmsg_batch_size(),
+            alt_svc_enabled: true,
+            alt_svc: None,
+            alt_svc_ma_secs: default_h3_alt_svc_ma(),
+        }
     }
 }
 
-/// HTTP/3 有効時に Alt-Svc 広告を初期化する（F-94、コールドパス）。
-///
-/// `server.http3_enabled && [http3].alt_svc_enabled` のとき、
-/// `[http3].alt_svc` 明示値、または listen ポートから自動生成した値を登録する。
-/// Alt-Svc 関連キーはすべて `[http3]` に集約する。
-fn apply_alt_svc_from_config(config: &Config) {
-    #[cfg(feature = "http3")]
-    {
-        let enabled = config.server.http3_enabled && config.http3.alt_svc_enabled;
-        let value = if enabled {
-            if let Some(ref explicit) = config.http3.alt_svc {
-                explicit.clone()
-            } else {
-                let listen = config
-                    .http3
-                    .listen
-                    .as_deref()
-                    .unwrap_or(config.server.listen.as_str());
-                crate::pool::build_alt_svc_value(listen, config.http3.alt_svc_ma_secs)
-            }
-        } else {
-            String::new()
-        };
-        crate::pool::init_alt_svc(enabled, &value);
-    }
-    #[cfg(not(feature = "http3"))]
-    {
-        let _ = config;
-        crate::pool::init_alt_svc(false, "");
+#[cfg(feature = "http3")]
+impl Http3ConfigSection {
+    /// HTTP/3 設定を Http3ServerConfig に変換
+    pub fn to_http3_config(
+        &self,
+        cert_path: &str,
+        key_path: &str,
+    ) -> http3_server::Http3ServerConfig {
+        http3_server::Http3ServerConfig {
+            cert_path: cert_path.to_string(),
+            key_path: key_path.to_string(),
+            cert_pem: None, // quicheはファイルパスからの読み込みのみサポート
+            key_pem: None,
+            max_idle_timeout: self.max_idle_timeout,
+            max_udp_payload_size: self.max_udp_payload_size,
+            initial_max_data: self.initial_max_data,
+            initial_max_stream_data_bidi_local: self.initial_max_stream_data_bidi_local,
+            initial_max_stream_data_bidi_remote: self.initial_max_stream_data_bidi_remote,
+            initial_max_stream_data_uni: self.initial_max_stream_data_uni,
+            initial_max_streams_bidi: self.initial_max_streams_bidi,
+            initial_max_streams_uni: self.initial_max_streams_uni,
+            gso_gro_enabled: self.gso_gro_enabled,
+            cc_algorithm: self.cc_algorithm.clone(),
+            pacing: self.pacing,
+            max_pacing_rate: self.max_pacing_rate,
+            hystart: self.hystart,
+            mmsg_batch_size: self
+                .mmsg_batch_size
+                .clamp(1, crate::udp::socket::MMSG_BATCH_MAX),
+        }
     }
 }
 
-fn validate_config(config: &Config) -> io::Result<()> {
-    // TLS証明書ファイルの存在チェック
-    let cert_path = Path::new(&config.tls.cert_path);
-    if !cert_path.exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("TLS certificate file not found: {}", config.tls.cert_path),
-        ));
-    }
-
-    let key_path = Path::new(&config.tls.key_path);
-    if !key_path.exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("TLS key file not found: {}", config.tls.key_path),
-        ));
-    }
+#[derive(Deserialize)]
+pub struct ServerConfigSection {
+    pub listen: String,
+    /// HTTPリスナーアドレス（オプション）
+    ///
+    /// 指定した場合、HTTPアクセスをHTTPSにリダイレクトするリスナーを起動します。
+    /// 例: "0.0.0.0:80"
+    ///
+    /// リダイレクトのみを行い、コンテンツは配信しません（セキュリティ考慮）。
+    #[serde(default)]
+    pub http: Option<String>,
+    /// ワーカースレッド数
+    /// 未指定または0の場合はCPUコア数と同じスレッド数を使用
+    #[serde(default)]
+    pub threads: Option<usize>,
 
-    // バインドアドレスの妥当性チェック
-    if config.server.listen.parse::<SocketAddr>().is_err() {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            format!("Invalid listen address: {}", config.server.listen),
-        ));
-    }
+    // ====================
+    // Serverヘッダー設定
+    // ====================
+    /// Serverヘッダーを有効化（デフォルト: false）
+    ///
+    /// セキュリティ考慮事項:
+    /// - Serverヘッダーはサーバーソフトウェア情報を公開します
+    /// - 攻撃者がバージョン別の脆弱性を狙う手がかりになり得ます
+    /// - 本番環境では無効化を推奨
+    #[serde(default)]
+    pub server_header_enabled: bool,
 
-    // Upstream設定の妥当性チェック
-    if let Some(ref upstreams) = config.upstreams {
-        for (name, upstream) in upstreams {
-            if upstream.servers.is_empty() {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    format!("Upstream '{}' has no servers configured", name),
-                ));
-            }
+    /// Serverヘッダーの値（server_header_enabled = true時のみ有効）
+    ///
+    /// デフォルト: "veil"
+    /// 空文字の場合: ヘッダー自体を送信しない
+    #[serde(default = "default_server_header_value")]
+    pub server_header_value: String,
+
+    // ====================
+    // HTTP/2・HTTP/3 設定
+    // ====================
+    /// HTTP/2 を有効化するかどうか
+    ///
+    /// TLS ALPN ネゴシエーションにより HTTP/2 (h2) をサポートします。
+    /// HTTP/1.1 へのフォールバックも可能です。
+    ///
+    /// 効果:
+    /// - ストリーム多重化によるレイテンシ削減
+    /// - HPACK ヘッダー圧縮によるオーバーヘッド削減
+    /// - サーバープッシュ（無効化推奨）
+    ///
+    /// 注意: `--features http2` でビルドする必要があります
+    #[serde(default)]
+    #[cfg_attr(not(feature = "http2"), allow(dead_code))]
+    pub http2_enabled: bool,
+
+    /// HTTP/3 を有効化するかどうか
+    ///
+    /// QUIC/UDP ベースの HTTP/3 プロトコルをサポートします。
+    ///
+    /// 効果:
+    /// - 0-RTT 接続確立
+    /// - 接続マイグレーション
+    /// - Head-of-Line ブロッキング解消
+    ///
+    /// 注意:
+    /// - `--features http3` でビルドする必要があります
+    /// - HTTP/3 は UDP ベースのため kTLS は使用不可
+    /// - GSO/GRO による高パフォーマンス UDP 処理を使用
+    /// - リッスンアドレスは [http3].listen で設定
+    #[serde(default)]
+    #[cfg_attr(not(feature = "http3"), allow(dead_code))]
+    pub http3_enabled: bool,
+
+    // ====================
+    // H2C (HTTP/2 Cleartext) 設定
+    // ====================
+    /// H2C (HTTP/2 Cleartext) サーバーを有効化するかどうか
+    ///
+    /// TLSなしでHTTP/2を使用するプロトコルです（RFC 7540 Section 3.4）。
+    /// Prior Knowledgeモードをサポートします。
+    ///
+    /// 効果:
+    /// - ストリーム多重化によるレイテンシ削減
+    /// - HPACK ヘッダー圧縮によるオーバーヘッド削減
+    /// - gRPC バックエンドへの接続に適している
+    ///
+    /// セキュリティ考慮事項:
+    /// - H2Cは平文通信のため、本番環境では内部ネットワークでのみ使用を推奨
+    /// - 外部公開時はTLS必須
+    /// - デフォルトで無効（明示的に有効化が必要）
+    ///
+    /// 注意: `--features http2` でビルドする必要があります
+    #[serde(default)]
+    #[cfg_attr(not(feature = "http2"), allow(dead_code))]
+    pub h2c_enabled: bool,
+
+    /// H2C リスニングアドレス（オプション）
+    ///
+    /// 指定した場合、H2C専用のリスナーを起動します。
+    /// 未指定の場合は、TLSリスナーと同じアドレスでプロトコル検出を行います。
+    ///
+    /// 例: "0.0.0.0:8080"
+    ///
+    /// 注意: 同じポートでTLSとH2Cの両方を処理する場合は未指定にしてください。
+    #[serde(default)]
+    #[cfg_attr(not(feature = "http2"), allow(dead_code))]
+    pub h2c_listen: Option<String>,
+
+    // ====================
+    // グレースフルシャットダウン設定
+    // ====================
+    /// グレースフルシャットダウンのドレインタイムアウト（秒）
+    ///
+    /// SIGTERMまたはSIGINT受信時に、既存の接続が完了するまで待機する最大時間。
+    /// タイムアウト後は残りの接続を強制終了します。
+    ///
+    /// デフォルト: 30秒
+    /// 0: 待機せずに即座に終了（既存の動作）
+    #[serde(default = "default_graceful_shutdown_timeout")]
+    pub graceful_shutdown_timeout_secs: u64,
+
+    /// リスナー（`listen` / `http` / `h2c_listen`）に適用するソケットプロファイル名（F-142）
+    ///
+    /// 起動時に作成するリスナーにのみ反映する（リロードでは変わらない）。
+    #[serde(default)]
+    pub socket_profile: Option<String>,
+}
+
+/// グレースフルシャットダウンタイムアウトのデフォルト値（30秒）
+fn default_graceful_shutdown_timeout() -> u64 {
+    30
+}
+
+/// Serverヘッダーのデフォルト値
+fn default_server_header_value() -> String {
+    "veil".to_string()
+}
+
+#[derive(Deserialize)]
+pub struct TlsConfigSection {
+    pub cert_path: String,
+    pub key_path: String,
+    /// kTLSを有効化するかどうか（Linux 5.15+、modprobe tls 必須）
+    ///
+    /// kTLS有効化時の効果:
+    /// - TLSデータ転送フェーズでカーネルオフロード
+    /// - sendfileでゼロコピー送信（TLS暗号化済み）
+    /// - 高負荷時にCPU 20-40%節約、スループット最大2倍
+    ///
+    /// 注意事項:
+    /// - TLSハンドシェイクはrustlsで実行（セキュリティ維持）
+    /// - AES-GCM暗号スイートのみサポート
+    /// - カーネルバグの影響範囲に注意
+    #[serde(default)]
+    pub ktls_enabled: bool,
+    /// kTLS有効化失敗時にrustlsへフォールバックするかどうか
+    ///
+    /// - false: kTLS必須モード（失敗時は接続拒否）
+    /// - true: kTLS失敗時はrustlsで継続（デフォルト）
+    ///
+    /// フォールバック無効化のメリット:
+    /// - パフォーマンス予測可能性（確実にkTLSを使用）
+    /// - デバッグ容易性（kTLS/rustls混在なし）
+    /// - 環境問題の早期発見
+    #[serde(default = "default_ktls_fallback")]
+    pub ktls_fallback_enabled: bool,
+    /// kTLS有効時にTCP_CORKを使用するかどうか
+    ///
+    /// TCP_CORKはkTLS設定中に小さなTCPパケットの送信を遅延し、
+    /// パケット結合により効率的なネットワーク転送を実現します。
+    ///
+    /// - true: TCP_CORK有効（デフォルト、推奨）
+    /// - false: TCP_CORK無効（特定の低遅延要件がある場合）
+    #[serde(default = "default_tcp_cork")]
+    pub tcp_cork_enabled: bool,
+    /// 利用を許可する TLS 暗号スイート（nginx の `ssl_ciphers` 相当、F-50）
+    ///
+    /// **記載順 = サーバ優先度順**（rustls はサーバ選好でネゴシエートする）。
+    /// 未指定（空）の場合は rustls 既定（DEFAULT_CIPHER_SUITES）を使用する。
+    /// 不正なスイート名は起動時（設定検証時）にエラーとなる。
+    ///
+    /// 指定可能な名前（aws-lc-rs プロバイダ）:
+    /// - TLS 1.3: `TLS13_AES_256_GCM_SHA384`, `TLS13_AES_128_GCM_SHA256`,
+    ///   `TLS13_CHACHA20_POLY1305_SHA256`
+    /// - TLS 1.2: `TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384`,
+    ///   `TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256`,
+    ///   `TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256`,
+    ///   `TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384`,
+    ///   `TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256`,
+    ///   `TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256`
+    ///
+    /// 注意: kTLS は AES-GCM 系のみ対応。kTLS 有効時に CHACHA20 系を含めると、
+    /// そのスイートでネゴシエートした接続は kTLS へオフロードされず rustls で
+    /// 処理される（`ktls_fallback_enabled = false` の場合は接続拒否）。
+    #[serde(default)]
+    pub cipher_suites: Vec<String>,
+    /// 証明書ファイルの変更を自動検知してリロードするか（F-03）
+    /// デフォルト: false
+    #[serde(default)]
+    pub auto_reload: bool,
+    /// 証明書変更チェックの間隔（秒、F-03）
+    /// デフォルト: 60
+    #[serde(default = "default_tls_reload_interval")]
+    pub reload_interval_secs: u64,
+}
+
+/// 証明書リロードチェック間隔のデフォルト値（秒）
+fn default_tls_reload_interval() -> u64 {
+    60
+}
+
+/// kTLSフォールバックのデフォルト値（true = フォールバック有効）
+fn default_ktls_fallback() -> bool {
+    true
+}
+
+/// TCP_CORKのデフォルト値（true = 有効）
+fn default_tcp_cork() -> bool {
+    true
+}
+
+/// パフォーマンス設定
+#[derive(Deserialize, Clone, Default)]
+pub struct PerformanceConfigSection {
+    /// SO_REUSEPORTの振り分け方式
+    /// - "kernel": カーネルデフォルト（3元タプルハッシュ）
+    /// - "cbpf": クライアントIPベースのCBPF振り分け（Linux 4.6+必須）
+    #[serde(default)]
+    pub reuseport_balancing: ReuseportBalancing,
+    /// Huge Pages (Large OS Pages) の使用
+    ///
+    /// mimallocでHuge Pages（2MB）を優先使用し、TLBミスを削減します。
+    ///
+    /// 効果:
+    /// - TLB（Translation Lookaside Buffer）ミス削減
+    /// - 大容量メモリ使用時のページフォルト減少
+    /// - kTLS/splice時のカーネル連携で5-10%パフォーマンス向上
+    ///
+    /// 要件（Linux）:
+    /// - /proc/sys/vm/nr_hugepages に十分な値を設定
+    /// - コンテナ環境では追加設定が必要な場合あり
+    #[serde(default)]
+    pub huge_pages_enabled: bool,
+
+    // ====================
+    // Viaヘッダー設定
+    // ====================
+    /// Viaヘッダーを追加するかどうか
+    ///
+    /// RFC 7230 Section 5.7.1 に従い、プロキシ経由のリクエスト/レスポンスに
+    /// Via ヘッダーを追加します。
+    ///
+    /// 形式: Via: 1.1 <hostname>
+    ///
+    /// - true: Viaヘッダーを追加
+    /// - false: Viaヘッダーを追加しない（デフォルト）
+    #[serde(default)]
+    pub via_header_enabled: bool,
+
+    /// Viaヘッダーに使用するホスト名
+    ///
+    /// via_header_enabled = true 時に使用します。
+    /// 未指定の場合はシステムホスト名または "veil" を使用します。
+    #[serde(default)]
+    pub via_header_hostname: Option<String>,
+
+    // ====================
+    // sendfile/splice チャンクサイズ設定
+    // ====================
+    /// チャンクサイズ調整モード
+    ///
+    /// - "dynamic": ファイルサイズに応じて動的にチャンクサイズを決定（デフォルト）
+    ///   - 0-64KB: 64KB
+    ///   - 64KB-1MB: 256KB
+    ///   - 1MB超: 1MB
+    /// - "manual": 固定チャンクサイズを使用
+    #[serde(default = "default_chunk_size_mode")]
+    #[cfg_attr(not(veil_ktls), allow(dead_code))]
+    pub chunk_size_mode: ChunkSizeMode,
+
+    /// 手動チャンクサイズ（バイト）
+    ///
+    /// chunk_size_mode = "manual" 時に使用します。
+    /// デフォルト: 1048576 (1MB)
+    #[serde(default = "default_manual_chunk_size")]
+    #[cfg_attr(not(veil_ktls), allow(dead_code))]
+    pub manual_chunk_size: usize,
+
+    // ====================
+    // パイプ割当設定
+    // ====================
+    /// ストリーム毎にパイプを割り当てるかどうか
+    ///
+    /// kTLS splice 使用時のパイプバッファ管理方式を設定します。
+    ///
+    /// - false: スレッドローカルパイプを再利用（デフォルト、メモリ効率重視）
+    /// - true: ストリーム毎にパイプを割り当て（高並行性環境向け）
+    ///
+    /// 高並行性環境（同時接続数1000+）ではtrueを推奨します。
+    #[serde(default)]
+    #[cfg_attr(not(veil_ktls), allow(dead_code))]
+    pub per_stream_pipe_enabled: bool,
+
+    // ====================
+    // OpenFileCache設定
+    // ====================
+    /// OpenFileCache（ファイルメタデータキャッシュ）のグローバルデフォルト設定
+    ///
+    /// 効果:
+    ///   - canonicalize、metadata、mime_guessのシステムコールをキャッシュ
+    ///   - 1リクエストあたり5〜6回のシステムコールを2回に削減（キャッシュヒット時）
+    ///   - パフォーマンス向上: 60〜67%のシステムコール削減
+    ///
+    /// 注意事項:
+    ///   - ファイル変更の検出が最大60秒（デフォルト）遅延する可能性
+    ///   - シンボリックリンク変更の検出が遅延する可能性
+    ///   - 静的ファイル配信に最適（動的に変更されるファイルには不向き）
+    ///
+    /// ルーティングごとの設定:
+    ///   - 各ルーティング（[[route]]）で`open_file_cache`セクションを指定可能
+    ///   - ルーティング設定がない場合は、このグローバル設定が使用される
+    ///
+    /// デフォルト: false（無効）
+    #[serde(default)]
+    pub open_file_cache_enabled: bool,
+
+    /// OpenFileCacheの有効期間（秒、グローバルデフォルト）
+    /// キャッシュされたファイル情報が有効とみなされる期間
+    /// デフォルト: 60秒
+    #[serde(default = "default_open_file_cache_valid_duration")]
+    pub open_file_cache_valid_duration_secs: u64,
+
+    /// OpenFileCacheの最大エントリ数（グローバルデフォルト）
+    /// キャッシュに保持する最大ファイル情報数
+    /// デフォルト: 10000
+    #[serde(default = "default_open_file_cache_max_entries")]
+    pub open_file_cache_max_entries: usize,
+
+    /// 上流接続の Happy Eyeballs（F-140、`[performance.happy_eyeballs]`）
+    #[serde(default)]
+    pub happy_eyeballs: HappyEyeballsConfig,
+}
+
+/// 上流接続の Happy Eyeballs 設定（RFC 8305、F-140）
+///
+/// 上流ホスト名が複数アドレスに解決された場合に、IPv6 / IPv4 を交互に並べて
+/// `connection_attempt_delay_ms` ごとに段階的に並行 connect する。
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HappyEyeballsConfig {
+    /// 有効化（デフォルト: true。false では最初に解決されたアドレスのみへ接続）
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 次のアドレスへの connect を始めるまでの待ち時間（ミリ秒、デフォルト: 250、下限 10）
+    #[serde(default = "default_happy_eyeballs_delay_ms")]
+    pub connection_attempt_delay_ms: u64,
+}
+
+impl Default for HappyEyeballsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            connection_attempt_delay_ms: default_happy_eyeballs_delay_ms(),
+        }
+    }
+}
+
+impl HappyEyeballsConfig {
+    /// ランタイムの connect ヘルパーへ反映する
+    pub fn apply(&self) {
+        crate::runtime::happy_eyeballs::configure(
+            self.enabled,
+            Duration::from_millis(self.connection_attempt_delay_ms),
+        );
+    }
+}
+
+fn default_happy_eyeballs_delay_ms() -> u64 {
+    crate::runtime::happy_eyeballs::DEFAULT_CONNECTION_ATTEMPT_DELAY.as_millis() as u64
+}
+
+// ====================
+// ソケットオプションのプロファイル（F-142）
+// ====================
+
+/// 名前付きのソケットオプション（TOML: `[socket_profiles.<name>]`）
+///
+/// `[server]`・`[upstreams.*]`・`[[l4]]` の `socket_profile` から名前で参照する。
+/// 未指定の項目はカーネルの既定値（sysctl）のまま。
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SocketProfileConfig {
+    /// `listen(2)` の backlog（リスナーのみ、デフォルト: 1024。net.core.somaxconn で頭打ち）
+    #[serde(default)]
+    pub backlog: Option<u32>,
+    /// TCP Fast Open。リスナーでは TCP_FASTOPEN のキュー長、上流接続では 1 以上で
+    /// TCP_FASTOPEN_CONNECT を有効にする（Linux のみ）
+    #[serde(default)]
+    pub tcp_fastopen: Option<u32>,
+    /// SO_KEEPALIVE（`keepalive_*` を指定した場合は省略しても有効）
+    #[serde(default)]
+    pub keepalive: Option<bool>,
+    /// TCP_KEEPIDLE（秒、1-32767、Linux のみ）
+    #[serde(default)]
+    pub keepalive_idle_secs: Option<u32>,
+    /// TCP_KEEPINTVL（秒、1-32767、Linux のみ）
+    #[serde(default)]
+    pub keepalive_interval_secs: Option<u32>,
+    /// TCP_KEEPCNT（1-127、Linux のみ）
+    #[serde(default)]
+    pub keepalive_count: Option<u32>,
+    /// TCP_USER_TIMEOUT（ミリ秒、Linux のみ）
+    #[serde(default)]
+    pub user_timeout_ms: Option<u32>,
+    /// TCP_NOTSENT_LOWAT（バイト、Linux のみ）
+    #[serde(default)]
+    pub notsent_lowat: Option<u32>,
+    /// IP_TOS / IPV6_TCLASS の値（0-255、`dscp` と排他）
+    #[serde(default)]
+    pub tos: Option<u8>,
+    /// DSCP（0-63、TOS の上位 6 ビットとして設定。`tos` と排他）
+    #[serde(default)]
+    pub dscp: Option<u8>,
+    /// SO_MARK（fwmark、Linux のみ。CAP_NET_ADMIN を権限降格後も保持する）
+    #[serde(default)]
+    pub mark: Option<u32>,
+    /// TCP_CONGESTION のアルゴリズム名（例: "bbr"、Linux のみ）
+    #[serde(default)]
+    pub congestion: Option<String>,
+    /// SO_RCVBUF（バイト）
+    #[serde(default)]
+    pub recv_buffer: Option<u32>,
+    /// SO_SNDBUF（バイト）
+    #[serde(default)]
+    pub send_buffer: Option<u32>,
+}
+
+impl SocketProfileConfig {
+    /// 値を検証してランタイムのソケットオプションへ変換する
+    pub fn resolve(&self, name: &str) -> io::Result<SocketOptions> {
+        let invalid = |msg: String| {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("socket profile '{}': {}", name, msg),
+            ))
+        };
+        if cfg!(windows) {
+            let has_socket_options = self.tcp_fastopen.is_some()
+                || self.keepalive.is_some()
+                || self.keepalive_idle_secs.is_some()
+                || self.keepalive_interval_secs.is_some()
+                || self.keepalive_count.is_some()
+                || self.user_timeout_ms.is_some()
+                || self.notsent_lowat.is_some()
+                || self.tos.is_some()
+                || self.dscp.is_some()
+                || self.mark.is_some()
+                || self.congestion.is_some()
+                || self.recv_buffer.is_some()
+                || self.send_buffer.is_some();
+            if has_socket_options {
+                return invalid("only backlog is supported on Windows".into());
+            }
+        }
+        let linux_only = [
+            ("tcp_fastopen", self.tcp_fastopen.is_some()),
+            ("keepalive_idle_secs", self.keepalive_idle_secs.is_some()),
+            (
+                "keepalive_interval_secs",
+                self.keepalive_interval_secs.is_some(),
+            ),
+            ("keepalive_count", self.keepalive_count.is_some()),
+            ("user_timeout_ms", self.user_timeout_ms.is_some()),
+            ("notsent_lowat", self.notsent_lowat.is_some()),
+            ("mark", self.mark.is_some()),
+            ("congestion", self.congestion.is_some()),
+        ];
+        if !cfg!(target_os = "linux") {
+            if let Some((key, _)) = linux_only.iter().find(|(_, set)| *set) {
+                return invalid(format!("{} requires Linux", key));
+            }
+        }
+        if self.backlog == Some(0) {
+            return invalid("backlog must be at least 1".into());
+        }
+        for (key, value, max) in [
+            ("keepalive_idle_secs", self.keepalive_idle_secs, 32_767),
+            (
+                "keepalive_interval_secs",
+                self.keepalive_interval_secs,
+                32_767,
+            ),
+            ("keepalive_count", self.keepalive_count, 127),
+        ] {
+            if let Some(v) = value.filter(|v| !(1..=max).contains(v)) {
+                return invalid(format!("{} must be between 1 and {} (got {})", key, max, v));
+            }
+        }
+        if self.keepalive == Some(false)
+            && (self.keepalive_idle_secs.is_some()
+                || self.keepalive_interval_secs.is_some()
+                || self.keepalive_count.is_some())
+        {
+            return invalid("keepalive_* options require keepalive to be enabled".into());
+        }
+        let tos = match (self.tos, self.dscp) {
+            (Some(_), Some(_)) => return invalid("tos and dscp are mutually exclusive".into()),
+            (_, Some(dscp)) if dscp > 63 => {
+                return invalid(format!("dscp must be between 0 and 63 (got {})", dscp))
+            }
+            (_, Some(dscp)) => Some(dscp << 2),
+            (tos, None) => tos,
+        };
+        if let Some(name) = &self.congestion {
+            if name.is_empty()
+                || name.len() > CONGESTION_NAME_MAX
+                || !name
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+            {
+                return invalid(format!(
+                    "congestion must be an algorithm name of up to {} characters (got {:?})",
+                    CONGESTION_NAME_MAX, name
+                ));
+            }
+        }
+        for (key, value) in [
+            ("recv_buffer", self.recv_buffer),
+            ("send_buffer", self.send_buffer),
+        ] {
+            if value == Some(0) {
+                return invalid(format!("{} must be at least 1", key));
+            }
+        }
+        Ok(SocketOptions {
+            backlog: self.backlog,
+            tcp_fastopen: self.tcp_fastopen,
+            keepalive: self.keepalive,
+            keepalive_idle_secs: self.keepalive_idle_secs,
+            keepalive_interval_secs: self.keepalive_interval_secs,
+            keepalive_count: self.keepalive_count,
+            user_timeout_ms: self.user_timeout_ms,
+            notsent_lowat: self.notsent_lowat,
+            tos,
+            mark: self.mark,
+            congestion: self.congestion.clone(),
+            recv_buffer: self.recv_buffer,
+            send_buffer: self.send_buffer,
+        })
+    }
+}
+
+fn default_open_file_cache_valid_duration() -> u64 {
+    60
+}
+
+fn default_open_file_cache_max_entries() -> usize {
+    10000
+}
+
+// ====================
+// ログ設定
+// ====================
+//
+// ftlogは内部でバックグラウンドスレッドとチャネルを使用した
+// 非同期ログライブラリです。以下の設定で最適化が可能です。
+//
+// ## grokの指摘に対する検証結果
+//
+// grokは「ftlogは同期ログ」と主張していましたが、これは不正確です。
+// ftlogは以下の非同期アーキテクチャを使用しています：
+// - ログマクロ → 内部チャネルにプッシュ（ノンブロッキング）
+// - バックグラウンドスレッド → チャネルから読み取りファイルI/O
+//
+// したがって、tokio::sync::mpscを使った追加の非同期化層は不要であり、
+// むしろオーバーヘッドを増やす可能性があります。
+//
+// ## 推奨される最適化
+// - channel_size: 高負荷時のバックプレッシャーを軽減
+// - flush_interval_ms: ディスクI/O頻度の調整
+// - level: 本番環境ではinfo以上を推奨
+// ====================
+
+// LogFormat, LoggingConfigSection, parse_log_level は
+// crate::logging モジュールに移動しました。
+
+/// SO_REUSEPORTの振り分け方式
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum ReuseportBalancing {
+    /// カーネルデフォルト（3元タプルハッシュ: protocol + source IP + source port）
+    #[default]
+    Kernel,
+    /// クライアントIPベースのCBPF振り分け
+    /// 同一クライアントIPからの接続を常に同じワーカースレッドに振り分け
+    /// CPUキャッシュ効率とセッション再開効率を向上
+    Cbpf,
+}
+
+impl<'de> serde::Deserialize<'de> for ReuseportBalancing {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        match s.as_str() {
+            "kernel" => Ok(ReuseportBalancing::Kernel),
+            "cbpf" => Ok(ReuseportBalancing::Cbpf),
+            other => Err(serde::de::Error::custom(format!(
+                "unknown reuseport_balancing value: '{}', expected 'kernel' or 'cbpf'",
+                other
+            ))),
+        }
+    }
+}
+
+/// sendfile/splice チャンクサイズ調整モード
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum ChunkSizeMode {
+    /// ファイルサイズに応じて動的にチャンクサイズを決定
+    /// - 0-64KB: 64KB
+    /// - 64KB-1MB: 256KB
+    /// - 1MB超: 1MB
+    #[default]
+    Dynamic,
+    /// 固定チャンクサイズを使用（manual_chunk_sizeで指定）
+    Manual,
+}
+
+impl<'de> serde::Deserialize<'de> for ChunkSizeMode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        match s.as_str() {
+            "dynamic" | "Dynamic" => Ok(ChunkSizeMode::Dynamic),
+            "manual" | "Manual" => Ok(ChunkSizeMode::Manual),
+            other => Err(serde::de::Error::custom(format!(
+                "unknown chunk_size_mode value: '{}', expected 'dynamic' or 'manual'",
+                other
+            ))),
+        }
+    }
+}
+
+/// チャンクサイズモードのデフォルト値（動的調整）
+fn default_chunk_size_mode() -> ChunkSizeMode {
+    ChunkSizeMode::Dynamic
+}
+
+/// 手動チャンクサイズのデフォルト値（1MB）
+fn default_manual_chunk_size() -> usize {
+    1048576 // 1MB
+}
+
+/// ファイルサイズに応じた最適なチャンクサイズを計算
+///
+/// 動的調整モード時に使用されます。
+pub fn calculate_optimal_chunk_size(file_size: u64) -> usize {
+    match file_size {
+        0..=65_536 => 65_536,          // 64KB以下: 64KB
+        65_537..=1_048_576 => 262_144, // 1MB以下: 256KB
+        _ => 1_048_576,                // 1MB超: 1MB
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum BackendConfig {
+    /// 単一URLプロキシ（後方互換性のため維持）
+    /// - sni_name: TLS接続時のSNI名（IP直打ち時にドメイン名を指定可能）
+    /// - use_h2c: H2C (HTTP/2 over cleartext) を使用するかどうか
+    ///
+    /// 注意: security, compression, buffering, cache, modules は route 直下で設定
+    Proxy {
+        url: String,
+        sni_name: Option<String>,
+        use_h2c: bool,
+    },
+    /// Upstream グループ参照（ロードバランシング用）
+    /// 注意: security, compression, buffering, cache, modules は route 直下で設定
+    ProxyUpstream { upstream: String, use_h2c: bool },
+    /// File バックエンド設定
+    /// - path: ファイルまたはディレクトリのパス
+    /// - mode: "sendfile" または "memory"
+    /// - index: ディレクトリアクセス時に返すファイル名（デフォルト: "index.html"）
+    ///
+    /// 注意: security, cache, open_file_cache, modules は route 直下で設定
+    File {
+        path: String,
+        mode: String,
+        index: Option<String>,
+    },
+    /// Redirect バックエンド設定
+    /// - redirect_url: リダイレクト先URL（$request_uri, $host, $path 変数使用可能）
+    /// - redirect_status: ステータスコード（301, 302, 307, 308）
+    /// - preserve_path: 元のパスをリダイレクト先に追加するか
+    ///
+    /// 注意: modules は route 直下で設定
+    Redirect {
+        redirect_url: String,
+        redirect_status: u16,
+        preserve_path: bool,
+    },
+}
+
+impl<'de> serde::Deserialize<'de> for BackendConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::{MapAccess, Visitor};
+
+        struct BackendConfigVisitor;
+
+        impl<'de> Visitor<'de> for BackendConfigVisitor {
+            type Value = BackendConfig;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a backend configuration object")
+            }
+
+            fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
+            where
+                M: MapAccess<'de>,
+            {
+                let mut backend_type: Option<String> = None;
+                let mut url: Option<String> = None;
+                let mut upstream: Option<String> = None;
+                let mut path: Option<String> = None;
+                let mut mode: Option<String> = None;
+                let mut index: Option<String> = None;
+                // Redirect 用フィールド
+                let mut redirect_url: Option<String> = None;
+                let mut redirect_status: Option<u16> = None;
+                let mut preserve_path: Option<bool> = None;
+                // SNI 用フィールド（Proxy用）
+                let mut sni_name: Option<String> = None;
+                // H2C 用フィールド（Proxy用）
+                let mut use_h2c: Option<bool> = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "type" => backend_type = Some(map.next_value()?),
+                        "url" => url = Some(map.next_value()?),
+                        "upstream" => upstream = Some(map.next_value()?),
+                        "path" => path = Some(map.next_value()?),
+                        "mode" => mode = Some(map.next_value()?),
+                        "index" => index = Some(map.next_value()?),
+                        // security, compression, buffering, cache, open_file_cache, modules は route 直下で設定されるため、ここでは無視
+                        "security" | "compression" | "buffering" | "cache" | "open_file_cache"
+                        | "modules" => {
+                            let _: serde::de::IgnoredAny = map.next_value()?;
+                        }
+                        "redirect_url" => redirect_url = Some(map.next_value()?),
+                        "redirect_status" => redirect_status = Some(map.next_value()?),
+                        "preserve_path" => preserve_path = Some(map.next_value()?),
+                        "sni_name" => sni_name = Some(map.next_value()?),
+                        "use_h2c" | "h2c" => use_h2c = Some(map.next_value()?),
+                        _ => {
+                            let _: serde::de::IgnoredAny = map.next_value()?;
+                        }
+                    }
+                }
+
+                let backend_type = backend_type.unwrap_or_else(|| "File".to_string());
+
+                match backend_type.as_str() {
+                    "Proxy" => {
+                        // upstream が指定されている場合はロードバランシング用
+                        if let Some(upstream_name) = upstream {
+                            let use_h2c = use_h2c.unwrap_or(false);
+                            Ok(BackendConfig::ProxyUpstream {
+                                upstream: upstream_name,
+                                use_h2c,
+                            })
+                        } else {
+                            let url = url.ok_or_else(|| {
+                                serde::de::Error::missing_field("url or upstream")
+                            })?;
+                            let use_h2c = use_h2c.unwrap_or(false);
+                            Ok(BackendConfig::Proxy {
+                                url,
+                                sni_name,
+                                use_h2c,
+                            })
+                        }
+                    }
+                    "Redirect" => {
+                        let redirect_url = redirect_url
+                            .ok_or_else(|| serde::de::Error::missing_field("redirect_url"))?;
+                        let redirect_status = redirect_status.unwrap_or(301);
+                        // ステータスコードの検証（301, 302, 303, 307, 308のみ許可）
+                        if !matches!(redirect_status, 301 | 302 | 303 | 307 | 308) {
+                            return Err(serde::de::Error::custom(format!(
+                                "invalid redirect_status: {}, expected 301, 302, 303, 307, or 308",
+                                redirect_status
+                            )));
+                        }
+                        let preserve_path = preserve_path.unwrap_or(false);
+                        Ok(BackendConfig::Redirect {
+                            redirect_url,
+                            redirect_status,
+                            preserve_path,
+                        })
+                    }
+                    _ => {
+                        let path = path.ok_or_else(|| serde::de::Error::missing_field("path"))?;
+                        let mode = mode.unwrap_or_else(|| "sendfile".to_string());
+                        Ok(BackendConfig::File { path, mode, index })
+                    }
+                }
+            }
+        }
+
+        deserializer.deserialize_map(BackendConfigVisitor)
+    }
+}
+
+// ====================
+// ランタイムBackend
+// ====================
+
+#[derive(Clone)]
+pub enum Backend {
+    /// Proxy バックエンド（ロードバランシング対応）
+    /// - Arc<UpstreamGroup>: アップストリームグループ（単一または複数バックエンド）
+    /// - Arc<SecurityConfig>: ルートごとのセキュリティ設定
+    /// - Arc<CompressionConfig>: 圧縮設定
+    /// - Arc<buffering::BufferingConfig>: バッファリング設定
+    /// - Arc<cache::CacheConfig>: キャッシュ設定
+    Proxy(
+        Arc<UpstreamGroup>,
+        Arc<SecurityConfig>,
+        Arc<CompressionConfig>,
+        Arc<buffering::BufferingConfig>,
+        Arc<cache::CacheConfig>,
+        /// WASMモジュール名のリスト（このバックエンドに適用するWASMモジュール）
+        Option<Arc<Vec<String>>>,
+    ),
+    /// MemoryFile バックエンド
+    /// - Arc<Vec<u8>>: ファイルコンテンツ
+    /// - Arc<str>: MIMEタイプ
+    /// - Arc<SecurityConfig>: ルートごとのセキュリティ設定
+    MemoryFile(
+        Arc<Vec<u8>>,
+        Arc<str>,
+        Arc<SecurityConfig>,
+        /// WASMモジュール名のリスト（このバックエンドに適用するWASMモジュール）
+        Option<Arc<Vec<String>>>,
+    ),
+    /// SendFile バックエンド
+    /// - Arc<PathBuf>: ベースパス
+    /// - bool: ディレクトリかどうか
+    /// - Option<Arc<str>>: インデックスファイル名（None = "index.html"）
+    /// - Arc<SecurityConfig>: ルートごとのセキュリティ設定
+    /// - Arc<cache::CacheConfig>: キャッシュ設定
+    /// - Option<Arc<cache::OpenFileCacheConfig>>: OpenFileCache設定（ルーティングごと）
+    SendFile(
+        Arc<PathBuf>,
+        bool,
+        Option<Arc<str>>,
+        Arc<SecurityConfig>,
+        Arc<cache::CacheConfig>,
+        Option<Arc<cache::OpenFileCacheConfig>>,
+        /// WASMモジュール名のリスト（このバックエンドに適用するWASMモジュール）
+        Option<Arc<Vec<String>>>,
+    ),
+    /// Redirect バックエンド
+    /// - Arc<str>: リダイレクト先URL
+    /// - u16: ステータスコード（301, 302, 307, 308）
+    /// - bool: 元のパスを保持するか
+    Redirect(
+        Arc<str>,
+        u16,
+        bool,
+        /// WASMモジュール名のリスト（このバックエンドに適用するWASMモジュール）
+        Option<Arc<Vec<String>>>,
+    ),
+}
+
+impl Backend {
+    /// このバックエンドのセキュリティ設定を取得
+    #[inline]
+    pub fn security(&self) -> &SecurityConfig {
+        // デフォルトのセキュリティ設定（Redirect用）
+        static DEFAULT_SECURITY: Lazy<SecurityConfig> = Lazy::new(SecurityConfig::default);
+
+        match self {
+            Backend::Proxy(_, security, _, _, _, _) => security,
+            Backend::MemoryFile(_, _, security, _) => security,
+            Backend::SendFile(_, _, _, security, _, _, _) => security,
+            Backend::Redirect(_, _, _, _) => &DEFAULT_SECURITY,
+        }
+    }
+
+    /// このバックエンドに適用するWASMモジュール名のリストを取得
+    #[inline]
+    /// F-43: WASM モジュールリストを Arc 共有で取得する（リクエストごとの deep copy 排除）。
+    pub fn modules_arc(&self) -> Option<&Arc<Vec<String>>> {
+        match self {
+            Backend::Proxy(_, _, _, _, _, modules) => modules.as_ref(),
+            Backend::MemoryFile(_, _, _, modules) => modules.as_ref(),
+            Backend::SendFile(_, _, _, _, _, _, modules) => modules.as_ref(),
+            Backend::Redirect(_, _, _, modules) => modules.as_ref(),
+        }
+    }
+
+    pub fn modules(&self) -> Option<&[String]> {
+        match self {
+            Backend::Proxy(_, _, _, _, _, modules) => modules.as_deref().map(|v| v.as_slice()),
+            Backend::MemoryFile(_, _, _, modules) => modules.as_deref().map(|v| v.as_slice()),
+            Backend::SendFile(_, _, _, _, _, _, modules) => {
+                modules.as_deref().map(|v| v.as_slice())
+            }
+            Backend::Redirect(_, _, _, modules) => modules.as_deref().map(|v| v.as_slice()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ProxyTarget {
+    pub host: String,
+    pub port: u16,
+    pub use_tls: bool,
+    pub path_prefix: String,
+    /// SNI (Server Name Indication) に使用するホスト名
+    /// Noneの場合はhostを使用。IP直打ちの場合にドメイン名を指定可能
+    pub sni_name: Option<String>,
+    /// H2C (HTTP/2 over cleartext) を使用するかどうか
+    /// true の場合、非TLSバックエンドにHTTP/2で接続
+    /// HTTP/2 Upgrade 経由ではなく、Prior Knowledge モードを使用
+    pub use_h2c: bool,
+    /// 上流プロトコル（F-134、TLS バックエンドで h2 / auto のとき ALPN h2 で多重化）
+    pub protocol: UpstreamProtocol,
+    /// h2 / h3 接続 1 本あたりの同時ストリーム上限（F-134、F-135）
+    pub h2_max_concurrent_streams: u32,
+    /// 多重化接続で受け取る応答ボディの上限（F-134、F-135、バイト）
+    pub max_response_body_bytes: usize,
+    /// コネクションプールの上限とライフサイクル（F-136）
+    pub connection_pool: ConnectionPoolConfig,
+    /// 上流接続の送信元アドレス設定（F-141）
+    pub source: Option<Arc<UpstreamSource>>,
+    /// このリクエストで bind する送信元（F-141、[`ProxyTarget::bound_for`] が設定する）
+    pub bind: Option<LocalBind>,
+    /// 上流接続のソケットオプション（F-142）
+    pub socket: Option<Arc<SocketOptions>>,
+}
+
+impl ProxyTarget {
+    pub fn parse(url: &str) -> Option<Self> {
+        let (scheme, rest) = if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else {
+            return None;
+        };
+
+        let (host_port, path) = match rest.find('/') {
+            Some(idx) => (&rest[..idx], &rest[idx..]),
+            None => (rest, "/"),
+        };
+
+        let (host, port) = match host_port.find(':') {
+            Some(idx) => {
+                let h = &host_port[..idx];
+                let p = host_port[idx + 1..].parse().ok()?;
+                (h.to_string(), p)
+            }
+            None => (host_port.to_string(), if scheme { 443 } else { 80 }),
+        };
+
+        Some(ProxyTarget {
+            host,
+            port,
+            use_tls: scheme,
+            path_prefix: path.to_string(),
+            sni_name: None,
+            use_h2c: false, // デフォルトでは無効
+            protocol: UpstreamProtocol::Http1,
+            h2_max_concurrent_streams: default_upstream_h2_max_concurrent_streams(),
+            max_response_body_bytes: default_upstream_max_response_body_bytes(),
+            connection_pool: ConnectionPoolConfig::default(),
+            source: None,
+            bind: None,
+            socket: None,
+        })
+    }
+
+    /// SNI名を設定したコピーを作成
+    pub fn with_sni_name(mut self, sni_name: Option<String>) -> Self {
+        self.sni_name = sni_name;
+        self
+    }
+
+    /// H2C設定を変更したコピーを作成
+    pub fn with_h2c(mut self, use_h2c: bool) -> Self {
+        // H2Cは非TLSの場合のみ有効
+        if !self.use_tls {
+            self.use_h2c = use_h2c;
+        }
+        self
+    }
+
+    /// 上流プロトコルを設定したコピーを作成（F-134）
+    ///
+    /// 平文バックエンドの `h2` は h2c（Prior Knowledge）として扱う。`auto` は ALPN が
+    /// 無い平文では HTTP/1.1 のまま。
+    pub fn with_protocol(
+        mut self,
+        protocol: UpstreamProtocol,
+        max_streams: u32,
+        max_response_body: usize,
+    ) -> Self {
+        self.protocol = protocol;
+        self.h2_max_concurrent_streams = max_streams.max(1);
+        self.max_response_body_bytes = max_response_body;
+        if protocol == UpstreamProtocol::H2 && !self.use_tls {
+            self.use_h2c = true;
+        }
+        self
+    }
+
+    /// このリクエストの送信元を決めたターゲットを返す（F-141、送信元の設定が無ければ借用のまま）
+    ///
+    /// 透過モードでは `client_ip`（IPv4-mapped IPv6 は IPv4 として扱う）を送信元にする。
+    pub fn bound_for(&self, client_ip: &str) -> io::Result<Cow<'_, Self>> {
+        let Some(source) = &self.source else {
+            return Ok(Cow::Borrowed(self));
+        };
+        let bind = source.local_bind(client_ip.parse().ok())?;
+        let mut target = self.clone();
+        target.bind = Some(bind);
+        Ok(Cow::Owned(target))
+    }
+
+    /// TLS 上の h2（ALPN）で接続するか（F-134）
+    #[inline]
+    pub fn uses_tls_h2(&self) -> bool {
+        self.use_tls && matches!(self.protocol, UpstreamProtocol::H2 | UpstreamProtocol::Auto)
+    }
+
+    /// HTTP/3（QUIC）で接続するか（F-135、http3 feature 無効時は常に false）
+    #[inline]
+    pub fn uses_h3(&self) -> bool {
+        cfg!(feature = "http3") && self.use_tls && self.protocol == UpstreamProtocol::H3
+    }
+
+    /// TLS接続時に使用するSNI名を取得
+    #[inline]
+    pub fn sni(&self) -> &str {
+        self.sni_name.as_deref().unwrap_or(&self.host)
+    }
+
+    /// デフォルトポートかどうかを判定
+    #[inline]
+    pub fn is_default_port(&self) -> bool {
+        if self.use_tls {
+            self.port == 443
+        } else {
+            self.port == 80
+        }
+    }
+}
+
+// ====================
+// ロードバランシング（Upstream Group）
+// ====================
+//
+// 複数のバックエンドサーバーへのリクエスト分散をサポートします。
+//
+// ## サポートするアルゴリズム
+// - RoundRobin: 順番に振り分け（デフォルト）
+// - LeastConnections: 接続数が最も少ないサーバーを選択
+// - IpHash: クライアントIPに基づいて一貫したサーバーを選択
+//
+// ## 設定例
+// ```toml
+// [upstreams."backend-pool"]
+// algorithm = "round_robin"
+// servers = ["http://localhost:8080", "http://localhost:8081"]
+//
+// [[route]]
+// conditions = { host = "example.com", path = "/api/*" }
+// type = "Proxy"
+// upstream = "backend-pool"
+// ```
+// ====================
+
+/// Consistent Hash 用のハッシュキー
+///
+/// シリアライズ形式:
+/// - `"ip"`              -> クライアント IP（デフォルト）
+/// - `"header:X-User-Id"` -> 指定ヘッダーの値
+/// - `"cookie:session_id"` -> 指定 Cookie の値
+/// - `"path"`            -> リクエストパス（クエリ除く。キャッシュシャーディング向け）
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub enum HashKey {
+    /// クライアント IP アドレス
+    #[default]
+    Ip,
+    /// 指定したリクエストヘッダーの値
+    Header(String),
+    /// 指定した Cookie の値
+    Cookie(String),
+    /// リクエストパス（クエリ文字列を除く）
+    Path,
+}
+
+/// Cookie ヘッダ値から指定名の値を取り出す（アロケーションなし）。
+/// `name=value` を `;` 区切りで走査し、名前一致時に value スライスを返す。
+fn extract_cookie_value<'a>(cookie_header: &'a str, name: &str) -> Option<&'a str> {
+    for part in cookie_header.split(';') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        if let Some((k, v)) = part.split_once('=') {
+            if k.trim().eq_ignore_ascii_case(name) {
+                return Some(v.trim());
+            }
+        }
+    }
+    None
+}
+
+impl HashKey {
+    /// 文字列からパース（`"ip"`, `"path"`, `"header:X-Foo"`, `"cookie:session"`）
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("ip") {
+            return Ok(HashKey::Ip);
+        }
+        if s.eq_ignore_ascii_case("path") {
+            return Ok(HashKey::Path);
+        }
+        if let Some(rest) = s.strip_prefix("header:") {
+            return Ok(HashKey::Header(rest.trim().to_string()));
+        }
+        if let Some(rest) = s.strip_prefix("cookie:") {
+            return Ok(HashKey::Cookie(rest.trim().to_string()));
+        }
+        Err(format!(
+            "invalid hash_key: '{}', expected 'ip', 'path', 'header:NAME', or 'cookie:NAME'",
+            s
+        ))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for HashKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        HashKey::parse(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// ロードバランシングアルゴリズム
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum LoadBalanceAlgorithm {
+    /// ラウンドロビン（順番に振り分け）
+    #[default]
+    RoundRobin,
+    /// Least Connections（接続数が最も少ないサーバー）
+    LeastConnections,
+    /// IP Hash（クライアントIPに基づく一貫したルーティング）
+    IpHash,
+    /// Weighted Round Robin（重み付きラウンドロビン）
+    Weighted,
+    /// Consistent Hash（仮想ノードリングによる一貫したルーティング）
+    ConsistentHash {
+        /// ハッシュキーの種類
+        hash_key: HashKey,
+    },
+    /// Bounded-Load Consistent Hash（リング上で平均負荷 × hash_load_factor を超える
+    /// サーバーをスキップする。キャッシュ局所性を保ちつつホットキーを分散）
+    BoundedLoadHash {
+        /// ハッシュキーの種類
+        hash_key: HashKey,
+    },
+    /// Maglev（素数サイズのルックアップテーブルによる O(1) 一貫ハッシュ）
+    Maglev {
+        /// ハッシュキーの種類
+        hash_key: HashKey,
+    },
+}
+
+impl LoadBalanceAlgorithm {
+    /// ハッシュ系アルゴリズムのキー種別（ハッシュ系以外は None）
+    pub fn hash_key(&self) -> Option<&HashKey> {
+        match self {
+            LoadBalanceAlgorithm::ConsistentHash { hash_key }
+            | LoadBalanceAlgorithm::BoundedLoadHash { hash_key }
+            | LoadBalanceAlgorithm::Maglev { hash_key } => Some(hash_key),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for LoadBalanceAlgorithm {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        match s.to_lowercase().as_str() {
+            "round_robin" | "roundrobin" => Ok(LoadBalanceAlgorithm::RoundRobin),
+            "least_conn" | "least_connections" | "leastconn" => Ok(LoadBalanceAlgorithm::LeastConnections),
+            "ip_hash" | "iphash" => Ok(LoadBalanceAlgorithm::IpHash),
+            "weighted" | "weighted_round_robin" | "wrr" => Ok(LoadBalanceAlgorithm::Weighted),
+            // hash_key は UpstreamConfig の別フィールドで上書きされる（デフォルト Ip）
+            "consistent_hash" | "consistenthash" => Ok(LoadBalanceAlgorithm::ConsistentHash {
+                hash_key: HashKey::Ip,
+            }),
+            "bounded_load" | "consistent_hash_bounded" | "bounded_load_hash" => {
+                Ok(LoadBalanceAlgorithm::BoundedLoadHash {
+                    hash_key: HashKey::Ip,
+                })
+            }
+            "maglev" => Ok(LoadBalanceAlgorithm::Maglev {
+                hash_key: HashKey::Ip,
+            }),
+            other => Err(serde::de::Error::custom(format!(
+                "unknown load balance algorithm: '{}', expected 'round_robin', 'least_conn', 'ip_hash', 'weighted', 'consistent_hash', 'bounded_load', or 'maglev'",
+                other
+            ))),
+        }
+    }
+}
+
+/// algorithm と hash_key 設定から実際に使うアルゴリズムを決定する。
+///
+/// algorithm がハッシュ系（ConsistentHash / BoundedLoadHash / Maglev）の場合、
+/// UpstreamConfig 側の hash_key フィールドがあればそれで上書きする。
+pub fn resolve_algorithm(
+    algorithm: &LoadBalanceAlgorithm,
+    hash_key: &Option<HashKey>,
+) -> LoadBalanceAlgorithm {
+    match algorithm {
+        LoadBalanceAlgorithm::ConsistentHash { hash_key: inner } => {
+            let key = hash_key.clone().unwrap_or_else(|| inner.clone());
+            LoadBalanceAlgorithm::ConsistentHash { hash_key: key }
+        }
+        LoadBalanceAlgorithm::BoundedLoadHash { hash_key: inner } => {
+            let key = hash_key.clone().unwrap_or_else(|| inner.clone());
+            LoadBalanceAlgorithm::BoundedLoadHash { hash_key: key }
+        }
+        LoadBalanceAlgorithm::Maglev { hash_key: inner } => {
+            let key = hash_key.clone().unwrap_or_else(|| inner.clone());
+            LoadBalanceAlgorithm::Maglev { hash_key: key }
+        }
+        other => other.clone(),
+    }
+}
+
+/// Upstream サーバーの状態
+#[derive(Clone)]
+pub struct UpstreamServer {
+    /// バックエンドターゲット
+    pub target: ProxyTarget,
+    /// 現在のアクティブ接続数（Least Connections用）
+    pub active_connections: Arc<AtomicUsize>,
+    /// サーバーが利用可能かどうか（ヘルスチェック用）
+    pub healthy: Arc<AtomicBool>,
+    /// 連続成功回数（健康チェック用）
+    pub consecutive_successes: Arc<AtomicUsize>,
+    /// 連続失敗回数（健康チェック用）
+    pub consecutive_failures: Arc<AtomicUsize>,
+    /// サーキットブレーカー（F-06、有効時のみ Some）
+    pub circuit_breaker: Option<crate::resilience::CircuitBreaker>,
+    /// 異常検知用エラー率ウィンドウ（F-06）
+    pub error_rate_window: Arc<std::sync::Mutex<crate::resilience::SlidingWindow>>,
+    /// EWMA レイテンシ（ミリ秒、F-06）
+    pub avg_latency_ms: Arc<AtomicU64>,
+    /// 排除期限（F-06、Some の間は select 対象外）
+    pub ejected_until: Arc<std::sync::Mutex<Option<std::time::Instant>>>,
+    /// 優先度ティア（F-133、0 が最優先）
+    pub priority: u32,
+    /// 直近の復帰時刻（F-133、[`monotonic_ms`] 基準。0 は復帰イベントなし）
+    pub recovered_at_ms: Arc<AtomicU64>,
+    /// パッシブヘルスチェックの状態（F-144）
+    pub passive: Arc<crate::passive_health::PassiveHealthState>,
+}
+
+/// プロセス起動時を基準とした単調ミリ秒（F-133 スロースタート用、常に 1 以上）
+pub fn monotonic_ms() -> u64 {
+    static EPOCH: Lazy<std::time::Instant> = Lazy::new(std::time::Instant::now);
+    EPOCH.elapsed().as_millis() as u64 + 1
+}
+
+impl UpstreamServer {
+    pub fn new(target: ProxyTarget) -> Self {
+        Self {
+            target,
+            active_connections: Arc::new(AtomicUsize::new(0)),
+            healthy: Arc::new(AtomicBool::new(true)),
+            consecutive_successes: Arc::new(AtomicUsize::new(0)),
+            consecutive_failures: Arc::new(AtomicUsize::new(0)),
+            circuit_breaker: None,
+            error_rate_window: Arc::new(std::sync::Mutex::new(
+                crate::resilience::SlidingWindow::new(std::time::Duration::from_secs(10)),
+            )),
+            avg_latency_ms: Arc::new(AtomicU64::new(0)),
+            ejected_until: Arc::new(std::sync::Mutex::new(None)),
+            priority: 0,
+            recovered_at_ms: Arc::new(AtomicU64::new(0)),
+            passive: Arc::new(crate::passive_health::PassiveHealthState::default()),
+        }
+    }
+
+    /// 優先度ティアを設定したコピーを返す（F-133）
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// 復帰（healthy 化・排除解除）を記録する（F-133、スロースタートの起点）
+    pub fn mark_recovered(&self) {
+        self.recovered_at_ms
+            .store(monotonic_ms(), Ordering::Relaxed);
+    }
+
+    /// スロースタートの実効重み係数（F-133、ランプ中でなければ 1.0）
+    pub fn slow_start_factor(&self, cfg: &SlowStartConfig) -> f64 {
+        let since = self.recovered_at_ms.load(Ordering::Relaxed);
+        if since == 0 || cfg.window_secs == 0 {
+            return 1.0;
+        }
+        cfg.factor(monotonic_ms().saturating_sub(since))
+    }
+
+    /// サーキットブレーカーを設定したコピーを返す
+    pub fn with_circuit_breaker(mut self, cb: Option<crate::resilience::CircuitBreaker>) -> Self {
+        self.circuit_breaker = cb;
+        self
+    }
+
+    /// このサーバーが排除中かどうか（排除期限を過ぎていれば自動復帰）
+    pub fn is_ejected(&self) -> bool {
+        let mut guard = match self.ejected_until.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        match *guard {
+            Some(until) => {
+                if std::time::Instant::now() >= until {
+                    *guard = None;
+                    self.mark_recovered();
+                    false
+                } else {
+                    true
+                }
+            }
+            None => false,
+        }
+    }
+
+    /// 指定時間だけサーバーを排除する
+    pub fn eject_for(&self, dur: std::time::Duration) {
+        if let Ok(mut guard) = self.ejected_until.lock() {
+            *guard = Some(std::time::Instant::now() + dur);
+        }
+    }
+
+    /// リクエスト結果を記録し、必要なら排除判定を行う（パッシブ Outlier Detection）
+    ///
+    /// `outlier` が None または無効の場合は EWMA レイテンシのみ更新する。
+    pub fn record_outcome(&self, success: bool, latency_ms: u64, outlier: Option<&OutlierConfig>) {
+        // EWMA レイテンシ更新（係数 0.2）
+        let prev = self.avg_latency_ms.load(Ordering::Relaxed);
+        let next = if prev == 0 {
+            latency_ms
+        } else {
+            ((prev as f64) * 0.8 + (latency_ms as f64) * 0.2) as u64
+        };
+        self.avg_latency_ms.store(next, Ordering::Relaxed);
+
+        // サーキットブレーカーへ反映
+        if let Some(cb) = &self.circuit_breaker {
+            if success {
+                cb.record_success();
+            } else {
+                cb.record_failure();
+            }
+        }
+
+        // Outlier Detection
+        if let Some(cfg) = outlier {
+            if !cfg.enabled {
+                return;
+            }
+            let (total, errors) = {
+                let mut w = match self.error_rate_window.lock() {
+                    Ok(g) => g,
+                    Err(p) => p.into_inner(),
+                };
+                w.record(success);
+                (w.total(), w.failures())
+            };
+            // 一定サンプル数を超えたらエラー率を評価
+            if total >= 5 {
+                let rate = errors as f64 / total as f64;
+                if rate >= cfg.error_rate_threshold {
+                    self.eject_for(std::time::Duration::from_secs(cfg.base_ejection_time_secs));
+                }
+            }
+        }
+    }
+
+    /// 実トラフィックの結果をパッシブヘルスチェックへ反映する（F-144）
+    ///
+    /// 閾値に達して healthy から unhealthy へ遷移したら、判定した規則を返す。
+    pub fn record_passive(
+        &self,
+        cfg: &PassiveHealthConfig,
+        outcome: crate::passive_health::PassiveOutcome,
+    ) -> Option<crate::passive_health::PassiveRule> {
+        let rule = self.passive.observe(cfg, outcome)?;
+        if !self.healthy.swap(false, Ordering::SeqCst) {
+            return None;
+        }
+        self.consecutive_successes.store(0, Ordering::Relaxed);
+        self.passive.trip(cfg, rule.describe(cfg));
+        warn!(
+            "Upstream {}:{} is now unhealthy ({})",
+            self.target.host,
+            self.target.port,
+            rule.describe(cfg)
+        );
+        Some(rule)
+    }
+
+    /// パッシブ判定の復帰タイマーが満了していれば healthy に戻す（戻したら true、F-144）
+    pub fn expire_passive(&self, now_ms: u64) -> bool {
+        if !self.passive.take_expired(now_ms) {
+            return false;
+        }
+        self.passive.clear();
+        if self.healthy.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        self.mark_recovered();
+        info!(
+            "Upstream {}:{} is now healthy (passive recovery timer)",
+            self.target.host, self.target.port
+        );
+        true
+    }
+
+    /// 接続カウンターを増加
+    pub fn acquire(&self) {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 接続カウンターを減少
+    pub fn release(&self) {
+        self.active_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// 現在の接続数を取得
+    pub fn connections(&self) -> usize {
+        self.active_connections.load(Ordering::Relaxed)
+    }
+
+    /// サーバーが健全かどうか
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    /// 健康チェック成功を記録（healthy へ遷移したら true、F-143 の遷移履歴用）
+    pub fn record_success(&self, healthy_threshold: u32) -> bool {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        let successes = self.consecutive_successes.fetch_add(1, Ordering::Relaxed) + 1;
+
+        // 閾値に達したら healthy に設定
+        if successes >= healthy_threshold as usize && !self.is_healthy() {
+            self.healthy.store(true, Ordering::SeqCst);
+            self.passive.clear();
+            self.mark_recovered();
+            info!(
+                "Upstream {}:{} is now healthy",
+                self.target.host, self.target.port
+            );
+            return true;
+        }
+        false
+    }
+
+    /// 健康チェック失敗を記録（unhealthy へ遷移したら true）
+    pub fn record_failure(&self, unhealthy_threshold: u32) -> bool {
+        self.consecutive_successes.store(0, Ordering::Relaxed);
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        // F-144: パッシブ判定で落ちている間に失敗したら復帰はアクティブチェックに任せる
+        self.passive.cancel_timer();
+
+        // 閾値に達したら unhealthy に設定
+        if failures >= unhealthy_threshold as usize && self.is_healthy() {
+            self.healthy.store(false, Ordering::SeqCst);
+            warn!(
+                "Upstream {}:{} is now unhealthy",
+                self.target.host, self.target.port
+            );
+            return true;
+        }
+        false
+    }
+
+    /// Get the host address
+    pub fn host(&self) -> &str {
+        &self.target.host
+    }
+
+    /// Get the port number
+    pub fn port(&self) -> u16 {
+        self.target.port
+    }
+
+    /// Check if TLS is enabled
+    pub fn use_tls(&self) -> bool {
+        self.target.use_tls
+    }
+}
+
+/// `HashKey::Path` 用にリクエストパスからクエリを除いた部分を取り出す
+pub(crate) fn path_hash_value(path: &[u8]) -> Option<&str> {
+    let end = path.iter().position(|&b| b == b'?').unwrap_or(path.len());
+    match std::str::from_utf8(&path[..end]) {
+        Ok(p) if !p.is_empty() => Some(p),
+        _ => None,
+    }
+}
+
+/// Consistent Hash の仮想ノード数（サーバーあたり）
+const CONSISTENT_HASH_VNODES: usize = 150;
+/// Consistent Hash 用のシード（固定）
+const CONSISTENT_HASH_SEED: u64 = 0x9E3779B97F4A7C15;
+/// スロースタートの受け入れ判定用シード（F-133、リング配置とは独立させる）
+const SLOW_START_SEED: u64 = 0x165667B19E3779F9;
+/// Maglev の skip 計算用シード（offset 用とは独立させる）
+const MAGLEV_SKIP_SEED: u64 = 0xC2B2AE3D27D4EB4F;
+/// Bounded-Load の負荷係数デフォルト（Google の論文推奨値 1.25）
+pub const DEFAULT_HASH_LOAD_FACTOR: f64 = 1.25;
+/// Maglev テーブルサイズのデフォルト（素数）
+pub const DEFAULT_MAGLEV_TABLE_SIZE: usize = 65537;
+
+/// n が素数かどうか（Maglev テーブルサイズ検証用、試し割り）
+pub(crate) fn is_prime(n: usize) -> bool {
+    if n < 2 {
+        return false;
+    }
+    if n.is_multiple_of(2) {
+        return n == 2;
+    }
+    let mut d = 3usize;
+    while d.saturating_mul(d) <= n {
+        if n.is_multiple_of(d) {
+            return false;
+        }
+        d += 2;
+    }
+    true
+}
+
+/// Upstream グループ（複数バックエンドのロードバランシング）
+#[derive(Clone)]
+pub struct UpstreamGroup {
+    /// グループ名（ログ出力用）
+    pub name: String,
+    /// バックエンドサーバーリスト
+    pub servers: Vec<UpstreamServer>,
+    /// ロードバランシングアルゴリズム
+    pub algorithm: LoadBalanceAlgorithm,
+    /// ラウンドロビン用カウンター
+    pub rr_counter: Arc<AtomicUsize>,
+    /// 健康チェック設定（オプション）
+    pub health_check: Option<HealthCheckConfig>,
+    /// TLS証明書検証を無効化（自己署名証明書を許可）
+    pub tls_insecure: bool,
+    /// H2C (HTTP/2 over cleartext) を強制するかどうか
+    pub use_h2c: bool,
+    /// Weighted Round Robin 用の累積重みオフセット（構築時計算、O(1) 選択用）
+    /// servers と同じ順序。weighted_offsets[i] は servers[0..=i] の重みの累積和。
+    pub weighted_offsets: Vec<u32>,
+    /// 全サーバーの重み合計（0 の場合は Weighted を使わない）
+    pub total_weight: u32,
+    /// Consistent Hash の仮想ノードリング（(hash, server_idx) を hash 昇順でソート）
+    pub consistent_ring: Vec<(u64, usize)>,
+    /// Bounded-Load の負荷係数 c
+    pub hash_load_factor: f64,
+    /// Maglev ルックアップテーブル（slot -> server_idx、Maglev 時のみ構築）
+    pub maglev_table: Arc<Vec<u32>>,
+    /// プロキシ発行のスティッキー Cookie（F-132、設定時のみ Some）
+    pub sticky: Option<Arc<crate::sticky::StickyCookie>>,
+    /// スティッキー Cookie 用のサーバー安定 ID（servers と同順、sticky 設定時のみ）
+    pub sticky_ids: Vec<u64>,
+    /// 優先度ティア `(priority, ティア内の重み合計)`（priority 昇順、F-133）
+    pub tiers: Vec<(u32, u32)>,
+    /// ティアのフェイルオーバー閾値（F-133）
+    pub priority_failover_threshold: f64,
+    /// スロースタート設定（F-133）
+    pub slow_start: SlowStartConfig,
+    /// スロースタートの受け入れ判定用シーケンス（ハッシュ系以外、F-133）
+    pub slow_start_seq: Arc<AtomicU64>,
+    /// 異常検知設定（select 時に排除中サーバーを除外するために保持）
+    pub outlier_detection: OutlierConfig,
+    /// パッシブヘルスチェック設定（F-144、設定時のみ Some）
+    pub passive_health: Option<PassiveHealthConfig>,
+
+    /// ルートのリクエストヘッジングポリシー（F-137、ルート設定時のみ Some）
+    pub hedging: Option<Arc<crate::hedging::HedgePolicy>>,
+    /// 応答開始までの時間の観測値（F-137、グループ内の全ルートで共有）
+    pub header_latency: Arc<crate::hedging::LatencyHistogram>,
+    /// 適応型の同時実行数制限（F-138、設定時のみ Some。グループ内の全ルートで共有）
+    pub concurrency_limiter: Option<Arc<crate::resilience::AdaptiveConcurrency>>,
+}
+
+impl UpstreamGroup {
+    /// 新しい Upstream グループを作成
+    pub fn new(
+        name: String,
+        entries: Vec<UpstreamServerEntry>,
+        algorithm: LoadBalanceAlgorithm,
+        health_check: Option<HealthCheckConfig>,
+        tls_insecure: bool,
+    ) -> Option<Self> {
+        // 有効なエントリのみを (entry, server) として残す
+        let pairs: Vec<(UpstreamServerEntry, UpstreamServer)> = entries
+            .iter()
+            .filter_map(|entry| {
+                ProxyTarget::parse(&entry.url)
+                    .map(|target| target.with_sni_name(entry.sni_name.clone()))
+                    .map(|target| target.with_h2c(entry.use_h2c))
+                    .map(|target| {
+                        let server = UpstreamServer::new(target).with_priority(entry.priority);
+                        (entry.clone(), server)
+                    })
+            })
+            .collect();
+
+        if pairs.is_empty() {
+            return None;
+        }
+
+        let servers: Vec<UpstreamServer> = pairs.iter().map(|(_, s)| s.clone()).collect();
+
+        // Weighted Round Robin 用の累積オフセットを構築
+        let mut weighted_offsets = Vec::with_capacity(pairs.len());
+        let mut acc: u32 = 0;
+        for (entry, _) in &pairs {
+            acc = acc.saturating_add(entry.weight.max(1));
+            weighted_offsets.push(acc);
+        }
+        let total_weight = acc;
+
+        // Consistent Hash 用の仮想ノードリングを構築
+        let consistent_ring = Self::build_ring(&pairs);
+
+        // 優先度ティア（priority 昇順）とティアごとの重み合計
+        let mut tiers: Vec<(u32, u32)> = Vec::new();
+        for (entry, _) in &pairs {
+            match tiers.iter_mut().find(|(p, _)| *p == entry.priority) {
+                Some((_, w)) => *w = w.saturating_add(entry.weight.max(1)),
+                None => tiers.push((entry.priority, entry.weight.max(1))),
+            }
+        }
+        tiers.sort_by_key(|(p, _)| *p);
+
+        let mut group = Self {
+            name,
+            servers,
+            algorithm,
+            rr_counter: Arc::new(AtomicUsize::new(0)),
+            health_check,
+            tls_insecure,
+            use_h2c: false, // デフォルトでは各サーバーの設定に従う
+            weighted_offsets,
+            total_weight,
+            consistent_ring,
+            hash_load_factor: DEFAULT_HASH_LOAD_FACTOR,
+            maglev_table: Arc::new(Vec::new()),
+            sticky: None,
+            sticky_ids: Vec::new(),
+            tiers,
+            priority_failover_threshold: 0.0,
+            slow_start: SlowStartConfig::default(),
+            slow_start_seq: Arc::new(AtomicU64::new(0)),
+            outlier_detection: OutlierConfig::default(),
+            passive_health: None,
+            hedging: None,
+            header_latency: Arc::new(crate::hedging::LatencyHistogram::new()),
+            concurrency_limiter: None,
+        };
+        if matches!(group.algorithm, LoadBalanceAlgorithm::Maglev { .. }) {
+            group.maglev_table = Arc::new(group.build_maglev_table(DEFAULT_MAGLEV_TABLE_SIZE));
+        }
+        Some(group)
+    }
+
+    /// ハッシュ系アルゴリズムのパラメータを適用したグループを返す（設定読み込み時に使用）
+    ///
+    /// `maglev_table_size` は `validate_config` で検証済み（素数かつサーバー数超）の値をそのまま使う。
+    pub fn with_hash_options(mut self, load_factor: f64, maglev_table_size: usize) -> Self {
+        self.hash_load_factor = if load_factor.is_finite() && load_factor > 1.0 {
+            load_factor
+        } else {
+            DEFAULT_HASH_LOAD_FACTOR
+        };
+        if matches!(self.algorithm, LoadBalanceAlgorithm::Maglev { .. })
+            && maglev_table_size != self.maglev_table.len()
+        {
+            self.maglev_table = Arc::new(self.build_maglev_table(maglev_table_size));
+        }
+        self
+    }
+
+    /// スティッキー Cookie を設定したグループを返す（設定読み込み時に使用、F-132）
+    pub fn with_sticky_cookie(mut self, cfg: Option<&StickyCookieConfig>) -> Self {
+        match cfg {
+            Some(cfg) => {
+                self.sticky = Some(Arc::new(crate::sticky::StickyCookie::new(cfg, &self.name)));
+                self.sticky_ids = self
+                    .servers
+                    .iter()
+                    .map(|s| crate::sticky::StickyCookie::server_id(&s.target))
+                    .collect();
+            }
+            None => {
+                self.sticky = None;
+                self.sticky_ids.clear();
+            }
+        }
+        self
+    }
+
+    /// スロースタートと優先度ティアを適用したグループを返す（設定読み込み時に使用、F-133）
+    pub fn with_traffic_shaping(mut self, slow_start: &SlowStartConfig, threshold: f64) -> Self {
+        self.slow_start = slow_start.clone();
+        self.priority_failover_threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 送信元アドレス設定を全サーバーへ適用したグループを返す（設定読み込み時に使用、F-141）
+    pub fn with_source(mut self, source: Option<Arc<UpstreamSource>>) -> Self {
+        for server in &mut self.servers {
+            server.target.source = source.clone();
+        }
+        self
+    }
+
+    /// ソケットプロファイルを全サーバーへ適用したグループを返す（設定読み込み時に使用、F-142）
+    pub fn with_socket(mut self, socket: Option<Arc<SocketOptions>>) -> Self {
+        for server in &mut self.servers {
+            server.target.socket = socket.clone();
+        }
+        self
+    }
+
+    /// コネクションプール設定を全サーバーへ適用したグループを返す（設定読み込み時に使用、F-136）
+    pub fn with_connection_pool(mut self, cfg: &ConnectionPoolConfig) -> Self {
+        for server in &mut self.servers {
+            server.target.connection_pool = *cfg;
+        }
+        self
+    }
+
+    /// 上流プロトコルを全サーバーへ適用したグループを返す（設定読み込み時に使用、F-134）
+    pub fn with_protocol(
+        mut self,
+        protocol: UpstreamProtocol,
+        max_streams: u32,
+        max_response_body: usize,
+    ) -> Self {
+        for server in &mut self.servers {
+            server.target =
+                server
+                    .target
+                    .clone()
+                    .with_protocol(protocol, max_streams, max_response_body);
+        }
+        self
+    }
+
+    /// いずれかのサーバーが HTTP/2（h2c / ALPN h2）または HTTP/3 で接続するか（F-134、F-135）
+    ///
+    /// ストリーミング経路は HTTP/1.1 上流専用のため、該当グループはバッファ経路で扱う。
+    pub fn uses_multiplexed_upstream(&self) -> bool {
+        self.use_h2c
+            || self
+                .servers
+                .iter()
+                .any(|s| s.target.use_h2c || s.target.uses_tls_h2() || s.target.uses_h3())
+    }
+
+    /// Maglev ルックアップテーブルを構築する（重み付き、テーブルサイズは素数前提）
+    ///
+    /// 各サーバーは host:port から offset / skip を導出した置換列を持ち、
+    /// 重みの回数だけ順番に空きスロットを埋める。メンバー変更時に移動する
+    /// スロットは概ね変更されたサーバーの担当分に限られる。
+    fn build_maglev_table(&self, size: usize) -> Vec<u32> {
+        use xxhash_rust::xxh3::xxh3_64_with_seed;
+        let n = self.servers.len();
+        if n == 0 || size < 2 {
+            return Vec::new();
+        }
+        let perms: Vec<(usize, usize)> = self
+            .servers
+            .iter()
+            .map(|s| {
+                let id = format!("{}:{}", s.target.host, s.target.port);
+                let offset =
+                    (xxh3_64_with_seed(id.as_bytes(), CONSISTENT_HASH_SEED) as usize) % size;
+                let skip =
+                    (xxh3_64_with_seed(id.as_bytes(), MAGLEV_SKIP_SEED) as usize) % (size - 1) + 1;
+                (offset, skip)
+            })
+            .collect();
+        let mut next = vec![0usize; n];
+        let mut table = vec![u32::MAX; size];
+        let mut filled = 0usize;
+        while filled < size {
+            let before = filled;
+            for (i, &(offset, skip)) in perms.iter().enumerate() {
+                for _ in 0..self.weight_of(i) {
+                    // 置換列に沿って次の空きスロットを探す（素数サイズなら必ず見つかる）
+                    loop {
+                        let slot = (offset + next[i] * skip) % size;
+                        next[i] += 1;
+                        if table[slot] == u32::MAX {
+                            table[slot] = i as u32;
+                            filled += 1;
+                            break;
+                        }
+                        if next[i] > size * 2 {
+                            break;
+                        }
+                    }
+                    if filled == size {
+                        return table;
+                    }
+                }
+            }
+            if filled == before {
+                break;
+            }
+        }
+        table
+    }
+
+    /// 仮想ノードリングを構築する（サーバーごとに CONSISTENT_HASH_VNODES 個の vnode）
+    fn build_ring(pairs: &[(UpstreamServerEntry, UpstreamServer)]) -> Vec<(u64, usize)> {
+        use xxhash_rust::xxh3::xxh3_64_with_seed;
+        let mut ring: Vec<(u64, usize)> = Vec::with_capacity(pairs.len() * CONSISTENT_HASH_VNODES);
+        for (idx, (_, server)) in pairs.iter().enumerate() {
+            // サーバー識別子（host:port）を基に vnode を生成
+            let id = format!("{}:{}", server.target.host, server.target.port);
+            for vnode in 0..CONSISTENT_HASH_VNODES {
+                let key = format!("{}#{}", id, vnode);
+                let h = xxh3_64_with_seed(key.as_bytes(), CONSISTENT_HASH_SEED);
+                ring.push((h, idx));
+            }
+        }
+        ring.sort_by_key(|(h, _)| *h);
+        ring
+    }
+
+    /// サーキットブレーカー・異常検知を適用したグループを返す（設定読み込み時に使用）
+    pub fn with_resilience(
+        mut self,
+        cb_config: &CircuitBreakerConfig,
+        outlier: &OutlierConfig,
+    ) -> Self {
+        if cb_config.enabled {
+            for server in &mut self.servers {
+                server.circuit_breaker =
+                    Some(crate::resilience::CircuitBreaker::new(cb_config.clone()));
+            }
+        }
+        // error_rate_window の長さを interval に合わせて再構築
+        if outlier.enabled {
+            for server in &mut self.servers {
+                server.error_rate_window = Arc::new(std::sync::Mutex::new(
+                    crate::resilience::SlidingWindow::new(std::time::Duration::from_secs(
+                        outlier.interval_secs,
+                    )),
+                ));
+            }
+        }
+        self.outlier_detection = outlier.clone();
+        self
+    }
+
+    /// パッシブヘルスチェックを適用したグループを返す（設定読み込み時に使用、F-144）
+    pub fn with_passive_health(mut self, cfg: Option<&PassiveHealthConfig>) -> Self {
+        if let Some(cfg) = cfg {
+            let window = std::time::Duration::from_secs(cfg.gateway_error_window_secs);
+            for server in &mut self.servers {
+                server.passive = Arc::new(crate::passive_health::PassiveHealthState::new(window));
+            }
+        }
+        self.passive_health = cfg.cloned();
+        self
+    }
+
+    /// 適応型の同時実行数制限を適用したグループを返す（設定読み込み時に使用、F-138）
+    pub fn with_adaptive_concurrency(mut self, cfg: &AdaptiveConcurrencyConfig) -> Self {
+        self.concurrency_limiter = cfg
+            .enabled
+            .then(|| Arc::new(crate::resilience::AdaptiveConcurrency::new(cfg)));
+        self
+    }
+
+    /// 単一サーバーからグループを作成
+    pub fn single(target: ProxyTarget) -> Self {
+        let server = UpstreamServer::new(target);
+        Self {
+            name: String::new(),
+            servers: vec![server],
+            algorithm: LoadBalanceAlgorithm::RoundRobin,
+            rr_counter: Arc::new(AtomicUsize::new(0)),
+            health_check: None,  // 単一サーバーでは健康チェックなし
+            tls_insecure: false, // 単一サーバーではデフォルトで証明書検証を有効
+            use_h2c: false,
+            weighted_offsets: vec![1],
+            total_weight: 1,
+            consistent_ring: Vec::new(),
+            hash_load_factor: DEFAULT_HASH_LOAD_FACTOR,
+            maglev_table: Arc::new(Vec::new()),
+            sticky: None,
+            sticky_ids: Vec::new(),
+            tiers: vec![(0, 1)],
+            priority_failover_threshold: 0.0,
+            slow_start: SlowStartConfig::default(),
+            slow_start_seq: Arc::new(AtomicU64::new(0)),
+            outlier_detection: OutlierConfig::default(),
+            passive_health: None,
+            hedging: None,
+            header_latency: Arc::new(crate::hedging::LatencyHistogram::new()),
+            concurrency_limiter: None,
+        }
+    }
+
+    /// H2C設定を変更したコピーを作成
+    pub fn with_h2c(&self, use_h2c: bool) -> Self {
+        let mut new_group = self.clone();
+        new_group.use_h2c = use_h2c;
+        new_group
+    }
+
+    /// ルートのヘッジング設定を適用したコピーを作成（F-137）
+    ///
+    /// サーバー状態と応答時間の観測値は元のグループと共有し、ヘッジ予算はルートごとに持つ。
+    pub fn with_hedging(&self, hedging: Option<&HedgingConfig>) -> Self {
+        let mut new_group = self.clone();
+        new_group.hedging = hedging.map(|cfg| Arc::new(crate::hedging::HedgePolicy::new(cfg)));
+        new_group
+    }
+
+    /// ヘッジ先として `exclude` 以外のサーバーを選択する（F-137）
+    ///
+    /// 通常の候補（アクティブなティア）から選び、候補が `exclude` のみの場合は
+    /// 下位ティアを含む利用可能なサーバーから選ぶ。該当がなければ None。
+    pub fn select_alternate(
+        &self,
+        exclude: &UpstreamServer,
+        client_ip: &str,
+    ) -> Option<&UpstreamServer> {
+        let mut candidates = self.candidates();
+        candidates.retain(|(_, s)| !std::ptr::eq(*s, exclude));
+        if candidates.is_empty() {
+            candidates = self
+                .servers
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| !std::ptr::eq(*s, exclude) && Self::is_available(s))
+                .collect();
+        }
+        self.pick(&candidates, client_ip, None)
+    }
+
+    /// サーバーが選択可能か（healthy・排除されていない・CB が許可）
+    fn is_available(server: &UpstreamServer) -> bool {
+        server.is_healthy()
+            && !server.is_ejected()
+            && match &server.circuit_breaker {
+                Some(cb) => cb.allow_request(),
+                None => true,
+            }
+    }
+
+    /// 選択候補となるサーバーを抽出（healthy かつ排除されていないもの）
+    ///
+    /// サーキットブレーカーが Open のサーバーも除外する。優先度ティアがある場合は
+    /// [`Self::apply_tiers`] で下位ティアを絞り込む（全アルゴリズム共通、F-133）。
+    fn candidates(&self) -> Vec<(usize, &UpstreamServer)> {
+        if self.passive_health.is_some() {
+            self.expire_passive();
+        }
+        let avail: Vec<(usize, &UpstreamServer)> = self
+            .servers
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| Self::is_available(s))
+            .collect();
+        let pool = if !avail.is_empty() {
+            avail
+        } else {
+            // 全て利用不可なら healthy なものへフォールバック（全滅回避）
+            self.servers
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.is_healthy())
+                .collect()
+        };
+        if self.tiers.len() > 1 {
+            self.apply_tiers(pool)
+        } else {
+            pool
+        }
+    }
+
+    /// 優先度ティアで候補を絞り込む（F-133）
+    ///
+    /// priority の高い順にティアを加え、加えたティアの利用可能容量（重み比）が
+    /// `priority_failover_threshold` 以上になった時点で打ち切る。容量 0 のティアは
+    /// 常に次のティアへ進む。
+    fn apply_tiers<'a>(
+        &self,
+        pool: Vec<(usize, &'a UpstreamServer)>,
+    ) -> Vec<(usize, &'a UpstreamServer)> {
+        let mut out = Vec::with_capacity(pool.len());
+        for &(priority, tier_weight) in &self.tiers {
+            let mut avail_weight: u32 = 0;
+            for &(idx, s) in pool.iter().filter(|(_, s)| s.priority == priority) {
+                avail_weight = avail_weight.saturating_add(self.weight_of(idx));
+                out.push((idx, s));
+            }
+            if avail_weight > 0
+                && (avail_weight as f64 / tier_weight.max(1) as f64)
+                    >= self.priority_failover_threshold
+            {
+                break;
+            }
+        }
+        out
+    }
+
+    /// 次のバックエンドサーバーを選択
+    ///
+    /// # Arguments
+    /// * `client_ip` - クライアントIPアドレス（IpHash / ConsistentHash 用）
+    ///
+    /// # Returns
+    /// 選択されたサーバーへの参照（健全なサーバーがない場合は None）
+    pub fn select(&self, client_ip: &str) -> Option<&UpstreamServer> {
+        self.select_with_key(client_ip, None, None)
+    }
+
+    /// リクエストヘッダから Consistent Hash キーを解決してサーバーを選択する。
+    ///
+    /// - `ConsistentHash { Header }` / `Cookie` のときだけ `get_header` を呼ぶ
+    ///   （それ以外のアルゴリズムではヘッダ走査ゼロ — ホットパス最適化）。
+    /// - 値が取れない場合は `client_ip` にフォールバックする。
+    ///
+    /// `get_header` はヘッダ名（小文字比較用バイト列）を受け取り、値のバイト列を返す。
+    /// Cookie 名は `HashKey::Cookie` の名前で `cookie` ヘッダをパースする。
+    pub fn select_with_header_fn<'a, F>(
+        &'a self,
+        client_ip: &str,
+        get_header: F,
+    ) -> Option<&'a UpstreamServer>
+    where
+        F: FnMut(&[u8]) -> Option<&'a [u8]>,
+    {
+        self.select_for_request(client_ip, b"", get_header)
+    }
+
+    /// リクエスト属性（ヘッダ・パス）からハッシュキーを解決してサーバーを選択する。
+    ///
+    /// `select_with_header_fn` に加えて `HashKey::Path` 用のパスを受け取る。
+    /// パスはクエリ文字列を除いた部分をキーとし、空の場合は `client_ip` へ
+    /// フォールバックする。
+    pub fn select_for_request<'a, F>(
+        &'a self,
+        client_ip: &str,
+        path: &[u8],
+        mut get_header: F,
+    ) -> Option<&'a UpstreamServer>
+    where
+        F: FnMut(&[u8]) -> Option<&'a [u8]>,
+    {
+        match self.algorithm.hash_key() {
+            Some(HashKey::Header(name)) => {
+                let val = get_header(name.as_bytes()).and_then(|v| std::str::from_utf8(v).ok());
+                self.select_with_key(client_ip, val, None)
+            }
+            Some(HashKey::Cookie(name)) => {
+                let cookie_hdr = get_header(b"cookie").and_then(|v| std::str::from_utf8(v).ok());
+                let val = cookie_hdr.and_then(|c| extract_cookie_value(c, name));
+                self.select_with_key(client_ip, val, None)
+            }
+            Some(HashKey::Path) => {
+                let val = path_hash_value(path);
+                self.select_with_key(client_ip, val, None)
+            }
+            _ => self.select(client_ip),
+        }
+    }
+
+    /// スティッキー Cookie を考慮してサーバーを選択する（F-132）
+    ///
+    /// 署名が有効な Cookie が指すサーバーが選択可能ならそれを返す。Cookie が無い・
+    /// 不正・期限切れ、または固定先が利用不可の場合は `select_for_request` で
+    /// 選び直し、新しい固定先の `Set-Cookie` 値を併せて返す（透過的な再固定）。
+    /// スティッキー未設定のグループでは常に `(server, None)`。
+    pub fn select_with_affinity<'a, F>(
+        &'a self,
+        client_ip: &str,
+        path: &[u8],
+        mut get_header: F,
+    ) -> Option<(&'a UpstreamServer, Option<String>)>
+    where
+        F: FnMut(&[u8]) -> Option<&'a [u8]>,
+    {
+        let sticky = match &self.sticky {
+            Some(s) => s,
+            None => {
+                return self
+                    .select_for_request(client_ip, path, get_header)
+                    .map(|s| (s, None));
+            }
+        };
+        let pinned = get_header(b"cookie")
+            .and_then(|v| std::str::from_utf8(v).ok())
+            .and_then(|c| extract_cookie_value(c, sticky.name()))
+            .and_then(|v| sticky.decode(v));
+        if let Some(id) = pinned {
+            if let Some(idx) = self.sticky_ids.iter().position(|x| *x == id) {
+                // 利用可能かつ現在アクティブなティア（F-133）に含まれる場合のみ固定を維持
+                let pinned_ok = if self.tiers.len() > 1 {
+                    self.candidates().iter().any(|(i, _)| *i == idx)
+                } else {
+                    Self::is_available(&self.servers[idx])
+                };
+                if pinned_ok {
+                    return Some((&self.servers[idx], None));
+                }
+            }
+        }
+        let server = self.select_for_request(client_ip, path, &mut get_header)?;
+        let cookie = self
+            .servers
+            .iter()
+            .position(|s| std::ptr::eq(s, server))
+            .and_then(|idx| self.sticky_ids.get(idx))
+            .map(|id| sticky.set_cookie_value(*id));
+        Some((server, cookie))
+    }
+
+    /// ハッシュキーの値を指定してサーバーを選択する
+    ///
+    /// ハッシュ系アルゴリズムで `header:` / `cookie:` / `path` を使う場合は、
+    /// 呼び出し側で該当値を解決して `hash_value` に渡す。値が解決
+    /// できない場合は client_ip にフォールバックする。
+    ///
+    /// スロースタート中（F-133）のサーバーが選ばれた場合は実効重み係数の確率で
+    /// 受け入れ、外れたら当該サーバーを除いて選び直す。ハッシュ系アルゴリズムでは
+    /// キーのハッシュで判定するため、同じキーは係数が上がるまで同じ結果になる。
+    pub fn select_with_key(
+        &self,
+        client_ip: &str,
+        hash_value: Option<&str>,
+        _unused: Option<()>,
+    ) -> Option<&UpstreamServer> {
+        let mut candidates = self.candidates();
+        loop {
+            let server = self.pick(&candidates, client_ip, hash_value)?;
+            if candidates.len() <= 1 || self.slow_start.window_secs == 0 {
+                return Some(server);
+            }
+            let factor = server.slow_start_factor(&self.slow_start);
+            if factor >= 1.0 || self.slow_start_sample(client_ip, hash_value) < factor {
+                return Some(server);
+            }
+            candidates.retain(|(_, s)| !std::ptr::eq(*s, server));
+        }
+    }
+
+    /// スロースタート受け入れ判定用の [0, 1) の値（F-133）
+    fn slow_start_sample(&self, client_ip: &str, hash_value: Option<&str>) -> f64 {
+        use xxhash_rust::xxh3::xxh3_64_with_seed;
+        let bits = match self.algorithm.hash_key() {
+            Some(hash_key) => {
+                let key = Self::hash_input(hash_key, client_ip, hash_value);
+                xxh3_64_with_seed(key.as_bytes(), SLOW_START_SEED)
+            }
+            // Weyl 列（黄金比）で一様に分散させる
+            None => self
+                .slow_start_seq
+                .fetch_add(1, Ordering::Relaxed)
+                .wrapping_mul(0x9E37_79B9_7F4A_7C15),
+        };
+        (bits >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// アルゴリズムに従って候補からサーバーを 1 台選ぶ
+    fn pick<'a>(
+        &'a self,
+        candidates: &[(usize, &'a UpstreamServer)],
+        client_ip: &str,
+        hash_value: Option<&str>,
+    ) -> Option<&'a UpstreamServer> {
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let selected_idx = match &self.algorithm {
+            LoadBalanceAlgorithm::RoundRobin => {
+                let counter = self.rr_counter.fetch_add(1, Ordering::Relaxed);
+                counter % candidates.len()
+            }
+            LoadBalanceAlgorithm::LeastConnections => candidates
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (_, s))| s.connections())
+                .map(|(i, _)| i)
+                .unwrap_or(0),
+            LoadBalanceAlgorithm::IpHash => {
+                let hash = Self::fnv1a(client_ip.as_bytes());
+                (hash as usize) % candidates.len()
+            }
+            LoadBalanceAlgorithm::Weighted => {
+                // 健全なサーバー集合に対する重み合計を計算し、
+                // rr_counter を total で割った余りで二分探索する。
+                return self.select_weighted(candidates);
+            }
+            LoadBalanceAlgorithm::ConsistentHash { hash_key } => {
+                let key = Self::hash_input(hash_key, client_ip, hash_value);
+                return self.select_consistent(key, candidates);
+            }
+            LoadBalanceAlgorithm::BoundedLoadHash { hash_key } => {
+                let key = Self::hash_input(hash_key, client_ip, hash_value);
+                return self.select_bounded(key, candidates);
+            }
+            LoadBalanceAlgorithm::Maglev { hash_key } => {
+                let key = Self::hash_input(hash_key, client_ip, hash_value);
+                return self.select_maglev(key, candidates);
+            }
+        };
+
+        candidates.get(selected_idx).map(|(_, s)| *s)
+    }
+
+    /// ハッシュ対象の値を決定（解決できなければ client_ip）
+    fn hash_input<'k>(
+        hash_key: &HashKey,
+        client_ip: &'k str,
+        hash_value: Option<&'k str>,
+    ) -> &'k str {
+        match hash_key {
+            HashKey::Ip => client_ip,
+            HashKey::Header(_) | HashKey::Cookie(_) | HashKey::Path => {
+                hash_value.unwrap_or(client_ip)
+            }
+        }
+    }
+
+    /// FNV-1a ハッシュ（IpHash 用）
+    fn fnv1a(bytes: &[u8]) -> u64 {
+        let mut hash: u64 = 14695981039346656037;
+        for &byte in bytes {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(1099511628211);
+        }
+        hash
+    }
+
+    /// Weighted Round Robin 選択（候補内の重みで按分）
+    fn select_weighted<'a>(
+        &'a self,
+        candidates: &[(usize, &'a UpstreamServer)],
+    ) -> Option<&'a UpstreamServer> {
+        // 候補（healthy）の重みを weighted_offsets から逆算して累積を作る
+        let mut cum: Vec<(u32, usize)> = Vec::with_capacity(candidates.len());
+        let mut acc: u32 = 0;
+        for (ci, (orig_idx, _)) in candidates.iter().enumerate() {
+            let w = self.weight_of(*orig_idx);
+            acc = acc.saturating_add(w);
+            cum.push((acc, ci));
+        }
+        let total = acc;
+        if total == 0 {
+            return candidates.first().map(|(_, s)| *s);
+        }
+        let pos = (self.rr_counter.fetch_add(1, Ordering::Relaxed) as u32) % total;
+        // pos < offset となる最初の要素を二分探索
+        let idx = match cum.binary_search_by(|(off, _)| {
+            if *off <= pos {
+                std::cmp::Ordering::Less
+            } else {
+                std::cmp::Ordering::Greater
+            }
+        }) {
+            Ok(i) => i,
+            Err(i) => i,
+        };
+        let ci = cum.get(idx).map(|(_, ci)| *ci).unwrap_or(0);
+        candidates.get(ci).map(|(_, s)| *s)
+    }
+
+    /// 元インデックスのサーバーの重みを取得
+    fn weight_of(&self, orig_idx: usize) -> u32 {
+        let prev = if orig_idx == 0 {
+            0
+        } else {
+            self.weighted_offsets
+                .get(orig_idx - 1)
+                .copied()
+                .unwrap_or(0)
+        };
+        let cur = self
+            .weighted_offsets
+            .get(orig_idx)
+            .copied()
+            .unwrap_or(prev + 1);
+        cur.saturating_sub(prev).max(1)
+    }
+
+    /// Consistent Hash 選択（リング上の二分探索）
+    fn select_consistent<'a>(
+        &'a self,
+        key: &str,
+        candidates: &[(usize, &'a UpstreamServer)],
+    ) -> Option<&'a UpstreamServer> {
+        use xxhash_rust::xxh3::xxh3_64_with_seed;
+        if self.consistent_ring.is_empty() {
+            // リング未構築（単一サーバー等）の場合はハッシュで按分
+            let h = xxh3_64_with_seed(key.as_bytes(), CONSISTENT_HASH_SEED);
+            let idx = (h as usize) % candidates.len();
+            return candidates.get(idx).map(|(_, s)| *s);
+        }
+        let h = xxh3_64_with_seed(key.as_bytes(), CONSISTENT_HASH_SEED);
+        // h 以上の最初の vnode を探す（なければ先頭へラップ）
+        let start = self.consistent_ring.partition_point(|(vh, _)| *vh < h);
+        let ring_len = self.consistent_ring.len();
+        // リングを start から一周し、候補に含まれる最初のサーバーを選ぶ
+        for offset in 0..ring_len {
+            let (_, server_idx) = self.consistent_ring[(start + offset) % ring_len];
+            if let Some((_, s)) = candidates.iter().find(|(oi, _)| *oi == server_idx) {
+                return Some(*s);
+            }
+        }
+        candidates.first().map(|(_, s)| *s)
+    }
+
+    /// Bounded-Load Consistent Hash 選択
+    ///
+    /// 上限 `ceil(c × (総接続数 + 1) / 候補数)` 未満のサーバーが見つかるまで
+    /// リングを時計回りに進む。全候補が上限に達している場合は最小接続数の候補。
+    fn select_bounded<'a>(
+        &'a self,
+        key: &str,
+        candidates: &[(usize, &'a UpstreamServer)],
+    ) -> Option<&'a UpstreamServer> {
+        use xxhash_rust::xxh3::xxh3_64_with_seed;
+        let total: usize = candidates.iter().map(|(_, s)| s.connections()).sum();
+        let cap = ((self.hash_load_factor * (total + 1) as f64) / candidates.len() as f64).ceil();
+        let cap = (cap as usize).max(1);
+        if !self.consistent_ring.is_empty() {
+            let h = xxh3_64_with_seed(key.as_bytes(), CONSISTENT_HASH_SEED);
+            let start = self.consistent_ring.partition_point(|(vh, _)| *vh < h);
+            let ring_len = self.consistent_ring.len();
+            for offset in 0..ring_len {
+                let (_, server_idx) = self.consistent_ring[(start + offset) % ring_len];
+                if let Some((_, s)) = candidates.iter().find(|(oi, _)| *oi == server_idx) {
+                    if s.connections() < cap {
+                        return Some(*s);
+                    }
+                }
+            }
+        }
+        candidates
+            .iter()
+            .min_by_key(|(_, s)| s.connections())
+            .map(|(_, s)| *s)
+    }
+
+    /// Maglev 選択（テーブル参照。担当サーバーが候補外なら後続スロットを探す）
+    fn select_maglev<'a>(
+        &'a self,
+        key: &str,
+        candidates: &[(usize, &'a UpstreamServer)],
+    ) -> Option<&'a UpstreamServer> {
+        use xxhash_rust::xxh3::xxh3_64_with_seed;
+        let table = &self.maglev_table;
+        if table.is_empty() {
+            return self.select_consistent(key, candidates);
+        }
+        let h = xxh3_64_with_seed(key.as_bytes(), CONSISTENT_HASH_SEED);
+        let start = (h as usize) % table.len();
+        for offset in 0..table.len() {
+            let server_idx = table[(start + offset) % table.len()] as usize;
+            if let Some((_, s)) = candidates.iter().find(|(oi, _)| *oi == server_idx) {
+                return Some(*s);
+            }
+        }
+        candidates.first().map(|(_, s)| *s)
+    }
+
+    /// 指定インデックスのサーバーのリクエスト結果を記録（F-06）
+    pub fn record_outcome(&self, server_idx: usize, success: bool, latency_ms: u64) {
+        if let Some(server) = self.servers.get(server_idx) {
+            server.record_outcome(success, latency_ms, Some(&self.outlier_detection));
+        }
+    }
+
+    /// 指定インデックスのサーバーの結果をパッシブヘルスチェックへ反映（F-144）
+    ///
+    /// unhealthy へ遷移したら遷移履歴とメトリクスに理由を記録する。
+    pub fn record_passive(
+        &self,
+        server_idx: usize,
+        outcome: crate::passive_health::PassiveOutcome,
+    ) {
+        let (Some(cfg), Some(server)) = (&self.passive_health, self.servers.get(server_idx)) else {
+            return;
+        };
+        if let Some(rule) = server.record_passive(cfg, outcome) {
+            let label = format!("{}:{}", server.target.host, server.target.port);
+            crate::health::record_transition(&self.name, &label, false, &rule.describe(cfg));
+            crate::metrics::update_upstream_health(&self.name, &label, false);
+            crate::metrics::record_passive_health_trip(&self.name, &label, rule.as_str());
+        }
+    }
+
+    /// 復帰タイマーが満了したパッシブ判定を解除する（F-144、サーバー選択時に評価）
+    fn expire_passive(&self) {
+        let now = monotonic_ms();
+        for server in &self.servers {
+            if server.expire_passive(now) {
+                let label = format!("{}:{}", server.target.host, server.target.port);
+                crate::health::record_transition(
+                    &self.name,
+                    &label,
+                    true,
+                    "passive: recovery timer elapsed",
+                );
+                crate::metrics::update_upstream_health(&self.name, &label, true);
+            }
+        }
+    }
+
+    /// サーバー数を取得
+    pub fn len(&self) -> usize {
+        self.servers.len()
+    }
+
+    /// サーバーが空かどうか
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty()
+    }
+
+    /// TLS証明書検証を無効化するかどうかを取得
+    pub fn tls_insecure(&self) -> bool {
+        self.tls_insecure
+    }
+
+    /// H2C (HTTP/2 over cleartext) を強制するかどうかを取得
+    pub fn use_h2c(&self) -> bool {
+        self.use_h2c
+    }
+}
+
+// ====================
+// 非同期I/Oトレイト（コード重複解消）
+// ====================
+
+/// 非同期読み込みトレイト（SafeReadBuffer対応）
+///
+/// 読み込み操作で `SafeReadBuffer` を受け取り、返却します。
+/// monoio の `set_init()` コールバックにより、読み込み完了時に
+/// 自動的に `valid_len` が設定されます。
+#[allow(async_fn_in_trait)]
+pub trait AsyncReader {
+    async fn read_buf(&mut self, buf: SafeReadBuffer) -> (io::Result<usize>, SafeReadBuffer);
+}
+
+/// 非同期書き込みトレイト
+///
+/// 書き込み操作では `Vec<u8>` を受け取ります。
+/// 書き込みデータは既に有効なデータなので、SafeReadBuffer は不要です。
+#[allow(async_fn_in_trait)]
+pub trait AsyncWriter {
+    async fn write_buf(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>);
+}
+
+// TcpStream用の実装
+impl AsyncReader for TcpStream {
+    async fn read_buf(&mut self, buf: SafeReadBuffer) -> (io::Result<usize>, SafeReadBuffer) {
+        use crate::runtime::io::AsyncReadRent;
+        self.read(buf).await
+    }
+}
+
+impl AsyncWriter for TcpStream {
+    async fn write_buf(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
+        self.write_all(buf).await
+    }
+}
+
+// KtlsServerStream用の実装（rustls + ktls2）
+#[cfg(veil_ktls)]
+impl AsyncReader for KtlsServerStream {
+    async fn read_buf(&mut self, buf: SafeReadBuffer) -> (io::Result<usize>, SafeReadBuffer) {
+        use crate::runtime::io::AsyncReadRent;
+        self.read(buf).await
+    }
+}
+
+#[cfg(veil_ktls)]
+impl AsyncWriter for KtlsServerStream {
+    async fn write_buf(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
+        self.write_all(buf).await
+    }
+}
+
+// KtlsClientStream用の実装（rustls + ktls2）
+#[cfg(veil_ktls)]
+impl AsyncReader for KtlsClientStream {
+    async fn read_buf(&mut self, buf: SafeReadBuffer) -> (io::Result<usize>, SafeReadBuffer) {
+        use crate::runtime::io::AsyncReadRent;
+        self.read(buf).await
+    }
+}
+
+#[cfg(veil_ktls)]
+impl AsyncWriter for KtlsClientStream {
+    async fn write_buf(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
+        self.write_all(buf).await
+    }
+}
+
+// SimpleTlsServerStream用の実装（rustls のみ）
+#[cfg(not(veil_ktls))]
+impl AsyncReader for crate::simple_tls::SimpleTlsServerStream {
+    async fn read_buf(&mut self, buf: SafeReadBuffer) -> (io::Result<usize>, SafeReadBuffer) {
+        use crate::runtime::io::AsyncReadRent;
+        self.read(buf).await
+    }
+}
+
+#[cfg(not(veil_ktls))]
+impl AsyncWriter for crate::simple_tls::SimpleTlsServerStream {
+    async fn write_buf(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
+        self.write_all(buf).await
+    }
+}
+
+// SimpleTlsClientStream用の実装（rustls のみ）
+#[cfg(not(veil_ktls))]
+impl AsyncReader for crate::simple_tls::SimpleTlsClientStream {
+    async fn read_buf(&mut self, buf: SafeReadBuffer) -> (io::Result<usize>, SafeReadBuffer) {
+        use crate::runtime::io::AsyncReadRent;
+        self.read(buf).await
+    }
+}
+
+#[cfg(not(veil_ktls))]
+impl AsyncWriter for crate::simple_tls::SimpleTlsClientStream {
+    async fn write_buf(&mut self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
+        self.write_all(buf).await
+    }
+}
+
+/// HTTP/3 有効時に Alt-Svc 広告を初期化する（F-94、コールドパス）。
+///
+/// `server.http3_enabled && [http3].alt_svc_enabled` のとき、
+/// `[http3].alt_svc` 明示値、または listen ポートから自動生成した値を登録する。
+/// Alt-Svc 関連キーはすべて `[http3]` に集約する。
+fn apply_alt_svc_from_config(config: &Config) {
+    #[cfg(feature = "http3")]
+    {
+        let enabled = config.server.http3_enabled && config.http3.alt_svc_enabled;
+        let value = if enabled {
+            if let Some(ref explicit) = config.http3.alt_svc {
+                explicit.clone()
+            } else {
+                let listen = config
+                    .http3
+                    .listen
+                    .as_deref()
+                    .unwrap_or(config.server.listen.as_str());
+                crate::pool::build_alt_svc_value(listen, config.http3.alt_svc_ma_secs)
+            }
+        } else {
+            String::new()
+        };
+        crate::pool::init_alt_svc(enabled, &value);
+    }
+    #[cfg(not(feature = "http3"))]
+    {
+        let _ = config;
+        crate::pool::init_alt_svc(false, "");
+    }
+}
+
+/// UpstreamConfig から UpstreamGroup を構築する（起動時・リロード時で共通）
+fn build_upstream_group(name: &str, cfg: &UpstreamConfig) -> Option<UpstreamGroup> {
+    let algorithm = resolve_algorithm(&cfg.algorithm, &cfg.hash_key);
+    UpstreamGroup::new(
+        name.to_string(),
+        cfg.servers.clone(),
+        algorithm,
+        cfg.health_check.clone(),
+        cfg.tls_insecure,
+    )
+    .map(|group| {
+        group
+            .with_resilience(&cfg.circuit_breaker, &cfg.outlier_detection)
+            .with_passive_health(cfg.passive_health.as_ref())
+            .with_hash_options(cfg.hash_load_factor, cfg.maglev_table_size)
+            .with_sticky_cookie(cfg.sticky_cookie.as_ref())
+            .with_traffic_shaping(&cfg.slow_start, cfg.priority_failover_threshold)
+            .with_protocol(
+                cfg.protocol,
+                cfg.h2_max_concurrent_streams,
+                cfg.max_response_body_bytes,
+            )
+            .with_connection_pool(&cfg.connection_pool)
+            .with_adaptive_concurrency(&cfg.adaptive_concurrency)
+            .with_source(UpstreamSource::from_config(
+                cfg.transparent,
+                &cfg.source_address,
+            ))
+            .with_socket(cfg.socket.clone())
+    })
+}
+
+/// ハッシュ系アルゴリズムのパラメータ検証（F-131）
+///
+/// `maglev_table_size` は切り上げず、素数かつサーバー数より大きい値のみ受け付ける。
+fn validate_hash_options(name: &str, upstream: &UpstreamConfig) -> io::Result<()> {
+    if !upstream.hash_load_factor.is_finite() || upstream.hash_load_factor <= 1.0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Upstream '{}': hash_load_factor must be greater than 1.0 (got {})",
+                name, upstream.hash_load_factor
+            ),
+        ));
+    }
+    if matches!(upstream.algorithm, LoadBalanceAlgorithm::Maglev { .. })
+        && (!is_prime(upstream.maglev_table_size)
+            || upstream.maglev_table_size <= upstream.servers.len())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Upstream '{}': maglev_table_size must be a prime larger than the server count (got {})",
+                name, upstream.maglev_table_size
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// コネクションプール設定の妥当性チェック（F-136）
+fn validate_connection_pool(upstream: &str, cfg: &ConnectionPoolConfig) -> io::Result<()> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    if cfg.max_concurrent_connects == 0 {
+        return Err(invalid(format!(
+            "Upstream '{}': connection_pool.max_concurrent_connects must be at least 1",
+            upstream
+        )));
+    }
+    if cfg.pending_timeout_ms == 0 {
+        return Err(invalid(format!(
+            "Upstream '{}': connection_pool.pending_timeout_ms must be at least 1",
+            upstream
+        )));
+    }
+    if cfg.max_connections > 0 && cfg.prewarm_connections > cfg.max_connections {
+        return Err(invalid(format!(
+            "Upstream '{}': connection_pool.prewarm_connections ({}) exceeds max_connections ({})",
+            upstream, cfg.prewarm_connections, cfg.max_connections
+        )));
+    }
+    Ok(())
+}
+
+/// パッシブヘルスチェック設定の妥当性チェック（F-144）
+fn validate_passive_health(
+    upstream: &str,
+    cfg: &PassiveHealthConfig,
+    has_active_check: bool,
+) -> io::Result<()> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    if cfg.consecutive_5xx == 0 && cfg.consecutive_connect_failures == 0 && cfg.gateway_errors == 0
+    {
+        return Err(invalid(format!(
+            "Upstream '{}': passive_health requires at least one of consecutive_5xx, \
+             consecutive_connect_failures or gateway_errors",
+            upstream
+        )));
+    }
+    if cfg.gateway_errors > 0 && cfg.gateway_error_window_secs == 0 {
+        return Err(invalid(format!(
+            "Upstream '{}': passive_health.gateway_error_window_secs must be at least 1",
+            upstream
+        )));
+    }
+    // タイマーもアクティブチェックもなければ unhealthy のまま戻らない
+    if cfg.recovery_secs == 0 && !has_active_check {
+        return Err(invalid(format!(
+            "Upstream '{}': passive_health.recovery_secs = 0 requires health_check",
+            upstream
+        )));
+    }
+    Ok(())
+}
+
+/// 適応型の同時実行数制限の妥当性チェック（F-138）
+fn validate_adaptive_concurrency(
+    upstream: &str,
+    cfg: &AdaptiveConcurrencyConfig,
+) -> io::Result<()> {
+    if !cfg.enabled {
+        return Ok(());
+    }
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    if cfg.min_limit == 0 || cfg.min_limit > cfg.max_limit {
+        return Err(invalid(format!(
+            "Upstream '{}': adaptive_concurrency requires 1 <= min_limit <= max_limit (got {} / {})",
+            upstream, cfg.min_limit, cfg.max_limit
+        )));
+    }
+    if !(cfg.min_limit..=cfg.max_limit).contains(&cfg.initial_limit) {
+        return Err(invalid(format!(
+            "Upstream '{}': adaptive_concurrency.initial_limit must be between min_limit and max_limit (got {})",
+            upstream, cfg.initial_limit
+        )));
+    }
+    if !cfg.rtt_tolerance.is_finite() || cfg.rtt_tolerance < 1.0 {
+        return Err(invalid(format!(
+            "Upstream '{}': adaptive_concurrency.rtt_tolerance must be at least 1.0 (got {})",
+            upstream, cfg.rtt_tolerance
+        )));
+    }
+    if !cfg.backoff_ratio.is_finite() || cfg.backoff_ratio <= 0.0 || cfg.backoff_ratio >= 1.0 {
+        return Err(invalid(format!(
+            "Upstream '{}': adaptive_concurrency.backoff_ratio must be between 0.0 and 1.0 exclusive (got {})",
+            upstream, cfg.backoff_ratio
+        )));
+    }
+    if cfg.sample_window_ms == 0 || cfg.min_rtt_recalc_secs == 0 {
+        return Err(invalid(format!(
+            "Upstream '{}': adaptive_concurrency.sample_window_ms and min_rtt_recalc_secs must be at least 1",
+            upstream
+        )));
+    }
+    Ok(())
+}
+
+/// スロースタート・優先度ティア設定の妥当性チェック（F-133）
+fn validate_traffic_shaping(upstream: &str, cfg: &UpstreamConfig) -> io::Result<()> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let threshold = cfg.priority_failover_threshold;
+    if !threshold.is_finite() || !(0.0..=1.0).contains(&threshold) {
+        return Err(invalid(format!(
+            "Upstream '{}': priority_failover_threshold must be between 0.0 and 1.0 (got {})",
+            upstream, threshold
+        )));
+    }
+    let ss = &cfg.slow_start;
+    if !ss.aggression.is_finite() || ss.aggression <= 0.0 {
+        return Err(invalid(format!(
+            "Upstream '{}': slow_start.aggression must be greater than 0 (got {})",
+            upstream, ss.aggression
+        )));
+    }
+    if !(1..=100).contains(&ss.min_weight_percent) {
+        return Err(invalid(format!(
+            "Upstream '{}': slow_start.min_weight_percent must be between 1 and 100 (got {})",
+            upstream, ss.min_weight_percent
+        )));
+    }
+    Ok(())
+}
+
+/// スティッキー Cookie 設定の妥当性チェック（F-132）
+fn validate_sticky_cookie(upstream: &str, cfg: &StickyCookieConfig) -> io::Result<()> {
+    // RFC 6265 の cookie-name（token）: 英数字と一部記号のみ
+    let valid_name = !cfg.name.is_empty()
+        && cfg
+            .name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+    if !valid_name {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Upstream '{}': invalid sticky_cookie name '{}'",
+                upstream, cfg.name
+            ),
+        ));
+    }
+    if !cfg.path.starts_with('/') || cfg.path.bytes().any(|b| b == b';' || b.is_ascii_control()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Upstream '{}': sticky_cookie path must start with '/' and must not contain ';'",
+                upstream
+            ),
+        ));
+    }
+    // ブラウザは Secure なしの SameSite=None を拒否する
+    if cfg.same_site == SameSite::None && !cfg.secure {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Upstream '{}': sticky_cookie same_site = \"none\" requires secure = true",
+                upstream
+            ),
+        ));
+    }
+    if cfg.secret.as_deref().is_none_or(str::is_empty) {
+        warn!(
+            "Upstream '{}': sticky_cookie.secret is not set; using a per-process random key \
+             (affinity resets on restart and is not shared across instances)",
+            upstream
+        );
+    }
+    Ok(())
+}
+
+fn validate_config(config: &Config) -> io::Result<()> {
+    // TLS証明書ファイルの存在チェック
+    let cert_path = Path::new(&config.tls.cert_path);
+    if !cert_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("TLS certificate file not found: {}", config.tls.cert_path),
+        ));
+    }
+
+    let key_path = Path::new(&config.tls.key_path);
+    if !key_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("TLS key file not found: {}", config.tls.key_path),
+        ));
+    }
+
+    // バインドアドレスの妥当性チェック
+    if config.server.listen.parse::<SocketAddr>().is_err() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid listen address: {}", config.server.listen),
+        ));
+    }
+
+    // Upstream設定の妥当性チェック
+    if let Some(ref upstreams) = config.upstreams {
+        for (name, upstream) in upstreams {
+            if upstream.servers.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Upstream '{}' has no servers configured", name),
+                ));
+            }
 
             for entry in &upstream.servers {
                 if ProxyTarget::parse(&entry.url).is_none() {
                     return Err(io::Error::new(
                         io::ErrorKind::InvalidInput,
-                        format!("Invalid server URL in upstream '{}': {}", name, entry.url),
+                        format!("Invalid server URL in upstream '{}': {}", name, entry.url),
+                    ));
+                }
+            }
+
+            if let Some(sticky) = &upstream.sticky_cookie {
+                validate_sticky_cookie(name, sticky)?;
+            }
+            validate_traffic_shaping(name, upstream)?;
+            if upstream.protocol == UpstreamProtocol::H3 {
+                if let Some(entry) = upstream
+                    .servers
+                    .iter()
+                    .find(|e| !e.url.starts_with("https://"))
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "Upstream '{}': protocol = \"h3\" requires https:// servers ({})",
+                            name, entry.url
+                        ),
+                    ));
+                }
+                if !cfg!(feature = "http3") {
+                    warn!(
+                        "Upstream '{}': protocol = \"h3\" requires the http3 feature, using HTTP/1.1",
+                        name
+                    );
+                }
+            }
+            if let Some(hc) = &upstream.health_check {
+                validate_health_check(
+                    "Upstream",
+                    name,
+                    hc,
+                    upstream.protocol == UpstreamProtocol::H3,
+                    false,
+                )?;
+            }
+            validate_connection_pool(name, &upstream.connection_pool)?;
+            if let Some(passive) = &upstream.passive_health {
+                validate_passive_health(name, passive, upstream.health_check.is_some())?;
+            }
+            validate_adaptive_concurrency(name, &upstream.adaptive_concurrency)?;
+            validate_upstream_source(
+                "Upstream",
+                name,
+                upstream.transparent,
+                &upstream.source_address,
+            )?;
+            // 多重化接続（h2 / h3）は複数クライアントのストリームを 1 本の接続に載せるため、
+            // 接続単位の送信元を持てない
+            if (upstream.transparent || !upstream.source_address.is_empty())
+                && (upstream.protocol != UpstreamProtocol::Http1
+                    || upstream.servers.iter().any(|e| e.use_h2c))
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Upstream '{}': transparent / source_address require protocol = \"http1\" without h2c",
+                        name
+                    ),
+                ));
+            }
+            if upstream.h2_max_concurrent_streams == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Upstream '{}': h2_max_concurrent_streams must be at least 1",
+                        name
+                    ),
+                ));
+            }
+            if upstream.max_response_body_bytes == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Upstream '{}': max_response_body_bytes must be at least 1",
+                        name
+                    ),
+                ));
+            }
+            validate_hash_options(name, upstream)?;
+        }
+    }
+
+    // L4 リスナーの送信元指定（F-141）
+    #[cfg(feature = "l4-proxy")]
+    for l4 in config.l4.iter().flatten() {
+        validate_upstream_source("L4 listener", &l4.name, l4.transparent, &l4.source_address)?;
+        if let Some(hc) = &l4.health_check {
+            validate_health_check("L4 listener", &l4.name, hc, false, true)?;
+        }
+    }
+
+    // ソケットプロファイルの値と参照（F-142）
+    let profiles = resolve_socket_profiles(config)?;
+    lookup_socket_profile(
+        &profiles,
+        "[server]",
+        config.server.socket_profile.as_deref(),
+    )?;
+    for (name, upstream) in config.upstreams.iter().flatten() {
+        lookup_socket_profile(
+            &profiles,
+            &format!("Upstream '{}'", name),
+            upstream.socket_profile.as_deref(),
+        )?;
+    }
+    #[cfg(feature = "l4-proxy")]
+    for l4 in config.l4.iter().flatten() {
+        let owner = format!("L4 listener '{}'", l4.name);
+        lookup_socket_profile(&profiles, &owner, l4.socket_profile.as_deref())?;
+        lookup_socket_profile(&profiles, &owner, l4.upstream_socket_profile.as_deref())?;
+    }
+
+    // 自動遮断（F-152）
+    if let Some(ref auto_ban) = config.security.auto_ban {
+        validate_auto_ban_config(auto_ban)?;
+    }
+    if let Some(ref client_limits) = config.security.client_limits {
+        validate_client_limits_config(client_limits)?;
+    }
+
+    // 統合ルーティング（[[route]]）の妥当性チェック
+    if let Some(ref routes) = config.route {
+        for (i, route) in routes.iter().enumerate() {
+            let route_name = format!("route[{}]", i);
+            validate_route_config(
+                route,
+                &route_name,
+                #[cfg(feature = "wasm")]
+                config.wasm.as_ref(),
+            )?;
+            if let Some(ref global) = route.global_rate_limit {
+                validate_global_rate_limit_config(
+                    global,
+                    config.rate_limit_service.as_ref(),
+                    &route_name,
+                )?;
+            }
+        }
+    }
+
+    // WASM設定のバリデーション
+    #[cfg(feature = "wasm")]
+    if let Some(wasm_cfg) = &config.wasm {
+        if wasm_cfg.enabled {
+            wasm_cfg.validate().map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("WASM config validation failed: {}", e),
+                )
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// ルート設定の妥当性チェック
+fn validate_route_config(
+    route: &Route,
+    route_name: &str,
+    #[cfg(feature = "wasm")] wasm_config: Option<&crate::wasm::WasmConfig>,
+) -> io::Result<()> {
+    match &route.action {
+        BackendConfig::Proxy { url, .. } => {
+            if ProxyTarget::parse(url).is_none() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid proxy URL for route '{}': {}", route_name, url),
+                ));
+            }
+        }
+        BackendConfig::ProxyUpstream { upstream, .. } => {
+            // upstream の存在は load_backend でチェックされる
+            if upstream.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Empty upstream name for route '{}'", route_name),
+                ));
+            }
+        }
+        BackendConfig::File { path, mode, .. } => {
+            let file_path = Path::new(path);
+            if !file_path.exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "File/directory not found for route '{}': {}",
+                        route_name, path
+                    ),
+                ));
+            }
+
+            if !["sendfile", "memory", ""].contains(&mode.as_str()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid mode for route '{}': {} (expected 'sendfile' or 'memory')",
+                        route_name, mode
+                    ),
+                ));
+            }
+        }
+        BackendConfig::Redirect {
+            redirect_url,
+            redirect_status,
+            ..
+        } => {
+            if redirect_url.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Empty redirect_url for route '{}'", route_name),
+                ));
+            }
+            if !matches!(*redirect_status, 301 | 302 | 303 | 307 | 308) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid redirect_status for route '{}': {} (expected 301, 302, 303, 307, or 308)", route_name, redirect_status)
+                ));
+            }
+        }
+    }
+
+    // リクエストヘッジング（F-137）はプロキシルートのみ
+    if let Some(ref hedging) = route.hedging {
+        if !matches!(
+            route.action,
+            BackendConfig::Proxy { .. } | BackendConfig::ProxyUpstream { .. }
+        ) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Route '{}': hedging is only supported for proxy routes",
+                    route_name
+                ),
+            ));
+        }
+        validate_hedging_config(hedging, route_name)?;
+    }
+
+    // 上流タイムアウト（F-139）
+    if let Some(ref security) = route.security {
+        validate_timeouts_config(security, route_name)?;
+    }
+
+    // トークンバケット式レートリミット（F-146）
+    validate_rate_limit_config(&route.rate_limits, route_name)?;
+
+    // JWT 認証（F-148）。クレームによるルーティングは署名を検証しないため jwt を必須とする
+    if let Some(ref jwt) = route.jwt {
+        validate_jwt_config(jwt, route_name)?;
+    }
+    if route.conditions.jwt_claims.is_some() && route.jwt.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Route '{}': conditions.jwt_claims requires [route.jwt]",
+                route_name
+            ),
+        ));
+    }
+
+    // レートリミットの `jwt_claim:` キー（F-146 / F-147）は検証済みのクレームだけを使う
+    validate_rate_limit_claim_keys(route, route_name)?;
+
+    // 外部認可（F-149）
+    if let Some(ref ext_authz) = route.ext_authz {
+        validate_ext_authz_config(ext_authz, route_name)?;
+    }
+
+    // OIDC ログイン（F-150）。Bearer トークンの JWT 認証とは併用しない
+    if let Some(ref oidc) = route.oidc {
+        if route.jwt.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Route '{}': [route.oidc] and [route.jwt] cannot be combined",
+                    route_name
+                ),
+            ));
+        }
+        validate_oidc_config(oidc, route_name)?;
+    }
+
+    // Basic 認証・API キー認証（F-151）。JWT 認証・OIDC ログインとは併用しない
+    if route.basic_auth.is_some() || route.api_key.is_some() {
+        if route.jwt.is_some() || route.oidc.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Route '{}': [route.basic_auth] and [route.api_key] cannot be combined with [route.jwt] or [route.oidc]",
+                    route_name
+                ),
+            ));
+        }
+        if let Some(ref basic) = route.basic_auth {
+            validate_basic_auth_config(basic, route_name)?;
+        }
+        if let Some(ref api_key) = route.api_key {
+            validate_api_key_config(api_key, route_name)?;
+        }
+    }
+
+    // WAF（F-154）
+    if let Some(ref waf) = route.waf {
+        validate_waf_config(waf, route_name)?;
+    }
+
+    // CORS（F-155）
+    if let Some(ref cors) = route.cors {
+        validate_cors_config(cors, route_name)?;
+    }
+
+    // WASMモジュールの参照チェック（route直下のmodulesを使用）
+    #[cfg(feature = "wasm")]
+    if let Some(wasm_cfg) = wasm_config {
+        if let Some(ref modules) = route.modules {
+            for module_name in modules {
+                if !wasm_cfg.modules.iter().any(|m| &m.name == module_name) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "Route '{}' references unknown WASM module: {}",
+                            route_name, module_name
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// レートリミット規則の検証（F-146）
+fn validate_rate_limit_config(rules: &[RateLimitRuleConfig], route_name: &str) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Route '{}': rate_limits {}", route_name, msg),
+        ))
+    };
+    for (i, rule) in rules.iter().enumerate() {
+        // RateLimit-Policy の識別子に埋め込むため記号は `_` `-` `.` のみ
+        if rule.name.is_empty()
+            || !rule
+                .name
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.'))
+        {
+            return invalid(format!(
+                "name '{}' must be non-empty and use only [A-Za-z0-9_.-]",
+                rule.name
+            ));
+        }
+        if rules[..i].iter().any(|r| r.name == rule.name) {
+            return invalid(format!("name '{}' is duplicated", rule.name));
+        }
+        if rule.name == crate::rate_limit::PER_MINUTE_RULE {
+            return invalid(format!(
+                "name '{}' is reserved for rate_limit_requests_per_min",
+                rule.name
+            ));
+        }
+        if rule.rate == 0 || rule.period_secs == 0 {
+            return invalid(format!(
+                "'{}': rate and period_secs must be greater than 0",
+                rule.name
+            ));
+        }
+        if rule.key.is_empty() {
+            return invalid(format!("'{}': key must not be empty", rule.name));
+        }
+        if let Some(part) = rule
+            .key
+            .iter()
+            .find(|k| crate::rate_limit::KeyPart::parse(k).is_none())
+        {
+            return invalid(format!(
+                "'{}': unknown key part '{}' (expected client_ip, header:<name>, cookie:<name>, path, route, jwt_claim:<claim> or api_key)",
+                rule.name, part
+            ));
+        }
+        if rule.classes.iter().any(|c| c.is_empty()) {
+            return invalid(format!(
+                "'{}': classes must not be empty strings",
+                rule.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `jwt_claim:` キーを使うレートリミットにクレームを検証する認証があるか（F-146 / F-147）
+fn validate_rate_limit_claim_keys(route: &Route, route_name: &str) -> io::Result<()> {
+    let uses_claim_key = route
+        .rate_limits
+        .iter()
+        .flat_map(|rule| rule.key.iter())
+        .chain(
+            route
+                .global_rate_limit
+                .iter()
+                .flat_map(|global| global.descriptors.iter())
+                .flat_map(|descriptor| descriptor.entries.iter())
+                .filter_map(|entry| entry.from.as_ref()),
+        )
+        .any(|k| {
+            matches!(
+                crate::rate_limit::KeyPart::parse(k),
+                Some(crate::rate_limit::KeyPart::JwtClaim(_))
+            )
+        });
+    if uses_claim_key
+        && route.jwt.is_none()
+        && route.oidc.is_none()
+        && route.basic_auth.is_none()
+        && route.api_key.is_none()
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Route '{}': rate limit key jwt_claim:<claim> requires [route.jwt], [route.oidc], [route.basic_auth] or [route.api_key]",
+                route_name
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// 自動遮断の検証（F-152）
+fn validate_auto_ban_config(cfg: &AutoBanConfig) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("[security.auto_ban] {}", msg),
+        ))
+    };
+    if cfg.ban_secs == 0 || cfg.max_ban_secs < cfg.ban_secs {
+        return invalid(format!(
+            "ban_secs must be greater than 0 and at most max_ban_secs (got {} and {})",
+            cfg.ban_secs, cfg.max_ban_secs
+        ));
+    }
+    if cfg.escalation_factor == 0 {
+        return invalid("escalation_factor must be at least 1".to_string());
+    }
+    if !(32..=128).contains(&cfg.ipv6_prefix_len) {
+        return invalid(format!(
+            "ipv6_prefix_len must be between 32 and 128 (got {})",
+            cfg.ipv6_prefix_len
+        ));
+    }
+    if cfg.max_entries == 0 {
+        return invalid("max_entries must be greater than 0".to_string());
+    }
+    if let Some(ip) = cfg
+        .exempt_ips
+        .iter()
+        .find(|ip| CidrRange::parse(ip).is_none())
+    {
+        return invalid(format!("exempt_ips: invalid IP or CIDR '{}'", ip));
+    }
+    if cfg.persist_file.as_deref() == Some("") {
+        return invalid("persist_file must not be empty".to_string());
+    }
+    for (i, rule) in cfg.rules.iter().enumerate() {
+        // 一覧の reason・メトリクスのラベルに出すため記号は `_` `-` `.` のみ
+        if rule.name.is_empty()
+            || !rule
+                .name
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.'))
+        {
+            return invalid(format!(
+                "rules: name '{}' must be non-empty and use only [A-Za-z0-9_.-]",
+                rule.name
+            ));
+        }
+        if cfg.rules[..i].iter().any(|r| r.name == rule.name) {
+            return invalid(format!("rules: name '{}' is duplicated", rule.name));
+        }
+        if rule.name == crate::auto_ban::MANUAL_REASON {
+            return invalid(format!(
+                "rules: name '{}' is reserved for bans added through the admin API",
+                rule.name
+            ));
+        }
+        if rule.threshold == 0 || rule.window_secs == 0 {
+            return invalid(format!(
+                "rules: '{}': threshold and window_secs must be greater than 0",
+                rule.name
+            ));
+        }
+        if rule.signals.is_empty() {
+            return invalid(format!("rules: '{}': signals must not be empty", rule.name));
+        }
+        if let Some(signal) = rule
+            .signals
+            .iter()
+            .find(|s| crate::auto_ban::Signal::parse(s).is_none())
+        {
+            return invalid(format!(
+                "rules: '{}': unknown signal '{}' (expected rate_limited, unauthorized, forbidden, not_found, h2_flood or waf)",
+                rule.name, signal
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 接続元ごとの上限の検証（F-153）
+fn validate_client_limits_config(cfg: &ClientLimitsConfig) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("[security.client_limits] {}", msg),
+        ))
+    };
+    if !(8..=32).contains(&cfg.ipv4_prefix_len) {
+        return invalid(format!(
+            "ipv4_prefix_len must be between 8 and 32 (got {})",
+            cfg.ipv4_prefix_len
+        ));
+    }
+    if !(32..=128).contains(&cfg.ipv6_prefix_len) {
+        return invalid(format!(
+            "ipv6_prefix_len must be between 32 and 128 (got {})",
+            cfg.ipv6_prefix_len
+        ));
+    }
+    if let Some(ip) = cfg
+        .exempt_ips
+        .iter()
+        .find(|ip| CidrRange::parse(ip).is_none())
+    {
+        return invalid(format!("exempt_ips: invalid IP or CIDR '{}'", ip));
+    }
+    for (name, per_ip, per_prefix) in [
+        (
+            "max_connections",
+            cfg.per_ip.max_connections,
+            cfg.per_prefix.max_connections,
+        ),
+        (
+            "max_quic_connections",
+            cfg.per_ip.max_quic_connections,
+            cfg.per_prefix.max_quic_connections,
+        ),
+        (
+            "max_streams",
+            cfg.per_ip.max_streams,
+            cfg.per_prefix.max_streams,
+        ),
+        (
+            "max_new_connections_per_sec",
+            cfg.per_ip.max_new_connections_per_sec,
+            cfg.per_prefix.max_new_connections_per_sec,
+        ),
+    ] {
+        // プレフィックスは IP を含むため、IP の上限より小さいと IP の上限が意味を持たない
+        if per_ip > 0 && per_prefix > 0 && per_prefix < per_ip {
+            return invalid(format!(
+                "per_prefix.{} ({}) must not be smaller than per_ip.{} ({})",
+                name, per_prefix, name, per_ip
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// グローバルレートリミットの検証（F-147）
+fn validate_global_rate_limit_config(
+    cfg: &GlobalRateLimitConfig,
+    service: Option<&RateLimitServiceConfig>,
+    route_name: &str,
+) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Route '{}': global_rate_limit {}", route_name, msg),
+        ))
+    };
+    if !cfg!(all(feature = "http2", feature = "grpc")) {
+        return invalid("requires the http2 and grpc features".to_string());
+    }
+    let Some(service) = service else {
+        return invalid("requires a [rate_limit_service] section".to_string());
+    };
+    if service.address.is_empty() || service.domain.is_empty() || service.timeout_ms == 0 {
+        return invalid(
+            "requires [rate_limit_service] with non-empty address and domain and timeout_ms > 0"
+                .to_string(),
+        );
+    }
+    if cfg.descriptors.is_empty() {
+        return invalid("descriptors must not be empty".to_string());
+    }
+    for (i, descriptor) in cfg.descriptors.iter().enumerate() {
+        if descriptor.entries.is_empty() {
+            return invalid(format!("descriptors[{}]: entries must not be empty", i));
+        }
+        for entry in &descriptor.entries {
+            if entry.key.is_empty() {
+                return invalid(format!("descriptors[{}]: entry key must not be empty", i));
+            }
+            match (&entry.value, &entry.from) {
+                (Some(_), None) => {}
+                (None, Some(from)) if crate::rate_limit::KeyPart::parse(from).is_some() => {}
+                (None, Some(from)) => {
+                    return invalid(format!(
+                        "descriptors[{}]: entry '{}' has unknown from '{}'",
+                        i, entry.key, from
+                    ));
+                }
+                _ => {
+                    return invalid(format!(
+                        "descriptors[{}]: entry '{}' must set exactly one of value or from",
+                        i, entry.key
                     ));
                 }
             }
         }
     }
+    Ok(())
+}
+
+/// JWT 認証設定の検証（F-148）
+fn validate_jwt_config(cfg: &JwtAuthConfig, route_name: &str) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Route '{}': jwt {}", route_name, msg),
+        ))
+    };
+    if cfg.algorithms.is_empty() {
+        return invalid("algorithms must not be empty".to_string());
+    }
+    if let Some(alg) = cfg
+        .algorithms
+        .iter()
+        .find(|a| crate::jwt_auth::Algorithm::parse(a).is_none())
+    {
+        return invalid(format!(
+            "unknown algorithm '{}' (expected HS256/384/512, RS256/384/512, PS256/384/512, ES256/384 or EdDSA)",
+            alg
+        ));
+    }
+    match (&cfg.jwks_file, &cfg.jwks_url) {
+        (Some(_), None) => {}
+        (None, Some(url)) => {
+            if ProxyTarget::parse(url).is_none() {
+                return invalid(format!("invalid jwks_url '{}'", url));
+            }
+            if cfg.jwks_refresh_secs == 0 || cfg.jwks_timeout_ms == 0 {
+                return invalid(
+                    "jwks_refresh_secs and jwks_timeout_ms must be greater than 0".to_string(),
+                );
+            }
+        }
+        _ => return invalid("requires exactly one of jwks_file or jwks_url".to_string()),
+    }
+    // ファイルの鍵はここで読み、使える鍵が無ければ起動・リロードを失敗させる
+    if cfg.jwks_file.is_some() {
+        if let Err(e) = crate::jwt_auth::JwtAuth::new(cfg) {
+            return invalid(e);
+        }
+    }
+    if cfg.token_cookie.as_deref() == Some("") {
+        return invalid("token_cookie must not be empty".to_string());
+    }
+    for (claim, values) in &cfg.required_claims {
+        if claim.is_empty() || values.is_empty() {
+            return invalid(format!(
+                "required_claims '{}' must name a claim and list at least one value",
+                claim
+            ));
+        }
+    }
+    for (claim, header) in &cfg.claims_to_headers {
+        if claim.is_empty() || !crate::http_utils::is_valid_header_name(header.as_bytes()) {
+            return invalid(format!(
+                "claims_to_headers '{}' = '{}' must map a claim to a valid header name",
+                claim, header
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 外部認可設定の検証（F-149）
+fn validate_ext_authz_config(cfg: &ExtAuthzConfig, route_name: &str) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Route '{}': ext_authz {}", route_name, msg),
+        ))
+    };
+    match (cfg.protocol, &cfg.url, &cfg.address) {
+        (ExtAuthzProtocol::Http, Some(url), None) => {
+            if ProxyTarget::parse(url).is_none() {
+                return invalid(format!("invalid url '{}'", url));
+            }
+        }
+        (ExtAuthzProtocol::Http, _, _) => {
+            return invalid("protocol \"http\" requires url (and no address)".to_string())
+        }
+        (ExtAuthzProtocol::Grpc, None, Some(address)) => {
+            if !cfg!(all(feature = "http2", feature = "grpc")) {
+                return invalid(
+                    "protocol \"grpc\" requires the http2 and grpc features".to_string(),
+                );
+            }
+            if address
+                .rsplit_once(':')
+                .is_none_or(|(host, port)| host.is_empty() || port.parse::<u16>().is_err())
+            {
+                return invalid(format!("address '{}' must be host:port", address));
+            }
+        }
+        (ExtAuthzProtocol::Grpc, _, _) => {
+            return invalid("protocol \"grpc\" requires address (and no url)".to_string())
+        }
+    }
+    if cfg.timeout_ms == 0 {
+        return invalid("timeout_ms must be greater than 0".to_string());
+    }
+    if !(400..=599).contains(&cfg.status_on_error) {
+        return invalid(format!(
+            "status_on_error {} must be a 4xx or 5xx status",
+            cfg.status_on_error
+        ));
+    }
+    if let Some(name) = cfg
+        .request_headers
+        .iter()
+        .chain(&cfg.upstream_headers)
+        .find(|h| !crate::http_utils::is_valid_header_name(h.as_bytes()))
+    {
+        return invalid(format!("invalid header name '{}'", name));
+    }
+    if let Some(part) = cfg
+        .cache_key
+        .iter()
+        .find(|p| crate::rate_limit::KeyPart::parse(p).is_none())
+    {
+        return invalid(format!("unknown cache_key part '{}'", part));
+    }
+    if cfg.cache_key.is_empty() != (cfg.cache_ttl_secs == 0) {
+        return invalid("cache_key and cache_ttl_secs must be set together".to_string());
+    }
+    Ok(())
+}
+
+/// OIDC ログイン設定の検証（F-150）
+fn validate_oidc_config(cfg: &OidcConfig, route_name: &str) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Route '{}': oidc {}", route_name, msg),
+        ))
+    };
+    if ProxyTarget::parse(&cfg.issuer).is_none() {
+        return invalid(format!("invalid issuer '{}'", cfg.issuer));
+    }
+    if cfg.client_id.is_empty() {
+        return invalid("client_id must not be empty".to_string());
+    }
+    if cfg.token_endpoint_auth_method == OidcClientAuthMethod::ClientSecretPost
+        && cfg.client_secret.is_none()
+    {
+        return invalid("client_secret_post requires client_secret".to_string());
+    }
+    if !cfg.scopes.iter().any(|s| s == "openid") {
+        return invalid("scopes must include \"openid\"".to_string());
+    }
+    for (key, url) in [
+        ("authorization_endpoint", &cfg.authorization_endpoint),
+        ("token_endpoint", &cfg.token_endpoint),
+        ("jwks_uri", &cfg.jwks_uri),
+        ("end_session_endpoint", &cfg.end_session_endpoint),
+    ] {
+        if let Some(url) = url {
+            if ProxyTarget::parse(url).is_none() {
+                return invalid(format!("invalid {} '{}'", key, url));
+            }
+        }
+    }
+    let callback_path = match ProxyTarget::parse(&cfg.redirect_uri) {
+        Some(target) => target.path_prefix,
+        None if cfg.redirect_uri.starts_with('/') => cfg.redirect_uri.clone(),
+        None => {
+            return invalid(format!(
+                "redirect_uri '{}' must be a URL or a path",
+                cfg.redirect_uri
+            ))
+        }
+    };
+    if !cfg.logout_path.starts_with('/') || cfg.logout_path == callback_path {
+        return invalid(format!(
+            "logout_path '{}' must be a path other than the callback path",
+            cfg.logout_path
+        ));
+    }
+    if cfg.timeout_ms == 0 {
+        return invalid("timeout_ms must be greater than 0".to_string());
+    }
+    if cfg.algorithms.is_empty() {
+        return invalid("algorithms must not be empty".to_string());
+    }
+    if let Some(alg) = cfg
+        .algorithms
+        .iter()
+        .find(|a| crate::jwt_auth::Algorithm::parse(a).is_none())
+    {
+        return invalid(format!("unknown algorithm '{}'", alg));
+    }
+    for (claim, values) in &cfg.required_claims {
+        if claim.is_empty() || values.is_empty() {
+            return invalid(format!(
+                "required_claims '{}' must name a claim and list at least one value",
+                claim
+            ));
+        }
+    }
+    for (claim, header) in &cfg.claims_to_headers {
+        if claim.is_empty() || !crate::http_utils::is_valid_header_name(header.as_bytes()) {
+            return invalid(format!(
+                "claims_to_headers '{}' = '{}' must map a claim to a valid header name",
+                claim, header
+            ));
+        }
+    }
+    if !crate::http_utils::is_valid_header_name(cfg.cookie_name.as_bytes()) {
+        return invalid(format!("invalid cookie_name '{}'", cfg.cookie_name));
+    }
+    if !cfg.cookie_path.starts_with('/')
+        || cfg
+            .cookie_path
+            .bytes()
+            .any(|b| b <= b' ' || b == b';' || b == 0x7f)
+    {
+        return invalid(format!("invalid cookie_path '{}'", cfg.cookie_path));
+    }
+    if cfg.cookie_same_site == SameSite::None && !cfg.cookie_secure {
+        return invalid("cookie_same_site = \"none\" requires cookie_secure".to_string());
+    }
+    if cfg.cookie_secret.len() < 32 {
+        return invalid("cookie_secret must be at least 32 bytes".to_string());
+    }
+    if cfg.session_lifetime_secs == 0 {
+        return invalid("session_lifetime_secs must be greater than 0".to_string());
+    }
+    Ok(())
+}
 
-    // 統合ルーティング（[[route]]）の妥当性チェック
-    if let Some(ref routes) = config.route {
-        for (i, route) in routes.iter().enumerate() {
-            let route_name = format!("route[{}]", i);
-            validate_route_config(
-                route,
-                &route_name,
-                #[cfg(feature = "wasm")]
-                config.wasm.as_ref(),
-            )?;
+/// クレーム → ヘッダー名の対応の検証（F-151）
+fn validate_claim_headers(claims_to_headers: &BTreeMap<String, String>) -> Result<(), String> {
+    for (claim, header) in claims_to_headers {
+        if claim.is_empty() || !crate::http_utils::is_valid_header_name(header.as_bytes()) {
+            return Err(format!(
+                "claims_to_headers '{}' = '{}' must map a claim to a valid header name",
+                claim, header
+            ));
         }
     }
+    Ok(())
+}
 
-    // WASM設定のバリデーション
-    #[cfg(feature = "wasm")]
-    if let Some(wasm_cfg) = &config.wasm {
-        if wasm_cfg.enabled {
-            wasm_cfg.validate().map_err(|e| {
-                io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    format!("WASM config validation failed: {}", e),
-                )
-            })?;
+/// Basic 認証設定の検証（F-151）
+fn validate_basic_auth_config(cfg: &BasicAuthConfig, route_name: &str) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Route '{}': basic_auth {}", route_name, msg),
+        ))
+    };
+    // ファイルはここで読み、読めない・不正な行があれば起動・リロードを失敗させる
+    if let Err(e) = crate::credential_auth::check_htpasswd_file(&cfg.htpasswd_file) {
+        return invalid(e);
+    }
+    if cfg.realm.is_empty()
+        || cfg
+            .realm
+            .bytes()
+            .any(|b| b < b' ' || b == b'"' || b == b'\\' || b == 0x7f)
+    {
+        return invalid(format!("invalid realm '{}'", cfg.realm));
+    }
+    if let Err(e) = validate_claim_headers(&cfg.claims_to_headers) {
+        return invalid(e);
+    }
+    Ok(())
+}
+
+/// API キー認証設定の検証（F-151）
+fn validate_api_key_config(cfg: &ApiKeyAuthConfig, route_name: &str) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Route '{}': api_key {}", route_name, msg),
+        ))
+    };
+    if let Err(e) = crate::credential_auth::check_api_key_file(&cfg.keys_file) {
+        return invalid(e);
+    }
+    if !crate::http_utils::is_valid_header_name(cfg.header.as_bytes()) {
+        return invalid(format!("invalid header '{}'", cfg.header));
+    }
+    if cfg.route_name.as_deref() == Some("") {
+        return invalid("route_name must not be empty".to_string());
+    }
+    if let Err(e) = validate_claim_headers(&cfg.claims_to_headers) {
+        return invalid(e);
+    }
+    Ok(())
+}
+
+/// WAF の検証（F-154）。規則ファイルを読み、使える規則があることも確かめる
+fn validate_cors_config(cfg: &CorsConfig, route_name: &str) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Route '{}': cors {}", route_name, msg),
+        ))
+    };
+    if cfg.allowed_origins.is_empty() && cfg.allowed_origin_regexes.is_empty() {
+        return invalid("needs allowed_origins or allowed_origin_regexes".to_string());
+    }
+    let any_origin = cfg.allowed_origins.iter().any(|o| o == "*");
+    if any_origin && cfg.allow_credentials {
+        return invalid(
+            "allowed_origins \"*\" cannot be combined with allow_credentials".to_string(),
+        );
+    }
+    if let Some(origin) = cfg
+        .allowed_origins
+        .iter()
+        .find(|o| *o != "*" && *o != "null" && (!o.contains("://") || o.ends_with('/')))
+    {
+        return invalid(format!(
+            "invalid origin '{}' (expected scheme://host[:port] without a trailing slash)",
+            origin
+        ));
+    }
+    if cfg.allowed_methods.is_empty() {
+        return invalid("allowed_methods must not be empty".to_string());
+    }
+    if let Some(m) = cfg
+        .allowed_methods
+        .iter()
+        .chain(&cfg.allowed_headers)
+        .chain(&cfg.exposed_headers)
+        .find(|m| !crate::http_utils::is_valid_header_name(m.as_bytes()))
+    {
+        return invalid(format!("invalid method or header name '{}'", m));
+    }
+    if let Err(e) = crate::cors::Cors::new(cfg) {
+        return invalid(e);
+    }
+    Ok(())
+}
+
+fn validate_waf_config(cfg: &WafConfig, route_name: &str) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Route '{}': waf {}", route_name, msg),
+        ))
+    };
+    if cfg.rule_files.is_empty() && cfg.rules.trim().is_empty() {
+        return invalid("needs rule_files or rules".to_string());
+    }
+    if cfg.anomaly_threshold == 0 {
+        return invalid("anomaly_threshold must be at least 1".to_string());
+    }
+    if !(1..=4).contains(&cfg.paranoia_level) {
+        return invalid("paranoia_level must be between 1 and 4".to_string());
+    }
+    if cfg.inspect_body && cfg.max_body_bytes == 0 {
+        return invalid("max_body_bytes must be at least 1 when inspect_body is set".to_string());
+    }
+    if let Some(id) = cfg
+        .disabled_rule_ids
+        .iter()
+        .find(|id| crate::waf::parse_id_range(id).is_none())
+    {
+        return invalid(format!("invalid rule id '{}' in disabled_rule_ids", id));
+    }
+    if cfg.audit_log.as_deref() == Some("") {
+        return invalid("audit_log must not be empty".to_string());
+    }
+    if let Err(e) = crate::waf::check_config(cfg) {
+        return invalid(e);
+    }
+    Ok(())
+}
+
+/// ヘッジング設定の検証（F-137）
+fn validate_hedging_config(cfg: &HedgingConfig, route_name: &str) -> io::Result<()> {
+    let invalid = |msg: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Route '{}': hedging {}", route_name, msg),
+        ))
+    };
+    if cfg.methods.is_empty() {
+        return invalid("methods must not be empty".to_string());
+    }
+    for method in &cfg.methods {
+        if !HEDGING_IDEMPOTENT_METHODS
+            .iter()
+            .any(|m| m.eq_ignore_ascii_case(method))
+        {
+            return invalid(format!(
+                "method '{}' is not idempotent (allowed: {})",
+                method,
+                HEDGING_IDEMPOTENT_METHODS.join(", ")
+            ));
         }
     }
+    if !(cfg.percentile > 0.0 && cfg.percentile <= 100.0) {
+        return invalid(format!(
+            "percentile must be in (0, 100]: {}",
+            cfg.percentile
+        ));
+    }
+    if cfg.min_delay_ms > cfg.max_delay_ms {
+        return invalid(format!(
+            "min_delay_ms ({}) must not exceed max_delay_ms ({})",
+            cfg.min_delay_ms, cfg.max_delay_ms
+        ));
+    }
+    if !(cfg.budget_percent > 0.0 && cfg.budget_percent <= 100.0) {
+        return invalid(format!(
+            "budget_percent must be in (0, 100]: {}",
+            cfg.budget_percent
+        ));
+    }
+    Ok(())
+}
 
+/// 上流タイムアウト設定の検証（F-139）
+fn validate_timeouts_config(cfg: &SecurityConfig, route_name: &str) -> io::Result<()> {
+    if cfg.request_timeout_ms > 0 && cfg.per_try_timeout_ms > cfg.request_timeout_ms {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Route '{}': per_try_timeout_ms ({}) must not exceed request_timeout_ms ({})",
+                route_name, cfg.per_try_timeout_ms, cfg.request_timeout_ms
+            ),
+        ));
+    }
     Ok(())
 }
 
-/// ルート設定の妥当性チェック
-fn validate_route_config(
-    route: &Route,
-    route_name: &str,
-    #[cfg(feature = "wasm")] wasm_config: Option<&crate::wasm::WasmConfig>,
-) -> io::Result<()> {
-    match &route.action {
-        BackendConfig::Proxy { url, .. } => {
-            if ProxyTarget::parse(url).is_none() {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    format!("Invalid proxy URL for route '{}': {}", route_name, url),
-                ));
+// rustls 用の TLS 設定読み込み（統一）
+/// 証明書・秘密鍵パスから ServerConfig を構築する（F-03 リローダー用の公開 API）
+///
+/// `load_tls_config` と同じ手順（ALPN / kTLS シークレット抽出）で再構築する。
+pub fn build_server_config_from_paths(
+    cert_path: &Path,
+    key_path: &Path,
+    ktls_enabled: bool,
+    http2_enabled: bool,
+    cipher_suites: &[String],
+) -> anyhow::Result<Arc<ServerConfig>> {
+    let section = TlsConfigSection {
+        cert_path: cert_path.to_string_lossy().into_owned(),
+        key_path: key_path.to_string_lossy().into_owned(),
+        ktls_enabled,
+        ktls_fallback_enabled: true,
+        tcp_cork_enabled: true,
+        cipher_suites: cipher_suites.to_vec(),
+        auto_reload: false,
+        reload_interval_secs: default_tls_reload_interval(),
+    };
+    load_tls_config(&section, ktls_enabled, http2_enabled)
+        .map_err(|e| anyhow::anyhow!("TLS reload build failed: {}", e))
+}
+
+/// 設定名（例: `TLS13_AES_256_GCM_SHA384`）から rustls の暗号スイートを解決する（F-50）。
+///
+/// 配列の順序を保持する（記載順 = サーバ優先度順）。不明な名前・重複はエラー。
+/// 設定読み込み時（非ホットパス）にのみ呼ばれるため、ここでのアロケーションは許容される。
+pub fn resolve_cipher_suites(names: &[String]) -> io::Result<Vec<rustls::SupportedCipherSuite>> {
+    use crate::tls_provider::provider::ALL_CIPHER_SUITES;
+
+    let mut out: Vec<rustls::SupportedCipherSuite> = Vec::with_capacity(names.len());
+    for name in names {
+        let found = ALL_CIPHER_SUITES
+            .iter()
+            .find(|s| format!("{:?}", s.suite()).eq_ignore_ascii_case(name));
+        match found {
+            Some(s) => {
+                if out.iter().any(|e| e.suite() == s.suite()) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("[tls] cipher_suites: duplicate cipher suite '{}'", name),
+                    ));
+                }
+                out.push(*s);
             }
-        }
-        BackendConfig::ProxyUpstream { upstream, .. } => {
-            // upstream の存在は load_backend でチェックされる
-            if upstream.is_empty() {
+            None => {
+                let valid: Vec<String> = ALL_CIPHER_SUITES
+                    .iter()
+                    .map(|s| format!("{:?}", s.suite()))
+                    .collect();
                 return Err(io::Error::new(
                     io::ErrorKind::InvalidInput,
-                    format!("Empty upstream name for route '{}'", route_name),
-                ));
-            }
-        }
-        BackendConfig::File { path, mode, .. } => {
-            let file_path = Path::new(path);
-            if !file_path.exists() {
-                return Err(io::Error::new(
-                    io::ErrorKind::NotFound,
                     format!(
-                        "File/directory not found for route '{}': {}",
-                        route_name, path
+                        "[tls] cipher_suites: unknown cipher suite '{}'. Valid names: {}",
+                        name,
+                        valid.join(", ")
                     ),
                 ));
             }
+        }
+    }
+    Ok(out)
+}
+
+fn load_tls_config(
+    tls_config: &TlsConfigSection,
+    ktls_enabled: bool,
+    #[allow(unused_variables)] http2_enabled: bool,
+) -> io::Result<Arc<ServerConfig>> {
+    let cert_file = File::open(&tls_config.cert_path)?;
+    let key_file = File::open(&tls_config.key_path)?;
+
+    let cert_reader = BufReader::new(cert_file);
+    let cert_chain: Vec<CertificateDer<'static>> = CertificateDer::pem_reader_iter(cert_reader)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Certificate parse error: {}", e),
+            )
+        })?;
+
+    let key_reader = BufReader::new(key_file);
+    let keys: PrivateKeyDer<'static> = PrivateKeyDer::from_pem_reader(key_reader).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Private key parse error: {}", e),
+        )
+    })?;
+
+    // F-50: [tls] cipher_suites による暗号スイートの取捨選択・優先度指定
+    //
+    // 指定がある場合は CryptoProvider の cipher_suites を設定順（= サーバ優先度順）で
+    // 差し替える。不正な名前はここでエラーになる（起動時 / リロード時の検証）。
+    let custom_suites = if !tls_config.cipher_suites.is_empty() {
+        let suites = resolve_cipher_suites(&tls_config.cipher_suites)?;
+        info!(
+            "TLS cipher suites restricted by config (priority order): {:?}",
+            tls_config.cipher_suites
+        );
+        Some(suites)
+    } else {
+        None
+    };
 
-            if !["sendfile", "memory", ""].contains(&mode.as_str()) {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    format!(
-                        "Invalid mode for route '{}': {} (expected 'sendfile' or 'memory')",
-                        route_name, mode
-                    ),
-                ));
-            }
-        }
-        BackendConfig::Redirect {
-            redirect_url,
-            redirect_status,
-            ..
-        } => {
-            if redirect_url.is_empty() {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    format!("Empty redirect_url for route '{}'", route_name),
-                ));
-            }
-            if !matches!(*redirect_status, 301 | 302 | 303 | 307 | 308) {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    format!("Invalid redirect_status for route '{}': {} (expected 301, 302, 303, 307, or 308)", route_name, redirect_status)
-                ));
+    // kTLS 互換性チェック: kTLS は AES-GCM 系のみオフロード可能。
+    // 非互換スイートが指定されている場合は警告する（該当接続は rustls フォールバック、
+    // ktls_fallback_enabled = false ならハンドシェイク後に拒否される）。
+    #[cfg(veil_ktls)]
+    if ktls_enabled {
+        if let Some(ref suites) = custom_suites {
+            let compat = crate::ktls::ktls_compatible_cipher_suites();
+            let incompatible: Vec<String> = suites
+                .iter()
+                .filter(|s| !compat.contains(s))
+                .map(|s| format!("{:?}", s.suite()))
+                .collect();
+            if !incompatible.is_empty() {
+                warn!(
+                    "kTLS enabled but non-kTLS-compatible cipher suites configured: {}. \
+                     Connections negotiating these will not be offloaded to kTLS",
+                    incompatible.join(", ")
+                );
             }
+        } else {
+            info!("kTLS enabled: kTLS offload is available for AES-GCM cipher suites");
         }
     }
 
-    // WASMモジュールの参照チェック（route直下のmodulesを使用）
-    #[cfg(feature = "wasm")]
-    if let Some(wasm_cfg) = wasm_config {
-        if let Some(ref modules) = route.modules {
-            for module_name in modules {
-                if !wasm_cfg.modules.iter().any(|m| &m.name == module_name) {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        format!(
-                            "Route '{}' references unknown WASM module: {}",
-                            route_name, module_name
-                        ),
-                    ));
-                }
-            }
+    // kTLS 有効時のみ config を変更するため、条件付きで mut を使用
+    #[allow(unused_mut)]
+    let mut config = {
+        #[cfg(not(veil_ktls))]
+        let _ = ktls_enabled;
+
+        let mut provider = crate::tls_provider::provider::default_provider();
+        if let Some(suites) = custom_suites {
+            provider.cipher_suites = suites;
         }
+
+        ServerConfig::builder_with_provider(provider.into())
+            .with_protocol_versions(rustls::DEFAULT_VERSIONS)
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("TLS version error: {}", e),
+                )
+            })?
+            .with_no_client_auth()
+            .with_single_cert(cert_chain, keys)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+    };
+
+    // kTLS が有効な場合のみシークレット抽出を有効化
+    // これにより dangerous_extract_secrets() が使用可能になる
+    #[cfg(veil_ktls)]
+    if ktls_enabled {
+        config.enable_secret_extraction = true;
+        info!("TLS secret extraction enabled for kTLS support");
     }
 
-    Ok(())
+    // HTTP/2 有効時は ALPN を設定
+    #[cfg(feature = "http2")]
+    if http2_enabled {
+        config = protocol::configure_alpn_h2(config, false);
+        info!("HTTP/2 enabled via ALPN negotiation (h2, http/1.1)");
+    }
+
+    Ok(Arc::new(config))
 }
 
-// rustls 用の TLS 設定読み込み（統一）
-/// 証明書・秘密鍵パスから ServerConfig を構築する（F-03 リローダー用の公開 API）
+/// 設定読み込みの戻り値型（統一）
+pub struct LoadedConfig {
+    pub listen_addr: String,
+    /// HTTPリスナーアドレス（HTTPSリダイレクト用、オプション）
+    pub listen_http_addr: Option<SocketAddr>,
+    pub tls_config: Arc<ServerConfig>,
+    /// TLS証明書パス（ログ・表示用）
+    #[cfg_attr(not(feature = "http3"), allow(dead_code))]
+    pub tls_cert_path: String,
+    /// TLS秘密鍵パス（ログ・表示用）
+    #[cfg_attr(not(feature = "http3"), allow(dead_code))]
+    pub tls_key_path: String,
+    /// 証明書の自動リロードを有効にするか（F-03）
+    pub tls_auto_reload: bool,
+    /// 証明書リロードチェック間隔（秒、F-03）
+    pub tls_reload_interval_secs: u64,
+    /// 設定された TLS 暗号スイート（F-50、リロード時の再構築用）
+    pub tls_cipher_suites: Vec<String>,
+    /// TLS証明書（PEM形式、事前読み込み済み）
+    ///
+    /// Landlock適用前に読み込まれた証明書データ。
+    /// HTTP/3ではmemfd経由でquicheに渡すことで、
+    /// Landlockによるファイルシステム制限下でも動作可能。
+    #[cfg_attr(not(feature = "http3"), allow(dead_code))]
+    pub tls_cert_pem: Arc<Vec<u8>>,
+    /// TLS秘密鍵（PEM形式、事前読み込み済み）
+    ///
+    /// Landlock適用前に読み込まれた秘密鍵データ。
+    /// HTTP/3ではmemfd経由でquicheに渡す。
+    #[cfg_attr(not(feature = "http3"), allow(dead_code))]
+    pub tls_key_pem: Arc<Vec<u8>>,
+    /// 統合ルーティング（唯一のルーティング方式）
+    pub route: Arc<Vec<Route>>,
+    /// 最適化ルーター（Phase 1-4最適化適用）
+    pub optimized_router: Arc<routing::OptimizedRouter>,
+    pub ktls_config: KtlsConfig,
+    pub reuseport_balancing: ReuseportBalancing,
+    pub num_threads: usize,
+    /// Huge Pages (Large OS Pages) を有効化するかどうか
+    pub huge_pages_enabled: bool,
+    /// グローバルセキュリティ設定
+    pub global_security: GlobalSecurityConfig,
+    /// ログ設定
+    pub logging: LoggingConfigSection,
+    /// Prometheusメトリクス設定
+    pub prometheus_config: PrometheusConfig,
+    /// 管理 API 設定（F-20）
+    #[cfg(feature = "admin")]
+    pub admin_config: AdminConfig,
+    /// 構造化アクセスログ設定（F-21）
+    #[cfg(feature = "access-log")]
+    pub access_log_config: crate::access_log::AccessLogConfig,
+    /// OpenTelemetry 設定（F-10）
+    pub opentelemetry: OpenTelemetryConfig,
+    /// Upstream グループ（健康チェック用）
+    pub upstream_groups: Arc<HashMap<String, Arc<UpstreamGroup>>>,
+    /// HTTP/2 を有効化するかどうか
+    #[cfg(feature = "http2")]
+    pub http2_enabled: bool,
+    /// HTTP/3 を有効化するかどうか
+    #[cfg(feature = "http3")]
+    pub http3_enabled: bool,
+    /// HTTP/3 リスナーアドレス (UDP)
+    #[cfg(feature = "http3")]
+    pub http3_listen: Option<String>,
+    /// HTTP/2 設定（詳細設定）
+    #[cfg(feature = "http2")]
+    pub http2_config: Http2ConfigSection,
+    /// HTTP/3 設定（詳細設定）
+    #[cfg(feature = "http3")]
+    pub http3_config: Http3ConfigSection,
+    /// H2C (HTTP/2 Cleartext) を有効化するかどうか
+    #[cfg(feature = "http2")]
+    pub h2c_enabled: bool,
+    /// H2C リスニングアドレス（オプション）
+    #[cfg(feature = "http2")]
+    pub h2c_listen: Option<String>,
+    /// WASM Filter Engine（WASM機能が有効な場合）
+    #[cfg(feature = "wasm")]
+    pub wasm_filter_engine: Option<Arc<crate::wasm::FilterEngine>>,
+    /// パフォーマンス設定
+    pub performance: PerformanceConfigSection,
+    /// グレースフルシャットダウンタイムアウト（秒）
+    pub graceful_shutdown_timeout_secs: u64,
+    /// L4 プロキシリスナー設定（F-18）
+    #[cfg(feature = "l4-proxy")]
+    pub l4_listeners: Vec<L4ListenerConfig>,
+    /// `[server]` のリスナーに適用するソケットオプション（F-142）
+    pub listener_socket: Option<Arc<SocketOptions>>,
+}
+
+// ====================
+// ホットリロード対応のランタイム設定
+// ====================
+//
+// ArcSwap を使用することで、設定変更時にロックフリーで
+// 新しい設定に切り替えることができます。
+//
+// ## メリット
+// - 読み込みはロックフリーで非常に高速（数ナノ秒）
+// - 設定更新中もリクエスト処理を継続可能
+// - 古い設定を参照中のリクエストは安全に完了
+//
+// ## 使用方法
+// ```rust
+// // 設定の読み込み（ロックフリー）
+// let config = CURRENT_CONFIG.load();
+//
+// // 設定の更新（アトミック）
+// CURRENT_CONFIG.store(Arc::new(new_config));
+// ```
+
+/// ランタイムで使用する設定（ホットリロード対応）
 ///
-/// `load_tls_config` と同じ手順（ALPN / kTLS シークレット抽出）で再構築する。
-pub fn build_server_config_from_paths(
-    cert_path: &Path,
-    key_path: &Path,
-    ktls_enabled: bool,
-    http2_enabled: bool,
-    cipher_suites: &[String],
-) -> anyhow::Result<Arc<ServerConfig>> {
-    let section = TlsConfigSection {
-        cert_path: cert_path.to_string_lossy().into_owned(),
-        key_path: key_path.to_string_lossy().into_owned(),
-        ktls_enabled,
-        ktls_fallback_enabled: true,
-        tcp_cork_enabled: true,
-        cipher_suites: cipher_suites.to_vec(),
-        auto_reload: false,
-        reload_interval_secs: default_tls_reload_interval(),
-    };
-    load_tls_config(&section, ktls_enabled, http2_enabled)
-        .map_err(|e| anyhow::anyhow!("TLS reload build failed: {}", e))
+/// 一部のフィールドはホットリロード機能のために保持されているが、
+/// 現在は読み取られていない（将来的にTLS再設定などで使用予定）
+pub struct RuntimeConfig {
+    /// 統合ルーティング（唯一のルーティング方式）
+    pub route: Arc<Vec<Route>>,
+    /// 最適化ルーター（Phase 1-4最適化適用）
+    pub optimized_router: Arc<routing::OptimizedRouter>,
+    /// TLS設定（ホットリロード時の参照用）
+    pub tls_config: Option<Arc<ServerConfig>>,
+    /// kTLS設定（ホットリロード時の参照用）
+    pub ktls_config: Arc<KtlsConfig>,
+    /// グローバルセキュリティ設定（ホットリロード時の参照用）
+    pub global_security: Arc<GlobalSecurityConfig>,
+    /// Prometheusメトリクス設定
+    pub prometheus_config: Arc<PrometheusConfig>,
+    /// 管理 API 設定（F-20）
+    #[cfg(feature = "admin")]
+    pub admin_config: Arc<AdminConfig>,
+    /// 構造化アクセスログ設定（F-21）
+    #[cfg(feature = "access-log")]
+    pub access_log_config: Arc<crate::access_log::AccessLogConfig>,
+    /// Upstream グループ（健康チェック用）
+    pub upstream_groups: Arc<HashMap<String, Arc<UpstreamGroup>>>,
+    /// HTTP/2 有効化フラグ
+    #[cfg(feature = "http2")]
+    pub http2_enabled: bool,
+    /// HTTP/2 設定（詳細設定）
+    #[cfg(feature = "http2")]
+    pub http2_config: Http2ConfigSection,
+    /// HTTP/3 設定（圧縮設定の解決に使用）
+    #[cfg(feature = "http3")]
+    pub http3_config: Http3ConfigSection,
+    /// H2C (HTTP/2 Cleartext) を有効化するかどうか
+    #[cfg(feature = "http2")]
+    pub h2c_enabled: bool,
+    /// H2C リスニングアドレス（オプション）
+    /// ホットリロード時の参照用（現在は起動時のみ使用）
+    #[cfg(feature = "http2")]
+    pub h2c_listen: Option<String>,
+    /// WASM Filter Engine（WASM機能が有効な場合）
+    #[cfg(feature = "wasm")]
+    pub wasm_filter_engine: Option<Arc<crate::wasm::FilterEngine>>,
+    /// パフォーマンス設定（Via header, chunk size等）
+    pub performance: PerformanceConfigSection,
+    /// L4 プロキシリスナー設定（F-18）
+    #[cfg(feature = "l4-proxy")]
+    pub l4_listeners: Arc<Vec<L4ListenerConfig>>,
 }
 
-/// 設定名（例: `TLS13_AES_256_GCM_SHA384`）から rustls の暗号スイートを解決する（F-50）。
-///
-/// 配列の順序を保持する（記載順 = サーバ優先度順）。不明な名前・重複はエラー。
-/// 設定読み込み時（非ホットパス）にのみ呼ばれるため、ここでのアロケーションは許容される。
-pub fn resolve_cipher_suites(names: &[String]) -> io::Result<Vec<rustls::SupportedCipherSuite>> {
-    use crate::tls_provider::provider::ALL_CIPHER_SUITES;
-
-    let mut out: Vec<rustls::SupportedCipherSuite> = Vec::with_capacity(names.len());
-    for name in names {
-        let found = ALL_CIPHER_SUITES
-            .iter()
-            .find(|s| format!("{:?}", s.suite()).eq_ignore_ascii_case(name));
-        match found {
-            Some(s) => {
-                if out.iter().any(|e| e.suite() == s.suite()) {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        format!("[tls] cipher_suites: duplicate cipher suite '{}'", name),
-                    ));
-                }
-                out.push(*s);
-            }
-            None => {
-                let valid: Vec<String> = ALL_CIPHER_SUITES
-                    .iter()
-                    .map(|s| format!("{:?}", s.suite()))
-                    .collect();
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    format!(
-                        "[tls] cipher_suites: unknown cipher suite '{}'. Valid names: {}",
-                        name,
-                        valid.join(", ")
-                    ),
-                ));
-            }
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            route: Arc::new(Vec::new()),
+            optimized_router: Arc::new(routing::OptimizedRouter::new()),
+            tls_config: None,
+            ktls_config: Arc::new(KtlsConfig::default()),
+            global_security: Arc::new(GlobalSecurityConfig::default()),
+            prometheus_config: Arc::new(PrometheusConfig::default()),
+            #[cfg(feature = "admin")]
+            admin_config: Arc::new(AdminConfig::default()),
+            #[cfg(feature = "access-log")]
+            access_log_config: Arc::new(crate::access_log::AccessLogConfig::default()),
+            upstream_groups: Arc::new(HashMap::new()),
+            #[cfg(feature = "http2")]
+            http2_enabled: false,
+            #[cfg(feature = "http2")]
+            http2_config: Http2ConfigSection::default(),
+            #[cfg(feature = "http3")]
+            http3_config: Http3ConfigSection::default(),
+            #[cfg(feature = "http2")]
+            h2c_enabled: false,
+            #[cfg(feature = "http2")]
+            h2c_listen: None,
+            #[cfg(feature = "wasm")]
+            wasm_filter_engine: None,
+            performance: PerformanceConfigSection::default(),
+            #[cfg(feature = "l4-proxy")]
+            l4_listeners: Arc::new(Vec::new()),
         }
     }
-    Ok(out)
 }
 
-fn load_tls_config(
-    tls_config: &TlsConfigSection,
-    ktls_enabled: bool,
-    #[allow(unused_variables)] http2_enabled: bool,
-) -> io::Result<Arc<ServerConfig>> {
-    let cert_file = File::open(&tls_config.cert_path)?;
-    let key_file = File::open(&tls_config.key_path)?;
-
-    let cert_reader = BufReader::new(cert_file);
-    let cert_chain: Vec<CertificateDer<'static>> = CertificateDer::pem_reader_iter(cert_reader)
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Certificate parse error: {}", e),
-            )
-        })?;
-
-    let key_reader = BufReader::new(key_file);
-    let keys: PrivateKeyDer<'static> = PrivateKeyDer::from_pem_reader(key_reader).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("Private key parse error: {}", e),
-        )
-    })?;
+/// グローバルな設定保持用（ホットリロード対応）
+/// 読み込みはロックフリーで非常に高速
+pub static CURRENT_CONFIG: Lazy<ArcSwap<RuntimeConfig>> =
+    Lazy::new(|| ArcSwap::from_pointee(RuntimeConfig::default()));
 
-    // F-50: [tls] cipher_suites による暗号スイートの取捨選択・優先度指定
-    //
-    // 指定がある場合は CryptoProvider の cipher_suites を設定順（= サーバ優先度順）で
-    // 差し替える。不正な名前はここでエラーになる（起動時 / リロード時の検証）。
-    let custom_suites = if !tls_config.cipher_suites.is_empty() {
-        let suites = resolve_cipher_suites(&tls_config.cipher_suites)?;
-        info!(
-            "TLS cipher suites restricted by config (priority order): {:?}",
-            tls_config.cipher_suites
-        );
-        Some(suites)
-    } else {
-        None
-    };
+/// デフォルトの設定ファイルパス
+const DEFAULT_CONFIG_PATH: &str = "/etc/veil/config.toml";
 
-    // kTLS 互換性チェック: kTLS は AES-GCM 系のみオフロード可能。
-    // 非互換スイートが指定されている場合は警告する（該当接続は rustls フォールバック、
-    // ktls_fallback_enabled = false ならハンドシェイク後に拒否される）。
-    #[cfg(veil_ktls)]
-    if ktls_enabled {
-        if let Some(ref suites) = custom_suites {
-            let compat = crate::ktls::ktls_compatible_cipher_suites();
-            let incompatible: Vec<String> = suites
-                .iter()
-                .filter(|s| !compat.contains(s))
-                .map(|s| format!("{:?}", s.suite()))
-                .collect();
-            if !incompatible.is_empty() {
-                warn!(
-                    "kTLS enabled but non-kTLS-compatible cipher suites configured: {}. \
-                     Connections negotiating these will not be offloaded to kTLS",
-                    incompatible.join(", ")
-                );
-            }
-        } else {
-            info!("kTLS enabled: kTLS offload is available for AES-GCM cipher suites");
-        }
-    }
+/// グローバルな設定ファイルパス（ホットリロード用）
+/// コマンドライン引数で指定されたパス、またはデフォルトパスを保持
+pub static CONFIG_PATH: Lazy<ArcSwap<PathBuf>> =
+    Lazy::new(|| ArcSwap::from_pointee(PathBuf::from(DEFAULT_CONFIG_PATH)));
 
-    // kTLS 有効時のみ config を変更するため、条件付きで mut を使用
-    #[allow(unused_mut)]
-    let mut config = {
-        #[cfg(not(veil_ktls))]
-        let _ = ktls_enabled;
+/// HTTPSリダイレクト先のポート（listen設定から抽出）
+///
+/// HTTP→HTTPSリダイレクト時に使用するポート番号。
+/// デフォルトは443（HTTPSの標準ポート）。
+/// main関数で`[server].listen`の値から初期化される。
+pub static HTTPS_REDIRECT_PORT: std::sync::atomic::AtomicU16 =
+    std::sync::atomic::AtomicU16::new(443);
 
-        let mut provider = crate::tls_provider::provider::default_provider();
-        if let Some(suites) = custom_suites {
-            provider.cipher_suites = suites;
-        }
+/// 設定をホットリロードする
+///
+/// 実行中のリクエストは古い設定を参照し続け、
+/// 新規リクエストは新しい設定を使用します。
+///
+/// ## セキュリティに関する注意
+///
+/// TLS証明書・秘密鍵はホットリロードの対象外です。
+/// これはLandlockによるファイルシステム制限を適用後、
+/// 証明書ファイルへのアクセスを禁止するためです。
+///
+/// 証明書を更新する場合は、サーバーを再起動してください。
+pub fn reload_config(path: &Path) -> io::Result<()> {
+    let loaded = load_config_without_tls(path)?;
 
-        ServerConfig::builder_with_provider(provider.into())
-            .with_protocol_versions(rustls::DEFAULT_VERSIONS)
-            .map_err(|e| {
-                io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("TLS version error: {}", e),
-                )
-            })?
-            .with_no_client_auth()
-            .with_single_cert(cert_chain, keys)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
-    };
+    // 現在のTLS設定を維持（ホットリロード対象外）
+    let current = CURRENT_CONFIG.load();
 
-    // kTLS が有効な場合のみシークレット抽出を有効化
-    // これにより dangerous_extract_secrets() が使用可能になる
-    #[cfg(veil_ktls)]
-    if ktls_enabled {
-        config.enable_secret_extraction = true;
-        info!("TLS secret extraction enabled for kTLS support");
-    }
+    // キャッシュをクリア（設定変更時）
+    loaded.optimized_router.clear_cache();
 
-    // HTTP/2 有効時は ALPN を設定
-    #[cfg(feature = "http2")]
-    if http2_enabled {
-        config = protocol::configure_alpn_h2(config, false);
-        info!("HTTP/2 enabled via ALPN negotiation (h2, http/1.1)");
-    }
+    let runtime_config = RuntimeConfig {
+        route: loaded.route,
+        optimized_router: loaded.optimized_router,
+        // TLS設定は起動時のものを維持（セキュリティ上の理由）
+        tls_config: current.tls_config.clone(),
+        ktls_config: current.ktls_config.clone(),
+        global_security: Arc::new(loaded.global_security),
+        prometheus_config: Arc::new(loaded.prometheus_config),
+        #[cfg(feature = "admin")]
+        admin_config: Arc::new(loaded.admin_config),
+        #[cfg(feature = "access-log")]
+        access_log_config: Arc::new(loaded.access_log_config),
+        upstream_groups: loaded.upstream_groups,
+        #[cfg(feature = "http2")]
+        http2_enabled: loaded.http2_enabled,
+        #[cfg(feature = "http2")]
+        http2_config: loaded.http2_config,
+        #[cfg(feature = "http3")]
+        http3_config: loaded.http3_config,
+        #[cfg(feature = "http2")]
+        h2c_enabled: loaded.h2c_enabled,
+        #[cfg(feature = "http2")]
+        h2c_listen: loaded.h2c_listen.clone(),
+        #[cfg(feature = "wasm")]
+        wasm_filter_engine: current.wasm_filter_engine.clone(),
+        performance: loaded.performance.clone(),
+        // L4 リスナーはホットリロード対象外（再起動が必要）
+        #[cfg(feature = "l4-proxy")]
+        l4_listeners: current.l4_listeners.clone(),
+    };
 
-    Ok(Arc::new(config))
+    // アトミックに設定を入れ替え
+    CURRENT_CONFIG.store(Arc::new(runtime_config));
+
+    info!("Configuration reloaded successfully (TLS certificates unchanged - restart required for TLS updates)");
+    Ok(())
 }
 
-/// 設定読み込みの戻り値型（統一）
-pub struct LoadedConfig {
-    pub listen_addr: String,
-    /// HTTPリスナーアドレス（HTTPSリダイレクト用、オプション）
-    pub listen_http_addr: Option<SocketAddr>,
-    pub tls_config: Arc<ServerConfig>,
-    /// TLS証明書パス（ログ・表示用）
-    #[cfg_attr(not(feature = "http3"), allow(dead_code))]
-    pub tls_cert_path: String,
-    /// TLS秘密鍵パス（ログ・表示用）
-    #[cfg_attr(not(feature = "http3"), allow(dead_code))]
-    pub tls_key_path: String,
-    /// 証明書の自動リロードを有効にするか（F-03）
-    pub tls_auto_reload: bool,
-    /// 証明書リロードチェック間隔（秒、F-03）
-    pub tls_reload_interval_secs: u64,
-    /// 設定された TLS 暗号スイート（F-50、リロード時の再構築用）
-    pub tls_cipher_suites: Vec<String>,
-    /// TLS証明書（PEM形式、事前読み込み済み）
-    ///
-    /// Landlock適用前に読み込まれた証明書データ。
-    /// HTTP/3ではmemfd経由でquicheに渡すことで、
-    /// Landlockによるファイルシステム制限下でも動作可能。
-    #[cfg_attr(not(feature = "http3"), allow(dead_code))]
-    pub tls_cert_pem: Arc<Vec<u8>>,
-    /// TLS秘密鍵（PEM形式、事前読み込み済み）
-    ///
-    /// Landlock適用前に読み込まれた秘密鍵データ。
-    /// HTTP/3ではmemfd経由でquicheに渡す。
-    #[cfg_attr(not(feature = "http3"), allow(dead_code))]
-    pub tls_key_pem: Arc<Vec<u8>>,
+/// ホットリロード用の設定（TLS証明書を除く）
+///
+/// Landlock適用後はTLS証明書ファイルへのアクセスが制限されるため、
+/// ホットリロード時はルーティング設定等のみを更新します。
+pub struct LoadedConfigWithoutTls {
     /// 統合ルーティング（唯一のルーティング方式）
     pub route: Arc<Vec<Route>>,
     /// 最適化ルーター（Phase 1-4最適化適用）
     pub optimized_router: Arc<routing::OptimizedRouter>,
-    pub ktls_config: KtlsConfig,
-    pub reuseport_balancing: ReuseportBalancing,
-    pub num_threads: usize,
-    /// Huge Pages (Large OS Pages) を有効化するかどうか
-    pub huge_pages_enabled: bool,
-    /// グローバルセキュリティ設定
     pub global_security: GlobalSecurityConfig,
-    /// ログ設定
-    pub logging: LoggingConfigSection,
-    /// Prometheusメトリクス設定
     pub prometheus_config: PrometheusConfig,
-    /// 管理 API 設定（F-20）
     #[cfg(feature = "admin")]
     pub admin_config: AdminConfig,
     /// 構造化アクセスログ設定（F-21）
     #[cfg(feature = "access-log")]
     pub access_log_config: crate::access_log::AccessLogConfig,
-    /// OpenTelemetry 設定（F-10）
-    pub opentelemetry: OpenTelemetryConfig,
-    /// Upstream グループ（健康チェック用）
     pub upstream_groups: Arc<HashMap<String, Arc<UpstreamGroup>>>,
-    /// HTTP/2 を有効化するかどうか
     #[cfg(feature = "http2")]
     pub http2_enabled: bool,
-    /// HTTP/3 を有効化するかどうか
-    #[cfg(feature = "http3")]
-    pub http3_enabled: bool,
-    /// HTTP/3 リスナーアドレス (UDP)
-    #[cfg(feature = "http3")]
-    pub http3_listen: Option<String>,
-    /// HTTP/2 設定（詳細設定）
     #[cfg(feature = "http2")]
     pub http2_config: Http2ConfigSection,
-    /// HTTP/3 設定（詳細設定）
     #[cfg(feature = "http3")]
     pub http3_config: Http3ConfigSection,
-    /// H2C (HTTP/2 Cleartext) を有効化するかどうか
     #[cfg(feature = "http2")]
     pub h2c_enabled: bool,
-    /// H2C リスニングアドレス（オプション）
     #[cfg(feature = "http2")]
     pub h2c_listen: Option<String>,
-    /// WASM Filter Engine（WASM機能が有効な場合）
-    #[cfg(feature = "wasm")]
-    pub wasm_filter_engine: Option<Arc<crate::wasm::FilterEngine>>,
-    /// パフォーマンス設定
     pub performance: PerformanceConfigSection,
-    /// グレースフルシャットダウンタイムアウト（秒）
-    pub graceful_shutdown_timeout_secs: u64,
-    /// L4 プロキシリスナー設定（F-18）
-    #[cfg(feature = "l4-proxy")]
-    pub l4_listeners: Vec<L4ListenerConfig>,
 }
 
-// ====================
-// ホットリロード対応のランタイム設定
-// ====================
-//
-// ArcSwap を使用することで、設定変更時にロックフリーで
-// 新しい設定に切り替えることができます。
-//
-// ## メリット
-// - 読み込みはロックフリーで非常に高速（数ナノ秒）
-// - 設定更新中もリクエスト処理を継続可能
-// - 古い設定を参照中のリクエストは安全に完了
-//
-// ## 使用方法
-// ```rust
-// // 設定の読み込み（ロックフリー）
-// let config = CURRENT_CONFIG.load();
-//
-// // 設定の更新（アトミック）
-// CURRENT_CONFIG.store(Arc::new(new_config));
-// ```
+/// TLS証明書を除いた設定をロード（ホットリロード用）
+///
+/// Landlock適用後は証明書ファイルへのアクセスが制限されるため、
+/// この関数ではTLS関連の読み込みをスキップします。
+// 理由付き allow: 起動・リロード・設定検証時のみ実行されるコールドパス（データプレーン非経由）。
+#[allow(clippy::disallowed_methods)]
+fn load_config_without_tls(path: &Path) -> io::Result<LoadedConfigWithoutTls> {
+    let config_str = fs::read_to_string(path)?;
+    let mut config: Config = toml::from_str(&config_str).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("TOML parse error: {}", e),
+        )
+    })?;
+
+    // 設定ファイルのバリデーション
+    validate_config(&config)?;
+    // リスナーはリロードで作り直さないため、上流・L4 の参照のみ反映する（F-142）
+    apply_socket_profiles(&mut config)?;
+
+    // HTTP/2・HTTP/3・H2C 設定を読み込み
+    #[cfg(feature = "http2")]
+    let http2_enabled = config.server.http2_enabled;
+    #[cfg(feature = "http2")]
+    let http2_config = config.http2.clone();
+    #[cfg(feature = "http3")]
+    let http3_config = config.http3.clone();
+    #[cfg(feature = "http2")]
+    let h2c_enabled = config.server.h2c_enabled;
+    #[cfg(feature = "http2")]
+    let h2c_listen = config.server.h2c_listen.clone();
+
+    // 統合ルーティング（[[route]]）の読み込み（失敗すれば何も反映せずに直前の設定のまま）
+    let rate_limit_service = config.rate_limit_service.clone().map(Arc::new);
+    let routes = if let Some(routes_config) = config.route.take() {
+        let mut routes_vec = Vec::with_capacity(routes_config.len());
+        for (i, mut route) in routes_config.into_iter().enumerate() {
+            route.prepare(i, rate_limit_service.as_ref())?;
+            routes_vec.push(route);
+        }
+        Arc::new(routes_vec)
+    } else {
+        Arc::new(Vec::new())
+    };
+
+    // Serverヘッダー設定を更新（リロード対応）
+    init_server_header(
+        config.server.server_header_enabled,
+        &config.server.server_header_value,
+    );
+
+    // Alt-Svc（HTTP/3 広告、F-94）— リロードでも同期
+    apply_alt_svc_from_config(&config);
+
+    // Upstream グループを構築（ロードバランシング用）
+    let mut upstream_groups: HashMap<String, Arc<UpstreamGroup>> = HashMap::new();
+    if let Some(upstreams) = &config.upstreams {
+        for (name, cfg) in upstreams {
+            if let Some(group) = build_upstream_group(name, cfg) {
+                info!(
+                    "Reloaded upstream '{}' with {} servers ({:?})",
+                    name,
+                    group.len(),
+                    group.algorithm
+                );
+                upstream_groups.insert(name.clone(), Arc::new(group));
+            } else {
+                warn!("Failed to reload upstream '{}': no valid servers", name);
+            }
+        }
+    }
+
+    // OptimizedRouter を構築（Phase 1-4 最適化）
+    let optimized_router = build_optimized_router(&routes);
+
+    // グローバルOpenFileCache設定を適用
+    let performance_config = &config.performance;
+    cache::configure_global_open_file_cache(
+        performance_config.open_file_cache_enabled,
+        performance_config.open_file_cache_valid_duration_secs,
+        performance_config.open_file_cache_max_entries,
+    );
+    // F-140: 上流接続の Happy Eyeballs
+    performance_config.happy_eyeballs.apply();
+
+    // F-35: グローバル IP ブロックリストを適用（起動時・SIGHUP リロード時の両方で本関数が
+    // 呼ばれるためここで一元的に適用する）。CIDR はパース済みで保持され accept ホットパスでは
+    // 文字列解析を行わない。
+    set_global_blocked_ips(&config.security.blocked_ips);
+    // F-152: 自動遮断の規則（遮断中の IP はリロードをまたいで残す）
+    crate::auto_ban::configure(config.security.auto_ban.as_ref());
+    crate::client_limits::configure(config.security.client_limits.as_ref());
+
+    // AdminConfig の事前計算フィールドを補完
+    #[cfg(feature = "admin")]
+    let admin_config = {
+        let mut admin = config.admin;
+        admin.compute_derived();
+        admin
+    };
+
+    Ok(LoadedConfigWithoutTls {
+        route: routes,
+        optimized_router,
+        global_security: config.security,
+        prometheus_config: config.prometheus,
+        #[cfg(feature = "admin")]
+        admin_config,
+        #[cfg(feature = "access-log")]
+        access_log_config: config.access_log,
+        upstream_groups: Arc::new(upstream_groups),
+        #[cfg(feature = "http2")]
+        http2_enabled,
+        #[cfg(feature = "http2")]
+        http2_config,
+        #[cfg(feature = "http3")]
+        http3_config,
+        #[cfg(feature = "http2")]
+        h2c_enabled,
+        #[cfg(feature = "http2")]
+        h2c_listen,
+        performance: config.performance.clone(),
+    })
+}
+
+/// ルート配列から OptimizedRouter を構築
+///
+/// Phase 1: Host-based グループ化
+/// Phase 2: Path Radix Tree (matchit)
+/// Phase 3: CIDR Tree 最適化
+/// Phase 4: LRU キャッシュ（構築時に初期化）
+fn build_optimized_router(routes: &[Route]) -> Arc<routing::OptimizedRouter> {
+    let mut router = routing::OptimizedRouter::with_cache_capacity(10000);
+
+    for (idx, route) in routes.iter().enumerate() {
+        let conditions = &route.conditions;
+
+        // host条件
+        let host = conditions.host.as_deref();
+        // path条件
+        let path = conditions.path.as_deref();
+        // source_ip条件
+        let source_ip = conditions.source_ip.as_deref();
+
+        router.add_route(idx, host, path, source_ip);
+    }
+
+    // CIDRマッチャーを最適化（ソート）
+    router.finalize();
+
+    info!(
+        "Built OptimizedRouter: {} routes indexed (host groups: {} exact + {} wildcard, path patterns: {})",
+        routes.len(),
+        router.host_router.exact_count(),
+        router.host_router.wildcard_count(),
+        router.path_router.patterns_count()
+    );
+
+    Arc::new(router)
+}
+
+// 理由付き allow: 起動・リロード・設定検証時のみ実行されるコールドパス（データプレーン非経由）。
+#[allow(clippy::disallowed_methods)]
+pub fn load_config(path: &Path) -> io::Result<LoadedConfig> {
+    let config_str = fs::read_to_string(path)?;
+    let mut config: Config = toml::from_str(&config_str).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("TOML parse error: {}", e),
+        )
+    })?;
+
+    // 設定ファイルのバリデーション
+    validate_config(&config)?;
+    let listener_socket = apply_socket_profiles(&mut config)?;
 
-/// ランタイムで使用する設定（ホットリロード対応）
-///
-/// 一部のフィールドはホットリロード機能のために保持されているが、
-/// 現在は読み取られていない（将来的にTLS再設定などで使用予定）
-pub struct RuntimeConfig {
-    /// 統合ルーティング（唯一のルーティング方式）
-    pub route: Arc<Vec<Route>>,
-    /// 最適化ルーター（Phase 1-4最適化適用）
-    pub optimized_router: Arc<routing::OptimizedRouter>,
-    /// TLS設定（ホットリロード時の参照用）
-    pub tls_config: Option<Arc<ServerConfig>>,
-    /// kTLS設定（ホットリロード時の参照用）
-    pub ktls_config: Arc<KtlsConfig>,
-    /// グローバルセキュリティ設定（ホットリロード時の参照用）
-    pub global_security: Arc<GlobalSecurityConfig>,
-    /// Prometheusメトリクス設定
-    pub prometheus_config: Arc<PrometheusConfig>,
-    /// 管理 API 設定（F-20）
-    #[cfg(feature = "admin")]
-    pub admin_config: Arc<AdminConfig>,
-    /// 構造化アクセスログ設定（F-21）
-    #[cfg(feature = "access-log")]
-    pub access_log_config: Arc<crate::access_log::AccessLogConfig>,
-    /// Upstream グループ（健康チェック用）
-    pub upstream_groups: Arc<HashMap<String, Arc<UpstreamGroup>>>,
-    /// HTTP/2 有効化フラグ
+    // kTLS設定（TLS設定より先に読み込む）
+    let ktls_config = KtlsConfig {
+        enabled: config.tls.ktls_enabled,
+        enable_tx: config.tls.ktls_enabled,
+        enable_rx: config.tls.ktls_enabled,
+        fallback_enabled: config.tls.ktls_fallback_enabled,
+        tcp_cork_enabled: config.tls.tcp_cork_enabled,
+    };
+
+    // HTTP/2・HTTP/3・H2C 設定を読み込み
+    // 有効化フラグは server セクションで管理、詳細設定は [http2]/[http3] セクション
     #[cfg(feature = "http2")]
-    pub http2_enabled: bool,
-    /// HTTP/2 設定（詳細設定）
+    let http2_enabled = config.server.http2_enabled;
+    #[cfg(feature = "http3")]
+    let http3_enabled = config.server.http3_enabled;
     #[cfg(feature = "http2")]
-    pub http2_config: Http2ConfigSection,
-    /// HTTP/3 設定（圧縮設定の解決に使用）
+    let http2_config = config.http2.clone();
     #[cfg(feature = "http3")]
-    pub http3_config: Http3ConfigSection,
-    /// H2C (HTTP/2 Cleartext) を有効化するかどうか
+    let http3_config = config.http3.clone();
+    #[cfg(feature = "http3")]
+    let http3_listen = http3_config.listen.clone();
     #[cfg(feature = "http2")]
-    pub h2c_enabled: bool,
-    /// H2C リスニングアドレス（オプション）
-    /// ホットリロード時の参照用（現在は起動時のみ使用）
+    let h2c_enabled = config.server.h2c_enabled;
     #[cfg(feature = "http2")]
-    pub h2c_listen: Option<String>,
-    /// WASM Filter Engine（WASM機能が有効な場合）
-    #[cfg(feature = "wasm")]
-    pub wasm_filter_engine: Option<Arc<crate::wasm::FilterEngine>>,
-    /// パフォーマンス設定（Via header, chunk size等）
-    pub performance: PerformanceConfigSection,
-    /// L4 プロキシリスナー設定（F-18）
-    #[cfg(feature = "l4-proxy")]
-    pub l4_listeners: Arc<Vec<L4ListenerConfig>>,
-}
+    let h2c_listen = config.server.h2c_listen.clone();
 
-impl Default for RuntimeConfig {
-    fn default() -> Self {
-        Self {
-            route: Arc::new(Vec::new()),
-            optimized_router: Arc::new(routing::OptimizedRouter::new()),
-            tls_config: None,
-            ktls_config: Arc::new(KtlsConfig::default()),
-            global_security: Arc::new(GlobalSecurityConfig::default()),
-            prometheus_config: Arc::new(PrometheusConfig::default()),
-            #[cfg(feature = "admin")]
-            admin_config: Arc::new(AdminConfig::default()),
-            #[cfg(feature = "access-log")]
-            access_log_config: Arc::new(crate::access_log::AccessLogConfig::default()),
-            upstream_groups: Arc::new(HashMap::new()),
-            #[cfg(feature = "http2")]
-            http2_enabled: false,
-            #[cfg(feature = "http2")]
-            http2_config: Http2ConfigSection::default(),
-            #[cfg(feature = "http3")]
-            http3_config: Http3ConfigSection::default(),
-            #[cfg(feature = "http2")]
-            h2c_enabled: false,
-            #[cfg(feature = "http2")]
-            h2c_listen: None,
-            #[cfg(feature = "wasm")]
-            wasm_filter_engine: None,
-            performance: PerformanceConfigSection::default(),
-            #[cfg(feature = "l4-proxy")]
-            l4_listeners: Arc::new(Vec::new()),
+    // Serverヘッダー設定を初期化
+    init_server_header(
+        config.server.server_header_enabled,
+        &config.server.server_header_value,
+    );
+
+    // Alt-Svc（HTTP/3 広告、F-94）
+    apply_alt_svc_from_config(&config);
+
+    // バッファプール設定を初期化
+    init_buffer_pool_config(config.buffer_pool.clone());
+
+    // TLS設定（kTLS有効時はシークレット抽出を有効化、HTTP/2有効時はALPN設定）
+    #[cfg(feature = "http2")]
+    let tls_config = load_tls_config(&config.tls, ktls_config.enabled, http2_enabled)?;
+    #[cfg(not(feature = "http2"))]
+    let tls_config = load_tls_config(&config.tls, ktls_config.enabled, false)?;
+
+    // Upstream グループを構築（ロードバランシング用）
+    let mut upstream_groups: HashMap<String, Arc<UpstreamGroup>> = HashMap::new();
+    if let Some(upstreams) = &config.upstreams {
+        for (name, cfg) in upstreams {
+            if let Some(group) = build_upstream_group(name, cfg) {
+                info!(
+                    "Loaded upstream '{}' with {} servers ({:?})",
+                    name,
+                    group.len(),
+                    group.algorithm
+                );
+                if cfg.health_check.is_some() {
+                    info!("  Health check enabled for '{}'", name);
+                }
+                upstream_groups.insert(name.clone(), Arc::new(group));
+            } else {
+                warn!("Failed to load upstream '{}': no valid servers", name);
+            }
         }
     }
-}
 
-/// グローバルな設定保持用（ホットリロード対応）
-/// 読み込みはロックフリーで非常に高速
-pub static CURRENT_CONFIG: Lazy<ArcSwap<RuntimeConfig>> =
-    Lazy::new(|| ArcSwap::from_pointee(RuntimeConfig::default()));
+    // 統合ルーティング（[[route]]）の読み込み
+    let rate_limit_service = config.rate_limit_service.clone().map(Arc::new);
+    let routes = if let Some(routes_config) = config.route {
+        let mut routes_vec = Vec::with_capacity(routes_config.len());
+        for (i, mut route) in routes_config.into_iter().enumerate() {
+            route.prepare(i, rate_limit_service.as_ref())?;
+            routes_vec.push(route);
+        }
+        Arc::new(routes_vec)
+    } else {
+        Arc::new(Vec::new())
+    };
 
-/// デフォルトの設定ファイルパス
-const DEFAULT_CONFIG_PATH: &str = "/etc/veil/config.toml";
+    // OptimizedRouter を構築（Phase 1-4 最適化）
+    let optimized_router = build_optimized_router(&routes);
 
-/// グローバルな設定ファイルパス（ホットリロード用）
-/// コマンドライン引数で指定されたパス、またはデフォルトパスを保持
-pub static CONFIG_PATH: Lazy<ArcSwap<PathBuf>> =
-    Lazy::new(|| ArcSwap::from_pointee(PathBuf::from(DEFAULT_CONFIG_PATH)));
+    // グローバルOpenFileCache設定を適用
+    let performance_config = &config.performance;
+    cache::configure_global_open_file_cache(
+        performance_config.open_file_cache_enabled,
+        performance_config.open_file_cache_valid_duration_secs,
+        performance_config.open_file_cache_max_entries,
+    );
+    // F-140: 上流接続の Happy Eyeballs
+    performance_config.happy_eyeballs.apply();
 
-/// HTTPSリダイレクト先のポート（listen設定から抽出）
-///
-/// HTTP→HTTPSリダイレクト時に使用するポート番号。
-/// デフォルトは443（HTTPSの標準ポート）。
-/// main関数で`[server].listen`の値から初期化される。
-pub static HTTPS_REDIRECT_PORT: std::sync::atomic::AtomicU16 =
-    std::sync::atomic::AtomicU16::new(443);
+    // F-35: グローバル IP ブロックリストを適用（起動時・SIGHUP リロード時の両方で本関数が
+    // 呼ばれるためここで一元的に適用する）。CIDR はパース済みで保持され accept ホットパスでは
+    // 文字列解析を行わない。
+    set_global_blocked_ips(&config.security.blocked_ips);
+    // F-152: 自動遮断の規則（遮断中の IP はリロードをまたいで残す）
+    crate::auto_ban::configure(config.security.auto_ban.as_ref());
+    crate::client_limits::configure(config.security.client_limits.as_ref());
 
-/// 設定をホットリロードする
-///
-/// 実行中のリクエストは古い設定を参照し続け、
-/// 新規リクエストは新しい設定を使用します。
-///
-/// ## セキュリティに関する注意
-///
-/// TLS証明書・秘密鍵はホットリロードの対象外です。
-/// これはLandlockによるファイルシステム制限を適用後、
-/// 証明書ファイルへのアクセスを禁止するためです。
-///
-/// 証明書を更新する場合は、サーバーを再起動してください。
-pub fn reload_config(path: &Path) -> io::Result<()> {
-    let loaded = load_config_without_tls(path)?;
+    // スレッド数の決定: 未指定または0の場合はCPUコア数を使用
+    let num_threads = match config.server.threads {
+        Some(n) if n > 0 => n,
+        _ => num_cpus::get(),
+    };
 
-    // 現在のTLS設定を維持（ホットリロード対象外）
-    let current = CURRENT_CONFIG.load();
+    // HTTPリスナーアドレスをパース（HTTPSリダイレクト用）
+    let listen_http_addr =
+        config
+            .server
+            .http
+            .as_ref()
+            .and_then(|addr| match addr.parse::<SocketAddr>() {
+                Ok(socket_addr) => Some(socket_addr),
+                Err(e) => {
+                    warn!("Invalid HTTP listen address '{}': {}", addr, e);
+                    None
+                }
+            });
 
-    // キャッシュをクリア（設定変更時）
-    loaded.optimized_router.clear_cache();
+    // Prometheusメトリクス設定をログ出力
+    // F-09: ランタイムトグルを設定（enabled=false で record_* がノーオップ・endpoint が 404）
+    crate::metrics::set_metrics_runtime_enabled(config.prometheus.enabled);
+    if config.prometheus.enabled {
+        info!(
+            "Prometheus metrics enabled at path: {}",
+            config.prometheus.path
+        );
+        if !config.prometheus.allowed_ips.is_empty() {
+            info!("  Allowed IPs: {:?}", config.prometheus.allowed_ips);
+        }
+    } else {
+        info!("Prometheus metrics disabled");
+    }
+
+    // TLS証明書をバイト列として読み込み（HTTP/3用、Landlock適用前に読み込み）
+    // これによりLandlock適用後も証明書ファイルへのアクセスなしで動作可能
+    let tls_cert_pem = fs::read(&config.tls.cert_path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Failed to read TLS cert '{}': {}", config.tls.cert_path, e),
+        )
+    })?;
+    let tls_key_pem = fs::read(&config.tls.key_path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Failed to read TLS key '{}': {}", config.tls.key_path, e),
+        )
+    })?;
+
+    info!(
+        "TLS certificates pre-loaded for Landlock compatibility (cert: {} bytes, key: {} bytes)",
+        tls_cert_pem.len(),
+        tls_key_pem.len()
+    );
+
+    // WASM Filter Engineの初期化
+    #[cfg(feature = "wasm")]
+    let wasm_filter_engine = if let Some(wasm_config) = &config.wasm {
+        if wasm_config.enabled {
+            // バリデーション（validate_configで既に実行されているが、念のため）
+            if let Err(e) = wasm_config.validate() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("WASM config validation failed: {}", e),
+                ));
+            }
+
+            // FilterEngine初期化
+            info!("Initializing WASM Filter Engine...");
+            match crate::wasm::init(wasm_config) {
+                Ok(engine) => {
+                    info!("WASM Filter Engine initialized successfully");
+                    Some(Arc::new(engine))
+                }
+                Err(e) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Failed to initialize WASM Filter Engine: {}", e),
+                    ));
+                }
+            }
+        } else {
+            info!("WASM extensions disabled");
+            None
+        }
+    } else {
+        None
+    };
 
-    let runtime_config = RuntimeConfig {
-        route: loaded.route,
-        optimized_router: loaded.optimized_router,
-        // TLS設定は起動時のものを維持（セキュリティ上の理由）
-        tls_config: current.tls_config.clone(),
-        ktls_config: current.ktls_config.clone(),
-        global_security: Arc::new(loaded.global_security),
-        prometheus_config: Arc::new(loaded.prometheus_config),
+    Ok(LoadedConfig {
+        listen_addr: config.server.listen,
+        listen_http_addr,
+        tls_config,
+        tls_cert_path: config.tls.cert_path.clone(),
+        tls_key_path: config.tls.key_path.clone(),
+        tls_auto_reload: config.tls.auto_reload,
+        tls_reload_interval_secs: config.tls.reload_interval_secs,
+        tls_cipher_suites: config.tls.cipher_suites.clone(),
+        tls_cert_pem: Arc::new(tls_cert_pem),
+        tls_key_pem: Arc::new(tls_key_pem),
+        route: routes,
+        optimized_router,
+        ktls_config,
+        reuseport_balancing: config.performance.reuseport_balancing,
+        num_threads,
+        huge_pages_enabled: config.performance.huge_pages_enabled,
+        global_security: config.security,
+        logging: config.logging,
+        prometheus_config: config.prometheus,
         #[cfg(feature = "admin")]
-        admin_config: Arc::new(loaded.admin_config),
+        admin_config: {
+            let mut a = config.admin;
+            a.compute_derived();
+            a
+        },
         #[cfg(feature = "access-log")]
-        access_log_config: Arc::new(loaded.access_log_config),
-        upstream_groups: loaded.upstream_groups,
+        access_log_config: config.access_log,
+        opentelemetry: config.opentelemetry,
+        upstream_groups: Arc::new(upstream_groups),
         #[cfg(feature = "http2")]
-        http2_enabled: loaded.http2_enabled,
+        http2_enabled,
+        #[cfg(feature = "http3")]
+        http3_enabled,
+        #[cfg(feature = "http3")]
+        http3_listen,
         #[cfg(feature = "http2")]
-        http2_config: loaded.http2_config,
+        http2_config,
         #[cfg(feature = "http3")]
-        http3_config: loaded.http3_config,
+        http3_config,
         #[cfg(feature = "http2")]
-        h2c_enabled: loaded.h2c_enabled,
+        h2c_enabled,
         #[cfg(feature = "http2")]
-        h2c_listen: loaded.h2c_listen.clone(),
+        h2c_listen,
         #[cfg(feature = "wasm")]
-        wasm_filter_engine: current.wasm_filter_engine.clone(),
-        performance: loaded.performance.clone(),
-        // L4 リスナーはホットリロード対象外（再起動が必要）
+        wasm_filter_engine,
+        performance: config.performance.clone(),
+        graceful_shutdown_timeout_secs: config.server.graceful_shutdown_timeout_secs,
         #[cfg(feature = "l4-proxy")]
-        l4_listeners: current.l4_listeners.clone(),
-    };
-
-    // アトミックに設定を入れ替え
-    CURRENT_CONFIG.store(Arc::new(runtime_config));
-
-    info!("Configuration reloaded successfully (TLS certificates unchanged - restart required for TLS updates)");
-    Ok(())
-}
-
-/// ホットリロード用の設定（TLS証明書を除く）
-///
-/// Landlock適用後はTLS証明書ファイルへのアクセスが制限されるため、
-/// ホットリロード時はルーティング設定等のみを更新します。
-pub struct LoadedConfigWithoutTls {
-    /// 統合ルーティング（唯一のルーティング方式）
-    pub route: Arc<Vec<Route>>,
-    /// 最適化ルーター（Phase 1-4最適化適用）
-    pub optimized_router: Arc<routing::OptimizedRouter>,
-    pub global_security: GlobalSecurityConfig,
-    pub prometheus_config: PrometheusConfig,
-    #[cfg(feature = "admin")]
-    pub admin_config: AdminConfig,
-    /// 構造化アクセスログ設定（F-21）
-    #[cfg(feature = "access-log")]
-    pub access_log_config: crate::access_log::AccessLogConfig,
-    pub upstream_groups: Arc<HashMap<String, Arc<UpstreamGroup>>>,
-    #[cfg(feature = "http2")]
-    pub http2_enabled: bool,
-    #[cfg(feature = "http2")]
-    pub http2_config: Http2ConfigSection,
-    #[cfg(feature = "http3")]
-    pub http3_config: Http3ConfigSection,
-    #[cfg(feature = "http2")]
-    pub h2c_enabled: bool,
-    #[cfg(feature = "http2")]
-    pub h2c_listen: Option<String>,
-    pub performance: PerformanceConfigSection,
+        l4_listeners: config.l4.unwrap_or_default(),
+        listener_socket,
+    })
 }
 
-/// TLS証明書を除いた設定をロード（ホットリロード用）
-///
-/// Landlock適用後は証明書ファイルへのアクセスが制限されるため、
-/// この関数ではTLS関連の読み込みをスキップします。
+/// 設定ファイルからログ設定のみを読み込む（ログ初期化前用）
 // 理由付き allow: 起動・リロード・設定検証時のみ実行されるコールドパス（データプレーン非経由）。
 #[allow(clippy::disallowed_methods)]
-fn load_config_without_tls(path: &Path) -> io::Result<LoadedConfigWithoutTls> {
+pub fn load_logging_config(path: &Path) -> io::Result<LoggingConfigSection> {
     let config_str = fs::read_to_string(path)?;
     let config: Config = toml::from_str(&config_str).map_err(|e| {
         io::Error::new(
@@ -5557,156 +10137,383 @@ fn load_config_without_tls(path: &Path) -> io::Result<LoadedConfigWithoutTls> {
             format!("TOML parse error: {}", e),
         )
     })?;
+    Ok(config.logging)
+}
 
-    // 設定ファイルのバリデーション
-    validate_config(&config)?;
+// JSON形式ログフォーマッタ（JsonLogFormat 等）と init_logging は
+// crate::logging モジュールに移動しました。
 
-    // HTTP/2・HTTP/3・H2C 設定を読み込み
-    #[cfg(feature = "http2")]
-    let http2_enabled = config.server.http2_enabled;
-    #[cfg(feature = "http2")]
-    let http2_config = config.http2.clone();
-    #[cfg(feature = "http3")]
-    let http3_config = config.http3.clone();
-    #[cfg(feature = "http2")]
-    let h2c_enabled = config.server.h2c_enabled;
-    #[cfg(feature = "http2")]
-    let h2c_listen = config.server.h2c_listen.clone();
+// 理由付き allow: 起動・リロード・設定検証時のみ実行されるコールドパス（データプレーン非経由）。
+#[allow(clippy::disallowed_methods)]
+pub fn load_backend(
+    route: &Route,
+    upstream_groups: &HashMap<String, Arc<UpstreamGroup>>,
+) -> io::Result<Backend> {
+    // Routeレベルの設定を取得（route直下の設定のみを使用）
+    let mut security = route.security.clone().unwrap_or_default();
+    security.rate_limit = route.rate_limiter.clone();
+    if let Some(jwt) = &route.jwt_auth {
+        security
+            .remove_request_headers
+            .extend(jwt.stripped_request_headers());
+        security.jwt = Some(jwt.clone());
+    }
+    security.ext_authz = route.ext_authorizer.clone();
+    if let Some(oidc) = &route.oidc_auth {
+        security
+            .remove_request_headers
+            .extend(oidc.stripped_request_headers());
+        security.oidc = Some(oidc.clone());
+    }
+    if let Some(auth) = &route.credential_auth {
+        security
+            .remove_request_headers
+            .extend(auth.stripped_request_headers());
+        security.credential_auth = Some(auth.clone());
+    }
+    security.waf = route.waf_engine.clone();
+    if let Some(cors) = &route.cors_policy {
+        security
+            .remove_response_headers
+            .extend(cors.owned_response_headers());
+        security.cors = Some(cors.clone());
+    }
+    let compression = route.compression.clone().unwrap_or_default();
+    let buffering = route.buffering.clone().unwrap_or_default();
+    let cache = route.cache.clone().unwrap_or_default();
+    let modules_arc = route.modules.as_ref().map(|m| Arc::new(m.clone()));
 
-    // Serverヘッダー設定を更新（リロード対応）
-    init_server_header(
-        config.server.server_header_enabled,
-        &config.server.server_header_value,
-    );
+    match &route.action {
+        BackendConfig::Proxy {
+            url,
+            sni_name,
+            use_h2c,
+        } => {
+            // 単一URLの場合は UpstreamGroup::single で単一サーバーのグループを作成
+            let target = ProxyTarget::parse(url)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid proxy URL"))?
+                .with_sni_name(sni_name.clone())
+                .with_h2c(*use_h2c);
 
-    // Alt-Svc（HTTP/3 広告、F-94）— リロードでも同期
-    apply_alt_svc_from_config(&config);
+            if *use_h2c && !target.use_tls {
+                info!("H2C (HTTP/2 over cleartext) enabled for backend: {}", url);
+            }
+
+            // 圧縮設定のログ出力
+            if compression.enabled {
+                info!(
+                    "Response compression enabled for backend: {} (gzip_level={}, brotli_level={})",
+                    url, compression.gzip_level, compression.brotli_level
+                );
+            }
+
+            // バッファリング設定のログ出力
+            if buffering.is_enabled() {
+                info!(
+                    "Response buffering enabled for backend: {} (mode={:?}, max_memory={})",
+                    url, buffering.mode, buffering.max_memory_buffer
+                );
+            }
+
+            // キャッシュ設定のログ出力
+            if cache.enabled {
+                info!(
+                    "Proxy cache enabled for backend: {} (max_memory={}, ttl={}s)",
+                    url, cache.max_memory_size, cache.default_ttl_secs
+                );
+            }
+
+            if route.hedging.is_some() {
+                warn!(
+                    "Request hedging has no effect for single-server backend: {}",
+                    url
+                );
+            }
+
+            let group = UpstreamGroup::single(target);
+            Ok(Backend::Proxy(
+                Arc::new(group),
+                Arc::new(security.clone()),
+                Arc::new(compression.clone()),
+                Arc::new(buffering.clone()),
+                Arc::new(cache.clone()),
+                modules_arc.clone(),
+            ))
+        }
+        BackendConfig::ProxyUpstream { upstream, use_h2c } => {
+            // Upstream グループ参照
+            let group = upstream_groups.get(upstream).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Upstream '{}' not found", upstream),
+                )
+            })?;
+
+            // ルート設定で use_h2c / hedging が指定されている場合はオーバーライド
+            let group = match (*use_h2c, route.hedging.as_ref()) {
+                (false, None) => group.clone(),
+                (use_h2c, hedging) => {
+                    let group = if use_h2c {
+                        group.with_h2c(true)
+                    } else {
+                        (**group).clone()
+                    };
+                    if let Some(cfg) = hedging {
+                        info!(
+                            "Request hedging enabled for upstream: {} (delay_ms={}, p{}, budget={}%)",
+                            upstream, cfg.delay_ms, cfg.percentile, cfg.budget_percent
+                        );
+                    }
+                    Arc::new(group.with_hedging(hedging))
+                }
+            };
+
+            // 圧縮設定のログ出力
+            if compression.enabled {
+                info!("Response compression enabled for upstream: {} (gzip_level={}, brotli_level={})", 
+                      upstream, compression.gzip_level, compression.brotli_level);
+            }
+
+            // バッファリング設定のログ出力
+            if buffering.is_enabled() {
+                info!(
+                    "Response buffering enabled for upstream: {} (mode={:?}, max_memory={})",
+                    upstream, buffering.mode, buffering.max_memory_buffer
+                );
+            }
+
+            // キャッシュ設定のログ出力
+            if cache.enabled {
+                info!(
+                    "Proxy cache enabled for upstream: {} (max_memory={}, ttl={}s)",
+                    upstream, cache.max_memory_size, cache.default_ttl_secs
+                );
+            }
+
+            Ok(Backend::Proxy(
+                group.clone(),
+                Arc::new(security.clone()),
+                Arc::new(compression.clone()),
+                Arc::new(buffering.clone()),
+                Arc::new(cache.clone()),
+                modules_arc.clone(),
+            ))
+        }
+        BackendConfig::File { path, mode, index } => {
+            // Routeレベルの設定のみを使用
+            let security = route.security.clone().unwrap_or_default();
+            let cache = route.cache.clone().unwrap_or_default();
+            let access_err = |e: io::Error| {
+                let error_msg = format!(
+                    "Failed to access file '{}': {} (error code: {})",
+                    path,
+                    e,
+                    e.raw_os_error().unwrap_or(-1)
+                );
+                io::Error::new(e.kind(), error_msg)
+            };
+            // F-123: FreeBSD capability mode 下では絶対パス `fs::metadata` が禁止される
+            // ため、登録済みルート dirfd 相対の `fstatat` で is_dir を判定する
+            // （File ルート自身は登録ルートなので rel="." で解決される）。
+            #[cfg(target_os = "freebsd")]
+            let is_dir = if let Some(res) =
+                crate::security::capsicum::stat_static(std::path::Path::new(path))
+            {
+                res.map_err(access_err)?.is_dir
+            } else {
+                fs::metadata(path).map_err(access_err)?.is_dir()
+            };
+            #[cfg(not(target_os = "freebsd"))]
+            let is_dir = fs::metadata(path).map_err(access_err)?.is_dir();
+            // インデックスファイル名を Arc<str> に変換（None = デフォルトで "index.html"）
+            let index_file: Option<Arc<str>> = index.as_ref().map(|s| Arc::from(s.as_str()));
+            let security = Arc::new(security.clone());
+            let cache = Arc::new(cache.clone());
+            let open_file_cache_arc = route.open_file_cache.as_ref().map(|c| Arc::new(c.clone()));
 
-    // Upstream グループを構築（ロードバランシング用）
-    let mut upstream_groups: HashMap<String, Arc<UpstreamGroup>> = HashMap::new();
-    if let Some(upstreams) = &config.upstreams {
-        for (name, cfg) in upstreams {
-            let algorithm = resolve_algorithm(&cfg.algorithm, &cfg.hash_key);
-            if let Some(group) = UpstreamGroup::new(
-                name.clone(),
-                cfg.servers.clone(),
-                algorithm.clone(),
-                cfg.health_check.clone(),
-                cfg.tls_insecure,
-            ) {
-                let group = group.with_resilience(&cfg.circuit_breaker, &cfg.outlier_detection);
+            // キャッシュ設定のログ出力
+            if cache.enabled {
                 info!(
-                    "Reloaded upstream '{}' with {} servers ({:?})",
-                    name,
-                    group.len(),
-                    algorithm
+                    "File cache enabled for path: {} (max_memory={}, ttl={}s)",
+                    path, cache.max_memory_size, cache.default_ttl_secs
                 );
-                upstream_groups.insert(name.clone(), Arc::new(group));
-            } else {
-                warn!("Failed to reload upstream '{}': no valid servers", name);
+            }
+
+            match mode.as_str() {
+                "memory" => {
+                    if is_dir {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "Memory mode not supported for directories",
+                        ));
+                    }
+                    let data = fs::read(path)?;
+                    let mime_type = mime_guess::from_path(path).first_or_octet_stream();
+
+                    Ok(Backend::MemoryFile(
+                        Arc::new(data),
+                        Arc::from(mime_type.as_ref()),
+                        security,
+                        modules_arc.clone(),
+                    ))
+                }
+                "sendfile" | "" => Ok(Backend::SendFile(
+                    Arc::new(PathBuf::from(path)),
+                    is_dir,
+                    index_file,
+                    security,
+                    cache,
+                    open_file_cache_arc,
+                    modules_arc.clone(),
+                )),
+                _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid mode")),
             }
         }
+        BackendConfig::Redirect {
+            redirect_url,
+            redirect_status,
+            preserve_path,
+        } => Ok(Backend::Redirect(
+            Arc::from(redirect_url.as_str()),
+            *redirect_status,
+            *preserve_path,
+            modules_arc.clone(),
+        )),
     }
+}
 
-    // 統合ルーティング（[[route]]）の読み込み
-    let routes = if let Some(routes_config) = config.route {
-        let mut routes_vec = Vec::with_capacity(routes_config.len());
-        for route in routes_config {
-            routes_vec.push(route);
-        }
-        Arc::new(routes_vec)
-    } else {
-        Arc::new(Vec::new())
-    };
+// ====================
+// コマンドライン引数パース
+// ====================
 
-    // OptimizedRouter を構築（Phase 1-4 最適化）
-    let optimized_router = build_optimized_router(&routes);
+// ====================
+// HTTP to HTTPS リダイレクトハンドラー
+// ====================
+//
+// HTTPアクセスをHTTPSにリダイレクトするための軽量ハンドラー。
+// セキュリティ上の理由から、HTTPではリダイレクトのみを行い、
+// コンテンツは一切配信しません。
+//
+// 301 Moved Permanently を使用することで、ブラウザがリダイレクト先を
+// キャッシュし、以降のアクセスでは直接HTTPSに接続します。
 
-    // グローバルOpenFileCache設定を適用
-    let performance_config = &config.performance;
-    cache::configure_global_open_file_cache(
-        performance_config.open_file_cache_enabled,
-        performance_config.open_file_cache_valid_duration_secs,
-        performance_config.open_file_cache_max_entries,
-    );
+// HTTP_301_REDIRECT_TEMPLATE, HTTP_301_REDIRECT_SUFFIX は
+// crate::constants モジュールに移動しました
 
-    // F-35: グローバル IP ブロックリストを適用（起動時・SIGHUP リロード時の両方で本関数が
-    // 呼ばれるためここで一元的に適用する）。CIDR はパース済みで保持され accept ホットパスでは
-    // 文字列解析を行わない。
-    set_global_blocked_ips(&config.security.blocked_ips);
+/// HTTPリクエストを処理し、HTTPSにリダイレクトする
+///
+/// リクエストからHostヘッダーとパスを読み取り、
+/// https://{host}:{port}{path} への301リダイレクトを返します。
+/// ポートはHETTPS_REDIRECT_PORT（[server].listenから抽出）を使用し、
+/// 443の場合はURL中のポート指定を省略します。
+pub async fn handle_http_redirect(mut stream: TcpStream) {
+    // リクエストを読み取るためのバッファ（ヘッダーのみなので小さめ）
+    let mut buffer = vec![0u8; 4096];
 
-    // AdminConfig の事前計算フィールドを補完
-    #[cfg(feature = "admin")]
-    let admin_config = {
-        let mut admin = config.admin;
-        admin.compute_derived();
-        admin
+    // タイムアウト付きで読み取り
+    let read_result = timeout(Duration::from_secs(5), stream.read(buffer)).await;
+
+    let (result, buf) = match read_result {
+        Ok(r) => r,
+        Err(_) => {
+            // タイムアウト
+            return;
+        }
     };
+    buffer = buf;
 
-    Ok(LoadedConfigWithoutTls {
-        route: routes,
-        optimized_router,
-        global_security: config.security,
-        prometheus_config: config.prometheus,
-        #[cfg(feature = "admin")]
-        admin_config,
-        #[cfg(feature = "access-log")]
-        access_log_config: config.access_log,
-        upstream_groups: Arc::new(upstream_groups),
-        #[cfg(feature = "http2")]
-        http2_enabled,
-        #[cfg(feature = "http2")]
-        http2_config,
-        #[cfg(feature = "http3")]
-        http3_config,
-        #[cfg(feature = "http2")]
-        h2c_enabled,
-        #[cfg(feature = "http2")]
-        h2c_listen,
-        performance: config.performance.clone(),
-    })
-}
+    let bytes_read = match result {
+        Ok(n) if n > 0 => n,
+        _ => return,
+    };
 
-/// ルート配列から OptimizedRouter を構築
-///
-/// Phase 1: Host-based グループ化
-/// Phase 2: Path Radix Tree (matchit)
-/// Phase 3: CIDR Tree 最適化
-/// Phase 4: LRU キャッシュ（構築時に初期化）
-fn build_optimized_router(routes: &[Route]) -> Arc<routing::OptimizedRouter> {
-    let mut router = routing::OptimizedRouter::with_cache_capacity(10000);
+    // HTTPリクエストをパース
+    let mut headers = [httparse::EMPTY_HEADER; 32];
+    let mut req = Request::new(&mut headers);
 
-    for (idx, route) in routes.iter().enumerate() {
-        let conditions = &route.conditions;
+    let path = match req.parse(&buffer[..bytes_read]) {
+        Ok(Status::Complete(_)) | Ok(Status::Partial) => req.path.unwrap_or("/"),
+        Err(_) => "/",
+    };
 
-        // host条件
-        let host = conditions.host.as_deref();
-        // path条件
-        let path = conditions.path.as_deref();
-        // source_ip条件
-        let source_ip = conditions.source_ip.as_deref();
+    // Hostヘッダーを取得
+    let host = req
+        .headers
+        .iter()
+        .find(|h| h.name.eq_ignore_ascii_case("Host"))
+        .map(|h| std::str::from_utf8(h.value).unwrap_or(""))
+        .unwrap_or("");
 
-        router.add_route(idx, host, path, source_ip);
-    }
+    // 設定から抽出したHTTPSポートを取得
+    let port = HTTPS_REDIRECT_PORT.load(std::sync::atomic::Ordering::Relaxed);
 
-    // CIDRマッチャーを最適化（ソート）
-    router.finalize();
+    // リダイレクトURLを構築
+    let redirect_url = if host.is_empty() {
+        // Hostヘッダーがない場合はlocalhost
+        if port == 443 {
+            format!("https://localhost{}", path)
+        } else {
+            format!("https://localhost:{}{}", port, path)
+        }
+    } else {
+        // ホストにポート番号が含まれている場合は除去
+        let clean_host = host.split(':').next().unwrap_or(host);
+        if port == 443 {
+            format!("https://{}{}", clean_host, path)
+        } else {
+            format!("https://{}:{}{}", clean_host, port, path)
+        }
+    };
 
-    info!(
-        "Built OptimizedRouter: {} routes indexed (host groups: {} exact + {} wildcard, path patterns: {})",
-        routes.len(),
-        router.host_router.exact_count(),
-        router.host_router.wildcard_count(),
-        router.path_router.patterns_count()
+    // 301レスポンスを構築
+    let mut response = Vec::with_capacity(
+        HTTP_301_REDIRECT_TEMPLATE.len() + redirect_url.len() + HTTP_301_REDIRECT_SUFFIX.len(),
     );
+    response.extend_from_slice(HTTP_301_REDIRECT_TEMPLATE);
+    response.extend_from_slice(redirect_url.as_bytes());
+    response.extend_from_slice(HTTP_301_REDIRECT_SUFFIX);
+
+    // レスポンスを送信
+    let _ = timeout(Duration::from_secs(5), stream.write_all(response)).await;
+}
+
+/// High-Performance Reverse Proxy Server
+///
+/// io_uring (monoio) と rustls を使用した高性能リバースプロキシサーバー
+#[derive(Parser, Debug)]
+#[command(name = "veil")]
+#[command(author, version, about, long_about = None)]
+pub struct CliArgs {
+    /// 設定ファイルのパス
+    #[arg(short, long, default_value = DEFAULT_CONFIG_PATH)]
+    pub config: PathBuf,
 
-    Arc::new(router)
+    /// 設定ファイルの構文と内容を検証して終了（nginx -t 相当）
+    #[arg(short = 't', long = "test")]
+    pub test_config: bool,
 }
 
+/// 設定ファイルを検証（読み込みとバリデーションのみ、起動しない）
+///
+/// nginx -t 相当の機能を提供します。
+/// - TOML構文のパース
+/// - 設定値のバリデーション
+/// - TLS証明書・秘密鍵の存在確認
+///
+/// 成功時は表示用の補足（ソケットプロファイルと適用先、F-142）を返す。
 // 理由付き allow: 起動・リロード・設定検証時のみ実行されるコールドパス（データプレーン非経由）。
 #[allow(clippy::disallowed_methods)]
-pub fn load_config(path: &Path) -> io::Result<LoadedConfig> {
-    let config_str = fs::read_to_string(path)?;
+pub fn test_config_file(path: &Path) -> io::Result<Vec<String>> {
+    // ファイル存在確認
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("configuration file not found: {}", path.display()),
+        ));
+    }
+
+    // TOMLパース
+    let config_str = std::fs::read_to_string(path)?;
     let config: Config = toml::from_str(&config_str).map_err(|e| {
         io::Error::new(
             io::ErrorKind::InvalidData,
@@ -5714,879 +10521,2019 @@ pub fn load_config(path: &Path) -> io::Result<LoadedConfig> {
         )
     })?;
 
-    // 設定ファイルのバリデーション
+    // 設定バリデーション
     validate_config(&config)?;
 
-    // kTLS設定（TLS設定より先に読み込む）
-    let ktls_config = KtlsConfig {
-        enabled: config.tls.ktls_enabled,
-        enable_tx: config.tls.ktls_enabled,
-        enable_rx: config.tls.ktls_enabled,
-        fallback_enabled: config.tls.ktls_fallback_enabled,
-        tcp_cork_enabled: config.tls.tcp_cork_enabled,
-    };
+    // TLS証明書の存在確認
+    let cert_path = Path::new(&config.tls.cert_path);
+    if !cert_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("TLS certificate not found: {}", config.tls.cert_path),
+        ));
+    }
 
-    // HTTP/2・HTTP/3・H2C 設定を読み込み
-    // 有効化フラグは server セクションで管理、詳細設定は [http2]/[http3] セクション
-    #[cfg(feature = "http2")]
-    let http2_enabled = config.server.http2_enabled;
-    #[cfg(feature = "http3")]
-    let http3_enabled = config.server.http3_enabled;
-    #[cfg(feature = "http2")]
-    let http2_config = config.http2.clone();
-    #[cfg(feature = "http3")]
-    let http3_config = config.http3.clone();
-    #[cfg(feature = "http3")]
-    let http3_listen = http3_config.listen.clone();
-    #[cfg(feature = "http2")]
-    let h2c_enabled = config.server.h2c_enabled;
-    #[cfg(feature = "http2")]
-    let h2c_listen = config.server.h2c_listen.clone();
+    // TLS秘密鍵の存在確認
+    let key_path = Path::new(&config.tls.key_path);
+    if !key_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("TLS key not found: {}", config.tls.key_path),
+        ));
+    }
 
-    // Serverヘッダー設定を初期化
-    init_server_header(
-        config.server.server_header_enabled,
-        &config.server.server_header_value,
-    );
+    Ok(socket_profile_report(&config))
+}
 
-    // Alt-Svc（HTTP/3 広告、F-94）
-    apply_alt_svc_from_config(&config);
+// ====================
+// ロードバランシングのテスト（F-19）
+// ====================
+#[cfg(test)]
+mod blocklist_tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
-    // バッファプール設定を初期化
-    init_buffer_pool_config(config.buffer_pool.clone());
+    #[test]
+    fn test_cidr_contains_addr_ipv4() {
+        let cidr = CidrRange::parse("192.168.1.0/24").unwrap();
+        assert!(cidr.contains_addr(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 50))));
+        assert!(cidr.contains_addr(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 255))));
+        assert!(!cidr.contains_addr(IpAddr::V4(Ipv4Addr::new(192, 168, 2, 1))));
+        // IPv6 は IPv4 CIDR にマッチしない
+        assert!(!cidr.contains_addr(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+    }
 
-    // TLS設定（kTLS有効時はシークレット抽出を有効化、HTTP/2有効時はALPN設定）
-    #[cfg(feature = "http2")]
-    let tls_config = load_tls_config(&config.tls, ktls_config.enabled, http2_enabled)?;
-    #[cfg(not(feature = "http2"))]
-    let tls_config = load_tls_config(&config.tls, ktls_config.enabled, false)?;
+    #[test]
+    fn test_cidr_contains_addr_single_ipv4() {
+        let cidr = CidrRange::parse("10.0.0.5").unwrap();
+        assert!(cidr.contains_addr(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5))));
+        assert!(!cidr.contains_addr(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 6))));
+    }
 
-    // Upstream グループを構築（ロードバランシング用）
-    let mut upstream_groups: HashMap<String, Arc<UpstreamGroup>> = HashMap::new();
-    if let Some(upstreams) = &config.upstreams {
-        for (name, cfg) in upstreams {
-            let algorithm = resolve_algorithm(&cfg.algorithm, &cfg.hash_key);
-            if let Some(group) = UpstreamGroup::new(
-                name.clone(),
-                cfg.servers.clone(),
-                algorithm.clone(),
-                cfg.health_check.clone(),
-                cfg.tls_insecure,
-            ) {
-                let group = group.with_resilience(&cfg.circuit_breaker, &cfg.outlier_detection);
-                info!(
-                    "Loaded upstream '{}' with {} servers ({:?})",
-                    name,
-                    group.len(),
-                    algorithm
-                );
-                if cfg.health_check.is_some() {
-                    info!("  Health check enabled for '{}'", name);
-                }
-                upstream_groups.insert(name.clone(), Arc::new(group));
-            } else {
-                warn!("Failed to load upstream '{}': no valid servers", name);
-            }
-        }
+    #[test]
+    fn test_cidr_contains_addr_ipv6() {
+        let cidr = CidrRange::parse("2001:db8::/32").unwrap();
+        assert!(cidr.contains_addr("2001:db8::1".parse::<IpAddr>().unwrap()));
+        assert!(!cidr.contains_addr("2001:db9::1".parse::<IpAddr>().unwrap()));
+        // IPv4 は IPv6 CIDR にマッチしない
+        assert!(!cidr.contains_addr(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1))));
     }
 
-    // 統合ルーティング（[[route]]）の読み込み
-    let routes = if let Some(routes_config) = config.route {
-        let mut routes_vec = Vec::with_capacity(routes_config.len());
-        for route in routes_config {
-            routes_vec.push(route);
-        }
-        Arc::new(routes_vec)
-    } else {
-        Arc::new(Vec::new())
-    };
+    #[test]
+    fn test_global_blocklist_set_and_check() {
+        // 空のときは常に false（accept ホットパスの早期 return）
+        set_global_blocked_ips(&[]);
+        assert!(!is_ip_blocked(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 5))));
 
-    // OptimizedRouter を構築（Phase 1-4 最適化）
-    let optimized_router = build_optimized_router(&routes);
+        set_global_blocked_ips(&["203.0.113.0/24".to_string(), "198.51.100.7".to_string()]);
+        assert!(is_ip_blocked(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 5))));
+        assert!(is_ip_blocked(IpAddr::V4(Ipv4Addr::new(198, 51, 100, 7))));
+        assert!(!is_ip_blocked(IpAddr::V4(Ipv4Addr::new(198, 51, 100, 8))));
+        assert!(!is_ip_blocked(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8))));
 
-    // グローバルOpenFileCache設定を適用
-    let performance_config = &config.performance;
-    cache::configure_global_open_file_cache(
-        performance_config.open_file_cache_enabled,
-        performance_config.open_file_cache_valid_duration_secs,
-        performance_config.open_file_cache_max_entries,
-    );
+        // グローバル状態を空に戻す（他テストへの影響を避ける）
+        set_global_blocked_ips(&[]);
+    }
+}
 
-    // F-35: グローバル IP ブロックリストを適用（起動時・SIGHUP リロード時の両方で本関数が
-    // 呼ばれるためここで一元的に適用する）。CIDR はパース済みで保持され accept ホットパスでは
-    // 文字列解析を行わない。
-    set_global_blocked_ips(&config.security.blocked_ips);
+#[cfg(test)]
+mod load_balancing_tests {
+    use super::*;
 
-    // スレッド数の決定: 未指定または0の場合はCPUコア数を使用
-    let num_threads = match config.server.threads {
-        Some(n) if n > 0 => n,
-        _ => num_cpus::get(),
-    };
+    fn entry(url: &str, weight: u32) -> UpstreamServerEntry {
+        UpstreamServerEntry {
+            url: url.to_string(),
+            sni_name: None,
+            use_h2c: false,
+            weight,
+            priority: 0,
+        }
+    }
 
-    // HTTPリスナーアドレスをパース（HTTPSリダイレクト用）
-    let listen_http_addr =
-        config
-            .server
-            .http
-            .as_ref()
-            .and_then(|addr| match addr.parse::<SocketAddr>() {
-                Ok(socket_addr) => Some(socket_addr),
-                Err(e) => {
-                    warn!("Invalid HTTP listen address '{}': {}", addr, e);
-                    None
-                }
-            });
+    #[test]
+    fn weighted_distribution_roughly_2_to_1() {
+        let entries = vec![
+            entry("http://10.0.0.1:80", 2),
+            entry("http://10.0.0.2:80", 1),
+        ];
+        let group = UpstreamGroup::new(
+            "wrr".into(),
+            entries,
+            LoadBalanceAlgorithm::Weighted,
+            None,
+            false,
+        )
+        .unwrap();
 
-    // Prometheusメトリクス設定をログ出力
-    // F-09: ランタイムトグルを設定（enabled=false で record_* がノーオップ・endpoint が 404）
-    crate::metrics::set_metrics_runtime_enabled(config.prometheus.enabled);
-    if config.prometheus.enabled {
-        info!(
-            "Prometheus metrics enabled at path: {}",
-            config.prometheus.path
+        let mut counts = [0usize; 2];
+        for _ in 0..3000 {
+            let s = group.select("1.2.3.4").unwrap();
+            if s.target.host == "10.0.0.1" {
+                counts[0] += 1;
+            } else {
+                counts[1] += 1;
+            }
+        }
+        // 重み 2:1 なので server1 はおよそ server2 の2倍
+        let ratio = counts[0] as f64 / counts[1] as f64;
+        assert!(
+            ratio > 1.7 && ratio < 2.3,
+            "weighted ratio out of range: {} (counts={:?})",
+            ratio,
+            counts
         );
-        if !config.prometheus.allowed_ips.is_empty() {
-            info!("  Allowed IPs: {:?}", config.prometheus.allowed_ips);
+    }
+
+    #[test]
+    fn consistent_hash_same_key_same_server() {
+        let entries = vec![
+            entry("http://10.0.0.1:80", 1),
+            entry("http://10.0.0.2:80", 1),
+            entry("http://10.0.0.3:80", 1),
+        ];
+        let group = UpstreamGroup::new(
+            "ch".into(),
+            entries,
+            LoadBalanceAlgorithm::ConsistentHash {
+                hash_key: HashKey::Ip,
+            },
+            None,
+            false,
+        )
+        .unwrap();
+
+        // 同じキーは常に同じサーバーへ
+        let first = group.select("192.168.1.50").unwrap().target.host.clone();
+        for _ in 0..100 {
+            let host = group.select("192.168.1.50").unwrap().target.host.clone();
+            assert_eq!(host, first, "consistent hash not stable");
+        }
+
+        // 別のキーでも安定していること
+        let second = group.select("8.8.8.8").unwrap().target.host.clone();
+        for _ in 0..100 {
+            let host = group.select("8.8.8.8").unwrap().target.host.clone();
+            assert_eq!(host, second);
         }
-    } else {
-        info!("Prometheus metrics disabled");
     }
 
-    // TLS証明書をバイト列として読み込み（HTTP/3用、Landlock適用前に読み込み）
-    // これによりLandlock適用後も証明書ファイルへのアクセスなしで動作可能
-    let tls_cert_pem = fs::read(&config.tls.cert_path).map_err(|e| {
-        io::Error::new(
-            e.kind(),
-            format!("Failed to read TLS cert '{}': {}", config.tls.cert_path, e),
-        )
-    })?;
-    let tls_key_pem = fs::read(&config.tls.key_path).map_err(|e| {
-        io::Error::new(
-            e.kind(),
-            format!("Failed to read TLS key '{}': {}", config.tls.key_path, e),
+    #[test]
+    fn consistent_hash_distributes_keys() {
+        let entries = vec![
+            entry("http://10.0.0.1:80", 1),
+            entry("http://10.0.0.2:80", 1),
+            entry("http://10.0.0.3:80", 1),
+        ];
+        let group = UpstreamGroup::new(
+            "ch".into(),
+            entries,
+            LoadBalanceAlgorithm::ConsistentHash {
+                hash_key: HashKey::Ip,
+            },
+            None,
+            false,
         )
-    })?;
+        .unwrap();
 
-    info!(
-        "TLS certificates pre-loaded for Landlock compatibility (cert: {} bytes, key: {} bytes)",
-        tls_cert_pem.len(),
-        tls_key_pem.len()
-    );
+        let mut seen = std::collections::HashSet::new();
+        for i in 0..200 {
+            let key = format!("10.20.30.{}", i % 256);
+            let host = group.select(&key).unwrap().target.host.clone();
+            seen.insert(host);
+        }
+        // 150 vnodes/サーバーで十分に分散され、全サーバーが使われるはず
+        assert_eq!(
+            seen.len(),
+            3,
+            "keys not distributed across all servers: {:?}",
+            seen
+        );
+    }
 
-    // WASM Filter Engineの初期化
-    #[cfg(feature = "wasm")]
-    let wasm_filter_engine = if let Some(wasm_config) = &config.wasm {
-        if wasm_config.enabled {
-            // バリデーション（validate_configで既に実行されているが、念のため）
-            if let Err(e) = wasm_config.validate() {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("WASM config validation failed: {}", e),
-                ));
-            }
+    #[test]
+    fn hash_key_parse() {
+        assert_eq!(HashKey::parse("ip").unwrap(), HashKey::Ip);
+        assert_eq!(
+            HashKey::parse("header:X-User-Id").unwrap(),
+            HashKey::Header("X-User-Id".into())
+        );
+        assert_eq!(
+            HashKey::parse("cookie:session_id").unwrap(),
+            HashKey::Cookie("session_id".into())
+        );
+        assert!(HashKey::parse("garbage").is_err());
+    }
 
-            // FilterEngine初期化
-            info!("Initializing WASM Filter Engine...");
-            match crate::wasm::init(wasm_config) {
-                Ok(engine) => {
-                    info!("WASM Filter Engine initialized successfully");
-                    Some(Arc::new(engine))
-                }
-                Err(e) => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        format!("Failed to initialize WASM Filter Engine: {}", e),
-                    ));
-                }
-            }
-        } else {
-            info!("WASM extensions disabled");
-            None
+    /// F-97: header キーの Consistent Hash が select_with_header_fn で安定
+    #[test]
+    fn consistent_hash_header_key_via_select_with_header_fn() {
+        let entries = vec![
+            entry("http://10.0.0.1:80", 1),
+            entry("http://10.0.0.2:80", 1),
+        ];
+        let group = UpstreamGroup::new(
+            "ch-hdr".into(),
+            entries,
+            LoadBalanceAlgorithm::ConsistentHash {
+                hash_key: HashKey::Header("x-user-id".into()),
+            },
+            None,
+            false,
+        )
+        .unwrap();
+
+        let headers: Vec<(&[u8], &[u8])> = vec![(b"x-user-id", b"user-stable-1")];
+        let first = group
+            .select_with_header_fn("1.2.3.4", |name| {
+                headers
+                    .iter()
+                    .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                    .map(|(_, v)| *v)
+            })
+            .unwrap()
+            .target
+            .host
+            .clone();
+        for _ in 0..20 {
+            let host = group
+                .select_with_header_fn("9.9.9.9", |name| {
+                    headers
+                        .iter()
+                        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                        .map(|(_, v)| *v)
+                })
+                .unwrap()
+                .target
+                .host
+                .clone();
+            assert_eq!(
+                host, first,
+                "same header value must stick to same backend regardless of client IP"
+            );
         }
-    } else {
-        None
-    };
 
-    Ok(LoadedConfig {
-        listen_addr: config.server.listen,
-        listen_http_addr,
-        tls_config,
-        tls_cert_path: config.tls.cert_path.clone(),
-        tls_key_path: config.tls.key_path.clone(),
-        tls_auto_reload: config.tls.auto_reload,
-        tls_reload_interval_secs: config.tls.reload_interval_secs,
-        tls_cipher_suites: config.tls.cipher_suites.clone(),
-        tls_cert_pem: Arc::new(tls_cert_pem),
-        tls_key_pem: Arc::new(tls_key_pem),
-        route: routes,
-        optimized_router,
-        ktls_config,
-        reuseport_balancing: config.performance.reuseport_balancing,
-        num_threads,
-        huge_pages_enabled: config.performance.huge_pages_enabled,
-        global_security: config.security,
-        logging: config.logging,
-        prometheus_config: config.prometheus,
-        #[cfg(feature = "admin")]
-        admin_config: {
-            let mut a = config.admin;
-            a.compute_derived();
-            a
-        },
-        #[cfg(feature = "access-log")]
-        access_log_config: config.access_log,
-        opentelemetry: config.opentelemetry,
-        upstream_groups: Arc::new(upstream_groups),
-        #[cfg(feature = "http2")]
-        http2_enabled,
-        #[cfg(feature = "http3")]
-        http3_enabled,
-        #[cfg(feature = "http3")]
-        http3_listen,
-        #[cfg(feature = "http2")]
-        http2_config,
-        #[cfg(feature = "http3")]
-        http3_config,
-        #[cfg(feature = "http2")]
-        h2c_enabled,
-        #[cfg(feature = "http2")]
-        h2c_listen,
-        #[cfg(feature = "wasm")]
-        wasm_filter_engine,
-        performance: config.performance.clone(),
-        graceful_shutdown_timeout_secs: config.server.graceful_shutdown_timeout_secs,
-        #[cfg(feature = "l4-proxy")]
-        l4_listeners: config.l4.unwrap_or_default(),
-    })
-}
+        // Cookie キー
+        assert_eq!(
+            extract_cookie_value("a=1; session_id=abc; b=2", "session_id"),
+            Some("abc")
+        );
+    }
 
-/// 設定ファイルからログ設定のみを読み込む（ログ初期化前用）
-// 理由付き allow: 起動・リロード・設定検証時のみ実行されるコールドパス（データプレーン非経由）。
-#[allow(clippy::disallowed_methods)]
-pub fn load_logging_config(path: &Path) -> io::Result<LoggingConfigSection> {
-    let config_str = fs::read_to_string(path)?;
-    let config: Config = toml::from_str(&config_str).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("TOML parse error: {}", e),
-        )
-    })?;
-    Ok(config.logging)
-}
+    #[test]
+    fn weighted_entry_default_weight_is_one() {
+        let s = entry("http://x:80", 1);
+        assert_eq!(s.weight, 1);
+    }
 
-// JSON形式ログフォーマッタ（JsonLogFormat 等）と init_logging は
-// crate::logging モジュールに移動しました。
+    #[test]
+    fn round_robin_still_cycles() {
+        let entries = vec![
+            entry("http://10.0.0.1:80", 1),
+            entry("http://10.0.0.2:80", 1),
+        ];
+        let group = UpstreamGroup::new(
+            "rr".into(),
+            entries,
+            LoadBalanceAlgorithm::RoundRobin,
+            None,
+            false,
+        )
+        .unwrap();
+        let a = group.select("x").unwrap().target.host.clone();
+        let b = group.select("x").unwrap().target.host.clone();
+        assert_ne!(a, b, "round robin should alternate");
+    }
 
-// 理由付き allow: 起動・リロード・設定検証時のみ実行されるコールドパス（データプレーン非経由）。
-#[allow(clippy::disallowed_methods)]
-pub fn load_backend(
-    route: &Route,
-    upstream_groups: &HashMap<String, Arc<UpstreamGroup>>,
-) -> io::Result<Backend> {
-    // Routeレベルの設定を取得（route直下の設定のみを使用）
-    let security = route.security.clone().unwrap_or_default();
-    let compression = route.compression.clone().unwrap_or_default();
-    let buffering = route.buffering.clone().unwrap_or_default();
-    let cache = route.cache.clone().unwrap_or_default();
-    let modules_arc = route.modules.as_ref().map(|m| Arc::new(m.clone()));
+    fn hash_group(name: &str, hosts: &[&str], algorithm: LoadBalanceAlgorithm) -> UpstreamGroup {
+        let entries = hosts
+            .iter()
+            .map(|h| entry(&format!("http://{}:80", h), 1))
+            .collect();
+        UpstreamGroup::new(name.into(), entries, algorithm, None, false).unwrap()
+    }
+
+    /// 10,000 キーの割り当て先（host）を返す
+    fn key_mapping(group: &UpstreamGroup) -> Vec<String> {
+        (0..10_000)
+            .map(|i| {
+                let key = format!("/objects/{}", i);
+                group
+                    .select_with_key("0.0.0.0", Some(&key), None)
+                    .unwrap()
+                    .target
+                    .host
+                    .clone()
+            })
+            .collect()
+    }
 
-    match &route.action {
-        BackendConfig::Proxy {
-            url,
-            sni_name,
-            use_h2c,
-        } => {
-            // 単一URLの場合は UpstreamGroup::single で単一サーバーのグループを作成
-            let target = ProxyTarget::parse(url)
-                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid proxy URL"))?
-                .with_sni_name(sni_name.clone())
-                .with_h2c(*use_h2c);
+    /// (移動キー割合, 残存サーバー担当キーのうち移動した割合)
+    fn moved_fraction(before: &[String], after: &[String], removed: &str) -> (f64, f64) {
+        let moved = before.iter().zip(after).filter(|(b, a)| b != a).count();
+        let kept: Vec<_> = before
+            .iter()
+            .zip(after)
+            .filter(|(b, _)| *b != removed)
+            .collect();
+        let kept_moved = kept.iter().filter(|(b, a)| b != a).count();
+        (
+            moved as f64 / before.len() as f64,
+            kept_moved as f64 / kept.len().max(1) as f64,
+        )
+    }
 
-            if *use_h2c && !target.use_tls {
-                info!("H2C (HTTP/2 over cleartext) enabled for backend: {}", url);
-            }
+    const FIVE: [&str; 5] = ["10.0.0.1", "10.0.0.2", "10.0.0.3", "10.0.0.4", "10.0.0.5"];
+    const FOUR: [&str; 4] = ["10.0.0.1", "10.0.0.2", "10.0.0.4", "10.0.0.5"];
 
-            // 圧縮設定のログ出力
-            if compression.enabled {
-                info!(
-                    "Response compression enabled for backend: {} (gzip_level={}, brotli_level={})",
-                    url, compression.gzip_level, compression.brotli_level
-                );
-            }
+    #[test]
+    fn consistent_hash_membership_change_moves_only_removed_keys() {
+        let algo = LoadBalanceAlgorithm::ConsistentHash {
+            hash_key: HashKey::Path,
+        };
+        let before = key_mapping(&hash_group("ch", &FIVE, algo.clone()));
+        let after = key_mapping(&hash_group("ch", &FOUR, algo));
+        let (moved, kept_moved) = moved_fraction(&before, &after, "10.0.0.3");
+        assert_eq!(kept_moved, 0.0, "keys of surviving servers must not move");
+        assert!(moved > 0.1 && moved < 0.3, "moved fraction {}", moved);
+    }
 
-            // バッファリング設定のログ出力
-            if buffering.is_enabled() {
-                info!(
-                    "Response buffering enabled for backend: {} (mode={:?}, max_memory={})",
-                    url, buffering.mode, buffering.max_memory_buffer
-                );
-            }
+    #[test]
+    fn maglev_membership_change_has_minimal_disruption() {
+        let algo = LoadBalanceAlgorithm::Maglev {
+            hash_key: HashKey::Path,
+        };
+        let before = key_mapping(&hash_group("mg", &FIVE, algo.clone()));
+        let after = key_mapping(&hash_group("mg", &FOUR, algo.clone()));
+        let (moved, kept_moved) = moved_fraction(&before, &after, "10.0.0.3");
+        // 理想は 1/5。Maglev は完全な最小移動ではないが、残存サーバー分の移動は僅か
+        assert!(moved > 0.15 && moved < 0.3, "moved fraction {}", moved);
+        assert!(kept_moved < 0.05, "surviving keys moved {}", kept_moved);
 
-            // キャッシュ設定のログ出力
-            if cache.enabled {
-                info!(
-                    "Proxy cache enabled for backend: {} (max_memory={}, ttl={}s)",
-                    url, cache.max_memory_size, cache.default_ttl_secs
-                );
-            }
+        // 追加時も同様（4 -> 5）
+        let (moved, _) = moved_fraction(&after, &before, "");
+        assert!(moved < 0.3, "moved fraction on add {}", moved);
+    }
 
-            let group = UpstreamGroup::single(target);
-            Ok(Backend::Proxy(
-                Arc::new(group),
-                Arc::new(security.clone()),
-                Arc::new(compression.clone()),
-                Arc::new(buffering.clone()),
-                Arc::new(cache.clone()),
-                modules_arc.clone(),
-            ))
+    #[test]
+    fn maglev_unhealthy_server_only_moves_its_keys() {
+        let algo = LoadBalanceAlgorithm::Maglev {
+            hash_key: HashKey::Path,
+        };
+        let group = hash_group("mg", &FIVE, algo);
+        let before = key_mapping(&group);
+        group.servers[2].healthy.store(false, Ordering::SeqCst);
+        let after = key_mapping(&group);
+        let (_, kept_moved) = moved_fraction(&before, &after, "10.0.0.3");
+        assert_eq!(kept_moved, 0.0);
+        assert!(after.iter().all(|h| h != "10.0.0.3"));
+    }
+
+    #[test]
+    fn maglev_table_is_evenly_populated() {
+        let group = hash_group(
+            "mg",
+            &FIVE,
+            LoadBalanceAlgorithm::Maglev {
+                hash_key: HashKey::Ip,
+            },
+        )
+        .with_hash_options(DEFAULT_HASH_LOAD_FACTOR, 1009);
+        assert_eq!(group.maglev_table.len(), 1009);
+        let mut counts = [0usize; 5];
+        for &idx in group.maglev_table.iter() {
+            counts[idx as usize] += 1;
         }
-        BackendConfig::ProxyUpstream { upstream, use_h2c } => {
-            // Upstream グループ参照
-            let group = upstream_groups.get(upstream).ok_or_else(|| {
-                io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    format!("Upstream '{}' not found", upstream),
-                )
-            })?;
+        // 均等重みならスロット数の差は高々 1
+        let (min, max) = (counts.iter().min().unwrap(), counts.iter().max().unwrap());
+        assert!(max - min <= 1, "uneven table: {:?}", counts);
+    }
 
-            // ルート設定で use_h2c が指定されている場合はオーバーライド
-            let group = if *use_h2c {
-                Arc::new(group.with_h2c(true))
-            } else {
-                group.clone()
-            };
+    #[test]
+    fn bounded_load_matches_ring_when_idle() {
+        let ring = hash_group(
+            "ch",
+            &FIVE,
+            LoadBalanceAlgorithm::ConsistentHash {
+                hash_key: HashKey::Path,
+            },
+        );
+        let bounded = hash_group(
+            "bl",
+            &FIVE,
+            LoadBalanceAlgorithm::BoundedLoadHash {
+                hash_key: HashKey::Path,
+            },
+        );
+        assert_eq!(key_mapping(&ring), key_mapping(&bounded));
+
+        // メンバー変更時の移動も Consistent Hash と同じ（負荷がない場合）
+        let after = key_mapping(&hash_group(
+            "bl",
+            &FOUR,
+            LoadBalanceAlgorithm::BoundedLoadHash {
+                hash_key: HashKey::Path,
+            },
+        ));
+        let (_, kept_moved) = moved_fraction(&key_mapping(&bounded), &after, "10.0.0.3");
+        assert_eq!(kept_moved, 0.0);
+    }
 
-            // 圧縮設定のログ出力
-            if compression.enabled {
-                info!("Response compression enabled for upstream: {} (gzip_level={}, brotli_level={})", 
-                      upstream, compression.gzip_level, compression.brotli_level);
-            }
+    #[test]
+    fn bounded_load_caps_hot_key() {
+        let group = hash_group(
+            "bl",
+            &["10.0.0.1", "10.0.0.2", "10.0.0.3"],
+            LoadBalanceAlgorithm::BoundedLoadHash {
+                hash_key: HashKey::Path,
+            },
+        )
+        .with_hash_options(1.25, DEFAULT_MAGLEV_TABLE_SIZE);
+        // 同一キーへの同時リクエストを解放せずに積み上げる
+        let total = 300;
+        for _ in 0..total {
+            let s = group
+                .select_with_key("0.0.0.0", Some("/hot/object"), None)
+                .unwrap();
+            s.acquire();
+        }
+        let cap = (1.25 * total as f64 / 3.0).ceil() as usize;
+        for s in &group.servers {
+            assert!(
+                s.connections() <= cap,
+                "{} exceeded cap {}: {}",
+                s.target.host,
+                cap,
+                s.connections()
+            );
+        }
+        // ホットキーのオーナー以外にも溢れている
+        assert!(group.servers.iter().filter(|s| s.connections() > 0).count() >= 2);
+    }
 
-            // バッファリング設定のログ出力
-            if buffering.is_enabled() {
-                info!(
-                    "Response buffering enabled for upstream: {} (mode={:?}, max_memory={})",
-                    upstream, buffering.mode, buffering.max_memory_buffer
-                );
-            }
+    #[test]
+    fn path_hash_key_ignores_query() {
+        assert_eq!(HashKey::parse("path").unwrap(), HashKey::Path);
+        assert_eq!(path_hash_value(b"/a/b?x=1"), Some("/a/b"));
+        assert_eq!(path_hash_value(b"?x=1"), None);
+        let group = hash_group(
+            "ch",
+            &FIVE,
+            LoadBalanceAlgorithm::Maglev {
+                hash_key: HashKey::Path,
+            },
+        );
+        let pick = |path: &[u8], ip: &str| {
+            group
+                .select_for_request(ip, path, |_| None)
+                .unwrap()
+                .target
+                .host
+                .clone()
+        };
+        let first = pick(b"/img/cat.png?w=100", "1.1.1.1");
+        assert_eq!(pick(b"/img/cat.png?w=200", "2.2.2.2"), first);
+        assert_eq!(pick(b"/img/cat.png", "3.3.3.3"), first);
+    }
 
-            // キャッシュ設定のログ出力
-            if cache.enabled {
-                info!(
-                    "Proxy cache enabled for upstream: {} (max_memory={}, ttl={}s)",
-                    upstream, cache.max_memory_size, cache.default_ttl_secs
-                );
+    #[test]
+    fn hash_algorithms_parse_from_toml() {
+        let cfg: UpstreamConfig = toml::from_str(
+            r#"
+            algorithm = "bounded_load"
+            hash_key = "path"
+            hash_load_factor = 1.5
+            servers = ["http://10.0.0.1:80"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            resolve_algorithm(&cfg.algorithm, &cfg.hash_key),
+            LoadBalanceAlgorithm::BoundedLoadHash {
+                hash_key: HashKey::Path
             }
-
-            Ok(Backend::Proxy(
-                group.clone(),
-                Arc::new(security.clone()),
-                Arc::new(compression.clone()),
-                Arc::new(buffering.clone()),
-                Arc::new(cache.clone()),
-                modules_arc.clone(),
-            ))
-        }
-        BackendConfig::File { path, mode, index } => {
-            // Routeレベルの設定のみを使用
-            let security = route.security.clone().unwrap_or_default();
-            let cache = route.cache.clone().unwrap_or_default();
-            let access_err = |e: io::Error| {
-                let error_msg = format!(
-                    "Failed to access file '{}': {} (error code: {})",
-                    path,
-                    e,
-                    e.raw_os_error().unwrap_or(-1)
-                );
-                io::Error::new(e.kind(), error_msg)
-            };
-            // F-123: FreeBSD capability mode 下では絶対パス `fs::metadata` が禁止される
-            // ため、登録済みルート dirfd 相対の `fstatat` で is_dir を判定する
-            // （File ルート自身は登録ルートなので rel="." で解決される）。
-            #[cfg(target_os = "freebsd")]
-            let is_dir = if let Some(res) =
-                crate::security::capsicum::stat_static(std::path::Path::new(path))
-            {
-                res.map_err(access_err)?.is_dir
-            } else {
-                fs::metadata(path).map_err(access_err)?.is_dir()
+        );
+        assert_eq!(cfg.hash_load_factor, 1.5);
+        assert_eq!(cfg.maglev_table_size, DEFAULT_MAGLEV_TABLE_SIZE);
+
+        let cfg: UpstreamConfig = toml::from_str(
+            r#"
+            algorithm = "maglev"
+            maglev_table_size = 251
+            servers = ["http://10.0.0.1:80", "http://10.0.0.2:80"]
+            "#,
+        )
+        .unwrap();
+        validate_hash_options("mg", &cfg).unwrap();
+        let group = build_upstream_group("mg", &cfg).unwrap();
+        assert_eq!(group.maglev_table.len(), 251);
+        assert!(is_prime(65537) && !is_prime(65535));
+
+        // 素数でないサイズやサーバー数以下のサイズは切り上げずに拒否する
+        let invalid = |size: usize| {
+            let cfg = UpstreamConfig {
+                maglev_table_size: size,
+                ..cfg.clone()
             };
-            #[cfg(not(target_os = "freebsd"))]
-            let is_dir = fs::metadata(path).map_err(access_err)?.is_dir();
-            // インデックスファイル名を Arc<str> に変換（None = デフォルトで "index.html"）
-            let index_file: Option<Arc<str>> = index.as_ref().map(|s| Arc::from(s.as_str()));
-            let security = Arc::new(security.clone());
-            let cache = Arc::new(cache.clone());
-            let open_file_cache_arc = route.open_file_cache.as_ref().map(|c| Arc::new(c.clone()));
-
-            // キャッシュ設定のログ出力
-            if cache.enabled {
-                info!(
-                    "File cache enabled for path: {} (max_memory={}, ttl={}s)",
-                    path, cache.max_memory_size, cache.default_ttl_secs
-                );
+            validate_hash_options("mg", &cfg).is_err()
+        };
+        assert!(invalid(250));
+        assert!(invalid(2));
+        assert!(!invalid(3));
+        assert!(validate_hash_options(
+            "mg",
+            &UpstreamConfig {
+                hash_load_factor: 1.0,
+                ..cfg.clone()
             }
+        )
+        .is_err());
+    }
 
-            match mode.as_str() {
-                "memory" => {
-                    if is_dir {
-                        return Err(io::Error::new(
-                            io::ErrorKind::InvalidInput,
-                            "Memory mode not supported for directories",
-                        ));
-                    }
-                    let data = fs::read(path)?;
-                    let mime_type = mime_guess::from_path(path).first_or_octet_stream();
+    fn sticky_group() -> UpstreamGroup {
+        hash_group("pool", &FIVE, LoadBalanceAlgorithm::RoundRobin).with_sticky_cookie(Some(
+            &StickyCookieConfig {
+                secret: Some("s3cret".into()),
+                ..Default::default()
+            },
+        ))
+    }
 
-                    Ok(Backend::MemoryFile(
-                        Arc::new(data),
-                        Arc::from(mime_type.as_ref()),
-                        security,
-                        modules_arc.clone(),
-                    ))
-                }
-                "sendfile" | "" => Ok(Backend::SendFile(
-                    Arc::new(PathBuf::from(path)),
-                    is_dir,
-                    index_file,
-                    security,
-                    cache,
-                    open_file_cache_arc,
-                    modules_arc.clone(),
-                )),
-                _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid mode")),
-            }
+    /// `Set-Cookie` 値から `name=value` 部分だけを取り出す
+    fn cookie_pair(set_cookie: &str) -> String {
+        set_cookie.split(';').next().unwrap().to_string()
+    }
+
+    #[test]
+    fn sticky_cookie_pins_server_across_requests() {
+        let group = sticky_group();
+        let (first, set) = group
+            .select_with_affinity("1.1.1.1", b"/", |_| None)
+            .unwrap();
+        let set = set.expect("first request must issue a cookie");
+        let cookie = cookie_pair(&set);
+        for _ in 0..20 {
+            let (s, set) = group
+                .select_with_affinity("1.1.1.1", b"/", |n| {
+                    (n == b"cookie").then_some(cookie.as_bytes())
+                })
+                .unwrap();
+            assert_eq!(s.target.host, first.target.host);
+            assert!(set.is_none(), "valid cookie must not be re-issued");
         }
-        BackendConfig::Redirect {
-            redirect_url,
-            redirect_status,
-            preserve_path,
-        } => Ok(Backend::Redirect(
-            Arc::from(redirect_url.as_str()),
-            *redirect_status,
-            *preserve_path,
-            modules_arc.clone(),
-        )),
     }
-}
 
-// ====================
-// コマンドライン引数パース
-// ====================
+    #[test]
+    fn sticky_cookie_repins_when_server_unhealthy_or_tampered() {
+        let group = sticky_group();
+        let (first, set) = group
+            .select_with_affinity("1.1.1.1", b"/", |_| None)
+            .unwrap();
+        let cookie = cookie_pair(&set.unwrap());
+        let idx = group
+            .servers
+            .iter()
+            .position(|s| std::ptr::eq(s, first))
+            .unwrap();
+        group.servers[idx].healthy.store(false, Ordering::SeqCst);
+        let (s, set) = group
+            .select_with_affinity("1.1.1.1", b"/", |n| {
+                (n == b"cookie").then_some(cookie.as_bytes())
+            })
+            .unwrap();
+        assert_ne!(s.target.host, first.target.host);
+        assert!(set.is_some(), "re-pin must issue a new cookie");
+
+        // 改ざん Cookie は無視して新規に固定する
+        let forged = format!("{}0", cookie);
+        let (_, set) = group
+            .select_with_affinity("1.1.1.1", b"/", |n| {
+                (n == b"cookie").then_some(forged.as_bytes())
+            })
+            .unwrap();
+        assert!(set.is_some());
+    }
 
-// ====================
-// HTTP to HTTPS リダイレクトハンドラー
-// ====================
-//
-// HTTPアクセスをHTTPSにリダイレクトするための軽量ハンドラー。
-// セキュリティ上の理由から、HTTPではリダイレクトのみを行い、
-// コンテンツは一切配信しません。
-//
-// 301 Moved Permanently を使用することで、ブラウザがリダイレクト先を
-// キャッシュし、以降のアクセスでは直接HTTPSに接続します。
+    /// 全 LoadBalanceAlgorithm（ハッシュ系は path キー）
+    fn all_algorithms() -> Vec<LoadBalanceAlgorithm> {
+        vec![
+            LoadBalanceAlgorithm::RoundRobin,
+            LoadBalanceAlgorithm::LeastConnections,
+            LoadBalanceAlgorithm::IpHash,
+            LoadBalanceAlgorithm::Weighted,
+            LoadBalanceAlgorithm::ConsistentHash {
+                hash_key: HashKey::Path,
+            },
+            LoadBalanceAlgorithm::BoundedLoadHash {
+                hash_key: HashKey::Path,
+            },
+            LoadBalanceAlgorithm::Maglev {
+                hash_key: HashKey::Path,
+            },
+        ]
+    }
+
+    /// 2,000 リクエスト（IP・キーを変化させる）の選択先 host ごとの件数
+    fn selection_counts(group: &UpstreamGroup) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for i in 0..2_000 {
+            let ip = format!("10.{}.{}.{}", i % 7, i / 256, i % 256);
+            let key = format!("/objects/{}", i);
+            let s = group.select_with_key(&ip, Some(&key), None).unwrap();
+            *counts.entry(s.target.host.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// primary 2 台 + backup 1 台のグループ
+    fn tiered_group(algorithm: LoadBalanceAlgorithm, threshold: f64) -> UpstreamGroup {
+        let mut entries = vec![
+            entry("http://10.0.0.1:80", 1),
+            entry("http://10.0.0.2:80", 1),
+            entry("http://10.0.9.1:80", 1),
+        ];
+        entries[2].priority = BACKUP_PRIORITY;
+        UpstreamGroup::new("tiered".into(), entries, algorithm, None, false)
+            .unwrap()
+            .with_traffic_shaping(&SlowStartConfig::default(), threshold)
+    }
 
-// HTTP_301_REDIRECT_TEMPLATE, HTTP_301_REDIRECT_SUFFIX は
-// crate::constants モジュールに移動しました
+    #[test]
+    fn slow_start_factor_ramps_linearly_or_by_curve() {
+        let linear = SlowStartConfig {
+            window_secs: 10,
+            aggression: 1.0,
+            min_weight_percent: 10,
+        };
+        assert_eq!(linear.factor(0), 0.1);
+        assert!((linear.factor(5_000) - 0.5).abs() < 1e-9);
+        assert_eq!(linear.factor(10_000), 1.0);
+
+        // aggression > 1 は序盤に速く立ち上がる（√t）
+        let curve = SlowStartConfig {
+            aggression: 2.0,
+            ..linear.clone()
+        };
+        assert!((curve.factor(2_500) - 0.5).abs() < 1e-9);
+        assert!(curve.factor(2_500) > linear.factor(2_500));
 
-/// HTTPリクエストを処理し、HTTPSにリダイレクトする
-///
-/// リクエストからHostヘッダーとパスを読み取り、
-/// https://{host}:{port}{path} への301リダイレクトを返します。
-/// ポートはHETTPS_REDIRECT_PORT（[server].listenから抽出）を使用し、
-/// 443の場合はURL中のポート指定を省略します。
-pub async fn handle_http_redirect(mut stream: TcpStream) {
-    // リクエストを読み取るためのバッファ（ヘッダーのみなので小さめ）
-    let mut buffer = vec![0u8; 4096];
+        // 無効時は常に 1.0
+        assert_eq!(SlowStartConfig::default().factor(0), 1.0);
+    }
 
-    // タイムアウト付きで読み取り
-    let read_result = timeout(Duration::from_secs(5), stream.read(buffer)).await;
+    #[test]
+    fn health_and_ejection_recovery_start_slow_start() {
+        let server = UpstreamServer::new(ProxyTarget::parse("http://10.0.0.1:80").unwrap());
+        assert_eq!(server.recovered_at_ms.load(Ordering::Relaxed), 0);
+        server.record_failure(1);
+        server.record_success(1);
+        assert!(server.recovered_at_ms.load(Ordering::Relaxed) > 0);
 
-    let (result, buf) = match read_result {
-        Ok(r) => r,
-        Err(_) => {
-            // タイムアウト
-            return;
-        }
-    };
-    buffer = buf;
+        let server = UpstreamServer::new(ProxyTarget::parse("http://10.0.0.2:80").unwrap());
+        server.eject_for(std::time::Duration::from_millis(0));
+        assert!(!server.is_ejected());
+        assert!(server.recovered_at_ms.load(Ordering::Relaxed) > 0);
+    }
 
-    let bytes_read = match result {
-        Ok(n) if n > 0 => n,
-        _ => return,
-    };
+    #[test]
+    fn slow_start_limits_recovering_server_for_all_algorithms() {
+        let ss = SlowStartConfig {
+            window_secs: 600,
+            aggression: 1.0,
+            min_weight_percent: 1,
+        };
+        for algo in all_algorithms() {
+            let group = hash_group("ss", &FIVE, algo.clone()).with_traffic_shaping(&ss, 0.0);
+            let baseline = selection_counts(&group)
+                .get("10.0.0.1")
+                .copied()
+                .unwrap_or(0);
 
-    // HTTPリクエストをパース
-    let mut headers = [httparse::EMPTY_HEADER; 32];
-    let mut req = Request::new(&mut headers);
+            // 復帰直後: 係数 1% → 選択数はベースラインの 1 割未満
+            group.servers[0].mark_recovered();
+            let ramping = selection_counts(&group)
+                .get("10.0.0.1")
+                .copied()
+                .unwrap_or(0);
+            assert!(
+                ramping * 10 <= baseline.max(10),
+                "{:?}: baseline={} ramping={}",
+                algo,
+                baseline,
+                ramping
+            );
 
-    let path = match req.parse(&buffer[..bytes_read]) {
-        Ok(Status::Complete(_)) | Ok(Status::Partial) => req.path.unwrap_or("/"),
-        Err(_) => "/",
-    };
+            // ランプ完了（復帰イベントなし扱い）で元の割合に戻る
+            group.servers[0].recovered_at_ms.store(0, Ordering::Relaxed);
+            let after = selection_counts(&group)
+                .get("10.0.0.1")
+                .copied()
+                .unwrap_or(0);
+            if !matches!(algo, LoadBalanceAlgorithm::LeastConnections) {
+                assert_eq!(after, baseline, "{:?}", algo);
+            }
+        }
+    }
 
-    // Hostヘッダーを取得
-    let host = req
-        .headers
-        .iter()
-        .find(|h| h.name.eq_ignore_ascii_case("Host"))
-        .map(|h| std::str::from_utf8(h.value).unwrap_or(""))
-        .unwrap_or("");
+    #[test]
+    fn slow_start_keeps_hash_keys_stable_during_ramp() {
+        let ss = SlowStartConfig {
+            window_secs: 600,
+            aggression: 1.0,
+            min_weight_percent: 30,
+        };
+        let group = hash_group(
+            "ss",
+            &FIVE,
+            LoadBalanceAlgorithm::ConsistentHash {
+                hash_key: HashKey::Path,
+            },
+        )
+        .with_traffic_shaping(&ss, 0.0);
+        group.servers[0].mark_recovered();
+        // 同じ係数の間は同じキーが同じサーバーへ向かう
+        assert_eq!(key_mapping(&group), key_mapping(&group));
+    }
 
-    // 設定から抽出したHTTPSポートを取得
-    let port = HTTPS_REDIRECT_PORT.load(std::sync::atomic::Ordering::Relaxed);
+    #[test]
+    fn backup_tier_is_unused_while_primaries_are_available() {
+        for algo in all_algorithms() {
+            let group = tiered_group(algo.clone(), 0.0);
+            let counts = selection_counts(&group);
+            assert!(!counts.contains_key("10.0.9.1"), "{:?}: {:?}", algo, counts);
 
-    // リダイレクトURLを構築
-    let redirect_url = if host.is_empty() {
-        // Hostヘッダーがない場合はlocalhost
-        if port == 443 {
-            format!("https://localhost{}", path)
-        } else {
-            format!("https://localhost:{}{}", port, path)
+            // primary 1 台停止: 閾値 0.0 では backup は使わない
+            group.servers[0].healthy.store(false, Ordering::SeqCst);
+            let counts = selection_counts(&group);
+            assert!(!counts.contains_key("10.0.9.1"), "{:?}: {:?}", algo, counts);
+
+            // primary 全滅: backup が全量を受ける
+            group.servers[1].healthy.store(false, Ordering::SeqCst);
+            let counts = selection_counts(&group);
+            assert_eq!(counts.get("10.0.9.1"), Some(&2_000), "{:?}", algo);
         }
-    } else {
-        // ホストにポート番号が含まれている場合は除去
-        let clean_host = host.split(':').next().unwrap_or(host);
-        if port == 443 {
-            format!("https://{}{}", clean_host, path)
-        } else {
-            format!("https://{}:{}{}", clean_host, port, path)
+    }
+
+    #[test]
+    fn lower_tier_spills_in_below_failover_threshold() {
+        for algo in all_algorithms() {
+            let group = tiered_group(algo.clone(), 0.75);
+            assert!(!selection_counts(&group).contains_key("10.0.9.1"));
+            // 容量 50% < 75% → backup も候補に入る
+            group.servers[0].healthy.store(false, Ordering::SeqCst);
+            let counts = selection_counts(&group);
+            assert!(!counts.contains_key("10.0.0.1"), "{:?}", algo);
+            // 接続数が増えないテストでは least_conn は常に先頭候補を選ぶため分散を見ない
+            if !matches!(algo, LoadBalanceAlgorithm::LeastConnections) {
+                assert!(counts.contains_key("10.0.9.1"), "{:?}: {:?}", algo, counts);
+            }
         }
-    };
+    }
 
-    // 301レスポンスを構築
-    let mut response = Vec::with_capacity(
-        HTTP_301_REDIRECT_TEMPLATE.len() + redirect_url.len() + HTTP_301_REDIRECT_SUFFIX.len(),
-    );
-    response.extend_from_slice(HTTP_301_REDIRECT_TEMPLATE);
-    response.extend_from_slice(redirect_url.as_bytes());
-    response.extend_from_slice(HTTP_301_REDIRECT_SUFFIX);
+    #[test]
+    fn priority_and_slow_start_parse_from_toml() {
+        let cfg: UpstreamConfig = toml::from_str(
+            r#"
+            priority_failover_threshold = 0.5
+            servers = [
+                "http://10.0.0.1:80",
+                { url = "http://10.0.0.2:80", priority = 1 },
+                { url = "http://10.0.0.3:80", backup = true },
+            ]
+            [slow_start]
+            window_secs = 30
+            aggression = 2.0
+            "#,
+        )
+        .unwrap();
+        let prios: Vec<u32> = cfg.servers.iter().map(|e| e.priority).collect();
+        assert_eq!(prios, vec![0, 1, BACKUP_PRIORITY]);
+        assert_eq!(cfg.slow_start.window_secs, 30);
+        assert_eq!(cfg.slow_start.min_weight_percent, 10);
+        let group = build_upstream_group("p", &cfg).unwrap();
+        assert_eq!(group.tiers, vec![(0, 1), (1, 1), (BACKUP_PRIORITY, 1)]);
+        assert_eq!(group.priority_failover_threshold, 0.5);
+    }
 
-    // レスポンスを送信
-    let _ = timeout(Duration::from_secs(5), stream.write_all(response)).await;
-}
+    #[test]
+    fn sticky_cookie_parses_from_toml() {
+        let cfg: UpstreamConfig = toml::from_str(
+            r#"
+            servers = ["http://10.0.0.1:80", "http://10.0.0.2:80"]
+            [sticky_cookie]
+            name = "route"
+            ttl_secs = 600
+            same_site = "strict"
+            secret = "k"
+            "#,
+        )
+        .unwrap();
+        let sc = cfg.sticky_cookie.as_ref().unwrap();
+        assert_eq!(sc.name, "route");
+        assert_eq!(sc.ttl_secs, 600);
+        assert_eq!(sc.same_site, SameSite::Strict);
+        assert!(sc.secure && sc.http_only);
+        let group = build_upstream_group("pool", &cfg).unwrap();
+        assert!(group.sticky.is_some());
+        assert_eq!(group.sticky_ids.len(), 2);
+    }
 
-/// High-Performance Reverse Proxy Server
-///
-/// io_uring (monoio) と rustls を使用した高性能リバースプロキシサーバー
-#[derive(Parser, Debug)]
-#[command(name = "veil")]
-#[command(author, version, about, long_about = None)]
-pub struct CliArgs {
-    /// 設定ファイルのパス
-    #[arg(short, long, default_value = DEFAULT_CONFIG_PATH)]
-    pub config: PathBuf,
+    #[test]
+    fn upstream_protocol_parses_and_maps_cleartext_h2_to_h2c() {
+        let cfg: UpstreamConfig = toml::from_str(
+            r#"
+            protocol = "h2"
+            h2_max_concurrent_streams = 32
+            max_response_body_bytes = 1048576
+            servers = ["https://10.0.0.1:443", "http://10.0.0.2:80"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.protocol, UpstreamProtocol::H2);
+        let group = build_upstream_group("h2", &cfg).unwrap();
+        let tls = &group.servers[0].target;
+        assert!(tls.uses_tls_h2() && !tls.use_h2c);
+        assert_eq!(tls.h2_max_concurrent_streams, 32);
+        assert_eq!(tls.max_response_body_bytes, 1048576);
+        // 平文の h2 は h2c（Prior Knowledge）
+        let plain = &group.servers[1].target;
+        assert!(plain.use_h2c && !plain.uses_tls_h2());
+        assert!(group.uses_multiplexed_upstream());
+
+        let auto: UpstreamConfig = toml::from_str(
+            r#"
+            protocol = "auto"
+            servers = ["http://10.0.0.2:80"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(auto.h2_max_concurrent_streams, 100);
+        assert_eq!(auto.max_response_body_bytes, 64 * 1024 * 1024);
+        // 平文の auto は ALPN が無いため HTTP/1.1 のまま
+        let group = build_upstream_group("auto", &auto).unwrap();
+        assert!(!group.uses_multiplexed_upstream());
+
+        let default: UpstreamConfig = toml::from_str(r#"servers = ["https://10.0.0.1"]"#).unwrap();
+        assert_eq!(default.protocol, UpstreamProtocol::Http1);
+        assert!(toml::from_str::<UpstreamConfig>(
+            r#"
+            protocol = "spdy"
+            servers = ["https://10.0.0.1"]
+            "#
+        )
+        .is_err());
+    }
 
-    /// 設定ファイルの構文と内容を検証して終了（nginx -t 相当）
-    #[arg(short = 't', long = "test")]
-    pub test_config: bool,
-}
+    #[test]
+    fn upstream_protocol_h3_applies_to_https_servers_only() {
+        let cfg: UpstreamConfig = toml::from_str(
+            r#"
+            protocol = "h3"
+            servers = ["https://10.0.0.1:443"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.protocol, UpstreamProtocol::H3);
+        let group = build_upstream_group("h3", &cfg).unwrap();
+        let target = &group.servers[0].target;
+        // h3 は ALPN h2 / h2c とは排他。http3 feature 無効時は HTTP/1.1 で接続する
+        assert!(!target.uses_tls_h2() && !target.use_h2c);
+        assert_eq!(target.uses_h3(), cfg!(feature = "http3"));
+        assert_eq!(group.uses_multiplexed_upstream(), cfg!(feature = "http3"));
+
+        let plain = ProxyTarget::parse("http://10.0.0.2:80")
+            .unwrap()
+            .with_protocol(UpstreamProtocol::H3, 100, 1024);
+        assert!(!plain.uses_h3() && !plain.use_h2c);
+    }
 
-/// 設定ファイルを検証（読み込みとバリデーションのみ、起動しない）
-///
-/// nginx -t 相当の機能を提供します。
-/// - TOML構文のパース
-/// - 設定値のバリデーション
-/// - TLS証明書・秘密鍵の存在確認
-// 理由付き allow: 起動・リロード・設定検証時のみ実行されるコールドパス（データプレーン非経由）。
-#[allow(clippy::disallowed_methods)]
-pub fn test_config_file(path: &Path) -> io::Result<()> {
-    // ファイル存在確認
-    if !path.exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("configuration file not found: {}", path.display()),
-        ));
+    #[test]
+    fn connection_pool_settings_apply_to_all_servers() {
+        let cfg: UpstreamConfig = toml::from_str(
+            r#"
+            servers = ["http://10.0.0.1:80", "http://10.0.0.2:80"]
+            [connection_pool]
+            max_connections = 32
+            max_pending_requests = 8
+            pending_timeout_ms = 250
+            max_requests_per_connection = 1000
+            max_connection_lifetime_secs = 60
+            prewarm_connections = 4
+            "#,
+        )
+        .unwrap();
+        let pool = cfg.connection_pool;
+        assert_eq!(pool.max_connections, 32);
+        assert_eq!(pool.max_concurrent_connects, 64);
+        assert_eq!(pool.pending_timeout(), Duration::from_millis(250));
+        assert!(validate_connection_pool("p", &pool).is_ok());
+        let group = build_upstream_group("p", &cfg).unwrap();
+        assert!(group
+            .servers
+            .iter()
+            .all(|s| s.target.connection_pool == pool));
+
+        let default: UpstreamConfig = toml::from_str(r#"servers = ["http://10.0.0.1"]"#).unwrap();
+        assert_eq!(default.connection_pool, ConnectionPoolConfig::default());
+        assert_eq!(default.connection_pool.max_connections, 0);
+
+        let invalid = |pool: ConnectionPoolConfig| validate_connection_pool("p", &pool).is_err();
+        assert!(invalid(ConnectionPoolConfig {
+            max_concurrent_connects: 0,
+            ..pool
+        }));
+        assert!(invalid(ConnectionPoolConfig {
+            pending_timeout_ms: 0,
+            ..pool
+        }));
+        assert!(invalid(ConnectionPoolConfig {
+            prewarm_connections: 33,
+            ..pool
+        }));
     }
 
-    // TOMLパース
-    let config_str = std::fs::read_to_string(path)?;
-    let config: Config = toml::from_str(&config_str).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("TOML parse error: {}", e),
+    #[test]
+    fn passive_health_marks_server_unhealthy_and_recovers() {
+        use crate::passive_health::PassiveOutcome;
+
+        let cfg: UpstreamConfig = toml::from_str(
+            r#"
+            servers = ["http://10.0.0.1:80", "http://10.0.0.2:80"]
+            [passive_health]
+            consecutive_5xx = 2
+            gateway_errors = 4
+            "#,
         )
-    })?;
+        .unwrap();
+        let passive = cfg.passive_health.clone().unwrap();
+        assert_eq!(passive.consecutive_connect_failures, 3);
+        assert_eq!(passive.gateway_error_window_secs, 10);
+        assert_eq!(passive.recovery_secs, 30);
+        assert!(validate_passive_health("p", &passive, false).is_ok());
+        assert!(validate_passive_health(
+            "p",
+            &PassiveHealthConfig {
+                recovery_secs: 0,
+                ..passive.clone()
+            },
+            false
+        )
+        .is_err());
+        assert!(validate_passive_health(
+            "p",
+            &PassiveHealthConfig {
+                consecutive_5xx: 0,
+                consecutive_connect_failures: 0,
+                gateway_errors: 0,
+                ..passive.clone()
+            },
+            true
+        )
+        .is_err());
+
+        // health_check なしでも 5xx の連続で即座に選択対象から外れる
+        let group = build_upstream_group("p", &cfg).unwrap();
+        assert!(group.health_check.is_none());
+        group.record_passive(1, PassiveOutcome::ServerError(500));
+        assert!(group.servers[1].is_healthy());
+        group.record_passive(1, PassiveOutcome::ServerError(503));
+        assert!(!group.servers[1].is_healthy());
+        assert_eq!(
+            group.servers[1].passive.last_reason().as_deref(),
+            Some("passive: 2 consecutive 5xx responses")
+        );
+        for _ in 0..4 {
+            let s = group.select("127.0.0.1").unwrap();
+            assert_eq!(s.target.host, "10.0.0.1");
+        }
 
-    // 設定バリデーション
-    validate_config(&config)?;
+        // 復帰タイマー満了で healthy に戻る
+        let later = monotonic_ms() + 31_000;
+        assert!(group.servers[1].expire_passive(later));
+        assert!(group.servers[1].is_healthy());
+        assert!(!group.servers[1].passive.is_down());
 
-    // TLS証明書の存在確認
-    let cert_path = Path::new(&config.tls.cert_path);
-    if !cert_path.exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("TLS certificate not found: {}", config.tls.cert_path),
+        // アクティブチェックの成功でも戻る
+        group.record_passive(1, PassiveOutcome::ConnectFailure);
+        group.record_passive(1, PassiveOutcome::ConnectFailure);
+        assert!(!group.servers[1].is_healthy());
+        assert!(group.servers[1].record_success(1));
+        assert!(!group.servers[1].passive.is_down());
+    }
+
+    #[test]
+    fn adaptive_concurrency_config_parses_and_validates() {
+        let cfg: UpstreamConfig = toml::from_str(
+            r#"
+            servers = ["http://10.0.0.1:80", "http://10.0.0.2:80"]
+            [adaptive_concurrency]
+            enabled = true
+            algorithm = "aimd"
+            initial_limit = 50
+            max_limit = 200
+            retry_after_secs = 3
+            "#,
+        )
+        .unwrap();
+        let ac = cfg.adaptive_concurrency.clone();
+        assert_eq!(ac.algorithm, AdaptiveConcurrencyAlgorithm::Aimd);
+        assert_eq!(ac.min_limit, 1);
+        assert_eq!(ac.rtt_tolerance, 1.5);
+        assert!(validate_adaptive_concurrency("p", &ac).is_ok());
+        let group = build_upstream_group("p", &cfg).unwrap();
+        let limiter = group.concurrency_limiter.as_ref().unwrap();
+        assert_eq!(limiter.limit(), 50);
+        assert_eq!(limiter.retry_after_secs(), 3);
+        // ルートごとのコピー（F-137）でも同じ制限を共有する
+        let route_copy = group.with_hedging(None);
+        assert!(Arc::ptr_eq(
+            route_copy.concurrency_limiter.as_ref().unwrap(),
+            limiter
         ));
+
+        let default: UpstreamConfig = toml::from_str(r#"servers = ["http://10.0.0.1"]"#).unwrap();
+        assert!(!default.adaptive_concurrency.enabled);
+        assert!(build_upstream_group("p", &default)
+            .unwrap()
+            .concurrency_limiter
+            .is_none());
+
+        let invalid =
+            |cfg: AdaptiveConcurrencyConfig| validate_adaptive_concurrency("p", &cfg).is_err();
+        assert!(invalid(AdaptiveConcurrencyConfig {
+            initial_limit: 500,
+            ..ac.clone()
+        }));
+        assert!(invalid(AdaptiveConcurrencyConfig {
+            min_limit: 0,
+            ..ac.clone()
+        }));
+        assert!(invalid(AdaptiveConcurrencyConfig {
+            rtt_tolerance: 0.5,
+            ..ac.clone()
+        }));
+        assert!(invalid(AdaptiveConcurrencyConfig {
+            backoff_ratio: 1.0,
+            ..ac.clone()
+        }));
+        assert!(invalid(AdaptiveConcurrencyConfig {
+            sample_window_ms: 0,
+            ..ac
+        }));
     }
 
-    // TLS秘密鍵の存在確認
-    let key_path = Path::new(&config.tls.key_path);
-    if !key_path.exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("TLS key not found: {}", config.tls.key_path),
+    #[test]
+    fn select_alternate_excludes_primary_and_falls_back_to_backup_tier() {
+        let group = tiered_group(LoadBalanceAlgorithm::RoundRobin, 0.0);
+        let primary = &group.servers[0];
+        for _ in 0..8 {
+            let alt = group.select_alternate(primary, "10.1.1.1").unwrap();
+            // アクティブティアの残り 1 台が選ばれる
+            assert!(std::ptr::eq(alt, &group.servers[1]));
+        }
+
+        // アクティブティアの他サーバーが利用不可ならバックアップへ
+        group.servers[1].healthy.store(false, Ordering::SeqCst);
+        let alt = group.select_alternate(primary, "10.1.1.1").unwrap();
+        assert!(std::ptr::eq(alt, &group.servers[2]));
+
+        let single = UpstreamGroup::single(ProxyTarget::parse("http://10.0.0.1:80").unwrap());
+        assert!(single
+            .select_alternate(&single.servers[0], "10.1.1.1")
+            .is_none());
+    }
+
+    #[test]
+    fn hedging_route_config_parses_and_validates() {
+        let route: Route = toml::from_str(
+            r#"
+            action = { type = "Proxy", upstream = "api" }
+            [hedging]
+            delay_ms = 30
+            budget_percent = 5.0
+            "#,
+        )
+        .unwrap();
+        let hedging = route.hedging.clone().unwrap();
+        assert_eq!(hedging.delay_ms, 30);
+        assert_eq!(hedging.percentile, 95.0);
+        assert_eq!(hedging.methods, vec!["GET", "HEAD", "OPTIONS"]);
+        assert!(validate_hedging_config(&hedging, "r").is_ok());
+
+        let mut groups = HashMap::new();
+        let base = Arc::new(tiered_group(LoadBalanceAlgorithm::RoundRobin, 0.0));
+        groups.insert("api".to_string(), base.clone());
+        let Backend::Proxy(group, ..) = load_backend(&route, &groups).unwrap() else {
+            panic!("expected proxy backend");
+        };
+        // ポリシーはルート単位、サーバー状態と観測値は共有
+        assert!(group.hedging.is_some() && base.hedging.is_none());
+        assert!(Arc::ptr_eq(&group.header_latency, &base.header_latency));
+        assert!(Arc::ptr_eq(
+            &group.servers[0].active_connections,
+            &base.servers[0].active_connections
         ));
+
+        let invalid = |cfg: HedgingConfig| validate_hedging_config(&cfg, "r").is_err();
+        assert!(invalid(HedgingConfig {
+            methods: vec!["POST".into()],
+            ..hedging.clone()
+        }));
+        assert!(invalid(HedgingConfig {
+            methods: vec![],
+            ..hedging.clone()
+        }));
+        assert!(invalid(HedgingConfig {
+            percentile: 0.0,
+            ..hedging.clone()
+        }));
+        assert!(invalid(HedgingConfig {
+            min_delay_ms: 2000,
+            ..hedging.clone()
+        }));
+        assert!(invalid(HedgingConfig {
+            budget_percent: 150.0,
+            ..hedging
+        }));
     }
 
-    Ok(())
-}
+    #[test]
+    fn rate_limit_route_config_parses_and_validates() {
+        let mut route: Route = toml::from_str(
+            r#"
+            action = { type = "Proxy", upstream = "api" }
+            [security]
+            rate_limit_requests_per_min = 600
+            [[rate_limits]]
+            name = "tenant"
+            key = ["header:X-Tenant", "path"]
+            rate = 100
+            period_secs = 60
+            [[rate_limits]]
+            name = "burst"
+            rate = 10
+            burst = 20
+            "#,
+        )
+        .unwrap();
+        assert_eq!(route.rate_limits.len(), 2);
+        assert_eq!(route.rate_limits[0].effective_burst(), 100);
+        assert_eq!(route.rate_limits[1].key, vec!["client_ip"]);
+        assert_eq!(route.rate_limits[1].period_secs, 1);
+        assert_eq!(route.rate_limits[1].effective_burst(), 20);
+        assert!(validate_rate_limit_config(&route.rate_limits, "r").is_ok());
+
+        // load_backend のセキュリティ設定がルートのレートリミッターを共有する
+        route.prepare(0, None).unwrap();
+        let mut groups = HashMap::new();
+        groups.insert(
+            "api".to_string(),
+            Arc::new(tiered_group(LoadBalanceAlgorithm::RoundRobin, 0.0)),
+        );
+        let backend = load_backend(&route, &groups).unwrap();
+        let limiter = backend.security().rate_limit.clone().unwrap();
+        assert!(Arc::ptr_eq(&limiter, route.rate_limiter.as_ref().unwrap()));
+        assert!(backend.security().has_security_checks());
+
+        let base = route.rate_limits[1].clone();
+        let invalid = |rule: RateLimitRuleConfig| {
+            validate_rate_limit_config(&[route.rate_limits[0].clone(), rule], "r").is_err()
+        };
+        assert!(invalid(RateLimitRuleConfig {
+            name: "tenant".into(),
+            ..base.clone()
+        }));
+        assert!(invalid(RateLimitRuleConfig {
+            name: "has space".into(),
+            ..base.clone()
+        }));
+        assert!(invalid(RateLimitRuleConfig {
+            name: "per_minute".into(),
+            ..base.clone()
+        }));
+        assert!(invalid(RateLimitRuleConfig {
+            rate: 0,
+            ..base.clone()
+        }));
+        assert!(invalid(RateLimitRuleConfig {
+            key: vec!["query:page".into()],
+            ..base.clone()
+        }));
+        assert!(invalid(RateLimitRuleConfig {
+            key: vec![],
+            ..base.clone()
+        }));
+        assert!(invalid(RateLimitRuleConfig {
+            classes: vec![String::new()],
+            ..base.clone()
+        }));
+
+        // jwt_claim: キーはクレームを検証する認証のあるルートでのみ使える
+        assert!(validate_rate_limit_claim_keys(&route, "r").is_ok());
+        route.rate_limits.push(RateLimitRuleConfig {
+            name: "per-user".into(),
+            key: vec!["jwt_claim:sub".into()],
+            ..base
+        });
+        assert!(validate_rate_limit_claim_keys(&route, "r").is_err());
+        route.jwt = Some(
+            toml::from_str(r#"jwks_url = "https://idp.example.com/.well-known/jwks.json""#)
+                .unwrap(),
+        );
+        assert!(validate_rate_limit_claim_keys(&route, "r").is_ok());
+    }
 
-// ====================
-// ロードバランシングのテスト（F-19）
-// ====================
-#[cfg(test)]
-mod blocklist_tests {
-    use super::*;
-    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    #[test]
+    fn global_rate_limit_config_parses_and_validates() {
+        let mut route: Route = toml::from_str(
+            r#"
+            action = { type = "Proxy", upstream = "api" }
+            [global_rate_limit]
+            failure_mode = "closed"
+            [[global_rate_limit.descriptors]]
+            entries = [{ key = "tenant", from = "header:X-Tenant" }]
+            [[global_rate_limit.descriptors]]
+            entries = [{ key = "generic_key", value = "api" }, { key = "remote_address", from = "client_ip" }]
+            "#,
+        )
+        .unwrap();
+        let service: RateLimitServiceConfig =
+            toml::from_str(r#"address = "127.0.0.1:8081""#).unwrap();
+        assert_eq!(service.domain, "veil");
+        assert_eq!(service.timeout_ms, 100);
+        let global = route.global_rate_limit.clone().unwrap();
+        assert_eq!(global.failure_mode, RateLimitFailureMode::Closed);
+        assert_eq!(
+            global.descriptors[1].entries[1].from.as_deref(),
+            Some("client_ip")
+        );
+
+        let result = validate_global_rate_limit_config(&global, Some(&service), "r");
+        if cfg!(all(feature = "http2", feature = "grpc")) {
+            assert!(result.is_ok());
+            // ローカルの規則がなくてもレートリミッターを構築する
+            route.prepare(0, Some(&Arc::new(service.clone()))).unwrap();
+            assert!(route.rate_limiter.as_ref().unwrap().has_global());
+        } else {
+            assert!(result.is_err());
+        }
+        assert!(validate_global_rate_limit_config(&global, None, "r").is_err());
+
+        let invalid = |entry: &str| {
+            let cfg: GlobalRateLimitConfig =
+                toml::from_str(&format!("[[descriptors]]\nentries = [{}]", entry)).unwrap();
+            validate_global_rate_limit_config(&cfg, Some(&service), "r").is_err()
+        };
+        assert!(invalid(r#"{ key = "k" }"#));
+        assert!(invalid(r#"{ key = "k", value = "v", from = "path" }"#));
+        assert!(invalid(r#"{ key = "k", from = "query:page" }"#));
+        assert!(invalid(r#"{ key = "", value = "v" }"#));
+    }
 
     #[test]
-    fn test_cidr_contains_addr_ipv4() {
-        let cidr = CidrRange::parse("192.168.1.0/24").unwrap();
-        assert!(cidr.contains_addr(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 50))));
-        assert!(cidr.contains_addr(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 255))));
-        assert!(!cidr.contains_addr(IpAddr::V4(Ipv4Addr::new(192, 168, 2, 1))));
-        // IPv6 は IPv4 CIDR にマッチしない
-        assert!(!cidr.contains_addr(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+    #[allow(clippy::disallowed_methods)]
+    fn jwt_config_parses_and_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let jwks_path = dir.path().join("jwks.json");
+        std::fs::write(
+            &jwks_path,
+            r#"{"keys":[{"kty":"oct","kid":"k1","alg":"HS256","k":"c2VjcmV0"}]}"#,
+        )
+        .unwrap();
+        let mut route: Route = toml::from_str(&format!(
+            r#"
+            action = {{ type = "Proxy", upstream = "api" }}
+            conditions = {{ jwt_claims = {{ "tenant" = "acme-*" }} }}
+            [jwt]
+            algorithms = ["HS256"]
+            jwks_file = "{}"
+            audiences = ["api"]
+            required_claims = {{ "scope" = ["read"] }}
+            claims_to_headers = {{ "sub" = "X-User" }}
+            "#,
+            jwks_path.display()
+        ))
+        .unwrap();
+        let jwt = route.jwt.clone().unwrap();
+        assert!(jwt.require_exp);
+        assert!(jwt.forward_token);
+        assert_eq!(jwt.clock_skew_secs, 60);
+        assert_eq!(jwt.claims_to_headers["sub"], "X-User");
+        assert!(validate_jwt_config(&jwt, "r").is_ok());
+        route.prepare(0, None).unwrap();
+        assert!(route.jwt_auth.is_some());
+
+        // 公開鍵方式がデフォルトで、HS256 だけの JWKS では使える鍵が無い
+        let public_only = JwtAuthConfig {
+            algorithms: default_jwt_algorithms(),
+            ..jwt.clone()
+        };
+        assert!(validate_jwt_config(&public_only, "r").is_err());
+
+        let url: JwtAuthConfig =
+            toml::from_str(r#"jwks_url = "https://idp.example.com/.well-known/jwks.json""#)
+                .unwrap();
+        assert_eq!(url.jwks_refresh_secs, 300);
+        assert!(validate_jwt_config(&url, "r").is_ok());
+
+        let invalid = |cfg: JwtAuthConfig| validate_jwt_config(&cfg, "r").is_err();
+        assert!(invalid(JwtAuthConfig {
+            jwks_url: url.jwks_url.clone(),
+            ..jwt.clone()
+        }));
+        assert!(invalid(JwtAuthConfig {
+            algorithms: vec!["none".into()],
+            ..jwt.clone()
+        }));
+        assert!(invalid(JwtAuthConfig {
+            jwks_refresh_secs: 0,
+            ..url
+        }));
+        assert!(invalid(JwtAuthConfig {
+            claims_to_headers: [("sub".to_string(), "X User".to_string())].into(),
+            ..jwt.clone()
+        }));
+        assert!(invalid(JwtAuthConfig {
+            required_claims: [("scope".to_string(), vec![])].into(),
+            ..jwt
+        }));
     }
 
     #[test]
-    fn test_cidr_contains_addr_single_ipv4() {
-        let cidr = CidrRange::parse("10.0.0.5").unwrap();
-        assert!(cidr.contains_addr(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5))));
-        assert!(!cidr.contains_addr(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 6))));
+    fn ext_authz_config_parses_and_validates() {
+        let mut route: Route = toml::from_str(
+            r#"
+            action = { type = "Proxy", upstream = "api" }
+            [ext_authz]
+            url = "http://authz.internal:9000/check"
+            upstream_headers = ["X-Auth-User"]
+            cache_key = ["header:Authorization"]
+            cache_ttl_secs = 30
+            "#,
+        )
+        .unwrap();
+        let http = route.ext_authz.clone().unwrap();
+        assert_eq!(http.protocol, ExtAuthzProtocol::Http);
+        assert_eq!(http.failure_mode, ExtAuthzFailureMode::Closed);
+        assert_eq!(http.timeout_ms, 200);
+        assert_eq!(http.status_on_error, 403);
+        assert_eq!(http.request_headers, ["Authorization", "Cookie"]);
+        assert!(validate_ext_authz_config(&http, "r").is_ok());
+        route.prepare(0, None).unwrap();
+        assert!(route.ext_authorizer.is_some());
+
+        let grpc: ExtAuthzConfig = toml::from_str(
+            r#"
+            protocol = "grpc"
+            address = "127.0.0.1:9001"
+            failure_mode = "open"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(grpc.failure_mode, ExtAuthzFailureMode::Open);
+        assert_eq!(
+            validate_ext_authz_config(&grpc, "r").is_ok(),
+            cfg!(all(feature = "http2", feature = "grpc"))
+        );
+
+        let invalid = |cfg: ExtAuthzConfig| validate_ext_authz_config(&cfg, "r").is_err();
+        assert!(invalid(ExtAuthzConfig {
+            address: grpc.address.clone(),
+            ..http.clone()
+        }));
+        assert!(invalid(ExtAuthzConfig {
+            address: Some("no-port".into()),
+            ..grpc
+        }));
+        assert!(invalid(ExtAuthzConfig {
+            url: Some("ftp://authz".into()),
+            ..http.clone()
+        }));
+        assert!(invalid(ExtAuthzConfig {
+            status_on_error: 200,
+            ..http.clone()
+        }));
+        assert!(invalid(ExtAuthzConfig {
+            cache_key: vec!["nope".into()],
+            ..http.clone()
+        }));
+        assert!(invalid(ExtAuthzConfig {
+            cache_ttl_secs: 0,
+            ..http.clone()
+        }));
+        assert!(invalid(ExtAuthzConfig {
+            upstream_headers: vec!["X Bad".into()],
+            ..http
+        }));
+    }
+
+    #[test]
+    fn oidc_config_parses_and_validates() {
+        let route: Route = toml::from_str(
+            r#"
+            action = { type = "Proxy", upstream = "dashboards" }
+            [oidc]
+            issuer = "https://idp.example.com/realms/main"
+            client_id = "dashboards"
+            client_secret = "s3cret"
+            cookie_secret = "0123456789abcdef0123456789abcdef"
+            "#,
+        )
+        .unwrap();
+        let oidc = route.oidc.clone().unwrap();
+        assert_eq!(
+            oidc.token_endpoint_auth_method,
+            OidcClientAuthMethod::ClientSecretBasic
+        );
+        assert_eq!(oidc.scopes, ["openid", "profile", "email"]);
+        assert_eq!(oidc.redirect_uri, "/oauth2/callback");
+        assert_eq!(oidc.logout_path, "/oauth2/logout");
+        assert_eq!(oidc.cookie_name, "veil_oidc");
+        assert_eq!(
+            oidc.claims_to_headers.get("sub").map(String::as_str),
+            Some("X-Auth-Request-User")
+        );
+        assert_eq!(oidc.session_lifetime_secs, 86400);
+        assert!(validate_oidc_config(&oidc, "r").is_ok());
+        assert!(validate_oidc_config(
+            &OidcConfig {
+                redirect_uri: "https://dash.example.com/cb".into(),
+                client_secret: None,
+                ..oidc.clone()
+            },
+            "r"
+        )
+        .is_ok());
+
+        let invalid = |cfg: OidcConfig| validate_oidc_config(&cfg, "r").is_err();
+        assert!(invalid(OidcConfig {
+            issuer: "idp.example.com".into(),
+            ..oidc.clone()
+        }));
+        assert!(invalid(OidcConfig {
+            scopes: vec!["profile".into()],
+            ..oidc.clone()
+        }));
+        assert!(invalid(OidcConfig {
+            token_endpoint_auth_method: OidcClientAuthMethod::ClientSecretPost,
+            client_secret: None,
+            ..oidc.clone()
+        }));
+        assert!(invalid(OidcConfig {
+            redirect_uri: "callback".into(),
+            ..oidc.clone()
+        }));
+        assert!(invalid(OidcConfig {
+            logout_path: "/oauth2/callback".into(),
+            ..oidc.clone()
+        }));
+        assert!(invalid(OidcConfig {
+            cookie_secret: "short".into(),
+            ..oidc.clone()
+        }));
+        assert!(invalid(OidcConfig {
+            cookie_path: "/; Domain=evil".into(),
+            ..oidc.clone()
+        }));
+        assert!(invalid(OidcConfig {
+            cookie_same_site: SameSite::None,
+            cookie_secure: false,
+            ..oidc.clone()
+        }));
+        assert!(invalid(OidcConfig {
+            algorithms: vec!["none".into()],
+            ..oidc
+        }));
     }
 
     #[test]
-    fn test_cidr_contains_addr_ipv6() {
-        let cidr = CidrRange::parse("2001:db8::/32").unwrap();
-        assert!(cidr.contains_addr("2001:db8::1".parse::<IpAddr>().unwrap()));
-        assert!(!cidr.contains_addr("2001:db9::1".parse::<IpAddr>().unwrap()));
-        // IPv4 は IPv6 CIDR にマッチしない
-        assert!(!cidr.contains_addr(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1))));
+    #[allow(clippy::disallowed_methods)]
+    fn credential_auth_config_parses_and_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let htpasswd_path = dir.path().join("htpasswd");
+        std::fs::write(
+            &htpasswd_path,
+            "alice:$5$saltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5\n",
+        )
+        .unwrap();
+        let keys_path = dir.path().join("keys.toml");
+        std::fs::write(
+            &keys_path,
+            "[[key]]\nid = \"ci\"\nhash = \"sha256:36294c655e462786692d261f9d8bf6be31670bc66004afd9c91416223221410b\"\n",
+        )
+        .unwrap();
+        let mut route: Route = toml::from_str(&format!(
+            r#"
+            action = {{ type = "Proxy", upstream = "api" }}
+            [basic_auth]
+            htpasswd_file = "{}"
+            [api_key]
+            keys_file = "{}"
+            claims_to_headers = {{ "tenant" = "X-Tenant" }}
+            "#,
+            htpasswd_path.display(),
+            keys_path.display()
+        ))
+        .unwrap();
+        let basic = route.basic_auth.clone().unwrap();
+        let api_key = route.api_key.clone().unwrap();
+        assert_eq!(basic.realm, "veil");
+        assert!(!basic.strip_credentials);
+        assert_eq!(api_key.header, "X-API-Key");
+        assert!(api_key.route_name.is_none());
+        assert!(validate_basic_auth_config(&basic, "r").is_ok());
+        assert!(validate_api_key_config(&api_key, "r").is_ok());
+        route.prepare(0, None).unwrap();
+        let auth = route.credential_auth.clone().unwrap();
+        assert_eq!(auth.stripped_request_headers(), ["X-Tenant"]);
+
+        let invalid_basic = |cfg: BasicAuthConfig| validate_basic_auth_config(&cfg, "r").is_err();
+        assert!(invalid_basic(BasicAuthConfig {
+            htpasswd_file: dir.path().join("missing").display().to_string(),
+            ..basic.clone()
+        }));
+        assert!(invalid_basic(BasicAuthConfig {
+            htpasswd_file: keys_path.display().to_string(),
+            ..basic.clone()
+        }));
+        assert!(invalid_basic(BasicAuthConfig {
+            realm: "a\"b".into(),
+            ..basic.clone()
+        }));
+        assert!(invalid_basic(BasicAuthConfig {
+            claims_to_headers: [("sub".to_string(), "X User".to_string())].into(),
+            ..basic
+        }));
+
+        let invalid_key = |cfg: ApiKeyAuthConfig| validate_api_key_config(&cfg, "r").is_err();
+        assert!(invalid_key(ApiKeyAuthConfig {
+            keys_file: htpasswd_path.display().to_string(),
+            ..api_key.clone()
+        }));
+        assert!(invalid_key(ApiKeyAuthConfig {
+            header: "X API Key".into(),
+            ..api_key.clone()
+        }));
+        assert!(invalid_key(ApiKeyAuthConfig {
+            route_name: Some(String::new()),
+            ..api_key
+        }));
     }
 
     #[test]
-    fn test_global_blocklist_set_and_check() {
-        // 空のときは常に false（accept ホットパスの早期 return）
-        set_global_blocked_ips(&[]);
-        assert!(!is_ip_blocked(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 5))));
-
-        set_global_blocked_ips(&["203.0.113.0/24".to_string(), "198.51.100.7".to_string()]);
-        assert!(is_ip_blocked(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 5))));
-        assert!(is_ip_blocked(IpAddr::V4(Ipv4Addr::new(198, 51, 100, 7))));
-        assert!(!is_ip_blocked(IpAddr::V4(Ipv4Addr::new(198, 51, 100, 8))));
-        assert!(!is_ip_blocked(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8))));
-
-        // グローバル状態を空に戻す（他テストへの影響を避ける）
-        set_global_blocked_ips(&[]);
+    fn waf_config_parses_and_validates() {
+        let cfg: WafConfig = toml::from_str(
+            r#"
+            rules = 'SecRule ARGS "@detectSQLi" "id:1,phase:2,block,severity:CRITICAL"'
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.mode, WafMode::Blocking);
+        assert_eq!(cfg.anomaly_threshold, 5);
+        assert_eq!(cfg.paranoia_level, 1);
+        assert!(cfg.inspect_body);
+        assert_eq!(cfg.max_body_bytes, 131_072);
+        assert_eq!(cfg.body_limit_action, WafBodyLimitAction::Reject);
+        assert!(cfg.audit_log.is_none());
+        assert!(validate_waf_config(&cfg, "r").is_ok());
+        let detect: WafConfig = toml::from_str(
+            r#"
+            mode = "detection_only"
+            body_limit_action = "process_partial"
+            rule_files = ["/nonexistent/crs/*.conf"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(detect.mode, WafMode::DetectionOnly);
+        assert_eq!(detect.body_limit_action, WafBodyLimitAction::ProcessPartial);
+        assert!(validate_waf_config(&detect, "r").is_err());
+
+        let invalid = |cfg: WafConfig| validate_waf_config(&cfg, "r").is_err();
+        assert!(invalid(WafConfig {
+            rules: String::new(),
+            ..cfg.clone()
+        }));
+        assert!(invalid(WafConfig {
+            anomaly_threshold: 0,
+            ..cfg.clone()
+        }));
+        assert!(invalid(WafConfig {
+            paranoia_level: 5,
+            ..cfg.clone()
+        }));
+        assert!(invalid(WafConfig {
+            max_body_bytes: 0,
+            ..cfg.clone()
+        }));
+        assert!(invalid(WafConfig {
+            disabled_rule_ids: vec!["942199-942100".into()],
+            ..cfg.clone()
+        }));
+        assert!(invalid(WafConfig {
+            audit_log: Some(String::new()),
+            ..cfg.clone()
+        }));
+        assert!(invalid(WafConfig {
+            rules: r#"SecRule ARGS "@rx (?<=a)b" "id:1,deny""#.into(),
+            ..cfg
+        }));
     }
-}
 
-#[cfg(test)]
-mod load_balancing_tests {
-    use super::*;
+    #[test]
+    fn extra_headers_carry_per_request_changes() {
+        let mut security = SecurityConfig::default();
+        security
+            .add_request_headers
+            .insert("X-Tenant".into(), "default".into());
+        security.remove_request_headers.push("X-User".into());
+        security
+            .add_response_headers
+            .insert("Set-Cookie".into(), "route=1".into());
+
+        let mut extra = ExtraHeaders::default();
+        extra.set_request_headers(vec![("X-User".into(), "alice".into())]);
+        extra.set_request_headers(vec![("x-user".into(), "bob".into())]);
+        assert_eq!(
+            extra.request,
+            vec![("x-user".to_string(), "bob".to_string())]
+        );
 
-    fn entry(url: &str, weight: u32) -> UpstreamServerEntry {
-        UpstreamServerEntry {
-            url: url.to_string(),
-            sni_name: None,
-            use_h2c: false,
-            weight,
-        }
+        // 外部認可の設定はルートの追加分を置き換え、削除はクライアントのヘッダーにも効く
+        extra.apply_request_header_changes(
+            &[("X-Tenant".into(), "acme".into())],
+            &["X-Debug".into(), "X-User".into()],
+        );
+        assert_eq!(
+            extra.request,
+            vec![("X-Tenant".to_string(), "acme".to_string())]
+        );
+        assert!(extra.overrides_request_header(b"x-tenant"));
+        assert!(security.is_removed_request_header(b"x-debug", &extra));
+        assert!(security.is_removed_request_header(b"X-User", &extra));
+        assert!(!security.is_removed_request_header(b"accept", &extra));
+
+        // 同名の Set-Cookie も置き換えずに並べる
+        extra.push_response_header("Set-Cookie", "sticky=2".into());
+        extra.push_cors_headers(vec![("Vary", "Origin".into())]);
+        let response: Vec<_> = security.response_headers(&extra).collect();
+        assert_eq!(
+            response,
+            vec![
+                ("Set-Cookie", "route=1"),
+                ("Set-Cookie", "sticky=2"),
+                ("Vary", "Origin"),
+            ]
+        );
+        assert_eq!(
+            extra.response_header_pairs()[0],
+            (b"set-cookie".to_vec(), b"sticky=2".to_vec())
+        );
     }
 
     #[test]
-    fn weighted_distribution_roughly_2_to_1() {
-        let entries = vec![
-            entry("http://10.0.0.1:80", 2),
-            entry("http://10.0.0.2:80", 1),
-        ];
-        let group = UpstreamGroup::new(
-            "wrr".into(),
-            entries,
-            LoadBalanceAlgorithm::Weighted,
-            None,
-            false,
+    fn cors_config_parses_and_validates() {
+        let cfg: CorsConfig = toml::from_str(
+            r#"
+            allowed_origins = ["https://app.example.com", "https://*.example.org"]
+            allowed_headers = ["Content-Type"]
+            allow_credentials = true
+            max_age_secs = 600
+            "#,
         )
         .unwrap();
-
-        let mut counts = [0usize; 2];
-        for _ in 0..3000 {
-            let s = group.select("1.2.3.4").unwrap();
-            if s.target.host == "10.0.0.1" {
-                counts[0] += 1;
-            } else {
-                counts[1] += 1;
-            }
-        }
-        // 重み 2:1 なので server1 はおよそ server2 の2倍
-        let ratio = counts[0] as f64 / counts[1] as f64;
-        assert!(
-            ratio > 1.7 && ratio < 2.3,
-            "weighted ratio out of range: {} (counts={:?})",
-            ratio,
-            counts
-        );
+        assert_eq!(cfg.allowed_methods, vec!["GET", "HEAD", "POST"]);
+        assert!(cfg.allowed_origin_regexes.is_empty());
+        assert!(cfg.exposed_headers.is_empty());
+        assert_eq!(cfg.max_age_secs, Some(600));
+        assert!(validate_cors_config(&cfg, "r").is_ok());
+
+        let invalid = |cfg: CorsConfig| validate_cors_config(&cfg, "r").is_err();
+        assert!(invalid(CorsConfig {
+            allowed_origins: Vec::new(),
+            ..cfg.clone()
+        }));
+        assert!(invalid(CorsConfig {
+            allowed_origins: vec!["*".into()],
+            ..cfg.clone()
+        }));
+        assert!(invalid(CorsConfig {
+            allowed_origins: vec!["app.example.com".into()],
+            ..cfg.clone()
+        }));
+        assert!(invalid(CorsConfig {
+            allowed_origins: vec!["https://app.example.com/".into()],
+            ..cfg.clone()
+        }));
+        assert!(invalid(CorsConfig {
+            allowed_origin_regexes: vec!["(".into()],
+            ..cfg.clone()
+        }));
+        assert!(invalid(CorsConfig {
+            allowed_methods: vec!["GET POST".into()],
+            ..cfg.clone()
+        }));
+        assert!(invalid(CorsConfig {
+            exposed_headers: vec!["X Total".into()],
+            ..cfg.clone()
+        }));
+        assert!(validate_cors_config(
+            &CorsConfig {
+                allowed_origins: vec!["*".into()],
+                allowed_methods: vec!["*".into()],
+                allowed_headers: vec!["*".into()],
+                allow_credentials: false,
+                ..cfg
+            },
+            "r"
+        )
+        .is_ok());
     }
 
     #[test]
-    fn consistent_hash_same_key_same_server() {
-        let entries = vec![
-            entry("http://10.0.0.1:80", 1),
-            entry("http://10.0.0.2:80", 1),
-            entry("http://10.0.0.3:80", 1),
-        ];
-        let group = UpstreamGroup::new(
-            "ch".into(),
-            entries,
-            LoadBalanceAlgorithm::ConsistentHash {
-                hash_key: HashKey::Ip,
-            },
-            None,
-            false,
+    fn auto_ban_config_parses_and_validates() {
+        let cfg: AutoBanConfig = toml::from_str(
+            r#"
+            exempt_ips = ["10.0.0.0/8", "::1"]
+            [[rules]]
+            name = "scan"
+            signals = ["not_found", "h2_flood", "waf"]
+            threshold = 50
+            "#,
         )
         .unwrap();
-
-        // 同じキーは常に同じサーバーへ
-        let first = group.select("192.168.1.50").unwrap().target.host.clone();
-        for _ in 0..100 {
-            let host = group.select("192.168.1.50").unwrap().target.host.clone();
-            assert_eq!(host, first, "consistent hash not stable");
-        }
-
-        // 別のキーでも安定していること
-        let second = group.select("8.8.8.8").unwrap().target.host.clone();
-        for _ in 0..100 {
-            let host = group.select("8.8.8.8").unwrap().target.host.clone();
-            assert_eq!(host, second);
+        assert_eq!(cfg.ban_secs, 600);
+        assert_eq!(cfg.max_ban_secs, 86400);
+        assert_eq!(cfg.escalation_factor, 2);
+        assert_eq!(cfg.ipv6_prefix_len, 64);
+        assert_eq!(cfg.rules[0].window_secs, 60);
+        assert!(validate_auto_ban_config(&cfg).is_ok());
+
+        let invalid = |cfg: AutoBanConfig| validate_auto_ban_config(&cfg).is_err();
+        let rule = cfg.rules[0].clone();
+        assert!(invalid(AutoBanConfig {
+            ban_secs: 0,
+            ..cfg.clone()
+        }));
+        assert!(invalid(AutoBanConfig {
+            max_ban_secs: 60,
+            ..cfg.clone()
+        }));
+        assert!(invalid(AutoBanConfig {
+            ipv6_prefix_len: 16,
+            ..cfg.clone()
+        }));
+        assert!(invalid(AutoBanConfig {
+            exempt_ips: vec!["10.0.0.0/33".into()],
+            ..cfg.clone()
+        }));
+        for rule in [
+            AutoBanRuleConfig {
+                name: "admin".into(),
+                ..rule.clone()
+            },
+            AutoBanRuleConfig {
+                name: "bad name".into(),
+                ..rule.clone()
+            },
+            AutoBanRuleConfig {
+                threshold: 0,
+                ..rule.clone()
+            },
+            AutoBanRuleConfig {
+                signals: vec!["teapot".into()],
+                ..rule.clone()
+            },
+        ] {
+            assert!(invalid(AutoBanConfig {
+                rules: vec![rule],
+                ..cfg.clone()
+            }));
         }
+        assert!(invalid(AutoBanConfig {
+            rules: vec![rule.clone(), rule],
+            ..cfg
+        }));
     }
 
     #[test]
-    fn consistent_hash_distributes_keys() {
-        let entries = vec![
-            entry("http://10.0.0.1:80", 1),
-            entry("http://10.0.0.2:80", 1),
-            entry("http://10.0.0.3:80", 1),
-        ];
-        let group = UpstreamGroup::new(
-            "ch".into(),
-            entries,
-            LoadBalanceAlgorithm::ConsistentHash {
-                hash_key: HashKey::Ip,
-            },
-            None,
-            false,
+    fn client_limits_config_parses_and_validates() {
+        let cfg: ClientLimitsConfig = toml::from_str(
+            r#"
+            exempt_ips = ["100.64.0.0/10"]
+            [per_ip]
+            max_connections = 10
+            max_streams = 100
+            [per_prefix]
+            max_connections = 50
+            "#,
         )
         .unwrap();
+        assert_eq!(cfg.ipv4_prefix_len, 24);
+        assert_eq!(cfg.ipv6_prefix_len, 64);
+        assert_eq!(cfg.per_ip.max_quic_connections, 0);
+        assert_eq!(cfg.per_prefix.max_streams, 0);
+        assert!(validate_client_limits_config(&cfg).is_ok());
+
+        let invalid = |cfg: ClientLimitsConfig| validate_client_limits_config(&cfg).is_err();
+        assert!(invalid(ClientLimitsConfig {
+            ipv4_prefix_len: 33,
+            ..cfg.clone()
+        }));
+        assert!(invalid(ClientLimitsConfig {
+            ipv6_prefix_len: 16,
+            ..cfg.clone()
+        }));
+        assert!(invalid(ClientLimitsConfig {
+            exempt_ips: vec!["not-an-ip".into()],
+            ..cfg.clone()
+        }));
+        assert!(invalid(ClientLimitsConfig {
+            per_prefix: ClientLimitValues {
+                max_connections: 5,
+                ..Default::default()
+            },
+            ..cfg
+        }));
+    }
 
-        let mut seen = std::collections::HashSet::new();
-        for i in 0..200 {
-            let key = format!("10.20.30.{}", i % 256);
-            let host = group.select(&key).unwrap().target.host.clone();
-            seen.insert(host);
-        }
-        // 150 vnodes/サーバーで十分に分散され、全サーバーが使われるはず
-        assert_eq!(
-            seen.len(),
-            3,
-            "keys not distributed across all servers: {:?}",
-            seen
-        );
+    #[test]
+    fn route_timeouts_config_parses_and_validates() {
+        let route: Route = toml::from_str(
+            r#"
+            action = { type = "Proxy", upstream = "api" }
+            [security]
+            upstream_header_timeout_ms = 2000
+            request_timeout_ms = 5000
+            per_try_timeout_ms = 1500
+            max_grpc_timeout_ms = 3000
+            "#,
+        )
+        .unwrap();
+        let security = route.security.clone().unwrap();
+        assert_eq!(security.upstream_header_timeout_ms, 2000);
+        assert_eq!(security.upstream_idle_timeout_ms, 0);
+        assert!(security.grpc_deadline_propagation);
+        assert!(security.upstream_deadline.is_none());
+        assert!(validate_timeouts_config(&security, "r").is_ok());
+
+        // 試行ごとの上限はリクエスト全体の期限を超えられない
+        let invalid = SecurityConfig {
+            per_try_timeout_ms: 6000,
+            ..security.clone()
+        };
+        assert!(validate_timeouts_config(&invalid, "r").is_err());
+        // 期限なしなら試行ごとの上限のみでよい
+        let unbounded = SecurityConfig {
+            request_timeout_ms: 0,
+            ..invalid
+        };
+        assert!(validate_timeouts_config(&unbounded, "r").is_ok());
     }
 
     #[test]
-    fn hash_key_parse() {
-        assert_eq!(HashKey::parse("ip").unwrap(), HashKey::Ip);
-        assert_eq!(
-            HashKey::parse("header:X-User-Id").unwrap(),
-            HashKey::Header("X-User-Id".into())
-        );
+    fn happy_eyeballs_config_defaults_and_parses() {
+        // [performance] 省略時も有効（250ms）
         assert_eq!(
-            HashKey::parse("cookie:session_id").unwrap(),
-            HashKey::Cookie("session_id".into())
+            PerformanceConfigSection::default().happy_eyeballs,
+            HappyEyeballsConfig {
+                enabled: true,
+                connection_attempt_delay_ms: 250,
+            }
         );
-        assert!(HashKey::parse("garbage").is_err());
+        let perf: PerformanceConfigSection = toml::from_str(
+            r#"
+            [happy_eyeballs]
+            connection_attempt_delay_ms = 100
+            "#,
+        )
+        .unwrap();
+        assert!(perf.happy_eyeballs.enabled);
+        assert_eq!(perf.happy_eyeballs.connection_attempt_delay_ms, 100);
     }
 
-    /// F-97: header キーの Consistent Hash が select_with_header_fn で安定
     #[test]
-    fn consistent_hash_header_key_via_select_with_header_fn() {
-        let entries = vec![
-            entry("http://10.0.0.1:80", 1),
-            entry("http://10.0.0.2:80", 1),
-        ];
-        let group = UpstreamGroup::new(
-            "ch-hdr".into(),
-            entries,
-            LoadBalanceAlgorithm::ConsistentHash {
-                hash_key: HashKey::Header("x-user-id".into()),
-            },
-            None,
-            false,
+    fn upstream_source_settings_parse_and_bind_per_request() {
+        let single: UpstreamConfig = toml::from_str(
+            r#"
+            source_address = "127.0.0.2"
+            servers = ["http://10.0.0.1:80"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            single.source_address,
+            vec!["127.0.0.2".parse::<IpAddr>().unwrap()]
+        );
+        let pool: UpstreamConfig = toml::from_str(
+            r#"
+            source_address = ["192.0.2.10", "192.0.2.11"]
+            servers = ["http://10.0.0.1:80"]
+            "#,
         )
         .unwrap();
+        assert!(validate_upstream_source("Upstream", "p", false, &pool.source_address).is_ok());
 
-        let headers: Vec<(&[u8], &[u8])> = vec![(b"x-user-id", b"user-stable-1")];
-        let first = group
-            .select_with_header_fn("1.2.3.4", |name| {
-                headers
-                    .iter()
-                    .find(|(n, _)| n.eq_ignore_ascii_case(name))
-                    .map(|(_, v)| *v)
-            })
-            .unwrap()
-            .target
-            .host
-            .clone();
-        for _ in 0..20 {
-            let host = group
-                .select_with_header_fn("9.9.9.9", |name| {
-                    headers
-                        .iter()
-                        .find(|(n, _)| n.eq_ignore_ascii_case(name))
-                        .map(|(_, v)| *v)
-                })
-                .unwrap()
-                .target
-                .host
-                .clone();
-            assert_eq!(
-                host, first,
-                "same header value must stick to same backend regardless of client IP"
-            );
-        }
+        // プールはラウンドロビンで送信元を選び、送信元ごとに別のターゲットになる
+        let group = build_upstream_group("p", &pool).unwrap();
+        let target = &group.servers[0].target;
+        let first = target.bound_for("203.0.113.5").unwrap();
+        let second = target.bound_for("203.0.113.5").unwrap();
+        assert_eq!(
+            first.bind.unwrap().ip,
+            "192.0.2.10".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            second.bind.unwrap().ip,
+            "192.0.2.11".parse::<IpAddr>().unwrap()
+        );
+        assert!(!first.bind.unwrap().transparent);
 
-        // Cookie キー
+        // 透過モードはクライアント IP（mapped は IPv4 に正規化）、取れなければエラー
+        let mut transparent = ProxyTarget::parse("http://10.0.0.1:80").unwrap();
+        transparent.source = UpstreamSource::from_config(true, &[]);
+        let bound = transparent.bound_for("::ffff:203.0.113.5").unwrap();
         assert_eq!(
-            extract_cookie_value("a=1; session_id=abc; b=2", "session_id"),
-            Some("abc")
+            bound.bind,
+            Some(LocalBind::new("203.0.113.5".parse().unwrap(), true))
         );
-    }
+        assert!(transparent.bound_for("unix").is_err());
 
-    #[test]
-    fn weighted_entry_default_weight_is_one() {
-        let s = entry("http://x:80", 1);
-        assert_eq!(s.weight, 1);
+        // 送信元の設定が無ければ借用のまま
+        let plain = ProxyTarget::parse("http://10.0.0.1:80").unwrap();
+        assert!(matches!(
+            plain.bound_for("203.0.113.5"),
+            Ok(Cow::Borrowed(_))
+        ));
+
+        let v4: IpAddr = "192.0.2.10".parse().unwrap();
+        let v6: IpAddr = "2001:db8::10".parse().unwrap();
+        let invalid = |transparent, addrs: &[IpAddr]| {
+            validate_upstream_source("Upstream", "p", transparent, addrs).is_err()
+        };
+        assert!(invalid(true, &[v4]));
+        assert!(invalid(false, &[v4, v6]));
+        assert!(invalid(false, &["0.0.0.0".parse().unwrap()]));
+        assert!(invalid(false, &["ff02::1".parse().unwrap()]));
     }
 
+    #[cfg(target_os = "linux")]
     #[test]
-    fn round_robin_still_cycles() {
-        let entries = vec![
-            entry("http://10.0.0.1:80", 1),
-            entry("http://10.0.0.2:80", 1),
-        ];
-        let group = UpstreamGroup::new(
-            "rr".into(),
-            entries,
-            LoadBalanceAlgorithm::RoundRobin,
-            None,
-            false,
+    fn socket_profiles_resolve_and_attach_to_upstreams() {
+        let profile = |s: &str| {
+            toml::from_str::<SocketProfileConfig>(s)
+                .unwrap()
+                .resolve("p")
+        };
+
+        let opts = profile(
+            r#"
+            backlog = 4096
+            dscp = 10
+            keepalive_idle_secs = 30
+            mark = 7
+            congestion = "bbr"
+            "#,
         )
         .unwrap();
-        let a = group.select("x").unwrap().target.host.clone();
-        let b = group.select("x").unwrap().target.host.clone();
-        assert_ne!(a, b, "round robin should alternate");
+        assert_eq!(opts.backlog, Some(4096));
+        // DSCP は TOS の上位 6 ビット
+        assert_eq!(opts.tos, Some(0x28));
+        assert_eq!(opts.mark, Some(7));
+        assert_eq!(opts.congestion.as_deref(), Some("bbr"));
+
+        assert!(profile("tos = 40\ndscp = 10").is_err());
+        assert!(profile("dscp = 64").is_err());
+        assert!(profile("backlog = 0").is_err());
+        assert!(profile("keepalive_count = 128").is_err());
+        assert!(profile("keepalive = false\nkeepalive_idle_secs = 30").is_err());
+        assert!(profile(r#"congestion = "bbr; reno""#).is_err());
+        assert!(profile(r#"congestion = "a-very-long-algorithm""#).is_err());
+
+        let profiles = HashMap::from([("bulk".to_string(), Arc::new(opts))]);
+        let err = lookup_socket_profile(&profiles, "Upstream 'api'", Some("missing")).unwrap_err();
+        assert!(err.to_string().contains("unknown socket profile 'missing'"));
+        assert!(lookup_socket_profile(&profiles, "Upstream 'api'", None)
+            .unwrap()
+            .is_none());
+
+        // 解決したプロファイルはグループ内の全サーバーへ共有される
+        let mut upstream: UpstreamConfig = toml::from_str(
+            r#"
+            socket_profile = "bulk"
+            servers = ["http://10.0.0.1:80", "http://10.0.0.2:80"]
+            "#,
+        )
+        .unwrap();
+        upstream.socket = lookup_socket_profile(
+            &profiles,
+            "Upstream 'api'",
+            upstream.socket_profile.as_deref(),
+        )
+        .unwrap();
+        let group = build_upstream_group("api", &upstream).unwrap();
+        assert!(group
+            .servers
+            .iter()
+            .all(|s| s.target.socket.as_ref() == Some(&profiles["bulk"])));
     }
 }
 
@@ -6699,6 +12646,7 @@ mod circuit_breaker_upstream_tests {
             sni_name: None,
             use_h2c: false,
             weight: 1,
+            priority: 0,
         }
     }
 
@@ -6862,6 +12810,7 @@ mod advanced_lb_integration_tests {
             sni_name: None,
             use_h2c: false,
             weight,
+            priority: 0,
         }
     }
 
@@ -7035,6 +12984,75 @@ verify_cert = false
         // check_type を省略すると Http になる
         let cfg: HealthCheckConfig = toml::from_str("").unwrap();
         assert_eq!(cfg.check_type, HealthCheckType::Http);
+        assert_eq!(cfg.method, "GET");
+        assert_eq!(cfg.protocol, None);
+        assert_eq!(cfg.jitter_percent, 0);
+    }
+
+    /// F-143: リクエスト・検証オプションとプロトコルの組み合わせを検証する
+    #[test]
+    fn test_health_check_validation() {
+        let check = |toml: &str, h3: bool, l4: bool| {
+            let hc: HealthCheckConfig = toml::from_str(toml).unwrap();
+            validate_health_check("Upstream", "api", &hc, h3, l4).map_err(|e| e.to_string())
+        };
+
+        let full = r#"
+method = "post"
+host = "status.internal"
+body = "{}"
+expect_body_regex = "^ok"
+protocol = "h2"
+use_tls = true
+jitter_percent = 50
+unhealthy_interval_secs = 2
+[headers]
+Authorization = "Bearer x"
+[expect_json]
+"$.checks[0].status" = "UP"
+"#;
+        assert!(check(full, false, false).is_ok());
+        assert!(check("protocol = \"http/1.1\"\nexpect_body = \"ok\"", false, true).is_ok());
+
+        for (toml, expected) in [
+            (
+                "jitter_percent = 51",
+                "jitter_percent must be between 0 and 50",
+            ),
+            (
+                "unhealthy_interval_secs = 0",
+                "intervals must be at least 1 second",
+            ),
+            ("method = \"GET /\"", "invalid method"),
+            ("[headers]\n\"X Bad\" = \"1\"", "invalid header"),
+            ("expect_body_regex = \"(\"", "expect_body_regex:"),
+            ("[expect_json]\n\"$.a..b\" = 1", "invalid expect_json path"),
+            ("protocol = \"h2\"", "requires use_tls = true"),
+            (
+                "protocol = \"h2c\"\nuse_tls = true",
+                "cannot be used with use_tls",
+            ),
+        ] {
+            let err = check(toml, false, false).unwrap_err();
+            assert!(err.contains(expected), "{}: {}", toml, err);
+        }
+
+        let body = format!(
+            "body = \"{}\"",
+            "x".repeat(crate::health::MAX_PROBE_REQUEST_BODY + 1)
+        );
+        assert!(check(&body, false, false)
+            .unwrap_err()
+            .contains("body must be at most"));
+
+        // L4 は protocol 未指定だと TCP 接続確認のため、HTTP のオプションはエラー
+        let err = check("expect_body = \"ok\"", false, true).unwrap_err();
+        assert!(err.contains("require protocol"), "{}", err);
+        assert!(check("expect_body = \"ok\"", false, false).is_ok());
+        #[cfg(feature = "http3")]
+        assert!(check("expect_body = \"ok\"", true, false)
+            .unwrap_err()
+            .contains("not supported by HTTP/3 probes"));
     }
 }
 
@@ -11,10 +11,11 @@ pub(crate) static ERR_MSG_NOT_FOUND: &[u8] =
     b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
 pub(crate) static ERR_MSG_METHOD_NOT_ALLOWED: &[u8] =
     b"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
-pub(crate) static ERR_MSG_TOO_MANY_REQUESTS: &[u8] =
-    b"HTTP/1.1 429 Too Many Requests\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
 pub(crate) static ERR_MSG_BAD_GATEWAY: &[u8] =
     b"HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+/// 上流接続の待機キューが満杯・待ち時間超過の場合（F-136）
+pub(crate) static ERR_MSG_SERVICE_UNAVAILABLE: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
 pub(crate) static ERR_MSG_INSUFFICIENT_STORAGE: &[u8] =
     b"HTTP/1.1 507 Insufficient Storage\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
 pub(crate) static ERR_MSG_REQUEST_TOO_LARGE: &[u8] =
@@ -0,0 +1,541 @@
+//! ルート単位の CORS（F-155）
+//!
+//! `[route.cors]` を設定したルートで、プリフライト（`Origin` と
+//! `Access-Control-Request-Method` を持つ `OPTIONS`）に上流へ送らずに応答し、通常の
+//! リクエストの応答には `Access-Control-*` ヘッダーを付ける。gRPC-Web の CORS
+//! （`grpc::web::GrpcWebCorsConfig`）も同じ判定を使う。
+//!
+//! - オリジンは完全一致（`"*"` はすべて）、`*` を含むワイルドカード（`*` は `/` 以外の
+//!   1 文字以上）、正規表現（オリジン全体に一致させる）で指定する。比較は大文字小文字を区別しない。
+//! - `allowed_methods` / `allowed_headers` の `"*"` は要求されたメソッド・ヘッダーをそのまま返す
+//!   （資格情報付きのリクエストではブラウザが `*` を文字どおりに扱うため）。
+//! - 許可する `Access-Control-Allow-Origin` がリクエストの `Origin` によって変わる設定では、
+//!   `Origin` の無いリクエストや許可しないオリジンの応答にも `Vary: Origin` を付ける。
+//!   上流の `Access-Control-*` は削除して置き換えるため、キャッシュ（`cache`）はオリジンに
+//!   依存しない上流の応答を保存し、ヒット時も同じヘッダーを付けて返す。
+
+use std::fmt::Write as _;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::config::CorsConfig;
+
+/// ルートが置き換える上流の応答ヘッダー
+const OWNED_RESPONSE_HEADERS: [&str; 6] = [
+    "Access-Control-Allow-Origin",
+    "Access-Control-Allow-Credentials",
+    "Access-Control-Allow-Methods",
+    "Access-Control-Allow-Headers",
+    "Access-Control-Expose-Headers",
+    "Access-Control-Max-Age",
+];
+
+/// プリフライトの応答の `Vary`（要求されたメソッド・ヘッダーを返すことがあるため）
+const PREFLIGHT_VARY: &str =
+    "Origin, Access-Control-Request-Method, Access-Control-Request-Headers";
+
+/// 許可するオリジンの 1 つの指定
+#[derive(Debug)]
+enum OriginRule {
+    Exact(String),
+    Pattern(Regex),
+}
+
+impl OriginRule {
+    fn matches(&self, origin: &str) -> bool {
+        match self {
+            Self::Exact(o) => o.eq_ignore_ascii_case(origin),
+            Self::Pattern(re) => re.is_match(origin),
+        }
+    }
+}
+
+/// `*` を含むオリジンを正規表現へ変換する（`*` は `/` 以外の 1 文字以上）
+fn wildcard_regex(pattern: &str) -> Result<Regex, String> {
+    let body: Vec<String> = pattern.split('*').map(regex::escape).collect();
+    RegexBuilder::new(&format!("^{}$", body.join("[^/]+")))
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// ルートの CORS の判定
+#[derive(Debug)]
+pub struct Cors {
+    any_origin: bool,
+    origins: Vec<OriginRule>,
+    /// 大文字（`"*"` の場合は `any_method`）
+    methods: Vec<String>,
+    any_method: bool,
+    /// 小文字（`"*"` の場合は `any_header`）
+    headers: Vec<String>,
+    any_header: bool,
+    expose_headers: String,
+    allow_credentials: bool,
+    max_age: Option<u64>,
+}
+
+/// プリフライトへの応答（許可は 204、拒否は 403）
+#[derive(Debug)]
+pub struct CorsPreflight {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+}
+
+impl CorsPreflight {
+    /// 応答のステータス
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// 応答のボディ（204 は空）
+    pub fn body(&self) -> &'static [u8] {
+        if self.status == 204 {
+            b""
+        } else {
+            b"Forbidden"
+        }
+    }
+
+    /// 応答のヘッダー（`Access-Control-*` と `Vary`）
+    pub fn headers(&self) -> &[(&'static str, String)] {
+        &self.headers
+    }
+
+    /// HTTP/2・HTTP/3 用の小文字のヘッダー
+    pub fn h2_headers(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.headers
+            .iter()
+            .map(|(n, v)| (n.to_ascii_lowercase().into_bytes(), v.as_bytes().to_vec()))
+            .collect()
+    }
+
+    /// HTTP/1.1 の応答（`Connection: close`）
+    pub fn http1_response(&self) -> Vec<u8> {
+        let mut response = if self.status == 204 {
+            String::from("HTTP/1.1 204 No Content\r\n")
+        } else {
+            String::from("HTTP/1.1 403 Forbidden\r\n")
+        };
+        for (name, value) in &self.headers {
+            let _ = write!(response, "{}: {}\r\n", name, value);
+        }
+        let body = self.body();
+        if self.status != 204 {
+            let _ = write!(response, "Content-Length: {}\r\n", body.len());
+        }
+        response.push_str("Connection: close\r\n\r\n");
+        let mut response = response.into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+}
+
+/// ヘッダーの値を探す（名前は大文字小文字を区別しない）
+fn header<'a>(headers: &[(&[u8], &'a [u8])], name: &str) -> Option<&'a [u8]> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name.as_bytes()))
+        .map(|(_, v)| *v)
+}
+
+/// `Access-Control-Request-Headers` の名前（小文字、空要素は除く）
+fn requested_headers(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(',')
+        .map(|h| h.trim().to_ascii_lowercase())
+        .filter(|h| !h.is_empty())
+}
+
+impl Cors {
+    /// 設定から構築する（オリジンの正規表現が不正ならエラー）
+    pub fn new(cfg: &CorsConfig) -> Result<Self, String> {
+        let mut origins = Vec::new();
+        let mut any_origin = false;
+        for origin in &cfg.allowed_origins {
+            if origin == "*" {
+                any_origin = true;
+            } else if origin.contains('*') {
+                let re = wildcard_regex(origin)
+                    .map_err(|e| format!("invalid origin '{}': {}", origin, e))?;
+                origins.push(OriginRule::Pattern(re));
+            } else {
+                origins.push(OriginRule::Exact(origin.clone()));
+            }
+        }
+        for pattern in &cfg.allowed_origin_regexes {
+            let re = RegexBuilder::new(&format!("^(?:{})$", pattern))
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("invalid origin regex '{}': {}", pattern, e))?;
+            origins.push(OriginRule::Pattern(re));
+        }
+        let any_method = cfg.allowed_methods.iter().any(|m| m == "*");
+        let any_header = cfg.allowed_headers.iter().any(|h| h == "*");
+        Ok(Self {
+            any_origin,
+            origins,
+            methods: cfg
+                .allowed_methods
+                .iter()
+                .filter(|m| *m != "*")
+                .map(|m| m.to_ascii_uppercase())
+                .collect(),
+            any_method,
+            headers: cfg
+                .allowed_headers
+                .iter()
+                .filter(|h| *h != "*")
+                .map(|h| h.to_ascii_lowercase())
+                .collect(),
+            any_header,
+            expose_headers: cfg.exposed_headers.join(", "),
+            allow_credentials: cfg.allow_credentials,
+            max_age: cfg.max_age_secs,
+        })
+    }
+
+    /// 許可するオリジンかどうか
+    pub fn is_origin_allowed(&self, origin: &str) -> bool {
+        self.any_origin || self.origins.iter().any(|rule| rule.matches(origin))
+    }
+
+    /// 応答がリクエストの `Origin` によって変わるかどうか（`Vary: Origin` が要る）
+    fn varies_by_origin(&self) -> bool {
+        !self.any_origin || self.allow_credentials
+    }
+
+    /// 設定で置き換える上流の応答ヘッダー（ルートの `remove_response_headers` に加える）
+    pub fn owned_response_headers(&self) -> impl Iterator<Item = String> {
+        OWNED_RESPONSE_HEADERS.iter().map(|h| h.to_string())
+    }
+
+    /// 許可したオリジンへの `Access-Control-Allow-Origin`
+    fn allow_origin(&self, origin: &str) -> String {
+        if self.any_origin && !self.allow_credentials {
+            "*".to_string()
+        } else {
+            origin.to_string()
+        }
+    }
+
+    /// 通常のリクエストの応答に付けるヘッダー（`Vary` を含む）
+    pub fn response_headers(&self, headers: &[(&[u8], &[u8])]) -> Vec<(&'static str, String)> {
+        let origin = header(headers, "origin").and_then(|o| std::str::from_utf8(o).ok());
+        let headers = self.origin_headers(origin);
+        if let Some(origin) = origin {
+            crate::metrics::record_cors_request(if self.is_origin_allowed(origin) {
+                "allowed"
+            } else {
+                "rejected"
+            });
+        }
+        headers
+    }
+
+    /// `origin` への通常の応答のヘッダー（許可しないオリジン・`Origin` 無しは `Vary` だけ）
+    pub fn origin_headers(&self, origin: Option<&str>) -> Vec<(&'static str, String)> {
+        let mut out = Vec::new();
+        if let Some(origin) = origin.filter(|o| self.is_origin_allowed(o)) {
+            out.push(("Access-Control-Allow-Origin", self.allow_origin(origin)));
+            if self.allow_credentials {
+                out.push(("Access-Control-Allow-Credentials", "true".to_string()));
+            }
+            if !self.expose_headers.is_empty() {
+                out.push(("Access-Control-Expose-Headers", self.expose_headers.clone()));
+            }
+        }
+        if self.varies_by_origin() {
+            out.push(("Vary", "Origin".to_string()));
+        }
+        out
+    }
+
+    /// プリフライトなら応答を返す（プリフライトでなければ None）
+    pub fn preflight(&self, method: &[u8], headers: &[(&[u8], &[u8])]) -> Option<CorsPreflight> {
+        if method != b"OPTIONS" {
+            return None;
+        }
+        let origin = std::str::from_utf8(header(headers, "origin")?).ok()?;
+        let request_method =
+            std::str::from_utf8(header(headers, "access-control-request-method")?).ok()?;
+        let request_headers = header(headers, "access-control-request-headers")
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .unwrap_or("");
+        let preflight = match self.preflight_headers(origin, request_method, request_headers) {
+            Some(headers) => CorsPreflight {
+                status: 204,
+                headers,
+            },
+            None => CorsPreflight {
+                status: 403,
+                headers: vec![
+                    ("Content-Type", "text/plain".to_string()),
+                    ("Vary", PREFLIGHT_VARY.to_string()),
+                ],
+            },
+        };
+        crate::metrics::record_cors_request(if preflight.status == 204 {
+            "preflight"
+        } else {
+            "preflight_rejected"
+        });
+        Some(preflight)
+    }
+
+    /// プリフライトを許可する場合の応答ヘッダー（オリジン・メソッド・ヘッダーのいずれかを
+    /// 許可しなければ None）
+    pub fn preflight_headers(
+        &self,
+        origin: &str,
+        request_method: &str,
+        request_headers: &str,
+    ) -> Option<Vec<(&'static str, String)>> {
+        if !self.is_origin_allowed(origin) {
+            return None;
+        }
+        let request_method = request_method.trim();
+        if !self.any_method
+            && !self
+                .methods
+                .iter()
+                .any(|m| m.eq_ignore_ascii_case(request_method))
+        {
+            return None;
+        }
+        if !self.any_header
+            && !requested_headers(request_headers).all(|h| self.headers.contains(&h))
+        {
+            return None;
+        }
+
+        let mut out = vec![("Access-Control-Allow-Origin", self.allow_origin(origin))];
+        if self.allow_credentials {
+            out.push(("Access-Control-Allow-Credentials", "true".to_string()));
+        }
+        out.push((
+            "Access-Control-Allow-Methods",
+            if self.any_method {
+                request_method.to_string()
+            } else {
+                self.methods.join(", ")
+            },
+        ));
+        let allow_headers = if self.any_header {
+            requested_headers(request_headers)
+                .collect::<Vec<_>>()
+                .join(", ")
+        } else {
+            self.headers.join(", ")
+        };
+        if !allow_headers.is_empty() {
+            out.push(("Access-Control-Allow-Headers", allow_headers));
+        }
+        if let Some(max_age) = self.max_age {
+            out.push(("Access-Control-Max-Age", max_age.to_string()));
+        }
+        out.push(("Vary", PREFLIGHT_VARY.to_string()));
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cors(toml_str: &str) -> Cors {
+        let cfg: CorsConfig = toml::from_str(toml_str).unwrap();
+        Cors::new(&cfg).unwrap()
+    }
+
+    fn value<'a>(headers: &'a [(&'static str, String)], name: &str) -> Option<&'a str> {
+        headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn origins_match_exactly_by_wildcard_and_by_regex() {
+        let c = cors(
+            r#"
+            allowed_origins = ["https://app.example.com", "https://*.example.org", "http://localhost:*"]
+            allowed_origin_regexes = ['https://(dev|stg)\.example\.net']
+            "#,
+        );
+        for origin in [
+            "https://app.example.com",
+            "HTTPS://APP.EXAMPLE.COM",
+            "https://a.example.org",
+            "https://a.b.example.org",
+            "http://localhost:3000",
+            "https://dev.example.net",
+        ] {
+            assert!(c.is_origin_allowed(origin), "{}", origin);
+        }
+        for origin in [
+            "https://example.com",
+            "http://app.example.com",
+            "https://example.org",
+            "https://evil.com/.example.org",
+            "https://a.example.org.evil.com",
+            "http://localhost",
+            "https://prod.example.net",
+            "https://dev.example.net.evil.com",
+            "null",
+        ] {
+            assert!(!c.is_origin_allowed(origin), "{}", origin);
+        }
+        assert!(cors(r#"allowed_origins = ["*"]"#).is_origin_allowed("null"));
+        let bad: CorsConfig = toml::from_str(r#"allowed_origin_regexes = ["("]"#).unwrap();
+        assert!(Cors::new(&bad).is_err());
+    }
+
+    #[test]
+    fn preflights_are_answered_from_the_policy() {
+        let c = cors(
+            r#"
+            allowed_origins = ["https://app.example.com"]
+            allowed_methods = ["GET", "PUT"]
+            allowed_headers = ["Content-Type", "X-Request-Id"]
+            allow_credentials = true
+            max_age_secs = 600
+            "#,
+        );
+        let request = |method: &'static [u8], extra: &[(&'static [u8], &'static [u8])]| {
+            let mut headers: Vec<(&[u8], &[u8])> = vec![(b"origin", b"https://app.example.com")];
+            headers.extend_from_slice(extra);
+            c.preflight(method, &headers)
+        };
+
+        let ok = request(
+            b"OPTIONS",
+            &[
+                (b"Access-Control-Request-Method", b"PUT"),
+                (
+                    b"Access-Control-Request-Headers",
+                    b"content-type, x-request-id",
+                ),
+            ],
+        )
+        .unwrap();
+        assert_eq!(ok.status(), 204);
+        assert!(ok.body().is_empty());
+        let h = ok.headers();
+        assert_eq!(
+            value(h, "access-control-allow-origin"),
+            Some("https://app.example.com")
+        );
+        assert_eq!(value(h, "access-control-allow-credentials"), Some("true"));
+        assert_eq!(value(h, "access-control-allow-methods"), Some("GET, PUT"));
+        assert_eq!(
+            value(h, "access-control-allow-headers"),
+            Some("content-type, x-request-id")
+        );
+        assert_eq!(value(h, "access-control-max-age"), Some("600"));
+        assert_eq!(value(h, "vary"), Some(PREFLIGHT_VARY));
+        let http1 = String::from_utf8(ok.http1_response()).unwrap();
+        assert!(http1.starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(!http1.contains("Content-Length"));
+        assert!(http1.ends_with("Connection: close\r\n\r\n"));
+        assert!(ok
+            .h2_headers()
+            .iter()
+            .any(|(n, v)| n == b"access-control-allow-methods" && v == b"GET, PUT"));
+
+        // メソッド・ヘッダー・オリジンのいずれかを許可しなければ 403
+        for rejected in [
+            request(b"OPTIONS", &[(b"Access-Control-Request-Method", b"DELETE")]),
+            request(
+                b"OPTIONS",
+                &[
+                    (b"Access-Control-Request-Method", b"GET"),
+                    (b"Access-Control-Request-Headers", b"x-other"),
+                ],
+            ),
+        ] {
+            let rejected = rejected.unwrap();
+            assert_eq!(rejected.status(), 403);
+            assert!(value(rejected.headers(), "access-control-allow-origin").is_none());
+            let http1 = String::from_utf8(rejected.http1_response()).unwrap();
+            assert!(http1.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+            assert!(http1.ends_with("\r\n\r\nForbidden"));
+        }
+        let other: Vec<(&[u8], &[u8])> = vec![
+            (b"origin", b"https://evil.example"),
+            (b"access-control-request-method", b"GET"),
+        ];
+        assert_eq!(c.preflight(b"OPTIONS", &other).unwrap().status(), 403);
+
+        // Access-Control-Request-Method の無い OPTIONS・他のメソッドはプリフライトではない
+        assert!(request(b"OPTIONS", &[]).is_none());
+        assert!(request(b"GET", &[(b"Access-Control-Request-Method", b"GET")]).is_none());
+        let no_origin: Vec<(&[u8], &[u8])> = vec![(b"access-control-request-method", b"GET")];
+        assert!(c.preflight(b"OPTIONS", &no_origin).is_none());
+    }
+
+    #[test]
+    fn wildcard_methods_and_headers_echo_the_request() {
+        let c = cors(
+            r#"
+            allowed_origins = ["*"]
+            allowed_methods = ["*"]
+            allowed_headers = ["*"]
+            "#,
+        );
+        let h = c
+            .preflight_headers("https://any.example", "PATCH", "X-A,  x-b")
+            .unwrap();
+        assert_eq!(value(&h, "access-control-allow-origin"), Some("*"));
+        assert_eq!(value(&h, "access-control-allow-methods"), Some("PATCH"));
+        assert_eq!(value(&h, "access-control-allow-headers"), Some("x-a, x-b"));
+        assert!(value(&h, "access-control-max-age").is_none());
+        let h = c
+            .preflight_headers("https://any.example", "GET", "")
+            .unwrap();
+        assert!(value(&h, "access-control-allow-headers").is_none());
+    }
+
+    #[test]
+    fn responses_carry_allow_origin_and_vary() {
+        let c = cors(
+            r#"
+            allowed_origins = ["https://app.example.com"]
+            exposed_headers = ["X-Total-Count", "ETag"]
+            "#,
+        );
+        let allowed: Vec<(&[u8], &[u8])> = vec![(b"Origin", b"https://app.example.com")];
+        let h = c.response_headers(&allowed);
+        assert_eq!(
+            value(&h, "access-control-allow-origin"),
+            Some("https://app.example.com")
+        );
+        assert_eq!(
+            value(&h, "access-control-expose-headers"),
+            Some("X-Total-Count, ETag")
+        );
+        assert!(value(&h, "access-control-allow-credentials").is_none());
+        assert_eq!(value(&h, "vary"), Some("Origin"));
+
+        // 許可しないオリジン・Origin 無しでも応答はオリジンによって変わる
+        let denied: Vec<(&[u8], &[u8])> = vec![(b"origin", b"https://evil.example")];
+        assert_eq!(
+            c.response_headers(&denied),
+            vec![("Vary", "Origin".to_string())]
+        );
+        assert_eq!(
+            c.response_headers(&[]),
+            vec![("Vary", "Origin".to_string())]
+        );
+
+        // すべてのオリジンを資格情報なしで許可するなら "*" で、Vary は要らない
+        let any = cors(r#"allowed_origins = ["*"]"#);
+        assert_eq!(
+            any.origin_headers(Some("https://x.example")),
+            vec![("Access-Control-Allow-Origin", "*".to_string())]
+        );
+        assert!(any.origin_headers(None).is_empty());
+        let owned: Vec<String> = any.owned_response_headers().collect();
+        assert!(owned.contains(&"Access-Control-Allow-Origin".to_string()));
+    }
+}
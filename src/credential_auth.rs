@@ -0,0 +1,1171 @@
+//! 資格情報ファイルによる Basic 認証・API キー認証（F-151）
+//!
+//! `[route.basic_auth]` / `[route.api_key]` を設定したルートでは、上流へ転送する前に
+//! `Authorization: Basic` の利用者・パスワード、または API キーのヘッダーを検証する。
+//!
+//! - htpasswd: `利用者:ハッシュ` の行を並べたファイル。ハッシュは bcrypt（`$2a$`/`$2b$`/`$2y$`）と
+//!   SHA-crypt（`$5$`/`$6$`、`rounds=N$` つきも可）。平文・MD5・crypt(3) の DES は受け付けない。
+//!   bcrypt・SHA-crypt の検証は重いので [`crate::runtime::offload`] で実行し、成功した
+//!   利用者・パスワードの組はプロセス内の鍵の HMAC でキャッシュする（ファイルの更新で破棄）。
+//! - API キー: `[[key]]` に `id`・`hash`（`sha256:<16 進>`）・`tenant`・`routes`・
+//!   `rate_limit_class` を並べた TOML ファイル。鍵そのものはファイルに書かない。
+//!   `routes` を指定した鍵は、そのルート（`route_name`、省略時は `route[N]`）でしか使えない。
+//! - 再読み込み: 専用スレッドがファイルの更新時刻・サイズを 1 秒ごとに確認して読み直す。
+//!   SIGHUP の設定リロードでも読み直す。読み直しに失敗した間は直前の内容を使い続ける。
+//!   同じパスのファイルはルート・リロードをまたいで共有する。
+//! - 比較: ハッシュの照合は定数時間で行う。
+//!
+//! 資格情報が無い・不正なリクエストは 401（Basic 認証があれば `WWW-Authenticate: Basic`）、
+//! `routes` に含まれないルートで使われた API キーは 403 で拒否する。認証した利用者・鍵は
+//! `sub`（と `tenant`・`rate_limit_class`）のクレームとして JWT 認証（F-148）と同じ経路に
+//! 載せ、`claims_to_headers`・レートリミット（F-146 の `jwt_claim:` キー、`classes`）・
+//! 外部認可（F-149）・アクセスログの `user`・WASM の `request.auth.*` プロパティに使う。
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex, Once, Weak};
+use std::time::{Duration, SystemTime};
+
+use arc_swap::ArcSwap;
+use ftlog::{info, warn};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::config::{monotonic_ms, ApiKeyAuthConfig, BasicAuthConfig};
+use crate::http_utils::base64_decode;
+use crate::jwt_auth::claims_to_headers;
+use crate::tls_provider::crypto::{digest, hmac, rand::SystemRandom};
+
+/// 監視スレッドの巡回間隔
+const WATCH_TICK: Duration = Duration::from_secs(1);
+
+/// 検証に成功した利用者・パスワードのキャッシュの有効期間（ミリ秒）
+const VERIFIED_TTL_MS: u64 = 300_000;
+
+/// 検証に成功した利用者・パスワードのキャッシュの上限（超えたら空にする）
+const MAX_VERIFIED: usize = 10_000;
+
+/// SHA-crypt の既定のラウンド数と範囲
+const SHA_CRYPT_DEFAULT_ROUNDS: u32 = 5000;
+const SHA_CRYPT_MIN_ROUNDS: u32 = 1000;
+const SHA_CRYPT_MAX_ROUNDS: u32 = 999_999_999;
+
+/// SHA-crypt の salt の最大長
+const SHA_CRYPT_MAX_SALT: usize = 16;
+
+/// crypt(3) の base64 の文字
+const CRYPT_ALPHABET: &[u8; 64] =
+    b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// SHA-256-crypt の出力の並び（3 バイト → 4 文字、最後の 2 バイトは別扱い）
+const SHA256_CRYPT_ORDER: [[usize; 3]; 10] = [
+    [0, 10, 20],
+    [21, 1, 11],
+    [12, 22, 2],
+    [3, 13, 23],
+    [24, 4, 14],
+    [15, 25, 5],
+    [6, 16, 26],
+    [27, 7, 17],
+    [18, 28, 8],
+    [9, 19, 29],
+];
+
+/// SHA-512-crypt の出力の並び（3 バイト → 4 文字、最後の 1 バイトは別扱い）
+const SHA512_CRYPT_ORDER: [[usize; 3]; 21] = [
+    [0, 21, 42],
+    [22, 43, 1],
+    [44, 2, 23],
+    [3, 24, 45],
+    [25, 46, 4],
+    [47, 5, 26],
+    [6, 27, 48],
+    [28, 49, 7],
+    [50, 8, 29],
+    [9, 30, 51],
+    [31, 52, 10],
+    [53, 11, 32],
+    [12, 33, 54],
+    [34, 55, 13],
+    [56, 14, 35],
+    [15, 36, 57],
+    [37, 58, 16],
+    [59, 17, 38],
+    [18, 39, 60],
+    [40, 61, 19],
+    [62, 20, 41],
+];
+
+/// 検証済みキャッシュのキーを作る HMAC の鍵（プロセスごとに生成）
+static VERIFIED_KEY: Lazy<Option<hmac::Key>> =
+    Lazy::new(|| hmac::Key::generate(hmac::HMAC_SHA256, &SystemRandom::new()).ok());
+
+/// 監視スレッドの起動
+static WATCHER: Once = Once::new();
+
+/// htpasswd ファイル（パスごとに 1 つ、使われなくなれば破棄）
+static HTPASSWD_FILES: Lazy<Mutex<HashMap<String, Weak<WatchedFile<Htpasswd>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// API キーファイル（パスごとに 1 つ、使われなくなれば破棄）
+static API_KEY_FILES: Lazy<Mutex<HashMap<String, Weak<WatchedFile<ApiKeys>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 定数時間のバイト列比較（長さの違いは即座に false）
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 監視して読み直す資格情報ファイルの内容
+trait CredentialFile: Default + Send + Sync + Sized + 'static {
+    /// ログに出す種類
+    const KIND: &'static str;
+
+    fn parse(body: &[u8]) -> Result<Self, String>;
+
+    fn registry() -> &'static Mutex<HashMap<String, Weak<WatchedFile<Self>>>>;
+}
+
+/// 更新を監視する資格情報ファイル
+struct WatchedFile<T> {
+    path: String,
+    content: ArcSwap<T>,
+    /// 最後に読んだときの更新時刻・サイズ（読めなければ None）
+    stamp: Mutex<Option<(SystemTime, u64)>>,
+}
+
+impl<T: CredentialFile> WatchedFile<T> {
+    /// ファイルを開く（既に同じパスのファイルがあれば共有し、読み直す）
+    ///
+    /// 読めない・不正なファイルは警告して空（すべて拒否）で始める。設定の検証で
+    /// 事前に読んでいるので、通常は起動・リロードの間に消されたときだけ起きる。
+    fn open(path: &str) -> Arc<Self> {
+        let mut files = T::registry().lock().unwrap();
+        let file = match files.get(path).and_then(Weak::upgrade) {
+            Some(file) => file,
+            None => {
+                let file = Arc::new(Self {
+                    path: path.to_string(),
+                    content: ArcSwap::from_pointee(T::default()),
+                    stamp: Mutex::new(None),
+                });
+                files.retain(|_, f| f.strong_count() > 0);
+                files.insert(path.to_string(), Arc::downgrade(&file));
+                file
+            }
+        };
+        drop(files);
+
+        file.reload();
+        WATCHER.call_once(|| {
+            if let Err(e) = std::thread::Builder::new()
+                .name("veil-credentials".to_string())
+                .spawn(watch_loop)
+            {
+                warn!("Failed to spawn credential file watcher thread: {}", e);
+            }
+        });
+        file
+    }
+
+    /// ファイルを読み直す（失敗時は直前の内容を残す）
+    // 理由付き allow: 設定の読み込み・リロード時と監視スレッドからのみ呼ばれる（データプレーン非経由）。
+    #[allow(clippy::disallowed_methods)]
+    fn reload(&self) {
+        // 読む前に更新時刻を取る（読んでいる間の更新は次の巡回で拾う）
+        let stamp = file_stamp(&self.path);
+        let result = std::fs::read(&self.path)
+            .map_err(|e| e.to_string())
+            .and_then(|body| T::parse(&body));
+        match result {
+            Ok(content) => {
+                info!("Loaded {} {}", T::KIND, self.path);
+                self.content.store(Arc::new(content));
+            }
+            Err(e) => warn!(
+                "Failed to load {} {}: {} (keeping the previous entries)",
+                T::KIND,
+                self.path,
+                e
+            ),
+        }
+        *self.stamp.lock().unwrap() = stamp;
+    }
+
+    /// 更新されていれば読み直す
+    fn poll(&self) {
+        let stamp = file_stamp(&self.path);
+        if stamp != *self.stamp.lock().unwrap() {
+            self.reload();
+        }
+    }
+
+    /// 登録済みのファイルをすべて確認する
+    fn poll_all() {
+        let files: Vec<Arc<Self>> = {
+            let mut files = T::registry().lock().unwrap();
+            files.retain(|_, f| f.strong_count() > 0);
+            files.values().filter_map(Weak::upgrade).collect()
+        };
+        for file in files {
+            file.poll();
+        }
+    }
+}
+
+/// ファイルの更新時刻とサイズ（読めなければ None）
+// 理由付き allow: 監視スレッドと設定の読み込み時にのみ呼ばれる（データプレーン非経由）。
+#[allow(clippy::disallowed_methods)]
+fn file_stamp(path: &str) -> Option<(SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// 資格情報ファイルの更新を監視する（専用スレッド）
+// 理由付き allow: 専用スレッドの巡回待ち（イベントループ外）。
+#[allow(clippy::disallowed_methods)]
+fn watch_loop() {
+    info!("Credential file watcher thread started");
+    loop {
+        std::thread::sleep(WATCH_TICK);
+        WatchedFile::<Htpasswd>::poll_all();
+        WatchedFile::<ApiKeys>::poll_all();
+    }
+}
+
+/// 設定の検証用に資格情報ファイルを読む
+// 理由付き allow: 設定の読み込み・リロード時にのみ呼ばれる（データプレーン非経由）。
+#[allow(clippy::disallowed_methods)]
+fn check_file<T: CredentialFile>(path: &str) -> Result<(), String> {
+    let body = std::fs::read(path).map_err(|e| format!("read {}: {}", path, e))?;
+    T::parse(&body)
+        .map(|_| ())
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+/// htpasswd ファイルを読んで検証する（設定の検証用）
+pub fn check_htpasswd_file(path: &str) -> Result<(), String> {
+    check_file::<Htpasswd>(path)
+}
+
+/// API キーファイルを読んで検証する（設定の検証用）
+pub fn check_api_key_file(path: &str) -> Result<(), String> {
+    check_file::<ApiKeys>(path)
+}
+
+// --- htpasswd ---
+
+/// パスワードのハッシュ
+#[derive(Debug)]
+enum PasswordHash {
+    /// bcrypt（`$2a$` / `$2b$` / `$2y$`）
+    Bcrypt(String),
+    /// SHA-crypt（`$5$` / `$6$`）
+    ShaCrypt(ShaCrypt),
+}
+
+#[derive(Debug)]
+struct ShaCrypt {
+    sha512: bool,
+    rounds: u32,
+    salt: Vec<u8>,
+    /// crypt(3) の base64 で符号化したハッシュ
+    hash: Vec<u8>,
+}
+
+impl PasswordHash {
+    fn parse(hash: &str) -> Result<Self, String> {
+        if ["$2a$", "$2b$", "$2y$"].iter().any(|p| hash.starts_with(p)) {
+            hash.parse::<bcrypt::HashParts>()
+                .map_err(|e| format!("invalid bcrypt hash: {}", e))?;
+            return Ok(Self::Bcrypt(hash.to_string()));
+        }
+        let (sha512, rest) = if let Some(rest) = hash.strip_prefix("$5$") {
+            (false, rest)
+        } else if let Some(rest) = hash.strip_prefix("$6$") {
+            (true, rest)
+        } else {
+            return Err("unsupported password hash (use bcrypt or SHA-crypt)".to_string());
+        };
+        let (rounds, rest) = match rest.strip_prefix("rounds=") {
+            Some(rest) => {
+                let (n, rest) = rest
+                    .split_once('$')
+                    .ok_or_else(|| "invalid SHA-crypt rounds".to_string())?;
+                let n: u64 = n
+                    .parse()
+                    .map_err(|_| "invalid SHA-crypt rounds".to_string())?;
+                let n = n.clamp(SHA_CRYPT_MIN_ROUNDS as u64, SHA_CRYPT_MAX_ROUNDS as u64);
+                (n as u32, rest)
+            }
+            None => (SHA_CRYPT_DEFAULT_ROUNDS, rest),
+        };
+        let (salt, encoded) = rest
+            .split_once('$')
+            .ok_or_else(|| "invalid SHA-crypt hash".to_string())?;
+        let expected_len = if sha512 { 86 } else { 43 };
+        if encoded.len() != expected_len || !encoded.bytes().all(|b| CRYPT_ALPHABET.contains(&b)) {
+            return Err("invalid SHA-crypt hash".to_string());
+        }
+        let salt = salt.as_bytes();
+        Ok(Self::ShaCrypt(ShaCrypt {
+            sha512,
+            rounds,
+            salt: salt[..salt.len().min(SHA_CRYPT_MAX_SALT)].to_vec(),
+            hash: encoded.as_bytes().to_vec(),
+        }))
+    }
+
+    fn verify(&self, password: &[u8]) -> bool {
+        match self {
+            Self::Bcrypt(hash) => bcrypt::verify(password, hash).unwrap_or(false),
+            Self::ShaCrypt(c) => {
+                let computed = sha_crypt(c.sha512, password, &c.salt, c.rounds);
+                constant_time_eq(&computed, &c.hash)
+            }
+        }
+    }
+}
+
+/// SHA-crypt（Ulrich Drepper の仕様）の符号化済みハッシュを計算する
+fn sha_crypt(sha512: bool, password: &[u8], salt: &[u8], rounds: u32) -> Vec<u8> {
+    let algorithm = if sha512 {
+        &digest::SHA512
+    } else {
+        &digest::SHA256
+    };
+    let hash = |parts: &[&[u8]]| -> Vec<u8> {
+        let mut ctx = digest::Context::new(algorithm);
+        for part in parts {
+            ctx.update(part);
+        }
+        ctx.finish().as_ref().to_vec()
+    };
+    // ダイジェストを `len` バイトまで繰り返す
+    let repeat = |digest: &[u8], len: usize| -> Vec<u8> {
+        digest.iter().copied().cycle().take(len).collect()
+    };
+
+    let b = hash(&[password, salt, password]);
+    let mut ctx = digest::Context::new(algorithm);
+    ctx.update(password);
+    ctx.update(salt);
+    ctx.update(&repeat(&b, password.len()));
+    let mut n = password.len();
+    while n > 0 {
+        ctx.update(if n & 1 == 1 { &b } else { password });
+        n >>= 1;
+    }
+    let a = ctx.finish().as_ref().to_vec();
+
+    let dp = hash(&vec![password; password.len()]);
+    let p = repeat(&dp, password.len());
+    let ds = hash(&vec![salt; 16 + a[0] as usize]);
+    let s = repeat(&ds, salt.len());
+
+    let mut c = a;
+    for i in 0..rounds {
+        let mut ctx = digest::Context::new(algorithm);
+        ctx.update(if i % 2 == 1 { &p } else { &c });
+        if i % 3 != 0 {
+            ctx.update(&s);
+        }
+        if i % 7 != 0 {
+            ctx.update(&p);
+        }
+        ctx.update(if i % 2 == 1 { &c } else { &p });
+        c = ctx.finish().as_ref().to_vec();
+    }
+
+    let mut out = Vec::with_capacity(86);
+    let mut push = |mut w: u32, chars: usize| {
+        for _ in 0..chars {
+            out.push(CRYPT_ALPHABET[(w & 0x3f) as usize]);
+            w >>= 6;
+        }
+    };
+    let (order, tail): (&[[usize; 3]], _) = if sha512 {
+        (&SHA512_CRYPT_ORDER[..], (c[63] as u32, 2))
+    } else {
+        (
+            &SHA256_CRYPT_ORDER[..],
+            ((c[31] as u32) << 8 | c[30] as u32, 3),
+        )
+    };
+    for &[x, y, z] in order {
+        push((c[x] as u32) << 16 | (c[y] as u32) << 8 | c[z] as u32, 4);
+    }
+    push(tail.0, tail.1);
+    out
+}
+
+/// htpasswd ファイルの内容
+#[derive(Debug, Default)]
+struct Htpasswd {
+    users: HashMap<String, PasswordHash>,
+    /// 存在しない利用者でも照合する先頭の利用者（応答時間から利用者名を推測させない）
+    dummy_user: Option<String>,
+    /// 検証に成功した利用者・パスワードの HMAC → 期限（[`monotonic_ms`]）
+    verified: Mutex<HashMap<[u8; 32], u64>>,
+}
+
+impl CredentialFile for Htpasswd {
+    const KIND: &'static str = "htpasswd file";
+
+    fn parse(body: &[u8]) -> Result<Self, String> {
+        let body = std::str::from_utf8(body).map_err(|_| "not UTF-8".to_string())?;
+        let mut users = HashMap::new();
+        let mut dummy_user = None;
+        for (i, line) in body.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (user, hash) = line
+                .split_once(':')
+                .filter(|(user, _)| !user.is_empty())
+                .ok_or_else(|| format!("line {}: expected 'user:hash'", i + 1))?;
+            let hash = PasswordHash::parse(hash).map_err(|e| format!("line {}: {}", i + 1, e))?;
+            if users.insert(user.to_string(), hash).is_some() {
+                return Err(format!("line {}: duplicate user '{}'", i + 1, user));
+            }
+            dummy_user.get_or_insert_with(|| user.to_string());
+        }
+        Ok(Self {
+            users,
+            dummy_user,
+            verified: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn registry() -> &'static Mutex<HashMap<String, Weak<WatchedFile<Self>>>> {
+        &HTPASSWD_FILES
+    }
+}
+
+impl Htpasswd {
+    /// パスワードを検証する（重い、[`crate::runtime::offload`] から呼ぶ）
+    ///
+    /// 存在しない利用者は先頭の利用者のハッシュ（同じ方式・コスト）で照合してから
+    /// 拒否し、存在する利用者と同じだけ時間をかける。
+    fn verify(&self, user: &str, password: &[u8]) -> bool {
+        match self.users.get(user) {
+            Some(hash) => hash.verify(password),
+            None => {
+                if let Some(hash) = self.dummy_user.as_ref().and_then(|u| self.users.get(u)) {
+                    hash.verify(password);
+                }
+                false
+            }
+        }
+    }
+
+    /// 検証済みキャッシュに有効な記録があるか
+    fn is_verified(&self, tag: &[u8; 32]) -> bool {
+        let verified = self.verified.lock().unwrap();
+        verified
+            .get(tag)
+            .is_some_and(|&expires| expires > monotonic_ms())
+    }
+
+    /// 検証に成功した組を記録する
+    fn remember(&self, tag: [u8; 32]) {
+        let mut verified = self.verified.lock().unwrap();
+        if verified.len() >= MAX_VERIFIED {
+            verified.clear();
+        }
+        verified.insert(tag, monotonic_ms() + VERIFIED_TTL_MS);
+    }
+}
+
+/// 検証済みキャッシュのキー（利用者・パスワードの HMAC、パスワードそのものは保持しない）
+fn verified_tag(user: &str, password: &[u8]) -> Option<[u8; 32]> {
+    let key = VERIFIED_KEY.as_ref()?;
+    let mut ctx = hmac::Context::with_key(key);
+    ctx.update(user.as_bytes());
+    ctx.update(b"\0");
+    ctx.update(password);
+    ctx.sign().as_ref().try_into().ok()
+}
+
+// --- API キー ---
+
+/// API キーファイル
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApiKeysFile {
+    #[serde(default)]
+    key: Vec<ApiKeyEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApiKeyEntry {
+    id: String,
+    hash: String,
+    #[serde(default)]
+    tenant: Option<String>,
+    #[serde(default)]
+    routes: Vec<String>,
+    #[serde(default)]
+    rate_limit_class: Option<String>,
+}
+
+/// 読み込んだ API キー
+#[derive(Debug)]
+struct ApiKey {
+    id: String,
+    digest: [u8; 32],
+    tenant: Option<String>,
+    routes: Vec<String>,
+    rate_limit_class: Option<String>,
+}
+
+impl ApiKey {
+    /// 上流・レートリミット・ログへ渡すクレーム
+    fn claims(&self) -> Value {
+        let mut claims = Map::new();
+        claims.insert("sub".to_string(), Value::String(self.id.clone()));
+        if let Some(tenant) = &self.tenant {
+            claims.insert("tenant".to_string(), Value::String(tenant.clone()));
+        }
+        if let Some(class) = &self.rate_limit_class {
+            claims.insert("rate_limit_class".to_string(), Value::String(class.clone()));
+        }
+        Value::Object(claims)
+    }
+}
+
+/// API キーファイルの内容（SHA-256 の先頭 8 バイトで引き、全体を定数時間で照合する）
+#[derive(Debug, Default)]
+struct ApiKeys {
+    keys: HashMap<[u8; 8], Vec<ApiKey>>,
+}
+
+impl CredentialFile for ApiKeys {
+    const KIND: &'static str = "API key file";
+
+    fn parse(body: &[u8]) -> Result<Self, String> {
+        let body = std::str::from_utf8(body).map_err(|_| "not UTF-8".to_string())?;
+        let file: ApiKeysFile = toml::from_str(body).map_err(|e| e.to_string())?;
+        let mut ids = HashSet::new();
+        let mut keys: HashMap<[u8; 8], Vec<ApiKey>> = HashMap::new();
+        for entry in file.key {
+            if entry.id.is_empty() || !ids.insert(entry.id.clone()) {
+                return Err(format!(
+                    "key id '{}' must be non-empty and unique",
+                    entry.id
+                ));
+            }
+            let digest = entry
+                .hash
+                .strip_prefix("sha256:")
+                .and_then(decode_hex_digest)
+                .ok_or_else(|| {
+                    format!("key '{}': hash must be 'sha256:<64 hex digits>'", entry.id)
+                })?;
+            if entry.routes.iter().any(String::is_empty)
+                || entry.rate_limit_class.as_deref() == Some("")
+            {
+                return Err(format!(
+                    "key '{}': routes and rate_limit_class must not be empty strings",
+                    entry.id
+                ));
+            }
+            let bucket = keys.entry(prefix(&digest)).or_default();
+            if bucket.iter().any(|k| k.digest == digest) {
+                return Err(format!("key '{}': duplicate hash", entry.id));
+            }
+            bucket.push(ApiKey {
+                id: entry.id,
+                digest,
+                tenant: entry.tenant,
+                routes: entry.routes,
+                rate_limit_class: entry.rate_limit_class,
+            });
+        }
+        Ok(Self { keys })
+    }
+
+    fn registry() -> &'static Mutex<HashMap<String, Weak<WatchedFile<Self>>>> {
+        &API_KEY_FILES
+    }
+}
+
+impl ApiKeys {
+    fn find(&self, key: &[u8]) -> Option<&ApiKey> {
+        let digest = digest::digest(&digest::SHA256, key);
+        let digest = digest.as_ref();
+        self.keys
+            .get(&prefix(digest))?
+            .iter()
+            .find(|k| constant_time_eq(&k.digest, digest))
+    }
+}
+
+fn prefix(digest: &[u8]) -> [u8; 8] {
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    prefix
+}
+
+/// 64 桁の 16 進（大文字・小文字）を 32 バイトにする
+fn decode_hex_digest(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(out)
+}
+
+// --- 認証 ---
+
+/// 認証の失敗
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CredentialRejection {
+    /// 資格情報が無い・不正（401、Basic 認証があれば `WWW-Authenticate` の値つき）
+    Unauthorized(Option<Arc<str>>),
+    /// API キーがこのルートで使えない（403）
+    Forbidden,
+}
+
+impl CredentialRejection {
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::Unauthorized(_) => 401,
+            Self::Forbidden => 403,
+        }
+    }
+
+    /// 応答ボディ
+    pub fn body(&self) -> &'static [u8] {
+        match self {
+            Self::Unauthorized(_) => b"Unauthorized",
+            Self::Forbidden => b"Forbidden",
+        }
+    }
+
+    /// HTTP/2・HTTP/3 の応答ヘッダー（小文字）
+    pub fn headers(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        match self {
+            Self::Unauthorized(Some(challenge)) => {
+                vec![(b"www-authenticate".to_vec(), challenge.as_bytes().to_vec())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// HTTP/1.1 の応答
+    pub fn http1_response(&self) -> Vec<u8> {
+        let body = self.body();
+        let challenge = match self {
+            Self::Unauthorized(Some(challenge)) => format!("WWW-Authenticate: {}\r\n", challenge),
+            _ => String::new(),
+        };
+        let mut response = format!(
+            "HTTP/1.1 {} {}\r\n{}Content-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status(),
+            String::from_utf8_lossy(body),
+            challenge,
+            body.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+}
+
+/// 認証の失敗の内訳（メトリクスのラベル）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Failure {
+    Missing,
+    Invalid,
+    Forbidden,
+}
+
+impl Failure {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Missing => "missing",
+            Self::Invalid => "invalid",
+            Self::Forbidden => "forbidden",
+        }
+    }
+}
+
+/// ルートの Basic 認証
+struct BasicAuth {
+    file: Arc<WatchedFile<Htpasswd>>,
+    strip_credentials: bool,
+    claims_to_headers: Vec<(String, String)>,
+}
+
+/// ルートの API キー認証
+struct ApiKeyAuth {
+    file: Arc<WatchedFile<ApiKeys>>,
+    header: String,
+    route_name: String,
+    strip_credentials: bool,
+    claims_to_headers: Vec<(String, String)>,
+}
+
+/// 構築済みのルートの Basic 認証・API キー認証
+pub struct CredentialAuth {
+    basic: Option<BasicAuth>,
+    api_key: Option<ApiKeyAuth>,
+    /// 401 の `WWW-Authenticate`（Basic 認証があるときだけ）
+    challenge: Option<Arc<str>>,
+}
+
+impl fmt::Debug for CredentialAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CredentialAuth")
+            .field("htpasswd_file", &self.basic.as_ref().map(|b| &b.file.path))
+            .field("keys_file", &self.api_key.as_ref().map(|k| &k.file.path))
+            .finish()
+    }
+}
+
+/// 認証に成功したリクエストのクレームと上流へ転送するヘッダー
+pub type Authenticated = (Value, Vec<(String, String)>);
+
+impl CredentialAuth {
+    /// 設定から構築する（資格情報ファイルはここで読む）
+    ///
+    /// `route` はこのルートの既定の名前（`route[N]`）で、API キーの `routes` と照合する。
+    pub fn new(
+        route: String,
+        basic: Option<&BasicAuthConfig>,
+        api_key: Option<&ApiKeyAuthConfig>,
+    ) -> Self {
+        let to_vec = |mapping: &std::collections::BTreeMap<String, String>| {
+            mapping
+                .iter()
+                .map(|(claim, header)| (claim.clone(), header.clone()))
+                .collect::<Vec<_>>()
+        };
+        Self {
+            challenge: basic.map(|cfg| Arc::from(format!("Basic realm=\"{}\"", cfg.realm))),
+            basic: basic.map(|cfg| BasicAuth {
+                file: WatchedFile::open(&cfg.htpasswd_file),
+                strip_credentials: cfg.strip_credentials,
+                claims_to_headers: to_vec(&cfg.claims_to_headers),
+            }),
+            api_key: api_key.map(|cfg| ApiKeyAuth {
+                file: WatchedFile::open(&cfg.keys_file),
+                header: cfg.header.clone(),
+                route_name: cfg.route_name.clone().unwrap_or(route),
+                strip_credentials: cfg.strip_credentials,
+                claims_to_headers: to_vec(&cfg.claims_to_headers),
+            }),
+        }
+    }
+
+    /// クライアントから上流へ渡さないリクエストヘッダー
+    ///
+    /// `claims_to_headers` の転送先（なりすまし防止）と、`strip_credentials` の
+    /// `Authorization`・API キーのヘッダー。
+    pub fn stripped_request_headers(&self) -> Vec<String> {
+        let mut headers = Vec::new();
+        if let Some(basic) = &self.basic {
+            headers.extend(basic.claims_to_headers.iter().map(|(_, h)| h.clone()));
+            if basic.strip_credentials {
+                headers.push("Authorization".to_string());
+            }
+        }
+        if let Some(api_key) = &self.api_key {
+            headers.extend(api_key.claims_to_headers.iter().map(|(_, h)| h.clone()));
+            if api_key.strip_credentials {
+                headers.push(api_key.header.clone());
+            }
+        }
+        headers
+    }
+
+    /// リクエストを認証し、クレームと上流へ転送するヘッダーを返す
+    ///
+    /// API キーのヘッダーがあれば API キー、無ければ `Authorization: Basic` で認証する。
+    /// 結果は `veil_credential_auth_total` に記録する。
+    pub async fn authenticate(
+        &self,
+        headers: &[(&[u8], &[u8])],
+    ) -> Result<Authenticated, CredentialRejection> {
+        let (method, result) = self.verify(headers).await;
+        crate::metrics::record_credential_auth(
+            method,
+            match &result {
+                Ok(_) => "ok",
+                Err(failure) => failure.label(),
+            },
+        );
+        result.map_err(|failure| match failure {
+            Failure::Forbidden => CredentialRejection::Forbidden,
+            Failure::Missing | Failure::Invalid => {
+                CredentialRejection::Unauthorized(self.challenge.clone())
+            }
+        })
+    }
+
+    async fn verify(
+        &self,
+        headers: &[(&[u8], &[u8])],
+    ) -> (&'static str, Result<Authenticated, Failure>) {
+        if let Some(api_key) = &self.api_key {
+            if let Some(key) = header_value(headers, api_key.header.as_bytes()) {
+                return ("api_key", api_key.verify(key));
+            }
+        }
+        if let Some(basic) = &self.basic {
+            if let Some(credentials) = basic_credentials(headers) {
+                return ("basic", basic.verify(credentials).await);
+            }
+        }
+        ("none", Err(Failure::Missing))
+    }
+}
+
+impl ApiKeyAuth {
+    fn verify(&self, key: &[u8]) -> Result<Authenticated, Failure> {
+        let keys = self.file.content.load();
+        let key = keys.find(key).ok_or(Failure::Invalid)?;
+        if !key.routes.is_empty() && !key.routes.contains(&self.route_name) {
+            return Err(Failure::Forbidden);
+        }
+        let claims = key.claims();
+        let headers = claims_to_headers(&self.claims_to_headers, &claims);
+        Ok((claims, headers))
+    }
+}
+
+impl BasicAuth {
+    async fn verify(&self, credentials: &[u8]) -> Result<Authenticated, Failure> {
+        let decoded = base64_decode(credentials).ok_or(Failure::Invalid)?;
+        let colon = decoded
+            .iter()
+            .position(|&b| b == b':')
+            .ok_or(Failure::Invalid)?;
+        let user = std::str::from_utf8(&decoded[..colon])
+            .map_err(|_| Failure::Invalid)?
+            .to_string();
+        let password = decoded[colon + 1..].to_vec();
+
+        let htpasswd = self.file.content.load_full();
+        let tag = verified_tag(&user, &password);
+        if !tag.is_some_and(|tag| htpasswd.is_verified(&tag)) {
+            let file = htpasswd.clone();
+            let name = user.clone();
+            let ok = crate::runtime::offload::offload(move || file.verify(&name, &password)).await;
+            if !ok {
+                return Err(Failure::Invalid);
+            }
+            if let Some(tag) = tag {
+                htpasswd.remember(tag);
+            }
+        }
+        let claims = serde_json::json!({ "sub": user });
+        let headers = claims_to_headers(&self.claims_to_headers, &claims);
+        Ok((claims, headers))
+    }
+}
+
+/// 名前が一致するリクエストヘッダーの値（前後の空白を除き、空なら None）
+fn header_value<'a>(headers: &[(&'a [u8], &'a [u8])], name: &[u8]) -> Option<&'a [u8]> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.trim_ascii())
+        .filter(|v| !v.is_empty())
+}
+
+/// `Authorization: Basic` の資格情報（base64 のまま）
+fn basic_credentials<'a>(headers: &[(&'a [u8], &'a [u8])]) -> Option<&'a [u8]> {
+    let auth = header_value(headers, b"authorization")?;
+    auth.get(..6)
+        .filter(|scheme| scheme.eq_ignore_ascii_case(b"basic "))
+        .map(|_| auth[6..].trim_ascii())
+        .filter(|credentials| !credentials.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    // 理由付き allow: テストコードは同期 I/O を使用してよい（データプレーン非経由）。
+    #![allow(clippy::disallowed_methods)]
+    use super::*;
+    use crate::http_utils::base64_encode;
+
+    /// "s3cr3t" の bcrypt（cost 4）
+    const BCRYPT_S3CR3T: &str = "$2y$04$KBCwKxOzLha2MUDgW0PjXe4/G/VOhqN5KiuuxLwSWm4d5jyZGASdm";
+
+    /// "Hello world!" の SHA-crypt（salt "saltstring"）
+    const SHA256_HELLO: &str = "$5$saltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5";
+    const SHA512_HELLO: &str = "$6$saltstring$svn8UoSVapNtMuq1ukKS4tPQd8iKwSMHWjl/O817G3uBnIFNjnQJuesI68u4OTLiBFdcbYEdFCoEOfaS35inz1";
+
+    /// "k-alpha" / "k-beta" の SHA-256
+    const ALPHA_SHA256: &str = "36294c655e462786692d261f9d8bf6be31670bc66004afd9c91416223221410b";
+    const BETA_SHA256: &str = "3b6424f5938ab57d09f708b7e81994276b9ea3be655baffd5dbd3ca06433c3c6";
+
+    fn write(dir: &tempfile::TempDir, name: &str, body: &str) -> String {
+        let path = dir.path().join(name);
+        std::fs::write(&path, body).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    fn keys_file() -> String {
+        format!(
+            "[[key]]\nid = \"alpha\"\nhash = \"sha256:{}\"\ntenant = \"acme\"\nrate_limit_class = \"gold\"\n\n\
+             [[key]]\nid = \"beta\"\nhash = \"sha256:{}\"\nroutes = [\"admin\"]\n",
+            ALPHA_SHA256,
+            BETA_SHA256.to_ascii_uppercase()
+        )
+    }
+
+    fn basic_header(user: &str, password: &str) -> Vec<u8> {
+        format!(
+            "Basic {}",
+            base64_encode(format!("{}:{}", user, password).as_bytes())
+        )
+        .into_bytes()
+    }
+
+    fn authenticate(
+        auth: &CredentialAuth,
+        headers: &[(&[u8], &[u8])],
+    ) -> Result<Authenticated, CredentialRejection> {
+        futures::executor::block_on(auth.authenticate(headers))
+    }
+
+    #[test]
+    fn sha_crypt_matches_reference_vectors() {
+        for (hash, password) in [
+            (SHA256_HELLO, "Hello world!"),
+            (SHA512_HELLO, "Hello world!"),
+            (
+                "$5$rounds=10000$saltstringsaltstring$3xv.VbSHBb41AL9AvLeujZkZRBAwqFMz2.opqey6IcA",
+                "Hello world!",
+            ),
+            (
+                "$6$rounds=1400$anotherlongsalts$AP.vbZcNbWD30OfPAcUJe702LINHtb7RqILoLW9vJ/DHPMJyr6a.5rQHcOzBXuDOEzAqm8/9xW6EF/z3vOBQp0",
+                "we have a short salt string but not a short password",
+            ),
+        ] {
+            let parsed = PasswordHash::parse(hash).unwrap();
+            assert!(parsed.verify(password.as_bytes()), "{}", hash);
+            assert!(!parsed.verify(b"Hello world?"), "{}", hash);
+        }
+    }
+
+    #[test]
+    fn bcrypt_hashes_verify() {
+        let parsed = PasswordHash::parse(BCRYPT_S3CR3T).unwrap();
+        assert!(parsed.verify(b"s3cr3t"));
+        assert!(!parsed.verify(b"s3cr3T"));
+        assert!(PasswordHash::parse("$2b$04$short").is_err());
+    }
+
+    #[test]
+    fn htpasswd_accepts_only_bcrypt_and_sha_crypt() {
+        let file = Htpasswd::parse(
+            format!(
+                "# users\n\nalice:{}\r\nbob:{}\n",
+                BCRYPT_S3CR3T, SHA256_HELLO
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+        assert!(file.verify("alice", b"s3cr3t"));
+        assert!(file.verify("bob", b"Hello world!"));
+        // 存在しない利用者は先頭の利用者のハッシュで照合するが、一致しても拒否する
+        assert_eq!(file.dummy_user.as_deref(), Some("alice"));
+        assert!(!file.verify("carol", b"s3cr3t"));
+
+        for body in [
+            "alice:plaintext\n",
+            "alice:$apr1$salt$hash\n",
+            "alice:{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=\n",
+            "alice\n",
+            ":$5$saltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5\n",
+            "alice:$5$saltstring$tooshort\n",
+        ] {
+            assert!(Htpasswd::parse(body.as_bytes()).is_err(), "{}", body);
+        }
+        let duplicate = format!("alice:{}\nalice:{}\n", SHA256_HELLO, SHA512_HELLO);
+        assert!(Htpasswd::parse(duplicate.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn api_keys_are_looked_up_by_hash() {
+        let keys = ApiKeys::parse(keys_file().as_bytes()).unwrap();
+        let alpha = keys.find(b"k-alpha").unwrap();
+        assert_eq!(alpha.id, "alpha");
+        assert_eq!(
+            alpha.claims(),
+            serde_json::json!({"sub": "alpha", "tenant": "acme", "rate_limit_class": "gold"})
+        );
+        assert_eq!(keys.find(b"k-beta").unwrap().id, "beta");
+        assert!(keys.find(b"k-gamma").is_none());
+
+        for body in [
+            "[[key]]\nid = \"a\"\nhash = \"k-alpha\"\n".to_string(),
+            "[[key]]\nid = \"a\"\nhash = \"sha256:abcd\"\n".to_string(),
+            format!("[[key]]\nid = \"\"\nhash = \"sha256:{}\"\n", ALPHA_SHA256),
+            format!(
+                "[[key]]\nid = \"a\"\nhash = \"sha256:{}\"\n[[key]]\nid = \"a\"\nhash = \"sha256:{}\"\n",
+                ALPHA_SHA256, BETA_SHA256
+            ),
+            format!(
+                "[[key]]\nid = \"a\"\nhash = \"sha256:{}\"\n[[key]]\nid = \"b\"\nhash = \"sha256:{}\"\n",
+                ALPHA_SHA256, ALPHA_SHA256
+            ),
+            format!("[[key]]\nid = \"a\"\nhash = \"sha256:{}\"\nroute = [\"x\"]\n", ALPHA_SHA256),
+        ] {
+            assert!(ApiKeys::parse(body.as_bytes()).is_err(), "{}", body);
+        }
+    }
+
+    #[test]
+    fn basic_auth_verifies_users_and_challenges() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(&dir, "htpasswd", &format!("alice:{}\n", BCRYPT_S3CR3T));
+        let cfg: BasicAuthConfig = toml::from_str(&format!(
+            "htpasswd_file = \"{}\"\nrealm = \"staff\"\nstrip_credentials = true\n\
+             claims_to_headers = {{ sub = \"X-User\" }}\n",
+            path
+        ))
+        .unwrap();
+        let auth = CredentialAuth::new("route[0]".to_string(), Some(&cfg), None);
+        assert_eq!(auth.stripped_request_headers(), ["X-User", "Authorization"]);
+
+        let ok = basic_header("alice", "s3cr3t");
+        for _ in 0..2 {
+            // 2 回目は検証済みキャッシュから
+            let (claims, headers) =
+                authenticate(&auth, &[(b"authorization", ok.as_slice())]).unwrap();
+            assert_eq!(claims, serde_json::json!({"sub": "alice"}));
+            assert_eq!(headers, [("X-User".to_string(), "alice".to_string())]);
+        }
+
+        let challenge = CredentialRejection::Unauthorized(Some(Arc::from("Basic realm=\"staff\"")));
+        let wrong = basic_header("alice", "wrong");
+        let unknown = basic_header("mallory", "s3cr3t");
+        for headers in [
+            vec![],
+            vec![(b"authorization".as_slice(), wrong.as_slice())],
+            vec![(b"authorization".as_slice(), unknown.as_slice())],
+            vec![(b"authorization".as_slice(), b"Basic !!!".as_slice())],
+            vec![(b"authorization".as_slice(), b"Bearer abc".as_slice())],
+        ] {
+            assert_eq!(authenticate(&auth, &headers).unwrap_err(), challenge);
+        }
+        let response = String::from_utf8(challenge.http1_response()).unwrap();
+        assert!(response.starts_with("HTTP/1.1 401 Unauthorized\r\n"));
+        assert!(response.contains("\r\nWWW-Authenticate: Basic realm=\"staff\"\r\n"));
+    }
+
+    #[test]
+    fn api_keys_are_restricted_to_their_routes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(&dir, "keys.toml", &keys_file());
+        let cfg: ApiKeyAuthConfig = toml::from_str(&format!(
+            "keys_file = \"{}\"\nclaims_to_headers = {{ tenant = \"X-Tenant\" }}\n",
+            path
+        ))
+        .unwrap();
+        let auth = CredentialAuth::new("route[3]".to_string(), None, Some(&cfg));
+        assert_eq!(auth.stripped_request_headers(), ["X-Tenant"]);
+
+        let (claims, headers) = authenticate(&auth, &[(b"x-api-key", b" k-alpha ")]).unwrap();
+        assert_eq!(claims["sub"], "alpha");
+        assert_eq!(headers, [("X-Tenant".to_string(), "acme".to_string())]);
+
+        assert_eq!(
+            authenticate(&auth, &[(b"x-api-key", b"k-beta")]).unwrap_err(),
+            CredentialRejection::Forbidden
+        );
+        assert_eq!(
+            authenticate(&auth, &[(b"x-api-key", b"k-gamma")]).unwrap_err(),
+            CredentialRejection::Unauthorized(None)
+        );
+        assert_eq!(
+            authenticate(&auth, &[]).unwrap_err(),
+            CredentialRejection::Unauthorized(None)
+        );
+        assert!(CredentialRejection::Unauthorized(None).headers().is_empty());
+
+        let admin_cfg: ApiKeyAuthConfig = toml::from_str(&format!(
+            "keys_file = \"{}\"\nroute_name = \"admin\"\nheader = \"X-Admin-Key\"\nstrip_credentials = true\n",
+            path
+        ))
+        .unwrap();
+        let admin = CredentialAuth::new("route[4]".to_string(), None, Some(&admin_cfg));
+        assert_eq!(admin.stripped_request_headers(), ["X-Admin-Key"]);
+        assert_eq!(
+            authenticate(&admin, &[(b"x-admin-key", b"k-beta")])
+                .unwrap()
+                .0["sub"],
+            "beta"
+        );
+    }
+
+    #[test]
+    fn api_key_takes_precedence_over_basic() {
+        let dir = tempfile::tempdir().unwrap();
+        let htpasswd = write(&dir, "htpasswd", &format!("alice:{}\n", BCRYPT_S3CR3T));
+        let keys = write(&dir, "keys.toml", &keys_file());
+        let basic: BasicAuthConfig =
+            toml::from_str(&format!("htpasswd_file = \"{}\"\n", htpasswd)).unwrap();
+        let api_key: ApiKeyAuthConfig =
+            toml::from_str(&format!("keys_file = \"{}\"\n", keys)).unwrap();
+        let auth = CredentialAuth::new("route[0]".to_string(), Some(&basic), Some(&api_key));
+
+        let ok = basic_header("alice", "s3cr3t");
+        let both = [
+            (b"authorization".as_slice(), ok.as_slice()),
+            (b"x-api-key".as_slice(), b"k-alpha".as_slice()),
+        ];
+        assert_eq!(authenticate(&auth, &both).unwrap().0["sub"], "alpha");
+        assert_eq!(authenticate(&auth, &both[..1]).unwrap().0["sub"], "alice");
+        assert_eq!(
+            authenticate(&auth, &[(b"x-api-key", b"k-gamma")]).unwrap_err(),
+            CredentialRejection::Unauthorized(Some(Arc::from("Basic realm=\"veil\"")))
+        );
+    }
+
+    #[test]
+    fn files_are_reloaded_when_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(&dir, "htpasswd", &format!("alice:{}\n", BCRYPT_S3CR3T));
+        let cfg: BasicAuthConfig =
+            toml::from_str(&format!("htpasswd_file = \"{}\"\n", path)).unwrap();
+        let auth = CredentialAuth::new("route[0]".to_string(), Some(&cfg), None);
+        let file = &auth.basic.as_ref().unwrap().file;
+        let alice = basic_header("alice", "s3cr3t");
+        let bob = basic_header("bob", "Hello world!");
+        assert!(authenticate(&auth, &[(b"authorization", alice.as_slice())]).is_ok());
+
+        // 利用者を差し替える（検証済みキャッシュも破棄される）
+        write(&dir, "htpasswd", &format!("bob:{}\n", SHA512_HELLO));
+        file.poll();
+        assert!(authenticate(&auth, &[(b"authorization", alice.as_slice())]).is_err());
+        assert!(authenticate(&auth, &[(b"authorization", bob.as_slice())]).is_ok());
+
+        // 不正な内容・削除の間は直前の内容を使い続ける
+        write(&dir, "htpasswd", "bob:plaintext-password\n");
+        file.poll();
+        assert!(authenticate(&auth, &[(b"authorization", bob.as_slice())]).is_ok());
+        std::fs::remove_file(&path).unwrap();
+        file.poll();
+        assert!(authenticate(&auth, &[(b"authorization", bob.as_slice())]).is_ok());
+
+        // 同じパスはルート・リロードをまたいで共有し、開き直すと読み直す
+        write(&dir, "htpasswd", &format!("alice:{}\n", BCRYPT_S3CR3T));
+        let reloaded = CredentialAuth::new("route[0]".to_string(), Some(&cfg), None);
+        assert!(Arc::ptr_eq(file, &reloaded.basic.as_ref().unwrap().file));
+        assert!(authenticate(&auth, &[(b"authorization", alice.as_slice())]).is_ok());
+        assert!(check_htpasswd_file(&path).is_ok());
+        assert!(check_htpasswd_file(&format!("{}.missing", path)).is_err());
+    }
+}
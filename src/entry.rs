@@ -73,7 +73,10 @@ pub fn run() {
     // -t オプション: 設定ファイルのテストのみ
     if cli_args.test_config {
         match test_config_file(&config_path) {
-            Ok(()) => {
+            Ok(report) => {
+                for line in report {
+                    println!("veil: {}", line);
+                }
                 println!(
                     "veil: configuration file {} test is successful",
                     config_path.display()
@@ -499,10 +502,36 @@ pub fn run() {
     // 5. seccomp（システムコール制限）
     // ====================
 
+    // F-141: 透過モード（IP_TRANSPARENT）の上流があれば降格後も CAP_NET_ADMIN を保持する
+    // F-142: SO_MARK を設定するソケットプロファイルも同様（リスナーは降格後に作成される）
+    let sets_mark = |socket: &Option<Arc<crate::runtime::sockopt::SocketOptions>>| {
+        socket.as_ref().is_some_and(|opts| opts.mark.is_some())
+    };
+    let needs_net_admin = sets_mark(&loaded_config.listener_socket)
+        || loaded_config
+            .upstream_groups
+            .values()
+            .flat_map(|group| group.servers.iter())
+            .any(|server| {
+                matches!(
+                    server.target.source.as_deref(),
+                    Some(crate::config::UpstreamSource::Transparent)
+                ) || sets_mark(&server.target.socket)
+            });
+    #[cfg(feature = "l4-proxy")]
+    let needs_net_admin = needs_net_admin
+        || loaded_config.l4_listeners.iter().any(|l4| {
+            l4.transparent || sets_mark(&l4.listener_socket) || sets_mark(&l4.upstream_socket)
+        });
+
     let sandbox_config = if loaded_config.global_security.enable_sandbox {
         // サンドボックスサポート状況をレポート
         crate::security::report_sandbox_support();
-        Some(build_sandbox_config(&loaded_config.global_security))
+        let mut sandbox_config = build_sandbox_config(&loaded_config.global_security);
+        if needs_net_admin {
+            sandbox_config.retain_capability(crate::security::Capability::CAP_NET_ADMIN);
+        }
+        Some(sandbox_config)
     } else {
         None
     };
@@ -548,7 +577,7 @@ pub fn run() {
     // 注意: 特権ポート（1024未満）を使用する場合は、
     // CAP_NET_BIND_SERVICEケイパビリティを付与するか、
     // 権限降格を無効にする必要があります。
-    if let Err(e) = drop_privileges(&loaded_config.global_security) {
+    if let Err(e) = drop_privileges(&loaded_config.global_security, needs_net_admin) {
         error!("Failed to drop privileges: {}", e);
         return;
     }
@@ -804,6 +833,7 @@ pub fn run() {
             let addr = listen_addr;
             let balancing = reuseport_balancing;
             let workers = num_threads;
+            let listener_socket = loaded_config.listener_socket.clone();
             let max_conn = max_connections;
             #[cfg(target_os = "freebsd")]
             let listeners_ready = std::sync::Arc::clone(&listeners_ready);
@@ -834,7 +864,13 @@ pub fn run() {
                 }
 
                 crate::runtime::block_on(async move {
-                    let listener = match create_listener(addr, balancing, workers, thread_id) {
+                    let listener = match create_listener(
+                        addr,
+                        balancing,
+                        workers,
+                        thread_id,
+                        listener_socket.as_deref(),
+                    ) {
                         Ok(l) => l,
                         Err(e) => {
                             error!("[Thread {}] Bind error: {}", thread_id, e);
@@ -853,6 +889,9 @@ pub fn run() {
 
                     info!("[Thread {}] Worker started", thread_id);
 
+                    // F-136: upstream ごとのコネクションプール事前確立（このスレッドのプールへ）
+                    crate::system::spawn_with_panic_catch(prewarm_upstream_pools());
+
                     // F-46: 接続ハンドラの型付きタスクプール（spawn ごとの Box 確保を排除）
                     let conn_pool = crate::runtime::TaskPool::new();
 
@@ -896,6 +935,23 @@ pub fn run() {
                             }
                         }
 
+                        // F-153: 接続元 IP・プレフィックスごとの同時接続数・新規接続レート
+                        let client_permit = match crate::client_limits::acquire(
+                            peer_addr.ip(),
+                            crate::client_limits::ConnKind::Tcp,
+                        ) {
+                            Ok(permit) => permit,
+                            Err(rejected) => {
+                                debug!(
+                                    "[Thread {}] Client limit {} reached, rejecting connection from {}",
+                                    thread_id,
+                                    rejected.label(),
+                                    peer_addr
+                                );
+                                continue;
+                            }
+                        };
+
                         let _ = stream.set_nodelay(true);
 
                         let acceptor = acceptor_clone.clone();
@@ -905,6 +961,7 @@ pub fn run() {
                             // ConnectionGuard がスコープ内で生存している間、接続がカウントされる
                             // パニック時も Drop が呼ばれるため、カウンターの整合性が保証される
                             let _guard = ConnectionGuard::new();
+                            let _client_permit = client_permit;
                             // handle_connection 内で CURRENT_CONFIG から最新の設定を取得
                             // これによりホットリロード時に新しい設定が即座に反映される
                             handle_connection(stream, acceptor, peer_addr).await;
@@ -1091,10 +1148,11 @@ pub fn run() {
         info!("All HTTP requests will be redirected to HTTPS (301)");
         info!("============================================");
 
+        let listener_socket = loaded_config.listener_socket.clone();
         let http_handle = spawn_worker_thread(move || {
             crate::runtime::block_on(async move {
                 // HTTPリスナーを作成（SO_REUSEADDRを有効化）
-                let listener = match TcpListener::bind(http_addr) {
+                let listener = match TcpListener::bind_with(http_addr, listener_socket.as_deref()) {
                     Ok(l) => l,
                     Err(e) => {
                         error!("[HTTP] Bind error on {}: {}", http_addr, e);
@@ -1304,6 +1362,7 @@ pub fn run() {
         let core_ids = core_ids.clone();
         for thread_id in 0..num_threads {
             let balancing = loaded_config.reuseport_balancing;
+            let listener_socket = loaded_config.listener_socket.clone();
             let max_conn = loaded_config.global_security.max_concurrent_connections;
 
             // このスレッドに割り当てるコアIDを決定
@@ -1329,17 +1388,25 @@ pub fn run() {
                 }
 
                 crate::runtime::block_on(async move {
-                    let listener =
-                        match create_listener(h2c_addr, balancing, num_threads, thread_id) {
-                            Ok(l) => l,
-                            Err(e) => {
-                                error!("[H2C Worker {}] Bind error: {}", thread_id, e);
-                                return;
-                            }
-                        };
+                    let listener = match create_listener(
+                        h2c_addr,
+                        balancing,
+                        num_threads,
+                        thread_id,
+                        listener_socket.as_deref(),
+                    ) {
+                        Ok(l) => l,
+                        Err(e) => {
+                            error!("[H2C Worker {}] Bind error: {}", thread_id, e);
+                            return;
+                        }
+                    };
 
                     info!("[H2C Worker {}] Started", thread_id);
 
+                    // F-136: upstream ごとのコネクションプール事前確立（このスレッドのプールへ）
+                    crate::system::spawn_with_panic_catch(prewarm_upstream_pools());
+
                     // F-46: H2C 接続ハンドラの型付きタスクプール
                     let conn_pool = crate::runtime::TaskPool::new();
 
@@ -1382,11 +1449,29 @@ pub fn run() {
                             }
                         }
 
+                        // F-153: 接続元 IP・プレフィックスごとの同時接続数・新規接続レート
+                        let client_permit = match crate::client_limits::acquire(
+                            peer_addr.ip(),
+                            crate::client_limits::ConnKind::Tcp,
+                        ) {
+                            Ok(permit) => permit,
+                            Err(rejected) => {
+                                debug!(
+                                    "[H2C Worker {}] Client limit {} reached, rejecting connection from {}",
+                                    thread_id,
+                                    rejected.label(),
+                                    peer_addr
+                                );
+                                continue;
+                            }
+                        };
+
                         let _ = stream.set_nodelay(true);
 
                         // H2C接続処理をspawn（パニック耐性あり・型付きプール）
                         spawn_pooled_with_panic_catch(&conn_pool, async move {
                             let _guard = ConnectionGuard::new();
+                            let _client_permit = client_permit;
                             // H2C専用リスナーでも、プロトコル検出を実行して初期データを取得
                             // これにより、クライアントがまだプリフェースを送信していない場合でも
                             // 正しく処理できる
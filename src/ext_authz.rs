@@ -0,0 +1,882 @@
+//! 外部認可サービスへのサブリクエスト（F-149）
+//!
+//! ルートに `[route.ext_authz]` があれば、上流へ転送する前に認可サービスへ問い合わせる。
+//!
+//! - `protocol = "http"`: 元のメソッドとパス（`url` のパスを前置）で HTTP/1.1 のサブリクエストを
+//!   送る。2xx なら許可し、`upstream_headers` に挙げた応答ヘッダーを上流へのリクエストへ移す
+//!   （`x-envoy-auth-headers-to-remove` に挙げたヘッダーは削除）。それ以外の応答はステータス・
+//!   ヘッダー・ボディをそのままクライアントへ返す（[`crate::subrequest`]）。
+//! - `protocol = "grpc"`: Envoy の `envoy.service.auth.v3.Authorization/Check` を h2c で呼び出す
+//!   （http2 + grpc feature）。`OK` なら `ok_response` のヘッダー操作を適用し、それ以外は
+//!   `denied_response`（無ければ 403）を返す。
+//! - `cache_key`（F-146 のキー構成要素）と `cache_ttl_secs` があれば、サービスの判定を
+//!   プロセス内に記憶し、同じキーのリクエストは問い合わせずに判定する。
+//! - サービスに到達できない・応答が不正・タイムアウトの場合は `failure_mode` に従い
+//!   許可（`open`）または `status_on_error` で拒否（`closed`）する。障害時の判定は記憶しない。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ftlog::warn;
+use once_cell::sync::Lazy;
+
+use crate::config::{
+    monotonic_ms, ExtAuthzConfig, ExtAuthzFailureMode, ExtAuthzProtocol, ProxyTarget,
+};
+use crate::http_utils::{
+    is_hop_by_hop_header, is_valid_header_name, is_valid_header_value, status_reason_phrase,
+};
+use crate::rate_limit::{KeyPart, RateLimitRequest};
+use crate::runtime::time::timeout;
+use crate::subrequest::{Endpoint, HttpResponse};
+
+/// 許可時に上流へのリクエストから削除するヘッダーを挙げる応答ヘッダー（Envoy 互換）
+const HEADERS_TO_REMOVE: &[u8] = b"x-envoy-auth-headers-to-remove";
+
+/// 記憶した判定がこの件数を超えたら期限切れを掃除する
+const DECISION_CACHE_SWEEP_LEN: usize = 4096;
+
+/// 記憶する判定の最大件数（掃除しても減らなければ新しい判定は記憶しない）
+const DECISION_CACHE_MAX_LEN: usize = 65536;
+
+/// キャッシュキー（ルート名と構成要素のバイト列そのもの）→ (期限 `monotonic_ms`, 判定)
+///
+/// クライアントが値を選べるキーなので、ハッシュの衝突で他人の判定を引かないよう
+/// 全バイトで比較する。
+static DECISION_CACHE: Lazy<Mutex<HashMap<Vec<u8>, (u64, Arc<AuthzDecision>)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 問い合わせ先
+#[derive(Debug)]
+enum Service {
+    Http {
+        endpoint: Box<Endpoint>,
+        /// `url` のパス（末尾の `/` を除く）
+        path_prefix: String,
+    },
+    Grpc {
+        #[cfg_attr(not(all(feature = "http2", feature = "grpc")), allow(dead_code))]
+        address: String,
+    },
+}
+
+/// ルートの外部認可（`Route::prepare` で構築し `SecurityConfig::ext_authz` で共有する）
+#[derive(Debug)]
+pub struct ExtAuthz {
+    route: String,
+    service: Service,
+    timeout: Duration,
+    failure_mode: ExtAuthzFailureMode,
+    status_on_error: u16,
+    /// 小文字
+    request_headers: Vec<String>,
+    max_body_bytes: usize,
+    allow_partial_body: bool,
+    /// 小文字
+    upstream_headers: Vec<String>,
+    cache_key: Vec<KeyPart>,
+    cache_ttl_ms: u64,
+}
+
+/// 認可に使うリクエストの属性
+pub(crate) struct AuthzRequest<'a> {
+    pub method: &'a [u8],
+    /// クエリ文字列を含むパス
+    pub path: &'a [u8],
+    pub host: &'a [u8],
+    pub client_ip: &'a str,
+    /// 疑似ヘッダーを除くリクエストヘッダー
+    pub headers: &'a [(&'a [u8], &'a [u8])],
+    /// 認可サービスへ渡すボディ（[`ExtAuthz::authz_body`] で切り詰め済み）
+    pub body: &'a [u8],
+    /// JWT 認証（F-148）で検証済みのクレーム（キャッシュキーに使う）
+    pub claims: Option<&'a serde_json::Value>,
+}
+
+/// 認可の判定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum AuthzDecision {
+    /// 許可（上流へのリクエストに設定するヘッダーと削除するヘッダー）
+    Allow {
+        set: Vec<(String, String)>,
+        remove: Vec<String>,
+    },
+    /// 拒否（クライアントへ返す応答）
+    Deny(AuthzDenied),
+}
+
+/// 拒否時にクライアントへ返す応答
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AuthzDenied {
+    pub status: u16,
+    /// hop-by-hop・`Content-Length`・`Transfer-Encoding` を除いた応答ヘッダー
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl AuthzDenied {
+    fn new(status: u16, headers: Vec<(String, String)>, body: Vec<u8>) -> Self {
+        let headers = headers
+            .into_iter()
+            .filter(|(name, value)| {
+                let name = name.as_bytes();
+                is_valid_header_name(name)
+                    && is_valid_header_value(value.as_bytes())
+                    && !is_hop_by_hop_header(name)
+                    && !name.eq_ignore_ascii_case(b"content-length")
+                    && !name.eq_ignore_ascii_case(b"transfer-encoding")
+            })
+            .collect();
+        Self {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    /// HTTP/2・HTTP/3 の応答ヘッダー（小文字）
+    #[cfg_attr(not(any(feature = "http2", feature = "http3")), allow(dead_code))]
+    pub fn h2_headers(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_ascii_lowercase().into_bytes(),
+                    value.as_bytes().to_vec(),
+                )
+            })
+            .collect()
+    }
+
+    /// HTTP/1.1 の応答
+    pub fn http1_response(&self) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 {} {}\r\n",
+            self.status,
+            status_reason_phrase(self.status)
+        )
+        .into_bytes();
+        for (name, value) in &self.headers {
+            response.extend_from_slice(name.as_bytes());
+            response.extend_from_slice(b": ");
+            response.extend_from_slice(value.as_bytes());
+            response.extend_from_slice(b"\r\n");
+        }
+        response.extend_from_slice(
+            format!(
+                "Content-Length: {}\r\nConnection: close\r\n\r\n",
+                self.body.len()
+            )
+            .as_bytes(),
+        );
+        response.extend_from_slice(&self.body);
+        response
+    }
+}
+
+impl ExtAuthz {
+    /// 設定から構築する（設定の妥当性は読み込み時に検証済みであること）
+    pub(crate) fn new(route: String, cfg: &ExtAuthzConfig) -> Self {
+        let service = match (
+            cfg.protocol,
+            cfg.url.as_deref().and_then(ProxyTarget::parse),
+        ) {
+            (ExtAuthzProtocol::Http, Some(target)) => {
+                let path_prefix = target.path_prefix.trim_end_matches('/').to_string();
+                Service::Http {
+                    endpoint: Box::new(Endpoint::new(target)),
+                    path_prefix,
+                }
+            }
+            _ => Service::Grpc {
+                address: cfg.address.clone().unwrap_or_default(),
+            },
+        };
+        let lowercase = |names: &[String]| -> Vec<String> {
+            names.iter().map(|n| n.to_ascii_lowercase()).collect()
+        };
+        Self {
+            route,
+            service,
+            timeout: Duration::from_millis(cfg.timeout_ms),
+            failure_mode: cfg.failure_mode,
+            status_on_error: cfg.status_on_error,
+            request_headers: lowercase(&cfg.request_headers),
+            max_body_bytes: cfg.max_body_bytes,
+            allow_partial_body: cfg.allow_partial_body,
+            upstream_headers: lowercase(&cfg.upstream_headers),
+            cache_key: cfg
+                .cache_key
+                .iter()
+                .filter_map(|part| KeyPart::parse(part))
+                .collect(),
+            cache_ttl_ms: cfg.cache_ttl_secs.saturating_mul(1000),
+        }
+    }
+
+    /// 認可サービスへボディを渡すか（渡すならボディを受信し終えてから問い合わせる）
+    #[inline]
+    pub(crate) fn sends_body(&self) -> bool {
+        self.max_body_bytes > 0
+    }
+
+    /// 認可サービスへ渡すボディの上限（バイト、0 = 渡さない）
+    #[inline]
+    pub(crate) fn max_body_bytes(&self) -> usize {
+        self.max_body_bytes
+    }
+
+    /// 認可サービスへ渡すボディ（上限超過で `allow_partial_body = false` なら None = 413）
+    pub(crate) fn authz_body<'b>(&self, body: &'b [u8]) -> Option<&'b [u8]> {
+        if body.len() <= self.max_body_bytes {
+            Some(body)
+        } else if self.allow_partial_body {
+            Some(&body[..self.max_body_bytes])
+        } else {
+            None
+        }
+    }
+
+    /// 認可サービスに問い合わせる
+    ///
+    /// 結果は `veil_ext_authz_total` に記録する。
+    pub(crate) async fn check(&self, req: &AuthzRequest<'_>) -> Arc<AuthzDecision> {
+        let cache_key = self.cache_key(req);
+        if let Some(key) = &cache_key {
+            if let Some(decision) = cached_decision(key, monotonic_ms()) {
+                crate::metrics::record_ext_authz("cached");
+                return decision;
+            }
+        }
+        let result = match timeout(self.timeout, self.call(req)).await {
+            Ok(result) => result,
+            Err(_) => Err("timed out".to_string()),
+        };
+        match result {
+            Ok(decision) => {
+                crate::metrics::record_ext_authz(match decision {
+                    AuthzDecision::Allow { .. } => "ok",
+                    AuthzDecision::Deny(_) => "denied",
+                });
+                let decision = Arc::new(decision);
+                if let Some(key) = cache_key {
+                    cache_decision(key, monotonic_ms() + self.cache_ttl_ms, &decision);
+                }
+                decision
+            }
+            Err(e) => {
+                crate::metrics::record_ext_authz("error");
+                warn!(
+                    "[ExtAuthz] {} authorization service unavailable ({}), failing {}",
+                    self.route,
+                    e,
+                    match self.failure_mode {
+                        ExtAuthzFailureMode::Open => "open",
+                        ExtAuthzFailureMode::Closed => "closed",
+                    }
+                );
+                Arc::new(match self.failure_mode {
+                    ExtAuthzFailureMode::Open => AuthzDecision::Allow {
+                        set: Vec::new(),
+                        remove: Vec::new(),
+                    },
+                    ExtAuthzFailureMode::Closed => AuthzDecision::Deny(AuthzDenied::new(
+                        self.status_on_error,
+                        vec![("Content-Type".to_string(), "text/plain".to_string())],
+                        status_reason_phrase(self.status_on_error)
+                            .as_bytes()
+                            .to_vec(),
+                    )),
+                })
+            }
+        }
+    }
+
+    /// 判定のキャッシュキー（キャッシュしない・構成要素が欠けていれば None）
+    ///
+    /// ルート名と各構成要素を長さ付きで連結し、区切りの曖昧さが生じないようにする。
+    fn cache_key(&self, req: &AuthzRequest<'_>) -> Option<Vec<u8>> {
+        if self.cache_ttl_ms == 0 || self.cache_key.is_empty() {
+            return None;
+        }
+        let attrs = RateLimitRequest {
+            client_ip: req.client_ip,
+            path: req.path,
+            headers: req.headers,
+            claims: req.claims,
+        };
+        let mut key = Vec::with_capacity(64);
+        push_key_part(&mut key, self.route.as_bytes());
+        for part in &self.cache_key {
+            push_key_part(&mut key, &part.resolve(&self.route, &attrs)?);
+        }
+        Some(key)
+    }
+
+    async fn call(&self, req: &AuthzRequest<'_>) -> Result<AuthzDecision, String> {
+        match &self.service {
+            Service::Http {
+                endpoint,
+                path_prefix,
+            } => {
+                let request = self.http_request(endpoint, path_prefix, req)?;
+                let response = endpoint.send(request, req.method == b"HEAD").await?;
+                Ok(self.http_decision(response))
+            }
+            #[cfg(all(feature = "http2", feature = "grpc"))]
+            Service::Grpc { address } => {
+                let message = self.encode_check_request(req);
+                let response =
+                    crate::grpc::client::unary_call(address, CHECK_PATH, &message, self.timeout)
+                        .await?;
+                decode_check_response(&response).ok_or_else(|| "malformed response".to_string())
+            }
+            #[cfg(not(all(feature = "http2", feature = "grpc")))]
+            Service::Grpc { .. } => Err("gRPC support is not compiled in".to_string()),
+        }
+    }
+
+    // ====================
+    // HTTP
+    // ====================
+
+    /// サブリクエスト（元のメソッド・`url` のパス + 元のパス）
+    fn http_request(
+        &self,
+        endpoint: &Endpoint,
+        path_prefix: &str,
+        req: &AuthzRequest<'_>,
+    ) -> Result<Vec<u8>, String> {
+        if req.path.iter().any(|&b| b <= b' ' || b == 0x7f) || !req.path.starts_with(b"/") {
+            return Err("request path cannot be forwarded".to_string());
+        }
+        let mut request = Vec::with_capacity(256 + req.body.len());
+        request.extend_from_slice(req.method);
+        request.push(b' ');
+        request.extend_from_slice(path_prefix.as_bytes());
+        request.extend_from_slice(req.path);
+        request.extend_from_slice(b" HTTP/1.1\r\nHost: ");
+        request.extend_from_slice(endpoint.authority().as_bytes());
+        request.extend_from_slice(b"\r\n");
+        if is_valid_header_value(req.host) && !req.host.is_empty() {
+            request.extend_from_slice(b"X-Forwarded-Host: ");
+            request.extend_from_slice(req.host);
+            request.extend_from_slice(b"\r\n");
+        }
+        request.extend_from_slice(b"X-Forwarded-For: ");
+        request.extend_from_slice(req.client_ip.as_bytes());
+        request.extend_from_slice(b"\r\n");
+        for (name, value) in req.headers {
+            let listed = self
+                .request_headers
+                .iter()
+                .any(|h| name.eq_ignore_ascii_case(h.as_bytes()));
+            if listed && is_valid_header_name(name) && is_valid_header_value(value) {
+                request.extend_from_slice(name);
+                request.extend_from_slice(b": ");
+                request.extend_from_slice(value);
+                request.extend_from_slice(b"\r\n");
+            }
+        }
+        request.extend_from_slice(format!("Content-Length: {}\r\n\r\n", req.body.len()).as_bytes());
+        request.extend_from_slice(req.body);
+        Ok(request)
+    }
+
+    /// 2xx は許可、それ以外は応答をそのまま返す拒否
+    fn http_decision(&self, response: HttpResponse) -> AuthzDecision {
+        if !(200..300).contains(&response.status) {
+            return AuthzDecision::Deny(AuthzDenied::new(
+                response.status,
+                response.headers,
+                response.body,
+            ));
+        }
+        let mut set = Vec::new();
+        let mut remove = Vec::new();
+        for (name, value) in &response.headers {
+            if name.as_bytes().eq_ignore_ascii_case(HEADERS_TO_REMOVE) {
+                remove.extend(
+                    value
+                        .split(',')
+                        .map(|h| h.trim().to_ascii_lowercase())
+                        .filter(|h| !h.is_empty()),
+                );
+            } else if self
+                .upstream_headers
+                .iter()
+                .any(|h| name.eq_ignore_ascii_case(h))
+                && !set.iter().any(|(n, _): &(String, String)| n == name)
+            {
+                set.push((name.clone(), value.clone()));
+            }
+        }
+        allow(set, remove)
+    }
+
+    // ====================
+    // gRPC（envoy.service.auth.v3）
+    // ====================
+
+    /// `CheckRequest{attributes = 1}`
+    /// （`AttributeContext{source = 1, request = 4}`、`Peer{address = 1}`、
+    /// `Address{socket_address = 1}`、`SocketAddress{address = 2}`、`Request{http = 2}`、
+    /// `HttpRequest{method = 2, headers = 3, path = 4, host = 5, size = 9, protocol = 10,
+    /// raw_body = 12}`）
+    #[cfg(all(feature = "http2", feature = "grpc"))]
+    fn encode_check_request(&self, req: &AuthzRequest<'_>) -> Vec<u8> {
+        use crate::grpc::protobuf::{put_bytes, put_uint};
+
+        let mut socket_address = Vec::new();
+        put_bytes(&mut socket_address, 2, req.client_ip.as_bytes());
+        let mut address = Vec::new();
+        put_bytes(&mut address, 1, &socket_address);
+        let mut peer = Vec::new();
+        put_bytes(&mut peer, 1, &address);
+
+        let mut http = Vec::with_capacity(128 + req.body.len());
+        put_bytes(&mut http, 2, req.method);
+        let mut entry = Vec::new();
+        for (name, value) in req.headers {
+            let name = String::from_utf8_lossy(name).to_ascii_lowercase();
+            if self.request_headers.contains(&name) {
+                entry.clear();
+                put_bytes(&mut entry, 1, name.as_bytes());
+                put_bytes(&mut entry, 2, value);
+                put_bytes(&mut http, 3, &entry);
+            }
+        }
+        put_bytes(&mut http, 4, req.path);
+        put_bytes(&mut http, 5, req.host);
+        put_uint(&mut http, 9, req.body.len() as u64);
+        put_bytes(&mut http, 10, b"HTTP/1.1");
+        if !req.body.is_empty() {
+            put_bytes(&mut http, 12, req.body);
+        }
+        let mut request = Vec::new();
+        put_bytes(&mut request, 2, &http);
+
+        let mut attributes = Vec::new();
+        put_bytes(&mut attributes, 1, &peer);
+        put_bytes(&mut attributes, 4, &request);
+        let mut message = Vec::new();
+        put_bytes(&mut message, 1, &attributes);
+        message
+    }
+}
+
+fn allow(set: Vec<(String, String)>, remove: Vec<String>) -> AuthzDecision {
+    let set = set
+        .into_iter()
+        .filter(|(name, value)| {
+            is_valid_header_name(name.as_bytes()) && is_valid_header_value(value.as_bytes())
+        })
+        .collect();
+    AuthzDecision::Allow { set, remove }
+}
+
+/// キーの構成要素を長さ付きで追加する
+fn push_key_part(key: &mut Vec<u8>, part: &[u8]) {
+    key.extend_from_slice(&(part.len() as u32).to_be_bytes());
+    key.extend_from_slice(part);
+}
+
+/// 記憶した判定（期限内のもの）
+fn cached_decision(key: &[u8], now_ms: u64) -> Option<Arc<AuthzDecision>> {
+    let cache = DECISION_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let (until_ms, decision) = cache.get(key)?;
+    (*until_ms > now_ms).then(|| decision.clone())
+}
+
+fn cache_decision(key: Vec<u8>, until_ms: u64, decision: &Arc<AuthzDecision>) {
+    let now_ms = monotonic_ms();
+    let mut cache = DECISION_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if cache.len() >= DECISION_CACHE_SWEEP_LEN {
+        cache.retain(|_, (until, _)| *until > now_ms);
+    }
+    if cache.len() < DECISION_CACHE_MAX_LEN {
+        cache.insert(key, (until_ms, decision.clone()));
+    }
+}
+
+// ====================
+// gRPC の応答（必要なフィールドのみ）
+// ====================
+
+/// `Check` のメソッドパス
+#[cfg(all(feature = "http2", feature = "grpc"))]
+const CHECK_PATH: &[u8] = b"/envoy.service.auth.v3.Authorization/Check";
+
+/// `CheckResponse{status = 1, denied_response = 2, ok_response = 3}`
+/// （`google.rpc.Status{code = 1}`、`DeniedHttpResponse{status = 1, headers = 2, body = 3}`、
+/// `HttpStatus{code = 1}`、`OkHttpResponse{headers = 2, headers_to_remove = 5}`）
+#[cfg(all(feature = "http2", feature = "grpc"))]
+fn decode_check_response(buf: &[u8]) -> Option<AuthzDecision> {
+    use crate::grpc::protobuf::{Field, Fields};
+
+    let mut code = 0;
+    let mut denied: Option<&[u8]> = None;
+    let mut ok: Option<&[u8]> = None;
+    let mut fields = Fields::new(buf);
+    for (number, value) in fields.by_ref() {
+        match (number, value) {
+            (1, Field::Bytes(status)) => {
+                let mut status_fields = Fields::new(status);
+                for (number, field) in status_fields.by_ref() {
+                    if let (1, Field::Varint(c)) = (number, field) {
+                        code = c;
+                    }
+                }
+                if status_fields.malformed {
+                    return None;
+                }
+            }
+            (2, Field::Bytes(b)) => denied = Some(b),
+            (3, Field::Bytes(b)) => ok = Some(b),
+            _ => {}
+        }
+    }
+    if fields.malformed {
+        return None;
+    }
+
+    if code == 0 {
+        let (mut set, mut remove) = (Vec::new(), Vec::new());
+        let mut fields = Fields::new(ok.unwrap_or_default());
+        for (number, value) in fields.by_ref() {
+            match (number, value) {
+                (2, Field::Bytes(option)) => set.extend(decode_header_value_option(option)?),
+                (5, Field::Bytes(name)) => {
+                    remove.push(String::from_utf8_lossy(name).to_ascii_lowercase())
+                }
+                _ => {}
+            }
+        }
+        return (!fields.malformed).then(|| allow(set, remove));
+    }
+
+    let (mut status, mut headers, mut body) = (403, Vec::new(), Vec::new());
+    let mut fields = Fields::new(denied.unwrap_or_default());
+    for (number, value) in fields.by_ref() {
+        match (number, value) {
+            (1, Field::Bytes(http_status)) => {
+                let mut status_fields = Fields::new(http_status);
+                for (number, field) in status_fields.by_ref() {
+                    if let (1, Field::Varint(c)) = (number, field) {
+                        if (200..=599).contains(&c) {
+                            status = c as u16;
+                        }
+                    }
+                }
+                if status_fields.malformed {
+                    return None;
+                }
+            }
+            (2, Field::Bytes(option)) => headers.extend(decode_header_value_option(option)?),
+            (3, Field::Bytes(b)) => body = b.to_vec(),
+            _ => {}
+        }
+    }
+    (!fields.malformed).then(|| AuthzDecision::Deny(AuthzDenied::new(status, headers, body)))
+}
+
+/// `HeaderValueOption{header = 1}`（`HeaderValue{key = 1, value = 2}`）
+#[cfg(all(feature = "http2", feature = "grpc"))]
+fn decode_header_value_option(buf: &[u8]) -> Option<Option<(String, String)>> {
+    use crate::grpc::protobuf::{Field, Fields};
+
+    let mut header = None;
+    let mut fields = Fields::new(buf);
+    for (number, value) in fields.by_ref() {
+        if let (1, Field::Bytes(b)) = (number, value) {
+            header = Some(b);
+        }
+    }
+    if fields.malformed {
+        return None;
+    }
+    let Some(header) = header else {
+        return Some(None);
+    };
+    let (mut key, mut value) = (None, None);
+    let mut fields = Fields::new(header);
+    for (number, field) in fields.by_ref() {
+        match (number, field) {
+            (1, Field::Bytes(b)) => key = Some(String::from_utf8_lossy(b).into_owned()),
+            (2, Field::Bytes(b)) => value = Some(String::from_utf8_lossy(b).into_owned()),
+            _ => {}
+        }
+    }
+    if fields.malformed {
+        return None;
+    }
+    Some(
+        key.filter(|k| !k.is_empty())
+            .map(|k| (k, value.unwrap_or_default())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authz(toml_src: &str) -> ExtAuthz {
+        let cfg: ExtAuthzConfig = toml::from_str(toml_src).unwrap();
+        ExtAuthz::new("route[0]".to_string(), &cfg)
+    }
+
+    fn request<'a>(headers: &'a [(&'a [u8], &'a [u8])], body: &'a [u8]) -> AuthzRequest<'a> {
+        AuthzRequest {
+            method: b"POST",
+            path: b"/api/items?id=1",
+            host: b"app.example.com",
+            client_ip: "10.0.0.1",
+            headers,
+            body,
+            claims: None,
+        }
+    }
+
+    #[test]
+    fn http_subrequest_prefixes_path_and_forwards_listed_headers() {
+        let ext = authz(r#"url = "http://authz.internal:9000/check/""#);
+        let Service::Http {
+            endpoint,
+            path_prefix,
+        } = &ext.service
+        else {
+            panic!("expected HTTP service");
+        };
+        let headers: [(&[u8], &[u8]); 3] = [
+            (b"Authorization", b"Bearer t"),
+            (b"X-Secret", b"s"),
+            (b"cookie", b"sid=1"),
+        ];
+        let sent = ext
+            .http_request(endpoint, path_prefix, &request(&headers, b"{}"))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(sent).unwrap(),
+            "POST /check/api/items?id=1 HTTP/1.1\r\nHost: authz.internal:9000\r\n\
+             X-Forwarded-Host: app.example.com\r\nX-Forwarded-For: 10.0.0.1\r\n\
+             Authorization: Bearer t\r\ncookie: sid=1\r\nContent-Length: 2\r\n\r\n{}"
+        );
+        let mut bad = request(&headers, b"");
+        bad.path = b"/a b";
+        assert!(ext.http_request(endpoint, path_prefix, &bad).is_err());
+    }
+
+    #[test]
+    fn http_response_maps_to_decision() {
+        let ext = authz(
+            r#"
+            url = "http://authz.internal"
+            upstream_headers = ["X-Auth-User"]
+            "#,
+        );
+        let allowed = ext.http_decision(HttpResponse {
+            status: 200,
+            headers: vec![
+                ("x-auth-user".to_string(), "alice".to_string()),
+                ("X-Other".to_string(), "ignored".to_string()),
+                (
+                    "X-Envoy-Auth-Headers-To-Remove".to_string(),
+                    "Authorization, X-Debug".to_string(),
+                ),
+            ],
+            body: Vec::new(),
+        });
+        assert_eq!(
+            allowed,
+            AuthzDecision::Allow {
+                set: vec![("x-auth-user".to_string(), "alice".to_string())],
+                remove: vec!["authorization".to_string(), "x-debug".to_string()],
+            }
+        );
+
+        let denied = ext.http_decision(HttpResponse {
+            status: 302,
+            headers: vec![
+                ("Location".to_string(), "/login".to_string()),
+                ("Connection".to_string(), "keep-alive".to_string()),
+                ("Content-Length".to_string(), "5".to_string()),
+            ],
+            body: b"login".to_vec(),
+        });
+        let AuthzDecision::Deny(denied) = denied else {
+            panic!("expected denial");
+        };
+        assert_eq!(
+            denied.headers,
+            vec![("Location".to_string(), "/login".to_string())]
+        );
+        assert_eq!(
+            denied.http1_response(),
+            b"HTTP/1.1 302 Found\r\nLocation: /login\r\nContent-Length: 5\r\nConnection: close\r\n\r\nlogin"
+        );
+        assert_eq!(
+            denied.h2_headers(),
+            vec![(b"location".to_vec(), b"/login".to_vec())]
+        );
+    }
+
+    #[test]
+    fn body_limit_truncates_or_rejects() {
+        let strict = authz(
+            r#"
+            url = "http://authz.internal"
+            max_body_bytes = 4
+            "#,
+        );
+        assert!(strict.sends_body());
+        assert_eq!(strict.authz_body(b"abcd"), Some(&b"abcd"[..]));
+        assert_eq!(strict.authz_body(b"abcde"), None);
+
+        let partial = authz(
+            r#"
+            url = "http://authz.internal"
+            max_body_bytes = 4
+            allow_partial_body = true
+            "#,
+        );
+        assert_eq!(partial.authz_body(b"abcdef"), Some(&b"abcd"[..]));
+        assert!(!authz(r#"url = "http://authz.internal""#).sends_body());
+    }
+
+    #[test]
+    fn cache_key_requires_every_part() {
+        let ext = authz(
+            r#"
+            url = "http://authz.internal"
+            cache_key = ["header:Authorization", "path"]
+            cache_ttl_secs = 30
+            "#,
+        );
+        let with_token: [(&[u8], &[u8]); 1] = [(b"authorization", b"Bearer t")];
+        let key = ext.cache_key(&request(&with_token, b"")).unwrap();
+        // クエリ文字列はキーに含めない
+        let mut other_query = request(&with_token, b"");
+        other_query.path = b"/api/items?id=2";
+        assert_eq!(ext.cache_key(&other_query), Some(key.clone()));
+        // 構成要素の境界をずらしても同じキーにならない
+        let shifted: [(&[u8], &[u8]); 1] = [(b"authorization", b"Bearer t\0/api")];
+        assert_ne!(ext.cache_key(&request(&shifted, b"")), Some(key.clone()));
+        assert!(ext.cache_key(&request(&[], b"")).is_none());
+        assert!(authz(r#"url = "http://authz.internal""#)
+            .cache_key(&request(&with_token, b""))
+            .is_none());
+
+        let decision = Arc::new(allow(Vec::new(), Vec::new()));
+        let now = monotonic_ms();
+        cache_decision(key.clone(), now + 1000, &decision);
+        assert_eq!(cached_decision(&key, now), Some(decision));
+        assert!(cached_decision(&key, now + 1000).is_none());
+        let mut other = key.clone();
+        *other.last_mut().unwrap() ^= 1;
+        assert!(cached_decision(&other, now).is_none());
+    }
+
+    #[cfg(all(feature = "http2", feature = "grpc"))]
+    #[test]
+    fn check_request_carries_listed_headers_and_body() {
+        let ext = authz(
+            r#"
+            protocol = "grpc"
+            address = "127.0.0.1:9001"
+            "#,
+        );
+        let headers: [(&[u8], &[u8]); 2] = [(b"Authorization", b"Bearer t"), (b"X-Secret", b"s")];
+        let message = ext.encode_check_request(&request(&headers, b"{}"));
+
+        use crate::grpc::protobuf::{Field, Fields};
+        // CheckRequest.attributes.request.http
+        let nested = |buf: &[u8], field: u64| -> Vec<u8> {
+            Fields::new(buf)
+                .find_map(|(n, f)| match (n, f) {
+                    (n, Field::Bytes(b)) if n == field => Some(b.to_vec()),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        let attributes = nested(&message, 1);
+        let source = nested(&nested(&nested(&attributes, 1), 1), 1);
+        assert_eq!(nested(&source, 2), b"10.0.0.1");
+        let http = nested(&nested(&attributes, 4), 2);
+        assert_eq!(nested(&http, 2), b"POST");
+        assert_eq!(nested(&http, 4), b"/api/items?id=1");
+        assert_eq!(nested(&http, 5), b"app.example.com");
+        assert_eq!(nested(&http, 12), b"{}");
+        let header_entries: Vec<Vec<u8>> = Fields::new(&http)
+            .filter_map(|(n, f)| match (n, f) {
+                (3, Field::Bytes(b)) => Some(b.to_vec()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(header_entries.len(), 1);
+        assert_eq!(nested(&header_entries[0], 1), b"authorization");
+        assert_eq!(nested(&header_entries[0], 2), b"Bearer t");
+    }
+
+    #[cfg(all(feature = "http2", feature = "grpc"))]
+    #[test]
+    fn check_response_decodes_ok_and_denied() {
+        use crate::grpc::protobuf::{put_bytes, put_uint};
+
+        let header_option = |key: &str, value: &str| {
+            let mut header = Vec::new();
+            put_bytes(&mut header, 1, key.as_bytes());
+            put_bytes(&mut header, 2, value.as_bytes());
+            let mut option = Vec::new();
+            put_bytes(&mut option, 1, &header);
+            option
+        };
+
+        let mut ok_response = Vec::new();
+        put_bytes(&mut ok_response, 2, &header_option("x-auth-user", "alice"));
+        put_bytes(&mut ok_response, 5, b"Authorization");
+        let mut ok = Vec::new();
+        put_bytes(&mut ok, 1, &[]);
+        put_bytes(&mut ok, 3, &ok_response);
+        assert_eq!(
+            decode_check_response(&ok),
+            Some(AuthzDecision::Allow {
+                set: vec![("x-auth-user".to_string(), "alice".to_string())],
+                remove: vec!["authorization".to_string()],
+            })
+        );
+
+        let mut status = Vec::new();
+        put_uint(&mut status, 1, 7); // PERMISSION_DENIED
+        let mut http_status = Vec::new();
+        put_uint(&mut http_status, 1, 401);
+        let mut denied_response = Vec::new();
+        put_bytes(&mut denied_response, 1, &http_status);
+        put_bytes(
+            &mut denied_response,
+            2,
+            &header_option("www-authenticate", "Bearer"),
+        );
+        put_bytes(&mut denied_response, 3, b"no token");
+        let mut denied = Vec::new();
+        put_bytes(&mut denied, 1, &status);
+        put_bytes(&mut denied, 2, &denied_response);
+        assert_eq!(
+            decode_check_response(&denied),
+            Some(AuthzDecision::Deny(AuthzDenied {
+                status: 401,
+                headers: vec![("www-authenticate".to_string(), "Bearer".to_string())],
+                body: b"no token".to_vec(),
+            }))
+        );
+
+        // denied_response が無ければ 403
+        let mut bare = Vec::new();
+        put_bytes(&mut bare, 1, &status);
+        let Some(AuthzDecision::Deny(bare)) = decode_check_response(&bare) else {
+            panic!("expected denial");
+        };
+        assert_eq!(bare.status, 403);
+        assert!(decode_check_response(&[0x0a, 5, 0x08]).is_none());
+    }
+}
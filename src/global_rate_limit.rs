@@ -0,0 +1,463 @@
+//! 外部レートリミットサービスによるグローバルレートリミット（F-147）
+//!
+//! 複数インスタンスで共有する上限（テナント単位のクォータなど）のため、Envoy の Rate Limit
+//! Service（RLS）プロトコル `envoy.service.ratelimit.v3.RateLimitService/ShouldRateLimit` で
+//! 外部サービスへ問い合わせる。接続は上流 h2c 用のクライアントとプール（`H2C_POOL`）を、
+//! メッセージは gRPC フレーミングを再利用し、protobuf は必要なフィールドだけを手で読み書きする。
+//!
+//! - ディスクリプタはリクエスト属性（F-146 のキー構成要素）と固定値から組み立てる。
+//!   属性が欠けたディスクリプタは送らず、1 つも残らなければ問い合わせない。
+//! - `OVER_LIMIT` となった問い合わせは `duration_until_reset`（最大 60 秒）の間プロセス内に
+//!   記憶し、同じディスクリプタの組は RPC を送らずに拒否する。
+//! - サービスに到達できない・エラー応答・タイムアウトの場合はルートの `failure_mode` に従い
+//!   許可（`open`）または 503（`closed`）とする。
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ftlog::warn;
+use once_cell::sync::Lazy;
+
+use crate::config::{
+    monotonic_ms, GlobalRateLimitConfig, RateLimitFailureMode, RateLimitServiceConfig,
+};
+use crate::grpc::protobuf::{put_bytes, Field, Fields};
+use crate::rate_limit::{KeyPart, RateLimitRequest};
+use crate::runtime::time::timeout;
+
+/// `ShouldRateLimit` のメソッドパス
+const SHOULD_RATE_LIMIT_PATH: &[u8] =
+    b"/envoy.service.ratelimit.v3.RateLimitService/ShouldRateLimit";
+
+/// `OVER_LIMIT` を記憶する最長期間（ミリ秒）
+const MAX_OVER_LIMIT_CACHE_MS: u64 = 60_000;
+
+/// 記憶した `OVER_LIMIT` がこの件数を超えたら期限切れを掃除する
+const OVER_LIMIT_CACHE_SWEEP_LEN: usize = 4096;
+
+/// `RateLimitResponse.Code`
+const CODE_OVER_LIMIT: u64 = 2;
+
+/// 問い合わせのハッシュ → 拒否し続ける期限（`monotonic_ms`）
+static OVER_LIMIT_CACHE: Lazy<Mutex<HashMap<u64, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// ディスクリプタのエントリの値
+#[derive(Debug)]
+enum EntryValue {
+    Fixed(String),
+    From(KeyPart),
+}
+
+/// ルートのグローバルレートリミット（`RouteRateLimit` が保持する）
+#[derive(Debug)]
+pub struct GlobalRateLimit {
+    service: Arc<RateLimitServiceConfig>,
+    descriptors: Vec<Vec<(String, EntryValue)>>,
+    failure_mode: RateLimitFailureMode,
+}
+
+/// 問い合わせの結果
+pub(crate) enum GlobalVerdict {
+    /// 許可（サービスが返した応答ヘッダー）
+    Allowed(Vec<(String, String)>),
+    /// 超過
+    OverLimit {
+        retry_after_secs: u64,
+        headers: Vec<(String, String)>,
+    },
+    /// サービスに問い合わせできず `failure_mode = "closed"`
+    Unavailable,
+}
+
+impl GlobalRateLimit {
+    /// 設定から構築する（設定の妥当性は読み込み時に検証済みであること）
+    pub(crate) fn new(service: Arc<RateLimitServiceConfig>, cfg: &GlobalRateLimitConfig) -> Self {
+        let descriptors = cfg
+            .descriptors
+            .iter()
+            .map(|descriptor| {
+                descriptor
+                    .entries
+                    .iter()
+                    .filter_map(|entry| {
+                        let value = match (&entry.value, &entry.from) {
+                            (Some(value), _) => EntryValue::Fixed(value.clone()),
+                            (None, Some(from)) => EntryValue::From(KeyPart::parse(from)?),
+                            (None, None) => return None,
+                        };
+                        Some((entry.key.clone(), value))
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            service,
+            descriptors,
+            failure_mode: cfg.failure_mode,
+        }
+    }
+
+    /// リクエストのディスクリプタ（属性が欠けたディスクリプタは除く）
+    fn descriptors<'a>(
+        &'a self,
+        route: &'a str,
+        req: &RateLimitRequest<'a>,
+    ) -> Vec<Vec<(&'a str, Cow<'a, str>)>> {
+        self.descriptors
+            .iter()
+            .filter_map(|entries| {
+                entries
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = match value {
+                            EntryValue::Fixed(v) => Cow::Borrowed(v.as_str()),
+                            EntryValue::From(part) => match part.resolve(route, req)? {
+                                Cow::Borrowed(b) => String::from_utf8_lossy(b),
+                                Cow::Owned(b) => {
+                                    Cow::Owned(String::from_utf8_lossy(&b).into_owned())
+                                }
+                            },
+                        };
+                        Some((key.as_str(), value))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// サービスに問い合わせる（送るディスクリプタがなければ None）
+    ///
+    /// 結果は `veil_global_rate_limit_requests_total` に記録する。
+    pub(crate) async fn check(
+        &self,
+        route: &str,
+        req: &RateLimitRequest<'_>,
+    ) -> Option<GlobalVerdict> {
+        let descriptors = self.descriptors(route, req);
+        if descriptors.is_empty() {
+            return None;
+        }
+        let message = encode_request(&self.service.domain, &descriptors);
+        let key = xxhash_rust::xxh3::xxh3_64(&message);
+        if let Some(retry_after_secs) = cached_over_limit(key, monotonic_ms()) {
+            crate::metrics::record_global_rate_limit("cached");
+            return Some(GlobalVerdict::OverLimit {
+                retry_after_secs,
+                headers: Vec::new(),
+            });
+        }
+        let limit = Duration::from_millis(self.service.timeout_ms);
+        let result = match timeout(limit, self.should_rate_limit(&message, limit)).await {
+            Ok(result) => result,
+            Err(_) => Err("timed out".to_string()),
+        };
+        match result {
+            Ok(response) if response.over_limit => {
+                crate::metrics::record_global_rate_limit("over_limit");
+                if let Some(reset_ms) = response.reset_ms {
+                    cache_over_limit(key, monotonic_ms(), reset_ms);
+                }
+                Some(GlobalVerdict::OverLimit {
+                    retry_after_secs: response.retry_after_secs(),
+                    headers: response.headers,
+                })
+            }
+            Ok(response) => {
+                crate::metrics::record_global_rate_limit("ok");
+                Some(GlobalVerdict::Allowed(response.headers))
+            }
+            Err(e) => {
+                crate::metrics::record_global_rate_limit("error");
+                warn!(
+                    "[RateLimit] service {} unavailable ({}), failing {}",
+                    self.service.address,
+                    e,
+                    match self.failure_mode {
+                        RateLimitFailureMode::Open => "open",
+                        RateLimitFailureMode::Closed => "closed",
+                    }
+                );
+                Some(match self.failure_mode {
+                    RateLimitFailureMode::Open => GlobalVerdict::Allowed(Vec::new()),
+                    RateLimitFailureMode::Closed => GlobalVerdict::Unavailable,
+                })
+            }
+        }
+    }
+
+    /// `ShouldRateLimit` を 1 回呼び出す（接続はワーカーの h2c プールで使い回す）
+    async fn should_rate_limit(
+        &self,
+        message: &[u8],
+        limit: Duration,
+    ) -> Result<RlsResponse, String> {
+        let response = crate::grpc::client::unary_call(
+            &self.service.address,
+            SHOULD_RATE_LIMIT_PATH,
+            message,
+            limit,
+        )
+        .await?;
+        decode_response(&response).ok_or_else(|| "malformed response".to_string())
+    }
+}
+
+/// 記憶した `OVER_LIMIT` が有効なら `Retry-After`（秒）
+fn cached_over_limit(key: u64, now_ms: u64) -> Option<u64> {
+    let cache = OVER_LIMIT_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let until_ms = *cache.get(&key)?;
+    (until_ms > now_ms).then(|| (until_ms - now_ms).div_ceil(1000))
+}
+
+fn cache_over_limit(key: u64, now_ms: u64, reset_ms: u64) {
+    let mut cache = OVER_LIMIT_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if cache.len() >= OVER_LIMIT_CACHE_SWEEP_LEN {
+        cache.retain(|_, until_ms| *until_ms > now_ms);
+    }
+    cache.insert(key, now_ms + reset_ms.min(MAX_OVER_LIMIT_CACHE_MS));
+}
+
+// ====================
+// protobuf（必要なフィールドのみ）
+// ====================
+
+/// `RateLimitRequest{domain = 1, descriptors = 2}`
+/// （`RateLimitDescriptor{entries = 1}`、`Entry{key = 1, value = 2}`）
+fn encode_request(domain: &str, descriptors: &[Vec<(&str, Cow<'_, str>)>]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(64);
+    put_bytes(&mut message, 1, domain.as_bytes());
+    let mut descriptor = Vec::new();
+    let mut entry = Vec::new();
+    for entries in descriptors {
+        descriptor.clear();
+        for (key, value) in entries {
+            entry.clear();
+            put_bytes(&mut entry, 1, key.as_bytes());
+            put_bytes(&mut entry, 2, value.as_bytes());
+            put_bytes(&mut descriptor, 1, &entry);
+        }
+        put_bytes(&mut message, 2, &descriptor);
+    }
+    message
+}
+
+/// `ShouldRateLimit` の応答のうち使う部分
+#[derive(Debug, PartialEq, Eq)]
+struct RlsResponse {
+    over_limit: bool,
+    /// `OVER_LIMIT` のディスクリプタの `duration_until_reset` の最大値（ミリ秒）
+    reset_ms: Option<u64>,
+    /// `response_headers_to_add`
+    headers: Vec<(String, String)>,
+}
+
+impl RlsResponse {
+    fn retry_after_secs(&self) -> u64 {
+        self.reset_ms.map_or(1, |ms| ms.div_ceil(1000).max(1))
+    }
+}
+
+/// `RateLimitResponse{overall_code = 1, statuses = 2, response_headers_to_add = 3}`
+/// （`DescriptorStatus{code = 1, duration_until_reset = 4}`、`Duration{seconds = 1, nanos = 2}`、
+/// `HeaderValue{key = 1, value = 2}`）
+fn decode_response(buf: &[u8]) -> Option<RlsResponse> {
+    let mut response = RlsResponse {
+        over_limit: false,
+        reset_ms: None,
+        headers: Vec::new(),
+    };
+    let mut fields = Fields::new(buf);
+    for (number, value) in fields.by_ref() {
+        match (number, value) {
+            (1, Field::Varint(code)) => response.over_limit = code == CODE_OVER_LIMIT,
+            (2, Field::Bytes(status)) => {
+                if let Some(ms) = decode_status_reset_ms(status)? {
+                    response.reset_ms = Some(response.reset_ms.map_or(ms, |r| r.max(ms)));
+                }
+            }
+            (3, Field::Bytes(header)) => {
+                let (mut key, mut value) = (None, None);
+                let mut header_fields = Fields::new(header);
+                for (number, field) in header_fields.by_ref() {
+                    match (number, field) {
+                        (1, Field::Bytes(b)) => key = Some(String::from_utf8_lossy(b)),
+                        (2, Field::Bytes(b)) => value = Some(String::from_utf8_lossy(b)),
+                        _ => {}
+                    }
+                }
+                if header_fields.malformed {
+                    return None;
+                }
+                if let Some(key) = key.filter(|k| !k.is_empty()) {
+                    response
+                        .headers
+                        .push((key.into_owned(), value.unwrap_or_default().into_owned()));
+                }
+            }
+            _ => {}
+        }
+    }
+    (!fields.malformed).then_some(response)
+}
+
+/// `OVER_LIMIT` の `DescriptorStatus` の `duration_until_reset`（ミリ秒）
+fn decode_status_reset_ms(buf: &[u8]) -> Option<Option<u64>> {
+    let (mut over_limit, mut reset_ms) = (false, None);
+    let mut fields = Fields::new(buf);
+    for (number, value) in fields.by_ref() {
+        match (number, value) {
+            (1, Field::Varint(code)) => over_limit = code == CODE_OVER_LIMIT,
+            (4, Field::Bytes(duration)) => {
+                let (mut secs, mut nanos) = (0u64, 0u64);
+                let mut duration_fields = Fields::new(duration);
+                for (number, field) in duration_fields.by_ref() {
+                    match (number, field) {
+                        (1, Field::Varint(v)) => secs = v,
+                        (2, Field::Varint(v)) => nanos = v,
+                        _ => {}
+                    }
+                }
+                if duration_fields.malformed {
+                    return None;
+                }
+                reset_ms = Some(
+                    secs.saturating_mul(1000)
+                        .saturating_add(nanos.div_ceil(1_000_000)),
+                );
+            }
+            _ => {}
+        }
+    }
+    if fields.malformed {
+        return None;
+    }
+    Some(reset_ms.filter(|_| over_limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::protobuf::put_varint;
+
+    #[test]
+    fn request_encodes_domain_and_descriptor_entries() {
+        let descriptors = vec![
+            vec![
+                ("tenant", Cow::Borrowed("acme")),
+                ("generic_key", Cow::Borrowed("api")),
+            ],
+            vec![("remote_address", Cow::Borrowed("10.0.0.1"))],
+        ];
+        let message = encode_request("veil", &descriptors);
+        let mut expected = vec![0x0a, 4];
+        expected.extend_from_slice(b"veil");
+        expected.extend_from_slice(&[0x12, 36, 0x0a, 14, 0x0a, 6]);
+        expected.extend_from_slice(b"tenant");
+        expected.extend_from_slice(&[0x12, 4]);
+        expected.extend_from_slice(b"acme");
+        expected.extend_from_slice(&[0x0a, 18, 0x0a, 11]);
+        expected.extend_from_slice(b"generic_key");
+        expected.extend_from_slice(&[0x12, 3]);
+        expected.extend_from_slice(b"api");
+        expected.extend_from_slice(&[0x12, 28, 0x0a, 26, 0x0a, 14]);
+        expected.extend_from_slice(b"remote_address");
+        expected.extend_from_slice(&[0x12, 8]);
+        expected.extend_from_slice(b"10.0.0.1");
+        assert_eq!(message, expected);
+    }
+
+    #[test]
+    fn response_decodes_over_limit_reset_and_headers() {
+        let mut status_ok = Vec::new();
+        put_varint(&mut status_ok, 0x08);
+        put_varint(&mut status_ok, 1);
+        let mut duration = Vec::new();
+        put_varint(&mut duration, 0x08);
+        put_varint(&mut duration, 41);
+        put_varint(&mut duration, 0x10);
+        put_varint(&mut duration, 500_000_000);
+        let mut status_over = vec![0x08, 2, 0x18, 0];
+        put_bytes(&mut status_over, 4, &duration);
+        let mut header = Vec::new();
+        put_bytes(&mut header, 1, b"x-ratelimit-tenant");
+        put_bytes(&mut header, 2, b"acme");
+        let mut message = vec![0x08, 2];
+        put_bytes(&mut message, 2, &status_ok);
+        put_bytes(&mut message, 2, &status_over);
+        put_bytes(&mut message, 3, &header);
+        // 未知のフィールド（raw_body = 5）は読み飛ばす
+        put_bytes(&mut message, 5, b"ignored");
+
+        let response = decode_response(&message).unwrap();
+        assert!(response.over_limit);
+        assert_eq!(response.reset_ms, Some(41_500));
+        assert_eq!(response.retry_after_secs(), 42);
+        assert_eq!(
+            response.headers,
+            vec![("x-ratelimit-tenant".to_string(), "acme".to_string())]
+        );
+
+        let ok = decode_response(&[0x08, 1]).unwrap();
+        assert!(!ok.over_limit);
+        assert_eq!(ok.retry_after_secs(), 1);
+        assert!(decode_response(&[]).is_some_and(|r| !r.over_limit));
+        // 長さが足りない
+        assert!(decode_response(&[0x12, 5, 0x08]).is_none());
+    }
+
+    #[test]
+    fn descriptors_skip_unresolvable_attributes() {
+        let service = Arc::new(RateLimitServiceConfig {
+            address: "127.0.0.1:1".to_string(),
+            domain: "veil".to_string(),
+            timeout_ms: 100,
+        });
+        let cfg: GlobalRateLimitConfig = toml::from_str(
+            r#"
+            [[descriptors]]
+            entries = [{ key = "tenant", from = "header:X-Tenant" }]
+            [[descriptors]]
+            entries = [{ key = "generic_key", value = "api" }, { key = "route", from = "route" }]
+            "#,
+        )
+        .unwrap();
+        let limiter = GlobalRateLimit::new(service, &cfg);
+        let headers: &[(&[u8], &[u8])] = &[(b"x-tenant", b"acme")];
+        let with_tenant = RateLimitRequest {
+            client_ip: "10.0.0.1",
+            path: b"/",
+            headers,
+            claims: None,
+        };
+        assert_eq!(
+            limiter.descriptors("route[7]", &with_tenant),
+            vec![
+                vec![("tenant", Cow::Borrowed("acme"))],
+                vec![
+                    ("generic_key", Cow::Borrowed("api")),
+                    ("route", Cow::Borrowed("route[7]"))
+                ],
+            ]
+        );
+        let without_tenant = RateLimitRequest {
+            client_ip: "10.0.0.1",
+            path: b"/",
+            headers: &[],
+            claims: None,
+        };
+        assert_eq!(limiter.descriptors("route[7]", &without_tenant).len(), 1);
+    }
+
+    #[test]
+    fn over_limit_cache_expires_and_is_capped() {
+        let now = 10_000_000;
+        cache_over_limit(1, now, 2_500);
+        assert_eq!(cached_over_limit(1, now), Some(3));
+        assert_eq!(cached_over_limit(1, now + 2_500), None);
+        cache_over_limit(2, now, 3_600_000);
+        assert_eq!(cached_over_limit(2, now), Some(60));
+        assert_eq!(cached_over_limit(3, now), None);
+    }
+}
@@ -0,0 +1,73 @@
+//! 外部サービスへの単発の gRPC 呼び出し（F-147 / F-149）
+//!
+//! 接続は上流 h2c 用のクライアント（`H2cClient`）とワーカーごとのプール（`H2C_POOL`）を
+//! 再利用する。TLS なしの HTTP/2 のみ。
+
+use std::time::Duration;
+
+use crate::config::ConnectionPoolConfig;
+use crate::pool::{ConnLifecycle, H2C_POOL};
+use crate::runtime::tcp::TcpStream;
+
+/// サービスへの接続をプールに残す数（ワーカーごと）
+const MAX_IDLE_CONNECTIONS: usize = 4;
+
+/// プールに残した接続のアイドルタイムアウト（秒）
+const IDLE_CONNECTION_TIMEOUT_SECS: u64 = 60;
+
+/// `addr`（`host:port`）の `path` を 1 回呼び出し、応答メッセージ（フレーム解除済み）を返す
+///
+/// HTTP ステータス・`grpc-status` のエラーや圧縮された応答は `Err`。
+/// 呼び出し全体の期限は呼び出し側で付けること（`limit` は `grpc-timeout` として送る）。
+pub(crate) async fn unary_call(
+    addr: &str,
+    path: &[u8],
+    message: &[u8],
+    limit: Duration,
+) -> Result<Vec<u8>, String> {
+    let (mut client, lifecycle) = match H2C_POOL.with(|p| p.borrow_mut().get(addr)) {
+        Some(pooled) => pooled,
+        None => {
+            let stream = TcpStream::connect_str_with(addr, None, None)
+                .await
+                .map_err(|e| format!("connect: {}", e))?;
+            let _ = stream.set_nodelay(true);
+            let mut client =
+                crate::http2::H2cClient::new(stream, crate::http2::Http2Settings::default());
+            client
+                .handshake()
+                .await
+                .map_err(|e| format!("handshake: {}", e))?;
+            (client, ConnLifecycle::new(&ConnectionPoolConfig::default()))
+        }
+    };
+    let response = client
+        .send_grpc_request(path, addr.as_bytes(), message, Some(limit))
+        .await
+        .map_err(|e| format!("request: {}", e))?;
+    if client.is_reusable() {
+        H2C_POOL.with(|p| {
+            p.borrow_mut().put(
+                addr.to_string(),
+                client,
+                lifecycle.served(),
+                MAX_IDLE_CONNECTIONS,
+                IDLE_CONNECTION_TIMEOUT_SECS,
+            )
+        });
+    }
+    if response.http_status != 200 || response.grpc_status != 0 {
+        return Err(format!(
+            "status {} grpc-status {} {}",
+            response.http_status,
+            response.grpc_status,
+            response.grpc_message.unwrap_or_default()
+        ));
+    }
+    let (frame, _) = crate::grpc::framing::decode_grpc_frame(&response.body)
+        .map_err(|e| format!("framing: {}", e))?;
+    if frame.compressed {
+        return Err("compressed response".to_string());
+    }
+    Ok(frame.data)
+}
@@ -23,8 +23,12 @@
 
 pub mod framing;
 pub mod headers;
+pub(crate) mod protobuf;
 pub mod status;
 
+#[cfg(feature = "http2")]
+pub(crate) mod client;
+
 #[cfg(feature = "grpc")]
 pub mod stream;
 
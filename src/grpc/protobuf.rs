@@ -0,0 +1,99 @@
+//! protobuf の最小限のエンコード・デコード（F-147 / F-149）
+//!
+//! 外部サービス（Envoy RLS・ext_authz）とのやり取りで使うフィールドだけを手で読み書きする。
+//! 生成コードや `prost` には依存しない。
+
+/// varint を書く
+pub(crate) fn put_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+/// 長さ区切りのフィールド（bytes / string / 入れ子のメッセージ）を書く
+pub(crate) fn put_bytes(buf: &mut Vec<u8>, field: u32, value: &[u8]) {
+    put_varint(buf, ((field as u64) << 3) | 2);
+    put_varint(buf, value.len() as u64);
+    buf.extend_from_slice(value);
+}
+
+/// varint のフィールドを書く
+pub(crate) fn put_uint(buf: &mut Vec<u8>, field: u32, value: u64) {
+    put_varint(buf, (field as u64) << 3);
+    put_varint(buf, value);
+}
+
+/// protobuf のフィールド値
+pub(crate) enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// protobuf メッセージのフィールドを順に読む（不正なら None を返して終了）
+pub(crate) struct Fields<'a> {
+    buf: &'a [u8],
+    /// 途中で不正なエンコードに当たった
+    pub(crate) malformed: bool,
+}
+
+impl<'a> Fields<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Self {
+            buf,
+            malformed: false,
+        }
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&b, rest) = self.buf.split_first()?;
+            self.buf = rest;
+            v |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Some(v);
+            }
+        }
+        None
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.buf.len() < n {
+            return None;
+        }
+        let (head, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Some(head)
+    }
+
+    fn field(&mut self) -> Option<(u64, Field<'a>)> {
+        let tag = self.varint()?;
+        let value = match tag & 7 {
+            0 => Field::Varint(self.varint()?),
+            1 => self.take(8).map(|_| Field::Fixed)?,
+            2 => {
+                let len = usize::try_from(self.varint()?).ok()?;
+                Field::Bytes(self.take(len)?)
+            }
+            5 => self.take(4).map(|_| Field::Fixed)?,
+            _ => return None,
+        };
+        Some((tag >> 3, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = (u64, Field<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() || self.malformed {
+            return None;
+        }
+        let field = self.field();
+        self.malformed = field.is_none();
+        field
+    }
+}
@@ -13,6 +13,8 @@
 //! - CORS header handling (per-route configuration)
 //! - Trailer encoding for browsers
 
+use std::io;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// gRPC-Web content type prefix
@@ -40,6 +42,8 @@ pub fn is_grpc_web_text(content_type: &[u8]) -> bool {
 }
 
 /// gRPC-Web CORS configuration for a route
+///
+/// Compile it with [`GrpcWebCorsConfig::build`] when the configuration is loaded.
 #[derive(Debug, Clone)]
 pub struct GrpcWebCorsConfig {
     /// Allowed origins (use "*" for any origin)
@@ -85,126 +89,100 @@ impl GrpcWebCorsConfig {
         }
     }
 
-    /// Check if origin is allowed
+    /// Build the shared CORS policy (see [`crate::cors`]) from this configuration
+    ///
+    /// Origins that do not compile are configuration errors.
+    pub fn build(&self) -> io::Result<GrpcWebCors> {
+        let cfg = crate::config::CorsConfig {
+            allowed_origins: self.allowed_origins.clone(),
+            allowed_origin_regexes: Vec::new(),
+            allowed_methods: vec!["POST".to_string(), "OPTIONS".to_string()],
+            allowed_headers: self.allowed_headers.clone(),
+            exposed_headers: self.exposed_headers.clone(),
+            allow_credentials: self.allow_credentials,
+            max_age_secs: Some(self.max_age.as_secs()),
+        };
+        let policy = crate::cors::Cors::new(&cfg).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("grpc-web cors {}", e))
+        })?;
+        Ok(GrpcWebCors {
+            policy,
+            exposed_headers: self.exposed_headers.join(", "),
+        })
+    }
+}
+
+/// Compiled gRPC-Web CORS policy
+#[derive(Debug)]
+pub struct GrpcWebCors {
+    policy: crate::cors::Cors,
+    /// Exposed headers listed on preflight responses
+    exposed_headers: String,
+}
+
+impl GrpcWebCors {
+    /// Check if origin is allowed (exact or wildcard, case-insensitive)
     pub fn is_origin_allowed(&self, origin: &str) -> bool {
-        self.allowed_origins.iter().any(|o| o == "*" || o == origin)
+        self.policy.is_origin_allowed(origin)
     }
 
-    /// Get CORS headers for preflight response
+    /// Get CORS headers for preflight response (empty if the origin is not allowed)
     pub fn preflight_headers(&self, origin: &str) -> Vec<(Vec<u8>, Vec<u8>)> {
-        let mut headers = Vec::new();
-
-        // Access-Control-Allow-Origin
-        if self.is_origin_allowed(origin) {
-            let allowed_origin = if self.allowed_origins.contains(&"*".to_string()) {
-                "*"
-            } else {
-                origin
-            };
-            headers.push((
-                b"access-control-allow-origin".to_vec(),
-                allowed_origin.as_bytes().to_vec(),
-            ));
-        }
-
-        // Access-Control-Allow-Methods
-        headers.push((
-            b"access-control-allow-methods".to_vec(),
-            b"POST, OPTIONS".to_vec(),
-        ));
-
-        // Access-Control-Allow-Headers
-        let allowed_headers = self.allowed_headers.join(", ");
-        headers.push((
-            b"access-control-allow-headers".to_vec(),
-            allowed_headers.into_bytes(),
-        ));
-
-        // Access-Control-Expose-Headers
-        if !self.exposed_headers.is_empty() {
-            let exposed = self.exposed_headers.join(", ");
+        let mut headers = lowercase(
+            self.policy
+                .preflight_headers(origin, "POST", "")
+                .unwrap_or_default(),
+        );
+        // Earlier releases also listed the exposed headers on the preflight; keep doing so
+        if !headers.is_empty() && !self.exposed_headers.is_empty() {
             headers.push((
                 b"access-control-expose-headers".to_vec(),
-                exposed.into_bytes(),
+                self.exposed_headers.clone().into_bytes(),
             ));
         }
-
-        // Access-Control-Allow-Credentials
-        if self.allow_credentials {
-            headers.push((
-                b"access-control-allow-credentials".to_vec(),
-                b"true".to_vec(),
-            ));
-        }
-
-        // Access-Control-Max-Age
-        headers.push((
-            b"access-control-max-age".to_vec(),
-            self.max_age.as_secs().to_string().into_bytes(),
-        ));
-
         headers
     }
 
     /// Get CORS headers for actual response
     pub fn response_headers(&self, origin: &str) -> Vec<(Vec<u8>, Vec<u8>)> {
-        let mut headers = Vec::new();
-
-        if self.is_origin_allowed(origin) {
-            let allowed_origin = if self.allowed_origins.contains(&"*".to_string()) {
-                "*"
-            } else {
-                origin
-            };
-            headers.push((
-                b"access-control-allow-origin".to_vec(),
-                allowed_origin.as_bytes().to_vec(),
-            ));
-
-            if !self.exposed_headers.is_empty() {
-                let exposed = self.exposed_headers.join(", ");
-                headers.push((
-                    b"access-control-expose-headers".to_vec(),
-                    exposed.into_bytes(),
-                ));
-            }
-
-            if self.allow_credentials {
-                headers.push((
-                    b"access-control-allow-credentials".to_vec(),
-                    b"true".to_vec(),
-                ));
-            }
-        }
-
-        headers
+        lowercase(self.policy.origin_headers(Some(origin)))
     }
 }
 
+/// Convert `(name, value)` pairs to lowercase HTTP/2 header bytes
+fn lowercase(headers: Vec<(&'static str, String)>) -> Vec<(Vec<u8>, Vec<u8>)> {
+    headers
+        .into_iter()
+        .map(|(n, v)| (n.to_ascii_lowercase().into_bytes(), v.into_bytes()))
+        .collect()
+}
+
 /// gRPC-Web full configuration for a route
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct GrpcWebConfig {
     /// Enable gRPC-Web support for this route
     pub enabled: bool,
-    /// CORS configuration
-    pub cors: GrpcWebCorsConfig,
+    /// CORS policy (compiled when the configuration is built)
+    pub cors: Arc<GrpcWebCors>,
 }
 
 impl GrpcWebConfig {
-    /// Create enabled config with default CORS
-    pub fn enabled() -> Self {
-        Self {
+    /// Create enabled config from a CORS configuration
+    pub fn new(cors: &GrpcWebCorsConfig) -> io::Result<Self> {
+        Ok(Self {
             enabled: true,
-            cors: GrpcWebCorsConfig::default(),
-        }
+            cors: Arc::new(cors.build()?),
+        })
+    }
+
+    /// Create enabled config with default CORS
+    pub fn enabled() -> io::Result<Self> {
+        Self::new(&GrpcWebCorsConfig::default())
     }
 
     /// Create enabled config with specific origins
-    pub fn with_origins(origins: Vec<String>) -> Self {
-        Self {
-            enabled: true,
-            cors: GrpcWebCorsConfig::with_origins(origins),
-        }
+    pub fn with_origins(origins: Vec<String>) -> io::Result<Self> {
+        Self::new(&GrpcWebCorsConfig::with_origins(origins))
     }
 }
 
@@ -382,13 +360,34 @@ mod tests {
 
     #[test]
     fn test_cors_origin_check() {
-        let config = GrpcWebCorsConfig::default();
+        let config = GrpcWebConfig::enabled().unwrap().cors;
         assert!(config.is_origin_allowed("http://example.com"));
         assert!(config.is_origin_allowed("http://localhost:3000"));
 
-        let restricted = GrpcWebCorsConfig::with_origins(vec!["http://example.com".to_string()]);
+        let restricted = GrpcWebConfig::with_origins(vec!["http://example.com".to_string()])
+            .unwrap()
+            .cors;
         assert!(restricted.is_origin_allowed("http://example.com"));
         assert!(!restricted.is_origin_allowed("http://other.com"));
+        assert!(restricted.preflight_headers("http://other.com").is_empty());
+
+        let wildcard = GrpcWebCorsConfig::with_origins(vec!["https://*.example.com".to_string()])
+            .build()
+            .unwrap();
+        assert!(wildcard.is_origin_allowed("https://app.example.com"));
+        assert!(!wildcard.is_origin_allowed("https://example.com"));
+        assert!(wildcard
+            .response_headers("https://app.example.com")
+            .iter()
+            .any(|(n, v)| n == b"vary" && v == b"Origin"));
+    }
+
+    #[test]
+    fn test_cors_origin_compile_errors_are_config_errors() {
+        let huge = format!("https://{}", "*.".repeat(200_000));
+        let err = GrpcWebConfig::with_origins(vec![huge]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().starts_with("grpc-web cors invalid origin"));
     }
 
     #[test]
@@ -414,7 +413,7 @@ mod tests {
 
     #[test]
     fn test_preflight_headers() {
-        let config = GrpcWebCorsConfig::default();
+        let config = GrpcWebCorsConfig::default().build().unwrap();
         let headers = config.preflight_headers("http://example.com");
 
         assert!(headers
@@ -0,0 +1,1005 @@
+//! アクティブヘルスチェックの共通プローブ・スケジュール・遷移履歴（F-143）
+//!
+//! HTTP 上流（`server::spawn_health_check_thread`）と L4 上流（`l4::health`）の双方から使う。
+//! プローブは専用ヘルスチェックスレッド上の同期 I/O で実行し、失敗時は理由を文字列で返す。
+//! healthy / unhealthy の遷移は理由付きでリングバッファに記録し、管理 API
+//! （`GET /__admin/health`）から参照できる。
+
+use crate::config::{HealthCheckConfig, HealthCheckProtocol, HealthCheckType};
+use crate::upstream::{perform_grpc_health_check, perform_tcp_health_check};
+use once_cell::sync::Lazy;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream as StdTcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 検証に使うレスポンスボディの上限（超過分は読み捨てずに打ち切る）
+const MAX_PROBE_BODY: usize = 64 * 1024;
+
+/// 保持する遷移イベント数（古いものから破棄）
+const HEALTH_EVENT_CAPACITY: usize = 256;
+
+/// リクエストボディの上限（HTTP/2 の既定 SETTINGS_MAX_FRAME_SIZE 1 フレーム分）
+pub(crate) const MAX_PROBE_REQUEST_BODY: usize = 16 * 1024;
+
+/// プローブ対象のサーバー
+pub struct ProbeTarget<'a> {
+    /// 接続先 `host:port`
+    pub addr: &'a str,
+    /// Host ヘッダー / `:authority` の既定値（`host` 未指定時）
+    pub host: &'a str,
+    /// TLS の SNI
+    pub sni: &'a str,
+    /// HTTP/3 上流（`protocol` 未指定の HTTP チェックを HTTP/3 で行う）
+    pub h3: bool,
+    /// L4 上流（`protocol` 未指定の HTTP チェックを TCP 接続確認で行う）
+    pub l4: bool,
+}
+
+/// HTTP プローブの応答
+#[derive(Debug)]
+struct ProbeResponse {
+    status: u16,
+    body: Vec<u8>,
+}
+
+/// ヘルスチェックを 1 回実行する。失敗時は理由を返す。
+// 理由付き allow: 専用ヘルスチェックスレッドから呼ばれる同期プローブ（イベントループ外）。
+#[allow(clippy::disallowed_methods)]
+pub fn probe(hc: &HealthCheckConfig, target: &ProbeTarget<'_>) -> Result<(), String> {
+    let timeout = Duration::from_secs(hc.timeout_secs);
+    match hc.check_type {
+        HealthCheckType::Tcp => tcp_probe(target.addr, timeout),
+        HealthCheckType::Grpc => {
+            if perform_grpc_health_check(target.addr, &hc.path, hc.use_tls, hc.verify_cert, timeout)
+            {
+                Ok(())
+            } else {
+                Err("gRPC health check did not report SERVING".to_string())
+            }
+        }
+        HealthCheckType::Http => match hc.protocol {
+            Some(protocol) => http_probe(hc, target, protocol, hc.use_tls),
+            // L4 の HTTP チェックは従来どおり TCP 接続確認のみ
+            None if target.l4 => tcp_probe(target.addr, timeout),
+            #[cfg(feature = "http3")]
+            None if target.h3 => h3_probe(hc, target),
+            None => http_probe(hc, target, HealthCheckProtocol::Http1, hc.use_tls),
+        },
+    }
+}
+
+fn tcp_probe(addr: &str, timeout: Duration) -> Result<(), String> {
+    if perform_tcp_health_check(addr, timeout) {
+        Ok(())
+    } else {
+        Err(format!("TCP connect to {} failed", addr))
+    }
+}
+
+/// HTTP/3（QUIC）で GET を送る（F-135）。QUIC ハンドシェイクが成立しない場合は
+/// データ経路と同じく TCP 上の HTTPS（HTTP/1.1）へフォールバックして判定する。
+#[cfg(feature = "http3")]
+fn h3_probe(hc: &HealthCheckConfig, target: &ProbeTarget<'_>) -> Result<(), String> {
+    use crate::http3_client::{probe_blocking, H3UpstreamError};
+
+    let addr = resolve(target.addr)?;
+    let timeout = Duration::from_secs(hc.timeout_secs);
+    match probe_blocking(addr, target.sni, &hc.path, hc.verify_cert, timeout) {
+        Ok(status) => check_response(hc, status, &[]),
+        Err(H3UpstreamError::Handshake(e)) => {
+            ftlog::debug!(
+                "HTTP/3 health check handshake failed for {}: {}",
+                target.addr,
+                e
+            );
+            http_probe(hc, target, HealthCheckProtocol::Http1, true)
+        }
+        Err(e) => Err(format!("HTTP/3: {}", e)),
+    }
+}
+
+/// HTTP/1.1・h2c・ALPN h2 でリクエストを送り、ステータスとボディを検証する
+fn http_probe(
+    hc: &HealthCheckConfig,
+    target: &ProbeTarget<'_>,
+    protocol: HealthCheckProtocol,
+    tls: bool,
+) -> Result<(), String> {
+    let timeout = Duration::from_secs(hc.timeout_secs);
+    let deadline = Instant::now() + timeout;
+    let addr = resolve(target.addr)?;
+    let tcp = StdTcpStream::connect_timeout(&addr, timeout)
+        .map_err(|e| format!("connect {}: {}", addr, e))?;
+    let _ = tcp.set_read_timeout(Some(timeout));
+    let _ = tcp.set_write_timeout(Some(timeout));
+    let _ = tcp.set_nodelay(true);
+    let authority = hc.host.as_deref().unwrap_or(target.host);
+
+    let response = match protocol {
+        HealthCheckProtocol::Http1 if tls => {
+            let mut stream = tls_connect(tcp, target.sni, hc.verify_cert, &[])?;
+            exchange_h1(&mut stream, hc, authority)?
+        }
+        HealthCheckProtocol::Http1 => {
+            let mut stream = tcp;
+            exchange_h1(&mut stream, hc, authority)?
+        }
+        #[cfg(feature = "http2")]
+        HealthCheckProtocol::H2 => {
+            let mut stream = tls_connect(tcp, target.sni, hc.verify_cert, &[b"h2"])?;
+            if stream.conn.alpn_protocol() != Some(b"h2") {
+                return Err("server did not negotiate h2 via ALPN".to_string());
+            }
+            exchange_h2(&mut stream, hc, b"https", authority, deadline)?
+        }
+        #[cfg(feature = "http2")]
+        HealthCheckProtocol::H2c => {
+            let mut stream = tcp;
+            exchange_h2(&mut stream, hc, b"http", authority, deadline)?
+        }
+        #[cfg(not(feature = "http2"))]
+        HealthCheckProtocol::H2 | HealthCheckProtocol::H2c => {
+            let _ = deadline;
+            return Err("h2 / h2c probes require the http2 feature".to_string());
+        }
+    };
+    check_response(hc, response.status, &response.body)
+}
+
+/// `host:port` を解決する（ホスト名も可、最初のアドレスを使う）
+fn resolve(addr: &str) -> Result<SocketAddr, String> {
+    addr.to_socket_addrs()
+        .map_err(|e| format!("resolve {}: {}", addr, e))?
+        .next()
+        .ok_or_else(|| format!("resolve {}: no addresses", addr))
+}
+
+/// プローブ用 TLS クライアント設定（`alpn` が空なら ALPN を広告しない）
+fn probe_tls_config(verify_cert: bool, alpn: &[&[u8]]) -> Arc<ClientConfig> {
+    let mut config = if verify_cert {
+        let mut root_store = RootCertStore::empty();
+        root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        ClientConfig::builder()
+            .with_root_certificates(root_store)
+            .with_no_client_auth()
+    } else {
+        // 証明書検証を無効化（自己署名証明書を許可）
+        #[cfg(veil_ktls)]
+        let config = (*crate::ktls_rustls::insecure_client_config()).clone();
+        #[cfg(not(veil_ktls))]
+        let config = (*crate::simple_tls::insecure_client_config()).clone();
+        config
+    };
+    config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+    Arc::new(config)
+}
+
+/// TLS ハンドシェイクを同期で完了させる
+fn tls_connect(
+    mut tcp: StdTcpStream,
+    sni: &str,
+    verify_cert: bool,
+    alpn: &[&[u8]],
+) -> Result<StreamOwned<ClientConnection, StdTcpStream>, String> {
+    let server_name = ServerName::try_from(sni.to_string())
+        .map_err(|_| format!("invalid TLS server name \"{}\"", sni))?;
+    let mut conn = ClientConnection::new(probe_tls_config(verify_cert, alpn), server_name)
+        .map_err(|e| format!("TLS: {}", e))?;
+    while conn.is_handshaking() {
+        conn.complete_io(&mut tcp)
+            .map_err(|e| format!("TLS handshake: {}", e))?;
+    }
+    Ok(StreamOwned::new(conn, tcp))
+}
+
+/// 同期の HTTP/1.1 GET（`http://` / `https://`、証明書は webpki のルートで検証）
+///
+/// 2xx ならボディ（最大 64 KiB）を返す。JWKS の取得（F-148）に使う。
+// 理由付き allow: JWKS のリフレッシュスレッドと設定読み込み時にのみ呼ばれる同期 I/O（データプレーン非経由）。
+#[allow(clippy::disallowed_methods)]
+pub(crate) fn http_get(url: &str, timeout: Duration) -> Result<Vec<u8>, String> {
+    let target =
+        crate::config::ProxyTarget::parse(url).ok_or_else(|| format!("invalid URL \"{}\"", url))?;
+    let addr = resolve(&format!("{}:{}", target.host, target.port))?;
+    let tcp = StdTcpStream::connect_timeout(&addr, timeout)
+        .map_err(|e| format!("connect {}: {}", addr, e))?;
+    let _ = tcp.set_read_timeout(Some(timeout));
+    let _ = tcp.set_write_timeout(Some(timeout));
+    let default_port = if target.use_tls { 443 } else { 80 };
+    let authority = if target.port == default_port {
+        target.host.clone()
+    } else {
+        format!("{}:{}", target.host, target.port)
+    };
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: application/json\r\nConnection: close\r\nUser-Agent: veil\r\n\r\n",
+        target.path_prefix, authority
+    );
+    let response = if target.use_tls {
+        let mut stream = tls_connect(tcp, &target.host, true, &[])?;
+        exchange_raw_h1(&mut stream, request.as_bytes(), false)?
+    } else {
+        let mut stream = tcp;
+        exchange_raw_h1(&mut stream, request.as_bytes(), false)?
+    };
+    if !(200..300).contains(&response.status) {
+        return Err(format!("unexpected status {}", response.status));
+    }
+    Ok(response.body)
+}
+
+/// HTTP/1.1 リクエストを組み立てる（`Connection: close` で応答終端を EOF にする）
+fn build_h1_request(hc: &HealthCheckConfig, authority: &str) -> Vec<u8> {
+    let mut request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nUser-Agent: VeilHealthCheck/1.0\r\n",
+        hc.method.to_ascii_uppercase(),
+        hc.path,
+        authority
+    );
+    for (name, value) in &hc.headers {
+        if name.eq_ignore_ascii_case("host") || name.eq_ignore_ascii_case("connection") {
+            continue;
+        }
+        request.push_str(name);
+        request.push_str(": ");
+        request.push_str(value);
+        request.push_str("\r\n");
+    }
+    if let Some(body) = &hc.body {
+        request.push_str("Content-Length: ");
+        request.push_str(&body.len().to_string());
+        request.push_str("\r\n");
+    }
+    request.push_str("\r\n");
+    let mut bytes = request.into_bytes();
+    if let Some(body) = &hc.body {
+        bytes.extend_from_slice(body.as_bytes());
+    }
+    bytes
+}
+
+fn exchange_h1<S: Read + Write>(
+    stream: &mut S,
+    hc: &HealthCheckConfig,
+    authority: &str,
+) -> Result<ProbeResponse, String> {
+    let head_only = hc.method.eq_ignore_ascii_case("HEAD");
+    exchange_raw_h1(stream, &build_h1_request(hc, authority), head_only)
+}
+
+/// 組み立て済みの HTTP/1.1 リクエストを送り、応答を受け取る
+fn exchange_raw_h1<S: Read + Write>(
+    stream: &mut S,
+    request: &[u8],
+    head_only: bool,
+) -> Result<ProbeResponse, String> {
+    stream
+        .write_all(request)
+        .and_then(|_| stream.flush())
+        .map_err(|e| format!("write: {}", e))?;
+
+    let mut buf = Vec::with_capacity(4096);
+    let mut chunk = [0u8; 4096];
+    loop {
+        let eof = match stream.read(&mut chunk) {
+            Ok(0) => true,
+            Ok(n) => {
+                buf.extend_from_slice(&chunk[..n]);
+                false
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            // close_notify なしの切断・タイムアウトは受信済みの範囲で判定する
+            Err(e) if buf.is_empty() => return Err(format!("read: {}", e)),
+            Err(_) => true,
+        };
+        if let Some(result) = parse_h1_response(&buf, eof, head_only) {
+            return result;
+        }
+    }
+}
+
+/// 受信済みバイト列から HTTP/1.1 応答を取り出す（未完了なら None）
+fn parse_h1_response(
+    buf: &[u8],
+    eof: bool,
+    head_only: bool,
+) -> Option<Result<ProbeResponse, String>> {
+    let mut headers = [httparse::EMPTY_HEADER; 64];
+    let mut response = httparse::Response::new(&mut headers);
+    let head_len = match response.parse(buf) {
+        Ok(httparse::Status::Complete(n)) => n,
+        Ok(httparse::Status::Partial) if !eof => return None,
+        Ok(httparse::Status::Partial) if buf.is_empty() => {
+            return Some(Err("connection closed without response".to_string()))
+        }
+        Ok(httparse::Status::Partial) => {
+            return Some(Err("incomplete response headers".to_string()))
+        }
+        Err(e) => return Some(Err(format!("malformed response: {}", e))),
+    };
+    let status = response.code.unwrap_or(0);
+
+    let mut content_length = None;
+    let mut chunked = false;
+    for header in response.headers.iter() {
+        if header.name.eq_ignore_ascii_case("content-length") {
+            content_length = std::str::from_utf8(header.value)
+                .ok()
+                .and_then(|v| v.trim().parse::<usize>().ok());
+        } else if header.name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = crate::http_utils::is_chunked_encoding(header.value);
+        }
+    }
+
+    let raw = &buf[head_len..];
+    let body = if head_only || status == 204 || status == 304 || status < 200 {
+        Vec::new()
+    } else if chunked {
+        if !eof && !raw.ends_with(b"0\r\n\r\n") && raw.len() < MAX_PROBE_BODY {
+            return None;
+        }
+        let mut body = crate::http_utils::decode_chunked_body(raw);
+        body.truncate(MAX_PROBE_BODY);
+        body
+    } else if let Some(len) = content_length {
+        let want = len.min(MAX_PROBE_BODY);
+        if raw.len() < want {
+            if !eof {
+                return None;
+            }
+            return Some(Err(format!(
+                "truncated response body ({} of {} bytes)",
+                raw.len(),
+                len
+            )));
+        }
+        raw[..want].to_vec()
+    } else {
+        if !eof && raw.len() < MAX_PROBE_BODY {
+            return None;
+        }
+        raw[..raw.len().min(MAX_PROBE_BODY)].to_vec()
+    };
+    Some(Ok(ProbeResponse { status, body }))
+}
+
+/// HTTP/2（h2c Prior Knowledge / ALPN h2）で 1 リクエストを送り応答を受け取る
+#[cfg(feature = "http2")]
+fn exchange_h2<S: Read + Write>(
+    stream: &mut S,
+    hc: &HealthCheckConfig,
+    scheme: &[u8],
+    authority: &str,
+    deadline: Instant,
+) -> Result<ProbeResponse, String> {
+    use crate::http2::client::CONNECTION_PREFACE;
+    use crate::http2::frame::{Frame, FrameDecoder, FrameEncoder, FrameHeader};
+    use crate::http2::hpack::{HpackDecoder, HpackEncoder};
+    use crate::http2::settings::defaults;
+
+    let io_err = |e: std::io::Error| format!("HTTP/2: {}", e);
+    let enc = FrameEncoder::new(defaults::MAX_FRAME_SIZE);
+    let decoder = FrameDecoder::new(defaults::MAX_FRAME_SIZE);
+    let mut hpack = HpackEncoder::new(defaults::HEADER_TABLE_SIZE as usize);
+    let mut hpack_dec = HpackDecoder::new(defaults::HEADER_TABLE_SIZE as usize);
+
+    // Preface + SETTINGS + HEADERS (+ DATA)
+    let mut out = CONNECTION_PREFACE.to_vec();
+    out.extend_from_slice(&enc.encode_settings(
+        &[(0x3, defaults::MAX_CONCURRENT_STREAMS), (0x4, 65_535)],
+        false,
+    ));
+    let method = hc.method.to_ascii_uppercase();
+    let extra: Vec<(Vec<u8>, &[u8])> = hc
+        .headers
+        .iter()
+        .filter(|(name, _)| {
+            !name.eq_ignore_ascii_case("host") && !name.eq_ignore_ascii_case("connection")
+        })
+        .map(|(name, value)| (name.to_ascii_lowercase().into_bytes(), value.as_bytes()))
+        .collect();
+    let content_length = hc.body.as_ref().map(|b| b.len().to_string());
+    let mut fields: Vec<(&[u8], &[u8], bool)> = vec![
+        (b":method", method.as_bytes(), false),
+        (b":scheme", scheme, false),
+        (b":authority", authority.as_bytes(), false),
+        (b":path", hc.path.as_bytes(), false),
+        (b"user-agent", b"VeilHealthCheck/1.0", false),
+    ];
+    for (name, value) in &extra {
+        fields.push((name, value, false));
+    }
+    if let Some(len) = &content_length {
+        fields.push((b"content-length", len.as_bytes(), false));
+    }
+    let block = hpack
+        .encode(&fields)
+        .map_err(|e| format!("HTTP/2 HPACK: {:?}", e))?;
+    let body = hc.body.as_deref().unwrap_or("").as_bytes();
+    out.extend_from_slice(&enc.encode_headers(1, &block, body.is_empty(), true, None));
+    if !body.is_empty() {
+        out.extend_from_slice(&enc.encode_data(1, body, true));
+    }
+    stream.write_all(&out).map_err(io_err)?;
+    stream.flush().map_err(io_err)?;
+
+    let mut buf = vec![0u8; 32 * 1024];
+    let mut filled = 0usize;
+    let mut header_block = Vec::new();
+    let mut status: Option<u16> = None;
+    let mut body = Vec::new();
+
+    while Instant::now() < deadline {
+        if filled == buf.len() {
+            buf.resize(buf.len() * 2, 0);
+        }
+        match stream.read(&mut buf[filled..]) {
+            Ok(0) => return Err("HTTP/2: connection closed before end of stream".to_string()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(io_err(e)),
+        }
+
+        let mut offset = 0usize;
+        while offset + FrameHeader::SIZE <= filled {
+            let mut hdr9 = [0u8; 9];
+            hdr9.copy_from_slice(&buf[offset..offset + FrameHeader::SIZE]);
+            let header = FrameHeader::decode(&hdr9);
+            let frame_end = offset + FrameHeader::SIZE + header.length as usize;
+            if frame_end > filled {
+                break;
+            }
+            let payload = &buf[offset + FrameHeader::SIZE..frame_end];
+            let frame = decoder
+                .decode(&header, payload)
+                .map_err(|e| format!("HTTP/2 frame: {:?}", e))?;
+            offset = frame_end;
+
+            let (block, end_headers, end_stream) = match frame {
+                Frame::Settings { ack: false, .. } => {
+                    stream
+                        .write_all(&enc.encode_settings_ack())
+                        .map_err(io_err)?;
+                    continue;
+                }
+                Frame::Ping { ack: false, data } => {
+                    stream
+                        .write_all(&enc.encode_ping(&data, true))
+                        .map_err(io_err)?;
+                    continue;
+                }
+                Frame::GoAway { error_code, .. } => {
+                    return Err(format!("HTTP/2: GOAWAY (error code {})", error_code));
+                }
+                Frame::RstStream {
+                    stream_id: 1,
+                    error_code,
+                } => {
+                    return Err(format!("HTTP/2: stream reset (error code {})", error_code));
+                }
+                Frame::Headers {
+                    stream_id: 1,
+                    end_stream,
+                    end_headers,
+                    header_block,
+                    ..
+                } => (header_block, end_headers, end_stream),
+                Frame::Continuation {
+                    stream_id: 1,
+                    end_headers,
+                    header_block,
+                } => (header_block, end_headers, false),
+                Frame::Data {
+                    stream_id: 1,
+                    end_stream,
+                    data,
+                } => {
+                    let room = MAX_PROBE_BODY.saturating_sub(body.len());
+                    body.extend_from_slice(&data[..data.len().min(room)]);
+                    if end_stream || body.len() >= MAX_PROBE_BODY {
+                        return finish_h2(status, body);
+                    }
+                    if !data.is_empty() {
+                        let inc = data.len() as u32;
+                        let mut wu = enc.encode_window_update(0, inc);
+                        wu.extend_from_slice(&enc.encode_window_update(1, inc));
+                        stream.write_all(&wu).map_err(io_err)?;
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+
+            header_block.extend_from_slice(&block);
+            if end_headers {
+                let decoded = hpack_dec
+                    .decode(&header_block)
+                    .map_err(|e| format!("HTTP/2 HPACK: {:?}", e))?;
+                header_block.clear();
+                // 1xx（中間応答）は読み飛ばし、最初の最終ステータスを採用する
+                if status.is_none_or(|s| s < 200) {
+                    status = decoded
+                        .iter()
+                        .find(|f| f.name == b":status")
+                        .and_then(|f| std::str::from_utf8(&f.value).ok())
+                        .and_then(|v| v.parse::<u16>().ok());
+                }
+            }
+            if end_stream {
+                return finish_h2(status, body);
+            }
+        }
+        if offset > 0 {
+            buf.copy_within(offset..filled, 0);
+            filled -= offset;
+        }
+    }
+    Err("HTTP/2: timed out waiting for response".to_string())
+}
+
+#[cfg(feature = "http2")]
+fn finish_h2(status: Option<u16>, body: Vec<u8>) -> Result<ProbeResponse, String> {
+    match status {
+        Some(status) => Ok(ProbeResponse { status, body }),
+        None => Err("HTTP/2: response without :status".to_string()),
+    }
+}
+
+// ====================
+// レスポンス検証
+// ====================
+
+/// ステータス・ボディ部分文字列・正規表現・JSON パスを順に検証する
+pub(crate) fn check_response(
+    hc: &HealthCheckConfig,
+    status: u16,
+    body: &[u8],
+) -> Result<(), String> {
+    if !hc.healthy_statuses.contains(&status) {
+        return Err(format!("unexpected status {}", status));
+    }
+    if let Some(needle) = &hc.expect_body {
+        if memchr::memmem::find(body, needle.as_bytes()).is_none() {
+            return Err(format!("body does not contain {:?}", needle));
+        }
+    }
+    if let Some(pattern) = &hc.expect_body_regex {
+        let regex = hc
+            .body_regex
+            .get_or_init(|| regex::bytes::Regex::new(pattern).ok());
+        match regex {
+            Some(regex) if regex.is_match(body) => {}
+            Some(_) => return Err(format!("body does not match /{}/", pattern)),
+            None => return Err(format!("invalid expect_body_regex /{}/", pattern)),
+        }
+    }
+    if !hc.expect_json.is_empty() {
+        let doc: serde_json::Value =
+            serde_json::from_slice(body).map_err(|e| format!("body is not valid JSON: {}", e))?;
+        for (path, expected) in &hc.expect_json {
+            let segments =
+                parse_json_path(path).ok_or_else(|| format!("invalid JSON path {}", path))?;
+            match lookup_json(&doc, &segments) {
+                Some(actual) if json_matches(actual, expected) => {}
+                Some(actual) => {
+                    return Err(format!("{}: expected {}, got {}", path, expected, actual));
+                }
+                None => return Err(format!("{}: not found", path)),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// JSON パスの 1 要素
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum JsonPathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// `$.a.b[0]["c.d"]` 形式の JSON パスを解析する（先頭の `$` は省略可）
+pub(crate) fn parse_json_path(path: &str) -> Option<Vec<JsonPathSegment>> {
+    let mut rest = match path.strip_prefix('$') {
+        Some(r) => r,
+        None if path.is_empty() || path.starts_with(['.', '[']) => return None,
+        None => path,
+    };
+    let mut segments = Vec::new();
+    // `$` 省略時は先頭がキー
+    let mut first = !path.starts_with('$');
+    while !rest.is_empty() || first {
+        if let Some(r) = rest.strip_prefix('[') {
+            let end = r.find(']')?;
+            let inner = &r[..end];
+            let quoted = inner
+                .strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .or_else(|| inner.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')));
+            segments.push(match quoted {
+                Some(key) => JsonPathSegment::Key(key.to_string()),
+                None => JsonPathSegment::Index(inner.parse().ok()?),
+            });
+            rest = &r[end + 1..];
+        } else {
+            let r = if first { rest } else { rest.strip_prefix('.')? };
+            let end = r.find(['.', '[']).unwrap_or(r.len());
+            if end == 0 {
+                return None;
+            }
+            segments.push(JsonPathSegment::Key(r[..end].to_string()));
+            rest = &r[end..];
+        }
+        first = false;
+    }
+    Some(segments)
+}
+
+fn lookup_json<'a>(
+    doc: &'a serde_json::Value,
+    segments: &[JsonPathSegment],
+) -> Option<&'a serde_json::Value> {
+    segments
+        .iter()
+        .try_fold(doc, |value, segment| match segment {
+            JsonPathSegment::Key(key) => value.get(key.as_str()),
+            JsonPathSegment::Index(i) => value.get(*i),
+        })
+}
+
+/// TOML の期待値と JSON の実際値を比較する（テーブルは指定キーのみ比較）
+fn json_matches(actual: &serde_json::Value, expected: &toml::Value) -> bool {
+    match expected {
+        toml::Value::String(s) => actual.as_str() == Some(s.as_str()),
+        toml::Value::Integer(i) => {
+            actual.as_i64() == Some(*i) || actual.as_f64().is_some_and(|f| f == *i as f64)
+        }
+        toml::Value::Float(f) => actual.as_f64().is_some_and(|a| a == *f),
+        toml::Value::Boolean(b) => actual.as_bool() == Some(*b),
+        toml::Value::Datetime(d) => actual.as_str() == Some(d.to_string().as_str()),
+        toml::Value::Array(items) => actual.as_array().is_some_and(|a| {
+            a.len() == items.len() && a.iter().zip(items).all(|(x, y)| json_matches(x, y))
+        }),
+        toml::Value::Table(table) => actual.as_object().is_some_and(|obj| {
+            table
+                .iter()
+                .all(|(k, v)| obj.get(k).is_some_and(|x| json_matches(x, v)))
+        }),
+    }
+}
+
+// ====================
+// スケジュール
+// ====================
+
+/// サーバーごとの次回チェック時刻（キーは呼び出し側で一意に決める）
+///
+/// 間隔は healthy なら `interval_secs`、unhealthy なら `unhealthy_interval_secs` を基準に
+/// `jitter_percent` の範囲でずらし、同じ upstream のサーバーへのプローブが同期しないようにする。
+pub struct HealthSchedule {
+    next_due: HashMap<String, Instant>,
+    rng: u64,
+}
+
+impl Default for HealthSchedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthSchedule {
+    pub fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x9E37_79B9_7F4A_7C15);
+        Self {
+            next_due: HashMap::new(),
+            rng: seed | 1,
+        }
+    }
+
+    /// [0, 1) の一様乱数（xorshift64、スケジュールのばらつき用）
+    fn next_unit(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// チェック期限が来ていれば true。
+    ///
+    /// 初めて見るキーは `interval × jitter_percent` 以内のランダムな遅延で登録する
+    /// （ジッターなしなら即時チェック）。
+    pub fn is_due(&mut self, key: &str, hc: &HealthCheckConfig, now: Instant) -> bool {
+        if let Some(due) = self.next_due.get(key) {
+            return now >= *due;
+        }
+        let spread = hc
+            .interval_for(true)
+            .mul_f64(f64::from(hc.jitter_percent.min(50)) / 100.0);
+        let offset = spread.mul_f64(self.next_unit());
+        self.next_due.insert(key.to_string(), now + offset);
+        offset.is_zero()
+    }
+
+    /// チェック後に次回時刻を設定する
+    pub fn schedule(&mut self, key: &str, hc: &HealthCheckConfig, healthy: bool, now: Instant) {
+        let unit = self.next_unit();
+        let delay = jittered(hc.interval_for(healthy), hc.jitter_percent, unit);
+        self.next_due.insert(key.to_string(), now + delay);
+    }
+
+    /// 設定リロードで消えたサーバーの記録を捨てる
+    pub fn retain(&mut self, live: &HashSet<String>) {
+        self.next_due.retain(|key, _| live.contains(key));
+    }
+}
+
+/// `base` を ±`percent`% の範囲でずらす（`unit` は [0, 1) の乱数）
+pub(crate) fn jittered(base: Duration, percent: u32, unit: f64) -> Duration {
+    let p = f64::from(percent.min(50)) / 100.0;
+    base.mul_f64(1.0 + p * (2.0 * unit - 1.0))
+}
+
+// ====================
+// 遷移履歴
+// ====================
+
+/// healthy / unhealthy の遷移 1 件
+#[derive(Debug, Clone)]
+pub struct HealthEvent {
+    /// 遷移時刻（UNIX エポックからのミリ秒）
+    pub unix_ms: u64,
+    /// upstream 名（L4 は `l4:<リスナー名>`）
+    pub upstream: String,
+    /// サーバー（`host:port`）
+    pub server: String,
+    pub healthy: bool,
+    /// 遷移の理由（unhealthy は最後の失敗理由）
+    pub reason: String,
+}
+
+static HEALTH_EVENTS: Lazy<Mutex<VecDeque<HealthEvent>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(HEALTH_EVENT_CAPACITY)));
+
+/// 遷移を記録する（保持数を超えたら古いものから破棄）
+pub fn record_transition(upstream: &str, server: &str, healthy: bool, reason: &str) {
+    let unix_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let mut events = HEALTH_EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+    if events.len() >= HEALTH_EVENT_CAPACITY {
+        events.pop_front();
+    }
+    events.push_back(HealthEvent {
+        unix_ms,
+        upstream: upstream.to_string(),
+        server: server.to_string(),
+        healthy,
+        reason: reason.to_string(),
+    });
+}
+
+/// 記録済みの遷移（古い順）
+pub fn recent_transitions() -> Vec<HealthEvent> {
+    HEALTH_EVENTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    // 理由付き allow: テストコードは同期 I/O を使用してよい（データプレーン非経由）。
+    #![allow(clippy::disallowed_methods)]
+    use super::*;
+    use std::net::TcpListener;
+
+    fn hc_from(toml: &str) -> HealthCheckConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    /// 1 接続だけ受け付け、リクエストを読んで固定応答を返すサーバー
+    fn serve_once(response: &'static [u8]) -> (String, std::thread::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = std::thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut req = vec![0u8; 4096];
+            let n = conn.read(&mut req).unwrap();
+            req.truncate(n);
+            conn.write_all(response).unwrap();
+            req
+        });
+        (addr, handle)
+    }
+
+    fn target(addr: &str) -> ProbeTarget<'_> {
+        ProbeTarget {
+            addr,
+            host: "backend.local",
+            sni: "backend.local",
+            h3: false,
+            l4: false,
+        }
+    }
+
+    #[test]
+    fn json_path_parses_dotted_indexed_and_quoted_segments() {
+        use JsonPathSegment::*;
+        assert_eq!(
+            parse_json_path("$.checks[1][\"db.primary\"].ok"),
+            Some(vec![
+                Key("checks".into()),
+                Index(1),
+                Key("db.primary".into()),
+                Key("ok".into())
+            ])
+        );
+        assert_eq!(parse_json_path("status"), Some(vec![Key("status".into())]));
+        assert_eq!(parse_json_path("$"), Some(vec![]));
+        assert_eq!(parse_json_path("$.a..b"), None);
+        assert_eq!(parse_json_path("$[x]"), None);
+        assert_eq!(parse_json_path(""), None);
+    }
+
+    #[test]
+    fn response_checks_status_body_regex_and_json() {
+        let hc = hc_from(
+            r#"
+healthy_statuses = [200]
+expect_body = "\"status\""
+expect_body_regex = "\"version\":\\s*\"2\\."
+[expect_json]
+"$.status" = "UP"
+"$.checks[0].latency_ms" = 3
+"$.checks[0]" = { ok = true }
+"#,
+        );
+        let body = br#"{"status":"UP","version": "2.1","checks":[{"ok":true,"latency_ms":3}]}"#;
+        assert_eq!(check_response(&hc, 200, body), Ok(()));
+        assert_eq!(
+            check_response(&hc, 503, body),
+            Err("unexpected status 503".to_string())
+        );
+        let down = br#"{"status":"DOWN","version":"2.1","checks":[{"ok":true,"latency_ms":3}]}"#;
+        assert_eq!(
+            check_response(&hc, 200, down),
+            Err("$.status: expected \"UP\", got \"DOWN\"".to_string())
+        );
+        let old = br#"{"status":"UP","version":"1.9"}"#;
+        assert!(check_response(&hc, 200, old)
+            .unwrap_err()
+            .starts_with("body does not match"));
+        assert!(check_response(&hc, 200, b"\"status\" ok")
+            .unwrap_err()
+            .starts_with("body does not match"));
+    }
+
+    #[test]
+    fn jitter_stays_within_percent_and_unhealthy_interval_applies() {
+        let hc = hc_from("interval_secs = 10\nunhealthy_interval_secs = 2\njitter_percent = 20");
+        assert_eq!(hc.interval_for(true), Duration::from_secs(10));
+        assert_eq!(hc.interval_for(false), Duration::from_secs(2));
+        assert_eq!(
+            jittered(Duration::from_secs(10), 20, 0.0),
+            Duration::from_secs(8)
+        );
+        assert_eq!(
+            jittered(Duration::from_secs(10), 20, 0.5),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            jittered(Duration::from_secs(10), 0, 0.9),
+            Duration::from_secs(10)
+        );
+
+        let mut schedule = HealthSchedule::new();
+        let now = Instant::now();
+        for i in 0..32 {
+            let key = format!("s{}", i);
+            // 初回は interval × 20% = 2 秒未満の範囲でずれる
+            let _ = schedule.is_due(&key, &hc, now);
+            assert!(schedule.is_due(&key, &hc, now + Duration::from_secs(2)));
+            // unhealthy 時は 2 秒 ±20%
+            schedule.schedule(&key, &hc, false, now);
+            assert!(!schedule.is_due(&key, &hc, now + Duration::from_millis(1590)));
+            assert!(schedule.is_due(&key, &hc, now + Duration::from_millis(2410)));
+        }
+        schedule.retain(&HashSet::from(["s1".to_string()]));
+        assert_eq!(schedule.next_due.len(), 1);
+    }
+
+    #[test]
+    fn http1_probe_sends_custom_request_and_decodes_chunked_body() {
+        let (addr, server) = serve_once(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n",
+        );
+        let hc = hc_from(
+            r#"
+method = "POST"
+path = "/ready"
+host = "status.internal"
+body = "ping"
+expect_body = "hello world"
+[headers]
+X-Probe = "1"
+"#,
+        );
+        assert_eq!(probe(&hc, &target(&addr)), Ok(()));
+        let request = String::from_utf8(server.join().unwrap()).unwrap();
+        assert!(request.starts_with("POST /ready HTTP/1.1\r\n"));
+        assert!(request.contains("Host: status.internal\r\n"));
+        assert!(request.contains("X-Probe: 1\r\n"));
+        assert!(request.contains("Content-Length: 4\r\n"));
+        assert!(request.ends_with("\r\n\r\nping"));
+    }
+
+    #[test]
+    fn http1_probe_reports_body_mismatch_reason() {
+        let (addr, _server) =
+            serve_once(b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nmaintenance");
+        let hc = hc_from("expect_body = \"ok\"");
+        assert_eq!(
+            probe(&hc, &target(&addr)),
+            Err("body does not contain \"ok\"".to_string())
+        );
+    }
+
+    #[cfg(feature = "http2")]
+    #[test]
+    fn h2c_probe_reads_status_and_body() {
+        use crate::http2::frame::FrameEncoder;
+        use crate::http2::hpack::HpackEncoder;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = std::thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut req = vec![0u8; 4096];
+            let _ = conn.read(&mut req).unwrap();
+            let enc = FrameEncoder::new(16_384);
+            let mut hpack = HpackEncoder::new(4096);
+            let block = hpack
+                .encode(&[(b":status".as_slice(), b"200".as_slice(), false)])
+                .unwrap();
+            let mut out = enc.encode_settings(&[], false);
+            out.extend_from_slice(&enc.encode_headers(1, &block, false, true, None));
+            out.extend_from_slice(&enc.encode_data(1, br#"{"status":"UP"}"#, true));
+            conn.write_all(&out).unwrap();
+            // クライアントの SETTINGS ACK 等を受け取ってから閉じる
+            let _ = conn.read(&mut req);
+        });
+        let hc = hc_from("protocol = \"h2c\"\n[expect_json]\n\"$.status\" = \"UP\"");
+        assert_eq!(probe(&hc, &target(&addr)), Ok(()));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn transitions_are_recorded_with_reason() {
+        record_transition(
+            "api-test-events",
+            "10.9.9.9:80",
+            false,
+            "unexpected status 503",
+        );
+        let events = recent_transitions();
+        let event = events
+            .iter()
+            .rev()
+            .find(|e| e.upstream == "api-test-events")
+            .unwrap();
+        assert!(!event.healthy);
+        assert_eq!(event.server, "10.9.9.9:80");
+        assert_eq!(event.reason, "unexpected status 503");
+        assert!(events.len() <= HEALTH_EVENT_CAPACITY);
+    }
+}
@@ -0,0 +1,322 @@
+//! リクエストヘッジング（F-137）
+//!
+//! 冪等メソッドのリクエストで、一次試行が遅延内に応答を返し始めない場合に、
+//! 同じ upstream の別サーバーへ二次試行（ヘッジ）を送る。先に応答を返し始めた方を
+//! 採用し、もう一方は接続を閉じて取り消す（プロキシ側の I/O は `proxy.rs`）。
+//!
+//! - 遅延は固定値（`delay_ms`）か、upstream ごとに観測した応答開始までの時間の
+//!   パーセンタイル（既定 p95、`[min_delay_ms, max_delay_ms]` に丸める）。
+//! - ヘッジ数はルートごとの予算（対象リクエスト数に対する割合）で上限を設ける。
+//!   対象リクエストごとに `budget_percent / 100` 回分を積み立て、ヘッジ 1 回で 1 回分を
+//!   消費する（積み立ては [`HEDGE_BUDGET_BURST`] 回分まで）。
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::config::HedgingConfig;
+
+/// 予算の積み立て上限（ヘッジ回数）。短時間の遅延スパイクでまとめて使える回数
+pub const HEDGE_BUDGET_BURST: u64 = 10;
+
+/// 予算の内部単位（ヘッジ 1 回 = 1000）
+const BUDGET_UNIT: u64 = 1000;
+
+/// パーセンタイル遅延を使うのに必要な最小サンプル数（不足中は `max_delay_ms`）
+const MIN_LATENCY_SAMPLES: u64 = 20;
+
+/// このサンプル数ごとに全バケットを半減させ、古い観測の影響を減衰させる
+const LATENCY_DECAY_INTERVAL: u64 = 2048;
+
+/// 応答開始までの時間のバケット上限（マイクロ秒、昇順）
+const LATENCY_BUCKETS_US: [u64; 26] = [
+    100,
+    250,
+    500,
+    750,
+    1_000,
+    1_500,
+    2_000,
+    3_000,
+    5_000,
+    7_500,
+    10_000,
+    15_000,
+    20_000,
+    30_000,
+    50_000,
+    75_000,
+    100_000,
+    150_000,
+    200_000,
+    300_000,
+    500_000,
+    750_000,
+    1_000_000,
+    2_000_000,
+    5_000_000,
+    u64::MAX,
+];
+
+/// upstream ごとの応答開始までの時間のヒストグラム（UpstreamGroup が共有保持）
+///
+/// 固定バケットの原子カウンタのみで構成し、ロックを取らない。パーセンタイルは
+/// 該当バケットの上限値を返す（実測より大きめ = ヘッジを控えめにする側へ丸める）。
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS_US.len()],
+    since_decay: AtomicU64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            since_decay: AtomicU64::new(0),
+        }
+    }
+
+    /// 観測値を 1 件記録する
+    pub fn record(&self, elapsed: Duration) {
+        let us = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        let idx = LATENCY_BUCKETS_US
+            .iter()
+            .position(|&bound| us <= bound)
+            .unwrap_or(LATENCY_BUCKETS_US.len() - 1);
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        // 区切りに到達したスレッドだけが減衰を行う（並行記録の取りこぼしは許容）
+        if self.since_decay.fetch_add(1, Ordering::Relaxed) + 1 == LATENCY_DECAY_INTERVAL {
+            self.since_decay.store(0, Ordering::Relaxed);
+            for bucket in &self.buckets {
+                let _ = bucket.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| Some(v / 2));
+            }
+        }
+    }
+
+    /// `percentile`（0-100）の推定値。サンプル不足なら None
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let counts: [u64; LATENCY_BUCKETS_US.len()] =
+            std::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed));
+        let total: u64 = counts.iter().sum();
+        if total < MIN_LATENCY_SAMPLES {
+            return None;
+        }
+        let rank = ((total as f64) * percentile.clamp(0.0, 100.0) / 100.0).ceil() as u64;
+        let rank = rank.max(1);
+        let mut acc = 0u64;
+        for (count, bound) in counts.iter().zip(LATENCY_BUCKETS_US) {
+            acc += count;
+            if acc >= rank {
+                return Some(Duration::from_micros(bound));
+            }
+        }
+        None
+    }
+}
+
+/// ヘッジの結果（アクセスログの `hedge` フィールド）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HedgeOutcome {
+    /// ヘッジを送らなかった
+    #[default]
+    NotHedged,
+    /// ヘッジを送ったが一次試行が先に応答した
+    PrimaryWon,
+    /// ヘッジが先に応答した
+    HedgeWon,
+}
+
+impl HedgeOutcome {
+    /// アクセスログ用の値（ヘッジなしは空文字列 = フィールドを出力しない）
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HedgeOutcome::NotHedged => "",
+            HedgeOutcome::PrimaryWon => "primary",
+            HedgeOutcome::HedgeWon => "hedge",
+        }
+    }
+}
+
+/// 構築済みのヘッジングポリシー（ルートごと、UpstreamGroup が保持）
+#[derive(Debug)]
+pub struct HedgePolicy {
+    /// 固定遅延（None なら観測パーセンタイル）
+    fixed_delay: Option<Duration>,
+    percentile: f64,
+    min_delay: Duration,
+    max_delay: Duration,
+    /// 対象メソッド（大文字）
+    methods: Vec<Box<[u8]>>,
+    /// 対象リクエスト 1 件あたりの積み立て量（BUDGET_UNIT 単位）
+    deposit: u64,
+    /// 現在の予算残高（BUDGET_UNIT 単位）
+    tokens: AtomicU64,
+}
+
+impl HedgePolicy {
+    /// 設定から構築する（妥当性は設定読み込み時に検証済み）
+    pub fn new(cfg: &HedgingConfig) -> Self {
+        Self {
+            fixed_delay: (cfg.delay_ms > 0).then(|| Duration::from_millis(cfg.delay_ms)),
+            percentile: cfg.percentile,
+            min_delay: Duration::from_millis(cfg.min_delay_ms),
+            max_delay: Duration::from_millis(cfg.max_delay_ms),
+            methods: cfg
+                .methods
+                .iter()
+                .map(|m| m.to_ascii_uppercase().into_bytes().into_boxed_slice())
+                .collect(),
+            deposit: (cfg.budget_percent.clamp(0.0, 100.0) / 100.0 * BUDGET_UNIT as f64) as u64,
+            tokens: AtomicU64::new(0),
+        }
+    }
+
+    /// このメソッドのリクエストがヘッジ対象か
+    #[inline]
+    pub fn allows_method(&self, method: &[u8]) -> bool {
+        self.methods.iter().any(|m| m.eq_ignore_ascii_case(method))
+    }
+
+    /// ヘッジを送るまでの遅延
+    pub fn delay(&self, latency: &LatencyHistogram) -> Duration {
+        if let Some(d) = self.fixed_delay {
+            return d;
+        }
+        match latency.percentile(self.percentile) {
+            Some(d) => d.clamp(self.min_delay, self.max_delay),
+            None => self.max_delay,
+        }
+    }
+
+    /// 対象リクエスト 1 件分の予算を積み立てる
+    pub fn deposit(&self) {
+        let cap = HEDGE_BUDGET_BURST * BUDGET_UNIT;
+        let _ = self
+            .tokens
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |t| {
+                Some(t.saturating_add(self.deposit).min(cap))
+            });
+    }
+
+    /// ヘッジ 1 回分の予算を消費する。残高不足なら false
+    pub fn try_withdraw(&self) -> bool {
+        self.tokens
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |t| {
+                t.checked_sub(BUDGET_UNIT)
+            })
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> HedgingConfig {
+        HedgingConfig::default()
+    }
+
+    #[test]
+    fn percentile_needs_samples_and_rounds_up_to_bucket() {
+        let h = LatencyHistogram::new();
+        for _ in 0..(MIN_LATENCY_SAMPLES - 1) {
+            h.record(Duration::from_millis(2));
+        }
+        assert_eq!(h.percentile(95.0), None);
+        h.record(Duration::from_millis(2));
+        assert_eq!(h.percentile(95.0), Some(Duration::from_millis(2)));
+
+        // 上位 5% だけが遅い: p95 は速い側、p99 は遅い側のバケット
+        let h = LatencyHistogram::new();
+        for _ in 0..95 {
+            h.record(Duration::from_micros(900));
+        }
+        for _ in 0..5 {
+            h.record(Duration::from_millis(180));
+        }
+        assert_eq!(h.percentile(95.0), Some(Duration::from_millis(1)));
+        assert_eq!(h.percentile(99.0), Some(Duration::from_millis(200)));
+    }
+
+    #[test]
+    fn histogram_decays_old_samples() {
+        let h = LatencyHistogram::new();
+        for _ in 0..(LATENCY_DECAY_INTERVAL - 1) {
+            h.record(Duration::from_millis(400));
+        }
+        h.record(Duration::from_millis(400));
+        // 区切りで半減している
+        let total: u64 = h.buckets.iter().map(|b| b.load(Ordering::Relaxed)).sum();
+        assert_eq!(total, LATENCY_DECAY_INTERVAL / 2);
+    }
+
+    #[test]
+    fn delay_is_fixed_or_clamped_percentile() {
+        let fixed = HedgePolicy::new(&HedgingConfig {
+            delay_ms: 40,
+            ..cfg()
+        });
+        assert_eq!(
+            fixed.delay(&LatencyHistogram::new()),
+            Duration::from_millis(40)
+        );
+
+        let adaptive = HedgePolicy::new(&HedgingConfig {
+            min_delay_ms: 5,
+            max_delay_ms: 300,
+            ..cfg()
+        });
+        let h = LatencyHistogram::new();
+        // サンプル不足の間は上限
+        assert_eq!(adaptive.delay(&h), Duration::from_millis(300));
+        for _ in 0..50 {
+            h.record(Duration::from_micros(50));
+        }
+        // 下限に丸める
+        assert_eq!(adaptive.delay(&h), Duration::from_millis(5));
+        for _ in 0..1000 {
+            h.record(Duration::from_secs(3));
+        }
+        // 上限に丸める
+        assert_eq!(adaptive.delay(&h), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn budget_limits_hedge_ratio() {
+        let p = HedgePolicy::new(&HedgingConfig {
+            budget_percent: 10.0,
+            ..cfg()
+        });
+        assert!(!p.try_withdraw(), "budget starts empty");
+        let mut hedges = 0;
+        for _ in 0..100 {
+            p.deposit();
+            if p.try_withdraw() {
+                hedges += 1;
+            }
+        }
+        assert_eq!(hedges, 10);
+
+        // 積み立ては HEDGE_BUDGET_BURST 回分まで
+        for _ in 0..10_000 {
+            p.deposit();
+        }
+        let burst = (0..100).take_while(|_| p.try_withdraw()).count() as u64;
+        assert_eq!(burst, HEDGE_BUDGET_BURST);
+    }
+
+    #[test]
+    fn methods_match_case_insensitively() {
+        let p = HedgePolicy::new(&cfg());
+        assert!(p.allows_method(b"GET"));
+        assert!(p.allows_method(b"head"));
+        assert!(!p.allows_method(b"POST"));
+        assert_eq!(HedgeOutcome::default().as_str(), "");
+        assert_eq!(HedgeOutcome::HedgeWon.as_str(), "hedge");
+    }
+}
@@ -17,17 +17,17 @@ pub const CONNECTION_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
 /// H2C クライアント接続
 pub struct H2cClient<S> {
     /// TCP ストリーム
-    stream: S,
+    pub(super) stream: S,
     /// ローカル設定 (クライアント)
-    local_settings: Http2Settings,
+    pub(super) local_settings: Http2Settings,
     /// リモート設定 (サーバー)
-    remote_settings: Http2Settings,
+    pub(super) remote_settings: Http2Settings,
     /// HPACK エンコーダ
     hpack_encoder: HpackEncoder,
     /// HPACK デコーダ
-    hpack_decoder: HpackDecoder,
+    pub(super) hpack_decoder: HpackDecoder,
     /// フレームエンコーダ
-    frame_encoder: FrameEncoder,
+    pub(super) frame_encoder: FrameEncoder,
     /// フレームデコーダ
     frame_decoder: FrameDecoder,
     /// 読み込みバッファ
@@ -37,13 +37,15 @@ pub struct H2cClient<S> {
     /// バッファ内の有効データ終了位置
     buf_end: usize,
     /// コネクションレベル送信ウィンドウ
-    conn_send_window: i32,
+    pub(super) conn_send_window: i32,
     /// コネクションレベル受信ウィンドウ
-    conn_recv_window: i32,
+    pub(super) conn_recv_window: i32,
     /// 次のストリームID (クライアントは奇数)
-    next_stream_id: u32,
+    pub(super) next_stream_id: u32,
     /// SETTINGS ACK 待ち
     settings_ack_pending: bool,
+    /// `:scheme` 疑似ヘッダ（h2c は `http`、ALPN h2 は `https`、F-134）
+    scheme: &'static [u8],
 }
 
 impl<S> H2cClient<S>
@@ -60,7 +62,11 @@ where
         Self {
             stream,
             local_settings: settings,
-            remote_settings: Http2Settings::default(),
+            // MAX_CONCURRENT_STREAMS は SETTINGS で通知されるまで無制限（RFC 9113 §6.5.2）
+            remote_settings: Http2Settings {
+                max_concurrent_streams: u32::MAX,
+                ..Http2Settings::default()
+            },
             hpack_encoder,
             hpack_decoder,
             frame_encoder,
@@ -72,9 +78,34 @@ where
             conn_recv_window: defaults::CONNECTION_WINDOW_SIZE as i32,
             next_stream_id: 1, // クライアントは奇数
             settings_ack_pending: false,
+            scheme: b"http",
         }
     }
 
+    /// `:scheme` を設定する（TLS 上の h2 では `https`、F-134）
+    pub fn with_scheme(mut self, scheme: &'static [u8]) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// サーバーが SETTINGS で通知した MAX_CONCURRENT_STREAMS（未通知なら `u32::MAX`、F-134）
+    #[inline]
+    pub fn peer_max_concurrent_streams(&self) -> u32 {
+        self.remote_settings.max_concurrent_streams
+    }
+
+    /// サーバーの SETTINGS_HEADER_TABLE_SIZE を HPACK エンコーダへ反映する
+    #[inline]
+    pub(super) fn set_peer_header_table_size(&mut self, size: u32) {
+        self.hpack_encoder.set_max_table_size(size as usize);
+    }
+
+    /// 下位ストリームへの参照（fd・復号済みバッファの問い合わせ用、F-134）
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
     /// このコネクションをプールで再利用可能か（F-106）。
     ///
     /// クライアントストリーム ID は奇数で単調増加し、HTTP/2 の 31bit 上限
@@ -97,7 +128,7 @@ where
     /// 正しく積み増す必要がある。ストリームレベル（stream_id != 0）の更新は
     /// 送信ゲートが接続レベルのみのため無視する。i32 上限でクランプする。
     #[inline]
-    fn apply_window_update(&mut self, stream_id: u32, increment: u32) {
+    pub(super) fn apply_window_update(&mut self, stream_id: u32, increment: u32) {
         if stream_id == 0 {
             self.conn_send_window = self
                 .conn_send_window
@@ -171,6 +202,7 @@ where
                 for &(id, value) in &settings {
                     match id {
                         0x1 => self.hpack_encoder.set_max_table_size(value as usize),
+                        0x3 => self.remote_settings.max_concurrent_streams = value,
                         0x4 => self.remote_settings.initial_window_size = value,
                         0x5 => {
                             self.frame_encoder.set_max_frame_size(value);
@@ -226,6 +258,35 @@ where
         let stream_id = self.next_stream_id;
         self.next_stream_id += 2;
 
+        let end_stream = body.is_none() || body.map(|b| b.is_empty()).unwrap_or(true);
+        let headers_frame =
+            self.encode_request_headers(stream_id, method, path, authority, headers, end_stream)?;
+        self.write_all(headers_frame).await?;
+
+        // ボディを送信
+        if let Some(body_data) = body {
+            if !body_data.is_empty() {
+                self.send_data(stream_id, body_data, true).await?;
+            }
+        }
+
+        // レスポンスを受信
+        self.receive_response(stream_id).await
+    }
+
+    /// リクエストの HEADERS フレームを構築する（多重化ドライバと共用、F-134）
+    ///
+    /// 転送しないヘッダ（[`Self::skip_forwarded_request_header`]）を除外し、
+    /// ヘッダ名を小文字化して HPACK エンコードする。
+    pub(super) fn encode_request_headers(
+        &mut self,
+        stream_id: u32,
+        method: &[u8],
+        path: &[u8],
+        authority: &[u8],
+        headers: &[(&[u8], &[u8])],
+        end_stream: bool,
+    ) -> Http2Result<Vec<u8>> {
         // ヘッダーリストを構築
         // HTTP/2 はヘッダ名の小文字必須（RFC 9113 §8.2）。H1 からの転送で
         // Content-Type 等が混ざると h2/tonic が HPACK InvalidUtf8 → GOAWAY する（B-40）。
@@ -245,7 +306,7 @@ where
         let mut header_list: Vec<(&[u8], &[u8], bool)> = Vec::with_capacity(headers.len() + 4);
         header_list.push((b":method", method, false));
         header_list.push((b":path", path, false));
-        header_list.push((b":scheme", b"http", false));
+        header_list.push((b":scheme", self.scheme, false));
         header_list.push((b":authority", authority, false));
 
         let mut li = 0usize;
@@ -257,31 +318,18 @@ where
             li += 1;
         }
 
-        let end_stream = body.is_none() || body.map(|b| b.is_empty()).unwrap_or(true);
         let header_block = self
             .hpack_encoder
             .encode(&header_list)
             .map_err(|e| Http2Error::HpackEncode(e.to_string()))?;
 
-        // HEADERS フレームを送信
-        let headers_frame = self.frame_encoder.encode_headers(
+        Ok(self.frame_encoder.encode_headers(
             stream_id,
             &header_block,
             end_stream,
             true, // end_headers
             None,
-        );
-        self.write_all(headers_frame).await?;
-
-        // ボディを送信
-        if let Some(body_data) = body {
-            if !body_data.is_empty() {
-                self.send_data(stream_id, body_data, true).await?;
-            }
-        }
-
-        // レスポンスを受信
-        self.receive_response(stream_id).await
+        ))
     }
 
     /// DATA フレームを送信
@@ -426,6 +474,7 @@ where
                     for &(id, value) in &settings {
                         match id {
                             0x1 => self.hpack_encoder.set_max_table_size(value as usize),
+                            0x3 => self.remote_settings.max_concurrent_streams = value,
                             0x4 => self.remote_settings.initial_window_size = value,
                             0x5 => self.remote_settings.max_frame_size = value,
                             _ => {}
@@ -440,18 +489,27 @@ where
                 Frame::RstStream {
                     stream_id: sid,
                     error_code,
-                } => {
-                    if sid == stream_id {
-                        return Err(Http2Error::stream_closed(stream_id, error_code));
-                    }
+                } if sid == stream_id => {
+                    return Err(Http2Error::stream_closed(stream_id, error_code));
                 }
                 _ => {}
             }
         }
     }
 
+    /// 読み込みバッファに完全なフレームが 1 つ以上あるか（F-134 多重化ドライバ用）
+    pub(super) fn has_buffered_frame(&self) -> bool {
+        let avail = self.buf_end - self.buf_start;
+        if avail < FrameHeader::SIZE {
+            return false;
+        }
+        let b = &self.read_buf[self.buf_start..];
+        let len = ((b[0] as usize) << 16) | ((b[1] as usize) << 8) | b[2] as usize;
+        avail >= FrameHeader::SIZE + len
+    }
+
     /// フレームを読み込み
-    async fn read_frame(&mut self) -> Http2Result<Frame> {
+    pub(super) async fn read_frame(&mut self) -> Http2Result<Frame> {
         // フレームヘッダー (9 bytes) を確保
         while self.buf_end - self.buf_start < FrameHeader::SIZE {
             self.read_more().await?;
@@ -539,7 +597,7 @@ where
     /// 排除する（proxy→バックエンド方向 HTTP/2 送信ホットパス最適化・F-73 残件）。
     /// runtime の write_all は short write を内部で継続する（B-27）ため、`Ok` は常に
     /// 完全書き込みを意味する。
-    async fn write_all(&mut self, mut buf: Vec<u8>) -> Http2Result<()> {
+    pub(super) async fn write_all(&mut self, mut buf: Vec<u8>) -> Http2Result<()> {
         loop {
             let (result, returned) = self.stream.write_all(buf).await;
             match result {
@@ -615,7 +673,7 @@ where
         let mut header_list: Vec<(&[u8], &[u8], bool)> = Vec::with_capacity(8);
         header_list.push((b":method", b"POST", false));
         header_list.push((b":path", service_method, false));
-        header_list.push((b":scheme", b"http", false));
+        header_list.push((b":scheme", self.scheme, false));
         header_list.push((b":authority", authority, false));
         header_list.push((b"content-type", b"application/grpc+proto", false));
         header_list.push((b"te", b"trailers", false));
@@ -762,6 +820,7 @@ where
                     for &(id, value) in &settings {
                         match id {
                             0x1 => self.hpack_encoder.set_max_table_size(value as usize),
+                            0x3 => self.remote_settings.max_concurrent_streams = value,
                             0x4 => self.remote_settings.initial_window_size = value,
                             0x5 => self.remote_settings.max_frame_size = value,
                             _ => {}
@@ -776,10 +835,8 @@ where
                 Frame::RstStream {
                     stream_id: sid,
                     error_code,
-                } => {
-                    if sid == stream_id {
-                        return Err(Http2Error::stream_closed(stream_id, error_code));
-                    }
+                } if sid == stream_id => {
+                    return Err(Http2Error::stream_closed(stream_id, error_code));
                 }
                 _ => {}
             }
@@ -75,6 +75,9 @@ pub struct Http2Connection<S> {
     /// 1 回にまとめる再利用バッファ。呼び出し境界では常に空（`flush_write_buf` 済み）。
     /// 接続をまたいでスレッドローカルプールで再利用する（F-73 続き）。
     write_buf: Vec<u8>,
+
+    /// 接続元ごとの同時ストリーム数の上限（F-153。上限が無ければ None）
+    client_streams: Option<crate::client_limits::StreamBudget>,
 }
 
 // ====================
@@ -240,9 +243,15 @@ where
             control_frame_window_start: now,
             continuation_count: 0,
             write_buf: acquire_h2_write_buf(),
+            client_streams: None,
         }
     }
 
+    /// 接続元ごとの同時ストリーム数の上限を適用する（F-153、ハンドシェイク前に呼ぶ）
+    pub fn set_client_stream_budget(&mut self, budget: Option<crate::client_limits::StreamBudget>) {
+        self.client_streams = budget;
+    }
+
     /// HTTP/2 ハンドシェイクを実行
     ///
     /// 1. クライアントプリフェースを受信
@@ -661,15 +670,13 @@ where
                     self.hpack_encoder.set_max_table_size(value as usize);
                     // Note: Our hpack_decoder limit is set when we send our own SETTINGS to the remote.
                 }
-                0x2 => {
-                    // ENABLE_PUSH - RFC 7540 Section 6.5.2: 0 または 1 のみ有効
-                    if value > 1 {
-                        return Err(Http2Error::connection_error(
-                            Http2ErrorCode::ProtocolError,
-                            "ENABLE_PUSH must be 0 or 1",
-                        ));
-                    }
-                    // サーバーでは ENABLE_PUSH の値自体は使用しない
+                // ENABLE_PUSH - RFC 7540 Section 6.5.2: 0 または 1 のみ有効
+                // （サーバーでは ENABLE_PUSH の値自体は使用しない）
+                0x2 if value > 1 => {
+                    return Err(Http2Error::connection_error(
+                        Http2ErrorCode::ProtocolError,
+                        "ENABLE_PUSH must be 0 or 1",
+                    ));
                 }
                 0x3 => {
                     // MAX_CONCURRENT_STREAMS
@@ -816,6 +823,9 @@ where
             self.streams.set_receiving_headers(None);
             self.continuation_count = 0; // リセット
             self.decode_and_set_headers(stream_id, is_trailer)?;
+            if !is_trailer {
+                self.admit_client_stream(stream_id)?;
+            }
 
             // リクエストが完了したかチェック
             if end_stream {
@@ -912,6 +922,9 @@ where
             self.streams.set_receiving_headers(None);
             self.continuation_count = 0; // リセット
             self.decode_and_set_headers(stream_id, is_trailer)?;
+            if !is_trailer {
+                self.admit_client_stream(stream_id)?;
+            }
 
             if end_stream {
                 return Ok(Some(ProcessedRequest {
@@ -930,6 +943,26 @@ where
         Ok(None)
     }
 
+    /// 接続元ごとの同時ストリーム数の上限を確認する（F-153）
+    ///
+    /// HPACK の状態を揃えるためヘッダーブロックをデコードした後に判定し、超えたストリームは
+    /// REFUSED_STREAM で拒否する（クライアントは別の接続・後で再試行できる）。
+    fn admit_client_stream(&mut self, stream_id: u32) -> Http2Result<()> {
+        let Some(budget) = self.client_streams.as_mut() else {
+            return Ok(());
+        };
+        // 判定中のストリーム自身は含めない
+        let open = self.streams.active_stream_count().saturating_sub(1);
+        if budget.try_open(open) {
+            return Ok(());
+        }
+        Err(Http2Error::stream_error(
+            stream_id,
+            Http2ErrorCode::RefusedStream,
+            "Client stream limit exceeded",
+        ))
+    }
+
     /// ヘッダーブロックをデコードしてストリームに設定
     fn decode_and_set_headers(&mut self, stream_id: u32, is_trailer: bool) -> Http2Result<()> {
         let stream = self.streams.get(stream_id).ok_or_else(|| {
@@ -1081,15 +1114,13 @@ where
                             "Connection-specific header field",
                         ));
                     }
-                    b"te" => {
-                        // TE ヘッダーは "trailers" 以外禁止 (RFC 7540 8.1.2.2)
-                        if !header.value.eq_ignore_ascii_case(b"trailers") {
-                            return Err(Http2Error::stream_error(
-                                stream_id,
-                                Http2ErrorCode::ProtocolError,
-                                "TE header with value other than 'trailers'",
-                            ));
-                        }
+                    // TE ヘッダーは "trailers" 以外禁止 (RFC 7540 8.1.2.2)
+                    b"te" if !header.value.eq_ignore_ascii_case(b"trailers") => {
+                        return Err(Http2Error::stream_error(
+                            stream_id,
+                            Http2ErrorCode::ProtocolError,
+                            "TE header with value other than 'trailers'",
+                        ));
                     }
                     _ => {}
                 }
@@ -2067,8 +2098,13 @@ where
     }
 
     /// クローズ済みストリームをクリーンアップ（外部からアクセス可能）
+    ///
+    /// 接続元ごとの同時ストリーム数（F-153）からも閉じたストリームを戻す。
     pub fn cleanup_closed(&mut self) {
         self.streams.cleanup_closed();
+        if let Some(budget) = self.client_streams.as_mut() {
+            budget.sync(self.streams.active_stream_count());
+        }
     }
 
     /// DATA フレームを送信ウィンドウの許す範囲だけ連結バッファへ積む（await しない、F-116）。
@@ -200,6 +200,22 @@ impl Http2Error {
         )
     }
 
+    /// 別コネクションで安全に再試行できるか（F-134）
+    ///
+    /// REFUSED_STREAM（GOAWAY の `last_stream_id` 超過分を含む）はサーバーが
+    /// 処理していないことが保証される（RFC 9113 §8.7）。
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::StreamError(_, Http2ErrorCode::RefusedStream, _))
+    }
+
+    /// フラッド対策の上限超過（ENHANCE_YOUR_CALM）で接続を切ったか（F-152 の兆候）
+    pub fn is_flood(&self) -> bool {
+        matches!(
+            self,
+            Self::ConnectionError(Http2ErrorCode::EnhanceYourCalm, _)
+        )
+    }
+
     /// RST_STREAM を送信すべきストリームID
     pub fn rst_stream_id(&self) -> Option<u32> {
         match self {
@@ -10,6 +10,7 @@
 //! - `stream`: HTTP/2 ストリーム管理
 //! - `connection`: HTTP/2 コネクション管理
 //! - `error`: HTTP/2 エラー定義
+//! - `mux`: 上流 h2 コネクションのストリーム多重化（F-134）
 //!
 //! ## 使用例
 //!
@@ -28,11 +29,13 @@ pub mod connection;
 pub mod error;
 pub mod frame;
 pub mod hpack;
+pub mod mux;
 pub mod settings;
 pub mod stream;
 
 pub use client::{H2cClient, H2cResponse};
 pub use connection::{Http2Connection, ProcessedRequest};
 pub use error::{Http2Error, Http2ErrorCode};
+pub use mux::{H2MuxHandle, H2MuxRequest};
 pub use settings::Http2Settings;
 pub use stream::{Stream, StreamManager, StreamState};
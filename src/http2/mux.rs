@@ -0,0 +1,881 @@
+//! # HTTP/2 上流多重化コネクション（F-134）
+//!
+//! ALPN で h2 をネゴシエートした TLS バックエンドへの 1 本の接続上で、複数の
+//! リクエストをストリームとして同時に流す。HTTP/2 フロントエンド（F-116）と同じ
+//! アクターモデルで、接続は専用のドライバタスクが専有駆動し、リクエスト側は
+//! [`H2MuxHandle`] 経由でキューへ積んで応答チャネルを待つ（ロック・アトミックなし）。
+//!
+//! - 同時ストリーム数はサーバーの SETTINGS_MAX_CONCURRENT_STREAMS と upstream 設定の
+//!   上限（`h2_max_concurrent_streams`）の小さい方。SETTINGS の更新にも追従する
+//! - GOAWAY 受信後は新規ストリームを開かず、プールからも外れる。`last_stream_id` より
+//!   大きいストリームとキュー上の未送信リクエストは REFUSED_STREAM で失敗させ、
+//!   呼び出し側が別接続で再試行できるようにする（[`Http2Error::is_retryable`]）
+//! - ストリームが無い状態が `idle_timeout` 続くと接続を閉じる
+//! - 応答ボディはバッファするため、`max_response_body` を超えたストリームは
+//!   RST_STREAM(CANCEL) で打ち切って失敗させる（upstream 設定の `max_response_body_bytes`）
+
+use crate::runtime::handle::AsRawFd;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::http2::client::{H2cClient, H2cResponse};
+use crate::http2::error::{Http2Error, Http2ErrorCode, Http2Result};
+use crate::http2::frame::Frame;
+use crate::runtime::io::{AsyncReadRent, AsyncWriteRentExt, BufferedReadState};
+use crate::stream_channel::{channel, Notify, Sender};
+
+/// 多重化接続へ投入するリクエスト
+#[derive(Clone)]
+pub struct H2MuxRequest {
+    pub method: Vec<u8>,
+    pub path: Vec<u8>,
+    pub authority: Vec<u8>,
+    pub headers: Vec<(Vec<u8>, Vec<u8>)>,
+    pub body: Vec<u8>,
+    /// 応答ボディの上限（バイト）
+    pub max_response_body: usize,
+}
+
+type Reply = Http2Result<H2cResponse>;
+
+/// ハンドルとドライバで共有する状態（同一スレッド内でのみ使用）
+struct MuxShared {
+    /// 未送信リクエスト（ドライバが同時ストリーム数の範囲で取り出す）
+    queue: RefCell<VecDeque<(H2MuxRequest, Sender<Reply>)>>,
+    /// 送信済みで応答待ちのストリーム数
+    active: Cell<usize>,
+    /// 実効同時ストリーム数（SETTINGS と設定上限の小さい方）
+    max_concurrent: Cell<usize>,
+    /// GOAWAY 受信済み（新規ストリーム不可）
+    goaway: Cell<bool>,
+    /// ドライバ終了済み
+    closed: Cell<bool>,
+    /// ハンドル → ドライバの起床通知
+    notify: Notify,
+}
+
+/// 多重化接続へのハンドル（`Clone` で共有、プールに保持する）
+#[derive(Clone)]
+pub struct H2MuxHandle {
+    shared: Rc<MuxShared>,
+}
+
+impl H2MuxHandle {
+    fn new(max_concurrent: usize) -> Self {
+        Self {
+            shared: Rc::new(MuxShared {
+                queue: RefCell::new(VecDeque::new()),
+                active: Cell::new(0),
+                max_concurrent: Cell::new(max_concurrent.max(1)),
+                goaway: Cell::new(false),
+                closed: Cell::new(false),
+                notify: Notify::new(),
+            }),
+        }
+    }
+
+    /// 新規リクエストを受け付けられるか（GOAWAY 受信・切断済みなら false）
+    #[inline]
+    pub fn is_usable(&self) -> bool {
+        !self.shared.goaway.get() && !self.shared.closed.get()
+    }
+
+    /// 応答待ち + 未送信のリクエスト数
+    #[inline]
+    pub fn in_flight(&self) -> usize {
+        self.shared.active.get() + self.shared.queue.borrow().len()
+    }
+
+    /// 実効同時ストリーム数
+    #[inline]
+    pub fn max_concurrent_streams(&self) -> usize {
+        self.shared.max_concurrent.get()
+    }
+
+    /// 同時ストリーム数に空きがあるか
+    #[inline]
+    pub fn has_capacity(&self) -> bool {
+        self.is_usable() && self.in_flight() < self.max_concurrent_streams()
+    }
+
+    /// リクエストを送信して応答（ボディ・トレイラー全体）を待つ
+    ///
+    /// 同時ストリーム数の上限に達している間はキューで待つ。Future を drop した
+    /// 場合、送信済みストリームの応答は破棄される。
+    pub async fn send_request(&self, request: H2MuxRequest) -> Reply {
+        if !self.is_usable() {
+            return Err(refused(0, "connection is draining"));
+        }
+        let (tx, rx) = channel(1);
+        self.shared.queue.borrow_mut().push_back((request, tx));
+        self.shared.notify.notify();
+        match rx.recv().await {
+            Some(reply) => reply,
+            None => Err(Http2Error::ConnectionClosed),
+        }
+    }
+}
+
+/// 再試行可能な拒否エラー（REFUSED_STREAM）
+fn refused(stream_id: u32, msg: &str) -> Http2Error {
+    Http2Error::stream_error(stream_id, Http2ErrorCode::RefusedStream, msg)
+}
+
+/// ハンドシェイク済みクライアントからドライバタスクを起動してハンドルを返す
+///
+/// `stream_cap` は upstream 設定の同時ストリーム上限（サーバー通知値とで小さい方を使う）。
+pub fn spawn<S>(client: H2cClient<S>, stream_cap: u32, idle_timeout: Duration) -> H2MuxHandle
+where
+    S: AsyncReadRent + AsyncWriteRentExt + AsRawFd + BufferedReadState + Unpin + 'static,
+{
+    let driver = MuxDriver::new(&client, stream_cap);
+    let handle = H2MuxHandle {
+        shared: driver.shared.clone(),
+    };
+    crate::system::spawn_with_panic_catch(driver.run(client, idle_timeout));
+    handle
+}
+
+/// 送信済みストリームの状態
+struct MuxStream {
+    reply: Sender<Reply>,
+    response: H2cResponse,
+    headers_received: bool,
+    /// 未送信のリクエストボディ（フロー制御待ち）
+    body: Vec<u8>,
+    body_sent: usize,
+    /// ストリームレベル送信ウィンドウ
+    send_window: i64,
+    /// WINDOW_UPDATE 未返却の受信バイト数
+    recv_unacked: u32,
+    /// 応答ボディの上限（バイト）
+    max_response_body: usize,
+}
+
+/// 接続を専有駆動するドライバ
+struct MuxDriver {
+    shared: Rc<MuxShared>,
+    streams: HashMap<u32, MuxStream>,
+    /// upstream 設定の同時ストリーム上限
+    stream_cap: usize,
+    /// CONTINUATION 待ちのヘッダブロック `(stream_id, block, end_stream)`
+    partial_headers: Option<(u32, Vec<u8>, bool)>,
+}
+
+impl MuxDriver {
+    fn new<S>(client: &H2cClient<S>, stream_cap: u32) -> Self
+    where
+        S: AsyncReadRent + AsyncWriteRentExt + Unpin,
+    {
+        let handle = H2MuxHandle::new(Self::effective_limit(
+            client.peer_max_concurrent_streams(),
+            stream_cap as usize,
+        ));
+        Self {
+            shared: handle.shared,
+            streams: HashMap::new(),
+            stream_cap: (stream_cap as usize).max(1),
+            partial_headers: None,
+        }
+    }
+
+    #[inline]
+    fn effective_limit(peer: u32, cap: usize) -> usize {
+        (peer as usize).min(cap).max(1)
+    }
+
+    fn sync_active(&self) {
+        self.shared.active.set(self.streams.len());
+    }
+
+    async fn run<S>(mut self, mut client: H2cClient<S>, idle_timeout: Duration)
+    where
+        S: AsyncReadRent + AsyncWriteRentExt + AsRawFd + BufferedReadState + Unpin,
+    {
+        if let Err(e) = self.drive(&mut client, idle_timeout).await {
+            ftlog::debug!("[h2-upstream] connection closed: {}", e);
+        }
+        // Drop で残りのストリーム・キューを失敗させる
+    }
+
+    async fn drive<S>(
+        &mut self,
+        client: &mut H2cClient<S>,
+        idle_timeout: Duration,
+    ) -> Http2Result<()>
+    where
+        S: AsyncReadRent + AsyncWriteRentExt + AsRawFd + BufferedReadState + Unpin,
+    {
+        let fd = client.get_ref().as_raw_fd();
+        loop {
+            self.open_streams(client).await?;
+            self.flush_bodies(client).await?;
+            if self.shared.goaway.get() && self.streams.is_empty() {
+                return Ok(());
+            }
+
+            let readable =
+                if client.has_buffered_frame() || client.get_ref().has_buffered_read_data() {
+                    true
+                } else if self.streams.is_empty() && self.shared.queue.borrow().is_empty() {
+                    match crate::runtime::time::timeout(
+                        idle_timeout,
+                        crate::stream_channel::readable_or_notify(fd, &self.shared.notify),
+                    )
+                    .await
+                    {
+                        Ok(readable) => readable,
+                        Err(_) => return Ok(()), // アイドルタイムアウト
+                    }
+                } else {
+                    crate::stream_channel::readable_or_notify(fd, &self.shared.notify).await
+                };
+
+            if readable {
+                let frame = client.read_frame().await?;
+                self.on_frame(client, frame).await?;
+            }
+        }
+    }
+
+    /// キューから同時ストリーム数の範囲でリクエストを取り出して HEADERS を送る
+    async fn open_streams<S>(&mut self, client: &mut H2cClient<S>) -> Http2Result<()>
+    where
+        S: AsyncReadRent + AsyncWriteRentExt + Unpin,
+    {
+        while !self.shared.goaway.get()
+            && self.streams.len() < self.shared.max_concurrent.get()
+            && client.is_reusable()
+        {
+            let Some((req, reply)) = self.shared.queue.borrow_mut().pop_front() else {
+                break;
+            };
+            let stream_id = client.next_stream_id;
+            client.next_stream_id += 2;
+
+            let headers: Vec<(&[u8], &[u8])> = req
+                .headers
+                .iter()
+                .map(|(n, v)| (n.as_slice(), v.as_slice()))
+                .collect();
+            let end_stream = req.body.is_empty();
+            let frame = client.encode_request_headers(
+                stream_id,
+                &req.method,
+                &req.path,
+                &req.authority,
+                &headers,
+                end_stream,
+            );
+            let frame = match frame {
+                Ok(f) => f,
+                Err(e) => {
+                    // HPACK エンコーダの状態が壊れた可能性があるため接続ごと終了する
+                    let _ = reply.try_send(Err(Http2Error::HpackEncode(e.to_string())));
+                    return Err(e);
+                }
+            };
+            client.write_all(frame).await?;
+
+            self.streams.insert(
+                stream_id,
+                MuxStream {
+                    reply,
+                    response: H2cResponse {
+                        status: 0,
+                        headers: Vec::new(),
+                        body: Vec::new(),
+                        trailers: Vec::new(),
+                    },
+                    headers_received: false,
+                    body: req.body,
+                    body_sent: 0,
+                    send_window: client.remote_settings.initial_window_size as i64,
+                    recv_unacked: 0,
+                    max_response_body: req.max_response_body,
+                },
+            );
+        }
+        // ストリーム ID 枯渇時は以降のリクエストを別接続へ回す
+        if !client.is_reusable() {
+            self.shared.goaway.set(true);
+        }
+        self.sync_active();
+        Ok(())
+    }
+
+    /// 送信ウィンドウの範囲で保留中のリクエストボディを DATA として送る
+    async fn flush_bodies<S>(&mut self, client: &mut H2cClient<S>) -> Http2Result<()>
+    where
+        S: AsyncReadRent + AsyncWriteRentExt + Unpin,
+    {
+        let max_frame = client.remote_settings.max_frame_size as usize;
+        let ids: Vec<u32> = self
+            .streams
+            .iter()
+            .filter(|(_, s)| s.body_sent < s.body.len())
+            .map(|(id, _)| *id)
+            .collect();
+        for sid in ids {
+            while let Some(stream) = self.streams.get_mut(&sid) {
+                let remaining = stream.body.len() - stream.body_sent;
+                let window = stream.send_window.min(client.conn_send_window as i64);
+                if remaining == 0 || window <= 0 {
+                    break;
+                }
+                let n = remaining.min(max_frame).min(window as usize);
+                let start = stream.body_sent;
+                let last = start + n == stream.body.len();
+                let frame =
+                    client
+                        .frame_encoder
+                        .encode_data(sid, &stream.body[start..start + n], last);
+                stream.body_sent += n;
+                stream.send_window -= n as i64;
+                client.conn_send_window -= n as i32;
+                if last {
+                    stream.body = Vec::new();
+                    stream.body_sent = 0;
+                }
+                client.write_all(frame).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// ストリームを完了させて応答を返す
+    fn complete(&mut self, stream_id: u32, result: Http2Result<()>) {
+        if let Some(stream) = self.streams.remove(&stream_id) {
+            match result {
+                Ok(()) => {
+                    let _ = stream.reply.try_send(Ok(stream.response));
+                }
+                Err(e) => {
+                    let _ = stream.reply.try_send(Err(e));
+                }
+            }
+        }
+        self.sync_active();
+    }
+
+    /// ヘッダブロックをデコードしてストリームへ反映する
+    fn on_header_block<S>(
+        &mut self,
+        client: &mut H2cClient<S>,
+        stream_id: u32,
+        block: &[u8],
+        end_stream: bool,
+    ) -> Http2Result<()>
+    where
+        S: AsyncReadRent + AsyncWriteRentExt + Unpin,
+    {
+        // 未知ストリーム宛てでも HPACK の動的テーブルを進めるため必ずデコードする
+        let headers = client
+            .hpack_decoder
+            .decode(block)
+            .map_err(|e| Http2Error::compression_error(e.to_string()))?;
+        let Some(stream) = self.streams.get_mut(&stream_id) else {
+            return Ok(());
+        };
+        if !stream.headers_received {
+            let mut informational = false;
+            for header in headers {
+                if header.name == b":status" {
+                    let status = std::str::from_utf8(&header.value)
+                        .ok()
+                        .and_then(|s| s.parse::<u16>().ok())
+                        .unwrap_or(0);
+                    // 1xx は最終応答ではないので読み捨てる
+                    informational = (100..200).contains(&status);
+                    stream.response.status = status;
+                } else if !header.name.starts_with(b":") && !informational {
+                    stream.response.headers.push((header.name, header.value));
+                }
+            }
+            stream.headers_received = !informational;
+        } else {
+            for header in headers {
+                stream.response.trailers.push((header.name, header.value));
+            }
+        }
+        if end_stream {
+            self.complete(stream_id, Ok(()));
+        }
+        Ok(())
+    }
+
+    async fn on_frame<S>(&mut self, client: &mut H2cClient<S>, frame: Frame) -> Http2Result<()>
+    where
+        S: AsyncReadRent + AsyncWriteRentExt + Unpin,
+    {
+        if let Some((sid, _, _)) = &self.partial_headers {
+            let sid = *sid;
+            match frame {
+                Frame::Continuation {
+                    stream_id,
+                    end_headers,
+                    header_block,
+                } if stream_id == sid => {
+                    if let Some((_, block, _)) = self.partial_headers.as_mut() {
+                        block.extend_from_slice(&header_block);
+                    }
+                    if end_headers {
+                        if let Some((sid, block, end_stream)) = self.partial_headers.take() {
+                            self.on_header_block(client, sid, &block, end_stream)?;
+                        }
+                    }
+                    return Ok(());
+                }
+                _ => return Err(Http2Error::protocol_error("Expected CONTINUATION frame")),
+            }
+        }
+
+        match frame {
+            Frame::Headers {
+                stream_id,
+                end_stream,
+                end_headers,
+                header_block,
+                ..
+            } => {
+                if end_headers {
+                    self.on_header_block(client, stream_id, &header_block, end_stream)?;
+                } else {
+                    self.partial_headers = Some((stream_id, header_block, end_stream));
+                }
+            }
+            Frame::Data {
+                stream_id,
+                end_stream,
+                data,
+            } => {
+                let len = data.len() as u32;
+                // 接続レベル受信ウィンドウ（未知ストリーム宛ても消費される）
+                client.conn_recv_window -= len as i32;
+                let conn_window = client.local_settings.connection_window_size as i32;
+                if client.conn_recv_window < conn_window / 2 {
+                    let increment = conn_window - client.conn_recv_window;
+                    let wu = client
+                        .frame_encoder
+                        .encode_window_update(0, increment as u32);
+                    client.write_all(wu).await?;
+                    client.conn_recv_window += increment;
+                }
+                let stream_window = client.local_settings.initial_window_size;
+                let mut stream_wu = None;
+                if let Some(stream) = self.streams.get_mut(&stream_id) {
+                    if stream.response.body.len() + data.len() > stream.max_response_body {
+                        let rst = client
+                            .frame_encoder
+                            .encode_rst_stream(stream_id, Http2ErrorCode::Cancel as u32);
+                        client.write_all(rst).await?;
+                        self.complete(
+                            stream_id,
+                            Err(Http2Error::stream_error(
+                                stream_id,
+                                Http2ErrorCode::Cancel,
+                                "response body too large",
+                            )),
+                        );
+                        return Ok(());
+                    }
+                    stream.response.body.extend_from_slice(&data);
+                    stream.recv_unacked += len;
+                    if !end_stream && stream.recv_unacked >= stream_window / 2 {
+                        stream_wu = Some(stream.recv_unacked);
+                        stream.recv_unacked = 0;
+                    }
+                }
+                if let Some(increment) = stream_wu {
+                    let wu = client
+                        .frame_encoder
+                        .encode_window_update(stream_id, increment);
+                    client.write_all(wu).await?;
+                }
+                if end_stream {
+                    self.complete(stream_id, Ok(()));
+                }
+            }
+            Frame::WindowUpdate {
+                stream_id,
+                increment,
+            } => {
+                if stream_id == 0 {
+                    client.apply_window_update(0, increment);
+                } else if let Some(stream) = self.streams.get_mut(&stream_id) {
+                    stream.send_window += increment as i64;
+                }
+            }
+            Frame::Settings {
+                ack: false,
+                settings,
+            } => {
+                for &(id, value) in &settings {
+                    match id {
+                        0x1 => client.set_peer_header_table_size(value),
+                        0x3 => {
+                            client.remote_settings.max_concurrent_streams = value;
+                            self.shared
+                                .max_concurrent
+                                .set(Self::effective_limit(value, self.stream_cap));
+                        }
+                        0x4 => {
+                            // 初期ウィンドウの変更は既存ストリームへ差分で反映（RFC 9113 §6.9.2）
+                            let delta =
+                                value as i64 - client.remote_settings.initial_window_size as i64;
+                            for stream in self.streams.values_mut() {
+                                stream.send_window += delta;
+                            }
+                            client.remote_settings.initial_window_size = value;
+                        }
+                        0x5 => {
+                            client.frame_encoder.set_max_frame_size(value);
+                            client.remote_settings.max_frame_size = value;
+                        }
+                        _ => {}
+                    }
+                }
+                let ack = client.frame_encoder.encode_settings_ack();
+                client.write_all(ack).await?;
+            }
+            Frame::Ping { ack: false, data } => {
+                let ack = client.frame_encoder.encode_ping(&data, true);
+                client.write_all(ack).await?;
+            }
+            Frame::RstStream {
+                stream_id,
+                error_code,
+            } => {
+                self.complete(
+                    stream_id,
+                    Err(Http2Error::stream_closed(stream_id, error_code)),
+                );
+            }
+            Frame::GoAway {
+                last_stream_id,
+                error_code,
+                ..
+            } => {
+                ftlog::debug!(
+                    "[h2-upstream] GOAWAY received (last_stream_id={}, error_code={})",
+                    last_stream_id,
+                    error_code
+                );
+                self.shared.goaway.set(true);
+                let refused_ids: Vec<u32> = self
+                    .streams
+                    .keys()
+                    .copied()
+                    .filter(|&sid| sid > last_stream_id)
+                    .collect();
+                for sid in refused_ids {
+                    self.complete(sid, Err(refused(sid, "stream refused by GOAWAY")));
+                }
+                let queued: Vec<_> = self.shared.queue.borrow_mut().drain(..).collect();
+                for (_, reply) in queued {
+                    let _ = reply.try_send(Err(refused(0, "connection received GOAWAY")));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Drop for MuxDriver {
+    fn drop(&mut self) {
+        self.shared.closed.set(true);
+        self.shared.active.set(0);
+        // 応答チャネルの送信端を drop すると待機側は ConnectionClosed を受け取る
+        self.streams.clear();
+        self.shared.queue.borrow_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http2::hpack::HpackEncoder;
+    use crate::http2::Http2Settings;
+    use crate::runtime::buf::{IoBuf, IoBufMut};
+    use crate::runtime::io::{AsyncWriteRent, BufResult};
+    use crate::stream_channel::TryRecv;
+    use std::future::Future;
+    use std::io;
+
+    /// 書き込みを記録するだけのモックストリーム（読み込みは常に EOF）
+    struct SinkStream {
+        written: Vec<u8>,
+    }
+
+    impl AsyncReadRent for SinkStream {
+        async fn read<T: IoBufMut>(&mut self, buf: T) -> BufResult<usize, T> {
+            (Ok(0), buf)
+        }
+    }
+
+    impl AsyncWriteRent for SinkStream {
+        async fn write<T: IoBuf>(&mut self, buf: T) -> BufResult<usize, T> {
+            let len = buf.bytes_init();
+            // SAFETY: read_ptr()..len は IoBuf の初期化済み領域。
+            let slice = unsafe { std::slice::from_raw_parts(buf.read_ptr(), len) };
+            self.written.extend_from_slice(slice);
+            (Ok(len), buf)
+        }
+
+        async fn shutdown(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn drive<F: Future>(mut fut: F) -> F::Output {
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake, Waker};
+        struct NoopWake;
+        impl Wake for NoopWake {
+            fn wake(self: Arc<Self>) {}
+        }
+        let waker = Waker::from(Arc::new(NoopWake));
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = unsafe { std::pin::Pin::new_unchecked(&mut fut) };
+        loop {
+            if let Poll::Ready(v) = fut.as_mut().poll(&mut cx) {
+                return v;
+            }
+        }
+    }
+
+    fn new_driver(peer_max: Option<u32>, cap: u32) -> (MuxDriver, H2cClient<SinkStream>) {
+        let mut client = H2cClient::new(
+            SinkStream {
+                written: Vec::new(),
+            },
+            Http2Settings::default(),
+        )
+        .with_scheme(b"https");
+        if let Some(v) = peer_max {
+            client.remote_settings.max_concurrent_streams = v;
+        }
+        (MuxDriver::new(&client, cap), client)
+    }
+
+    /// キューへ直接リクエストを積み、応答受信端を返す
+    fn enqueue(driver: &MuxDriver, path: &str) -> crate::stream_channel::Receiver<Reply> {
+        let (tx, rx) = channel(1);
+        driver.shared.queue.borrow_mut().push_back((
+            H2MuxRequest {
+                method: b"GET".to_vec(),
+                path: path.as_bytes().to_vec(),
+                authority: b"backend".to_vec(),
+                headers: Vec::new(),
+                body: Vec::new(),
+                max_response_body: 1024,
+            },
+            tx,
+        ));
+        rx
+    }
+
+    fn response_frames(hpack: &mut HpackEncoder, sid: u32, body: &[u8]) -> Vec<Frame> {
+        let block = hpack
+            .encode(&[(b":status", b"200", false)])
+            .expect("encode response headers");
+        vec![
+            Frame::Headers {
+                stream_id: sid,
+                end_stream: false,
+                end_headers: true,
+                priority: None,
+                header_block: block,
+            },
+            Frame::Data {
+                stream_id: sid,
+                end_stream: true,
+                data: body.to_vec(),
+            },
+        ]
+    }
+
+    fn take(rx: &crate::stream_channel::Receiver<Reply>) -> Option<Reply> {
+        match rx.try_recv() {
+            TryRecv::Item(r) => Some(r),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn interleaved_responses_are_routed_to_their_streams() {
+        let (mut driver, mut client) = new_driver(None, 100);
+        let rx1 = enqueue(&driver, "/one");
+        let rx3 = enqueue(&driver, "/two");
+        drive(driver.open_streams(&mut client)).unwrap();
+        assert_eq!(driver.streams.len(), 2, "both requests multiplexed");
+        assert_eq!(driver.shared.active.get(), 2);
+
+        // ストリーム 3 の応答が 1 より先に完了しても正しく振り分けられる
+        let mut hpack = HpackEncoder::new(4096);
+        let mut frames = response_frames(&mut hpack, 3, b"two");
+        frames.extend(response_frames(&mut hpack, 1, b"one"));
+        for f in frames {
+            drive(driver.on_frame(&mut client, f)).unwrap();
+        }
+
+        let r1 = take(&rx1).expect("reply for stream 1").expect("ok");
+        let r3 = take(&rx3).expect("reply for stream 3").expect("ok");
+        assert_eq!((r1.status, r1.body.as_slice()), (200, &b"one"[..]));
+        assert_eq!((r3.status, r3.body.as_slice()), (200, &b"two"[..]));
+        assert_eq!(driver.shared.active.get(), 0);
+    }
+
+    #[test]
+    fn concurrency_follows_peer_settings_and_local_cap() {
+        let (mut driver, mut client) = new_driver(Some(1), 100);
+        assert_eq!(driver.shared.max_concurrent.get(), 1);
+        let _rx1 = enqueue(&driver, "/a");
+        let _rx2 = enqueue(&driver, "/b");
+        drive(driver.open_streams(&mut client)).unwrap();
+        assert_eq!(driver.streams.len(), 1, "peer limit of 1 stream");
+        assert_eq!(driver.shared.queue.borrow().len(), 1);
+
+        // サーバーが上限を引き上げても設定上限（2）で頭打ち
+        let (mut driver, mut client) = new_driver(None, 2);
+        drive(driver.on_frame(
+            &mut client,
+            Frame::Settings {
+                ack: false,
+                settings: vec![(0x3, 50)],
+            },
+        ))
+        .unwrap();
+        assert_eq!(driver.shared.max_concurrent.get(), 2);
+        let _a = enqueue(&driver, "/a");
+        let _b = enqueue(&driver, "/b");
+        let _c = enqueue(&driver, "/c");
+        drive(driver.open_streams(&mut client)).unwrap();
+        assert_eq!(driver.streams.len(), 2);
+    }
+
+    #[test]
+    fn goaway_refuses_unprocessed_streams_and_drains() {
+        let (mut driver, mut client) = new_driver(None, 100);
+        let handle = H2MuxHandle {
+            shared: driver.shared.clone(),
+        };
+        let rx1 = enqueue(&driver, "/a");
+        let rx3 = enqueue(&driver, "/b");
+        drive(driver.open_streams(&mut client)).unwrap();
+        let rx_queued = enqueue(&driver, "/c");
+
+        drive(driver.on_frame(
+            &mut client,
+            Frame::GoAway {
+                last_stream_id: 1,
+                error_code: 0,
+                debug_data: Vec::new(),
+            },
+        ))
+        .unwrap();
+        assert!(!handle.is_usable(), "GOAWAY removes the handle from use");
+
+        let Err(err) = take(&rx3).expect("stream 3 refused") else {
+            panic!("stream 3 must be refused");
+        };
+        assert!(err.is_retryable(), "refused stream must be retryable");
+        let Err(err) = take(&rx_queued).expect("queued refused") else {
+            panic!("queued request must be refused");
+        };
+        assert!(err.is_retryable());
+        assert!(take(&rx1).is_none(), "stream 1 is still processed");
+
+        let mut hpack = HpackEncoder::new(4096);
+        for f in response_frames(&mut hpack, 1, b"ok") {
+            drive(driver.on_frame(&mut client, f)).unwrap();
+        }
+        assert_eq!(take(&rx1).unwrap().unwrap().body, b"ok");
+    }
+
+    #[test]
+    fn request_body_respects_stream_send_window() {
+        let (mut driver, mut client) = new_driver(None, 100);
+        client.remote_settings.initial_window_size = 10;
+        let (tx, _rx) = channel(1);
+        driver.shared.queue.borrow_mut().push_back((
+            H2MuxRequest {
+                method: b"POST".to_vec(),
+                path: b"/upload".to_vec(),
+                authority: b"backend".to_vec(),
+                headers: Vec::new(),
+                body: vec![b'x'; 25],
+                max_response_body: 1024,
+            },
+            tx,
+        ));
+        drive(driver.open_streams(&mut client)).unwrap();
+        drive(driver.flush_bodies(&mut client)).unwrap();
+        assert_eq!(driver.streams[&1].body_sent, 10, "window limits DATA");
+
+        drive(driver.on_frame(
+            &mut client,
+            Frame::WindowUpdate {
+                stream_id: 1,
+                increment: 100,
+            },
+        ))
+        .unwrap();
+        drive(driver.flush_bodies(&mut client)).unwrap();
+        assert!(driver.streams[&1].body.is_empty(), "body fully sent");
+        // HEADERS(9+n) + DATA(9+10) + DATA(9+15)
+        let written = client.get_ref().written.len();
+        assert!(written > 9 + 19 + 24, "frames written: {written}");
+    }
+
+    #[test]
+    fn oversized_response_body_resets_the_stream() {
+        let (mut driver, mut client) = new_driver(None, 100);
+        let rx1 = enqueue(&driver, "/big");
+        let rx3 = enqueue(&driver, "/small");
+        drive(driver.open_streams(&mut client)).unwrap();
+
+        let mut hpack = HpackEncoder::new(4096);
+        let mut frames = response_frames(&mut hpack, 1, b"");
+        frames.pop();
+        for end_stream in [false, true] {
+            frames.push(Frame::Data {
+                stream_id: 1,
+                end_stream,
+                data: vec![b'x'; 600],
+            });
+        }
+        let before = client.get_ref().written.len();
+        for f in frames {
+            drive(driver.on_frame(&mut client, f)).unwrap();
+        }
+        let Err(err) = take(&rx1).expect("stream 1 failed") else {
+            panic!("oversized response must fail");
+        };
+        assert!(!err.is_retryable());
+        assert!(!driver.streams.contains_key(&1));
+        // RST_STREAM(CANCEL) を送っている
+        assert!(client.get_ref().written[before..].ends_with(&[0, 0, 0, 0x8]));
+
+        // 同じ接続の他のストリームは影響を受けない
+        for f in response_frames(&mut hpack, 3, b"ok") {
+            drive(driver.on_frame(&mut client, f)).unwrap();
+        }
+        assert_eq!(take(&rx3).unwrap().unwrap().body, b"ok");
+    }
+
+    #[test]
+    fn driver_drop_closes_handle_and_fails_waiters() {
+        let (driver, _client) = new_driver(None, 100);
+        let handle = H2MuxHandle {
+            shared: driver.shared.clone(),
+        };
+        let rx = enqueue(&driver, "/a");
+        drop(driver);
+        assert!(!handle.is_usable());
+        assert!(matches!(rx.try_recv(), TryRecv::Closed));
+    }
+}
@@ -0,0 +1,998 @@
+//! # HTTP/3 上流クライアント（F-135）
+//!
+//! quiche のクライアントコネクションで TLS バックエンドへ HTTP/3（QUIC）で接続する。
+//! HTTP/2 上流（F-134、`http2::mux`）と同じアクターモデルで、1 本の QUIC 接続を専用の
+//! ドライバタスクが専有駆動し、リクエスト側は [`H3ClientHandle`] 経由でキューへ積んで
+//! 応答チャネルを待つ（ロック・アトミックなし）。
+//!
+//! - UDP 送受信は HTTP/3 サーバーと同じ [`QuicUdpSocket`]（Linux では GSO 送信 / GRO 受信）
+//! - 同時ストリーム数はサーバーの MAX_STREAMS（残りクレジット）と upstream 設定の上限
+//!   （`h2_max_concurrent_streams`）の小さい方
+//! - セッションチケットを保存しておき、次回接続で 0-RTT 再開する。0-RTT 中は
+//!   リプレイされても安全なメソッド（GET / HEAD / OPTIONS）のみ送り、それ以外は
+//!   ハンドシェイク完了まで待たせる
+//! - GOAWAY 受信後は新規ストリームを開かず、GOAWAY の ID 以上のストリームと
+//!   キュー上の未送信リクエストは [`H3UpstreamError::Refused`] で失敗させる
+//!   （呼び出し側が別接続で再試行できる）
+//! - ストリームが無い状態が `idle_timeout` 続くと接続を閉じる
+//! - 応答ボディはバッファするため、`max_response_body` を超えたストリームは
+//!   H3_REQUEST_CANCELLED で打ち切って失敗させる（upstream 設定の `max_response_body_bytes`）
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use ftlog::debug;
+use quiche::h3::{self, NameValue};
+use quiche::ConnectionId;
+
+use crate::http3_server::BackendProxyResult;
+use crate::stream_channel::{channel, Notify, Sender};
+use crate::tls_provider::{SecureRandom, SystemRandom};
+use crate::udp::QuicUdpSocket;
+
+/// 送信する QUIC パケットの最大サイズ（経路 MTU 探索なしで安全な値）
+const MAX_DATAGRAM_SIZE: usize = 1350;
+/// 1 回の GSO 送信にまとめる最大パケット数
+const MAX_GSO_SEGMENTS: usize = 16;
+/// 受信バッファ（GRO で集約されたデータグラムを受けられる大きさ）
+const RECV_BUF_SIZE: usize = 64 * 1024;
+/// recv_body 1 回分の読み出しサイズ
+const BODY_CHUNK: usize = 16 * 1024;
+/// QUIC のアイドルタイムアウト（ミリ秒）。プール側の `idle_timeout` より長くしておく
+const QUIC_IDLE_TIMEOUT_MS: u64 = 120_000;
+/// H3_REQUEST_CANCELLED（RFC 9114 §8.1。応答が不要になったリクエスト）
+const H3_REQUEST_CANCELLED: u64 = 0x10c;
+
+/// HTTP/3 上流へ投入するリクエスト
+#[derive(Clone)]
+pub struct H3Request {
+    pub method: Vec<u8>,
+    pub path: Vec<u8>,
+    pub authority: Vec<u8>,
+    /// 通常ヘッダー（hop-by-hop ヘッダー・Host は送信時に除去し、名前は小文字化する）
+    pub headers: Vec<(Vec<u8>, Vec<u8>)>,
+    pub body: Vec<u8>,
+    /// 応答ボディの上限（バイト）
+    pub max_response_body: usize,
+}
+
+/// HTTP/3 上流のエラー
+#[derive(Debug)]
+pub enum H3UpstreamError {
+    /// サーバーが処理せずに拒否した（GOAWAY 後・接続のドレイン中）。別接続で再試行してよい
+    Refused(&'static str),
+    /// QUIC / TLS ハンドシェイクに失敗した（UDP 不通を含む）
+    Handshake(String),
+    /// ストリームまたは接続の失敗（サーバーが処理した可能性がある）
+    Stream(String),
+}
+
+impl H3UpstreamError {
+    /// 別接続で安全に再試行できるか
+    #[inline]
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, H3UpstreamError::Refused(_))
+    }
+}
+
+impl std::fmt::Display for H3UpstreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            H3UpstreamError::Refused(msg) => write!(f, "request refused: {}", msg),
+            H3UpstreamError::Handshake(msg) => write!(f, "handshake failed: {}", msg),
+            H3UpstreamError::Stream(msg) => write!(f, "stream error: {}", msg),
+        }
+    }
+}
+
+type Reply = Result<BackendProxyResult, H3UpstreamError>;
+
+/// ハンドルとドライバで共有する状態（同一スレッド内でのみ使用）
+struct H3Shared {
+    /// 未送信リクエスト（ドライバが同時ストリーム数の範囲で取り出す）
+    queue: RefCell<VecDeque<(H3Request, Sender<Reply>)>>,
+    /// 送信済みで応答待ちのストリーム数
+    active: Cell<usize>,
+    /// 実効同時ストリーム数（MAX_STREAMS と設定上限の小さい方）
+    max_concurrent: Cell<usize>,
+    /// GOAWAY 受信済み（新規ストリーム不可）
+    goaway: Cell<bool>,
+    /// ドライバ終了済み
+    closed: Cell<bool>,
+    /// 0-RTT 再開で確立した接続か
+    resumed: Cell<bool>,
+    /// サーバーから受け取ったセッションチケット（次回接続の 0-RTT 用）
+    session: RefCell<Option<Vec<u8>>>,
+    /// ハンドル → ドライバの起床通知
+    notify: Notify,
+}
+
+/// HTTP/3 上流接続へのハンドル（`Clone` で共有、プールに保持する）
+#[derive(Clone)]
+pub struct H3ClientHandle {
+    shared: Rc<H3Shared>,
+}
+
+impl H3ClientHandle {
+    fn new(max_concurrent: usize, resumed: bool) -> Self {
+        Self {
+            shared: Rc::new(H3Shared {
+                queue: RefCell::new(VecDeque::new()),
+                active: Cell::new(0),
+                max_concurrent: Cell::new(max_concurrent.max(1)),
+                goaway: Cell::new(false),
+                closed: Cell::new(false),
+                resumed: Cell::new(resumed),
+                session: RefCell::new(None),
+                notify: Notify::new(),
+            }),
+        }
+    }
+
+    /// 新規リクエストを受け付けられるか（GOAWAY 受信・切断済みなら false）
+    #[inline]
+    pub fn is_usable(&self) -> bool {
+        !self.shared.goaway.get() && !self.shared.closed.get()
+    }
+
+    /// 応答待ち + 未送信のリクエスト数
+    #[inline]
+    pub fn in_flight(&self) -> usize {
+        self.shared.active.get() + self.shared.queue.borrow().len()
+    }
+
+    /// 同時ストリーム数に空きがあるか
+    #[inline]
+    pub fn has_capacity(&self) -> bool {
+        self.is_usable() && self.in_flight() < self.shared.max_concurrent.get()
+    }
+
+    /// 0-RTT 再開で確立した接続か
+    #[inline]
+    pub fn is_resumed(&self) -> bool {
+        self.shared.resumed.get()
+    }
+
+    /// 受け取り済みのセッションチケット
+    pub fn session_ticket(&self) -> Option<Vec<u8>> {
+        self.shared.session.borrow().clone()
+    }
+
+    /// リクエストを送信して応答（ボディ・トレイラー全体）を待つ
+    ///
+    /// 同時ストリーム数の上限に達している間はキューで待つ。Future を drop した
+    /// 場合、送信済みストリームの応答は破棄される。
+    pub async fn send_request(&self, request: H3Request) -> Reply {
+        if !self.is_usable() {
+            return Err(H3UpstreamError::Refused("connection is draining"));
+        }
+        let (tx, rx) = channel(1);
+        self.shared.queue.borrow_mut().push_back((request, tx));
+        self.shared.notify.notify();
+        match rx.recv().await {
+            Some(reply) => reply,
+            None => Err(H3UpstreamError::Stream("connection closed".to_string())),
+        }
+    }
+}
+
+/// HTTP/3 へ転送しないリクエストヘッダー（疑似ヘッダー・接続固有ヘッダー・Host）
+#[inline]
+fn skip_forwarded_request_header(name: &[u8]) -> bool {
+    name.starts_with(b":")
+        || name.eq_ignore_ascii_case(b"connection")
+        || name.eq_ignore_ascii_case(b"keep-alive")
+        || name.eq_ignore_ascii_case(b"proxy-connection")
+        || name.eq_ignore_ascii_case(b"transfer-encoding")
+        || name.eq_ignore_ascii_case(b"upgrade")
+        || name.eq_ignore_ascii_case(b"te")
+        || name.eq_ignore_ascii_case(b"host")
+        || name.eq_ignore_ascii_case(b"expect")
+        || name.eq_ignore_ascii_case(b"content-length")
+}
+
+/// 0-RTT（リプレイされ得る早期データ）で送ってよいメソッドか
+#[inline]
+fn is_replay_safe(method: &[u8]) -> bool {
+    matches!(method, b"GET" | b"HEAD" | b"OPTIONS")
+}
+
+/// クライアント用 quiche 設定（検証あり / なし）を生成する
+fn new_client_config(verify_peer: bool) -> quiche::Result<quiche::Config> {
+    let mut config = quiche::Config::new(quiche::PROTOCOL_VERSION)?;
+    config.set_application_protos(h3::APPLICATION_PROTOCOL)?;
+    // 検証ありの場合はシステムのデフォルト CA ストアを使う（quiche が読み込む）
+    config.verify_peer(verify_peer);
+    config.set_max_idle_timeout(QUIC_IDLE_TIMEOUT_MS);
+    config.set_max_recv_udp_payload_size(MAX_DATAGRAM_SIZE);
+    config.set_max_send_udp_payload_size(MAX_DATAGRAM_SIZE);
+    config.set_initial_max_data(10 * 1024 * 1024);
+    config.set_initial_max_stream_data_bidi_local(2 * 1024 * 1024);
+    config.set_initial_max_stream_data_bidi_remote(2 * 1024 * 1024);
+    config.set_initial_max_stream_data_uni(1024 * 1024);
+    config.set_initial_max_streams_bidi(100);
+    config.set_initial_max_streams_uni(100);
+    config.set_disable_active_migration(true);
+    config.enable_early_data();
+    Ok(config)
+}
+
+thread_local! {
+    /// スレッドごとのクライアント設定 `[検証あり, 検証なし]`（SSL_CTX の生成は重いため再利用）
+    static CLIENT_CONFIGS: RefCell<[Option<quiche::Config>; 2]> = const { RefCell::new([None, None]) };
+}
+
+/// 新規 QUIC クライアント接続を生成する
+fn new_client_connection(
+    sni: &str,
+    local: SocketAddr,
+    peer: SocketAddr,
+    verify_peer: bool,
+) -> Result<quiche::Connection, H3UpstreamError> {
+    let mut scid = [0u8; quiche::MAX_CONN_ID_LEN];
+    SystemRandom::new()
+        .fill(&mut scid)
+        .map_err(|_| H3UpstreamError::Handshake("RNG error".to_string()))?;
+    let scid = ConnectionId::from_ref(&scid);
+    CLIENT_CONFIGS.with(|configs| {
+        let mut configs = configs.borrow_mut();
+        let slot = &mut configs[if verify_peer { 0 } else { 1 }];
+        if slot.is_none() {
+            *slot = Some(
+                new_client_config(verify_peer)
+                    .map_err(|e| H3UpstreamError::Handshake(format!("config: {}", e)))?,
+            );
+        }
+        let config = slot.as_mut().expect("client config initialized above");
+        quiche::connect(Some(sni), &scid, local, peer, config)
+            .map_err(|e| H3UpstreamError::Handshake(e.to_string()))
+    })
+}
+
+/// 上流へ QUIC 接続してハンドシェイクし、ドライバタスクを起動してハンドルを返す
+///
+/// `session` に前回のセッションチケットを渡すと 0-RTT 再開を試み、早期データを
+/// 送れる状態になった時点で返る（ハンドシェイクの残りはドライバが進める）。
+/// `handshake_timeout` 以内に応答が無い場合は UDP 不通とみなして
+/// [`H3UpstreamError::Handshake`] を返す。
+pub async fn connect(
+    peer: SocketAddr,
+    sni: &str,
+    verify_peer: bool,
+    session: Option<&[u8]>,
+    stream_cap: u32,
+    handshake_timeout: Duration,
+    idle_timeout: Duration,
+) -> Result<H3ClientHandle, H3UpstreamError> {
+    let handshake_err = |e: io::Error| H3UpstreamError::Handshake(e.to_string());
+    let bind_addr: SocketAddr = if peer.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = QuicUdpSocket::bind(bind_addr)
+        .await
+        .map_err(handshake_err)?;
+    // connect 済みにして他ホストからのデータグラムをカーネルで捨て、ICMP 到達不能を
+    // ECONNREFUSED として受け取れるようにする
+    socket.inner().connect(peer).map_err(handshake_err)?;
+
+    let mut conn = new_client_connection(sni, socket.local_addr(), peer, verify_peer)?;
+    if let Some(ticket) = session {
+        // 期限切れ・破損したチケットは無視してフルハンドシェイクする
+        let _ = conn.set_session(ticket);
+    }
+
+    let mut io_bufs = IoBuffers::new();
+    let deadline = Instant::now() + handshake_timeout;
+    loop {
+        flush_egress(&socket, &mut conn, &mut io_bufs)
+            .await
+            .map_err(handshake_err)?;
+        if conn.is_established() || conn.is_in_early_data() {
+            break;
+        }
+        if conn.is_closed() {
+            let reason = conn
+                .peer_error()
+                .or(conn.local_error())
+                .map(|e| format!("{:?}", e))
+                .unwrap_or_else(|| "connection closed".to_string());
+            return Err(H3UpstreamError::Handshake(reason));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(H3UpstreamError::Handshake("timeout".to_string()));
+        }
+        let remaining = deadline - now;
+        let wait = conn.timeout().map_or(remaining, |t| t.min(remaining));
+        match crate::runtime::time::timeout(
+            wait,
+            crate::runtime::tcp::wait_readable_fd(socket.as_raw_fd()),
+        )
+        .await
+        {
+            Ok(Ok(())) => recv_ingress(&socket, &mut conn, &mut io_bufs).map_err(handshake_err)?,
+            Ok(Err(e)) => return Err(handshake_err(e)),
+            Err(_) => conn.on_timeout(),
+        }
+    }
+
+    let h3_config =
+        h3::Config::new().map_err(|e| H3UpstreamError::Handshake(format!("h3 config: {}", e)))?;
+    let h3_conn = h3::Connection::with_transport(&mut conn, &h3_config)
+        .map_err(|e| H3UpstreamError::Handshake(format!("h3: {}", e)))?;
+
+    let stream_cap = (stream_cap as usize).max(1);
+    let handle = H3ClientHandle::new(
+        stream_cap.min((conn.peer_streams_left_bidi() as usize).max(1)),
+        conn.is_in_early_data() || conn.is_resumed(),
+    );
+    let driver = H3Driver {
+        shared: handle.shared.clone(),
+        socket,
+        conn,
+        h3: h3_conn,
+        streams: HashMap::new(),
+        stream_cap,
+        io_bufs,
+        session_saved: false,
+    };
+    crate::system::spawn_with_panic_catch(driver.run(idle_timeout));
+    Ok(handle)
+}
+
+/// 送受信用スクラッチバッファ（接続ごとに 1 組を使い回す）
+struct IoBuffers {
+    recv: Vec<u8>,
+    send: Vec<u8>,
+    /// `send` 内の各パケットの (start, len)
+    offsets: Vec<(usize, usize)>,
+    body: Vec<u8>,
+}
+
+impl IoBuffers {
+    fn new() -> Self {
+        Self {
+            recv: vec![0u8; RECV_BUF_SIZE],
+            send: vec![0u8; MAX_DATAGRAM_SIZE * MAX_GSO_SEGMENTS],
+            offsets: Vec::with_capacity(MAX_GSO_SEGMENTS),
+            body: vec![0u8; BODY_CHUNK],
+        }
+    }
+}
+
+/// quiche が生成した送信パケットをすべて送り出す
+///
+/// 同一サイズのパケットを最大 [`MAX_GSO_SEGMENTS`] 個まで連結して 1 回の GSO 送信に
+/// まとめる（2 個目以降は先頭パケットのサイズで切り出すため、短いパケットは末尾にのみ来る）。
+async fn flush_egress(
+    socket: &QuicUdpSocket,
+    conn: &mut quiche::Connection,
+    bufs: &mut IoBuffers,
+) -> io::Result<()> {
+    loop {
+        bufs.offsets.clear();
+        let mut used = 0;
+        let mut segment = 0;
+        let mut target = None;
+        while bufs.offsets.len() < MAX_GSO_SEGMENTS {
+            let limit = if segment == 0 {
+                MAX_DATAGRAM_SIZE
+            } else {
+                segment
+            };
+            match conn.send(&mut bufs.send[used..used + limit]) {
+                Ok((written, info)) => {
+                    target.get_or_insert(info.to);
+                    bufs.offsets.push((used, written));
+                    used += written;
+                    if segment == 0 {
+                        segment = written;
+                    } else if written < segment {
+                        break;
+                    }
+                }
+                Err(quiche::Error::Done) => break,
+                Err(e) => return Err(io::Error::other(e.to_string())),
+            }
+        }
+        let Some(target) = target else {
+            return Ok(());
+        };
+        send_batch(socket, &bufs.send[..used], &bufs.offsets, segment, target).await?;
+    }
+}
+
+#[cfg(target_os = "linux")]
+async fn send_batch(
+    socket: &QuicUdpSocket,
+    combined: &[u8],
+    offsets: &[(usize, usize)],
+    segment: usize,
+    target: SocketAddr,
+) -> io::Result<()> {
+    socket
+        .send_gso_combined_async(combined, offsets, segment as u16, target)
+        .await
+        .map(|_| ())
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn send_batch(
+    socket: &QuicUdpSocket,
+    combined: &[u8],
+    offsets: &[(usize, usize)],
+    _segment: usize,
+    target: SocketAddr,
+) -> io::Result<()> {
+    for &(start, len) in offsets {
+        socket
+            .send_to_slice_async(&combined[start..start + len], target)
+            .await?;
+    }
+    Ok(())
+}
+
+/// 受信済みのデータグラムをすべて quiche へ供給する（ノンブロッキング、EAGAIN で戻る）
+fn recv_ingress(
+    socket: &QuicUdpSocket,
+    conn: &mut quiche::Connection,
+    bufs: &mut IoBuffers,
+) -> io::Result<()> {
+    let local = socket.local_addr();
+    loop {
+        let (len, from, segment) = match recv_datagram(socket, &mut bufs.recv) {
+            Ok(v) => v,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        // GRO 適用時はセグメントサイズ境界で複数パケットに分割する
+        let segment = segment.filter(|&s| s > 0).unwrap_or(len);
+        let mut offset = 0;
+        while offset < len {
+            let end = (offset + segment).min(len);
+            let info = quiche::RecvInfo { from, to: local };
+            if let Err(e) = conn.recv(&mut bufs.recv[offset..end], info) {
+                debug!("[h3 upstream] dropping packet from {}: {}", from, e);
+            }
+            offset = end;
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn recv_datagram(
+    socket: &QuicUdpSocket,
+    buf: &mut [u8],
+) -> io::Result<(usize, SocketAddr, Option<usize>)> {
+    let r = socket.recv_with_gro_sync(buf)?;
+    Ok((
+        r.bytes_received,
+        r.from,
+        r.gro_segment_size.map(|s| s as usize),
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn recv_datagram(
+    socket: &QuicUdpSocket,
+    buf: &mut [u8],
+) -> io::Result<(usize, SocketAddr, Option<usize>)> {
+    let (len, from) = socket.inner().recv_from(buf)?;
+    Ok((len, from, None))
+}
+
+/// 送信済みストリームの状態
+struct H3Stream {
+    reply: Sender<Reply>,
+    response: BackendProxyResult,
+    headers_received: bool,
+    /// 未送信のリクエストボディ（フロー制御待ち）
+    body: Vec<u8>,
+    body_sent: usize,
+    /// 応答ボディの上限（バイト）
+    max_response_body: usize,
+}
+
+/// 接続を専有駆動するドライバ
+struct H3Driver {
+    shared: Rc<H3Shared>,
+    socket: QuicUdpSocket,
+    conn: quiche::Connection,
+    h3: h3::Connection,
+    streams: HashMap<u64, H3Stream>,
+    /// upstream 設定の同時ストリーム上限
+    stream_cap: usize,
+    io_bufs: IoBuffers,
+    /// セッションチケットを共有状態へ保存済みか
+    session_saved: bool,
+}
+
+impl H3Driver {
+    async fn run(mut self, idle_timeout: Duration) {
+        if let Err(e) = self.drive(idle_timeout).await {
+            debug!("[h3 upstream] connection closed: {}", e);
+        }
+        // Drop で残りのストリーム・キューを失敗させる
+    }
+
+    async fn drive(&mut self, idle_timeout: Duration) -> io::Result<()> {
+        let fd = self.socket.as_raw_fd();
+        let mut idle_since = Instant::now();
+        loop {
+            self.open_streams();
+            self.send_bodies();
+            self.poll_events()?;
+            flush_egress(&self.socket, &mut self.conn, &mut self.io_bufs).await?;
+            self.save_session();
+            self.sync_limits();
+
+            if self.conn.is_closed() {
+                return Ok(());
+            }
+            let idle = self.streams.is_empty() && self.shared.queue.borrow().is_empty();
+            if !idle {
+                idle_since = Instant::now();
+            } else if self.shared.goaway.get() || idle_since.elapsed() >= idle_timeout {
+                // ドレイン完了またはアイドルタイムアウト: CONNECTION_CLOSE を送って終了
+                self.shared.goaway.set(true);
+                let _ = self.conn.close(true, 0x100, b"");
+                flush_egress(&self.socket, &mut self.conn, &mut self.io_bufs).await?;
+                return Ok(());
+            }
+
+            let mut wait = self.conn.timeout();
+            if idle {
+                let idle_left = idle_timeout.saturating_sub(idle_since.elapsed());
+                wait = Some(wait.map_or(idle_left, |t| t.min(idle_left)));
+            }
+            let readable = match wait {
+                Some(wait) => crate::runtime::time::timeout(
+                    wait,
+                    crate::stream_channel::readable_or_notify(fd, &self.shared.notify),
+                )
+                .await
+                .ok(),
+                None => {
+                    Some(crate::stream_channel::readable_or_notify(fd, &self.shared.notify).await)
+                }
+            };
+            match readable {
+                Some(true) => recv_ingress(&self.socket, &mut self.conn, &mut self.io_bufs)?,
+                Some(false) => {}
+                // quiche 側のタイマー（再送・アイドル）は期限切れのものだけ処理される
+                None => self.conn.on_timeout(),
+            }
+        }
+    }
+
+    /// 実効同時ストリーム数と処理中ストリーム数を共有状態へ反映する
+    fn sync_limits(&self) {
+        let limit = (self.streams.len() + self.conn.peer_streams_left_bidi() as usize)
+            .min(self.stream_cap)
+            .max(1);
+        self.shared.max_concurrent.set(limit);
+        self.shared.active.set(self.streams.len());
+    }
+
+    /// 受け取ったセッションチケットを保存する（次回接続の 0-RTT 用）
+    fn save_session(&mut self) {
+        if self.session_saved || !self.conn.is_established() {
+            return;
+        }
+        if let Some(session) = self.conn.session() {
+            *self.shared.session.borrow_mut() = Some(session.to_vec());
+            self.session_saved = true;
+        }
+        if self.conn.is_resumed() {
+            self.shared.resumed.set(true);
+        }
+    }
+
+    /// キューから取り出したリクエストの HEADERS を送る
+    fn open_streams(&mut self) {
+        while !self.shared.goaway.get() && self.streams.len() < self.stream_cap {
+            let early = self.conn.is_in_early_data() && !self.conn.is_established();
+            let (req, reply) = {
+                let mut queue = self.shared.queue.borrow_mut();
+                match queue.front() {
+                    // 0-RTT 中は先頭が安全なメソッドのときだけ送る（順序は保つ）
+                    Some((req, _)) if early && !is_replay_safe(&req.method) => return,
+                    Some(_) => {}
+                    None => return,
+                }
+                queue.pop_front().expect("queue front checked above")
+            };
+
+            let mut headers = Vec::with_capacity(req.headers.len() + 4);
+            headers.push(h3::Header::new(b":method", &req.method));
+            headers.push(h3::Header::new(b":scheme", b"https"));
+            headers.push(h3::Header::new(b":authority", &req.authority));
+            headers.push(h3::Header::new(b":path", &req.path));
+            for (name, value) in &req.headers {
+                if skip_forwarded_request_header(name) {
+                    continue;
+                }
+                // HTTP/3 はヘッダー名の小文字必須（RFC 9114 §4.2）
+                headers.push(h3::Header::new(&name.to_ascii_lowercase(), value));
+            }
+            let fin = req.body.is_empty();
+            match self.h3.send_request(&mut self.conn, &headers, fin) {
+                Ok(stream_id) => {
+                    self.streams.insert(
+                        stream_id,
+                        H3Stream {
+                            reply,
+                            response: BackendProxyResult {
+                                status_code: 0,
+                                body: Vec::new(),
+                                headers: Vec::new(),
+                                trailers: Vec::new(),
+                            },
+                            headers_received: false,
+                            body: req.body,
+                            body_sent: 0,
+                            max_response_body: req.max_response_body,
+                        },
+                    );
+                }
+                // ストリームクレジット・フロー制御待ち: キューへ戻して次回に回す
+                Err(h3::Error::StreamBlocked)
+                | Err(h3::Error::TransportError(quiche::Error::StreamLimit)) => {
+                    self.shared.queue.borrow_mut().push_front((req, reply));
+                    return;
+                }
+                Err(h3::Error::FrameUnexpected) => {
+                    // GOAWAY 受信後は新規リクエストを開けない
+                    self.shared.goaway.set(true);
+                    let _ = reply.try_send(Err(H3UpstreamError::Refused("GOAWAY received")));
+                }
+                Err(e) => {
+                    let _ = reply.try_send(Err(H3UpstreamError::Stream(e.to_string())));
+                }
+            }
+        }
+    }
+
+    /// フロー制御の範囲で保留中のリクエストボディを送る
+    fn send_bodies(&mut self) {
+        let mut failed = Vec::new();
+        for (&stream_id, stream) in self.streams.iter_mut() {
+            if stream.body_sent >= stream.body.len() {
+                continue;
+            }
+            match self.h3.send_body(
+                &mut self.conn,
+                stream_id,
+                &stream.body[stream.body_sent..],
+                true,
+            ) {
+                Ok(written) => stream.body_sent += written,
+                Err(h3::Error::Done) | Err(h3::Error::StreamBlocked) => {}
+                Err(e) => failed.push((stream_id, e.to_string())),
+            }
+        }
+        for (stream_id, msg) in failed {
+            self.fail_stream(stream_id, H3UpstreamError::Stream(msg));
+        }
+    }
+
+    /// HTTP/3 イベントを処理して応答を組み立てる
+    fn poll_events(&mut self) -> io::Result<()> {
+        loop {
+            match self.h3.poll(&mut self.conn) {
+                Ok((stream_id, h3::Event::Headers { list, more_frames })) => {
+                    self.on_headers(stream_id, list);
+                    if !more_frames {
+                        self.finish_stream(stream_id);
+                    }
+                }
+                Ok((stream_id, h3::Event::Data)) => self.on_data(stream_id),
+                Ok((stream_id, h3::Event::Finished)) => self.finish_stream(stream_id),
+                Ok((stream_id, h3::Event::Reset(code))) => {
+                    self.fail_stream(
+                        stream_id,
+                        H3UpstreamError::Stream(format!("stream reset (0x{:x})", code)),
+                    );
+                }
+                Ok((goaway_id, h3::Event::GoAway)) => self.on_goaway(goaway_id),
+                Ok((_, h3::Event::PriorityUpdate)) => {}
+                Err(h3::Error::Done) => return Ok(()),
+                Err(e) => return Err(io::Error::other(e.to_string())),
+            }
+        }
+    }
+
+    fn on_headers(&mut self, stream_id: u64, list: Vec<h3::Header>) {
+        let Some(stream) = self.streams.get_mut(&stream_id) else {
+            return;
+        };
+        if stream.headers_received {
+            // 2 つ目のヘッダーブロックはトレイラー
+            stream
+                .response
+                .trailers
+                .extend(list.iter().map(|h| (h.name().to_vec(), h.value().to_vec())));
+            return;
+        }
+        let status = list
+            .iter()
+            .find(|h| h.name() == b":status")
+            .and_then(|h| std::str::from_utf8(h.value()).ok())
+            .and_then(|s| s.parse::<u16>().ok());
+        match status {
+            // 1xx の中間応答は読み飛ばす
+            Some(status) if status < 200 => {}
+            Some(status) => {
+                stream.response.status_code = status;
+                stream.response.headers = list
+                    .iter()
+                    .filter(|h| !h.name().starts_with(b":"))
+                    .map(|h| (h.name().to_vec(), h.value().to_vec()))
+                    .collect();
+                stream.headers_received = true;
+            }
+            None => self.fail_stream(
+                stream_id,
+                H3UpstreamError::Stream("missing :status".to_string()),
+            ),
+        }
+    }
+
+    fn on_data(&mut self, stream_id: u64) {
+        loop {
+            match self
+                .h3
+                .recv_body(&mut self.conn, stream_id, &mut self.io_bufs.body)
+            {
+                Ok(n) => {
+                    let Some(stream) = self.streams.get_mut(&stream_id) else {
+                        continue;
+                    };
+                    if stream.response.body.len() + n > stream.max_response_body {
+                        for direction in [quiche::Shutdown::Read, quiche::Shutdown::Write] {
+                            let _ = self.conn.stream_shutdown(
+                                stream_id,
+                                direction,
+                                H3_REQUEST_CANCELLED,
+                            );
+                        }
+                        self.fail_stream(
+                            stream_id,
+                            H3UpstreamError::Stream("response body too large".to_string()),
+                        );
+                        return;
+                    }
+                    stream
+                        .response
+                        .body
+                        .extend_from_slice(&self.io_bufs.body[..n]);
+                }
+                Err(h3::Error::Done) => return,
+                Err(e) => {
+                    self.fail_stream(stream_id, H3UpstreamError::Stream(e.to_string()));
+                    return;
+                }
+            }
+        }
+    }
+
+    fn finish_stream(&mut self, stream_id: u64) {
+        let Some(stream) = self.streams.remove(&stream_id) else {
+            return;
+        };
+        let reply = if stream.headers_received {
+            Ok(stream.response)
+        } else {
+            Err(H3UpstreamError::Stream(
+                "stream finished without response".to_string(),
+            ))
+        };
+        let _ = stream.reply.try_send(reply);
+    }
+
+    fn fail_stream(&mut self, stream_id: u64, err: H3UpstreamError) {
+        if let Some(stream) = self.streams.remove(&stream_id) {
+            let _ = stream.reply.try_send(Err(err));
+        }
+    }
+
+    /// GOAWAY: ID 以上のストリームとキュー上のリクエストは未処理として拒否する
+    fn on_goaway(&mut self, goaway_id: u64) {
+        self.shared.goaway.set(true);
+        let refused: Vec<u64> = self
+            .streams
+            .keys()
+            .copied()
+            .filter(|&id| id >= goaway_id)
+            .collect();
+        for stream_id in refused {
+            self.fail_stream(stream_id, H3UpstreamError::Refused("GOAWAY received"));
+        }
+        for (_, reply) in self.shared.queue.borrow_mut().drain(..) {
+            let _ = reply.try_send(Err(H3UpstreamError::Refused("GOAWAY received")));
+        }
+    }
+}
+
+impl Drop for H3Driver {
+    fn drop(&mut self) {
+        self.shared.closed.set(true);
+        self.shared.active.set(0);
+        // 応答チャネルの送信端を drop すると待機側は Stream("connection closed") を受け取る
+        self.streams.clear();
+        self.shared.queue.borrow_mut().clear();
+    }
+}
+
+/// 同期 UDP ソケットで HTTP/3 の GET を 1 回送り、応答ステータスを返す（ヘルスチェック用）
+///
+/// 専用ヘルスチェックスレッドから呼ぶ（イベントループ外）。`timeout` 以内に
+/// ハンドシェイクが完了しない場合は [`H3UpstreamError::Handshake`] を返す。
+pub fn probe_blocking(
+    peer: SocketAddr,
+    sni: &str,
+    path: &str,
+    verify_peer: bool,
+    timeout: Duration,
+) -> Result<u16, H3UpstreamError> {
+    let io_err = |established: bool, e: String| {
+        if established {
+            H3UpstreamError::Stream(e)
+        } else {
+            H3UpstreamError::Handshake(e)
+        }
+    };
+    let bind_addr: SocketAddr = if peer.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = std::net::UdpSocket::bind(bind_addr).map_err(|e| io_err(false, e.to_string()))?;
+    socket
+        .connect(peer)
+        .map_err(|e| io_err(false, e.to_string()))?;
+    let local = socket
+        .local_addr()
+        .map_err(|e| io_err(false, e.to_string()))?;
+    let mut conn = new_client_connection(sni, local, peer, verify_peer)?;
+
+    let mut recv_buf = vec![0u8; RECV_BUF_SIZE];
+    let mut out = vec![0u8; MAX_DATAGRAM_SIZE];
+    let mut h3_conn: Option<h3::Connection> = None;
+    let deadline = Instant::now() + timeout;
+    loop {
+        if h3_conn.is_none() && conn.is_established() {
+            let h3_config = h3::Config::new().map_err(|e| io_err(true, e.to_string()))?;
+            let mut h3c = h3::Connection::with_transport(&mut conn, &h3_config)
+                .map_err(|e| io_err(true, e.to_string()))?;
+            let headers = [
+                h3::Header::new(b":method", b"GET"),
+                h3::Header::new(b":scheme", b"https"),
+                h3::Header::new(b":authority", sni.as_bytes()),
+                h3::Header::new(b":path", path.as_bytes()),
+                h3::Header::new(b"user-agent", b"veil-health-check"),
+            ];
+            h3c.send_request(&mut conn, &headers, true)
+                .map_err(|e| io_err(true, e.to_string()))?;
+            h3_conn = Some(h3c);
+        }
+        if let Some(h3c) = h3_conn.as_mut() {
+            loop {
+                match h3c.poll(&mut conn) {
+                    Ok((_, h3::Event::Headers { list, .. })) => {
+                        let status = list
+                            .iter()
+                            .find(|h| h.name() == b":status")
+                            .and_then(|h| std::str::from_utf8(h.value()).ok())
+                            .and_then(|s| s.parse::<u16>().ok());
+                        if let Some(status) = status.filter(|&s| s >= 200) {
+                            let _ = conn.close(true, 0x100, b"");
+                            if let Ok((n, _)) = conn.send(&mut out) {
+                                let _ = socket.send(&out[..n]);
+                            }
+                            return Ok(status);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(h3::Error::Done) => break,
+                    Err(e) => return Err(io_err(true, e.to_string())),
+                }
+            }
+        }
+
+        loop {
+            match conn.send(&mut out) {
+                Ok((n, _)) => {
+                    socket
+                        .send(&out[..n])
+                        .map_err(|e| io_err(conn.is_established(), e.to_string()))?;
+                }
+                Err(quiche::Error::Done) => break,
+                Err(e) => return Err(io_err(conn.is_established(), e.to_string())),
+            }
+        }
+        if conn.is_closed() {
+            return Err(io_err(
+                conn.is_established(),
+                "connection closed".to_string(),
+            ));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(io_err(conn.is_established(), "timeout".to_string()));
+        }
+        let remaining = deadline - now;
+        let wait = conn
+            .timeout()
+            .map_or(remaining, |t| t.min(remaining))
+            .max(Duration::from_millis(1));
+        let _ = socket.set_read_timeout(Some(wait));
+        match socket.recv(&mut recv_buf) {
+            Ok(n) => {
+                let info = quiche::RecvInfo {
+                    from: peer,
+                    to: local,
+                };
+                let _ = conn.recv(&mut recv_buf[..n], info);
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                conn.on_timeout()
+            }
+            Err(e) => return Err(io_err(conn.is_established(), e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_safe_methods() {
+        assert!(is_replay_safe(b"GET"));
+        assert!(is_replay_safe(b"HEAD"));
+        assert!(is_replay_safe(b"OPTIONS"));
+        assert!(!is_replay_safe(b"POST"));
+        assert!(!is_replay_safe(b"PUT"));
+    }
+
+    #[test]
+    fn connection_specific_headers_are_not_forwarded() {
+        assert!(skip_forwarded_request_header(b"Connection"));
+        assert!(skip_forwarded_request_header(b"transfer-encoding"));
+        assert!(skip_forwarded_request_header(b"Host"));
+        assert!(skip_forwarded_request_header(b":path"));
+        assert!(!skip_forwarded_request_header(b"Content-Type"));
+        assert!(!skip_forwarded_request_header(b"x-request-id"));
+    }
+
+    #[test]
+    fn only_refused_errors_are_retryable() {
+        assert!(H3UpstreamError::Refused("GOAWAY received").is_retryable());
+        assert!(!H3UpstreamError::Handshake("timeout".to_string()).is_retryable());
+        assert!(!H3UpstreamError::Stream("reset".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn handle_capacity_follows_shared_state() {
+        let handle = H3ClientHandle::new(2, false);
+        assert!(handle.is_usable() && handle.has_capacity());
+        handle.shared.active.set(2);
+        assert!(!handle.has_capacity());
+        handle.shared.active.set(0);
+        handle.shared.goaway.set(true);
+        assert!(!handle.is_usable() && !handle.has_capacity());
+        assert!(handle.session_ticket().is_none());
+    }
+}
@@ -17,6 +17,7 @@
 //! - プロキシ機能（HTTPSバックエンドへのプロトコル変換）
 //! - ファイル配信、リダイレクト、メトリクス
 
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet, VecDeque};
 // CString / AsRawFd / FromRawFd は memfd 経由の証明書リロード（Linux / FreeBSD）でのみ
@@ -46,12 +47,18 @@ const REQ_RECV_CHUNK: usize = 16 * 1024;
 const REQ_CHAN_CAP: usize = 8;
 /// F-32: レスポンス断片チャネルの容量（アイテム数。バックプレッシャ）。
 const RESP_CHAN_CAP: usize = 8;
+/// F-138: 同時実行数の上限超過時の 503 応答ボディ。
+const CONCURRENCY_LIMITED_BODY: &[u8] = b"Service Unavailable";
+/// F-146: レートリミット超過時の 429 応答ボディ。
+const RATE_LIMITED_BODY: &[u8] = b"Too Many Requests";
+/// H3_REQUEST_REJECTED（RFC 9114 §8.1。処理せずに拒否したリクエスト、再試行可能）
+const H3_REQUEST_REJECTED: u64 = 0x10b;
 
 use ftlog::{debug, error, info, warn};
 
 use crate::config::{
-    resolve_http3_compression_config, AcceptedEncoding, Backend, CompressionConfig, ProxyTarget,
-    SecurityConfig, UpstreamGroup, CURRENT_CONFIG, SHUTDOWN_FLAG,
+    resolve_http3_compression_config, AcceptedEncoding, Backend, CompressionConfig, ExtraHeaders,
+    ProxyTarget, SecurityConfig, UpstreamGroup, CURRENT_CONFIG, SHUTDOWN_FLAG,
 };
 use crate::logging::log_access;
 use crate::metrics::{
@@ -60,6 +67,7 @@ use crate::metrics::{
 };
 use crate::pool::MAX_HEADER_SIZE;
 use crate::proxy::{check_security, SecurityCheckResult};
+use crate::timeouts::{UpstreamDeadline, UpstreamTimeouts};
 use crate::upstream::find_backend_unified;
 
 /// HTTP/3 リクエストヘッダブロックの近似サイズ（name + value の合計）。
@@ -530,6 +538,10 @@ struct Http3Handler {
     _conn_metric: Http3ActiveConnGuard,
     /// F-99: メトリクス計上中のリクエストストリーム ID（open/close の二重計上防止）
     metric_open_streams: HashSet<u64>,
+    /// F-153: 接続元ごとの同時 QUIC 接続数（Drop で戻す）
+    _client_permit: Option<crate::client_limits::ConnectionPermit>,
+    /// F-153: 接続元ごとの同時ストリーム数（`metric_open_streams` の数で同期する）
+    client_streams: Option<crate::client_limits::StreamBudget>,
 }
 
 impl Http3Handler {
@@ -539,6 +551,7 @@ impl Http3Handler {
         peer_addr: SocketAddr,
         notify: crate::http3_stream::H3Notify,
         backend_spawner: crate::http3_stream::BackendSpawner,
+        client_permit: Option<crate::client_limits::ConnectionPermit>,
     ) -> Self {
         Self {
             conn,
@@ -553,6 +566,8 @@ impl Http3Handler {
             backend_spawner,
             _conn_metric: Http3ActiveConnGuard::new(),
             metric_open_streams: HashSet::new(),
+            _client_permit: client_permit,
+            client_streams: crate::client_limits::stream_budget(peer_addr.ip()),
         }
     }
 
@@ -569,6 +584,34 @@ impl Http3Handler {
     fn metric_stream_close(&mut self, stream_id: u64) {
         if self.metric_open_streams.remove(&stream_id) {
             http3_stream_closed();
+            if let Some(budget) = self.client_streams.as_mut() {
+                budget.sync(self.metric_open_streams.len());
+            }
+        }
+    }
+
+    /// 接続元ごとの同時ストリーム数を確認する（F-153）。超えたら H3_REQUEST_REJECTED で拒否する
+    fn admit_client_stream(&mut self, stream_id: u64) -> bool {
+        let open = self.metric_open_streams.len();
+        match self.client_streams.as_mut() {
+            Some(budget) if !self.metric_open_streams.contains(&stream_id) => {
+                if budget.try_open(open) {
+                    return true;
+                }
+                let _ = self.conn.stream_shutdown(
+                    stream_id,
+                    quiche::Shutdown::Read,
+                    H3_REQUEST_REJECTED,
+                );
+                let _ = self.conn.stream_shutdown(
+                    stream_id,
+                    quiche::Shutdown::Write,
+                    H3_REQUEST_REJECTED,
+                );
+                self.stream_bodies.remove(&stream_id);
+                false
+            }
+            _ => true,
         }
     }
 
@@ -671,6 +714,9 @@ impl Http3Handler {
 
         // --- 新規 Headers を分類して振り分け ---
         for (stream_id, headers, more_frames) in new_headers {
+            if !self.admit_client_stream(stream_id) {
+                continue;
+            }
             // F-99: リクエストストリーム open をメトリクス計上
             self.metric_stream_open(stream_id);
             match self.classify(stream_id, &headers, more_frames) {
@@ -835,6 +881,8 @@ impl Http3Handler {
                 Instant::now(),
                 &self.client_ip,
                 "",
+                "",
+                "",
             );
             return Decision::Handled;
         }
@@ -923,6 +971,16 @@ impl Http3Handler {
         if is_grpc {
             return Decision::Buffer;
         }
+        // F-134 / F-135: HTTP/2・HTTP/3 上流はバッファ経路（ストリーミング経路は HTTP/1.1 専用）
+        if upstream_group.uses_multiplexed_upstream() {
+            return Decision::Buffer;
+        }
+
+        // F-155: CORS のルートはプリフライトをメソッド制限より前に応答し、応答ヘッダーも
+        // 上流の Access-Control-* と置き換えるためバッファ経路。
+        if backend.security().cors.is_some() {
+            return Decision::Buffer;
+        }
 
         // セキュリティチェック（ストリーミング適格は早期拒否でアップロードを溜めない）。
         let security = backend.security();
@@ -942,21 +1000,114 @@ impl Http3Handler {
                 Instant::now(),
                 &self.client_ip,
                 "",
+                "",
+                "",
             );
             return Decision::Handled;
         }
 
-        // サーバ選択（F-97: Consistent Hash header/cookie キー対応）。
-        let server = match upstream_group.select_with_header_fn(&self.client_ip, |name| {
-            headers
-                .iter()
-                .find(|h| h.name().eq_ignore_ascii_case(name))
-                .map(|h| h.value())
+        // F-147: 外部レートリミットサービスへの問い合わせは非同期のためバッファ経路で評価する。
+        // F-148: JWT 認証のあるルートも、クレームの転送をまとめて扱うためバッファ経路にする。
+        // F-149: 外部認可の問い合わせも非同期のためバッファ経路。
+        // F-150: OIDC ログイン（トークンエンドポイントへの問い合わせがある）もバッファ経路。
+        // F-151: Basic 認証（パスワードの検証をオフロードする）・API キー認証もバッファ経路。
+        // F-154: WAF もボディとまとめて検査するためバッファ経路。
+        if security.jwt.is_some()
+            || security.oidc.is_some()
+            || security.credential_auth.is_some()
+            || security.waf.is_some()
+            || security.ext_authz.is_some()
+            || security
+                .rate_limit
+                .as_ref()
+                .is_some_and(|limiter| limiter.has_global())
+        {
+            return Decision::Buffer;
+        }
+
+        // F-146: トークンバケット式レートリミット（許可時は RateLimit ヘッダーを応答へ追加）。
+        let mut extra_response_headers = Vec::new();
+        if let Some(decision) = security.rate_limit.as_ref().and_then(|limiter| {
+            limiter.check(&crate::rate_limit::RateLimitRequest {
+                client_ip: &self.client_ip,
+                path,
+                headers: &headers_raw,
+                claims: None,
+            })
         }) {
-            Some(s) => s.clone(),
-            None => return Decision::Buffer, // handle_request -> 502
+            if decision.is_limited() {
+                let _ = self.send_rate_limited(stream_id, &decision);
+                log_access(
+                    method,
+                    authority,
+                    path,
+                    user_agent,
+                    content_length as u64,
+                    429,
+                    RATE_LIMITED_BODY.len() as u64,
+                    Instant::now(),
+                    &self.client_ip,
+                    "",
+                    "",
+                    "",
+                );
+                return Decision::Handled;
+            }
+            for (name, value) in decision.headers() {
+                extra_response_headers.push((
+                    Bytes::from(name.to_ascii_lowercase()),
+                    Bytes::from(value.to_string()),
+                ));
+            }
+        }
+
+        // F-138: 適応型の同時実行数制限（上限超過は上流へ送らず即 503 + Retry-After）。
+        let concurrency_permit = match crate::proxy::acquire_concurrency_permit(&upstream_group) {
+            Ok(permit) => permit,
+            Err(retry_after) => {
+                let _ = self.send_concurrency_limited(stream_id, retry_after);
+                log_access(
+                    method,
+                    authority,
+                    path,
+                    user_agent,
+                    content_length as u64,
+                    503,
+                    CONCURRENCY_LIMITED_BODY.len() as u64,
+                    Instant::now(),
+                    &self.client_ip,
+                    "",
+                    "",
+                    "",
+                );
+                return Decision::Handled;
+            }
         };
 
+        // サーバ選択（F-97: Consistent Hash header/cookie/path キー対応、F-132: スティッキー Cookie）。
+        let (server, sticky_set_cookie) =
+            match upstream_group.select_with_affinity(&self.client_ip, path, |name| {
+                headers
+                    .iter()
+                    .find(|h| h.name().eq_ignore_ascii_case(name))
+                    .map(|h| h.value())
+            }) {
+                Some((s, cookie)) => (s.clone(), cookie),
+                None => return Decision::Buffer, // handle_request -> 502
+            };
+        // F-141: 送信元アドレスの指定があればこのリクエストの送信元を決める
+        let mut server = server;
+        if server.target.source.is_some() {
+            match server.target.bound_for(&self.client_ip) {
+                Ok(target) => server.target = target.into_owned(),
+                Err(_) => return Decision::Buffer, // handle_request -> 502
+            }
+        }
+
+        if let Some(cookie) = sticky_set_cookie {
+            extra_response_headers.push((Bytes::from_static(b"set-cookie"), Bytes::from(cookie)));
+        }
+
         // --- リクエスト head 構築 ---
         let client_encoding = accept_encoding
             .map(AcceptedEncoding::parse)
@@ -969,6 +1120,13 @@ impl Http3Handler {
         let use_tls = server.target.use_tls;
         let sni = server.target.sni().to_string();
         let tls_insecure = upstream_group.tls_insecure();
+        // F-139: リクエスト期限は HEADERS 受信時点から数える（ストリーミング経路は gRPC を扱わない）
+        let timeouts = match UpstreamDeadline::resolve(security, Instant::now(), false, None) {
+            Some(deadline) => security
+                .with_upstream_deadline(deadline)
+                .upstream_timeouts(),
+            None => security.upstream_timeouts(),
+        };
 
         Decision::Stream(crate::http3_stream::BackendTaskParams {
             server,
@@ -977,10 +1135,13 @@ impl Http3Handler {
             compression,
             client_encoding,
             timeout_secs: 30,
+            timeouts,
             max_request_body: security.max_request_body_size as u64,
             use_tls,
             sni,
             tls_insecure,
+            extra_response_headers,
+            concurrency: concurrency_permit.map(|permit| (upstream_group, permit)),
         })
     }
 
@@ -1088,6 +1249,8 @@ impl Http3Handler {
                 start_time,
                 &self.client_ip,
                 "",
+                "",
+                "",
             );
             return Ok(());
         }
@@ -1132,6 +1295,8 @@ impl Http3Handler {
                         start_time,
                         &self.client_ip,
                         "",
+                        "",
+                        "",
                     );
                     return Ok(());
                 }
@@ -1164,6 +1329,8 @@ impl Http3Handler {
                     start_time,
                     &self.client_ip,
                     "",
+                    "",
+                    "",
                 );
                 return Ok(());
             }
@@ -1246,6 +1413,8 @@ impl Http3Handler {
                         start_time,
                         &self.client_ip,
                         "",
+                        "",
+                        "",
                     );
                     return Ok(());
                 }
@@ -1267,6 +1436,8 @@ impl Http3Handler {
                     start_time,
                     &self.client_ip,
                     "",
+                    "",
+                    "",
                 );
                 return Ok(());
             }
@@ -1274,6 +1445,40 @@ impl Http3Handler {
 
         // セキュリティチェック
         let security = backend.security();
+
+        // F-155: CORS のプリフライトはメソッド制限・認証より前に上流へ送らずに応答する
+        if let Some(cors) = security.cors.as_ref() {
+            let ip_filter = security.ip_filter();
+            let preflight = if !ip_filter.is_configured() || ip_filter.is_allowed(&self.client_ip) {
+                cors.preflight(&method, &headers_raw)
+            } else {
+                None
+            };
+            if let Some(preflight) = preflight {
+                self.send_cors_preflight(stream_id, &preflight)?;
+                let user_agent_slice: &[u8] = if user_agent.is_empty() {
+                    &[]
+                } else {
+                    &user_agent
+                };
+                log_access(
+                    &method,
+                    &authority,
+                    &path,
+                    user_agent_slice,
+                    request_body.len() as u64,
+                    preflight.status(),
+                    preflight.body().len() as u64,
+                    start_time,
+                    &self.client_ip,
+                    "",
+                    "",
+                    "",
+                );
+                return Ok(());
+            }
+        }
+
         let check_result =
             check_security(security, &self.client_ip, &method, content_length, false);
 
@@ -1297,10 +1502,56 @@ impl Http3Handler {
                 start_time,
                 &self.client_ip,
                 "",
+                "",
+                "",
             );
             return Ok(());
         }
 
+        // 認証・レートリミット・CORS・WAF・外部認可
+        let checked = crate::request_checks::check_request(
+            security,
+            &crate::request_checks::RequestInfo {
+                client_ip: &self.client_ip,
+                method: &method,
+                path: &path,
+                host: &authority,
+                headers: &headers_raw,
+            },
+            |_| std::future::ready(Some(Cow::Borrowed(request_body))),
+        )
+        .await;
+        // アクセスログに記録する認証済みの利用者（`sub` クレーム）
+        let auth_user = crate::jwt_auth::principal(checked.claims.as_ref());
+        #[cfg(feature = "wasm")]
+        let jwt_claims = checked.claims;
+        let extra_headers = match checked.outcome {
+            Ok(extra) => extra,
+            Err(rejection) => {
+                self.send_rejection(stream_id, &rejection)?;
+                let user_agent_slice: &[u8] = if user_agent.is_empty() {
+                    &[]
+                } else {
+                    &user_agent
+                };
+                log_access(
+                    &method,
+                    &authority,
+                    &path,
+                    user_agent_slice,
+                    request_body.len() as u64,
+                    rejection.status(),
+                    rejection.body().len() as u64,
+                    start_time,
+                    &self.client_ip,
+                    "",
+                    "",
+                    &auth_user,
+                );
+                return Ok(());
+            }
+        };
+
         // WASM モジュール適用（B-38: リクエストヘッダ変更 + レスポンスヘッダ変更）
         #[cfg(feature = "wasm")]
         let mut wasm_modules_to_apply: Option<std::sync::Arc<Vec<String>>> = None;
@@ -1329,6 +1580,7 @@ impl Http3Handler {
                         .map(|h| (h.name().to_vec(), h.value().to_vec()))
                         .collect();
 
+                    let auth_claims = jwt_claims.clone().map(Arc::new);
                     let wasm_result = wasm_engine
                         .on_request_headers_with_modules(
                             &modules_to_apply,
@@ -1336,6 +1588,7 @@ impl Http3Handler {
                             &std::sync::Arc::from(method_str),
                             headers_vec,
                             &std::sync::Arc::from(self.client_ip.as_str()),
+                            auth_claims.as_ref(),
                             request_body.is_empty(),
                         )
                         .await;
@@ -1368,6 +1621,8 @@ impl Http3Handler {
                                 start_time,
                                 &self.client_ip,
                                 "",
+                                "",
+                                &auth_user,
                             );
                             return Ok(());
                         }
@@ -1387,33 +1642,69 @@ impl Http3Handler {
 
         // バックエンド処理
         let (status, resp_size) = match backend {
-            Backend::Proxy(upstream_group, _, path_compression, _buffering, _cache, _) => {
+            Backend::Proxy(upstream_group, security, path_compression, _buffering, _cache, _) => {
                 debug!("[HTTP/3] Starting proxy request to upstream group");
 
+                // F-139: リクエスト全体の期限（gRPC は受信した grpc-timeout も期限として扱う）
+                #[cfg(feature = "grpc")]
+                let is_grpc = Self::is_grpc_request(headers);
+                #[cfg(not(feature = "grpc"))]
+                let is_grpc = false;
+                let grpc_timeout = headers
+                    .iter()
+                    .find(|h| h.name().eq_ignore_ascii_case(b"grpc-timeout"))
+                    .map(|h| h.value());
+                let security =
+                    match UpstreamDeadline::resolve(&security, start_time, is_grpc, grpc_timeout) {
+                        Some(deadline) => Arc::new(security.with_upstream_deadline(deadline)),
+                        None => security,
+                    };
+
                 // HTTP/3専用圧縮設定を解決
                 // 優先順位: パス設定 > HTTP/3設定 > デフォルト
                 let config = CURRENT_CONFIG.load();
                 let effective_compression =
                     resolve_http3_compression_config(&path_compression, &config.http3_config);
 
-                let result = self
-                    .handle_proxy(
-                        stream_id,
-                        &upstream_group,
-                        &effective_compression,
-                        client_encoding,
-                        &method,
-                        &path,
-                        &prefix,
-                        headers,
-                        request_body,
-                        #[cfg(feature = "wasm")]
-                        wasm_modules_to_apply.as_ref(),
-                        #[cfg(feature = "wasm")]
-                        wasm_request_headers.as_deref(),
-                    )
-                    .await
-                    .unwrap_or((502, 11));
+                let result = if security.upstream_deadline.is_some_and(|d| d.is_expired()) {
+                    self.send_upstream_timeout(stream_id, &security)?
+                } else {
+                    // F-138: 適応型の同時実行数制限（上限超過は上流へ送らず即 503 + Retry-After）
+                    match crate::proxy::acquire_concurrency_permit(&upstream_group) {
+                        Ok(concurrency_permit) => {
+                            let result = self
+                                .handle_proxy(
+                                    stream_id,
+                                    &upstream_group,
+                                    &security,
+                                    &extra_headers,
+                                    &effective_compression,
+                                    client_encoding,
+                                    &method,
+                                    &path,
+                                    &prefix,
+                                    headers,
+                                    request_body,
+                                    #[cfg(feature = "wasm")]
+                                    wasm_modules_to_apply.as_ref(),
+                                    #[cfg(feature = "wasm")]
+                                    wasm_request_headers.as_deref(),
+                                )
+                                .await
+                                .unwrap_or((502, 11));
+                            crate::proxy::complete_concurrency_permit(
+                                &upstream_group,
+                                concurrency_permit,
+                                result.0,
+                            );
+                            result
+                        }
+                        Err(retry_after) => {
+                            self.send_concurrency_limited(stream_id, retry_after)?;
+                            (503, CONCURRENCY_LIMITED_BODY.len())
+                        }
+                    }
+                };
                 debug!(
                     "[HTTP/3] Proxy request completed: status={}, size={}",
                     result.0, result.1
@@ -1441,11 +1732,10 @@ impl Http3Handler {
                         (b"server", b"veil/http3"),
                     ];
 
-                    // セキュリティヘッダー追加
+                    // セキュリティヘッダー追加（リクエスト単位の応答ヘッダーを含む）
                     let security_headers: Vec<(Vec<u8>, Vec<u8>)> = security
-                        .add_response_headers
-                        .iter()
-                        .map(|(k, v)| (k.as_bytes().to_vec(), v.as_bytes().to_vec()))
+                        .response_headers(&extra_headers)
+                        .map(|(k, v)| (k.to_ascii_lowercase().into_bytes(), v.as_bytes().to_vec()))
                         .collect();
 
                     for (k, v) in &security_headers {
@@ -1465,6 +1755,7 @@ impl Http3Handler {
                     &path,
                     &prefix,
                     &security,
+                    &extra_headers,
                 )
                 .await
                 .unwrap_or((404, 9)),
@@ -1496,6 +1787,8 @@ impl Http3Handler {
             start_time,
             &self.client_ip,
             "",
+            "",
+            &auth_user,
         );
         Ok(())
     }
@@ -1652,6 +1945,113 @@ impl Http3Handler {
         result
     }
 
+    /// 同時実行数の上限超過時の 503 応答を送信（F-138、`Retry-After` 付き）
+    fn send_concurrency_limited(
+        &mut self,
+        stream_id: u64,
+        retry_after_secs: u64,
+    ) -> io::Result<()> {
+        let retry_after = retry_after_secs.to_string();
+        self.send_response(
+            stream_id,
+            503,
+            &[
+                (b"content-type", b"text/plain"),
+                (b"server", b"veil/http3"),
+                (b"retry-after", retry_after.as_bytes()),
+            ],
+            Some(CONCURRENCY_LIMITED_BODY),
+        )
+    }
+
+    /// レートリミット超過時の 429 を送信（RateLimit / Retry-After 付き、F-146）
+    fn send_rate_limited(
+        &mut self,
+        stream_id: u64,
+        decision: &crate::rate_limit::RateLimitDecision,
+    ) -> io::Result<()> {
+        let rejection_headers = decision.rejection_headers();
+        let mut headers: Vec<(&[u8], &[u8])> =
+            vec![(b"content-type", b"text/plain"), (b"server", b"veil/http3")];
+        headers.extend(
+            rejection_headers
+                .iter()
+                .map(|(n, v)| (n.as_slice(), v.as_slice())),
+        );
+        self.send_response(stream_id, 429, &headers, Some(RATE_LIMITED_BODY))
+    }
+
+    /// 認証・レートリミット・WAF・外部認可の拒否を送信（F-146〜F-151・F-154）
+    fn send_rejection(
+        &mut self,
+        stream_id: u64,
+        rejection: &crate::request_checks::Rejection,
+    ) -> io::Result<()> {
+        let rejection_headers = rejection.h2_headers();
+        let mut headers: Vec<(&[u8], &[u8])> = vec![(b"server", b"veil/http3")];
+        headers.extend(
+            rejection_headers
+                .iter()
+                .map(|(n, v)| (n.as_slice(), v.as_slice())),
+        );
+        self.send_response(
+            stream_id,
+            rejection.status(),
+            &headers,
+            Some(rejection.body()),
+        )
+    }
+
+    /// CORS のプリフライトへの応答を送信（204 / 403、F-155）
+    fn send_cors_preflight(
+        &mut self,
+        stream_id: u64,
+        preflight: &crate::cors::CorsPreflight,
+    ) -> io::Result<()> {
+        let preflight_headers = preflight.h2_headers();
+        let mut headers: Vec<(&[u8], &[u8])> = vec![(b"server", b"veil/http3")];
+        headers.extend(
+            preflight_headers
+                .iter()
+                .map(|(n, v)| (n.as_slice(), v.as_slice())),
+        );
+        let body = preflight.body();
+        self.send_response(
+            stream_id,
+            preflight.status(),
+            &headers,
+            (!body.is_empty()).then_some(body),
+        )
+    }
+
+    /// 上流タイムアウト・リクエスト期限切れの応答を送信（F-139）
+    ///
+    /// gRPC の期限（`grpc-timeout`）切れなら DEADLINE_EXCEEDED、それ以外は 504。
+    fn send_upstream_timeout(
+        &mut self,
+        stream_id: u64,
+        security: &SecurityConfig,
+    ) -> io::Result<(u16, usize)> {
+        #[cfg(feature = "grpc")]
+        if security
+            .upstream_deadline
+            .is_some_and(|d| d.is_grpc() && d.is_expired())
+        {
+            self.send_grpc_response(
+                stream_id,
+                &[],
+                None,
+                crate::grpc::GrpcStatusCode::DeadlineExceeded.as_u8() as u32,
+                Some("upstream deadline exceeded"),
+            )?;
+            return Ok((200, 0));
+        }
+        #[cfg(not(feature = "grpc"))]
+        let _ = security;
+        self.send_error_response(stream_id, 504, b"Gateway Timeout")?;
+        Ok((504, 15))
+    }
+
     /// gRPC リクエストかどうかを判定
     ///
     /// Content-Type ヘッダーが `application/grpc` で始まる場合にgRPCリクエストと判定。
@@ -1772,6 +2172,8 @@ impl Http3Handler {
         &mut self,
         stream_id: u64,
         upstream_group: &Arc<UpstreamGroup>,
+        security: &SecurityConfig,
+        extra: &ExtraHeaders,
         compression: &CompressionConfig,
         client_encoding: AcceptedEncoding,
         method: &[u8],
@@ -1782,22 +2184,33 @@ impl Http3Handler {
         #[cfg(feature = "wasm")] wasm_modules: Option<&std::sync::Arc<Vec<String>>>,
         #[cfg(feature = "wasm")] wasm_request_headers: Option<&[(Vec<u8>, Vec<u8>)]>,
     ) -> io::Result<(u16, usize)> {
-        // サーバー選択（F-97: Consistent Hash header/cookie キー対応）
-        let server = match upstream_group.select_with_header_fn(&self.client_ip, |name| {
-            headers
-                .iter()
-                .find(|h| h.name().eq_ignore_ascii_case(name))
-                .map(|h| h.value())
-        }) {
-            Some(s) => s,
-            None => {
+        // サーバー選択（F-97: Consistent Hash header/cookie/path キー対応、F-132: スティッキー Cookie）
+        let (server, sticky_set_cookie) =
+            match upstream_group.select_with_affinity(&self.client_ip, req_path, |name| {
+                headers
+                    .iter()
+                    .find(|h| h.name().eq_ignore_ascii_case(name))
+                    .map(|h| h.value())
+            }) {
+                Some(s) => s,
+                None => {
+                    self.send_error_response(stream_id, 502, b"Bad Gateway")?;
+                    return Ok((502, 11));
+                }
+            };
+
+        // F-141: 送信元アドレスの指定があればこのリクエストの送信元を決める
+        let target = match server.target.bound_for(&self.client_ip) {
+            Ok(target) => target,
+            Err(e) => {
+                warn!("[HTTP/3] Upstream source address error: {}", e);
                 self.send_error_response(stream_id, 502, b"Bad Gateway")?;
                 return Ok((502, 11));
             }
         };
+        let target = &*target;
 
         server.acquire();
-        let target = &server.target;
 
         // リクエストパス構築
         let path_str = std::str::from_utf8(req_path).unwrap_or("/");
@@ -1805,7 +2218,7 @@ impl Http3Handler {
 
         // 上流へ送るヘッダソース（WASM 変更後 or 生 H3 ヘッダ）
         #[cfg(feature = "wasm")]
-        let header_pairs: Vec<(Vec<u8>, Vec<u8>)> = if let Some(ov) = wasm_request_headers {
+        let mut header_pairs: Vec<(Vec<u8>, Vec<u8>)> = if let Some(ov) = wasm_request_headers {
             ov.iter()
                 .filter(|(n, _)| {
                     !n.starts_with(b":")
@@ -1828,7 +2241,7 @@ impl Http3Handler {
                 .collect()
         };
         #[cfg(not(feature = "wasm"))]
-        let header_pairs: Vec<(Vec<u8>, Vec<u8>)> = headers
+        let mut header_pairs: Vec<(Vec<u8>, Vec<u8>)> = headers
             .iter()
             .filter(|h| {
                 !h.name().starts_with(b":")
@@ -1839,6 +2252,14 @@ impl Http3Handler {
             .map(|h| (h.name().to_vec(), h.value().to_vec()))
             .collect();
 
+        // 除去対象のヘッダーを除き、リクエスト単位のヘッダー（認証で得たクレーム等）を加える
+        header_pairs.retain(|(name, _)| !security.is_removed_request_header(name, extra));
+        header_pairs.extend(extra.request_header_pairs());
+
+        // F-139: gRPC 期限の残り時間を grpc-timeout として転送する
+        crate::proxy::apply_grpc_timeout(&mut header_pairs, security);
+        let timeouts = security.upstream_timeouts();
+
         // gRPC はサービス/メソッドのフルパスを保持（B-39）
         #[cfg(feature = "grpc")]
         let is_grpc_req = header_pairs_indicate_grpc(&header_pairs);
@@ -1849,9 +2270,45 @@ impl Http3Handler {
             compute_upstream_request_path(path_str, prefix, &target.path_prefix, is_grpc_req);
         let final_path = final_path_owned.as_str();
 
+        // F-134: protocol = "h2" / "auto" の TLS 上流は ALPN h2 の多重化接続（None は HTTP/1.1 へ）
+        #[cfg(feature = "http2")]
+        let h2_tls_result = if target.uses_tls_h2() {
+            proxy_to_h2_tls_backend_async(
+                target,
+                method,
+                final_path.as_bytes(),
+                &header_pairs,
+                request_body,
+                upstream_group.tls_insecure(),
+                timeouts,
+            )
+            .await
+        } else {
+            None
+        };
+        #[cfg(not(feature = "http2"))]
+        let h2_tls_result: Option<io::Result<BackendProxyResult>> = None;
+        // F-135: protocol = "h3" の上流は QUIC の多重化接続（None は UDP 不通で HTTP/1.1 へ）
+        let h2_tls_result = if h2_tls_result.is_none() && target.uses_h3() {
+            proxy_to_h3_backend_async(
+                target,
+                method,
+                final_path.as_bytes(),
+                &header_pairs,
+                request_body,
+                upstream_group.tls_insecure(),
+                timeouts,
+            )
+            .await
+        } else {
+            h2_tls_result
+        };
+
         // B-39: H2C 上流（gRPC 等）
         let use_h2c = target.use_h2c || upstream_group.use_h2c();
-        let proxy_result = if use_h2c {
+        let proxy_result = if let Some(result) = h2_tls_result {
+            result
+        } else if use_h2c {
             #[cfg(feature = "http2")]
             {
                 proxy_to_h2c_backend_async(
@@ -1861,6 +2318,7 @@ impl Http3Handler {
                     &header_pairs,
                     request_body,
                     timeout_secs,
+                    timeouts,
                 )
                 .await
             }
@@ -1903,7 +2361,8 @@ impl Http3Handler {
             request.extend_from_slice(request_body);
 
             let tls_insecure = upstream_group.tls_insecure();
-            proxy_to_backend_async_with_tls(target, request, timeout_secs, tls_insecure).await
+            proxy_to_backend_async_with_tls(target, request, timeout_secs, timeouts, tls_insecure)
+                .await
         };
 
         server.release();
@@ -1959,11 +2418,16 @@ impl Http3Handler {
                 };
 
                 // レスポンスヘッダ + H2C trailers をマージ（B-39）
-                let owned_headers = merge_response_headers_and_trailers(
+                let mut owned_headers = merge_response_headers_and_trailers(
                     &resp_header_store,
                     &trailers,
                     should_compress.is_some(),
                 );
+                if let Some(cookie) = &sticky_set_cookie {
+                    owned_headers.push((b"set-cookie".to_vec(), cookie.as_bytes().to_vec()));
+                }
+                crate::proxy::apply_h2_security_response_headers(&mut owned_headers, security);
+                owned_headers.extend(extra.response_header_pairs());
 
                 let response_body = if let Some(enc) = should_compress {
                     compress_body_h3(&body, enc, compression)
@@ -2003,6 +2467,10 @@ impl Http3Handler {
                 self.send_response(stream_id, status_code, &resp_headers, Some(&response_body))?;
                 Ok((status_code, response_body.len()))
             }
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                warn!("[HTTP/3] Async backend proxy timeout: {}", e);
+                self.send_upstream_timeout(stream_id, security)
+            }
             Err(e) => {
                 warn!("[HTTP/3] Async backend proxy error: {}", e);
                 self.send_error_response(stream_id, 502, b"Bad Gateway")?;
@@ -2021,6 +2489,7 @@ impl Http3Handler {
         req_path: &[u8],
         prefix: &[u8],
         security: &SecurityConfig,
+        extra: &ExtraHeaders,
     ) -> io::Result<(u16, usize)> {
         let path_str = std::str::from_utf8(req_path).unwrap_or("/");
         let prefix_str = std::str::from_utf8(prefix).unwrap_or("");
@@ -2082,11 +2551,10 @@ impl Http3Handler {
             (b"server", b"veil/http3"),
         ];
 
-        // セキュリティヘッダー追加
+        // セキュリティヘッダー追加（リクエスト単位の応答ヘッダーを含む）
         let security_headers: Vec<(Vec<u8>, Vec<u8>)> = security
-            .add_response_headers
-            .iter()
-            .map(|(k, v)| (k.as_bytes().to_vec(), v.as_bytes().to_vec()))
+            .response_headers(extra)
+            .map(|(k, v)| (k.to_ascii_lowercase().into_bytes(), v.as_bytes().to_vec()))
             .collect();
 
         for (k, v) in &security_headers {
@@ -2816,6 +3284,7 @@ pub(crate) async fn proxy_to_backend_async_with_tls(
     target: &ProxyTarget,
     request: Vec<u8>,
     timeout_secs: u64,
+    timeouts: UpstreamTimeouts,
     tls_insecure: bool,
 ) -> io::Result<BackendProxyResult> {
     use crate::runtime::tcp::TcpStream;
@@ -2825,7 +3294,7 @@ pub(crate) async fn proxy_to_backend_async_with_tls(
     debug!("[HTTP/3] Async connecting to backend {}", addr);
 
     // 非同期TCP接続（タイムアウト付き）
-    let connect_future = TcpStream::connect_str(&addr);
+    let connect_future = TcpStream::connect_str_with(&addr, target.bind, target.socket.clone());
     let backend = match crate::runtime::time::timeout(
         Duration::from_secs(timeout_secs),
         connect_future,
@@ -2851,8 +3320,15 @@ pub(crate) async fn proxy_to_backend_async_with_tls(
 
     // TLSバックエンドの場合
     if target.use_tls {
-        return proxy_to_tls_backend_async(target, request, backend, timeout_secs, tls_insecure)
-            .await;
+        return proxy_to_tls_backend_async(
+            target,
+            request,
+            backend,
+            timeout_secs,
+            timeouts,
+            tls_insecure,
+        )
+        .await;
     }
 
     let fd = backend.as_raw_fd();
@@ -2875,13 +3351,16 @@ pub(crate) async fn proxy_to_backend_async_with_tls(
     debug!("[HTTP/3] Async request sent: {} bytes", written);
 
     // レスポンス受信（非同期）
+    // F-139: 応答を一括で受け取るため、交換の期限（既定はアイドル上限）で打ち切る
     let mut response = Vec::with_capacity(16384);
     let mut buf = vec![0u8; 8192];
-    let read_timeout = Duration::from_secs(timeout_secs);
-    let start_time = std::time::Instant::now();
+    let deadline = timeouts.exchange_deadline(std::time::Instant::now());
+    let mut timed_out = false;
 
     loop {
-        if start_time.elapsed() > read_timeout {
+        let remaining = UpstreamTimeouts::until(deadline);
+        if remaining.is_zero() {
+            timed_out = true;
             break;
         }
 
@@ -2889,20 +3368,26 @@ pub(crate) async fn proxy_to_backend_async_with_tls(
             Ok(0) => break,
             Ok(n) => response.extend_from_slice(&buf[..n]),
             Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
-                let remaining = read_timeout.saturating_sub(start_time.elapsed());
-                if remaining.is_zero() {
-                    break;
-                }
                 match crate::runtime::time::timeout(remaining, backend.readable()).await {
                     Ok(Ok(())) => continue,
                     Ok(Err(e)) if response.is_empty() => return Err(e),
-                    _ => break,
+                    Ok(Err(_)) => break,
+                    Err(_) => {
+                        timed_out = true;
+                        break;
+                    }
                 }
             }
             Err(e) if response.is_empty() => return Err(e),
             Err(_) => break,
         }
     }
+    if timed_out && find_header_end(&response).is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "Backend response timeout",
+        ));
+    }
 
     debug!("[HTTP/3] Async response received: {} bytes", response.len());
     parse_http_response(&response)
@@ -2918,6 +3403,7 @@ async fn proxy_to_tls_backend_async(
     request: Vec<u8>,
     tcp_stream: crate::runtime::tcp::TcpStream,
     timeout_secs: u64,
+    timeouts: UpstreamTimeouts,
     tls_insecure: bool,
 ) -> io::Result<BackendProxyResult> {
     // monoio TcpStream は不要（別スレッドで std::net::TcpStream を使うため）
@@ -2925,6 +3411,8 @@ async fn proxy_to_tls_backend_async(
 
     let skip_verify = tls_insecure;
     let addr = format!("{}:{}", target.host, target.port);
+    let bind = target.bind;
+    let socket = target.socket.clone();
     let sni_name = target
         .sni_name
         .as_deref()
@@ -2996,7 +3484,18 @@ async fn proxy_to_tls_backend_async(
         use std::io::Write;
         let result = (|| -> io::Result<BackendProxyResult> {
             let timeout = Duration::from_secs(timeout_secs);
-            let mut std_stream = std::net::TcpStream::connect(&addr as &str).map_err(|e| {
+            // F-141: 送信元アドレスの指定があれば bind してから connect する
+            // F-142: ソケットプロファイルも connect 前に適用する
+            let connected = if bind.is_some() || socket.is_some() {
+                crate::runtime::local_bind::connect_blocking(
+                    &addr,
+                    bind.as_ref(),
+                    socket.as_deref(),
+                )
+            } else {
+                std::net::TcpStream::connect(&addr as &str)
+            };
+            let mut std_stream = connected.map_err(|e| {
                 warn!("[HTTP/3] std backend connect error: {}", e);
                 e
             })?;
@@ -3025,7 +3524,8 @@ async fn proxy_to_tls_backend_async(
     });
 
     // try_recv でポーリング（バックエンドが同一ホスト上のため数 ms で完了）
-    let deadline = std::time::Instant::now() + Duration::from_secs(timeout_secs);
+    // F-139: 応答を一括で受け取るため、交換の期限（既定はアイドル上限）で打ち切る
+    let deadline = timeouts.exchange_deadline(std::time::Instant::now());
     loop {
         match rx.try_recv() {
             Ok(result) => return result,
@@ -3055,6 +3555,7 @@ async fn proxy_to_tls_backend_async(
     request: Vec<u8>,
     tcp_stream: crate::runtime::tcp::TcpStream,
     timeout_secs: u64,
+    timeouts: UpstreamTimeouts,
     tls_insecure: bool,
 ) -> io::Result<BackendProxyResult> {
     use rustls::ClientConfig;
@@ -3065,6 +3566,8 @@ async fn proxy_to_tls_backend_async(
 
     let skip_verify = tls_insecure;
     let addr = format!("{}:{}", target.host, target.port);
+    let bind = target.bind;
+    let socket = target.socket.clone();
     let sni_name = target
         .sni_name
         .as_deref()
@@ -3133,7 +3636,18 @@ async fn proxy_to_tls_backend_async(
         use std::io::{Read, Write};
         let result = (|| -> io::Result<BackendProxyResult> {
             let timeout = Duration::from_secs(timeout_secs);
-            let mut std_stream = std::net::TcpStream::connect(&addr as &str).map_err(|e| {
+            // F-141: 送信元アドレスの指定があれば bind してから connect する
+            // F-142: ソケットプロファイルも connect 前に適用する
+            let connected = if bind.is_some() || socket.is_some() {
+                crate::runtime::local_bind::connect_blocking(
+                    &addr,
+                    bind.as_ref(),
+                    socket.as_deref(),
+                )
+            } else {
+                std::net::TcpStream::connect(&addr as &str)
+            };
+            let mut std_stream = connected.map_err(|e| {
                 warn!("[HTTP/3] std backend connect error: {}", e);
                 e
             })?;
@@ -3153,7 +3667,8 @@ async fn proxy_to_tls_backend_async(
     });
 
     // try_recv でポーリング（バックエンドが同一ホスト上のため数 ms で完了）
-    let deadline = std::time::Instant::now() + Duration::from_secs(timeout_secs);
+    // F-139: 応答を一括で受け取るため、交換の期限（既定はアイドル上限）で打ち切る
+    let deadline = timeouts.exchange_deadline(std::time::Instant::now());
     loop {
         match rx.try_recv() {
             Ok(result) => return result,
@@ -3277,6 +3792,7 @@ async fn proxy_to_h2c_backend_async(
     headers: &[(Vec<u8>, Vec<u8>)],
     request_body: &[u8],
     timeout_secs: u64,
+    timeouts: UpstreamTimeouts,
 ) -> io::Result<BackendProxyResult> {
     use crate::http2::{H2cClient, Http2Settings};
     use crate::runtime::tcp::TcpStream;
@@ -3284,7 +3800,7 @@ async fn proxy_to_h2c_backend_async(
     let addr = format!("{}:{}", target.host, target.port);
     debug!("[HTTP/3] H2C connecting to backend {}", addr);
 
-    let connect_future = TcpStream::connect_str(&addr);
+    let connect_future = TcpStream::connect_str_with(&addr, None, target.socket.clone());
     let backend = match crate::runtime::time::timeout(
         Duration::from_secs(timeout_secs),
         connect_future,
@@ -3326,7 +3842,7 @@ async fn proxy_to_h2c_backend_async(
     let authority = target.host.as_bytes();
 
     let response = match crate::runtime::time::timeout(
-        Duration::from_secs(timeout_secs),
+        UpstreamTimeouts::until(timeouts.exchange_deadline(std::time::Instant::now())),
         client.send_request(method, path, authority, &headers_ref, body),
     )
     .await
@@ -3360,6 +3876,110 @@ async fn proxy_to_h2c_backend_async(
     })
 }
 
+/// ALPN h2 の TLS 上流へ多重化接続でリクエストを送る（F-134）
+///
+/// `protocol = "auto"` でサーバーが HTTP/1.1 を選んだ場合は None。
+#[cfg(feature = "http2")]
+async fn proxy_to_h2_tls_backend_async(
+    target: &ProxyTarget,
+    method: &[u8],
+    path: &[u8],
+    headers: &[(Vec<u8>, Vec<u8>)],
+    request_body: &[u8],
+    tls_insecure: bool,
+    timeouts: UpstreamTimeouts,
+) -> Option<io::Result<BackendProxyResult>> {
+    use crate::proxy::{h2_tls_upstream_request, H2UpstreamOutcome};
+
+    let pool_key = format!(
+        "{}:{}:{}:{}",
+        target.host,
+        target.port,
+        target.sni(),
+        if tls_insecure { "insecure" } else { "verify" }
+    );
+    let request = crate::http2::H2MuxRequest {
+        method: method.to_vec(),
+        path: path.to_vec(),
+        authority: target.sni().as_bytes().to_vec(),
+        headers: headers.to_vec(),
+        body: request_body.to_vec(),
+        max_response_body: target.max_response_body_bytes,
+    };
+    match h2_tls_upstream_request(
+        target,
+        tls_insecure,
+        crate::pool::CONNECT_TIMEOUT,
+        timeouts,
+        &pool_key,
+        request,
+    )
+    .await
+    {
+        H2UpstreamOutcome::Response(response) => Some(Ok(BackendProxyResult {
+            status_code: response.status,
+            body: response.body,
+            headers: response.headers,
+            trailers: response.trailers,
+        })),
+        H2UpstreamOutcome::Http1 => None,
+        H2UpstreamOutcome::Failed(504) => Some(Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "h2 upstream timeout",
+        ))),
+        H2UpstreamOutcome::Failed(_) => Some(Err(io::Error::other("h2 upstream error"))),
+    }
+}
+
+/// HTTP/3 上流へ多重化接続でリクエストを送る（F-135）
+///
+/// QUIC ハンドシェイクが成立せず TCP へフォールバックする場合は None。
+async fn proxy_to_h3_backend_async(
+    target: &ProxyTarget,
+    method: &[u8],
+    path: &[u8],
+    headers: &[(Vec<u8>, Vec<u8>)],
+    request_body: &[u8],
+    tls_insecure: bool,
+    timeouts: UpstreamTimeouts,
+) -> Option<io::Result<BackendProxyResult>> {
+    use crate::proxy::{h3_upstream_request, H3UpstreamOutcome};
+
+    let pool_key = format!(
+        "{}:{}:{}:{}",
+        target.host,
+        target.port,
+        target.sni(),
+        if tls_insecure { "insecure" } else { "verify" }
+    );
+    let request = crate::http3_client::H3Request {
+        method: method.to_vec(),
+        path: path.to_vec(),
+        authority: target.sni().as_bytes().to_vec(),
+        headers: headers.to_vec(),
+        body: request_body.to_vec(),
+        max_response_body: target.max_response_body_bytes,
+    };
+    match h3_upstream_request(
+        target,
+        tls_insecure,
+        crate::pool::CONNECT_TIMEOUT,
+        timeouts,
+        &pool_key,
+        request,
+    )
+    .await
+    {
+        H3UpstreamOutcome::Response(response) => Some(Ok(response)),
+        H3UpstreamOutcome::Fallback => None,
+        H3UpstreamOutcome::Failed(504) => Some(Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "h3 upstream timeout",
+        ))),
+        H3UpstreamOutcome::Failed(_) => Some(Err(io::Error::other("h3 upstream error"))),
+    }
+}
+
 /// コネクション管理（Rc<RefCell> で共有）
 type ConnectionMap = Rc<RefCell<HashMap<ConnectionId<'static>, Http3Handler>>>;
 
@@ -3998,6 +4618,27 @@ fn process_datagram_segments(
                         continue;
                     }
 
+                    // F-35 / F-152: ブロックリスト・自動遮断の IP は接続を受け入れない
+                    if crate::config::is_ip_blocked(from.ip()) {
+                        continue;
+                    }
+
+                    // F-153: 接続元 IP・プレフィックスごとの同時 QUIC 接続数・新規接続レート
+                    let client_permit = match crate::client_limits::acquire(
+                        from.ip(),
+                        crate::client_limits::ConnKind::Quic,
+                    ) {
+                        Ok(permit) => permit,
+                        Err(rejected) => {
+                            debug!(
+                                "[HTTP/3] Client limit {} reached, dropping connection from {}",
+                                rejected.label(),
+                                from
+                            );
+                            continue;
+                        }
+                    };
+
                     // 新規コネクション
                     let mut scid = [0u8; quiche::MAX_CONN_ID_LEN];
                     rng.fill(&mut scid)
@@ -4010,8 +4651,13 @@ fn process_datagram_segments(
 
                     debug!("[HTTP/3] New connection from {}", from);
 
-                    let handler =
-                        Http3Handler::new(conn, from, notify.clone(), backend_spawner.clone());
+                    let handler = Http3Handler::new(
+                        conn,
+                        from,
+                        notify.clone(),
+                        backend_spawner.clone(),
+                        client_permit,
+                    );
                     conns.insert(scid.clone(), handler);
 
                     prev_cid = Some(scid.clone());
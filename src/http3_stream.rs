@@ -41,8 +41,10 @@ use std::time::Duration;
 use bytes::Bytes;
 use ftlog::{debug, warn};
 
-use crate::config::UpstreamServer;
+use crate::config::{UpstreamGroup, UpstreamServer};
+use crate::resilience::ConcurrencyPermit;
 use crate::runtime::tcp::TcpStream;
+use crate::timeouts::UpstreamTimeouts;
 use crate::{AcceptedEncoding, CompressionConfig};
 
 // ============================================================================
@@ -404,6 +406,8 @@ pub(crate) struct BackendTaskParams {
     pub client_encoding: AcceptedEncoding,
     /// 接続/読み取りタイムアウト秒。
     pub timeout_secs: u64,
+    /// ルートの上流タイムアウトとリクエスト期限（F-139）。
+    pub timeouts: UpstreamTimeouts,
     /// リクエストボディ上限（0 = 無制限）。メインループ側の `ProxyStream` が強制する。
     pub max_request_body: u64,
     /// TLS バックエンドか（F-44: `https://` アップストリーム）。
@@ -412,6 +416,10 @@ pub(crate) struct BackendTaskParams {
     pub sni: String,
     /// 証明書検証をスキップするか（アップストリーム設定 `tls_insecure`）。
     pub tls_insecure: bool,
+    /// レスポンス head に追記するヘッダ（F-132 のスティッキー Cookie 等）。
+    pub extra_response_headers: RespHeaders,
+    /// 適応型の同時実行数制限の枠と所属グループ（F-138、制限が有効な場合のみ）。
+    pub concurrency: Option<(std::sync::Arc<UpstreamGroup>, ConcurrencyPermit)>,
 }
 
 /// バックエンドタスクを起動するスポーナ（F-46: 型付きタスクプール）。
@@ -444,6 +452,7 @@ async fn backend_task(
     notify: H3Notify,
 ) {
     let server = params.server;
+    let has_request_body = params.has_request_body;
     server.acquire();
     let outcome = run_backend_task(
         &server,
@@ -452,9 +461,11 @@ async fn backend_task(
         &params.compression,
         params.client_encoding,
         params.timeout_secs,
+        params.timeouts,
         params.use_tls,
         &params.sni,
         params.tls_insecure,
+        params.extra_response_headers,
         &req_body_rx,
         &resp_tx,
         &notify,
@@ -462,6 +473,17 @@ async fn backend_task(
     .await;
     server.release();
 
+    // F-138: head 送出前のエラーを失敗として記録する。アップロードを伴う場合は RTT が
+    // クライアントの送信速度に左右されるため記録せず枠のみ返す
+    if let Some((group, permit)) = params.concurrency {
+        if has_request_body {
+            drop(permit);
+        } else {
+            let status = outcome.err().unwrap_or(200);
+            crate::proxy::complete_concurrency_permit(&group, Some(permit), status);
+        }
+    }
+
     if let Err(status) = outcome {
         // head 送出前のエラーはステータスを通知（送出後は resp_tx drop で fin）。
         let _ = resp_tx.send(RespMsg::Error { status }).await;
@@ -479,9 +501,11 @@ async fn run_backend_task(
     compression: &CompressionConfig,
     client_encoding: AcceptedEncoding,
     timeout_secs: u64,
+    timeouts: UpstreamTimeouts,
     use_tls: bool,
     sni: &str,
     tls_insecure: bool,
+    extra_response_headers: RespHeaders,
     req_body_rx: &Receiver<Bytes>,
     resp_tx: &Sender<RespMsg>,
     notify: &H3Notify,
@@ -491,7 +515,7 @@ async fn run_backend_task(
     let addr = addr.as_str();
 
     // --- 非同期接続（タイムアウト付き） ---
-    let connect = TcpStream::connect_str(addr);
+    let connect = TcpStream::connect_str_with(addr, target.bind, target.socket.clone());
     let tcp = match crate::runtime::time::timeout(Duration::from_secs(timeout_secs), connect).await
     {
         Ok(Ok(s)) => s,
@@ -584,6 +608,8 @@ async fn run_backend_task(
                 compression,
                 client_encoding,
                 timeout_secs,
+                timeouts,
+                extra_response_headers,
                 resp_tx,
                 notify,
             );
@@ -623,6 +649,8 @@ async fn run_backend_task(
                 compression,
                 client_encoding,
                 timeout_secs,
+                timeouts,
+                extra_response_headers,
                 resp_tx,
                 notify,
             )
@@ -637,21 +665,31 @@ async fn stream_response(
     compression: &CompressionConfig,
     client_encoding: AcceptedEncoding,
     timeout_secs: u64,
+    timeouts: UpstreamTimeouts,
+    extra_response_headers: RespHeaders,
     resp_tx: &Sender<RespMsg>,
     notify: &H3Notify,
 ) -> Result<(), u16> {
     // 読み取りバッファ（所有権ベース read のため都度払い出し→受け取り）。
     let mut read_buf = vec![0u8; RESP_READ_CHUNK];
     let mut head_buf: Vec<u8> = Vec::with_capacity(4096);
-    let deadline = std::time::Instant::now() + Duration::from_secs(timeout_secs);
+    let now = std::time::Instant::now();
+    // F-139: 全体はリクエスト期限、ヘッダーは応答ヘッダー待ち・試行ごとの上限でも打ち切る
+    let deadline = timeouts.bound(now + Duration::from_secs(timeout_secs));
+    let head_deadline = timeouts.exchange_deadline(now).min(deadline);
 
     // --- ヘッダ終端まで読む ---
     let header_end;
     loop {
-        if std::time::Instant::now() >= deadline {
-            return Err(504);
-        }
-        let (res, buf) = backend.read_into(read_buf).await;
+        let (res, buf) = match crate::runtime::time::timeout(
+            UpstreamTimeouts::until(head_deadline),
+            backend.read_into(read_buf),
+        )
+        .await
+        {
+            Ok(r) => r,
+            Err(_) => return Err(504),
+        };
         read_buf = buf;
         let n = match res {
             Ok(0) => {
@@ -702,6 +740,7 @@ async fn stream_response(
             enc,
             compression,
             deadline,
+            extra_response_headers,
             resp_tx,
         )
         .await;
@@ -714,6 +753,7 @@ async fn stream_response(
         // chunked のデータ長は不定 → content-length は付けない（quiche がストリーム長を管理）。
         headers.retain(|(n, _)| !n.eq_ignore_ascii_case(b"content-length"));
     }
+    headers.extend(extra_response_headers);
     if resp_tx
         .send(RespMsg::Head { status, headers })
         .await
@@ -900,6 +940,7 @@ async fn stream_response_compressed(
     enc: AcceptedEncoding,
     compression: &CompressionConfig,
     deadline: std::time::Instant,
+    extra_response_headers: RespHeaders,
     resp_tx: &Sender<RespMsg>,
 ) -> Result<(), u16> {
     // ボディ全体を読み取る（圧縮に必要）。
@@ -946,6 +987,7 @@ async fn stream_response_compressed(
         Bytes::from_static(b"content-encoding"),
         Bytes::from_static(enc.as_header_value()),
     ));
+    headers.extend(extra_response_headers);
 
     if resp_tx
         .send(RespMsg::Head { status, headers })
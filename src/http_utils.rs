@@ -1,4 +1,5 @@
 use crate::cache;
+use crate::config::{ExtraHeaders, SecurityConfig};
 use httparse::Status;
 use memchr::memchr3;
 
@@ -612,7 +613,6 @@ pub(crate) fn should_advertise_accept_ranges(method: &[u8]) -> bool {
 ///
 /// RFC 7230 Section 4.1に準拠した簡易的なChunkedデコーダ。
 /// Transfer-Encoding: chunked 形式のボディから、生のデータを抽出します。
-#[cfg(feature = "http2")]
 pub(crate) fn decode_chunked_body(chunked_data: &[u8]) -> Vec<u8> {
     let mut result = Vec::with_capacity(chunked_data.len());
     let mut pos = 0;
@@ -861,6 +861,90 @@ pub fn url_decode(input: &str) -> String {
     result
 }
 
+/// base64url（RFC 4648 §5、パディングなし / あり両対応）をデコードする（F-146）
+///
+/// JWT のセグメントの読み取り用。不正な文字・長さは None。
+pub(crate) fn base64url_decode(input: &[u8]) -> Option<Vec<u8>> {
+    base64_decode_with(input, b'-', b'_')
+}
+
+/// base64（RFC 4648 §4、パディングなし / あり両対応）をデコードする（F-151）
+///
+/// `Authorization: Basic` の資格情報の読み取り用。不正な文字・長さは None。
+pub(crate) fn base64_decode(input: &[u8]) -> Option<Vec<u8>> {
+    base64_decode_with(input, b'+', b'/')
+}
+
+fn base64_decode_with(input: &[u8], c62: u8, c63: u8) -> Option<Vec<u8>> {
+    let value = |c: u8| -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+            b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+            _ if c == c62 => Some(62),
+            _ if c == c63 => Some(63),
+            _ => None,
+        }
+    };
+
+    let input = match input.iter().position(|&b| b == b'=') {
+        Some(pad) => &input[..pad],
+        None => input,
+    };
+    if input.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    for chunk in input.chunks(4) {
+        let mut acc = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            acc |= value(c)? << (18 - 6 * i);
+        }
+        let bytes = acc.to_be_bytes();
+        out.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+    Some(out)
+}
+
+/// base64（RFC 4648 §4、パディングあり）にエンコードする（F-150）
+///
+/// OIDC のトークンエンドポイントへの `Authorization: Basic` に使う。
+pub(crate) fn base64_encode(input: &[u8]) -> String {
+    base64_encode_with(
+        input,
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+        true,
+    )
+}
+
+/// base64url（RFC 4648 §5、パディングなし）にエンコードする（F-150）
+///
+/// OIDC のセッション Cookie・`state`・PKCE の値に使う。
+pub(crate) fn base64url_encode(input: &[u8]) -> String {
+    base64_encode_with(
+        input,
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+        false,
+    )
+}
+
+fn base64_encode_with(input: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let acc = (chunk[0] as u32) << 16
+            | (chunk.get(1).copied().unwrap_or(0) as u32) << 8
+            | chunk.get(2).copied().unwrap_or(0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(alphabet[(acc >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else if pad {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
 // ====================
 // HTTPレスポンスパーサー（httparse使用）
 // ====================
@@ -877,6 +961,62 @@ pub(crate) struct ParsedResponse {
     pub(crate) is_chunked: bool,
     /// Connection: close かどうか（HTTP/1.1ではデフォルトはkeep-alive）
     pub(crate) is_connection_close: bool,
+    /// `Keep-Alive: timeout=N` の N（秒、F-145）
+    pub(crate) keep_alive_timeout_secs: Option<u64>,
+}
+
+impl ParsedResponse {
+    /// 上流接続を再利用できるか（F-145）
+    pub(crate) fn keep_alive(&self) -> BackendKeepAlive {
+        if self.is_connection_close {
+            BackendKeepAlive::Close
+        } else {
+            BackendKeepAlive::Reuse(self.keep_alive_timeout_secs)
+        }
+    }
+}
+
+/// 応答後に上流接続をプールへ戻せるか（F-145）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BackendKeepAlive {
+    /// 再利用しない（`Connection: close`・応答異常）
+    Close,
+    /// 再利用できる。上流が `Keep-Alive: timeout=N` を返していれば N（秒）
+    Reuse(Option<u64>),
+}
+
+impl BackendKeepAlive {
+    /// プールへ戻せるか
+    pub(crate) fn is_reusable(self) -> bool {
+        matches!(self, Self::Reuse(_))
+    }
+
+    /// プールでのアイドルタイムアウト（秒）
+    ///
+    /// 上流のヒントがあれば、上流が閉じる直前の接続を使わないよう 1 秒短くし、
+    /// 設定値（`idle_connection_timeout_secs`）と小さい方を使う。ヒントが 1 秒以下なら 0
+    /// （プールへ戻さない）。
+    pub(crate) fn idle_timeout_secs(self, configured: u64) -> u64 {
+        match self {
+            Self::Reuse(Some(hint)) => configured.min(hint.saturating_sub(1)),
+            _ => configured,
+        }
+    }
+}
+
+/// `Keep-Alive` ヘッダー値から `timeout=N` を取り出す（F-145、例: `timeout=5, max=100`）
+pub(crate) fn parse_keep_alive_timeout(value: &[u8]) -> Option<u64> {
+    value.split(|&b| b == b',').find_map(|param| {
+        let param = trim_ascii_whitespace(param);
+        let eq = param.iter().position(|&b| b == b'=')?;
+        if !trim_ascii_whitespace(&param[..eq]).eq_ignore_ascii_case(b"timeout") {
+            return None;
+        }
+        std::str::from_utf8(trim_ascii_whitespace(&param[eq + 1..]))
+            .ok()?
+            .parse()
+            .ok()
+    })
 }
 
 /// バックエンド応答バッファ先頭の 1xx 中間応答を読み捨てる（B-11）。
@@ -942,12 +1082,20 @@ pub(crate) fn parse_http_response(data: &[u8]) -> Option<ParsedResponse> {
                 })
                 .unwrap_or(false);
 
+            // F-145: 上流のアイドルタイムアウトのヒント
+            let keep_alive_timeout_secs = response
+                .headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case("keep-alive"))
+                .and_then(|h| parse_keep_alive_timeout(h.value));
+
             Some(ParsedResponse {
                 status_code,
                 header_len,
                 content_length,
                 is_chunked,
                 is_connection_close,
+                keep_alive_timeout_secs,
             })
         }
         Ok(Status::Partial) => None, // データ不足
@@ -1408,6 +1556,8 @@ pub(crate) fn extract_vary_headers_for_cache_key<'a>(
 /// 304 Not Modified レスポンスを構築
 pub(crate) fn build_304_response(
     cached_entry: &cache::CacheEntry,
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
     client_wants_close: bool,
     is_stale: bool,
 ) -> Vec<u8> {
@@ -1424,6 +1574,9 @@ pub(crate) fn build_304_response(
             || name.eq_ignore_ascii_case(b"vary")
             || name.eq_ignore_ascii_case(b"content-location")
         {
+            if is_removed_response_header(name, security) {
+                continue;
+            }
             response.extend_from_slice(name);
             response.extend_from_slice(b": ");
             response.extend_from_slice(value);
@@ -1431,6 +1584,8 @@ pub(crate) fn build_304_response(
         }
     }
 
+    push_added_response_headers(&mut response, security, extra);
+
     // X-Cache ヘッダー
     if is_stale {
         response.extend_from_slice(b"X-Cache: STALE\r\n");
@@ -1449,6 +1604,31 @@ pub(crate) fn build_304_response(
     response
 }
 
+/// キャッシュしたヘッダーのうちルートの `remove_response_headers` で除くものか
+///
+/// キャッシュは上流の応答ヘッダーをそのまま保存するため、ヒット時にもルートの
+/// レスポンスヘッダー操作を適用する（F-155 の CORS ヘッダーを含む）。
+fn is_removed_response_header(name: &[u8], security: &SecurityConfig) -> bool {
+    security
+        .remove_response_headers
+        .iter()
+        .any(|r| name.eq_ignore_ascii_case(r.as_bytes()))
+}
+
+/// ルートの `add_response_headers` とリクエスト単位の応答ヘッダーをヘッダー行として追記する
+fn push_added_response_headers(
+    response: &mut Vec<u8>,
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
+) {
+    for (name, value) in security.response_headers(extra) {
+        response.extend_from_slice(name.as_bytes());
+        response.extend_from_slice(b": ");
+        response.extend_from_slice(value.as_bytes());
+        response.extend_from_slice(b"\r\n");
+    }
+}
+
 /// キャッシュからのレスポンスを構築（メモリキャッシュ用）
 /// キャッシュレスポンスの **ヘッダー部のみ** を構築する（ボディは含めない）。
 ///
@@ -1456,6 +1636,8 @@ pub(crate) fn build_304_response(
 /// ゼロコピーでソケットへ書き込むため、ボディを連結しないこのビルダーを使う。
 pub(crate) fn build_cached_response_headers(
     cached_entry: &cache::CacheEntry,
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
     client_wants_close: bool,
     is_stale: bool,
 ) -> Vec<u8> {
@@ -1469,12 +1651,17 @@ pub(crate) fn build_cached_response_headers(
 
     // ヘッダー
     for (name, value) in cached_entry.headers.iter() {
+        if is_removed_response_header(name, security) {
+            continue;
+        }
         response.extend_from_slice(name);
         response.extend_from_slice(b": ");
         response.extend_from_slice(value);
         response.extend_from_slice(b"\r\n");
     }
 
+    push_added_response_headers(&mut response, security, extra);
+
     // X-Cache ヘッダー
     if is_stale {
         response.extend_from_slice(b"X-Cache: STALE\r\n");
@@ -1499,18 +1686,20 @@ pub(crate) fn build_cached_response_headers(
 /// メモリキャッシュのゼロコピー配信は [`build_cached_response_headers`] + ボディ別書き込みを使う。
 pub(crate) fn build_cached_response(
     cached_entry: &cache::CacheEntry,
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
     body_data: &[u8],
     client_wants_close: bool,
     is_stale: bool,
 ) -> Vec<u8> {
-    let mut response = build_cached_response_headers(cached_entry, client_wants_close, is_stale);
+    let mut response =
+        build_cached_response_headers(cached_entry, security, extra, client_wants_close, is_stale);
     response.reserve(body_data.len());
     response.extend_from_slice(body_data);
     response
 }
 
 /// ステータスコードに対応する理由フレーズを返す
-#[cfg(feature = "http2")]
 pub(crate) fn status_reason_phrase(status: u16) -> &'static str {
     match status {
         200 => "OK",
@@ -1718,8 +1907,8 @@ mod chunked_span_tests {
         assert!(limit);
     }
 
-    // decode_chunked_body は http2 feature でのみ提供されるため、比較テストも同 feature で gate する
-    #[cfg(feature = "http2")]
+    // decode_chunked_body は http2 / http3 feature でのみ提供されるため、比較テストも同 feature で gate する
+    #[cfg(any(feature = "http2", feature = "http3"))]
     #[test]
     fn test_span_matches_decode_chunked_body() {
         // ゼロコピー span 経路が既存の decode_chunked_body と同一出力になることを保証。
@@ -1893,6 +2082,28 @@ mod stack_fmt_tests {
         assert_eq!(parsed.status_code, 101);
     }
 
+    // F-145: Keep-Alive: timeout=N のヒント
+    #[test]
+    fn keep_alive_timeout_hint_limits_pool_idle_timeout() {
+        assert_eq!(parse_keep_alive_timeout(b"timeout=5, max=100"), Some(5));
+        assert_eq!(
+            parse_keep_alive_timeout(b"max=100 , Timeout = 15"),
+            Some(15)
+        );
+        assert_eq!(parse_keep_alive_timeout(b"max=100"), None);
+        assert_eq!(parse_keep_alive_timeout(b"timeout=abc"), None);
+
+        let buf = b"HTTP/1.1 200 OK\r\nKeep-Alive: timeout=5\r\nContent-Length: 0\r\n\r\n";
+        let keep_alive = parse_http_response(buf).unwrap().keep_alive();
+        assert_eq!(keep_alive, BackendKeepAlive::Reuse(Some(5)));
+        assert_eq!(keep_alive.idle_timeout_secs(30), 4);
+        assert_eq!(BackendKeepAlive::Reuse(Some(60)).idle_timeout_secs(30), 30);
+        assert_eq!(BackendKeepAlive::Reuse(None).idle_timeout_secs(30), 30);
+
+        let buf = b"HTTP/1.1 200 OK\r\nConnection: close\r\nKeep-Alive: timeout=5\r\n\r\n";
+        assert!(!parse_http_response(buf).unwrap().keep_alive().is_reusable());
+    }
+
     #[test]
     fn drain_interim_noop_for_final_response() {
         let mut buf = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_vec();
@@ -1940,4 +2151,40 @@ mod stack_fmt_tests {
         assert_eq!(strip_host_port(b"[::1]:443"), b"[::1]");
         assert_eq!(strip_host_port(b"[2001:db8::1]"), b"[2001:db8::1]");
     }
+
+    // F-146: JWT ペイロード（base64url、パディングなし）の復号
+    #[test]
+    fn base64url_decode_handles_padding_and_rejects_invalid() {
+        assert_eq!(base64url_decode(b"eyJhIjoxfQ").unwrap(), br#"{"a":1}"#);
+        assert_eq!(base64url_decode(b"eyJhIjoxfQ==").unwrap(), br#"{"a":1}"#);
+        assert_eq!(base64url_decode(b"-_8").unwrap(), [0xfb, 0xff]);
+        assert_eq!(base64url_decode(b"").unwrap(), b"");
+        assert!(base64url_decode(b"abcde").is_none());
+        assert!(base64url_decode(b"ab+/").is_none());
+    }
+
+    // F-151: Basic 認証の資格情報（base64）の復号
+    #[test]
+    fn base64_decode_uses_the_standard_alphabet() {
+        assert_eq!(base64_decode(b"YWxpY2U6czNjcjN0").unwrap(), b"alice:s3cr3t");
+        assert_eq!(base64_decode(b"+/8=").unwrap(), [0xfb, 0xff]);
+        assert!(base64_decode(b"-_8").is_none());
+    }
+
+    // F-150: OIDC の Cookie・PKCE（base64url）と Basic 認証（base64）
+    #[test]
+    fn base64_encode_matches_rfc4648() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_encode(&[0xfb, 0xff]), "+/8=");
+        assert_eq!(base64url_encode(&[0xfb, 0xff]), "-_8");
+        assert_eq!(base64url_encode(br#"{"a":1}"#), "eyJhIjoxfQ");
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(
+            base64url_decode(base64url_encode(&bytes).as_bytes()).unwrap(),
+            bytes
+        );
+    }
 }
@@ -193,11 +193,26 @@ pub struct KtlsClientStream {
     conn: Option<ClientConnection>,
     /// 現在の TLS モード
     mode: TlsMode,
+    /// ALPN でネゴシエートされたプロトコル（F-134、kTLS 移行後も参照できるよう保持）
+    alpn_protocol: Option<Vec<u8>>,
     /// kTLS 有効化前に rustls が復号したデータ（ドレインバッファ）
     drained_buffer: Vec<u8>,
 }
 
+impl crate::runtime::io::BufferedReadState for KtlsClientStream {
+    /// サーバー側と同じ判定（F-134: 上流 h2 多重化ドライバの可読待機前チェックに使う）。
+    #[inline]
+    fn has_buffered_read_data(&self) -> bool {
+        !self.is_ktls_recv_enabled() && !self.drained_buffer.is_empty()
+    }
+}
+
 impl KtlsClientStream {
+    /// ALPN でネゴシエートされたプロトコルを取得（F-134）
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.alpn_protocol.as_deref()
+    }
+
     /// 基盤となる TCP ストリームへの参照を取得
     pub fn get_ref(&self) -> &TcpStream {
         &self.inner
@@ -846,6 +861,7 @@ pub async fn connect(
 
     // ハンドシェイクを実行
     do_client_handshake(&stream, &mut conn).await?;
+    let alpn_protocol = conn.alpn_protocol().map(|p| p.to_vec());
 
     // kTLS の有効化を試みる
     #[cfg(feature = "ktls")]
@@ -894,6 +910,7 @@ pub async fn connect(
         inner: stream,
         conn: conn_option,
         mode,
+        alpn_protocol,
         drained_buffer,
     })
 }
@@ -1370,6 +1387,16 @@ impl RustlsConnector {
         self
     }
 
+    /// ALPN プロトコルリストを差し替えたコネクターを作成（F-134）
+    pub fn with_alpn_protocols(&self, protocols: &[&[u8]]) -> Self {
+        let mut config = (*self.config).clone();
+        config.alpn_protocols = protocols.iter().map(|p| p.to_vec()).collect();
+        RustlsConnector {
+            config: Arc::new(config),
+            ..self.clone()
+        }
+    }
+
     /// TLS ハンドシェイクを実行
     pub async fn connect(
         &self,
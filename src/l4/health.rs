@@ -1,14 +1,15 @@
 //! L4 プロキシ用ヘルスチェックモジュール
 //!
 //! 各 L4 upstream に対して定期的にヘルスチェックを実行し、
-//! 結果を `Arc<Vec<AtomicBool>>` に反映する。
+//! 結果を `Arc<Vec<AtomicBool>>` に反映する。プローブとスケジュールは
+//! HTTP 上流と共通の `crate::health`（F-143）を使う。
 
-use crate::config::{HealthCheckType, L4ListenerConfig, SHUTDOWN_FLAG};
-use crate::upstream::{perform_grpc_health_check, perform_tcp_health_check};
+use crate::config::{L4ListenerConfig, SHUTDOWN_FLAG};
+use crate::health::{self, HealthSchedule, ProbeTarget};
 use ftlog::{info, warn};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 /// L4 upstream のヘルス状態（upstream ごとに 1 エントリ）
 pub type L4HealthState = Arc<Vec<AtomicBool>>;
@@ -21,17 +22,17 @@ pub fn new_health_state(n_upstreams: usize) -> L4HealthState {
 /// ヘルスチェックスレッドを起動する
 ///
 /// `health_check` が設定されていない場合はスレッドを起動しない。
-/// スレッドは `SHUTDOWN_FLAG` が立つと終了する。
+/// スレッドは `SHUTDOWN_FLAG` が立つと終了する。遷移は `l4:<リスナー名>` の
+/// upstream 名で `health::record_transition` に記録する。
 pub fn spawn_l4_health_checker(config: Arc<L4ListenerConfig>, health_state: L4HealthState) {
     let hc = match &config.health_check {
         Some(hc) => hc.clone(),
         None => return, // ヘルスチェック設定なし → スキップ
     };
 
-    let interval = Duration::from_secs(hc.interval_secs);
-    let timeout = Duration::from_secs(hc.timeout_secs);
-
     std::thread::spawn(move || {
+        let label = format!("l4:{}", config.name);
+        let mut schedule = HealthSchedule::new();
         // 失敗・成功カウンタ（upstream ごと）
         let mut fail_counts: Vec<u32> = vec![0; config.upstreams.len()];
         let mut success_counts: Vec<u32> = vec![0; config.upstreams.len()];
@@ -43,66 +44,78 @@ pub fn spawn_l4_health_checker(config: Arc<L4ListenerConfig>, health_state: L4He
 
             for (i, upstream) in config.upstreams.iter().enumerate() {
                 let addr = &upstream.addr;
+                let now = Instant::now();
+                if !schedule.is_due(addr, &hc, now) {
+                    continue;
+                }
 
-                let healthy = match hc.check_type {
-                    HealthCheckType::Tcp => perform_tcp_health_check(addr, timeout),
-                    HealthCheckType::Grpc => perform_grpc_health_check(
+                let host = upstream_host(addr);
+                let result = health::probe(
+                    &hc,
+                    &ProbeTarget {
                         addr,
-                        &hc.path,
-                        hc.use_tls,
-                        hc.verify_cert,
-                        timeout,
-                    ),
-                    HealthCheckType::Http => {
-                        // L4 のHTTPヘルスチェックは TCP と同等（接続確認のみ）
-                        perform_tcp_health_check(addr, timeout)
-                    }
-                };
+                        host,
+                        sni: host,
+                        h3: false,
+                        l4: true,
+                    },
+                );
 
                 let was_healthy = health_state[i].load(Ordering::Relaxed);
 
-                if healthy {
-                    fail_counts[i] = 0;
-                    success_counts[i] = success_counts[i].saturating_add(1);
+                match result {
+                    Ok(()) => {
+                        fail_counts[i] = 0;
+                        success_counts[i] = success_counts[i].saturating_add(1);
 
-                    if !was_healthy && success_counts[i] >= hc.healthy_threshold {
-                        health_state[i].store(true, Ordering::Relaxed);
-                        info!("[L4:{}] upstream {} is now healthy", config.name, addr);
-                        success_counts[i] = 0;
+                        if !was_healthy && success_counts[i] >= hc.healthy_threshold {
+                            health_state[i].store(true, Ordering::Relaxed);
+                            info!("[L4:{}] upstream {} is now healthy", config.name, addr);
+                            let reason =
+                                format!("{} consecutive successful checks", hc.healthy_threshold);
+                            health::record_transition(&label, addr, true, &reason);
+                            success_counts[i] = 0;
+                        }
                     }
-                } else {
-                    success_counts[i] = 0;
-                    fail_counts[i] = fail_counts[i].saturating_add(1);
+                    Err(reason) => {
+                        success_counts[i] = 0;
+                        fail_counts[i] = fail_counts[i].saturating_add(1);
 
-                    if was_healthy && fail_counts[i] >= hc.unhealthy_threshold {
-                        health_state[i].store(false, Ordering::Relaxed);
-                        warn!("[L4:{}] upstream {} is now unhealthy", config.name, addr);
-                        fail_counts[i] = 0;
+                        if was_healthy && fail_counts[i] >= hc.unhealthy_threshold {
+                            health_state[i].store(false, Ordering::Relaxed);
+                            warn!(
+                                "[L4:{}] upstream {} is now unhealthy: {}",
+                                config.name, addr, reason
+                            );
+                            health::record_transition(&label, addr, false, &reason);
+                            fail_counts[i] = 0;
+                        }
                     }
                 }
+                schedule.schedule(addr, &hc, health_state[i].load(Ordering::Relaxed), now);
             }
 
-            // インターバル待機（1 秒ずつ polling して SHUTDOWN_FLAG をチェック）
-            let mut waited = Duration::ZERO;
-            while waited < interval {
-                if SHUTDOWN_FLAG.load(Ordering::Relaxed) {
-                    return;
-                }
-                // 理由付き allow: 専用ヘルスチェックスレッド上の待機（イベントループ外）。
-                #[allow(clippy::disallowed_methods)]
-                std::thread::sleep(Duration::from_secs(1));
-                waited += Duration::from_secs(1);
-            }
+            // 次の期限まで 500ms ずつ待機（SHUTDOWN_FLAG をチェック）
+            // 理由付き allow: 専用ヘルスチェックスレッド上の待機（イベントループ外）。
+            #[allow(clippy::disallowed_methods)]
+            std::thread::sleep(Duration::from_millis(500));
         }
     });
 }
 
+/// `host:port`（IPv6 は `[addr]:port`）からホスト部を取り出す（Host / SNI の既定値）
+fn upstream_host(addr: &str) -> &str {
+    let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host);
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
 #[cfg(test)]
 mod tests {
     // 理由付き allow: テストコードは同期 I/O・sleep を使用してよい（データプレーン非経由）。
     #![allow(clippy::disallowed_methods)]
     use super::*;
     use crate::config::{HealthCheckConfig, HealthCheckType, L4LbAlgorithm, L4UpstreamEntry};
+    use crate::upstream::perform_tcp_health_check;
 
     fn make_l4_config_with_hc(upstreams: Vec<&str>, hc: HealthCheckConfig) -> L4ListenerConfig {
         L4ListenerConfig {
@@ -122,6 +135,12 @@ mod tests {
             health_check: Some(hc),
             connect_timeout_secs: 10,
             idle_timeout_secs: 600,
+            transparent: false,
+            source_address: Vec::new(),
+            socket_profile: None,
+            upstream_socket_profile: None,
+            listener_socket: None,
+            upstream_socket: None,
         }
     }
 
@@ -165,6 +184,12 @@ mod tests {
             health_check: None,
             connect_timeout_secs: 10,
             idle_timeout_secs: 600,
+            transparent: false,
+            source_address: Vec::new(),
+            socket_profile: None,
+            upstream_socket_profile: None,
+            listener_socket: None,
+            upstream_socket: None,
         });
         let state = new_health_state(1);
         spawn_l4_health_checker(config, state.clone());
@@ -191,6 +216,7 @@ mod tests {
             healthy_statuses: vec![200],
             use_tls: false,
             verify_cert: true,
+            ..Default::default()
         };
 
         // 到達不能なアドレス（ポートを開いていない）
@@ -2,9 +2,10 @@
 //!
 //! バイダイレクショナルストリーム転送、ロードバランシング、TLS パススルーを実装する。
 
-use crate::config::{L4LbAlgorithm, L4ListenerConfig, L4TlsMode, CURRENT_CONFIG};
+use crate::config::{L4LbAlgorithm, L4ListenerConfig, L4TlsMode, UpstreamSource, CURRENT_CONFIG};
 use crate::runtime::io::{AsyncReadRent, AsyncWriteRent, AsyncWriteRentExt};
 use crate::runtime::offload::offload;
+use crate::runtime::sockopt::SocketOptions;
 // splice(2) は Linux 専用（設計ドキュメント 3.3 節）。BSD は forward_direction の
 // ユーザースペース read/write 転送へフォールバックするため splice/Pipe は使わない。
 #[cfg(target_os = "linux")]
@@ -508,6 +509,27 @@ pub async fn bidirectional_forward(
     }
 }
 
+/// upstream へ接続する（F-141: 送信元の指定があれば bind してから connect、
+/// F-142: ソケットプロファイルは connect 前に適用）
+async fn connect_upstream(
+    addr: SocketAddr,
+    peer_addr: SocketAddr,
+    source: Option<&UpstreamSource>,
+    socket: Option<Arc<SocketOptions>>,
+) -> io::Result<IoUringTcpStream> {
+    let bind = match source {
+        Some(source) => {
+            let bind = source.local_bind(Some(peer_addr.ip()))?;
+            if !bind.matches_family(&addr) {
+                return Err(bind.family_mismatch());
+            }
+            Some(bind)
+        }
+        None => None,
+    };
+    IoUringTcpStream::connect_with(addr, bind, socket).await
+}
+
 /// L4 接続を処理する（upstream 選択 → 接続 → バイダイレクショナル転送）
 pub async fn handle_l4_connection(
     client: IoUringTcpStream,
@@ -518,6 +540,7 @@ pub async fn handle_l4_connection(
     conn_counters: Arc<Vec<AtomicUsize>>,
     listener_counter: Arc<L4ConnectionCounter>,
     health_state: Arc<Vec<AtomicBool>>,
+    source: Option<Arc<UpstreamSource>>,
 ) {
     // 接続数制限チェック
     if config.max_connections > 0 {
@@ -558,6 +581,7 @@ pub async fn handle_l4_connection(
             rr_state,
             conn_counters,
             health_state,
+            source,
         )
         .await;
         return;
@@ -615,7 +639,13 @@ pub async fn handle_l4_connection(
     };
 
     let connect_timeout = Duration::from_secs(config.connect_timeout_secs);
-    let upstream = match timeout(connect_timeout, IoUringTcpStream::connect(socket_addr)).await {
+    let connect = connect_upstream(
+        socket_addr,
+        peer_addr,
+        source.as_deref(),
+        config.upstream_socket.clone(),
+    );
+    let upstream = match timeout(connect_timeout, connect).await {
         Ok(Ok(stream)) => stream,
         Ok(Err(e)) => {
             warn!(
@@ -797,6 +827,7 @@ async fn handle_l4_tls_terminate_connection(
     rr_state: Arc<RoundRobinState>,
     conn_counters: Arc<Vec<AtomicUsize>>,
     health_state: Arc<Vec<AtomicBool>>,
+    source: Option<Arc<UpstreamSource>>,
 ) {
     let handshake_timeout = Duration::from_secs(config.connect_timeout_secs);
     let Some(mut tls_client) =
@@ -855,7 +886,13 @@ async fn handle_l4_tls_terminate_connection(
     };
 
     let connect_timeout = Duration::from_secs(config.connect_timeout_secs);
-    let upstream = match timeout(connect_timeout, IoUringTcpStream::connect(socket_addr)).await {
+    let connect = connect_upstream(
+        socket_addr,
+        peer_addr,
+        source.as_deref(),
+        config.upstream_socket.clone(),
+    );
+    let upstream = match timeout(connect_timeout, connect).await {
         Ok(Ok(stream)) => stream,
         Ok(Err(e)) => {
             warn!(
@@ -907,6 +944,12 @@ mod tests {
             health_check: None,
             connect_timeout_secs: 10,
             idle_timeout_secs: 600,
+            transparent: false,
+            source_address: Vec::new(),
+            socket_profile: None,
+            upstream_socket_profile: None,
+            listener_socket: None,
+            upstream_socket: None,
         }
     }
 
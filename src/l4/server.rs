@@ -1,6 +1,6 @@
 //! L4 プロキシサーバー起動モジュール
 
-use crate::config::{L4ListenerConfig, L4Protocol, L4TlsMode, SHUTDOWN_FLAG};
+use crate::config::{L4ListenerConfig, L4Protocol, L4TlsMode, UpstreamSource, SHUTDOWN_FLAG};
 use crate::l4::health::{new_health_state, spawn_l4_health_checker};
 use crate::l4::proxy::{
     handle_l4_connection, parse_upstream_targets, L4ConnectionCounter, RoundRobinState,
@@ -36,6 +36,29 @@ pub fn spawn_l4_listeners(listeners: &[L4ListenerConfig]) {
             );
             config.tls = L4TlsMode::None;
         }
+        // F-141: 送信元の指定は TCP のみ（UDP は 1 本のソケットを全クライアントで共有するため）
+        if config.protocol == L4Protocol::Udp
+            && (config.transparent || !config.source_address.is_empty())
+        {
+            warn!(
+                "[L4:{}] protocol=udp does not support transparent / source_address; ignored",
+                config.name
+            );
+            config.transparent = false;
+            config.source_address.clear();
+        }
+        // F-142: ソケットプロファイルも TCP のみ
+        if config.protocol == L4Protocol::Udp
+            && (config.listener_socket.is_some() || config.upstream_socket.is_some())
+        {
+            warn!(
+                "[L4:{}] protocol=udp does not support socket_profile / upstream_socket_profile; ignored",
+                config.name
+            );
+            config.listener_socket = None;
+            config.upstream_socket = None;
+        }
+        let source = UpstreamSource::from_config(config.transparent, &config.source_address);
         let config = Arc::new(config);
         let n_upstreams = config.upstreams.len();
 
@@ -86,7 +109,10 @@ pub fn spawn_l4_listeners(listeners: &[L4ListenerConfig]) {
                 }
                 L4Protocol::Tcp => {
                     crate::runtime::block_on(async move {
-                        let listener = match TcpListener::bind(listen_addr) {
+                        let listener = match TcpListener::bind_with(
+                            listen_addr,
+                            config.listener_socket.as_deref(),
+                        ) {
                             Ok(l) => l,
                             Err(e) => {
                                 error!("[L4:{}] bind error on {}: {}", config.name, listen_addr, e);
@@ -125,6 +151,7 @@ pub fn spawn_l4_listeners(listeners: &[L4ListenerConfig]) {
                             let counters_clone = conn_counters.clone();
                             let listener_counter_clone = listener_counter.clone();
                             let health_clone = health_state.clone();
+                            let source_clone = source.clone();
 
                             crate::system::spawn_pooled_with_panic_catch(&conn_pool, async move {
                                 handle_l4_connection(
@@ -136,6 +163,7 @@ pub fn spawn_l4_listeners(listeners: &[L4ListenerConfig]) {
                                     counters_clone,
                                     listener_counter_clone,
                                     health_clone,
+                                    source_clone,
                                 )
                                 .await;
                             });
@@ -358,6 +358,12 @@ mod tests {
             health_check: None,
             connect_timeout_secs: 10,
             idle_timeout_secs: 1,
+            transparent: false,
+            source_address: Vec::new(),
+            socket_profile: None,
+            upstream_socket_profile: None,
+            listener_socket: None,
+            upstream_socket: None,
         }
     }
 
@@ -248,100 +248,6 @@ mod ip_filter_tests {
     }
 }
 
-// ====================
-// RateLimitEntry テスト
-// ====================
-
-mod rate_limit_tests {
-    use super::*;
-
-    #[test]
-    fn test_new_entry() {
-        // 新規エントリの初期状態
-        let entry = RateLimitEntry::new(100);
-        assert_eq!(entry.current_count, 1);
-        assert_eq!(entry.previous_count, 0);
-        assert_eq!(entry.current_minute, 100);
-    }
-
-    #[test]
-    fn test_record_same_minute() {
-        // 同一分内でのリクエスト記録
-        let mut entry = RateLimitEntry::new(100);
-
-        // 初期状態: count=1
-        let rate = entry.record_request(100, 30);
-        // count=2, previous=0, weight=(60-30)/60=0.5
-        // estimated = 0*0.5 + 2 = 2
-        assert_eq!(entry.current_count, 2);
-        assert!(rate >= 2);
-    }
-
-    #[test]
-    fn test_record_next_minute() {
-        // 次の分へ移行
-        let mut entry = RateLimitEntry::new(100);
-        entry.current_count = 10;
-
-        let rate = entry.record_request(101, 0);
-
-        // previous_count = 10（前の分のカウント）
-        // current_count = 1（新しい分）
-        assert_eq!(entry.current_minute, 101);
-        assert_eq!(entry.previous_count, 10);
-        assert_eq!(entry.current_count, 1);
-
-        // rate = 10 * (60-0)/60 + 1 = 10 + 1 = 11
-        assert_eq!(rate, 11);
-    }
-
-    #[test]
-    fn test_record_skip_minutes() {
-        // 2分以上経過した場合のリセット
-        let mut entry = RateLimitEntry::new(100);
-        entry.current_count = 100;
-        entry.previous_count = 50;
-
-        let rate = entry.record_request(103, 0);
-
-        // 2分以上経過なのでリセット
-        assert_eq!(entry.current_minute, 103);
-        assert_eq!(entry.previous_count, 0);
-        assert_eq!(entry.current_count, 1);
-        assert_eq!(rate, 1);
-    }
-
-    #[test]
-    fn test_sliding_window_calculation() {
-        // スライディングウィンドウ計算の検証
-        let mut entry = RateLimitEntry::new(100);
-        entry.current_count = 30;
-        entry.previous_count = 60;
-
-        // 分の真ん中（30秒経過）でのレート計算
-        let rate = entry.record_request(100, 30);
-        // current_count = 31, previous = 60
-        // weight = (60-30)/60 = 0.5
-        // estimated = 60*0.5 + 31 = 30 + 31 = 61
-        assert_eq!(entry.current_count, 31);
-        assert_eq!(rate, 61);
-    }
-
-    #[test]
-    fn test_sliding_window_end_of_minute() {
-        // 分の終わりでのレート計算（weight ≈ 0）
-        let mut entry = RateLimitEntry::new(100);
-        entry.current_count = 50;
-        entry.previous_count = 100;
-
-        let rate = entry.record_request(100, 59);
-        // weight = (60-59)/60 ≈ 0.0167
-        // estimated = 100*0.0167 + 51 ≈ 52.67 → ceil → 53
-        assert_eq!(entry.current_count, 51);
-        assert!((51..=53).contains(&rate));
-    }
-}
-
 // ====================
 // AcceptedEncoding テスト
 // ====================
@@ -714,7 +620,7 @@ mod pooled_connection_tests {
     fn test_pooled_connection_new() {
         // PooledConnectionの作成
         let stream = (); // ダミー型
-        let conn = PooledConnection::new(stream, 30);
+        let conn = PooledConnection::new(stream, 30, ConnLifecycle::unlimited());
 
         assert_eq!(conn.idle_timeout_secs, 30);
     }
@@ -723,7 +629,7 @@ mod pooled_connection_tests {
     fn test_pooled_connection_is_valid_immediately() {
         // 作成直後は有効
         let stream = ();
-        let conn = PooledConnection::new(stream, 30);
+        let conn = PooledConnection::new(stream, 30, ConnLifecycle::unlimited());
 
         assert!(conn.is_valid());
     }
@@ -732,7 +638,7 @@ mod pooled_connection_tests {
     fn test_pooled_connection_is_valid_with_zero_timeout() {
         // タイムアウト0秒の場合、即座に無効
         let stream = ();
-        let conn = PooledConnection::new(stream, 0);
+        let conn = PooledConnection::new(stream, 0, ConnLifecycle::unlimited());
 
         // 作成直後でも0秒以上経過しているため無効
         assert!(!conn.is_valid());
@@ -742,7 +648,7 @@ mod pooled_connection_tests {
     fn test_pooled_connection_is_valid_with_long_timeout() {
         // 長いタイムアウトの場合、有効
         let stream = ();
-        let conn = PooledConnection::new(stream, 3600);
+        let conn = PooledConnection::new(stream, 3600, ConnLifecycle::unlimited());
 
         assert!(conn.is_valid());
     }
@@ -1026,18 +932,21 @@ mod upstream_selection_tests {
                 sni_name: None,
                 use_h2c: false,
                 weight: 1,
+                priority: 0,
             },
             UpstreamServerEntry {
                 url: "http://server2:8080".into(),
                 sni_name: None,
                 use_h2c: false,
                 weight: 1,
+                priority: 0,
             },
             UpstreamServerEntry {
                 url: "http://server3:8080".into(),
                 sni_name: None,
                 use_h2c: false,
                 weight: 1,
+                priority: 0,
             },
         ]
     }
@@ -1078,6 +987,7 @@ mod upstream_selection_tests {
             sni_name: None,
             use_h2c: false,
             weight: 1,
+            priority: 0,
         }];
         let group = UpstreamGroup::new(
             "invalid".into(),
@@ -1281,12 +1191,14 @@ mod upstream_health_tests {
                 sni_name: None,
                 use_h2c: false,
                 weight: 1,
+                priority: 0,
             },
             UpstreamServerEntry {
                 url: "http://unhealthy:8080".into(),
                 sni_name: None,
                 use_h2c: false,
                 weight: 1,
+                priority: 0,
             },
         ];
         let group = UpstreamGroup::new(
@@ -1319,12 +1231,14 @@ mod upstream_health_tests {
                 sni_name: None,
                 use_h2c: false,
                 weight: 1,
+                priority: 0,
             },
             UpstreamServerEntry {
                 url: "http://server2:8080".into(),
                 sni_name: None,
                 use_h2c: false,
                 weight: 1,
+                priority: 0,
             },
         ];
         let group = UpstreamGroup::new(
@@ -42,6 +42,10 @@ pub mod http2;
 #[cfg(feature = "http3")]
 pub mod http3_server;
 
+/// HTTP/3（QUIC）上流クライアント（F-135）
+#[cfg(feature = "http3")]
+pub mod http3_client;
+
 #[cfg(feature = "http3")]
 pub mod http3_stream;
 
@@ -49,7 +53,7 @@ pub mod http3_stream;
 pub mod udp;
 
 /// HTTP/2 / HTTP/3 アクターモデル共通の単一スレッドチャネル/Notify（F-116）。
-/// `#![cfg(any(feature = "http2", feature = "http3"))]` で内部を feature ゲートする。
+/// Notify は上流コネクションゲート（F-136）でも使うため feature に関わらず有効。
 pub mod stream_channel;
 
 pub mod buffering;
@@ -74,8 +78,28 @@ pub mod http_utils;
 #[cfg(feature = "opentelemetry")]
 pub mod otel;
 
+pub mod auto_ban;
+pub mod client_limits;
+pub mod cors;
+pub mod credential_auth;
+pub mod ext_authz;
+pub mod health;
+pub mod hedging;
+pub mod jwt_auth;
+pub mod oidc;
+pub mod passive_health;
 pub mod pool;
+pub mod rate_limit;
+pub mod request_checks;
 pub mod resilience;
+pub mod sticky;
+pub mod subrequest;
+pub mod timeouts;
+pub mod waf;
+
+/// 外部レートリミットサービス（Envoy RLS）によるグローバルレートリミット（F-147）
+#[cfg(all(feature = "http2", feature = "grpc"))]
+pub mod global_rate_limit;
 
 #[cfg(feature = "access-log")]
 pub mod access_log;
@@ -500,13 +500,14 @@ pub(crate) fn log_ktls_status(ktls_config: &crate::KtlsConfig) {
 /// - 処理時間: `start_instant` からの経過時間を高精度で計測（Instant使用）
 /// - タイムスタンプ: Coarse Timer でキャッシュした時刻を使用（システムコール削減）
 /// - メトリクス: リクエスト数、処理時間、サイズをPrometheus形式で記録
+/// - 自動遮断（F-152）: 401 / 403 / 404 をクライアント IP の兆候として報告
 ///
 /// # access-log feature との連携
 ///
 /// access-log が有効な場合: 構造化ログ（JSON/テキスト）をログスレッドへ送信。
 ///   テキスト形式の info!() は出力しない（二重出力防止）。
 /// access-log が無効な場合: ftlog 経由のテキスト形式のみ出力。
-// client_ip / upstream は構造化ログ（access-log feature）でのみ使用する
+// upstream / hedge / user は構造化ログ（access-log feature）でのみ使用する
 #[cfg_attr(not(feature = "access-log"), allow(unused_variables))]
 pub(crate) fn log_access(
     method: &[u8],
@@ -519,6 +520,8 @@ pub(crate) fn log_access(
     start_instant: Instant,
     client_ip: &str,
     upstream: &str,
+    hedge: &str,
+    user: &str,
 ) {
     // 処理時間は Instant で高精度計測
     let duration = start_instant.elapsed();
@@ -553,6 +556,9 @@ pub(crate) fn log_access(
         duration_secs,
     );
 
+    // F-152: 401 / 403 / 404 を自動遮断の兆候として数える
+    crate::auto_ban::observe_status(client_ip, status);
+
     // 構造化アクセスログ出力（F-21）
     // log_time と duration_ms を渡すことで syscall 二重発行と clone を排除
     #[cfg(feature = "access-log")]
@@ -568,6 +574,8 @@ pub(crate) fn log_access(
         duration_ms,
         client_ip,
         upstream,
+        hedge,
+        user,
     );
 }
 
@@ -17,6 +17,7 @@
 // - http_upstream_health: アップストリームの健康状態
 // - http3_active_connections: HTTP/3 (QUIC) アクティブ接続数（F-99）
 // - http3_active_streams: HTTP/3 アクティブリクエストストリーム数（F-99）
+// - upstream_h3_handshakes_total / upstream_h3_fallbacks_total: HTTP/3 上流（F-135）
 //
 // metrics feature が無効の場合、全公開 API はノーオップスタブとして提供されます。
 //
@@ -579,6 +580,34 @@ pub fn set_outlier_ejected(_upstream: &str, _server: &str, _ejected: bool) {
     }
 }
 
+// --- パッシブヘルスチェック（F-144）---
+
+#[cfg(feature = "metrics")]
+/// パッシブヘルスチェックで unhealthy にした回数（upstream, server, rule）
+pub(crate) static UPSTREAM_PASSIVE_HEALTH_TRIPS_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_passive_health_trips_total",
+        "Total upstream servers marked unhealthy by passive health checks",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["upstream", "server", "rule"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: パッシブヘルスチェックによる unhealthy 判定を記録（rule は判定した規則）
+#[inline]
+pub fn record_passive_health_trip(_upstream: &str, _server: &str, _rule: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        UPSTREAM_PASSIVE_HEALTH_TRIPS_TOTAL
+            .with_label_values(&[_upstream, _server, _rule])
+            .inc();
+    }
+}
+
 // --- コネクションプール（F-09）---
 
 #[cfg(feature = "metrics")]
@@ -650,6 +679,628 @@ pub fn record_connection_pool_miss(_upstream: &str) {
     }
 }
 
+// --- コネクションプールの上限とライフサイクル（F-136）---
+
+#[cfg(feature = "metrics")]
+/// 使用中の上流接続数（upstream、max_connections 設定時のみ計上）
+pub(crate) static UPSTREAM_ACTIVE_CONNECTIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_active_connections",
+        "Upstream connections currently in use",
+    )
+    .namespace("veil");
+    let gauge = IntGaugeVec::new(opts, &["upstream"]).unwrap();
+    METRICS_REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+#[cfg(feature = "metrics")]
+/// 接続の空きを待っているリクエスト数（upstream）
+pub(crate) static UPSTREAM_PENDING_REQUESTS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_pending_requests",
+        "Requests waiting for an upstream connection",
+    )
+    .namespace("veil");
+    let gauge = IntGaugeVec::new(opts, &["upstream"]).unwrap();
+    METRICS_REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+#[cfg(feature = "metrics")]
+/// 接続待ちキューから 503 で拒否したリクエスト数（upstream, reason: "overflow" / "timeout"）
+pub(crate) static UPSTREAM_PENDING_REJECTED_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_pending_rejected_total",
+        "Requests rejected while waiting for an upstream connection",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["upstream", "reason"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+#[cfg(feature = "metrics")]
+/// ライフサイクル上限・切断検出で閉じた接続数（upstream, reason: "max_requests" /
+/// "max_lifetime" / "stale" / "stale_write"）
+pub(crate) static UPSTREAM_CONNECTIONS_RETIRED_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_connections_retired_total",
+        "Upstream connections closed by lifecycle limits or stale-connection detection",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["upstream", "reason"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+#[cfg(feature = "metrics")]
+/// 起動時に事前確立した接続数（upstream）
+pub(crate) static UPSTREAM_CONNECTIONS_PREWARMED_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_connections_prewarmed_total",
+        "Upstream connections opened by pool pre-warming",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["upstream"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: 使用中の上流接続数を増減
+#[inline]
+pub fn add_upstream_active_connections(_upstream: &str, _delta: i64) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        UPSTREAM_ACTIVE_CONNECTIONS
+            .with_label_values(&[_upstream])
+            .add(_delta);
+    }
+}
+
+/// メトリクス: 接続待ちリクエスト数を増減
+#[inline]
+pub fn add_upstream_pending_requests(_upstream: &str, _delta: i64) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        UPSTREAM_PENDING_REQUESTS
+            .with_label_values(&[_upstream])
+            .add(_delta);
+    }
+}
+
+/// メトリクス: 接続待ちキューからの 503 拒否を記録
+#[inline]
+pub fn record_upstream_pending_rejected(_upstream: &str, _reason: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        UPSTREAM_PENDING_REJECTED_TOTAL
+            .with_label_values(&[_upstream, _reason])
+            .inc();
+    }
+}
+
+/// メトリクス: ライフサイクル上限・切断検出による接続クローズを記録
+#[inline]
+pub fn record_upstream_connection_retired(_upstream: &str, _reason: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        UPSTREAM_CONNECTIONS_RETIRED_TOTAL
+            .with_label_values(&[_upstream, _reason])
+            .inc();
+    }
+}
+
+/// メトリクス: 事前確立した接続を記録
+#[inline]
+pub fn record_upstream_connection_prewarmed(_upstream: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        UPSTREAM_CONNECTIONS_PREWARMED_TOTAL
+            .with_label_values(&[_upstream])
+            .inc();
+    }
+}
+
+// --- HTTP/3 上流（F-135）---
+
+#[cfg(all(feature = "metrics", feature = "http3"))]
+/// HTTP/3 上流のハンドシェイク数（upstream, resumed: "true"=0-RTT/再開, "false"=フル）
+pub(crate) static UPSTREAM_H3_HANDSHAKES_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_h3_handshakes_total",
+        "Total HTTP/3 upstream handshakes",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["upstream", "resumed"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+#[cfg(all(feature = "metrics", feature = "http3"))]
+/// HTTP/3 上流から TCP へのフォールバック数（upstream）
+pub(crate) static UPSTREAM_H3_FALLBACKS_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_h3_fallbacks_total",
+        "Total HTTP/3 upstream fallbacks to TCP",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["upstream"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: HTTP/3 上流のハンドシェイク完了を記録
+#[cfg(feature = "http3")]
+#[inline]
+pub fn record_upstream_h3_handshake(_upstream: &str, _resumed: bool) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        UPSTREAM_H3_HANDSHAKES_TOTAL
+            .with_label_values(&[_upstream, if _resumed { "true" } else { "false" }])
+            .inc();
+    }
+}
+
+/// メトリクス: HTTP/3 上流から TCP へのフォールバックを記録
+#[cfg(feature = "http3")]
+#[inline]
+pub fn record_upstream_h3_fallback(_upstream: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        UPSTREAM_H3_FALLBACKS_TOTAL
+            .with_label_values(&[_upstream])
+            .inc();
+    }
+}
+
+// --- リクエストヘッジング（F-137）---
+
+#[cfg(feature = "metrics")]
+/// ヘッジ（二次試行）を送ったリクエスト数（upstream）
+pub(crate) static UPSTREAM_HEDGED_REQUESTS_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_hedged_requests_total",
+        "Total upstream requests that sent a hedged attempt",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["upstream"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+#[cfg(feature = "metrics")]
+/// ヘッジが一次試行より先に応答した数（upstream）
+pub(crate) static UPSTREAM_HEDGE_WINS_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_hedge_wins_total",
+        "Total hedged attempts that responded before the primary attempt",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["upstream"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+#[cfg(feature = "metrics")]
+/// 遅延を超えたがヘッジ予算不足で送らなかった数（upstream）
+pub(crate) static UPSTREAM_HEDGE_BUDGET_EXHAUSTED_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_hedge_budget_exhausted_total",
+        "Total hedges skipped because the hedging budget was exhausted",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["upstream"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: ヘッジの送信と勝者を記録（`_hedge_won`: ヘッジが先に応答した）
+#[inline]
+pub fn record_upstream_hedge(_upstream: &str, _hedge_won: bool) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        UPSTREAM_HEDGED_REQUESTS_TOTAL
+            .with_label_values(&[_upstream])
+            .inc();
+        if _hedge_won {
+            UPSTREAM_HEDGE_WINS_TOTAL
+                .with_label_values(&[_upstream])
+                .inc();
+        }
+    }
+}
+
+/// メトリクス: ヘッジ予算不足によるヘッジ見送りを記録
+#[inline]
+pub fn record_upstream_hedge_budget_exhausted(_upstream: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        UPSTREAM_HEDGE_BUDGET_EXHAUSTED_TOTAL
+            .with_label_values(&[_upstream])
+            .inc();
+    }
+}
+
+// --- 適応型の同時実行数制限（F-138）---
+
+#[cfg(feature = "metrics")]
+/// 適応型の同時実行数制限の現在の上限（upstream）
+pub(crate) static UPSTREAM_CONCURRENCY_LIMIT: Lazy<IntGaugeVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_concurrency_limit",
+        "Current adaptive concurrency limit of the upstream",
+    )
+    .namespace("veil");
+    let gauge = IntGaugeVec::new(opts, &["upstream"]).unwrap();
+    METRICS_REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+#[cfg(feature = "metrics")]
+/// 同時実行数の上限超過で 503 を返したリクエスト数（upstream）
+pub(crate) static UPSTREAM_CONCURRENCY_REJECTED_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "upstream_concurrency_rejected_total",
+        "Requests rejected because the adaptive concurrency limit was reached",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["upstream"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: 適応型の同時実行数制限の上限を設定
+#[inline]
+pub fn set_upstream_concurrency_limit(_upstream: &str, _limit: u32) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        UPSTREAM_CONCURRENCY_LIMIT
+            .with_label_values(&[_upstream])
+            .set(_limit as i64);
+    }
+}
+
+/// メトリクス: 同時実行数の上限超過による拒否を記録
+#[inline]
+pub fn record_upstream_concurrency_rejected(_upstream: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        UPSTREAM_CONCURRENCY_REJECTED_TOTAL
+            .with_label_values(&[_upstream])
+            .inc();
+    }
+}
+
+// --- トークンバケット式レートリミット（F-146）---
+
+#[cfg(feature = "metrics")]
+/// レートリミット超過で 429 を返したリクエスト数（rule）
+pub(crate) static RATE_LIMIT_HITS_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "rate_limit_hits_total",
+        "Requests rejected with 429 by a rate limit rule",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["rule"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: レートリミット超過による拒否を記録（rule は超過した規則名）
+#[inline]
+pub fn record_rate_limit_hit(_rule: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        RATE_LIMIT_HITS_TOTAL.with_label_values(&[_rule]).inc();
+    }
+}
+
+// --- 外部レートリミットサービス（F-147）---
+
+#[cfg(feature = "metrics")]
+/// 外部レートリミットサービスの判定数（result: ok / over_limit / cached / error）
+pub(crate) static GLOBAL_RATE_LIMIT_REQUESTS_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "global_rate_limit_requests_total",
+        "Global rate limit decisions by result",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["result"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: 外部レートリミットサービスの判定を記録（cached は記憶した超過で RPC を省略）
+#[inline]
+pub fn record_global_rate_limit(_result: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        GLOBAL_RATE_LIMIT_REQUESTS_TOTAL
+            .with_label_values(&[_result])
+            .inc();
+    }
+}
+
+// --- JWT 認証（F-148）---
+
+#[cfg(feature = "metrics")]
+/// JWT 認証の結果数（result: ok / missing / invalid / forbidden）
+pub(crate) static JWT_AUTH_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new("jwt_auth_total", "JWT authentication results").namespace("veil");
+    let counter = CounterVec::new(opts, &["result"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: JWT 認証の結果を記録
+#[inline]
+pub fn record_jwt_auth(_result: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        JWT_AUTH_TOTAL.with_label_values(&[_result]).inc();
+    }
+}
+
+// --- 外部認可（F-149）---
+
+#[cfg(feature = "metrics")]
+/// 外部認可の判定数（result: ok / denied / cached / error）
+pub(crate) static EXT_AUTHZ_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new("ext_authz_total", "External authorization decisions").namespace("veil");
+    let counter = CounterVec::new(opts, &["result"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: 外部認可の判定を記録（cached は記憶した判定で問い合わせを省略）
+#[inline]
+pub fn record_ext_authz(_result: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        EXT_AUTHZ_TOTAL.with_label_values(&[_result]).inc();
+    }
+}
+
+// --- OpenID Connect（F-150）---
+
+#[cfg(feature = "metrics")]
+/// OIDC ログインの結果数
+/// （result: ok / refreshed / redirected / callback_ok / callback_failed / logout / unauthorized / error）
+pub(crate) static OIDC_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new("oidc_total", "OpenID Connect authentication results").namespace("veil");
+    let counter = CounterVec::new(opts, &["result"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: OIDC ログインの結果を記録
+#[inline]
+pub fn record_oidc(_result: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        OIDC_TOTAL.with_label_values(&[_result]).inc();
+    }
+}
+
+// --- Basic 認証・API キー認証（F-151）---
+
+#[cfg(feature = "metrics")]
+/// 資格情報による認証の結果数
+/// （method: basic / api_key / none、result: ok / missing / invalid / forbidden）
+pub(crate) static CREDENTIAL_AUTH_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "credential_auth_total",
+        "Basic and API key authentication results",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["method", "result"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: Basic 認証・API キー認証の結果を記録（資格情報が無ければ method は none）
+#[inline]
+pub fn record_credential_auth(_method: &str, _result: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        CREDENTIAL_AUTH_TOTAL
+            .with_label_values(&[_method, _result])
+            .inc();
+    }
+}
+
+// --- 自動遮断（F-152）---
+
+#[cfg(feature = "metrics")]
+/// 遮断の回数（reason: 規則名 / admin）
+pub(crate) static AUTO_BAN_BANS_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new("auto_ban_bans_total", "IP bans by reason").namespace("veil");
+    let counter = CounterVec::new(opts, &["reason"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+#[cfg(feature = "metrics")]
+/// 遮断中の IP の数
+pub(crate) static AUTO_BAN_ACTIVE: Lazy<prometheus::IntGauge> = Lazy::new(|| {
+    let opts = Opts::new("auto_ban_active", "Number of currently banned IPs").namespace("veil");
+    let gauge = prometheus::IntGauge::with_opts(opts).unwrap();
+    METRICS_REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+#[cfg(feature = "metrics")]
+/// 遮断中の IP からの接続を切った回数
+pub(crate) static AUTO_BAN_DROPPED_CONNECTIONS_TOTAL: Lazy<prometheus::IntCounter> =
+    Lazy::new(|| {
+        let opts = Opts::new(
+            "auto_ban_dropped_connections_total",
+            "Connections dropped because the client IP is banned",
+        )
+        .namespace("veil");
+        let counter = prometheus::IntCounter::with_opts(opts).unwrap();
+        METRICS_REGISTRY
+            .register(Box::new(counter.clone()))
+            .unwrap();
+        counter
+    });
+
+/// メトリクス: IP の遮断を記録
+#[inline]
+pub fn record_auto_ban(_reason: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        AUTO_BAN_BANS_TOTAL.with_label_values(&[_reason]).inc();
+    }
+}
+
+/// メトリクス: 遮断中の IP の数を設定（自動遮断の専用スレッドが 1 秒ごとに呼ぶ）
+#[inline]
+pub fn set_auto_ban_active(_count: usize) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        AUTO_BAN_ACTIVE.set(_count as i64);
+    }
+}
+
+/// メトリクス: 遮断中の IP からの接続を切ったことを記録（accept ホットパス、ラベル無し）
+#[inline]
+pub fn record_auto_ban_drop() {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        AUTO_BAN_DROPPED_CONNECTIONS_TOTAL.inc();
+    }
+}
+
+// --- 接続元ごとの上限（F-153）---
+
+#[cfg(feature = "metrics")]
+/// 接続元ごとの上限で拒否した回数（limit: 上限の種類）
+pub(crate) static CLIENT_LIMIT_REJECTIONS_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new(
+        "client_limit_rejections_total",
+        "Connections and streams rejected by per-client limits",
+    )
+    .namespace("veil");
+    let counter = CounterVec::new(opts, &["limit"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: 接続元ごとの上限による拒否を記録
+#[inline]
+pub fn record_client_limit_rejection(_limit: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        CLIENT_LIMIT_REJECTIONS_TOTAL
+            .with_label_values(&[_limit])
+            .inc();
+    }
+}
+
+// --- WAF（F-154）---
+
+#[cfg(feature = "metrics")]
+/// WAF の規則に一致したリクエスト数（outcome: blocked / detected / matched）
+pub(crate) static WAF_REQUESTS_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new("waf_requests_total", "Requests that matched WAF rules").namespace("veil");
+    let counter = CounterVec::new(opts, &["outcome"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+#[cfg(feature = "metrics")]
+/// WAF の規則ごとの一致数（rule_id）
+pub(crate) static WAF_RULE_MATCHES_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new("waf_rule_matches_total", "WAF rule matches by rule ID").namespace("veil");
+    let counter = CounterVec::new(opts, &["rule_id"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: WAF の規則に一致したリクエストを記録
+#[inline]
+pub fn record_waf_request(_outcome: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        WAF_REQUESTS_TOTAL.with_label_values(&[_outcome]).inc();
+    }
+}
+
+/// メトリクス: WAF の規則の一致を記録
+#[inline]
+pub fn record_waf_rule_match(_rule_id: u64) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        let mut buf = itoa::Buffer::new();
+        WAF_RULE_MATCHES_TOTAL
+            .with_label_values(&[buf.format(_rule_id)])
+            .inc();
+    }
+}
+
+// --- CORS（F-155）---
+
+#[cfg(feature = "metrics")]
+/// CORS の判定を受けた `Origin` 付きリクエスト数
+/// （outcome: preflight / preflight_rejected / allowed / rejected）
+pub(crate) static CORS_REQUESTS_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    let opts = Opts::new("cors_requests_total", "CORS requests by outcome").namespace("veil");
+    let counter = CounterVec::new(opts, &["outcome"]).unwrap();
+    METRICS_REGISTRY
+        .register(Box::new(counter.clone()))
+        .unwrap();
+    counter
+});
+
+/// メトリクス: CORS の判定を記録
+#[inline]
+pub fn record_cors_request(_outcome: &str) {
+    #[cfg(feature = "metrics")]
+    if metrics_runtime_enabled() {
+        CORS_REQUESTS_TOTAL.with_label_values(&[_outcome]).inc();
+    }
+}
+
 // --- gRPC（F-09、metrics + grpc 両方有効時）---
 
 #[cfg(all(feature = "metrics", feature = "grpc"))]
@@ -1085,6 +1736,8 @@ mod tests {
             http3_stream_opened();
             http3_stream_closed();
             http3_streams_closed_n(3);
+            record_upstream_h3_handshake("up", true);
+            record_upstream_h3_fallback("up");
         }
         set_metrics_runtime_enabled(true);
     }
@@ -0,0 +1,1349 @@
+//! ルート単位の OpenID Connect ログイン（F-150）
+//!
+//! `[route.oidc]` を設定したルートでは、veil が OIDC のリライングパーティとしてブラウザの
+//! ログインを扱う（認可コードフロー + PKCE `S256`）。
+//!
+//! - ログイン: セッションの無い GET / HEAD は IdP の認可エンドポイントへリダイレクトする
+//!   （それ以外のメソッドは 401）。`state`・`nonce`・code verifier・戻り先は暗号化した
+//!   `{cookie_name}_state` Cookie に入れるため、ワーカー・インスタンスをまたいでも
+//!   コールバックを処理できる。
+//! - コールバック（`redirect_uri` のパス）: `state` を照合し、トークンエンドポイントでコードを
+//!   交換する（[`crate::subrequest`]）。ID トークンは F-148 の検証器で署名・`iss`・`aud`・
+//!   `exp`・`required_claims` を検証し、`nonce` を照合する。
+//! - セッション: クレーム・期限・リフレッシュトークン（`forward_access_token` のときは
+//!   アクセストークンも）を AES-256-GCM で暗号化・認証した Cookie に保存し、サーバー側の状態は
+//!   持たない。期限の来たセッションはリフレッシュトークンで更新し、応答で Cookie を差し替える。
+//!   `session_lifetime_secs` を過ぎたセッションは更新せずに再ログインさせる。
+//! - ログアウト（`logout_path`）: Cookie を消し、IdP に `end_session_endpoint` があれば
+//!   そこへリダイレクトする。
+//!
+//! IdP のメタデータは設定の読み込み時に discovery で取得する。失敗したら専用スレッド
+//! （`veil-oidc`）が再試行し、取得できるまでログインが必要なリクエストは 503 にする。
+//! 検証済みのクレームは `claims_to_headers` で上流へのヘッダーにし、JWT 認証と同じく
+//! レートリミット（F-146）の `jwt_claim:` キーと外部認可（F-149）にも使う。
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Once, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use arc_swap::ArcSwapOption;
+use ftlog::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::{monotonic_ms, JwtAuthConfig, OidcClientAuthMethod, OidcConfig, ProxyTarget};
+use crate::http_utils::{base64_encode, base64url_decode, base64url_encode, status_reason_phrase};
+use crate::jwt_auth::{claims_to_headers, cookie_value, JwtAuth, JwtRejection};
+use crate::runtime::time::timeout;
+use crate::subrequest::Endpoint;
+use crate::tls_provider::crypto::rand::{SecureRandom, SystemRandom};
+use crate::tls_provider::crypto::{aead, digest, hmac};
+
+/// discovery のパス（issuer に付ける）
+const DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+
+/// discovery に失敗した IdP を再試行する間隔
+const DISCOVERY_RETRY_MS: u64 = 10_000;
+
+/// 再試行スレッドの巡回間隔
+const DISCOVERY_TICK: Duration = Duration::from_secs(1);
+
+/// ID トークンの JWKS を取り直す間隔（秒）
+const JWKS_REFRESH_SECS: u64 = 300;
+
+/// ログイン中の状態の Cookie の有効期間（秒）
+const LOGIN_STATE_MAX_AGE_SECS: u64 = 600;
+
+/// Cookie の値の上限（ブラウザが 1 つの Cookie に保存できる大きさ）
+const MAX_COOKIE_LEN: usize = 4096;
+
+/// Cookie の暗号鍵を `cookie_secret` から導出するラベル
+const COOKIE_KEY_LABEL: &[u8] = b"veil-oidc-cookie-v1";
+
+/// セッションに保存しない ID トークンのクレーム（検証にだけ使うもの）
+const TRANSIENT_CLAIMS: &[&str] = &[
+    "iss",
+    "aud",
+    "azp",
+    "exp",
+    "nbf",
+    "iat",
+    "auth_time",
+    "jti",
+    "nonce",
+    "at_hash",
+    "c_hash",
+];
+
+/// discovery を待っている IdP
+static PENDING: Lazy<Mutex<Vec<Weak<ProviderSource>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// 再試行スレッドの起動
+static DISCOVERER: Once = Once::new();
+
+/// discovery 済みの IdP
+struct Provider {
+    authorization_endpoint: String,
+    /// トークンエンドポイント（パスは `target.path_prefix`）
+    token: Endpoint,
+    end_session_endpoint: Option<String>,
+    /// ID トークンの検証器（`aud` = `client_id`）
+    id_token: JwtAuth,
+}
+
+/// IdP のメタデータ（取得できるまで再試行する）
+struct ProviderSource {
+    route: String,
+    cfg: OidcConfig,
+    provider: ArcSwapOption<Provider>,
+    /// 次に discovery を試みる時刻（[`monotonic_ms`]）
+    next_attempt_ms: AtomicU64,
+}
+
+impl ProviderSource {
+    /// 最初の discovery をこの場で行う（失敗時は再試行スレッドに任せる）
+    fn new(route: &str, cfg: &OidcConfig) -> Arc<Self> {
+        let source = Arc::new(Self {
+            route: route.to_string(),
+            cfg: cfg.clone(),
+            provider: ArcSwapOption::empty(),
+            next_attempt_ms: AtomicU64::new(0),
+        });
+        if !source.discover() {
+            let mut pending = PENDING.lock().unwrap();
+            pending.retain(|s| s.strong_count() > 0);
+            pending.push(Arc::downgrade(&source));
+            drop(pending);
+            DISCOVERER.call_once(|| {
+                if let Err(e) = std::thread::Builder::new()
+                    .name("veil-oidc".to_string())
+                    .spawn(discovery_loop)
+                {
+                    warn!("Failed to spawn OIDC discovery thread: {}", e);
+                }
+            });
+        }
+        source
+    }
+
+    /// discovery を行う（成功したら true）
+    fn discover(&self) -> bool {
+        match discover(&self.cfg) {
+            Ok(provider) => {
+                info!(
+                    "[OIDC] {} discovered provider {} (authorization: {})",
+                    self.route, self.cfg.issuer, provider.authorization_endpoint
+                );
+                self.provider.store(Some(Arc::new(provider)));
+                true
+            }
+            Err(e) => {
+                warn!(
+                    "[OIDC] {} discovery for {} failed: {}",
+                    self.route, self.cfg.issuer, e
+                );
+                self.next_attempt_ms
+                    .store(monotonic_ms() + DISCOVERY_RETRY_MS, Ordering::Relaxed);
+                false
+            }
+        }
+    }
+}
+
+/// discovery に失敗した IdP を再試行する（専用スレッド）
+// 理由付き allow: 専用スレッドの巡回待ち（イベントループ外）。
+#[allow(clippy::disallowed_methods)]
+fn discovery_loop() {
+    info!("OIDC discovery thread started");
+    loop {
+        std::thread::sleep(DISCOVERY_TICK);
+        let now = monotonic_ms();
+        let due: Vec<Arc<ProviderSource>> = {
+            let mut pending = PENDING.lock().unwrap();
+            pending.retain(|s| s.upgrade().is_some_and(|s| s.provider.load().is_none()));
+            pending
+                .iter()
+                .filter_map(Weak::upgrade)
+                .filter(|s| s.next_attempt_ms.load(Ordering::Relaxed) <= now)
+                .collect()
+        };
+        for source in due {
+            source.discover();
+        }
+    }
+}
+
+/// IdP のメタデータを取得する（`authorization_endpoint`・`token_endpoint`・`jwks_uri` が
+/// すべて設定されていれば取得しない）
+fn discover(cfg: &OidcConfig) -> Result<Provider, String> {
+    let metadata = if cfg.authorization_endpoint.is_some()
+        && cfg.token_endpoint.is_some()
+        && cfg.jwks_uri.is_some()
+    {
+        Value::Null
+    } else {
+        let url = format!("{}{}", cfg.issuer.trim_end_matches('/'), DISCOVERY_PATH);
+        let body = crate::health::http_get(&url, Duration::from_millis(cfg.timeout_ms))?;
+        let metadata: Value =
+            serde_json::from_slice(&body).map_err(|e| format!("{}: {}", url, e))?;
+        // 別の IdP のメタデータを受け入れない（OpenID Connect Discovery 1.0 §4.3）
+        let issuer = metadata.get("issuer").and_then(Value::as_str);
+        if issuer != Some(cfg.issuer.as_str()) {
+            return Err(format!(
+                "{}: issuer {:?} does not match the configured issuer",
+                url, issuer
+            ));
+        }
+        metadata
+    };
+    let endpoint = |configured: &Option<String>, key: &str| {
+        configured
+            .clone()
+            .or_else(|| metadata.get(key)?.as_str().map(str::to_string))
+    };
+    let authorization_endpoint = endpoint(&cfg.authorization_endpoint, "authorization_endpoint")
+        .ok_or("no authorization_endpoint")?;
+    let token_endpoint =
+        endpoint(&cfg.token_endpoint, "token_endpoint").ok_or("no token_endpoint")?;
+    let jwks_uri = endpoint(&cfg.jwks_uri, "jwks_uri").ok_or("no jwks_uri")?;
+    let end_session_endpoint = endpoint(&cfg.end_session_endpoint, "end_session_endpoint");
+    if ProxyTarget::parse(&authorization_endpoint).is_none() {
+        return Err(format!(
+            "invalid authorization_endpoint \"{}\"",
+            authorization_endpoint
+        ));
+    }
+    let token = ProxyTarget::parse(&token_endpoint)
+        .ok_or_else(|| format!("invalid token_endpoint \"{}\"", token_endpoint))?;
+    let id_token = JwtAuth::new(&JwtAuthConfig {
+        algorithms: cfg.algorithms.clone(),
+        jwks_file: None,
+        jwks_url: Some(jwks_uri),
+        jwks_refresh_secs: JWKS_REFRESH_SECS,
+        jwks_timeout_ms: cfg.timeout_ms,
+        issuer: Some(cfg.issuer.clone()),
+        audiences: vec![cfg.client_id.clone()],
+        require_exp: true,
+        clock_skew_secs: cfg.clock_skew_secs,
+        token_cookie: None,
+        required_claims: cfg.required_claims.clone(),
+        claims_to_headers: BTreeMap::new(),
+        forward_token: true,
+    })?;
+    Ok(Provider {
+        authorization_endpoint,
+        token: Endpoint::new(token),
+        end_session_endpoint,
+        id_token,
+    })
+}
+
+/// 暗号化して Cookie に保存するセッション
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Session {
+    /// ID トークンのクレーム（[`TRANSIENT_CLAIMS`] を除く）
+    #[serde(rename = "c")]
+    claims: Value,
+    /// 更新が必要になる時刻（UNIX 秒、アクセストークンか ID トークンの期限）
+    #[serde(rename = "e")]
+    expires_at: u64,
+    #[serde(rename = "r", default, skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+    #[serde(rename = "a", default, skip_serializing_if = "Option::is_none")]
+    access_token: Option<String>,
+    /// ログインした時刻（UNIX 秒、更新しても変わらない）
+    #[serde(rename = "t")]
+    created_at: u64,
+}
+
+/// 暗号化して `{cookie_name}_state` Cookie に保存するログイン中の状態
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct LoginState {
+    #[serde(rename = "s")]
+    state: String,
+    #[serde(rename = "n")]
+    nonce: String,
+    /// PKCE の code verifier
+    #[serde(rename = "v")]
+    verifier: String,
+    /// ログイン後に戻るパス（クエリを含む）
+    #[serde(rename = "r")]
+    return_to: String,
+    /// 認可リクエストで送った `redirect_uri`（トークンリクエストで同じ値を送る）
+    #[serde(rename = "u")]
+    redirect_uri: String,
+    /// UNIX 秒
+    #[serde(rename = "e")]
+    expires_at: u64,
+}
+
+/// トークンエンドポイントの失敗
+#[derive(Debug)]
+enum TokenError {
+    /// IdP がリクエストを拒否した（4xx、リフレッシュトークンの失効等）
+    Rejected(String),
+    /// IdP に到達できない・応答が不正
+    Unavailable(String),
+}
+
+/// 認証に使うリクエストの属性
+pub(crate) struct OidcRequest<'a> {
+    pub method: &'a [u8],
+    /// クエリ文字列を含むパス
+    pub path: &'a [u8],
+    /// `Host`（HTTP/2・HTTP/3 は `:authority`）
+    pub host: &'a [u8],
+    /// 疑似ヘッダーを除くリクエストヘッダー
+    pub headers: &'a [(&'a [u8], &'a [u8])],
+}
+
+/// 認証の結果
+#[derive(Debug)]
+pub(crate) enum OidcOutcome {
+    /// ログイン済み（上流へ転送する）
+    Authenticated {
+        claims: Value,
+        /// 上流へのリクエストに設定するヘッダー
+        headers: Vec<(String, String)>,
+        /// クライアントへの応答に付ける `Set-Cookie`（セッションを更新したとき）
+        set_cookie: Option<String>,
+    },
+    /// veil が応答する（リダイレクト・コールバック・ログアウト・拒否）
+    Respond(OidcResponse),
+}
+
+/// veil が返す応答
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OidcResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+}
+
+impl OidcResponse {
+    fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: vec![("Cache-Control".to_string(), "no-store".to_string())],
+        }
+    }
+
+    fn redirect(location: String) -> Self {
+        Self::new(302).header("Location", location)
+    }
+
+    fn header(mut self, name: &str, value: String) -> Self {
+        self.headers.push((name.to_string(), value));
+        self
+    }
+
+    /// 応答ボディ（ステータスの説明）
+    pub fn body(&self) -> &'static [u8] {
+        status_reason_phrase(self.status).as_bytes()
+    }
+
+    /// HTTP/2・HTTP/3 の応答ヘッダー（小文字）
+    #[cfg_attr(not(any(feature = "http2", feature = "http3")), allow(dead_code))]
+    pub fn h2_headers(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_ascii_lowercase().into_bytes(),
+                    value.as_bytes().to_vec(),
+                )
+            })
+            .chain(std::iter::once((
+                b"content-type".to_vec(),
+                b"text/plain".to_vec(),
+            )))
+            .collect()
+    }
+
+    /// HTTP/1.1 の応答
+    pub fn http1_response(&self) -> Vec<u8> {
+        let body = self.body();
+        let mut response = format!(
+            "HTTP/1.1 {} {}\r\n",
+            self.status,
+            status_reason_phrase(self.status)
+        )
+        .into_bytes();
+        for (name, value) in &self.headers {
+            response.extend_from_slice(name.as_bytes());
+            response.extend_from_slice(b": ");
+            response.extend_from_slice(value.as_bytes());
+            response.extend_from_slice(b"\r\n");
+        }
+        response.extend_from_slice(
+            format!(
+                "Content-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .as_bytes(),
+        );
+        response.extend_from_slice(body);
+        response
+    }
+}
+
+/// ルートの OIDC ログイン（`Route::prepare` で構築し `SecurityConfig::oidc` で共有する）
+pub struct Oidc {
+    route: String,
+    client_id: String,
+    client_secret: Option<String>,
+    auth_method: OidcClientAuthMethod,
+    /// 空白区切りのスコープ
+    scope: String,
+    /// 設定の `redirect_uri`（パスならリクエストのホストから URL を組み立てる）
+    redirect_uri: String,
+    /// コールバックのパス（`redirect_uri` のパス部分）
+    callback_path: String,
+    logout_path: String,
+    post_logout_redirect_uri: Option<String>,
+    cookie_name: String,
+    state_cookie_name: String,
+    /// セッション Cookie の `; Path=/; Secure; HttpOnly; SameSite=Lax` 等（Max-Age を除く）
+    cookie_attributes: String,
+    /// ログイン中の状態の Cookie の属性（SameSite は常に Lax）
+    state_cookie_attributes: String,
+    key: aead::LessSafeKey,
+    session_lifetime_secs: u64,
+    claims_to_headers: Vec<(String, String)>,
+    forward_access_token: bool,
+    timeout: Duration,
+    provider: Arc<ProviderSource>,
+}
+
+impl std::fmt::Debug for Oidc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Oidc")
+            .field("route", &self.route)
+            .field("issuer", &self.provider.cfg.issuer)
+            .field("client_id", &self.client_id)
+            .field("cookie_name", &self.cookie_name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Oidc {
+    /// 設定から構築する（設定の妥当性は読み込み時に検証済みであること）
+    ///
+    /// IdP の discovery をこの場で行う（失敗時は再試行スレッドに任せる）。
+    pub(crate) fn new(route: String, cfg: &OidcConfig) -> Self {
+        let callback_path = match ProxyTarget::parse(&cfg.redirect_uri) {
+            Some(target) => target.path_prefix,
+            None => cfg.redirect_uri.clone(),
+        };
+        let callback_path = match callback_path.find('?') {
+            Some(idx) => callback_path[..idx].to_string(),
+            None => callback_path,
+        };
+        let attributes = |same_site: &str| {
+            let mut attributes = String::with_capacity(64);
+            attributes.push_str("; Path=");
+            attributes.push_str(&cfg.cookie_path);
+            if cfg.cookie_secure {
+                attributes.push_str("; Secure");
+            }
+            attributes.push_str("; HttpOnly; SameSite=");
+            attributes.push_str(same_site);
+            attributes
+        };
+        let key_bytes = hmac::sign(
+            &hmac::Key::new(hmac::HMAC_SHA256, cfg.cookie_secret.as_bytes()),
+            COOKIE_KEY_LABEL,
+        );
+        let key = aead::UnboundKey::new(&aead::AES_256_GCM, key_bytes.as_ref())
+            .expect("HMAC-SHA256 output is a valid AES-256 key");
+        Self {
+            provider: ProviderSource::new(&route, cfg),
+            route,
+            client_id: cfg.client_id.clone(),
+            client_secret: cfg.client_secret.clone().filter(|s| !s.is_empty()),
+            auth_method: cfg.token_endpoint_auth_method,
+            scope: cfg.scopes.join(" "),
+            redirect_uri: cfg.redirect_uri.clone(),
+            callback_path,
+            logout_path: cfg.logout_path.clone(),
+            post_logout_redirect_uri: cfg.post_logout_redirect_uri.clone(),
+            cookie_name: cfg.cookie_name.clone(),
+            state_cookie_name: format!("{}_state", cfg.cookie_name),
+            cookie_attributes: attributes(cfg.cookie_same_site.as_str()),
+            state_cookie_attributes: attributes("Lax"),
+            key: aead::LessSafeKey::new(key),
+            session_lifetime_secs: cfg.session_lifetime_secs,
+            claims_to_headers: cfg
+                .claims_to_headers
+                .iter()
+                .map(|(claim, header)| (claim.clone(), header.clone()))
+                .collect(),
+            forward_access_token: cfg.forward_access_token,
+            timeout: Duration::from_millis(cfg.timeout_ms),
+        }
+    }
+
+    /// クライアントから上流へ渡さないリクエストヘッダー
+    ///
+    /// `claims_to_headers` の転送先（なりすまし防止）と、`forward_access_token` の
+    /// `Authorization`。
+    pub fn stripped_request_headers(&self) -> impl Iterator<Item = String> + '_ {
+        let authorization = self
+            .forward_access_token
+            .then(|| "Authorization".to_string());
+        self.claims_to_headers
+            .iter()
+            .map(|(_, header)| header.clone())
+            .chain(authorization)
+    }
+
+    /// リクエストを認証する
+    ///
+    /// 結果は `veil_oidc_total` に記録する。
+    pub(crate) async fn authenticate(&self, req: &OidcRequest<'_>) -> OidcOutcome {
+        let (path, query) = match req.path.iter().position(|&b| b == b'?') {
+            Some(idx) => (&req.path[..idx], &req.path[idx + 1..]),
+            None => (req.path, &b""[..]),
+        };
+        if path == self.callback_path.as_bytes() {
+            return self.callback(req, query).await;
+        }
+        if path == self.logout_path.as_bytes() {
+            return self.logout();
+        }
+
+        let now = unix_now();
+        let session = cookie_value(req.headers, &self.cookie_name)
+            .and_then(|value| self.open::<Session>(&self.cookie_name, value))
+            .filter(|s| now < s.created_at.saturating_add(self.session_lifetime_secs));
+        if let Some(mut session) = session {
+            if now < session.expires_at {
+                crate::metrics::record_oidc("ok");
+                return self.authenticated(session, None);
+            }
+            if let Some(refresh_token) = session.refresh_token.clone() {
+                match self.refresh(&mut session, &refresh_token, now).await {
+                    Ok(()) => {
+                        let Some(cookie) = self.session_cookie(&session, now) else {
+                            return self.error(500);
+                        };
+                        crate::metrics::record_oidc("refreshed");
+                        return self.authenticated(session, Some(cookie));
+                    }
+                    Err(TokenError::Rejected(e)) => {
+                        debug!("[OIDC] {} session refresh rejected: {}", self.route, e);
+                    }
+                    Err(TokenError::Unavailable(e)) => {
+                        warn!("[OIDC] {} session refresh failed: {}", self.route, e);
+                        return self.error(503);
+                    }
+                }
+            }
+        }
+        self.login(req, now)
+    }
+
+    /// ログイン済みの結果を組み立てる
+    fn authenticated(&self, session: Session, set_cookie: Option<String>) -> OidcOutcome {
+        let mut headers = claims_to_headers(&self.claims_to_headers, &session.claims);
+        if let Some(access_token) = session.access_token.filter(|_| self.forward_access_token) {
+            headers.push((
+                "Authorization".to_string(),
+                format!("Bearer {}", access_token),
+            ));
+        }
+        OidcOutcome::Authenticated {
+            claims: session.claims,
+            headers,
+            set_cookie,
+        }
+    }
+
+    /// IdP の認可エンドポイントへリダイレクトする（GET / HEAD 以外は 401）
+    fn login(&self, req: &OidcRequest<'_>, now: u64) -> OidcOutcome {
+        if req.method != b"GET" && req.method != b"HEAD" {
+            crate::metrics::record_oidc("unauthorized");
+            return OidcOutcome::Respond(OidcResponse::new(401));
+        }
+        let Some(provider) = self.provider.provider.load_full() else {
+            debug!("[OIDC] {} provider is not available yet", self.route);
+            return self.error(503);
+        };
+        let (Some(state), Some(nonce), Some(verifier)) =
+            (random_token(16), random_token(16), random_token(32))
+        else {
+            return self.error(500);
+        };
+        let challenge =
+            base64url_encode(digest::digest(&digest::SHA256, verifier.as_bytes()).as_ref());
+        let redirect_uri = self.redirect_uri_for(req.host);
+        // 別オリジンへのリダイレクトに使わせない
+        let return_to = local_return_to(req.path).unwrap_or_else(|| "/".to_string());
+        let mut location = provider.authorization_endpoint.clone();
+        location.push(if location.contains('?') { '&' } else { '?' });
+        location.push_str(&form_encode(&[
+            ("response_type", "code"),
+            ("client_id", &self.client_id),
+            ("redirect_uri", &redirect_uri),
+            ("scope", &self.scope),
+            ("state", &state),
+            ("nonce", &nonce),
+            ("code_challenge", &challenge),
+            ("code_challenge_method", "S256"),
+        ]));
+        let login = LoginState {
+            state,
+            nonce,
+            verifier,
+            return_to,
+            redirect_uri,
+            expires_at: now + LOGIN_STATE_MAX_AGE_SECS,
+        };
+        let Some(cookie) = self.cookie(
+            &self.state_cookie_name,
+            &login,
+            LOGIN_STATE_MAX_AGE_SECS,
+            &self.state_cookie_attributes,
+        ) else {
+            return self.error(500);
+        };
+        crate::metrics::record_oidc("redirected");
+        OidcOutcome::Respond(OidcResponse::redirect(location).header("Set-Cookie", cookie))
+    }
+
+    /// 認可コードを交換してセッションを発行する
+    async fn callback(&self, req: &OidcRequest<'_>, query: &[u8]) -> OidcOutcome {
+        let params = parse_query(query);
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        let now = unix_now();
+        let fail = |status: u16, reason: &str| {
+            warn!("[OIDC] {} login failed: {}", self.route, reason);
+            crate::metrics::record_oidc("callback_failed");
+            OidcOutcome::Respond(
+                OidcResponse::new(status).header("Set-Cookie", self.clear_state_cookie()),
+            )
+        };
+
+        if let Some(error) = param("error") {
+            return fail(401, &format!("provider returned error \"{}\"", error));
+        }
+        let Some(login) = cookie_value(req.headers, &self.state_cookie_name)
+            .and_then(|value| self.open::<LoginState>(&self.state_cookie_name, value))
+            .filter(|login| now < login.expires_at)
+        else {
+            return fail(400, "missing or expired login state");
+        };
+        if param("state") != Some(login.state.as_str()) {
+            return fail(400, "state mismatch");
+        }
+        let Some(code) = param("code") else {
+            return fail(400, "missing authorization code");
+        };
+        let Some(provider) = self.provider.provider.load_full() else {
+            return self.error(503);
+        };
+
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", login.redirect_uri.as_str()),
+            ("code_verifier", login.verifier.as_str()),
+        ];
+        let tokens = match self.token_request(&provider, &form).await {
+            Ok(tokens) => tokens,
+            Err(TokenError::Rejected(e)) => return fail(401, &e),
+            Err(TokenError::Unavailable(e)) => {
+                warn!("[OIDC] {} token endpoint unavailable: {}", self.route, e);
+                return self.error(502);
+            }
+        };
+        let Some(id_token) = tokens.get("id_token").and_then(Value::as_str) else {
+            return fail(401, "token response has no id_token");
+        };
+        let claims = match provider.id_token.verify_token(id_token.as_bytes()) {
+            Ok(claims) => claims,
+            Err(JwtRejection::Forbidden) => return fail(403, "required claims not satisfied"),
+            Err(_) => return fail(401, "invalid ID token"),
+        };
+        if claims.get("nonce").and_then(Value::as_str) != Some(login.nonce.as_str()) {
+            return fail(401, "nonce mismatch");
+        }
+
+        let session = self.new_session(claims, &tokens, None, now);
+        let Some(cookie) = self.session_cookie(&session, now) else {
+            return self.error(500);
+        };
+        crate::metrics::record_oidc("callback_ok");
+        OidcOutcome::Respond(
+            OidcResponse::redirect(login.return_to)
+                .header("Set-Cookie", cookie)
+                .header("Set-Cookie", self.clear_state_cookie()),
+        )
+    }
+
+    /// リフレッシュトークンでセッションを更新する
+    async fn refresh(
+        &self,
+        session: &mut Session,
+        refresh_token: &str,
+        now: u64,
+    ) -> Result<(), TokenError> {
+        let provider = self
+            .provider
+            .provider
+            .load_full()
+            .ok_or_else(|| TokenError::Unavailable("provider is not available".to_string()))?;
+        let form = [
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ];
+        let tokens = self.token_request(&provider, &form).await?;
+        // 新しい ID トークンが返ればクレームを更新する（同じ利用者であること）
+        let claims = match tokens.get("id_token").and_then(Value::as_str) {
+            Some(id_token) => {
+                let claims = provider
+                    .id_token
+                    .verify_token(id_token.as_bytes())
+                    .map_err(|_| TokenError::Rejected("invalid ID token".to_string()))?;
+                if claims.get("sub") != session.claims.get("sub") {
+                    return Err(TokenError::Rejected("subject changed".to_string()));
+                }
+                claims
+            }
+            None => session.claims.clone(),
+        };
+        *session = self.new_session(claims, &tokens, Some(session), now);
+        Ok(())
+    }
+
+    /// トークンエンドポイントの応答からセッションを組み立てる
+    ///
+    /// `previous` は更新前のセッション（ログイン時刻と、応答に無いリフレッシュトークンを引き継ぐ）。
+    fn new_session(
+        &self,
+        mut claims: Value,
+        tokens: &Value,
+        previous: Option<&Session>,
+        now: u64,
+    ) -> Session {
+        let id_token_exp = claims.get("exp").and_then(Value::as_u64);
+        if let Some(claims) = claims.as_object_mut() {
+            for claim in TRANSIENT_CLAIMS {
+                claims.remove(*claim);
+            }
+        }
+        let expires_at = tokens
+            .get("expires_in")
+            .and_then(Value::as_u64)
+            .map(|secs| now.saturating_add(secs))
+            .or(id_token_exp)
+            .unwrap_or(now);
+        let token = |name: &str| tokens.get(name).and_then(Value::as_str).map(str::to_string);
+        Session {
+            claims,
+            expires_at,
+            refresh_token: token("refresh_token")
+                .or_else(|| previous.and_then(|s| s.refresh_token.clone())),
+            access_token: token("access_token").filter(|_| self.forward_access_token),
+            created_at: previous.map_or(now, |s| s.created_at),
+        }
+    }
+
+    /// セッション Cookie を消して IdP のログアウトへリダイレクトする
+    fn logout(&self) -> OidcOutcome {
+        let location = match self
+            .provider
+            .provider
+            .load()
+            .as_ref()
+            .and_then(|p| p.end_session_endpoint.clone())
+        {
+            Some(mut endpoint) => {
+                let mut params = vec![("client_id", self.client_id.as_str())];
+                if let Some(uri) = &self.post_logout_redirect_uri {
+                    params.push(("post_logout_redirect_uri", uri.as_str()));
+                }
+                endpoint.push(if endpoint.contains('?') { '&' } else { '?' });
+                endpoint.push_str(&form_encode(&params));
+                endpoint
+            }
+            None => self
+                .post_logout_redirect_uri
+                .clone()
+                .unwrap_or_else(|| "/".to_string()),
+        };
+        crate::metrics::record_oidc("logout");
+        OidcOutcome::Respond(
+            OidcResponse::redirect(location)
+                .header(
+                    "Set-Cookie",
+                    format!("{}={}; Max-Age=0", self.cookie_name, self.cookie_attributes),
+                )
+                .header("Set-Cookie", self.clear_state_cookie()),
+        )
+    }
+
+    /// トークンエンドポイントへフォームを POST する（クライアント認証を付ける）
+    async fn token_request(
+        &self,
+        provider: &Provider,
+        form: &[(&str, &str)],
+    ) -> Result<Value, TokenError> {
+        let mut form = form.to_vec();
+        let mut authorization = None;
+        match (&self.client_secret, self.auth_method) {
+            (Some(secret), OidcClientAuthMethod::ClientSecretBasic) => {
+                // RFC 6749 §2.3.1: ID とシークレットはフォームエンコードしてから連結する
+                let credentials = format!(
+                    "{}:{}",
+                    form_encode_component(&self.client_id),
+                    form_encode_component(secret)
+                );
+                authorization = Some(base64_encode(credentials.as_bytes()));
+            }
+            (Some(secret), OidcClientAuthMethod::ClientSecretPost) => {
+                form.push(("client_id", &self.client_id));
+                form.push(("client_secret", secret));
+            }
+            (None, _) => form.push(("client_id", &self.client_id)),
+        }
+        let body = form_encode(&form);
+        let endpoint = &provider.token;
+        let mut request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/x-www-form-urlencoded\r\nAccept: application/json\r\nContent-Length: {}\r\nUser-Agent: veil\r\n",
+            endpoint.target.path_prefix,
+            endpoint.authority(),
+            body.len()
+        );
+        if let Some(credentials) = authorization {
+            request.push_str("Authorization: Basic ");
+            request.push_str(&credentials);
+            request.push_str("\r\n");
+        }
+        request.push_str("\r\n");
+        request.push_str(&body);
+
+        let response = match timeout(self.timeout, endpoint.send(request.into_bytes(), false)).await
+        {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => return Err(TokenError::Unavailable(e)),
+            Err(_) => return Err(TokenError::Unavailable("timed out".to_string())),
+        };
+        let json = serde_json::from_slice::<Value>(&response.body)
+            .ok()
+            .filter(Value::is_object);
+        match (response.status, json) {
+            (200..=299, Some(json)) => Ok(json),
+            (200..=299, None) => Err(TokenError::Unavailable(
+                "token response is not a JSON object".to_string(),
+            )),
+            (400..=499, json) => Err(TokenError::Rejected(format!(
+                "token endpoint returned {} ({})",
+                response.status,
+                json.as_ref()
+                    .and_then(|j| j.get("error"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("no error code")
+            ))),
+            (status, _) => Err(TokenError::Unavailable(format!(
+                "token endpoint returned {}",
+                status
+            ))),
+        }
+    }
+
+    /// `redirect_uri` の URL（パスの設定ならリクエストのホストから組み立てる）
+    fn redirect_uri_for(&self, host: &[u8]) -> String {
+        if self.redirect_uri.starts_with('/') {
+            format!(
+                "https://{}{}",
+                String::from_utf8_lossy(host),
+                self.redirect_uri
+            )
+        } else {
+            self.redirect_uri.clone()
+        }
+    }
+
+    /// セッション Cookie（有効期間はログインからの残り時間）
+    fn session_cookie(&self, session: &Session, now: u64) -> Option<String> {
+        let max_age = session
+            .created_at
+            .saturating_add(self.session_lifetime_secs)
+            .saturating_sub(now);
+        self.cookie(&self.cookie_name, session, max_age, &self.cookie_attributes)
+    }
+
+    /// ログイン中の状態の Cookie を消す `Set-Cookie`
+    fn clear_state_cookie(&self) -> String {
+        format!(
+            "{}={}; Max-Age=0",
+            self.state_cookie_name, self.state_cookie_attributes
+        )
+    }
+
+    /// 値を暗号化した `Set-Cookie`（大きすぎる・暗号化できなければ None）
+    fn cookie<T: Serialize>(
+        &self,
+        name: &str,
+        value: &T,
+        max_age: u64,
+        attributes: &str,
+    ) -> Option<String> {
+        let sealed = self.seal(name, &serde_json::to_vec(value).ok()?)?;
+        if sealed.len() > MAX_COOKIE_LEN {
+            warn!(
+                "[OIDC] {} cookie {} is too large ({} bytes)",
+                self.route,
+                name,
+                sealed.len()
+            );
+            return None;
+        }
+        Some(format!(
+            "{}={}{}; Max-Age={}",
+            name, sealed, attributes, max_age
+        ))
+    }
+
+    /// `base64url(nonce || 暗号文 || タグ)`（AAD は Cookie 名、別の Cookie への流用を防ぐ）
+    fn seal(&self, name: &str, plaintext: &[u8]) -> Option<String> {
+        let mut nonce = [0u8; aead::NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).ok()?;
+        let mut data = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                aead::Nonce::assume_unique_for_key(nonce),
+                aead::Aad::from(name.as_bytes()),
+                &mut data,
+            )
+            .ok()?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&data);
+        Some(base64url_encode(&sealed))
+    }
+
+    /// [`Oidc::seal`] した Cookie を復号する（改ざん・別の鍵なら None）
+    fn open<T: for<'de> Deserialize<'de>>(&self, name: &str, value: &[u8]) -> Option<T> {
+        let mut data = base64url_decode(value)?;
+        if data.len() < aead::NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = data.split_at_mut(aead::NONCE_LEN);
+        let nonce = aead::Nonce::try_assume_unique_for_key(nonce).ok()?;
+        let plaintext = self
+            .key
+            .open_in_place(nonce, aead::Aad::from(name.as_bytes()), ciphertext)
+            .ok()?;
+        serde_json::from_slice(plaintext).ok()
+    }
+
+    /// veil 側の失敗（500 / 502 / 503）
+    fn error(&self, status: u16) -> OidcOutcome {
+        crate::metrics::record_oidc("error");
+        OidcOutcome::Respond(OidcResponse::new(status))
+    }
+}
+
+/// ランダムなトークン（`len` バイトの base64url）
+fn random_token(len: usize) -> Option<String> {
+    let mut bytes = vec![0u8; len];
+    SystemRandom::new().fill(&mut bytes).ok()?;
+    Some(base64url_encode(&bytes))
+}
+
+/// `application/x-www-form-urlencoded` の 1 要素
+fn form_encode_component(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for &b in value.as_bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(b as char)
+            }
+            b' ' => out.push('+'),
+            _ => {
+                out.push('%');
+                out.push(char::from(b"0123456789ABCDEF"[(b >> 4) as usize]));
+                out.push(char::from(b"0123456789ABCDEF"[(b & 0x0f) as usize]));
+            }
+        }
+    }
+    out
+}
+
+/// `application/x-www-form-urlencoded`（クエリ文字列・フォーム）
+fn form_encode(pairs: &[(&str, &str)]) -> String {
+    pairs
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "{}={}",
+                form_encode_component(name),
+                form_encode_component(value)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// クエリ文字列をデコードする（UTF-8 として不正な値は置き換える）
+fn parse_query(query: &[u8]) -> Vec<(String, String)> {
+    query
+        .split(|&b| b == b'&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = match pair.iter().position(|&b| b == b'=') {
+                Some(idx) => (&pair[..idx], &pair[idx + 1..]),
+                None => (pair, &b""[..]),
+            };
+            (percent_decode(name), percent_decode(value))
+        })
+        .collect()
+}
+
+/// ログイン後の戻り先に使えるパス（同じオリジンの `/` で始まるパスだけ）
+///
+/// `//host` と、ブラウザが `//` と同じに扱う `\`・制御文字を含むものは使わない
+/// （`%5C` のようにエンコードしたものもデコードして確かめる）。
+fn local_return_to(path: &[u8]) -> Option<String> {
+    let path = std::str::from_utf8(path).ok()?;
+    let decoded = percent_decode(path.as_bytes());
+    let unsafe_char = |c: char| c == '\\' || c.is_control();
+    let local = path.starts_with('/')
+        && !path.starts_with("//")
+        && !decoded.starts_with("//")
+        && !path.chars().chain(decoded.chars()).any(unsafe_char);
+    local.then(|| path.to_string())
+}
+
+/// `%XX` と `+` をデコードする
+fn percent_decode(input: &[u8]) -> String {
+    let hex = |b: u8| char::from(b).to_digit(16).map(|d| d as u8);
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'%' if i + 2 < input.len() => match (hex(input[i + 1]), hex(input[i + 2])) {
+                (Some(hi), Some(lo)) => {
+                    out.push(hi << 4 | lo);
+                    i += 2;
+                }
+                _ => out.push(b'%'),
+            },
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// 現在の UNIX 時刻（秒）
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    /// discovery に失敗する（到達できない issuer）ログインに、テスト用の IdP を入れる
+    fn oidc(extra: &str) -> Oidc {
+        let cfg: OidcConfig = toml::from_str(&format!(
+            "issuer = \"http://127.0.0.1:1\"\nclient_id = \"app\"\ncookie_secret = \"{}\"\n{}",
+            SECRET, extra
+        ))
+        .unwrap();
+        let oidc = Oidc::new("route[0]".to_string(), &cfg);
+        let jwt: JwtAuthConfig = toml::from_str("").unwrap();
+        oidc.provider.provider.store(Some(Arc::new(Provider {
+            authorization_endpoint: "https://idp.example/authorize".to_string(),
+            token: Endpoint::new(ProxyTarget::parse("https://idp.example/token").unwrap()),
+            end_session_endpoint: Some("https://idp.example/logout".to_string()),
+            id_token: JwtAuth::without_keys(&jwt),
+        })));
+        oidc
+    }
+
+    fn request<'a>(method: &'a [u8], path: &'a [u8]) -> OidcRequest<'a> {
+        OidcRequest {
+            method,
+            path,
+            host: b"app.example",
+            headers: &[],
+        }
+    }
+
+    fn response(outcome: OidcOutcome) -> OidcResponse {
+        match outcome {
+            OidcOutcome::Respond(response) => response,
+            other => panic!("expected a response, got {:?}", other),
+        }
+    }
+
+    fn header<'a>(response: &'a OidcResponse, name: &str) -> Vec<&'a str> {
+        response
+            .headers
+            .iter()
+            .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn login_redirects_to_provider_with_pkce_and_state_cookie() {
+        let oidc = oidc("");
+        let now = unix_now();
+        let redirect = response(oidc.login(&request(b"GET", b"/app?tab=1"), now));
+        assert_eq!(redirect.status, 302);
+        assert_eq!(header(&redirect, "cache-control"), ["no-store"]);
+
+        let location = header(&redirect, "location")[0];
+        let (endpoint, query) = location.split_once('?').unwrap();
+        assert_eq!(endpoint, "https://idp.example/authorize");
+        let params = parse_query(query.as_bytes());
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+                .unwrap()
+        };
+        assert_eq!(param("response_type"), "code");
+        assert_eq!(param("client_id"), "app");
+        assert_eq!(param("redirect_uri"), "https://app.example/oauth2/callback");
+        assert_eq!(param("scope"), "openid profile email");
+        assert_eq!(param("code_challenge_method"), "S256");
+
+        let cookie = header(&redirect, "set-cookie")[0];
+        assert!(cookie.starts_with("veil_oidc_state="));
+        assert!(cookie.ends_with("; Path=/; Secure; HttpOnly; SameSite=Lax; Max-Age=600"));
+        let value = &cookie["veil_oidc_state=".len()..cookie.find(';').unwrap()];
+        let login: LoginState = oidc.open("veil_oidc_state", value.as_bytes()).unwrap();
+        assert_eq!(login.state, param("state"));
+        assert_eq!(login.nonce, param("nonce"));
+        assert_eq!(login.return_to, "/app?tab=1");
+        assert_eq!(login.redirect_uri, param("redirect_uri"));
+        assert_eq!(login.expires_at, now + LOGIN_STATE_MAX_AGE_SECS);
+        let challenge =
+            base64url_encode(digest::digest(&digest::SHA256, login.verifier.as_bytes()).as_ref());
+        assert_eq!(param("code_challenge"), challenge);
+    }
+
+    #[test]
+    fn login_rejects_other_methods_and_waits_for_discovery() {
+        let oidc = oidc("redirect_uri = \"https://login.example/cb\"");
+        assert_eq!(oidc.callback_path, "/cb");
+        let now = unix_now();
+        assert_eq!(
+            response(oidc.login(&request(b"POST", b"/api"), now)).status,
+            401
+        );
+        // 別オリジンへの戻り先は使わない
+        let redirect = response(oidc.login(&request(b"GET", b"//evil.example/"), now));
+        let cookie = header(&redirect, "set-cookie")[0];
+        let value = &cookie["veil_oidc_state=".len()..cookie.find(';').unwrap()];
+        let login: LoginState = oidc.open("veil_oidc_state", value.as_bytes()).unwrap();
+        assert_eq!(login.return_to, "/");
+        assert_eq!(login.redirect_uri, "https://login.example/cb");
+        // ブラウザが `//` と同じに扱う `\`（エンコードしたものも）・制御文字も使わない
+        for path in [
+            &b"/\\evil.example"[..],
+            b"/%5Cevil.example",
+            b"/%5cevil.example",
+            b"/%2F%2Fevil.example",
+            b"/a\tb",
+            b"/a%0D%0ALocation:%20x",
+        ] {
+            assert_eq!(local_return_to(path), None, "{:?}", path);
+        }
+        assert_eq!(
+            local_return_to(b"/app/%E3%81%82?next=%2Fhome").as_deref(),
+            Some("/app/%E3%81%82?next=%2Fhome")
+        );
+
+        oidc.provider.provider.store(None);
+        assert_eq!(
+            response(oidc.login(&request(b"GET", b"/"), now)).status,
+            503
+        );
+    }
+
+    #[test]
+    fn sealed_cookies_reject_tampering_and_other_names() {
+        let oidc = oidc("");
+        let session = Session {
+            claims: serde_json::json!({"sub": "alice"}),
+            expires_at: 100,
+            refresh_token: Some("rt".to_string()),
+            access_token: None,
+            created_at: 50,
+        };
+        let sealed = oidc
+            .seal("veil_oidc", &serde_json::to_vec(&session).unwrap())
+            .unwrap();
+        assert_eq!(
+            oidc.open::<Session>("veil_oidc", sealed.as_bytes()),
+            Some(session)
+        );
+        // 同じ値でも別の Cookie としては開けない（AAD が Cookie 名）
+        assert_eq!(
+            oidc.open::<Session>("veil_oidc_state", sealed.as_bytes()),
+            None
+        );
+        let mut tampered = sealed.into_bytes();
+        let last = tampered.len() - 2;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+        assert_eq!(oidc.open::<Session>("veil_oidc", &tampered), None);
+        assert_eq!(oidc.open::<Session>("veil_oidc", b"!!"), None);
+
+        // 別の秘密で暗号化した Cookie も開けない
+        let other: OidcConfig = toml::from_str(
+            "issuer = \"http://127.0.0.1:1\"\nclient_id = \"app\"\ncookie_secret = \"ffffffffffffffffffffffffffffffff\"",
+        )
+        .unwrap();
+        let other = Oidc::new("route[1]".to_string(), &other);
+        let sealed = other.seal("veil_oidc", b"{}").unwrap();
+        assert_eq!(oidc.open::<Value>("veil_oidc", sealed.as_bytes()), None);
+    }
+
+    #[test]
+    fn sessions_keep_login_time_and_drop_transient_claims() {
+        let oidc = oidc("forward_access_token = true");
+        let claims = serde_json::json!({
+            "sub": "alice", "email": "alice@example.com", "iss": "http://127.0.0.1:1",
+            "aud": "app", "exp": 2000, "iat": 1000, "nonce": "n"
+        });
+        let tokens =
+            serde_json::json!({"access_token": "at", "refresh_token": "rt", "expires_in": 60});
+        let session = oidc.new_session(claims.clone(), &tokens, None, 1000);
+        assert_eq!(
+            session.claims,
+            serde_json::json!({"sub": "alice", "email": "alice@example.com"})
+        );
+        assert_eq!(session.expires_at, 1060);
+        assert_eq!(session.created_at, 1000);
+        assert_eq!(session.access_token.as_deref(), Some("at"));
+
+        // 更新応答にリフレッシュトークンが無ければ引き継ぐ。`expires_in` が無ければ ID トークンの期限
+        let refreshed = oidc.new_session(
+            claims,
+            &serde_json::json!({"access_token": "at2"}),
+            Some(&session),
+            1500,
+        );
+        assert_eq!(refreshed.created_at, 1000);
+        assert_eq!(refreshed.expires_at, 2000);
+        assert_eq!(refreshed.refresh_token.as_deref(), Some("rt"));
+
+        let OidcOutcome::Authenticated { headers, .. } = oidc.authenticated(refreshed, None) else {
+            panic!("expected authenticated");
+        };
+        assert_eq!(
+            headers,
+            [
+                (
+                    "X-Auth-Request-Email".to_string(),
+                    "alice@example.com".to_string()
+                ),
+                ("X-Auth-Request-User".to_string(), "alice".to_string()),
+                ("Authorization".to_string(), "Bearer at2".to_string()),
+            ]
+        );
+        assert_eq!(
+            oidc.stripped_request_headers().collect::<Vec<_>>(),
+            [
+                "X-Auth-Request-Email",
+                "X-Auth-Request-User",
+                "Authorization"
+            ]
+        );
+
+        // Max-Age はログインからの残り時間
+        let cookie = oidc.session_cookie(&session, 1000 + 86_000).unwrap();
+        assert!(cookie.ends_with("; Path=/; Secure; HttpOnly; SameSite=Lax; Max-Age=400"));
+    }
+
+    #[test]
+    fn logout_clears_cookies_and_redirects_to_end_session() {
+        let oidc = oidc(
+            "post_logout_redirect_uri = \"https://app.example/bye\"\ncookie_same_site = \"strict\"",
+        );
+        let logout = response(oidc.logout());
+        assert_eq!(logout.status, 302);
+        assert_eq!(
+            header(&logout, "location"),
+            ["https://idp.example/logout?client_id=app&post_logout_redirect_uri=https%3A%2F%2Fapp.example%2Fbye"]
+        );
+        assert_eq!(
+            header(&logout, "set-cookie"),
+            [
+                "veil_oidc=; Path=/; Secure; HttpOnly; SameSite=Strict; Max-Age=0",
+                "veil_oidc_state=; Path=/; Secure; HttpOnly; SameSite=Lax; Max-Age=0",
+            ]
+        );
+
+        oidc.provider.provider.store(None);
+        let logout = response(oidc.logout());
+        assert_eq!(header(&logout, "location"), ["https://app.example/bye"]);
+    }
+
+    #[test]
+    fn form_encoding_round_trips() {
+        let encoded = form_encode(&[
+            ("redirect_uri", "https://a.example/cb?x=1"),
+            ("scope", "openid email"),
+        ]);
+        assert_eq!(
+            encoded,
+            "redirect_uri=https%3A%2F%2Fa.example%2Fcb%3Fx%3D1&scope=openid+email"
+        );
+        assert_eq!(
+            parse_query(encoded.as_bytes()),
+            [
+                (
+                    "redirect_uri".to_string(),
+                    "https://a.example/cb?x=1".to_string()
+                ),
+                ("scope".to_string(), "openid email".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_query(b"code=%E3%81%82&bad=%zz&empty&&"),
+            [
+                ("code".to_string(), "あ".to_string()),
+                ("bad".to_string(), "%zz".to_string()),
+                ("empty".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn responses_render_for_each_protocol() {
+        let response =
+            OidcResponse::redirect("/home".to_string()).header("Set-Cookie", "a=b".to_string());
+        let http1 = String::from_utf8(response.http1_response()).unwrap();
+        assert!(http1.starts_with("HTTP/1.1 302 Found\r\nCache-Control: no-store\r\nLocation: /home\r\nSet-Cookie: a=b\r\n"));
+        assert!(http1.ends_with("Content-Length: 5\r\nConnection: close\r\n\r\nFound"));
+        assert_eq!(
+            response.h2_headers(),
+            [
+                (b"cache-control".to_vec(), b"no-store".to_vec()),
+                (b"location".to_vec(), b"/home".to_vec()),
+                (b"set-cookie".to_vec(), b"a=b".to_vec()),
+                (b"content-type".to_vec(), b"text/plain".to_vec()),
+            ]
+        );
+    }
+}
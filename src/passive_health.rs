@@ -0,0 +1,345 @@
+//! パッシブヘルスチェック（F-144）
+//!
+//! 実トラフィックの結果（応答ステータス・接続エラー）からサーバーを即座に unhealthy にする。
+//! アクティブチェック（`health_check`）の有無に関わらず動作し、判定は次の 3 規則のいずれか:
+//!
+//! - `consecutive_5xx`: 連続した 5xx 応答（接続エラーも 5xx として数える）
+//! - `consecutive_connect_failures`: 連続した接続エラー（上流から応答を得られなかった）
+//! - `gateway_errors`: ウィンドウ内のゲートウェイエラー（502/503/504・接続エラー）の回数
+//!
+//! unhealthy にしたサーバーは `recovery_secs` 経過後にサーバー選択時に自動で戻すか、
+//! アクティブチェックの成功（`healthy_threshold` 回）で戻す。unhealthy 中にアクティブチェックが
+//! 失敗した場合はタイマーを取り消し、以降の復帰はアクティブチェックに任せる。
+
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::{monotonic_ms, PassiveHealthConfig};
+use crate::resilience::SlidingWindow;
+
+/// タイマーで復帰しない（アクティブチェックでのみ復帰する）ことを表す期限
+const NO_TIMER: u64 = u64::MAX;
+
+/// 1 リクエストの結果の分類
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PassiveOutcome {
+    /// 5xx 以外の応答
+    Success,
+    /// 上流が返した 5xx 応答
+    ServerError(u16),
+    /// 上流から応答を得られなかった（接続失敗・タイムアウト・応答前の切断）
+    ConnectFailure,
+}
+
+impl PassiveOutcome {
+    /// プロキシ結果 `(status, 送信バイト数)` を分類する（None は応答なし）
+    ///
+    /// プロキシ自身が生成したエラーページ（5xx かつ送信バイト数 0）は接続エラーとして扱う。
+    pub fn classify(result: Option<(u16, u64)>) -> Self {
+        match result {
+            None => Self::ConnectFailure,
+            Some((status, 0)) if status >= 500 => Self::ConnectFailure,
+            Some((status, _)) if status >= 500 => Self::ServerError(status),
+            Some(_) => Self::Success,
+        }
+    }
+
+    /// 5xx として数えるか（接続エラーを含む）
+    fn is_5xx(self) -> bool {
+        !matches!(self, Self::Success)
+    }
+
+    /// ゲートウェイエラー（502/503/504・接続エラー）か
+    fn is_gateway_error(self) -> bool {
+        matches!(self, Self::ConnectFailure | Self::ServerError(502..=504))
+    }
+}
+
+/// unhealthy 判定に至った規則
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PassiveRule {
+    Consecutive5xx,
+    ConsecutiveConnectFailures,
+    GatewayErrors,
+}
+
+impl PassiveRule {
+    /// メトリクスのラベル値
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Consecutive5xx => "consecutive_5xx",
+            Self::ConsecutiveConnectFailures => "consecutive_connect_failures",
+            Self::GatewayErrors => "gateway_errors",
+        }
+    }
+
+    /// 遷移履歴・管理 API に出す理由
+    pub fn describe(self, cfg: &PassiveHealthConfig) -> String {
+        match self {
+            Self::Consecutive5xx => {
+                format!("passive: {} consecutive 5xx responses", cfg.consecutive_5xx)
+            }
+            Self::ConsecutiveConnectFailures => format!(
+                "passive: {} consecutive connect failures",
+                cfg.consecutive_connect_failures
+            ),
+            Self::GatewayErrors => format!(
+                "passive: {} gateway errors within {}s",
+                cfg.gateway_errors, cfg.gateway_error_window_secs
+            ),
+        }
+    }
+}
+
+/// サーバーごとのパッシブヘルス状態（UpstreamServer が Arc で保持）
+pub struct PassiveHealthState {
+    consecutive_5xx: AtomicU32,
+    consecutive_connect_failures: AtomicU32,
+    /// ゲートウェイエラーのみを失敗として記録するウィンドウ
+    gateway_errors: Mutex<SlidingWindow>,
+    /// タイマー復帰の期限（[`monotonic_ms`] 基準、0 はパッシブ判定で unhealthy にしていない）
+    down_until_ms: AtomicU64,
+    /// 直近のパッシブ判定の理由
+    reason: Mutex<Option<String>>,
+    /// パッシブ判定で unhealthy にした累計回数
+    trips: AtomicU64,
+}
+
+impl Default for PassiveHealthState {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(10))
+    }
+}
+
+impl PassiveHealthState {
+    /// ゲートウェイエラーのウィンドウ幅を指定して作成
+    pub fn new(window: Duration) -> Self {
+        Self {
+            consecutive_5xx: AtomicU32::new(0),
+            consecutive_connect_failures: AtomicU32::new(0),
+            gateway_errors: Mutex::new(SlidingWindow::new(window)),
+            down_until_ms: AtomicU64::new(0),
+            reason: Mutex::new(None),
+            trips: AtomicU64::new(0),
+        }
+    }
+
+    /// 結果を記録し、いずれかの規則の閾値に達したらその規則を返す
+    pub fn observe(
+        &self,
+        cfg: &PassiveHealthConfig,
+        outcome: PassiveOutcome,
+    ) -> Option<PassiveRule> {
+        if !outcome.is_5xx() {
+            self.consecutive_5xx.store(0, Ordering::Relaxed);
+            self.consecutive_connect_failures
+                .store(0, Ordering::Relaxed);
+            return None;
+        }
+
+        let connect_failures = if outcome == PassiveOutcome::ConnectFailure {
+            self.consecutive_connect_failures
+                .fetch_add(1, Ordering::Relaxed)
+                + 1
+        } else {
+            self.consecutive_connect_failures
+                .store(0, Ordering::Relaxed);
+            0
+        };
+        let server_errors = self.consecutive_5xx.fetch_add(1, Ordering::Relaxed) + 1;
+        let gateway_errors = if outcome.is_gateway_error() && cfg.gateway_errors > 0 {
+            let mut window = self
+                .gateway_errors
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            window.record(false);
+            window.failures()
+        } else {
+            0
+        };
+
+        if cfg.consecutive_connect_failures > 0
+            && connect_failures >= cfg.consecutive_connect_failures
+        {
+            Some(PassiveRule::ConsecutiveConnectFailures)
+        } else if cfg.consecutive_5xx > 0 && server_errors >= cfg.consecutive_5xx {
+            Some(PassiveRule::Consecutive5xx)
+        } else if cfg.gateway_errors > 0 && gateway_errors >= cfg.gateway_errors as usize {
+            Some(PassiveRule::GatewayErrors)
+        } else {
+            None
+        }
+    }
+
+    /// パッシブ判定で unhealthy にしたことを記録する（カウンターはリセット）
+    pub fn trip(&self, cfg: &PassiveHealthConfig, reason: String) {
+        let until = if cfg.recovery_secs == 0 {
+            NO_TIMER
+        } else {
+            monotonic_ms().saturating_add(cfg.recovery_secs.saturating_mul(1000))
+        };
+        self.reset_counters();
+        *self.reason.lock().unwrap_or_else(|e| e.into_inner()) = Some(reason);
+        self.down_until_ms.store(until, Ordering::Relaxed);
+        self.trips.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 復帰タイマーが満了していれば状態を解除して true（同時に呼ばれても true は 1 回だけ）
+    pub fn take_expired(&self, now_ms: u64) -> bool {
+        let until = self.down_until_ms.load(Ordering::Relaxed);
+        if until == 0 || until == NO_TIMER || now_ms < until {
+            return false;
+        }
+        self.down_until_ms
+            .compare_exchange(until, 0, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// パッシブ判定で unhealthy にしている間 true
+    pub fn is_down(&self) -> bool {
+        self.down_until_ms.load(Ordering::Relaxed) != 0
+    }
+
+    /// 復帰タイマーを取り消す（アクティブチェックが失敗し、復帰をアクティブチェックに任せる）
+    pub fn cancel_timer(&self) {
+        let until = self.down_until_ms.load(Ordering::Relaxed);
+        if until != 0 {
+            let _ = self.down_until_ms.compare_exchange(
+                until,
+                NO_TIMER,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            );
+        }
+    }
+
+    /// healthy へ戻ったときに状態を解除する（理由は管理 API 用に残す）
+    pub fn clear(&self) {
+        self.down_until_ms.store(0, Ordering::Relaxed);
+        self.reset_counters();
+    }
+
+    /// タイマー復帰までの残りミリ秒（タイマーなし・対象外は None）
+    pub fn recovers_in_ms(&self, now_ms: u64) -> Option<u64> {
+        match self.down_until_ms.load(Ordering::Relaxed) {
+            0 | NO_TIMER => None,
+            until => Some(until.saturating_sub(now_ms)),
+        }
+    }
+
+    /// 直近のパッシブ判定の理由
+    pub fn last_reason(&self) -> Option<String> {
+        self.reason
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// パッシブ判定で unhealthy にした累計回数
+    pub fn trips(&self) -> u64 {
+        self.trips.load(Ordering::Relaxed)
+    }
+
+    fn reset_counters(&self) {
+        self.consecutive_5xx.store(0, Ordering::Relaxed);
+        self.consecutive_connect_failures
+            .store(0, Ordering::Relaxed);
+        self.gateway_errors
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg(consecutive_5xx: u32, connect: u32, gateway: u32) -> PassiveHealthConfig {
+        PassiveHealthConfig {
+            consecutive_5xx,
+            consecutive_connect_failures: connect,
+            gateway_errors: gateway,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn classify_distinguishes_connect_failures() {
+        assert_eq!(
+            PassiveOutcome::classify(None),
+            PassiveOutcome::ConnectFailure
+        );
+        assert_eq!(
+            PassiveOutcome::classify(Some((502, 0))),
+            PassiveOutcome::ConnectFailure
+        );
+        assert_eq!(
+            PassiveOutcome::classify(Some((503, 120))),
+            PassiveOutcome::ServerError(503)
+        );
+        assert_eq!(
+            PassiveOutcome::classify(Some((404, 0))),
+            PassiveOutcome::Success
+        );
+    }
+
+    #[test]
+    fn consecutive_rules_reset_on_success() {
+        let cfg = cfg(3, 2, 0);
+        let state = PassiveHealthState::default();
+        let err = PassiveOutcome::ServerError(500);
+        assert_eq!(state.observe(&cfg, err), None);
+        assert_eq!(state.observe(&cfg, err), None);
+        assert_eq!(state.observe(&cfg, PassiveOutcome::Success), None);
+        assert_eq!(state.observe(&cfg, err), None);
+        assert_eq!(state.observe(&cfg, err), None);
+        assert_eq!(state.observe(&cfg, err), Some(PassiveRule::Consecutive5xx));
+
+        let state = PassiveHealthState::default();
+        assert_eq!(state.observe(&cfg, PassiveOutcome::ConnectFailure), None);
+        assert_eq!(
+            state.observe(&cfg, PassiveOutcome::ConnectFailure),
+            Some(PassiveRule::ConsecutiveConnectFailures)
+        );
+    }
+
+    #[test]
+    fn gateway_errors_counted_within_window() {
+        let cfg = cfg(0, 0, 3);
+        let state = PassiveHealthState::default();
+        assert_eq!(state.observe(&cfg, PassiveOutcome::ServerError(504)), None);
+        assert_eq!(state.observe(&cfg, PassiveOutcome::Success), None);
+        assert_eq!(state.observe(&cfg, PassiveOutcome::ServerError(500)), None);
+        assert_eq!(state.observe(&cfg, PassiveOutcome::ConnectFailure), None);
+        assert_eq!(
+            state.observe(&cfg, PassiveOutcome::ServerError(502)),
+            Some(PassiveRule::GatewayErrors)
+        );
+    }
+
+    #[test]
+    fn recovery_timer_expires_once() {
+        let cfg = PassiveHealthConfig {
+            recovery_secs: 1,
+            ..Default::default()
+        };
+        let state = PassiveHealthState::default();
+        state.trip(&cfg, "passive: test".to_string());
+        let now = monotonic_ms();
+        assert!(state.is_down());
+        assert!(!state.take_expired(now));
+        assert!(state.take_expired(now + 1000));
+        assert!(!state.take_expired(now + 1000));
+        assert!(!state.is_down());
+        assert_eq!(state.trips(), 1);
+
+        // アクティブチェックの失敗でタイマーを取り消すと期限では戻らない
+        state.trip(&cfg, "passive: test".to_string());
+        state.cancel_timer();
+        assert!(!state.take_expired(now + 10_000));
+        assert!(state.is_down());
+        assert_eq!(state.recovers_in_ms(now), None);
+    }
+}
@@ -271,20 +271,81 @@ pub(crate) const BACKEND_POOL_IDLE_TIMEOUT_SECS: u64 = 30; // アイドル接続
 // HTTP用とHTTPS用で別々のプールを管理し、ホスト:ポートをキーにしています。
 // ====================
 
+/// 上流接続のライフサイクル（F-136）
+///
+/// プールへの出し入れを跨いで接続に付随させ、`max_connection_lifetime_secs` と
+/// `max_requests_per_connection` に達した接続をプールへ戻さず閉じるために使う。
+/// 上限は接続確立時の設定で固定する（リロード後も既存接続には確立時の値を適用）。
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ConnLifecycle {
+    /// 寿命の期限（None = 無制限）
+    expires_at: Option<std::time::Instant>,
+    /// 残りリクエスト数（None = 無制限）
+    requests_left: Option<u32>,
+}
+
+impl ConnLifecycle {
+    /// 新規に確立した接続のライフサイクル
+    pub(crate) fn new(cfg: &crate::config::ConnectionPoolConfig) -> Self {
+        Self {
+            expires_at: (cfg.max_connection_lifetime_secs > 0).then(|| {
+                std::time::Instant::now() + Duration::from_secs(cfg.max_connection_lifetime_secs)
+            }),
+            requests_left: (cfg.max_requests_per_connection > 0)
+                .then_some(cfg.max_requests_per_connection),
+        }
+    }
+
+    /// 上限なしのライフサイクル（テスト用）
+    #[cfg(test)]
+    pub(crate) fn unlimited() -> Self {
+        Self {
+            expires_at: None,
+            requests_left: None,
+        }
+    }
+
+    /// リクエストを 1 件処理した後の状態
+    #[must_use]
+    pub(crate) fn served(mut self) -> Self {
+        if let Some(left) = &mut self.requests_left {
+            *left = left.saturating_sub(1);
+        }
+        self
+    }
+
+    /// 上限に達していれば理由（メトリクスラベル）を返す
+    pub(crate) fn retire_reason(&self) -> Option<&'static str> {
+        if self.requests_left == Some(0) {
+            Some("max_requests")
+        } else if self
+            .expires_at
+            .is_some_and(|at| std::time::Instant::now() >= at)
+        {
+            Some("max_lifetime")
+        } else {
+            None
+        }
+    }
+}
+
 /// プールされた接続のエントリ
 pub struct PooledConnection<T> {
     pub stream: T,
     pub created_at: std::time::Instant,
     /// この接続のアイドルタイムアウト（秒）
     pub idle_timeout_secs: u64,
+    /// 寿命・リクエスト数の上限（F-136）
+    pub(crate) lifecycle: ConnLifecycle,
 }
 
 impl<T> PooledConnection<T> {
-    pub(crate) fn new(stream: T, idle_timeout_secs: u64) -> Self {
+    pub(crate) fn new(stream: T, idle_timeout_secs: u64, lifecycle: ConnLifecycle) -> Self {
         Self {
             stream,
             created_at: std::time::Instant::now(),
             idle_timeout_secs,
+            lifecycle,
         }
     }
 
@@ -294,6 +355,61 @@ impl<T> PooledConnection<T> {
     }
 }
 
+/// キューから有効な接続を取り出す（HTTP / HTTPS / h2c プール共通）
+///
+/// アイドルタイムアウト超過は黙って破棄し、寿命超過（F-136）と上流が閉じた接続
+/// （`is_closed`、F-145）はメトリクスに記録して破棄する。
+fn take_valid<T>(
+    key: &str,
+    queue: &mut VecDeque<PooledConnection<T>>,
+    is_closed: impl Fn(&T) -> bool,
+) -> Option<(T, ConnLifecycle)> {
+    while let Some(entry) = queue.pop_front() {
+        if !entry.is_valid() {
+            continue;
+        }
+        if let Some(reason) = entry.lifecycle.retire_reason() {
+            crate::metrics::record_upstream_connection_retired(key, reason);
+            continue;
+        }
+        if is_closed(&entry.stream) {
+            crate::metrics::record_upstream_connection_retired(key, "stale");
+            continue;
+        }
+        return Some((entry.stream, entry.lifecycle));
+    }
+    None
+}
+
+/// 接続をキューへ返却する（HTTP / HTTPS / h2c プール共通）
+///
+/// ライフサイクル上限（F-136）に達した接続は返却せず閉じる。満杯なら古い接続から捨てる。
+fn push_idle<T>(
+    connections: &mut HashMap<String, VecDeque<PooledConnection<T>>>,
+    key: String,
+    stream: T,
+    lifecycle: ConnLifecycle,
+    max_idle: usize,
+    idle_timeout_secs: u64,
+) {
+    if let Some(reason) = lifecycle.retire_reason() {
+        crate::metrics::record_upstream_connection_retired(&key, reason);
+        return;
+    }
+    // F-09: メトリクス用にキーを保持（key は entry へムーブされるため）
+    let metric_key = key.clone();
+    let queue = connections.entry(key).or_default();
+
+    // 古い接続を削除（設定可能な最大数を使用）
+    while queue.len() >= max_idle {
+        queue.pop_front();
+    }
+
+    queue.push_back(PooledConnection::new(stream, idle_timeout_secs, lifecycle));
+    // F-09: プールサイズを更新
+    crate::metrics::set_connection_pool_size(&metric_key, queue.len());
+}
+
 /// HTTPバックエンド用コネクションプール（TcpStream）
 pub(crate) struct HttpConnectionPool {
     connections: HashMap<String, VecDeque<PooledConnection<TcpStream>>>,
@@ -307,16 +423,18 @@ impl HttpConnectionPool {
     }
 
     /// プールから接続を取得（有効な接続がなければNone）
-    pub(crate) fn get(&mut self, key: &str) -> Option<TcpStream> {
-        if let Some(queue) = self.connections.get_mut(key) {
-            while let Some(entry) = queue.pop_front() {
-                if entry.is_valid() {
-                    // F-09: コネクションプールヒットを記録
-                    crate::metrics::record_connection_pool_hit(key);
-                    return Some(entry.stream);
-                }
-                // 無効な接続は破棄
-            }
+    ///
+    /// 接続とともにライフサイクル（F-136）を返す。返却時は処理後の状態を `put` へ渡す。
+    /// 上流が既に閉じた接続（F-145）は読み飛ばす。
+    pub(crate) fn get(&mut self, key: &str) -> Option<(TcpStream, ConnLifecycle)> {
+        if let Some(found) = self
+            .connections
+            .get_mut(key)
+            .and_then(|queue| take_valid(key, queue, TcpStream::is_idle_closed))
+        {
+            // F-09: コネクションプールヒットを記録
+            crate::metrics::record_connection_pool_hit(key);
+            return Some(found);
         }
         // F-09: コネクションプールミスを記録
         crate::metrics::record_connection_pool_miss(key);
@@ -328,21 +446,18 @@ impl HttpConnectionPool {
         &mut self,
         key: String,
         stream: TcpStream,
+        lifecycle: ConnLifecycle,
         max_idle: usize,
         idle_timeout_secs: u64,
     ) {
-        // F-09: メトリクス用にキーを保持（key は entry へムーブされるため）
-        let metric_key = key.clone();
-        let queue = self.connections.entry(key).or_default();
-
-        // 古い接続を削除（設定可能な最大数を使用）
-        while queue.len() >= max_idle {
-            queue.pop_front();
-        }
-
-        queue.push_back(PooledConnection::new(stream, idle_timeout_secs));
-        // F-09: プールサイズを更新
-        crate::metrics::set_connection_pool_size(&metric_key, queue.len());
+        push_idle(
+            &mut self.connections,
+            key,
+            stream,
+            lifecycle,
+            max_idle,
+            idle_timeout_secs,
+        );
     }
 }
 
@@ -359,16 +474,19 @@ impl HttpsConnectionPool {
     }
 
     /// プールから接続を取得（有効な接続がなければNone）
-    pub(crate) fn get(&mut self, key: &str) -> Option<ClientTls> {
-        if let Some(queue) = self.connections.get_mut(key) {
-            while let Some(entry) = queue.pop_front() {
-                if entry.is_valid() {
-                    // F-09: コネクションプールヒットを記録
-                    crate::metrics::record_connection_pool_hit(key);
-                    return Some(entry.stream);
-                }
-                // 無効な接続は破棄
-            }
+    ///
+    /// 接続とともにライフサイクル（F-136）を返す。返却時は処理後の状態を `put` へ渡す。
+    /// 上流が既に閉じた接続・未読の復号済みデータを抱えた接続（F-145）は読み飛ばす。
+    pub(crate) fn get(&mut self, key: &str) -> Option<(ClientTls, ConnLifecycle)> {
+        use crate::runtime::io::BufferedReadState;
+        if let Some(found) = self.connections.get_mut(key).and_then(|queue| {
+            take_valid(key, queue, |stream: &ClientTls| {
+                stream.has_buffered_read_data() || stream.get_ref().is_idle_closed()
+            })
+        }) {
+            // F-09: コネクションプールヒットを記録
+            crate::metrics::record_connection_pool_hit(key);
+            return Some(found);
         }
         // F-09: コネクションプールミスを記録
         crate::metrics::record_connection_pool_miss(key);
@@ -380,21 +498,18 @@ impl HttpsConnectionPool {
         &mut self,
         key: String,
         stream: ClientTls,
+        lifecycle: ConnLifecycle,
         max_idle: usize,
         idle_timeout_secs: u64,
     ) {
-        // F-09: メトリクス用にキーを保持（key は entry へムーブされるため）
-        let metric_key = key.clone();
-        let queue = self.connections.entry(key).or_default();
-
-        // 古い接続を削除（設定可能な最大数を使用）
-        while queue.len() >= max_idle {
-            queue.pop_front();
-        }
-
-        queue.push_back(PooledConnection::new(stream, idle_timeout_secs));
-        // F-09: プールサイズを更新
-        crate::metrics::set_connection_pool_size(&metric_key, queue.len());
+        push_idle(
+            &mut self.connections,
+            key,
+            stream,
+            lifecycle,
+            max_idle,
+            idle_timeout_secs,
+        );
     }
 }
 
@@ -422,15 +537,18 @@ impl H2cConnectionPool {
     }
 
     /// プールから接続を取得（有効な接続がなければ None）
-    pub(crate) fn get(&mut self, key: &str) -> Option<crate::http2::H2cClient<TcpStream>> {
-        if let Some(queue) = self.connections.get_mut(key) {
-            while let Some(entry) = queue.pop_front() {
-                if entry.is_valid() {
-                    crate::metrics::record_connection_pool_hit(key);
-                    return Some(entry.stream);
-                }
-                // 無効（アイドルタイムアウト超過）な接続は破棄
-            }
+    pub(crate) fn get(
+        &mut self,
+        key: &str,
+    ) -> Option<(crate::http2::H2cClient<TcpStream>, ConnLifecycle)> {
+        if let Some(found) = self
+            .connections
+            .get_mut(key)
+            // HTTP/2 はアイドル中も PING / SETTINGS を受け取り得るため、可読性では判定しない
+            .and_then(|queue| take_valid(key, queue, |_| false))
+        {
+            crate::metrics::record_connection_pool_hit(key);
+            return Some(found);
         }
         crate::metrics::record_connection_pool_miss(key);
         None
@@ -441,19 +559,204 @@ impl H2cConnectionPool {
         &mut self,
         key: String,
         client: crate::http2::H2cClient<TcpStream>,
+        lifecycle: ConnLifecycle,
         max_idle: usize,
         idle_timeout_secs: u64,
     ) {
+        push_idle(
+            &mut self.connections,
+            key,
+            client,
+            lifecycle,
+            max_idle,
+            idle_timeout_secs,
+        );
+    }
+}
+
+/// ALPN h2 で多重化する TLS バックエンド用の接続プール（F-134）。
+///
+/// HTTP/1.1 系プールと異なり接続を取り出さず、ドライバタスクへのハンドルを共有する。
+/// 取得時は閉じた / GOAWAY 済みのハンドルを除去し、ストリームに空きのある中で
+/// 処理中ストリームが最も少ない接続を選ぶ。`auto` で h2 を選ばなかったホストは
+/// `http1_only` に一定時間記録し、その間は ALPN を試さず HTTP/1.1 プールを使う。
+#[cfg(feature = "http2")]
+pub(crate) struct H2MuxPool {
+    connections: HashMap<String, Vec<crate::http2::H2MuxHandle>>,
+    http1_only: HashMap<String, std::time::Instant>,
+}
+
+/// `auto` で HTTP/1.1 が選ばれたホストを記憶する期間（F-134）
+#[cfg(feature = "http2")]
+pub(crate) const H2_HTTP1_ONLY_TTL: Duration = Duration::from_secs(60);
+
+#[cfg(feature = "http2")]
+impl H2MuxPool {
+    pub(crate) fn new() -> Self {
+        Self {
+            connections: HashMap::new(),
+            http1_only: HashMap::new(),
+        }
+    }
+
+    /// ストリームに空きのある接続のハンドルを取得（無ければ None）
+    pub(crate) fn get(&mut self, key: &str) -> Option<crate::http2::H2MuxHandle> {
+        if let Some(handles) = self.connections.get_mut(key) {
+            handles.retain(|h| h.is_usable());
+            let picked = handles
+                .iter()
+                .filter(|h| h.has_capacity())
+                .min_by_key(|h| h.in_flight())
+                .cloned();
+            crate::metrics::set_connection_pool_size(key, handles.len());
+            if picked.is_some() {
+                crate::metrics::record_connection_pool_hit(key);
+                return picked;
+            }
+        }
+        crate::metrics::record_connection_pool_miss(key);
+        None
+    }
+
+    /// 新規接続のハンドルを登録（接続数が上限に達していれば最も古いものを外す）
+    pub(crate) fn put(&mut self, key: String, handle: crate::http2::H2MuxHandle, max_conns: usize) {
         let metric_key = key.clone();
-        let queue = self.connections.entry(key).or_default();
+        let handles = self.connections.entry(key).or_default();
+        handles.retain(|h| h.is_usable());
+        while handles.len() >= max_conns.max(1) {
+            handles.remove(0);
+        }
+        handles.push(handle);
+        crate::metrics::set_connection_pool_size(&metric_key, handles.len());
+    }
+
+    /// `auto` で HTTP/1.1 が選ばれたことを記録
+    pub(crate) fn mark_http1_only(&mut self, key: &str) {
+        self.http1_only.insert(
+            key.to_string(),
+            std::time::Instant::now() + H2_HTTP1_ONLY_TTL,
+        );
+    }
 
-        while queue.len() >= max_idle {
-            queue.pop_front();
+    /// 直近 `auto` で HTTP/1.1 が選ばれたホストか（期限切れの記録は消す）
+    pub(crate) fn is_http1_only(&mut self, key: &str) -> bool {
+        match self.http1_only.get(key) {
+            Some(until) if *until > std::time::Instant::now() => true,
+            Some(_) => {
+                self.http1_only.remove(key);
+                false
+            }
+            None => false,
         }
+    }
+}
+
+/// HTTP/3 上流接続プール（F-135）
+///
+/// [`H2MuxPool`] と同じくドライバタスクへのハンドルを共有する。加えてホストごとの
+/// セッションチケット（0-RTT 再開用）と、QUIC ハンドシェイクが成立しなかった
+/// （UDP 不通の）ホストを一定時間記録し、その間は TCP へ直接フォールバックさせる。
+#[cfg(feature = "http3")]
+pub(crate) struct H3ConnectionPool {
+    connections: HashMap<String, Vec<crate::http3_client::H3ClientHandle>>,
+    sessions: HashMap<String, Vec<u8>>,
+    udp_blocked: HashMap<String, std::time::Instant>,
+}
 
-        queue.push_back(PooledConnection::new(client, idle_timeout_secs));
-        crate::metrics::set_connection_pool_size(&metric_key, queue.len());
+/// QUIC ハンドシェイクに失敗したホストへ HTTP/3 を再試行しない期間（F-135）
+#[cfg(feature = "http3")]
+pub(crate) const H3_UDP_BLOCKED_TTL: Duration = Duration::from_secs(300);
+
+#[cfg(feature = "http3")]
+impl H3ConnectionPool {
+    pub(crate) fn new() -> Self {
+        Self {
+            connections: HashMap::new(),
+            sessions: HashMap::new(),
+            udp_blocked: HashMap::new(),
+        }
+    }
+
+    /// 閉じた接続を外す（外す前に受け取り済みのセッションチケットを回収する）
+    fn prune(&mut self, key: &str) {
+        let Some(handles) = self.connections.get_mut(key) else {
+            return;
+        };
+        for handle in handles.iter() {
+            if let Some(ticket) = handle.session_ticket() {
+                self.sessions.insert(key.to_string(), ticket);
+            }
+        }
+        handles.retain(|h| h.is_usable());
+    }
+
+    /// ストリームに空きのある接続のハンドルを取得（無ければ None）
+    pub(crate) fn get(&mut self, key: &str) -> Option<crate::http3_client::H3ClientHandle> {
+        self.prune(key);
+        if let Some(handles) = self.connections.get(key) {
+            let picked = handles
+                .iter()
+                .filter(|h| h.has_capacity())
+                .min_by_key(|h| h.in_flight())
+                .cloned();
+            crate::metrics::set_connection_pool_size(key, handles.len());
+            if picked.is_some() {
+                crate::metrics::record_connection_pool_hit(key);
+                return picked;
+            }
+        }
+        crate::metrics::record_connection_pool_miss(key);
+        None
+    }
+
+    /// 新規接続のハンドルを登録（接続数が上限に達していれば最も古いものを外す）
+    pub(crate) fn put(
+        &mut self,
+        key: String,
+        handle: crate::http3_client::H3ClientHandle,
+        max_conns: usize,
+    ) {
+        self.prune(&key);
+        let metric_key = key.clone();
+        let handles = self.connections.entry(key).or_default();
+        while handles.len() >= max_conns.max(1) {
+            handles.remove(0);
+        }
+        handles.push(handle);
+        crate::metrics::set_connection_pool_size(&metric_key, handles.len());
+    }
+
+    /// 0-RTT 再開に使うセッションチケット（プール内の接続が受け取った最新のもの）
+    pub(crate) fn session_ticket(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.prune(key);
+        self.sessions.get(key).cloned()
+    }
+
+    /// QUIC ハンドシェイクが成立しなかったことを記録（チケットも破棄する）
+    pub(crate) fn mark_udp_blocked(&mut self, key: &str) {
+        self.sessions.remove(key);
+        self.udp_blocked.insert(
+            key.to_string(),
+            std::time::Instant::now() + H3_UDP_BLOCKED_TTL,
+        );
     }
+
+    /// 直近 QUIC が通らなかったホストか（期限切れの記録は消す）
+    pub(crate) fn is_udp_blocked(&mut self, key: &str) -> bool {
+        match self.udp_blocked.get(key) {
+            Some(until) if *until > std::time::Instant::now() => true,
+            Some(_) => {
+                self.udp_blocked.remove(key);
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(feature = "http3")]
+thread_local! {
+    pub(crate) static H3_POOL: RefCell<H3ConnectionPool> = RefCell::new(H3ConnectionPool::new());
 }
 
 thread_local! {
@@ -464,6 +767,7 @@ thread_local! {
 #[cfg(feature = "http2")]
 thread_local! {
     pub(crate) static H2C_POOL: RefCell<H2cConnectionPool> = RefCell::new(H2cConnectionPool::new());
+    pub(crate) static H2_MUX_POOL: RefCell<H2MuxPool> = RefCell::new(H2MuxPool::new());
 }
 
 // kTLS 有効時のスレッドローカル Splice パイプの checkout/return 型プール（B-16）
@@ -1200,6 +1504,7 @@ mod tests {
     // 最古のものから破棄することの回帰テスト（BACKEND_POOL_MAX_IDLE_PER_HOST = 256）。
     mod http_connection_pool {
         use super::super::*;
+        use std::os::fd::AsRawFd as _;
 
         /// テスト用のダミー TcpStream を作る（socketpair の片端を io_uring TcpStream として
         /// ラップする）。put/get の保持数検証のみが目的でデータの送受信は行わない。
@@ -1213,6 +1518,17 @@ mod tests {
             unsafe { TcpStream::from_raw_fd(fds[0]) }
         }
 
+        /// 対向端を開いたままのダミー TcpStream（F-145 の事前検査で破棄されない接続）。
+        fn live_tcp_stream() -> (TcpStream, std::os::fd::OwnedFd) {
+            use std::os::fd::FromRawFd as _;
+            let mut fds = [0 as std::os::unix::io::RawFd; 2];
+            let ret =
+                unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, fds.as_mut_ptr()) };
+            assert_eq!(ret, 0, "socketpair failed");
+            let peer = unsafe { std::os::fd::OwnedFd::from_raw_fd(fds[1]) };
+            (unsafe { TcpStream::from_raw_fd(fds[0]) }, peer)
+        }
+
         #[test]
         fn test_put_respects_max_idle_256() {
             let mut pool = HttpConnectionPool::new();
@@ -1221,6 +1537,7 @@ mod tests {
                 pool.put(
                     key.to_string(),
                     dummy_tcp_stream(),
+                    ConnLifecycle::unlimited(),
                     BACKEND_POOL_MAX_IDLE_PER_HOST,
                     BACKEND_POOL_IDLE_TIMEOUT_SECS,
                 );
@@ -1241,6 +1558,7 @@ mod tests {
                 pool.put(
                     key.to_string(),
                     dummy_tcp_stream(),
+                    ConnLifecycle::unlimited(),
                     BACKEND_POOL_MAX_IDLE_PER_HOST,
                     BACKEND_POOL_IDLE_TIMEOUT_SECS,
                 );
@@ -1248,5 +1566,100 @@ mod tests {
             let len = pool.connections.get(key).map(|q| q.len()).unwrap_or(0);
             assert_eq!(len, n, "below max_idle, all connections should be retained");
         }
+
+        /// F-136: リクエスト数上限に達した接続は返却されず、寿命切れの接続は取得時に破棄される。
+        #[test]
+        fn test_lifecycle_limits_retire_connections() {
+            let cfg = crate::config::ConnectionPoolConfig {
+                max_requests_per_connection: 2,
+                ..Default::default()
+            };
+            let mut pool = HttpConnectionPool::new();
+            let key = "example.test:80";
+            let lifecycle = ConnLifecycle::new(&cfg).served();
+            let (stream, _peer) = live_tcp_stream();
+            pool.put(key.to_string(), stream, lifecycle, 8, 30);
+            let (stream, lifecycle) = pool.get(key).expect("one request left");
+            pool.put(key.to_string(), stream, lifecycle.served(), 8, 30);
+            assert!(
+                pool.get(key).is_none(),
+                "exhausted connection must be closed"
+            );
+
+            let mut expired = ConnLifecycle::unlimited();
+            expired.expires_at = Some(std::time::Instant::now());
+            assert_eq!(expired.retire_reason(), Some("max_lifetime"));
+            pool.connections
+                .entry(key.to_string())
+                .or_default()
+                .push_back(PooledConnection::new(dummy_tcp_stream(), 30, expired));
+            assert!(
+                pool.get(key).is_none(),
+                "expired connection must be dropped"
+            );
+        }
+
+        /// F-145: 対向が閉じた（EOF が読める）接続は取得時に破棄され、生きた接続だけが返る。
+        #[test]
+        fn test_get_skips_connections_closed_by_backend() {
+            let mut pool = HttpConnectionPool::new();
+            let key = "example.test:80";
+            let (live, _peer) = live_tcp_stream();
+            let live_fd = live.as_raw_fd();
+            pool.put(
+                key.to_string(),
+                dummy_tcp_stream(),
+                ConnLifecycle::unlimited(),
+                8,
+                30,
+            );
+            pool.put(key.to_string(), live, ConnLifecycle::unlimited(), 8, 30);
+            let (stream, _) = pool.get(key).expect("live connection");
+            assert_eq!(stream.as_raw_fd(), live_fd);
+            assert!(pool.get(key).is_none());
+        }
+    }
+
+    /// F-134: `auto` で HTTP/1.1 が選ばれたホストの記録は TTL で失効すること。
+    #[cfg(feature = "http2")]
+    mod h2_mux_pool {
+        use super::super::*;
+
+        #[test]
+        fn test_http1_only_mark_expires() {
+            let mut pool = H2MuxPool::new();
+            let key = "example.test:443:example.test:verify";
+            assert!(!pool.is_http1_only(key));
+            pool.mark_http1_only(key);
+            assert!(pool.is_http1_only(key));
+            assert!(!pool.is_http1_only("other:443:other:verify"));
+            pool.http1_only
+                .insert(key.to_string(), std::time::Instant::now());
+            assert!(!pool.is_http1_only(key));
+            assert!(pool.http1_only.is_empty());
+            assert!(pool.get(key).is_none());
+        }
+    }
+
+    /// F-135: UDP 不通の記録はチケットを破棄し、TTL で失効すること。
+    #[cfg(feature = "http3")]
+    mod h3_pool {
+        use super::super::*;
+
+        #[test]
+        fn test_udp_blocked_mark_drops_ticket_and_expires() {
+            let mut pool = H3ConnectionPool::new();
+            let key = "192.0.2.1:443:example.test:verify";
+            pool.sessions.insert(key.to_string(), vec![1, 2, 3]);
+            assert_eq!(pool.session_ticket(key), Some(vec![1, 2, 3]));
+            assert!(!pool.is_udp_blocked(key));
+            pool.mark_udp_blocked(key);
+            assert!(pool.is_udp_blocked(key));
+            assert!(pool.session_ticket(key).is_none());
+            pool.udp_blocked
+                .insert(key.to_string(), std::time::Instant::now());
+            assert!(!pool.is_udp_blocked(key));
+            assert!(pool.get(key).is_none());
+        }
     }
 }
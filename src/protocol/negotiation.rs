@@ -38,6 +38,9 @@ pub const ALPN_H2_HTTP11: &[&[u8]] = &[
 /// HTTP/2 のみの ALPN リスト
 pub const ALPN_H2_ONLY: &[&[u8]] = &[b"h2"];
 
+/// HTTP/1.1 のみの ALPN リスト（HTTP/1.1 で話す上流接続用、F-134）
+pub const ALPN_HTTP11_ONLY: &[&[u8]] = &[b"http/1.1"];
+
 /// rustls ServerConfig に HTTP/2 対応の ALPN を設定
 ///
 /// # Arguments
@@ -73,6 +76,15 @@ pub fn configure_alpn_h2_client(mut config: ClientConfig, http2_only: bool) -> C
     config
 }
 
+/// rustls ClientConfig に HTTP/1.1 のみの ALPN を設定（F-134）
+///
+/// HTTP/1.1 でリクエストを書き込む上流接続が h2 を広告すると、サーバーが h2 を
+/// 選んだ時点でプロトコル不一致になる。h2 を話す接続は `configure_alpn_h2_client` を使う。
+pub fn configure_alpn_http11_client(mut config: ClientConfig) -> ClientConfig {
+    config.alpn_protocols = ALPN_HTTP11_ONLY.iter().map(|p| p.to_vec()).collect();
+    config
+}
+
 /// ネゴシエートされたプロトコルを取得
 ///
 /// TLS ハンドシェイク完了後に呼び出し、選択されたプロトコルを返します。
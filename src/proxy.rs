@@ -8,12 +8,16 @@ use std::sync::Arc;
 use crate::runtime::buf::{IoBuf, IoBufMut};
 use crate::runtime::io::OpenOptions;
 use crate::runtime::io::{AsyncReadRent, AsyncWriteRentExt, IoVecBuf, IoVecBufMut};
+use crate::runtime::local_bind::LocalBind;
+#[cfg(feature = "http2")]
+use crate::runtime::sockopt::SocketOptions;
 use crate::runtime::tcp::TcpStream;
 use crate::runtime::time::timeout;
 #[cfg(feature = "http2")]
 use bytes::Bytes;
 use ftlog::{debug, error, info, warn};
 use httparse::{Request, Status};
+use std::borrow::Cow;
 use std::io;
 use std::net::SocketAddr;
 use std::path::Path;
@@ -23,11 +27,14 @@ use crate::buffering;
 use crate::cache;
 use crate::config::*;
 use crate::constants::*;
+use crate::hedging::{HedgeOutcome, HedgePolicy};
 use crate::http_utils::*;
 use crate::logging::*;
 use crate::metrics::*;
 use crate::pool::*;
+use crate::resilience::ConcurrencyPermit;
 use crate::runtime::handle::{AsRawFd, RawFd};
+use crate::timeouts::{UpstreamDeadline, UpstreamTimeouts};
 use crate::upstream::*;
 
 use crate::server::spawn_background_revalidation;
@@ -60,6 +67,17 @@ fn https_pool_key(host: &str, port: u16, sni: &str, tls_insecure: bool) -> Strin
     format!("{}:{}:{}:{}", host, port, sni, tag)
 }
 
+/// 送信元アドレスを指定した接続のプールキー（F-141: 送信元ごとにプールを分ける）
+///
+/// 透過モードでは送信元がクライアントの IP のため、別クライアントの接続を再利用しない。
+#[inline]
+fn bound_pool_key(key: &str, bind: Option<LocalBind>) -> Cow<'_, str> {
+    match bind {
+        Some(bind) => Cow::Owned(format!("{}@{}", key, bind.ip)),
+        None => Cow::Borrowed(key),
+    }
+}
+
 /// HTTPS コネクションプールキー（SNI なし）
 #[inline]
 fn https_pool_key_no_sni(host: &str, port: u16, tls_insecure: bool) -> String {
@@ -125,8 +143,10 @@ impl SecurityCheckResult {
 /// ## チェック項目
 /// 1. IP制限（allowed_ips, denied_ips）
 /// 2. HTTPメソッド制限（allowed_methods）
-/// 3. レートリミット（rate_limit_requests_per_min）
-/// 4. ボディサイズ制限（max_request_body_size）
+/// 3. ボディサイズ制限（max_request_body_size）
+///
+/// レートリミット（F-146）は応答ヘッダーを伴うため、各プロトコルのハンドラーが
+/// `SecurityConfig::rate_limit` を別途評価する。
 ///
 /// 管理 API: 設定情報をJSON形式で返す（F-21: GET /__admin/config）
 ///
@@ -165,6 +185,155 @@ fn build_admin_config_json(config: &crate::config::RuntimeConfig) -> String {
     )
 }
 
+/// 管理 API: 統計情報をJSON形式で返す（F-21: GET /__admin/stats）
+///
+/// 起動からの経過時間に加え、適応型の同時実行数制限（F-138）が有効な upstream ごとの
+/// 現在の上限・処理中・拒否累計・最小 RTT を `adaptive_concurrency` に含める。
+#[cfg(feature = "admin")]
+fn build_admin_stats_json(config: &crate::config::RuntimeConfig) -> String {
+    let mut limiters: Vec<_> = config
+        .upstream_groups
+        .iter()
+        .filter_map(|(name, group)| group.concurrency_limiter.as_ref().map(|l| (name, l)))
+        .collect();
+    limiters.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut json = format!(
+        "{{\"uptime_secs\":{},\"adaptive_concurrency\":{{",
+        PROXY_START_TIME.elapsed().as_secs()
+    );
+    for (i, (name, limiter)) in limiters.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let min_rtt_ms = match limiter.min_rtt() {
+            Some(rtt) => format!("{:.3}", rtt.as_secs_f64() * 1000.0),
+            None => "null".to_string(),
+        };
+        json.push_str(&format!(
+            "\"{}\":{{\"limit\":{},\"in_flight\":{},\"rejected\":{},\"min_rtt_ms\":{}}}",
+            name.replace('\\', "\\\\").replace('"', "\\\""),
+            limiter.limit(),
+            limiter.in_flight(),
+            limiter.rejected(),
+            min_rtt_ms,
+        ));
+    }
+    json.push_str("}}");
+    json
+}
+
+/// 管理 API: アクティブヘルスチェックの状態をJSON形式で返す（F-143: GET /__admin/health）
+///
+/// `upstreams` にヘルスチェック対象サーバーの現在の状態、`events` に healthy / unhealthy
+/// 遷移の履歴（古い順、理由付き）を含める。パッシブヘルスチェック（F-144）を設定した
+/// upstream も対象とし、各サーバーの `passive` に判定状態と理由を含める。
+#[cfg(feature = "admin")]
+fn build_admin_health_json(config: &crate::config::RuntimeConfig) -> String {
+    let mut names: Vec<_> = config
+        .upstream_groups
+        .iter()
+        .filter(|(_, group)| group.health_check.is_some() || group.passive_health.is_some())
+        .collect();
+    names.sort_by(|a, b| a.0.cmp(b.0));
+
+    let now_ms = crate::config::monotonic_ms();
+    let mut upstreams = serde_json::Map::new();
+    for (name, group) in names {
+        let servers: Vec<serde_json::Value> = group
+            .servers
+            .iter()
+            .map(|server| {
+                // F-144: パッシブ判定の状態（設定時のみ）
+                let passive = group.passive_health.as_ref().map(|_| {
+                    serde_json::json!({
+                        "down": server.passive.is_down(),
+                        "reason": server.passive.last_reason(),
+                        "recovers_in_ms": server.passive.recovers_in_ms(now_ms),
+                        "trips": server.passive.trips(),
+                    })
+                });
+                serde_json::json!({
+                    "server": format!("{}:{}", server.target.host, server.target.port),
+                    "healthy": server.is_healthy(),
+                    "passive": passive,
+                })
+            })
+            .collect();
+        upstreams.insert(name.clone(), serde_json::Value::Array(servers));
+    }
+
+    let events: Vec<serde_json::Value> = crate::health::recent_transitions()
+        .into_iter()
+        .map(|event| {
+            serde_json::json!({
+                "unix_ms": event.unix_ms,
+                "upstream": event.upstream,
+                "server": event.server,
+                "healthy": event.healthy,
+                "reason": event.reason,
+            })
+        })
+        .collect();
+
+    serde_json::json!({ "upstreams": upstreams, "events": events }).to_string()
+}
+
+/// 管理 API: 自動遮断のエンドポイントか（F-152: GET / POST / DELETE `/bans`）
+///
+/// 該当すればクエリ文字列（無ければ空）を返す。
+#[cfg(feature = "admin")]
+fn admin_bans_query<'a>(method: &[u8], path_suffix: &'a str) -> Option<&'a str> {
+    if !matches!(method, b"GET" | b"POST" | b"DELETE") {
+        return None;
+    }
+    match path_suffix.strip_prefix("/bans")? {
+        "" => Some(""),
+        rest => rest.strip_prefix('?'),
+    }
+}
+
+/// 管理 API: 遮断の一覧・追加・解除（F-152）。認証は呼び出し側で済ませておくこと。
+///
+/// - `GET /bans`                          : 遮断中の IP の一覧
+/// - `POST /bans?ip=203.0.113.7&secs=600` : 遮断（`secs` 省略時は `ban_secs`）
+/// - `DELETE /bans?ip=203.0.113.7`        : 遮断の解除
+///
+/// `(status, JSON ボディ)` を返す。
+#[cfg(feature = "admin")]
+fn handle_admin_bans(method: &[u8], query: &str) -> (u16, String) {
+    if method == b"GET" {
+        return (200, crate::auto_ban::list_json());
+    }
+    let mut ip: Option<String> = None;
+    let mut secs: Option<&str> = None;
+    for pair in query.split('&') {
+        let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+        match k {
+            "ip" => ip = Some(url_decode(v)),
+            "secs" => secs = Some(v),
+            _ => {}
+        }
+    }
+    let bad_request = |msg: String| (400, serde_json::json!({ "error": msg }).to_string());
+    let Some(ip) = ip else {
+        return bad_request("ip is required".to_string());
+    };
+    if method == b"DELETE" {
+        return match crate::auto_ban::unban(&ip) {
+            Ok(removed) => (200, format!("{{\"removed\":{}}}", removed)),
+            Err(e) => bad_request(e),
+        };
+    }
+    let Ok(secs) = secs.map(str::parse::<u64>).transpose() else {
+        return bad_request("secs must be a number".to_string());
+    };
+    match crate::auto_ban::ban_manual(&ip, secs) {
+        Ok(()) => (200, "{\"ok\":true}".to_string()),
+        Err(e) => bad_request(e),
+    }
+}
+
 /// 管理 API: キャッシュ Purge リクエストを処理する（F-20）
 ///
 /// クエリパラメータをパースし、キャッシュマネージャーの purge メソッドを呼ぶ。
@@ -290,13 +459,6 @@ pub fn check_security(
         }
     }
 
-    // レートリミットチェック
-    if security.rate_limit_requests_per_min > 0
-        && !check_rate_limit(client_ip, security.rate_limit_requests_per_min)
-    {
-        return SecurityCheckResult::RateLimitExceeded;
-    }
-
     // ボディサイズ制限（chunked以外）
     if !is_chunked && content_length > security.max_request_body_size {
         return SecurityCheckResult::RequestTooLarge;
@@ -504,6 +666,13 @@ where
 
     // HTTP/2 コネクションを作成
     let mut conn = Http2Connection::new(tls_stream, settings);
+    // F-153: 接続元 IP・プレフィックスごとの同時ストリーム数
+    conn.set_client_stream_budget(
+        client_ip
+            .parse()
+            .ok()
+            .and_then(crate::client_limits::stream_budget),
+    );
 
     // ハンドシェイク（プリフェース確認 + SETTINGS 交換）
     if let Err(e) = conn.handshake().await {
@@ -521,6 +690,9 @@ where
 
     if let Err(e) = result {
         warn!("[HTTP/2] Connection error: {}", e);
+        if e.is_flood() {
+            crate::auto_ban::report_str(client_ip, crate::auto_ban::Signal::H2Flood);
+        }
     }
 
     debug!("[HTTP/2] Connection closed from {}", client_ip);
@@ -756,7 +928,7 @@ where
             continue;
         } else {
             // idle: 可読 or notify を待つ。可読なら fill する。
-            h2_select_readable_or_notify(conn.raw_fd(), &notify).await
+            crate::stream_channel::readable_or_notify(conn.raw_fd(), &notify).await
         };
 
         if need_fill {
@@ -805,6 +977,9 @@ enum H2RespMsg {
     Trailers(Vec<(Vec<u8>, Vec<u8>)>),
     /// head 送出後のバックエンドエラー等でストリームをリセットする（RST_STREAM エラーコード）。
     Reset(u32),
+    /// 後続の [`H2RespMsg::Head`] に追記するヘッダ（Head より前に送る。F-132 の
+    /// スティッキー Cookie 等、バックエンド応答とは独立にフロントエンドが付与するもの）。
+    ExtraHeaders(Vec<(Vec<u8>, Vec<u8>)>),
 }
 
 /// per-stream リクエストタスクへ conn 非依存で引き渡すリクエスト情報（F-116）。
@@ -820,6 +995,10 @@ struct H2RequestCtx {
     body: Bytes,
     client_ip: Box<str>,
     start: Instant,
+    /// リクエストヘッジングの結果（F-137、アクセスログ用）
+    hedge: std::cell::Cell<HedgeOutcome>,
+    /// 認証した利用者（F-151、アクセスログ用、未認証なら空）
+    user: std::cell::RefCell<String>,
 }
 
 /// メインループ側のストリーム状態（F-116）。
@@ -845,6 +1024,8 @@ struct H2ActiveStream {
     end_sent: bool,
     /// フロー制御ウィンドウ待ちの未送信ボディ残 `(buf, 送信済みオフセット)`。
     pending_body: Option<(Bytes, usize)>,
+    /// Head 送出時に追記するヘッダ（[`H2RespMsg::ExtraHeaders`] で蓄積）。
+    extra_headers: Vec<(Vec<u8>, Vec<u8>)>,
 }
 
 /// per-stream リクエストタスクのスポーナ型（F-46: 型付きタスクプールで Box 確保を回避）。
@@ -944,6 +1125,8 @@ fn h2_spawn_for_request<S>(
         body: parts.body.freeze(),
         client_ip: Box::from(client_ip),
         start: Instant::now(),
+        hedge: std::cell::Cell::new(HedgeOutcome::NotHedged),
+        user: std::cell::RefCell::new(String::new()),
     };
 
     let (resp_tx, resp_rx) = crate::stream_channel::channel::<H2RespMsg>(H2_RESP_CHANNEL_CAP);
@@ -969,36 +1152,11 @@ fn h2_spawn_for_request<S>(
             head_sent: false,
             end_sent: false,
             pending_body: None,
+            extra_headers: Vec::new(),
         },
     );
 }
 
-/// 2 つの Future（ソケット可読 / notify）を自前 `poll_fn` で race する（futures 依存を増やさない）。
-///
-/// 戻り値 `true` = 可読、`false` = notify。両 Future ともキャンセル安全
-/// （`wait_readable_fd` は POLL_ADD、`notify.wait` はフラグ待ち）。
-#[cfg(feature = "http2")]
-async fn h2_select_readable_or_notify(fd: RawFd, notify: &crate::stream_channel::Notify) -> bool {
-    use std::future::poll_fn;
-    use std::future::Future;
-    use std::task::Poll;
-
-    let readable = crate::runtime::tcp::wait_readable_fd(fd);
-    let wait = notify.wait();
-    let mut readable = std::pin::pin!(readable);
-    let mut wait = std::pin::pin!(wait);
-    poll_fn(move |cx| {
-        if readable.as_mut().poll(cx).is_ready() {
-            return Poll::Ready(true);
-        }
-        if wait.as_mut().poll(cx).is_ready() {
-            return Poll::Ready(false);
-        }
-        Poll::Pending
-    })
-    .await
-}
-
 /// 全ストリームのレスポンスを 1 回駆動する（F-116）。
 ///
 /// 各ストリームで pending_body 再送 → resp_rx を try_recv して
@@ -1100,9 +1258,15 @@ where
             match msg {
                 H2RespMsg::Head {
                     status,
-                    headers,
+                    mut headers,
                     end_stream,
                 } => {
+                    {
+                        let st = streams.get_mut(&sid).unwrap();
+                        if !st.extra_headers.is_empty() {
+                            headers.append(&mut st.extra_headers);
+                        }
+                    }
                     let hv: Vec<(&[u8], &[u8])> = headers
                         .iter()
                         .map(|(k, v)| (k.as_slice(), v.as_slice()))
@@ -1151,6 +1315,10 @@ where
                     st.head_sent = true;
                     st.end_sent = true;
                 }
+                H2RespMsg::ExtraHeaders(mut extra) => {
+                    let st = streams.get_mut(&sid).unwrap();
+                    st.extra_headers.append(&mut extra);
+                }
                 H2RespMsg::Reset(code) => {
                     conn.flush_write_buf().await?;
                     let _ = conn
@@ -1180,7 +1348,7 @@ where
 /// リクエスト方向ストリーミングの適格判定（F-32 の条件を厳密に踏襲）。
 ///
 /// 条件: Proxy バックエンド + WASM モジュール非適用 + 非 gRPC + バッファリング非 Full +
-/// 上流 use_h2c 以外 + セキュリティ許可 + サーバー選択成功。conn は借用のみ（変更しない）。
+/// 外部認可へボディを渡さない（F-149）+ 上流 use_h2c 以外 + セキュリティ許可 + サーバー選択成功。conn は借用のみ（変更しない）。
 /// 適格なら `Some(max_request_body)`（0 = 無制限）を返す。
 #[cfg(feature = "http2")]
 fn h2_route_streaming_plan<S>(
@@ -1281,9 +1449,25 @@ where
     if buffering.mode == crate::buffering::BufferingMode::Full {
         return None;
     }
+    // F-149: ボディを認可サービスへ渡すルートは受信し終えてから問い合わせる。
+    if security.ext_authz.as_ref().is_some_and(|a| a.sends_body()) {
+        return None;
+    }
+    // F-154: ボディを検査する WAF も受信し終えてから検査する。
+    if security.waf.as_ref().is_some_and(|w| w.inspects_body()) {
+        return None;
+    }
+    // F-155: CORS のプリフライトはバッファ経路で上流へ送らずに応答する。
+    if security.cors.is_some() && method == b"OPTIONS" {
+        return None;
+    }
     if check_security(&security, client_ip, &method, 0, true) != SecurityCheckResult::Allowed {
         return None;
     }
+    // F-134 / F-135: HTTP/2・HTTP/3 上流は多重化接続へリクエスト全体を渡すためバッファ経路。
+    if upstream_group.uses_multiplexed_upstream() {
+        return None;
+    }
     let server = upstream_group.select(client_ip)?;
     if server.target.use_h2c {
         return None;
@@ -1336,6 +1520,8 @@ async fn h2_request_task(
             ctx.start,
             &ctx.client_ip,
             "",
+            ctx.hedge.get().as_str(),
+            &ctx.user.borrow(),
         );
     }
     // resp_tx / req_rx はここで drop → メインループへ EOF 伝播。
@@ -1355,6 +1541,20 @@ async fn h2_send(
     r
 }
 
+/// リクエスト単位の応答ヘッダーを Head より先に送り、接続ループ側で追記させる。
+#[cfg(feature = "http2")]
+async fn h2_send_extra_headers(
+    resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
+    notify: &crate::stream_channel::Notify,
+    extra: &ExtraHeaders,
+) -> Result<(), ()> {
+    if extra.response.is_empty() {
+        return Ok(());
+    }
+    let headers = extra.response_header_pairs();
+    h2_send(resp_tx, notify, H2RespMsg::ExtraHeaders(headers)).await
+}
+
 /// サーバー/Alt-Svc 等の共通レスポンスヘッダを所有ベクタで構築する。
 #[cfg(feature = "http2")]
 fn h2_base_headers(add_alt_svc: bool) -> Vec<(Vec<u8>, Vec<u8>)> {
@@ -1421,6 +1621,54 @@ async fn h2_emit_error(
     .await
 }
 
+/// 同時実行数の上限超過時の 503 応答（F-138、`Retry-After` 付き）。戻り値 `(status, body_len)`。
+#[cfg(feature = "http2")]
+async fn h2_emit_concurrency_limited(
+    resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
+    notify: &crate::stream_channel::Notify,
+    retry_after_secs: u64,
+) -> (u16, u64) {
+    let mut headers = h2_base_headers(false);
+    headers.push((
+        b"retry-after".to_vec(),
+        retry_after_secs.to_string().into_bytes(),
+    ));
+    h2_emit_full(
+        resp_tx,
+        notify,
+        503,
+        headers,
+        b"Service Unavailable".to_vec(),
+    )
+    .await
+}
+
+/// 認証・レートリミット・WAF・外部認可の拒否（F-146〜F-151・F-154）。
+#[cfg(feature = "http2")]
+async fn h2_emit_rejection(
+    resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
+    notify: &crate::stream_channel::Notify,
+    rejection: &crate::request_checks::Rejection,
+) -> (u16, u64) {
+    let mut headers = h2_base_headers(false);
+    headers.extend(rejection.h2_headers());
+    let body = rejection.body().to_vec();
+    h2_emit_full(resp_tx, notify, rejection.status(), headers, body).await
+}
+
+/// CORS のプリフライトへの応答（F-155）。
+#[cfg(feature = "http2")]
+async fn h2_emit_cors_preflight(
+    resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
+    notify: &crate::stream_channel::Notify,
+    preflight: &crate::cors::CorsPreflight,
+) -> (u16, u64) {
+    let mut headers = h2_base_headers(false);
+    headers.extend(preflight.h2_headers());
+    let body = preflight.body().to_vec();
+    h2_emit_full(resp_tx, notify, preflight.status(), headers, body).await
+}
+
 /// バッファ経路（END_STREAM 済み）の 1 リクエストを処理してレスポンスを送出する（F-116）。
 ///
 /// 戻り値 `(status, resp_size, req_size)`。`status == 0` はクライアント切断（ログ不要）。
@@ -1523,6 +1771,17 @@ async fn h2_dispatch(
 
     // セキュリティチェック。
     let security = backend.security();
+
+    // F-155: CORS のプリフライトはメソッド制限・認証より前に上流へ送らずに応答する。
+    if let Some(cors) = security.cors.as_ref() {
+        let ip_filter = security.ip_filter();
+        if !ip_filter.is_configured() || ip_filter.is_allowed(client_ip) {
+            if let Some(preflight) = cors.preflight(method, &headers_raw) {
+                return h2_emit_cors_preflight(resp_tx, notify, &preflight).await;
+            }
+        }
+    }
+
     let check_result = check_security(security, client_ip, method, ctx.body.len(), false);
     if check_result != SecurityCheckResult::Allowed {
         let status = check_result.status_code();
@@ -1530,6 +1789,28 @@ async fn h2_dispatch(
         return h2_emit_error(resp_tx, notify, status, msg).await;
     }
 
+    // 認証・レートリミット・CORS・WAF・外部認可。
+    let checked = crate::request_checks::check_request(
+        security,
+        &crate::request_checks::RequestInfo {
+            client_ip,
+            method,
+            path,
+            host: authority,
+            headers: &headers_raw,
+        },
+        |_| std::future::ready(Some(Cow::Borrowed(&ctx.body[..]))),
+    )
+    .await;
+    ctx.user
+        .replace(crate::jwt_auth::principal(checked.claims.as_ref()));
+    #[cfg(feature = "wasm")]
+    let jwt_claims = checked.claims;
+    let extra_headers = match checked.outcome {
+        Ok(extra) => extra,
+        Err(rejection) => return h2_emit_rejection(resp_tx, notify, &rejection).await,
+    };
+
     // WASM リクエストフィルタ。
     #[cfg(feature = "wasm")]
     let wasm_modules_to_apply: Arc<Vec<String>> = {
@@ -1556,6 +1837,7 @@ async fn h2_dispatch(
                         Arc::from(method_str),
                         headers_vec,
                         Arc::from(client_ip),
+                        jwt_claims.clone().map(Arc::new),
                         ctx.body.is_empty(),
                     )
                     .await;
@@ -1599,21 +1881,58 @@ async fn h2_dispatch(
         .map(|h| AcceptedEncoding::parse(&h.value))
         .unwrap_or(AcceptedEncoding::Identity);
 
+    if h2_send_extra_headers(resp_tx, notify, &extra_headers)
+        .await
+        .is_err()
+    {
+        return (499, 0);
+    }
+
     let result = match backend {
         Backend::Proxy(upstream_group, security, compression, _buffering, _cache, _) => {
-            h2_proxy(
-                ctx,
-                &upstream_group,
-                &compression,
-                client_encoding,
-                &prefix,
-                &security,
-                #[cfg(feature = "wasm")]
-                &wasm_modules_to_apply,
-                resp_tx,
-                notify,
-            )
-            .await
+            // F-139: リクエスト全体の期限（gRPC は受信した grpc-timeout も期限として扱う）
+            let is_grpc = ctx
+                .headers
+                .iter()
+                .any(|h| header_pair_is_grpc(&h.name, &h.value));
+            let grpc_timeout = ctx
+                .headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case(b"grpc-timeout"))
+                .map(|h| h.value.as_slice());
+            let security =
+                match UpstreamDeadline::resolve(&security, ctx.start, is_grpc, grpc_timeout) {
+                    Some(deadline) => Arc::new(security.with_upstream_deadline(deadline)),
+                    None => security,
+                };
+            if security.upstream_deadline.is_some_and(|d| d.is_expired()) {
+                h2_emit_upstream_timeout(resp_tx, notify, &security).await
+            } else {
+                // F-138: 適応型の同時実行数制限（上限超過は上流へ送らず即 503）
+                match acquire_concurrency_permit(&upstream_group) {
+                    Ok(permit) => {
+                        let result = h2_proxy(
+                            ctx,
+                            &upstream_group,
+                            &compression,
+                            client_encoding,
+                            &prefix,
+                            &security,
+                            &extra_headers,
+                            #[cfg(feature = "wasm")]
+                            &wasm_modules_to_apply,
+                            resp_tx,
+                            notify,
+                        )
+                        .await;
+                        complete_concurrency_permit(&upstream_group, permit, result.0);
+                        result
+                    }
+                    Err(retry_after) => {
+                        h2_emit_concurrency_limited(resp_tx, notify, retry_after).await
+                    }
+                }
+            }
         }
         Backend::MemoryFile(data, mime_type, security, _) => {
             let path_str = std::str::from_utf8(path).unwrap_or("/");
@@ -1709,6 +2028,7 @@ async fn h2_proxy(
     client_encoding: AcceptedEncoding,
     prefix: &[u8],
     security: &SecurityConfig,
+    extra: &ExtraHeaders,
     #[cfg(feature = "wasm")] wasm_modules: &Arc<Vec<String>>,
     resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
     notify: &crate::stream_channel::Notify,
@@ -1717,44 +2037,47 @@ async fn h2_proxy(
     let req_path = &ctx.path[..];
     let client_ip: &str = &ctx.client_ip;
 
-    // Consistent Hash キー解決。
-    let hash_key_owned: Option<String> = match &upstream_group.algorithm {
-        crate::config::LoadBalanceAlgorithm::ConsistentHash {
-            hash_key: crate::config::HashKey::Header(name),
-        } => ctx
-            .headers
-            .iter()
-            .find(|h| h.name.eq_ignore_ascii_case(name.as_bytes()))
-            .and_then(|h| std::str::from_utf8(&h.value).ok())
-            .map(|s| s.to_string()),
-        crate::config::LoadBalanceAlgorithm::ConsistentHash {
-            hash_key: crate::config::HashKey::Cookie(name),
-        } => ctx
-            .headers
-            .iter()
-            .find(|h| h.name.eq_ignore_ascii_case(b"cookie"))
-            .and_then(|h| std::str::from_utf8(&h.value).ok())
-            .and_then(|c| {
-                c.split(';').find_map(|part| {
-                    let part = part.trim();
-                    part.split_once('=').and_then(|(k, v)| {
-                        if k.trim().eq_ignore_ascii_case(name) {
-                            Some(v.trim().to_string())
-                        } else {
-                            None
-                        }
-                    })
-                })
-            }),
-        _ => None,
+    // ハッシュ系アルゴリズムのキー解決（header / cookie / path）と
+    // F-132 スティッキー Cookie による固定先の解決。
+    let (server, sticky_set_cookie) =
+        match upstream_group.select_with_affinity(client_ip, req_path, |name| {
+            ctx.headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case(name))
+                .map(|h| h.value.as_slice())
+        }) {
+            Some(s) => s,
+            None => return h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await,
+        };
+    if let Some(cookie) = sticky_set_cookie {
+        // Head より先に送り、接続ループ側でレスポンスヘッダへ追記させる
+        let extra = vec![(b"set-cookie".to_vec(), cookie.into_bytes())];
+        if h2_send(resp_tx, notify, H2RespMsg::ExtraHeaders(extra))
+            .await
+            .is_err()
+        {
+            return (499, 0);
+        }
+    }
+    // F-141: 送信元アドレスの指定があればこのリクエストの送信元を決める
+    let target = match server.target.bound_for(client_ip) {
+        Ok(target) => target,
+        Err(e) => {
+            warn!("[HTTP/2] Upstream source address error: {}", e);
+            return h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await;
+        }
     };
-
-    let server = match upstream_group.select_with_key(client_ip, hash_key_owned.as_deref(), None) {
-        Some(s) => s,
-        None => return h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await,
+    let target = &*target;
+    // F-136: 上流接続の使用枠（多重化上流はストリーム上限で制御するため対象外）
+    let mut lease = if target.uses_tls_h2() || target.uses_h3() {
+        None
+    } else {
+        match acquire_connection_lease(target).await {
+            Ok(lease) => lease,
+            Err(_) => return h2_emit_error(resp_tx, notify, 503, b"Service Unavailable").await,
+        }
     };
     server.acquire();
-    let target = &server.target;
 
     let path_str = std::str::from_utf8(req_path).unwrap_or("/");
     let preserve_grpc_path = ctx
@@ -1776,6 +2099,7 @@ async fn h2_proxy(
             method,
             final_path.as_bytes(),
             security,
+            extra,
             #[cfg(feature = "wasm")]
             wasm_modules,
             resp_tx,
@@ -1786,77 +2110,201 @@ async fn h2_proxy(
         return result;
     }
 
-    // H1/HTTPS バックエンドへの HTTP/1.1 リクエストを構築。
-    let mut request = request_buf_get(1024);
-    request.extend_from_slice(method);
-    request.extend_from_slice(b" ");
-    request.extend_from_slice(final_path.as_bytes());
-    request.extend_from_slice(b" HTTP/1.1\r\nHost: ");
-    request.extend_from_slice(target.host.as_bytes());
-    if !target.is_default_port() {
-        request.extend_from_slice(b":");
-        let mut port_buf = itoa::Buffer::new();
-        request.extend_from_slice(port_buf.format(target.port).as_bytes());
-    }
-    request.extend_from_slice(b"\r\n");
-    for header in ctx.headers.iter() {
-        if header.name.starts_with(b":") {
-            continue;
-        }
-        if header.name.eq_ignore_ascii_case(b"connection")
-            || header.name.eq_ignore_ascii_case(b"keep-alive")
-            || header.name.eq_ignore_ascii_case(b"transfer-encoding")
+    // F-134: protocol = "h2" / "auto" の TLS バックエンドは ALPN h2 の多重化接続で中継。
+    if target.uses_tls_h2() {
+        let addr = HostPortStr::new(&target.host, target.port);
+        if let Some(result) = h2_proxy_h2_tls(
+            ctx,
+            addr.as_str(),
+            target,
+            method,
+            final_path.as_bytes(),
+            security,
+            extra,
+            upstream_group.tls_insecure(),
+            #[cfg(feature = "wasm")]
+            wasm_modules,
+            resp_tx,
+            notify,
+        )
+        .await
         {
-            continue;
+            server.release();
+            return result;
         }
-        request.extend_from_slice(&header.name);
-        request.extend_from_slice(b": ");
-        request.extend_from_slice(&header.value);
-        request.extend_from_slice(b"\r\n");
     }
-    if !ctx.body.is_empty() {
-        request.extend_from_slice(b"Content-Length: ");
-        let mut len_buf = itoa::Buffer::new();
-        request.extend_from_slice(len_buf.format(ctx.body.len()).as_bytes());
-        request.extend_from_slice(b"\r\n");
+
+    // F-135: protocol = "h3" は QUIC の多重化接続で中継（UDP 不通時は HTTP/1.1 経路へ）。
+    #[cfg(feature = "http3")]
+    if target.uses_h3() {
+        let addr = HostPortStr::new(&target.host, target.port);
+        if let Some(result) = h2_proxy_h3(
+            ctx,
+            addr.as_str(),
+            target,
+            method,
+            final_path.as_bytes(),
+            security,
+            extra,
+            upstream_group.tls_insecure(),
+            #[cfg(feature = "wasm")]
+            wasm_modules,
+            resp_tx,
+            notify,
+        )
+        .await
+        {
+            server.release();
+            return result;
+        }
     }
-    request.extend_from_slice(b"Connection: keep-alive\r\n\r\n");
-    request.extend_from_slice(&ctx.body);
+
+    // H1/HTTPS バックエンドへの HTTP/1.1 リクエストを構築。
+    let request = h2_build_upstream_request(ctx, method, final_path, target, security, extra);
 
     let addr = HostPortStr::new(&target.host, target.port);
     let addr = addr.as_str();
 
+    // F-137: ヘッジ対象（ポリシー設定あり・対象メソッド・ボディなし・複数サーバー）
+    let hedge_policy = upstream_group.hedging.as_deref().filter(|p| {
+        ctx.body.is_empty()
+            && target.source.is_none()
+            && upstream_group.servers.len() > 1
+            && p.allows_method(method)
+    });
+    let mut served_by = server;
+
     let result = if target.use_tls {
         h2_proxy_https(
             ctx,
             addr,
+            target.bind,
+            target.socket.as_ref(),
             target.sni(),
             request,
             compression,
             client_encoding,
             security,
+            &target.connection_pool,
             upstream_group.tls_insecure(),
             resp_tx,
             notify,
         )
         .await
-    } else {
-        h2_proxy_http(
+    } else if let Some(policy) = hedge_policy {
+        let (result, winner) = h2_proxy_http_hedged(
+            ctx,
+            upstream_group,
+            policy,
+            server,
+            lease.take(),
+            request,
+            |alt: &ProxyTarget| {
+                let alt_path =
+                    compute_upstream_path(path_str, prefix, &alt.path_prefix, preserve_grpc_path);
+                h2_build_upstream_request(ctx, method, &alt_path, alt, security, extra)
+            },
+            compression,
+            client_encoding,
+            security,
+            resp_tx,
+            notify,
+        )
+        .await;
+        served_by = winner;
+        result
+    } else {
+        h2_proxy_http(
             ctx,
             addr,
+            target.bind,
+            target.socket.as_ref(),
             request,
             compression,
             client_encoding,
             security,
+            &target.connection_pool,
             resp_tx,
             notify,
+            None,
         )
         .await
     };
-    server.release();
+    drop(lease);
+    served_by.release();
     result
 }
 
+/// HTTP/2 ストリームから H1/HTTPS バックエンド向けの HTTP/1.1 リクエストを構築する。
+///
+/// F-137 のヘッジでは Host と `path_prefix` が異なる別サーバー向けに再構築する。
+/// F-139 の gRPC 期限があれば `grpc-timeout` を残り時間で置き換える。
+#[cfg(feature = "http2")]
+fn h2_build_upstream_request(
+    ctx: &H2RequestCtx,
+    method: &[u8],
+    final_path: &str,
+    target: &ProxyTarget,
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
+) -> Vec<u8> {
+    let grpc_timeout = security
+        .upstream_deadline
+        .and_then(|d| d.grpc_timeout_value());
+    let mut request = request_buf_get(1024);
+    request.extend_from_slice(method);
+    request.extend_from_slice(b" ");
+    request.extend_from_slice(final_path.as_bytes());
+    request.extend_from_slice(b" HTTP/1.1\r\nHost: ");
+    request.extend_from_slice(target.host.as_bytes());
+    if !target.is_default_port() {
+        request.extend_from_slice(b":");
+        let mut port_buf = itoa::Buffer::new();
+        request.extend_from_slice(port_buf.format(target.port).as_bytes());
+    }
+    request.extend_from_slice(b"\r\n");
+    for header in ctx.headers.iter() {
+        if header.name.starts_with(b":") {
+            continue;
+        }
+        if header.name.eq_ignore_ascii_case(b"connection")
+            || header.name.eq_ignore_ascii_case(b"keep-alive")
+            || header.name.eq_ignore_ascii_case(b"transfer-encoding")
+            || (grpc_timeout.is_some() && header.name.eq_ignore_ascii_case(b"grpc-timeout"))
+            || security.is_removed_request_header(&header.name, extra)
+        {
+            continue;
+        }
+        request.extend_from_slice(&header.name);
+        request.extend_from_slice(b": ");
+        request.extend_from_slice(&header.value);
+        request.extend_from_slice(b"\r\n");
+    }
+    for (name, value) in &extra.request {
+        if !is_valid_header_name(name.as_bytes()) || !is_valid_header_value(value.as_bytes()) {
+            continue;
+        }
+        request.extend_from_slice(name.as_bytes());
+        request.extend_from_slice(b": ");
+        request.extend_from_slice(value.as_bytes());
+        request.extend_from_slice(b"\r\n");
+    }
+    if let Some(value) = grpc_timeout {
+        request.extend_from_slice(b"grpc-timeout: ");
+        request.extend_from_slice(value.as_bytes());
+        request.extend_from_slice(b"\r\n");
+    }
+    if !ctx.body.is_empty() {
+        request.extend_from_slice(b"Content-Length: ");
+        let mut len_buf = itoa::Buffer::new();
+        request.extend_from_slice(len_buf.format(ctx.body.len()).as_bytes());
+        request.extend_from_slice(b"\r\n");
+    }
+    request.extend_from_slice(b"Connection: keep-alive\r\n\r\n");
+    request.extend_from_slice(&ctx.body);
+    request
+}
+
 /// バックエンド接続の EADDRNOTAVAIL 一時的失敗を指数バックオフでリトライして吸収する（B-44 第2段）。
 ///
 /// F-116 のストリーム多重化により、スレッドあたり同時 ~250 ストリームが独立にバックエンド
@@ -1879,11 +2327,16 @@ async fn h2_proxy(
 /// `timeout` 到達時は呼び出し側で 504 に区別できるよう `io::ErrorKind::TimedOut` を返す。
 /// それ以外の connect エラー（最終リトライ失敗を含む）はそのまま返す（呼び出し側で 502）。
 #[cfg(feature = "http2")]
-async fn connect_backend_with_retry(addr: &str) -> io::Result<TcpStream> {
+async fn connect_backend_with_retry(
+    addr: &str,
+    bind: Option<LocalBind>,
+    socket: Option<&Arc<SocketOptions>>,
+) -> io::Result<TcpStream> {
     const BACKOFF_MS: [u64; 3] = [10, 40, 160];
     let mut attempt = 0usize;
     loop {
-        match timeout(CONNECT_TIMEOUT, TcpStream::connect_str(addr)).await {
+        let connect = TcpStream::connect_str_with(addr, bind, socket.cloned());
+        match timeout(CONNECT_TIMEOUT, connect).await {
             Ok(Ok(stream)) => {
                 let _ = stream.set_nodelay(true);
                 return Ok(stream);
@@ -1901,11 +2354,7 @@ async fn connect_backend_with_retry(addr: &str) -> io::Result<TcpStream> {
     }
 }
 
-/// ホストごとの新規 connect 並行数の上限（スレッドごと、B-44 第3段）。
-#[cfg(feature = "http2")]
-const MAX_CONCURRENT_CONNECTS_PER_HOST: usize = 64;
-
-/// ホストごとの新規 connect 並行数ゲート（B-44 第3段）。
+/// ホストごとの上流接続ゲート（B-44 第3段、F-136 で使用枠と待機キューを追加）。
 ///
 /// 第2段のリトライ（10/40/160ms、最大 4 試行）で 5xx は約 1/3 に減ったが根絶できなかった。
 /// h2load の反復切り替え時に in-flight だった ~900 接続がタスクキャンセルで破棄され、
@@ -1913,17 +2362,28 @@ const MAX_CONCURRENT_CONNECTS_PER_HOST: usize = 64;
 /// 次リクエストを発行、の自己持続でストームが数秒継続し、バックオフ合計 210ms では
 /// 吸収できない。そこで **バックエンドへの新規 connect の同時実行数をホストごとに制限**する
 /// 構造的修正を行う（Envoy の upstream circuit breaker `max_connections`/pending queue 相当）。
-/// 新規 connect は 64/スレッドの波で進み、完了したリクエストの接続が返却され次第、
-/// 待機ストリームは再利用側で満たされるため、EADDRNOTAVAIL の発生源
-/// （数百規模の一斉 connect）自体が消える。
+/// 新規 connect は `max_concurrent_connects`（既定 64）/スレッドの波で進み、完了した
+/// リクエストの接続が返却され次第、待機ストリームは再利用側で満たされるため、
+/// EADDRNOTAVAIL の発生源（数百規模の一斉 connect）自体が消える。
 ///
-/// ゲートは **プールミス時のみ** 作動するコールドパスであり、プールヒット
-/// （ホットパス）はゲートに一切触れない（ホットパス絶対規則に反しない）。
+/// F-136 で upstream ごとの `connection_pool` 設定に従う使用枠（`max_connections`）と
+/// 待機キュー（`max_pending_requests` / `pending_timeout_ms`）を同じゲートに載せた。
+/// 待機者は connect スロットと使用枠のどちらの解放・プール返却でも起こされる。
+///
+/// connect ゲートは **プールミス時のみ** 作動するコールドパスであり、プールヒット
+/// （ホットパス）はゲートに一切触れない（ホットパス絶対規則に反しない）。使用枠は
+/// `max_connections` を設定した upstream でのみ確保する。
 /// thread-per-core 構成のためスレッドローカルでロック不要。
-#[cfg(feature = "http2")]
 struct ConnectGate {
+    /// メトリクスラベル（host:port）
+    host: Box<str>,
     /// このスレッド上で進行中の新規 connect 数。
+    #[cfg(feature = "http2")]
     in_flight: std::cell::Cell<usize>,
+    /// 使用中の接続数（F-136、`max_connections` 設定時のみ計上）。
+    active: std::cell::Cell<usize>,
+    /// 空きを待っているリクエスト数（F-136）。
+    pending: std::cell::Cell<usize>,
     /// スロット解放・プール返却を待つ待機者（待機者ごとの `Notify` を積む）。
     ///
     /// 設計原案はゲート共有の単一 `Notify` だったが、`Notify` は waker を 1 つしか
@@ -1934,11 +2394,14 @@ struct ConnectGate {
     waiters: std::cell::RefCell<std::collections::VecDeque<crate::stream_channel::Notify>>,
 }
 
-#[cfg(feature = "http2")]
 impl ConnectGate {
-    fn new() -> Self {
+    fn new(host: &str) -> Self {
         Self {
+            host: host.into(),
+            #[cfg(feature = "http2")]
             in_flight: std::cell::Cell::new(0),
+            active: std::cell::Cell::new(0),
+            pending: std::cell::Cell::new(0),
             waiters: std::cell::RefCell::new(std::collections::VecDeque::new()),
         }
     }
@@ -1953,6 +2416,178 @@ impl ConnectGate {
             waiter.notify();
         }
     }
+
+    /// 次の通知（スロット解放・プール返却）を `deadline` まで待つ（F-136）。
+    ///
+    /// 待機者の `Notify` 確保（`Rc` 1 個）は初回待機時のみで、以降の再待機では再利用する。
+    async fn wait_turn(
+        &self,
+        waiter: &mut Option<crate::stream_channel::Notify>,
+        deadline: Instant,
+    ) -> Result<(), PendingRejected> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(PendingRejected::Timeout);
+        }
+        let w = waiter.get_or_insert_with(crate::stream_channel::Notify::new);
+        self.waiters.borrow_mut().push_back(w.clone());
+        timeout(remaining, w.wait())
+            .await
+            .map_err(|_| PendingRejected::Timeout)
+    }
+
+    /// 使用枠に空きがあれば確保する（F-136）
+    fn try_lease(self: &std::rc::Rc<Self>, max_connections: usize) -> Option<ConnectionLease> {
+        if self.active.get() < max_connections {
+            self.active.set(self.active.get() + 1);
+            crate::metrics::add_upstream_active_connections(&self.host, 1);
+            Some(ConnectionLease { gate: self.clone() })
+        } else {
+            None
+        }
+    }
+}
+
+/// 接続待ちキューで 503 にした理由（F-136）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PendingRejected {
+    /// 待機数が `max_pending_requests` に達していた
+    Overflow,
+    /// `pending_timeout_ms` 以内に空きが出なかった
+    Timeout,
+}
+
+impl PendingRejected {
+    fn label(self) -> &'static str {
+        match self {
+            PendingRejected::Overflow => "overflow",
+            PendingRejected::Timeout => "timeout",
+        }
+    }
+}
+
+/// 待機中リクエスト数を数える RAII ガード（F-136）
+///
+/// 取得成功・503・タスクキャンセルのいずれの経路でも Drop で待機数を戻す。
+struct PendingGuard<'a> {
+    gate: &'a ConnectGate,
+}
+
+impl<'a> PendingGuard<'a> {
+    /// 待機キューに入る。満杯なら `Overflow`。
+    fn enter(gate: &'a ConnectGate, max_pending: usize) -> Result<Self, PendingRejected> {
+        if gate.pending.get() >= max_pending {
+            return Err(PendingRejected::Overflow);
+        }
+        gate.pending.set(gate.pending.get() + 1);
+        crate::metrics::add_upstream_pending_requests(&gate.host, 1);
+        Ok(Self { gate })
+    }
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.gate.pending.set(self.gate.pending.get() - 1);
+        crate::metrics::add_upstream_pending_requests(&self.gate.host, -1);
+    }
+}
+
+/// 上流接続の使用枠（F-136）。リクエストが接続を使い終えるまで保持する。
+///
+/// Drop で枠を返して待機者を起こす（応答完了・エラー・キャンセルのいずれでも解放漏れなし）。
+struct ConnectionLease {
+    gate: std::rc::Rc<ConnectGate>,
+}
+
+impl Drop for ConnectionLease {
+    fn drop(&mut self) {
+        self.gate.active.set(self.gate.active.get() - 1);
+        crate::metrics::add_upstream_active_connections(&self.gate.host, -1);
+        self.gate.notify_waiters();
+    }
+}
+
+/// 上流接続の使用枠を確保する（F-136）。
+///
+/// `max_connections` が 0（既定）ならゲートに触れず `Ok(None)` を返す。満杯なら枠の解放を
+/// 待ち、待機数が `max_pending_requests` に達している場合と `pending_timeout_ms` を過ぎた
+/// 場合は `Err`（呼び出し側で 503）。ALPN h2・HTTP/3 の多重化上流は対象外（呼び出し側で除外）。
+async fn acquire_connection_lease(
+    target: &ProxyTarget,
+) -> Result<Option<ConnectionLease>, PendingRejected> {
+    let cfg = &target.connection_pool;
+    if cfg.max_connections == 0 {
+        return Ok(None);
+    }
+    let addr = HostPortStr::new(&target.host, target.port);
+    let gate = connect_gate(addr.as_str());
+    if let Some(lease) = gate.try_lease(cfg.max_connections) {
+        return Ok(Some(lease));
+    }
+    let result: Result<Option<ConnectionLease>, PendingRejected> = async {
+        let _pending = PendingGuard::enter(&gate, cfg.max_pending_requests)?;
+        let deadline = Instant::now() + cfg.pending_timeout();
+        let mut waiter = None;
+        loop {
+            gate.wait_turn(&mut waiter, deadline).await?;
+            if let Some(lease) = gate.try_lease(cfg.max_connections) {
+                return Ok(Some(lease));
+            }
+        }
+    }
+    .await;
+    if let Err(reason) = result {
+        crate::metrics::record_upstream_pending_rejected(&gate.host, reason.label());
+    }
+    result
+}
+
+/// 適応型の同時実行数制限の枠を取る（F-138）。
+///
+/// 制限が無効なら `Ok(None)`。上限に達していれば拒否を記録し、`Err(Retry-After 秒)` を
+/// 返す（呼び出し側で即座に 503）。
+pub(crate) fn acquire_concurrency_permit(
+    group: &UpstreamGroup,
+) -> Result<Option<ConcurrencyPermit>, u64> {
+    let Some(limiter) = &group.concurrency_limiter else {
+        return Ok(None);
+    };
+    match limiter.try_acquire() {
+        Some(permit) => Ok(Some(permit)),
+        None => {
+            crate::metrics::record_upstream_concurrency_rejected(&group.name);
+            Err(limiter.retry_after_secs())
+        }
+    }
+}
+
+/// 完了したリクエストを同時実行数制限へ反映する（F-138）。
+///
+/// 5xx は失敗として記録する。クライアント切断（0 / 499）は上流の応答時間を表さないため
+/// RTT を記録せず枠のみ返す。
+pub(crate) fn complete_concurrency_permit(
+    group: &UpstreamGroup,
+    permit: Option<ConcurrencyPermit>,
+    status: u16,
+) {
+    let Some(permit) = permit else {
+        return;
+    };
+    if status == 0 || status == 499 {
+        return;
+    }
+    if let Some(limit) = permit.complete(status < 500) {
+        crate::metrics::set_upstream_concurrency_limit(&group.name, limit);
+    }
+}
+
+/// 同時実行数の上限超過時の 503 応答（F-138、`Retry-After` 付き）
+fn concurrency_limited_response(retry_after_secs: u64) -> Vec<u8> {
+    format!(
+        "HTTP/1.1 503 Service Unavailable\r\nRetry-After: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        retry_after_secs
+    )
+    .into_bytes()
 }
 
 /// [`ConnectGate`] の in_flight スロットを保持する RAII ガード（B-44 第3段）。
@@ -1967,8 +2602,8 @@ struct ConnectPermit {
 #[cfg(feature = "http2")]
 impl ConnectPermit {
     /// 空きスロットがあれば確保して permit を返す。満杯なら `None`。
-    fn try_acquire(gate: &std::rc::Rc<ConnectGate>) -> Option<Self> {
-        if gate.in_flight.get() < MAX_CONCURRENT_CONNECTS_PER_HOST {
+    fn try_acquire(gate: &std::rc::Rc<ConnectGate>, max_connects: usize) -> Option<Self> {
+        if gate.in_flight.get() < max_connects {
             gate.in_flight.set(gate.in_flight.get() + 1);
             Some(Self { gate: gate.clone() })
         } else {
@@ -1985,10 +2620,9 @@ impl Drop for ConnectPermit {
     }
 }
 
-#[cfg(feature = "http2")]
 thread_local! {
-    /// 接続先ホスト（addr）→ 新規 connect ゲート（B-44 第3段）。
-    /// エントリは初回のプールミス時に生成される（コールドパス）。
+    /// 接続先ホスト（addr）→ 上流接続ゲート（B-44 第3段、F-136）。
+    /// エントリは初回のプールミス時（使用枠は初回の確保時）に生成される（コールドパス）。
     static CONNECT_GATES: std::cell::RefCell<
         std::collections::HashMap<String, std::rc::Rc<ConnectGate>>,
     > = std::cell::RefCell::new(std::collections::HashMap::new());
@@ -1996,14 +2630,13 @@ thread_local! {
 
 /// ホストのゲートを取得する（なければ生成、コールドパス）。
 /// `Rc` クローンはゲート取得時のみで、プールヒット経路はここに到達しない。
-#[cfg(feature = "http2")]
 fn connect_gate(host: &str) -> std::rc::Rc<ConnectGate> {
     CONNECT_GATES.with(|g| {
         let mut map = g.borrow_mut();
         if let Some(gate) = map.get(host) {
             gate.clone()
         } else {
-            let gate = std::rc::Rc::new(ConnectGate::new());
+            let gate = std::rc::Rc::new(ConnectGate::new(host));
             map.insert(host.to_string(), gate.clone());
             gate
         }
@@ -2030,130 +2663,443 @@ enum GateAcquire<P> {
     Pooled(P),
     /// ゲートを通過して新規に確立した接続。
     Fresh(TcpStream),
+    /// 待機キューが満杯、または待ち時間を超過した（F-136、呼び出し側で 503）。
+    Rejected,
 }
 
 /// プールミス時のバックエンド接続取得（新規 connect 並行数ゲート付き、B-44 第3段）。
 ///
 /// ループ先頭で毎回プール（`pool_get`）を再試行し、ゲート通過待ちの間に返却された
 /// 接続があれば新規 connect よりも優先して再利用する（これがストームを構造的に消す本質）。
-/// プールミスかつ in_flight < [`MAX_CONCURRENT_CONNECTS_PER_HOST`] ならスロットを確保して
+/// プールミスかつ in_flight < `max_concurrent_connects` ならスロットを確保して
 /// [`connect_backend_with_retry`]（第2段のリトライは安全網として維持）へ進み、満杯なら
-/// スロット解放またはプール返却の通知を待って再ループする。
+/// スロット解放またはプール返却の通知を待って再ループする。待機は F-136 の待機キュー
+/// （`max_pending_requests` / `pending_timeout_ms`）に従う。
 ///
 /// `RefCell` の借用はいずれも同期区間のみで、`.await` を跨いで保持しない。
-/// 待機者の `Notify` 確保（`Rc` 1 個）は初回待機時のみで、以降の再待機では再利用する。
 #[cfg(feature = "http2")]
 async fn acquire_backend_conn<P>(
     addr: &str,
+    bind: Option<LocalBind>,
+    socket: Option<&Arc<SocketOptions>>,
+    cfg: &ConnectionPoolConfig,
     mut pool_get: impl FnMut() -> Option<P>,
 ) -> io::Result<GateAcquire<P>> {
     let gate = connect_gate(addr);
     let mut waiter: Option<crate::stream_channel::Notify> = None;
+    let mut pending: Option<PendingGuard<'_>> = None;
+    let mut deadline: Option<Instant> = None;
     loop {
         // ゲート通過待ちの間に返却された接続の再利用を最優先する
         if let Some(stream) = pool_get() {
             return Ok(GateAcquire::Pooled(stream));
         }
-        if let Some(_permit) = ConnectPermit::try_acquire(&gate) {
+        if let Some(_permit) = ConnectPermit::try_acquire(&gate, cfg.max_concurrent_connects) {
+            drop(pending);
             // 成功・失敗・キャンセルのすべての経路で permit の Drop がスロットを解放する
-            return connect_backend_with_retry(addr)
+            return connect_backend_with_retry(addr, bind, socket)
                 .await
                 .map(GateAcquire::Fresh);
         }
         // 満杯: スロット解放かプール返却を待つ
-        let w = waiter.get_or_insert_with(crate::stream_channel::Notify::new);
-        gate.waiters.borrow_mut().push_back(w.clone());
-        w.wait().await;
+        if pending.is_none() {
+            match PendingGuard::enter(&gate, cfg.max_pending_requests) {
+                Ok(guard) => pending = Some(guard),
+                Err(reason) => {
+                    crate::metrics::record_upstream_pending_rejected(&gate.host, reason.label());
+                    return Ok(GateAcquire::Rejected);
+                }
+            }
+        }
+        let wait_until = *deadline.get_or_insert_with(|| Instant::now() + cfg.pending_timeout());
+        if let Err(reason) = gate.wait_turn(&mut waiter, wait_until).await {
+            crate::metrics::record_upstream_pending_rejected(&gate.host, reason.label());
+            return Ok(GateAcquire::Rejected);
+        }
     }
 }
 
-/// H1 バックエンド（平文）へのプロキシ（プール再利用付き、B-28）。
-#[cfg(feature = "http2")]
-#[allow(clippy::too_many_arguments)]
-async fn h2_proxy_http(
-    _ctx: &H2RequestCtx,
-    addr: &str,
-    request: Vec<u8>,
-    compression: &CompressionConfig,
-    client_encoding: AcceptedEncoding,
-    security: &SecurityConfig,
-    resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
-    notify: &crate::stream_channel::Notify,
-) -> (u16, u64) {
-    let mut backend = match HTTP_POOL.with(|p| p.borrow_mut().get(addr)) {
-        Some(stream) => stream,
-        None => {
-            // プールミス: 新規 connect 並行数ゲート経由で取得（B-44 第3段）
-            match acquire_backend_conn(addr, || HTTP_POOL.with(|p| p.borrow_mut().get(addr))).await
-            {
-                Ok(GateAcquire::Pooled(stream) | GateAcquire::Fresh(stream)) => stream,
-                Err(e) if e.kind() == io::ErrorKind::TimedOut => {
-                    let (s, sz) = h2_emit_error(resp_tx, notify, 504, b"Gateway Timeout").await;
-                    return (s, sz);
+/// 上流コネクションプールの事前確立（F-136）。
+///
+/// ワーカースレッドの起動直後に呼び、`connection_pool.prewarm_connections` を設定した
+/// upstream の各サーバーへ接続を確立して、そのスレッドのプールへ入れる（プールは
+/// スレッドローカルのためワーカーごとに実行する）。対象は HTTP/1.1（HTTP / HTTPS）と h2c で、
+/// キーは HTTP/1 フロントエンドと同じ。失敗は警告のみで起動を妨げない。事前確立した接続も
+/// 通常どおりアイドルタイムアウト（`idle_connection_timeout_secs` の既定値）で回収される。
+pub(crate) async fn prewarm_upstream_pools() {
+    // await を跨ぐため Guard ではなく Arc で保持する
+    let config = CURRENT_CONFIG.load_full();
+    for group in config.upstream_groups.values() {
+        let tls_insecure = group.tls_insecure();
+        for server in &group.servers {
+            let target = &server.target;
+            let count = target
+                .connection_pool
+                .prewarm_connections
+                .min(BACKEND_POOL_MAX_IDLE_PER_HOST);
+            // F-141: 送信元を指定した上流はリクエストごとに送信元が決まるため事前確立しない
+            if count == 0 || target.uses_tls_h2() || target.uses_h3() || target.source.is_some() {
+                continue;
+            }
+            let addr = HostPortStr::new(&target.host, target.port);
+            let addr = addr.as_str();
+            let lifecycle = ConnLifecycle::new(&target.connection_pool);
+            let mut opened = 0usize;
+            if target.use_h2c || group.use_h2c() {
+                #[cfg(feature = "http2")]
+                for _ in 0..count {
+                    let Ok(client) = h2c_connect_and_handshake(addr, target.socket.as_ref()).await
+                    else {
+                        break;
+                    };
+                    H2C_POOL.with(|p| {
+                        p.borrow_mut().put(
+                            addr.to_string(),
+                            client,
+                            lifecycle,
+                            BACKEND_POOL_MAX_IDLE_PER_HOST,
+                            BACKEND_POOL_IDLE_TIMEOUT_SECS,
+                        )
+                    });
+                    opened += 1;
                 }
-                Err(e) => {
-                    warn!("[HTTP/2] Backend connect error: {}", e);
-                    let (s, sz) = h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await;
-                    return (s, sz);
+            } else if target.use_tls {
+                let pool_key = if target.sni_name.is_some() {
+                    https_pool_key(&target.host, target.port, target.sni(), tls_insecure)
+                } else {
+                    https_pool_key_no_sni(&target.host, target.port, tls_insecure)
+                };
+                for _ in 0..count {
+                    let Ok(stream) =
+                        connect_https_backend_fresh(target, CONNECT_TIMEOUT, tls_insecure).await
+                    else {
+                        break;
+                    };
+                    HTTPS_POOL.with(|p| {
+                        p.borrow_mut().put(
+                            pool_key.clone(),
+                            stream,
+                            lifecycle,
+                            BACKEND_POOL_MAX_IDLE_PER_HOST,
+                            BACKEND_POOL_IDLE_TIMEOUT_SECS,
+                        )
+                    });
+                    opened += 1;
+                }
+            } else {
+                for _ in 0..count {
+                    let connect = TcpStream::connect_str_with(addr, None, target.socket.clone());
+                    let Ok(Ok(stream)) = timeout(CONNECT_TIMEOUT, connect).await else {
+                        break;
+                    };
+                    let _ = stream.set_nodelay(true);
+                    HTTP_POOL.with(|p| {
+                        p.borrow_mut().put(
+                            addr.to_string(),
+                            stream,
+                            lifecycle,
+                            BACKEND_POOL_MAX_IDLE_PER_HOST,
+                            BACKEND_POOL_IDLE_TIMEOUT_SECS,
+                        )
+                    });
+                    opened += 1;
                 }
             }
+            for _ in 0..opened {
+                crate::metrics::record_upstream_connection_prewarmed(addr);
+            }
+            if opened < count {
+                warn!(
+                    "Upstream '{}': pre-warmed {}/{} connections to {}",
+                    group.name, opened, count, addr
+                );
+            }
         }
-    };
-
-    let (write_res, returned_request) = backend.write_all(request).await;
-    request_buf_put(returned_request);
-    if write_res.is_err() {
-        return h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await;
     }
+}
 
-    let (status, sent, reusable) =
-        h2_relay_backend_response(&mut backend, compression, client_encoding, resp_tx, notify)
-            .await;
-    if reusable {
-        HTTP_POOL.with(|p| {
-            p.borrow_mut().put(
-                addr.to_string(),
-                backend,
-                security.max_idle_connections_per_host,
-                security.idle_connection_timeout_secs,
-            )
-        });
-        // 返却した接続をゲート待機者に再利用させる（B-44 第3段）
-        notify_connect_gate_waiters(addr);
+/// H1 バックエンド（平文）への接続を取得する（プール優先、ミス時はゲート経由で connect）。
+///
+/// 失敗時は応答ステータスを返す（待機キュー超過 503、タイムアウト 504、その他 502）。
+#[cfg(feature = "http2")]
+async fn h2_open_http_backend(
+    addr: &str,
+    bind: Option<LocalBind>,
+    socket: Option<&Arc<SocketOptions>>,
+    pool_cfg: &ConnectionPoolConfig,
+) -> Result<(TcpStream, ConnLifecycle), u16> {
+    let pool_key = bound_pool_key(addr, bind);
+    if let Some(pooled) = HTTP_POOL.with(|p| p.borrow_mut().get(&pool_key)) {
+        return Ok(pooled);
+    }
+    // プールミス: 新規 connect 並行数ゲート経由で取得（B-44 第3段）
+    match acquire_backend_conn(addr, bind, socket, pool_cfg, || {
+        HTTP_POOL.with(|p| p.borrow_mut().get(&pool_key))
+    })
+    .await
+    {
+        Ok(GateAcquire::Pooled(pooled)) => Ok(pooled),
+        Ok(GateAcquire::Fresh(stream)) => Ok((stream, ConnLifecycle::new(pool_cfg))),
+        Ok(GateAcquire::Rejected) => Err(503),
+        Err(e) if e.kind() == io::ErrorKind::TimedOut => Err(504),
+        Err(e) => {
+            warn!("[HTTP/2] Backend connect error: {}", e);
+            Err(502)
+        }
     }
-    (status, sent)
 }
 
-/// HTTPS バックエンドへのプロキシ（TLS プール再利用付き、B-28）。
+/// H1 バックエンド（平文）への接続を取得し、プール接続ならリクエストヘッダーを先行送信する（F-145）。
+///
+/// プール接続への最初の書き込みが失敗した場合は一度だけ接続を取り直す。
+/// 戻り値のリクエストは未送信分（送信済みなら空）。
 #[cfg(feature = "http2")]
-#[allow(clippy::too_many_arguments)]
-async fn h2_proxy_https(
-    _ctx: &H2RequestCtx,
+async fn h2_open_http_backend_for_request(
     addr: &str,
-    sni: &str,
+    bind: Option<LocalBind>,
+    socket: Option<&Arc<SocketOptions>>,
+    pool_cfg: &ConnectionPoolConfig,
     request: Vec<u8>,
-    compression: &CompressionConfig,
-    client_encoding: AcceptedEncoding,
-    security: &SecurityConfig,
-    tls_insecure: bool,
+) -> Result<(TcpStream, ConnLifecycle, Vec<u8>), u16> {
+    let pool_key = bound_pool_key(addr, bind);
+    let request = match HTTP_POOL.with(|p| p.borrow_mut().get(&pool_key)) {
+        Some((mut stream, lifecycle)) => match write_head_to_pooled(&mut stream, request).await {
+            PooledWrite::Sent => return Ok((stream, lifecycle, Vec::new())),
+            PooledWrite::Stale(request) => {
+                debug!(
+                    "[HTTP/2] Pooled connection to {} was stale, reconnecting",
+                    addr
+                );
+                crate::metrics::record_upstream_connection_retired(&pool_key, "stale_write");
+                request
+            }
+            PooledWrite::Failed => return Err(502),
+        },
+        None => request,
+    };
+    let (stream, lifecycle) = h2_open_http_backend(addr, bind, socket, pool_cfg).await?;
+    Ok((stream, lifecycle, request))
+}
+
+/// 上流接続の失敗ステータス（502 / 503 / 504）をエラー応答として返す。
+#[cfg(feature = "http2")]
+async fn h2_emit_gateway_error(
     resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
     notify: &crate::stream_channel::Notify,
+    status: u16,
 ) -> (u16, u64) {
-    let pool_key = format!(
-        "{}:{}:{}",
-        addr,
-        sni,
-        if tls_insecure { "insecure" } else { "verify" }
-    );
+    let reason: &[u8] = match status {
+        503 => b"Service Unavailable",
+        504 => b"Gateway Timeout",
+        _ => b"Bad Gateway",
+    };
+    h2_emit_error(resp_tx, notify, status, reason).await
+}
 
-    let mut backend = match HTTPS_POOL.with(|p| p.borrow_mut().get(&pool_key)) {
-        Some(stream) => stream,
-        None => {
-            // プールミス: 新規 connect 並行数ゲート経由で取得（B-44 第3段）
-            let acquired = match acquire_backend_conn(addr, || {
-                HTTPS_POOL.with(|p| p.borrow_mut().get(&pool_key))
-            })
-            .await
+/// 上流タイムアウト・リクエスト期限切れを応答する（F-139）。
+///
+/// gRPC の期限（`grpc-timeout`）切れなら Trailers-Only の DEADLINE_EXCEEDED、それ以外は 504。
+#[cfg(feature = "http2")]
+async fn h2_emit_upstream_timeout(
+    resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
+    notify: &crate::stream_channel::Notify,
+    security: &SecurityConfig,
+) -> (u16, u64) {
+    #[cfg(feature = "grpc")]
+    if security
+        .upstream_deadline
+        .is_some_and(|d| d.is_grpc() && d.is_expired())
+    {
+        let mut headers = h2_base_headers(false);
+        headers.extend(crate::timeouts::grpc_deadline_exceeded_headers());
+        let _ = h2_send(
+            resp_tx,
+            notify,
+            H2RespMsg::Head {
+                status: 200,
+                headers,
+                end_stream: true,
+            },
+        )
+        .await;
+        return (200, 0);
+    }
+    #[cfg(not(feature = "grpc"))]
+    let _ = security;
+    h2_emit_error(resp_tx, notify, 504, b"Gateway Timeout").await
+}
+
+/// ヘッジ付きの H1 バックエンド（平文）へのプロキシ（F-137）。
+///
+/// 一次試行の接続をゲート経由で取得して [`hedged_exchange`] で競わせ、勝者の接続で
+/// [`h2_proxy_http`] の中継を行う。戻り値の 2 要素目は中継したサーバー
+/// （`acquire()` 済み、呼び出し側で `release()`）。
+#[cfg(feature = "http2")]
+#[allow(clippy::too_many_arguments)]
+async fn h2_proxy_http_hedged<'a>(
+    ctx: &H2RequestCtx,
+    group: &'a UpstreamGroup,
+    policy: &HedgePolicy,
+    server: &'a UpstreamServer,
+    lease: Option<ConnectionLease>,
+    request: Vec<u8>,
+    build_request: impl FnOnce(&ProxyTarget) -> Vec<u8>,
+    compression: &CompressionConfig,
+    client_encoding: AcceptedEncoding,
+    security: &SecurityConfig,
+    resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
+    notify: &crate::stream_channel::Notify,
+) -> ((u16, u64), &'a UpstreamServer) {
+    let target = &server.target;
+    let addr = HostPortStr::new(&target.host, target.port);
+    let exchanged = match h2_open_http_backend(
+        addr.as_str(),
+        None,
+        target.socket.as_ref(),
+        &target.connection_pool,
+    )
+    .await
+    {
+        Ok(conn) => {
+            hedged_exchange(
+                group,
+                policy,
+                server,
+                conn,
+                lease,
+                request,
+                &ctx.client_ip,
+                CONNECT_TIMEOUT,
+                security.upstream_timeouts(),
+                build_request,
+            )
+            .await
+        }
+        Err(status) => Err(status),
+    };
+    let winner = match exchanged {
+        Ok(winner) => winner,
+        Err(status) => return (h2_emit_gateway_error(resp_tx, notify, status).await, server),
+    };
+    ctx.hedge.set(winner.outcome);
+
+    let target = &winner.server.target;
+    let addr = HostPortStr::new(&target.host, target.port);
+    let result = h2_proxy_http(
+        ctx,
+        addr.as_str(),
+        None,
+        target.socket.as_ref(),
+        Vec::new(),
+        compression,
+        client_encoding,
+        security,
+        &target.connection_pool,
+        resp_tx,
+        notify,
+        Some(winner.conn),
+    )
+    .await;
+    drop(winner.lease);
+    (result, winner.server)
+}
+
+/// H1 バックエンド（平文）へのプロキシ（プール再利用付き、B-28）。
+#[cfg(feature = "http2")]
+#[allow(clippy::too_many_arguments)]
+async fn h2_proxy_http(
+    _ctx: &H2RequestCtx,
+    addr: &str,
+    bind: Option<LocalBind>,
+    socket: Option<&Arc<SocketOptions>>,
+    request: Vec<u8>,
+    compression: &CompressionConfig,
+    client_encoding: AcceptedEncoding,
+    security: &SecurityConfig,
+    pool_cfg: &ConnectionPoolConfig,
+    resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
+    notify: &crate::stream_channel::Notify,
+    prepared: Option<(TcpStream, ConnLifecycle)>,
+) -> (u16, u64) {
+    // F-137: ヘッジ済み（`prepared` が Some）ならリクエスト送信済みで `request` は空
+    let (mut backend, lifecycle, request) = match prepared {
+        Some((stream, lifecycle)) => (stream, lifecycle, request),
+        None => match h2_open_http_backend_for_request(addr, bind, socket, pool_cfg, request).await
+        {
+            Ok(conn) => conn,
+            Err(status) => return h2_emit_gateway_error(resp_tx, notify, status).await,
+        },
+    };
+
+    let (write_res, returned_request) = backend.write_all(request).await;
+    request_buf_put(returned_request);
+    if write_res.is_err() {
+        return h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await;
+    }
+
+    let (status, sent, keep_alive) = h2_relay_backend_response(
+        &mut backend,
+        compression,
+        client_encoding,
+        security,
+        resp_tx,
+        notify,
+    )
+    .await;
+    // F-145: アイドルタイムアウトは上流の `Keep-Alive: timeout=` を超えない
+    let idle_timeout = keep_alive.idle_timeout_secs(security.idle_connection_timeout_secs);
+    if keep_alive.is_reusable() && idle_timeout > 0 {
+        HTTP_POOL.with(|p| {
+            p.borrow_mut().put(
+                bound_pool_key(addr, bind).into_owned(),
+                backend,
+                lifecycle.served(),
+                security.max_idle_connections_per_host,
+                idle_timeout,
+            )
+        });
+        // 返却した接続をゲート待機者に再利用させる（B-44 第3段）
+        notify_connect_gate_waiters(addr);
+    }
+    (status, sent)
+}
+
+/// HTTPS バックエンドへのプロキシ（TLS プール再利用付き、B-28）。
+#[cfg(feature = "http2")]
+#[allow(clippy::too_many_arguments)]
+async fn h2_proxy_https(
+    _ctx: &H2RequestCtx,
+    addr: &str,
+    bind: Option<LocalBind>,
+    socket: Option<&Arc<SocketOptions>>,
+    sni: &str,
+    request: Vec<u8>,
+    compression: &CompressionConfig,
+    client_encoding: AcceptedEncoding,
+    security: &SecurityConfig,
+    pool_cfg: &ConnectionPoolConfig,
+    tls_insecure: bool,
+    resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
+    notify: &crate::stream_channel::Notify,
+) -> (u16, u64) {
+    let mut pool_key = format!(
+        "{}:{}:{}",
+        addr,
+        sni,
+        if tls_insecure { "insecure" } else { "verify" }
+    );
+    if let Cow::Owned(key) = bound_pool_key(&pool_key, bind) {
+        pool_key = key;
+    }
+
+    let (mut backend, lifecycle) = match HTTPS_POOL.with(|p| p.borrow_mut().get(&pool_key)) {
+        Some(pooled) => pooled,
+        None => {
+            // プールミス: 新規 connect 並行数ゲート経由で取得（B-44 第3段）
+            let acquired = match acquire_backend_conn(addr, bind, socket, pool_cfg, || {
+                HTTPS_POOL.with(|p| p.borrow_mut().get(&pool_key))
+            })
+            .await
             {
                 Ok(acquired) => acquired,
                 Err(e) if e.kind() == io::ErrorKind::TimedOut => {
@@ -2165,7 +3111,10 @@ async fn h2_proxy_https(
                 }
             };
             match acquired {
-                GateAcquire::Pooled(stream) => stream,
+                GateAcquire::Pooled(pooled) => pooled,
+                GateAcquire::Rejected => {
+                    return h2_emit_error(resp_tx, notify, 503, b"Service Unavailable").await;
+                }
                 GateAcquire::Fresh(backend_tcp) => {
                     let tls_result = if tls_insecure {
                         let connector = get_tls_connector_insecure();
@@ -2175,7 +3124,7 @@ async fn h2_proxy_https(
                         timeout(CONNECT_TIMEOUT, connector.connect(backend_tcp, sni)).await
                     };
                     match tls_result {
-                        Ok(Ok(stream)) => stream,
+                        Ok(Ok(stream)) => (stream, ConnLifecycle::new(pool_cfg)),
                         Ok(Err(e)) => {
                             warn!("[HTTP/2] TLS handshake error: {}", e);
                             return h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await;
@@ -2195,16 +3144,25 @@ async fn h2_proxy_https(
         return h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await;
     }
 
-    let (status, sent, reusable) =
-        h2_relay_backend_response(&mut backend, compression, client_encoding, resp_tx, notify)
-            .await;
-    if reusable {
+    let (status, sent, keep_alive) = h2_relay_backend_response(
+        &mut backend,
+        compression,
+        client_encoding,
+        security,
+        resp_tx,
+        notify,
+    )
+    .await;
+    // F-145: アイドルタイムアウトは上流の `Keep-Alive: timeout=` を超えない
+    let idle_timeout = keep_alive.idle_timeout_secs(security.idle_connection_timeout_secs);
+    if keep_alive.is_reusable() && idle_timeout > 0 {
         HTTPS_POOL.with(|p| {
             p.borrow_mut().put(
                 pool_key,
                 backend,
+                lifecycle.served(),
                 security.max_idle_connections_per_host,
-                security.idle_connection_timeout_secs,
+                idle_timeout,
             )
         });
         // 返却した接続をゲート待機者に再利用させる（ゲートは addr 単位、B-44 第3段）
@@ -2223,21 +3181,22 @@ async fn h2_proxy_h2c(
     method: &[u8],
     path: &[u8],
     security: &SecurityConfig,
+    extra: &ExtraHeaders,
     #[cfg(feature = "wasm")] wasm_modules: &Arc<Vec<String>>,
     resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
     notify: &crate::stream_channel::Notify,
 ) -> (u16, u64) {
     let ctx = _ctx;
     let from_pool;
-    let mut h2c_client = match H2C_POOL.with(|p| p.borrow_mut().get(addr)) {
-        Some(client) => {
+    let (mut h2c_client, mut lifecycle) = match H2C_POOL.with(|p| p.borrow_mut().get(addr)) {
+        Some(pooled) => {
             from_pool = true;
-            client
+            pooled
         }
         None => {
             from_pool = false;
-            match h2c_connect_and_handshake(addr).await {
-                Ok(client) => client,
+            match h2c_connect_and_handshake(addr, target.socket.as_ref()).await {
+                Ok(client) => (client, ConnLifecycle::new(&target.connection_pool)),
                 Err(status) => {
                     let msg: &[u8] = if status == 504 {
                         b"Gateway Timeout"
@@ -2254,7 +3213,11 @@ async fn h2_proxy_h2c(
         .headers
         .iter()
         .any(|h| header_pair_is_grpc(&h.name, &h.value));
-    let headers_vec: Vec<(&[u8], &[u8])> = ctx
+    // F-139: gRPC 期限の残り時間を grpc-timeout として転送する
+    let grpc_timeout = security
+        .upstream_deadline
+        .and_then(|d| d.grpc_timeout_value());
+    let mut headers_vec: Vec<(&[u8], &[u8])> = ctx
         .headers
         .iter()
         .filter(|h| !h.name.starts_with(b":"))
@@ -2265,9 +3228,20 @@ async fn h2_proxy_h2c(
                 && !h.name.eq_ignore_ascii_case(b"transfer-encoding")
                 && !h.name.eq_ignore_ascii_case(b"upgrade")
                 && (is_grpc_upstream || !h.name.eq_ignore_ascii_case(b"te"))
+                && (grpc_timeout.is_none() || !h.name.eq_ignore_ascii_case(b"grpc-timeout"))
+                && !security.is_removed_request_header(&h.name, extra)
         })
         .map(|h| (h.name.as_slice(), h.value.as_slice()))
         .collect();
+    let extra_request = extra.request_header_pairs();
+    headers_vec.extend(
+        extra_request
+            .iter()
+            .map(|(n, v)| (n.as_slice(), v.as_slice())),
+    );
+    if let Some(value) = grpc_timeout.as_deref() {
+        headers_vec.push((b"grpc-timeout", value.as_bytes()));
+    }
 
     let body: Option<&[u8]> = if ctx.body.is_empty() {
         None
@@ -2276,15 +3250,31 @@ async fn h2_proxy_h2c(
     };
     let authority = target.host.as_bytes();
 
-    let mut send_result = h2c_client
-        .send_request(method, path, authority, &headers_vec, body)
-        .await;
+    // F-139: 応答の一括受信を交換の期限で打ち切る（打ち切った接続はプールへ戻さない）
+    let exchange_deadline = security
+        .upstream_timeouts()
+        .exchange_deadline(Instant::now());
+    let Ok(mut send_result) = timeout(
+        UpstreamTimeouts::until(exchange_deadline),
+        h2c_client.send_request(method, path, authority, &headers_vec, body),
+    )
+    .await
+    else {
+        return h2_emit_upstream_timeout(resp_tx, notify, security).await;
+    };
     if send_result.is_err() && from_pool {
-        if let Ok(fresh) = h2c_connect_and_handshake(addr).await {
+        if let Ok(fresh) = h2c_connect_and_handshake(addr, target.socket.as_ref()).await {
             h2c_client = fresh;
-            send_result = h2c_client
-                .send_request(method, path, authority, &headers_vec, body)
-                .await;
+            lifecycle = ConnLifecycle::new(&target.connection_pool);
+            send_result = match timeout(
+                UpstreamTimeouts::until(exchange_deadline),
+                h2c_client.send_request(method, path, authority, &headers_vec, body),
+            )
+            .await
+            {
+                Ok(result) => result,
+                Err(_) => return h2_emit_upstream_timeout(resp_tx, notify, security).await,
+            };
         }
     }
 
@@ -2294,80 +3284,27 @@ async fn h2_proxy_h2c(
                 let max_idle = security.max_idle_connections_per_host;
                 let idle_timeout = security.idle_connection_timeout_secs;
                 H2C_POOL.with(|p| {
-                    p.borrow_mut()
-                        .put(addr.to_string(), h2c_client, max_idle, idle_timeout)
+                    p.borrow_mut().put(
+                        addr.to_string(),
+                        h2c_client,
+                        lifecycle.served(),
+                        max_idle,
+                        idle_timeout,
+                    )
                 });
             }
 
-            let mut header_store: Vec<(Vec<u8>, Vec<u8>)> = h2c_resp
-                .headers
-                .iter()
-                .map(|(k, v)| (k.clone(), v.clone()))
-                .collect();
-            #[cfg(feature = "wasm")]
-            {
-                header_store =
-                    apply_h2_wasm_response_headers(wasm_modules, h2c_resp.status, header_store)
-                        .await;
-            }
-            for (n, v) in h2_base_headers(true) {
-                header_store.push((n, v));
-            }
-
-            let has_body = !h2c_resp.body.is_empty();
-            let has_trailers = !h2c_resp.trailers.is_empty();
-            let status = h2c_resp.status;
-            let body_len = h2c_resp.body.len() as u64;
-
-            if h2_send(
+            h2_emit_upstream_h2_response(
+                h2c_resp,
+                path,
+                target,
+                security,
+                #[cfg(feature = "wasm")]
+                wasm_modules,
                 resp_tx,
                 notify,
-                H2RespMsg::Head {
-                    status,
-                    headers: header_store,
-                    end_stream: !has_body && !has_trailers,
-                },
             )
             .await
-            .is_err()
-            {
-                return (status, 0);
-            }
-
-            if has_body
-                && h2_send(resp_tx, notify, H2RespMsg::Body(Bytes::from(h2c_resp.body)))
-                    .await
-                    .is_err()
-            {
-                return (status, body_len);
-            }
-
-            if has_trailers {
-                #[cfg(feature = "grpc")]
-                {
-                    let mut grpc_status = 0u32;
-                    for (name, value) in &h2c_resp.trailers {
-                        if name == b"grpc-status" {
-                            if let Ok(s) = std::str::from_utf8(value) {
-                                grpc_status = s.trim().parse().unwrap_or(0);
-                            }
-                        }
-                    }
-                    // F-09: gRPC リクエストメトリクスを記録。
-                    let grpc_method = std::str::from_utf8(path).unwrap_or("");
-                    let mut status_buf = itoa::Buffer::new();
-                    let status_str = status_buf.format(grpc_status);
-                    crate::metrics::record_grpc_request(grpc_method, status_str, &target.host);
-                    let _ = h2_send(resp_tx, notify, H2RespMsg::Trailers(h2c_resp.trailers)).await;
-                }
-                #[cfg(not(feature = "grpc"))]
-                {
-                    // gRPC feature 無効時はトレイラーをスキップ。
-                    let _ = &h2c_resp.trailers;
-                }
-            }
-
-            (status, body_len)
         }
         Err(e) => {
             warn!("[HTTP/2] H2C request error ({}): {}", addr, e);
@@ -2376,73 +3313,323 @@ async fn h2_proxy_h2c(
     }
 }
 
-/// バックエンド HTTP/1.1 レスポンスを受信して [`H2RespMsg`] としてメインループへ流す（F-116）。
-///
-/// 戻り値 `(status, sent, reusable)`。`reusable` はバックエンド接続をプールへ返せるか
-/// （CL 全量消費 + 非 `Connection: close`。chunked/EOF/エラーは false）。
+/// HTTP/2 上流（h2c / ALPN h2）の応答を [`H2RespMsg`] としてメインループへ流す（F-134 で共通化）
+// path / target は gRPC メトリクス記録（grpc feature）でのみ使う
 #[cfg(feature = "http2")]
-async fn h2_relay_backend_response<B>(
-    backend: &mut B,
-    compression: &CompressionConfig,
-    client_encoding: AcceptedEncoding,
+#[cfg_attr(not(feature = "grpc"), allow(unused_variables))]
+async fn h2_emit_upstream_h2_response(
+    h2c_resp: http2::H2cResponse,
+    path: &[u8],
+    target: &ProxyTarget,
+    security: &SecurityConfig,
+    #[cfg(feature = "wasm")] wasm_modules: &Arc<Vec<String>>,
     resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
     notify: &crate::stream_channel::Notify,
-) -> (u16, u64, bool)
-where
-    B: crate::runtime::io::AsyncReadRent + Unpin,
-{
-    let mut response_buf = Vec::with_capacity(BUF_SIZE);
+) -> (u16, u64) {
+    let mut header_store: Vec<(Vec<u8>, Vec<u8>)> = h2c_resp
+        .headers
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    #[cfg(feature = "wasm")]
+    {
+        header_store =
+            apply_h2_wasm_response_headers(wasm_modules, h2c_resp.status, header_store).await;
+    }
+    for (n, v) in h2_base_headers(true) {
+        header_store.push((n, v));
+    }
+    apply_h2_security_response_headers(&mut header_store, security);
 
-    loop {
-        let buf = buf_get();
-        let read_result = timeout(READ_TIMEOUT, backend.read(buf)).await;
-        let (res, mut returned_buf) = match read_result {
-            Ok(r) => r,
-            Err(_) => {
-                let (s, sz) = h2_emit_error(resp_tx, notify, 504, b"Gateway Timeout").await;
-                return (s, sz, false);
-            }
-        };
-        let n = match res {
-            Ok(0) => {
-                buf_put(returned_buf);
-                break;
-            }
-            Ok(n) => n,
-            Err(_) => {
-                buf_put(returned_buf);
-                let (s, sz) = h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await;
-                return (s, sz, false);
-            }
-        };
-        returned_buf.set_valid_len(n);
-        response_buf.extend_from_slice(returned_buf.as_valid_slice());
-        buf_put(returned_buf);
+    let has_body = !h2c_resp.body.is_empty();
+    let has_trailers = !h2c_resp.trailers.is_empty();
+    let status = h2c_resp.status;
+    let body_len = h2c_resp.body.len() as u64;
 
-        if let Some(parsed) = parse_http_response(&response_buf) {
-            let status = parsed.status_code;
-            let body_start = parsed.header_len;
-            let body = &response_buf[body_start..];
+    if h2_send(
+        resp_tx,
+        notify,
+        H2RespMsg::Head {
+            status,
+            headers: header_store,
+            end_stream: !has_body && !has_trailers,
+        },
+    )
+    .await
+    .is_err()
+    {
+        return (status, 0);
+    }
 
-            let mut headers_storage = [httparse::EMPTY_HEADER; 64];
-            let mut resp = httparse::Response::new(&mut headers_storage);
-            let _ = resp.parse(&response_buf);
-            let content_type = resp
-                .headers
-                .iter()
-                .find(|h| h.name.eq_ignore_ascii_case("content-type"))
-                .map(|h| h.value);
-            let existing_encoding = resp
-                .headers
-                .iter()
-                .find(|h| h.name.eq_ignore_ascii_case("content-encoding"))
-                .map(|h| h.value);
+    if has_body
+        && h2_send(resp_tx, notify, H2RespMsg::Body(Bytes::from(h2c_resp.body)))
+            .await
+            .is_err()
+    {
+        return (status, body_len);
+    }
 
-            let stream_compress_hint = compression.should_compress(
-                client_encoding,
-                content_type,
-                parsed.content_length,
-                existing_encoding,
+    if has_trailers {
+        #[cfg(feature = "grpc")]
+        {
+            let mut grpc_status = 0u32;
+            for (name, value) in &h2c_resp.trailers {
+                if name == b"grpc-status" {
+                    if let Ok(s) = std::str::from_utf8(value) {
+                        grpc_status = s.trim().parse().unwrap_or(0);
+                    }
+                }
+            }
+            // F-09: gRPC リクエストメトリクスを記録。
+            let grpc_method = std::str::from_utf8(path).unwrap_or("");
+            let mut status_buf = itoa::Buffer::new();
+            let status_str = status_buf.format(grpc_status);
+            crate::metrics::record_grpc_request(grpc_method, status_str, &target.host);
+            let _ = h2_send(resp_tx, notify, H2RespMsg::Trailers(h2c_resp.trailers)).await;
+        }
+        #[cfg(not(feature = "grpc"))]
+        {
+            // gRPC feature 無効時はトレイラーをスキップ。
+            let _ = &h2c_resp.trailers;
+        }
+    }
+    (status, body_len)
+}
+
+/// F-134: ALPN h2 の TLS バックエンドへ HTTP/2 クライアントのリクエストを多重化接続で中継する。
+///
+/// `auto` でサーバーが HTTP/1.1 を選んだ場合は None（呼び出し側が HTTP/1.1 経路で処理）。
+#[cfg(feature = "http2")]
+async fn h2_proxy_h2_tls(
+    ctx: &H2RequestCtx,
+    addr: &str,
+    target: &ProxyTarget,
+    method: &[u8],
+    path: &[u8],
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
+    tls_insecure: bool,
+    #[cfg(feature = "wasm")] wasm_modules: &Arc<Vec<String>>,
+    resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
+    notify: &crate::stream_channel::Notify,
+) -> Option<(u16, u64)> {
+    // h2_proxy_https と同じキー（auto で HTTP/1.1 になった接続をそのまま再利用させる）
+    let pool_key = format!(
+        "{}:{}:{}",
+        addr,
+        target.sni(),
+        if tls_insecure { "insecure" } else { "verify" }
+    );
+    let is_grpc_upstream = ctx
+        .headers
+        .iter()
+        .any(|h| header_pair_is_grpc(&h.name, &h.value));
+    let mut request = http2::H2MuxRequest {
+        method: method.to_vec(),
+        path: path.to_vec(),
+        authority: target.sni().as_bytes().to_vec(),
+        headers: ctx
+            .headers
+            .iter()
+            .filter(|h| !h.name.starts_with(b":"))
+            .filter(|h| is_grpc_upstream || !h.name.eq_ignore_ascii_case(b"te"))
+            .filter(|h| !security.is_removed_request_header(&h.name, extra))
+            .map(|h| (h.name.clone(), h.value.clone()))
+            .collect(),
+        body: ctx.body.to_vec(),
+        max_response_body: target.max_response_body_bytes,
+    };
+    request.headers.extend(extra.request_header_pairs());
+    apply_grpc_timeout(&mut request.headers, security);
+    let connect_timeout = Duration::from_secs(security.backend_connect_timeout_secs);
+    match h2_tls_upstream_request(
+        target,
+        tls_insecure,
+        connect_timeout,
+        security.upstream_timeouts(),
+        &pool_key,
+        request,
+    )
+    .await
+    {
+        H2UpstreamOutcome::Response(resp) => Some(
+            h2_emit_upstream_h2_response(
+                resp,
+                path,
+                target,
+                security,
+                #[cfg(feature = "wasm")]
+                wasm_modules,
+                resp_tx,
+                notify,
+            )
+            .await,
+        ),
+        H2UpstreamOutcome::Http1 => None,
+        H2UpstreamOutcome::Failed(504) => {
+            Some(h2_emit_upstream_timeout(resp_tx, notify, security).await)
+        }
+        H2UpstreamOutcome::Failed(status) => {
+            Some(h2_emit_error(resp_tx, notify, status, b"Bad Gateway").await)
+        }
+    }
+}
+
+/// F-135: HTTP/3 上流へ HTTP/2 クライアントのリクエストを中継する。
+///
+/// UDP 不通で TCP へフォールバックする場合は None（呼び出し側が HTTP/1.1 経路で処理）。
+#[cfg(all(feature = "http2", feature = "http3"))]
+async fn h2_proxy_h3(
+    ctx: &H2RequestCtx,
+    addr: &str,
+    target: &ProxyTarget,
+    method: &[u8],
+    path: &[u8],
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
+    tls_insecure: bool,
+    #[cfg(feature = "wasm")] wasm_modules: &Arc<Vec<String>>,
+    resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
+    notify: &crate::stream_channel::Notify,
+) -> Option<(u16, u64)> {
+    let pool_key = format!(
+        "{}:{}:{}",
+        addr,
+        target.sni(),
+        if tls_insecure { "insecure" } else { "verify" }
+    );
+    let is_grpc_upstream = ctx
+        .headers
+        .iter()
+        .any(|h| header_pair_is_grpc(&h.name, &h.value));
+    let mut request = crate::http3_client::H3Request {
+        method: method.to_vec(),
+        path: path.to_vec(),
+        authority: target.sni().as_bytes().to_vec(),
+        headers: ctx
+            .headers
+            .iter()
+            .filter(|h| is_grpc_upstream || !h.name.eq_ignore_ascii_case(b"te"))
+            .filter(|h| !security.is_removed_request_header(&h.name, extra))
+            .map(|h| (h.name.clone(), h.value.clone()))
+            .collect(),
+        body: ctx.body.to_vec(),
+        max_response_body: target.max_response_body_bytes,
+    };
+    request.headers.extend(extra.request_header_pairs());
+    apply_grpc_timeout(&mut request.headers, security);
+    let connect_timeout = Duration::from_secs(security.backend_connect_timeout_secs);
+    match h3_upstream_request(
+        target,
+        tls_insecure,
+        connect_timeout,
+        security.upstream_timeouts(),
+        &pool_key,
+        request,
+    )
+    .await
+    {
+        H3UpstreamOutcome::Response(resp) => Some(
+            h2_emit_upstream_h2_response(
+                http2::H2cResponse {
+                    status: resp.status_code,
+                    headers: resp.headers,
+                    body: resp.body,
+                    trailers: resp.trailers,
+                },
+                path,
+                target,
+                security,
+                #[cfg(feature = "wasm")]
+                wasm_modules,
+                resp_tx,
+                notify,
+            )
+            .await,
+        ),
+        H3UpstreamOutcome::Fallback => None,
+        H3UpstreamOutcome::Failed(504) => {
+            Some(h2_emit_upstream_timeout(resp_tx, notify, security).await)
+        }
+        H3UpstreamOutcome::Failed(status) => {
+            Some(h2_emit_error(resp_tx, notify, status, b"Bad Gateway").await)
+        }
+    }
+}
+
+/// バックエンド HTTP/1.1 レスポンスを受信して [`H2RespMsg`] としてメインループへ流す（F-116）。
+///
+/// 戻り値 `(status, sent, keep_alive)`。`keep_alive` はバックエンド接続をプールへ返せるか
+/// （CL 全量消費 + 非 `Connection: close`。chunked/EOF/エラーは `Close`）と、
+/// 上流の `Keep-Alive: timeout=` ヒント（F-145）。
+#[cfg(feature = "http2")]
+async fn h2_relay_backend_response<B>(
+    backend: &mut B,
+    compression: &CompressionConfig,
+    client_encoding: AcceptedEncoding,
+    security: &SecurityConfig,
+    resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
+    notify: &crate::stream_channel::Notify,
+) -> (u16, u64, BackendKeepAlive)
+where
+    B: crate::runtime::io::AsyncReadRent + Unpin,
+{
+    let mut response_buf = Vec::with_capacity(BUF_SIZE);
+    // F-139: 応答ヘッダー待ち・試行ごとの上限・リクエスト期限
+    let timeouts = security.upstream_timeouts();
+    let header_deadline = timeouts.exchange_deadline(Instant::now());
+
+    loop {
+        let buf = buf_get();
+        let read_result =
+            timeout(UpstreamTimeouts::until(header_deadline), backend.read(buf)).await;
+        let (res, mut returned_buf) = match read_result {
+            Ok(r) => r,
+            Err(_) => {
+                let (s, sz) = h2_emit_upstream_timeout(resp_tx, notify, security).await;
+                return (s, sz, BackendKeepAlive::Close);
+            }
+        };
+        let n = match res {
+            Ok(0) => {
+                buf_put(returned_buf);
+                break;
+            }
+            Ok(n) => n,
+            Err(_) => {
+                buf_put(returned_buf);
+                let (s, sz) = h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await;
+                return (s, sz, BackendKeepAlive::Close);
+            }
+        };
+        returned_buf.set_valid_len(n);
+        response_buf.extend_from_slice(returned_buf.as_valid_slice());
+        buf_put(returned_buf);
+
+        if let Some(parsed) = parse_http_response(&response_buf) {
+            let status = parsed.status_code;
+            let body_start = parsed.header_len;
+            let body = &response_buf[body_start..];
+
+            let mut headers_storage = [httparse::EMPTY_HEADER; 64];
+            let mut resp = httparse::Response::new(&mut headers_storage);
+            let _ = resp.parse(&response_buf);
+            let content_type = resp
+                .headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case("content-type"))
+                .map(|h| h.value);
+            let existing_encoding = resp
+                .headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case("content-encoding"))
+                .map(|h| h.value);
+
+            let stream_compress_hint = compression.should_compress(
+                client_encoding,
+                content_type,
+                parsed.content_length,
+                existing_encoding,
             );
 
             // 非圧縮 + CL 既知 + 非 chunked → 逐次ストリーミング。
@@ -2462,6 +3649,7 @@ where
                         }
                         headers.push((header.name.as_bytes().to_vec(), header.value.to_vec()));
                     }
+                    apply_h2_security_response_headers(&mut headers, security);
                     let (sent, ok) = h2_stream_body_cl(
                         resp_tx,
                         notify,
@@ -2470,10 +3658,15 @@ where
                         backend,
                         body,
                         content_len,
+                        timeouts,
                     )
                     .await;
-                    let reusable = ok && sent == content_len as u64 && !parsed.is_connection_close;
-                    return (status, sent, reusable);
+                    let keep_alive = if ok && sent == content_len as u64 {
+                        parsed.keep_alive()
+                    } else {
+                        BackendKeepAlive::Close
+                    };
+                    return (status, sent, keep_alive);
                 }
             }
 
@@ -2494,9 +3687,12 @@ where
                     }
                     headers.push((header.name.as_bytes().to_vec(), header.value.to_vec()));
                 }
-                let sent =
-                    h2_stream_body_chunked(resp_tx, notify, status, headers, backend, body).await;
-                return (status, sent, false);
+                apply_h2_security_response_headers(&mut headers, security);
+                let sent = h2_stream_body_chunked(
+                    resp_tx, notify, status, headers, backend, body, timeouts,
+                )
+                .await;
+                return (status, sent, BackendKeepAlive::Close);
             }
 
             // 圧縮あり / 長さ不明 → 全読み込み後に（必要なら圧縮して）送信。
@@ -2508,7 +3704,7 @@ where
                 while !decoder.is_complete() {
                     let buf = buf_get();
                     let (res, mut returned_buf) =
-                        match timeout(READ_TIMEOUT, backend.read(buf)).await {
+                        match timeout(timeouts.read(), backend.read(buf)).await {
                             Ok(r) => r,
                             Err(_) => break,
                         };
@@ -2534,7 +3730,7 @@ where
                 while full_body.len() < content_len {
                     let buf = buf_get();
                     let (res, mut returned_buf) =
-                        match timeout(READ_TIMEOUT, backend.read(buf)).await {
+                        match timeout(timeouts.read(), backend.read(buf)).await {
                             Ok(r) => r,
                             Err(_) => break,
                         };
@@ -2599,6 +3795,7 @@ where
                 }
                 headers.push((header.name.as_bytes().to_vec(), header.value.to_vec()));
             }
+            apply_h2_security_response_headers(&mut headers, security);
 
             let response_body = if let Some(enc) = should_compress {
                 compress_body_h2(&final_body, enc, compression)
@@ -2607,21 +3804,22 @@ where
             };
             let (status2, sent) =
                 h2_emit_full(resp_tx, notify, status, headers, response_body).await;
-            return (
-                status2,
-                sent,
-                backend_reusable && !parsed.is_connection_close,
-            );
+            let keep_alive = if backend_reusable {
+                parsed.keep_alive()
+            } else {
+                BackendKeepAlive::Close
+            };
+            return (status2, sent, keep_alive);
         }
 
         if response_buf.len() > MAX_HEADER_SIZE {
             let (s, sz) = h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await;
-            return (s, sz, false);
+            return (s, sz, BackendKeepAlive::Close);
         }
     }
 
     let (s, sz) = h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await;
-    (s, sz, false)
+    (s, sz, BackendKeepAlive::Close)
 }
 
 /// 非圧縮・CL 既知ボディを [`H2RespMsg::Body`] として逐次転送する。戻り値 `(sent, ok)`。
@@ -2635,6 +3833,7 @@ async fn h2_stream_body_cl<B>(
     backend: &mut B,
     initial_body: &[u8],
     content_length: usize,
+    timeouts: UpstreamTimeouts,
 ) -> (u64, bool)
 where
     B: crate::runtime::io::AsyncReadRent + Unpin,
@@ -2676,7 +3875,7 @@ where
 
     while remaining > 0 {
         let buf = buf_get();
-        let (res, mut returned_buf) = match timeout(READ_TIMEOUT, backend.read(buf)).await {
+        let (res, mut returned_buf) = match timeout(timeouts.read(), backend.read(buf)).await {
             Ok(r) => r,
             Err(_) => {
                 // タイムアウト: HEADERS 送出済みのため RST_STREAM で打ち切る（旧 send_rst_stream 相当）。
@@ -2721,6 +3920,7 @@ async fn h2_stream_body_chunked<B>(
     headers: Vec<(Vec<u8>, Vec<u8>)>,
     backend: &mut B,
     initial_body: &[u8],
+    timeouts: UpstreamTimeouts,
 ) -> u64
 where
     B: crate::runtime::io::AsyncReadRent + Unpin,
@@ -2750,7 +3950,7 @@ where
 
     loop {
         let buf = buf_get();
-        let (res, mut returned_buf) = match timeout(READ_TIMEOUT, backend.read(buf)).await {
+        let (res, mut returned_buf) = match timeout(timeouts.read(), backend.read(buf)).await {
             Ok(r) => r,
             Err(_) => {
                 let _ = h2_send(resp_tx, notify, H2RespMsg::Reset(2)).await;
@@ -3060,6 +4260,53 @@ async fn h2_serve_streaming(
         }
     };
 
+    // 認証・レートリミット・CORS・WAF・外部認可（ボディを必要とするルートは適格判定で
+    // バッファ経路へ回している）
+    let checked = crate::request_checks::check_request(
+        &security,
+        &crate::request_checks::RequestInfo {
+            client_ip,
+            method,
+            path,
+            host: authority,
+            headers: &headers_raw,
+        },
+        |_| std::future::ready(Some(Cow::Borrowed(&b""[..]))),
+    )
+    .await;
+    ctx.user
+        .replace(crate::jwt_auth::principal(checked.claims.as_ref()));
+    let extra_headers = match checked.outcome {
+        Ok(extra) => extra,
+        Err(rejection) => {
+            while req_rx.recv().await.is_some() {}
+            let (s, sz) = h2_emit_rejection(resp_tx, notify, &rejection).await;
+            return (s, sz, 0);
+        }
+    };
+
+    // F-139: リクエスト全体の期限（ストリーミング経路は gRPC を扱わない）
+    let security = match UpstreamDeadline::resolve(&security, ctx.start, false, None) {
+        Some(deadline) if deadline.is_expired() => {
+            while req_rx.recv().await.is_some() {}
+            let (s, sz) = h2_emit_error(resp_tx, notify, 504, b"Gateway Timeout").await;
+            return (s, sz, 0);
+        }
+        Some(deadline) => Arc::new(security.with_upstream_deadline(deadline)),
+        None => security,
+    };
+
+    // F-138: アップロード中継はクライアントの送信速度に左右されるため RTT は記録せず、
+    // 同時実行数の計上と上限超過時の拒否のみ行う（枠は関数終了時に返す）
+    let _concurrency_permit = match acquire_concurrency_permit(&upstream_group) {
+        Ok(permit) => permit,
+        Err(retry_after) => {
+            while req_rx.recv().await.is_some() {}
+            let (s, sz) = h2_emit_concurrency_limited(resp_tx, notify, retry_after).await;
+            return (s, sz, 0);
+        }
+    };
+
     let server = match upstream_group.select(client_ip) {
         Some(s) => s,
         None => {
@@ -3076,8 +4323,27 @@ async fn h2_serve_streaming(
         .map(|h| AcceptedEncoding::parse(&h.value))
         .unwrap_or(AcceptedEncoding::Identity);
 
+    // F-141: 送信元アドレスの指定があればこのリクエストの送信元を決める
+    let target = match server.target.bound_for(client_ip) {
+        Ok(target) => target,
+        Err(e) => {
+            warn!("[HTTP/2] Upstream source address error: {}", e);
+            while req_rx.recv().await.is_some() {}
+            let (s, sz) = h2_emit_error(resp_tx, notify, 502, b"Bad Gateway").await;
+            return (s, sz, 0);
+        }
+    };
+    let target = &*target;
+
+    if h2_send_extra_headers(resp_tx, notify, &extra_headers)
+        .await
+        .is_err()
+    {
+        while req_rx.recv().await.is_some() {}
+        return (499, 0, 0);
+    }
+
     server.acquire();
-    let target = &server.target;
     let use_tls = target.use_tls;
     let sni = target.sni().to_string();
     let tls_insecure = upstream_group.tls_insecure();
@@ -3114,6 +4380,7 @@ async fn h2_serve_streaming(
             || header.name.eq_ignore_ascii_case(b"keep-alive")
             || header.name.eq_ignore_ascii_case(b"transfer-encoding")
             || header.name.eq_ignore_ascii_case(b"content-length")
+            || security.is_removed_request_header(&header.name, &extra_headers)
         {
             continue;
         }
@@ -3122,10 +4389,24 @@ async fn h2_serve_streaming(
         request.extend_from_slice(&header.value);
         request.extend_from_slice(b"\r\n");
     }
+    for (name, value) in &extra_headers.request {
+        if !is_valid_header_name(name.as_bytes()) || !is_valid_header_value(value.as_bytes()) {
+            continue;
+        }
+        request.extend_from_slice(name.as_bytes());
+        request.extend_from_slice(b": ");
+        request.extend_from_slice(value.as_bytes());
+        request.extend_from_slice(b"\r\n");
+    }
     request.extend_from_slice(b"Transfer-Encoding: chunked\r\nConnection: keep-alive\r\n\r\n");
 
     // バックエンド接続。
-    let backend_tcp = match timeout(CONNECT_TIMEOUT, TcpStream::connect_str(addr)).await {
+    let backend_tcp = match timeout(
+        CONNECT_TIMEOUT,
+        TcpStream::connect_str_with(addr, target.bind, target.socket.clone()),
+    )
+    .await
+    {
         Ok(Ok(s)) => s,
         _ => {
             server.release();
@@ -3152,6 +4433,7 @@ async fn h2_serve_streaming(
                     req_rx,
                     &compression,
                     client_encoding,
+                    &security,
                     resp_tx,
                     notify,
                 )
@@ -3172,12 +4454,12 @@ async fn h2_serve_streaming(
             req_rx,
             &compression,
             client_encoding,
+            &security,
             resp_tx,
             notify,
         )
         .await
     };
-    let _ = &security;
     server.release();
     (status, resp_size, req_size)
 }
@@ -3191,6 +4473,7 @@ async fn h2_run_streaming_upload<B>(
     req_rx: &crate::stream_channel::Receiver<Bytes>,
     compression: &CompressionConfig,
     client_encoding: AcceptedEncoding,
+    security: &SecurityConfig,
     resp_tx: &crate::stream_channel::Sender<H2RespMsg>,
     notify: &crate::stream_channel::Notify,
 ) -> (u16, u64, u64)
@@ -3227,8 +4510,15 @@ where
         return (s, sz, req_size);
     }
 
-    let (status, sent, _reusable) =
-        h2_relay_backend_response(backend, compression, client_encoding, resp_tx, notify).await;
+    let (status, sent, _keep_alive) = h2_relay_backend_response(
+        backend,
+        compression,
+        client_encoding,
+        security,
+        resp_tx,
+        notify,
+    )
+    .await;
     (status, sent, req_size)
 }
 
@@ -3277,11 +4567,16 @@ fn h2_admin_response(
     }
 
     let path_suffix = &path_str[admin_config.path_prefix.len()..];
+    let bans_query = admin_bans_query(method, path_suffix);
     let is_known_endpoint = matches!(
         (method, path_suffix),
-        (b"GET", "/config") | (b"GET", "/stats") | (b"POST", "/reload") | (b"POST", "/tls/reload")
+        (b"GET", "/config")
+            | (b"GET", "/stats")
+            | (b"GET", "/health")
+            | (b"POST", "/reload")
+            | (b"POST", "/tls/reload")
     );
-    if !is_known_endpoint {
+    if !is_known_endpoint && bans_query.is_none() {
         return None;
     }
 
@@ -3289,6 +4584,9 @@ fn h2_admin_response(
         (403, b"{\"error\":\"403\"}".to_vec())
     } else if !admin_config.check_auth(auth) {
         (401, b"{\"error\":\"401\"}".to_vec())
+    } else if let Some(query) = bans_query {
+        let (status, json) = handle_admin_bans(method, query);
+        (status, json.into_bytes())
     } else {
         match (method, path_suffix) {
             (b"GET", "/config") => {
@@ -3296,8 +4594,11 @@ fn h2_admin_response(
                 (200, json.into_bytes())
             }
             (b"GET", "/stats") => {
-                let uptime_secs = PROXY_START_TIME.elapsed().as_secs();
-                let json = format!("{{\"uptime_secs\":{}}}", uptime_secs);
+                let json = build_admin_stats_json(&config);
+                (200, json.into_bytes())
+            }
+            (b"GET", "/health") => {
+                let json = build_admin_health_json(&config);
                 (200, json.into_bytes())
             }
             (b"POST", "/reload") => {
@@ -3519,8 +4820,10 @@ fn build_h2_compressed_file_response(
 #[cfg(feature = "http2")]
 async fn h2c_connect_and_handshake(
     addr: &str,
+    socket: Option<&Arc<SocketOptions>>,
 ) -> Result<http2::H2cClient<crate::runtime::tcp::TcpStream>, u16> {
-    let connect_result = timeout(CONNECT_TIMEOUT, TcpStream::connect_str(addr)).await;
+    let connect = TcpStream::connect_str_with(addr, None, socket.cloned());
+    let connect_result = timeout(CONNECT_TIMEOUT, connect).await;
     let backend_stream = match connect_result {
         Ok(Ok(stream)) => {
             let _ = stream.set_nodelay(true);
@@ -3544,34 +4847,306 @@ async fn h2c_connect_and_handshake(
     Ok(client)
 }
 
-/// HTTP/2 用レスポンスボディ圧縮ヘルパー関数
-///
-/// バイト配列を受け取り、指定されたエンコーディングで圧縮して返します。
-/// 圧縮に失敗した場合は元のデータをそのまま返します。
-#[cfg(all(feature = "http2", feature = "compression"))]
-fn compress_body_h2(
-    body: &[u8],
-    encoding: AcceptedEncoding,
-    compression: &CompressionConfig,
-) -> Vec<u8> {
-    use flate2::write::GzEncoder;
-    use flate2::Compression;
-    use std::io::Write;
+/// F-134: ALPN h2 上流へのリクエスト結果
+#[cfg(feature = "http2")]
+pub(crate) enum H2UpstreamOutcome {
+    /// h2 で応答を受信した
+    Response(http2::H2cResponse),
+    /// `protocol = "auto"` でサーバーが h2 を選ばなかった（HTTP/1.1 経路で処理する）。
+    /// ネゴシエート済みの TLS 接続は HTTPS プールへ返却済み
+    Http1,
+    /// 失敗（送出すべきステータス: 502=接続/ハンドシェイク/ストリーム失敗, 504=タイムアウト）
+    Failed(u16),
+}
 
-    match encoding {
-        AcceptedEncoding::Zstd => {
-            match zstd::encode_all(std::io::Cursor::new(body), compression.zstd_level) {
-                Ok(compressed) => compressed,
-                Err(_) => body.to_vec(),
-            }
+/// F-134: TLS バックエンドへ ALPN h2 で新規接続し、多重化ドライバを起動してプールへ登録する。
+///
+/// `auto` でサーバーが http/1.1 を選んだ場合は接続を HTTPS プールへ返し、一定時間
+/// h2 を試さないよう記録して `Ok(None)` を返す。
+#[cfg(feature = "http2")]
+async fn h2_tls_connect(
+    target: &ProxyTarget,
+    tls_insecure: bool,
+    connect_timeout: Duration,
+    pool_key: &str,
+) -> Result<Option<http2::H2MuxHandle>, u16> {
+    let addr = HostPortStr::new(&target.host, target.port);
+    let addr = addr.as_str();
+    let connect = TcpStream::connect_str_with(addr, None, target.socket.clone());
+    let backend_tcp = match timeout(connect_timeout, connect).await {
+        Ok(Ok(stream)) => {
+            let _ = stream.set_nodelay(true);
+            stream
         }
-        AcceptedEncoding::Gzip => {
-            let level = Compression::new(compression.gzip_level);
-            let mut encoder = GzEncoder::new(Vec::with_capacity(body.len()), level);
-            if encoder.write_all(body).is_err() {
-                return body.to_vec();
-            }
-            encoder.finish().unwrap_or_else(|_| body.to_vec())
+        Ok(Err(e)) => {
+            warn!("[h2 upstream] connect error ({}): {}", addr, e);
+            return Err(502);
+        }
+        Err(_) => {
+            warn!("[h2 upstream] connect timeout ({})", addr);
+            return Err(504);
+        }
+    };
+    let allow_http11 = target.protocol == UpstreamProtocol::Auto;
+    let sni = target.sni();
+    let connector = get_tls_connector_h2(tls_insecure, allow_http11);
+    let tls = match timeout(connect_timeout, connector.connect(backend_tcp, sni)).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(e)) => {
+            warn!("[h2 upstream] TLS error ({} SNI: {}): {}", addr, sni, e);
+            return Err(502);
+        }
+        Err(_) => {
+            warn!("[h2 upstream] TLS timeout ({} SNI: {})", addr, sni);
+            return Err(504);
+        }
+    };
+    if tls.alpn_protocol() != Some(b"h2".as_slice()) {
+        if allow_http11 {
+            H2_MUX_POOL.with(|p| p.borrow_mut().mark_http1_only(pool_key));
+            HTTPS_POOL.with(|p| {
+                p.borrow_mut().put(
+                    pool_key.to_string(),
+                    tls,
+                    ConnLifecycle::new(&target.connection_pool),
+                    BACKEND_POOL_MAX_IDLE_PER_HOST,
+                    BACKEND_POOL_IDLE_TIMEOUT_SECS,
+                )
+            });
+            return Ok(None);
+        }
+        warn!(
+            "[h2 upstream] {} did not negotiate h2 via ALPN (protocol = \"h2\")",
+            addr
+        );
+        return Err(502);
+    }
+    let mut client =
+        http2::H2cClient::new(tls, http2::Http2Settings::default()).with_scheme(b"https");
+    match timeout(connect_timeout, client.handshake()).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            warn!("[h2 upstream] handshake error ({}): {}", addr, e);
+            return Err(502);
+        }
+        Err(_) => {
+            warn!("[h2 upstream] handshake timeout ({})", addr);
+            return Err(504);
+        }
+    }
+    let handle = http2::mux::spawn(
+        client,
+        target.h2_max_concurrent_streams,
+        Duration::from_secs(BACKEND_POOL_IDLE_TIMEOUT_SECS),
+    );
+    H2_MUX_POOL.with(|p| {
+        p.borrow_mut().put(
+            pool_key.to_string(),
+            handle.clone(),
+            BACKEND_POOL_MAX_IDLE_PER_HOST,
+        )
+    });
+    Ok(Some(handle))
+}
+
+/// F-134: ALPN h2 の TLS バックエンドへ多重化接続でリクエストを送る。
+///
+/// プール内で空きストリームのある接続を共有し、無ければ新規接続する。GOAWAY / REFUSED_STREAM
+/// で処理されなかったリクエストは別接続で一度だけ再試行する（サーバーは未処理を保証済み）。
+/// 応答待ちは試行ごとに `timeouts` の交換の期限で打ち切る（F-139）。
+#[cfg(feature = "http2")]
+pub(crate) async fn h2_tls_upstream_request(
+    target: &ProxyTarget,
+    tls_insecure: bool,
+    connect_timeout: Duration,
+    timeouts: UpstreamTimeouts,
+    pool_key: &str,
+    request: http2::H2MuxRequest,
+) -> H2UpstreamOutcome {
+    if target.protocol == UpstreamProtocol::Auto
+        && H2_MUX_POOL.with(|p| p.borrow_mut().is_http1_only(pool_key))
+    {
+        return H2UpstreamOutcome::Http1;
+    }
+    let mut attempt = 0u32;
+    loop {
+        attempt += 1;
+        let handle = match H2_MUX_POOL.with(|p| p.borrow_mut().get(pool_key)) {
+            Some(handle) => handle,
+            None => match h2_tls_connect(target, tls_insecure, connect_timeout, pool_key).await {
+                Ok(Some(handle)) => handle,
+                Ok(None) => return H2UpstreamOutcome::Http1,
+                Err(status) => return H2UpstreamOutcome::Failed(status),
+            },
+        };
+        let deadline = timeouts.exchange_deadline(Instant::now());
+        match timeout(
+            UpstreamTimeouts::until(deadline),
+            handle.send_request(request.clone()),
+        )
+        .await
+        {
+            Ok(Ok(response)) => return H2UpstreamOutcome::Response(response),
+            Ok(Err(e)) if e.is_retryable() && attempt < 2 => {
+                debug!(
+                    "[h2 upstream] stream refused, retrying on another connection: {}",
+                    e
+                );
+            }
+            Ok(Err(e)) => {
+                warn!(
+                    "[h2 upstream] request error ({}:{}): {}",
+                    target.host, target.port, e
+                );
+                return H2UpstreamOutcome::Failed(502);
+            }
+            Err(_) => {
+                warn!(
+                    "[h2 upstream] response timeout ({}:{})",
+                    target.host, target.port
+                );
+                return H2UpstreamOutcome::Failed(504);
+            }
+        }
+    }
+}
+
+/// F-135: HTTP/3 上流へのリクエスト結果
+#[cfg(feature = "http3")]
+pub(crate) enum H3UpstreamOutcome {
+    /// h3 で応答を受信した
+    Response(crate::http3_server::BackendProxyResult),
+    /// QUIC ハンドシェイクが成立しなかった（UDP 不通）。TCP（HTTP/1.1）経路で処理する
+    Fallback,
+    /// 失敗（送出すべきステータス: 502=ストリーム失敗, 504=タイムアウト）
+    Failed(u16),
+}
+
+/// F-135: HTTP/3 上流へリクエストを送る。
+///
+/// プール内で空きストリームのある QUIC 接続を共有し、無ければ保存済みのセッション
+/// チケットで 0-RTT 再開を試みつつ新規接続する。ハンドシェイクが成立しないホストは
+/// 一定時間 UDP 不通として記録し、その間は HTTP/3 を試さず [`H3UpstreamOutcome::Fallback`]
+/// を返す。GOAWAY で処理されなかったリクエストは別接続で一度だけ再試行する。
+/// 応答待ちは試行ごとに `timeouts` の交換の期限で打ち切る（F-139）。
+#[cfg(feature = "http3")]
+pub(crate) async fn h3_upstream_request(
+    target: &ProxyTarget,
+    tls_insecure: bool,
+    connect_timeout: Duration,
+    timeouts: UpstreamTimeouts,
+    pool_key: &str,
+    request: crate::http3_client::H3Request,
+) -> H3UpstreamOutcome {
+    use std::net::ToSocketAddrs;
+
+    let upstream_label = HostPortStr::new(&target.host, target.port);
+    let upstream_label = upstream_label.as_str();
+    if H3_POOL.with(|p| p.borrow_mut().is_udp_blocked(pool_key)) {
+        return H3UpstreamOutcome::Fallback;
+    }
+    let mut attempt = 0u32;
+    loop {
+        attempt += 1;
+        let handle = match H3_POOL.with(|p| p.borrow_mut().get(pool_key)) {
+            Some(handle) => handle,
+            None => {
+                let peer = match upstream_label.to_socket_addrs().map(|mut a| a.next()) {
+                    Ok(Some(peer)) => peer,
+                    _ => {
+                        warn!("[h3 upstream] cannot resolve {}", upstream_label);
+                        return H3UpstreamOutcome::Failed(502);
+                    }
+                };
+                let session = H3_POOL.with(|p| p.borrow_mut().session_ticket(pool_key));
+                match crate::http3_client::connect(
+                    peer,
+                    target.sni(),
+                    !tls_insecure,
+                    session.as_deref(),
+                    target.h2_max_concurrent_streams,
+                    connect_timeout,
+                    Duration::from_secs(BACKEND_POOL_IDLE_TIMEOUT_SECS),
+                )
+                .await
+                {
+                    Ok(handle) => {
+                        record_upstream_h3_handshake(upstream_label, handle.is_resumed());
+                        H3_POOL.with(|p| {
+                            p.borrow_mut().put(
+                                pool_key.to_string(),
+                                handle.clone(),
+                                BACKEND_POOL_MAX_IDLE_PER_HOST,
+                            )
+                        });
+                        handle
+                    }
+                    Err(e) => {
+                        warn!(
+                            "[h3 upstream] {} unreachable over QUIC, falling back to TCP: {}",
+                            upstream_label, e
+                        );
+                        H3_POOL.with(|p| p.borrow_mut().mark_udp_blocked(pool_key));
+                        record_upstream_h3_fallback(upstream_label);
+                        return H3UpstreamOutcome::Fallback;
+                    }
+                }
+            }
+        };
+        let deadline = timeouts.exchange_deadline(Instant::now());
+        match timeout(
+            UpstreamTimeouts::until(deadline),
+            handle.send_request(request.clone()),
+        )
+        .await
+        {
+            Ok(Ok(response)) => return H3UpstreamOutcome::Response(response),
+            Ok(Err(e)) if e.is_retryable() && attempt < 2 => {
+                debug!(
+                    "[h3 upstream] request refused, retrying on another connection: {}",
+                    e
+                );
+            }
+            Ok(Err(e)) => {
+                warn!("[h3 upstream] request error ({}): {}", upstream_label, e);
+                return H3UpstreamOutcome::Failed(502);
+            }
+            Err(_) => {
+                warn!("[h3 upstream] response timeout ({})", upstream_label);
+                return H3UpstreamOutcome::Failed(504);
+            }
+        }
+    }
+}
+
+/// HTTP/2 用レスポンスボディ圧縮ヘルパー関数
+///
+/// バイト配列を受け取り、指定されたエンコーディングで圧縮して返します。
+/// 圧縮に失敗した場合は元のデータをそのまま返します。
+#[cfg(all(feature = "http2", feature = "compression"))]
+fn compress_body_h2(
+    body: &[u8],
+    encoding: AcceptedEncoding,
+    compression: &CompressionConfig,
+) -> Vec<u8> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    match encoding {
+        AcceptedEncoding::Zstd => {
+            match zstd::encode_all(std::io::Cursor::new(body), compression.zstd_level) {
+                Ok(compressed) => compressed,
+                Err(_) => body.to_vec(),
+            }
+        }
+        AcceptedEncoding::Gzip => {
+            let level = Compression::new(compression.gzip_level);
+            let mut encoder = GzEncoder::new(Vec::with_capacity(body.len()), level);
+            if encoder.write_all(body).is_err() {
+                return body.to_vec();
+            }
+            encoder.finish().unwrap_or_else(|_| body.to_vec())
         }
         AcceptedEncoding::Brotli => {
             let mut compressed = Vec::with_capacity(body.len());
@@ -3718,6 +5293,41 @@ impl<S: crate::runtime::io::AsyncWriteRent + Unpin> crate::runtime::io::AsyncWri
     }
 }
 
+/// ソケットバッファを消費せずに覗き見る（MSG_PEEK）。
+///
+/// データ未着なら `WouldBlock`、接続終了なら `Ok(0)`。Windows には `MSG_DONTWAIT` が
+/// 無いが、ソケット自体が非ブロッキングのため `MSG_PEEK` 単独で同等に動作する。
+fn peek_socket(stream: &TcpStream, buf: &mut [u8]) -> io::Result<usize> {
+    let fd = stream.as_raw_fd();
+    #[cfg(unix)]
+    let ret = unsafe {
+        libc::recv(
+            fd,
+            buf.as_mut_ptr() as *mut libc::c_void,
+            buf.len(),
+            libc::MSG_PEEK | libc::MSG_DONTWAIT,
+        )
+    };
+    #[cfg(windows)]
+    let ret = unsafe {
+        windows_sys::Win32::Networking::WinSock::recv(
+            crate::runtime::handle::win::to_socket(fd),
+            buf.as_mut_ptr(),
+            buf.len() as i32,
+            windows_sys::Win32::Networking::WinSock::MSG_PEEK,
+        )
+    };
+    if ret < 0 {
+        #[cfg(unix)]
+        return Err(io::Error::last_os_error());
+        #[cfg(windows)]
+        return Err(io::Error::from_raw_os_error(unsafe {
+            windows_sys::Win32::Networking::WinSock::WSAGetLastError()
+        }));
+    }
+    Ok(ret as usize)
+}
+
 /// プロトコル検出とバッファ管理
 ///
 /// 最初の数バイトを読み込んでプロトコルを判別します。
@@ -3739,7 +5349,6 @@ pub async fn detect_protocol_with_buffer(stream: &mut TcpStream) -> (ProtocolTyp
     // `received corrupt message / InvalidContentType` で失敗する不具合があった
     // （h2c 検出が有効な全接続で発生しうる）。MSG_PEEK ならバイトはソケットに残るため、
     // 判別後に TLS/H2C/HTTP1.1 各ハンドラがそのまま読み直せる（initial_data は常に空）。
-    let fd = stream.as_raw_fd();
     let start_time = std::time::Instant::now();
     let timeout_duration = Duration::from_millis(200);
     let mut peeked = [0u8; 24];
@@ -3758,45 +5367,13 @@ pub async fn detect_protocol_with_buffer(stream: &mut TcpStream) -> (ProtocolTyp
         }
 
         // 消費せずに覗き見る（毎回ソケットバッファの先頭から最大 24 バイト）。
-        // Windows には `MSG_DONTWAIT` が無いが、ソケット自体が非ブロッキングのため
-        // `MSG_PEEK` 単独で同等に動作する。
-        #[cfg(unix)]
-        let ret = unsafe {
-            libc::recv(
-                fd,
-                peeked.as_mut_ptr() as *mut libc::c_void,
-                peeked.len(),
-                libc::MSG_PEEK | libc::MSG_DONTWAIT,
-            )
-        };
-        #[cfg(windows)]
-        let ret = unsafe {
-            windows_sys::Win32::Networking::WinSock::recv(
-                crate::runtime::handle::win::to_socket(fd),
-                peeked.as_mut_ptr(),
-                peeked.len() as i32,
-                windows_sys::Win32::Networking::WinSock::MSG_PEEK,
-            )
-        };
-        #[cfg(windows)]
-        let last_err = || {
-            io::Error::from_raw_os_error(unsafe {
-                windows_sys::Win32::Networking::WinSock::WSAGetLastError()
-            })
-        };
-        #[cfg(unix)]
-        let last_err = io::Error::last_os_error;
-        if ret < 0 {
+        n = match peek_socket(stream, &mut peeked) {
+            Ok(0) => break, // 接続終了
+            Ok(ret) => ret,
             // 偽の readable 通知（EAGAIN）なら再試行、その他はフォールバック。
-            if last_err().kind() == io::ErrorKind::WouldBlock {
-                continue;
-            }
-            break;
-        }
-        if ret == 0 {
-            break; // 接続終了
-        }
-        n = ret as usize;
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+            Err(_) => break,
+        };
         // TLS/HTTP1.1 は 5 バイト、H2C プリフェースは 24 バイトで判別可能。
         if n >= 24 || n >= 5 {
             break;
@@ -3853,6 +5430,13 @@ pub async fn handle_h2c_connection(stream: TcpStream, client_ip: &str, initial_d
     // 既に読み込んだデータ（プリフェース）を初期バッファとして渡す
     // これにより、不要な再読み込みを回避できる
     let mut conn = Http2Connection::new_with_initial_buffer(stream, settings, initial_data);
+    // F-153: 接続元 IP・プレフィックスごとの同時ストリーム数
+    conn.set_client_stream_budget(
+        client_ip
+            .parse()
+            .ok()
+            .and_then(crate::client_limits::stream_budget),
+    );
 
     // ハンドシェイク（プリフェース確認 + SETTINGS 交換）
     // expect_preface()は初期バッファからプリフェースを読み取る
@@ -3871,6 +5455,9 @@ pub async fn handle_h2c_connection(stream: TcpStream, client_ip: &str, initial_d
 
     if let Err(e) = result {
         warn!("[H2C] Connection error: {}", e);
+        if e.is_flood() {
+            crate::auto_ban::report_str(client_ip, crate::auto_ban::Signal::H2Flood);
+        }
     }
 
     debug!("[H2C] Connection closed from {}", client_ip);
@@ -4382,6 +5969,8 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                                 start_instant,
                                 client_ip,
                                 "",
+                                "",
+                                "",
                             );
                             accumulated.clear();
                             return;
@@ -4404,6 +5993,8 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                                 start_instant,
                                 client_ip,
                                 "",
+                                "",
+                                "",
                             );
                         }
 
@@ -4469,13 +6060,16 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                             start_instant,
                             client_ip,
                             "",
+                            "",
+                            "",
                         );
                         accumulated.clear();
                         return;
                     }
                 }
 
-                // 管理 API エンドポイントの処理（F-21: /config, /stats, /reload, /tls/reload）
+                // 管理 API エンドポイントの処理（F-21: /config, /stats, /reload, /tls/reload、F-143: /health、
+                // F-152: /bans）
                 #[cfg(feature = "admin")]
                 {
                     let config = CURRENT_CONFIG.load();
@@ -4486,18 +6080,21 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                         && method_bytes.as_ref() != b"PURGE"
                         && !path_str.starts_with(&admin_config.cache_purge_prefix)
                     {
-                        // GET /__admin/config, GET /__admin/stats,
+                        // GET /__admin/config, GET /__admin/stats, GET /__admin/health,
                         // POST /__admin/reload, POST /__admin/tls/reload のみを処理
                         let path_suffix = &path_str[admin_config.path_prefix.len()..];
+                        // F-152: GET / POST / DELETE /__admin/bans
+                        let bans_query = admin_bans_query(method_bytes.as_ref(), path_suffix);
                         let is_known_endpoint = matches!(
                             (method_bytes.as_ref(), path_suffix),
                             (b"GET", "/config")
                                 | (b"GET", "/stats")
+                                | (b"GET", "/health")
                                 | (b"POST", "/reload")
                                 | (b"POST", "/tls/reload")
                         );
 
-                        if is_known_endpoint {
+                        if is_known_endpoint || bans_query.is_some() {
                             let start_instant = Instant::now();
 
                             // IP制限チェック
@@ -4515,6 +6112,19 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
 
                                 if !admin_config.check_auth(auth) {
                                     b"HTTP/1.1 401 Unauthorized\r\nContent-Type: application/json\r\nContent-Length: 15\r\nConnection: close\r\n\r\n{\"error\":\"401\"}".to_vec()
+                                } else if let Some(query) = bans_query {
+                                    // 遮断の一覧・追加・解除（F-152）
+                                    let (status, body) =
+                                        handle_admin_bans(method_bytes.as_ref(), query);
+                                    let reason = if status == 200 { "OK" } else { "Bad Request" };
+                                    let mut resp = format!(
+                                        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                                        status,
+                                        reason,
+                                        body.len()
+                                    ).into_bytes();
+                                    resp.extend_from_slice(body.as_bytes());
+                                    resp
                                 } else {
                                     // 認証成功: エンドポイントに応じた処理
                                     match (method_bytes.as_ref(), path_suffix) {
@@ -4529,10 +6139,19 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                                             resp
                                         }
                                         (b"GET", "/stats") => {
-                                            // 起動からの経過時間を返す
+                                            // 起動からの経過時間と同時実行数制限（F-138）を返す
                                             // PROXY_START_TIME を初回アクセスで初期化
-                                            let uptime_secs = PROXY_START_TIME.elapsed().as_secs();
-                                            let body = format!("{{\"uptime_secs\":{}}}", uptime_secs);
+                                            let body = build_admin_stats_json(&config);
+                                            let mut resp = format!(
+                                                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                                                body.len()
+                                            ).into_bytes();
+                                            resp.extend_from_slice(body.as_bytes());
+                                            resp
+                                        }
+                                        (b"GET", "/health") => {
+                                            // サーバーごとの状態と遷移履歴（F-143）を返す
+                                            let body = build_admin_health_json(&config);
                                             let mut resp = format!(
                                                 "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
                                                 body.len()
@@ -4581,6 +6200,8 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                                 start_instant,
                                 client_ip,
                                 "",
+                                "",
+                                "",
                             );
                             accumulated.clear();
                             return;
@@ -4637,6 +6258,8 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                 let (prefix, backend, _route_compression) = match backend_result {
                     Some(b) => b,
                     None => {
+                        // F-152: アクセスログを経ない 404 も自動遮断の兆候として数える
+                        crate::auto_ban::observe_status(client_ip, 404);
                         let err_buf = ERR_MSG_NOT_FOUND.to_vec();
                         let _ = timeout(WRITE_TIMEOUT, tls_stream.write_all(err_buf)).await;
                         return;
@@ -4649,11 +6272,25 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                 // IP制限チェック（deny → allow の順で評価）
                 let ip_filter = security.ip_filter();
                 if ip_filter.is_configured() && !ip_filter.is_allowed(client_ip) {
+                    crate::auto_ban::observe_status(client_ip, 403);
                     let err_buf = ERR_MSG_FORBIDDEN.to_vec();
                     let _ = timeout(WRITE_TIMEOUT, tls_stream.write_all(err_buf)).await;
                     return;
                 }
 
+                // F-155: CORS のプリフライトはメソッド制限・認証より前に上流へ送らずに応答する
+                if let Some(cors) = security.cors.as_ref() {
+                    let headers_raw: Vec<(&[u8], &[u8])> = headers_for_proxy
+                        .iter()
+                        .map(|(n, v)| (n.as_ref(), v.as_ref()))
+                        .collect();
+                    if let Some(preflight) = cors.preflight(&method_bytes, &headers_raw) {
+                        let err_buf = preflight.http1_response();
+                        let _ = timeout(WRITE_TIMEOUT, tls_stream.write_all(err_buf)).await;
+                        return;
+                    }
+                }
+
                 // 許可メソッドチェック
                 if !security.allowed_methods.is_empty() {
                     let method_str = std::str::from_utf8(&method_bytes).unwrap_or("GET");
@@ -4675,14 +6312,55 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                     return;
                 }
 
-                // レートリミットチェック
-                if security.rate_limit_requests_per_min > 0
-                    && !check_rate_limit(client_ip, security.rate_limit_requests_per_min)
-                {
-                    let err_buf = ERR_MSG_TOO_MANY_REQUESTS.to_vec();
-                    let _ = timeout(WRITE_TIMEOUT, tls_stream.write_all(err_buf)).await;
-                    return;
-                }
+                // 認証・レートリミット・CORS・WAF・外部認可。WAF・外部認可が必要とするボディは
+                // 上限まで受信し、受信したバイトはヘッダー後のデータとしてそのまま上流へ転送する
+                let checked = {
+                    let headers_raw: Vec<(&[u8], &[u8])> = headers_for_proxy
+                        .iter()
+                        .map(|(n, v)| (n.as_ref(), v.as_ref()))
+                        .collect();
+                    let stream = &mut tls_stream;
+                    let buffered = &mut accumulated;
+                    crate::request_checks::check_request(
+                        security,
+                        &crate::request_checks::RequestInfo {
+                            client_ip,
+                            method: &method_bytes,
+                            path: &path_bytes,
+                            host: &host_bytes,
+                            headers: &headers_raw,
+                        },
+                        |limit| async move {
+                            if !is_chunked && content_length == 0 {
+                                return Some(Cow::Borrowed(&b""[..]));
+                            }
+                            read_inspected_body(
+                                stream,
+                                buffered,
+                                header_len,
+                                content_length,
+                                is_chunked,
+                                limit,
+                            )
+                            .await
+                            .map(Cow::Owned)
+                        },
+                    )
+                    .await
+                };
+                let jwt_claims = checked.claims;
+                let extra_headers = match checked.outcome {
+                    Ok(extra) => extra,
+                    Err(rejection) => {
+                        if let Some(err_buf) = rejection.http1_response() {
+                            let _ = timeout(WRITE_TIMEOUT, tls_stream.write_all(err_buf)).await;
+                        }
+                        return;
+                    }
+                };
+
+                // F-151: アクセスログに記録する認証済みの利用者（`sub` クレーム）
+                let auth_user = crate::jwt_auth::principal(jwt_claims.as_ref());
 
                 // 初期ボディ（ヘッダー後のデータ）
                 let initial_body: Vec<u8> = if header_len < accumulated.len() {
@@ -4725,6 +6403,7 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                                     Arc::from(method_str),
                                     headers_vec,
                                     Arc::from(client_ip),
+                                    jwt_claims.clone().map(Arc::new),
                                     initial_body.is_empty() && !is_chunked, // end_of_stream
                                 )
                                 .await;
@@ -4795,6 +6474,8 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                                             start_instant,
                                             client_ip,
                                             "",
+                                            "",
+                                            &auth_user,
                                         );
                                         // WASMライフサイクルコールバック: リクエスト完了
                                         crate::wasm::on_request_complete_async(
@@ -4846,17 +6527,29 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                             }
                         };
 
+                        // F-141: 送信元アドレスの指定があればこの接続の送信元を決める
+                        let target = match server.target.bound_for(client_ip) {
+                            Ok(target) => target,
+                            Err(e) => {
+                                error!("Upstream source address error: {}", e);
+                                let err_buf = ERR_MSG_BAD_GATEWAY.to_vec();
+                                let _ = timeout(WRITE_TIMEOUT, tls_stream.write_all(err_buf)).await;
+                                return;
+                            }
+                        };
+
                         server.acquire();
 
                         // WebSocket プロキシ処理（双方向転送）
                         let ws_result = handle_websocket_proxy(
                             tls_stream,
-                            &server.target,
+                            &target,
                             security,
                             &method_bytes,
                             &path_bytes,
                             &prefix,
                             &headers_for_proxy,
+                            &extra_headers,
                             &initial_body,
                         )
                         .await;
@@ -4875,6 +6568,8 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                                 start_instant,
                                 client_ip,
                                 "",
+                                "",
+                                &auth_user,
                             );
 
                             // WASMライフサイクルコールバック: リクエスト完了
@@ -4903,6 +6598,7 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                 }
 
                 // Backend処理
+                let hedge = std::cell::Cell::new(HedgeOutcome::NotHedged);
                 let result = handle_backend(
                     tls_stream,
                     backend,
@@ -4925,6 +6621,8 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                         }
                     },
                     client_ip,
+                    &hedge,
+                    extra_headers,
                 )
                 .await;
 
@@ -4941,6 +6639,8 @@ async fn handle_requests(mut tls_stream: ServerTls, client_ip: &str, peer_addr:
                             start_instant,
                             client_ip,
                             "",
+                            hedge.get().as_str(),
+                            &auth_user,
                         );
 
                         // WASMライフサイクルコールバック: リクエスト完了
@@ -5053,6 +6753,53 @@ async fn drain_request_body(
     }
 }
 
+/// 外部認可（F-149）・WAF（F-154）へ渡すボディを上限まで受信する。
+///
+/// 受信したバイトは `accumulated` に積み、ヘッダー後のデータとしてそのまま上流へ転送する。
+/// 返すボディは最大 `max + 1` バイト（上限を超えたかを呼び出し側で判定できるように）。
+/// 読み取りエラー・タイムアウト・途中で閉じた場合は None（呼び出し側は接続を閉じる）。
+async fn read_inspected_body(
+    stream: &mut ServerTls,
+    accumulated: &mut Vec<u8>,
+    header_len: usize,
+    content_length: usize,
+    is_chunked: bool,
+    max: usize,
+) -> Option<Vec<u8>> {
+    let mut decoder = ChunkedDecoder::new(max as u64);
+    let mut fed = header_len;
+    loop {
+        if is_chunked {
+            match decoder.feed(&accumulated[fed..]) {
+                ChunkedFeedResult::Continue => fed = accumulated.len(),
+                _ => {
+                    let mut body = decode_chunked_body(&accumulated[header_len..]);
+                    body.truncate(max + 1);
+                    return Some(body);
+                }
+            }
+        } else {
+            let want = content_length.min(max + 1);
+            if accumulated.len() - header_len >= want {
+                return Some(accumulated[header_len..header_len + want].to_vec());
+            }
+        }
+        let buf = buf_get();
+        match timeout(READ_TIMEOUT, stream.read(buf)).await {
+            Ok((Ok(n), mut b)) if n > 0 => {
+                b.set_valid_len(n);
+                accumulated.extend_from_slice(b.as_valid_slice());
+                buf_put(b);
+            }
+            Ok((_, b)) => {
+                buf_put(b);
+                return None;
+            }
+            Err(_) => return None,
+        }
+    }
+}
+
 async fn handle_backend(
     mut tls_stream: ServerTls,
     backend: Backend,
@@ -5066,6 +6813,8 @@ async fn handle_backend(
     client_wants_close: bool,
     wasm_modules: Arc<Vec<String>>,
     client_ip: &str,
+    hedge: &std::cell::Cell<HedgeOutcome>,
+    extra: ExtraHeaders,
 ) -> Option<(ServerTls, u16, u64, bool)> {
     // Proxy バックエンドはリクエストボディを上流へ転送して消費する。それ以外（File/Memory/
     // Redirect 等のローカル応答）はボディを読まないため、keep-alive 接続でボディが次の
@@ -5096,6 +6845,8 @@ async fn handle_backend(
                 client_wants_close,
                 wasm_modules,
                 client_ip,
+                hedge,
+                extra,
             )
             .await
         }
@@ -5129,7 +6880,7 @@ async fn handle_backend(
             header.extend_from_slice(b"\r\n");
 
             // 追加レスポンスヘッダー（セキュリティヘッダーなど）
-            for (header_name, header_value) in &security.add_response_headers {
+            for (header_name, header_value) in security.response_headers(&extra) {
                 header.extend_from_slice(header_name.as_bytes());
                 header.extend_from_slice(b": ");
                 header.extend_from_slice(header_value.as_bytes());
@@ -5259,6 +7010,7 @@ async fn handle_backend(
                 &prefix,
                 client_wants_close,
                 &security,
+                &extra,
                 range_header,
                 open_file_cache_config.as_deref(),
                 wasm_modules,
@@ -5394,6 +7146,7 @@ async fn handle_websocket_proxy(
     req_path: &[u8],
     prefix: &[u8],
     headers: &[(Box<[u8]>, Box<[u8]>)],
+    extra: &ExtraHeaders,
     initial_body: &[u8],
 ) -> Option<(u16, u64)> {
     let connect_timeout = Duration::from_secs(security.backend_connect_timeout_secs);
@@ -5431,9 +7184,9 @@ async fn handle_websocket_proxy(
 
     request.extend_from_slice(HEADER_CRLF);
 
-    // すべてのヘッダーを転送（Host 以外）
+    // すべてのヘッダーを転送（Host と除去対象以外）
     for (name, value) in headers {
-        if name.eq_ignore_ascii_case(b"host") {
+        if name.eq_ignore_ascii_case(b"host") || security.is_removed_request_header(name, extra) {
             continue;
         }
 
@@ -5446,6 +7199,16 @@ async fn handle_websocket_proxy(
         request.extend_from_slice(value);
         request.extend_from_slice(HEADER_CRLF);
     }
+    // リクエスト単位のヘッダー（認証で得たクレーム・外部認可の設定分）
+    for (name, value) in &extra.request {
+        if !is_valid_header_name(name.as_bytes()) || !is_valid_header_value(value.as_bytes()) {
+            continue;
+        }
+        request.extend_from_slice(name.as_bytes());
+        request.extend_from_slice(HEADER_COLON);
+        request.extend_from_slice(value.as_bytes());
+        request.extend_from_slice(HEADER_CRLF);
+    }
     request.extend_from_slice(HEADER_CRLF);
 
     // 初期ボディがあれば追加
@@ -5491,7 +7254,11 @@ async fn handle_websocket_proxy_http(
     // バックエンドに接続
     let addr = HostPortStr::new(&target.host, target.port); // F-41: スタック上に構築（ヒープ確保なし）
     let addr = addr.as_str();
-    let connect_result = timeout(connect_timeout, TcpStream::connect_str(addr)).await;
+    let connect_result = timeout(
+        connect_timeout,
+        TcpStream::connect_str_with(addr, target.bind, target.socket.clone()),
+    )
+    .await;
 
     let mut backend_stream = match connect_result {
         Ok(Ok(stream)) => {
@@ -5605,7 +7372,11 @@ async fn handle_websocket_proxy_https(
     // バックエンドに TCP 接続
     let addr = HostPortStr::new(&target.host, target.port); // F-41: スタック上に構築（ヒープ確保なし）
     let addr = addr.as_str();
-    let connect_result = timeout(connect_timeout, TcpStream::connect_str(addr)).await;
+    let connect_result = timeout(
+        connect_timeout,
+        TcpStream::connect_str_with(addr, target.bind, target.socket.clone()),
+    )
+    .await;
 
     let backend_tcp = match connect_result {
         Ok(Ok(stream)) => {
@@ -5940,6 +7711,7 @@ async fn websocket_bidirectional_transfer_tls(
 // Connection ヘッダーに基づいて接続をプールに返却します。
 // ====================
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_proxy(
     mut client_stream: ServerTls,
     upstream_group: &UpstreamGroup,
@@ -5957,7 +7729,12 @@ async fn handle_proxy(
     client_wants_close: bool,
     wasm_modules: Arc<Vec<String>>,
     client_ip: &str,
+    hedge: &std::cell::Cell<HedgeOutcome>,
+    mut extra: ExtraHeaders,
 ) -> Option<(ServerTls, u16, u64, bool)> {
+    // F-139: リクエスト期限の起点（ヘッダー受信完了時点）
+    let received_at = Instant::now();
+
     // クライアントの Accept-Encoding を解析
     let client_encoding = headers
         .iter()
@@ -6058,6 +7835,8 @@ async fn handle_proxy(
                                         debug!("ETag match, returning 304 Not Modified");
                                         let response = build_304_response(
                                             &cached_entry,
+                                            security,
+                                            &extra,
                                             client_wants_close,
                                             is_stale,
                                         );
@@ -6096,6 +7875,8 @@ async fn handle_proxy(
                                         );
                                         let response = build_304_response(
                                             &cached_entry,
+                                            security,
+                                            &extra,
                                             client_wants_close,
                                             is_stale,
                                         );
@@ -6145,6 +7926,8 @@ async fn handle_proxy(
                             // （平文接続。kTLS/rustls は内部で 2 回書き込みへフォールバック）
                             let headers = build_cached_response_headers(
                                 &cached_entry,
+                                security,
+                                &extra,
                                 client_wants_close,
                                 is_stale,
                             );
@@ -6173,6 +7956,8 @@ async fn handle_proxy(
                             match serve_from_disk_cache(
                                 &mut client_stream,
                                 &cached_entry,
+                                security,
+                                &extra,
                                 disk_path,
                                 client_wants_close,
                                 is_stale,
@@ -6253,55 +8038,462 @@ async fn handle_proxy(
         );
     }
 
-    // ロードバランシング: UpstreamGroup からサーバーを選択
-    // F-97: Consistent Hash の header:/cookie: をリクエストヘッダから解決
-    let server = match upstream_group.select_with_header_fn(client_ip, |name| {
+    // F-139: リクエスト全体の期限（gRPC は受信した grpc-timeout も期限として扱う）
+    let is_grpc_request = headers.iter().any(|(n, v)| header_pair_is_grpc(n, v));
+    let deadline_security;
+    let security = match UpstreamDeadline::resolve(
+        security,
+        received_at,
+        is_grpc_request,
         headers
             .iter()
-            .find(|(n, _)| n.as_ref().eq_ignore_ascii_case(name))
-            .map(|(_, v)| v.as_ref())
-    }) {
-        Some(s) => s,
-        None => {
-            // 利用可能なサーバーがない
-            error!("No healthy upstream servers available");
-            let err_buf = ERR_MSG_BAD_GATEWAY.to_vec();
+            .find(|(n, _)| n.eq_ignore_ascii_case(b"grpc-timeout"))
+            .map(|(_, v)| v.as_ref()),
+    ) {
+        Some(deadline) if deadline.is_expired() => {
+            let err_buf = ERR_MSG_GATEWAY_TIMEOUT.to_vec();
+            let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
+            return Some((client_stream, 504, 0, true));
+        }
+        Some(deadline) => {
+            deadline_security = security.with_upstream_deadline(deadline);
+            &deadline_security
+        }
+        None => security,
+    };
+
+    // F-138: 適応型の同時実行数制限（上限超過は上流へ送らず即 503 + Retry-After）
+    let concurrency_permit = match acquire_concurrency_permit(upstream_group) {
+        Ok(permit) => permit,
+        Err(retry_after) => {
+            let err_buf = concurrency_limited_response(retry_after);
+            let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
+            return Some((client_stream, 503, 0, true));
+        }
+    };
+
+    // ロードバランシング: UpstreamGroup からサーバーを選択
+    // F-97: Consistent Hash の header:/cookie: をリクエストヘッダから解決（path キーは req_path）
+    // F-132: スティッキー Cookie が有効なら固定先を優先し、必要なら Set-Cookie を発行
+    let (server, sticky_set_cookie) =
+        match upstream_group.select_with_affinity(client_ip, req_path, |name| {
+            headers
+                .iter()
+                .find(|(n, _)| n.as_ref().eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_ref())
+        }) {
+            Some(s) => s,
+            None => {
+                // 利用可能なサーバーがない
+                error!("No healthy upstream servers available");
+                let err_buf = ERR_MSG_BAD_GATEWAY.to_vec();
+                let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
+                return Some((client_stream, 502, 0, true));
+            }
+        };
+
+    // F-132: 固定先を（再）発行する場合はレスポンスヘッダーに Set-Cookie を追加
+    if let Some(cookie) = sticky_set_cookie {
+        extra.push_response_header("Set-Cookie", cookie);
+    }
+    let extra = &extra;
+
+    // F-141: 送信元アドレスの指定があればこのリクエストの送信元を決める
+    let target = match server.target.bound_for(client_ip) {
+        Ok(target) => target,
+        Err(e) => {
+            error!("Upstream source address error: {}", e);
+            let err_buf = ERR_MSG_BAD_GATEWAY.to_vec();
             let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
             return Some((client_stream, 502, 0, true));
         }
     };
 
+    // F-136: 上流接続の使用枠（多重化上流はストリーム上限で制御するため対象外）
+    let mut lease = if server.target.uses_tls_h2() || server.target.uses_h3() {
+        None
+    } else {
+        match acquire_connection_lease(&server.target).await {
+            Ok(lease) => lease,
+            Err(_) => {
+                let err_buf = ERR_MSG_SERVICE_UNAVAILABLE.to_vec();
+                let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
+                return Some((client_stream, 503, 0, true));
+            }
+        }
+    };
+
     // 接続カウンターを増加（Least Connections 用）
     server.acquire();
+    // 応答を中継したサーバー（F-137 のヘッジが勝つと一次試行とは別のサーバーになる）
+    let mut served_by = server;
 
-    // F-06: リクエスト結果記録用にサーバーのインデックスと開始時刻を記録
-    let resilience_server_idx = upstream_group
-        .servers
-        .iter()
-        .position(|s| std::ptr::eq(s, server));
+    // F-06: リクエスト結果記録用に開始時刻を記録
     let resilience_start = std::time::Instant::now();
 
-    let target = &server.target;
+    let target = &*target;
     // コネクションプールキーの生成
     // HTTPS: SNI と tls_insecure 毎に別プール（B-30: 検証設定の異なる接続の再利用を防ぐ）
     let tls_insecure = upstream_group.tls_insecure();
-    let pool_key = if target.use_tls && target.sni_name.is_some() {
+    let mut pool_key = if target.use_tls && target.sni_name.is_some() {
         https_pool_key(&target.host, target.port, target.sni(), tls_insecure)
     } else if target.use_tls {
         https_pool_key_no_sni(&target.host, target.port, tls_insecure)
     } else {
         format!("{}:{}", target.host, target.port)
     };
+    if let Cow::Owned(key) = bound_pool_key(&pool_key, target.bind) {
+        pool_key = key;
+    }
 
     // リクエストパス構築
     // gRPC はフルパス保持（/* プレフィックス除去で UNIMPLEMENTED → B-40）
     let path_str = std::str::from_utf8(req_path).unwrap_or("/");
-    let preserve_grpc_path = headers.iter().any(|(n, v)| header_pair_is_grpc(n, v));
+    let preserve_grpc_path = is_grpc_request;
     let final_path_owned =
         compute_upstream_path(path_str, prefix, &target.path_prefix, preserve_grpc_path);
     let final_path = final_path_owned.as_str();
 
+    // F-137: ヘッジ対象（ポリシー設定あり・対象メソッド・ボディなし・複数サーバー）
+    // F-141: 送信元を指定した上流は送信元ごとのプールを使うため対象外
+    let hedge_policy = upstream_group.hedging.as_deref().filter(|p| {
+        content_length == 0
+            && !is_chunked
+            && target.source.is_none()
+            && upstream_group.servers.len() > 1
+            && p.allows_method(method)
+    });
+
     // HTTPリクエスト構築（プール使用）
+    let request = build_upstream_request(
+        method, final_path, target, headers, is_chunked, security, extra, client_ip, path_str,
+    );
+
+    let result = if target.use_tls {
+        // F-134: protocol = "h2" / "auto" は ALPN h2 の多重化接続を優先
+        #[cfg(feature = "http2")]
+        let h2_result = if target.uses_tls_h2() {
+            proxy_h2_tls(
+                client_stream,
+                target,
+                security,
+                extra,
+                method,
+                final_path.as_bytes(),
+                headers,
+                content_length,
+                is_chunked,
+                initial_body,
+                client_wants_close,
+                tls_insecure,
+                &pool_key,
+            )
+            .await
+        } else {
+            Err((client_stream, None))
+        };
+        #[cfg(not(feature = "http2"))]
+        let h2_result: Result<
+            Option<(ServerTls, u16, u64, bool)>,
+            (ServerTls, Option<Vec<u8>>),
+        > = Err((client_stream, None));
+        // F-135: protocol = "h3" は QUIC を優先し、UDP 不通時は HTTP/1.1 経路へフォールバック
+        #[cfg(feature = "http3")]
+        let h2_result = match h2_result {
+            Err((client_stream, None)) if target.uses_h3() => {
+                proxy_h3_tls(
+                    client_stream,
+                    target,
+                    security,
+                    extra,
+                    method,
+                    final_path.as_bytes(),
+                    headers,
+                    content_length,
+                    is_chunked,
+                    initial_body,
+                    client_wants_close,
+                    tls_insecure,
+                    &pool_key,
+                )
+                .await
+            }
+            other => other,
+        };
+        match h2_result {
+            Ok(done) => done,
+            Err((client_stream, read_body)) => {
+                // HTTPS接続（キャッシュ保存はHTTPのみサポート、HTTPSは別途実装が必要）
+                // 上流証明書検証は per-upstream の tls_insecure のみで制御（B-30: VEIL_TLS_INSECURE はクライアント向け）
+                proxy_https_pooled(
+                    client_stream,
+                    target,
+                    security,
+                    extra,
+                    compression,
+                    buffering_config,
+                    client_encoding,
+                    &pool_key,
+                    request,
+                    content_length,
+                    is_chunked,
+                    read_body.as_deref().unwrap_or(initial_body),
+                    client_wants_close,
+                    tls_insecure,
+                    wasm_modules,
+                )
+                .await
+            }
+        }
+    } else if target.use_h2c || upstream_group.use_h2c() {
+        // H2C (HTTP/2 over cleartext) 接続
+        #[cfg(feature = "http2")]
+        {
+            // gRPCメッセージサイズ制限チェック
+            let is_grpc = headers.iter().any(|(name, value)| {
+                name.eq_ignore_ascii_case(b"content-type") && value.starts_with(b"application/grpc")
+            });
+            if is_grpc && content_length > MAX_GRPC_BODY_SIZE {
+                let err_buf = ERR_MSG_REQUEST_TOO_LARGE.to_vec();
+                let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
+                Some((client_stream, 413, 0, true))
+            } else {
+                proxy_h2c(
+                    client_stream,
+                    target,
+                    security,
+                    extra,
+                    method,
+                    final_path.as_bytes(),
+                    headers,
+                    initial_body,
+                    client_wants_close,
+                )
+                .await
+            }
+        }
+        #[cfg(not(feature = "http2"))]
+        {
+            // HTTP/2 feature が無効な場合はHTTP/1.1にフォールバック
+            warn!("H2C requested but http2 feature not enabled, falling back to HTTP/1.1");
+            proxy_http_pooled(
+                client_stream,
+                target,
+                security,
+                extra,
+                compression,
+                buffering_config,
+                client_encoding,
+                &pool_key,
+                request,
+                content_length,
+                is_chunked,
+                initial_body,
+                client_wants_close,
+                cache_save_ctx.as_mut(),
+                wasm_modules.clone(),
+                None,
+            )
+            .await
+        }
+    } else if let Some(policy) = hedge_policy {
+        // F-137: ヘッジ付き HTTP 接続（勝者の接続で中継する）
+        let (result, winner) = proxy_http_hedged(
+            client_stream,
+            upstream_group,
+            policy,
+            server,
+            lease.take(),
+            security,
+            extra,
+            compression,
+            buffering_config,
+            client_encoding,
+            &pool_key,
+            request,
+            client_wants_close,
+            cache_save_ctx.as_mut(),
+            wasm_modules,
+            client_ip,
+            |alt: &ProxyTarget| {
+                let alt_path =
+                    compute_upstream_path(path_str, prefix, &alt.path_prefix, preserve_grpc_path);
+                build_upstream_request(
+                    method, &alt_path, alt, headers, is_chunked, security, extra, client_ip,
+                    path_str,
+                )
+            },
+            hedge,
+        )
+        .await;
+        served_by = winner;
+        result
+    } else {
+        // HTTP接続（キャッシュ保存・バッファリング対応）
+        proxy_http_pooled(
+            client_stream,
+            target,
+            security,
+            extra,
+            compression,
+            buffering_config,
+            client_encoding,
+            &pool_key,
+            request,
+            content_length,
+            is_chunked,
+            initial_body,
+            client_wants_close,
+            cache_save_ctx.as_mut(),
+            wasm_modules,
+            None,
+        )
+        .await
+    };
+    drop(lease);
+
+    // 接続カウンターを減少（Least Connections 用）
+    served_by.release();
+
+    // F-06: リクエスト結果をサーキットブレーカー・異常検知へ反映
+    // F-137: ヘッジが勝った場合は中継したサーバーの結果として記録する
+    let resilience_server_idx = upstream_group
+        .servers
+        .iter()
+        .position(|s| std::ptr::eq(s, served_by));
+    if let Some(idx) = resilience_server_idx {
+        let latency_ms = resilience_start.elapsed().as_millis() as u64;
+        // 5xx をバックエンド障害として扱う
+        let success = match &result {
+            Some((_, status, _, _)) => *status < 500,
+            None => false,
+        };
+        upstream_group.record_outcome(idx, success, latency_ms);
+        // F-144: 5xx・接続エラーの連続などでサーバーを即座に unhealthy にする
+        upstream_group.record_passive(
+            idx,
+            crate::passive_health::PassiveOutcome::classify(
+                result
+                    .as_ref()
+                    .map(|(_, status, bytes, _)| (*status, *bytes)),
+            ),
+        );
+        #[cfg(feature = "metrics")]
+        {
+            if let Some(s) = upstream_group.servers.get(idx) {
+                if let Some(cb) = &s.circuit_breaker {
+                    crate::metrics::set_circuit_breaker_state(
+                        &upstream_group.name,
+                        cb.state_code(),
+                    );
+                }
+                if s.is_ejected() {
+                    crate::metrics::set_outlier_ejected(
+                        &upstream_group.name,
+                        &format!("{}:{}", s.target.host, s.target.port),
+                        true,
+                    );
+                }
+            }
+        }
+    }
+
+    // F-138: 応答なし（接続断など）は失敗として扱う
+    complete_concurrency_permit(
+        upstream_group,
+        concurrency_permit,
+        result.as_ref().map_or(502, |(_, status, _, _)| *status),
+    );
+
+    // stale-if-error: バックエンドエラー時にstaleキャッシュを返す
+    if cache_config.stale_if_error {
+        if let Some((mut client_stream, status_code, _, _)) = result {
+            // バックエンドエラー（502, 504）の場合
+            if status_code == 502 || status_code == 504 {
+                // staleキャッシュを確認
+                #[cfg(feature = "cache")]
+                let _cache_key_opt = cache_save_ctx.as_ref().map(|c| c.key.clone());
+                #[cfg(not(feature = "cache"))]
+                let _cache_key_opt: Option<cache::CacheKey> = None;
+                if let Some(cache_key) = _cache_key_opt {
+                    if let Some(cache_manager) = cache::get_global_cache() {
+                        // 最大1時間のstaleキャッシュを許容
+                        if let Some(stale_entry) = cache_manager.get_stale(&cache_key, 3600) {
+                            debug!("stale-if-error: serving stale cache for {}", host_str);
+
+                            // staleキャッシュを返す（ボディは bytes::Bytes をゼロコピーで送出）
+                            // F-59: ヘッダ + ボディを 1 回の SENDMSG（scatter-gather）で送出
+                            if let Some(body_data) = stale_entry.memory_body() {
+                                let body = body_data.clone(); // O(1) refcount、memcpy なし
+                                let body_len = body.len();
+                                let headers = build_cached_response_headers(
+                                    &stale_entry,
+                                    security,
+                                    extra,
+                                    client_wants_close,
+                                    true,
+                                );
+                                match timeout(
+                                    WRITE_TIMEOUT,
+                                    client_stream.write_all_vectored(headers, body),
+                                )
+                                .await
+                                {
+                                    Ok((Ok(()), _, _)) => {
+                                        return Some((
+                                            client_stream,
+                                            stale_entry.status_code,
+                                            body_len as u64,
+                                            client_wants_close,
+                                        ));
+                                    }
+                                    _ => {
+                                        return None;
+                                    }
+                                }
+                            } else if let Some(disk_path) = stale_entry.disk_path() {
+                                if let Some((code, size)) = serve_from_disk_cache(
+                                    &mut client_stream,
+                                    &stale_entry,
+                                    security,
+                                    extra,
+                                    disk_path,
+                                    client_wants_close,
+                                    true,
+                                )
+                                .await
+                                {
+                                    return Some((client_stream, code, size, client_wants_close));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            // staleキャッシュがない場合は元のエラーレスポンスをそのまま返す
+            return Some((client_stream, status_code, 0, client_wants_close));
+        }
+        return result;
+    }
+
+    result
+}
+
+/// バックエンドへの HTTP/1.1 リクエストヘッダーを構築する（HTTP/1 フロントエンド）
+///
+/// F-137 のヘッジでは Host と `path_prefix` が異なる別サーバー向けに再構築する。
+#[allow(clippy::too_many_arguments)]
+fn build_upstream_request(
+    method: &[u8],
+    final_path: &str,
+    target: &ProxyTarget,
+    headers: &[(Box<[u8]>, Box<[u8]>)],
+    is_chunked: bool,
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
+    client_ip: &str,
+    path_str: &str,
+) -> Vec<u8> {
     // 定数バイト列を使用してアロケーションを削減
     let mut request = request_buf_get(1024);
     request.extend_from_slice(method);
@@ -6318,12 +8510,21 @@ async fn handle_proxy(
 
     request.extend_from_slice(HEADER_CRLF);
 
+    // F-139: gRPC の期限は残り時間で grpc-timeout を付け直す
+    let grpc_timeout = security
+        .upstream_deadline
+        .and_then(|d| d.grpc_timeout_value());
+
     for (name, value) in headers {
         // host と connection ヘッダーは別途処理済みのためスキップ
         if name.eq_ignore_ascii_case(b"host") || name.eq_ignore_ascii_case(b"connection") {
             continue;
         }
 
+        if grpc_timeout.is_some() && name.eq_ignore_ascii_case(b"grpc-timeout") {
+            continue;
+        }
+
         // RFC 7230 Section 6.1: Hop-by-hopヘッダーを削除
         // Connection, Keep-Alive, Proxy-Connection, TE, Trailer, Transfer-Encoding, Upgrade
         // これらのヘッダーはプロキシで終端され、バックエンドに転送してはならない
@@ -6347,13 +8548,9 @@ async fn handle_proxy(
             continue;
         }
 
-        // 設定で削除が指定されているヘッダーをスキップ
+        // 設定で削除が指定されているヘッダー・リクエスト単位で置き換えるヘッダーをスキップ
         // eq_ignore_ascii_case でアロケーションなしに大文字小文字無視比較
-        if security
-            .remove_request_headers
-            .iter()
-            .any(|h| name.eq_ignore_ascii_case(h.as_bytes()))
-        {
+        if security.is_removed_request_header(name, extra) {
             continue;
         }
 
@@ -6384,6 +8581,10 @@ async fn handle_proxy(
     // 設定で追加が指定されているヘッダーを追加
     // 特殊変数の置換: $client_ip, $host, $request_uri
     for (header_name, header_value) in &security.add_request_headers {
+        if extra.overrides_request_header(header_name.as_bytes()) {
+            continue;
+        }
+
         // 特殊変数を置換
         let host_str = headers
             .iter()
@@ -6408,6 +8609,26 @@ async fn handle_proxy(
         request.extend_from_slice(HEADER_CRLF);
     }
 
+    // リクエスト単位のヘッダー（認証で得たクレーム・外部認可の設定分、変数は置換しない）
+    for (header_name, header_value) in &extra.request {
+        if !is_valid_header_name(header_name.as_bytes())
+            || !is_valid_header_value(header_value.as_bytes())
+        {
+            warn!("Invalid forwarded request header value: {}", header_name);
+            continue;
+        }
+        request.extend_from_slice(header_name.as_bytes());
+        request.extend_from_slice(HEADER_COLON);
+        request.extend_from_slice(header_value.as_bytes());
+        request.extend_from_slice(HEADER_CRLF);
+    }
+
+    if let Some(value) = grpc_timeout {
+        request.extend_from_slice(b"grpc-timeout: ");
+        request.extend_from_slice(value.as_bytes());
+        request.extend_from_slice(HEADER_CRLF);
+    }
+
     // Via ヘッダー追加 (RFC 7230 Section 5.7.1)
     // プロキシ経由のリクエストに Via ヘッダーを追加
     {
@@ -6438,195 +8659,405 @@ async fn handle_proxy(
     // バックエンドにはKeep-Aliveを要求
     request.extend_from_slice(HEADER_CONNECTION_KEEPALIVE_END);
 
-    let result = if target.use_tls {
-        // HTTPS接続（キャッシュ保存はHTTPのみサポート、HTTPSは別途実装が必要）
-        // 上流証明書検証は per-upstream の tls_insecure のみで制御（B-30: VEIL_TLS_INSECURE はクライアント向け）
-        proxy_https_pooled(
-            client_stream,
-            target,
-            security,
-            compression,
-            buffering_config,
-            client_encoding,
-            &pool_key,
-            request,
-            content_length,
-            is_chunked,
-            initial_body,
-            client_wants_close,
-            tls_insecure,
-            wasm_modules,
-        )
-        .await
-    } else if target.use_h2c || upstream_group.use_h2c() {
-        // H2C (HTTP/2 over cleartext) 接続
-        #[cfg(feature = "http2")]
-        {
-            // gRPCメッセージサイズ制限チェック
-            let is_grpc = headers.iter().any(|(name, value)| {
-                name.eq_ignore_ascii_case(b"content-type") && value.starts_with(b"application/grpc")
-            });
-            if is_grpc && content_length > MAX_GRPC_BODY_SIZE {
-                let err_buf = ERR_MSG_REQUEST_TOO_LARGE.to_vec();
-                let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
-                Some((client_stream, 413, 0, true))
-            } else {
-                proxy_h2c(
-                    client_stream,
-                    target,
-                    security,
-                    method,
-                    final_path.as_bytes(),
-                    headers,
-                    initial_body,
-                    client_wants_close,
-                )
-                .await
-            }
+    request
+}
+
+// ====================
+// リクエストヘッジング（F-137）
+// ====================
+
+/// 平文 HTTP/1.1 上流への接続を取得する（プール優先、なければ新規 connect）。
+///
+/// 失敗時は応答ステータスを返す（connect エラーは 502、タイムアウトは 504）。
+async fn open_http_backend(
+    target: &ProxyTarget,
+    pool_key: &str,
+    connect_timeout: Duration,
+) -> Result<(TcpStream, ConnLifecycle), u16> {
+    if let Some(pooled) = HTTP_POOL.with(|p| p.borrow_mut().get(pool_key)) {
+        return Ok(pooled);
+    }
+    connect_http_backend(target, connect_timeout).await
+}
+
+/// 平文 HTTP/1.1 上流へ新規 connect する（プールを経由しない）。
+async fn connect_http_backend(
+    target: &ProxyTarget,
+    connect_timeout: Duration,
+) -> Result<(TcpStream, ConnLifecycle), u16> {
+    let addr = HostPortStr::new(&target.host, target.port); // F-41: スタック上に構築（ヒープ確保なし）
+    let addr = addr.as_str();
+    match timeout(
+        connect_timeout,
+        TcpStream::connect_str_with(addr, target.bind, target.socket.clone()),
+    )
+    .await
+    {
+        Ok(Ok(stream)) => {
+            let _ = stream.set_nodelay(true);
+            Ok((stream, ConnLifecycle::new(&target.connection_pool)))
         }
-        #[cfg(not(feature = "http2"))]
-        {
-            // HTTP/2 feature が無効な場合はHTTP/1.1にフォールバック
-            warn!("H2C requested but http2 feature not enabled, falling back to HTTP/1.1");
-            proxy_http_pooled(
-                client_stream,
-                target,
-                security,
-                compression,
-                buffering_config,
-                client_encoding,
-                &pool_key,
-                request,
-                content_length,
-                is_chunked,
-                initial_body,
-                client_wants_close,
-                cache_save_ctx.as_mut(),
-                wasm_modules.clone(),
-            )
-            .await
+        Ok(Err(e)) => {
+            error!("Proxy connect error to {}: {}", addr, e);
+            Err(502)
         }
-    } else {
-        // HTTP接続（キャッシュ保存・バッファリング対応）
-        proxy_http_pooled(
-            client_stream,
-            target,
-            security,
-            compression,
-            buffering_config,
-            client_encoding,
-            &pool_key,
-            request,
-            content_length,
-            is_chunked,
-            initial_body,
-            client_wants_close,
-            cache_save_ctx.as_mut(),
-            wasm_modules,
-        )
-        .await
-    };
+        Err(_) => {
+            error!("Proxy connect timeout to {}", addr);
+            Err(504)
+        }
+    }
+}
 
-    // 接続カウンターを減少（Least Connections 用）
-    server.release();
+/// プール接続へのリクエストヘッダー先行送信の結果（F-145）。
+enum PooledWrite {
+    /// 送信完了
+    Sent,
+    /// 接続が既に閉じられていた（未送信のリクエストを返す）
+    Stale(Vec<u8>),
+    /// タイムアウトなどその他の失敗
+    Failed,
+}
 
-    // F-06: リクエスト結果をサーキットブレーカー・異常検知へ反映
-    if let Some(idx) = resilience_server_idx {
-        let latency_ms = resilience_start.elapsed().as_millis() as u64;
-        // 5xx をバックエンド障害として扱う
-        let success = match &result {
-            Some((_, status, _, _)) => *status < 500,
-            None => false,
-        };
-        upstream_group.record_outcome(idx, success, latency_ms);
-        #[cfg(feature = "metrics")]
-        {
-            if let Some(s) = upstream_group.servers.get(idx) {
-                if let Some(cb) = &s.circuit_breaker {
-                    crate::metrics::set_circuit_breaker_state(
-                        &upstream_group.name,
-                        cb.state_code(),
-                    );
-                }
-                if s.is_ejected() {
-                    crate::metrics::set_outlier_ejected(
-                        &upstream_group.name,
-                        &format!("{}:{}", s.target.host, s.target.port),
-                        true,
-                    );
-                }
-            }
+/// プールから取り出した接続へリクエストヘッダーを先に書き込む（F-145）。
+///
+/// 最初の書き込みが RST / EPIPE で失敗した場合は、バックエンドが idle 接続を
+/// 閉じていただけでリクエストは届いていないため、新規接続での再送を許す。
+async fn write_head_to_pooled<S: AsyncWriteRentExt>(
+    stream: &mut S,
+    request: Vec<u8>,
+) -> PooledWrite {
+    match timeout(WRITE_TIMEOUT, stream.write_all(request)).await {
+        Ok((Ok(_), buf)) => {
+            request_buf_put(buf);
+            PooledWrite::Sent
+        }
+        Ok((Err(e), buf)) if is_connection_closed_error(&e) => PooledWrite::Stale(buf),
+        _ => PooledWrite::Failed,
+    }
+}
+
+/// 平文 HTTP/1.1 上流への接続を取得し、プール接続ならリクエストヘッダーを先行送信する（F-145）。
+///
+/// プール接続への最初の書き込みが失敗した場合は、非冪等リクエストでも一度だけ
+/// 新規接続に切り替える。戻り値のリクエストは未送信分（送信済みなら空）。
+async fn open_http_backend_for_request(
+    target: &ProxyTarget,
+    pool_key: &str,
+    connect_timeout: Duration,
+    request: Vec<u8>,
+) -> Result<(TcpStream, ConnLifecycle, Vec<u8>), u16> {
+    let Some((mut stream, lifecycle)) = HTTP_POOL.with(|p| p.borrow_mut().get(pool_key)) else {
+        let (stream, lifecycle) = connect_http_backend(target, connect_timeout).await?;
+        return Ok((stream, lifecycle, request));
+    };
+    match write_head_to_pooled(&mut stream, request).await {
+        PooledWrite::Sent => Ok((stream, lifecycle, Vec::new())),
+        PooledWrite::Stale(request) => {
+            debug!(
+                "Pooled connection to {} was stale, retrying on a fresh connection",
+                pool_key
+            );
+            crate::metrics::record_upstream_connection_retired(pool_key, "stale_write");
+            drop(stream);
+            let (stream, lifecycle) = connect_http_backend(target, connect_timeout).await?;
+            Ok((stream, lifecycle, request))
+        }
+        PooledWrite::Failed => Err(502),
+    }
+}
+
+/// 上流接続の使用枠を待たずに確保する（F-137 のヘッジ用）。
+///
+/// `max_connections` が 0 なら `Ok(None)`。満杯なら待機せず `Err`（ヘッジを見送る）。
+fn try_connection_lease(target: &ProxyTarget) -> Result<Option<ConnectionLease>, PendingRejected> {
+    let cfg = &target.connection_pool;
+    if cfg.max_connections == 0 {
+        return Ok(None);
+    }
+    let addr = HostPortStr::new(&target.host, target.port);
+    connect_gate(addr.as_str())
+        .try_lease(cfg.max_connections)
+        .map(Some)
+        .ok_or(PendingRejected::Overflow)
+}
+
+/// 上流が応答を返し始める（最初の 1 バイトが届く）まで待つ（F-137）。
+///
+/// MSG_PEEK で覗くだけでバイトは消費しないため、勝者の接続はそのまま応答の中継に使える。
+/// 接続終了・エラーは 502、`deadline` 超過は 504。
+async fn wait_response_start(stream: &TcpStream, deadline: Instant) -> Result<(), u16> {
+    let mut byte = [0u8; 1];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match timeout(remaining, stream.readable()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(_)) => return Err(502),
+            Err(_) => return Err(504),
+        }
+        match peek_socket(stream, &mut byte) {
+            Ok(0) => return Err(502),
+            Ok(_) => return Ok(()),
+            // 偽の readable 通知（EAGAIN）なら再待機
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+            Err(_) => return Err(502),
         }
     }
+}
 
-    // stale-if-error: バックエンドエラー時にstaleキャッシュを返す
-    if cache_config.stale_if_error {
-        if let Some((mut client_stream, status_code, _, _)) = result {
-            // バックエンドエラー（502, 504）の場合
-            if status_code == 502 || status_code == 504 {
-                // staleキャッシュを確認
-                #[cfg(feature = "cache")]
-                let _cache_key_opt = cache_save_ctx.as_ref().map(|c| c.key.clone());
-                #[cfg(not(feature = "cache"))]
-                let _cache_key_opt: Option<cache::CacheKey> = None;
-                if let Some(cache_key) = _cache_key_opt {
-                    if let Some(cache_manager) = cache::get_global_cache() {
-                        // 最大1時間のstaleキャッシュを許容
-                        if let Some(stale_entry) = cache_manager.get_stale(&cache_key, 3600) {
-                            debug!("stale-if-error: serving stale cache for {}", host_str);
+/// ヘッジ試行: 接続を取得してリクエストを送り、応答開始を待つ（F-137）。
+///
+/// 戻り値の `Duration` は送信から応答開始までの時間。
+async fn hedge_attempt(
+    target: &ProxyTarget,
+    request: Vec<u8>,
+    connect_timeout: Duration,
+    deadline: Instant,
+) -> Result<(TcpStream, ConnLifecycle, Duration), u16> {
+    let pool_key = HostPortStr::new(&target.host, target.port);
+    let (mut stream, lifecycle) =
+        open_http_backend(target, pool_key.as_str(), connect_timeout).await?;
+    let (write_res, returned) = stream.write_all(request).await;
+    request_buf_put(returned);
+    write_res.map_err(|_| 502u16)?;
+    let sent_at = Instant::now();
+    wait_response_start(&stream, deadline).await?;
+    Ok((stream, lifecycle, sent_at.elapsed()))
+}
 
-                            // staleキャッシュを返す（ボディは bytes::Bytes をゼロコピーで送出）
-                            // F-59: ヘッダ + ボディを 1 回の SENDMSG（scatter-gather）で送出
-                            if let Some(body_data) = stale_entry.memory_body() {
-                                let body = body_data.clone(); // O(1) refcount、memcpy なし
-                                let body_len = body.len();
-                                let headers = build_cached_response_headers(
-                                    &stale_entry,
-                                    client_wants_close,
-                                    true,
-                                );
-                                match timeout(
-                                    WRITE_TIMEOUT,
-                                    client_stream.write_all_vectored(headers, body),
-                                )
-                                .await
-                                {
-                                    Ok((Ok(()), _, _)) => {
-                                        return Some((
-                                            client_stream,
-                                            stale_entry.status_code,
-                                            body_len as u64,
-                                            client_wants_close,
-                                        ));
-                                    }
-                                    _ => {
-                                        return None;
-                                    }
-                                }
-                            } else if let Some(disk_path) = stale_entry.disk_path() {
-                                if let Some((code, size)) = serve_from_disk_cache(
-                                    &mut client_stream,
-                                    &stale_entry,
-                                    disk_path,
-                                    client_wants_close,
-                                    true,
-                                )
-                                .await
-                                {
-                                    return Some((client_stream, code, size, client_wants_close));
-                                }
-                            }
-                        }
+/// ヘッジ付き交換の勝者（F-137）
+struct HedgeWinner<'a> {
+    /// 応答を中継するサーバー（`acquire()` 済み、呼び出し側で `release()`）
+    server: &'a UpstreamServer,
+    /// リクエスト送信済みで応答が届き始めた接続（応答は未消費）
+    conn: (TcpStream, ConnLifecycle),
+    /// 勝者側の接続使用枠（中継完了まで保持する）
+    lease: Option<ConnectionLease>,
+    outcome: HedgeOutcome,
+}
+
+/// 平文 HTTP/1.1 上流へヘッジ付きでリクエストを送る（F-137）。
+///
+/// 一次試行（`conn` へ `request` を送信）が `policy.delay()` 内に応答を返し始めなければ、
+/// 別サーバーへ `build_request` で再構築したリクエストを送り、先に応答を返し始めた方を
+/// 返す。敗者の接続は応答途中のためプールへ戻さず閉じる（上流側でも取り消される）。
+/// ヘッジ先・使用枠・予算のいずれかが無ければ一次試行だけを待つ。
+///
+/// 一方が失敗してももう一方を待ち、両方失敗なら一次試行側のステータス（502 / 504）を返す。
+/// `Err` の場合は一次試行のサーバーのみ `acquire()` 済みのまま残る。
+/// 応答開始の待ち時間は `timeouts` の応答ヘッダー待ちと試行ごとの上限に従う（F-139）。
+#[allow(clippy::too_many_arguments)]
+async fn hedged_exchange<'a>(
+    group: &'a UpstreamGroup,
+    policy: &HedgePolicy,
+    primary: &'a UpstreamServer,
+    conn: (TcpStream, ConnLifecycle),
+    lease: Option<ConnectionLease>,
+    request: Vec<u8>,
+    client_ip: &str,
+    connect_timeout: Duration,
+    timeouts: UpstreamTimeouts,
+    build_request: impl FnOnce(&ProxyTarget) -> Vec<u8>,
+) -> Result<HedgeWinner<'a>, u16> {
+    use futures::FutureExt;
+
+    policy.deposit();
+    let (mut stream, lifecycle) = conn;
+    let (write_res, returned) = stream.write_all(request).await;
+    request_buf_put(returned);
+    if write_res.is_err() {
+        return Err(502);
+    }
+    let sent_at = Instant::now();
+    let header_deadline = timeouts.header_deadline(sent_at);
+    let deadline = timeouts.try_deadline(header_deadline, sent_at);
+    let delay = policy.delay(&group.header_latency);
+
+    // 勝者がヘッジなら Some（一次試行の接続は下で閉じる）
+    let (hedged, outcome) = {
+        let mut primary_wait = std::pin::pin!(wait_response_start(&stream, deadline).fuse());
+        let early = futures::select_biased! {
+            r = primary_wait => Some(r),
+            _ = crate::runtime::time::sleep(delay).fuse() => None,
+        };
+        let alternate = if early.is_some() {
+            None
+        } else {
+            group
+                .select_alternate(primary, client_ip)
+                .filter(|s| !s.target.use_tls && !s.target.use_h2c)
+                .and_then(|s| try_connection_lease(&s.target).ok().map(|l| (s, l)))
+                .filter(|_| {
+                    let ok = policy.try_withdraw();
+                    if !ok {
+                        record_upstream_hedge_budget_exhausted(&group.name);
+                    }
+                    ok
+                })
+        };
+        match alternate {
+            None => {
+                // ヘッジなし: 一次試行の応答開始を待つ
+                let r = match early {
+                    Some(r) => r,
+                    None => primary_wait.await,
+                };
+                r?;
+                group.header_latency.record(sent_at.elapsed());
+                (None, HedgeOutcome::NotHedged)
+            }
+            Some((alt, alt_lease)) => {
+                debug!(
+                    "Hedging request to {}:{} after {:?} (primary {}:{})",
+                    alt.target.host,
+                    alt.target.port,
+                    delay,
+                    primary.target.host,
+                    primary.target.port
+                );
+                alt.acquire();
+                let mut hedge = std::pin::pin!(hedge_attempt(
+                    &alt.target,
+                    build_request(&alt.target),
+                    connect_timeout,
+                    timeouts.try_deadline(header_deadline, Instant::now()),
+                )
+                .fuse());
+                let first = futures::select_biased! {
+                    r = primary_wait => Ok(r),
+                    h = hedge => Err(h),
+                };
+                let winner = match first {
+                    Ok(Ok(())) => Ok(None),
+                    // 一次試行が失敗: ヘッジの結果を待つ
+                    Ok(Err(status)) => hedge.await.map(Some).map_err(|_| status),
+                    Err(Ok(won)) => Ok(Some(won)),
+                    // ヘッジが失敗: 一次試行を待つ
+                    Err(Err(_)) => primary_wait.await.map(|()| None),
+                };
+                record_upstream_hedge(&group.name, matches!(winner, Ok(Some(_))));
+                match winner {
+                    Ok(None) => {
+                        alt.release();
+                        group.header_latency.record(sent_at.elapsed());
+                        (None, HedgeOutcome::PrimaryWon)
+                    }
+                    Ok(Some((s, l, latency))) => {
+                        // 一次試行は少なくとも経過時間だけ遅い（打ち切り値として記録）
+                        group.header_latency.record(sent_at.elapsed());
+                        group.header_latency.record(latency);
+                        (Some((alt, s, l, alt_lease)), HedgeOutcome::HedgeWon)
+                    }
+                    Err(status) => {
+                        alt.release();
+                        return Err(status);
                     }
                 }
             }
-            // staleキャッシュがない場合は元のエラーレスポンスをそのまま返す
-            return Some((client_stream, status_code, 0, client_wants_close));
         }
-        return result;
-    }
+    };
 
-    result
+    Ok(match hedged {
+        None => HedgeWinner {
+            server: primary,
+            conn: (stream, lifecycle),
+            lease,
+            outcome,
+        },
+        Some((alt, s, l, alt_lease)) => {
+            drop((stream, lease));
+            primary.release();
+            HedgeWinner {
+                server: alt,
+                conn: (s, l),
+                lease: alt_lease,
+                outcome,
+            }
+        }
+    })
+}
+
+/// ヘッジ付きの HTTP プロキシ（F-137、HTTP/1 フロントエンド・平文 HTTP/1.1 上流）。
+///
+/// 一次試行の接続を取得して [`hedged_exchange`] で競わせ、勝者の接続で
+/// [`proxy_http_pooled`] の中継を行う。戻り値の 2 要素目は中継したサーバー
+/// （`acquire()` 済み、呼び出し側で `release()`）。
+#[allow(clippy::too_many_arguments)]
+async fn proxy_http_hedged<'a>(
+    mut client_stream: ServerTls,
+    group: &'a UpstreamGroup,
+    policy: &HedgePolicy,
+    server: &'a UpstreamServer,
+    lease: Option<ConnectionLease>,
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
+    compression: &CompressionConfig,
+    buffering_config: &buffering::BufferingConfig,
+    client_encoding: AcceptedEncoding,
+    pool_key: &str,
+    request: Vec<u8>,
+    client_wants_close: bool,
+    cache_ctx: Option<&mut CacheSaveContext>,
+    wasm_modules: Arc<Vec<String>>,
+    client_ip: &str,
+    build_request: impl FnOnce(&ProxyTarget) -> Vec<u8>,
+    hedge: &std::cell::Cell<HedgeOutcome>,
+) -> (Option<(ServerTls, u16, u64, bool)>, &'a UpstreamServer) {
+    let connect_timeout = Duration::from_secs(security.backend_connect_timeout_secs);
+    let exchanged = match open_http_backend(&server.target, pool_key, connect_timeout).await {
+        Ok(conn) => {
+            hedged_exchange(
+                group,
+                policy,
+                server,
+                conn,
+                lease,
+                request,
+                client_ip,
+                connect_timeout,
+                security.upstream_timeouts(),
+                build_request,
+            )
+            .await
+        }
+        Err(status) => Err(status),
+    };
+    let winner = match exchanged {
+        Ok(winner) => winner,
+        Err(status) => {
+            let err_buf = if status == 504 {
+                ERR_MSG_GATEWAY_TIMEOUT
+            } else {
+                ERR_MSG_BAD_GATEWAY
+            };
+            let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf.to_vec())).await;
+            return (Some((client_stream, status, 0, true)), server);
+        }
+    };
+    hedge.set(winner.outcome);
+
+    let target = &winner.server.target;
+    let pool_key = HostPortStr::new(&target.host, target.port);
+    let result = proxy_http_pooled(
+        client_stream,
+        target,
+        security,
+        extra,
+        compression,
+        buffering_config,
+        client_encoding,
+        pool_key.as_str(),
+        Vec::new(),
+        0,
+        false,
+        &[],
+        client_wants_close,
+        cache_ctx,
+        wasm_modules,
+        Some(winner.conn),
+    )
+    .await;
+    drop(winner.lease);
+    (result, winner.server)
 }
 
 // ====================
@@ -6637,6 +9068,7 @@ async fn proxy_http_pooled(
     mut client_stream: ServerTls,
     target: &ProxyTarget,
     security: &SecurityConfig,
+    extra: &ExtraHeaders,
     compression: &CompressionConfig,
     buffering_config: &buffering::BufferingConfig,
     client_encoding: AcceptedEncoding,
@@ -6648,35 +9080,31 @@ async fn proxy_http_pooled(
     client_wants_close: bool,
     cache_ctx: Option<&mut CacheSaveContext>,
     wasm_modules: Arc<Vec<String>>,
+    prepared: Option<(TcpStream, ConnLifecycle)>,
 ) -> Option<(ServerTls, u16, u64, bool)> {
     // セキュリティ設定からタイムアウトを取得
     let connect_timeout = Duration::from_secs(security.backend_connect_timeout_secs);
 
+    // バッファリングが有効かどうか判定
+    // F-97: Content-Type: application/grpc は Full バッファをバイパス（リクエスト行に含む）
+    // F-145: プール接続ではヘッダーを先行送信して `request` が空になるため、送信前に判定する
+    let is_grpc_req = request_bytes_indicate_grpc(&request);
+
     // プールから接続を取得、または新規作成
-    let mut backend_stream = match HTTP_POOL.with(|p| p.borrow_mut().get(pool_key)) {
-        Some(stream) => stream,
+    // F-137: ヘッジ済み（`prepared` が Some）ならリクエスト送信済みで `request` は空
+    let (mut backend_stream, lifecycle, request) = match prepared {
+        Some((stream, lifecycle)) => (stream, lifecycle, request),
         None => {
-            // 新規接続を作成
-            let addr = HostPortStr::new(&target.host, target.port); // F-41: スタック上に構築（ヒープ確保なし）
-            let addr = addr.as_str();
-            let connect_result = timeout(connect_timeout, TcpStream::connect_str(addr)).await;
-
-            match connect_result {
-                Ok(Ok(stream)) => {
-                    let _ = stream.set_nodelay(true);
-                    stream
-                }
-                Ok(Err(e)) => {
-                    error!("Proxy connect error to {}: {}", addr, e);
-                    let err_buf = ERR_MSG_BAD_GATEWAY.to_vec();
-                    let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
-                    return Some((client_stream, 502, 0, true));
-                }
-                Err(_) => {
-                    error!("Proxy connect timeout to {}", addr);
-                    let err_buf = ERR_MSG_GATEWAY_TIMEOUT.to_vec();
-                    let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
-                    return Some((client_stream, 504, 0, true));
+            match open_http_backend_for_request(target, pool_key, connect_timeout, request).await {
+                Ok(conn) => conn,
+                Err(status) => {
+                    let err_buf = if status == 504 {
+                        ERR_MSG_GATEWAY_TIMEOUT
+                    } else {
+                        ERR_MSG_BAD_GATEWAY
+                    };
+                    let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf.to_vec())).await;
+                    return Some((client_stream, status, 0, true));
                 }
             }
         }
@@ -6693,8 +9121,6 @@ async fn proxy_http_pooled(
     let host_str_for_metrics = &target.host;
 
     // バッファリングが有効かどうか判定
-    // F-97: Content-Type: application/grpc は Full バッファをバイパス（リクエスト行に含む）
-    let is_grpc_req = request_bytes_indicate_grpc(&request);
     let buffering_enabled = !is_grpc_req
         && buffering_config.is_enabled()
         && buffering_config.should_buffer(Some(content_length));
@@ -6715,6 +9141,7 @@ async fn proxy_http_pooled(
         // - キャッシュ保存不要
         // - バッファリング無効
         // - WASMモジュール未設定（WASM有効時はユーザー空間でレスポンスヘッダーを操作する必要がある）
+        // - レスポンスヘッダー操作なし（スティッキー Cookie 発行時を含む、F-132）
         #[cfg(feature = "wasm")]
         let wasm_modules_active = !wasm_modules.is_empty();
         #[cfg(not(feature = "wasm"))]
@@ -6725,6 +9152,9 @@ async fn proxy_http_pooled(
             && !cache_save_needed
             && !buffering_enabled
             && !wasm_modules_active
+            && security.add_response_headers.is_empty()
+            && security.remove_response_headers.is_empty()
+            && extra.response.is_empty()
         {
             let splice_result = try_splice_proxy(
                 &client_stream,
@@ -6733,6 +9163,7 @@ async fn proxy_http_pooled(
                 content_length,
                 is_chunked,
                 initial_body,
+                security.upstream_timeouts(),
             )
             .await;
 
@@ -6752,6 +9183,7 @@ async fn proxy_http_pooled(
                     client_encoding,
                     cache_ctx,
                     security,
+                    extra,
                     wasm_modules,
                 )
                 .await
@@ -6774,6 +9206,7 @@ async fn proxy_http_pooled(
                 buffering_config,
                 cache_ctx,
                 security,
+                extra,
             )
             .await
         } else {
@@ -6792,6 +9225,7 @@ async fn proxy_http_pooled(
                 client_encoding,
                 cache_ctx,
                 security,
+                extra,
                 wasm_modules,
             )
             .await
@@ -6813,6 +9247,7 @@ async fn proxy_http_pooled(
             buffering_config,
             cache_ctx,
             security,
+            extra,
         )
         .await
     } else {
@@ -6828,13 +9263,14 @@ async fn proxy_http_pooled(
             client_encoding,
             cache_ctx,
             security,
+            extra,
             wasm_modules,
         )
         .await
     };
 
     match result {
-        Some((status_code, total, backend_wants_keep_alive, client_must_close)) => {
+        Some((status_code, total, backend_keep_alive, client_must_close)) => {
             // B-17: クライアントへ 1 バイトも送らないままバックエンド異常で終わった場合、
             // エラーページ（502/504）を即時送出してクローズする（従来はクライアントが
             // 自身のタイムアウトまでハングしていた）
@@ -6848,12 +9284,19 @@ async fn proxy_http_pooled(
                 return Some((client_stream, status_code, 0, true));
             }
             // バックエンドがKeep-Aliveを許可している場合、プールに返却
-            if backend_wants_keep_alive {
+            // F-145: アイドルタイムアウトは上流の `Keep-Alive: timeout=` を超えない
+            let idle_timeout =
+                backend_keep_alive.idle_timeout_secs(security.idle_connection_timeout_secs);
+            if backend_keep_alive.is_reusable() && idle_timeout > 0 {
                 let max_idle = security.max_idle_connections_per_host;
-                let idle_timeout = security.idle_connection_timeout_secs;
                 HTTP_POOL.with(|p| {
-                    p.borrow_mut()
-                        .put(pool_key.to_string(), backend_stream, max_idle, idle_timeout)
+                    p.borrow_mut().put(
+                        pool_key.to_string(),
+                        backend_stream,
+                        lifecycle.served(),
+                        max_idle,
+                        idle_timeout,
+                    )
                 });
             }
             // 408 (body timeout) sends Connection: close — must actually close
@@ -6887,6 +9330,7 @@ async fn proxy_h2c(
     mut client_stream: ServerTls,
     target: &ProxyTarget,
     security: &SecurityConfig,
+    extra: &ExtraHeaders,
     method: &[u8],
     path: &[u8],
     headers: &[(Box<[u8]>, Box<[u8]>)],
@@ -6898,7 +9342,8 @@ async fn proxy_h2c(
     // バックエンドに接続
     let addr = HostPortStr::new(&target.host, target.port); // F-41: スタック上に構築（ヒープ確保なし）
     let addr = addr.as_str();
-    let connect_result = timeout(connect_timeout, TcpStream::connect_str(addr)).await;
+    let connect = TcpStream::connect_str_with(addr, None, target.socket.clone());
+    let connect_result = timeout(connect_timeout, connect).await;
 
     let backend_stream = match connect_result {
         Ok(Ok(stream)) => {
@@ -6932,10 +9377,25 @@ async fn proxy_h2c(
     }
 
     // ヘッダーを変換 (Box<[u8]> -> &[u8])
-    let headers_ref: Vec<(&[u8], &[u8])> = headers
+    // F-139: gRPC 期限があれば grpc-timeout を残り時間で置き換える
+    let grpc_timeout = security
+        .upstream_deadline
+        .and_then(|d| d.grpc_timeout_value());
+    let mut headers_ref: Vec<(&[u8], &[u8])> = headers
         .iter()
+        .filter(|(k, _)| grpc_timeout.is_none() || !k.eq_ignore_ascii_case(b"grpc-timeout"))
+        .filter(|(k, _)| !security.is_removed_request_header(k, extra))
         .map(|(k, v)| (k.as_ref(), v.as_ref()))
         .collect();
+    let extra_request = extra.request_header_pairs();
+    headers_ref.extend(
+        extra_request
+            .iter()
+            .map(|(n, v)| (n.as_slice(), v.as_slice())),
+    );
+    if let Some(value) = grpc_timeout.as_deref() {
+        headers_ref.push((b"grpc-timeout", value.as_bytes()));
+    }
 
     // リクエストを送信
     let body = if request_body.is_empty() {
@@ -6945,22 +9405,80 @@ async fn proxy_h2c(
     };
     let authority = target.host.as_bytes();
 
-    let response = match h2c_client
-        .send_request(method, path, authority, &headers_ref, body)
-        .await
+    // F-139: 応答の一括受信を交換の期限で打ち切る
+    let exchange_deadline = security
+        .upstream_timeouts()
+        .exchange_deadline(Instant::now());
+    let response = match timeout(
+        UpstreamTimeouts::until(exchange_deadline),
+        h2c_client.send_request(method, path, authority, &headers_ref, body),
+    )
+    .await
     {
-        Ok(resp) => resp,
-        Err(e) => {
+        Ok(Ok(resp)) => resp,
+        Ok(Err(e)) => {
             error!("H2C request error: {}", e);
             let err_buf = ERR_MSG_BAD_GATEWAY.to_vec();
             let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
             return Some((client_stream, 502, 0, true));
         }
+        Err(_) => {
+            warn!("H2C response timeout from {}", addr);
+            let err_buf = ERR_MSG_GATEWAY_TIMEOUT.to_vec();
+            let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
+            return Some((client_stream, 504, 0, true));
+        }
     };
 
     // レスポンスをHTTP/1.1形式でクライアントに返す
     let status_code = response.status;
-    let mut http11_response = Vec::with_capacity(512 + response.body.len());
+    let http11_response = multiplexed_response_to_http11(
+        response.status,
+        &response.headers,
+        &response.trailers,
+        &response.body,
+        security,
+        extra,
+        client_wants_close,
+    );
+
+    let resp_size = http11_response.len() as u64;
+
+    // クライアントに送信
+    let write_result = timeout(WRITE_TIMEOUT, client_stream.write_all(http11_response)).await;
+    if !matches!(write_result, Ok((Ok(_), _))) {
+        return None;
+    }
+
+    Some((client_stream, status_code, resp_size, client_wants_close))
+}
+
+/// 多重化上流（F-134 / F-135）へ転送するリクエストヘッダーの `grpc-timeout` を
+/// F-139 の gRPC 期限の残り時間で置き換える（期限が無ければそのまま）
+#[cfg(any(feature = "http2", feature = "http3"))]
+pub(crate) fn apply_grpc_timeout(headers: &mut Vec<(Vec<u8>, Vec<u8>)>, security: &SecurityConfig) {
+    if let Some(value) = security
+        .upstream_deadline
+        .and_then(|d| d.grpc_timeout_value())
+    {
+        headers.retain(|(name, _)| !name.eq_ignore_ascii_case(b"grpc-timeout"));
+        headers.push((b"grpc-timeout".to_vec(), value.into_bytes()));
+    }
+}
+
+/// HTTP/2 / HTTP/3 上流の応答を HTTP/1.1 クライアント向けのバイト列へ変換する
+/// （h2c / F-134 / F-135 共通）
+#[cfg(any(feature = "http2", feature = "http3"))]
+fn multiplexed_response_to_http11(
+    status_code: u16,
+    headers: &[(Vec<u8>, Vec<u8>)],
+    trailers: &[(Vec<u8>, Vec<u8>)],
+    body: &[u8],
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
+    client_wants_close: bool,
+) -> Vec<u8> {
+    let mut http11_response = Vec::with_capacity(512 + body.len());
 
     // ステータス行
     http11_response.extend_from_slice(b"HTTP/1.1 ");
@@ -6971,11 +9489,12 @@ async fn proxy_h2c(
     http11_response.extend_from_slice(b"\r\n");
 
     // レスポンスヘッダー
-    for (name, value) in &response.headers {
-        // ホップバイホップヘッダーはスキップ
+    for (name, value) in headers {
+        // ホップバイホップヘッダーと Content-Length（下で付け直す）はスキップ
         if name.eq_ignore_ascii_case(b"connection")
             || name.eq_ignore_ascii_case(b"transfer-encoding")
             || name.eq_ignore_ascii_case(b"keep-alive")
+            || name.eq_ignore_ascii_case(b"content-length")
         {
             continue;
         }
@@ -6986,16 +9505,24 @@ async fn proxy_h2c(
     }
 
     // トレーラーヘッダー（gRPC-status など）をレスポンスヘッダーとして転送
-    for (name, value) in &response.trailers {
+    for (name, value) in trailers {
         http11_response.extend_from_slice(name);
         http11_response.extend_from_slice(b": ");
         http11_response.extend_from_slice(value);
         http11_response.extend_from_slice(b"\r\n");
     }
 
+    // 追加レスポンスヘッダー（F-132 のスティッキー Cookie を含む）
+    for (name, value) in security.response_headers(extra) {
+        http11_response.extend_from_slice(name.as_bytes());
+        http11_response.extend_from_slice(b": ");
+        http11_response.extend_from_slice(value.as_bytes());
+        http11_response.extend_from_slice(b"\r\n");
+    }
+
     // Content-Length
     http11_response.extend_from_slice(b"Content-Length: ");
-    http11_response.extend_from_slice(status_buf.format(response.body.len()).as_bytes());
+    http11_response.extend_from_slice(status_buf.format(body.len()).as_bytes());
     http11_response.extend_from_slice(b"\r\n");
 
     // Connection ヘッダー
@@ -7005,20 +9532,277 @@ async fn proxy_h2c(
         http11_response.extend_from_slice(b"Connection: keep-alive\r\n");
     }
 
-    http11_response.extend_from_slice(b"\r\n");
-
-    // ボディ
-    http11_response.extend_from_slice(&response.body);
+    http11_response.extend_from_slice(b"\r\n");
+
+    // ボディ
+    http11_response.extend_from_slice(body);
+    http11_response
+}
+
+/// F-134: ALPN h2 の TLS バックエンドへ HTTP/1.1 クライアントのリクエストを中継する。
+///
+/// h2 ではリクエストをストリームとして多重化するため、ボディは全量読み込んでから送る
+/// （chunked はデコードする）。`auto` でサーバーが HTTP/1.1 を選んだ場合は、読み込んだ
+/// ボディ（転送フレーミングそのまま）とともにクライアントストリームを返し、呼び出し側が
+/// HTTPS（HTTP/1.1）経路で処理する。
+#[cfg(feature = "http2")]
+async fn proxy_h2_tls(
+    mut client_stream: ServerTls,
+    target: &ProxyTarget,
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
+    method: &[u8],
+    path: &[u8],
+    headers: &[(Box<[u8]>, Box<[u8]>)],
+    content_length: usize,
+    is_chunked: bool,
+    initial_body: &[u8],
+    client_wants_close: bool,
+    tls_insecure: bool,
+    pool_key: &str,
+) -> Result<Option<(ServerTls, u16, u64, bool)>, (ServerTls, Option<Vec<u8>>)> {
+    let raw_body =
+        match read_request_body_full(&mut client_stream, content_length, is_chunked, initial_body)
+            .await
+        {
+            Some(body) => body,
+            None => {
+                let _ = timeout(
+                    WRITE_TIMEOUT,
+                    client_stream.write_all(ERR_MSG_REQUEST_TOO_LARGE.to_vec()),
+                )
+                .await;
+                return Ok(Some((client_stream, 413, 0, true)));
+            }
+        };
+    let body = if is_chunked {
+        decode_chunked_body(&raw_body)
+    } else {
+        raw_body.clone()
+    };
+    let mut request = http2::H2MuxRequest {
+        method: method.to_vec(),
+        path: path.to_vec(),
+        authority: target.sni().as_bytes().to_vec(),
+        headers: headers
+            .iter()
+            .filter(|(k, _)| !security.is_removed_request_header(k, extra))
+            .map(|(k, v)| (k.to_vec(), v.to_vec()))
+            .collect(),
+        body,
+        max_response_body: target.max_response_body_bytes,
+    };
+    request.headers.extend(extra.request_header_pairs());
+    apply_grpc_timeout(&mut request.headers, security);
+    let connect_timeout = Duration::from_secs(security.backend_connect_timeout_secs);
+    let response = match h2_tls_upstream_request(
+        target,
+        tls_insecure,
+        connect_timeout,
+        security.upstream_timeouts(),
+        pool_key,
+        request,
+    )
+    .await
+    {
+        H2UpstreamOutcome::Response(response) => response,
+        H2UpstreamOutcome::Http1 => return Err((client_stream, Some(raw_body))),
+        H2UpstreamOutcome::Failed(status) => {
+            let err_buf = if status == 504 {
+                ERR_MSG_GATEWAY_TIMEOUT.to_vec()
+            } else {
+                ERR_MSG_BAD_GATEWAY.to_vec()
+            };
+            let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
+            return Ok(Some((client_stream, status, 0, true)));
+        }
+    };
+
+    let status_code = response.status;
+    let http11_response = multiplexed_response_to_http11(
+        response.status,
+        &response.headers,
+        &response.trailers,
+        &response.body,
+        security,
+        extra,
+        client_wants_close,
+    );
+    let resp_size = http11_response.len() as u64;
+    let write_result = timeout(WRITE_TIMEOUT, client_stream.write_all(http11_response)).await;
+    if !matches!(write_result, Ok((Ok(_), _))) {
+        return Ok(None);
+    }
+    Ok(Some((
+        client_stream,
+        status_code,
+        resp_size,
+        client_wants_close,
+    )))
+}
+
+/// F-135: HTTP/3 上流へ HTTP/1.1 クライアントのリクエストを中継する。
+///
+/// ボディは全量読み込んでから送る（chunked はデコードする）。UDP 不通で TCP へ
+/// フォールバックする場合は、読み込んだボディ（転送フレーミングそのまま）とともに
+/// クライアントストリームを返し、呼び出し側が HTTPS（HTTP/1.1）経路で処理する。
+#[cfg(feature = "http3")]
+async fn proxy_h3_tls(
+    mut client_stream: ServerTls,
+    target: &ProxyTarget,
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
+    method: &[u8],
+    path: &[u8],
+    headers: &[(Box<[u8]>, Box<[u8]>)],
+    content_length: usize,
+    is_chunked: bool,
+    initial_body: &[u8],
+    client_wants_close: bool,
+    tls_insecure: bool,
+    pool_key: &str,
+) -> Result<Option<(ServerTls, u16, u64, bool)>, (ServerTls, Option<Vec<u8>>)> {
+    let raw_body =
+        match read_request_body_full(&mut client_stream, content_length, is_chunked, initial_body)
+            .await
+        {
+            Some(body) => body,
+            None => {
+                let _ = timeout(
+                    WRITE_TIMEOUT,
+                    client_stream.write_all(ERR_MSG_REQUEST_TOO_LARGE.to_vec()),
+                )
+                .await;
+                return Ok(Some((client_stream, 413, 0, true)));
+            }
+        };
+    let body = if is_chunked {
+        decode_chunked_body(&raw_body)
+    } else {
+        raw_body.clone()
+    };
+    let mut request = crate::http3_client::H3Request {
+        method: method.to_vec(),
+        path: path.to_vec(),
+        authority: target.sni().as_bytes().to_vec(),
+        headers: headers
+            .iter()
+            .filter(|(k, _)| !security.is_removed_request_header(k, extra))
+            .map(|(k, v)| (k.to_vec(), v.to_vec()))
+            .collect(),
+        body,
+        max_response_body: target.max_response_body_bytes,
+    };
+    request.headers.extend(extra.request_header_pairs());
+    apply_grpc_timeout(&mut request.headers, security);
+    let connect_timeout = Duration::from_secs(security.backend_connect_timeout_secs);
+    let response = match h3_upstream_request(
+        target,
+        tls_insecure,
+        connect_timeout,
+        security.upstream_timeouts(),
+        pool_key,
+        request,
+    )
+    .await
+    {
+        H3UpstreamOutcome::Response(response) => response,
+        H3UpstreamOutcome::Fallback => return Err((client_stream, Some(raw_body))),
+        H3UpstreamOutcome::Failed(status) => {
+            let err_buf = if status == 504 {
+                ERR_MSG_GATEWAY_TIMEOUT.to_vec()
+            } else {
+                ERR_MSG_BAD_GATEWAY.to_vec()
+            };
+            let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(err_buf)).await;
+            return Ok(Some((client_stream, status, 0, true)));
+        }
+    };
 
+    let status_code = response.status_code;
+    let http11_response = multiplexed_response_to_http11(
+        response.status_code,
+        &response.headers,
+        &response.trailers,
+        &response.body,
+        security,
+        extra,
+        client_wants_close,
+    );
     let resp_size = http11_response.len() as u64;
-
-    // クライアントに送信
     let write_result = timeout(WRITE_TIMEOUT, client_stream.write_all(http11_response)).await;
     if !matches!(write_result, Ok((Ok(_), _))) {
-        return None;
+        return Ok(None);
     }
+    Ok(Some((
+        client_stream,
+        status_code,
+        resp_size,
+        client_wants_close,
+    )))
+}
 
-    Some((client_stream, status_code, resp_size, client_wants_close))
+/// リクエストボディをソケットから全量読み込む（転送フレーミングはそのまま、F-134 / F-135）
+///
+/// `MAX_BODY_SIZE` を超える場合や読み込みに失敗した場合は None。
+#[cfg(any(feature = "http2", feature = "http3"))]
+async fn read_request_body_full(
+    stream: &mut ServerTls,
+    content_length: usize,
+    is_chunked: bool,
+    initial_body: &[u8],
+) -> Option<Vec<u8>> {
+    let mut body = initial_body.to_vec();
+    if is_chunked {
+        let mut decoder = ChunkedDecoder::new(MAX_BODY_SIZE as u64);
+        match decoder.feed(initial_body) {
+            ChunkedFeedResult::Complete => return Some(body),
+            ChunkedFeedResult::SizeLimitExceeded => return None,
+            ChunkedFeedResult::Continue => {}
+        }
+        loop {
+            let buf = buf_get();
+            match timeout(READ_TIMEOUT, stream.read(buf)).await {
+                Ok((Ok(n), mut b)) if n > 0 => {
+                    b.set_valid_len(n);
+                    body.extend_from_slice(b.as_valid_slice());
+                    let res = decoder.feed(b.as_valid_slice());
+                    buf_put(b);
+                    match res {
+                        ChunkedFeedResult::Complete => return Some(body),
+                        ChunkedFeedResult::SizeLimitExceeded => return None,
+                        ChunkedFeedResult::Continue => {}
+                    }
+                }
+                Ok((_, b)) => {
+                    buf_put(b);
+                    return None;
+                }
+                Err(_) => return None,
+            }
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        return None;
+    }
+    body.truncate(content_length);
+    while body.len() < content_length {
+        let buf = buf_get();
+        match timeout(READ_TIMEOUT, stream.read(buf)).await {
+            Ok((Ok(n), mut b)) if n > 0 => {
+                b.set_valid_len(n);
+                let take = n.min(content_length - body.len());
+                body.extend_from_slice(&b.as_valid_slice()[..take]);
+                buf_put(b);
+            }
+            Ok((_, b)) => {
+                buf_put(b);
+                return None;
+            }
+            Err(_) => return None,
+        }
+    }
+    Some(body)
 }
 
 /// ディスクキャッシュからレスポンスを提供
@@ -7027,6 +9811,8 @@ async fn proxy_h2c(
 async fn serve_from_disk_cache(
     client_stream: &mut ServerTls,
     cached_entry: &cache::CacheEntry,
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
     disk_path: &std::path::Path,
     client_wants_close: bool,
     is_stale: bool,
@@ -7046,7 +9832,14 @@ async fn serve_from_disk_cache(
         };
 
     // レスポンスを構築
-    let response = build_cached_response(cached_entry, &body_data, client_wants_close, is_stale);
+    let response = build_cached_response(
+        cached_entry,
+        security,
+        extra,
+        &body_data,
+        client_wants_close,
+        is_stale,
+    );
 
     match timeout(WRITE_TIMEOUT, client_stream.write_all(response)).await {
         Ok((Ok(_), _)) => Some((cached_entry.status_code, body_data.len() as u64)),
@@ -7087,7 +9880,7 @@ impl BufferedBodyResult {}
 /// バックエンドからレスポンス全体を受信してバッファに格納し、
 /// バックエンド接続を解放してからクライアントへ送信します。
 ///
-/// 戻り値: Option<(status_code, response_size, backend_wants_keep_alive)>
+/// 戻り値: Option<(status_code, response_size, backend_keep_alive)>
 /// バッファリング転送でリクエストを処理
 async fn proxy_request_buffered<R>(
     client_stream: &mut ServerTls,
@@ -7100,7 +9893,8 @@ async fn proxy_request_buffered<R>(
     buffering_config: &buffering::BufferingConfig,
     cache_ctx: Option<&mut CacheSaveContext>,
     security: &SecurityConfig,
-) -> Option<(u16, u64, bool, bool)>
+    extra: &ExtraHeaders,
+) -> Option<(u16, u64, BackendKeepAlive, bool)>
 where
     R: AsyncReader
         + AsyncWriter
@@ -7143,7 +9937,7 @@ where
             let body_timeout = Duration::from_secs(security.client_body_timeout_secs);
             match timeout(
                 body_timeout,
-                transfer_exact_bytes(client_stream, backend_stream, remaining),
+                transfer_exact_bytes(client_stream, backend_stream, remaining, || READ_TIMEOUT),
             )
             .await
             {
@@ -7153,17 +9947,25 @@ where
                     let _ = client_stream
                         .write_all(ERR_MSG_REQUEST_TIMEOUT.to_vec())
                         .await;
-                    return Some((408, 0, false, true));
+                    return Some((408, 0, BackendKeepAlive::Close, true));
                 }
             }
         }
     }
 
     // 4. レスポンスを受信してバッファリング
-    let buffered = receive_and_buffer_response(backend_stream, buffering_config, cache_ctx).await;
+    let buffered = receive_and_buffer_response(
+        backend_stream,
+        buffering_config,
+        cache_ctx,
+        security.upstream_timeouts(),
+    )
+    .await;
 
     match buffered {
-        Some((status_code, headers_data, body_result, backend_wants_keep_alive)) => {
+        Ok((status_code, mut headers_data, body_result, backend_keep_alive)) => {
+            // バッファ経路でも add_response_headers を適用する（F-132 の Set-Cookie を含む）
+            append_security_response_headers(&mut headers_data, security, extra);
             // B-17: ボディのバッファリングに失敗した場合、クライアントへは未送信のため
             // ヘッダーだけ送って（CL 分のボディを待たせて）ハングさせず、
             // None → 呼び出し元の 502 送出 + クローズに委ねる
@@ -7205,7 +10007,7 @@ where
 
                         if !matches!(write_result, Ok((Ok(_), _))) {
                             let _ = crate::runtime::io::remove_file(&path).await;
-                            return Some((status_code, 0, false, true));
+                            return Some((status_code, 0, BackendKeepAlive::Close, true));
                         }
 
                         total = headers_len as u64;
@@ -7224,7 +10026,7 @@ where
                             }
                             None => {
                                 let _ = crate::runtime::io::remove_file(&path).await;
-                                return Some((status_code, total, false, true));
+                                return Some((status_code, total, BackendKeepAlive::Close, true));
                             }
                         }
                         let _ = crate::runtime::io::remove_file(&path).await;
@@ -7240,7 +10042,7 @@ where
                         if matches!(write_result, Ok((Ok(_), _))) {
                             total = headers_len as u64;
                         }
-                        return Some((status_code, total, false, true));
+                        return Some((status_code, total, BackendKeepAlive::Close, true));
                     }
                     BufferedBodyResult::LimitExceeded => {
                         // 507 Insufficient Storage を送信
@@ -7251,7 +10053,7 @@ where
                         )
                         .await;
                         // 507 エラー時は接続を閉じる (should_close = true, backend keep-alive = false)
-                        return Some((507, 0, false, true));
+                        return Some((507, 0, BackendKeepAlive::Close, true));
                     }
                 }
             } else {
@@ -7269,7 +10071,7 @@ where
                     if let BufferedBodyResult::Disk { ref path, .. } = body_result {
                         let _ = crate::runtime::io::remove_file(path).await;
                     }
-                    return Some((status_code, 0, false, true));
+                    return Some((status_code, 0, BackendKeepAlive::Close, true));
                 }
 
                 total = headers_len as u64;
@@ -7286,7 +10088,7 @@ where
                             .await;
 
                             if !matches!(write_result, Ok((Ok(_), _))) {
-                                return Some((status_code, total, false, true));
+                                return Some((status_code, total, BackendKeepAlive::Close, true));
                             }
 
                             total += body_len as u64;
@@ -7307,59 +10109,133 @@ where
                             }
                             None => {
                                 let _ = crate::runtime::io::remove_file(&path).await;
-                                return Some((status_code, total, false, true));
+                                return Some((status_code, total, BackendKeepAlive::Close, true));
                             }
                         }
                         let _ = crate::runtime::io::remove_file(&path).await;
                     }
                     BufferedBodyResult::Failed => {
-                        return Some((status_code, total, false, true));
+                        return Some((status_code, total, BackendKeepAlive::Close, true));
                     }
                     BufferedBodyResult::LimitExceeded => {
                         // すでにヘッダー送信済みのため、507を返すことはできないので接続を閉じる
-                        return Some((status_code, total, true, true));
+                        return Some((status_code, total, BackendKeepAlive::Reuse(None), true));
                     }
                 }
             }
 
-            Some((status_code, total, backend_wants_keep_alive, false))
+            Some((status_code, total, backend_keep_alive, false))
+        }
+        // F-139: 応答ヘッダー待ちの超過は 504（クライアントへは未送信）
+        Err(504) => Some((504, 0, BackendKeepAlive::Close, true)),
+        Err(_) => None,
+    }
+}
+
+/// HTTP/2・HTTP/3 の応答ヘッダーへルートのレスポンスヘッダー操作（削除 → 追加）を適用する
+///
+/// HTTP/1.1 の中継と同じく `remove_response_headers` を上流のヘッダーから除いてから
+/// `add_response_headers` を小文字の名前で加える（F-155 の CORS ヘッダーを含む）。
+#[cfg(any(feature = "http2", feature = "http3"))]
+pub(crate) fn apply_h2_security_response_headers(
+    headers: &mut Vec<(Vec<u8>, Vec<u8>)>,
+    security: &SecurityConfig,
+) {
+    if !security.remove_response_headers.is_empty() {
+        headers.retain(|(name, _)| {
+            !security
+                .remove_response_headers
+                .iter()
+                .any(|r| name.eq_ignore_ascii_case(r.as_bytes()))
+        });
+    }
+    for (name, value) in &security.add_response_headers {
+        headers.push((
+            name.to_ascii_lowercase().into_bytes(),
+            value.as_bytes().to_vec(),
+        ));
+    }
+}
+
+/// HTTP/1.1 レスポンスヘッダーブロック（末尾 `\r\n\r\n`）へ add_response_headers と
+/// リクエスト単位の応答ヘッダーを挿入する
+///
+/// remove_response_headers に一致する上流のヘッダー行は先に除く（F-155）。
+fn append_security_response_headers(
+    head: &mut Vec<u8>,
+    security: &SecurityConfig,
+    extra: &ExtraHeaders,
+) {
+    if (security.add_response_headers.is_empty()
+        && security.remove_response_headers.is_empty()
+        && extra.response.is_empty())
+        || !head.ends_with(b"\r\n\r\n")
+    {
+        return;
+    }
+    head.truncate(head.len() - 2);
+    if !security.remove_response_headers.is_empty() {
+        let mut kept = Vec::with_capacity(head.len());
+        for (i, line) in head.split_inclusive(|&b| b == b'\n').enumerate() {
+            let name = line.split(|&b| b == b':').next().unwrap_or(b"");
+            let removed = i > 0
+                && security
+                    .remove_response_headers
+                    .iter()
+                    .any(|r| name.trim_ascii().eq_ignore_ascii_case(r.as_bytes()));
+            if !removed {
+                kept.extend_from_slice(line);
+            }
         }
-        None => None,
+        *head = kept;
+    }
+    for (name, value) in security.response_headers(extra) {
+        head.extend_from_slice(name.as_bytes());
+        head.extend_from_slice(b": ");
+        head.extend_from_slice(value.as_bytes());
+        head.extend_from_slice(b"\r\n");
     }
+    head.extend_from_slice(b"\r\n");
 }
 
 /// バックエンドからレスポンスを受信してバッファリング
 ///
-/// 戻り値: Option<(status_code, headers_data, body_result, backend_wants_keep_alive)>
+/// 戻り値: Option<(status_code, headers_data, body_result, backend_keep_alive)>
 async fn receive_and_buffer_response<R>(
     backend_stream: &mut R,
     buffering_config: &buffering::BufferingConfig,
     mut cache_ctx: Option<&mut CacheSaveContext>,
-) -> Option<(u16, Vec<u8>, BufferedBodyResult, bool)>
+    timeouts: UpstreamTimeouts,
+) -> Result<(u16, Vec<u8>, BufferedBodyResult, BackendKeepAlive), u16>
 where
     R: AsyncReadRent + Unpin,
 {
     let mut accumulated = Vec::with_capacity(BUF_SIZE);
+    let header_deadline = timeouts.first_try_deadline(Instant::now());
 
-    // ヘッダー読み取り
+    // ヘッダー読み取り（F-139: 超過は 504、それ以外の失敗は 502）
     loop {
         let read_buf = buf_get();
-        let read_result = timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+        let read_result = timeout(
+            UpstreamTimeouts::until(header_deadline),
+            backend_stream.read(read_buf),
+        )
+        .await;
 
         let (res, mut returned_buf) = match read_result {
             Ok(result) => result,
-            Err(_) => return None,
+            Err(_) => return Err(504),
         };
 
         let n = match res {
             Ok(0) => {
                 buf_put(returned_buf);
-                return None;
+                return Err(502);
             }
             Ok(n) => n,
             Err(_) => {
                 buf_put(returned_buf);
-                return None;
+                return Err(502);
             }
         };
 
@@ -7373,7 +10249,7 @@ where
         // ヘッダーが完全に受信されたかチェック
         if let Some(parsed) = parse_http_response(&accumulated) {
             let status_code = parsed.status_code;
-            let backend_wants_keep_alive = !parsed.is_connection_close;
+            let backend_keep_alive = parsed.keep_alive();
 
             let header_len = parsed.header_len;
             let body_start = accumulated[header_len..].to_vec();
@@ -7401,20 +10277,16 @@ where
                 body_start,
                 buffering_config,
                 cache_ctx,
+                timeouts,
             )
             .await;
 
-            return Some((
-                status_code,
-                headers_data,
-                body_result,
-                backend_wants_keep_alive,
-            ));
+            return Ok((status_code, headers_data, body_result, backend_keep_alive));
         }
 
         // ヘッダーが大きすぎる場合は中止
         if accumulated.len() > MAX_HEADER_SIZE {
-            return None;
+            return Err(502);
         }
     }
 }
@@ -7459,6 +10331,7 @@ async fn buffer_response_body_with_spillover<R>(
     initial_body: Vec<u8>,
     buffering_config: &buffering::BufferingConfig,
     mut cache_ctx: Option<&mut CacheSaveContext>,
+    timeouts: UpstreamTimeouts,
 ) -> BufferedBodyResult
 where
     R: AsyncReadRent + Unpin,
@@ -7500,7 +10373,8 @@ where
                 // まず残りのデータをメモリに読み込み
                 if remaining > 0 {
                     let additional =
-                        buffer_exact_bytes(backend_stream, remaining, &mut cache_ctx).await;
+                        buffer_exact_bytes(backend_stream, remaining, &mut cache_ctx, timeouts)
+                            .await;
                     body.extend(additional);
                 }
 
@@ -7530,9 +10404,13 @@ where
                         .max_memory_buffer
                         .saturating_sub(body.len());
                     if max_additional > 0 {
-                        let additional =
-                            buffer_exact_bytes(backend_stream, max_additional, &mut cache_ctx)
-                                .await;
+                        let additional = buffer_exact_bytes(
+                            backend_stream,
+                            max_additional,
+                            &mut cache_ctx,
+                            timeouts,
+                        )
+                        .await;
                         body.extend(additional);
                     }
                 }
@@ -7542,7 +10420,7 @@ where
             // メモリ制限内
             if remaining > 0 {
                 let additional =
-                    buffer_exact_bytes(backend_stream, remaining, &mut cache_ctx).await;
+                    buffer_exact_bytes(backend_stream, remaining, &mut cache_ctx, timeouts).await;
                 body.extend(additional);
             }
         }
@@ -7584,7 +10462,7 @@ where
 
                         let read_buf = buf_get();
                         let read_result =
-                            timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+                            timeout(timeouts.read(), backend_stream.read(read_buf)).await;
 
                         let (res, mut returned_buf) = match read_result {
                             Ok(result) => result,
@@ -7654,7 +10532,7 @@ where
             }
 
             let read_buf = buf_get();
-            let read_result = timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+            let read_result = timeout(timeouts.read(), backend_stream.read(read_buf)).await;
 
             let (res, mut returned_buf) = match read_result {
                 Ok(result) => result,
@@ -7712,7 +10590,7 @@ where
 
                         let read_buf = buf_get();
                         let read_result =
-                            timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+                            timeout(timeouts.read(), backend_stream.read(read_buf)).await;
                         let (res, mut returned_buf) = match read_result {
                             Ok(result) => result,
                             Err(_) => break,
@@ -7772,7 +10650,7 @@ where
             }
 
             let read_buf = buf_get();
-            let read_result = timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+            let read_result = timeout(timeouts.read(), backend_stream.read(read_buf)).await;
             let (res, mut returned_buf) = match read_result {
                 Ok(result) => result,
                 Err(_) => break,
@@ -7813,6 +10691,7 @@ async fn buffer_exact_bytes<R>(
     backend_stream: &mut R,
     mut remaining: usize,
     cache_ctx: &mut Option<&mut CacheSaveContext>,
+    timeouts: UpstreamTimeouts,
 ) -> Vec<u8>
 where
     R: AsyncReadRent + Unpin,
@@ -7821,7 +10700,7 @@ where
 
     while remaining > 0 {
         let read_buf = buf_get();
-        let read_result = timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+        let read_result = timeout(timeouts.read(), backend_stream.read(read_buf)).await;
 
         let (res, mut returned_buf) = match read_result {
             Ok(r) => r,
@@ -7872,7 +10751,7 @@ where
 // ====================
 
 /// HTTPリクエストを送信してレスポンスを受信（圧縮対応版）
-/// 戻り値: Option<(status_code, response_size, backend_wants_keep_alive)>
+/// 戻り値: Option<(status_code, response_size, backend_keep_alive)>
 async fn proxy_http_request_with_compression(
     client_stream: &mut ServerTls,
     backend_stream: &mut TcpStream,
@@ -7885,8 +10764,9 @@ async fn proxy_http_request_with_compression(
     client_encoding: AcceptedEncoding,
     cache_ctx: Option<&mut CacheSaveContext>,
     security: &SecurityConfig,
+    extra: &ExtraHeaders,
     wasm_modules: Arc<Vec<String>>,
-) -> Option<(u16, u64, bool, bool)> {
+) -> Option<(u16, u64, BackendKeepAlive, bool)> {
     // 1. リクエストヘッダー送信（タイムアウト付き）
     let write_result = timeout(WRITE_TIMEOUT, backend_stream.write_all(request)).await;
     if !matches!(write_result, Ok((Ok(_), _))) {
@@ -7926,7 +10806,7 @@ async fn proxy_http_request_with_compression(
             let body_timeout = Duration::from_secs(security.client_body_timeout_secs);
             match timeout(
                 body_timeout,
-                transfer_exact_bytes(client_stream, backend_stream, remaining),
+                transfer_exact_bytes(client_stream, backend_stream, remaining, || READ_TIMEOUT),
             )
             .await
             {
@@ -7936,14 +10816,14 @@ async fn proxy_http_request_with_compression(
                     let _ = client_stream
                         .write_all(ERR_MSG_REQUEST_TIMEOUT.to_vec())
                         .await;
-                    return Some((408, 0, false, true));
+                    return Some((408, 0, BackendKeepAlive::Close, true));
                 }
             }
         }
     }
 
     // 4. レスポンスを受信して転送（圧縮対応、キャッシュ保存対応）
-    let (total, status_code, backend_wants_keep_alive, client_must_close) =
+    let (total, status_code, backend_keep_alive, client_must_close) =
         transfer_response_with_compression(
             backend_stream,
             client_stream,
@@ -7951,16 +10831,12 @@ async fn proxy_http_request_with_compression(
             client_encoding,
             cache_ctx,
             security,
+            extra,
             wasm_modules,
         )
         .await;
 
-    Some((
-        status_code,
-        total,
-        backend_wants_keep_alive,
-        client_must_close,
-    ))
+    Some((status_code, total, backend_keep_alive, client_must_close))
 }
 
 // ====================
@@ -7970,7 +10846,7 @@ async fn proxy_http_request_with_compression(
 /// レスポンスヘッダーを解析し、必要に応じて圧縮してクライアントに転送
 /// キャッシュコンテキストが指定されている場合、レスポンスボディをキャプチャしてキャッシュに保存
 ///
-/// 戻り値: (転送バイト数, ステータス, backend_wants_keep_alive, client_must_close)
+/// 戻り値: (転送バイト数, ステータス, backend_keep_alive, client_must_close)
 /// `client_must_close` は B-17: 上流異常（ヘッダー不完全・CL 未達 EOF 等）でクライアント
 /// 接続を即時クローズすべき場合に true。
 #[cfg_attr(not(feature = "wasm"), allow(unused_variables))]
@@ -7981,25 +10857,33 @@ async fn transfer_response_with_compression(
     client_encoding: AcceptedEncoding,
     mut cache_ctx: Option<&mut CacheSaveContext>,
     security: &SecurityConfig,
+    extra: &ExtraHeaders,
     wasm_modules: Arc<Vec<String>>,
-) -> (u64, u16, bool, bool) {
+) -> (u64, u16, BackendKeepAlive, bool) {
     let mut accumulated = Vec::with_capacity(BUF_SIZE);
     let mut total = 0u64;
     let mut status_code = 502u16;
     // 初期値false: エラー時はKeep-Aliveを無効化
-    let mut backend_wants_keep_alive = false;
+    let mut backend_keep_alive = BackendKeepAlive::Close;
+    // F-139: ルートの応答ヘッダー待ち・試行ごとのタイムアウトとリクエスト期限
+    let timeouts = security.upstream_timeouts();
+    let header_deadline = timeouts.first_try_deadline(Instant::now());
 
     // ヘッダー読み取り用バッファ
     loop {
         // B-17: ヘッダー読取は専用の短いタイムアウトで打ち切り、504 へ即変換する
         let read_buf = buf_get();
-        let read_result = timeout(BACKEND_HEADER_TIMEOUT, backend_stream.read(read_buf)).await;
+        let read_result = timeout(
+            UpstreamTimeouts::until(header_deadline),
+            backend_stream.read(read_buf),
+        )
+        .await;
 
         let (res, mut returned_buf) = match read_result {
             Ok(result) => result,
             Err(_) => {
                 warn!("Backend response header read timeout");
-                return (total, 504, false, true);
+                return (total, 504, BackendKeepAlive::Close, true);
             }
         };
 
@@ -8007,12 +10891,12 @@ async fn transfer_response_with_compression(
             Ok(0) => {
                 buf_put(returned_buf);
                 // B-17: ヘッダー完了前の EOF は 502 として即時応答する
-                return (total, status_code, backend_wants_keep_alive, true);
+                return (total, status_code, backend_keep_alive, true);
             }
             Ok(n) => n,
             Err(_) => {
                 buf_put(returned_buf);
-                return (total, status_code, backend_wants_keep_alive, true);
+                return (total, status_code, backend_keep_alive, true);
             }
         };
 
@@ -8027,7 +10911,7 @@ async fn transfer_response_with_compression(
         // ヘッダーが完全に受信されたかチェック
         if let Some(parsed) = parse_http_response(&accumulated) {
             status_code = parsed.status_code;
-            backend_wants_keep_alive = !parsed.is_connection_close;
+            backend_keep_alive = parsed.keep_alive();
 
             let header_len = parsed.header_len;
             let body_start = &accumulated[header_len..];
@@ -8061,8 +10945,9 @@ async fn transfer_response_with_compression(
                     parsed.is_chunked,
                     encoding,
                     compression,
-                    backend_wants_keep_alive,
+                    backend_keep_alive,
                     security,
+                    extra,
                 )
                 .await;
 
@@ -8125,7 +11010,7 @@ async fn transfer_response_with_compression(
                     }
 
                     // 追加するヘッダーを追加
-                    for (header_name, header_value) in &security.add_response_headers {
+                    for (header_name, header_value) in security.response_headers(extra) {
                         new_header_lines
                             .push(format!("{}: {}\r\n", header_name, header_value).into_bytes());
                     }
@@ -8230,7 +11115,7 @@ async fn transfer_response_with_compression(
                 )
                 .await;
                 if !matches!(write_result, Ok((Ok(()), _, _))) {
-                    return (total, status_code, false, true);
+                    return (total, status_code, BackendKeepAlive::Close, true);
                 }
                 total += header_len as u64;
                 total += body_start.len() as u64;
@@ -8253,6 +11138,7 @@ async fn transfer_response_with_compression(
                         parsed.is_chunked,
                         body_start,
                         cache_ctx,
+                        timeouts,
                     )
                     .await;
                     total += transferred;
@@ -8264,11 +11150,11 @@ async fn transfer_response_with_compression(
                             "Backend response body incomplete: {} < {}",
                             transferred, body_remaining
                         );
-                        return (total, status_code, false, true);
+                        return (total, status_code, BackendKeepAlive::Close, true);
                     }
                 }
 
-                return (total, status_code, backend_wants_keep_alive, false);
+                return (total, status_code, backend_keep_alive, false);
             }
         }
 
@@ -8278,13 +11164,13 @@ async fn transfer_response_with_compression(
                 "Backend response header too large (> {} bytes)",
                 MAX_RESPONSE_HEADER_SIZE
             );
-            return (0, 502, false, true);
+            return (0, 502, BackendKeepAlive::Close, true);
         }
     }
 }
 
 /// 圧縮してレスポンスを転送
-/// 戻り値: (転送バイト数, backend_wants_keep_alive)
+/// 戻り値: (転送バイト数, backend_keep_alive)
 #[cfg(feature = "compression")]
 async fn transfer_compressed_response(
     client_stream: &mut ServerTls,
@@ -8295,9 +11181,12 @@ async fn transfer_compressed_response(
     is_chunked: bool,
     encoding: AcceptedEncoding,
     compression: &CompressionConfig,
-    backend_wants_keep_alive: bool,
+    backend_keep_alive: BackendKeepAlive,
     security: &SecurityConfig,
-) -> (u64, bool) {
+    extra: &ExtraHeaders,
+) -> (u64, BackendKeepAlive) {
+    // F-139: 読み取りはルートのアイドル上限とリクエスト期限で打ち切る
+    let timeouts = security.upstream_timeouts();
     use flate2::write::GzEncoder;
     use flate2::Compression;
     use std::io::Write;
@@ -8313,12 +11202,12 @@ async fn transfer_compressed_response(
             let mut remaining_to_read = remaining;
             while remaining_to_read > 0 {
                 let read_buf = buf_get();
-                let read_result = timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+                let read_result = timeout(timeouts.read(), backend_stream.read(read_buf)).await;
 
                 let (res, mut returned_buf) = match read_result {
                     Ok(result) => result,
                     Err(_) => {
-                        return (total, false);
+                        return (total, BackendKeepAlive::Close);
                     }
                 };
 
@@ -8330,7 +11219,7 @@ async fn transfer_compressed_response(
                     Ok(n) => n.min(remaining_to_read),
                     Err(_) => {
                         buf_put(returned_buf);
-                        return (total, false);
+                        return (total, BackendKeepAlive::Close);
                     }
                 };
 
@@ -8352,7 +11241,7 @@ async fn transfer_compressed_response(
             // 残りを読み取り
             loop {
                 let read_buf = buf_get();
-                let read_result = timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+                let read_result = timeout(timeouts.read(), backend_stream.read(read_buf)).await;
 
                 let (res, mut returned_buf) = match read_result {
                     Ok(result) => result,
@@ -8461,14 +11350,19 @@ async fn transfer_compressed_response(
     };
 
     // 3. 新しいヘッダーを構築
-    let new_headers =
-        build_compressed_headers(original_headers, encoding, compressed_body.len(), security);
+    let new_headers = build_compressed_headers(
+        original_headers,
+        encoding,
+        compressed_body.len(),
+        security,
+        extra,
+    );
 
     // 4. ヘッダー送信（所有権を移動、clone 不要）
     let new_headers_len = new_headers.len();
     let write_result = timeout(WRITE_TIMEOUT, client_stream.write_all(new_headers)).await;
     if !matches!(write_result, Ok((Ok(_), _))) {
-        return (total, false);
+        return (total, BackendKeepAlive::Close);
     }
     total += new_headers_len as u64;
 
@@ -8476,11 +11370,11 @@ async fn transfer_compressed_response(
     let compressed_len = compressed_body.len();
     let write_result = timeout(WRITE_TIMEOUT, client_stream.write_all(compressed_body)).await;
     if !matches!(write_result, Ok((Ok(_), _))) {
-        return (total, false);
+        return (total, BackendKeepAlive::Close);
     }
     total += compressed_len as u64;
 
-    (total, backend_wants_keep_alive)
+    (total, backend_keep_alive)
 }
 
 /// compression feature 無効時のスタブ
@@ -8494,9 +11388,10 @@ async fn transfer_compressed_response(
     _is_chunked: bool,
     _encoding: AcceptedEncoding,
     _compression: &CompressionConfig,
-    _backend_wants_keep_alive: bool,
+    _backend_keep_alive: BackendKeepAlive,
     _security: &SecurityConfig,
-) -> (u64, bool) {
+    _extra: &ExtraHeaders,
+) -> (u64, BackendKeepAlive) {
     transfer_uncompressed_fallback(client_stream, original_headers, initial_body).await
 }
 
@@ -8505,7 +11400,7 @@ async fn transfer_uncompressed_fallback(
     client_stream: &mut ServerTls,
     original_headers: &[u8],
     body_data: &[u8],
-) -> (u64, bool) {
+) -> (u64, BackendKeepAlive) {
     let mut total = 0u64;
 
     // ヘッダー送信（monoio は所有権を要求するため to_vec、clone は不要）
@@ -8516,7 +11411,7 @@ async fn transfer_uncompressed_fallback(
     )
     .await;
     if !matches!(write_result, Ok((Ok(_), _))) {
-        return (total, false);
+        return (total, BackendKeepAlive::Close);
     }
     total += headers_len as u64;
 
@@ -8524,11 +11419,11 @@ async fn transfer_uncompressed_fallback(
     let body_len = body_data.len();
     let write_result = timeout(WRITE_TIMEOUT, client_stream.write_all(body_data.to_vec())).await;
     if !matches!(write_result, Ok((Ok(_), _))) {
-        return (total, false);
+        return (total, BackendKeepAlive::Close);
     }
     total += body_len as u64;
 
-    (total, true)
+    (total, BackendKeepAlive::Reuse(None))
 }
 
 /// 圧縮用にヘッダーを書き換え
@@ -8539,6 +11434,7 @@ fn build_compressed_headers(
     encoding: AcceptedEncoding,
     compressed_length: usize,
     security: &SecurityConfig,
+    extra: &ExtraHeaders,
 ) -> Vec<u8> {
     let mut headers_storage = [httparse::EMPTY_HEADER; 64];
     let mut response = httparse::Response::new(&mut headers_storage);
@@ -8596,7 +11492,7 @@ fn build_compressed_headers(
     new_headers.extend_from_slice(b"Vary: Accept-Encoding\r\n");
 
     // 追加するヘッダーを追加
-    for (header_name, header_value) in &security.add_response_headers {
+    for (header_name, header_value) in security.response_headers(extra) {
         new_headers.extend_from_slice(header_name.as_bytes());
         new_headers.extend_from_slice(b": ");
         new_headers.extend_from_slice(header_value.as_bytes());
@@ -8621,6 +11517,7 @@ async fn transfer_response_body_with_cache(
     is_chunked: bool,
     initial_body: &[u8],
     mut cache_ctx: Option<&mut CacheSaveContext>,
+    timeouts: UpstreamTimeouts,
 ) -> u64 {
     let mut total = 0u64;
 
@@ -8632,6 +11529,7 @@ async fn transfer_response_body_with_cache(
                 client_stream,
                 remaining,
                 cache_ctx,
+                timeouts,
             )
             .await;
             total += transferred;
@@ -8651,7 +11549,7 @@ async fn transfer_response_body_with_cache(
 
         loop {
             let read_buf = buf_get();
-            let read_result = timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+            let read_result = timeout(timeouts.read(), backend_stream.read(read_buf)).await;
 
             let (res, mut returned_buf) = match read_result {
                 Ok(result) => result,
@@ -8709,12 +11607,13 @@ async fn transfer_exact_bytes_from_backend_with_cache(
     client_stream: &mut ServerTls,
     mut remaining: usize,
     mut cache_ctx: Option<&mut CacheSaveContext>,
+    timeouts: UpstreamTimeouts,
 ) -> u64 {
     let mut total = 0u64;
 
     while remaining > 0 {
         let read_buf = buf_get();
-        let read_result = timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+        let read_result = timeout(timeouts.read(), backend_stream.read(read_buf)).await;
 
         let (res, mut returned_buf) = match read_result {
             Ok(result) => result,
@@ -8782,12 +11681,15 @@ async fn transfer_exact_bytes_from_backend_with_cache(
 ///
 /// pipe に取り込んだ n バイトは dst のバックプレッシャに追従して**必ず全量ドレイン**
 /// してから次のチャンクへ進む（pipe 内残データと `remaining` のずれによるデータ損失を防ぐ）。
+///
+/// `read_timeout` は src の読み取り待ちごとに評価する（F-139 のリクエスト期限で縮む）。
 #[cfg(all(veil_ktls, target_os = "linux"))]
 async fn splice_body_transfer(
     src_stream: &TcpStream,
     dst_stream: &TcpStream,
     pipe: &SplicePipe,
     mut remaining: usize,
+    read_timeout: impl Fn() -> Duration,
 ) -> u64 {
     use crate::runtime::splice::{splice as iouring_splice, splice_more as iouring_splice_more};
     use std::os::unix::io::AsRawFd;
@@ -8814,8 +11716,8 @@ async fn splice_body_transfer(
             Ok(n) => n,
             Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
                 // src にデータが無い → 読み取り可能になるまで待機（POLL_ADD）
-                // B-17: 無応答の上流で永久待機しないよう READ_TIMEOUT で打ち切る
-                match timeout(READ_TIMEOUT, src_stream.readable()).await {
+                // B-17: 無応答の上流で永久待機しないよう read_timeout で打ち切る
+                match timeout(read_timeout(), src_stream.readable()).await {
                     Ok(Ok(())) => {}
                     _ => break,
                 }
@@ -8901,7 +11803,8 @@ async fn try_splice_proxy(
     content_length: usize,
     is_chunked: bool,
     initial_body: &[u8],
-) -> Option<(u16, u64, bool, bool)> {
+    timeouts: UpstreamTimeouts,
+) -> Option<(u16, u64, BackendKeepAlive, bool)> {
     proxy_http_request_splice(
         client_stream,
         backend_stream,
@@ -8909,6 +11812,7 @@ async fn try_splice_proxy(
         content_length,
         is_chunked,
         initial_body,
+        timeouts,
     )
     .await
 }
@@ -8923,7 +11827,8 @@ async fn try_splice_proxy(
     _content_length: usize,
     _is_chunked: bool,
     _initial_body: &[u8],
-) -> Option<(u16, u64, bool, bool)> {
+    _timeouts: UpstreamTimeouts,
+) -> Option<(u16, u64, BackendKeepAlive, bool)> {
     None
 }
 
@@ -8935,7 +11840,8 @@ async fn proxy_http_request_splice(
     content_length: usize,
     is_chunked: bool,
     initial_body: &[u8],
-) -> Option<(u16, u64, bool, bool)> {
+    timeouts: UpstreamTimeouts,
+) -> Option<(u16, u64, BackendKeepAlive, bool)> {
     // 設定に基づいてパイプを取得または作成
     let per_stream_pipe_enabled = {
         let config = CURRENT_CONFIG.load();
@@ -9007,7 +11913,10 @@ async fn proxy_http_request_splice(
         // Content-Length の場合: splice でゼロコピー転送
         // kTLS クライアント → バックエンド TCP
         let transferred =
-            splice_body_transfer(client_tcp, backend_stream, pipe, remaining_body).await;
+            splice_body_transfer(client_tcp, backend_stream, pipe, remaining_body, || {
+                READ_TIMEOUT
+            })
+            .await;
 
         if transferred < remaining_body as u64 {
             warn!(
@@ -9019,7 +11928,7 @@ async fn proxy_http_request_splice(
     }
 
     // 4. レスポンスを受信して転送（splice 使用）
-    let result = splice_transfer_response_ktls(backend_stream, client_stream, pipe).await;
+    let result = splice_transfer_response_ktls(backend_stream, client_stream, pipe, timeouts).await;
 
     Some(result)
 }
@@ -9028,19 +11937,21 @@ async fn proxy_http_request_splice(
 ///
 /// バックエンド(TCP) からヘッダーを読み取り、パースしてクライアント(kTLS)に送信。
 /// ボディは Content-Length の場合は splice、Chunked の場合は通常転送。
-/// 戻り値: (ステータス, 転送バイト数, backend_wants_keep_alive, client_must_close)
+/// 戻り値: (ステータス, 転送バイト数, backend_keep_alive, client_must_close)
 #[cfg(all(veil_ktls, target_os = "linux"))]
 async fn splice_transfer_response_ktls(
     backend_stream: &TcpStream,
     client_stream: &KtlsServerStream,
     pipe: &SplicePipe,
-) -> (u16, u64, bool, bool) {
+    timeouts: UpstreamTimeouts,
+) -> (u16, u64, BackendKeepAlive, bool) {
     let client_tcp = client_stream.get_ref();
+    let header_deadline = timeouts.first_try_deadline(Instant::now());
 
     let mut total = 0u64;
     let mut status_code = 502u16;
     let mut accumulated = Vec::with_capacity(4096);
-    let mut backend_wants_keep_alive: bool;
+    let mut backend_keep_alive: BackendKeepAlive;
 
     // ヘッダー読み取り用バッファ
     let mut header_buf = [0u8; 8192];
@@ -9050,23 +11961,23 @@ async fn splice_transfer_response_ktls(
         // バックエンドからヘッダーを読み取り
         // B-17: ヘッダー読取は専用の短いタイムアウトで打ち切り、504 へ即変換する
         let n = match timeout(
-            BACKEND_HEADER_TIMEOUT,
+            UpstreamTimeouts::until(header_deadline),
             async_raw_read(backend_stream, &mut header_buf),
         )
         .await
         {
             Ok(Ok(0)) => {
                 // B-17: ヘッダー完了前の EOF は 502 として即時応答する
-                return (status_code, total, false, true);
+                return (status_code, total, BackendKeepAlive::Close, true);
             }
             Ok(Ok(n)) => n,
             Ok(Err(e)) => {
                 warn!("Failed to read response header: {}", e);
-                return (status_code, total, false, true);
+                return (status_code, total, BackendKeepAlive::Close, true);
             }
             Err(_) => {
                 warn!("Backend response header read timeout (splice)");
-                return (504, total, false, true);
+                return (504, total, BackendKeepAlive::Close, true);
             }
         };
 
@@ -9078,7 +11989,7 @@ async fn splice_transfer_response_ktls(
         // ヘッダーが完全に受信されたかチェック
         if let Some(parsed) = parse_http_response(&accumulated) {
             status_code = parsed.status_code;
-            backend_wants_keep_alive = !parsed.is_connection_close;
+            backend_keep_alive = parsed.keep_alive();
 
             let header_len = parsed.header_len;
             let body_start_len = accumulated.len().saturating_sub(header_len);
@@ -9086,7 +11997,7 @@ async fn splice_transfer_response_ktls(
             // ヘッダー + 初期ボディをクライアントに送信（raw_write）
             if let Err(e) = async_raw_write_all(client_tcp, &accumulated).await {
                 warn!("Failed to send response header: {}", e);
-                return (status_code, total, false, true);
+                return (status_code, total, BackendKeepAlive::Close, true);
             }
             total += accumulated.len() as u64;
 
@@ -9105,27 +12016,27 @@ async fn splice_transfer_response_ktls(
                         == ChunkedFeedResult::Complete
                 {
                     // 初期ボディで完了
-                    return (status_code, total, backend_wants_keep_alive, false);
+                    return (status_code, total, backend_keep_alive, false);
                 }
 
                 // 残りの Chunked ボディを転送
                 loop {
-                    // B-17: 無応答の上流で永久待機しないよう READ_TIMEOUT で打ち切る
+                    // B-17: 無応答の上流で永久待機しないよう アイドル上限（F-139）で打ち切る
                     let n = match timeout(
-                        READ_TIMEOUT,
+                        timeouts.read(),
                         async_raw_read(backend_stream, &mut header_buf),
                     )
                     .await
                     {
                         Ok(Ok(0)) => {
                             // B-17: 終端チャンク前の EOF はクライアントを待たせないようクローズ
-                            backend_wants_keep_alive = false;
+                            backend_keep_alive = BackendKeepAlive::Close;
                             client_must_close = true;
                             break;
                         }
                         Ok(Ok(n)) => n,
                         Ok(Err(_)) | Err(_) => {
-                            backend_wants_keep_alive = false;
+                            backend_keep_alive = BackendKeepAlive::Close;
                             client_must_close = true;
                             break;
                         }
@@ -9134,7 +12045,7 @@ async fn splice_transfer_response_ktls(
                     let feed_result = chunked_decoder.feed(&header_buf[..n]);
 
                     if (async_raw_write_all(client_tcp, &header_buf[..n]).await).is_err() {
-                        backend_wants_keep_alive = false;
+                        backend_keep_alive = BackendKeepAlive::Close;
                         client_must_close = true;
                         break;
                     }
@@ -9150,25 +12061,28 @@ async fn splice_transfer_response_ktls(
 
                 if remaining > 0 {
                     let transferred =
-                        splice_body_transfer(backend_stream, client_tcp, pipe, remaining).await;
+                        splice_body_transfer(backend_stream, client_tcp, pipe, remaining, || {
+                            timeouts.read()
+                        })
+                        .await;
 
                     total += transferred;
 
                     if transferred < remaining as u64 {
                         // B-17: CL 未達のままの終了はクライアントを待たせないようクローズ
-                        backend_wants_keep_alive = false;
+                        backend_keep_alive = BackendKeepAlive::Close;
                         client_must_close = true;
                     }
                 }
             } else {
                 // Content-Length も Chunked もない場合: 接続クローズまで読み取り
                 // この場合は Keep-Alive 不可
-                backend_wants_keep_alive = false;
+                backend_keep_alive = BackendKeepAlive::Close;
 
                 loop {
-                    // B-17: 無応答の上流で永久待機しないよう READ_TIMEOUT で打ち切る
+                    // B-17: 無応答の上流で永久待機しないよう アイドル上限（F-139）で打ち切る
                     let n = match timeout(
-                        READ_TIMEOUT,
+                        timeouts.read(),
                         async_raw_read(backend_stream, &mut header_buf),
                     )
                     .await
@@ -9188,12 +12102,7 @@ async fn splice_transfer_response_ktls(
                 client_must_close = true;
             }
 
-            return (
-                status_code,
-                total,
-                backend_wants_keep_alive,
-                client_must_close,
-            );
+            return (status_code, total, backend_keep_alive, client_must_close);
         }
 
         // B-17: レスポンスヘッダーの上限超過は 502 で即時応答する
@@ -9202,7 +12111,7 @@ async fn splice_transfer_response_ktls(
                 "Backend response header too large (> {} bytes, splice)",
                 MAX_RESPONSE_HEADER_SIZE
             );
-            return (502, 0, false, true);
+            return (502, 0, BackendKeepAlive::Close, true);
         }
     }
 }
@@ -9223,7 +12132,12 @@ async fn connect_https_backend_fresh(
 ) -> Result<ClientTls, (u16, &'static [u8])> {
     let addr = HostPortStr::new(&target.host, target.port); // F-41: スタック上に構築（ヒープ確保なし）
     let addr = addr.as_str();
-    let backend_tcp = match timeout(connect_timeout, TcpStream::connect_str(addr)).await {
+    let backend_tcp = match timeout(
+        connect_timeout,
+        TcpStream::connect_str_with(addr, target.bind, target.socket.clone()),
+    )
+    .await
+    {
         Ok(Ok(stream)) => {
             let _ = stream.set_nodelay(true);
             stream
@@ -9267,6 +12181,7 @@ async fn proxy_https_pooled(
     mut client_stream: ServerTls,
     target: &ProxyTarget,
     security: &SecurityConfig,
+    extra: &ExtraHeaders,
     compression: &CompressionConfig,
     buffering_config: &buffering::BufferingConfig,
     client_encoding: AcceptedEncoding,
@@ -9313,11 +12228,11 @@ async fn proxy_https_pooled(
         } else {
             None
         };
-        let (mut backend_stream, from_pool) = match pooled {
-            Some(stream) => (stream, true),
+        let (mut backend_stream, lifecycle, from_pool) = match pooled {
+            Some((stream, lifecycle)) => (stream, lifecycle, true),
             None => {
                 match connect_https_backend_fresh(target, connect_timeout, tls_insecure).await {
-                    Ok(stream) => (stream, false),
+                    Ok(stream) => (stream, ConnLifecycle::new(&target.connection_pool), false),
                     Err((code, msg)) => {
                         let _ = timeout(WRITE_TIMEOUT, client_stream.write_all(msg.to_vec())).await;
                         return Some((client_stream, code, 0, true));
@@ -9332,6 +12247,35 @@ async fn proxy_https_pooled(
         } else {
             request_holder.take().unwrap_or_default()
         };
+        // F-145: プール接続にはヘッダーを先行送信し、最初の書き込みが失敗したら
+        // 非冪等・ボディ転送ありの要求でも（未送信のため）新規接続で一度だけ再送する。
+        let req = if from_pool {
+            match write_head_to_pooled(&mut backend_stream, req).await {
+                PooledWrite::Sent => Vec::new(),
+                PooledWrite::Stale(req) => {
+                    debug!(
+                        "Pooled TLS connection to {} was stale, retrying on a fresh connection",
+                        pool_key
+                    );
+                    crate::metrics::record_upstream_connection_retired(pool_key, "stale_write");
+                    if !replayable {
+                        request_holder = Some(req);
+                    }
+                    continue;
+                }
+                PooledWrite::Failed if replayable && attempt < 2 => continue,
+                PooledWrite::Failed => {
+                    let _ = timeout(
+                        WRITE_TIMEOUT,
+                        client_stream.write_all(ERR_MSG_BAD_GATEWAY.to_vec()),
+                    )
+                    .await;
+                    return Some((client_stream, 502, 0, true));
+                }
+            }
+        } else {
+            req
+        };
         // wasm_modules は通常空（割り当てなし）のため毎試行クローンしても実質コストは無い。
         let wasm_mods = wasm_modules.clone();
 
@@ -9351,6 +12295,7 @@ async fn proxy_https_pooled(
                 buffering_config,
                 None,
                 security,
+                extra,
             )
             .await
         } else {
@@ -9366,13 +12311,14 @@ async fn proxy_https_pooled(
                 compression,
                 client_encoding,
                 security,
+                extra,
                 wasm_mods,
             )
             .await
         };
 
         match result {
-            Some((status_code, total, backend_wants_keep_alive, client_must_close)) => {
+            Some((status_code, total, backend_keep_alive, client_must_close)) => {
                 // プールから取り出した接続が応答前に死んでいた（total==0 かつ status は初期値 502 = レスポンス未受信）。
                 // クライアントへ未送信のため、新規接続で一度だけ透過リトライ。死んだ接続はプールに戻さない。
                 if from_pool && total == 0 && status_code == 502 && replayable && attempt < 2 {
@@ -9390,13 +12336,16 @@ async fn proxy_https_pooled(
                     return Some((client_stream, status_code, 0, true));
                 }
                 // バックエンドがKeep-Aliveを許可している場合、プールに返却
-                if backend_wants_keep_alive {
+                // F-145: アイドルタイムアウトは上流の `Keep-Alive: timeout=` を超えない
+                let idle_timeout =
+                    backend_keep_alive.idle_timeout_secs(security.idle_connection_timeout_secs);
+                if backend_keep_alive.is_reusable() && idle_timeout > 0 {
                     let max_idle = security.max_idle_connections_per_host;
-                    let idle_timeout = security.idle_connection_timeout_secs;
                     HTTPS_POOL.with(|p| {
                         p.borrow_mut().put(
                             pool_key.to_string(),
                             backend_stream,
+                            lifecycle.served(),
                             max_idle,
                             idle_timeout,
                         )
@@ -9422,7 +12371,7 @@ async fn proxy_https_pooled(
 }
 
 /// HTTPSリクエストを送信してレスポンスを受信（圧縮対応版）
-/// 戻り値: Option<(status_code, response_size, backend_wants_keep_alive)>
+/// 戻り値: Option<(status_code, response_size, backend_keep_alive)>
 async fn proxy_https_request_with_compression(
     client_stream: &mut ServerTls,
     backend_stream: &mut ClientTls,
@@ -9434,8 +12383,9 @@ async fn proxy_https_request_with_compression(
     compression: &CompressionConfig,
     client_encoding: AcceptedEncoding,
     security: &SecurityConfig,
+    extra: &ExtraHeaders,
     wasm_modules: Arc<Vec<String>>,
-) -> Option<(u16, u64, bool, bool)> {
+) -> Option<(u16, u64, BackendKeepAlive, bool)> {
     // 1. リクエストヘッダー送信
     let write_result = timeout(WRITE_TIMEOUT, backend_stream.write_all(request)).await;
     if !matches!(write_result, Ok((Ok(_), _))) {
@@ -9475,7 +12425,7 @@ async fn proxy_https_request_with_compression(
                 _ = futures::FutureExt::fuse(crate::runtime::time::sleep(body_timeout)) => {
                     true
                 }
-                transferred = futures::FutureExt::fuse(transfer_exact_bytes(client_stream, backend_stream, remaining)) => {
+                transferred = futures::FutureExt::fuse(transfer_exact_bytes(client_stream, backend_stream, remaining, || READ_TIMEOUT)) => {
                     if transferred < remaining as u64 { return None; }
                     false
                 }
@@ -9484,29 +12434,25 @@ async fn proxy_https_request_with_compression(
                 let _ = client_stream
                     .write_all(ERR_MSG_REQUEST_TIMEOUT.to_vec())
                     .await;
-                return Some((408, 0, false, true));
+                return Some((408, 0, BackendKeepAlive::Close, true));
             }
         }
     }
 
     // 4. レスポンスを受信して転送（圧縮対応）
-    let (total, status_code, backend_wants_keep_alive, client_must_close) =
+    let (total, status_code, backend_keep_alive, client_must_close) =
         transfer_https_response_with_compression(
             backend_stream,
             client_stream,
             compression,
             client_encoding,
             security,
+            extra,
             wasm_modules,
         )
         .await;
 
-    Some((
-        status_code,
-        total,
-        backend_wants_keep_alive,
-        client_must_close,
-    ))
+    Some((status_code, total, backend_keep_alive, client_must_close))
 }
 
 /// HTTPSレスポンス転送（圧縮対応版）
@@ -9517,25 +12463,33 @@ async fn transfer_https_response_with_compression(
     compression: &CompressionConfig,
     client_encoding: AcceptedEncoding,
     security: &SecurityConfig,
+    extra: &ExtraHeaders,
     wasm_modules: Arc<Vec<String>>,
-) -> (u64, u16, bool, bool) {
+) -> (u64, u16, BackendKeepAlive, bool) {
     let mut accumulated = Vec::with_capacity(BUF_SIZE);
     let mut total = 0u64;
     let mut status_code = 502u16;
     // 初期値false: エラー時はKeep-Aliveを無効化
-    let mut backend_wants_keep_alive = false;
+    let mut backend_keep_alive = BackendKeepAlive::Close;
+    // F-139: ルートの応答ヘッダー待ち・試行ごとのタイムアウトとリクエスト期限
+    let timeouts = security.upstream_timeouts();
+    let header_deadline = timeouts.first_try_deadline(Instant::now());
 
     // ヘッダー読み取り用バッファ
     loop {
         // B-17: ヘッダー読取は専用の短いタイムアウトで打ち切り、504 へ即変換する
         let read_buf = buf_get();
-        let read_result = timeout(BACKEND_HEADER_TIMEOUT, backend_stream.read(read_buf)).await;
+        let read_result = timeout(
+            UpstreamTimeouts::until(header_deadline),
+            backend_stream.read(read_buf),
+        )
+        .await;
 
         let (res, mut returned_buf) = match read_result {
             Ok(result) => result,
             Err(_) => {
                 warn!("Backend response timeout while reading headers");
-                return (total, 504, false, true);
+                return (total, 504, BackendKeepAlive::Close, true);
             }
         };
 
@@ -9543,7 +12497,7 @@ async fn transfer_https_response_with_compression(
             Ok(0) => {
                 buf_put(returned_buf);
                 warn!("Backend closed connection without sending response (read returned 0 bytes)");
-                return (total, status_code, backend_wants_keep_alive, true);
+                return (total, status_code, backend_keep_alive, true);
             }
             Ok(n) => n,
             Err(e) => {
@@ -9558,7 +12512,7 @@ async fn transfer_https_response_with_compression(
                 } else {
                     warn!("Backend read error: {}", e);
                 }
-                return (total, status_code, backend_wants_keep_alive, true);
+                return (total, status_code, backend_keep_alive, true);
             }
         };
 
@@ -9573,7 +12527,7 @@ async fn transfer_https_response_with_compression(
         // ヘッダーが完全に受信されたかチェック
         if let Some(parsed) = parse_http_response(&accumulated) {
             status_code = parsed.status_code;
-            backend_wants_keep_alive = !parsed.is_connection_close;
+            backend_keep_alive = parsed.keep_alive();
 
             let header_len = parsed.header_len;
             let body_start = &accumulated[header_len..];
@@ -9602,8 +12556,9 @@ async fn transfer_https_response_with_compression(
                     parsed.is_chunked,
                     encoding,
                     compression,
-                    backend_wants_keep_alive,
+                    backend_keep_alive,
                     security,
+                    extra,
                 )
                 .await;
 
@@ -9646,7 +12601,7 @@ async fn transfer_https_response_with_compression(
                     }
 
                     // 追加するヘッダーを追加
-                    for (header_name, header_value) in &security.add_response_headers {
+                    for (header_name, header_value) in security.response_headers(extra) {
                         new_header_lines
                             .push(format!("{}: {}\r\n", header_name, header_value).into_bytes());
                     }
@@ -9735,7 +12690,7 @@ async fn transfer_https_response_with_compression(
                 )
                 .await;
                 if !matches!(write_result, Ok((Ok(()), _, _))) {
-                    return (total, status_code, false, true);
+                    return (total, status_code, BackendKeepAlive::Close, true);
                 }
                 total += header_len as u64;
                 total += body_start.len() as u64;
@@ -9756,6 +12711,7 @@ async fn transfer_https_response_with_compression(
                         parsed.content_length,
                         parsed.is_chunked,
                         body_start,
+                        timeouts,
                     )
                     .await;
                     total += transferred;
@@ -9767,11 +12723,11 @@ async fn transfer_https_response_with_compression(
                             "Backend response body incomplete: {} < {}",
                             transferred, body_remaining
                         );
-                        return (total, status_code, false, true);
+                        return (total, status_code, BackendKeepAlive::Close, true);
                     }
                 }
 
-                return (total, status_code, backend_wants_keep_alive, false);
+                return (total, status_code, backend_keep_alive, false);
             }
         }
 
@@ -9781,7 +12737,7 @@ async fn transfer_https_response_with_compression(
                 "Backend response header too large (> {} bytes)",
                 MAX_RESPONSE_HEADER_SIZE
             );
-            return (0, 502, false, true);
+            return (0, 502, BackendKeepAlive::Close, true);
         }
     }
 }
@@ -9797,9 +12753,12 @@ async fn transfer_compressed_https_response(
     is_chunked: bool,
     encoding: AcceptedEncoding,
     compression: &CompressionConfig,
-    backend_wants_keep_alive: bool,
+    backend_keep_alive: BackendKeepAlive,
     security: &SecurityConfig,
-) -> (u64, bool) {
+    extra: &ExtraHeaders,
+) -> (u64, BackendKeepAlive) {
+    // F-139: 読み取りはルートのアイドル上限とリクエスト期限で打ち切る
+    let timeouts = security.upstream_timeouts();
     use flate2::write::GzEncoder;
     use flate2::Compression;
     use std::io::Write;
@@ -9815,12 +12774,12 @@ async fn transfer_compressed_https_response(
             let mut remaining_to_read = remaining;
             while remaining_to_read > 0 {
                 let read_buf = buf_get();
-                let read_result = timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+                let read_result = timeout(timeouts.read(), backend_stream.read(read_buf)).await;
 
                 let (res, mut returned_buf) = match read_result {
                     Ok(result) => result,
                     Err(_) => {
-                        return (total, false);
+                        return (total, BackendKeepAlive::Close);
                     }
                 };
 
@@ -9832,7 +12791,7 @@ async fn transfer_compressed_https_response(
                     Ok(n) => n.min(remaining_to_read),
                     Err(_) => {
                         buf_put(returned_buf);
-                        return (total, false);
+                        return (total, BackendKeepAlive::Close);
                     }
                 };
 
@@ -9848,7 +12807,7 @@ async fn transfer_compressed_https_response(
 
         loop {
             let read_buf = buf_get();
-            let read_result = timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+            let read_result = timeout(timeouts.read(), backend_stream.read(read_buf)).await;
 
             let (res, mut returned_buf) = match read_result {
                 Ok(result) => result,
@@ -9952,14 +12911,19 @@ async fn transfer_compressed_https_response(
     };
 
     // 3. 新しいヘッダーを構築
-    let new_headers =
-        build_compressed_headers(original_headers, encoding, compressed_body.len(), security);
+    let new_headers = build_compressed_headers(
+        original_headers,
+        encoding,
+        compressed_body.len(),
+        security,
+        extra,
+    );
 
     // 4. ヘッダー送信（所有権を移動、clone 不要）
     let new_headers_len = new_headers.len();
     let write_result = timeout(WRITE_TIMEOUT, client_stream.write_all(new_headers)).await;
     if !matches!(write_result, Ok((Ok(_), _))) {
-        return (total, false);
+        return (total, BackendKeepAlive::Close);
     }
     total += new_headers_len as u64;
 
@@ -9967,11 +12931,11 @@ async fn transfer_compressed_https_response(
     let compressed_len = compressed_body.len();
     let write_result = timeout(WRITE_TIMEOUT, client_stream.write_all(compressed_body)).await;
     if !matches!(write_result, Ok((Ok(_), _))) {
-        return (total, false);
+        return (total, BackendKeepAlive::Close);
     }
     total += compressed_len as u64;
 
-    (total, backend_wants_keep_alive)
+    (total, backend_keep_alive)
 }
 
 /// compression feature 無効時のスタブ
@@ -9985,9 +12949,10 @@ async fn transfer_compressed_https_response(
     _is_chunked: bool,
     _encoding: AcceptedEncoding,
     _compression: &CompressionConfig,
-    _backend_wants_keep_alive: bool,
+    _backend_keep_alive: BackendKeepAlive,
     _security: &SecurityConfig,
-) -> (u64, bool) {
+    _extra: &ExtraHeaders,
+) -> (u64, BackendKeepAlive) {
     transfer_uncompressed_fallback(client_stream, original_headers, initial_body).await
 }
 
@@ -9998,13 +12963,16 @@ async fn transfer_https_response_body(
     content_length: Option<usize>,
     is_chunked: bool,
     initial_body: &[u8],
+    timeouts: UpstreamTimeouts,
 ) -> u64 {
     let mut total = 0u64;
 
     if let Some(cl) = content_length {
         let remaining = cl.saturating_sub(initial_body.len());
         if remaining > 0 {
-            let transferred = transfer_exact_bytes(backend_stream, client_stream, remaining).await;
+            let transferred =
+                transfer_exact_bytes(backend_stream, client_stream, remaining, || timeouts.read())
+                    .await;
             total += transferred;
         }
     } else if is_chunked {
@@ -10013,7 +12981,7 @@ async fn transfer_https_response_body(
 
         loop {
             let read_buf = buf_get();
-            let read_result = timeout(READ_TIMEOUT, backend_stream.read(read_buf)).await;
+            let read_result = timeout(timeouts.read(), backend_stream.read(read_buf)).await;
 
             let (res, mut returned_buf) = match read_result {
                 Ok(result) => result,
@@ -10059,16 +13027,19 @@ async fn transfer_https_response_body(
 // ====================
 
 /// 正確なバイト数を転送
+///
+/// `read_timeout` は読み取りごとに評価する（F-139: 上流からの読み取りはリクエスト期限で頭打ち）。
 async fn transfer_exact_bytes<R: AsyncReader, W: AsyncWriter>(
     reader: &mut R,
     writer: &mut W,
     mut remaining: usize,
+    read_timeout: impl Fn() -> Duration,
 ) -> u64 {
     let mut total = 0u64;
 
     while remaining > 0 {
         let buf = buf_get();
-        let read_result = timeout(READ_TIMEOUT, reader.read_buf(buf)).await;
+        let read_result = timeout(read_timeout(), reader.read_buf(buf)).await;
 
         let (res, mut returned_buf) = match read_result {
             Ok(result) => result,
@@ -10282,6 +13253,7 @@ async fn handle_sendfile(
     prefix: &[u8],
     client_wants_close: bool,
     security: &SecurityConfig,
+    extra: &ExtraHeaders,
     range_header: Option<&[u8]>, // RFC 7233 Range header support
     open_file_cache_config: Option<&cache::OpenFileCacheConfig>, // OpenFileCache設定（ルーティングごと）
     wasm_modules: Arc<Vec<String>>,
@@ -10477,7 +13449,7 @@ async fn handle_sendfile(
     };
 
     // 追加レスポンスヘッダー（セキュリティヘッダーなど）
-    for (header_name, header_value) in &security.add_response_headers {
+    for (header_name, header_value) in security.response_headers(extra) {
         header_buf.extend_from_slice(header_name.as_bytes());
         header_buf.extend_from_slice(b": ");
         header_buf.extend_from_slice(header_value.as_bytes());
@@ -10847,6 +13819,9 @@ mod connect_gate_tests {
     use std::cell::Cell;
     use std::rc::Rc;
 
+    /// 新規 connect 並行数の既定値（`connection_pool.max_concurrent_connects`）
+    const MAX_CONNECTS: usize = 64;
+
     // io_uring/epoll を許可しない環境（Docker ビルドサンドボックス・古いカーネル等）では
     // ランタイムドライバの生成が失敗し `block_on` が panic するため、ランタイムを要する
     // テストはスキップする（`src/l4/proxy.rs` の tests と同じパターン）。
@@ -10889,11 +13864,12 @@ mod connect_gate_tests {
             return;
         }
 
-        let gate = Rc::new(ConnectGate::new());
+        let gate = Rc::new(ConnectGate::new("127.0.0.1:1"));
         let waiter = crate::stream_channel::Notify::new();
         gate.waiters.borrow_mut().push_back(waiter.clone());
 
-        let permit = ConnectPermit::try_acquire(&gate).expect("must acquire on empty gate");
+        let permit =
+            ConnectPermit::try_acquire(&gate, MAX_CONNECTS).expect("must acquire on empty gate");
         assert_eq!(gate.in_flight.get(), 1);
 
         drop(permit);
@@ -10919,14 +13895,16 @@ mod connect_gate_tests {
         }
 
         crate::runtime::block_on(async {
-            let gate = Rc::new(ConnectGate::new());
+            let gate = Rc::new(ConnectGate::new("127.0.0.1:1"));
             // 64 スロットをすべて占有する
-            let mut permits: Vec<ConnectPermit> = (0..MAX_CONCURRENT_CONNECTS_PER_HOST)
-                .map(|_| ConnectPermit::try_acquire(&gate).expect("slot must be available"))
+            let mut permits: Vec<ConnectPermit> = (0..MAX_CONNECTS)
+                .map(|_| {
+                    ConnectPermit::try_acquire(&gate, MAX_CONNECTS).expect("slot must be available")
+                })
                 .collect();
-            assert_eq!(gate.in_flight.get(), MAX_CONCURRENT_CONNECTS_PER_HOST);
+            assert_eq!(gate.in_flight.get(), MAX_CONNECTS);
             assert!(
-                ConnectPermit::try_acquire(&gate).is_none(),
+                ConnectPermit::try_acquire(&gate, MAX_CONNECTS).is_none(),
                 "65th acquisition must be rejected while the gate is full"
             );
 
@@ -10937,7 +13915,7 @@ mod connect_gate_tests {
             crate::runtime::executor::spawn(async move {
                 let mut waiter: Option<crate::stream_channel::Notify> = None;
                 loop {
-                    if let Some(_permit) = ConnectPermit::try_acquire(&gate_task) {
+                    if let Some(_permit) = ConnectPermit::try_acquire(&gate_task, MAX_CONNECTS) {
                         progressed_task.set(true);
                         return;
                     }
@@ -10963,4 +13941,219 @@ mod connect_gate_tests {
             );
         });
     }
+
+    fn limited_target(port: u16, max_connections: usize, max_pending: usize) -> ProxyTarget {
+        let mut target =
+            ProxyTarget::parse(&format!("http://127.0.0.1:{}", port)).expect("valid url");
+        target.connection_pool = ConnectionPoolConfig {
+            max_connections,
+            max_pending_requests: max_pending,
+            pending_timeout_ms: 30,
+            ..ConnectionPoolConfig::default()
+        };
+        target
+    }
+
+    /// max_connections 未設定ではゲートに触れず、使用枠を確保しないこと（F-136）。
+    #[test]
+    fn test_lease_is_skipped_without_max_connections() {
+        if !io_uring_available() {
+            eprintln!(
+                "io_uring unavailable; skipping test_lease_is_skipped_without_max_connections"
+            );
+            return;
+        }
+        crate::runtime::block_on(async {
+            let target = limited_target(2, 0, 0);
+            assert!(matches!(acquire_connection_lease(&target).await, Ok(None)));
+            assert!(CONNECT_GATES.with(|g| g.borrow().get("127.0.0.1:2").is_none()));
+        });
+    }
+
+    /// 満杯時はキューで待ち、溢れた分と待ち時間超過は 503 相当で拒否されること（F-136）。
+    #[test]
+    fn test_lease_queue_rejects_overflow_and_timeout() {
+        if !io_uring_available() {
+            eprintln!(
+                "io_uring unavailable; skipping test_lease_queue_rejects_overflow_and_timeout"
+            );
+            return;
+        }
+        crate::runtime::block_on(async {
+            let target = limited_target(3, 1, 1);
+            let first = acquire_connection_lease(&target)
+                .await
+                .expect("first lease")
+                .expect("limit is configured");
+
+            // 2 件目は待機キューへ入り、期限までに空かなければ Timeout
+            let outcome = Rc::new(Cell::new(None));
+            let outcome_task = outcome.clone();
+            let target_task = target.clone();
+            crate::runtime::executor::spawn(async move {
+                let result = acquire_connection_lease(&target_task).await;
+                outcome_task.set(Some(result.err()));
+            });
+            crate::runtime::timer::sleep(Duration::from_millis(5)).await;
+
+            // 3 件目は待機数上限（1）を超えるため即座に Overflow
+            assert_eq!(
+                acquire_connection_lease(&target).await.err(),
+                Some(PendingRejected::Overflow)
+            );
+
+            crate::runtime::timer::sleep(Duration::from_millis(60)).await;
+            assert_eq!(outcome.get(), Some(Some(PendingRejected::Timeout)));
+
+            // 枠を返せば次の確保は成功する
+            drop(first);
+            assert!(matches!(
+                acquire_connection_lease(&target).await,
+                Ok(Some(_))
+            ));
+        });
+    }
+
+    /// 使用枠の返却で待機者が起こされ、枠を引き継ぐこと（F-136）。
+    #[test]
+    fn test_lease_release_wakes_waiter() {
+        if !io_uring_available() {
+            eprintln!("io_uring unavailable; skipping test_lease_release_wakes_waiter");
+            return;
+        }
+        crate::runtime::block_on(async {
+            let mut target = limited_target(4, 1, 4);
+            target.connection_pool.pending_timeout_ms = 1_000;
+            let first = acquire_connection_lease(&target)
+                .await
+                .expect("first lease")
+                .expect("limit is configured");
+
+            let acquired = Rc::new(Cell::new(false));
+            let acquired_task = acquired.clone();
+            let target_task = target.clone();
+            crate::runtime::executor::spawn(async move {
+                let lease = acquire_connection_lease(&target_task).await;
+                acquired_task.set(matches!(lease, Ok(Some(_))));
+            });
+            crate::runtime::timer::sleep(Duration::from_millis(10)).await;
+            assert!(
+                !acquired.get(),
+                "the second request must wait while the limit is reached"
+            );
+
+            drop(first);
+            crate::runtime::timer::sleep(Duration::from_millis(10)).await;
+            assert!(acquired.get(), "releasing a lease must wake the waiter");
+        });
+    }
+
+    /// 指定時間待ってから応答する HTTP/1.1 テストバックエンドを起動し、ポートを返す。
+    fn spawn_delayed_backend(delay: Duration) -> u16 {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                std::thread::spawn(move || {
+                    use std::io::{Read, Write};
+                    let mut buf = [0u8; 1024];
+                    let _ = stream.read(&mut buf);
+                    // 理由付き allow: テスト用バックエンドの専用スレッド。イベントループを
+                    // ブロックしない。
+                    #[allow(clippy::disallowed_methods)]
+                    std::thread::sleep(delay);
+                    let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+                    let _ = stream.read(&mut buf);
+                });
+            }
+        });
+        port
+    }
+
+    /// 一次試行が遅延内に応答しなければ別サーバーへヘッジし、先に応答した方を採用すること（F-137）。
+    #[test]
+    fn test_hedged_exchange_switches_to_faster_server() {
+        if !io_uring_available() {
+            eprintln!(
+                "io_uring unavailable; skipping test_hedged_exchange_switches_to_faster_server"
+            );
+            return;
+        }
+
+        let slow = spawn_delayed_backend(Duration::from_millis(500));
+        let fast = spawn_delayed_backend(Duration::ZERO);
+        let entries = [slow, fast]
+            .iter()
+            .map(|port| UpstreamServerEntry {
+                url: format!("http://127.0.0.1:{}", port),
+                sni_name: None,
+                use_h2c: false,
+                weight: 1,
+                priority: 0,
+            })
+            .collect();
+        let group = UpstreamGroup::new(
+            "hedge".into(),
+            entries,
+            LoadBalanceAlgorithm::RoundRobin,
+            None,
+            false,
+        )
+        .unwrap()
+        .with_hedging(Some(&HedgingConfig {
+            delay_ms: 20,
+            budget_percent: 100.0,
+            ..HedgingConfig::default()
+        }));
+
+        crate::runtime::block_on(async move {
+            // 一次試行が遅い: ヘッジが勝ち、一次試行のサーバーは release 済み
+            let winner = hedge_test_exchange(&group, &group.servers[0]).await;
+            assert_eq!(winner.outcome, HedgeOutcome::HedgeWon);
+            assert!(std::ptr::eq(winner.server, &group.servers[1]));
+            assert_eq!(
+                group.servers[0]
+                    .active_connections
+                    .load(std::sync::atomic::Ordering::Relaxed),
+                0
+            );
+            winner.server.release();
+
+            // 一次試行が遅延内に応答: ヘッジしない
+            let winner = hedge_test_exchange(&group, &group.servers[1]).await;
+            assert_eq!(winner.outcome, HedgeOutcome::NotHedged);
+            assert!(std::ptr::eq(winner.server, &group.servers[1]));
+            winner.server.release();
+        });
+    }
+
+    /// `primary` を一次試行としてヘッジ付き交換を行う（テスト用）
+    async fn hedge_test_exchange<'a>(
+        group: &'a UpstreamGroup,
+        primary: &'a UpstreamServer,
+    ) -> HedgeWinner<'a> {
+        let request = |target: &ProxyTarget| {
+            format!("GET / HTTP/1.1\r\nHost: {}\r\n\r\n", target.host).into_bytes()
+        };
+        primary.acquire();
+        let key = HostPortStr::new(&primary.target.host, primary.target.port);
+        let conn = open_http_backend(&primary.target, key.as_str(), CONNECT_TIMEOUT)
+            .await
+            .expect("backend must accept");
+        hedged_exchange(
+            group,
+            group.hedging.as_deref().unwrap(),
+            primary,
+            conn,
+            None,
+            request(&primary.target),
+            "127.0.0.1",
+            CONNECT_TIMEOUT,
+            SecurityConfig::default().upstream_timeouts(),
+            request,
+        )
+        .await
+        .expect("one of the attempts must respond")
+    }
 }
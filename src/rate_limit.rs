@@ -0,0 +1,778 @@
+//! プロセス全体で共有するトークンバケット式レートリミット（F-146）
+//!
+//! 従来の `rate_limit_requests_per_min` はスレッドローカルな分単位のスライディングウィンドウで、
+//! 実効上限がワーカー数倍になり、キーもクライアント IP に限られていた。本モジュールは全ワーカーで
+//! 共有するバケット表（キーのハッシュで分けたシャードごとの Mutex）を持ち、ルートごとに複数の規則を
+//! 評価する。
+//!
+//! - 各規則は `rate` 回 / `period_secs` 秒で補充される容量 `burst` のトークンバケット。
+//! - キーはクライアント IP・ヘッダー・Cookie・パス・ルート・JWT クレーム・API キーの組み合わせ。
+//!   構成要素が欠けたリクエストにはその規則を適用しない。
+//! - `classes` を指定した規則は、検証済みのクレーム `rate_limit_class`（F-151 の API キーの属性）が
+//!   一致するリクエストにだけ適用する。
+//! - 名前とパラメータが同じ規則はルートをまたいでバケットを共有する。
+//! - 応答には `RateLimit-Policy` / `RateLimit`（draft-ietf-httpapi-ratelimit-headers）を付け、
+//!   超過時は 429 と `Retry-After` を返す。
+//!
+//! 規則ごとに独立して消費するため、後続の規則で拒否されたリクエストも先に評価した規則の
+//! トークンを消費する。
+//!
+//! ルートに `global_rate_limit`（F-147）があれば、ローカルの規則で許可されたリクエストだけを
+//! 外部レートリミットサービスへ問い合わせる（[`RouteRateLimit::evaluate`]）。超過したクライアントは
+//! 自動遮断（F-152）の対象として数える。
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::config::{
+    monotonic_ms, GlobalRateLimitConfig, RateLimitRuleConfig, RateLimitServiceConfig,
+};
+
+/// バケット表のシャード数
+const SHARDS: usize = 64;
+
+/// 満杯に戻ったバケットを掃除する間隔（ミリ秒、シャードごと）
+const SWEEP_INTERVAL_MS: u64 = 10_000;
+
+/// `rate_limit_requests_per_min` から作る規則の名前
+pub const PER_MINUTE_RULE: &str = "per_minute";
+
+/// API キーを読むヘッダー
+const API_KEY_HEADER: &[u8] = b"x-api-key";
+
+/// 規則の `classes` と照合する検証済みクレーム（F-151、API キーの属性）
+const RATE_LIMIT_CLASS_CLAIM: &str = "rate_limit_class";
+
+/// キーの構成要素
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyPart {
+    /// クライアント IP
+    ClientIp,
+    /// 指定したリクエストヘッダーの値（名前は小文字で保持）
+    Header(String),
+    /// 指定した Cookie の値
+    Cookie(String),
+    /// リクエストパス（クエリ文字列を除く）
+    Path,
+    /// マッチしたルート（`route[N]`）
+    Route,
+    /// 検証済みのクレーム（`.` 区切りで入れ子を辿る）
+    ///
+    /// JWT 認証（F-148）・OIDC（F-150）・Basic / API キー認証（F-151）のあるルートでのみ使える
+    /// （設定読み込み時に検証する）。署名を検証していないトークンからは読まない。
+    JwtClaim(String),
+    /// `X-API-Key` ヘッダーの値
+    ApiKey,
+}
+
+impl KeyPart {
+    /// 文字列からパース（`"client_ip"`, `"header:X-Tenant"`, `"cookie:session"`, `"path"`,
+    /// `"route"`, `"jwt_claim:sub"`, `"api_key"`）
+    pub fn parse(s: &str) -> Option<Self> {
+        let non_empty = |v: &str| (!v.is_empty()).then(|| v.to_string());
+        match s {
+            "client_ip" => Some(Self::ClientIp),
+            "path" => Some(Self::Path),
+            "route" => Some(Self::Route),
+            "api_key" => Some(Self::ApiKey),
+            _ => {
+                let (kind, arg) = s.split_once(':')?;
+                match kind {
+                    "header" => non_empty(arg).map(|v| Self::Header(v.to_ascii_lowercase())),
+                    "cookie" => non_empty(arg).map(Self::Cookie),
+                    "jwt_claim" => non_empty(arg).map(Self::JwtClaim),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// リクエストから値を取り出す（`route` はキー `route` の値、欠けていれば None）
+    pub(crate) fn resolve<'a>(
+        &self,
+        route: &'a str,
+        req: &RateLimitRequest<'a>,
+    ) -> Option<Cow<'a, [u8]>> {
+        Some(match self {
+            KeyPart::ClientIp => Cow::Borrowed(req.client_ip.as_bytes()),
+            KeyPart::Header(name) => Cow::Borrowed(req.header(name.as_bytes())?),
+            KeyPart::Cookie(name) => Cow::Borrowed(req.cookie(name)?),
+            KeyPart::Path => {
+                let end = req.path.iter().position(|&b| b == b'?');
+                Cow::Borrowed(&req.path[..end.unwrap_or(req.path.len())])
+            }
+            KeyPart::Route => Cow::Borrowed(route.as_bytes()),
+            KeyPart::JwtClaim(claim) => match req.jwt_claim(claim)? {
+                Cow::Borrowed(v) => Cow::Borrowed(v.as_bytes()),
+                Cow::Owned(v) => Cow::Owned(v.into_bytes()),
+            },
+            KeyPart::ApiKey => Cow::Borrowed(req.header(API_KEY_HEADER)?),
+        })
+    }
+}
+
+/// キーの解決に使うリクエストの情報
+pub struct RateLimitRequest<'a> {
+    pub client_ip: &'a str,
+    /// リクエストパス（クエリ文字列を含んでもよい）
+    pub path: &'a [u8],
+    /// 疑似ヘッダーを除くリクエストヘッダー
+    pub headers: &'a [(&'a [u8], &'a [u8])],
+    /// JWT 認証（F-148）・OIDC（F-150）・Basic / API キー認証（F-151）で検証済みのクレーム
+    pub claims: Option<&'a serde_json::Value>,
+}
+
+impl<'a> RateLimitRequest<'a> {
+    fn header(&self, name: &[u8]) -> Option<&'a [u8]> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| *v)
+    }
+
+    fn cookie(&self, name: &str) -> Option<&'a [u8]> {
+        self.headers
+            .iter()
+            .filter(|(n, _)| n.eq_ignore_ascii_case(b"cookie"))
+            .flat_map(|(_, v)| v.split(|&b| b == b';'))
+            .find_map(|pair| {
+                let pair = pair.trim_ascii();
+                let eq = pair.iter().position(|&b| b == b'=')?;
+                (pair[..eq].trim_ascii() == name.as_bytes()).then(|| pair[eq + 1..].trim_ascii())
+            })
+    }
+
+    /// 検証済みのクレームの値
+    fn jwt_claim(&self, claim: &str) -> Option<Cow<'a, str>> {
+        let value = crate::jwt_auth::claim_value(self.claims?, claim)?;
+        crate::jwt_auth::scalar_string(value)
+    }
+
+    /// レートリミットのクラス（検証済みのクレームからのみ読む）
+    fn rate_limit_class(&self) -> Option<Cow<'a, str>> {
+        let value = crate::jwt_auth::claim_value(self.claims?, RATE_LIMIT_CLASS_CLAIM)?;
+        crate::jwt_auth::scalar_string(value)
+    }
+}
+
+/// 構築済みの規則
+#[derive(Debug)]
+struct Rule {
+    name: String,
+    key: Vec<KeyPart>,
+    /// 適用するレートリミットのクラス（空ならすべて）
+    classes: Vec<String>,
+    burst: f64,
+    /// `period_ms` あたりの補充トークン数
+    rate: f64,
+    period_ms: f64,
+    /// バケットが空から満杯に戻るまでの秒数（`RateLimit-Policy` の `w`）
+    window_secs: u64,
+    /// 名前とパラメータから求めたシード（同一規則のバケット共有に使う）
+    seed: u64,
+}
+
+impl Rule {
+    fn new(name: &str, key: Vec<KeyPart>, rate: u32, period_secs: u64, burst: u32) -> Self {
+        let window_secs = (burst as u64 * period_secs).div_ceil(rate as u64);
+        let mut hasher = Xxh3::new();
+        hasher.update(name.as_bytes());
+        for v in [rate as u64, period_secs, burst as u64] {
+            hasher.update(&v.to_le_bytes());
+        }
+        Self {
+            name: name.to_string(),
+            key,
+            classes: Vec::new(),
+            burst: burst as f64,
+            rate: rate as f64,
+            period_ms: period_secs as f64 * 1000.0,
+            window_secs,
+            seed: hasher.digest(),
+        }
+    }
+
+    /// リクエストのキーのハッシュ（構成要素が欠けている・クラスが違えば None）
+    fn key_hash(&self, route: &str, req: &RateLimitRequest<'_>) -> Option<u64> {
+        if !self.classes.is_empty() {
+            let class = req.rate_limit_class()?;
+            if !self.classes.iter().any(|c| *c == class) {
+                return None;
+            }
+        }
+        let mut hasher = Xxh3::with_seed(self.seed);
+        for part in &self.key {
+            let value = part.resolve(route, req)?;
+            hasher.update(&(value.len() as u64).to_le_bytes());
+            hasher.update(&value);
+        }
+        Some(hasher.digest())
+    }
+
+    /// `tokens` 個を補充するのにかかるミリ秒
+    fn refill_ms(&self, tokens: f64) -> f64 {
+        tokens.max(0.0) * self.period_ms / self.rate
+    }
+
+    /// 残りトークンが満杯に戻るまでの秒数
+    fn reset_secs(&self, tokens: f64) -> u64 {
+        (self.refill_ms(self.burst - tokens) / 1000.0).ceil() as u64
+    }
+
+    /// 1 トークン貯まるまでの秒数（最小 1）
+    fn retry_after_secs(&self, tokens: f64) -> u64 {
+        ((self.refill_ms(1.0 - tokens) / 1000.0).ceil() as u64).max(1)
+    }
+}
+
+/// 1 バケットの状態
+struct Bucket {
+    tokens: f64,
+    updated_ms: u64,
+    /// 補充で満杯に戻る時刻（掃除の判定用）
+    full_at_ms: u64,
+}
+
+struct Shard {
+    buckets: HashMap<u64, Bucket>,
+    last_sweep_ms: u64,
+}
+
+/// 全ワーカーで共有するバケット表
+struct BucketStore {
+    shards: Vec<Mutex<Shard>>,
+}
+
+static STORE: Lazy<BucketStore> = Lazy::new(BucketStore::new);
+
+impl BucketStore {
+    fn new() -> Self {
+        Self {
+            shards: (0..SHARDS)
+                .map(|_| {
+                    Mutex::new(Shard {
+                        buckets: HashMap::new(),
+                        last_sweep_ms: 0,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// トークンを 1 つ消費する。戻り値は（許可されたか, 消費後の残りトークン）
+    fn take(&self, key: u64, rule: &Rule, now_ms: u64) -> (bool, f64) {
+        let mut shard = self.shards[(key as usize) % SHARDS]
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if now_ms.saturating_sub(shard.last_sweep_ms) >= SWEEP_INTERVAL_MS {
+            shard.buckets.retain(|_, b| b.full_at_ms > now_ms);
+            shard.last_sweep_ms = now_ms;
+        }
+        let bucket = shard.buckets.entry(key).or_insert(Bucket {
+            tokens: rule.burst,
+            updated_ms: now_ms,
+            full_at_ms: now_ms,
+        });
+        let elapsed = now_ms.saturating_sub(bucket.updated_ms) as f64;
+        bucket.tokens = (bucket.tokens + elapsed * rule.rate / rule.period_ms).min(rule.burst);
+        bucket.updated_ms = now_ms;
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        bucket.full_at_ms = now_ms + rule.refill_ms(rule.burst - bucket.tokens).ceil() as u64;
+        (allowed, bucket.tokens)
+    }
+}
+
+/// ルートのレートリミッター（`Route::prepare` で構築し、`SecurityConfig` が共有する）
+#[derive(Debug)]
+pub struct RouteRateLimit {
+    /// キー `route` の値（`route[N]`）
+    route: String,
+    rules: Vec<Rule>,
+    /// 外部レートリミットサービス（F-147）
+    #[cfg(all(feature = "http2", feature = "grpc"))]
+    global: Option<crate::global_rate_limit::GlobalRateLimit>,
+}
+
+impl RouteRateLimit {
+    /// 規則・`rate_limit_requests_per_min`・グローバルレートリミットから構築する
+    /// （いずれもなければ None）
+    ///
+    /// 規則の妥当性は設定読み込み時に検証済みであること（不正なキーは無視する）。
+    #[cfg_attr(not(all(feature = "http2", feature = "grpc")), allow(unused_variables))]
+    pub fn build(
+        route: String,
+        rules: &[RateLimitRuleConfig],
+        per_min: u64,
+        global: Option<(&GlobalRateLimitConfig, &Arc<RateLimitServiceConfig>)>,
+    ) -> Option<Self> {
+        let mut built: Vec<Rule> = rules
+            .iter()
+            .filter(|cfg| cfg.rate > 0 && cfg.period_secs > 0)
+            .map(|cfg| {
+                let key = cfg.key.iter().filter_map(|k| KeyPart::parse(k)).collect();
+                let mut rule = Rule::new(
+                    &cfg.name,
+                    key,
+                    cfg.rate,
+                    cfg.period_secs,
+                    cfg.effective_burst(),
+                );
+                rule.classes = cfg.classes.clone();
+                rule
+            })
+            .collect();
+        if per_min > 0 {
+            let limit = per_min.min(u32::MAX as u64) as u32;
+            built.push(Rule::new(
+                PER_MINUTE_RULE,
+                vec![KeyPart::ClientIp],
+                limit,
+                60,
+                limit,
+            ));
+        }
+        #[cfg(all(feature = "http2", feature = "grpc"))]
+        let global = global.map(|(cfg, service)| {
+            crate::global_rate_limit::GlobalRateLimit::new(service.clone(), cfg)
+        });
+        #[cfg(all(feature = "http2", feature = "grpc"))]
+        let has_global = global.is_some();
+        #[cfg(not(all(feature = "http2", feature = "grpc")))]
+        let has_global = false;
+        if built.is_empty() && !has_global {
+            return None;
+        }
+        Some(Self {
+            route,
+            rules: built,
+            #[cfg(all(feature = "http2", feature = "grpc"))]
+            global,
+        })
+    }
+
+    /// 外部レートリミットサービスへ問い合わせるか（F-147）
+    #[cfg(all(feature = "http2", feature = "grpc"))]
+    pub fn has_global(&self) -> bool {
+        self.global.is_some()
+    }
+
+    /// 外部レートリミットサービスへ問い合わせるか（F-147）
+    #[cfg(not(all(feature = "http2", feature = "grpc")))]
+    pub fn has_global(&self) -> bool {
+        false
+    }
+
+    /// ローカルの規則を評価し、許可されたリクエストを外部レートリミットサービスへ問い合わせる
+    /// （F-147）
+    ///
+    /// ローカルの規則で超過したリクエストは問い合わせない（RPC の前段フィルタ）。
+    /// 超過は自動遮断（F-152）の兆候 `rate_limited` として報告する。
+    pub async fn evaluate(&self, req: &RateLimitRequest<'_>) -> RateLimitOutcome {
+        let outcome = self.evaluate_rules(req).await;
+        if matches!(outcome, RateLimitOutcome::Limited(_)) {
+            crate::auto_ban::report_str(req.client_ip, crate::auto_ban::Signal::RateLimited);
+        }
+        outcome
+    }
+
+    async fn evaluate_rules(&self, req: &RateLimitRequest<'_>) -> RateLimitOutcome {
+        let local = self.check(req);
+        if let Some(decision) = local.as_ref().filter(|d| d.is_limited()) {
+            return RateLimitOutcome::Limited(decision.clone());
+        }
+        #[cfg(all(feature = "http2", feature = "grpc"))]
+        if let Some(global) = &self.global {
+            use crate::global_rate_limit::GlobalVerdict;
+            let merge = |retry_after_secs: Option<u64>, headers: Vec<(String, String)>| {
+                let mut decision = local.clone().unwrap_or_default();
+                decision.retry_after_secs = retry_after_secs;
+                decision.extra_headers = headers;
+                decision
+            };
+            match global.check(&self.route, req).await {
+                None => {}
+                Some(GlobalVerdict::Allowed(headers)) if headers.is_empty() => {}
+                Some(GlobalVerdict::Allowed(headers)) => {
+                    return RateLimitOutcome::Allowed(Some(merge(None, headers)));
+                }
+                Some(GlobalVerdict::OverLimit {
+                    retry_after_secs,
+                    headers,
+                }) => {
+                    return RateLimitOutcome::Limited(merge(Some(retry_after_secs), headers));
+                }
+                Some(GlobalVerdict::Unavailable) => return RateLimitOutcome::Unavailable,
+            }
+        }
+        RateLimitOutcome::Allowed(local)
+    }
+
+    /// リクエストを評価し、適用した規則のトークンを消費する（適用した規則がなければ None）
+    ///
+    /// 超過した規則は `veil_rate_limit_hits_total` に記録する。
+    pub fn check(&self, req: &RateLimitRequest<'_>) -> Option<RateLimitDecision> {
+        self.check_at(&STORE, req, monotonic_ms())
+    }
+
+    fn check_at(
+        &self,
+        store: &BucketStore,
+        req: &RateLimitRequest<'_>,
+        now_ms: u64,
+    ) -> Option<RateLimitDecision> {
+        let mut state = String::new();
+        let mut policy = String::new();
+        let mut retry_after_secs = None;
+        for rule in &self.rules {
+            let Some(key) = rule.key_hash(&self.route, req) else {
+                continue;
+            };
+            let (allowed, tokens) = store.take(key, rule, now_ms);
+            if !allowed {
+                crate::metrics::record_rate_limit_hit(&rule.name);
+                let secs = rule.retry_after_secs(tokens);
+                retry_after_secs = Some(retry_after_secs.map_or(secs, |s: u64| s.max(secs)));
+            }
+            if !state.is_empty() {
+                state.push_str(", ");
+                policy.push_str(", ");
+            }
+            let _ = write!(
+                state,
+                "\"{}\";r={};t={}",
+                rule.name,
+                tokens as u64,
+                rule.reset_secs(tokens)
+            );
+            let _ = write!(
+                policy,
+                "\"{}\";q={};w={}",
+                rule.name, rule.burst as u64, rule.window_secs
+            );
+        }
+        if state.is_empty() {
+            return None;
+        }
+        Some(RateLimitDecision {
+            policy,
+            state,
+            retry_after_secs,
+            extra_headers: Vec::new(),
+        })
+    }
+}
+
+/// [`RouteRateLimit::evaluate`] の結果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RateLimitOutcome {
+    /// 許可（応答ヘッダーがあれば評価結果）
+    Allowed(Option<RateLimitDecision>),
+    /// 超過（429）
+    Limited(RateLimitDecision),
+    /// 外部レートリミットサービスに問い合わせできず `failure_mode = "closed"`（503）
+    Unavailable,
+}
+
+/// 1 リクエストの評価結果
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    /// `RateLimit-Policy` の値（ローカルの規則を適用しなければ空）
+    policy: String,
+    /// `RateLimit` の値（ローカルの規則を適用しなければ空）
+    state: String,
+    /// 超過時の `Retry-After`（秒）。許可なら None
+    retry_after_secs: Option<u64>,
+    /// 外部レートリミットサービスが返した応答ヘッダー（F-147）
+    extra_headers: Vec<(String, String)>,
+}
+
+impl RateLimitDecision {
+    /// いずれかの規則を超過したか
+    pub fn is_limited(&self) -> bool {
+        self.retry_after_secs.is_some()
+    }
+
+    /// 超過時の `Retry-After`（秒）
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        self.retry_after_secs
+    }
+
+    /// 応答に付けるヘッダー（`RateLimit-Policy` / `RateLimit` と外部サービスが返したヘッダー）
+    pub fn headers(&self) -> Vec<(&str, &str)> {
+        let mut headers = Vec::with_capacity(2 + self.extra_headers.len());
+        if !self.policy.is_empty() {
+            headers.push(("RateLimit-Policy", self.policy.as_str()));
+            headers.push(("RateLimit", self.state.as_str()));
+        }
+        headers.extend(
+            self.extra_headers
+                .iter()
+                .map(|(n, v)| (n.as_str(), v.as_str())),
+        );
+        headers
+    }
+
+    /// 429 応答のヘッダー（`Retry-After` を含む、HTTP/2・HTTP/3 用に小文字）
+    pub fn rejection_headers(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let mut headers: Vec<(Vec<u8>, Vec<u8>)> = self
+            .headers()
+            .into_iter()
+            .map(|(n, v)| (n.to_ascii_lowercase().into_bytes(), v.as_bytes().to_vec()))
+            .collect();
+        if let Some(secs) = self.retry_after_secs {
+            headers.push((b"retry-after".to_vec(), secs.to_string().into_bytes()));
+        }
+        headers
+    }
+
+    /// HTTP/1.1 の 429 応答
+    pub fn too_many_requests_response(&self) -> Vec<u8> {
+        let mut response = String::from("HTTP/1.1 429 Too Many Requests\r\n");
+        for (name, value) in self.headers() {
+            let _ = write!(response, "{}: {}\r\n", name, value);
+        }
+        let _ = write!(
+            response,
+            "Retry-After: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            self.retry_after_secs.unwrap_or(1)
+        );
+        response.into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(
+        name: &str,
+        key: &[&str],
+        rate: u32,
+        period_secs: u64,
+        burst: u32,
+    ) -> RateLimitRuleConfig {
+        RateLimitRuleConfig {
+            name: name.to_string(),
+            key: key.iter().map(|k| k.to_string()).collect(),
+            rate,
+            period_secs,
+            burst,
+            classes: Vec::new(),
+        }
+    }
+
+    fn req<'a>(client_ip: &'a str, headers: &'a [(&'a [u8], &'a [u8])]) -> RateLimitRequest<'a> {
+        RateLimitRequest {
+            client_ip,
+            path: b"/api/items?page=2",
+            headers,
+            claims: None,
+        }
+    }
+
+    #[test]
+    fn key_parts_parse() {
+        assert_eq!(KeyPart::parse("client_ip"), Some(KeyPart::ClientIp));
+        assert_eq!(
+            KeyPart::parse("header:X-Tenant"),
+            Some(KeyPart::Header("x-tenant".to_string()))
+        );
+        assert_eq!(
+            KeyPart::parse("jwt_claim:org.id"),
+            Some(KeyPart::JwtClaim("org.id".to_string()))
+        );
+        assert_eq!(KeyPart::parse("cookie:"), None);
+        assert_eq!(KeyPart::parse("query:page"), None);
+    }
+
+    #[test]
+    fn bucket_allows_burst_then_refills_at_rate() {
+        let store = BucketStore::new();
+        let limiter = RouteRateLimit::build(
+            "route[0]".into(),
+            &[rule("t-burst", &["client_ip"], 2, 1, 3)],
+            0,
+            None,
+        )
+        .unwrap();
+        let r = req("10.0.0.1", &[]);
+        let t0 = 1_000_000;
+        for remaining in [2, 1, 0] {
+            let d = limiter.check_at(&store, &r, t0).unwrap();
+            assert!(!d.is_limited());
+            assert!(d.state.contains(&format!("r={}", remaining)), "{}", d.state);
+        }
+        let denied = limiter.check_at(&store, &r, t0).unwrap();
+        assert!(denied.is_limited());
+        assert_eq!(denied.retry_after_secs(), Some(1));
+        assert_eq!(denied.policy, "\"t-burst\";q=3;w=2");
+        // 2 回 / 秒で補充: 500ms 後に 1 つ許可される
+        assert!(!limiter.check_at(&store, &r, t0 + 500).unwrap().is_limited());
+        assert!(limiter.check_at(&store, &r, t0 + 500).unwrap().is_limited());
+    }
+
+    #[test]
+    fn composite_keys_are_independent_and_missing_parts_skip_the_rule() {
+        let store = BucketStore::new();
+        let limiter = RouteRateLimit::build(
+            "route[1]".into(),
+            &[rule(
+                "t-tenant",
+                &["client_ip", "header:x-tenant"],
+                1,
+                60,
+                0,
+            )],
+            0,
+            None,
+        )
+        .unwrap();
+        let t0 = 2_000_000;
+        let limited = |ip: &str, headers: &[(&[u8], &[u8])]| {
+            limiter
+                .check_at(&store, &req(ip, headers), t0)
+                .map(|d| d.is_limited())
+        };
+        let a: &[(&[u8], &[u8])] = &[(b"X-Tenant", b"a")];
+        let b: &[(&[u8], &[u8])] = &[(b"X-Tenant", b"b")];
+        assert_eq!(limited("10.0.0.2", a), Some(false));
+        assert_eq!(limited("10.0.0.2", a), Some(true));
+        assert_eq!(limited("10.0.0.2", b), Some(false));
+        assert_eq!(limited("10.0.0.3", a), Some(false));
+        // ヘッダーがなければ規則を適用しない
+        assert_eq!(limited("10.0.0.2", &[]), None);
+    }
+
+    #[test]
+    fn class_rules_apply_only_to_verified_classes() {
+        let store = BucketStore::new();
+        let gold = RateLimitRuleConfig {
+            classes: vec!["gold".to_string()],
+            ..rule("t-gold", &["jwt_claim:sub"], 1, 60, 0)
+        };
+        let limiter = RouteRateLimit::build("route[2]".into(), &[gold], 0, None).unwrap();
+        let t0 = 3_000_000;
+        let claims = |class: &str| serde_json::json!({"sub": "ci", "rate_limit_class": class});
+        let limited = |claims: Option<&serde_json::Value>| {
+            let r = RateLimitRequest {
+                claims,
+                ..req("10.0.0.5", &[])
+            };
+            limiter.check_at(&store, &r, t0).map(|d| d.is_limited())
+        };
+        let (gold, silver) = (claims("gold"), claims("silver"));
+        assert_eq!(limited(Some(&gold)), Some(false));
+        assert_eq!(limited(Some(&gold)), Some(true));
+        assert_eq!(limited(Some(&silver)), None);
+        // 検証済みのクレームが無ければ（署名の無い JWT からは）クラスを読まない
+        assert_eq!(limited(None), None);
+    }
+
+    #[test]
+    fn cookie_jwt_claim_and_api_key_resolve() {
+        // {"sub":"alice","org":{"id":42}}
+        let bearer: &[u8] =
+            b"Bearer eyJhbGciOiJub25lIn0.eyJzdWIiOiJhbGljZSIsIm9yZyI6eyJpZCI6NDJ9fQ.";
+        let headers: &[(&[u8], &[u8])] = &[
+            (b"Cookie", b"theme=dark; session = s1 "),
+            (b"Authorization", bearer),
+            (b"X-API-Key", b"k-123"),
+        ];
+        let r = req("10.0.0.4", headers);
+        assert_eq!(r.cookie("session"), Some(&b"s1"[..]));
+        assert_eq!(r.cookie("missing"), None);
+        assert_eq!(r.header(API_KEY_HEADER), Some(&b"k-123"[..]));
+        // 署名を検証していないトークンのクレームは使わない
+        assert_eq!(r.jwt_claim("sub"), None);
+
+        let verified = serde_json::json!({"sub": "bob", "org": {"id": 42}});
+        let r = RateLimitRequest {
+            claims: Some(&verified),
+            ..req("10.0.0.4", headers)
+        };
+        assert_eq!(r.jwt_claim("sub").as_deref(), Some("bob"));
+        assert_eq!(r.jwt_claim("org.id").as_deref(), Some("42"));
+        assert_eq!(r.jwt_claim("org"), None);
+    }
+
+    #[test]
+    fn jwt_claim_keys_skip_requests_without_verified_claims() {
+        let store = BucketStore::new();
+        let limiter = RouteRateLimit::build(
+            "route[6]".into(),
+            &[rule("t-sub", &["jwt_claim:sub"], 1, 60, 0)],
+            0,
+            None,
+        )
+        .unwrap();
+        let t0 = 5_000_000;
+        // {"sub":"alice"}（署名なし）: 偽造したトークンで他人のバケットを消費できない
+        let forged: &[(&[u8], &[u8])] = &[(
+            b"Authorization",
+            b"Bearer eyJhbGciOiJub25lIn0.eyJzdWIiOiJhbGljZSJ9.",
+        )];
+        assert_eq!(limiter.check_at(&store, &req("10.0.0.7", forged), t0), None);
+        let alice = serde_json::json!({"sub": "alice"});
+        let verified = |ip| RateLimitRequest {
+            claims: Some(&alice),
+            ..req(ip, &[])
+        };
+        assert!(!limiter
+            .check_at(&store, &verified("10.0.0.7"), t0)
+            .unwrap()
+            .is_limited());
+        assert!(limiter
+            .check_at(&store, &verified("10.0.0.8"), t0)
+            .unwrap()
+            .is_limited());
+    }
+
+    #[test]
+    fn multiple_rules_report_every_policy_and_the_longest_retry_after() {
+        let store = BucketStore::new();
+        let limiter = RouteRateLimit::build(
+            "route[2]".into(),
+            &[
+                rule("t-fast", &["client_ip"], 10, 1, 0),
+                rule("t-slow", &["client_ip", "route"], 1, 30, 0),
+            ],
+            0,
+            None,
+        )
+        .unwrap();
+        let r = req("10.0.0.5", &[]);
+        let t0 = 3_000_000;
+        let first = limiter.check_at(&store, &r, t0).unwrap();
+        assert_eq!(first.policy, "\"t-fast\";q=10;w=1, \"t-slow\";q=1;w=30");
+        assert_eq!(first.state, "\"t-fast\";r=9;t=1, \"t-slow\";r=0;t=30");
+        let second = limiter.check_at(&store, &r, t0).unwrap();
+        assert_eq!(second.retry_after_secs(), Some(30));
+        let response = String::from_utf8(second.too_many_requests_response()).unwrap();
+        assert!(response.starts_with("HTTP/1.1 429 Too Many Requests\r\n"));
+        assert!(response.contains("\r\nRetry-After: 30\r\n"));
+    }
+
+    #[test]
+    fn per_minute_setting_becomes_a_shared_client_ip_bucket() {
+        let store = BucketStore::new();
+        let a = RouteRateLimit::build("route[3]".into(), &[], 2, None).unwrap();
+        let b = RouteRateLimit::build("route[4]".into(), &[], 2, None).unwrap();
+        let r = req("10.0.0.6", &[]);
+        let t0 = 4_000_000;
+        assert!(!a.check_at(&store, &r, t0).unwrap().is_limited());
+        // 同じ名前・パラメータの規則はルートをまたいで共有する
+        assert!(!b.check_at(&store, &r, t0).unwrap().is_limited());
+        let denied = a.check_at(&store, &r, t0).unwrap();
+        assert!(denied.is_limited());
+        assert_eq!(denied.retry_after_secs(), Some(30));
+        assert!(RouteRateLimit::build("route[5]".into(), &[], 0, None).is_none());
+    }
+}
@@ -0,0 +1,433 @@
+//! ルートの認証・レートリミット・CORS・WAF・外部認可（HTTP/1.1・HTTP/2・HTTP/3 共通）
+//!
+//! 基本のセキュリティチェック（IP 制限・メソッド・ボディサイズ）と CORS のプリフライトを通った
+//! リクエストを次の順に検査する。
+//!
+//! 1. JWT 認証（F-148）・OIDC ログイン（F-150）・Basic / API キー認証（F-151）。
+//!    複数の認証があれば検証済みのクレームと上流へ転送するヘッダーを合わせる
+//!    （同名のクレーム・ヘッダーは後の認証の値を使う）。
+//! 2. レートリミット（F-146 / F-147）。許可時も `RateLimit` ヘッダーを返す。
+//! 3. CORS の応答ヘッダー（F-155）
+//! 4. WAF（F-154）
+//! 5. 外部認可（F-149）。検証済みのクレームを渡す。
+//!
+//! ボディは、ボディを検査する WAF・ボディを渡す外部認可があるときだけ呼び出し側から受け取る
+//! （HTTP/1.1 はここで上限まで受信する）。拒否の応答は [`Rejection`] からプロトコルごとに組み立てる。
+
+use std::borrow::Cow;
+use std::future::Future;
+
+use serde_json::Value;
+
+use crate::config::{ExtraHeaders, SecurityConfig};
+use crate::constants::{ERR_MSG_FORBIDDEN, ERR_MSG_REQUEST_TOO_LARGE, ERR_MSG_SERVICE_UNAVAILABLE};
+use crate::credential_auth::CredentialRejection;
+use crate::ext_authz::{AuthzDecision, AuthzDenied};
+use crate::jwt_auth::JwtRejection;
+use crate::oidc::{OidcOutcome, OidcResponse};
+use crate::rate_limit::{RateLimitDecision, RateLimitOutcome};
+use crate::waf::WafDecision;
+
+/// 検査するリクエスト
+pub(crate) struct RequestInfo<'a> {
+    pub client_ip: &'a str,
+    pub method: &'a [u8],
+    /// リクエストパス（クエリ文字列を含む）
+    pub path: &'a [u8],
+    pub host: &'a [u8],
+    /// 疑似ヘッダーを除くリクエストヘッダー
+    pub headers: &'a [(&'a [u8], &'a [u8])],
+}
+
+/// 検査の結果
+pub(crate) struct Checked {
+    /// 検証済みのクレーム（拒否した場合はそれまでの認証で検証したもの）
+    pub claims: Option<Value>,
+    /// 通過すればリクエスト単位で加えるヘッダー、拒否ならクライアントへ返す応答
+    pub outcome: Result<ExtraHeaders, Rejection>,
+}
+
+/// 拒否
+#[derive(Debug)]
+pub(crate) enum Rejection {
+    Jwt(JwtRejection),
+    /// OIDC ログインのリダイレクト・コールバック・ログアウト・拒否
+    Oidc(OidcResponse),
+    Credential(CredentialRejection),
+    /// レートリミット超過（429）
+    RateLimited(RateLimitDecision),
+    /// 外部レートリミットサービスに問い合わせできない（503）
+    RateLimitUnavailable,
+    /// WAF が遮断した（403）
+    WafBlocked,
+    /// WAF が検査する・外部認可へ渡すボディが上限を超えた（413）
+    PayloadTooLarge,
+    AuthzDenied(AuthzDenied),
+    /// ボディを受信できなかった（応答せずに接続を閉じる）
+    BodyUnavailable,
+}
+
+impl Rejection {
+    /// 応答のステータス
+    #[cfg_attr(not(any(feature = "http2", feature = "http3")), allow(dead_code))]
+    pub(crate) fn status(&self) -> u16 {
+        match self {
+            Self::Jwt(rejection) => rejection.status(),
+            Self::Oidc(response) => response.status,
+            Self::Credential(rejection) => rejection.status(),
+            Self::RateLimited(_) => 429,
+            Self::RateLimitUnavailable => 503,
+            Self::WafBlocked => 403,
+            Self::PayloadTooLarge => 413,
+            Self::AuthzDenied(denied) => denied.status,
+            Self::BodyUnavailable => 400,
+        }
+    }
+
+    /// 応答ボディ
+    #[cfg_attr(not(any(feature = "http2", feature = "http3")), allow(dead_code))]
+    pub(crate) fn body(&self) -> &[u8] {
+        match self {
+            Self::Jwt(rejection) => rejection.body(),
+            Self::Oidc(response) => response.body(),
+            Self::Credential(rejection) => rejection.body(),
+            Self::RateLimited(_) => b"Too Many Requests",
+            Self::RateLimitUnavailable => b"Service Unavailable",
+            Self::WafBlocked => b"Forbidden",
+            Self::PayloadTooLarge => b"Payload Too Large",
+            Self::AuthzDenied(denied) => &denied.body,
+            Self::BodyUnavailable => b"",
+        }
+    }
+
+    /// HTTP/2・HTTP/3 の応答ヘッダー（小文字、`server` は呼び出し側が付ける）
+    #[cfg_attr(not(any(feature = "http2", feature = "http3")), allow(dead_code))]
+    pub(crate) fn h2_headers(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let mut headers = match self {
+            Self::Jwt(rejection) => rejection.headers(),
+            Self::Oidc(response) => return response.h2_headers(),
+            Self::Credential(rejection) => rejection.headers(),
+            Self::RateLimited(decision) => decision.rejection_headers(),
+            Self::AuthzDenied(denied) => {
+                let mut headers = denied.h2_headers();
+                headers.retain(|(name, _)| name != b"server");
+                return headers;
+            }
+            _ => Vec::new(),
+        };
+        headers.push((b"content-type".to_vec(), b"text/plain".to_vec()));
+        headers
+    }
+
+    /// HTTP/1.1 の応答（None なら応答せずに接続を閉じる）
+    pub(crate) fn http1_response(&self) -> Option<Vec<u8>> {
+        Some(match self {
+            Self::Jwt(rejection) => rejection.http1_response(),
+            Self::Oidc(response) => response.http1_response(),
+            Self::Credential(rejection) => rejection.http1_response(),
+            Self::RateLimited(decision) => decision.too_many_requests_response(),
+            Self::RateLimitUnavailable => ERR_MSG_SERVICE_UNAVAILABLE.to_vec(),
+            Self::WafBlocked => ERR_MSG_FORBIDDEN.to_vec(),
+            Self::PayloadTooLarge => ERR_MSG_REQUEST_TOO_LARGE.to_vec(),
+            Self::AuthzDenied(denied) => denied.http1_response(),
+            Self::BodyUnavailable => return None,
+        })
+    }
+}
+
+/// 認証で確定した利用者
+#[derive(Default)]
+struct Identity {
+    claims: Option<Value>,
+    /// 上流へのリクエストに設定するヘッダー
+    headers: Vec<(String, String)>,
+}
+
+impl Identity {
+    /// 認証の結果を加える（同名のクレームは置き換え、ヘッダーは後に並べる）
+    fn merge(&mut self, claims: Value, headers: Vec<(String, String)>) {
+        match (self.claims.as_mut(), claims) {
+            (Some(Value::Object(current)), Value::Object(claims)) => current.extend(claims),
+            (_, claims) => self.claims = Some(claims),
+        }
+        self.headers.extend(headers);
+    }
+}
+
+/// ルートの認証・レートリミット・CORS・WAF・外部認可を評価する
+///
+/// `read_body` は WAF・外部認可がボディを必要とするときだけ、必要な上限（バイト）を渡して呼ぶ。
+/// 上限を超えたかを判定できるように上限より 1 バイト以上多く返してよい。None を返すと
+/// [`Rejection::BodyUnavailable`] になる。
+pub(crate) async fn check_request<'b, F, Fut>(
+    security: &SecurityConfig,
+    req: &RequestInfo<'_>,
+    read_body: F,
+) -> Checked
+where
+    F: FnOnce(usize) -> Fut,
+    Fut: Future<Output = Option<Cow<'b, [u8]>>>,
+{
+    let mut identity = Identity::default();
+    let outcome = check(security, req, read_body, &mut identity).await;
+    Checked {
+        claims: identity.claims,
+        outcome,
+    }
+}
+
+async fn check<'b, F, Fut>(
+    security: &SecurityConfig,
+    req: &RequestInfo<'_>,
+    read_body: F,
+    identity: &mut Identity,
+) -> Result<ExtraHeaders, Rejection>
+where
+    F: FnOnce(usize) -> Fut,
+    Fut: Future<Output = Option<Cow<'b, [u8]>>>,
+{
+    // F-148: JWT 認証
+    if let Some(jwt) = security.jwt.as_ref() {
+        let claims = jwt.authenticate(req.headers).map_err(Rejection::Jwt)?;
+        let headers = jwt.forwarded_headers(&claims);
+        identity.merge(claims, headers);
+    }
+
+    // F-150: OIDC ログイン
+    let mut set_cookie = None;
+    if let Some(oidc) = security.oidc.as_ref() {
+        let outcome = oidc
+            .authenticate(&crate::oidc::OidcRequest {
+                method: req.method,
+                path: req.path,
+                host: req.host,
+                headers: req.headers,
+            })
+            .await;
+        match outcome {
+            OidcOutcome::Authenticated {
+                claims,
+                headers,
+                set_cookie: cookie,
+            } => {
+                identity.merge(claims, headers);
+                set_cookie = cookie;
+            }
+            OidcOutcome::Respond(response) => return Err(Rejection::Oidc(response)),
+        }
+    }
+
+    // F-151: Basic 認証・API キー認証
+    if let Some(auth) = security.credential_auth.as_ref() {
+        let (claims, headers) = auth
+            .authenticate(req.headers)
+            .await
+            .map_err(Rejection::Credential)?;
+        identity.merge(claims, headers);
+    }
+
+    let mut extra = ExtraHeaders::default();
+
+    // F-146 / F-147: レートリミット（検証済みのクレームをキーに使う）
+    if let Some(limiter) = security.rate_limit.as_ref() {
+        let outcome = limiter
+            .evaluate(&crate::rate_limit::RateLimitRequest {
+                client_ip: req.client_ip,
+                path: req.path,
+                headers: req.headers,
+                claims: identity.claims.as_ref(),
+            })
+            .await;
+        match outcome {
+            RateLimitOutcome::Allowed(None) => {}
+            RateLimitOutcome::Allowed(Some(decision)) => extra.push_rate_limit_headers(&decision),
+            RateLimitOutcome::Limited(decision) => return Err(Rejection::RateLimited(decision)),
+            RateLimitOutcome::Unavailable => return Err(Rejection::RateLimitUnavailable),
+        }
+    }
+    if let Some(cookie) = set_cookie {
+        extra.push_response_header("Set-Cookie", cookie);
+    }
+    extra.set_request_headers(std::mem::take(&mut identity.headers));
+
+    // F-155: CORS の応答ヘッダー（上流の Access-Control-* は置き換える）
+    if let Some(cors) = security.cors.as_ref() {
+        extra.push_cors_headers(cors.response_headers(req.headers));
+    }
+
+    let waf = security.waf.as_ref();
+    let authz = security.ext_authz.as_ref();
+    let body_limit = [
+        waf.filter(|w| w.inspects_body())
+            .map(|w| w.max_body_bytes()),
+        authz.filter(|a| a.sends_body()).map(|a| a.max_body_bytes()),
+    ]
+    .into_iter()
+    .flatten()
+    .max();
+    let body = match body_limit {
+        Some(limit) => read_body(limit).await.ok_or(Rejection::BodyUnavailable)?,
+        None => Cow::Borrowed(&b""[..]),
+    };
+
+    // F-154: WAF
+    if let Some(waf) = waf {
+        let decision = waf.inspect(&crate::waf::WafRequest {
+            client_ip: req.client_ip,
+            method: req.method,
+            host: req.host,
+            uri: req.path,
+            headers: req.headers,
+            body: &body,
+        });
+        match decision {
+            WafDecision::Allow => {}
+            WafDecision::Block => return Err(Rejection::WafBlocked),
+            WafDecision::BodyTooLarge => return Err(Rejection::PayloadTooLarge),
+        }
+    }
+
+    // F-149: 外部認可
+    if let Some(authz) = authz {
+        let body: &[u8] = if authz.sends_body() { &body } else { b"" };
+        let body = authz.authz_body(body).ok_or(Rejection::PayloadTooLarge)?;
+        let decision = authz
+            .check(&crate::ext_authz::AuthzRequest {
+                method: req.method,
+                path: req.path,
+                host: req.host,
+                client_ip: req.client_ip,
+                headers: req.headers,
+                body,
+                claims: identity.claims.as_ref(),
+            })
+            .await;
+        match &*decision {
+            AuthzDecision::Allow { set, remove } => extra.apply_request_header_changes(set, remove),
+            AuthzDecision::Deny(denied) => return Err(Rejection::AuthzDenied(denied.clone())),
+        }
+    }
+
+    Ok(extra)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn info<'a>(headers: &'a [(&'a [u8], &'a [u8])]) -> RequestInfo<'a> {
+        RequestInfo {
+            client_ip: "192.0.2.10",
+            method: b"POST",
+            path: b"/api/items?page=2",
+            host: b"api.example",
+            headers,
+        }
+    }
+
+    fn run<'b, F, Fut>(
+        security: &SecurityConfig,
+        headers: &[(&[u8], &[u8])],
+        read_body: F,
+    ) -> Checked
+    where
+        F: FnOnce(usize) -> Fut,
+        Fut: Future<Output = Option<Cow<'b, [u8]>>>,
+    {
+        futures::executor::block_on(check_request(security, &info(headers), read_body))
+    }
+
+    fn no_body(_: usize) -> std::future::Ready<Option<Cow<'static, [u8]>>> {
+        panic!("body must not be read");
+    }
+
+    #[test]
+    fn identities_merge_claims_and_forwarded_headers() {
+        let mut identity = Identity::default();
+        identity.merge(
+            serde_json::json!({"sub": "svc", "scope": "read"}),
+            vec![("X-User".to_string(), "svc".to_string())],
+        );
+        identity.merge(
+            serde_json::json!({"sub": "alice", "email": "alice@example.com"}),
+            vec![(
+                "X-Auth-Request-Email".to_string(),
+                "alice@example.com".to_string(),
+            )],
+        );
+        assert_eq!(
+            identity.claims,
+            Some(
+                serde_json::json!({"sub": "alice", "scope": "read", "email": "alice@example.com"})
+            )
+        );
+        assert_eq!(
+            identity.headers,
+            [
+                ("X-User".to_string(), "svc".to_string()),
+                (
+                    "X-Auth-Request-Email".to_string(),
+                    "alice@example.com".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn routes_without_checks_pass_without_reading_the_body() {
+        let checked = run(&SecurityConfig::default(), &[], no_body);
+        assert!(checked.claims.is_none());
+        let extra = checked.outcome.unwrap();
+        assert!(extra.request.is_empty() && extra.response.is_empty());
+    }
+
+    #[test]
+    fn authentication_failures_stop_before_the_other_checks() {
+        let jwt: crate::config::JwtAuthConfig = toml::from_str("").unwrap();
+        let security = SecurityConfig {
+            jwt: Some(Arc::new(crate::jwt_auth::JwtAuth::without_keys(&jwt))),
+            ..SecurityConfig::default()
+        };
+        let rejection = run(&security, &[], no_body).outcome.unwrap_err();
+        assert!(matches!(rejection, Rejection::Jwt(JwtRejection::Missing)));
+        assert_eq!(rejection.status(), 401);
+        let response = String::from_utf8(rejection.http1_response().unwrap()).unwrap();
+        assert!(response.starts_with("HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Bearer\r\n"));
+    }
+
+    #[test]
+    fn waf_reads_the_body_up_to_its_limit() {
+        let mut cfg: crate::config::WafConfig =
+            toml::from_str("inspect_body = true\nmax_body_bytes = 16").unwrap();
+        cfg.rules = r#"SecRule REQUEST_BODY "@contains attack" "id:1,phase:2,deny""#.to_string();
+        let security = SecurityConfig {
+            waf: Some(Arc::new(
+                crate::waf::Waf::new("route[0]".to_string(), &cfg).unwrap(),
+            )),
+            ..SecurityConfig::default()
+        };
+        let body = |data: &'static [u8]| {
+            move |limit: usize| {
+                assert_eq!(limit, 16);
+                std::future::ready(Some(Cow::Borrowed(data)))
+            }
+        };
+        let blocked = run(&security, &[], body(b"an attack")).outcome.unwrap_err();
+        assert!(matches!(blocked, Rejection::WafBlocked));
+        assert_eq!(blocked.http1_response().unwrap(), ERR_MSG_FORBIDDEN);
+        assert!(run(&security, &[], body(b"benign")).outcome.is_ok());
+        // 上限を超えたボディは検査しきれないので 413
+        let oversized = run(&security, &[], body(b"benign but too long"))
+            .outcome
+            .unwrap_err();
+        assert!(matches!(oversized, Rejection::PayloadTooLarge));
+        assert_eq!(
+            oversized.http1_response().unwrap(),
+            ERR_MSG_REQUEST_TOO_LARGE
+        );
+
+        let closed = run(&security, &[], |_| std::future::ready(None));
+        assert!(closed.outcome.unwrap_err().http1_response().is_none());
+    }
+}
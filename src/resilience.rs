@@ -4,17 +4,20 @@
 //! - スライディングウィンドウ（失敗率の計測）
 //! - リトライポリシーは未実装（旧 RetryPolicy 構造体は dead code のため F-51 で削除）
 //! - パッシブ異常検知（Outlier Detection、config.rs の UpstreamServer で実装）
+//! - 適応型の同時実行数制限（F-138、upstream グループ単位の勾配 / AIMD）
 //!
 //! データプレーン上で動作するため tokio に依存しない。
 //! 状態は `std::sync::Mutex` と Atomic で保護する（ハンドシェイク後の
 //! ホットパスでは可能な限り Atomic のみで判定する）。
 
 use std::collections::VecDeque;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use crate::config::CircuitBreakerConfig;
+use crate::config::{
+    AdaptiveConcurrencyAlgorithm, AdaptiveConcurrencyConfig, CircuitBreakerConfig,
+};
 
 /// 一定時間ウィンドウ内の成功/失敗を記録するリングバッファ
 ///
@@ -264,6 +267,259 @@ impl CircuitBreaker {
     }
 }
 
+// ====================
+// 適応型の同時実行数制限（F-138）
+// ====================
+
+/// 上限を見直すのに必要なウィンドウ内の最小サンプル数（少数の観測で上限を動かさない）
+const MIN_WINDOW_SAMPLES: u32 = 5;
+
+/// 勾配方式の平滑化係数（新しい推定値の重み）
+const GRADIENT_SMOOTHING: f64 = 0.2;
+
+/// 勾配の下限（1 ウィンドウで上限を半分未満へ下げない）
+const MIN_GRADIENT: f64 = 0.5;
+
+/// RTT の集計ウィンドウ
+#[derive(Debug)]
+struct LimiterWindow {
+    started_at: Instant,
+    rtt_sum: Duration,
+    min_rtt: Duration,
+    samples: u32,
+    failures: u32,
+    /// ウィンドウ内で観測した最大の同時実行数（上限が使われているかの判定用）
+    max_in_flight: u32,
+}
+
+impl LimiterWindow {
+    fn new(now: Instant) -> Self {
+        Self {
+            started_at: now,
+            rtt_sum: Duration::ZERO,
+            min_rtt: Duration::MAX,
+            samples: 0,
+            failures: 0,
+            max_in_flight: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    /// 小数部を保持した上限（公開値は `AdaptiveConcurrency::limit` に丸めて反映）
+    limit: f64,
+    /// 基準となる最小 RTT（`min_rtt_expires` 経過後のウィンドウで測り直す）
+    min_rtt: Option<Duration>,
+    min_rtt_expires: Instant,
+    window: LimiterWindow,
+}
+
+/// 適応型の同時実行数制限（upstream グループ単位、全ワーカーで共有）
+///
+/// 受け入れ判定は Atomic のみで行い、完了時の RTT 集計のみ Mutex を取る
+/// （CircuitBreaker の記録と同じ粒度）。上限は `sample_window_ms` ごとに見直す。
+#[derive(Debug)]
+pub struct AdaptiveConcurrency {
+    algorithm: AdaptiveConcurrencyAlgorithm,
+    min_limit: f64,
+    max_limit: f64,
+    rtt_tolerance: f64,
+    backoff_ratio: f64,
+    sample_window: Duration,
+    min_rtt_recalc: Duration,
+    retry_after_secs: u64,
+    /// 現在の上限
+    limit: AtomicU32,
+    /// 処理中のリクエスト数
+    in_flight: AtomicU32,
+    /// 上限超過で拒否した累計
+    rejected: AtomicU64,
+    /// 最小 RTT（マイクロ秒、0 = 未計測）統計用
+    min_rtt_us: AtomicU64,
+    state: Mutex<LimiterState>,
+}
+
+impl AdaptiveConcurrency {
+    /// 設定から作成（妥当性は設定読み込み時に検証済み）
+    pub fn new(cfg: &AdaptiveConcurrencyConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            algorithm: cfg.algorithm,
+            min_limit: cfg.min_limit as f64,
+            max_limit: cfg.max_limit as f64,
+            rtt_tolerance: cfg.rtt_tolerance,
+            backoff_ratio: cfg.backoff_ratio,
+            sample_window: Duration::from_millis(cfg.sample_window_ms),
+            min_rtt_recalc: Duration::from_secs(cfg.min_rtt_recalc_secs),
+            retry_after_secs: cfg.retry_after_secs,
+            limit: AtomicU32::new(cfg.initial_limit),
+            in_flight: AtomicU32::new(0),
+            rejected: AtomicU64::new(0),
+            min_rtt_us: AtomicU64::new(0),
+            state: Mutex::new(LimiterState {
+                limit: cfg.initial_limit as f64,
+                min_rtt: None,
+                min_rtt_expires: now,
+                window: LimiterWindow::new(now),
+            }),
+        }
+    }
+
+    /// 同時実行の枠を取る。上限に達していれば None（拒否数を加算）
+    pub fn try_acquire(self: &Arc<Self>) -> Option<ConcurrencyPermit> {
+        let limit = self.limit.load(Ordering::Relaxed);
+        match self
+            .in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |n| {
+                (n < limit).then_some(n + 1)
+            }) {
+            Ok(prev) => Some(ConcurrencyPermit {
+                limiter: Arc::clone(self),
+                started_at: Instant::now(),
+                in_flight: prev + 1,
+            }),
+            Err(_) => {
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// 現在の上限
+    pub fn limit(&self) -> u32 {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    /// 処理中のリクエスト数
+    pub fn in_flight(&self) -> u32 {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// 上限超過で拒否した累計
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// 基準の最小 RTT（未計測なら None）
+    pub fn min_rtt(&self) -> Option<Duration> {
+        match self.min_rtt_us.load(Ordering::Relaxed) {
+            0 => None,
+            us => Some(Duration::from_micros(us)),
+        }
+    }
+
+    /// 拒否時の `Retry-After`（秒）
+    pub fn retry_after_secs(&self) -> u64 {
+        self.retry_after_secs
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, LimiterState> {
+        match self.state.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        }
+    }
+
+    /// 完了したリクエストの RTT を集計し、ウィンドウを閉じたら上限を見直す。
+    /// 上限が変わった場合は新しい上限を返す。
+    fn record(&self, rtt: Duration, success: bool, in_flight: u32, now: Instant) -> Option<u32> {
+        let mut state = self.lock_state();
+        let window = &mut state.window;
+        window.rtt_sum += rtt;
+        window.min_rtt = window.min_rtt.min(rtt);
+        window.samples += 1;
+        window.max_in_flight = window.max_in_flight.max(in_flight);
+        if !success {
+            window.failures += 1;
+        }
+        if window.samples < MIN_WINDOW_SAMPLES
+            || now.saturating_duration_since(window.started_at) < self.sample_window
+        {
+            return None;
+        }
+        let window = std::mem::replace(&mut state.window, LimiterWindow::new(now));
+        let sampled_rtt = window.rtt_sum / window.samples;
+
+        // 最小 RTT は期限まで下方向にのみ更新し、期限切れ後はこのウィンドウの最小値で測り直す
+        let min_rtt = match state.min_rtt {
+            Some(min) if now < state.min_rtt_expires => min.min(window.min_rtt),
+            _ => {
+                state.min_rtt_expires = now + self.min_rtt_recalc;
+                window.min_rtt
+            }
+        };
+        state.min_rtt = Some(min_rtt);
+        self.min_rtt_us
+            .store((min_rtt.as_micros() as u64).max(1), Ordering::Relaxed);
+
+        let limit = state.limit;
+        let tolerated = min_rtt.as_secs_f64() * self.rtt_tolerance;
+        let sampled = sampled_rtt.as_secs_f64();
+        let utilized = window.max_in_flight as f64 * 2.0 >= limit;
+        let new_limit = match self.algorithm {
+            // 上限の半分も使われていなければ RTT の変化は同時実行数によるものではないため据え置く
+            AdaptiveConcurrencyAlgorithm::Gradient if !utilized => limit,
+            AdaptiveConcurrencyAlgorithm::Gradient => {
+                let gradient = if sampled > 0.0 {
+                    (tolerated / sampled).clamp(MIN_GRADIENT, 1.0)
+                } else {
+                    1.0
+                };
+                // √上限 をキューの余裕として加え、RTT が許容内なら上限を押し上げる
+                let estimate = limit * gradient + limit.sqrt();
+                limit * (1.0 - GRADIENT_SMOOTHING) + estimate * GRADIENT_SMOOTHING
+            }
+            AdaptiveConcurrencyAlgorithm::Aimd => {
+                if window.failures > 0 || sampled > tolerated {
+                    limit * self.backoff_ratio
+                } else if utilized {
+                    limit + 1.0
+                } else {
+                    limit
+                }
+            }
+        }
+        .clamp(self.min_limit, self.max_limit);
+        state.limit = new_limit;
+
+        let rounded = (new_limit.round() as u32).max(1);
+        let prev = self.limit.swap(rounded, Ordering::Relaxed);
+        (prev != rounded).then_some(rounded)
+    }
+}
+
+/// 同時実行の枠（drop で返却する）
+///
+/// [`ConcurrencyPermit::complete`] を呼ばずに drop した場合（クライアント切断・
+/// アップロード中継など RTT が上流の応答時間を表さない場合）は RTT を記録しない。
+#[derive(Debug)]
+pub struct ConcurrencyPermit {
+    limiter: Arc<AdaptiveConcurrency>,
+    started_at: Instant,
+    /// 枠を取った時点の同時実行数
+    in_flight: u32,
+}
+
+impl ConcurrencyPermit {
+    /// リクエスト完了を記録して枠を返す。上限が変わった場合は新しい上限を返す
+    pub fn complete(self, success: bool) -> Option<u32> {
+        let now = Instant::now();
+        self.limiter.record(
+            now.saturating_duration_since(self.started_at),
+            success,
+            self.in_flight,
+            now,
+        )
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        self.limiter.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     // 理由付き allow: テストコードは同期 I/O・sleep を使用してよい（データプレーン非経由）。
@@ -348,4 +604,101 @@ mod tests {
         assert!(cb.stats().failure_count.load(Ordering::Relaxed) >= 3);
         assert_eq!(cb.stats().open_count.load(Ordering::Relaxed), 1);
     }
+
+    fn limiter(algorithm: AdaptiveConcurrencyAlgorithm) -> Arc<AdaptiveConcurrency> {
+        Arc::new(AdaptiveConcurrency::new(&AdaptiveConcurrencyConfig {
+            enabled: true,
+            algorithm,
+            initial_limit: 20,
+            min_limit: 2,
+            max_limit: 100,
+            sample_window_ms: 100,
+            ..AdaptiveConcurrencyConfig::default()
+        }))
+    }
+
+    /// 1 ウィンドウ分（MIN_WINDOW_SAMPLES 件）の RTT を記録し、最後の戻り値を返す
+    fn feed_window(
+        l: &AdaptiveConcurrency,
+        now: &mut Instant,
+        rtt_ms: u64,
+        success: bool,
+        in_flight: u32,
+    ) -> Option<u32> {
+        *now += Duration::from_millis(100);
+        let mut changed = None;
+        for _ in 0..MIN_WINDOW_SAMPLES {
+            changed = l.record(Duration::from_millis(rtt_ms), success, in_flight, *now);
+        }
+        changed
+    }
+
+    #[test]
+    fn adaptive_concurrency_rejects_over_limit() {
+        let l = Arc::new(AdaptiveConcurrency::new(&AdaptiveConcurrencyConfig {
+            enabled: true,
+            initial_limit: 2,
+            ..AdaptiveConcurrencyConfig::default()
+        }));
+        let a = l.try_acquire().expect("first permit");
+        let _b = l.try_acquire().expect("second permit");
+        assert!(l.try_acquire().is_none());
+        assert_eq!(l.rejected(), 1);
+        assert_eq!(l.in_flight(), 2);
+        // 完了（または drop）で枠が戻る
+        drop(a);
+        assert_eq!(l.in_flight(), 1);
+        assert!(l.try_acquire().is_some());
+    }
+
+    #[test]
+    fn gradient_grows_within_tolerance_and_shrinks_on_latency() {
+        let l = limiter(AdaptiveConcurrencyAlgorithm::Gradient);
+        let mut now = Instant::now();
+        // RTT が最小 RTT と同じ（許容内）で上限近くまで使われていれば増える
+        let grown = feed_window(&l, &mut now, 10, true, 20).expect("limit grows");
+        assert!(grown > 20, "grown = {}", grown);
+        assert_eq!(l.min_rtt(), Some(Duration::from_millis(10)));
+        // RTT が最小 RTT × 許容率を超えると減る
+        let before = l.limit();
+        feed_window(&l, &mut now, 60, true, before);
+        assert!(l.limit() < before, "{} -> {}", before, l.limit());
+        // 遅延が続くと √上限 の余裕分（上限 4 前後）まで下がり、下限（2）は下回らない
+        for _ in 0..100 {
+            feed_window(&l, &mut now, 60, true, l.limit());
+        }
+        assert!((2..=4).contains(&l.limit()), "limit = {}", l.limit());
+    }
+
+    #[test]
+    fn gradient_keeps_limit_when_underutilized() {
+        let l = limiter(AdaptiveConcurrencyAlgorithm::Gradient);
+        let mut now = Instant::now();
+        feed_window(&l, &mut now, 10, true, 2);
+        assert_eq!(feed_window(&l, &mut now, 80, true, 2), None);
+        assert_eq!(l.limit(), 20);
+    }
+
+    #[test]
+    fn aimd_backs_off_on_failure_and_latency() {
+        let l = limiter(AdaptiveConcurrencyAlgorithm::Aimd);
+        let mut now = Instant::now();
+        assert_eq!(feed_window(&l, &mut now, 10, true, 20), Some(21));
+        // 失敗を含むウィンドウは倍率減少（21 × 0.9 = 18.9）
+        assert_eq!(feed_window(&l, &mut now, 10, false, 20), Some(19));
+        // RTT が許容を超えても減少
+        assert_eq!(feed_window(&l, &mut now, 30, true, 20), Some(17));
+    }
+
+    #[test]
+    fn min_rtt_is_recalculated_after_interval() {
+        let l = limiter(AdaptiveConcurrencyAlgorithm::Aimd);
+        let mut now = Instant::now();
+        feed_window(&l, &mut now, 10, true, 1);
+        feed_window(&l, &mut now, 40, true, 1);
+        assert_eq!(l.min_rtt(), Some(Duration::from_millis(10)));
+        now += Duration::from_secs(30);
+        feed_window(&l, &mut now, 40, true, 1);
+        assert_eq!(l.min_rtt(), Some(Duration::from_millis(40)));
+    }
 }
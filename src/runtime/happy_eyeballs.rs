@@ -0,0 +1,314 @@
+//! 上流接続の Happy Eyeballs（RFC 8305、F-140）
+//!
+//! ホスト名が IPv6 と IPv4 の両方に解決される場合、アドレスを 1 つずつ順に試すと
+//! 到達できない IPv6 経路が新規接続のたびに connect タイムアウト丸ごとの遅延になる。
+//! そこで解決済みアドレスをファミリーが交互になるよう並べ（RFC 8305 §4）、前の試行が
+//! 応答しないまま `connection_attempt_delay`（既定 250ms）経てば次のアドレスへの connect を
+//! 並行して開始する（§5）。試行が失敗した場合は待たずに次を開始する。
+//!
+//! - 最初に確立した接続を採用し、残りの試行は drop する。drop はバックエンドの `Connect` が
+//!   後始末する（io_uring は `OpTable` から detach して ASYNC_CANCEL、reactor は FdTable から
+//!   unregister して close）。
+//! - 採用したアドレスファミリーをホストごとに覚え（ワーカースレッドごと、10 分間）、次回の
+//!   接続ではそのファミリーから試す。未学習のホストはリゾルバの順序（RFC 6724）に従う。
+//! - 単一アドレスに解決された場合（IP リテラル等）は従来どおり直接 connect する。
+//! - 送信元アドレスを指定した接続（F-141）は送信元と同じファミリーのアドレスだけを試す。
+//!
+//! ランタイムのバックエンド（`uring` / `reactor`）に依存しない。設定は起動時・リロード時に
+//! [`configure`] で反映する（`[performance.happy_eyeballs]`）。
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use crate::runtime::local_bind::LocalBind;
+use crate::runtime::sockopt::SocketOptions;
+use crate::runtime::tcp::{Connect, TcpStream};
+use crate::runtime::timer::{sleep, Sleep};
+
+/// 既定の接続試行間隔（RFC 8305 §8 の推奨値）
+pub const DEFAULT_CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// 試行間隔の下限（RFC 8305 §5: 10ms 未満にしない）
+const MIN_CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(10);
+
+/// ホストごとのファミリー優先の保持期間
+const PREFERENCE_TTL: Duration = Duration::from_secs(600);
+
+/// ファミリー優先を覚えるホスト数の上限（超えたら全消去して学習し直す）
+const MAX_REMEMBERED_HOSTS: usize = 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+static ATTEMPT_DELAY_MS: AtomicU64 =
+    AtomicU64::new(DEFAULT_CONNECTION_ATTEMPT_DELAY.as_millis() as u64);
+
+thread_local! {
+    /// ホスト → (IPv6 を優先するか, 記録時刻)
+    static PREFERRED_FAMILY: RefCell<HashMap<Box<str>, (bool, Instant)>> =
+        RefCell::new(HashMap::new());
+}
+
+/// 設定を反映する（起動時・設定リロード時）
+///
+/// `enabled = false` では従来どおり最初に解決されたアドレスのみへ接続する。
+pub fn configure(enabled: bool, attempt_delay: Duration) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    let delay = attempt_delay.max(MIN_CONNECTION_ATTEMPT_DELAY);
+    ATTEMPT_DELAY_MS.store(delay.as_millis() as u64, Ordering::Relaxed);
+}
+
+fn attempt_delay() -> Duration {
+    Duration::from_millis(ATTEMPT_DELAY_MS.load(Ordering::Relaxed))
+}
+
+/// "host:port" を解決して接続する（`TcpStream::connect_str` / `connect_str_from` /
+/// `connect_str_with` の実体）
+///
+/// DNS 解決はブロッキングで行う（コールドパスのみ）。ソケットオプション（F-142）は各試行の
+/// ソケットへ connect 前に適用する。
+pub async fn connect(
+    addr: &str,
+    source: Option<LocalBind>,
+    socket: Option<Arc<SocketOptions>>,
+) -> io::Result<TcpStream> {
+    let mut addrs: Vec<SocketAddr> = addr
+        .to_socket_addrs()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no address resolved",
+        ));
+    }
+    if let Some(source) = source {
+        addrs.retain(|a| source.matches_family(a));
+        if addrs.is_empty() {
+            return Err(source.family_mismatch());
+        }
+    }
+    if addrs.len() == 1 || !ENABLED.load(Ordering::Relaxed) {
+        return TcpStream::connect_with(addrs.swap_remove(0), source, socket).await;
+    }
+    let host = host_of(addr);
+    connect_addrs(host, addrs, source, socket, attempt_delay()).await
+}
+
+/// 解決済みアドレスへ段階的に並行 connect し、最初に確立した接続を返す
+async fn connect_addrs(
+    host: &str,
+    addrs: Vec<SocketAddr>,
+    source: Option<LocalBind>,
+    socket: Option<Arc<SocketOptions>>,
+    delay: Duration,
+) -> io::Result<TcpStream> {
+    let prefer_v6 = preferred_family(host).unwrap_or(addrs[0].is_ipv6());
+    let (winner, stream) = Race::new(interleave(addrs, prefer_v6), source, socket, delay).await?;
+    remember_family(host, winner.is_ipv6());
+    Ok(stream)
+}
+
+/// "host:port" / "[v6]:port" からホスト部を取り出す
+fn host_of(addr: &str) -> &str {
+    addr.rsplit_once(':').map_or(addr, |(host, _)| host)
+}
+
+/// 優先ファミリーから始めてファミリーが交互になるよう並べる（同じファミリー内の順序は維持）
+fn interleave(addrs: Vec<SocketAddr>, prefer_v6: bool) -> Vec<SocketAddr> {
+    let (preferred, other): (Vec<_>, Vec<_>) =
+        addrs.into_iter().partition(|a| a.is_ipv6() == prefer_v6);
+    let mut ordered = Vec::with_capacity(preferred.len() + other.len());
+    let mut preferred = preferred.into_iter();
+    let mut other = other.into_iter();
+    loop {
+        match (preferred.next(), other.next()) {
+            (None, None) => return ordered,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+}
+
+fn preferred_family(host: &str) -> Option<bool> {
+    PREFERRED_FAMILY.with(|m| {
+        let m = m.borrow();
+        let &(prefer_v6, at) = m.get(host)?;
+        (at.elapsed() < PREFERENCE_TTL).then_some(prefer_v6)
+    })
+}
+
+fn remember_family(host: &str, prefer_v6: bool) {
+    PREFERRED_FAMILY.with(|m| {
+        let mut m = m.borrow_mut();
+        if let Some(entry) = m.get_mut(host) {
+            *entry = (prefer_v6, Instant::now());
+            return;
+        }
+        if m.len() >= MAX_REMEMBERED_HOSTS {
+            m.clear();
+        }
+        m.insert(host.into(), (prefer_v6, Instant::now()));
+    });
+}
+
+/// 段階的な並行 connect（RFC 8305 §5）
+///
+/// `delay` ごと、または進行中の試行がすべて失敗した時点で次のアドレスへの connect を始める。
+/// 完了時に残りの試行は drop され、バックエンドの `Connect` の Drop がキャンセルする。
+struct Race {
+    addrs: Vec<SocketAddr>,
+    source: Option<LocalBind>,
+    socket: Option<Arc<SocketOptions>>,
+    next: usize,
+    attempts: Vec<(SocketAddr, Connect)>,
+    stagger: Option<Sleep>,
+    delay: Duration,
+    last_err: Option<io::Error>,
+}
+
+impl Race {
+    fn new(
+        addrs: Vec<SocketAddr>,
+        source: Option<LocalBind>,
+        socket: Option<Arc<SocketOptions>>,
+        delay: Duration,
+    ) -> Self {
+        Self {
+            attempts: Vec::with_capacity(addrs.len()),
+            addrs,
+            source,
+            socket,
+            next: 0,
+            stagger: None,
+            delay,
+            last_err: None,
+        }
+    }
+}
+
+impl Future for Race {
+    type Output = io::Result<(SocketAddr, TcpStream)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            let mut i = 0;
+            while i < this.attempts.len() {
+                match Pin::new(&mut this.attempts[i].1).poll(cx) {
+                    Poll::Ready(Ok(stream)) => {
+                        let addr = this.attempts[i].0;
+                        // 負けた試行をここでキャンセルする
+                        this.attempts.clear();
+                        this.stagger = None;
+                        return Poll::Ready(Ok((addr, stream)));
+                    }
+                    Poll::Ready(Err(e)) => {
+                        this.attempts.swap_remove(i);
+                        this.last_err = Some(e);
+                    }
+                    Poll::Pending => i += 1,
+                }
+            }
+            if this.next >= this.addrs.len() {
+                if this.attempts.is_empty() {
+                    let err = this.last_err.take().unwrap_or_else(|| {
+                        io::Error::new(io::ErrorKind::InvalidInput, "no address resolved")
+                    });
+                    return Poll::Ready(Err(err));
+                }
+                return Poll::Pending;
+            }
+            let start_next = this.attempts.is_empty()
+                || this
+                    .stagger
+                    .as_mut()
+                    .is_some_and(|s| Pin::new(s).poll(cx).is_ready());
+            if !start_next {
+                return Poll::Pending;
+            }
+            let addr = this.addrs[this.next];
+            this.next += 1;
+            this.attempts.push((
+                addr,
+                TcpStream::connect_with(addr, this.source, this.socket.clone()),
+            ));
+            this.stagger = Some(sleep(this.delay));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v4(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    fn v6(port: u16) -> SocketAddr {
+        SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn interleave_alternates_families_from_preferred() {
+        let addrs = vec![v6(1), v6(2), v6(3), v4(4), v4(5)];
+        assert_eq!(
+            interleave(addrs.clone(), true),
+            vec![v6(1), v4(4), v6(2), v4(5), v6(3)]
+        );
+        assert_eq!(
+            interleave(addrs, false),
+            vec![v4(4), v6(1), v4(5), v6(2), v6(3)]
+        );
+        assert_eq!(interleave(vec![v4(1), v4(2)], true), vec![v4(1), v4(2)]);
+    }
+
+    #[test]
+    fn family_preference_is_remembered_per_host() {
+        assert_eq!(host_of("api.example.com:443"), "api.example.com");
+        assert_eq!(host_of("[::1]:8080"), "[::1]");
+        assert_eq!(preferred_family("api.example.com"), None);
+        remember_family("api.example.com", false);
+        assert_eq!(preferred_family("api.example.com"), Some(false));
+        assert_eq!(preferred_family("other.example.com"), None);
+        remember_family("api.example.com", true);
+        assert_eq!(preferred_family("api.example.com"), Some(true));
+    }
+
+    /// 失敗した試行の後は遅延を待たずに次のアドレスへ進み、成功したファミリーを覚えること
+    #[cfg(veil_rt_uring)]
+    #[test]
+    fn failed_attempt_falls_back_without_waiting() {
+        if crate::runtime::ring::IoUring::new(8, 0).is_err() {
+            eprintln!("io_uring unavailable; skipping failed_attempt_falls_back_without_waiting");
+            return;
+        }
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let open = listener.local_addr().expect("local_addr");
+        // 閉じたポート（接続拒否で即座に失敗する）
+        let closed = {
+            let l = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+            l.local_addr().expect("local_addr")
+        };
+        let started = Instant::now();
+        let stream = crate::runtime::block_on(async move {
+            connect_addrs(
+                "fallback.test",
+                vec![closed, open],
+                None,
+                None,
+                Duration::from_secs(5),
+            )
+            .await
+        });
+        assert!(stream.is_ok(), "fallback connect should succeed");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(preferred_family("fallback.test"), Some(false));
+    }
+}
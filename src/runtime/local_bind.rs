@@ -0,0 +1,208 @@
+//! 上流接続の送信元アドレス指定（F-141）
+//!
+//! connect の前にソケットを送信元 IP へ bind する。ポートは常にカーネルに任せる（0）。
+//!
+//! - 透過モード（`transparent = true`）: `IP_TRANSPARENT` / `IPV6_TRANSPARENT` を立てて
+//!   クライアントの IP（プロキシにとって非ローカルなアドレス）へ bind する。上流からの応答を
+//!   プロキシへ戻すポリシールーティング（TPROXY の `ip rule` / `ip route`）と CAP_NET_ADMIN が
+//!   必要で、Linux のみ対応する。
+//! - 送信元アドレス固定（`source_address`）: ローカル IP へ bind する。Linux では
+//!   `IP_BIND_ADDRESS_NO_PORT` によりポート割り当てを connect 時まで遅らせ、送信元 IP を
+//!   固定してもエフェメラルポートを宛先ごとに再利用できるようにする。
+//!
+//! バックエンド（`uring` / `reactor`）の `Connect` がソケット作成直後に [`bind_socket`] を呼ぶ。
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+
+/// 上流接続の送信元（F-141）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalBind {
+    /// 送信元 IP（IPv4-mapped IPv6 は IPv4 に正規化済み）
+    pub ip: IpAddr,
+    /// 非ローカルアドレスへの bind を許可する（IP_TRANSPARENT）
+    pub transparent: bool,
+}
+
+impl LocalBind {
+    pub fn new(ip: IpAddr, transparent: bool) -> Self {
+        Self {
+            ip: ip.to_canonical(),
+            transparent,
+        }
+    }
+
+    /// 宛先と同じアドレスファミリーか（異なるファミリー間では bind できない）
+    #[inline]
+    pub fn matches_family(&self, addr: &SocketAddr) -> bool {
+        self.ip.is_ipv6() == addr.is_ipv6()
+    }
+
+    /// 宛先のファミリーと合わない場合のエラー
+    pub(crate) fn family_mismatch(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::AddrNotAvailable,
+            format!(
+                "upstream has no address in the source address family ({})",
+                self.ip
+            ),
+        )
+    }
+}
+
+/// ソケットを送信元アドレスへ bind する（connect の前に呼ぶ）
+#[cfg(unix)]
+pub(crate) fn bind_socket(fd: std::os::unix::io::RawFd, bind: &LocalBind) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        if bind.transparent {
+            let (level, opt) = match bind.ip {
+                IpAddr::V4(_) => (libc::SOL_IP, libc::IP_TRANSPARENT),
+                IpAddr::V6(_) => (libc::SOL_IPV6, libc::IPV6_TRANSPARENT),
+            };
+            set_int_option(fd, level, opt)?;
+        } else {
+            // 失敗（古いカーネル）してもポートの先行割り当てになるだけなので無視する
+            let _ = set_int_option(fd, libc::SOL_IP, libc::IP_BIND_ADDRESS_NO_PORT);
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    if bind.transparent {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "transparent upstream connections require Linux",
+        ));
+    }
+
+    let (storage, len) = sockaddr_to_storage(&SocketAddr::new(bind.ip, 0));
+    let ret = unsafe { libc::bind(fd, &storage as *const _ as *const libc::sockaddr, len) };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn set_int_option(
+    fd: std::os::unix::io::RawFd,
+    level: libc::c_int,
+    opt: libc::c_int,
+) -> io::Result<()> {
+    let one: libc::c_int = 1;
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            opt,
+            &one as *const _ as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn sockaddr_to_storage(addr: &SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let len = match addr {
+        SocketAddr::V4(v4) => {
+            let sin = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in) };
+            sin.sin_family = libc::AF_INET as _;
+            sin.sin_port = v4.port().to_be();
+            sin.sin_addr.s_addr = u32::from_ne_bytes(v4.ip().octets());
+            std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t
+        }
+        SocketAddr::V6(v6) => {
+            let sin6 = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6) };
+            sin6.sin6_family = libc::AF_INET6 as _;
+            sin6.sin6_port = v6.port().to_be();
+            sin6.sin6_addr.s6_addr = v6.ip().octets();
+            sin6.sin6_flowinfo = v6.flowinfo();
+            sin6.sin6_scope_id = v6.scope_id();
+            std::mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t
+        }
+    };
+    (storage, len)
+}
+
+/// 送信元の bind とソケットオプション（F-142）を設定してブロッキング connect する
+/// （専用スレッドで std の TLS を使う経路用）
+#[cfg(unix)]
+pub fn connect_blocking(
+    addr: &str,
+    bind: Option<&LocalBind>,
+    socket: Option<&crate::runtime::sockopt::SocketOptions>,
+) -> io::Result<std::net::TcpStream> {
+    use std::net::ToSocketAddrs;
+    use std::os::unix::io::FromRawFd;
+
+    let mut last_err = None;
+    let targets = addr
+        .to_socket_addrs()?
+        .filter(|a| bind.is_none_or(|b| b.matches_family(a)));
+    for target in targets {
+        let domain = if target.is_ipv6() {
+            libc::AF_INET6
+        } else {
+            libc::AF_INET
+        };
+        let fd = unsafe { libc::socket(domain, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // 以降のエラー経路では drop で close される
+        let stream = unsafe { std::net::TcpStream::from_raw_fd(fd) };
+        let prepared = socket
+            .map_or(Ok(()), |opts| {
+                crate::runtime::sockopt::apply_connect(fd, opts, target.is_ipv6())
+            })
+            .and_then(|()| bind.map_or(Ok(()), |b| bind_socket(fd, b)));
+        if let Err(e) = prepared {
+            last_err = Some(e);
+            continue;
+        }
+        let (storage, len) = sockaddr_to_storage(&target);
+        let ret = unsafe { libc::connect(fd, &storage as *const _ as *const libc::sockaddr, len) };
+        if ret == 0 {
+            return Ok(stream);
+        }
+        last_err = Some(io::Error::last_os_error());
+    }
+    Err(last_err.unwrap_or_else(|| match bind {
+        Some(bind) => bind.family_mismatch(),
+        None => io::Error::new(io::ErrorKind::InvalidInput, "no address resolved"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapped_addresses_are_canonicalized() {
+        let mapped: IpAddr = "::ffff:192.0.2.7".parse().unwrap();
+        let bind = LocalBind::new(mapped, true);
+        assert_eq!(bind.ip, "192.0.2.7".parse::<IpAddr>().unwrap());
+        assert!(bind.matches_family(&"198.51.100.1:80".parse().unwrap()));
+        assert!(!bind.matches_family(&"[2001:db8::1]:80".parse().unwrap()));
+    }
+
+    /// 127.0.0.0/8 は全体がローカルのため、特権なしで 127.0.0.1 以外へ bind して接続できる
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn blocking_connect_uses_source_address() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("local_addr").to_string();
+        let bind = LocalBind::new("127.0.0.2".parse().unwrap(), false);
+        let stream = connect_blocking(&addr, Some(&bind), None).expect("connect");
+        assert_eq!(
+            stream.local_addr().expect("local_addr").ip(),
+            "127.0.0.2".parse::<IpAddr>().unwrap()
+        );
+        let (_, peer) = listener.accept().expect("accept");
+        assert_eq!(peer.ip(), "127.0.0.2".parse::<IpAddr>().unwrap());
+    }
+}
@@ -16,6 +16,8 @@
 //! - `buf` - IoBuf/IoBufMut トレイト（バックエンド共有）
 //! - `io` - I/O トレイト・File（バックエンド共有）
 //! - `offload` - ブロッキング処理のワーカースレッド退避（バックエンド共有）
+//! - `happy_eyeballs` - 上流接続の IPv6/IPv4 段階的並行 connect（バックエンド共有、F-140）
+//! - `sockopt` - リスナー / 上流接続のソケットオプションのプロファイル（バックエンド共有、F-142）
 //! - `uring` - io_uring バックエンド（`veil_rt_uring`）:
 //!   - `ring` - io_uring リング管理（setup/enter/register、SQE/CQE raw 操作）
 //!   - `executor` - シングルスレッド非同期エグゼキュータ
@@ -35,8 +37,11 @@ pub mod buf;
 /// `pub`（`pub(crate)` ではない）にする理由は `handle.rs` のモジュール doc 参照
 /// （`private_bounds`/`private_interfaces` lint 回避）。
 pub mod handle;
+pub mod happy_eyeballs;
 pub mod io;
+pub mod local_bind;
 pub mod offload;
+pub mod sockopt;
 // L4 UDP プロキシ専用の汎用 UDP ソケット（F-124）。`l4-proxy` feature でのみ使用するため
 // dead_code 警告を避けるべくゲートする。
 #[cfg(feature = "l4-proxy")]
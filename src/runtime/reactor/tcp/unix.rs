@@ -16,10 +16,13 @@ use std::io;
 use std::net::SocketAddr;
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use crate::runtime::buf::{IoBuf, IoBufMut};
 use crate::runtime::executor::{register_read, register_write, unregister};
+use crate::runtime::local_bind::{bind_socket, LocalBind};
+use crate::runtime::sockopt::{self, SocketOptions};
 
 // SO_* ソケットオプション
 const TCP_NODELAY: libc::c_int = 1;
@@ -152,15 +155,35 @@ pub struct TcpListener {
 impl TcpListener {
     /// アドレスにバインドしてリッスンを開始する。
     pub fn bind(addr: impl std::net::ToSocketAddrs) -> io::Result<Self> {
-        Self::bind_impl(addr, false)
+        Self::bind_impl(addr, false, None)
+    }
+
+    /// ソケットオプションのプロファイルを適用してバインドする（F-142、`None` は [`TcpListener::bind`] と同じ）。
+    pub fn bind_with(
+        addr: impl std::net::ToSocketAddrs,
+        opts: Option<&SocketOptions>,
+    ) -> io::Result<Self> {
+        Self::bind_impl(addr, false, opts)
     }
 
     /// SO_REUSEPORT を設定してバインドする。
     pub fn bind_reuse_port(addr: impl std::net::ToSocketAddrs) -> io::Result<Self> {
-        Self::bind_impl(addr, true)
+        Self::bind_impl(addr, true, None)
+    }
+
+    /// SO_REUSEPORT とソケットオプションのプロファイルを設定してバインドする（F-142）。
+    pub fn bind_reuse_port_with(
+        addr: impl std::net::ToSocketAddrs,
+        opts: Option<&SocketOptions>,
+    ) -> io::Result<Self> {
+        Self::bind_impl(addr, true, opts)
     }
 
-    fn bind_impl(addr: impl std::net::ToSocketAddrs, reuse_port: bool) -> io::Result<Self> {
+    fn bind_impl(
+        addr: impl std::net::ToSocketAddrs,
+        reuse_port: bool,
+        opts: Option<&SocketOptions>,
+    ) -> io::Result<Self> {
         let addr = addr
             .to_socket_addrs()?
             .next()
@@ -201,6 +224,13 @@ impl TcpListener {
             }
         }
 
+        if let Some(opts) = opts {
+            if let Err(e) = sockopt::apply_listener(fd, opts, addr.is_ipv6()) {
+                unsafe { libc::close(fd) };
+                return Err(e);
+            }
+        }
+
         let (storage, len) = sockaddr_to_storage(&addr);
         let ret = unsafe { libc::bind(fd, &storage as *const _ as *const libc::sockaddr, len) };
         if ret < 0 {
@@ -209,7 +239,7 @@ impl TcpListener {
             return Err(e);
         }
 
-        let ret = unsafe { libc::listen(fd, 1024) };
+        let ret = unsafe { libc::listen(fd, SocketOptions::backlog(opts)) };
         if ret < 0 {
             let e = io::Error::last_os_error();
             unsafe { libc::close(fd) };
@@ -344,8 +374,24 @@ impl TcpStream {
 
     /// アドレスに非同期で接続する。
     pub fn connect(addr: SocketAddr) -> Connect {
+        Self::connect_with(addr, None, None)
+    }
+
+    /// 送信元アドレスを bind してから接続する（F-141、`None` は [`TcpStream::connect`] と同じ）。
+    pub fn connect_from(addr: SocketAddr, source: Option<LocalBind>) -> Connect {
+        Self::connect_with(addr, source, None)
+    }
+
+    /// ソケットオプションのプロファイル（F-142）と送信元（F-141）を設定してから接続する。
+    pub fn connect_with(
+        addr: SocketAddr,
+        source: Option<LocalBind>,
+        socket: Option<Arc<SocketOptions>>,
+    ) -> Connect {
         Connect {
             addr,
+            source,
+            socket,
             fd: -1,
             registered: false,
         }
@@ -353,15 +399,26 @@ impl TcpStream {
 
     /// 文字列アドレス（"host:port"）から接続する。
     ///
-    /// DNS 解決はブロッキングで行う（コールドパスのみ）。
+    /// DNS 解決はブロッキングで行う（コールドパスのみ）。複数アドレスに解決された場合は
+    /// Happy Eyeballs（F-140、`runtime::happy_eyeballs`）で IPv6/IPv4 を段階的に並行して試す。
     pub async fn connect_str(addr: &str) -> io::Result<TcpStream> {
-        use std::net::ToSocketAddrs;
-        let socket_addr = addr
-            .to_socket_addrs()
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
-            .next()
-            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address resolved"))?;
-        TcpStream::connect(socket_addr).await
+        crate::runtime::happy_eyeballs::connect(addr, None, None).await
+    }
+
+    /// 送信元アドレスを bind して文字列アドレスへ接続する（F-141）。
+    ///
+    /// 送信元と同じファミリーのアドレスだけを試す。
+    pub async fn connect_str_from(addr: &str, source: Option<LocalBind>) -> io::Result<TcpStream> {
+        crate::runtime::happy_eyeballs::connect(addr, source, None).await
+    }
+
+    /// ソケットオプションのプロファイル（F-142）と送信元（F-141）を設定して文字列アドレスへ接続する。
+    pub async fn connect_str_with(
+        addr: &str,
+        source: Option<LocalBind>,
+        socket: Option<Arc<SocketOptions>>,
+    ) -> io::Result<TcpStream> {
+        crate::runtime::happy_eyeballs::connect(addr, source, socket).await
     }
 
     /// バッファに非同期で読み込む。バッファの所有権を取り、完了時に `(Result<usize>, T)` を返す。
@@ -456,6 +513,19 @@ impl TcpStream {
         }
     }
 
+    /// プール中のアイドル接続が閉じられているか（F-145、ゼロタイムアウトの poll）。
+    ///
+    /// 応答待ちでない接続が読み取り可能なのは相手の FIN / RST か想定外のデータのためで、
+    /// いずれも再利用できない。ブロックしない。
+    pub fn is_idle_closed(&self) -> bool {
+        let mut pfd = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut pfd, 1, 0) != 0 }
+    }
+
     /// TCP_NODELAY を設定する。
     pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
         let optval: libc::c_int = if nodelay { 1 } else { 0 };
@@ -592,6 +662,8 @@ impl AsRawFd for TcpStream {
 /// connect Future（非ブロッキング `connect(2)` → writable 待ち → `SO_ERROR` 確認）。
 pub struct Connect {
     addr: SocketAddr,
+    source: Option<LocalBind>,
+    socket: Option<Arc<SocketOptions>>,
     fd: RawFd,
     registered: bool,
 }
@@ -627,6 +699,18 @@ impl Future for Connect {
                 Ok(fd) => fd,
                 Err(e) => return Poll::Ready(Err(e)),
             };
+            if let Some(socket) = &self.socket {
+                if let Err(e) = sockopt::apply_connect(fd, socket, self.addr.is_ipv6()) {
+                    unsafe { libc::close(fd) };
+                    return Poll::Ready(Err(e));
+                }
+            }
+            if let Some(source) = self.source {
+                if let Err(e) = bind_socket(fd, &source) {
+                    unsafe { libc::close(fd) };
+                    return Poll::Ready(Err(e));
+                }
+            }
             self.fd = fd;
 
             let (storage, len) = sockaddr_to_storage(&self.addr);
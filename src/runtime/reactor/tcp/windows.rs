@@ -28,6 +28,8 @@ use windows_sys::Win32::Networking::WinSock::{
 use crate::runtime::buf::{IoBuf, IoBufMut};
 use crate::runtime::executor::{register_read, register_write, unregister};
 use crate::runtime::handle::{win, AsRawFd, RawFd};
+use crate::runtime::local_bind::LocalBind;
+use crate::runtime::sockopt::SocketOptions;
 
 /// `WSAStartup` を一度だけ実行する。プロセス内のどの Winsock API 呼び出しよりも
 /// 先に済ませる必要があるため、ソケット生成の入口（`create_nonblocking_socket`）で
@@ -163,17 +165,37 @@ pub struct TcpListener {
 
 impl TcpListener {
     pub fn bind(addr: impl std::net::ToSocketAddrs) -> io::Result<Self> {
-        Self::bind_impl(addr, false)
+        Self::bind_impl(addr, false, None)
+    }
+
+    /// ソケットオプションのプロファイル（F-142）は Windows では backlog のみ反映する
+    /// （それ以外の項目は設定検証でエラーにする）。
+    pub fn bind_with(
+        addr: impl std::net::ToSocketAddrs,
+        opts: Option<&SocketOptions>,
+    ) -> io::Result<Self> {
+        Self::bind_impl(addr, false, opts)
     }
 
     /// Windows には Linux 的な `SO_REUSEPORT`（カーネル分散）が無いため、
     /// `SO_REUSEADDR` のみを設定する（thread-per-core の accept 分散は保証されない。
     /// F-125 設計ドキュメント参照。ビルド通過のみが目的で実機性能検証は対象外）。
     pub fn bind_reuse_port(addr: impl std::net::ToSocketAddrs) -> io::Result<Self> {
-        Self::bind_impl(addr, true)
+        Self::bind_impl(addr, true, None)
+    }
+
+    pub fn bind_reuse_port_with(
+        addr: impl std::net::ToSocketAddrs,
+        opts: Option<&SocketOptions>,
+    ) -> io::Result<Self> {
+        Self::bind_impl(addr, true, opts)
     }
 
-    fn bind_impl(addr: impl std::net::ToSocketAddrs, _reuse_port: bool) -> io::Result<Self> {
+    fn bind_impl(
+        addr: impl std::net::ToSocketAddrs,
+        _reuse_port: bool,
+        opts: Option<&SocketOptions>,
+    ) -> io::Result<Self> {
         let addr = addr
             .to_socket_addrs()?
             .next()
@@ -190,7 +212,7 @@ impl TcpListener {
             unsafe { closesocket(sock) };
             return Err(e);
         }
-        let ret = unsafe { WinSock::listen(sock, 1024) };
+        let ret = unsafe { WinSock::listen(sock, SocketOptions::backlog(opts)) };
         if ret == SOCKET_ERROR {
             let e = last_wsa_error();
             unsafe { closesocket(sock) };
@@ -282,21 +304,43 @@ impl TcpStream {
     }
 
     pub fn connect(addr: SocketAddr) -> Connect {
+        Self::connect_from(addr, None)
+    }
+
+    /// 送信元アドレスの指定（F-141）は Windows では未対応（`Some` は Unsupported で失敗する）。
+    pub fn connect_from(addr: SocketAddr, source: Option<LocalBind>) -> Connect {
+        Self::connect_with(addr, source, None)
+    }
+
+    /// ソケットオプションのプロファイル（F-142）は上流接続では無視する（Windows で
+    /// 指定できるのはリスナー用の backlog のみ）。
+    pub fn connect_with(
+        addr: SocketAddr,
+        source: Option<LocalBind>,
+        _socket: Option<std::sync::Arc<SocketOptions>>,
+    ) -> Connect {
         Connect {
             addr,
+            source,
             fd: crate::runtime::handle::INVALID_FD,
             registered: false,
         }
     }
 
     pub async fn connect_str(addr: &str) -> io::Result<TcpStream> {
-        use std::net::ToSocketAddrs;
-        let socket_addr = addr
-            .to_socket_addrs()
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
-            .next()
-            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address resolved"))?;
-        TcpStream::connect(socket_addr).await
+        crate::runtime::happy_eyeballs::connect(addr, None, None).await
+    }
+
+    pub async fn connect_str_from(addr: &str, source: Option<LocalBind>) -> io::Result<TcpStream> {
+        crate::runtime::happy_eyeballs::connect(addr, source, None).await
+    }
+
+    pub async fn connect_str_with(
+        addr: &str,
+        source: Option<LocalBind>,
+        socket: Option<std::sync::Arc<SocketOptions>>,
+    ) -> io::Result<TcpStream> {
+        crate::runtime::happy_eyeballs::connect(addr, source, socket).await
     }
 
     pub fn read<T: IoBufMut>(&self, buf: T) -> ReadFuture<T> {
@@ -367,6 +411,11 @@ impl TcpStream {
         }
     }
 
+    /// プール中のアイドル接続が閉じられているか（F-145、ゼロタイムアウトの `WSAPoll`）。
+    pub fn is_idle_closed(&self) -> bool {
+        poll_ready_now(self.fd, false)
+    }
+
     pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
         let optval: i32 = if nodelay { 1 } else { 0 };
         let ret = unsafe {
@@ -480,6 +529,7 @@ impl AsRawFd for TcpStream {
 
 pub struct Connect {
     addr: SocketAddr,
+    source: Option<LocalBind>,
     fd: RawFd,
     registered: bool,
 }
@@ -502,6 +552,12 @@ impl Future for Connect {
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         if self.fd < 0 {
+            if self.source.is_some() {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "upstream source address binding is not supported on Windows",
+                )));
+            }
             let domain = if self.addr.is_ipv6() {
                 AF_INET6
             } else {
@@ -0,0 +1,381 @@
+//! ソケットオプションのプロファイル（F-142）
+//!
+//! `[socket_profiles.<name>]` で定義したカーネル向けのチューニングを、HTTP / L4 リスナーと
+//! 上流接続のソケットへ適用する。設定の解釈と検証は `config` が行い、ここは解決済みの値を
+//! setsockopt するだけにする。
+//!
+//! - リスナー: ソケット作成直後（bind の前）に [`apply_listener`] を呼び、`listen(2)` には
+//!   [`SocketOptions::backlog`] を渡す。Linux では accept したソケットがリスナーの値
+//!   （keepalive・バッファ・TOS・mark・輻輳制御・TCP_USER_TIMEOUT・TCP_NOTSENT_LOWAT）を
+//!   引き継ぐため、accept ごとの setsockopt は行わない。
+//! - 上流接続: ソケット作成直後（送信元の bind・connect の前）に [`apply_connect`] を呼ぶ。
+//!   SYN から DSCP / mark / バッファ（ウィンドウスケール）が効くように connect 前に設定する。
+//!   `tcp_fastopen` は上流接続では `TCP_FASTOPEN_CONNECT` を立てる。
+//!
+//! Linux 以外の Unix では SO_KEEPALIVE・バッファサイズ・IP_TOS / IPV6_TCLASS・backlog のみ
+//! 対応する（それ以外の項目は設定検証でエラーにする）。
+
+use std::fmt;
+#[cfg(unix)]
+use std::io;
+
+/// `listen(2)` の既定 backlog（プロファイル未指定時）
+pub const DEFAULT_BACKLOG: i32 = 1024;
+
+/// TCP_CONGESTION のアルゴリズム名の最大長（Linux の TCP_CA_NAME_MAX - 1）
+pub const CONGESTION_NAME_MAX: usize = 15;
+
+/// 解決済みのソケットオプション（F-142、未指定の項目はカーネルの既定値のまま）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SocketOptions {
+    /// `listen(2)` の backlog（リスナーのみ）
+    pub backlog: Option<u32>,
+    /// リスナー: TCP_FASTOPEN のキュー長。上流接続: 1 以上で TCP_FASTOPEN_CONNECT
+    pub tcp_fastopen: Option<u32>,
+    /// SO_KEEPALIVE（keepalive の間隔等を指定した場合は有効として扱う）
+    pub keepalive: Option<bool>,
+    /// TCP_KEEPIDLE（秒）
+    pub keepalive_idle_secs: Option<u32>,
+    /// TCP_KEEPINTVL（秒）
+    pub keepalive_interval_secs: Option<u32>,
+    /// TCP_KEEPCNT
+    pub keepalive_count: Option<u32>,
+    /// TCP_USER_TIMEOUT（ミリ秒）
+    pub user_timeout_ms: Option<u32>,
+    /// TCP_NOTSENT_LOWAT（バイト）
+    pub notsent_lowat: Option<u32>,
+    /// IP_TOS / IPV6_TCLASS（DSCP は上位 6 ビット）
+    pub tos: Option<u8>,
+    /// SO_MARK
+    pub mark: Option<u32>,
+    /// TCP_CONGESTION のアルゴリズム名
+    pub congestion: Option<String>,
+    /// SO_RCVBUF（バイト）
+    pub recv_buffer: Option<u32>,
+    /// SO_SNDBUF（バイト）
+    pub send_buffer: Option<u32>,
+}
+
+impl SocketOptions {
+    /// `listen(2)` に渡す backlog
+    #[inline]
+    pub fn backlog(opts: Option<&Self>) -> i32 {
+        opts.and_then(|o| o.backlog)
+            .map_or(DEFAULT_BACKLOG, |b| b.min(i32::MAX as u32) as i32)
+    }
+
+    /// SO_KEEPALIVE を立てるか（`keepalive = false` の明示は無効化として扱う）
+    fn keepalive_enabled(&self) -> Option<bool> {
+        self.keepalive.or_else(|| {
+            (self.keepalive_idle_secs.is_some()
+                || self.keepalive_interval_secs.is_some()
+                || self.keepalive_count.is_some())
+            .then_some(true)
+        })
+    }
+}
+
+/// `veil -t` の表示用（設定した項目のみ、`key=value` を空白区切りで並べる）
+impl fmt::Display for SocketOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut items: Vec<String> = Vec::new();
+        if let Some(v) = self.backlog {
+            items.push(format!("backlog={}", v));
+        }
+        if let Some(v) = self.tcp_fastopen {
+            items.push(format!("tcp_fastopen={}", v));
+        }
+        if let Some(v) = self.keepalive_enabled() {
+            items.push(format!("keepalive={}", if v { "on" } else { "off" }));
+        }
+        if let Some(v) = self.keepalive_idle_secs {
+            items.push(format!("keepalive_idle={}s", v));
+        }
+        if let Some(v) = self.keepalive_interval_secs {
+            items.push(format!("keepalive_interval={}s", v));
+        }
+        if let Some(v) = self.keepalive_count {
+            items.push(format!("keepalive_count={}", v));
+        }
+        if let Some(v) = self.user_timeout_ms {
+            items.push(format!("user_timeout={}ms", v));
+        }
+        if let Some(v) = self.notsent_lowat {
+            items.push(format!("notsent_lowat={}", v));
+        }
+        if let Some(v) = self.tos {
+            items.push(format!("tos=0x{:02x}(dscp={})", v, v >> 2));
+        }
+        if let Some(v) = self.mark {
+            items.push(format!("mark={}", v));
+        }
+        if let Some(v) = &self.congestion {
+            items.push(format!("congestion={}", v));
+        }
+        if let Some(v) = self.recv_buffer {
+            items.push(format!("recv_buffer={}", v));
+        }
+        if let Some(v) = self.send_buffer {
+            items.push(format!("send_buffer={}", v));
+        }
+        if items.is_empty() {
+            f.write_str("(kernel defaults)")
+        } else {
+            f.write_str(&items.join(" "))
+        }
+    }
+}
+
+/// リスナーソケットへ適用する（bind の前に呼ぶ。backlog は `listen(2)` 側で使う）
+#[cfg(unix)]
+pub(crate) fn apply_listener(
+    fd: std::os::unix::io::RawFd,
+    opts: &SocketOptions,
+    ipv6: bool,
+) -> io::Result<()> {
+    apply_common(fd, opts, ipv6)?;
+    #[cfg(target_os = "linux")]
+    if let Some(qlen) = opts.tcp_fastopen {
+        set_int(
+            fd,
+            libc::IPPROTO_TCP,
+            libc::TCP_FASTOPEN,
+            qlen,
+            "TCP_FASTOPEN",
+        )?;
+    }
+    Ok(())
+}
+
+/// 上流接続のソケットへ適用する（送信元の bind・connect の前に呼ぶ）
+#[cfg(unix)]
+pub(crate) fn apply_connect(
+    fd: std::os::unix::io::RawFd,
+    opts: &SocketOptions,
+    ipv6: bool,
+) -> io::Result<()> {
+    apply_common(fd, opts, ipv6)?;
+    #[cfg(target_os = "linux")]
+    if opts.tcp_fastopen.is_some_and(|v| v > 0) {
+        set_int(
+            fd,
+            libc::IPPROTO_TCP,
+            libc::TCP_FASTOPEN_CONNECT,
+            1,
+            "TCP_FASTOPEN_CONNECT",
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn apply_common(fd: std::os::unix::io::RawFd, opts: &SocketOptions, ipv6: bool) -> io::Result<()> {
+    if let Some(size) = opts.recv_buffer {
+        set_int(fd, libc::SOL_SOCKET, libc::SO_RCVBUF, size, "SO_RCVBUF")?;
+    }
+    if let Some(size) = opts.send_buffer {
+        set_int(fd, libc::SOL_SOCKET, libc::SO_SNDBUF, size, "SO_SNDBUF")?;
+    }
+    if let Some(on) = opts.keepalive_enabled() {
+        set_int(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_KEEPALIVE,
+            on as u32,
+            "SO_KEEPALIVE",
+        )?;
+    }
+    if let Some(tos) = opts.tos {
+        if ipv6 {
+            set_int(
+                fd,
+                libc::IPPROTO_IPV6,
+                libc::IPV6_TCLASS,
+                tos as u32,
+                "IPV6_TCLASS",
+            )?;
+        } else {
+            set_int(fd, libc::IPPROTO_IP, libc::IP_TOS, tos as u32, "IP_TOS")?;
+        }
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let tcp = libc::IPPROTO_TCP;
+        if let Some(v) = opts.keepalive_idle_secs {
+            set_int(fd, tcp, libc::TCP_KEEPIDLE, v, "TCP_KEEPIDLE")?;
+        }
+        if let Some(v) = opts.keepalive_interval_secs {
+            set_int(fd, tcp, libc::TCP_KEEPINTVL, v, "TCP_KEEPINTVL")?;
+        }
+        if let Some(v) = opts.keepalive_count {
+            set_int(fd, tcp, libc::TCP_KEEPCNT, v, "TCP_KEEPCNT")?;
+        }
+        if let Some(v) = opts.user_timeout_ms {
+            set_int(fd, tcp, libc::TCP_USER_TIMEOUT, v, "TCP_USER_TIMEOUT")?;
+        }
+        if let Some(v) = opts.notsent_lowat {
+            set_int(fd, tcp, libc::TCP_NOTSENT_LOWAT, v, "TCP_NOTSENT_LOWAT")?;
+        }
+        if let Some(mark) = opts.mark {
+            set_int(fd, libc::SOL_SOCKET, libc::SO_MARK, mark, "SO_MARK")?;
+        }
+        if let Some(name) = &opts.congestion {
+            set_raw(
+                fd,
+                tcp,
+                libc::TCP_CONGESTION,
+                name.as_bytes(),
+                "TCP_CONGESTION",
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_int(
+    fd: std::os::unix::io::RawFd,
+    level: libc::c_int,
+    opt: libc::c_int,
+    value: u32,
+    name: &str,
+) -> io::Result<()> {
+    let value = value.min(libc::c_int::MAX as u32) as libc::c_int;
+    set_raw(fd, level, opt, &value.to_ne_bytes(), name)
+}
+
+#[cfg(unix)]
+fn set_raw(
+    fd: std::os::unix::io::RawFd,
+    level: libc::c_int,
+    opt: libc::c_int,
+    value: &[u8],
+    name: &str,
+) -> io::Result<()> {
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            opt,
+            value.as_ptr() as *const libc::c_void,
+            value.len() as libc::socklen_t,
+        )
+    };
+    if ret < 0 {
+        let e = io::Error::last_os_error();
+        return Err(io::Error::new(
+            e.kind(),
+            format!("setsockopt {}: {}", name, e),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backlog_defaults_and_clamps() {
+        assert_eq!(SocketOptions::backlog(None), DEFAULT_BACKLOG);
+        let opts = SocketOptions {
+            backlog: Some(u32::MAX),
+            ..Default::default()
+        };
+        assert_eq!(SocketOptions::backlog(Some(&opts)), i32::MAX);
+    }
+
+    #[test]
+    fn keepalive_tuning_implies_keepalive() {
+        let opts = SocketOptions {
+            keepalive_idle_secs: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(opts.keepalive_enabled(), Some(true));
+        let off = SocketOptions {
+            keepalive: Some(false),
+            keepalive_idle_secs: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(off.keepalive_enabled(), Some(false));
+        assert_eq!(SocketOptions::default().keepalive_enabled(), None);
+    }
+
+    #[test]
+    fn display_lists_configured_options() {
+        assert_eq!(SocketOptions::default().to_string(), "(kernel defaults)");
+        let opts = SocketOptions {
+            backlog: Some(4096),
+            tos: Some(0x28),
+            congestion: Some("bbr".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            opts.to_string(),
+            "backlog=4096 tos=0x28(dscp=10) congestion=bbr"
+        );
+    }
+
+    /// 特権不要の項目を実ソケットへ適用し、getsockopt で読み戻す
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn connect_options_are_applied() {
+        use std::os::unix::io::AsRawFd;
+
+        fn get_int(fd: i32, level: libc::c_int, opt: libc::c_int) -> libc::c_int {
+            let mut value: libc::c_int = 0;
+            let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+            let ret = unsafe {
+                libc::getsockopt(
+                    fd,
+                    level,
+                    opt,
+                    &mut value as *mut _ as *mut libc::c_void,
+                    &mut len,
+                )
+            };
+            assert_eq!(ret, 0);
+            value
+        }
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let fd = listener.as_raw_fd();
+        let opts = SocketOptions {
+            keepalive_idle_secs: Some(45),
+            keepalive_interval_secs: Some(7),
+            keepalive_count: Some(4),
+            user_timeout_ms: Some(12_000),
+            notsent_lowat: Some(16_384),
+            tos: Some(0x28),
+            ..Default::default()
+        };
+        apply_connect(fd, &opts, false).expect("apply");
+        assert_eq!(get_int(fd, libc::SOL_SOCKET, libc::SO_KEEPALIVE), 1);
+        assert_eq!(get_int(fd, libc::IPPROTO_TCP, libc::TCP_KEEPIDLE), 45);
+        assert_eq!(get_int(fd, libc::IPPROTO_TCP, libc::TCP_KEEPINTVL), 7);
+        assert_eq!(get_int(fd, libc::IPPROTO_TCP, libc::TCP_KEEPCNT), 4);
+        assert_eq!(
+            get_int(fd, libc::IPPROTO_TCP, libc::TCP_USER_TIMEOUT),
+            12_000
+        );
+        assert_eq!(
+            get_int(fd, libc::IPPROTO_TCP, libc::TCP_NOTSENT_LOWAT),
+            16_384
+        );
+        assert_eq!(get_int(fd, libc::IPPROTO_IP, libc::IP_TOS), 0x28);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn unknown_congestion_control_names_the_option() {
+        use std::os::unix::io::AsRawFd;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let opts = SocketOptions {
+            congestion: Some("no-such-cc".into()),
+            ..Default::default()
+        };
+        let err = apply_listener(listener.as_raw_fd(), &opts, false).unwrap_err();
+        assert!(err.to_string().contains("TCP_CONGESTION"), "{}", err);
+    }
+}
@@ -15,6 +15,7 @@ use std::io;
 use std::net::SocketAddr;
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use crate::runtime::buf::{IoBuf, IoBufMut};
@@ -22,10 +23,12 @@ use crate::runtime::executor::{
     alloc_op, detach_op, peek_op_result, remove_op, set_op_waker, submit_sqes, take_op_result,
     with_ring, OpGuard,
 };
+use crate::runtime::local_bind::{bind_socket, LocalBind};
 use crate::runtime::ring::{
     IORING_OP_ACCEPT, IORING_OP_CONNECT, IORING_OP_POLL_ADD, IORING_OP_RECV, IORING_OP_SEND,
     IORING_OP_SENDMSG,
 };
+use crate::runtime::sockopt::{self, SocketOptions};
 
 // POLL イベントフラグ
 const POLLIN: i16 = 0x0001;
@@ -121,48 +124,35 @@ pub struct TcpListener {
 impl TcpListener {
     /// アドレスにバインドしてリッスンを開始する
     pub fn bind(addr: impl std::net::ToSocketAddrs) -> io::Result<Self> {
-        let addr = addr
-            .to_socket_addrs()?
-            .next()
-            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address"))?;
-
-        let domain = if addr.is_ipv6() {
-            libc::AF_INET6
-        } else {
-            libc::AF_INET
-        };
-        let fd = create_nonblocking_socket(domain)?;
-
-        // SO_REUSEADDR
-        let optval: libc::c_int = 1;
-        unsafe {
-            libc::setsockopt(
-                fd,
-                libc::SOL_SOCKET,
-                libc::SO_REUSEADDR,
-                &optval as *const _ as *const libc::c_void,
-                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
-            );
-        }
-
-        let (storage, len) = sockaddr_to_storage(&addr);
-        let ret = unsafe { libc::bind(fd, &storage as *const _ as *const libc::sockaddr, len) };
-        if ret < 0 {
-            unsafe { libc::close(fd) };
-            return Err(io::Error::last_os_error());
-        }
-
-        let ret = unsafe { libc::listen(fd, 1024) };
-        if ret < 0 {
-            unsafe { libc::close(fd) };
-            return Err(io::Error::last_os_error());
-        }
+        Self::bind_with(addr, None)
+    }
 
-        Ok(Self { fd })
+    /// ソケットオプションのプロファイルを適用してバインドする（F-142、`None` は [`TcpListener::bind`] と同じ）
+    pub fn bind_with(
+        addr: impl std::net::ToSocketAddrs,
+        opts: Option<&SocketOptions>,
+    ) -> io::Result<Self> {
+        Self::bind_inner(addr, false, opts)
     }
 
     /// SO_REUSEPORT を設定してバインドする
     pub fn bind_reuse_port(addr: impl std::net::ToSocketAddrs) -> io::Result<Self> {
+        Self::bind_reuse_port_with(addr, None)
+    }
+
+    /// SO_REUSEPORT とソケットオプションのプロファイルを設定してバインドする（F-142）
+    pub fn bind_reuse_port_with(
+        addr: impl std::net::ToSocketAddrs,
+        opts: Option<&SocketOptions>,
+    ) -> io::Result<Self> {
+        Self::bind_inner(addr, true, opts)
+    }
+
+    fn bind_inner(
+        addr: impl std::net::ToSocketAddrs,
+        reuse_port: bool,
+        opts: Option<&SocketOptions>,
+    ) -> io::Result<Self> {
         let addr = addr
             .to_socket_addrs()?
             .next()
@@ -184,13 +174,22 @@ impl TcpListener {
                 &optval as *const _ as *const libc::c_void,
                 std::mem::size_of::<libc::c_int>() as libc::socklen_t,
             );
-            libc::setsockopt(
-                fd,
-                libc::SOL_SOCKET,
-                libc::SO_REUSEPORT,
-                &optval as *const _ as *const libc::c_void,
-                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
-            );
+            if reuse_port {
+                libc::setsockopt(
+                    fd,
+                    libc::SOL_SOCKET,
+                    libc::SO_REUSEPORT,
+                    &optval as *const _ as *const libc::c_void,
+                    std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+                );
+            }
+        }
+
+        if let Some(opts) = opts {
+            if let Err(e) = sockopt::apply_listener(fd, opts, addr.is_ipv6()) {
+                unsafe { libc::close(fd) };
+                return Err(e);
+            }
         }
 
         let (storage, len) = sockaddr_to_storage(&addr);
@@ -200,7 +199,7 @@ impl TcpListener {
             return Err(io::Error::last_os_error());
         }
 
-        let ret = unsafe { libc::listen(fd, 1024) };
+        let ret = unsafe { libc::listen(fd, SocketOptions::backlog(opts)) };
         if ret < 0 {
             unsafe { libc::close(fd) };
             return Err(io::Error::last_os_error());
@@ -373,8 +372,24 @@ impl TcpStream {
 
     /// アドレスに非同期で接続する（io_uring CONNECT）
     pub fn connect(addr: SocketAddr) -> Connect {
+        Self::connect_with(addr, None, None)
+    }
+
+    /// 送信元アドレスを bind してから接続する（F-141、`None` は [`TcpStream::connect`] と同じ）
+    pub fn connect_from(addr: SocketAddr, source: Option<LocalBind>) -> Connect {
+        Self::connect_with(addr, source, None)
+    }
+
+    /// ソケットオプションのプロファイル（F-142）と送信元（F-141）を設定してから接続する
+    pub fn connect_with(
+        addr: SocketAddr,
+        source: Option<LocalBind>,
+        socket: Option<Arc<SocketOptions>>,
+    ) -> Connect {
         Connect {
             addr,
+            source,
+            socket,
             fd: -1,
             user_data: 0,
             addr_storage: Box::new(unsafe { std::mem::zeroed() }),
@@ -385,15 +400,26 @@ impl TcpStream {
 
     /// 文字列アドレス（"host:port"）から接続する
     ///
-    /// DNS 解決はブロッキングで行う（コールドパスのみ）。
+    /// DNS 解決はブロッキングで行う（コールドパスのみ）。複数アドレスに解決された場合は
+    /// Happy Eyeballs（F-140、`runtime::happy_eyeballs`）で IPv6/IPv4 を段階的に並行して試す。
     pub async fn connect_str(addr: &str) -> io::Result<TcpStream> {
-        use std::net::ToSocketAddrs;
-        let socket_addr = addr
-            .to_socket_addrs()
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
-            .next()
-            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address resolved"))?;
-        TcpStream::connect(socket_addr).await
+        crate::runtime::happy_eyeballs::connect(addr, None, None).await
+    }
+
+    /// 送信元アドレスを bind して文字列アドレスへ接続する（F-141）
+    ///
+    /// 送信元と同じファミリーのアドレスだけを試す。
+    pub async fn connect_str_from(addr: &str, source: Option<LocalBind>) -> io::Result<TcpStream> {
+        crate::runtime::happy_eyeballs::connect(addr, source, None).await
+    }
+
+    /// ソケットオプションのプロファイル（F-142）と送信元（F-141）を設定して文字列アドレスへ接続する
+    pub async fn connect_str_with(
+        addr: &str,
+        source: Option<LocalBind>,
+        socket: Option<Arc<SocketOptions>>,
+    ) -> io::Result<TcpStream> {
+        crate::runtime::happy_eyeballs::connect(addr, source, socket).await
     }
 
     /// バッファに非同期で読み込む（io_uring RECV）
@@ -490,6 +516,20 @@ impl TcpStream {
         }
     }
 
+    /// プール中のアイドル接続が閉じられているか（F-145、ゼロタイムアウトの poll）
+    ///
+    /// 応答待ちでない接続が読み取り可能なのは相手の FIN / RST か想定外のデータのためで、
+    /// いずれも再利用できない。プールからの取り出しは同期処理のため POLL_ADD ではなく
+    /// poll(2) で即時に判定する（ブロックしない）。
+    pub fn is_idle_closed(&self) -> bool {
+        let mut pfd = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN | libc::POLLRDHUP,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut pfd, 1, 0) != 0 }
+    }
+
     /// TCP_NODELAY を設定する
     pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
         let optval: libc::c_int = if nodelay { 1 } else { 0 };
@@ -619,6 +659,8 @@ impl AsRawFd for TcpStream {
 /// connect Future（IORING_OP_CONNECT）
 pub struct Connect {
     addr: SocketAddr,
+    source: Option<LocalBind>,
+    socket: Option<Arc<SocketOptions>>,
     fd: RawFd,
     user_data: u64,
     addr_storage: Box<libc::sockaddr_storage>,
@@ -641,6 +683,18 @@ impl Future for Connect {
                 Ok(fd) => fd,
                 Err(e) => return Poll::Ready(Err(e)),
             };
+            if let Some(socket) = &self.socket {
+                if let Err(e) = sockopt::apply_connect(fd, socket, self.addr.is_ipv6()) {
+                    unsafe { libc::close(fd) };
+                    return Poll::Ready(Err(e));
+                }
+            }
+            if let Some(source) = self.source {
+                if let Err(e) = bind_socket(fd, &source) {
+                    unsafe { libc::close(fd) };
+                    return Poll::Ready(Err(e));
+                }
+            }
             self.fd = fd;
 
             let user_data = alloc_op();
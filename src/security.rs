@@ -191,11 +191,11 @@ pub const ALLOWED_SYSCALLS: &[i64] = &[
     46,  // sendmsg
     47,  // recvmsg
     48,  // shutdown
-    49,  // bind
+    49,  // bind（F-141: 上流接続の送信元 bind にも使用）
     50,  // listen
     51,  // getsockname
     52,  // getpeername
-    54,  // setsockopt
+    54,  // setsockopt（F-141: IP_TRANSPARENT / IP_BIND_ADDRESS_NO_PORT を含む）
     55,  // getsockopt
     288, // accept4
     299, // recvmmsg (DNS解決 + HTTP/3 フォールバック経路: F-115/F-124。F-130 でホットパスは io_uring RECVMSG パイプラインへ移行済み、recvmmsg は VEIL_H3_MULTISHOT=0/reactor ビルド専用)
@@ -332,11 +332,11 @@ pub const ALLOWED_SYSCALLS: &[i64] = &[
     46,  // sendmsg
     47,  // recvmsg
     48,  // shutdown
-    49,  // bind
+    49,  // bind（F-141: 上流接続の送信元 bind にも使用）
     50,  // listen
     51,  // getsockname
     52,  // getpeername
-    54,  // setsockopt
+    54,  // setsockopt（F-141: IP_TRANSPARENT / IP_BIND_ADDRESS_NO_PORT を含む）
     55,  // getsockopt
     288, // accept4
     299, // recvmmsg (DNS解決 + HTTP/3 フォールバック経路: F-115/F-124。F-130 でホットパスは io_uring RECVMSG パイプラインへ移行済み、recvmmsg は VEIL_H3_MULTISHOT=0/reactor ビルド専用)
@@ -464,7 +464,7 @@ pub const ALLOWED_SYSCALLS: &[i64] = &[
     // ネットワーク
     // ============================================
     198, // socket
-    200, // bind
+    200, // bind（F-141: 上流接続の送信元 bind にも使用）
     201, // listen
     202, // accept
     203, // connect
@@ -472,7 +472,7 @@ pub const ALLOWED_SYSCALLS: &[i64] = &[
     205, // getpeername
     206, // sendto
     207, // recvfrom
-    208, // setsockopt
+    208, // setsockopt（F-141: IP_TRANSPARENT / IP_BIND_ADDRESS_NO_PORT を含む）
     209, // getsockopt
     210, // shutdown
     211, // sendmsg
@@ -597,7 +597,7 @@ pub const ALLOWED_SYSCALLS: &[i64] = &[
     // ネットワーク
     // ============================================
     198, // socket
-    200, // bind
+    200, // bind（F-141: 上流接続の送信元 bind にも使用）
     201, // listen
     202, // accept
     203, // connect
@@ -605,7 +605,7 @@ pub const ALLOWED_SYSCALLS: &[i64] = &[
     205, // getpeername
     206, // sendto
     207, // recvfrom
-    208, // setsockopt
+    208, // setsockopt（F-141: IP_TRANSPARENT / IP_BIND_ADDRESS_NO_PORT を含む）
     209, // getsockopt
     210, // shutdown
     211, // sendmsg
@@ -2272,6 +2272,77 @@ fn drop_capability(cap: Capability) -> io::Result<()> {
     Ok(())
 }
 
+/// setuid による権限降格の前に呼び、降格後もケイパビリティの permitted を保持する（F-141）
+///
+/// root 以外への setuid は permitted / effective のケイパビリティを全て失わせる。
+/// PR_SET_KEEPCAPS を立てると permitted が残るため、降格後に [`restore_net_admin`] で
+/// CAP_NET_ADMIN だけを有効化し直す。
+#[cfg(target_os = "linux")]
+pub fn keep_capabilities_across_setuid() -> io::Result<()> {
+    let ret = unsafe { libc::prctl(libc::PR_SET_KEEPCAPS, 1, 0, 0, 0) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// 権限降格後に CAP_NET_ADMIN だけを permitted / effective に残す（F-141）
+///
+/// 透過モード（IP_TRANSPARENT）のソケットオプション設定に必要。それ以外のケイパビリティは
+/// permitted からも外すため、降格後のプロセスが取り戻せるのは CAP_NET_ADMIN のみになる。
+#[cfg(target_os = "linux")]
+pub fn restore_net_admin() -> io::Result<()> {
+    #[repr(C)]
+    struct CapHeader {
+        version: u32,
+        pid: libc::c_int,
+    }
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    struct CapData {
+        effective: u32,
+        permitted: u32,
+        inheritable: u32,
+    }
+    const LINUX_CAPABILITY_VERSION_3: u32 = 0x2008_0522;
+
+    let bit = 1u32 << (Capability::CAP_NET_ADMIN as u32);
+    let header = CapHeader {
+        version: LINUX_CAPABILITY_VERSION_3,
+        pid: 0,
+    };
+    // v3 は 64 ビット分のケイパビリティを 2 要素で表す（CAP_NET_ADMIN = 12 は 1 要素目）
+    let data = [
+        CapData {
+            effective: bit,
+            permitted: bit,
+            inheritable: 0,
+        },
+        CapData::default(),
+    ];
+    let ret = unsafe { libc::syscall(libc::SYS_capset, &header, data.as_ptr()) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // 以降の setuid でケイパビリティを持ち越さない
+    unsafe { libc::prctl(libc::PR_SET_KEEPCAPS, 0, 0, 0, 0) };
+    info!("Retained CAP_NET_ADMIN after privilege drop (transparent upstream)");
+    Ok(())
+}
+
+impl SandboxConfig {
+    /// 指定したケイパビリティを保持する設定に直す（F-141: 透過モードの CAP_NET_ADMIN 等）
+    ///
+    /// `keep_capabilities` を使う構成では一覧へ追加し、`drop_capabilities` からは除く。
+    pub fn retain_capability(&mut self, cap: Capability) {
+        let is_cap = |name: &String| Capability::parse_str(name) == Some(cap);
+        if !self.keep_capabilities.is_empty() && !self.keep_capabilities.iter().any(is_cap) {
+            self.keep_capabilities.push(format!("{:?}", cap));
+        }
+        self.drop_capabilities.retain(|name| !is_cap(name));
+    }
+}
+
 /// サンドボックスの推奨設定を生成
 ///
 /// リバースプロキシサーバー用の推奨設定を返します。
@@ -3379,6 +3450,27 @@ mod tests {
         assert!(!config.unshare_net);
     }
 
+    #[test]
+    fn test_retain_capability_updates_keep_and_drop_lists() {
+        let mut config = SandboxConfig {
+            keep_capabilities: vec!["NET_BIND_SERVICE".into()],
+            drop_capabilities: vec!["net_admin".into(), "CAP_SYS_ADMIN".into()],
+            ..Default::default()
+        };
+        config.retain_capability(Capability::CAP_NET_ADMIN);
+        assert_eq!(
+            config.keep_capabilities,
+            vec!["NET_BIND_SERVICE".to_string(), "CAP_NET_ADMIN".to_string()]
+        );
+        assert_eq!(config.drop_capabilities, vec!["CAP_SYS_ADMIN".to_string()]);
+        // 既に保持対象なら重複させない。keep 未指定の構成には追加しない
+        config.retain_capability(Capability::CAP_NET_ADMIN);
+        assert_eq!(config.keep_capabilities.len(), 2);
+        let mut drop_only = SandboxConfig::default();
+        drop_only.retain_capability(Capability::CAP_NET_ADMIN);
+        assert!(drop_only.keep_capabilities.is_empty());
+    }
+
     #[test]
     fn test_recommended_sandbox_config() {
         let config = recommended_sandbox_config();
@@ -3487,6 +3579,13 @@ mod tests {
             assert!(ALLOWED_SYSCALLS.contains(&42)); // connect
             assert!(ALLOWED_SYSCALLS.contains(&49)); // bind
             assert!(ALLOWED_SYSCALLS.contains(&50)); // listen
+                                                     // F-141: 上流接続の送信元指定（IP_TRANSPARENT + bind）
+            assert!(ALLOWED_SYSCALLS.contains(&54)); // setsockopt
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            assert!(ALLOWED_SYSCALLS.contains(&200)); // bind
+            assert!(ALLOWED_SYSCALLS.contains(&208)); // setsockopt
         }
     }
 
@@ -4,6 +4,7 @@
 
 use crate::config::*;
 use crate::runtime::io::{AsyncReadRent, AsyncWriteRentExt};
+use crate::runtime::sockopt::SocketOptions;
 use crate::runtime::tcp::{TcpListener, TcpStream};
 use crate::runtime::time::timeout;
 use ftlog::{debug, error, info, warn};
@@ -21,7 +22,6 @@ use crate::http_utils::*;
 use crate::metrics::*;
 use crate::pool::*;
 use crate::system::*;
-use crate::upstream::*;
 
 use crate::cache;
 // AsRawFd は Linux（CBPF reuseport）と FreeBSD（capsicum rights 制限）の
@@ -253,7 +253,11 @@ pub fn spawn_background_revalidation(
 
         // バックエンドに接続
         let connect_timeout = Duration::from_secs(security.backend_connect_timeout_secs);
-        let connect_result = timeout(connect_timeout, TcpStream::connect_str(&addr)).await;
+        let connect_result = timeout(
+            connect_timeout,
+            TcpStream::connect_str_with(&addr, None, target.socket.clone()),
+        )
+        .await;
 
         let mut backend_stream = match connect_result {
             Ok(Ok(stream)) => {
@@ -638,11 +642,18 @@ pub fn spawn_wasm_tick_thread() {
     });
 }
 
+/// アクティブヘルスチェックスレッドを起動する
+///
+/// サーバーごとに次回チェック時刻を持ち（F-143: `jitter_percent` / `unhealthy_interval_secs`）、
+/// 500ms ごとに期限の来たサーバーだけをプローブする。healthy / unhealthy の遷移は理由付きで
+/// `health::record_transition` に記録する。
 // 理由付き allow: 専用ヘルスチェックスレッド上の待機（イベントループ外）。
 #[allow(clippy::disallowed_methods)]
 pub fn spawn_health_check_thread() {
     thread::spawn(move || {
         info!("Health check thread started");
+        let mut schedule = crate::health::HealthSchedule::new();
+        let mut live = std::collections::HashSet::new();
 
         loop {
             // シャットダウン中はチェックしない
@@ -650,74 +661,70 @@ pub fn spawn_health_check_thread() {
                 break;
             }
 
-            // 設定を取得
+            // 設定を取得（リロード後は新しいグループ・サーバーを対象にする）
             let config = CURRENT_CONFIG.load();
+            live.clear();
 
             // 各 Upstream グループをチェック
             for (name, group) in config.upstream_groups.iter() {
-                if let Some(ref hc_config) = group.health_check {
-                    // 各サーバーをチェック
-                    for server in &group.servers {
-                        if SHUTDOWN_FLAG.load(Ordering::Relaxed) {
-                            break;
-                        }
+                let Some(ref hc_config) = group.health_check else {
+                    continue;
+                };
+                for server in &group.servers {
+                    if SHUTDOWN_FLAG.load(Ordering::Relaxed) {
+                        break;
+                    }
 
-                        let target = &server.target;
-                        let addr = format!("{}:{}", target.host, target.port);
-
-                        // チェック種別に応じてヘルスチェックを実行（F-22）
-                        let timeout_dur = Duration::from_secs(hc_config.timeout_secs);
-                        let check_result = match hc_config.check_type {
-                            HealthCheckType::Tcp => perform_tcp_health_check(&addr, timeout_dur),
-                            HealthCheckType::Grpc => perform_grpc_health_check(
-                                &addr,
-                                &hc_config.path,
-                                hc_config.use_tls,
-                                hc_config.verify_cert,
-                                timeout_dur,
-                            ),
-                            HealthCheckType::Http => perform_health_check(
-                                &addr,
-                                &target.host,
-                                &hc_config.path,
-                                hc_config.use_tls,
-                                hc_config.verify_cert,
-                                timeout_dur,
-                                &hc_config.healthy_statuses,
-                            ),
-                        };
+                    let target = &server.target;
+                    let addr = format!("{}:{}", target.host, target.port);
+                    let key = format!("{}/{}", name, addr);
+                    let now = std::time::Instant::now();
+                    if !schedule.is_due(&key, hc_config, now) {
+                        live.insert(key);
+                        continue;
+                    }
 
-                        // メトリクス: ヘルスチェック結果を更新
-                        update_upstream_health(name, &addr, check_result);
+                    let result = crate::health::probe(
+                        hc_config,
+                        &crate::health::ProbeTarget {
+                            addr: &addr,
+                            host: &target.host,
+                            sni: target.sni(),
+                            h3: target.uses_h3(),
+                            l4: false,
+                        },
+                    );
 
-                        if check_result {
-                            server.record_success(hc_config.healthy_threshold);
-                        } else {
-                            server.record_failure(hc_config.unhealthy_threshold);
-                            ftlog::debug!("Health check failed for {} (upstream: {})", addr, name);
+                    // メトリクス: ヘルスチェック結果を更新
+                    update_upstream_health(name, &addr, result.is_ok());
+
+                    match result {
+                        Ok(()) => {
+                            if server.record_success(hc_config.healthy_threshold) {
+                                let reason = format!(
+                                    "{} consecutive successful checks",
+                                    hc_config.healthy_threshold
+                                );
+                                crate::health::record_transition(name, &addr, true, &reason);
+                            }
+                        }
+                        Err(reason) => {
+                            debug!(
+                                "Health check failed for {} (upstream: {}): {}",
+                                addr, name, reason
+                            );
+                            if server.record_failure(hc_config.unhealthy_threshold) {
+                                crate::health::record_transition(name, &addr, false, &reason);
+                            }
                         }
                     }
+                    schedule.schedule(&key, hc_config, server.is_healthy(), now);
+                    live.insert(key);
                 }
             }
+            schedule.retain(&live);
 
-            // 次のチェックまで待機（最短間隔を使用）
-            // シャットダウン時に迅速に終了するため、短い間隔で分割してスリープ
-            let min_interval = config
-                .upstream_groups
-                .values()
-                .filter_map(|g| g.health_check.as_ref())
-                .map(|hc| hc.interval_secs)
-                .min()
-                .unwrap_or(10);
-
-            // 500ms間隔でシャットダウンフラグをチェック
-            let sleep_iterations = (min_interval * 2) as usize; // 500ms × 2 = 1秒
-            for _ in 0..sleep_iterations {
-                if SHUTDOWN_FLAG.load(Ordering::Relaxed) {
-                    break;
-                }
-                cap_safe_sleep(Duration::from_millis(500));
-            }
+            cap_safe_sleep(Duration::from_millis(500));
         }
 
         info!("Health check thread stopped");
@@ -737,14 +744,16 @@ pub fn spawn_health_check_thread() {
 /// * `balancing` - 振り分け方式
 /// * `num_workers` - ワーカースレッド数（CBPF使用時に必要）
 /// * `worker_id` - このワーカーのID（最初のワーカーがCBPFをアタッチ）
+/// * `socket` - ソケットオプションのプロファイル（F-142、backlog 等）
 pub fn create_listener(
     addr: SocketAddr,
     #[allow(unused_variables)] balancing: ReuseportBalancing,
     #[allow(unused_variables)] num_workers: usize,
     #[allow(unused_variables)] worker_id: usize,
+    socket: Option<&SocketOptions>,
 ) -> io::Result<TcpListener> {
     // SO_REUSEPORT を有効にして listen する（カスタム io_uring 実装）
-    let listener = TcpListener::bind_reuse_port(addr)?;
+    let listener = TcpListener::bind_reuse_port_with(addr, socket)?;
 
     // FreeBSD: capsicum が有効なら、このリスナー fd を最小権利へ制限する（F-120 Phase 4）。
     // 全リスナー作成経路（HTTP/H2C/L4）がこの関数を通るため、ここが単一の適用ポイント。
@@ -223,7 +223,20 @@ pub struct SimpleTlsClientStream {
     drained_buffer: Vec<u8>,
 }
 
+impl crate::runtime::io::BufferedReadState for SimpleTlsClientStream {
+    /// 復号済みで未消費の平文を保持していれば `true`（F-134: 上流 h2 多重化ドライバ用）。
+    #[inline]
+    fn has_buffered_read_data(&self) -> bool {
+        !self.drained_buffer.is_empty()
+    }
+}
+
 impl SimpleTlsClientStream {
+    /// ALPN でネゴシエートされたプロトコルを取得（F-134）
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.conn.alpn_protocol()
+    }
+
     pub fn get_ref(&self) -> &TcpStream {
         &self.inner
     }
@@ -845,6 +858,15 @@ impl SimpleTlsConnector {
         self
     }
 
+    /// ALPN プロトコルリストを差し替えたコネクターを作成（F-134）
+    pub fn with_alpn_protocols(&self, protocols: &[&[u8]]) -> Self {
+        let mut config = (*self.config).clone();
+        config.alpn_protocols = protocols.iter().map(|p| p.to_vec()).collect();
+        SimpleTlsConnector {
+            config: Arc::new(config),
+        }
+    }
+
     pub async fn connect(
         &self,
         stream: TcpStream,
@@ -0,0 +1,245 @@
+//! プロキシ発行のスティッキーセッション Cookie（F-132）
+//!
+//! `HashKey::Cookie` はアプリ側 Cookie をハッシュするだけのため、メンバー変更で
+//! アフィニティが崩れる。本モジュールは veil 自身が Cookie を発行し、選択した
+//! サーバーの ID を HMAC-SHA256 で署名して埋め込む。
+//!
+//! Cookie 値の形式: `<server_id:16hex>.<expires_unix>.<hmac:64hex>`
+//!
+//! - `server_id` は `host:port` の xxh3 ハッシュ（インデックスではないため
+//!   サーバーの追加・削除・並べ替えでも他サーバーの固定は崩れない）。
+//! - `expires_unix` は `ttl_secs = 0`（セッション Cookie）のとき 0。
+//! - 署名対象は `<server_id>.<expires_unix>` とアップストリーム名（別グループの
+//!   Cookie を流用させない）。検証は `hmac::verify`（定数時間比較）で行う。
+
+use once_cell::sync::Lazy;
+
+use crate::config::{ProxyTarget, StickyCookieConfig};
+use crate::tls_provider::crypto::hmac;
+
+/// サーバー ID 生成用のシード（Consistent Hash とは独立）
+const STICKY_ID_SEED: u64 = 0x5EED_57C1_C0DE_0001;
+
+/// `secret` 未設定時に使うプロセス単位のランダム鍵（リロードでは変わらない）
+static PROCESS_KEY: Lazy<[u8; 32]> = Lazy::new(|| {
+    use crate::tls_provider::crypto::rand::{SecureRandom, SystemRandom};
+    let mut key = [0u8; 32];
+    if SystemRandom::new().fill(&mut key).is_err() {
+        // 乱数源が使えない環境は想定外。時刻ベースで最低限の一意性を確保する
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        key[..16].copy_from_slice(&now.to_le_bytes());
+    }
+    key
+});
+
+/// 構築済みのスティッキー Cookie 設定（UpstreamGroup が保持）
+pub struct StickyCookie {
+    name: String,
+    ttl_secs: u64,
+    /// `; Path=/; Secure; HttpOnly; SameSite=Lax` 等の属性（構築時に連結済み）
+    attributes: String,
+    key: hmac::Key,
+    /// 署名に含めるアップストリーム名
+    scope: String,
+}
+
+impl std::fmt::Debug for StickyCookie {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StickyCookie")
+            .field("name", &self.name)
+            .field("ttl_secs", &self.ttl_secs)
+            .field("attributes", &self.attributes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StickyCookie {
+    /// 設定から構築する（`scope` はアップストリーム名）
+    pub fn new(cfg: &StickyCookieConfig, scope: &str) -> Self {
+        let key_bytes: &[u8] = match &cfg.secret {
+            Some(s) if !s.is_empty() => s.as_bytes(),
+            _ => &PROCESS_KEY[..],
+        };
+        let mut attributes = String::with_capacity(64);
+        attributes.push_str("; Path=");
+        attributes.push_str(&cfg.path);
+        if cfg.ttl_secs > 0 {
+            attributes.push_str("; Max-Age=");
+            attributes.push_str(itoa::Buffer::new().format(cfg.ttl_secs));
+        }
+        if cfg.secure {
+            attributes.push_str("; Secure");
+        }
+        if cfg.http_only {
+            attributes.push_str("; HttpOnly");
+        }
+        attributes.push_str("; SameSite=");
+        attributes.push_str(cfg.same_site.as_str());
+        Self {
+            name: cfg.name.clone(),
+            ttl_secs: cfg.ttl_secs,
+            attributes,
+            key: hmac::Key::new(hmac::HMAC_SHA256, key_bytes),
+            scope: scope.to_string(),
+        }
+    }
+
+    /// Cookie 名
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// サーバーの安定 ID（`host:port` の xxh3）
+    pub fn server_id(target: &ProxyTarget) -> u64 {
+        use xxhash_rust::xxh3::xxh3_64_with_seed;
+        let id = format!("{}:{}", target.host, target.port);
+        xxh3_64_with_seed(id.as_bytes(), STICKY_ID_SEED)
+    }
+
+    fn sign(&self, payload: &str) -> hmac::Tag {
+        let mut ctx = hmac::Context::with_key(&self.key);
+        ctx.update(self.scope.as_bytes());
+        ctx.update(b"|");
+        ctx.update(payload.as_bytes());
+        ctx.sign()
+    }
+
+    /// 指定サーバーを固定する Cookie 値を生成する
+    pub fn encode(&self, server_id: u64) -> String {
+        let expires = if self.ttl_secs > 0 {
+            unix_now().saturating_add(self.ttl_secs)
+        } else {
+            0
+        };
+        let payload = format!("{:016x}.{}", server_id, expires);
+        let tag = self.sign(&payload);
+        let mut out = String::with_capacity(payload.len() + 1 + 64);
+        out.push_str(&payload);
+        out.push('.');
+        for b in tag.as_ref() {
+            out.push_str(&format!("{:02x}", b));
+        }
+        out
+    }
+
+    /// Cookie 値を検証してサーバー ID を返す（改ざん・期限切れ・形式不正は None）
+    pub fn decode(&self, value: &str) -> Option<u64> {
+        let (payload, mac_hex) = value.rsplit_once('.')?;
+        let (id_hex, expires) = payload.split_once('.')?;
+        if id_hex.len() != 16 || mac_hex.len() != 64 {
+            return None;
+        }
+        let mut mac = [0u8; 32];
+        for (i, chunk) in mac_hex.as_bytes().chunks(2).enumerate() {
+            let s = std::str::from_utf8(chunk).ok()?;
+            mac[i] = u8::from_str_radix(s, 16).ok()?;
+        }
+        let mut ctx_payload = Vec::with_capacity(self.scope.len() + 1 + payload.len());
+        ctx_payload.extend_from_slice(self.scope.as_bytes());
+        ctx_payload.push(b'|');
+        ctx_payload.extend_from_slice(payload.as_bytes());
+        hmac::verify(&self.key, &ctx_payload, &mac).ok()?;
+        let expires: u64 = expires.parse().ok()?;
+        if expires != 0 && expires < unix_now() {
+            return None;
+        }
+        u64::from_str_radix(id_hex, 16).ok()
+    }
+
+    /// `Set-Cookie` ヘッダー値（属性込み）を生成する
+    pub fn set_cookie_value(&self, server_id: u64) -> String {
+        let value = self.encode(server_id);
+        let mut out =
+            String::with_capacity(self.name.len() + 1 + value.len() + self.attributes.len());
+        out.push_str(&self.name);
+        out.push('=');
+        out.push_str(&value);
+        out.push_str(&self.attributes);
+        out
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SameSite;
+
+    fn cfg() -> StickyCookieConfig {
+        StickyCookieConfig {
+            secret: Some("test-secret".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn roundtrip_and_tamper_detection() {
+        let sc = StickyCookie::new(&cfg(), "pool");
+        let v = sc.encode(0xdead_beef);
+        assert_eq!(sc.decode(&v), Some(0xdead_beef));
+
+        // サーバー ID の改ざんは署名不一致
+        let forged = v.replacen("00000000deadbeef", "00000000deadbeee", 1);
+        assert_eq!(sc.decode(&forged), None);
+        // 形式不正
+        assert_eq!(sc.decode("garbage"), None);
+        assert_eq!(sc.decode(""), None);
+    }
+
+    #[test]
+    fn cookie_is_scoped_to_upstream_and_key() {
+        let a = StickyCookie::new(&cfg(), "pool-a");
+        let b = StickyCookie::new(&cfg(), "pool-b");
+        let v = a.encode(42);
+        assert_eq!(b.decode(&v), None, "other upstream must reject");
+
+        let other_key = StickyCookie::new(
+            &StickyCookieConfig {
+                secret: Some("other".into()),
+                ..Default::default()
+            },
+            "pool-a",
+        );
+        assert_eq!(other_key.decode(&v), None, "other key must reject");
+    }
+
+    #[test]
+    fn expired_cookie_is_rejected() {
+        let sc = StickyCookie::new(&cfg(), "pool");
+        // 期限を過去にした payload を正しい鍵で署名し直す
+        let payload = format!("{:016x}.{}", 7u64, 1);
+        let tag = sc.sign(&payload);
+        let hex: String = tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(sc.decode(&format!("{}.{}", payload, hex)), None);
+    }
+
+    #[test]
+    fn set_cookie_attributes() {
+        let sc = StickyCookie::new(
+            &StickyCookieConfig {
+                name: "aff".into(),
+                ttl_secs: 3600,
+                same_site: SameSite::Strict,
+                http_only: false,
+                ..cfg()
+            },
+            "pool",
+        );
+        let h = sc.set_cookie_value(1);
+        assert!(h.starts_with("aff=0000000000000001."), "{}", h);
+        assert!(
+            h.ends_with("; Path=/; Max-Age=3600; Secure; SameSite=Strict"),
+            "{}",
+            h
+        );
+    }
+}
@@ -16,8 +16,11 @@
 //!   リクエストチャネル満杯 → 送信停止、という**バックプレッシャ**が双方向に自然伝播する。
 //!   プロセスのヒープ保持は「並行ストリーム数 × 1 ストリームあたり有界バッファ」に収まり、
 //!   **RSS は総ペイロードサイズに比例しない**。
+//!
+//! [`Notify`] は上流コネクションゲート（F-136）の待機にも使うため、HTTP/2・HTTP/3 feature
+//! 無効時もモジュール自体は有効にする（チャネル等は未使用になる）。
 
-#![cfg(any(feature = "http2", feature = "http3"))]
+#![cfg_attr(not(any(feature = "http2", feature = "http3")), allow(dead_code))]
 
 use std::cell::RefCell;
 use std::collections::VecDeque;
@@ -92,6 +95,30 @@ impl Default for Notify {
     }
 }
 
+/// ソケット可読と [`Notify`] を race する（F-116、F-134 で上流多重化と共用）。
+///
+/// 2 つの Future を自前 `poll_fn` で race する（futures 依存を増やさない）。
+/// 戻り値 `true` = 可読、`false` = notify。両 Future ともキャンセル安全
+/// （`wait_readable_fd` は POLL_ADD、`notify.wait` はフラグ待ち）。
+pub async fn readable_or_notify(fd: crate::runtime::handle::RawFd, notify: &Notify) -> bool {
+    use std::future::Future;
+
+    let readable = crate::runtime::tcp::wait_readable_fd(fd);
+    let wait = notify.wait();
+    let mut readable = std::pin::pin!(readable);
+    let mut wait = std::pin::pin!(wait);
+    poll_fn(move |cx| {
+        if readable.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(true);
+        }
+        if wait.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(false);
+        }
+        Poll::Pending
+    })
+    .await
+}
+
 // ============================================================================
 // 単一スレッド SPSC 非同期チャネル（ロック・アトミックなし）
 // ============================================================================
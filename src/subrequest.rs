@@ -0,0 +1,306 @@
+//! 補助サービスへの HTTP/1.1 サブリクエスト（F-149 / F-150）
+//!
+//! 外部認可サービス（F-149）と OIDC のトークンエンドポイント（F-150）への問い合わせに使う。
+//! 接続は上流と同じワーカーごとのプール（`HTTP_POOL` / `HTTPS_POOL`）で使い回し、プールの接続が
+//! 失敗したとき（相手が閉じていた等）は新しい接続で 1 回だけやり直す。応答ヘッダーとボディは
+//! 上限を超えるとエラーにする。
+
+use ftlog::debug;
+
+use crate::config::ProxyTarget;
+use crate::http_utils::{
+    decode_chunked_body, drain_interim_responses, parse_http_response, BackendKeepAlive,
+    ChunkedDecoder, ChunkedFeedResult, HostPortStr,
+};
+use crate::pool::{buf_get, buf_put, ConnLifecycle, HTTPS_POOL, HTTP_POOL};
+use crate::runtime::io::{AsyncReadRent, AsyncWriteRentExt};
+use crate::runtime::tcp::TcpStream;
+
+/// 応答ヘッダーの上限（バイト）
+const MAX_RESPONSE_HEAD: usize = 16 * 1024;
+
+/// 応答ボディの上限（バイト）
+const MAX_RESPONSE_BODY: usize = 64 * 1024;
+
+/// 接続をプールに残す数（ワーカー・接続先ごと）
+const MAX_IDLE_CONNECTIONS: usize = 8;
+
+/// プールに残した接続のアイドルタイムアウト（秒）
+const IDLE_CONNECTION_TIMEOUT_SECS: u64 = 60;
+
+/// サブリクエストの接続先（`http://` / `https://` の URL から構築する）
+pub(crate) struct Endpoint {
+    pub target: ProxyTarget,
+    /// HTTP は `host:port`、HTTPS は `host:port:sni:verify`（上流のプールキーと同じ形式）
+    pool_key: String,
+}
+
+impl std::fmt::Debug for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Endpoint")
+            .field("pool_key", &self.pool_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Endpoint {
+    pub fn new(target: ProxyTarget) -> Self {
+        let pool_key = if target.use_tls {
+            format!("{}:{}:{}:verify", target.host, target.port, target.sni())
+        } else {
+            HostPortStr::new(&target.host, target.port)
+                .as_str()
+                .to_string()
+        };
+        Self { target, pool_key }
+    }
+
+    /// `Host` ヘッダーの値（既定のポートは省く）
+    pub fn authority(&self) -> String {
+        if self.target.is_default_port() {
+            self.target.host.clone()
+        } else {
+            format!("{}:{}", self.target.host, self.target.port)
+        }
+    }
+
+    /// リクエスト（ヘッダーとボディを組み立て済み）を送って応答を読む
+    ///
+    /// `head` は HEAD リクエスト（応答にボディが無い）。
+    pub async fn send(&self, request: Vec<u8>, head: bool) -> Result<HttpResponse, String> {
+        if self.target.use_tls {
+            self.send_https(request, head).await
+        } else {
+            self.send_http(request, head).await
+        }
+    }
+
+    async fn send_http(&self, request: Vec<u8>, head: bool) -> Result<HttpResponse, String> {
+        let (target, pool_key) = (&self.target, self.pool_key.as_str());
+        let pooled = HTTP_POOL.with(|p| p.borrow_mut().get(pool_key));
+        let connect = || async {
+            let addr = HostPortStr::new(&target.host, target.port);
+            let stream = TcpStream::connect_str_with(addr.as_str(), None, target.socket.clone())
+                .await
+                .map_err(|e| format!("connect: {}", e))?;
+            let _ = stream.set_nodelay(true);
+            Ok(stream)
+        };
+        let (response, reusable) = exchange(pooled, connect, target, request, head).await?;
+        if let Some((stream, lifecycle, idle_timeout)) = reusable {
+            HTTP_POOL.with(|p| {
+                p.borrow_mut().put(
+                    pool_key.to_string(),
+                    stream,
+                    lifecycle,
+                    MAX_IDLE_CONNECTIONS,
+                    idle_timeout,
+                )
+            });
+        }
+        Ok(response)
+    }
+
+    async fn send_https(&self, request: Vec<u8>, head: bool) -> Result<HttpResponse, String> {
+        let (target, pool_key) = (&self.target, self.pool_key.as_str());
+        let pooled = HTTPS_POOL.with(|p| p.borrow_mut().get(pool_key));
+        let connect = || async {
+            let addr = HostPortStr::new(&target.host, target.port);
+            let stream = TcpStream::connect_str_with(addr.as_str(), None, target.socket.clone())
+                .await
+                .map_err(|e| format!("connect: {}", e))?;
+            let _ = stream.set_nodelay(true);
+            crate::config::get_tls_connector()
+                .connect(stream, target.sni())
+                .await
+                .map_err(|e| format!("TLS handshake: {}", e))
+        };
+        let (response, reusable) = exchange(pooled, connect, target, request, head).await?;
+        if let Some((stream, lifecycle, idle_timeout)) = reusable {
+            HTTPS_POOL.with(|p| {
+                p.borrow_mut().put(
+                    pool_key.to_string(),
+                    stream,
+                    lifecycle,
+                    MAX_IDLE_CONNECTIONS,
+                    idle_timeout,
+                )
+            });
+        }
+        Ok(response)
+    }
+}
+
+/// 補助サービスの HTTP 応答
+#[derive(Debug)]
+pub(crate) struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// プールへ戻す接続（接続・ライフサイクル・アイドルタイムアウト秒）
+type Reusable<S> = Option<(S, ConnLifecycle, u64)>;
+
+/// 1 往復する。プールの接続が失敗したら（相手が閉じていた等）新規接続で 1 回だけやり直す。
+async fn exchange<S, F, Fut>(
+    pooled: Option<(S, ConnLifecycle)>,
+    connect: F,
+    target: &ProxyTarget,
+    request: Vec<u8>,
+    head: bool,
+) -> Result<(HttpResponse, Reusable<S>), String>
+where
+    S: AsyncReadRent + AsyncWriteRentExt + Unpin,
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<S, String>>,
+{
+    let mut request = request;
+    if let Some((mut stream, lifecycle)) = pooled {
+        let (result, returned) = round_trip(&mut stream, request, head).await;
+        match result {
+            Ok((response, keep_alive)) => {
+                return Ok((response, reusable(stream, lifecycle, keep_alive)));
+            }
+            Err(e) => {
+                debug!(
+                    "[Subrequest] pooled connection failed ({}), reconnecting",
+                    e
+                );
+                request = returned;
+            }
+        }
+    }
+    let mut stream = connect().await?;
+    let lifecycle = ConnLifecycle::new(&target.connection_pool);
+    let (result, _) = round_trip(&mut stream, request, head).await;
+    let (response, keep_alive) = result?;
+    Ok((response, reusable(stream, lifecycle, keep_alive)))
+}
+
+fn reusable<S>(stream: S, lifecycle: ConnLifecycle, keep_alive: BackendKeepAlive) -> Reusable<S> {
+    let idle_timeout = keep_alive.idle_timeout_secs(IDLE_CONNECTION_TIMEOUT_SECS);
+    (keep_alive.is_reusable() && idle_timeout > 0)
+        .then(|| (stream, lifecycle.served(), idle_timeout))
+}
+
+/// リクエストを書いて応答を読む（書いたリクエストは再送用に返す）
+async fn round_trip<S>(
+    stream: &mut S,
+    request: Vec<u8>,
+    head: bool,
+) -> (Result<(HttpResponse, BackendKeepAlive), String>, Vec<u8>)
+where
+    S: AsyncReadRent + AsyncWriteRentExt + Unpin,
+{
+    let (res, request) = stream.write_all(request).await;
+    if let Err(e) = res {
+        return (Err(format!("write: {}", e)), request);
+    }
+    (read_response(stream, head).await, request)
+}
+
+/// 読み取ったバイト数（0 = 相手が閉じた）
+async fn read_into<S: AsyncReadRent>(stream: &mut S, acc: &mut Vec<u8>) -> Result<usize, String> {
+    let (res, mut buf) = stream.read(buf_get()).await;
+    let n = match res {
+        Ok(n) => n,
+        Err(e) => {
+            buf_put(buf);
+            return Err(format!("read: {}", e));
+        }
+    };
+    buf.set_valid_len(n);
+    acc.extend_from_slice(buf.as_valid_slice());
+    buf_put(buf);
+    Ok(n)
+}
+
+async fn read_response<S: AsyncReadRent>(
+    stream: &mut S,
+    head: bool,
+) -> Result<(HttpResponse, BackendKeepAlive), String> {
+    let mut acc = Vec::with_capacity(1024);
+    let parsed = loop {
+        if read_into(stream, &mut acc).await? == 0 {
+            return Err("connection closed before response".to_string());
+        }
+        drain_interim_responses(&mut acc);
+        if let Some(parsed) = parse_http_response(&acc) {
+            break parsed;
+        }
+        if acc.len() > MAX_RESPONSE_HEAD {
+            return Err("response header too large".to_string());
+        }
+    };
+    let header_len = parsed.header_len;
+    let mut keep_alive = parsed.keep_alive();
+    let no_body = head || parsed.status_code == 204 || parsed.status_code == 304;
+
+    let body = if no_body {
+        Vec::new()
+    } else if parsed.is_chunked {
+        let mut decoder = ChunkedDecoder::new(MAX_RESPONSE_BODY as u64);
+        let mut fed = header_len;
+        loop {
+            match decoder.feed(&acc[fed..]) {
+                ChunkedFeedResult::Complete => break,
+                ChunkedFeedResult::SizeLimitExceeded => {
+                    return Err("response body too large".to_string())
+                }
+                ChunkedFeedResult::Continue => {}
+            }
+            fed = acc.len();
+            if read_into(stream, &mut acc).await? == 0 {
+                return Err("truncated response body".to_string());
+            }
+        }
+        decode_chunked_body(&acc[header_len..])
+    } else if let Some(len) = parsed.content_length {
+        if len > MAX_RESPONSE_BODY {
+            return Err("response body too large".to_string());
+        }
+        while acc.len() < header_len + len {
+            if read_into(stream, &mut acc).await? == 0 {
+                return Err("truncated response body".to_string());
+            }
+        }
+        if acc.len() > header_len + len {
+            // 応答の後ろに余分なデータがある接続は使い回さない
+            keep_alive = BackendKeepAlive::Close;
+        }
+        acc[header_len..header_len + len].to_vec()
+    } else {
+        // 長さの指定が無い応答は接続の終わりまで
+        keep_alive = BackendKeepAlive::Close;
+        while read_into(stream, &mut acc).await? > 0 {
+            if acc.len() - header_len > MAX_RESPONSE_BODY {
+                return Err("response body too large".to_string());
+            }
+        }
+        acc[header_len..].to_vec()
+    };
+
+    let mut storage = [httparse::EMPTY_HEADER; 64];
+    let mut response = httparse::Response::new(&mut storage);
+    let _ = response.parse(&acc[..header_len]);
+    let headers = response
+        .headers
+        .iter()
+        .filter(|h| !h.name.is_empty())
+        .map(|h| {
+            (
+                h.name.to_string(),
+                String::from_utf8_lossy(h.value).into_owned(),
+            )
+        })
+        .collect();
+    Ok((
+        HttpResponse {
+            status: parsed.status_code,
+            headers,
+            body,
+        },
+        keep_alive,
+    ))
+}
@@ -355,8 +355,14 @@ pub(crate) fn get_gid_by_name(groupname: &str) -> Option<u32> {
 /// グループ→ユーザーの順で降格する（逆順では失敗する可能性あり）。
 /// `setgid`/`setgroups`/`setuid` は POSIX のため Linux/FreeBSD/OpenBSD 共通で動作する
 /// （F-120 Phase 4 で Linux 限定 cfg とスタブを撤去）。
+///
+/// `retain_net_admin` が true（透過モードの上流がある、F-141）なら、Linux ではユーザー降格後も
+/// CAP_NET_ADMIN だけを保持する（IP_TRANSPARENT の設定に必要）。
 #[cfg(unix)]
-pub(crate) fn drop_privileges(security: &crate::GlobalSecurityConfig) -> io::Result<()> {
+pub(crate) fn drop_privileges(
+    security: &crate::GlobalSecurityConfig,
+    retain_net_admin: bool,
+) -> io::Result<()> {
     // rootでない場合は何もしない
     if unsafe { libc::getuid() } != 0 {
         info!("Not running as root, skipping privilege drop");
@@ -396,12 +402,24 @@ pub(crate) fn drop_privileges(security: &crate::GlobalSecurityConfig) -> io::Res
             )
         })?;
 
+        #[cfg(target_os = "linux")]
+        if retain_net_admin {
+            crate::security::keep_capabilities_across_setuid()?;
+        }
+
         if unsafe { libc::setuid(uid) } != 0 {
             return Err(io::Error::last_os_error());
         }
 
         info!("Dropped user privileges to '{}' (uid={})", user_name, uid);
+
+        #[cfg(target_os = "linux")]
+        if retain_net_admin {
+            crate::security::restore_net_admin()?;
+        }
     }
+    #[cfg(not(target_os = "linux"))]
+    let _ = retain_net_admin;
 
     // 降格成功の確認
     if security.drop_privileges_user.is_some() || security.drop_privileges_group.is_some() {
@@ -425,7 +443,10 @@ pub(crate) fn drop_privileges(security: &crate::GlobalSecurityConfig) -> io::Res
 /// `drop_privileges_group` 設定は無視して警告のみ出す（best-effort。実機検証不可のため
 /// 保守的に no-op とする。将来的には制限付きトークン/AppContainer での実装が課題）。
 #[cfg(windows)]
-pub(crate) fn drop_privileges(security: &crate::GlobalSecurityConfig) -> io::Result<()> {
+pub(crate) fn drop_privileges(
+    security: &crate::GlobalSecurityConfig,
+    _retain_net_admin: bool,
+) -> io::Result<()> {
     if security.drop_privileges_user.is_some() || security.drop_privileges_group.is_some() {
         warn!(
             "drop_privileges_user/drop_privileges_group are not supported on Windows \
@@ -0,0 +1,269 @@
+//! 上流タイムアウトとリクエスト期限（F-139）
+//!
+//! ルートごとの `security` 設定で上流とのやり取りのタイムアウトを個別に指定する。
+//!
+//! - 応答ヘッダー待ち（`upstream_header_timeout_ms`）: リクエスト送信から応答ヘッダー受信まで
+//! - アイドル読み取り（`upstream_idle_timeout_ms`）: 応答受信中の 1 回の読み取りの待ち時間
+//! - リクエスト全体の期限（`request_timeout_ms`）: リクエスト受信から中継完了まで
+//! - 試行ごと（`per_try_timeout_ms`）: 各試行（一次試行・F-137 のヘッジ）が応答を返し始めるまで
+//!
+//! gRPC リクエスト（`grpc` feature）は受信した `grpc-timeout` も期限として扱い、ルートの
+//! 期限と短い方を採用する。上流へは経過時間を差し引いた残りを `grpc-timeout` として転送し、
+//! 応答ヘッダー前に期限切れになった場合は 504 の代わりに DEADLINE_EXCEEDED を返す。
+
+use std::time::{Duration, Instant};
+
+use crate::config::SecurityConfig;
+use crate::pool::{BACKEND_HEADER_TIMEOUT, READ_TIMEOUT};
+
+/// リクエスト単位の上流期限（`SecurityConfig::upstream_deadline` に載せて引き回す）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpstreamDeadline {
+    at: Instant,
+    grpc: bool,
+}
+
+impl UpstreamDeadline {
+    /// ルート設定と受信した `grpc-timeout` から期限を決める。期限が無ければ None
+    ///
+    /// `received_at` はリクエスト受信時刻（ここからの経過時間が期限から差し引かれる）。
+    #[cfg_attr(not(feature = "grpc"), allow(unused_variables))]
+    pub fn resolve(
+        cfg: &SecurityConfig,
+        received_at: Instant,
+        is_grpc: bool,
+        grpc_timeout: Option<&[u8]>,
+    ) -> Option<Self> {
+        let route =
+            (cfg.request_timeout_ms > 0).then(|| Duration::from_millis(cfg.request_timeout_ms));
+        #[cfg(feature = "grpc")]
+        let grpc = grpc_timeout
+            .filter(|_| is_grpc && cfg.grpc_deadline_propagation)
+            .and_then(crate::grpc::parse_grpc_timeout)
+            .map(|d| match cfg.max_grpc_timeout_ms {
+                0 => d,
+                max => d.min(Duration::from_millis(max)),
+            });
+        #[cfg(not(feature = "grpc"))]
+        let grpc: Option<Duration> = None;
+        let budget = match (route, grpc) {
+            (Some(a), Some(b)) => a.min(b),
+            (a, b) => a.or(b)?,
+        };
+        Some(Self {
+            at: received_at + budget,
+            grpc: cfg!(feature = "grpc") && is_grpc,
+        })
+    }
+
+    /// 期限の時刻
+    #[inline]
+    pub fn at(&self) -> Instant {
+        self.at
+    }
+
+    /// 残り時間（期限切れなら 0）
+    #[inline]
+    pub fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    /// 期限切れか
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.at
+    }
+
+    /// gRPC リクエストの期限か（期限切れは DEADLINE_EXCEEDED で応答する）
+    #[inline]
+    pub fn is_grpc(&self) -> bool {
+        self.grpc
+    }
+
+    /// 上流へ転送する `grpc-timeout` の値（gRPC リクエストのみ、残り時間）
+    pub fn grpc_timeout_value(&self) -> Option<String> {
+        #[cfg(feature = "grpc")]
+        if self.grpc {
+            return Some(crate::grpc::headers::format_grpc_timeout(self.remaining()));
+        }
+        None
+    }
+}
+
+/// 上流の読み取りタイムアウト群（`SecurityConfig::upstream_timeouts` で構築、コピーして渡す）
+#[derive(Clone, Copy, Debug)]
+pub struct UpstreamTimeouts {
+    /// 応答ヘッダー待ち（None = 既定の [`BACKEND_HEADER_TIMEOUT`]）
+    header: Option<Duration>,
+    idle: Duration,
+    per_try: Option<Duration>,
+    deadline: Option<Instant>,
+}
+
+impl UpstreamTimeouts {
+    pub fn new(cfg: &SecurityConfig) -> Self {
+        let ms = |v: u64| (v > 0).then(|| Duration::from_millis(v));
+        Self {
+            header: ms(cfg.upstream_header_timeout_ms),
+            idle: ms(cfg.upstream_idle_timeout_ms).unwrap_or(READ_TIMEOUT),
+            per_try: ms(cfg.per_try_timeout_ms),
+            deadline: cfg.upstream_deadline.map(|d| d.at()),
+        }
+    }
+
+    /// 応答ヘッダー受信の期限（`sent_at` は最初の試行の送信時刻）
+    pub fn header_deadline(&self, sent_at: Instant) -> Instant {
+        self.bound(sent_at + self.header.unwrap_or(BACKEND_HEADER_TIMEOUT))
+    }
+
+    /// 単一試行の応答ヘッダー受信の期限（ヘッダー待ち・試行ごと・リクエスト期限の最短）
+    pub fn first_try_deadline(&self, sent_at: Instant) -> Instant {
+        self.try_deadline(self.header_deadline(sent_at), sent_at)
+    }
+
+    /// HTTP/2・HTTP/3 フロントエンドの上流交換の期限（応答開始または一括応答まで）
+    ///
+    /// 多重化上流（h2c / h2 / h3）は応答ヘッダーとボディをまとめて待つため、ヘッダー待ちは
+    /// 明示設定時のみ適用し、既定ではアイドル上限（既定 30 秒）で打ち切る。
+    pub fn exchange_deadline(&self, sent_at: Instant) -> Instant {
+        let mut at = sent_at + self.header.map_or(self.idle, |h| h.min(self.idle));
+        if let Some(per_try) = self.per_try {
+            at = at.min(sent_at + per_try);
+        }
+        self.bound(at)
+    }
+
+    /// 1 回の試行が応答を返し始める期限（`sent_at` はその試行の送信時刻）
+    ///
+    /// `header_deadline` は [`Self::header_deadline`] の値（試行をまたいだ全体の上限）。
+    pub fn try_deadline(&self, header_deadline: Instant, sent_at: Instant) -> Instant {
+        match self.per_try {
+            Some(per_try) => header_deadline.min(sent_at + per_try),
+            None => header_deadline,
+        }
+    }
+
+    /// 応答受信中の 1 回の読み取りのタイムアウト（アイドル上限とリクエスト期限の短い方）
+    #[inline]
+    pub fn read(&self) -> Duration {
+        match self.deadline {
+            None => self.idle,
+            Some(at) => self.idle.min(at.saturating_duration_since(Instant::now())),
+        }
+    }
+
+    /// `at` までの残り時間（過ぎていれば 0）
+    #[inline]
+    pub fn until(at: Instant) -> Duration {
+        at.saturating_duration_since(Instant::now())
+    }
+
+    /// `at` をリクエスト期限で頭打ちにする
+    pub fn bound(&self, at: Instant) -> Instant {
+        match self.deadline {
+            Some(deadline) => at.min(deadline),
+            None => at,
+        }
+    }
+}
+
+/// gRPC の期限切れ応答（Trailers-Only、DEADLINE_EXCEEDED）のヘッダー
+#[cfg(feature = "grpc")]
+pub fn grpc_deadline_exceeded_headers() -> Vec<(Vec<u8>, Vec<u8>)> {
+    use crate::grpc::{GrpcStatus, GrpcStatusCode};
+
+    let mut headers = vec![(b"content-type".to_vec(), b"application/grpc".to_vec())];
+    headers.extend(
+        GrpcStatus::error(
+            GrpcStatusCode::DeadlineExceeded,
+            "upstream deadline exceeded",
+        )
+        .to_trailers(),
+    );
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> SecurityConfig {
+        SecurityConfig::default()
+    }
+
+    #[test]
+    fn defaults_keep_builtin_timeouts() {
+        let cfg = cfg();
+        assert!(UpstreamDeadline::resolve(&cfg, Instant::now(), false, None).is_none());
+        let t = cfg.upstream_timeouts();
+        assert_eq!(t.read(), READ_TIMEOUT);
+        let now = Instant::now();
+        assert_eq!(t.header_deadline(now), now + BACKEND_HEADER_TIMEOUT);
+        assert_eq!(t.first_try_deadline(now), now + BACKEND_HEADER_TIMEOUT);
+        // 多重化上流は既定でアイドル上限まで待つ
+        assert_eq!(t.exchange_deadline(now), now + READ_TIMEOUT);
+    }
+
+    #[test]
+    fn per_try_and_deadline_bound_header_wait() {
+        let now = Instant::now();
+        let mut cfg = SecurityConfig {
+            upstream_header_timeout_ms: 5_000,
+            upstream_idle_timeout_ms: 2_000,
+            per_try_timeout_ms: 300,
+            request_timeout_ms: 1_000,
+            ..cfg()
+        };
+        let deadline = UpstreamDeadline::resolve(&cfg, now, false, None).unwrap();
+        assert_eq!(deadline.at(), now + Duration::from_secs(1));
+        assert!(!deadline.is_grpc() && deadline.grpc_timeout_value().is_none());
+        cfg = cfg.with_upstream_deadline(deadline);
+
+        let t = cfg.upstream_timeouts();
+        // 全体の応答ヘッダー待ちはリクエスト期限で頭打ち
+        let header = t.header_deadline(now);
+        assert_eq!(header, now + Duration::from_secs(1));
+        // 試行ごとの上限は試行の送信時刻から
+        let later = now + Duration::from_millis(800);
+        assert_eq!(
+            t.try_deadline(header, now),
+            now + Duration::from_millis(300)
+        );
+        assert_eq!(t.try_deadline(header, later), header);
+        assert_eq!(t.first_try_deadline(now), now + Duration::from_millis(300));
+        assert_eq!(t.exchange_deadline(now), now + Duration::from_millis(300));
+        // 読み取りは残り時間で頭打ち
+        assert!(t.read() <= Duration::from_secs(1));
+    }
+
+    #[cfg(feature = "grpc")]
+    #[test]
+    fn grpc_timeout_is_honored_capped_and_forwarded() {
+        let now = Instant::now();
+        let cfg = SecurityConfig {
+            request_timeout_ms: 10_000,
+            max_grpc_timeout_ms: 3_000,
+            ..cfg()
+        };
+        // 短い grpc-timeout が優先される
+        let d = UpstreamDeadline::resolve(&cfg, now, true, Some(b"200m")).unwrap();
+        assert_eq!(d.at(), now + Duration::from_millis(200));
+        assert!(d.is_grpc());
+        let forwarded = d.grpc_timeout_value().unwrap();
+        let remaining = crate::grpc::parse_grpc_timeout(forwarded.as_bytes()).unwrap();
+        assert!(remaining <= Duration::from_millis(200));
+
+        // 上限で丸める
+        let d = UpstreamDeadline::resolve(&cfg, now, true, Some(b"1H")).unwrap();
+        assert_eq!(d.at(), now + Duration::from_secs(3));
+
+        // gRPC 以外・伝播無効では grpc-timeout を無視する
+        let d = UpstreamDeadline::resolve(&cfg, now, false, Some(b"200m")).unwrap();
+        assert_eq!(d.at(), now + Duration::from_secs(10));
+        let off = SecurityConfig {
+            grpc_deadline_propagation: false,
+            ..SecurityConfig::default()
+        };
+        assert!(UpstreamDeadline::resolve(&off, now, true, Some(b"200m")).is_none());
+    }
+}
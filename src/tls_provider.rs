@@ -69,3 +69,20 @@ pub use aws_lc_rs::rand::{SecureRandom, SystemRandom};
     )
 ))]
 pub use ring::rand::{SecureRandom, SystemRandom};
+
+/// TLS 以外の用途（HMAC 署名・ダイジェスト・乱数等）に使う暗号ライブラリ。
+///
+/// aws-lc-rs は ring 互換 API（`hmac` / `digest` / `rand` / `signature` / `aead`）を
+/// 提供するため、rustls プロバイダと同じ target 分割で別名再エクスポートする。
+/// 呼び出し側は `crate::tls_provider::crypto::hmac` のように参照する。
+#[cfg(any(
+    not(any(target_os = "openbsd", target_os = "macos", target_os = "windows")),
+    all(target_os = "windows", target_arch = "aarch64")
+))]
+pub use aws_lc_rs as crypto;
+#[cfg(any(
+    target_os = "openbsd",
+    target_os = "macos",
+    all(target_os = "windows", not(target_arch = "aarch64"))
+))]
+pub use ring as crypto;
@@ -1,6 +1,7 @@
 //! アップストリーム選択・ヘルスチェックモジュール
 //!
-//! バックエンド検索、条件マッチング、ヘルスチェック関数を提供します。
+//! バックエンド検索、条件マッチング、TCP / gRPC ヘルスチェック関数を提供します。
+//! HTTP プローブとスケジュールは `health` モジュール（F-143）にあります。
 
 use crate::config::*;
 use crate::routing;
@@ -10,205 +11,6 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
-/// 同期的な健康チェックを実行
-///
-/// TCP 接続して HTTP GET リクエストを送信し、レスポンスをチェック。
-/// TLS接続もサポート（use_tls=true時）。
-// 理由付き allow: 専用ヘルスチェックスレッドから呼ばれる同期プローブ（イベントループ外）。
-#[allow(clippy::disallowed_methods)]
-pub(crate) fn perform_health_check(
-    addr: &str,
-    host: &str,
-    path: &str,
-    use_tls: bool,
-    verify_cert: bool,
-    timeout: Duration,
-    healthy_statuses: &[u16],
-) -> bool {
-    use rustls::pki_types::ServerName;
-    use rustls::{ClientConfig, ClientConnection, RootCertStore};
-    use std::io::{ErrorKind, Read, Write};
-    use std::net::TcpStream as StdTcpStream;
-    use std::sync::Arc;
-
-    // TCP 接続
-    let mut tcp_stream = match StdTcpStream::connect_timeout(
-        &addr
-            .parse()
-            .unwrap_or_else(|_| std::net::SocketAddr::from(([127, 0, 0, 1], 80))),
-        timeout,
-    ) {
-        Ok(s) => s,
-        Err(_) => return false,
-    };
-
-    let _ = tcp_stream.set_read_timeout(Some(timeout));
-    let _ = tcp_stream.set_write_timeout(Some(timeout));
-
-    // TLS接続の場合
-    if use_tls {
-        // rustls クライアント設定
-        let config: Arc<ClientConfig> = if verify_cert {
-            // 証明書検証を有効化（デフォルトのルート証明書ストアを使用）
-            let mut root_store = RootCertStore::empty();
-            root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
-            Arc::new(
-                ClientConfig::builder()
-                    .with_root_certificates(root_store)
-                    .with_no_client_auth(),
-            )
-        } else {
-            // 証明書検証を完全に無効化（自己署名証明書を許可）
-            use rustls::client::danger::{
-                HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
-            };
-            use rustls::pki_types::{CertificateDer, UnixTime};
-            use rustls::{DigitallySignedStruct, Error as TlsError, SignatureScheme};
-
-            #[derive(Debug)]
-            struct NoVerify;
-
-            impl ServerCertVerifier for NoVerify {
-                fn verify_server_cert(
-                    &self,
-                    _: &CertificateDer,
-                    _: &[CertificateDer],
-                    _: &rustls::pki_types::ServerName,
-                    _: &[u8],
-                    _: UnixTime,
-                ) -> Result<ServerCertVerified, TlsError> {
-                    Ok(ServerCertVerified::assertion())
-                }
-                fn verify_tls12_signature(
-                    &self,
-                    _: &[u8],
-                    _: &CertificateDer,
-                    _: &DigitallySignedStruct,
-                ) -> Result<HandshakeSignatureValid, TlsError> {
-                    Ok(HandshakeSignatureValid::assertion())
-                }
-                fn verify_tls13_signature(
-                    &self,
-                    _: &[u8],
-                    _: &CertificateDer,
-                    _: &DigitallySignedStruct,
-                ) -> Result<HandshakeSignatureValid, TlsError> {
-                    Ok(HandshakeSignatureValid::assertion())
-                }
-                fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
-                    vec![
-                        SignatureScheme::RSA_PKCS1_SHA256,
-                        SignatureScheme::RSA_PKCS1_SHA384,
-                        SignatureScheme::RSA_PKCS1_SHA512,
-                        SignatureScheme::ECDSA_NISTP256_SHA256,
-                        SignatureScheme::ECDSA_NISTP384_SHA384,
-                        SignatureScheme::RSA_PSS_SHA256,
-                        SignatureScheme::RSA_PSS_SHA384,
-                        SignatureScheme::RSA_PSS_SHA512,
-                    ]
-                }
-            }
-
-            Arc::new(
-                ClientConfig::builder()
-                    .dangerous()
-                    .with_custom_certificate_verifier(Arc::new(NoVerify))
-                    .with_no_client_auth(),
-            )
-        };
-
-        // SNI名を決定
-        let server_name = match ServerName::try_from(host.to_string()) {
-            Ok(name) => name,
-            Err(_) => return false,
-        };
-
-        // TLS接続を確立
-        let mut tls_conn = match ClientConnection::new(config, server_name) {
-            Ok(conn) => conn,
-            Err(_) => return false,
-        };
-
-        // ハンドシェイクを実行（同期）
-        while tls_conn.is_handshaking() {
-            match tls_conn.complete_io(&mut tcp_stream) {
-                Ok(_) => {}
-                Err(e) if e.kind() == ErrorKind::WouldBlock => {
-                    // 非ブロッキングI/Oの場合は短い待機
-                    std::thread::sleep(Duration::from_millis(10));
-                    continue;
-                }
-                Err(_) => return false,
-            }
-        }
-
-        // rustls::Streamを使用して読み書き
-        let mut stream = rustls::Stream::new(&mut tls_conn, &mut tcp_stream);
-
-        // HTTP リクエスト送信
-        let request = format!(
-            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nUser-Agent: HealthCheck/1.0\r\n\r\n",
-            path, host
-        );
-
-        if stream.write_all(request.as_bytes()).is_err() {
-            return false;
-        }
-
-        // レスポンス読み取り
-        let mut response = [0u8; 1024];
-        let n = match stream.read(&mut response) {
-            Ok(n) if n > 0 => n,
-            _ => return false,
-        };
-
-        // ステータスコードを抽出
-        let response_str = String::from_utf8_lossy(&response[..n]);
-        if let Some(status_line) = response_str.lines().next() {
-            // "HTTP/1.1 200 OK" のようなパターン
-            let parts: Vec<&str> = status_line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                if let Ok(status_code) = parts[1].parse::<u16>() {
-                    return healthy_statuses.contains(&status_code);
-                }
-            }
-        }
-
-        false
-    } else {
-        // HTTP接続（既存の実装）
-        let request = format!(
-            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nUser-Agent: HealthCheck/1.0\r\n\r\n",
-            path, host
-        );
-
-        if tcp_stream.write_all(request.as_bytes()).is_err() {
-            return false;
-        }
-
-        // レスポンス読み取り
-        let mut response = [0u8; 1024];
-        let n = match tcp_stream.read(&mut response) {
-            Ok(n) if n > 0 => n,
-            _ => return false,
-        };
-
-        // ステータスコードを抽出
-        let response_str = String::from_utf8_lossy(&response[..n]);
-        if let Some(status_line) = response_str.lines().next() {
-            // "HTTP/1.1 200 OK" のようなパターン
-            let parts: Vec<&str> = status_line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                if let Ok(status_code) = parts[1].parse::<u16>() {
-                    return healthy_statuses.contains(&status_code);
-                }
-            }
-        }
-
-        false
-    }
-}
-
 /// TCP 接続の確立可否のみ確認するヘルスチェック（F-22）
 ///
 /// HTTP リクエストは送信せず、TCP 3-way ハンドシェイクが完了すれば healthy と判断。
@@ -339,10 +141,8 @@ fn perform_grpc_health_check_h2c(
             }
             let payload = &buf[offset + 9..offset + 9 + frame_len];
             match decoder.decode(&header, payload) {
-                Ok(Frame::Settings { ack, .. }) => {
-                    if !ack {
-                        let _ = stream.write_all(&enc.encode_settings_ack());
-                    }
+                Ok(Frame::Settings { ack, .. }) if !ack => {
+                    let _ = stream.write_all(&enc.encode_settings_ack());
                 }
                 Ok(Frame::WindowUpdate { .. }) | Ok(Frame::Ping { .. }) => {}
                 Ok(Frame::Headers {
@@ -685,6 +485,13 @@ pub(crate) fn matches_conditions(
         }
     }
 
+    // jwt_claims条件のチェック（F-148）
+    if let Some(ref claim_conds) = conditions.jwt_claims {
+        if !matches_jwt_claims(claim_conds, headers) {
+            return false;
+        }
+    }
+
     true
 }
 
@@ -877,9 +684,48 @@ pub(crate) fn matches_remaining_conditions(
         }
     }
 
+    // jwt_claims条件のチェック（F-148）
+    if let Some(ref claim_conds) = conditions.jwt_claims {
+        if !matches_jwt_claims(claim_conds, headers) {
+            return false;
+        }
+    }
+
     true
 }
 
+/// JWT クレーム条件の照合（F-148）
+///
+/// 署名は検証しない。マッチしたルートの `[route.jwt]` が転送前に検証する。
+fn matches_jwt_claims(claim_conds: &HashMap<String, String>, headers: &[(&[u8], &[u8])]) -> bool {
+    let Some(claims) = crate::jwt_auth::unverified_claims(headers) else {
+        return false;
+    };
+    claim_conds.iter().all(|(claim, value_pattern)| {
+        crate::jwt_auth::claim_value(&claims, claim).is_some_and(|value| {
+            crate::jwt_auth::claim_matches(value, |v| matches_claim_pattern(value_pattern, v))
+        })
+    })
+}
+
+/// クレーム値のワイルドカード照合（`*` は任意の文字列、例: `acme-*`、`*-admin`）
+fn matches_claim_pattern(pattern: &str, text: &str) -> bool {
+    let Some((head, tail)) = pattern.split_once('*') else {
+        return pattern == text;
+    };
+    let Some(mut rest) = text.strip_prefix(head) else {
+        return false;
+    };
+    let (middle, last) = tail.rsplit_once('*').unwrap_or(("", tail));
+    for part in middle.split('*').filter(|p| !p.is_empty()) {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
 /// フォールバック用線形探索（セーフティネット）
 pub(crate) fn find_backend_linear(
     host: &[u8],
@@ -1232,4 +1078,63 @@ mod tests {
             );
         }
     }
+
+    // ====================
+    // F-148: JWT クレームによるルーティング条件
+    // ====================
+
+    #[test]
+    fn test_matches_claim_pattern() {
+        assert!(matches_claim_pattern("acme", "acme"));
+        assert!(!matches_claim_pattern("acme", "acme-eu"));
+        assert!(matches_claim_pattern("acme-*", "acme-eu"));
+        assert!(matches_claim_pattern("*-admin", "ops-admin"));
+        assert!(matches_claim_pattern("a*m*e", "acme"));
+        assert!(matches_claim_pattern("*", ""));
+        assert!(!matches_claim_pattern("acme-*", "acm"));
+        assert!(!matches_claim_pattern("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_matches_jwt_claims_conditions() {
+        // {"tenant":"acme","roles":["dev","admin"]}（署名は照合しない）
+        let bearer: &[u8] =
+            b"Bearer eyJhbGciOiJIUzI1NiJ9.eyJ0ZW5hbnQiOiJhY21lIiwicm9sZXMiOlsiZGV2IiwiYWRtaW4iXX0.x";
+        let conditions = RouteConditions {
+            jwt_claims: Some(HashMap::from([
+                ("tenant".to_string(), "ac*".to_string()),
+                ("roles".to_string(), "admin".to_string()),
+            ])),
+            ..Default::default()
+        };
+        let with_token: &[(&[u8], &[u8])] = &[(b"authorization", bearer)];
+        assert!(matches_remaining_conditions(
+            &conditions,
+            b"GET",
+            with_token,
+            b""
+        ));
+        let source_ip: SocketAddr = "10.0.0.1:1234".parse().unwrap();
+        assert!(matches_conditions(
+            &conditions,
+            b"example.com",
+            b"/",
+            b"GET",
+            with_token,
+            b"",
+            &source_ip
+        ));
+        assert!(!matches_remaining_conditions(&conditions, b"GET", &[], b""));
+
+        let other_role = RouteConditions {
+            jwt_claims: Some(HashMap::from([("roles".to_string(), "ops".to_string())])),
+            ..Default::default()
+        };
+        assert!(!matches_remaining_conditions(
+            &other_role,
+            b"GET",
+            with_token,
+            b""
+        ));
+    }
 }
@@ -0,0 +1,2236 @@
+//! ModSecurity の SecRule のサブセットによる WAF（F-154）
+//!
+//! WASM の WAF フィルタ（`examples/wasm-filters/waf-filter`）はリクエストごとにモジュールを
+//! 呼ぶため重く、規則の差し替えもモジュールの再ビルドになる。本モジュールは `[route.waf]` を
+//! 設定したルートで、ModSecurity 形式の規則ファイル（OWASP CRS を含む）を読み込んで
+//! リクエストを検査する。
+//!
+//! - ディレクティブ: `SecRule`・`SecRuleRemoveById`・`Include`（規則ファイルからの相対パス、
+//!   ファイル名の `*` 1 つまで）。`SecAction`・`SecMarker`・`SecRuleEngine` 等は読み飛ばす。
+//! - 変数: `ARGS`・`ARGS_NAMES`・`ARGS_GET`・`ARGS_POST`（と `_NAMES`）・`REQUEST_HEADERS`・
+//!   `REQUEST_HEADERS_NAMES`・`REQUEST_COOKIES`・`REQUEST_COOKIES_NAMES`・`REQUEST_URI`・
+//!   `REQUEST_FILENAME`・`REQUEST_BASENAME`・`QUERY_STRING`・`REQUEST_METHOD`・`REQUEST_BODY`。
+//!   `:名前` / `:/正規表現/` の絞り込みと `!` の除外に対応する。`TX`・`XML` 等の対応しない変数は
+//!   除き、対象の変数が残らない規則は読み飛ばす。
+//! - 演算子: `@rx`・`@pm`・`@pmFromFile`・`@contains`・`@streq`・`@beginsWith`・`@endsWith`・
+//!   `@within`・`@validateByteRange`・`@detectSQLi`・`@detectXSS`（後の 2 つは libinjection では
+//!   なく、典型的な攻撃の形を見る簡易な判定）と `!` による否定。
+//! - 変換: `lowercase`・`urlDecode(Uni)`・`htmlEntityDecode`・`jsDecode`・`cmdLine`・
+//!   `compressWhitespace`・`removeWhitespace`・`removeNulls`・`replaceNulls`・`removeComments`・
+//!   `replaceComments`・`normalizePath`・`trim` 等。値の意味が変わる変換（`length`・`sha1` 等）を
+//!   使う規則は読み飛ばし、それ以外の未対応の変換は何もしない。
+//! - 処理: フェーズ 1・2 の規則を 1 → 2 の順に評価する（レスポンスのフェーズは読み飛ばす）。
+//!   `chain` の規則はすべて一致したときだけ一致とする。`deny` / `drop` は即座に遮断、
+//!   `allow` は以降の評価をやめて通す。それ以外は `setvar` の `tx.*anomaly_score*` の加算
+//!   （`%{tx.critical_anomaly_score}` 等は CRS の既定値）を異常スコアに足し、`anomaly_threshold`
+//!   以上で遮断する。`setvar` の無い `block` は重大度の点数（重大度も無ければしきい値）を足す。
+//! - CRS の paranoia level は `tag:'paranoia-level/N'` で判定し、`paranoia_level` を超える規則は
+//!   読まない（CRS の `TX` による切り替えの規則は読み飛ばされるため）。
+//!
+//! `max_body_bytes` を超えたボディは、`body_limit_action = "reject"`（既定）なら `blocking` で
+//! 413 を返し、`process_partial` なら先頭だけを検査する。`detection_only` では遮断せずに
+//! 記録だけする。異常スコアがしきい値に達したリクエストは自動 BAN（F-152）の `waf` の兆候として
+//! 報告する。一致はログに要約し、`audit_log` があればパスごとに 1 本の専用スレッドが JSON Lines で
+//! 書き出す。規則は設定の読み込み・リロード時に読み、読めなければ設定の読み込みを失敗させる
+//! （リロードでは直前の設定のまま動く）。
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex, Weak};
+
+use ftlog::{info, warn};
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use serde_json::{json, Value};
+
+use crate::auto_ban::Signal;
+use crate::config::{WafBodyLimitAction, WafConfig, WafMode};
+
+/// 規則の正規表現のコンパイル後の上限（CRS の大きな正規表現が収まるように）
+const REGEX_SIZE_LIMIT: usize = 32 << 20;
+
+/// `Include` の入れ子の上限
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// 1 つのリクエストから取り出す引数の上限（クエリ・ボディそれぞれ）
+const MAX_ARGS: usize = 1024;
+
+/// JSON ボディを引数へ展開する深さの上限
+const MAX_JSON_DEPTH: usize = 32;
+
+/// ログ・監査ログに残す一致した値の長さの上限（文字数）
+const MAX_LOGGED_DATA: usize = 128;
+
+/// 監査ログの書き出しスレッドへのキューの長さ（溢れた分は捨てる）
+const AUDIT_QUEUE: usize = 4096;
+
+/// CRS の既定の重大度ごとの異常スコア（`tx.critical_anomaly_score` 等）
+fn severity_score(severity: u8) -> u32 {
+    match severity {
+        0..=2 => 5,
+        3 => 4,
+        4 => 3,
+        5 => 2,
+        _ => 0,
+    }
+}
+
+fn parse_severity(value: &str) -> Option<u8> {
+    const NAMES: [&str; 8] = [
+        "EMERGENCY",
+        "ALERT",
+        "CRITICAL",
+        "ERROR",
+        "WARNING",
+        "NOTICE",
+        "INFO",
+        "DEBUG",
+    ];
+    if let Ok(n) = value.parse::<u8>() {
+        return (n < 8).then_some(n);
+    }
+    NAMES
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(value))
+        .map(|i| i as u8)
+}
+
+fn severity_name(severity: u8) -> &'static str {
+    match severity {
+        0 => "EMERGENCY",
+        1 => "ALERT",
+        2 => "CRITICAL",
+        3 => "ERROR",
+        4 => "WARNING",
+        5 => "NOTICE",
+        6 => "INFO",
+        _ => "DEBUG",
+    }
+}
+
+// ====================
+// リクエストの変数
+// ====================
+
+/// 規則が参照する変数
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Collection {
+    Args,
+    ArgsNames,
+    ArgsGet,
+    ArgsGetNames,
+    ArgsPost,
+    ArgsPostNames,
+    RequestHeaders,
+    RequestHeadersNames,
+    RequestCookies,
+    RequestCookiesNames,
+    RequestUri,
+    RequestFilename,
+    RequestBasename,
+    QueryString,
+    RequestMethod,
+    RequestBody,
+}
+
+impl Collection {
+    fn parse(name: &str) -> Option<Self> {
+        let c = match name.to_ascii_uppercase().as_str() {
+            "ARGS" => Self::Args,
+            "ARGS_NAMES" => Self::ArgsNames,
+            "ARGS_GET" => Self::ArgsGet,
+            "ARGS_GET_NAMES" => Self::ArgsGetNames,
+            "ARGS_POST" => Self::ArgsPost,
+            "ARGS_POST_NAMES" => Self::ArgsPostNames,
+            "REQUEST_HEADERS" => Self::RequestHeaders,
+            "REQUEST_HEADERS_NAMES" => Self::RequestHeadersNames,
+            "REQUEST_COOKIES" => Self::RequestCookies,
+            "REQUEST_COOKIES_NAMES" => Self::RequestCookiesNames,
+            "REQUEST_URI" | "REQUEST_URI_RAW" => Self::RequestUri,
+            "REQUEST_FILENAME" => Self::RequestFilename,
+            "REQUEST_BASENAME" => Self::RequestBasename,
+            "QUERY_STRING" => Self::QueryString,
+            "REQUEST_METHOD" => Self::RequestMethod,
+            "REQUEST_BODY" => Self::RequestBody,
+            _ => return None,
+        };
+        Some(c)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Args => "ARGS",
+            Self::ArgsNames => "ARGS_NAMES",
+            Self::ArgsGet => "ARGS_GET",
+            Self::ArgsGetNames => "ARGS_GET_NAMES",
+            Self::ArgsPost => "ARGS_POST",
+            Self::ArgsPostNames => "ARGS_POST_NAMES",
+            Self::RequestHeaders => "REQUEST_HEADERS",
+            Self::RequestHeadersNames => "REQUEST_HEADERS_NAMES",
+            Self::RequestCookies => "REQUEST_COOKIES",
+            Self::RequestCookiesNames => "REQUEST_COOKIES_NAMES",
+            Self::RequestUri => "REQUEST_URI",
+            Self::RequestFilename => "REQUEST_FILENAME",
+            Self::RequestBasename => "REQUEST_BASENAME",
+            Self::QueryString => "QUERY_STRING",
+            Self::RequestMethod => "REQUEST_METHOD",
+            Self::RequestBody => "REQUEST_BODY",
+        }
+    }
+}
+
+/// 変数の名前による絞り込み（`ARGS:id`・`ARGS:/^id_/`）
+#[derive(Debug)]
+enum Selector {
+    All,
+    Name(String),
+    Pattern(Regex),
+}
+
+impl Selector {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Name(n) => n.eq_ignore_ascii_case(name),
+            Self::Pattern(re) => re.is_match(name),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Target {
+    collection: Collection,
+    selector: Selector,
+}
+
+/// 検査するリクエストの内容
+pub struct WafRequest<'a> {
+    pub client_ip: &'a str,
+    pub method: &'a [u8],
+    pub host: &'a [u8],
+    /// クエリ文字列を含むリクエストのパス
+    pub uri: &'a [u8],
+    pub headers: &'a [(&'a [u8], &'a [u8])],
+    /// 受信済みのボディ（検査しないルート・経路では空）
+    pub body: &'a [u8],
+}
+
+/// リクエストから取り出した変数の値
+struct Inputs {
+    args_get: Vec<(String, String)>,
+    args_post: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    cookies: Vec<(String, String)>,
+    uri: String,
+    filename: String,
+    basename: String,
+    query: String,
+    method: String,
+    body: String,
+}
+
+impl Inputs {
+    fn new(req: &WafRequest<'_>, body: &[u8]) -> Self {
+        let uri = String::from_utf8_lossy(req.uri).into_owned();
+        let (filename, query) = match uri.split_once('?') {
+            Some((path, query)) => (path.to_string(), query.to_string()),
+            None => (uri.clone(), String::new()),
+        };
+        let basename = filename.rsplit('/').next().unwrap_or_default().to_string();
+        let mut headers = Vec::with_capacity(req.headers.len());
+        let mut cookies = Vec::new();
+        let mut content_type = String::new();
+        for (name, value) in req.headers {
+            if name.starts_with(b":") {
+                continue;
+            }
+            let name = String::from_utf8_lossy(name).into_owned();
+            let value = String::from_utf8_lossy(value).into_owned();
+            if name.eq_ignore_ascii_case("cookie") {
+                for pair in value.split(';') {
+                    let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+                    if cookies.len() < MAX_ARGS && !k.trim().is_empty() {
+                        cookies.push((k.trim().to_string(), v.trim().to_string()));
+                    }
+                }
+            } else if name.eq_ignore_ascii_case("content-type") {
+                content_type = value.to_ascii_lowercase();
+            }
+            headers.push((name, value));
+        }
+        let mut args_post = Vec::new();
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+        if mime == "application/x-www-form-urlencoded" {
+            parse_urlencoded(&String::from_utf8_lossy(body), &mut args_post);
+        } else if mime == "application/json" || mime.ends_with("+json") {
+            if let Ok(value) = serde_json::from_slice::<Value>(body) {
+                flatten_json("json", &value, 0, &mut args_post);
+            }
+        }
+        let mut args_get = Vec::new();
+        parse_urlencoded(&query, &mut args_get);
+        Self {
+            args_get,
+            args_post,
+            headers,
+            cookies,
+            uri,
+            filename,
+            basename,
+            query,
+            method: String::from_utf8_lossy(req.method).into_owned(),
+            body: String::from_utf8_lossy(body).into_owned(),
+        }
+    }
+
+    /// 変数の値を順に `f(名前, 値)` へ渡す（`f` が true を返したら打ち切って true）
+    fn visit(
+        &self,
+        target: &Target,
+        exclusions: &[Target],
+        f: &mut dyn FnMut(&str, &str) -> bool,
+    ) -> bool {
+        let (lists, names): (&[&[(String, String)]], bool) = match target.collection {
+            Collection::Args => (&[&self.args_get, &self.args_post], false),
+            Collection::ArgsNames => (&[&self.args_get, &self.args_post], true),
+            Collection::ArgsGet => (&[&self.args_get], false),
+            Collection::ArgsGetNames => (&[&self.args_get], true),
+            Collection::ArgsPost => (&[&self.args_post], false),
+            Collection::ArgsPostNames => (&[&self.args_post], true),
+            Collection::RequestHeaders => (&[&self.headers], false),
+            Collection::RequestHeadersNames => (&[&self.headers], true),
+            Collection::RequestCookies => (&[&self.cookies], false),
+            Collection::RequestCookiesNames => (&[&self.cookies], true),
+            Collection::RequestUri => return f("", &self.uri),
+            Collection::RequestFilename => return f("", &self.filename),
+            Collection::RequestBasename => return f("", &self.basename),
+            Collection::QueryString => return f("", &self.query),
+            Collection::RequestMethod => return f("", &self.method),
+            Collection::RequestBody => return !self.body.is_empty() && f("", &self.body),
+        };
+        lists
+            .iter()
+            .flat_map(|list| list.iter())
+            .any(|(name, value)| {
+                target.selector.matches(name)
+                    && !exclusions
+                        .iter()
+                        .any(|ex| ex.collection == target.collection && ex.selector.matches(name))
+                    && f(name, if names { name } else { value })
+            })
+    }
+}
+
+/// `application/x-www-form-urlencoded` の引数を取り出す（名前・値はデコード済み）
+fn parse_urlencoded(input: &str, out: &mut Vec<(String, String)>) {
+    for pair in input.split('&') {
+        if pair.is_empty() || out.len() >= MAX_ARGS {
+            continue;
+        }
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        out.push((
+            url_decode(name, true, false),
+            url_decode(value, true, false),
+        ));
+    }
+}
+
+/// JSON のボディを `json.a.b` / `json.list.0` の名前の引数へ展開する
+fn flatten_json(prefix: &str, value: &Value, depth: usize, out: &mut Vec<(String, String)>) {
+    if out.len() >= MAX_ARGS || depth > MAX_JSON_DEPTH {
+        return;
+    }
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map {
+                flatten_json(&format!("{}.{}", prefix, key), v, depth + 1, out);
+            }
+        }
+        Value::Array(list) => {
+            for (i, v) in list.iter().enumerate() {
+                flatten_json(&format!("{}.{}", prefix, i), v, depth + 1, out);
+            }
+        }
+        Value::String(s) => out.push((prefix.to_string(), s.clone())),
+        Value::Null => out.push((prefix.to_string(), String::new())),
+        other => out.push((prefix.to_string(), other.to_string())),
+    }
+}
+
+// ====================
+// 変換
+// ====================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Transform {
+    Lowercase,
+    Uppercase,
+    UrlDecode,
+    UrlDecodeUni,
+    HtmlEntityDecode,
+    JsDecode,
+    CmdLine,
+    CompressWhitespace,
+    RemoveWhitespace,
+    RemoveNulls,
+    ReplaceNulls,
+    RemoveComments,
+    ReplaceComments,
+    NormalizePath,
+    Trim,
+    TrimLeft,
+    TrimRight,
+}
+
+/// `t:` の解釈
+enum ParsedTransform {
+    /// `t:none`（それまでの変換を取り消す）
+    Reset,
+    Apply(Transform),
+    /// 未対応だが値の意味は変わらないため何もしない
+    Ignored,
+    /// 値の意味が変わる未対応の変換（規則を読み飛ばす）
+    Unsupported,
+}
+
+impl Transform {
+    fn parse(name: &str) -> ParsedTransform {
+        let t = match name.to_ascii_lowercase().as_str() {
+            "none" => return ParsedTransform::Reset,
+            "lowercase" => Self::Lowercase,
+            "uppercase" => Self::Uppercase,
+            "urldecode" => Self::UrlDecode,
+            "urldecodeuni" => Self::UrlDecodeUni,
+            "htmlentitydecode" => Self::HtmlEntityDecode,
+            "jsdecode" => Self::JsDecode,
+            "cmdline" => Self::CmdLine,
+            "compresswhitespace" => Self::CompressWhitespace,
+            "removewhitespace" => Self::RemoveWhitespace,
+            "removenulls" => Self::RemoveNulls,
+            "replacenulls" => Self::ReplaceNulls,
+            "removecomments" => Self::RemoveComments,
+            "replacecomments" => Self::ReplaceComments,
+            "normalizepath" | "normalisepath" => Self::NormalizePath,
+            "trim" => Self::Trim,
+            "trimleft" => Self::TrimLeft,
+            "trimright" => Self::TrimRight,
+            "length" | "md5" | "sha1" | "hexencode" | "base64encode" | "urlencode" => {
+                return ParsedTransform::Unsupported
+            }
+            _ => return ParsedTransform::Ignored,
+        };
+        ParsedTransform::Apply(t)
+    }
+
+    fn apply(self, input: &str) -> String {
+        match self {
+            Self::Lowercase => input.to_ascii_lowercase(),
+            Self::Uppercase => input.to_ascii_uppercase(),
+            Self::UrlDecode => url_decode(input, true, false),
+            Self::UrlDecodeUni => url_decode(input, true, true),
+            Self::HtmlEntityDecode => html_entity_decode(input),
+            Self::JsDecode => js_decode(input),
+            Self::CmdLine => cmd_line(input),
+            Self::CompressWhitespace => compress_whitespace(input),
+            Self::RemoveWhitespace => input.chars().filter(|c| !is_space(*c)).collect(),
+            Self::RemoveNulls => input.replace('\0', ""),
+            Self::ReplaceNulls => input.replace('\0', " "),
+            Self::RemoveComments => remove_comments(input, false),
+            Self::ReplaceComments => remove_comments(input, true),
+            Self::NormalizePath => normalize_path(input),
+            Self::Trim => input.trim_matches(is_space).to_string(),
+            Self::TrimLeft => input.trim_start_matches(is_space).to_string(),
+            Self::TrimRight => input.trim_end_matches(is_space).to_string(),
+        }
+    }
+}
+
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0b' | '\x0c' | '\u{a0}')
+}
+
+fn hex_value(b: u8) -> Option<u32> {
+    (b as char).to_digit(16)
+}
+
+/// `%XX`（`uni` なら `%uXXXX` も）をデコードする。不正な並びはそのまま残す
+fn url_decode(input: &str, plus_as_space: bool, uni: bool) -> String {
+    if !(input.contains('%') || plus_as_space && input.contains('+')) {
+        return input.to_string();
+    }
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if uni && i + 5 < bytes.len() && (bytes[i + 1] | 0x20) == b'u' => {
+                let code = bytes[i + 2..i + 6]
+                    .iter()
+                    .try_fold(0u32, |acc, &b| hex_value(b).map(|v| acc << 4 | v));
+                match code.and_then(char::from_u32) {
+                    Some(c) => {
+                        let mut buf = [0u8; 4];
+                        out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                        i += 6;
+                    }
+                    None => {
+                        out.push(b'%');
+                        i += 1;
+                    }
+                }
+            }
+            b'%' if i + 2 < bytes.len() => {
+                match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                    (Some(h), Some(l)) => {
+                        out.push((h << 4 | l) as u8);
+                        i += 3;
+                    }
+                    _ => {
+                        out.push(b'%');
+                        i += 1;
+                    }
+                }
+            }
+            b'+' if plus_as_space => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// `&lt;`・`&#60;`・`&#x3c;` 等の文字参照をデコードする（`;` は省略可）
+fn html_entity_decode(input: &str) -> String {
+    if !input.contains('&') {
+        return input.to_string();
+    }
+    const NAMED: [(&str, char); 6] = [
+        ("lt", '<'),
+        ("gt", '>'),
+        ("amp", '&'),
+        ("quot", '"'),
+        ("apos", '\''),
+        ("nbsp", '\u{a0}'),
+    ];
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(pos) = rest.find('&') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+        let decoded = if let Some(num) = rest.strip_prefix('#') {
+            let (digits, radix, skip) = match num.strip_prefix(['x', 'X']) {
+                Some(hex) => (hex, 16, 2),
+                None => (num, 10, 1),
+            };
+            let len = digits
+                .bytes()
+                .take_while(|b| (*b as char).is_digit(radix))
+                .count()
+                .min(8);
+            u32::from_str_radix(&digits[..len], radix)
+                .ok()
+                .and_then(char::from_u32)
+                .map(|c| (c, skip + len))
+        } else {
+            NAMED.iter().find_map(|(name, c)| {
+                let head = rest.get(..name.len())?;
+                head.eq_ignore_ascii_case(name).then_some((*c, name.len()))
+            })
+        };
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+                rest = rest.strip_prefix(';').unwrap_or(rest);
+            }
+            None => out.push('&'),
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// JavaScript のエスケープ（`\xHH`・`\uHHHH`・`\n` 等）をデコードする
+fn js_decode(input: &str) -> String {
+    if !input.contains('\\') {
+        return input.to_string();
+    }
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let Some(next) = chars.next() else {
+            out.push('\\');
+            break;
+        };
+        let digits = match next {
+            'x' => 2,
+            'u' => 4,
+            _ => 0,
+        };
+        if digits > 0 {
+            let hex: String = chars.clone().take(digits).collect();
+            let code = (hex.len() == digits)
+                .then(|| u32::from_str_radix(&hex, 16).ok())
+                .flatten()
+                .and_then(char::from_u32);
+            if let Some(code) = code {
+                out.push(code);
+                for _ in 0..digits {
+                    chars.next();
+                }
+            } else {
+                out.push(next);
+            }
+            continue;
+        }
+        out.push(match next {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'b' => '\x08',
+            'f' => '\x0c',
+            'v' => '\x0b',
+            '0' => '\0',
+            other => other,
+        });
+    }
+    out
+}
+
+/// コマンドラインの難読化を外す（ModSecurity の `cmdLine` と同じ手順）
+fn cmd_line(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut space = false;
+    for c in input.chars() {
+        match c {
+            '\\' | '"' | '\'' | '^' => {}
+            c if is_space(c) || c == ',' || c == ';' => space = true,
+            '/' | '(' => {
+                // スラッシュと開き括弧の前の空白は消す
+                space = false;
+                out.push(c);
+            }
+            c => {
+                if space && !out.is_empty() {
+                    out.push(' ');
+                }
+                space = false;
+                out.push(c.to_ascii_lowercase());
+            }
+        }
+    }
+    out
+}
+
+fn compress_whitespace(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut space = false;
+    for c in input.chars() {
+        if is_space(c) {
+            if !space {
+                out.push(' ');
+            }
+            space = true;
+        } else {
+            out.push(c);
+            space = false;
+        }
+    }
+    out
+}
+
+/// `/* */` と `<!-- -->`（`replace` でなければ `--`・`#` 以降も）のコメントを消す
+fn remove_comments(input: &str, replace: bool) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    loop {
+        let c_style = rest.find("/*");
+        let html = if replace { None } else { rest.find("<!--") };
+        let line = if replace {
+            None
+        } else {
+            [rest.find("--"), rest.find('#')]
+                .into_iter()
+                .flatten()
+                .min()
+        };
+        let next = [c_style, html, line].into_iter().flatten().min();
+        let Some(pos) = next else {
+            out.push_str(rest);
+            return out;
+        };
+        out.push_str(&rest[..pos]);
+        if Some(pos) == line && Some(pos) != c_style && Some(pos) != html {
+            return out;
+        }
+        let (close, open_len) = if Some(pos) == c_style {
+            ("*/", 2)
+        } else {
+            ("-->", 4)
+        };
+        if replace {
+            out.push(' ');
+        }
+        match rest[pos + open_len..].find(close) {
+            Some(end) => rest = &rest[pos + open_len + end + close.len()..],
+            None => return out,
+        }
+    }
+}
+
+/// `//`・`/./`・`/../` を畳む
+fn normalize_path(input: &str) -> String {
+    let absolute = input.starts_with('/');
+    let trailing = input.len() > 1 && (input.ends_with('/') || input.ends_with("/."));
+    let mut parts: Vec<&str> = Vec::new();
+    for seg in input.split('/') {
+        match seg {
+            "" | "." => {}
+            ".." => {
+                if parts.last().is_some_and(|p| *p != "..") {
+                    parts.pop();
+                } else if !absolute {
+                    parts.push("..");
+                }
+            }
+            seg => parts.push(seg),
+        }
+    }
+    let mut out = String::with_capacity(input.len());
+    if absolute {
+        out.push('/');
+    }
+    out.push_str(&parts.join("/"));
+    if trailing && !out.ends_with('/') {
+        out.push('/');
+    }
+    out
+}
+
+// ====================
+// 演算子
+// ====================
+
+/// `@detectSQLi` の簡易判定（引用符からの論理式・コメント、UNION SELECT、積み重ねた文、
+/// 時間差を生む関数等）
+static SQLI: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?is)(?:['"`]\s*\)?\s*(?:or|and|xor|\|\||&&)\b\s*\(?\s*['"`]?[\w\s]*['"`]?\s*(?:=|<>|!=|<|>|\blike\b|\bis\b|\bregexp\b)|['"`]\s*\)?\s*(?:or|and)\s+(?:true|false|not\b|\d+\b)|\b\d+\s*\)?\s+(?:or|and)\s+\(?\s*\d+\s*(?:=|<>|!=|<|>)\s*\d+|['"`]\s*\)?\s*(?:;|--|#|/\*)|\bunion\b(?:\s|/\*.*?\*/|\()+(?:all\b|distinct\b)?(?:\s|/\*.*?\*/|\()*select\b|;\s*(?:select|insert|update|delete|drop|create|alter|truncate|exec|execute|declare|shutdown)\b|\b(?:sleep|benchmark|pg_sleep|load_file|extractvalue|updatexml)\s*\(|\bwaitfor\s+delay\b|\binto\s+(?:out|dump)file\b|\binformation_schema\b)"#,
+    )
+    .expect("static SQLi pattern")
+});
+
+/// `@detectXSS` の簡易判定（script 要素、イベントハンドラー属性、`javascript:` 等）
+static XSS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?is)(?:<\s*/?\s*script\b|\b(?:java|vb)script\s*:|<[^>]*?\bon[a-z]{3,}\s*=|<\s*(?:iframe|frame|object|embed|applet|meta|base|svg|math|link|style)\b|\bsrcdoc\s*=|\bexpression\s*\(|\bdata\s*:\s*text/html)",
+    )
+    .expect("static XSS pattern")
+});
+
+#[derive(Debug)]
+enum Operator {
+    Rx(Regex),
+    /// `@pm` / `@pmFromFile`（大文字小文字を区別しない語句の一覧）
+    Pm(Regex),
+    Contains(String),
+    Streq(String),
+    BeginsWith(String),
+    EndsWith(String),
+    Within(String),
+    /// 許可するバイト（範囲外のバイトを含めば一致）
+    ValidateByteRange(Box<[bool; 256]>),
+    DetectSqli,
+    DetectXss,
+    UnconditionalMatch,
+    NoMatch,
+}
+
+impl Operator {
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            Self::Rx(re) | Self::Pm(re) => re.is_match(value),
+            Self::Contains(s) => value.contains(s.as_str()),
+            Self::Streq(s) => value == s,
+            Self::BeginsWith(s) => value.starts_with(s.as_str()),
+            Self::EndsWith(s) => value.ends_with(s.as_str()),
+            Self::Within(s) => s.contains(value),
+            Self::ValidateByteRange(allowed) => value.bytes().any(|b| !allowed[b as usize]),
+            Self::DetectSqli => SQLI.is_match(value),
+            Self::DetectXss => XSS.is_match(value),
+            Self::UnconditionalMatch => true,
+            Self::NoMatch => false,
+        }
+    }
+}
+
+fn compile_regex(pattern: &str, case_insensitive: bool) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+fn phrase_regex(phrases: &[String]) -> Result<Regex, String> {
+    let alternatives: Vec<String> = phrases.iter().map(|p| regex::escape(p)).collect();
+    compile_regex(&alternatives.join("|"), true)
+}
+
+fn parse_byte_ranges(arg: &str) -> Option<Box<[bool; 256]>> {
+    let mut allowed = Box::new([false; 256]);
+    for part in arg.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (lo, hi) = part.split_once('-').unwrap_or((part, part));
+        let (lo, hi) = (lo.trim().parse::<u8>().ok()?, hi.trim().parse::<u8>().ok()?);
+        if lo > hi {
+            return None;
+        }
+        allowed[lo as usize..=hi as usize].fill(true);
+    }
+    Some(allowed)
+}
+
+// ====================
+// 規則
+// ====================
+
+/// 一致したときの処理
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Disruption {
+    Pass,
+    Block,
+    Deny,
+    Allow,
+}
+
+/// `SecRule` 1 つ分の条件（`chain` の規則はこれを並べる）
+#[derive(Debug)]
+struct Link {
+    targets: Vec<Target>,
+    exclusions: Vec<Target>,
+    transforms: Vec<Transform>,
+    operator: Operator,
+    negated: bool,
+}
+
+impl Link {
+    /// 一致した変数の名前と（変換後の）値
+    fn find(&self, inputs: &Inputs) -> Option<(String, String)> {
+        let mut found = None;
+        for target in &self.targets {
+            let hit = inputs.visit(target, &self.exclusions, &mut |name, value| {
+                let mut value = Cow::Borrowed(value);
+                for t in &self.transforms {
+                    value = Cow::Owned(t.apply(&value));
+                }
+                if self.operator.matches(&value) == self.negated {
+                    return false;
+                }
+                let variable = if name.is_empty() {
+                    target.collection.name().to_string()
+                } else {
+                    format!("{}:{}", target.collection.name(), name)
+                };
+                found = Some((variable, value.chars().take(MAX_LOGGED_DATA).collect()));
+                true
+            });
+            if hit {
+                break;
+            }
+        }
+        found
+    }
+}
+
+#[derive(Debug)]
+struct Rule {
+    id: u64,
+    phase: u8,
+    links: Vec<Link>,
+    disruption: Disruption,
+    score: u32,
+    severity: Option<u8>,
+    msg: String,
+    log: bool,
+}
+
+/// 一致した規則
+#[derive(Debug)]
+struct RuleMatch {
+    id: u64,
+    msg: String,
+    severity: Option<u8>,
+    variable: String,
+    data: String,
+}
+
+/// 評価の結果
+#[derive(Debug, Default)]
+struct Verdict {
+    score: u32,
+    /// `deny` の規則に一致した・異常スコアがしきい値に達した
+    blocked: bool,
+    matches: Vec<RuleMatch>,
+}
+
+// ====================
+// 規則ファイルの読み込み
+// ====================
+
+/// 規則の `,` 区切りのアクション
+#[derive(Default)]
+struct Actions {
+    id: Option<u64>,
+    phase: Option<u8>,
+    transforms: Vec<Transform>,
+    disruption: Option<Disruption>,
+    severity: Option<u8>,
+    msg: Option<String>,
+    paranoia_level: Option<u8>,
+    chain: bool,
+    score: u32,
+    nolog: bool,
+    /// 読み飛ばす理由
+    unsupported: Option<String>,
+}
+
+impl Actions {
+    fn parse(input: &str, loader_stats: &mut LoadStats) -> Result<Self, String> {
+        let mut actions = Self::default();
+        for (name, value) in split_actions(input) {
+            match name.to_ascii_lowercase().as_str() {
+                "id" => {
+                    actions.id = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid rule id '{}'", value))?,
+                    )
+                }
+                "phase" => {
+                    actions.phase = match value.to_ascii_lowercase().as_str() {
+                        "1" => Some(1),
+                        "2" | "request" => Some(2),
+                        _ => {
+                            actions.unsupported = Some(format!("phase:{}", value));
+                            None
+                        }
+                    }
+                }
+                "t" => match Transform::parse(&value) {
+                    ParsedTransform::Reset => actions.transforms.clear(),
+                    ParsedTransform::Apply(t) => actions.transforms.push(t),
+                    ParsedTransform::Ignored => loader_stats.approximated += 1,
+                    ParsedTransform::Unsupported => {
+                        actions.unsupported = Some(format!("t:{}", value))
+                    }
+                },
+                "severity" => actions.severity = parse_severity(&value),
+                "msg" => actions.msg = Some(value),
+                "tag" => {
+                    if let Some(level) = value
+                        .to_ascii_lowercase()
+                        .strip_prefix("paranoia-level/")
+                        .and_then(|n| n.parse().ok())
+                    {
+                        actions.paranoia_level = Some(level);
+                    }
+                }
+                "chain" => actions.chain = true,
+                "deny" | "drop" => actions.disruption = Some(Disruption::Deny),
+                "block" => actions.disruption = Some(Disruption::Block),
+                "pass" => actions.disruption = Some(Disruption::Pass),
+                "allow" => actions.disruption = Some(Disruption::Allow),
+                "nolog" => actions.nolog = true,
+                "log" => actions.nolog = false,
+                "setvar" => actions.score += anomaly_increment(&value),
+                "ctl" | "skip" | "skipafter" | "exec" | "redirect" | "proxy" | "initcol"
+                | "setuid" | "setsid" | "pause" => {
+                    actions.unsupported = Some(name.to_string());
+                }
+                // 記録用のアクション（status・capture・logdata・ver 等）は評価に影響しない
+                _ => {}
+            }
+        }
+        Ok(actions)
+    }
+}
+
+/// `setvar` が異常スコアに足す点数（`tx.*anomaly_score*=+N` 以外は 0）
+fn anomaly_increment(setvar: &str) -> u32 {
+    let setvar = setvar.to_ascii_lowercase();
+    let Some((name, value)) = setvar.split_once('=') else {
+        return 0;
+    };
+    if !name.starts_with("tx.") || !name.contains("anomaly_score") || name.contains("outbound") {
+        return 0;
+    }
+    let Some(value) = value.strip_prefix('+') else {
+        return 0;
+    };
+    if let Ok(n) = value.parse() {
+        return n;
+    }
+    match value {
+        "%{tx.critical_anomaly_score}" => 5,
+        "%{tx.error_anomaly_score}" => 4,
+        "%{tx.warning_anomaly_score}" => 3,
+        "%{tx.notice_anomaly_score}" => 2,
+        _ => 0,
+    }
+}
+
+/// `id:1,msg:'a, b'` を (名前, 値) に分ける（`'` で囲った値の `,` では分けない）
+fn split_actions(input: &str) -> Vec<(String, String)> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if quoted => {
+                if let Some(next) = chars.next() {
+                    if next != '\'' {
+                        current.push('\\');
+                    }
+                    current.push(next);
+                }
+            }
+            '\'' => quoted = !quoted,
+            ',' if !quoted => parts.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    parts.push(current);
+    parts
+        .into_iter()
+        .filter_map(|part| {
+            let part = part.trim();
+            if part.is_empty() {
+                return None;
+            }
+            let (name, value) = part.split_once(':').unwrap_or((part, ""));
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// ディレクティブの引数に分ける（`"` で囲った引数の中は `\"` だけをエスケープとして扱う）
+fn split_args(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        let Some(&first) = chars.peek() else {
+            return Ok(args);
+        };
+        let mut arg = String::new();
+        if first == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    None => return Err("unterminated quote".to_string()),
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('"') => arg.push('"'),
+                        Some(c) => {
+                            arg.push('\\');
+                            arg.push(c);
+                        }
+                        None => return Err("unterminated quote".to_string()),
+                    },
+                    Some(c) => arg.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                arg.push(c);
+            }
+        }
+        args.push(arg);
+    }
+}
+
+/// `ARGS|!ARGS:/^x/|REQUEST_HEADERS:User-Agent` を `|` で分ける（`/.../` の中では分けない）
+fn split_variables(spec: &str) -> Vec<&str> {
+    let bytes = spec.as_bytes();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut in_regex = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if in_regex {
+            if b == b'\\' {
+                i += 2;
+                continue;
+            }
+            if b == b'/' {
+                in_regex = false;
+            }
+        } else if b == b'/' && i > 0 && matches!(bytes[i - 1], b':' | b'\'') {
+            in_regex = true;
+        } else if b == b'|' {
+            parts.push(&spec[start..i]);
+            start = i + 1;
+        }
+        i += 1;
+    }
+    parts.push(&spec[start..]);
+    parts
+}
+
+/// `SecRuleRemoveById` / `disabled_rule_ids` の ID（`942100`・`942100-942199`）
+pub(crate) fn parse_id_range(spec: &str) -> Option<(u64, u64)> {
+    let (lo, hi) = spec.split_once('-').unwrap_or((spec, spec));
+    let (lo, hi) = (lo.trim().parse().ok()?, hi.trim().parse().ok()?);
+    (lo <= hi).then_some((lo, hi))
+}
+
+/// 読み込みの集計
+#[derive(Debug, Default)]
+struct LoadStats {
+    /// 対応しない変数・演算子・アクションを含むため読み飛ばした規則
+    skipped: usize,
+    /// 正規表現がコンパイルできずに読み飛ばした規則
+    invalid: usize,
+    /// 読み飛ばしたディレクティブ（`SecAction` 等）
+    ignored: usize,
+    /// 何もしない変換として扱った未対応の変換
+    approximated: usize,
+}
+
+/// 読み込み中の `chain` の規則
+struct OpenChain {
+    rule: Option<Rule>,
+    skip_reason: Option<String>,
+}
+
+struct Loader {
+    /// 読み込み中のファイルのディレクトリ（`@pmFromFile` の相対パスの基準）
+    dir: PathBuf,
+    paranoia_level: u8,
+    threshold: u32,
+    removed: Vec<(u64, u64)>,
+    rules: Vec<Rule>,
+    chain: Option<OpenChain>,
+    stats: LoadStats,
+}
+
+impl Loader {
+    fn new(cfg: &WafConfig) -> Self {
+        Self {
+            dir: PathBuf::from("."),
+            paranoia_level: cfg.paranoia_level,
+            threshold: cfg.anomaly_threshold,
+            removed: cfg
+                .disabled_rule_ids
+                .iter()
+                .filter_map(|s| parse_id_range(s))
+                .collect(),
+            rules: Vec::new(),
+            chain: None,
+            stats: LoadStats::default(),
+        }
+    }
+
+    /// 設定の規則ファイルとインラインの規則を読む
+    fn load(mut self, cfg: &WafConfig) -> Result<(Vec<Rule>, LoadStats), String> {
+        for path in &cfg.rule_files {
+            for file in expand_glob(Path::new(path))? {
+                self.load_file(&file, 0)?;
+            }
+        }
+        if !cfg.rules.is_empty() {
+            self.load_source("rules", &cfg.rules, Path::new("."), 0)?;
+        }
+        if self.chain.is_some() {
+            return Err("the last rule has 'chain' but no chained rule follows".to_string());
+        }
+        let removed = self.removed;
+        let mut rules = self.rules;
+        rules.retain(|r| !removed.iter().any(|(lo, hi)| (*lo..=*hi).contains(&r.id)));
+        // ModSecurity と同じくフェーズ 1 の規則をすべて評価してからフェーズ 2 を評価する
+        rules.sort_by_key(|r| r.phase);
+        Ok((rules, self.stats))
+    }
+
+    // 理由付き allow: 規則ファイルは設定の読み込み・リロード時にのみ読む（データプレーン非経由）。
+    #[allow(clippy::disallowed_methods)]
+    fn load_file(&mut self, path: &Path, depth: usize) -> Result<(), String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        self.load_source(&path.display().to_string(), &source, dir, depth)
+    }
+
+    fn load_source(
+        &mut self,
+        name: &str,
+        source: &str,
+        dir: &Path,
+        depth: usize,
+    ) -> Result<(), String> {
+        let outer = std::mem::replace(&mut self.dir, dir.to_path_buf());
+        let result = self.load_lines(name, source, depth);
+        self.dir = outer;
+        result
+    }
+
+    fn load_lines(&mut self, name: &str, source: &str, depth: usize) -> Result<(), String> {
+        let mut logical = String::new();
+        let mut start_line = 0;
+        for (i, line) in source.lines().enumerate() {
+            let trimmed = line.trim();
+            if logical.is_empty() {
+                if trimmed.is_empty() || trimmed.starts_with('#') {
+                    continue;
+                }
+                start_line = i + 1;
+            }
+            // 行末の `\` は次の行へ続く
+            match trimmed.strip_suffix('\\') {
+                Some(head) => {
+                    logical.push_str(head);
+                    logical.push(' ');
+                }
+                None => {
+                    logical.push_str(trimmed);
+                    let directive = std::mem::take(&mut logical);
+                    self.directive(&directive, depth)
+                        .map_err(|e| format!("{}:{}: {}", name, start_line, e))?;
+                }
+            }
+        }
+        if !logical.is_empty() {
+            self.directive(&logical, depth)
+                .map_err(|e| format!("{}:{}: {}", name, start_line, e))?;
+        }
+        Ok(())
+    }
+
+    fn directive(&mut self, line: &str, depth: usize) -> Result<(), String> {
+        let args = split_args(line)?;
+        let Some((directive, args)) = args.split_first() else {
+            return Ok(());
+        };
+        match directive.to_ascii_lowercase().as_str() {
+            "secrule" => {
+                if args.len() < 2 || args.len() > 3 {
+                    return Err("SecRule needs variables, an operator and actions".to_string());
+                }
+                self.sec_rule(&args[0], &args[1], args.get(2).map_or("", |a| a.as_str()))
+            }
+            "secruleremovebyid" => {
+                for spec in args {
+                    let range = parse_id_range(spec)
+                        .ok_or_else(|| format!("invalid rule id '{}'", spec))?;
+                    self.removed.push(range);
+                }
+                Ok(())
+            }
+            "include" => {
+                if depth >= MAX_INCLUDE_DEPTH {
+                    return Err("Include is nested too deeply".to_string());
+                }
+                let path = args.first().ok_or("Include needs a path")?;
+                for file in expand_glob(&self.dir.join(path))? {
+                    self.load_file(&file, depth + 1)?;
+                }
+                Ok(())
+            }
+            _ => {
+                self.stats.ignored += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn sec_rule(&mut self, variables: &str, operator: &str, actions: &str) -> Result<(), String> {
+        let actions = Actions::parse(actions, &mut self.stats)?;
+        let link = match &actions.unsupported {
+            Some(reason) => Err(reason.clone()),
+            None => self.link(variables, operator, &actions),
+        };
+        let link = match link {
+            Err(reason) if reason.starts_with("invalid regex") => {
+                self.stats.invalid += 1;
+                Err(reason)
+            }
+            other => other,
+        };
+
+        match self.chain.take() {
+            // chain の続きの規則（ID・処理は先頭の規則のものを使う）
+            Some(mut open) => {
+                match (link, open.rule.as_mut()) {
+                    (Ok(link), Some(rule)) if open.skip_reason.is_none() => rule.links.push(link),
+                    (Err(reason), _) if open.skip_reason.is_none() => {
+                        open.skip_reason = Some(reason)
+                    }
+                    _ => {}
+                }
+                if actions.chain {
+                    self.chain = Some(open);
+                } else {
+                    self.finish(open);
+                }
+            }
+            None => {
+                let id = actions
+                    .id
+                    .ok_or_else(|| "SecRule without an id".to_string())?;
+                let disruption = actions.disruption.unwrap_or(Disruption::Pass);
+                let score = match (actions.score, disruption, actions.severity) {
+                    (0, Disruption::Block, Some(severity)) => severity_score(severity),
+                    (0, Disruption::Block, None) => self.threshold,
+                    (score, _, _) => score,
+                };
+                let (rule, skip_reason) = match link {
+                    Ok(link) => (
+                        Some(Rule {
+                            id,
+                            phase: actions.phase.unwrap_or(2),
+                            links: vec![link],
+                            disruption,
+                            score,
+                            severity: actions.severity,
+                            msg: actions.msg.clone().unwrap_or_default(),
+                            log: !actions.nolog,
+                        }),
+                        None,
+                    ),
+                    Err(reason) => (None, Some(reason)),
+                };
+                let skip_reason = skip_reason.or_else(|| {
+                    actions
+                        .paranoia_level
+                        .filter(|level| *level > self.paranoia_level)
+                        .map(|level| format!("paranoia-level/{}", level))
+                });
+                let open = OpenChain { rule, skip_reason };
+                if actions.chain {
+                    self.chain = Some(open);
+                } else {
+                    self.finish(open);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, open: OpenChain) {
+        match (open.rule, open.skip_reason) {
+            (Some(rule), None) => self.rules.push(rule),
+            // paranoia level で除いた規則と正規表現の誤りは別に数える
+            (_, Some(reason))
+                if reason.starts_with("paranoia-level/") || reason.starts_with("invalid regex") => {
+            }
+            _ => self.stats.skipped += 1,
+        }
+    }
+
+    /// 変数・演算子・変換から条件を作る（対応しないものを含めば読み飛ばす理由）
+    fn link(&self, variables: &str, operator: &str, actions: &Actions) -> Result<Link, String> {
+        let mut targets = Vec::new();
+        let mut exclusions = Vec::new();
+        for part in split_variables(variables) {
+            let part = part.trim();
+            if part.starts_with('&') {
+                return Err(format!("variable {}", part));
+            }
+            let (excluded, part) = match part.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, part),
+            };
+            let (name, selector) = part.split_once(':').unwrap_or((part, ""));
+            let Some(collection) = Collection::parse(name) else {
+                continue;
+            };
+            let selector = selector.trim_matches('\'');
+            let selector = if selector.is_empty() {
+                Selector::All
+            } else if selector.len() >= 2 && selector.starts_with('/') && selector.ends_with('/') {
+                Selector::Pattern(
+                    compile_regex(&selector[1..selector.len() - 1], true)
+                        .map_err(|e| format!("invalid regex: {}", e))?,
+                )
+            } else {
+                Selector::Name(selector.to_string())
+            };
+            let target = Target {
+                collection,
+                selector,
+            };
+            if excluded {
+                exclusions.push(target);
+            } else {
+                targets.push(target);
+            }
+        }
+        if targets.is_empty() {
+            return Err(format!("variables {}", variables));
+        }
+
+        let (negated, operator) = match operator.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, operator),
+        };
+        let (name, arg) = match operator.strip_prefix('@') {
+            Some(rest) => rest.split_once(' ').unwrap_or((rest, "")),
+            None => ("rx", operator),
+        };
+        if arg.contains("%{") {
+            return Err(format!("macro in @{}", name));
+        }
+        let regex = |pattern: &str, ci: bool| {
+            compile_regex(pattern, ci).map_err(|e| format!("invalid regex: {}", e))
+        };
+        let operator = match name.to_ascii_lowercase().as_str() {
+            "rx" => Operator::Rx(regex(arg, false)?),
+            "pm" => {
+                let phrases: Vec<String> = arg.split_whitespace().map(String::from).collect();
+                if phrases.is_empty() {
+                    return Err("@pm without phrases".to_string());
+                }
+                Operator::Pm(phrase_regex(&phrases).map_err(|e| format!("invalid regex: {}", e))?)
+            }
+            "pmfromfile" | "pmf" => {
+                let phrases = self.phrase_files(arg)?;
+                if phrases.is_empty() {
+                    return Err(format!("@{} without phrases", name));
+                }
+                Operator::Pm(phrase_regex(&phrases).map_err(|e| format!("invalid regex: {}", e))?)
+            }
+            "contains" => Operator::Contains(arg.to_string()),
+            "streq" => Operator::Streq(arg.to_string()),
+            "beginswith" => Operator::BeginsWith(arg.to_string()),
+            "endswith" => Operator::EndsWith(arg.to_string()),
+            "within" => Operator::Within(arg.to_string()),
+            "validatebyterange" => Operator::ValidateByteRange(
+                parse_byte_ranges(arg).ok_or_else(|| format!("byte range '{}'", arg))?,
+            ),
+            "detectsqli" => Operator::DetectSqli,
+            "detectxss" => Operator::DetectXss,
+            "unconditionalmatch" => Operator::UnconditionalMatch,
+            "nomatch" => Operator::NoMatch,
+            other => return Err(format!("operator @{}", other)),
+        };
+        Ok(Link {
+            targets,
+            exclusions,
+            transforms: actions.transforms.clone(),
+            operator,
+            negated,
+        })
+    }
+
+    /// `@pmFromFile` の語句ファイル（空白区切りで複数可）を読む
+    // 理由付き allow: 規則ファイルは設定の読み込み・リロード時にのみ読む（データプレーン非経由）。
+    #[allow(clippy::disallowed_methods)]
+    fn phrase_files(&self, arg: &str) -> Result<Vec<String>, String> {
+        let mut phrases = Vec::new();
+        for file in arg.split_whitespace() {
+            let path = self.dir.join(file);
+            let body = std::fs::read_to_string(&path)
+                .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+            phrases.extend(
+                body.lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                    .map(String::from),
+            );
+        }
+        Ok(phrases)
+    }
+}
+
+/// ファイル名に `*` を 1 つ含むパスを、同じディレクトリの一致するファイル（名前順）に展開する
+fn expand_glob(path: &Path) -> Result<Vec<PathBuf>, String> {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return Ok(vec![path.to_path_buf()]);
+    };
+    let Some((prefix, suffix)) = name.split_once('*') else {
+        return Ok(vec![path.to_path_buf()]);
+    };
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty());
+    let dir = dir.unwrap_or(Path::new("."));
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("failed to read {}: {}", dir.display(), e))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .filter(|entry| {
+            entry.file_name().to_str().is_some_and(|n| {
+                n.len() >= prefix.len() + suffix.len()
+                    && n.starts_with(prefix)
+                    && n.ends_with(suffix)
+            })
+        })
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// 設定の検証: 規則を読み込み、使える規則の数を返す
+pub fn check_config(cfg: &WafConfig) -> Result<usize, String> {
+    let (rules, _) = Loader::new(cfg).load(cfg)?;
+    if rules.is_empty() {
+        return Err("no supported rules were loaded".to_string());
+    }
+    Ok(rules.len())
+}
+
+// ====================
+// ルートの WAF
+// ====================
+
+/// [`Waf::inspect`] の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WafDecision {
+    /// 通す（`detection_only` で記録だけした場合も含む）
+    Allow,
+    /// 遮断する（403）
+    Block,
+    /// ボディが `max_body_bytes` を超えたので拒否する（413）
+    BodyTooLarge,
+}
+
+/// 構築済みのルートの WAF
+pub struct Waf {
+    /// ログ・監査ログのルート名（`route[N]`）
+    route: String,
+    mode: WafMode,
+    threshold: u32,
+    inspect_body: bool,
+    max_body_bytes: usize,
+    body_limit_action: WafBodyLimitAction,
+    rules: Vec<Rule>,
+    audit: Option<Arc<AuditLog>>,
+}
+
+impl fmt::Debug for Waf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Waf")
+            .field("route", &self.route)
+            .field("mode", &self.mode)
+            .field("rules", &self.rules.len())
+            .finish()
+    }
+}
+
+impl Waf {
+    /// 設定から構築する（規則ファイルはここで読む）
+    ///
+    /// 検証後にファイルが読めなくなった・使える規則が無くなった場合はエラー（すべて通す WAF には
+    /// しない）。
+    pub fn new(route: String, cfg: &WafConfig) -> io::Result<Self> {
+        let (rules, stats) = Loader::new(cfg)
+            .load(cfg)
+            .and_then(|(rules, stats)| {
+                if rules.is_empty() {
+                    Err("no supported rules were loaded".to_string())
+                } else {
+                    Ok((rules, stats))
+                }
+            })
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: WAF rules unavailable: {}", route, e),
+                )
+            })?;
+        info!(
+            "[waf] {}: loaded {} rules ({} unsupported and {} invalid skipped, {} directives ignored, {} transformations approximated)",
+            route,
+            rules.len(),
+            stats.skipped,
+            stats.invalid,
+            stats.ignored,
+            stats.approximated
+        );
+        Ok(Self {
+            audit: cfg.audit_log.as_deref().and_then(AuditLog::open),
+            route,
+            mode: cfg.mode,
+            threshold: cfg.anomaly_threshold,
+            inspect_body: cfg.inspect_body,
+            max_body_bytes: cfg.max_body_bytes,
+            body_limit_action: cfg.body_limit_action,
+            rules,
+        })
+    }
+
+    /// ボディを検査するか（ボディを受信してから検査する経路へ回す）
+    pub(crate) fn inspects_body(&self) -> bool {
+        self.inspect_body
+    }
+
+    /// 検査するボディの上限（バイト）
+    pub(crate) fn max_body_bytes(&self) -> usize {
+        self.max_body_bytes
+    }
+
+    /// リクエストを検査する
+    ///
+    /// `req.body` は `max_body_bytes` を超えたかを判定できるよう、上限より 1 バイト以上多く
+    /// 渡す。一致はログ・監査ログ・`veil_waf_*` のメトリクスに記録し、しきい値に達した
+    /// リクエストは自動 BAN の `waf` の兆候として報告する。
+    pub fn inspect(&self, req: &WafRequest<'_>) -> WafDecision {
+        let body = if self.inspect_body {
+            &req.body[..req.body.len().min(self.max_body_bytes)]
+        } else {
+            b""
+        };
+        if self.inspect_body
+            && req.body.len() > self.max_body_bytes
+            && self.body_limit_action == WafBodyLimitAction::Reject
+        {
+            let rejected = self.mode == WafMode::Blocking;
+            warn!(
+                "[waf] {}: request body from {} {} {} exceeds {} bytes ({})",
+                self.route,
+                req.client_ip,
+                String::from_utf8_lossy(req.method),
+                String::from_utf8_lossy(req.uri),
+                self.max_body_bytes,
+                if rejected {
+                    "rejected"
+                } else {
+                    "inspecting the first bytes"
+                }
+            );
+            if rejected {
+                crate::metrics::record_waf_request("body_too_large");
+                return WafDecision::BodyTooLarge;
+            }
+        }
+        let verdict = self.evaluate(&Inputs::new(req, body));
+        if verdict.matches.is_empty() && !verdict.blocked {
+            return WafDecision::Allow;
+        }
+        let reject = verdict.blocked && self.mode == WafMode::Blocking;
+        let outcome = match (reject, verdict.blocked) {
+            (true, _) => "blocked",
+            (false, true) => "detected",
+            (false, false) => "matched",
+        };
+        crate::metrics::record_waf_request(outcome);
+        for m in &verdict.matches {
+            crate::metrics::record_waf_rule_match(m.id);
+        }
+        if verdict.blocked {
+            crate::auto_ban::report_str(req.client_ip, Signal::Waf);
+            let ids: Vec<String> = verdict.matches.iter().map(|m| m.id.to_string()).collect();
+            warn!(
+                "[waf] {}: {} request from {} {} {} (score {}, rules {})",
+                self.route,
+                outcome,
+                req.client_ip,
+                String::from_utf8_lossy(req.method),
+                String::from_utf8_lossy(req.uri),
+                verdict.score,
+                ids.join(",")
+            );
+        }
+        if let Some(audit) = &self.audit {
+            // 溢れた分は捨てる（書き出しが追いつかない間もリクエストを止めない）
+            let _ = audit.tx.try_send(self.audit_record(req, outcome, &verdict));
+        }
+        if reject {
+            WafDecision::Block
+        } else {
+            WafDecision::Allow
+        }
+    }
+
+    fn evaluate(&self, inputs: &Inputs) -> Verdict {
+        let mut verdict = Verdict::default();
+        for rule in &self.rules {
+            let mut first = None;
+            let mut matched = true;
+            for link in &rule.links {
+                match link.find(inputs) {
+                    Some(hit) => {
+                        first.get_or_insert(hit);
+                    }
+                    None => {
+                        matched = false;
+                        break;
+                    }
+                }
+            }
+            let Some((variable, data)) = first.filter(|_| matched) else {
+                continue;
+            };
+            if rule.log {
+                verdict.matches.push(RuleMatch {
+                    id: rule.id,
+                    msg: rule.msg.clone(),
+                    severity: rule.severity,
+                    variable,
+                    data,
+                });
+            }
+            match rule.disruption {
+                Disruption::Allow => {
+                    verdict.blocked = false;
+                    return verdict;
+                }
+                Disruption::Deny => {
+                    verdict.score += rule.score;
+                    verdict.blocked = true;
+                    return verdict;
+                }
+                Disruption::Block | Disruption::Pass => verdict.score += rule.score,
+            }
+        }
+        verdict.blocked = verdict.score >= self.threshold;
+        verdict
+    }
+
+    fn audit_record(&self, req: &WafRequest<'_>, outcome: &str, verdict: &Verdict) -> Vec<u8> {
+        let timestamp = time::OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_default();
+        let matches: Vec<Value> = verdict
+            .matches
+            .iter()
+            .map(|m| {
+                json!({
+                    "id": m.id,
+                    "msg": m.msg,
+                    "severity": m.severity.map(severity_name),
+                    "variable": m.variable,
+                    "data": m.data,
+                })
+            })
+            .collect();
+        let record = json!({
+            "timestamp": timestamp,
+            "route": self.route,
+            "client_ip": req.client_ip,
+            "method": String::from_utf8_lossy(req.method),
+            "host": String::from_utf8_lossy(req.host),
+            "uri": String::from_utf8_lossy(req.uri),
+            "mode": match self.mode {
+                WafMode::Blocking => "blocking",
+                WafMode::DetectionOnly => "detection_only",
+            },
+            "outcome": outcome,
+            "anomaly_score": verdict.score,
+            "matches": matches,
+        });
+        let mut line = serde_json::to_vec(&record).unwrap_or_default();
+        line.push(b'\n');
+        line
+    }
+}
+
+/// 監査ログ（パスごとに 1 つ、使われなくなれば破棄）
+static AUDIT_LOGS: Lazy<Mutex<HashMap<String, Weak<AuditLog>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 監査ログの書き出し先（同じパスのルート・リロード前後の WAF で共有する）
+struct AuditLog {
+    tx: SyncSender<Vec<u8>>,
+}
+
+impl AuditLog {
+    /// 監査ログを開く（既に同じパスの書き出しスレッドがあれば共有する）
+    ///
+    /// 最後の WAF が破棄されて送信側が無くなればスレッドは終わる。開けなければ警告して
+    /// 監査ログなしで動く（次の設定の読み込みで開き直す）。
+    // 理由付き allow: 設定の読み込み・リロード時にのみ呼ばれる（データプレーン非経由）。
+    #[allow(clippy::disallowed_methods)]
+    fn open(path: &str) -> Option<Arc<Self>> {
+        let mut logs = AUDIT_LOGS.lock().unwrap();
+        if let Some(log) = logs.get(path).and_then(Weak::upgrade) {
+            return Some(log);
+        }
+        let mut file = match std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+        {
+            Ok(file) => file,
+            Err(e) => {
+                warn!("[waf] Failed to open audit log {}: {}", path, e);
+                return None;
+            }
+        };
+        let (tx, rx) = mpsc::sync_channel::<Vec<u8>>(AUDIT_QUEUE);
+        let name = path.to_string();
+        let spawned = std::thread::Builder::new()
+            .name("veil-waf-audit".to_string())
+            .spawn(move || {
+                // 1 行ずつ書く（同じファイルへ書く他のルートの行と混ざらないように）
+                while let Ok(line) = rx.recv() {
+                    if let Err(e) = file.write_all(&line) {
+                        warn!("[waf] Failed to write audit log {}: {}", name, e);
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            warn!("Failed to spawn WAF audit log thread: {}", e);
+            return None;
+        }
+        let log = Arc::new(Self { tx });
+        logs.retain(|_, l| l.strong_count() > 0);
+        logs.insert(path.to_string(), Arc::downgrade(&log));
+        Some(log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 理由付き allow: テストコードは同期 I/O を使用してよい（データプレーン非経由）。
+    #![allow(clippy::disallowed_methods)]
+    use super::*;
+
+    fn config(extra: &str, rules: &str) -> WafConfig {
+        let mut cfg: WafConfig = toml::from_str(extra).unwrap();
+        cfg.rules = rules.to_string();
+        cfg
+    }
+
+    fn waf(extra: &str, rules: &str) -> Waf {
+        Waf::new("route[0]".to_string(), &config(extra, rules)).unwrap()
+    }
+
+    fn get<'a>(uri: &'a str, headers: &'a [(&'a [u8], &'a [u8])]) -> WafRequest<'a> {
+        WafRequest {
+            client_ip: "192.0.2.1",
+            method: b"GET",
+            host: b"example.com",
+            uri: uri.as_bytes(),
+            headers,
+            body: b"",
+        }
+    }
+
+    fn verdict(waf: &Waf, req: &WafRequest<'_>) -> Verdict {
+        waf.evaluate(&Inputs::new(req, req.body))
+    }
+
+    const CRS_SQLI: &str = r#"
+# CRS 形式（行の継続・タグ・setvar）
+SecRule REQUEST_COOKIES|!REQUEST_COOKIES:/__utm/|ARGS_NAMES|ARGS|XML:/* "@detectSQLi" \
+    "id:942100,\
+    phase:2,\
+    block,\
+    capture,\
+    t:none,t:utf8toUnicode,t:urlDecodeUni,t:removeNulls,\
+    msg:'SQL Injection Attack Detected via libinjection',\
+    logdata:'Matched Data: %{TX.0} found within %{MATCHED_VAR_NAME}: %{MATCHED_VAR}',\
+    tag:'attack-sqli',\
+    tag:'paranoia-level/1',\
+    severity:'CRITICAL',\
+    setvar:'tx.sql_injection_score=+%{tx.critical_anomaly_score}',\
+    setvar:'tx.inbound_anomaly_score_pl1=+%{tx.critical_anomaly_score}'"
+"#;
+
+    #[test]
+    fn crs_style_rules_add_anomaly_scores() {
+        let waf = waf("", CRS_SQLI);
+        assert_eq!(waf.rules.len(), 1);
+        let rule = &waf.rules[0];
+        assert_eq!((rule.id, rule.phase, rule.score), (942100, 2, 5));
+        assert_eq!(rule.msg, "SQL Injection Attack Detected via libinjection");
+        assert_eq!(rule.links[0].targets.len(), 3);
+        assert_eq!(rule.links[0].exclusions.len(), 1);
+
+        let v = verdict(&waf, &get("/search?q=1%27%20OR%20%271%27%3D%271", &[]));
+        assert!(v.blocked);
+        assert_eq!(v.score, 5);
+        assert_eq!(v.matches[0].variable, "ARGS:q");
+        assert_eq!(v.matches[0].data, "1' OR '1'='1");
+        assert_eq!(
+            waf.inspect(&get("/search?q=1%27%20OR%20%271%27%3D%271", &[])),
+            WafDecision::Block
+        );
+
+        assert_eq!(
+            waf.inspect(&get("/search?q=rock%20%27n%27%20roll", &[])),
+            WafDecision::Allow
+        );
+        // 除外した Cookie は検査しない
+        let cookie: &[(&[u8], &[u8])] = &[(b"cookie", b"__utma=' or 1=1--; sid=abc")];
+        assert!(!verdict(&waf, &get("/", cookie)).blocked);
+        let cookie: &[(&[u8], &[u8])] = &[(b"cookie", b"sid=' or 1=1--")];
+        assert_eq!(
+            verdict(&waf, &get("/", cookie)).matches[0].variable,
+            "REQUEST_COOKIES:sid"
+        );
+    }
+
+    #[test]
+    fn scores_accumulate_up_to_the_threshold() {
+        let rules = r#"
+SecRule REQUEST_HEADERS:User-Agent "@pm sqlmap nikto" "id:1,phase:1,block,severity:WARNING"
+SecRule ARGS "@contains ../" "id:2,phase:2,pass,setvar:tx.anomaly_score_pl1=+2"
+SecRule REQUEST_URI "@rx ^/internal" "id:3,phase:1,pass,msg:'internal'"
+"#;
+        let waf = waf("anomaly_threshold = 5", rules);
+        let ua: &[(&[u8], &[u8])] = &[(b"User-Agent", b"Mozilla/5.0 SQLMap/1.7")];
+        let v = verdict(&waf, &get("/internal?f=../x", ua));
+        assert_eq!(v.score, 5);
+        assert!(v.blocked);
+        assert_eq!(
+            v.matches.iter().map(|m| m.id).collect::<Vec<_>>(),
+            vec![1, 3, 2]
+        );
+        let v = verdict(&waf, &get("/internal?f=../x", &[]));
+        assert_eq!((v.score, v.blocked, v.matches.len()), (2, false, 2));
+        // setvar も重大度も無い block はしきい値の点数
+        let waf = self::waf("", r#"SecRule ARGS:cmd "@streq reboot" "id:4,block""#);
+        assert!(verdict(&waf, &get("/?cmd=reboot", &[])).blocked);
+        assert!(!verdict(&waf, &get("/?other=reboot", &[])).blocked);
+    }
+
+    #[test]
+    fn chains_deny_allow_and_detection_only() {
+        let rules = r#"
+SecRule REQUEST_FILENAME "@beginsWith /health" "id:10,phase:1,allow,nolog"
+SecRule REQUEST_METHOD "@streq POST" "id:11,phase:2,deny,chain,msg:'shell upload'"
+    SecRule ARGS_POST_NAMES "@rx ^cmd$" "chain"
+        SecRule ARGS_POST:cmd "!@rx ^(?:ls|pwd)$" "t:lowercase"
+SecRule ARGS "@detectXSS" "id:12,phase:2,deny,t:htmlEntityDecode"
+"#;
+        let waf = waf("", rules);
+        assert_eq!(waf.rules.len(), 3);
+        let form: &[(&[u8], &[u8])] = &[(b"content-type", b"application/x-www-form-urlencoded")];
+        let post = |uri: &'static str, body: &'static [u8]| WafRequest {
+            method: b"POST",
+            body,
+            ..get(uri, form)
+        };
+        assert!(verdict(&waf, &post("/run", b"cmd=rm+-rf+%2F")).blocked);
+        assert!(!verdict(&waf, &post("/run", b"cmd=LS")).blocked);
+        assert!(!verdict(&waf, &get("/run?cmd=rm", &[])).blocked);
+        // allow は以降の規則を評価しない
+        assert!(!verdict(&waf, &get("/health?x=%3Cscript%3E", &[])).blocked);
+        let v = verdict(&waf, &get("/?x=%26lt%3Bscript%26gt%3B", &[]));
+        assert!(v.blocked);
+        assert_eq!(v.matches[0].id, 12);
+
+        let detect = self::waf(r#"mode = "detection_only""#, rules);
+        assert_eq!(detect.inspect(&post("/run", b"cmd=id")), WafDecision::Allow);
+        assert_eq!(waf.inspect(&post("/run", b"cmd=id")), WafDecision::Block);
+    }
+
+    #[test]
+    fn request_bodies_become_arguments() {
+        let rules = r#"SecRule ARGS_POST "@rx (?i)drop\s+table" "id:20,deny""#;
+        let waf = waf("", rules);
+        let json: &[(&[u8], &[u8])] = &[(b"content-type", b"application/json; charset=utf-8")];
+        let req = WafRequest {
+            method: b"POST",
+            body: br#"{"user": {"notes": ["ok", "x; DROP TABLE users"]}}"#,
+            ..get("/api", json)
+        };
+        let v = verdict(&waf, &req);
+        assert!(v.blocked);
+        assert_eq!(v.matches[0].variable, "ARGS_POST:json.user.notes.1");
+        assert_eq!(waf.inspect(&req), WafDecision::Block);
+
+        // inspect_body = false は検査しない
+        let off = self::waf("inspect_body = false", rules);
+        assert_eq!(off.inspect(&req), WafDecision::Allow);
+    }
+
+    #[test]
+    fn oversized_bodies_are_rejected_unless_partial() {
+        let rules = r#"SecRule ARGS_POST "@rx (?i)drop\s+table" "id:20,deny""#;
+        let form: &[(&[u8], &[u8])] = &[(b"content-type", b"application/x-www-form-urlencoded")];
+        let req = |body: &'static [u8]| WafRequest {
+            method: b"POST",
+            body,
+            ..get("/api", form)
+        };
+        let short = waf("max_body_bytes = 16", rules);
+        assert_eq!(short.inspect(&req(b"a=1234567890abcd")), WafDecision::Allow);
+        assert_eq!(
+            short.inspect(&req(b"a=1234567890abcde")),
+            WafDecision::BodyTooLarge
+        );
+
+        // process_partial・detection_only は先頭だけ検査する
+        let partial = waf(
+            r#"
+            max_body_bytes = 16
+            body_limit_action = "process_partial"
+            "#,
+            rules,
+        );
+        assert_eq!(
+            partial.inspect(&req(b"a=drop table&b=1234567890")),
+            WafDecision::Block
+        );
+        assert_eq!(
+            partial.inspect(&req(b"b=1234567890&a=drop table")),
+            WafDecision::Allow
+        );
+        let detect = waf(
+            r#"
+            max_body_bytes = 16
+            mode = "detection_only"
+            "#,
+            rules,
+        );
+        assert_eq!(
+            detect.inspect(&req(b"a=1234567890abcde")),
+            WafDecision::Allow
+        );
+    }
+
+    #[test]
+    fn blocked_requests_are_reported_to_auto_ban() {
+        let auto_ban: crate::config::AutoBanConfig = toml::from_str(
+            r#"
+            exempt_ips = ["192.0.2.0/24"]
+            [[rules]]
+            name = "waf"
+            signals = ["waf"]
+            threshold = 2
+            window_secs = 60
+            "#,
+        )
+        .unwrap();
+        crate::auto_ban::configure(Some(&auto_ban));
+        let detect = waf(
+            r#"mode = "detection_only""#,
+            r#"SecRule ARGS "@detectSQLi" "id:1,deny""#,
+        );
+        let attacker: std::net::IpAddr = "198.51.100.154".parse().unwrap();
+        let attack = WafRequest {
+            client_ip: "198.51.100.154",
+            ..get("/?q=1%27%20OR%20%271%27%3D%271", &[])
+        };
+        let benign = WafRequest {
+            client_ip: "198.51.100.155",
+            ..get("/?q=hello", &[])
+        };
+        for _ in 0..3 {
+            assert_eq!(detect.inspect(&benign), WafDecision::Allow);
+        }
+        assert_eq!(detect.inspect(&attack), WafDecision::Allow);
+        assert!(!crate::auto_ban::is_banned(attacker));
+        // detection_only でもしきい値に達したリクエストは数える
+        assert_eq!(detect.inspect(&attack), WafDecision::Allow);
+        let banned = crate::auto_ban::is_banned(attacker);
+        let benign_banned = crate::auto_ban::is_banned("198.51.100.155".parse().unwrap());
+        crate::auto_ban::configure(None);
+        assert!(banned);
+        assert!(!benign_banned);
+    }
+
+    #[test]
+    fn unsupported_rules_are_skipped() {
+        let rules = r#"
+SecRuleEngine On
+SecAction "id:900000,phase:1,pass,nolog,setvar:tx.blocking_paranoia_level=1"
+SecRule TX:DETECTION_PARANOIA_LEVEL "@lt 2" "id:942013,phase:1,pass,nolog,skipAfter:END"
+SecRule &ARGS "@gt 255" "id:920380,phase:2,block"
+SecRule ARGS "@gt 1" "id:30,phase:2,block"
+SecRule ARGS "@rx a" "id:31,phase:2,block,ctl:ruleRemoveById=942100"
+SecRule RESPONSE_BODY "@rx secret" "id:32,phase:4,block"
+SecRule ARGS "@rx a" "id:33,phase:2,block,t:length"
+SecRule ARGS "@rx (?<=a)b" "id:34,phase:2,block"
+SecRule ARGS "@rx ok" "id:35,phase:2,block,tag:'paranoia-level/2'"
+SecRule ARGS "@rx ok" "id:36,phase:2,block,chain"
+    SecRule TX:foo "@eq 1" ""
+SecMarker END
+SecRule ARGS "@rx ok" "id:37,phase:2,block,t:cssDecode"
+SecRule ARGS "@rx ok" "id:38,phase:2,block"
+SecRule ARGS "@rx ok" "id:39,phase:2,block"
+SecRule ARGS "@rx ok" "id:40,phase:2,block"
+SecRuleRemoveById 39
+"#;
+        let cfg = config(r#"disabled_rule_ids = ["40-41"]"#, rules);
+        let (loaded, stats) = Loader::new(&cfg).load(&cfg).unwrap();
+        assert_eq!(
+            loaded.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![37, 38]
+        );
+        assert_eq!(stats.skipped, 7);
+        assert_eq!(stats.invalid, 1);
+        assert_eq!(stats.ignored, 3);
+        assert_eq!(stats.approximated, 1);
+
+        let level2 = config("paranoia_level = 2", rules);
+        assert_eq!(check_config(&level2).unwrap(), 4);
+    }
+
+    #[test]
+    fn rule_files_are_included_with_phrase_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("rules")).unwrap();
+        std::fs::write(
+            dir.path().join("main.conf"),
+            "Include rules/*.conf\nSecRuleRemoveById 102\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("rules/a-scanners.conf"),
+            r#"SecRule REQUEST_HEADERS:User-Agent "@pmFromFile scanners.data" "id:101,phase:1,deny""#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("rules/b.conf"),
+            r#"SecRule ARGS "@rx x" "id:102,phase:2,deny""#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("rules/skip.txt"), "not a rule").unwrap();
+        std::fs::write(
+            dir.path().join("rules/scanners.data"),
+            "# scanners\nnikto\nmasscan\n",
+        )
+        .unwrap();
+        let main = dir.path().join("main.conf").display().to_string();
+        let cfg: WafConfig = toml::from_str(&format!("rule_files = [{:?}]", main)).unwrap();
+        assert_eq!(check_config(&cfg).unwrap(), 1);
+        let waf = Waf::new("route[0]".to_string(), &cfg).unwrap();
+        let ua: &[(&[u8], &[u8])] = &[(b"user-agent", b"Mozilla/5.00 (Nikto/2.5.0)")];
+        assert_eq!(waf.inspect(&get("/", ua)), WafDecision::Block);
+        assert_eq!(waf.inspect(&get("/?a=x", &[])), WafDecision::Allow);
+
+        let glob = dir.path().join("rules/*.conf").display().to_string();
+        let cfg: WafConfig = toml::from_str(&format!("rule_files = [{:?}]", glob)).unwrap();
+        assert_eq!(check_config(&cfg).unwrap(), 2);
+    }
+
+    #[test]
+    fn invalid_rule_files_are_errors() {
+        let err = |rules: &str| check_config(&config("", rules)).unwrap_err();
+        assert!(err(r#"SecRule ARGS "@rx a" "id:1"#).contains("unterminated quote"));
+        assert!(err(r#"SecRule ARGS "@rx a" "phase:2,deny""#).contains("without an id"));
+        assert!(err(r#"SecRule ARGS "@rx a" "id:1,deny,chain""#).contains("chain"));
+        assert!(err(r#"SecRule ARGS"#).contains("rules:1"));
+        assert!(err(r#"SecRule TX:x "@rx a" "id:1,deny""#).contains("no supported rules"));
+        let missing: WafConfig =
+            toml::from_str(r#"rule_files = ["/nonexistent/waf.conf"]"#).unwrap();
+        assert!(check_config(&missing)
+            .unwrap_err()
+            .contains("failed to read"));
+        // 検証後に読めなくなった規則はすべて通さずにエラー（リロードは直前の設定のまま）
+        assert!(Waf::new("route[0]".to_string(), &missing)
+            .unwrap_err()
+            .to_string()
+            .contains("route[0]: WAF rules unavailable"));
+    }
+
+    #[test]
+    fn transformations_decode_like_modsecurity() {
+        assert_eq!(url_decode("a%20b+c%2", true, false), "a b c%2");
+        assert_eq!(url_decode("%u003cscript%zz", true, true), "<script%zz");
+        assert_eq!(url_decode("%u003c", true, false), "%u003c");
+        assert_eq!(
+            html_entity_decode("&lt;a&#62;&#x3C;&amp;&bogus;&QUOT"),
+            "<a><&&bogus;\""
+        );
+        assert_eq!(js_decode(r"\x3cscript>\n\q"), "<script>\nq");
+        assert_eq!(
+            cmd_line("C^at  /ETC/pa\"ss'wd ;ls , ( x"),
+            "cat/etc/passwd ls( x"
+        );
+        assert_eq!(
+            normalize_path("/a//b/./c/../../etc/passwd"),
+            "/a/etc/passwd"
+        );
+        assert_eq!(normalize_path("/../../x/"), "/x/");
+        assert_eq!(compress_whitespace("a \t\n b"), "a b");
+        assert_eq!(
+            remove_comments("un/**/ion sel/*x*/ect -- tail", false),
+            "union select "
+        );
+        assert_eq!(remove_comments("1<!-- x -->2#3", false), "12");
+        assert_eq!(remove_comments("un/**/ion -- x", true), "un ion -- x");
+        assert_eq!(Transform::RemoveNulls.apply("a\0b"), "ab");
+    }
+
+    #[test]
+    fn operators_and_selectors() {
+        let pm = phrase_regex(&["union select".to_string(), "a.b".to_string()]).unwrap();
+        assert!(pm.is_match("x UNION SELECT y"));
+        assert!(!pm.is_match("axb"));
+        let range = parse_byte_ranges("32-126, 9,10").unwrap();
+        assert!(!Operator::ValidateByteRange(range.clone()).matches("ok\ttext"));
+        assert!(Operator::ValidateByteRange(range).matches("nul\0"));
+        assert!(parse_byte_ranges("10-1").is_none());
+        assert_eq!(
+            split_variables("ARGS|!ARGS:/^a|b$/|REQUEST_HEADERS:'/x|y/'"),
+            vec!["ARGS", "!ARGS:/^a|b$/", "REQUEST_HEADERS:'/x|y/'"]
+        );
+        assert_eq!(
+            split_args(r#"SecRule ARGS "@rx a\"b\\" 'x'"#).unwrap(),
+            vec!["SecRule", "ARGS", r#"@rx a"b\\"#, "'x'"]
+        );
+        assert_eq!(
+            split_actions("id:1,msg:'a, b',t:none"),
+            vec![
+                ("id".to_string(), "1".to_string()),
+                ("msg".to_string(), "a, b".to_string()),
+                ("t".to_string(), "none".to_string()),
+            ]
+        );
+        assert_eq!(anomaly_increment("tx.anomaly_score=+3"), 3);
+        assert_eq!(anomaly_increment("tx.outbound_anomaly_score_pl1=+5"), 0);
+        assert_eq!(anomaly_increment("tx.sql_injection_score=+5"), 0);
+    }
+
+    #[test]
+    fn sqli_and_xss_heuristics() {
+        for attack in [
+            "1' or '1'='1",
+            "admin'--",
+            "\" OR \"\"=\"",
+            "1 or 1=1",
+            "1) AND (2>1",
+            "x' and true",
+            "1 UNION ALL SELECT password FROM users",
+            "1 union/**/select 1",
+            "1; DROP TABLE users",
+            "1 and sleep(5)",
+            "'; waitfor delay '0:0:5'",
+        ] {
+            assert!(SQLI.is_match(attack), "{}", attack);
+        }
+        for benign in [
+            "rock 'n' roll",
+            "it's fine; thanks",
+            "select your seat",
+            "o'reilly and sons",
+            "1 or 2 items",
+        ] {
+            assert!(!SQLI.is_match(benign), "{}", benign);
+        }
+        for attack in [
+            "<script>alert(1)</script>",
+            "<img src=x onerror=alert(1)>",
+            "javascript:alert(1)",
+            "<svg/onload=alert(1)>",
+            "<iframe srcdoc='x'>",
+        ] {
+            assert!(XSS.is_match(attack), "{}", attack);
+        }
+        for benign in ["a < b and c > d", "online = true", "the script was long"] {
+            assert!(!XSS.is_match(benign), "{}", benign);
+        }
+    }
+}
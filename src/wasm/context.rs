@@ -121,6 +121,8 @@ pub struct HttpContext {
     // === Metadata ===
     /// Client IP address
     pub client_ip: std::sync::Arc<str>,
+    /// Claims of the authenticated user (JWT / OIDC / Basic / API key, F-151)
+    pub auth_claims: Option<std::sync::Arc<serde_json::Value>>,
     /// Plugin name
     pub plugin_name: String,
     /// Plugin configuration
@@ -247,6 +249,7 @@ impl HttpContext {
             response_trailers: Vec::new(),
             response_body_complete: false,
             client_ip: std::sync::Arc::from(""),
+            auth_claims: None,
             plugin_name: String::new(),
             plugin_configuration: Vec::new(),
             vm_configuration: Vec::new(),
@@ -68,6 +68,7 @@ impl FilterEngine {
     ///
     /// F-43: `path`/`method`/`client_ip` は `Arc<str>` 共有（per-module の `to_string` 排除）、
     /// ヘッダは所有権ムーブスルー（per-module の deep copy 排除）。
+    /// F-151: `auth_claims` は認証済みのクレーム（`request.auth.*` プロパティ）。
     pub async fn on_request_headers_with_modules(
         &self,
         module_names: &[String],
@@ -75,6 +76,7 @@ impl FilterEngine {
         method: &Arc<str>,
         headers: Vec<(Vec<u8>, Vec<u8>)>,
         client_ip: &Arc<str>,
+        auth_claims: Option<&Arc<serde_json::Value>>,
         end_of_stream: bool,
     ) -> FilterResult {
         let modules: Vec<Arc<LoadedModule>> = module_names
@@ -99,6 +101,7 @@ impl FilterEngine {
                     method,
                     current_headers,
                     client_ip,
+                    auth_claims,
                     end_of_stream,
                 )
                 .await;
@@ -137,6 +140,7 @@ impl FilterEngine {
         method: Arc<str>,
         headers: Vec<(Vec<u8>, Vec<u8>)>,
         client_ip: Arc<str>,
+        auth_claims: Option<Arc<serde_json::Value>>,
         end_of_stream: bool,
     ) -> FilterResult {
         self.on_request_headers_with_modules(
@@ -145,6 +149,7 @@ impl FilterEngine {
             &method,
             headers,
             &client_ip,
+            auth_claims.as_ref(),
             end_of_stream,
         )
         .await
@@ -385,6 +390,7 @@ impl FilterEngine {
         method: &Arc<str>,
         headers: Vec<(Vec<u8>, Vec<u8>)>,
         client_ip: &Arc<str>,
+        auth_claims: Option<&Arc<serde_json::Value>>,
         end_of_stream: bool,
     ) -> (Vec<(Vec<u8>, Vec<u8>)>, anyhow::Result<ModuleAction>) {
         let num_headers = headers.len() as i32;
@@ -392,6 +398,7 @@ impl FilterEngine {
         // Create context（文字列は Arc 共有、ヘッダはムーブ）
         let mut http_ctx = HttpContext::new(1, module.capabilities.clone());
         http_ctx.set_request(method.clone(), path.clone(), headers, client_ip.clone());
+        http_ctx.auth_claims = auth_claims.cloned();
         http_ctx.plugin_name = module.name.clone();
         http_ctx.plugin_configuration = module.configuration.clone();
 
@@ -2362,6 +2369,7 @@ mod exec_smoke_tests {
             &Arc::from("GET"),
             headers,
             &Arc::from("127.0.0.1"),
+            None,
             true,
         ));
         // 中身は問わない。パニックせず FilterResult を返すことだけを確認する。
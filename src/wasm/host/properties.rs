@@ -46,6 +46,18 @@ fn get_property_value(state: &HostState, path: &str) -> Option<Vec<u8>> {
         "source.address" => Some(state.http_ctx.client_ip.as_bytes().to_vec()),
         "destination.address" => Some(b"0.0.0.0:0".to_vec()),
 
+        // Authentication properties (F-151)
+        "request.auth.principal" => state
+            .http_ctx
+            .auth_claims
+            .as_deref()
+            .map(|claims| crate::jwt_auth::principal(Some(claims)).into_bytes()),
+        _ if path.starts_with("request.auth.claims.") => {
+            let claims = state.http_ctx.auth_claims.as_deref()?;
+            let claim = &path["request.auth.claims.".len()..];
+            auth_claim_bytes(crate::jwt_auth::claim_value(claims, claim)?)
+        }
+
         // Plugin properties
         "plugin_name" => Some(state.http_ctx.plugin_name.as_bytes().to_vec()),
         "plugin_root_id" => Some(state.http_ctx.root_context_id.to_string().into_bytes()),
@@ -55,6 +67,23 @@ fn get_property_value(state: &HostState, path: &str) -> Option<Vec<u8>> {
     }
 }
 
+/// Claim value as property bytes (scalars as text, arrays comma-separated, objects as JSON)
+fn auth_claim_bytes(value: &serde_json::Value) -> Option<Vec<u8>> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(crate::jwt_auth::scalar_string)
+                .collect::<Vec<_>>()
+                .join(",")
+                .into_bytes(),
+        ),
+        serde_json::Value::Object(_) => Some(value.to_string().into_bytes()),
+        other => crate::jwt_auth::scalar_string(other).map(|s| s.into_owned().into_bytes()),
+    }
+}
+
 /// Helper to allocate memory in WASM
 ///
 /// B-20: async store のため `call_async` を使用（同期 `call` は panic する）。
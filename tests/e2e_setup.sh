@@ -61,6 +61,9 @@ BACKEND_CHUNKED_PORT=9007
 BACKEND_ECHO_PORT=9008
 BACKEND_TLS_ECHO_PORT=9018
 BACKEND_UDP_ECHO_PORT=9019
+BACKEND_RLS_PORT=9020
+BACKEND_AUTHZ_PORT=9021
+BACKEND_OIDC_PORT=9022
 
 # 色付き出力
 RED='\033[0;31m'
@@ -192,6 +195,29 @@ prepare_fixtures() {
         echo "h2c stream test ${i}" > "${FIXTURES_DIR}/backend_h2c/test${i}.txt"
     done
     
+    # F-148: JWT 認証用の JWKS（HS256 の共有鍵 "e2e-secret"）
+    echo '{"keys":[{"kty":"oct","kid":"e2e","alg":"HS256","k":"ZTJlLXNlY3JldA"}]}' > "${FIXTURES_DIR}/jwks.json"
+
+    # F-151: Basic 認証の htpasswd（alice = "s3cr3t" の bcrypt、bob = "hunter2" の SHA-256 crypt）と
+    # API キー（"e2e-api-key" はこのルート用、"e2e-other-key" は別ルート専用）
+    cat > "${FIXTURES_DIR}/htpasswd" << 'EOF'
+# e2e users
+alice:$2y$04$KBCwKxOzLha2MUDgW0PjXe4/G/VOhqN5KiuuxLwSWm4d5jyZGASdm
+bob:$5$e2esalt$WCxMevHsuhbcfIasCOWvIEMFqMTwa8.VVKGHpklhYD/
+EOF
+    cat > "${FIXTURES_DIR}/api_keys.toml" << 'EOF'
+[[key]]
+id = "ci"
+hash = "sha256:4ef78803d8fc2e82a84b91cf3080df3142474ed2864ba0e7b7b890ead8b811c0"
+tenant = "acme"
+routes = ["cred"]
+
+[[key]]
+id = "other"
+hash = "sha256:9b74d9b60b1d73904acb855e21632027a9758f33a05587580b30ce3cdca42603"
+routes = ["elsewhere"]
+EOF
+
     # /healthエンドポイント用JSONファイル（プロキシが直接サービスする）
     echo '{"status":"ok","proxy":"veil"}' > "${FIXTURES_DIR}/proxy_health.json"
 
@@ -222,6 +248,15 @@ prepare_fixtures() {
         # B-10: ロードバランシング分散テスト専用パス（共有 "/" の RR ステートと隔離）
         mkdir -p "${FIXTURES_DIR}/${backend}/rr-test"
         echo "<h1>RR Test</h1>" > "${FIXTURES_DIR}/${backend}/rr-test/index.html"
+        # F-135: HTTP/3 上流の TCP フォールバック検証用（バックエンドは UDP を待ち受けない）
+        mkdir -p "${FIXTURES_DIR}/${backend}/h3-fallback"
+        echo "<h1>H3 Fallback</h1>" > "${FIXTURES_DIR}/${backend}/h3-fallback/index.html"
+        # F-136: コネクションプール上限・ライフサイクル検証用
+        mkdir -p "${FIXTURES_DIR}/${backend}/pool-limits"
+        echo "<h1>Pool Limits</h1>" > "${FIXTURES_DIR}/${backend}/pool-limits/index.html"
+        # F-138: 適応型の同時実行数制限検証用
+        mkdir -p "${FIXTURES_DIR}/${backend}/adaptive"
+        echo "<h1>Adaptive Concurrency</h1>" > "${FIXTURES_DIR}/${backend}/adaptive/index.html"
     done
 
     # 必要なディレクトリの作成
@@ -434,6 +469,10 @@ reload_interval_secs = 60
 [logging]
 level = "info"
 
+# F-140: 上流接続の Happy Eyeballs（localhost は ::1 / 127.0.0.1 の両方に解決される）
+[performance.happy_eyeballs]
+connection_attempt_delay_ms = 50
+
 [prometheus]
 enabled = true
 path = "/__metrics"
@@ -443,10 +482,34 @@ enabled = true
 path_prefix = "/__admin"
 secret = "test-admin-secret"
 
+# F-152: 自動遮断（E2E のクライアントは遮断しないよう除外し、管理 API からの遮断を検証する）
+[security.auto_ban]
+exempt_ips = ["127.0.0.0/8", "::1"]
+persist_file = "${FIXTURES_DIR}/bans.json"
+
+[[security.auto_ban.rules]]
+name = "scan"
+signals = ["not_found", "unauthorized"]
+threshold = 100
+window_secs = 10
+
+# F-153: 接続元ごとの上限（E2E の全リクエストが数える経路を通るよう、上限は十分大きくする）
+[security.client_limits]
+[security.client_limits.per_ip]
+max_connections = 4096
+max_quic_connections = 1024
+max_streams = 65536
+
 [http3]
 listen = "127.0.0.1:${PROXY_HTTPS_PORT}"
 compression_enabled = true
 
+# F-147: 外部レートリミットサービス（test_backends の stand-in RLS）
+[rate_limit_service]
+address = "127.0.0.1:${BACKEND_RLS_PORT}"
+domain = "veil-e2e"
+timeout_ms = 500
+
 # L4 TCP プロキシ（TLS パススルー、F-30 splice 検証用）
 # クライアントの TLS は backend1/2(HTTPS) へ透過転送され、L4 は生バイトを双方向中継する。
 [[l4]]
@@ -524,6 +587,53 @@ servers = [
 ]
 tls_insecure = true
 
+# F-135: protocol = "h3"。バックエンドは TCP のみのため QUIC は成立せず TCP へフォールバックする
+[upstreams."h3-fallback-pool"]
+servers = ["https://127.0.0.1:${BACKEND1_PORT}"]
+protocol = "h3"
+tls_insecure = true
+
+# F-136: 使用枠 2、1 接続 2 リクエストで入れ替え、起動時に 1 接続を事前確立
+[upstreams."pool-limits-pool"]
+servers = ["https://127.0.0.1:${BACKEND1_PORT}"]
+tls_insecure = true
+
+[upstreams."pool-limits-pool".connection_pool]
+max_connections = 2
+max_pending_requests = 16
+pending_timeout_ms = 5000
+max_requests_per_connection = 2
+max_connection_lifetime_secs = 60
+prewarm_connections = 1
+
+# F-138: 適応型の同時実行数制限（AIMD、上限 50 から調整）
+[upstreams."adaptive-pool"]
+servers = [
+    "https://127.0.0.1:${BACKEND1_PORT}",
+    "https://127.0.0.1:${BACKEND2_PORT}"
+]
+tls_insecure = true
+
+[upstreams."adaptive-pool".adaptive_concurrency]
+enabled = true
+algorithm = "aimd"
+initial_limit = 50
+min_limit = 10
+sample_window_ms = 100
+retry_after_secs = 2
+
+# F-137: リクエストヘッジング（同じ echo バックエンドを別アドレスで 2 台として登録）
+[upstreams."hedge-pool"]
+servers = [
+    "http://127.0.0.1:${BACKEND_ECHO_PORT}",
+    "http://localhost:${BACKEND_ECHO_PORT}"
+]
+
+# F-141: 送信元アドレスを 127.0.0.2 に固定（Linux では 127.0.0.0/8 全体がローカル）
+[upstreams."source-pool"]
+source_address = "127.0.0.2"
+servers = ["http://127.0.0.1:${BACKEND_ECHO_PORT}"]
+
 # F-97: gRPC Consistent Hash（x-user-id。無い場合は client_ip フォールバック）
 [upstreams."grpc-pool"]
 algorithm = "consistent_hash"
@@ -1013,6 +1123,396 @@ path = "/bad-backend/*"
 type = "Proxy"
 upstream = "bad-pool"
 
+# F-135: HTTP/3 上流の TCP フォールバック
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/h3-fallback/*"
+[route.action]
+type = "Proxy"
+upstream = "h3-fallback-pool"
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/h3-fallback/*"
+[route.action]
+type = "Proxy"
+upstream = "h3-fallback-pool"
+
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/pool-limits/*"
+[route.action]
+type = "Proxy"
+upstream = "pool-limits-pool"
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/pool-limits/*"
+[route.action]
+type = "Proxy"
+upstream = "pool-limits-pool"
+
+# F-138: 適応型の同時実行数制限
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/adaptive/*"
+[route.action]
+type = "Proxy"
+upstream = "adaptive-pool"
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/adaptive/*"
+[route.action]
+type = "Proxy"
+upstream = "adaptive-pool"
+
+# F-137: 一次試行が 20ms 以内に応答しなければ別サーバーへヘッジ
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/hedge/*"
+[route.action]
+type = "Proxy"
+upstream = "hedge-pool"
+[route.hedging]
+delay_ms = 20
+budget_percent = 100.0
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/hedge/*"
+[route.action]
+type = "Proxy"
+upstream = "hedge-pool"
+[route.hedging]
+delay_ms = 20
+budget_percent = 100.0
+
+# F-140: IPv4 のみで待ち受けるバックエンドをホスト名（localhost）で指定
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/happy-eyeballs/*"
+[route.action]
+type = "Proxy"
+url = "http://localhost:${BACKEND_ECHO_PORT}"
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/happy-eyeballs/*"
+[route.action]
+type = "Proxy"
+url = "http://localhost:${BACKEND_ECHO_PORT}"
+
+# F-141: 送信元アドレスを固定した上流（echo バックエンドが X-Peer-Ip で接続元を返す）
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/source-address/*"
+[route.action]
+type = "Proxy"
+upstream = "source-pool"
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/source-address/*"
+[route.action]
+type = "Proxy"
+upstream = "source-pool"
+
+# F-147: 外部レートリミットサービス（stand-in RLS はディスクリプタごとに 3 回 / 60 秒まで許可）
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/global-rate-limit/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.global_rate_limit]
+[[route.global_rate_limit.descriptors]]
+entries = [{ key = "generic_key", value = "e2e" }, { key = "tenant", from = "header:X-Tenant" }]
+
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/global-rate-limit-closed/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.global_rate_limit]
+failure_mode = "closed"
+[[route.global_rate_limit.descriptors]]
+entries = [{ key = "generic_key", value = "e2e-closed" }, { key = "tenant", from = "header:X-Tenant" }]
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/global-rate-limit/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.global_rate_limit]
+[[route.global_rate_limit.descriptors]]
+entries = [{ key = "generic_key", value = "e2e" }, { key = "tenant", from = "header:X-Tenant" }]
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/global-rate-limit-closed/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.global_rate_limit]
+failure_mode = "closed"
+[[route.global_rate_limit.descriptors]]
+entries = [{ key = "generic_key", value = "e2e-closed" }, { key = "tenant", from = "header:X-Tenant" }]
+
+# F-148: JWT 認証（echo バックエンドが X-Jwt-Sub を X-Echo-Jwt-Sub で返す）
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/jwt/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.jwt]
+algorithms = ["HS256"]
+jwks_file = "${FIXTURES_DIR}/jwks.json"
+audiences = ["e2e"]
+required_claims = { "scope" = ["read"] }
+claims_to_headers = { "sub" = "X-Jwt-Sub" }
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/jwt/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.jwt]
+algorithms = ["HS256"]
+jwks_file = "${FIXTURES_DIR}/jwks.json"
+audiences = ["e2e"]
+required_claims = { "scope" = ["read"] }
+claims_to_headers = { "sub" = "X-Jwt-Sub" }
+
+# F-149: 外部認可（stand-in の認可サービスが X-Auth-Token を見て X-Auth-User を注入し、トークンを除去させる）
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/authz/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.ext_authz]
+url = "http://127.0.0.1:${BACKEND_AUTHZ_PORT}/check"
+request_headers = ["X-Auth-Token"]
+upstream_headers = ["X-Auth-User"]
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/authz/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.ext_authz]
+url = "http://127.0.0.1:${BACKEND_AUTHZ_PORT}/check"
+request_headers = ["X-Auth-Token"]
+upstream_headers = ["X-Auth-User"]
+
+# F-149: 認可サービスに到達できない（failure_mode = closed で 503）
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/authz-down/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.ext_authz]
+url = "http://127.0.0.1:1/check"
+failure_mode = "closed"
+status_on_error = 503
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/authz-down/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.ext_authz]
+url = "http://127.0.0.1:1/check"
+failure_mode = "closed"
+status_on_error = 503
+
+# F-151: Basic 認証と API キー認証（鍵は X-Auth-Token で受け、上流へは送らない）
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/cred/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.basic_auth]
+htpasswd_file = "${FIXTURES_DIR}/htpasswd"
+realm = "e2e"
+claims_to_headers = { "sub" = "X-Jwt-Sub" }
+[route.api_key]
+keys_file = "${FIXTURES_DIR}/api_keys.toml"
+header = "X-Auth-Token"
+route_name = "cred"
+strip_credentials = true
+claims_to_headers = { "sub" = "X-Jwt-Sub", "tenant" = "X-Auth-User" }
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/cred/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.basic_auth]
+htpasswd_file = "${FIXTURES_DIR}/htpasswd"
+realm = "e2e"
+claims_to_headers = { "sub" = "X-Jwt-Sub" }
+[route.api_key]
+keys_file = "${FIXTURES_DIR}/api_keys.toml"
+header = "X-Auth-Token"
+route_name = "cred"
+strip_credentials = true
+claims_to_headers = { "sub" = "X-Jwt-Sub", "tenant" = "X-Auth-User" }
+
+# F-154: WAF（SQL インジェクションは即座に拒否、スキャナーの User-Agent は異常スコアで拒否）
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/waf/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.waf]
+rules = """
+SecRule ARGS "@detectSQLi" "id:10001,phase:2,deny,msg:'SQL injection'"
+SecRule REQUEST_HEADERS:User-Agent "@pm sqlmap nikto" "id:10002,phase:1,block,t:lowercase,severity:'CRITICAL'"
+"""
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/waf/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.waf]
+rules = """
+SecRule ARGS "@detectSQLi" "id:10001,phase:2,deny,msg:'SQL injection'"
+SecRule REQUEST_HEADERS:User-Agent "@pm sqlmap nikto" "id:10002,phase:1,block,t:lowercase,severity:'CRITICAL'"
+"""
+
+# F-155: CORS（プリフライトは allowed_methods に OPTIONS が無くても上流へ送らずに応答する）
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/cors/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.security]
+allowed_methods = ["GET", "PUT"]
+[route.cors]
+allowed_origins = ["https://app.example.com", "https://*.example.org"]
+allowed_methods = ["GET", "PUT"]
+allowed_headers = ["Content-Type", "X-Request-Id"]
+exposed_headers = ["X-Request-Id"]
+allow_credentials = true
+max_age_secs = 600
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/cors/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.security]
+allowed_methods = ["GET", "PUT"]
+[route.cors]
+allowed_origins = ["https://app.example.com", "https://*.example.org"]
+allowed_methods = ["GET", "PUT"]
+allowed_headers = ["Content-Type", "X-Request-Id"]
+exposed_headers = ["X-Request-Id"]
+allow_credentials = true
+max_age_secs = 600
+
+# F-150: OIDC ログイン（test_backends の mock IdP、ID トークンは HS256、アクセストークンは 1 秒で期限切れ）
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/oidc/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.oidc]
+issuer = "http://127.0.0.1:${BACKEND_OIDC_PORT}"
+client_id = "e2e-client"
+client_secret = "e2e-secret"
+redirect_uri = "/oidc/callback"
+logout_path = "/oidc/logout"
+algorithms = ["HS256"]
+claims_to_headers = { "sub" = "X-Jwt-Sub" }
+cookie_secret = "e2e-oidc-cookie-secret-0123456789abcdef"
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/oidc/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.oidc]
+issuer = "http://127.0.0.1:${BACKEND_OIDC_PORT}"
+client_id = "e2e-client"
+client_secret = "e2e-secret"
+redirect_uri = "/oidc/callback"
+logout_path = "/oidc/logout"
+algorithms = ["HS256"]
+claims_to_headers = { "sub" = "X-Jwt-Sub" }
+cookie_secret = "e2e-oidc-cookie-secret-0123456789abcdef"
+
+# F-139: 応答ヘッダー待ち 300ms・全体期限 2 秒（遅い応答は 504）
+[[route]]
+[route.conditions]
+host = "localhost"
+path = "/route-timeout/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.security]
+upstream_header_timeout_ms = 300
+request_timeout_ms = 2000
+
+[[route]]
+[route.conditions]
+host = "127.0.0.1"
+path = "/route-timeout/*"
+[route.action]
+type = "Proxy"
+url = "http://127.0.0.1:${BACKEND_ECHO_PORT}"
+[route.security]
+upstream_header_timeout_ms = 300
+request_timeout_ms = 2000
+
 # B-10: Round Robin 分散テスト専用ルート（共有 "/" と RR ステートを隔離）
 [[route]]
 [route.conditions]
@@ -1595,16 +2095,16 @@ start_servers() {
     log_info "Starting Rust test backends (WS echo + HTTP error + chunked + body-echo)..."
     WS_PORT="${BACKEND_WS_PORT}" ERROR_PORT="${BACKEND_ERROR_PORT}" BAD_PORT="${BACKEND_BAD_PORT}" CHUNKED_PORT="${BACKEND_CHUNKED_PORT}" ECHO_PORT="${BACKEND_ECHO_PORT}" \
         TLS_ECHO_PORT="${BACKEND_TLS_ECHO_PORT}" TLS_CERT_PATH="${FIXTURES_DIR}/cert.pem" TLS_KEY_PATH="${FIXTURES_DIR}/key.pem" \
-        UDP_ECHO_PORT="${BACKEND_UDP_ECHO_PORT}" \
+        UDP_ECHO_PORT="${BACKEND_UDP_ECHO_PORT}" RLS_PORT="${BACKEND_RLS_PORT}" AUTHZ_PORT="${BACKEND_AUTHZ_PORT}" OIDC_PORT="${BACKEND_OIDC_PORT}" \
         RUST_LOG=info "${SCRIPT_DIR}/test_backends/target/debug/test-backends" \
         > /tmp/test_backends.log 2>&1 &
     echo $! >> "$PIDS_FILE"
-    log_info "Test backends started (WS: ${BACKEND_WS_PORT}, error: ${BACKEND_ERROR_PORT}, chunked: ${BACKEND_CHUNKED_PORT}, echo: ${BACKEND_ECHO_PORT}, udp-echo: ${BACKEND_UDP_ECHO_PORT}, PID: $!, logs: /tmp/test_backends.log)"
+    log_info "Test backends started (WS: ${BACKEND_WS_PORT}, error: ${BACKEND_ERROR_PORT}, chunked: ${BACKEND_CHUNKED_PORT}, echo: ${BACKEND_ECHO_PORT}, udp-echo: ${BACKEND_UDP_ECHO_PORT}, rls: ${BACKEND_RLS_PORT}, authz: ${BACKEND_AUTHZ_PORT}, oidc: ${BACKEND_OIDC_PORT}, PID: $!, logs: /tmp/test_backends.log)"
 
     # test_backendsの起動待機（全ポートがリッスン状態になるまで）
     local tb_wait=0
     while [ $tb_wait -lt 30 ]; do
-        if check_port_in_use "$BACKEND_WS_PORT" && check_port_in_use "$BACKEND_ERROR_PORT" && check_port_in_use "$BACKEND_CHUNKED_PORT" && check_port_in_use "$BACKEND_ECHO_PORT" && check_port_in_use "$BACKEND_TLS_ECHO_PORT" && check_port_in_use "$BACKEND_BAD_PORT" && check_port_in_use "$BACKEND_UDP_ECHO_PORT"; then
+        if check_port_in_use "$BACKEND_WS_PORT" && check_port_in_use "$BACKEND_ERROR_PORT" && check_port_in_use "$BACKEND_CHUNKED_PORT" && check_port_in_use "$BACKEND_ECHO_PORT" && check_port_in_use "$BACKEND_TLS_ECHO_PORT" && check_port_in_use "$BACKEND_BAD_PORT" && check_port_in_use "$BACKEND_UDP_ECHO_PORT" && check_port_in_use "$BACKEND_RLS_PORT" && check_port_in_use "$BACKEND_AUTHZ_PORT" && check_port_in_use "$BACKEND_OIDC_PORT"; then
             sleep 0.2
             break
         fi
@@ -1767,7 +2267,7 @@ check_port_conflicts() {
     log_info "Checking for port conflicts..."
     local conflicts=0
     
-    for port in $PROXY_HTTPS_PORT $PROXY_HTTP_PORT $PROXY_H2C_PORT $PROXY_L4_PORT $PROXY_L4_LEAST_CONN_PORT $PROXY_L4_TERMINATE_PORT $PROXY_L4_UDP_PORT $BACKEND1_PORT $BACKEND2_PORT $BACKEND_H2C_PORT $BACKEND_GRPC_PORT $BACKEND_GRPC2_PORT $BACKEND_WS_PORT $BACKEND_ERROR_PORT $BACKEND_BAD_PORT $BACKEND_CHUNKED_PORT $BACKEND_ECHO_PORT $BACKEND_TLS_ECHO_PORT $BACKEND_UDP_ECHO_PORT; do
+    for port in $PROXY_HTTPS_PORT $PROXY_HTTP_PORT $PROXY_H2C_PORT $PROXY_L4_PORT $PROXY_L4_LEAST_CONN_PORT $PROXY_L4_TERMINATE_PORT $PROXY_L4_UDP_PORT $BACKEND1_PORT $BACKEND2_PORT $BACKEND_H2C_PORT $BACKEND_GRPC_PORT $BACKEND_GRPC2_PORT $BACKEND_WS_PORT $BACKEND_ERROR_PORT $BACKEND_BAD_PORT $BACKEND_CHUNKED_PORT $BACKEND_ECHO_PORT $BACKEND_TLS_ECHO_PORT $BACKEND_UDP_ECHO_PORT $BACKEND_RLS_PORT $BACKEND_AUTHZ_PORT $BACKEND_OIDC_PORT; do
         if check_port_in_use "$port"; then
             log_error "Port $port is already in use"
             conflicts=$((conflicts + 1))
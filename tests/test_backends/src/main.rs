@@ -4,9 +4,15 @@
 //! - HTTP 500 Error Server (ERROR_PORT env var, default 9006)
 //! - HTTP Chunked Streaming Server (CHUNKED_PORT env var, default 9007)
 //! - プロトコル違反サーバー (BAD_PORT env var, default 9009) — B-17 回帰テスト用
+//! - Envoy RLS 互換レートリミットサービス (RLS_PORT env var, default 9020) — F-147 用
+//! - 外部認可サービス (AUTHZ_PORT env var, default 9021) — F-149 用
+//! - OpenID Connect プロバイダ (OIDC_PORT env var, default 9022) — F-150 用
 
 use futures_util::{SinkExt, StreamExt};
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream, UdpSocket};
 use tracing::{debug, error, info};
@@ -311,7 +317,7 @@ async fn run_echo_server(addr: SocketAddr) {
             Ok((stream, peer)) => {
                 debug!("New echo HTTP connection from {}", peer);
                 tokio::spawn(async move {
-                    if let Err(e) = handle_echo(stream).await {
+                    if let Err(e) = handle_echo(stream, peer).await {
                         debug!("Echo handler error: {}", e);
                     }
                 });
@@ -351,7 +357,7 @@ async fn run_tls_echo_server(addr: SocketAddr, cert_path: String, key_path: Stri
                 tokio::spawn(async move {
                     match acceptor.accept(stream).await {
                         Ok(tls) => {
-                            if let Err(e) = handle_echo(tls).await {
+                            if let Err(e) = handle_echo(tls, peer).await {
                                 debug!("TLS echo handler error: {}", e);
                             }
                         }
@@ -364,7 +370,10 @@ async fn run_tls_echo_server(addr: SocketAddr, cert_path: String, key_path: Stri
     }
 }
 
-async fn handle_echo<S>(mut stream: S) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+async fn handle_echo<S>(
+    mut stream: S,
+    peer: SocketAddr,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
 where
     S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
 {
@@ -401,6 +410,24 @@ where
         .nth(1)
         .and_then(|s| s.split("\r\n").next())
         .and_then(|s| s.trim().parse().ok());
+    // F-148 JWT 認証 E2E 用: プロキシが付けた `x-jwt-sub` ヘッダーを応答で返す
+    let jwt_sub: Option<String> = header_str
+        .split("\r\nx-jwt-sub:")
+        .nth(1)
+        .and_then(|s| s.split("\r\n").next())
+        .map(|s| s.trim().to_string());
+    // F-149 外部認可 E2E 用: 認可サービスが注入した `x-auth-user` と、
+    // 除去されるべき `x-auth-token` が届いたかを応答で返す
+    let auth_user: Option<String> = header_str
+        .split("\r\nx-auth-user:")
+        .nth(1)
+        .and_then(|s| s.split("\r\n").next())
+        .map(|s| s.trim().to_string());
+    let auth_token: Option<String> = header_str
+        .split("\r\nx-auth-token:")
+        .nth(1)
+        .and_then(|s| s.split("\r\n").next())
+        .map(|s| s.trim().to_string());
 
     // ボディを読み取り、デコードして echo 用バッファへ
     let mut body: Vec<u8> = Vec::new();
@@ -464,6 +491,17 @@ where
         b"HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: ",
     );
     out.extend_from_slice(body.len().to_string().as_bytes());
+    // 接続元 IP（F-141 送信元アドレス指定の E2E 用）
+    out.extend_from_slice(format!("\r\nX-Peer-Ip: {}", peer.ip()).as_bytes());
+    if let Some(sub) = jwt_sub {
+        out.extend_from_slice(format!("\r\nX-Echo-Jwt-Sub: {}", sub).as_bytes());
+    }
+    if let Some(user) = auth_user {
+        out.extend_from_slice(format!("\r\nX-Echo-Auth-User: {}", user).as_bytes());
+    }
+    if let Some(token) = auth_token {
+        out.extend_from_slice(format!("\r\nX-Echo-Auth-Token: {}", token).as_bytes());
+    }
     out.extend_from_slice(b"\r\nConnection: close\r\n\r\n");
     out.extend_from_slice(&body);
 
@@ -472,6 +510,682 @@ where
     Ok(())
 }
 
+/// stand-in RLS のディスクリプタごとの許可数（60 秒の固定ウィンドウ）
+const RLS_LIMIT: u64 = 3;
+const RLS_WINDOW: Duration = Duration::from_secs(60);
+
+/// ディスクリプタ（domain + エンコード済みディスクリプタ）→（ウィンドウ開始, 回数）
+type RlsCounters = Arc<Mutex<HashMap<Vec<u8>, (Instant, u64)>>>;
+
+/// Envoy RLS 互換の `ShouldRateLimit` サーバー（F-147 グローバルレートリミットの E2E 用）
+///
+/// h2c と protobuf を必要な分だけ手で扱う最小実装。ディスクリプタごとに `RLS_LIMIT` 回 / 60 秒
+/// まで OK を返し、値が `blocked` のエントリを含むディスクリプタは常に OVER_LIMIT、`error` を
+/// 含む問い合わせには gRPC の UNAVAILABLE を返す。OK の応答には残り回数を
+/// `x-rls-remaining` ヘッダーとして付けるよう指示する。
+async fn run_rls_server(addr: SocketAddr) {
+    let listener = TcpListener::bind(addr)
+        .await
+        .unwrap_or_else(|e| panic!("Failed to bind RLS server on {}: {}", addr, e));
+    info!("Rate limit service (h2c gRPC) listening on {}", addr);
+    let counters: RlsCounters = Arc::new(Mutex::new(HashMap::new()));
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                debug!("New RLS connection from {}", peer);
+                let counters = counters.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_rls(stream, counters).await {
+                        debug!("RLS handler error: {}", e);
+                    }
+                });
+            }
+            Err(e) => error!("RLS accept error: {}", e),
+        }
+    }
+}
+
+async fn write_h2_frame(
+    stream: &mut TcpStream,
+    kind: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: &[u8],
+) -> std::io::Result<()> {
+    let mut frame = Vec::with_capacity(9 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes()[1..]);
+    frame.push(kind);
+    frame.push(flags);
+    frame.extend_from_slice(&stream_id.to_be_bytes());
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame).await
+}
+
+/// HPACK のリテラル（インデックスなし・新しい名前）
+fn hpack_literal(block: &mut Vec<u8>, name: &[u8], value: &[u8]) {
+    block.push(0x00);
+    block.push(name.len() as u8);
+    block.extend_from_slice(name);
+    block.push(value.len() as u8);
+    block.extend_from_slice(value);
+}
+
+async fn handle_rls(mut stream: TcpStream, counters: RlsCounters) -> std::io::Result<()> {
+    let mut preface = [0u8; 24];
+    stream.read_exact(&mut preface).await?;
+    if &preface != b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n" {
+        return Ok(());
+    }
+    write_h2_frame(&mut stream, 0x4, 0, 0, &[]).await?;
+
+    let mut bodies: HashMap<u32, Vec<u8>> = HashMap::new();
+    loop {
+        let mut header = [0u8; 9];
+        if stream.read_exact(&mut header).await.is_err() {
+            return Ok(());
+        }
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let (kind, flags) = (header[3], header[4]);
+        let stream_id =
+            u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff;
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).await?;
+        let end_stream = flags & 0x1 != 0;
+        match kind {
+            // SETTINGS / PING は ACK を返す
+            0x4 if flags & 0x1 == 0 => write_h2_frame(&mut stream, 0x4, 0x1, 0, &[]).await?,
+            0x6 if flags & 0x1 == 0 => write_h2_frame(&mut stream, 0x6, 0x1, 0, &payload).await?,
+            // HEADERS（内容は見ない）
+            0x1 => {
+                bodies.entry(stream_id).or_default();
+                if end_stream {
+                    bodies.remove(&stream_id);
+                }
+            }
+            0x0 => {
+                let data = if flags & 0x8 != 0 && !payload.is_empty() {
+                    let pad = payload[0] as usize;
+                    &payload[1..payload.len().saturating_sub(pad)]
+                } else {
+                    &payload[..]
+                };
+                bodies.entry(stream_id).or_default().extend_from_slice(data);
+                if !payload.is_empty() {
+                    // コネクションの受信ウィンドウを戻す
+                    write_h2_frame(&mut stream, 0x8, 0, 0, &(len as u32).to_be_bytes()).await?;
+                }
+                if end_stream {
+                    let body = bodies.remove(&stream_id).unwrap_or_default();
+                    respond_rls(&mut stream, stream_id, &body, &counters).await?;
+                }
+            }
+            // GOAWAY
+            0x7 => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+fn pb_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut v = 0u64;
+    for shift in (0..64).step_by(7) {
+        let b = *buf.get(*pos)?;
+        *pos += 1;
+        v |= ((b & 0x7f) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Some(v);
+        }
+    }
+    None
+}
+
+/// 長さ区切りフィールドを（番号, 値）で列挙する（それ以外のワイヤ型は読み飛ばす）
+fn pb_fields(buf: &[u8]) -> Vec<(u64, &[u8])> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < buf.len() {
+        let Some(tag) = pb_varint(buf, &mut pos) else {
+            break;
+        };
+        match tag & 7 {
+            0 => {
+                if pb_varint(buf, &mut pos).is_none() {
+                    break;
+                }
+            }
+            2 => {
+                let Some(len) = pb_varint(buf, &mut pos) else {
+                    break;
+                };
+                let end = pos + len as usize;
+                if end > buf.len() {
+                    break;
+                }
+                fields.push((tag >> 3, &buf[pos..end]));
+                pos = end;
+            }
+            _ => break,
+        }
+    }
+    fields
+}
+
+fn pb_put_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn pb_put_bytes(buf: &mut Vec<u8>, field: u64, value: &[u8]) {
+    pb_put_varint(buf, (field << 3) | 2);
+    pb_put_varint(buf, value.len() as u64);
+    buf.extend_from_slice(value);
+}
+
+async fn respond_rls(
+    stream: &mut TcpStream,
+    stream_id: u32,
+    body: &[u8],
+    counters: &RlsCounters,
+) -> std::io::Result<()> {
+    // gRPC フレーム（圧縮なし）の RateLimitRequest{domain = 1, descriptors = 2}
+    let request = body.get(5..).unwrap_or_default();
+    let fields = pb_fields(request);
+    let domain = fields
+        .iter()
+        .find(|(n, _)| *n == 1)
+        .map_or(&[][..], |(_, v)| *v);
+    let descriptors: Vec<&[u8]> = fields
+        .iter()
+        .filter(|(n, _)| *n == 2)
+        .map(|(_, v)| *v)
+        .collect();
+    // Entry{key = 1, value = 2} の値
+    let values = |descriptor: &[u8]| -> Vec<Vec<u8>> {
+        pb_fields(descriptor)
+            .into_iter()
+            .filter(|(n, _)| *n == 1)
+            .flat_map(|(_, entry)| pb_fields(entry))
+            .filter(|(n, _)| *n == 2)
+            .map(|(_, v)| v.to_vec())
+            .collect()
+    };
+
+    let mut block = vec![0x88]; // :status 200
+    hpack_literal(&mut block, b"content-type", b"application/grpc");
+    write_h2_frame(stream, 0x1, 0x4, stream_id, &block).await?;
+
+    if descriptors
+        .iter()
+        .any(|d| values(d).iter().any(|v| v == b"error"))
+    {
+        let mut trailers = Vec::new();
+        hpack_literal(&mut trailers, b"grpc-status", b"14");
+        hpack_literal(&mut trailers, b"grpc-message", b"unavailable");
+        return write_h2_frame(stream, 0x1, 0x5, stream_id, &trailers).await;
+    }
+
+    // DescriptorStatus{code = 1, limit_remaining = 3, duration_until_reset = 4}
+    let mut over_limit = false;
+    let mut min_remaining = u64::MAX;
+    let mut statuses = Vec::new();
+    {
+        let mut counters = counters.lock().unwrap();
+        let now = Instant::now();
+        for descriptor in &descriptors {
+            let mut key = domain.to_vec();
+            key.extend_from_slice(descriptor);
+            let entry = counters.entry(key).or_insert((now, 0));
+            if now.duration_since(entry.0) >= RLS_WINDOW {
+                *entry = (now, 0);
+            }
+            entry.1 += 1;
+            let blocked = values(descriptor).iter().any(|v| v == b"blocked");
+            let remaining = RLS_LIMIT.saturating_sub(entry.1);
+            let limited = blocked || entry.1 > RLS_LIMIT;
+            let reset = if blocked {
+                1
+            } else {
+                RLS_WINDOW
+                    .saturating_sub(now.duration_since(entry.0))
+                    .as_secs()
+                    .max(1)
+            };
+            over_limit |= limited;
+            min_remaining = min_remaining.min(remaining);
+            let mut status = vec![0x08, if limited { 2 } else { 1 }, 0x18];
+            pb_put_varint(&mut status, remaining);
+            let mut duration = vec![0x08];
+            pb_put_varint(&mut duration, reset);
+            pb_put_bytes(&mut status, 4, &duration);
+            statuses.push(status);
+        }
+    }
+
+    // RateLimitResponse{overall_code = 1, statuses = 2, response_headers_to_add = 3}
+    let mut message = vec![0x08, if over_limit { 2 } else { 1 }];
+    for status in &statuses {
+        pb_put_bytes(&mut message, 2, status);
+    }
+    if !over_limit && min_remaining != u64::MAX {
+        let mut header = Vec::new();
+        pb_put_bytes(&mut header, 1, b"x-rls-remaining");
+        pb_put_bytes(&mut header, 2, min_remaining.to_string().as_bytes());
+        pb_put_bytes(&mut message, 3, &header);
+    }
+    let mut data = vec![0];
+    data.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    data.extend_from_slice(&message);
+    write_h2_frame(stream, 0x0, 0, stream_id, &data).await?;
+
+    let mut trailers = Vec::new();
+    hpack_literal(&mut trailers, b"grpc-status", b"0");
+    write_h2_frame(stream, 0x1, 0x5, stream_id, &trailers).await
+}
+
+/// 外部認可サービス（F-149 外部認可 HTTP モードの E2E 用）
+///
+/// `X-Auth-Token: letmein` を持つリクエストには 200 で `X-Auth-User: alice` を返し、
+/// `X-Envoy-Auth-Headers-To-Remove` でトークンの除去を指示する。それ以外は 401 に
+/// `WWW-Authenticate` と受け取ったパス（`X-Authz-Path`）を付けて拒否する。
+/// keep-alive で 1 接続上の複数リクエストを順に処理する。
+async fn run_authz_server(addr: SocketAddr) {
+    let listener = TcpListener::bind(addr)
+        .await
+        .unwrap_or_else(|e| panic!("Failed to bind authz server on {}: {}", addr, e));
+    info!("Authz server listening on {}", addr);
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                debug!("New authz connection from {}", peer);
+                tokio::spawn(async move {
+                    if let Err(e) = handle_authz(stream).await {
+                        debug!("Authz handler error: {}", e);
+                    }
+                });
+            }
+            Err(e) => error!("Authz accept error: {}", e),
+        }
+    }
+}
+
+async fn handle_authz(mut stream: TcpStream) -> std::io::Result<()> {
+    let mut buf: Vec<u8> = Vec::with_capacity(8192);
+    let mut tmp = [0u8; 8192];
+    loop {
+        let header_end = loop {
+            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            if buf.len() > 1 << 16 {
+                return Ok(());
+            }
+            let n = stream.read(&mut tmp).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            buf.extend_from_slice(&tmp[..n]);
+        };
+
+        let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+        let lower = head.to_lowercase();
+        let path = head
+            .split("\r\n")
+            .next()
+            .and_then(|line| line.split(' ').nth(1))
+            .unwrap_or("")
+            .to_string();
+        let content_length: usize = lower
+            .split("\r\ncontent-length:")
+            .nth(1)
+            .and_then(|s| s.split("\r\n").next())
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(0);
+        while buf.len() < header_end + content_length {
+            let n = stream.read(&mut tmp).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            buf.extend_from_slice(&tmp[..n]);
+        }
+        buf.drain(..header_end + content_length);
+
+        let token = lower
+            .split("\r\nx-auth-token:")
+            .nth(1)
+            .and_then(|s| s.split("\r\n").next())
+            .map(|s| s.trim().to_string());
+        let response = if token.as_deref() == Some("letmein") {
+            "HTTP/1.1 200 OK\r\nX-Auth-User: alice\r\n\
+             X-Envoy-Auth-Headers-To-Remove: x-auth-token\r\nContent-Length: 0\r\n\r\n"
+                .to_string()
+        } else {
+            let body = "authz denied";
+            format!(
+                "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Custom\r\nX-Authz-Path: {}\r\n\
+                 Content-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+                path,
+                body.len(),
+                body
+            )
+        };
+        stream.write_all(response.as_bytes()).await?;
+        stream.flush().await?;
+    }
+}
+
+/// mock IdP のクライアント ID / シークレット / ID トークンの署名鍵（HS256）
+const OIDC_CLIENT_ID: &str = "e2e-client";
+const OIDC_CLIENT_SECRET: &str = "e2e-secret";
+const OIDC_SIGNING_KEY: &[u8] = b"veil-e2e-oidc-signing-key-0123456789";
+
+/// 発行済みの認可コード → (nonce, code_challenge, redirect_uri)
+type OidcCodes = Arc<Mutex<HashMap<String, (String, String, String)>>>;
+
+/// OpenID Connect プロバイダ（F-150 OIDC ログインの E2E 用）
+///
+/// discovery・認可（同意画面なしで `redirect_uri` へコードを返す）・トークン・JWKS を
+/// 最小限だけ実装する。トークンエンドポイントは `client_secret_basic` と PKCE（S256）を
+/// 検証し、`sub = alice` の HS256 ID トークンと `expires_in = 1` のアクセストークン、
+/// リフレッシュトークンを返す（すぐにリフレッシュが必要になる）。
+async fn run_oidc_server(addr: SocketAddr) {
+    let listener = TcpListener::bind(addr)
+        .await
+        .unwrap_or_else(|e| panic!("Failed to bind OIDC server on {}: {}", addr, e));
+    info!("OIDC provider listening on {}", addr);
+    let issuer = format!("http://{}", addr);
+    let codes: OidcCodes = Arc::new(Mutex::new(HashMap::new()));
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                debug!("New OIDC connection from {}", peer);
+                let issuer = issuer.clone();
+                let codes = codes.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_oidc(stream, issuer, codes).await {
+                        debug!("OIDC handler error: {}", e);
+                    }
+                });
+            }
+            Err(e) => error!("OIDC accept error: {}", e),
+        }
+    }
+}
+
+async fn handle_oidc(
+    mut stream: TcpStream,
+    issuer: String,
+    codes: OidcCodes,
+) -> std::io::Result<()> {
+    let mut buf: Vec<u8> = Vec::with_capacity(8192);
+    let mut tmp = [0u8; 8192];
+    loop {
+        let header_end = loop {
+            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            if buf.len() > 1 << 16 {
+                return Ok(());
+            }
+            let n = stream.read(&mut tmp).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            buf.extend_from_slice(&tmp[..n]);
+        };
+        let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+        let lower = head.to_lowercase();
+        let content_length: usize = lower
+            .split("\r\ncontent-length:")
+            .nth(1)
+            .and_then(|s| s.split("\r\n").next())
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(0);
+        while buf.len() < header_end + content_length {
+            let n = stream.read(&mut tmp).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            buf.extend_from_slice(&tmp[..n]);
+        }
+        let body =
+            String::from_utf8_lossy(&buf[header_end..header_end + content_length]).to_string();
+        buf.drain(..header_end + content_length);
+
+        let target = head
+            .split("\r\n")
+            .next()
+            .and_then(|line| line.split(' ').nth(1))
+            .unwrap_or("/")
+            .to_string();
+        let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+        let authorization = lower
+            .split("\r\nauthorization:")
+            .nth(1)
+            .and_then(|s| s.split("\r\n").next())
+            .map(|s| s.trim().to_string());
+        let (status, extra, body) = match path {
+            "/.well-known/openid-configuration" => (
+                "200 OK",
+                String::new(),
+                format!(
+                    r#"{{"issuer":"{0}","authorization_endpoint":"{0}/authorize","token_endpoint":"{0}/token","jwks_uri":"{0}/jwks","end_session_endpoint":"{0}/logout"}}"#,
+                    issuer
+                ),
+            ),
+            "/jwks" => (
+                "200 OK",
+                String::new(),
+                format!(
+                    r#"{{"keys":[{{"kty":"oct","kid":"e2e","alg":"HS256","k":"{}"}}]}}"#,
+                    base64url(OIDC_SIGNING_KEY)
+                ),
+            ),
+            "/authorize" => oidc_authorize(&form_params(query), &codes),
+            "/token" => oidc_token(
+                &form_params(&body),
+                authorization.as_deref(),
+                &issuer,
+                &codes,
+            ),
+            _ => ("404 Not Found", String::new(), "not found".to_string()),
+        };
+        let response = format!(
+            "HTTP/1.1 {}\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            status,
+            extra,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await?;
+        stream.flush().await?;
+    }
+}
+
+/// 認可リクエスト: コードを発行して `redirect_uri` へ戻す
+fn oidc_authorize(
+    params: &HashMap<String, String>,
+    codes: &OidcCodes,
+) -> (&'static str, String, String) {
+    let param = |name: &str| params.get(name).cloned().unwrap_or_default();
+    if param("client_id") != OIDC_CLIENT_ID
+        || param("response_type") != "code"
+        || param("code_challenge_method") != "S256"
+        || !param("scope").split(' ').any(|s| s == "openid")
+    {
+        return (
+            "400 Bad Request",
+            String::new(),
+            r#"{"error":"invalid_request"}"#.to_string(),
+        );
+    }
+    let redirect_uri = param("redirect_uri");
+    let code = format!("code-{}", codes.lock().unwrap().len() + 1);
+    codes.lock().unwrap().insert(
+        code.clone(),
+        (
+            param("nonce"),
+            param("code_challenge"),
+            redirect_uri.clone(),
+        ),
+    );
+    let location = format!("{}?code={}&state={}", redirect_uri, code, param("state"));
+    (
+        "302 Found",
+        format!("Location: {}\r\n", location),
+        String::new(),
+    )
+}
+
+/// トークンリクエスト: コード（PKCE を検証）かリフレッシュトークンを交換する
+fn oidc_token(
+    params: &HashMap<String, String>,
+    authorization: Option<&str>,
+    issuer: &str,
+    codes: &OidcCodes,
+) -> (&'static str, String, String) {
+    let invalid = |error: &str| {
+        (
+            "400 Bad Request",
+            String::new(),
+            format!(r#"{{"error":"{}"}}"#, error),
+        )
+    };
+    let basic = format!(
+        "basic {}",
+        base64(format!("{}:{}", OIDC_CLIENT_ID, OIDC_CLIENT_SECRET).as_bytes())
+    );
+    if authorization != Some(basic.to_lowercase().as_str()) {
+        return (
+            "401 Unauthorized",
+            String::new(),
+            r#"{"error":"invalid_client"}"#.to_string(),
+        );
+    }
+    let param = |name: &str| params.get(name).cloned().unwrap_or_default();
+    let nonce = match param("grant_type").as_str() {
+        "authorization_code" => {
+            let Some((nonce, challenge, redirect_uri)) =
+                codes.lock().unwrap().remove(&param("code"))
+            else {
+                return invalid("invalid_grant");
+            };
+            let verifier = param("code_verifier");
+            let expected = base64url(
+                ring::digest::digest(&ring::digest::SHA256, verifier.as_bytes()).as_ref(),
+            );
+            if expected != challenge || param("redirect_uri") != redirect_uri {
+                return invalid("invalid_grant");
+            }
+            Some(nonce)
+        }
+        "refresh_token" if param("refresh_token").starts_with("rt-") => None,
+        _ => return invalid("invalid_grant"),
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut claims = format!(
+        r#"{{"iss":"{}","aud":"{}","sub":"alice","email":"alice@example.com","iat":{},"exp":{}"#,
+        issuer,
+        OIDC_CLIENT_ID,
+        now,
+        now + 300
+    );
+    if let Some(nonce) = nonce {
+        claims.push_str(&format!(r#","nonce":"{}""#, nonce));
+    }
+    claims.push('}');
+    let signing_input = format!(
+        "{}.{}",
+        base64url(br#"{"alg":"HS256","kid":"e2e","typ":"JWT"}"#),
+        base64url(claims.as_bytes())
+    );
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, OIDC_SIGNING_KEY);
+    let signature = ring::hmac::sign(&key, signing_input.as_bytes());
+    let id_token = format!("{}.{}", signing_input, base64url(signature.as_ref()));
+    (
+        "200 OK",
+        "Cache-Control: no-store\r\n".to_string(),
+        format!(
+            r#"{{"access_token":"at-{}","token_type":"Bearer","expires_in":1,"refresh_token":"rt-{}","id_token":"{}"}}"#,
+            now, now, id_token
+        ),
+    )
+}
+
+/// `application/x-www-form-urlencoded` を読む
+fn form_params(input: &str) -> HashMap<String, String> {
+    fn decode(s: &str) -> String {
+        let bytes = s.as_bytes();
+        let mut out = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'%' if i + 2 < bytes.len() => match u8::from_str_radix(&s[i + 1..i + 3], 16) {
+                    Ok(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    Err(_) => out.push(b'%'),
+                },
+                b'+' => out.push(b' '),
+                b => out.push(b),
+            }
+            i += 1;
+        }
+        String::from_utf8_lossy(&out).into_owned()
+    }
+    input
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(name), decode(value))
+        })
+        .collect()
+}
+
+fn base64_with(input: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..=chunk.len() {
+            out.push(alphabet[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+        if pad {
+            for _ in chunk.len()..3 {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base64(input: &[u8]) -> String {
+    base64_with(
+        input,
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+        true,
+    )
+}
+
+fn base64url(input: &[u8]) -> String {
+    base64_with(
+        input,
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+        false,
+    )
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt()
@@ -510,6 +1224,18 @@ async fn main() {
         .ok()
         .and_then(|v| v.parse().ok())
         .unwrap_or(9019);
+    let rls_port: u16 = std::env::var("RLS_PORT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(9020);
+    let authz_port: u16 = std::env::var("AUTHZ_PORT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(9021);
+    let oidc_port: u16 = std::env::var("OIDC_PORT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(9022);
     let tls_cert = std::env::var("TLS_CERT_PATH").unwrap_or_else(|_| "cert.pem".to_string());
     let tls_key = std::env::var("TLS_KEY_PATH").unwrap_or_else(|_| "key.pem".to_string());
 
@@ -520,10 +1246,13 @@ async fn main() {
     let echo_addr: SocketAddr = format!("127.0.0.1:{}", echo_port).parse().unwrap();
     let tls_echo_addr: SocketAddr = format!("127.0.0.1:{}", tls_echo_port).parse().unwrap();
     let udp_echo_addr: SocketAddr = format!("127.0.0.1:{}", udp_echo_port).parse().unwrap();
+    let rls_addr: SocketAddr = format!("127.0.0.1:{}", rls_port).parse().unwrap();
+    let authz_addr: SocketAddr = format!("127.0.0.1:{}", authz_port).parse().unwrap();
+    let oidc_addr: SocketAddr = format!("127.0.0.1:{}", oidc_port).parse().unwrap();
 
     info!(
-        "Starting test-backends: WS={}, HTTP-error={}, chunked={}, echo={}, tls-echo={}, udp-echo={}, bad={}",
-        ws_addr, error_addr, chunked_addr, echo_addr, tls_echo_addr, udp_echo_addr, bad_addr
+        "Starting test-backends: WS={}, HTTP-error={}, chunked={}, echo={}, tls-echo={}, udp-echo={}, bad={}, rls={}, authz={}, oidc={}",
+        ws_addr, error_addr, chunked_addr, echo_addr, tls_echo_addr, udp_echo_addr, bad_addr, rls_addr, authz_addr, oidc_addr
     );
 
     tokio::join!(
@@ -534,5 +1263,8 @@ async fn main() {
         run_tls_echo_server(tls_echo_addr, tls_cert, tls_key),
         run_udp_echo_server(udp_echo_addr),
         run_bad_backend_server(bad_addr),
+        run_rls_server(rls_addr),
+        run_authz_server(authz_addr),
+        run_oidc_server(oidc_addr),
     );
 }